@@ -1,4 +1,5 @@
 mod parser;
+mod reindent;
 mod seek_sequence;
 mod standalone_executable;
 
@@ -252,6 +253,9 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                 })
                 .unwrap_or_else(|| cwd.to_path_buf());
             let mut changes = HashMap::new();
+            // Updates none of whose hunks apply are left out, as `apply_patch`
+            // does; the first such failure is reported if nothing else applies.
+            let mut skipped: Option<ApplyPatchError> = None;
             for hunk in hunks {
                 let path = hunk.resolve_path(&effective_cwd);
                 match hunk {
@@ -280,6 +284,10 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                             content: contents,
                         } = match unified_diff_from_chunks(&path, &chunks) {
                             Ok(diff) => diff,
+                            Err(e @ ApplyPatchError::ComputeReplacements(_)) => {
+                                skipped.get_or_insert(e);
+                                continue;
+                            }
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(e);
                             }
@@ -295,6 +303,11 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                     }
                 }
             }
+            if changes.is_empty()
+                && let Some(e) = skipped
+            {
+                return MaybeApplyPatchVerified::CorrectnessError(e);
+            }
             MaybeApplyPatchVerified::Body(ApplyPatchAction {
                 changes,
                 patch,
@@ -474,8 +487,38 @@ pub fn apply_patch(
     stdout: &mut impl std::io::Write,
     stderr: &mut impl std::io::Write,
 ) -> Result<(), ApplyPatchError> {
-    let hunks = match parse_patch(patch) {
-        Ok(source) => source.hunks,
+    let hunks = parse_patch_reporting_errors(patch, stderr)?;
+    apply_hunks(&hunks, stdout, stderr)?;
+
+    Ok(())
+}
+
+/// Checks that the patch applies to the files under `cwd`, without changing
+/// anything, and prints what applying it would do: the same per-file and
+/// per-hunk report as [`apply_patch`].
+pub fn dry_run_patch(
+    patch: &str,
+    cwd: &Path,
+    stdout: &mut impl std::io::Write,
+    stderr: &mut impl std::io::Write,
+) -> Result<(), ApplyPatchError> {
+    let hunks = parse_patch_reporting_errors(patch, stderr)?;
+    match plan_hunks(&hunks, cwd) {
+        Ok(plan) => {
+            write_summary(&plan.affected, SummaryKind::DryRun, stdout)
+                .map_err(ApplyPatchError::from)?;
+            Ok(())
+        }
+        Err(err) => Err(report_error(err, stderr)),
+    }
+}
+
+fn parse_patch_reporting_errors(
+    patch: &str,
+    stderr: &mut impl std::io::Write,
+) -> Result<Vec<Hunk>, ApplyPatchError> {
+    match parse_patch(patch) {
+        Ok(source) => Ok(source.hunks),
         Err(e) => {
             match &e {
                 InvalidPatchError(message) => {
@@ -492,13 +535,9 @@ pub fn apply_patch(
                     .map_err(ApplyPatchError::from)?;
                 }
             }
-            return Err(ApplyPatchError::ParseError(e));
+            Err(ApplyPatchError::ParseError(e))
         }
-    };
-
-    apply_hunks(&hunks, stdout, stderr)?;
-
-    Ok(())
+    }
 }
 
 /// Applies hunks and continues to update stdout/stderr
@@ -507,89 +546,142 @@ pub fn apply_hunks(
     stdout: &mut impl std::io::Write,
     stderr: &mut impl std::io::Write,
 ) -> Result<(), ApplyPatchError> {
-    let _existing_paths: Vec<&Path> = hunks
-        .iter()
-        .filter_map(|hunk| match hunk {
-            Hunk::AddFile { .. } => {
-                // The file is being added, so it doesn't exist yet.
-                None
-            }
-            Hunk::DeleteFile { path } => Some(path.as_path()),
-            Hunk::UpdateFile {
-                path, move_path, ..
-            } => match move_path {
-                Some(move_path) => {
-                    if std::fs::metadata(move_path)
-                        .map(|m| m.is_file())
-                        .unwrap_or(false)
-                    {
-                        Some(move_path.as_path())
-                    } else {
-                        None
-                    }
-                }
-                None => Some(path.as_path()),
-            },
-        })
-        .collect::<Vec<&Path>>();
-
     // Delegate to a helper that applies each hunk to the filesystem.
     match apply_hunks_to_files(hunks) {
         Ok(affected) => {
             print_summary(&affected, stdout).map_err(ApplyPatchError::from)?;
             Ok(())
         }
-        Err(err) => {
-            let msg = err.to_string();
-            writeln!(stderr, "{msg}").map_err(ApplyPatchError::from)?;
-            if let Some(io) = err.downcast_ref::<std::io::Error>() {
-                Err(ApplyPatchError::from(io))
-            } else {
-                Err(ApplyPatchError::IoError(IoError {
-                    context: msg,
-                    source: std::io::Error::other(err),
-                }))
-            }
-        }
+        Err(err) => Err(report_error(err, stderr)),
+    }
+}
+
+/// Writes `err` to `stderr` and converts it for the caller.
+fn report_error(err: anyhow::Error, stderr: &mut impl std::io::Write) -> ApplyPatchError {
+    let msg = err.to_string();
+    if let Err(write_err) = writeln!(stderr, "{msg}") {
+        return ApplyPatchError::from(write_err);
+    }
+    if let Some(io) = err.downcast_ref::<std::io::Error>() {
+        ApplyPatchError::from(io)
+    } else {
+        ApplyPatchError::IoError(IoError {
+            context: msg,
+            source: std::io::Error::other(err),
+        })
     }
 }
 
-/// Applies each parsed patch hunk to the filesystem.
-/// Returns an error if any of the changes could not be applied.
 /// Tracks file paths affected by applying a patch.
 pub struct AffectedPaths {
     pub added: Vec<PathBuf>,
     pub modified: Vec<PathBuf>,
     pub deleted: Vec<PathBuf>,
+    /// Modified files whose hunks were re-indented to match the file.
+    pub reindented: Vec<PathBuf>,
+    /// Hunks of updated files that did not apply cleanly.
+    pub hunks: Vec<HunkReport>,
+}
+
+/// A hunk of an `*** Update File` section that did not apply cleanly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkReport {
+    pub path: PathBuf,
+    /// 1-based position of the hunk in its file section.
+    pub hunk: usize,
+    pub outcome: HunkOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkOutcome {
+    /// The hunk's context had drifted: it was merged into the lines that are
+    /// there now, keeping the file's version of the context.
+    Fuzzy,
+    /// The hunk could not be located and was left out.
+    Failed(String),
 }
 
 /// Apply the hunks to the filesystem, returning which files were added, modified, or deleted.
 /// Returns an error if the patch could not be applied.
 fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
+    let plan = plan_hunks(hunks, Path::new(""))?;
+    for write in &plan.writes {
+        match write {
+            PlannedWrite::Write { path, contents } => {
+                if let Some(parent) = path.parent()
+                    && !parent.as_os_str().is_empty()
+                {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Failed to create parent directories for {}", path.display())
+                    })?;
+                }
+                std::fs::write(path, contents)
+                    .with_context(|| format!("Failed to write file {}", path.display()))?;
+            }
+            PlannedWrite::Remove { path } => {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
+            }
+        }
+    }
+    Ok(plan.affected)
+}
+
+enum PlannedWrite {
+    Write { path: PathBuf, contents: String },
+    Remove { path: PathBuf },
+}
+
+/// What applying a patch does, worked out before anything is written.
+struct PatchPlan {
+    writes: Vec<PlannedWrite>,
+    affected: AffectedPaths,
+}
+
+/// Validates every hunk against the files under `cwd` (relative paths are
+/// resolved against it) and plans the writes that apply them. Nothing is
+/// written, so a patch that does not apply leaves every file untouched.
+///
+/// Hunks of an updated file that cannot be located are left out and
+/// reported; the patch only fails when none of its changes apply.
+fn plan_hunks(hunks: &[Hunk], cwd: &Path) -> anyhow::Result<PatchPlan> {
     if hunks.is_empty() {
         anyhow::bail!("No files were modified.");
     }
 
+    // Contents as earlier hunks of this patch leave them; `None` once deleted.
+    let mut pending: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut writes: Vec<PlannedWrite> = Vec::new();
     let mut added: Vec<PathBuf> = Vec::new();
     let mut modified: Vec<PathBuf> = Vec::new();
     let mut deleted: Vec<PathBuf> = Vec::new();
+    let mut reindented: Vec<PathBuf> = Vec::new();
+    let mut reports: Vec<HunkReport> = Vec::new();
     for hunk in hunks {
         match hunk {
             Hunk::AddFile { path, contents } => {
-                if let Some(parent) = path.parent()
-                    && !parent.as_os_str().is_empty()
-                {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!("Failed to create parent directories for {}", path.display())
-                    })?;
-                }
-                std::fs::write(path, contents)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
+                let target = cwd.join(path);
+                pending.insert(target.clone(), Some(contents.clone()));
+                writes.push(PlannedWrite::Write {
+                    path: target,
+                    contents: contents.clone(),
+                });
                 added.push(path.clone());
             }
             Hunk::DeleteFile { path } => {
-                std::fs::remove_file(path)
-                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
+                let target = cwd.join(path);
+                let exists = match pending.get(&target) {
+                    Some(contents) => contents.is_some(),
+                    None => target.is_file(),
+                };
+                if !exists {
+                    return Err(anyhow::Error::new(std::io::Error::from(
+                        std::io::ErrorKind::NotFound,
+                    ))
+                    .context(format!("Failed to delete file {}", path.display())));
+                }
+                pending.insert(target.clone(), None);
+                writes.push(PlannedWrite::Remove { path: target });
                 deleted.push(path.clone());
             }
             Hunk::UpdateFile {
@@ -597,43 +689,105 @@ fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
                 move_path,
                 chunks,
             } => {
-                let AppliedPatch { new_contents, .. } =
-                    derive_new_contents_from_chunks(path, chunks)?;
+                let target = cwd.join(path);
+                let original_contents = match pending.get(&target) {
+                    Some(Some(contents)) => contents.clone(),
+                    Some(None) => anyhow::bail!(
+                        "Failed to read file to update {}: deleted earlier in the patch",
+                        path.display()
+                    ),
+                    None => std::fs::read_to_string(&target).map_err(|err| {
+                        ApplyPatchError::IoError(IoError {
+                            context: format!("Failed to read file to update {}", path.display()),
+                            source: err,
+                        })
+                    })?,
+                };
+                let AppliedPatch {
+                    new_contents,
+                    reindented: hunks_reindented,
+                    hunks: outcomes,
+                    ..
+                } = derive_new_contents(original_contents, path, chunks)?;
+                let any_applied = outcomes
+                    .iter()
+                    .any(|outcome| !matches!(outcome, Some(HunkOutcome::Failed(_))));
+                reports.extend(
+                    outcomes
+                        .into_iter()
+                        .enumerate()
+                        .filter_map(|(index, outcome)| {
+                            outcome.map(|outcome| HunkReport {
+                                path: path.clone(),
+                                hunk: index + 1,
+                                outcome,
+                            })
+                        }),
+                );
+                if !any_applied && !chunks.is_empty() {
+                    continue;
+                }
+                if hunks_reindented {
+                    reindented.push(move_path.as_ref().unwrap_or(path).clone());
+                }
                 if let Some(dest) = move_path {
-                    if let Some(parent) = dest.parent()
-                        && !parent.as_os_str().is_empty()
-                    {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", dest.display())
-                        })?;
-                    }
-                    std::fs::write(dest, new_contents)
-                        .with_context(|| format!("Failed to write file {}", dest.display()))?;
-                    std::fs::remove_file(path)
-                        .with_context(|| format!("Failed to remove original {}", path.display()))?;
+                    let dest_target = cwd.join(dest);
+                    pending.insert(dest_target.clone(), Some(new_contents.clone()));
+                    pending.insert(target.clone(), None);
+                    writes.push(PlannedWrite::Write {
+                        path: dest_target,
+                        contents: new_contents,
+                    });
+                    writes.push(PlannedWrite::Remove { path: target });
                     modified.push(dest.clone());
                 } else {
-                    std::fs::write(path, new_contents)
-                        .with_context(|| format!("Failed to write file {}", path.display()))?;
+                    pending.insert(target.clone(), Some(new_contents.clone()));
+                    writes.push(PlannedWrite::Write {
+                        path: target,
+                        contents: new_contents,
+                    });
                     modified.push(path.clone());
                 }
             }
         }
     }
-    Ok(AffectedPaths {
-        added,
-        modified,
-        deleted,
+
+    if writes.is_empty() {
+        // Every hunk failed to apply: report the first failure as the error.
+        let reason = reports
+            .iter()
+            .find_map(|report| match &report.outcome {
+                HunkOutcome::Failed(reason) => Some(reason.clone()),
+                HunkOutcome::Fuzzy => None,
+            })
+            .unwrap_or_else(|| "No files were modified.".to_string());
+        return Err(ApplyPatchError::ComputeReplacements(reason).into());
+    }
+
+    Ok(PatchPlan {
+        writes,
+        affected: AffectedPaths {
+            added,
+            modified,
+            deleted,
+            reindented,
+            hunks: reports,
+        },
     })
 }
 
 struct AppliedPatch {
     original_contents: String,
     new_contents: String,
+    /// Whether any hunk had to be re-indented to match the file.
+    reindented: bool,
+    /// How each hunk applied, in order; `None` when it applied cleanly.
+    hunks: Vec<Option<HunkOutcome>>,
 }
 
 /// Return *only* the new file contents (joined into a single `String`) after
-/// applying the chunks to the file at `path`.
+/// applying the chunks to the file at `path`. Fails unless at least one of
+/// the chunks applies.
 fn derive_new_contents_from_chunks(
     path: &Path,
     chunks: &[UpdateFileChunk],
@@ -648,6 +802,32 @@ fn derive_new_contents_from_chunks(
         }
     };
 
+    let applied = derive_new_contents(original_contents, path, chunks)?;
+    if let Some(reason) = first_failure_if_none_applied(&applied.hunks) {
+        return Err(ApplyPatchError::ComputeReplacements(reason));
+    }
+    Ok(applied)
+}
+
+/// The first failure, when every hunk failed.
+fn first_failure_if_none_applied(hunks: &[Option<HunkOutcome>]) -> Option<String> {
+    let mut first_failure = None;
+    for outcome in hunks {
+        match outcome {
+            Some(HunkOutcome::Failed(reason)) => {
+                first_failure.get_or_insert_with(|| reason.clone());
+            }
+            _ => return None,
+        }
+    }
+    first_failure
+}
+
+fn derive_new_contents(
+    original_contents: String,
+    path: &Path,
+    chunks: &[UpdateFileChunk],
+) -> std::result::Result<AppliedPatch, ApplyPatchError> {
     let mut original_lines: Vec<String> = original_contents.split('\n').map(String::from).collect();
 
     // Drop the trailing empty element that results from the final newline so
@@ -656,7 +836,7 @@ fn derive_new_contents_from_chunks(
         original_lines.pop();
     }
 
-    let replacements = compute_replacements(&original_lines, path, chunks)?;
+    let (replacements, reindented, hunks) = compute_replacements(&original_lines, path, chunks)?;
     let new_lines = apply_replacements(original_lines, &replacements);
     let mut new_lines = new_lines;
     if !new_lines.last().is_some_and(String::is_empty) {
@@ -666,37 +846,69 @@ fn derive_new_contents_from_chunks(
     Ok(AppliedPatch {
         original_contents,
         new_contents,
+        reindented,
+        hunks,
     })
 }
 
+/// Replacements, whether any was re-indented, and how each chunk applied.
+type Replacements = (
+    Vec<(usize, usize, Vec<String>)>,
+    bool,
+    Vec<Option<HunkOutcome>>,
+);
+
 /// Compute a list of replacements needed to transform `original_lines` into the
 /// new lines, given the patch `chunks`. Each replacement is returned as
-/// `(start_index, old_len, new_lines)`.
+/// `(start_index, old_len, new_lines)`, alongside whether any of them had to be
+/// re-indented to match the file and how each chunk applied.
+///
+/// For whitespace-insensitive languages, lines also match when they only
+/// differ in whitespace, and the new lines of such hunks are re-indented to
+/// follow the file's style instead of the patch's.
+///
+/// A chunk whose lines are not in the file is merged into the region its
+/// context has drifted to, if there is one (see [`fuzzy_merge`]); otherwise
+/// it is left out and reported as failed, and the other chunks still apply.
 fn compute_replacements(
     original_lines: &[String],
     path: &Path,
     chunks: &[UpdateFileChunk],
-) -> std::result::Result<Vec<(usize, usize, Vec<String>)>, ApplyPatchError> {
+) -> std::result::Result<Replacements, ApplyPatchError> {
     let mut replacements: Vec<(usize, usize, Vec<String>)> = Vec::new();
+    let mut outcomes: Vec<Option<HunkOutcome>> = Vec::with_capacity(chunks.len());
     let mut line_index: usize = 0;
+    let mut reindented = false;
+
+    let whitespace_insensitive = reindent::is_whitespace_insensitive(path);
+    let seek = |pattern: &[String], start: usize, eof: bool| {
+        seek_sequence::seek_sequence(original_lines, pattern, start, eof).or_else(|| {
+            if whitespace_insensitive {
+                seek_sequence::seek_sequence_collapsing_whitespace(
+                    original_lines,
+                    pattern,
+                    start,
+                    eof,
+                )
+            } else {
+                None
+            }
+        })
+    };
 
     for chunk in chunks {
         // If a chunk has a `change_context`, we use seek_sequence to find it, then
         // adjust our `line_index` to continue from there.
         if let Some(ctx_line) = &chunk.change_context {
-            if let Some(idx) = seek_sequence::seek_sequence(
-                original_lines,
-                std::slice::from_ref(ctx_line),
-                line_index,
-                false,
-            ) {
+            if let Some(idx) = seek(std::slice::from_ref(ctx_line), line_index, false) {
                 line_index = idx + 1;
             } else {
-                return Err(ApplyPatchError::ComputeReplacements(format!(
+                outcomes.push(Some(HunkOutcome::Failed(format!(
                     "Failed to find context '{}' in {}",
                     ctx_line,
                     path.display()
-                )));
+                ))));
+                continue;
             }
         }
 
@@ -709,6 +921,7 @@ fn compute_replacements(
                 original_lines.len()
             };
             replacements.push((insertion_idx, 0, chunk.new_lines.clone()));
+            outcomes.push(None);
             continue;
         }
 
@@ -724,8 +937,7 @@ fn compute_replacements(
         // located reliably.
 
         let mut pattern: &[String] = &chunk.old_lines;
-        let mut found =
-            seek_sequence::seek_sequence(original_lines, pattern, line_index, chunk.is_end_of_file);
+        let mut found = seek(pattern, line_index, chunk.is_end_of_file);
 
         let mut new_slice: &[String] = &chunk.new_lines;
 
@@ -737,29 +949,136 @@ fn compute_replacements(
                 new_slice = &new_slice[..new_slice.len() - 1];
             }
 
-            found = seek_sequence::seek_sequence(
-                original_lines,
-                pattern,
-                line_index,
-                chunk.is_end_of_file,
-            );
+            found = seek(pattern, line_index, chunk.is_end_of_file);
         }
 
         if let Some(start_idx) = found {
-            replacements.push((start_idx, pattern.len(), new_slice.to_vec()));
+            let matched = &original_lines[start_idx..start_idx + pattern.len()];
+            let new_lines = match whitespace_insensitive
+                .then(|| reindent::reindent_hunk(original_lines, matched, pattern, new_slice))
+                .flatten()
+            {
+                Some(new_lines) => {
+                    reindented = true;
+                    new_lines
+                }
+                None => new_slice.to_vec(),
+            };
+            replacements.push((start_idx, pattern.len(), new_lines));
+            line_index = start_idx + pattern.len();
+            outcomes.push(None);
+        } else if let Some((start_idx, new_lines)) = fuzzy_merge(
+            original_lines,
+            pattern,
+            new_slice,
+            line_index,
+            &replacements,
+        ) {
+            replacements.push((start_idx, pattern.len(), new_lines));
             line_index = start_idx + pattern.len();
+            outcomes.push(Some(HunkOutcome::Fuzzy));
         } else {
-            return Err(ApplyPatchError::ComputeReplacements(format!(
+            outcomes.push(Some(HunkOutcome::Failed(format!(
                 "Failed to find expected lines in {}:\n{}",
                 path.display(),
                 chunk.old_lines.join("\n"),
-            )));
+            ))));
         }
     }
 
     replacements.sort_by(|(lhs_idx, _, _), (rhs_idx, _, _)| lhs_idx.cmp(rhs_idx));
 
-    Ok(replacements)
+    Ok((replacements, reindented, outcomes))
+}
+
+/// Three-way merge of a hunk whose lines no longer match the file, like
+/// `git apply -3`: the hunk's old lines are the base, its new lines one side
+/// and a same-sized region of the file the other.
+///
+/// The region must contain every line the hunk removes or replaces (compared
+/// ignoring surrounding whitespace), and at least half of the hunk's old
+/// lines overall, in place. Within it, the hunk's changes are applied while
+/// context lines keep the file's version. Regions at or after `start` are
+/// preferred, and regions overlapping an earlier replacement are skipped.
+///
+/// Returns the region's start and its merged lines.
+fn fuzzy_merge(
+    original_lines: &[String],
+    old_lines: &[String],
+    new_lines: &[String],
+    start: usize,
+    taken: &[(usize, usize, Vec<String>)],
+) -> Option<(usize, Vec<String>)> {
+    use similar::DiffOp;
+
+    let len = old_lines.len();
+    if len == 0 || len > original_lines.len() {
+        return None;
+    }
+    let ops = similar::capture_diff_slices(similar::Algorithm::Myers, old_lines, new_lines);
+    let changed: Vec<usize> = ops
+        .iter()
+        .filter(|op| !matches!(op, DiffOp::Equal { .. } | DiffOp::Insert { .. }))
+        .flat_map(DiffOp::old_range)
+        .collect();
+    let same = |a: &str, b: &str| a.trim() == b.trim();
+    let overlaps = |at: usize| {
+        taken
+            .iter()
+            .any(|(idx, old_len, _)| at < idx + (*old_len).max(1) && *idx < at + len)
+    };
+
+    // (matching lines, starts before `start`, region start)
+    let mut best: Option<(usize, bool, usize)> = None;
+    for at in 0..=original_lines.len() - len {
+        let window = &original_lines[at..at + len];
+        if overlaps(at) || !changed.iter().all(|&i| same(&window[i], &old_lines[i])) {
+            continue;
+        }
+        let score = window
+            .iter()
+            .zip(old_lines)
+            .filter(|(file, old)| same(file, old))
+            .count();
+        let candidate = (score, at < start, at);
+        let better = match best {
+            None => true,
+            Some((best_score, best_before, _)) => {
+                score > best_score || (score == best_score && best_before && at >= start)
+            }
+        };
+        if better {
+            best = Some(candidate);
+        }
+    }
+    let (score, _, at) = best?;
+    if score * 2 < len {
+        return None;
+    }
+
+    let window = &original_lines[at..at + len];
+    let mut merged = Vec::with_capacity(new_lines.len());
+    for op in &ops {
+        match *op {
+            DiffOp::Equal {
+                old_index,
+                len: equal_len,
+                ..
+            } => {
+                merged.extend_from_slice(&window[old_index..old_index + equal_len]);
+            }
+            DiffOp::Insert {
+                new_index, new_len, ..
+            }
+            | DiffOp::Replace {
+                new_index, new_len, ..
+            } => {
+                merged.extend_from_slice(&new_lines[new_index..new_index + new_len]);
+            }
+            DiffOp::Delete { .. } => {}
+        }
+    }
+    Some((at, merged))
 }
 
 /// Apply the `(start_index, old_len, new_lines)` replacements to `original_lines`,
@@ -812,6 +1131,7 @@ pub fn unified_diff_from_chunks_with_context(
     let AppliedPatch {
         original_contents,
         new_contents,
+        ..
     } = derive_new_contents_from_chunks(path, chunks)?;
     let text_diff = TextDiff::from_lines(&original_contents, &new_contents);
     let unified_diff = text_diff.unified_diff().context_radius(context).to_string();
@@ -827,7 +1147,33 @@ pub fn print_summary(
     affected: &AffectedPaths,
     out: &mut impl std::io::Write,
 ) -> std::io::Result<()> {
-    writeln!(out, "Success. Updated the following files:")?;
+    write_summary(affected, SummaryKind::Applied, out)
+}
+
+#[derive(Clone, Copy)]
+enum SummaryKind {
+    Applied,
+    DryRun,
+}
+
+fn write_summary(
+    affected: &AffectedPaths,
+    kind: SummaryKind,
+    out: &mut impl std::io::Write,
+) -> std::io::Result<()> {
+    let partial = affected
+        .hunks
+        .iter()
+        .any(|report| matches!(report.outcome, HunkOutcome::Failed(_)));
+    let header = match (kind, partial) {
+        (SummaryKind::Applied, false) => "Success. Updated the following files:",
+        (SummaryKind::Applied, true) => "Partially applied. Updated the following files:",
+        (SummaryKind::DryRun, false) => "Dry run. The patch would update the following files:",
+        (SummaryKind::DryRun, true) => {
+            "Dry run. The patch would partially apply, updating the following files:"
+        }
+    };
+    writeln!(out, "{header}")?;
     for path in &affected.added {
         writeln!(out, "A {}", path.display())?;
     }
@@ -837,6 +1183,29 @@ pub fn print_summary(
     for path in &affected.deleted {
         writeln!(out, "D {}", path.display())?;
     }
+    for path in &affected.reindented {
+        writeln!(
+            out,
+            "Note: re-indented the patch for {} to match the file's indentation.",
+            path.display()
+        )?;
+    }
+    for report in &affected.hunks {
+        match &report.outcome {
+            HunkOutcome::Fuzzy => writeln!(
+                out,
+                "Note: hunk {} of {} applied to drifted context; check the result.",
+                report.hunk,
+                report.path.display()
+            )?,
+            HunkOutcome::Failed(reason) => writeln!(
+                out,
+                "Not applied: hunk {} of {}: {reason}",
+                report.hunk,
+                report.path.display()
+            )?,
+        }
+    }
     Ok(())
 }
 
@@ -1337,6 +1706,41 @@ PATCH"#,
         assert_eq!(String::from_utf8(stderr).unwrap(), "");
     }
 
+    #[test]
+    fn test_update_reindents_hunk_to_match_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("main.go");
+        fs::write(&path, "func main() {\n\tif ok {\n\t\trun()\n\t}\n}\n").unwrap();
+
+        // The model wrote the hunk with spaces, while the file uses tabs.
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+   if ok {{
+     run()
++    log("done")
+   }}"#,
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            contents,
+            "func main() {\n\tif ok {\n\t\trun()\n\t\tlog(\"done\")\n\t}\n}\n"
+        );
+        let stdout_str = String::from_utf8(stdout).unwrap();
+        let expected_out = format!(
+            "Success. Updated the following files:\nM {}\nNote: re-indented the patch for {} to match the file's indentation.\n",
+            path.display(),
+            path.display()
+        );
+        assert_eq!(stdout_str, expected_out);
+    }
+
     #[test]
     fn test_unified_diff() {
         // Start with a file containing four lines.
@@ -1621,4 +2025,124 @@ g
         let result = apply_patch(&patch, &mut stdout, &mut stderr);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_update_merges_hunk_into_drifted_context() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        // The line after the change was edited since the model last read the file.
+        fs::write(&path, "alpha\nbeta\ngamma (edited)\ndelta\n").unwrap();
+
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+ alpha
+-beta
++BETA
+ gamma
+ delta"#,
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "alpha\nBETA\ngamma (edited)\ndelta\n"
+        );
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            format!(
+                "Success. Updated the following files:\nM {}\nNote: hunk 1 of {} applied to drifted context; check the result.\n",
+                path.display(),
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_update_applies_matching_hunks_and_reports_the_rest() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+-one
++ONE
+@@
+-missing
++MISSING"#,
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ONE\ntwo\nthree\n");
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            format!(
+                "Partially applied. Updated the following files:\nM {}\nNot applied: hunk 2 of {}: Failed to find expected lines in {}:\nmissing\n",
+                path.display(),
+                path.display(),
+                path.display()
+            )
+        );
+
+        // A patch none of whose hunks apply still fails, and changes nothing.
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n-missing\n+MISSING",
+            path.display()
+        ));
+        let result = apply_patch(&patch, &mut Vec::new(), &mut Vec::new());
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ONE\ntwo\nthree\n");
+    }
+
+    #[test]
+    fn test_dry_run_reports_without_writing() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("existing.txt"), "old\n").unwrap();
+
+        let patch = wrap_patch(
+            r#"*** Add File: added.txt
++new
+*** Update File: added.txt
+@@
+-new
++newer
+*** Update File: existing.txt
+@@
+-old
++new"#,
+        );
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        dry_run_patch(&patch, dir.path(), &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            "Dry run. The patch would update the following files:\nA added.txt\nM added.txt\nM existing.txt\n"
+        );
+        assert!(!dir.path().join("added.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("existing.txt")).unwrap(),
+            "old\n"
+        );
+
+        let patch = wrap_patch("*** Update File: missing.txt\n@@\n-a\n+b");
+        let mut stderr = Vec::new();
+        assert!(dry_run_patch(&patch, dir.path(), &mut Vec::new(), &mut stderr).is_err());
+        assert!(
+            String::from_utf8(stderr)
+                .unwrap()
+                .starts_with("Failed to read file to update missing.txt")
+        );
+    }
 }
@@ -0,0 +1,212 @@
+//! Adjusts the indentation of hunks whose context only matched the file after
+//! ignoring leading whitespace, e.g. because the model wrote a patch with two
+//! spaces against a file indented with tabs.
+//!
+//! This is only done for languages where indentation carries no meaning, so
+//! re-indenting the replacement lines cannot change what the code does.
+
+use std::path::Path;
+
+/// Extensions of languages whose syntax does not depend on indentation.
+const WHITESPACE_INSENSITIVE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cjs", "cpp", "cs", "css", "cxx", "dart", "go", "h", "hh", "hpp", "htm", "html",
+    "java", "js", "json", "jsonc", "jsx", "kt", "kts", "less", "lua", "m", "mjs", "mm", "php",
+    "proto", "rs", "scala", "scss", "sql", "svelte", "swift", "ts", "tsx", "vue", "xml", "zig",
+];
+
+/// Indentation unit used when a file or a hunk has no nested indentation to
+/// learn it from.
+const DEFAULT_INDENT_WIDTH: usize = 4;
+
+pub(crate) fn is_whitespace_insensitive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            WHITESPACE_INSENSITIVE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndentStyle {
+    Tabs,
+    Spaces(usize),
+}
+
+impl IndentStyle {
+    /// Learns the style from the leading whitespace of `lines`. Returns
+    /// `None` when no line is indented.
+    fn detect<'a>(lines: impl IntoIterator<Item = &'a String>) -> Option<Self> {
+        let mut tabs = 0;
+        let mut spaces = 0;
+        let mut previous_width = 0;
+        let mut steps: Vec<usize> = Vec::new();
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tabs += 1;
+                continue;
+            }
+            let width = line.len() - line.trim_start_matches(' ').len();
+            if width > 0 {
+                spaces += 1;
+            }
+            if width > previous_width {
+                steps.push(width - previous_width);
+            }
+            previous_width = width;
+        }
+        if tabs == 0 && spaces == 0 {
+            return None;
+        }
+        if tabs > spaces {
+            return Some(IndentStyle::Tabs);
+        }
+        // The most common increase in indentation is the unit; alignment of
+        // continuation lines produces odd steps that are rarer.
+        steps.sort_unstable();
+        let width = steps
+            .chunk_by(|a, b| a == b)
+            .max_by_key(|run| run.len())
+            .map(|run| run[0])
+            .unwrap_or(DEFAULT_INDENT_WIDTH);
+        Some(IndentStyle::Spaces(width))
+    }
+
+    /// Splits the leading whitespace of `line` into whole indentation levels
+    /// and leftover alignment columns.
+    fn measure(self, line: &str) -> (usize, usize) {
+        let indent = leading_whitespace(line);
+        match self {
+            IndentStyle::Tabs => {
+                let levels = indent.chars().take_while(|c| *c == '\t').count();
+                (levels, indent.len() - levels)
+            }
+            IndentStyle::Spaces(width) => {
+                let columns: usize = indent
+                    .chars()
+                    .map(|c| if c == '\t' { width } else { 1 })
+                    .sum();
+                (columns / width, columns % width)
+            }
+        }
+    }
+
+    fn render(self, levels: usize, alignment: usize) -> String {
+        match self {
+            IndentStyle::Tabs => "\t".repeat(levels) + &" ".repeat(alignment),
+            IndentStyle::Spaces(width) => " ".repeat(levels * width + alignment),
+        }
+    }
+}
+
+/// Re-indents `new_lines` so they follow the indentation of the file.
+///
+/// `pattern` holds the hunk's old lines and `matched` the file lines they
+/// were matched against. Returns `None` when the indentation of the two
+/// already agrees and nothing needs to change.
+pub(crate) fn reindent_hunk(
+    file_lines: &[String],
+    matched: &[String],
+    pattern: &[String],
+    new_lines: &[String],
+) -> Option<Vec<String>> {
+    let drifted = matched.iter().zip(pattern).any(|(line, pat)| {
+        !pat.trim().is_empty() && leading_whitespace(line) != leading_whitespace(pat)
+    });
+    if !drifted {
+        return None;
+    }
+
+    let file_style =
+        IndentStyle::detect(file_lines).unwrap_or(IndentStyle::Spaces(DEFAULT_INDENT_WIDTH));
+    let patch_style = IndentStyle::detect(pattern.iter().chain(new_lines)).unwrap_or(file_style);
+
+    // The first non-blank context line anchors the patch's levels to the
+    // file's.
+    let (file_level, patch_level) = matched
+        .iter()
+        .zip(pattern)
+        .find(|(_, pat)| !pat.trim().is_empty())
+        .map(|(line, pat)| (file_style.measure(line).0, patch_style.measure(pat).0))?;
+
+    let mut next_context = 0;
+    let reindented = new_lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return String::new();
+            }
+            // Context lines carried over unchanged keep the file's text.
+            if let Some(offset) = pattern[next_context..].iter().position(|pat| pat == line) {
+                next_context += offset + 1;
+                return matched[next_context - 1].clone();
+            }
+            let (level, alignment) = patch_style.measure(line);
+            let level = (level + file_level).saturating_sub(patch_level);
+            file_style.render(level, alignment) + line.trim_start()
+        })
+        .collect();
+    Some(reindented)
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn detects_language_from_extension() {
+        assert!(is_whitespace_insensitive(Path::new("src/main.RS")));
+        assert!(is_whitespace_insensitive(Path::new("web/app.tsx")));
+        assert!(!is_whitespace_insensitive(Path::new("tool.py")));
+        assert!(!is_whitespace_insensitive(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn converts_two_space_hunk_to_tabs() {
+        let file = lines("func main() {\n\tif ok {\n\t\trun()\n\t}\n}");
+        let matched = &file[1..4];
+        let pattern = lines("  if ok {\n    run()\n  }");
+        let new_lines = lines("  if ok {\n    run()\n    log(\"done\")\n  }");
+
+        assert_eq!(
+            reindent_hunk(&file, matched, &pattern, &new_lines),
+            Some(lines("\tif ok {\n\t\trun()\n\t\tlog(\"done\")\n\t}"))
+        );
+    }
+
+    #[test]
+    fn shifts_hunk_written_at_the_wrong_depth() {
+        let file = lines("impl A {\n    fn a() {\n        b();\n    }\n}");
+        let matched = &file[2..3];
+        let pattern = lines("b();");
+        let new_lines = lines("b();\nif c {\n    d();\n}");
+
+        assert_eq!(
+            reindent_hunk(&file, matched, &pattern, &new_lines),
+            Some(lines(
+                "        b();\n        if c {\n            d();\n        }"
+            ))
+        );
+    }
+
+    #[test]
+    fn leaves_matching_indentation_alone() {
+        let file = lines("fn a() {\n    b();\n}");
+        let pattern = lines("    b();");
+        assert_eq!(
+            reindent_hunk(&file, &file[1..2], &pattern, &lines("    c();")),
+            None
+        );
+    }
+}
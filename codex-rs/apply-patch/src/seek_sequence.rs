@@ -109,9 +109,44 @@ pub(crate) fn seek_sequence(
     None
 }
 
+/// Like [`seek_sequence`], but lines also match when they only differ in the
+/// amount of whitespace between tokens. Only used for languages where such
+/// whitespace is insignificant.
+pub(crate) fn seek_sequence_collapsing_whitespace(
+    lines: &[String],
+    pattern: &[String],
+    start: usize,
+    eof: bool,
+) -> Option<usize> {
+    if pattern.is_empty() {
+        return Some(start);
+    }
+    if pattern.len() > lines.len() {
+        return None;
+    }
+    let search_start = if eof {
+        lines.len() - pattern.len()
+    } else {
+        start
+    };
+
+    fn collapse(s: &str) -> String {
+        s.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+
+    let pattern: Vec<String> = pattern.iter().map(|line| collapse(line)).collect();
+    (search_start..=lines.len() - pattern.len()).find(|&i| {
+        pattern
+            .iter()
+            .enumerate()
+            .all(|(p_idx, pat)| collapse(&lines[i + p_idx]) == *pat)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::seek_sequence;
+    use super::seek_sequence_collapsing_whitespace;
     use std::string::ToString;
 
     fn to_vec(strings: &[&str]) -> Vec<String> {
@@ -148,4 +183,15 @@ mod tests {
         // Should not panic – must return None when pattern cannot possibly fit.
         assert_eq!(seek_sequence(&lines, &pattern, 0, false), None);
     }
+
+    #[test]
+    fn test_collapsing_match_ignores_whitespace_between_tokens() {
+        let lines = to_vec(&["\tlet x  =\t1;", "\treturn x;"]);
+        let pattern = to_vec(&["let x = 1;", "return  x;"]);
+        assert_eq!(seek_sequence(&lines, &pattern, 0, false), None);
+        assert_eq!(
+            seek_sequence_collapsing_whitespace(&lines, &pattern, 0, false),
+            Some(0)
+        );
+    }
 }
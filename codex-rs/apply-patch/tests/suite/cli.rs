@@ -88,3 +88,33 @@ fn test_apply_patch_cli_stdin_add_and_update() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_apply_patch_cli_dry_run_leaves_files_untouched() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let file = "cli_test.txt";
+    let absolute_path = tmp.path().join(file);
+    fs::write(&absolute_path, "hello\n")?;
+
+    let update_patch = format!(
+        r#"*** Begin Patch
+*** Update File: {file}
+@@
+-hello
++world
+*** End Patch"#
+    );
+    Command::cargo_bin("apply_patch")
+        .expect("should find apply_patch binary")
+        .arg("--dry-run")
+        .arg(update_patch)
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(format!(
+            "Dry run. The patch would update the following files:\nM {file}\n"
+        ));
+    assert_eq!(fs::read_to_string(&absolute_path)?, "hello\n");
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use codex_core::CODEX_APPLY_PATCH_ARG1;
+use codex_windows_sandbox::CODEX_WINDOWS_SANDBOX_ARG1;
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
 use tempfile::TempDir;
@@ -19,7 +20,9 @@ const MISSPELLED_APPLY_PATCH_ARG0: &str = "applypatch";
 ///
 /// When the current executable is invoked through the hard-link or alias named
 /// `codex-linux-sandbox` we *directly* execute
-/// [`codex_linux_sandbox::run_main`] (which never returns). Otherwise we:
+/// [`codex_linux_sandbox::run_main`] (which never returns). Likewise, when the
+/// first argument is [`CODEX_WINDOWS_SANDBOX_ARG1`] we execute
+/// [`codex_windows_sandbox::run_main`]. Otherwise we:
 ///
 /// 1.  Load `.env` values from `~/.codex/.env` before creating any threads.
 /// 2.  Construct a Tokio multi-thread runtime.
@@ -53,6 +56,10 @@ where
     }
 
     let argv1 = args.next().unwrap_or_default();
+    if argv1 == CODEX_WINDOWS_SANDBOX_ARG1 {
+        // Safety: [`run_main`] never returns.
+        codex_windows_sandbox::run_main();
+    }
     if argv1 == CODEX_APPLY_PATCH_ARG1 {
         let patch_arg = args.next().and_then(|s| s.to_str().map(str::to_owned));
         let exit_code = match patch_arg {
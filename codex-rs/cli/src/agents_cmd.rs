@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::project_doc::get_user_instructions;
+use codex_core::project_doc::load_project_docs;
+
+/// Inspect the instructions Codex assembles from AGENTS.md files.
+#[derive(Debug, clap::Parser)]
+pub struct AgentsCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: AgentsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum AgentsSubcommand {
+    /// Print the instructions a session started here would get: the
+    /// AGENTS.md files that apply, merged with includes expanded, together
+    /// with any global instructions, survey and project memory.
+    Show(ShowArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ShowArgs {
+    /// Directory to show the instructions for (default: the current one).
+    #[arg(long = "cd", short = 'C', value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+}
+
+impl AgentsCli {
+    pub async fn run(self) -> Result<()> {
+        let overrides = self
+            .config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        let AgentsSubcommand::Show(args) = self.cmd;
+        let config = Config::load_with_cli_overrides(
+            overrides,
+            ConfigOverrides {
+                cwd: args.cwd,
+                ..Default::default()
+            },
+        )
+        .context("failed to load configuration")?;
+
+        let docs = load_project_docs(&config).context("failed to read AGENTS.md files")?;
+        if docs.is_empty() {
+            eprintln!("No AGENTS.md files apply to {}.", config.cwd.display());
+        }
+        for doc in &docs {
+            eprintln!("Merged {} (priority {})", doc.path.display(), doc.priority);
+        }
+        match get_user_instructions(&config).await {
+            Some(instructions) => println!("{instructions}"),
+            None => eprintln!("Sessions started here get no instructions."),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::sandbox_audit::AuditApproval;
+use codex_core::sandbox_audit::AuditQuery;
+use codex_core::sandbox_audit::SandboxAuditRecord;
+use codex_core::sandbox_audit::audit_log_path;
+use codex_core::sandbox_audit::parse_since;
+use codex_core::sandbox_audit::read_audit_log;
+use codex_protocol::mcp_protocol::ConversationId;
+
+/// Show the commands Codex ran or refused to run, with their sandbox and
+/// approval, from `~/.codex/log/sandbox-audit.jsonl`.
+#[derive(Debug, clap::Parser)]
+pub struct AuditCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Only show commands from this session.
+    #[arg(long = "session", value_name = "SESSION_ID")]
+    pub session_id: Option<String>,
+
+    /// Only show commands since this time: an RFC 3339 timestamp or an age
+    /// such as `30m`, `12h` or `7d`.
+    #[arg(long, value_name = "TIME")]
+    pub since: Option<String>,
+
+    /// Only show commands that were denied, rejected or blocked by the sandbox.
+    #[arg(long, default_value_t = false)]
+    pub blocked: bool,
+
+    /// Show at most this many of the most recent commands.
+    #[arg(long, short = 'n', value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Print the matching records as JSON Lines.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+impl AuditCli {
+    pub fn run(self) -> Result<()> {
+        // Validate any provided overrides even though the log location only
+        // depends on CODEX_HOME.
+        self.config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+
+        let session_id = self
+            .session_id
+            .as_deref()
+            .map(ConversationId::from_string)
+            .transpose()
+            .context("invalid session id")?;
+        let since = self
+            .since
+            .as_deref()
+            .map(parse_since)
+            .transpose()
+            .map_err(|e| anyhow!(e))?;
+        let query = AuditQuery {
+            session_id,
+            since,
+            blocked_only: self.blocked,
+            limit: self.limit,
+        };
+
+        let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+        let records = read_audit_log(&codex_home, &query)
+            .with_context(|| format!("failed to read {}", audit_log_path(&codex_home).display()))?;
+
+        if self.json {
+            for record in &records {
+                println!("{}", serde_json::to_string(record)?);
+            }
+            return Ok(());
+        }
+
+        if records.is_empty() {
+            eprintln!(
+                "No matching commands in {}.",
+                audit_log_path(&codex_home).display()
+            );
+            return Ok(());
+        }
+        for record in &records {
+            print_record(record);
+        }
+        Ok(())
+    }
+}
+
+fn print_record(record: &SandboxAuditRecord) {
+    let outcome = match (record.approval, record.exit_code) {
+        (AuditApproval::Rejected, _) => "rejected".to_string(),
+        (AuditApproval::Denied, _) => "denied by user".to_string(),
+        (_, _) if record.sandbox_denied => "blocked by sandbox".to_string(),
+        (_, Some(exit_code)) => format!("exit {exit_code}"),
+        (_, None) => "did not run".to_string(),
+    };
+    let approval = match record.approval {
+        AuditApproval::Auto => "auto",
+        AuditApproval::Approved => "approved",
+        AuditApproval::ApprovedForSession => "approved for session",
+        AuditApproval::Trusted => "trusted",
+        AuditApproval::Denied | AuditApproval::Rejected => "-",
+    };
+    println!(
+        "{}  {}  sandbox={} ({})  approval={}  {}",
+        record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        record.session_id,
+        record.sandbox,
+        record.sandbox_policy,
+        approval,
+        outcome,
+    );
+    println!("    {} $ {}", record.cwd.display(), record.command_line());
+    if let Some(reason) = &record.reason {
+        println!("    reason: {reason}");
+    }
+    for denial in &record.suspected_denials {
+        println!("    possibly denied: {denial}");
+    }
+}
@@ -85,6 +85,9 @@ async fn run_command_under_sandbox(
 
     let stdio_policy = StdioPolicy::Inherit;
     let env = create_env(&config.shell_environment_policy);
+    let resource_limits = config
+        .sandbox_resource_limits
+        .for_policy(&config.sandbox_policy);
 
     let mut child = match sandbox_type {
         SandboxType::Seatbelt => {
@@ -93,6 +96,7 @@ async fn run_command_under_sandbox(
                 cwd,
                 &config.sandbox_policy,
                 sandbox_policy_cwd.as_path(),
+                &resource_limits,
                 stdio_policy,
                 env,
             )
@@ -109,6 +113,7 @@ async fn run_command_under_sandbox(
                 cwd,
                 &config.sandbox_policy,
                 sandbox_policy_cwd.as_path(),
+                &resource_limits,
                 stdio_policy,
                 env,
             )
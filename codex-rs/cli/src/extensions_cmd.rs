@@ -0,0 +1,100 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::extensions::ExtensionStatus;
+use codex_core::extensions::extensions_dir;
+use codex_core::extensions::set_extension_enabled;
+
+/// Manage the extensions installed under `$CODEX_HOME/extensions`.
+#[derive(Debug, clap::Parser)]
+pub struct ExtensionsCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: ExtensionsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ExtensionsSubcommand {
+    /// List the installed extensions, their versions and whether they are
+    /// enabled.
+    List,
+
+    /// Enable an extension for new sessions.
+    Enable(ExtensionArgs),
+
+    /// Disable an extension without uninstalling it.
+    Disable(ExtensionArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ExtensionArgs {
+    /// Name of the extension, as shown by `codex extensions list`.
+    #[arg(value_name = "NAME")]
+    pub name: String,
+}
+
+impl ExtensionsCli {
+    pub fn run(self) -> Result<()> {
+        let overrides = self
+            .config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+            .context("failed to load configuration")?;
+
+        match self.cmd {
+            ExtensionsSubcommand::List => {
+                if config.extensions.is_empty() {
+                    eprintln!(
+                        "No extensions installed in {}.",
+                        extensions_dir(&config.codex_home).display()
+                    );
+                }
+                for extension in &config.extensions {
+                    let version = extension
+                        .manifest
+                        .as_ref()
+                        .map_or("?", |manifest| manifest.version.as_str());
+                    let status = match &extension.status {
+                        ExtensionStatus::Enabled => "enabled".to_string(),
+                        ExtensionStatus::Disabled => "disabled".to_string(),
+                        ExtensionStatus::Unusable(problem) => format!("not loaded: {problem}"),
+                    };
+                    let description = extension
+                        .manifest
+                        .as_ref()
+                        .and_then(|manifest| manifest.description.as_deref())
+                        .unwrap_or_default();
+                    println!("{}  {version}  {status}  {description}", extension.name);
+                }
+            }
+            ExtensionsSubcommand::Enable(args) => {
+                set_enabled(&config, &args.name, true)?;
+                println!("Enabled {}.", args.name);
+            }
+            ExtensionsSubcommand::Disable(args) => {
+                set_enabled(&config, &args.name, false)?;
+                println!("Disabled {}.", args.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn set_enabled(config: &Config, name: &str, enabled: bool) -> Result<()> {
+    if !config
+        .extensions
+        .iter()
+        .any(|extension| extension.name == name)
+    {
+        bail!("No extension named `{name}` is installed.");
+    }
+    set_extension_enabled(&config.codex_home, name, enabled)
+        .with_context(|| format!("failed to update {}", config.codex_home.display()))
+}
@@ -1,17 +1,27 @@
 use codex_common::CliConfigOverrides;
 use codex_core::CodexAuth;
 use codex_core::auth::CLIENT_ID;
+use codex_core::auth::list_accounts;
 use codex_core::auth::login_with_api_key;
 use codex_core::auth::logout;
+use codex_core::auth::remove_provider_api_key;
+use codex_core::auth::save_account;
+use codex_core::auth::set_provider_api_key;
+use codex_core::auth::switch_account;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config_types::CredentialStoreMode;
 use codex_login::ServerOptions;
 use codex_login::run_login_server;
 use codex_protocol::mcp_protocol::AuthMode;
+use std::io::BufRead;
 use std::path::PathBuf;
 
-pub async fn login_with_chatgpt(codex_home: PathBuf) -> std::io::Result<()> {
-    let opts = ServerOptions::new(codex_home, CLIENT_ID.to_string());
+pub async fn login_with_chatgpt(
+    codex_home: PathBuf,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<()> {
+    let opts = ServerOptions::new(codex_home, CLIENT_ID.to_string(), credential_store_mode);
     let server = run_login_server(opts)?;
 
     eprintln!(
@@ -25,7 +35,7 @@ pub async fn login_with_chatgpt(codex_home: PathBuf) -> std::io::Result<()> {
 pub async fn run_login_with_chatgpt(cli_config_overrides: CliConfigOverrides) -> ! {
     let config = load_config_or_exit(cli_config_overrides);
 
-    match login_with_chatgpt(config.codex_home).await {
+    match login_with_chatgpt(config.codex_home, config.credential_store).await {
         Ok(_) => {
             eprintln!("Successfully logged in");
             std::process::exit(0);
@@ -43,7 +53,7 @@ pub async fn run_login_with_api_key(
 ) -> ! {
     let config = load_config_or_exit(cli_config_overrides);
 
-    match login_with_api_key(&config.codex_home, &api_key) {
+    match login_with_api_key(&config.codex_home, &api_key, config.credential_store) {
         Ok(_) => {
             eprintln!("Successfully logged in");
             std::process::exit(0);
@@ -58,7 +68,7 @@ pub async fn run_login_with_api_key(
 pub async fn run_login_status(cli_config_overrides: CliConfigOverrides) -> ! {
     let config = load_config_or_exit(cli_config_overrides);
 
-    match CodexAuth::from_codex_home(&config.codex_home) {
+    match CodexAuth::from_codex_home(&config.codex_home, config.credential_store) {
         Ok(Some(auth)) => match auth.mode {
             AuthMode::ApiKey => match auth.get_token().await {
                 Ok(api_key) => {
@@ -89,7 +99,7 @@ pub async fn run_login_status(cli_config_overrides: CliConfigOverrides) -> ! {
 pub async fn run_logout(cli_config_overrides: CliConfigOverrides) -> ! {
     let config = load_config_or_exit(cli_config_overrides);
 
-    match logout(&config.codex_home) {
+    match logout(&config.codex_home, config.credential_store) {
         Ok(true) => {
             eprintln!("Successfully logged out");
             std::process::exit(0);
@@ -105,6 +115,125 @@ pub async fn run_logout(cli_config_overrides: CliConfigOverrides) -> ! {
     }
 }
 
+pub async fn run_auth_list(cli_config_overrides: CliConfigOverrides) -> ! {
+    let config = load_config_or_exit(cli_config_overrides);
+
+    match list_accounts(&config.codex_home, config.credential_store) {
+        Ok(accounts) if accounts.is_empty() => {
+            eprintln!("No stored accounts. Save the current login with `codex auth save <NAME>`.");
+            std::process::exit(0);
+        }
+        Ok(accounts) => {
+            for account in accounts {
+                let marker = if account.active { "*" } else { " " };
+                let mode = match account.mode {
+                    AuthMode::ApiKey => "API key".to_string(),
+                    AuthMode::ChatGPT => match account.email {
+                        Some(email) => format!("ChatGPT ({email})"),
+                        None => "ChatGPT".to_string(),
+                    },
+                };
+                println!("{marker} {}\t{mode}", account.name);
+            }
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error reading stored accounts: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn run_auth_use(cli_config_overrides: CliConfigOverrides, name: String) -> ! {
+    let config = load_config_or_exit(cli_config_overrides);
+
+    match switch_account(&config.codex_home, &name, config.credential_store) {
+        Ok(()) => {
+            eprintln!("Switched to account `{name}`");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error switching account: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn run_auth_save(cli_config_overrides: CliConfigOverrides, name: String) -> ! {
+    let config = load_config_or_exit(cli_config_overrides);
+
+    match save_account(&config.codex_home, &name, config.credential_store) {
+        Ok(()) => {
+            eprintln!("Saved current login as account `{name}`");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error saving account: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn run_auth_set_key(
+    cli_config_overrides: CliConfigOverrides,
+    provider: String,
+    api_key: Option<String>,
+) -> ! {
+    let config = load_config_or_exit(cli_config_overrides);
+
+    let api_key = match api_key {
+        Some(api_key) => api_key,
+        None => {
+            eprintln!("Enter the API key for `{provider}`:");
+            let mut line = String::new();
+            if let Err(e) = std::io::stdin().lock().read_line(&mut line) {
+                eprintln!("Error reading API key: {e}");
+                std::process::exit(1);
+            }
+            line
+        }
+    };
+
+    match set_provider_api_key(
+        &config.codex_home,
+        &config.model_providers,
+        &provider,
+        &api_key,
+        config.credential_store,
+    ) {
+        Ok(()) => {
+            eprintln!(
+                "Saved API key for `{provider}` - {}",
+                safe_format_key(api_key.trim())
+            );
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error saving API key: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn run_auth_remove_key(cli_config_overrides: CliConfigOverrides, provider: String) -> ! {
+    let config = load_config_or_exit(cli_config_overrides);
+
+    match remove_provider_api_key(&config.codex_home, &provider, config.credential_store) {
+        Ok(true) => {
+            eprintln!("Removed API key for `{provider}`");
+            std::process::exit(0);
+        }
+        Ok(false) => {
+            eprintln!("No API key stored for `{provider}`");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error removing API key: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
 fn load_config_or_exit(cli_config_overrides: CliConfigOverrides) -> Config {
     let cli_overrides = match cli_config_overrides.parse_overrides() {
         Ok(v) => v,
@@ -7,6 +7,11 @@ use codex_chatgpt::apply_command::ApplyCommand;
 use codex_chatgpt::apply_command::run_apply_command;
 use codex_cli::LandlockCommand;
 use codex_cli::SeatbeltCommand;
+use codex_cli::login::run_auth_list;
+use codex_cli::login::run_auth_remove_key;
+use codex_cli::login::run_auth_save;
+use codex_cli::login::run_auth_set_key;
+use codex_cli::login::run_auth_use;
 use codex_cli::login::run_login_status;
 use codex_cli::login::run_login_with_api_key;
 use codex_cli::login::run_login_with_chatgpt;
@@ -14,16 +19,34 @@ use codex_cli::login::run_logout;
 use codex_cli::proto;
 use codex_common::CliConfigOverrides;
 use codex_exec::Cli as ExecCli;
+use codex_exec::ExecCommand;
+use codex_exec::SurveyArgs;
 use codex_tui::AppExitInfo;
 use codex_tui::Cli as TuiCli;
 use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod agents_cmd;
+mod audit_cmd;
+mod extensions_cmd;
 mod mcp_cmd;
-
+mod report_cmd;
+mod review_cmd;
+mod sessions_cmd;
+mod worktree_cmd;
+
+use crate::agents_cmd::AgentsCli;
+use crate::audit_cmd::AuditCli;
+use crate::extensions_cmd::ExtensionsCli;
 use crate::mcp_cmd::McpCli;
 use crate::proto::ProtoCli;
+use crate::report_cmd::ReportCli;
+use crate::review_cmd::ReviewCli;
+use crate::sessions_cmd::SessionsCli;
+use crate::worktree_cmd::WorktreeCli;
+use crate::worktree_cmd::create_session_worktree;
+use crate::worktree_cmd::finish_session_worktree;
 
 /// Codex CLI
 ///
@@ -46,15 +69,36 @@ struct MultitoolCli {
     #[clap(flatten)]
     interactive: TuiCli,
 
+    /// Run the interactive session in a new git worktree and branch of the
+    /// current repository, so your working tree is left untouched; at the end,
+    /// choose to merge the work, open a pull request, keep it or discard it.
+    /// Cannot be combined with a subcommand.
+    #[arg(long = "worktree", default_value_t = false)]
+    worktree: bool,
+
     #[clap(subcommand)]
     subcommand: Option<Subcommand>,
 }
 
+impl MultitoolCli {
+    /// Rejects flags that only apply to the interactive session when a
+    /// subcommand is given, instead of silently ignoring them.
+    fn validate(&self) -> Result<(), clap::Error> {
+        if self.worktree && self.subcommand.is_some() {
+            return Err(MultitoolCli::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "`--worktree` only applies to the interactive session and cannot be used with a subcommand",
+            ));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, clap::Subcommand)]
 enum Subcommand {
     /// Run Codex non-interactively.
     #[clap(visible_alias = "e")]
-    Exec(ExecCli),
+    Exec(Box<ExecCli>),
 
     /// Manage login.
     Login(LoginCommand),
@@ -62,6 +106,13 @@ enum Subcommand {
     /// Remove stored authentication credentials.
     Logout(LogoutCommand),
 
+    /// Manage stored accounts and switch between them.
+    Auth(AuthCommand),
+
+    /// Explore the repository under a token budget and save an architecture
+    /// overview that later sessions load as context.
+    Survey(SurveyCommand),
+
     /// [experimental] Run Codex as an MCP server and manage MCP servers.
     Mcp(McpCli),
 
@@ -69,6 +120,30 @@ enum Subcommand {
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
 
+    /// Review a diff or pull request and print the findings as text, JSON
+    /// or SARIF.
+    Review(ReviewCli),
+
+    /// Show the sandbox audit log of commands Codex ran or refused to run.
+    Audit(AuditCli),
+
+    /// Summarize what Codex did over a period as a Markdown report.
+    Report(ReportCli),
+
+    /// Show token usage and estimated cost of recorded sessions.
+    Sessions(SessionsCli),
+
+    /// List, merge, open pull requests for, or remove the git worktrees of
+    /// `--worktree` sessions.
+    Worktree(WorktreeCli),
+
+    /// Show the instructions assembled from AGENTS.md files.
+    Agents(AgentsCli),
+
+    /// List, enable or disable the extensions installed under
+    /// `$CODEX_HOME/extensions`.
+    Extensions(ExtensionsCli),
+
     /// Generate shell completion scripts.
     Completion(CompletionCommand),
 
@@ -148,6 +223,70 @@ struct LogoutCommand {
     config_overrides: CliConfigOverrides,
 }
 
+#[derive(Debug, Parser)]
+struct AuthCommand {
+    #[clap(skip)]
+    config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    action: AuthSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AuthSubcommand {
+    /// List stored accounts.
+    List,
+
+    /// Switch to a stored account.
+    Use {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+
+    /// Store the current login under NAME so it can be switched back to later.
+    Save {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+
+    /// Store or replace the API key for a provider in `model_providers`.
+    /// The key is read from stdin unless --api-key is given.
+    SetKey {
+        #[arg(long, value_name = "ID")]
+        provider: String,
+
+        #[arg(long = "api-key", value_name = "API_KEY")]
+        api_key: Option<String>,
+    },
+
+    /// Remove the stored API key for a provider.
+    RemoveKey {
+        #[arg(long, value_name = "ID")]
+        provider: String,
+    },
+}
+
+#[derive(Debug, Parser)]
+struct SurveyCommand {
+    #[clap(flatten)]
+    args: SurveyArgs,
+
+    /// Model the agent should use.
+    #[arg(long, short = 'm')]
+    model: Option<String>,
+
+    /// Survey the specified directory instead of the current one.
+    #[clap(long = "cd", short = 'C', value_name = "DIR")]
+    cwd: Option<PathBuf>,
+
+    /// Allow surveying a directory outside a Git repository.
+    #[arg(long = "skip-git-repo-check", default_value_t = false)]
+    skip_git_repo_check: bool,
+
+    #[clap(skip)]
+    config_overrides: CliConfigOverrides,
+}
+
 #[derive(Debug, Parser)]
 struct GenerateTsCommand {
     /// Output directory where .ts files will be written
@@ -159,6 +298,29 @@ struct GenerateTsCommand {
     prettier: Option<PathBuf>,
 }
 
+/// `codex survey` is `codex exec survey` with a reduced set of options.
+fn survey_exec_cli(
+    survey_cli: SurveyCommand,
+    root_config_overrides: CliConfigOverrides,
+) -> ExecCli {
+    let SurveyCommand {
+        args,
+        model,
+        cwd,
+        skip_git_repo_check,
+        mut config_overrides,
+    } = survey_cli;
+    prepend_config_flags(&mut config_overrides, root_config_overrides);
+
+    let mut exec_cli = ExecCli::parse_from(["codex exec"]);
+    exec_cli.command = Some(ExecCommand::Survey(args));
+    exec_cli.model = model;
+    exec_cli.cwd = cwd;
+    exec_cli.skip_git_repo_check = skip_git_repo_check;
+    exec_cli.config_overrides = config_overrides;
+    exec_cli
+}
+
 fn format_exit_messages(exit_info: AppExitInfo, color_enabled: bool) -> Vec<String> {
     let AppExitInfo {
         token_usage,
@@ -223,11 +385,16 @@ fn main() -> anyhow::Result<()> {
 }
 
 async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
+    let cli = MultitoolCli::parse();
+    if let Err(err) = cli.validate() {
+        err.exit();
+    }
     let MultitoolCli {
         config_overrides: root_config_overrides,
         mut interactive,
+        worktree,
         subcommand,
-    } = MultitoolCli::parse();
+    } = cli;
 
     match subcommand {
         None => {
@@ -235,14 +402,33 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 &mut interactive.config_overrides,
                 root_config_overrides.clone(),
             );
+            let worktree = if worktree {
+                let cwd = match &interactive.cwd {
+                    Some(cwd) => cwd.clone(),
+                    None => std::env::current_dir()?,
+                };
+                let (worktree, session_cwd) = create_session_worktree(&cwd).await?;
+                interactive.cwd = Some(session_cwd);
+                Some(worktree)
+            } else {
+                None
+            };
+            let session_config_overrides = interactive.config_overrides.clone();
             let exit_info = codex_tui::run_main(interactive, codex_linux_sandbox_exe).await?;
             print_exit_messages(exit_info);
+            if let Some(worktree) = worktree {
+                finish_session_worktree(&worktree, &session_config_overrides).await?;
+            }
         }
         Some(Subcommand::Exec(mut exec_cli)) => {
             prepend_config_flags(
                 &mut exec_cli.config_overrides,
                 root_config_overrides.clone(),
             );
+            codex_exec::run_main(*exec_cli, codex_linux_sandbox_exe).await?;
+        }
+        Some(Subcommand::Survey(survey_cli)) => {
+            let exec_cli = survey_exec_cli(survey_cli, root_config_overrides.clone());
             codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
         }
         Some(Subcommand::Mcp(mut mcp_cli)) => {
@@ -289,6 +475,27 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             );
             run_logout(logout_cli.config_overrides).await;
         }
+        Some(Subcommand::Auth(mut auth_cli)) => {
+            prepend_config_flags(
+                &mut auth_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            match auth_cli.action {
+                AuthSubcommand::List => run_auth_list(auth_cli.config_overrides).await,
+                AuthSubcommand::Use { name } => {
+                    run_auth_use(auth_cli.config_overrides, name).await;
+                }
+                AuthSubcommand::Save { name } => {
+                    run_auth_save(auth_cli.config_overrides, name).await;
+                }
+                AuthSubcommand::SetKey { provider, api_key } => {
+                    run_auth_set_key(auth_cli.config_overrides, provider, api_key).await;
+                }
+                AuthSubcommand::RemoveKey { provider } => {
+                    run_auth_remove_key(auth_cli.config_overrides, provider).await;
+                }
+            }
+        }
         Some(Subcommand::Proto(mut proto_cli)) => {
             prepend_config_flags(
                 &mut proto_cli.config_overrides,
@@ -296,6 +503,55 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             );
             proto::run_main(proto_cli).await?;
         }
+        Some(Subcommand::Audit(mut audit_cli)) => {
+            prepend_config_flags(
+                &mut audit_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            audit_cli.run()?;
+        }
+        Some(Subcommand::Report(mut report_cli)) => {
+            prepend_config_flags(
+                &mut report_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            report_cli.run()?;
+        }
+        Some(Subcommand::Sessions(mut sessions_cli)) => {
+            prepend_config_flags(
+                &mut sessions_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            sessions_cli.run()?;
+        }
+        Some(Subcommand::Worktree(mut worktree_cli)) => {
+            prepend_config_flags(
+                &mut worktree_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            worktree_cli.run().await?;
+        }
+        Some(Subcommand::Review(mut review_cli)) => {
+            prepend_config_flags(
+                &mut review_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            review_cli.run().await?;
+        }
+        Some(Subcommand::Agents(mut agents_cli)) => {
+            prepend_config_flags(
+                &mut agents_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            agents_cli.run().await?;
+        }
+        Some(Subcommand::Extensions(mut extensions_cli)) => {
+            prepend_config_flags(
+                &mut extensions_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            extensions_cli.run()?;
+        }
         Some(Subcommand::Completion(completion_cli)) => {
             print_completion(completion_cli);
         }
@@ -434,6 +690,7 @@ mod tests {
         let MultitoolCli {
             interactive,
             config_overrides: root_overrides,
+            worktree: _,
             subcommand,
         } = cli;
 
@@ -495,6 +752,51 @@ mod tests {
         assert!(lines[1].contains("\u{1b}[36m"));
     }
 
+    #[test]
+    fn worktree_is_rejected_with_a_subcommand() {
+        let cli =
+            MultitoolCli::try_parse_from(["codex", "--worktree", "exec", "fix it"]).expect("parse");
+        let err = cli.validate().expect_err("--worktree with exec");
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        let cli = MultitoolCli::try_parse_from(["codex", "--worktree"]).expect("parse");
+        assert!(cli.validate().is_ok());
+    }
+
+    #[test]
+    fn survey_forwards_to_exec_survey() {
+        let cli = MultitoolCli::try_parse_from(
+            [
+                "codex",
+                "-c",
+                "a=1",
+                "survey",
+                "--max-tokens",
+                "5000",
+                "-m",
+                "gpt-5-test",
+                "the tui",
+            ]
+            .as_ref(),
+        )
+        .expect("parse");
+        let Some(Subcommand::Survey(survey_cli)) = cli.subcommand else {
+            unreachable!()
+        };
+
+        let exec_cli = survey_exec_cli(survey_cli, cli.config_overrides);
+        let Some(ExecCommand::Survey(args)) = exec_cli.command else {
+            unreachable!()
+        };
+        assert_eq!(args.max_tokens, 5000);
+        assert_eq!(args.focus.as_deref(), Some("the tui"));
+        assert_eq!(exec_cli.model.as_deref(), Some("gpt-5-test"));
+        assert_eq!(
+            exec_cli.config_overrides.raw_overrides,
+            vec!["a=1".to_string()]
+        );
+    }
+
     #[test]
     fn resume_model_flag_applies_when_no_root_flags() {
         let interactive = finalize_from_args(["codex", "resume", "-m", "gpt-5-test"].as_ref());
@@ -13,6 +13,10 @@ use codex_core::config::load_global_mcp_servers;
 use codex_core::config::write_global_mcp_servers;
 use codex_core::config_types::McpServerConfig;
 use codex_core::config_types::McpServerTransportConfig;
+use codex_core::credential_store::credential_store;
+use codex_core::mcp_oauth::remove_mcp_oauth_credentials;
+use codex_login::McpLoginOptions;
+use codex_login::login_mcp_server;
 
 /// [experimental] Launch Codex as an MCP server or manage configured MCP servers.
 ///
@@ -22,6 +26,8 @@ use codex_core::config_types::McpServerTransportConfig;
 /// - `get`    — show a single server (with `--json`)
 /// - `add`    — add a server launcher entry to `~/.codex/config.toml`
 /// - `remove` — delete a server entry
+/// - `login`  — sign in to a remote server that requires OAuth
+/// - `logout` — delete the OAuth tokens of a remote server
 #[derive(Debug, clap::Parser)]
 pub struct McpCli {
     #[clap(flatten)]
@@ -47,6 +53,12 @@ pub enum McpSubcommand {
 
     /// [experimental] Remove a global MCP server entry.
     Remove(RemoveArgs),
+
+    /// [experimental] Sign in to a remote MCP server that requires OAuth.
+    Login(LoginArgs),
+
+    /// [experimental] Delete the stored OAuth tokens of a remote MCP server.
+    Logout(LogoutArgs),
 }
 
 #[derive(Debug, clap::Parser)]
@@ -86,6 +98,22 @@ pub struct RemoveArgs {
     pub name: String,
 }
 
+#[derive(Debug, clap::Parser)]
+pub struct LoginArgs {
+    /// Name of the MCP server to sign in to.
+    pub name: String,
+
+    /// Print the sign-in URL instead of opening the browser.
+    #[arg(long)]
+    pub no_browser: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct LogoutArgs {
+    /// Name of the MCP server whose tokens to delete.
+    pub name: String,
+}
+
 impl McpCli {
     pub async fn run(self, codex_linux_sandbox_exe: Option<PathBuf>) -> Result<()> {
         let McpCli {
@@ -110,6 +138,12 @@ impl McpCli {
             McpSubcommand::Remove(args) => {
                 run_remove(&config_overrides, args)?;
             }
+            McpSubcommand::Login(args) => {
+                run_login(&config_overrides, args).await?;
+            }
+            McpSubcommand::Logout(args) => {
+                run_logout(&config_overrides, args)?;
+            }
         }
 
         Ok(())
@@ -152,6 +186,8 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
         },
         startup_timeout_sec: None,
         tool_timeout_sec: None,
+        enabled_tools: None,
+        disabled_tools: None,
     };
 
     servers.insert(name.clone(), new_entry);
@@ -191,6 +227,54 @@ fn run_remove(config_overrides: &CliConfigOverrides, remove_args: RemoveArgs) ->
     Ok(())
 }
 
+async fn run_login(config_overrides: &CliConfigOverrides, login_args: LoginArgs) -> Result<()> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+    let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+        .context("failed to load configuration")?;
+
+    let LoginArgs { name, no_browser } = login_args;
+    let Some(server) = config.mcp_servers.get(&name) else {
+        bail!("No MCP server named '{name}' found.");
+    };
+    let url = match &server.transport {
+        McpServerTransportConfig::StreamableHttp { url, .. }
+        | McpServerTransportConfig::Sse { url, .. } => url.clone(),
+        McpServerTransportConfig::Stdio { .. } => {
+            bail!("MCP server '{name}' runs locally over stdio; only remote servers use OAuth.");
+        }
+    };
+
+    login_mcp_server(McpLoginOptions {
+        codex_home: config.codex_home.clone(),
+        credential_store_mode: config.credential_store,
+        server_name: name.clone(),
+        url,
+        open_browser: !no_browser,
+    })
+    .await
+    .with_context(|| format!("failed to sign in to MCP server '{name}'"))?;
+
+    println!("Signed in to MCP server '{name}'.");
+    Ok(())
+}
+
+fn run_logout(config_overrides: &CliConfigOverrides, logout_args: LogoutArgs) -> Result<()> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+    let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+        .context("failed to load configuration")?;
+
+    let LogoutArgs { name } = logout_args;
+    let store = credential_store(&config.codex_home, config.credential_store);
+    if remove_mcp_oauth_credentials(store.as_ref(), &name)
+        .context("failed to update stored credentials")?
+    {
+        println!("Signed out of MCP server '{name}'.");
+    } else {
+        println!("No stored tokens for MCP server '{name}'.");
+    }
+    Ok(())
+}
+
 fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Result<()> {
     let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
     let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
@@ -210,13 +294,26 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
                         "args": args,
                         "env": env,
                     }),
-                    McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
-                        serde_json::json!({
-                            "type": "streamable_http",
-                            "url": url,
-                            "bearer_token": bearer_token,
-                        })
-                    }
+                    McpServerTransportConfig::StreamableHttp {
+                        url,
+                        bearer_token,
+                        headers,
+                    } => serde_json::json!({
+                        "type": "streamable_http",
+                        "url": url,
+                        "bearer_token": bearer_token,
+                        "headers": headers,
+                    }),
+                    McpServerTransportConfig::Sse {
+                        url,
+                        bearer_token,
+                        headers,
+                    } => serde_json::json!({
+                        "type": "sse",
+                        "url": url,
+                        "bearer_token": bearer_token,
+                        "headers": headers,
+                    }),
                 };
 
                 serde_json::json!({
@@ -228,6 +325,8 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
                     "tool_timeout_sec": cfg
                         .tool_timeout_sec
                         .map(|timeout| timeout.as_secs_f64()),
+                    "enabled_tools": cfg.enabled_tools,
+                    "disabled_tools": cfg.disabled_tools,
                 })
             })
             .collect();
@@ -267,7 +366,12 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
                 };
                 stdio_rows.push([name.clone(), command.clone(), args_display, env_display]);
             }
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+            McpServerTransportConfig::StreamableHttp {
+                url, bearer_token, ..
+            }
+            | McpServerTransportConfig::Sse {
+                url, bearer_token, ..
+            } => {
                 let has_bearer = if bearer_token.is_some() {
                     "True"
                 } else {
@@ -368,10 +472,25 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
                 "args": args,
                 "env": env,
             }),
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => serde_json::json!({
+            McpServerTransportConfig::StreamableHttp {
+                url,
+                bearer_token,
+                headers,
+            } => serde_json::json!({
                 "type": "streamable_http",
                 "url": url,
                 "bearer_token": bearer_token,
+                "headers": headers,
+            }),
+            McpServerTransportConfig::Sse {
+                url,
+                bearer_token,
+                headers,
+            } => serde_json::json!({
+                "type": "sse",
+                "url": url,
+                "bearer_token": bearer_token,
+                "headers": headers,
             }),
         };
         let output = serde_json::to_string_pretty(&serde_json::json!({
@@ -383,6 +502,8 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
             "tool_timeout_sec": server
                 .tool_timeout_sec
                 .map(|timeout| timeout.as_secs_f64()),
+            "enabled_tools": server.enabled_tools,
+            "disabled_tools": server.disabled_tools,
         }))?;
         println!("{output}");
         return Ok(());
@@ -414,11 +535,21 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
             };
             println!("  env: {env_display}");
         }
-        McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token,
+            headers,
+        } => {
             println!("  transport: streamable_http");
-            println!("  url: {url}");
-            let bearer = bearer_token.as_deref().unwrap_or("-");
-            println!("  bearer_token: {bearer}");
+            print_http_transport(url, bearer_token.as_deref(), headers.as_ref());
+        }
+        McpServerTransportConfig::Sse {
+            url,
+            bearer_token,
+            headers,
+        } => {
+            println!("  transport: sse");
+            print_http_transport(url, bearer_token.as_deref(), headers.as_ref());
         }
     }
     if let Some(timeout) = server.startup_timeout_sec {
@@ -427,11 +558,36 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
     if let Some(timeout) = server.tool_timeout_sec {
         println!("  tool_timeout_sec: {}", timeout.as_secs_f64());
     }
+    if let Some(tools) = &server.enabled_tools {
+        println!("  enabled_tools: {}", tools.join(", "));
+    }
+    if let Some(tools) = &server.disabled_tools {
+        println!("  disabled_tools: {}", tools.join(", "));
+    }
     println!("  remove: codex mcp remove {}", get_args.name);
 
     Ok(())
 }
 
+fn print_http_transport(
+    url: &str,
+    bearer_token: Option<&str>,
+    headers: Option<&HashMap<String, String>>,
+) {
+    println!("  url: {url}");
+    println!("  bearer_token: {}", bearer_token.unwrap_or("-"));
+    // Header values often hold credentials, so only the names are shown.
+    let headers_display = match headers {
+        Some(map) if !map.is_empty() => {
+            let mut names: Vec<_> = map.keys().map(String::as_str).collect();
+            names.sort();
+            names.join(", ")
+        }
+        _ => "-".to_string(),
+    };
+    println!("  headers: {headers_display}");
+}
+
 fn parse_env_pair(raw: &str) -> Result<(String, String), String> {
     let mut parts = raw.splitn(2, '=');
     let key = parts
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::sandbox_audit::parse_since;
+use codex_core::team_report::TokenPrices;
+use codex_core::team_report::build_report;
+
+/// Summarize what Codex did over a period as a Markdown report: sessions,
+/// patches applied, tests fixed, tokens used and why tool calls failed.
+#[derive(Debug, clap::Parser)]
+pub struct ReportCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Cover sessions active since this time: an RFC 3339 timestamp or an age
+    /// such as `12h`, `7d` or `2w`.
+    #[arg(long, value_name = "TIME", default_value = "1w")]
+    pub since: String,
+
+    /// Write the report to this file instead of stdout.
+    #[arg(long, short = 'o', value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Print the report as JSON instead of Markdown.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    /// Price of input tokens in US dollars per million, to estimate the cost.
+    #[arg(long, value_name = "USD", requires = "output_price")]
+    pub input_price: Option<f64>,
+
+    /// Price of cached input tokens in US dollars per million. Defaults to
+    /// the input price.
+    #[arg(long, value_name = "USD", requires = "input_price")]
+    pub cached_input_price: Option<f64>,
+
+    /// Price of output tokens in US dollars per million, to estimate the cost.
+    #[arg(long, value_name = "USD", requires = "input_price")]
+    pub output_price: Option<f64>,
+}
+
+impl ReportCli {
+    pub fn run(self) -> Result<()> {
+        // Validate any provided overrides even though the sessions location
+        // only depends on CODEX_HOME.
+        self.config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+
+        let since = parse_since(&self.since).map_err(|e| anyhow!(e))?;
+        let prices = self
+            .input_price
+            .zip(self.output_price)
+            .map(|(input, output)| TokenPrices {
+                input,
+                cached_input: self.cached_input_price.unwrap_or(input),
+                output,
+            });
+
+        let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+        let report = build_report(&codex_home, since, prices.as_ref())
+            .context("failed to read session rollouts")?;
+
+        let rendered = if self.json {
+            format!("{}\n", serde_json::to_string_pretty(&report)?)
+        } else {
+            report.to_markdown()
+        };
+        match &self.output {
+            Some(path) => std::fs::write(path, rendered)
+                .with_context(|| format!("failed to write {}", path.display()))?,
+            None => print!("{rendered}"),
+        }
+        Ok(())
+    }
+}
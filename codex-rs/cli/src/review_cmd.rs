@@ -0,0 +1,153 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExitedReviewModeEvent;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewOutputEvent;
+use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::ReviewTarget;
+use codex_core::review_format::format_review_findings_block;
+use codex_core::review_format::review_to_sarif;
+use codex_core::review_target::parse_review_target;
+use codex_core::review_target::review_request;
+use codex_protocol::config_types::SandboxMode;
+
+/// Review a diff or pull request and print the findings.
+#[derive(Debug, clap::Parser)]
+pub struct ReviewCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// What to review: a revision range such as `main..HEAD`, a commit, or a
+    /// pull request URL. Defaults to the uncommitted changes.
+    #[arg(value_name = "TARGET", conflicts_with = "staged")]
+    pub target: Option<String>,
+
+    /// Review only the changes staged for commit.
+    #[arg(long)]
+    pub staged: bool,
+
+    /// How to print the findings.
+    #[arg(long, value_enum, default_value_t = ReviewFormat::Text)]
+    pub format: ReviewFormat,
+
+    /// Write the findings to this file instead of stdout.
+    #[arg(long, short = 'o', value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Repository to review (default: the current directory).
+    #[arg(long = "cd", short = 'C', value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReviewFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl ReviewCli {
+    pub async fn run(self) -> Result<()> {
+        let overrides = self
+            .config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        // The reviewer only reads; nobody is there to approve anything else.
+        let config = Config::load_with_cli_overrides(
+            overrides,
+            ConfigOverrides {
+                cwd: self.cwd,
+                approval_policy: Some(AskForApproval::Never),
+                sandbox_mode: Some(SandboxMode::ReadOnly),
+                ..Default::default()
+            },
+        )
+        .context("failed to load configuration")?;
+
+        let target = if self.staged {
+            ReviewTarget::StagedChanges
+        } else {
+            parse_review_target(self.target.as_deref().unwrap_or_default())
+        };
+        let request = review_request(&target, &config.cwd)
+            .await
+            .context("failed to prepare the review")?;
+        eprintln!("Reviewing {}…", request.user_facing_hint);
+
+        let review = run_review(&config, request).await?;
+        let output = match self.format {
+            ReviewFormat::Text => format_text(&review),
+            ReviewFormat::Json => serde_json::to_string_pretty(&review)?,
+            ReviewFormat::Sarif => {
+                serde_json::to_string_pretty(&review_to_sarif(&review, &config.cwd))?
+            }
+        };
+        match self.output {
+            Some(path) => {
+                std::fs::write(&path, format!("{output}\n"))
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                eprintln!(
+                    "Wrote {} finding(s) to {}",
+                    review.findings.len(),
+                    path.display()
+                );
+            }
+            None => println!("{output}"),
+        }
+        Ok(())
+    }
+}
+
+/// Runs the review in a new session and waits for its findings.
+async fn run_review(config: &Config, request: ReviewRequest) -> Result<ReviewOutputEvent> {
+    let conversation_manager = ConversationManager::new(AuthManager::shared_from_config(config));
+    let NewConversation { conversation, .. } = conversation_manager
+        .new_conversation(config.clone())
+        .await?;
+    conversation
+        .submit(Op::Review {
+            review_request: request,
+        })
+        .await?;
+
+    let review = loop {
+        match conversation.next_event().await?.msg {
+            EventMsg::ExitedReviewMode(ExitedReviewModeEvent { review_output }) => {
+                break review_output;
+            }
+            EventMsg::Error(err) => bail!("review failed: {}", err.message),
+            EventMsg::TurnAborted(_) => break None,
+            _ => {}
+        }
+    };
+    conversation.submit(Op::Shutdown).await.ok();
+    review.context("the review was interrupted before it finished")
+}
+
+fn format_text(review: &ReviewOutputEvent) -> String {
+    let mut text = String::new();
+    if !review.overall_correctness.is_empty() {
+        text.push_str(&format!("Verdict: {}\n", review.overall_correctness));
+    }
+    if !review.overall_explanation.is_empty() {
+        text.push_str(&format!("{}\n", review.overall_explanation.trim()));
+    }
+    if review.findings.is_empty() {
+        text.push_str("\nNo findings.");
+    } else {
+        text.push_str(&format_review_findings_block(&review.findings, None));
+    }
+    text.trim().to_string()
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::cost::cost_by_model;
+use codex_core::protocol::TokenUsage;
+use codex_core::sandbox_audit::parse_since;
+use codex_core::team_report::build_report;
+use codex_protocol::num_format::format_with_separators;
+use serde_json::json;
+
+/// Inspect recorded sessions.
+#[derive(Debug, clap::Parser)]
+pub struct SessionsCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: SessionsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SessionsSubcommand {
+    /// Show the tokens each session used and what they cost, per session and
+    /// in total. Costs are estimated from the `[[pricing]]` table in the
+    /// config.
+    Stats(StatsArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct StatsArgs {
+    /// Cover sessions active since this time: an RFC 3339 timestamp or an age
+    /// such as `12h`, `7d` or `2w`.
+    #[arg(long, value_name = "TIME", default_value = "1w")]
+    pub since: String,
+
+    /// Print the stats as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+impl SessionsCli {
+    pub fn run(self) -> Result<()> {
+        match self.cmd {
+            SessionsSubcommand::Stats(args) => run_stats(&self.config_overrides, args),
+        }
+    }
+}
+
+fn run_stats(config_overrides: &CliConfigOverrides, args: StatsArgs) -> Result<()> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+    let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+        .context("failed to load configuration")?;
+    let since = parse_since(&args.since).map_err(|e| anyhow!(e))?;
+
+    let report =
+        build_report(&config.codex_home, since, None).context("failed to read session rollouts")?;
+    // Rollouts do not record the provider, so prices for the configured one
+    // apply.
+    let cost = |tokens_by_model: &BTreeMap<String, TokenUsage>| {
+        cost_by_model(tokens_by_model, &config.pricing, &config.model_provider_id)
+    };
+    let sessions: Vec<_> = report
+        .sessions
+        .iter()
+        .map(|session| (session, cost(&session.tokens_by_model)))
+        .collect();
+    let total_cost = cost(&report.totals.tokens_by_model);
+
+    if args.json {
+        let sessions: Vec<_> = sessions
+            .iter()
+            .map(|(session, cost)| {
+                json!({
+                    "id": session.id,
+                    "started_at": session.started_at,
+                    "cwd": session.cwd,
+                    "tokens": session.tokens,
+                    "tokens_by_model": session.tokens_by_model,
+                    "estimated_cost_usd": cost,
+                })
+            })
+            .collect();
+        let stats = json!({
+            "since": report.since,
+            "sessions": sessions,
+            "totals": {
+                "tokens": report.totals.tokens,
+                "estimated_cost_usd": total_cost,
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    if sessions.is_empty() {
+        eprintln!(
+            "No sessions since {}.",
+            report.since.format("%Y-%m-%d %H:%M")
+        );
+        return Ok(());
+    }
+    for (session, cost) in &sessions {
+        let id = session
+            .id
+            .map_or_else(|| "-".to_string(), |id| id.to_string());
+        println!(
+            "{}  {}  {:>12} tokens  {:>9}  {}",
+            session.started_at.as_deref().unwrap_or("-"),
+            id,
+            format_with_separators(session.tokens.blended_total()),
+            format_cost(*cost),
+            session
+                .cwd
+                .as_ref()
+                .map_or_else(String::new, |cwd| cwd.display().to_string()),
+        );
+    }
+    println!(
+        "Total: {} sessions, {} tokens, {}",
+        sessions.len(),
+        format_with_separators(report.totals.tokens.blended_total()),
+        format_cost(total_cost),
+    );
+    if total_cost.is_none() {
+        println!("Add [[pricing]] entries to the config to estimate costs.");
+    }
+    Ok(())
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map_or_else(|| "-".to_string(), |cost| format!("${cost:.2}"))
+}
@@ -0,0 +1,177 @@
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::config_types::Integrations;
+use codex_core::worktree::Worktree;
+use codex_core::worktree::create_worktree;
+use codex_core::worktree::find_worktree;
+use codex_core::worktree::list_worktrees;
+
+/// Manage the git worktrees that `codex --worktree` sessions run in.
+#[derive(Debug, clap::Parser)]
+pub struct WorktreeCli {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: WorktreeSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum WorktreeSubcommand {
+    /// List the worktrees, their branches and whether they have changes.
+    List,
+
+    /// Merge a worktree's branch into the branch checked out in its
+    /// repository, committing any uncommitted changes first.
+    Merge(WorktreeArgs),
+
+    /// Push a worktree's branch to `origin` and open a pull request, as
+    /// configured under `[integrations]`.
+    Pr(WorktreeArgs),
+
+    /// Remove a worktree and delete its branch, discarding its changes.
+    Remove(WorktreeArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct WorktreeArgs {
+    /// Name of the worktree, as shown by `codex worktree list`.
+    #[arg(value_name = "NAME")]
+    pub name: String,
+}
+
+impl WorktreeCli {
+    pub async fn run(self) -> Result<()> {
+        // Validate any provided overrides even though only pull requests
+        // depend on the configuration.
+        self.config_overrides
+            .parse_overrides()
+            .map_err(|e| anyhow!(e))?;
+        let codex_home = find_codex_home().context("failed to find CODEX_HOME")?;
+
+        match self.cmd {
+            WorktreeSubcommand::List => {
+                let worktrees = list_worktrees(&codex_home).await?;
+                if worktrees.is_empty() {
+                    eprintln!("No worktrees.");
+                }
+                for worktree in worktrees {
+                    let changes = match worktree.has_changes().await {
+                        Ok(true) => "changes",
+                        Ok(false) => "no changes",
+                        Err(_) => "unknown",
+                    };
+                    println!(
+                        "{}  {}  {}  {}",
+                        worktree.name,
+                        worktree.branch,
+                        changes,
+                        worktree.repo_root.display()
+                    );
+                }
+            }
+            WorktreeSubcommand::Merge(args) => {
+                let worktree = find_worktree(&codex_home, &args.name).await?;
+                let target = worktree.merge().await?;
+                println!("Merged {} into {target}.", worktree.branch);
+            }
+            WorktreeSubcommand::Pr(args) => {
+                let worktree = find_worktree(&codex_home, &args.name).await?;
+                let integrations = load_integrations(&self.config_overrides)?;
+                println!("{}", worktree.open_pull_request(&integrations).await?);
+            }
+            WorktreeSubcommand::Remove(args) => {
+                let worktree = find_worktree(&codex_home, &args.name).await?;
+                worktree.remove().await?;
+                println!("Removed {}.", worktree.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Creates the worktree an interactive `--worktree` session runs in and
+/// returns it, with the directory in it that corresponds to `cwd`.
+pub async fn create_session_worktree(cwd: &Path) -> Result<(Worktree, std::path::PathBuf)> {
+    let codex_home = find_codex_home().context("failed to find CODEX_HOME")?;
+    let worktree = create_worktree(&codex_home, cwd)
+        .await
+        .context("--worktree needs a git repository")?;
+    let session_cwd = worktree.path_for(cwd);
+    eprintln!(
+        "Working in {} on branch {}.",
+        worktree.path.display(),
+        worktree.branch
+    );
+    Ok((worktree, session_cwd))
+}
+
+/// Asks what to do with the work of a `--worktree` session once it ends:
+/// merge it, open a pull request, keep the worktree, or discard it.
+pub async fn finish_session_worktree(
+    worktree: &Worktree,
+    config_overrides: &CliConfigOverrides,
+) -> Result<()> {
+    if !worktree.has_changes().await? {
+        worktree.remove().await?;
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!(
+            "The session left changes on {}. [m]erge, open a [p]ull request, [k]eep or [d]iscard? ",
+            worktree.branch
+        );
+        std::io::stdout().flush()?;
+        let answer = match lines.next() {
+            Some(line) => line?,
+            // No one to ask: keep the work.
+            None => "k".to_string(),
+        };
+        match answer.trim().to_lowercase().as_str() {
+            "m" | "merge" => {
+                let target = worktree.merge().await?;
+                println!("Merged {} into {target}.", worktree.branch);
+                worktree.remove().await?;
+                return Ok(());
+            }
+            "p" | "pr" => {
+                let integrations = load_integrations(config_overrides)?;
+                println!("{}", worktree.open_pull_request(&integrations).await?);
+                return Ok(());
+            }
+            "k" | "keep" | "" => {
+                println!(
+                    "Kept {}. Later, run `codex worktree merge {}`, `pr` or `remove`.",
+                    worktree.path.display(),
+                    worktree.name
+                );
+                return Ok(());
+            }
+            "d" | "discard" => {
+                worktree.remove().await?;
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The `[integrations]` pull requests are opened with.
+fn load_integrations(config_overrides: &CliConfigOverrides) -> Result<Integrations> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+    let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+        .context("failed to load configuration")?;
+    Ok(config.integrations)
+}
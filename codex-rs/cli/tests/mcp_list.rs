@@ -76,7 +76,9 @@ fn list_and_get_render_expected_output() -> Result<()> {
               }
             },
             "startup_timeout_sec": null,
-            "tool_timeout_sec": null
+            "tool_timeout_sec": null,
+            "enabled_tools": null,
+            "disabled_tools": null
           }
         ]
         )
@@ -27,6 +27,8 @@ pub use sandbox_summary::summarize_sandbox_policy;
 mod config_summary;
 
 pub use config_summary::create_config_summary_entries;
+// Batching of streamed model output, shared by the TUI and exec
+pub mod stream_coalescer;
 // Shared fuzzy matcher (used by TUI selection popups and other UI filtering)
 pub mod fuzzy_match;
 // Shared model presets used by TUI and MCP server
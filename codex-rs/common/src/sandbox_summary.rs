@@ -9,6 +9,8 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             network_access,
             exclude_tmpdir_env_var,
             exclude_slash_tmp,
+            network_allowlist,
+            deny,
         } => {
             let mut summary = "workspace-write".to_string();
 
@@ -26,6 +28,37 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
                     .map(|p| p.to_string_lossy().to_string()),
             );
 
+            summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
+            if !deny.is_empty() {
+                summary.push_str(&format!(" (read-only: {})", deny.join(", ")));
+            }
+            if *network_access {
+                summary.push_str(" (network access enabled)");
+            } else if !network_allowlist.is_empty() {
+                summary.push_str(&format!(
+                    " (network allowlist: {})",
+                    network_allowlist.join(", ")
+                ));
+            }
+            summary
+        }
+        SandboxPolicy::Container {
+            image,
+            runtime,
+            writable_roots,
+            network_access,
+        } => {
+            let mut summary = match runtime {
+                Some(runtime) => format!("container {image} ({runtime})"),
+                None => format!("container {image}"),
+            };
+
+            let mut writable_entries = vec!["workdir".to_string()];
+            writable_entries.extend(
+                writable_roots
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string()),
+            );
             summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
             if *network_access {
                 summary.push_str(" (network access enabled)");
@@ -0,0 +1,123 @@
+//! Holds back streamed model output so that it is drawn in fewer, larger
+//! chunks, as configured by [`StreamCoalescing`].
+
+use std::time::Duration;
+use std::time::Instant;
+
+use codex_core::config_types::StreamCoalescing;
+
+#[derive(Debug)]
+pub struct DeltaCoalescer {
+    /// `None` when chunks are passed through as they arrive.
+    interval: Option<Duration>,
+    max_bytes: Option<usize>,
+    pending: String,
+    pending_since: Option<Instant>,
+}
+
+impl DeltaCoalescer {
+    pub fn new(config: &StreamCoalescing) -> Self {
+        Self::with_interval(config.flush_interval(), config.max_bytes)
+    }
+
+    fn with_interval(interval: Option<Duration>, max_bytes: Option<usize>) -> Self {
+        Self {
+            interval,
+            max_bytes,
+            pending: String::new(),
+            pending_since: None,
+        }
+    }
+
+    /// Adds a streamed chunk. Returns the text to draw now, if any; the rest
+    /// is held until [`Self::deadline`].
+    pub fn push(&mut self, delta: &str, now: Instant) -> Option<String> {
+        let Some(interval) = self.interval else {
+            return Some(delta.to_string());
+        };
+        if delta.is_empty() {
+            return None;
+        }
+        self.pending.push_str(delta);
+        let since = *self.pending_since.get_or_insert(now);
+        let full = self
+            .max_bytes
+            .is_some_and(|max_bytes| self.pending.len() >= max_bytes);
+        if full || now.duration_since(since) >= interval {
+            self.take()
+        } else {
+            None
+        }
+    }
+
+    /// When the held-back text is due to be drawn, if there is any.
+    pub fn deadline(&self) -> Option<Instant> {
+        Some(self.pending_since? + self.interval?)
+    }
+
+    /// The held-back text, if it is due at `now`.
+    pub fn take_if_due(&mut self, now: Instant) -> Option<String> {
+        if self.deadline().is_some_and(|deadline| now >= deadline) {
+            self.take()
+        } else {
+            None
+        }
+    }
+
+    /// All held-back text, e.g. before the stream ends or other output is
+    /// drawn.
+    pub fn take(&mut self) -> Option<String> {
+        self.pending_since = None;
+        if self.pending.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.pending))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_chunks_through_when_disabled() {
+        let mut coalescer = DeltaCoalescer::new(&StreamCoalescing::default());
+        let now = Instant::now();
+        assert_eq!(coalescer.push("Hel", now), Some("Hel".to_string()));
+        assert_eq!(coalescer.deadline(), None);
+        assert_eq!(coalescer.take(), None);
+    }
+
+    #[test]
+    fn flushes_after_interval_or_max_bytes() {
+        let mut coalescer =
+            DeltaCoalescer::with_interval(Some(Duration::from_millis(100)), Some(8));
+        let start = Instant::now();
+
+        assert_eq!(coalescer.push("Hel", start), None);
+        assert_eq!(
+            coalescer.push("lo", start + Duration::from_millis(40)),
+            None
+        );
+        assert_eq!(
+            coalescer.deadline(),
+            Some(start + Duration::from_millis(100))
+        );
+        assert_eq!(
+            coalescer.take_if_due(start + Duration::from_millis(99)),
+            None
+        );
+        assert_eq!(
+            coalescer.take_if_due(start + Duration::from_millis(100)),
+            Some("Hello".to_string())
+        );
+        assert_eq!(coalescer.deadline(), None);
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(coalescer.push(", wo", later), None);
+        assert_eq!(coalescer.push("rld!", later), Some(", world!".to_string()));
+        assert_eq!(coalescer.push("?", later), None);
+        assert_eq!(coalescer.take(), Some("?".to_string()));
+    }
+}
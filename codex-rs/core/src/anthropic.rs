@@ -0,0 +1,869 @@
+//! Client for Anthropic's Messages API (`wire_api = "anthropic"`).
+//!
+//! The conversation is mapped onto Messages requests: the instructions become
+//! the `system` prompt, tool calls and their outputs become `tool_use` and
+//! `tool_result` blocks, and reasoning items carry extended thinking blocks
+//! back to the model with their signatures. Streamed events are mapped onto
+//! [`ResponseEvent`]s so the rest of the pipeline treats Claude models like
+//! any other.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use tracing::warn;
+
+use crate::ModelProviderInfo;
+use crate::auth::CodexAuth;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::openai_tools::create_tools_json_for_anthropic_api;
+use crate::protocol::TokenUsage;
+use crate::provider_failover::EndpointFailover;
+use crate::util::backoff;
+use crate::wire_log;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::USER_INSTRUCTIONS_OPEN_TAG;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is required by the Messages API; this is used when neither
+/// the config nor the model info gives a limit.
+const DEFAULT_MAX_TOKENS: u64 = 8_192;
+
+/// Smallest thinking budget the API accepts.
+const MIN_THINKING_BUDGET: u64 = 1_024;
+
+/// Prefixes of `encrypted_content` on reasoning items made from thinking
+/// blocks. Reasoning items from other providers have none and are not sent.
+const THINKING_SIGNATURE_PREFIX: &str = "anthropic-signature:";
+const REDACTED_THINKING_PREFIX: &str = "anthropic-redacted:";
+
+pub(crate) async fn stream_anthropic_messages(
+    prompt: &Prompt,
+    config: &Config,
+    effort: Option<ReasoningEffortConfig>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    failover: Option<&Arc<EndpointFailover>>,
+    provider_auth: &Option<CodexAuth>,
+) -> Result<ResponseStream> {
+    let payload = build_payload(prompt, config, effort)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(provider_auth, &config.model_family.slug),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let (provider, endpoint) = match failover {
+            Some(failover) => {
+                let (provider, endpoint) = failover.select(provider, client).await;
+                (provider, Some(endpoint))
+            }
+            None => (Cow::Borrowed(provider), None),
+        };
+        let req_builder = provider
+            .create_request_builder(client, provider_auth, &config.model_family.slug)
+            .await?;
+
+        let req_builder = req_builder
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload);
+        wire_log::log_request(&req_builder);
+        let res = req_builder.send().await;
+        if let Ok(resp) = &res {
+            wire_log::log_response(resp);
+        }
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                if let Some(endpoint) = endpoint {
+                    endpoint.succeeded();
+                }
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = wire_log::tee(resp.bytes_stream()).map_err(CodexErr::Reqwest);
+                tokio::spawn(process_anthropic_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                // 529 means the API is overloaded and counts as a server error.
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                let switched = status.is_server_error()
+                    && endpoint.is_some_and(|endpoint| {
+                        endpoint.failed(&format!("the endpoint returned {status}"))
+                    });
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+                if switched {
+                    continue;
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                let switched = endpoint.is_some_and(|endpoint| {
+                    endpoint.failed(&format!("could not reach the endpoint: {e}"))
+                });
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                if switched {
+                    continue;
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+fn build_payload(
+    prompt: &Prompt,
+    config: &Config,
+    effort: Option<ReasoningEffortConfig>,
+) -> Result<Value> {
+    let model_family = &config.model_family;
+    let mut messages = build_messages(&prompt.get_formatted_input());
+    add_cache_breakpoints(&mut messages);
+
+    // Thinking tokens count towards `max_tokens`.
+    let max_tokens = config.model_max_output_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let mut payload = json!({
+        "model": model_family.slug,
+        "max_tokens": max_tokens,
+        "system": [{
+            "type": "text",
+            "text": prompt.get_full_instructions_with_output_schema(model_family),
+            "cache_control": {"type": "ephemeral"},
+        }],
+        "messages": messages,
+        "stream": true,
+    });
+
+    let tools_json = create_tools_json_for_anthropic_api(&prompt.tools)?;
+    if !tools_json.is_empty() {
+        payload["tools"] = Value::Array(tools_json);
+    }
+
+    if let Some(temperature) = config.model_temperature {
+        payload["temperature"] = json!(temperature);
+    }
+
+    if model_family.supports_reasoning_summaries
+        && let Some(budget_tokens) = thinking_budget(effort, max_tokens)
+    {
+        payload["thinking"] = json!({
+            "type": "enabled",
+            "budget_tokens": budget_tokens,
+        });
+    }
+
+    Ok(payload)
+}
+
+/// Marks the stable parts of the conversation as cacheable: the user
+/// instructions (`AGENTS.md`), which are shared by every session in the
+/// project, and the conversation so far. The system prompt and tools are
+/// cached by the breakpoint on the system prompt.
+fn add_cache_breakpoints(messages: &mut [Value]) {
+    let ephemeral = json!({"type": "ephemeral"});
+    if let Some(block) = messages
+        .iter_mut()
+        .filter_map(|message| message["content"].as_array_mut())
+        .flatten()
+        .find(|block| {
+            block["text"]
+                .as_str()
+                .is_some_and(|text| text.starts_with(USER_INSTRUCTIONS_OPEN_TAG))
+        })
+    {
+        block["cache_control"] = ephemeral.clone();
+    }
+    if let Some(block) = messages
+        .last_mut()
+        .and_then(|message| message["content"].as_array_mut())
+        .and_then(|content| content.last_mut())
+        && !matches!(
+            block["type"].as_str(),
+            Some("thinking" | "redacted_thinking")
+        )
+    {
+        block["cache_control"] = ephemeral;
+    }
+}
+
+/// Extended thinking budget for `effort`, which must leave room for the
+/// answer within `max_tokens`. `None` disables thinking.
+fn thinking_budget(effort: Option<ReasoningEffortConfig>, max_tokens: u64) -> Option<u64> {
+    let budget = match effort? {
+        ReasoningEffortConfig::Minimal => return None,
+        ReasoningEffortConfig::Low => 4_096,
+        ReasoningEffortConfig::Medium => 16_384,
+        ReasoningEffortConfig::High => 32_000,
+    };
+    let budget = budget.min(max_tokens / 2);
+    (budget >= MIN_THINKING_BUDGET).then_some(budget)
+}
+
+/// Maps the conversation onto Messages API messages. Consecutive items of
+/// the same role are merged into one message, since the API expects user
+/// and assistant messages to alternate.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<(&'static str, Vec<Value>)> = Vec::new();
+    let mut push = |role: &'static str, block: Value| match messages.last_mut() {
+        Some((last_role, blocks)) if *last_role == role => blocks.push(block),
+        _ => messages.push((role, vec![block])),
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for content_item in content {
+                    match content_item {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push(role, json!({"type": "text", "text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push(role, image_block(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::Reasoning {
+                summary,
+                encrypted_content,
+                ..
+            } => {
+                let Some(encrypted_content) = encrypted_content else {
+                    continue;
+                };
+                if let Some(signature) = encrypted_content.strip_prefix(THINKING_SIGNATURE_PREFIX) {
+                    let thinking: String = summary
+                        .iter()
+                        .map(|ReasoningItemReasoningSummary::SummaryText { text }| text.as_str())
+                        .collect();
+                    push(
+                        "assistant",
+                        json!({
+                            "type": "thinking",
+                            "thinking": thinking,
+                            "signature": signature,
+                        }),
+                    );
+                } else if let Some(data) = encrypted_content.strip_prefix(REDACTED_THINKING_PREFIX)
+                {
+                    push(
+                        "assistant",
+                        json!({"type": "redacted_thinking", "data": data}),
+                    );
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": input}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let Some(call_id) = call_id.as_ref().or(id.as_ref()) else {
+                    continue;
+                };
+                push(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": "local_shell",
+                        "input": action,
+                    }),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                push(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": {"input": input},
+                    }),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output.content,
+                });
+                if output.success == Some(false) {
+                    block["is_error"] = Value::Bool(true);
+                }
+                push("user", block);
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push(
+                    "user",
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": call_id,
+                        "content": output,
+                    }),
+                );
+            }
+            ResponseItem::WebSearchCall { .. } | ResponseItem::Other => {}
+        }
+    }
+
+    messages
+        .into_iter()
+        .map(|(role, content)| json!({"role": role, "content": content}))
+        .collect()
+}
+
+fn image_block(image_url: &str) -> Value {
+    if let Some(data_url) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = data_url.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        });
+    }
+    json!({
+        "type": "image",
+        "source": {"type": "url", "url": image_url},
+    })
+}
+
+/// A content block being streamed, by its index in the message.
+enum StreamedBlock {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        /// Streamed as partial JSON; empty when the input is `{}`.
+        input_json: String,
+    },
+}
+
+impl StreamedBlock {
+    fn from_start(block: &Value) -> Option<Self> {
+        let str_field = |key: &str| block[key].as_str().unwrap_or_default().to_string();
+        match block["type"].as_str()? {
+            "text" => Some(Self::Text(str_field("text"))),
+            "thinking" => Some(Self::Thinking {
+                thinking: str_field("thinking"),
+                signature: str_field("signature"),
+            }),
+            "redacted_thinking" => Some(Self::RedactedThinking(str_field("data"))),
+            "tool_use" => Some(Self::ToolUse {
+                id: str_field("id"),
+                name: str_field("name"),
+                input_json: String::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn into_item(self) -> Option<ResponseItem> {
+        match self {
+            Self::Text(text) if text.is_empty() => None,
+            Self::Text(text) => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            Self::Thinking {
+                thinking,
+                signature,
+            } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText { text: thinking }],
+                content: None,
+                encrypted_content: Some(format!("{THINKING_SIGNATURE_PREFIX}{signature}")),
+            }),
+            Self::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(format!("{REDACTED_THINKING_PREFIX}{data}")),
+            }),
+            Self::ToolUse {
+                id,
+                name,
+                input_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if input_json.trim().is_empty() {
+                    "{}".to_string()
+                } else {
+                    input_json
+                },
+                call_id: id,
+            }),
+        }
+    }
+}
+
+/// SSE processor for the Messages API streaming format.
+async fn process_anthropic_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut blocks: HashMap<u64, StreamedBlock> = HashMap::new();
+    let mut response_id = String::new();
+    let mut usage = MessageUsage::default();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let event: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("anthropic received SSE event: {event:?}");
+
+        match event["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let message = &event["message"];
+                response_id = message["id"].as_str().unwrap_or_default().to_string();
+                usage.record(&message["usage"]);
+                let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+            }
+            "content_block_start" => {
+                if let Some(index) = event["index"].as_u64()
+                    && let Some(block) = StreamedBlock::from_start(&event["content_block"])
+                {
+                    blocks.insert(index, block);
+                }
+            }
+            "content_block_delta" => {
+                let Some(block) = event["index"].as_u64().and_then(|i| blocks.get_mut(&i)) else {
+                    continue;
+                };
+                let delta = &event["delta"];
+                let delta_str = |key: &str| delta[key].as_str().unwrap_or_default();
+                let forward = match (block, delta["type"].as_str().unwrap_or_default()) {
+                    (StreamedBlock::Text(text), "text_delta") => {
+                        text.push_str(delta_str("text"));
+                        Some(ResponseEvent::OutputTextDelta(
+                            delta_str("text").to_string(),
+                        ))
+                    }
+                    (StreamedBlock::Thinking { thinking, .. }, "thinking_delta") => {
+                        thinking.push_str(delta_str("thinking"));
+                        Some(ResponseEvent::ReasoningSummaryDelta(
+                            delta_str("thinking").to_string(),
+                        ))
+                    }
+                    (StreamedBlock::Thinking { signature, .. }, "signature_delta") => {
+                        signature.push_str(delta_str("signature"));
+                        None
+                    }
+                    (StreamedBlock::ToolUse { input_json, .. }, "input_json_delta") => {
+                        input_json.push_str(delta_str("partial_json"));
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(forward) = forward
+                    && tx_event.send(Ok(forward)).await.is_err()
+                {
+                    return;
+                }
+            }
+            "content_block_stop" => {
+                if let Some(item) = event["index"]
+                    .as_u64()
+                    .and_then(|i| blocks.remove(&i))
+                    .and_then(StreamedBlock::into_item)
+                {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "message_delta" => {
+                usage.record(&event["usage"]);
+                if event["delta"]["stop_reason"].as_str() == Some("max_tokens") {
+                    warn!(
+                        "Anthropic response stopped at max_tokens; raise model_max_output_tokens"
+                    );
+                }
+            }
+            "message_stop" => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(usage.into()),
+                    }))
+                    .await;
+                return;
+            }
+            "error" => {
+                let message = event["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string();
+                let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Token counts reported across `message_start` and `message_delta`. Each
+/// report holds the running totals of the counts it includes.
+#[derive(Debug, Default)]
+struct MessageUsage {
+    input_tokens: u64,
+    cache_creation_input_tokens: u64,
+    cache_read_input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl MessageUsage {
+    fn record(&mut self, usage: &Value) {
+        let fields = [
+            ("input_tokens", &mut self.input_tokens),
+            (
+                "cache_creation_input_tokens",
+                &mut self.cache_creation_input_tokens,
+            ),
+            ("cache_read_input_tokens", &mut self.cache_read_input_tokens),
+            ("output_tokens", &mut self.output_tokens),
+        ];
+        for (key, field) in fields {
+            if let Some(count) = usage[key].as_u64() {
+                *field = count;
+            }
+        }
+    }
+}
+
+impl From<MessageUsage> for TokenUsage {
+    /// The API counts cached input separately from `input_tokens`, whereas
+    /// `TokenUsage` includes it.
+    fn from(usage: MessageUsage) -> Self {
+        let input_tokens =
+            usage.input_tokens + usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: usage.cache_read_input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + usage.output_tokens,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn maps_tool_calls_and_merges_roles() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "<environment_context/>".to_string(),
+                }],
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![
+                    ContentItem::InputText {
+                        text: "list files".to_string(),
+                    },
+                    ContentItem::InputImage {
+                        image_url: "data:image/png;base64,AAAA".to_string(),
+                    },
+                ],
+            },
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                    text: "Use ls.".to_string(),
+                }],
+                content: None,
+                encrypted_content: Some(format!("{THINKING_SIGNATURE_PREFIX}sig")),
+            },
+            // From another provider, so it cannot be sent back.
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some("gAAAA".to_string()),
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "toolu_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "permission denied".to_string(),
+                    success: Some(false),
+                },
+            },
+        ];
+
+        assert_eq!(
+            build_messages(&input),
+            vec![
+                json!({"role": "user", "content": [
+                    {"type": "text", "text": "<environment_context/>"},
+                    {"type": "text", "text": "list files"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+                ]}),
+                json!({"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "Use ls.", "signature": "sig"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {"command": ["ls"]}},
+                ]}),
+                json!({"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "permission denied", "is_error": true},
+                ]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn caches_user_instructions_and_the_latest_block() {
+        let instructions =
+            format!("{USER_INSTRUCTIONS_OPEN_TAG}\n\nrun the tests\n\n</user_instructions>");
+        let mut messages = vec![
+            json!({"role": "user", "content": [
+                {"type": "text", "text": instructions},
+                {"type": "text", "text": "<environment_context/>"},
+                {"type": "text", "text": "fix the build"},
+            ]}),
+            json!({"role": "assistant", "content": [
+                {"type": "thinking", "thinking": "...", "signature": "sig"},
+                {"type": "text", "text": "done"},
+            ]}),
+        ];
+
+        add_cache_breakpoints(&mut messages);
+
+        let cached: Vec<&str> = messages
+            .iter()
+            .filter_map(|message| message["content"].as_array())
+            .flatten()
+            .filter(|block| block.get("cache_control").is_some())
+            .filter_map(|block| block["text"].as_str())
+            .collect();
+        assert_eq!(cached, vec![instructions.as_str(), "done"]);
+    }
+
+    #[test]
+    fn thinking_budget_fits_within_max_tokens() {
+        assert_eq!(thinking_budget(None, 64_000), None);
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::Minimal), 64_000),
+            None
+        );
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::High), 64_000),
+            Some(32_000)
+        );
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::High), 8_192),
+            Some(4_096)
+        );
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::Low), 1_500),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_streamed_events() {
+        let events = [
+            json!({"type": "message_start", "message": {"id": "msg_1", "usage": {"input_tokens": 10, "cache_read_input_tokens": 90, "output_tokens": 1}}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "Check."}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "sig"}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "ping"}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "Running ls."}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "content_block_start", "index": 2, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "{\"command\":"}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "[\"ls\"]}"}}),
+            json!({"type": "content_block_stop", "index": 2}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 40}}),
+            json!({"type": "message_stop"}),
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| {
+                format!(
+                    "event: {}\ndata: {event}\n\n",
+                    event["type"].as_str().unwrap()
+                )
+            })
+            .collect();
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        let (tx, mut rx) = mpsc::channel(16);
+        process_anthropic_sse(stream, tx, Duration::from_secs(5)).await;
+
+        let mut received = Vec::new();
+        while let Some(event) = rx.recv().await {
+            received.push(format!("{:?}", event.expect("event")));
+        }
+        let expected = [
+            ResponseEvent::Created,
+            ResponseEvent::ReasoningSummaryDelta("Check.".to_string()),
+            ResponseEvent::OutputItemDone(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                    text: "Check.".to_string(),
+                }],
+                content: None,
+                encrypted_content: Some(format!("{THINKING_SIGNATURE_PREFIX}sig")),
+            }),
+            ResponseEvent::OutputTextDelta("Running ls.".to_string()),
+            ResponseEvent::OutputItemDone(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Running ls.".to_string(),
+                }],
+            }),
+            ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "toolu_1".to_string(),
+            }),
+            ResponseEvent::Completed {
+                response_id: "msg_1".to_string(),
+                token_usage: Some(TokenUsage {
+                    input_tokens: 100,
+                    cached_input_tokens: 90,
+                    output_tokens: 40,
+                    reasoning_output_tokens: 0,
+                    total_tokens: 140,
+                }),
+            },
+        ]
+        .map(|event| format!("{event:?}"));
+        assert_eq!(received, expected);
+    }
+}
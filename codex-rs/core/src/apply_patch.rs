@@ -1,13 +1,21 @@
 use crate::codex::Session;
 use crate::codex::TurnContext;
 use crate::function_tool::FunctionCallError;
+use crate::patch_risk::assess_patch_risk;
+use crate::protocol::AskForApproval;
 use crate::protocol::FileChange;
 use crate::protocol::ReviewDecision;
+use crate::protocol::SandboxPolicy;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_patch_safety;
+use crate::state::PatchEdit;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_apply_patch::MaybeApplyPatchVerified;
+use codex_apply_patch::maybe_parse_apply_patch_verified;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 
 pub const CODEX_APPLY_PATCH_ARG1: &str = "--codex-run-as-apply-patch";
@@ -30,6 +38,9 @@ pub(crate) enum InternalApplyPatchInvocation {
 pub(crate) struct ApplyPatchExec {
     pub(crate) action: ApplyPatchAction,
     pub(crate) user_explicitly_approved_this_action: bool,
+    /// Reported to the model with the result when the user edited the patch
+    /// before approving it.
+    pub(crate) note: Option<String>,
 }
 
 pub(crate) async fn apply_patch(
@@ -39,16 +50,38 @@ pub(crate) async fn apply_patch(
     call_id: &str,
     action: ApplyPatchAction,
 ) -> InternalApplyPatchInvocation {
-    match assess_patch_safety(
+    let risk =
+        assess_patch_risk(&convert_apply_patch_to_protocol(&action), &action.cwd).into_option();
+    let mut safety = assess_patch_safety(
         &action,
         turn_context.approval_policy,
         &turn_context.sandbox_policy,
         &turn_context.cwd,
-    ) {
+    );
+    // Patches at or above the configured risk level are never auto-approved.
+    if let SafetyCheck::AutoApprove { .. } = safety
+        && let Some(threshold) = sess.escalate_patch_risk()
+        && let Some(risk) = &risk
+        && risk.level >= threshold
+    {
+        safety = if turn_context.approval_policy == AskForApproval::Never {
+            SafetyCheck::Reject {
+                reason: format!(
+                    "risky patch requires approval ({}); rejected by user approval settings",
+                    risk.reasons.join("; ")
+                ),
+            }
+        } else {
+            SafetyCheck::AskUser
+        };
+    }
+
+    match safety {
         SafetyCheck::AutoApprove { .. } => {
             InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
                 action,
                 user_explicitly_approved_this_action: false,
+                note: None,
             })
         }
         SafetyCheck::AskUser => {
@@ -60,14 +93,43 @@ pub(crate) async fn apply_patch(
             // that similar patches can be auto-approved in the future during
             // this session.
             let rx_approve = sess
-                .request_patch_approval(sub_id.to_owned(), call_id.to_owned(), &action, None, None)
+                .request_patch_approval(
+                    sub_id.to_owned(),
+                    call_id.to_owned(),
+                    &action,
+                    None,
+                    None,
+                    risk,
+                )
+                .await;
+            let mut files: Vec<PathBuf> = action.changes().keys().cloned().collect();
+            files.sort();
+            let decision = sess
+                .review_decision_with_hooks(
+                    sub_id,
+                    call_id,
+                    None,
+                    Some(files.as_slice()),
+                    None,
+                    rx_approve.await.unwrap_or_default(),
+                )
                 .await;
-            match rx_approve.await.unwrap_or_default() {
+            match decision {
                 ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
-                    InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
-                        action,
-                        user_explicitly_approved_this_action: true,
-                    })
+                    match sess.take_patch_edit(sub_id).await {
+                        Some(edit) => apply_edited_patch(
+                            action,
+                            edit,
+                            turn_context.approval_policy,
+                            &turn_context.sandbox_policy,
+                            &turn_context.cwd,
+                        ),
+                        None => InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+                            action,
+                            user_explicitly_approved_this_action: true,
+                            note: None,
+                        }),
+                    }
                 }
                 ReviewDecision::Denied | ReviewDecision::Abort => {
                     InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
@@ -82,6 +144,88 @@ pub(crate) async fn apply_patch(
     }
 }
 
+/// Applies the patch the user approved in place of the proposed `action`,
+/// resolving its paths against the same directory.
+///
+/// The edited patch goes through the same safety checks as a proposed one.
+/// Outside the writable roots, the approval only covers files of the
+/// proposed patch.
+fn apply_edited_patch(
+    action: ApplyPatchAction,
+    edit: PatchEdit,
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> InternalApplyPatchInvocation {
+    let argv = vec!["apply_patch".to_string(), edit.patch];
+    let edited = match maybe_parse_apply_patch_verified(&argv, &action.cwd) {
+        MaybeApplyPatchVerified::Body(edited) => edited,
+        MaybeApplyPatchVerified::CorrectnessError(err) => {
+            return InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+                format!("the user edited the patch, but the edit could not be applied: {err}"),
+            )));
+        }
+        MaybeApplyPatchVerified::ShellParseError(_) | MaybeApplyPatchVerified::NotApplyPatch => {
+            return InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+                "the user edited the patch, but the edit is not a valid patch".to_string(),
+            )));
+        }
+    };
+    let user_explicitly_approved_this_action = match assess_patch_safety(
+        &edited,
+        approval_policy,
+        sandbox_policy,
+        cwd,
+    ) {
+        SafetyCheck::AutoApprove { .. } => false,
+        SafetyCheck::AskUser => {
+            let approved = touched_paths(&action);
+            if let Some(path) = touched_paths(&edited)
+                .into_iter()
+                .find(|path| !approved.contains(path))
+            {
+                return InternalApplyPatchInvocation::Output(Err(
+                    FunctionCallError::RespondToModel(format!(
+                        "patch rejected: the user's edit changes {}, which was not part of the approved patch",
+                        path.display()
+                    )),
+                ));
+            }
+            true
+        }
+        SafetyCheck::Reject { reason } => {
+            return InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+                format!("patch rejected: {reason}"),
+            )));
+        }
+    };
+    let note = match edit.note {
+        Some(note) => format!("The user applied an edited version of the patch. {note}"),
+        None => "The user applied an edited version of the patch.".to_string(),
+    };
+    InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+        action: edited,
+        user_explicitly_approved_this_action,
+        note: Some(note),
+    })
+}
+
+/// Every path `action` writes, including the destinations of moves.
+fn touched_paths(action: &ApplyPatchAction) -> HashSet<PathBuf> {
+    let mut paths = HashSet::new();
+    for (path, change) in action.changes() {
+        paths.insert(path.clone());
+        if let ApplyPatchFileChange::Update {
+            move_path: Some(move_path),
+            ..
+        } = change
+        {
+            paths.insert(move_path.clone());
+        }
+    }
+    paths
+}
+
 pub(crate) fn convert_apply_patch_to_protocol(
     action: &ApplyPatchAction,
 ) -> HashMap<PathBuf, FileChange> {
@@ -108,3 +252,118 @@ pub(crate) fn convert_apply_patch_to_protocol(
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn parse(patch: &str, cwd: &Path) -> ApplyPatchAction {
+        let MaybeApplyPatchVerified::Body(action) =
+            maybe_parse_apply_patch_verified(&["apply_patch".to_string(), patch.to_string()], cwd)
+        else {
+            panic!("expected a valid patch");
+        };
+        action
+    }
+
+    fn rejection(invocation: InternalApplyPatchInvocation) -> String {
+        match invocation {
+            InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+                reason,
+            ))) => reason,
+            _ => panic!("expected the edited patch to be rejected"),
+        }
+    }
+
+    #[test]
+    fn edited_patches_replace_the_proposed_one() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        let action = parse(
+            "*** Begin Patch\n*** Add File: a.txt\n+a\n*** Add File: b.txt\n+b\n*** End Patch",
+            cwd,
+        );
+        let edit = PatchEdit {
+            patch: "*** Begin Patch\n*** Add File: b.txt\n+b\n*** End Patch".to_string(),
+            note: Some("These changes were rejected and not applied:\n- a.txt".to_string()),
+        };
+
+        let InternalApplyPatchInvocation::DelegateToExec(exec) = apply_edited_patch(
+            action,
+            edit,
+            AskForApproval::OnRequest,
+            &SandboxPolicy::new_read_only_policy(),
+            cwd,
+        ) else {
+            panic!("expected the edited patch to be applied");
+        };
+        assert_eq!(
+            exec.action.changes().keys().collect::<Vec<_>>(),
+            vec![&cwd.join("b.txt")]
+        );
+        assert!(exec.user_explicitly_approved_this_action);
+        assert_eq!(
+            exec.note.as_deref(),
+            Some(
+                "The user applied an edited version of the patch. These changes were rejected and not applied:\n- a.txt"
+            )
+        );
+    }
+
+    #[test]
+    fn edits_cannot_add_files_outside_the_approved_patch() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        let action = parse(
+            "*** Begin Patch\n*** Add File: a.txt\n+a\n*** End Patch",
+            cwd,
+        );
+        let edit = PatchEdit {
+            patch:
+                "*** Begin Patch\n*** Add File: a.txt\n+a\n*** Add File: b.txt\n+b\n*** End Patch"
+                    .to_string(),
+            note: None,
+        };
+
+        let reason = rejection(apply_edited_patch(
+            action,
+            edit,
+            AskForApproval::OnRequest,
+            &SandboxPolicy::new_read_only_policy(),
+            cwd,
+        ));
+        assert!(reason.contains("b.txt"), "{reason}");
+    }
+
+    #[test]
+    fn edits_cannot_write_denied_paths() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        let action = parse(
+            "*** Begin Patch\n*** Add File: a.txt\n+a\n*** End Patch",
+            cwd,
+        );
+        let edit = PatchEdit {
+            patch: "*** Begin Patch\n*** Add File: .env\n+TOKEN=x\n*** End Patch".to_string(),
+            note: None,
+        };
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            network_allowlist: vec![],
+            deny: vec![".env".to_string()],
+        };
+
+        let reason = rejection(apply_edited_patch(
+            action,
+            edit,
+            AskForApproval::OnRequest,
+            &policy,
+            cwd,
+        ));
+        assert!(reason.contains("sandbox_workspace_write.deny"), "{reason}");
+    }
+}
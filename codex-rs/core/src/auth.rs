@@ -2,6 +2,8 @@ use chrono::DateTime;
 use chrono::Utc;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::fs::OpenOptions;
@@ -13,13 +15,45 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use codex_protocol::mcp_protocol::AuthMode;
-
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::config_types::CredentialStoreMode;
+use crate::credential_store::CredentialStore;
+use crate::credential_store::FileCredentialStore;
+use crate::credential_store::credential_store;
+use crate::external_auth::ExternalAuthProvider;
+use crate::mcp_oauth::McpOAuthCredentials;
+use crate::mcp_oauth::stored_mcp_oauth_credentials;
+use crate::model_provider_info::ModelProviderInfo;
 use crate::token_data::PlanType;
 use crate::token_data::TokenData;
 use crate::token_data::parse_id_token;
+use crate::token_data::parse_jwt_expiration;
+use crate::util::backoff;
+
+/// Access tokens are refreshed this long before they expire so that requests
+/// in flight never carry an expired token.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Tokens without a readable expiry are refreshed after this many days, as
+/// they always have been.
+const REFRESH_FALLBACK_DAYS: i64 = 28;
+
+/// Transient refresh failures are retried this many times with jittered
+/// exponential backoff before giving up.
+const MAX_REFRESH_ATTEMPTS: u64 = 5;
+
+/// Longest the background refresh task sleeps before checking the tokens
+/// again, so that it notices credentials that changed in the meantime.
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 pub struct CodexAuth {
@@ -27,7 +61,10 @@ pub struct CodexAuth {
 
     pub(crate) api_key: Option<String>,
     pub(crate) auth_dot_json: Arc<Mutex<Option<AuthDotJson>>>,
-    pub(crate) auth_file: PathBuf,
+    pub(crate) store: Arc<dyn CredentialStore>,
+    /// Set when tokens come from `external_auth_command` rather than the
+    /// credential store.
+    pub(crate) external: Option<Arc<ExternalAuthProvider>>,
     pub(crate) client: reqwest::Client,
 }
 
@@ -39,17 +76,19 @@ impl PartialEq for CodexAuth {
 
 impl CodexAuth {
     pub async fn refresh_token(&self) -> Result<String, std::io::Error> {
+        self.try_refresh().await.map_err(std::io::Error::from)
+    }
+
+    async fn try_refresh(&self) -> Result<String, RefreshTokenError> {
         let token_data = self
             .get_current_token_data()
             .ok_or(std::io::Error::other("Token data is not available."))?;
         let token = token_data.refresh_token;
 
-        let refresh_response = try_refresh_token(token, &self.client)
-            .await
-            .map_err(std::io::Error::other)?;
+        let refresh_response = try_refresh_token(token, &self.client).await?;
 
         let updated = update_tokens(
-            &self.auth_file,
+            self.store.as_ref(),
             refresh_response.id_token,
             refresh_response.access_token,
             refresh_response.refresh_token,
@@ -63,17 +102,20 @@ impl CodexAuth {
         let access = match updated.tokens {
             Some(t) => t.access_token,
             None => {
-                return Err(std::io::Error::other(
-                    "Token data is not available after refresh.",
-                ));
+                return Err(
+                    std::io::Error::other("Token data is not available after refresh.").into(),
+                );
             }
         };
         Ok(access)
     }
 
-    /// Loads the available auth information from the auth.json.
-    pub fn from_codex_home(codex_home: &Path) -> std::io::Result<Option<CodexAuth>> {
-        load_auth(codex_home)
+    /// Loads the available auth information from the credential store.
+    pub fn from_codex_home(
+        codex_home: &Path,
+        credential_store_mode: CredentialStoreMode,
+    ) -> std::io::Result<Option<CodexAuth>> {
+        load_auth(codex_home, credential_store_mode)
     }
 
     pub async fn get_token_data(&self) -> Result<TokenData, std::io::Error> {
@@ -92,11 +134,10 @@ impl CodexAuth {
                     .await
                     .map_err(|_| {
                         std::io::Error::other("timed out while refreshing OpenAI API key")
-                    })?
-                    .map_err(std::io::Error::other)?;
+                    })??;
 
                     let updated_auth_dot_json = update_tokens(
-                        &self.auth_file,
+                        self.store.as_ref(),
                         refresh_response.id_token,
                         refresh_response.access_token,
                         refresh_response.refresh_token,
@@ -123,7 +164,10 @@ impl CodexAuth {
 
     pub async fn get_token(&self) -> Result<String, std::io::Error> {
         match self.mode {
-            AuthMode::ApiKey => Ok(self.api_key.clone().unwrap_or_default()),
+            AuthMode::ApiKey => match &self.external {
+                Some(external) => external.token().await,
+                None => Ok(self.api_key.clone().unwrap_or_default()),
+            },
             AuthMode::ChatGPT => {
                 let id_token = self.get_token_data().await?.access_token;
                 Ok(id_token)
@@ -160,13 +204,18 @@ impl CodexAuth {
                 account_id: Some("account_id".to_string()),
             }),
             last_refresh: Some(Utc::now()),
+            active_account: None,
+            accounts: Vec::new(),
+            provider_api_keys: BTreeMap::new(),
+            mcp_oauth: BTreeMap::new(),
         };
 
         let auth_dot_json = Arc::new(Mutex::new(Some(auth_dot_json)));
         Self {
             api_key: None,
             mode: AuthMode::ChatGPT,
-            auth_file: PathBuf::new(),
+            store: Arc::new(FileCredentialStore::new(PathBuf::new())),
+            external: None,
             auth_dot_json,
             client: crate::default_client::create_client(),
         }
@@ -176,7 +225,8 @@ impl CodexAuth {
         Self {
             api_key: Some(api_key.to_owned()),
             mode: AuthMode::ApiKey,
-            auth_file: PathBuf::new(),
+            store: Arc::new(FileCredentialStore::new(PathBuf::new())),
+            external: None,
             auth_dot_json: Arc::new(Mutex::new(None)),
             client,
         }
@@ -185,6 +235,13 @@ impl CodexAuth {
     pub fn from_api_key(api_key: &str) -> Self {
         Self::from_api_key_with_client(api_key, crate::default_client::create_client())
     }
+
+    fn from_external_auth(external: Arc<ExternalAuthProvider>) -> Self {
+        Self {
+            external: Some(external),
+            ..Self::from_api_key("")
+        }
+    }
 }
 
 pub const OPENAI_API_KEY_ENV_VAR: &str = "OPENAI_API_KEY";
@@ -200,56 +257,261 @@ pub fn get_auth_file(codex_home: &Path) -> PathBuf {
     codex_home.join("auth.json")
 }
 
-/// Delete the auth.json file inside `codex_home` if it exists. Returns `Ok(true)`
-/// if a file was removed, `Ok(false)` if no auth file was present.
-pub fn logout(codex_home: &Path) -> std::io::Result<bool> {
-    let auth_file = get_auth_file(codex_home);
-    match std::fs::remove_file(&auth_file) {
-        Ok(_) => Ok(true),
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
-        Err(err) => Err(err),
-    }
+/// Delete the stored credentials for `codex_home`, if any. Returns `Ok(true)`
+/// if credentials were removed, `Ok(false)` if none were present.
+pub fn logout(
+    codex_home: &Path,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<bool> {
+    credential_store(codex_home, credential_store_mode).delete()
 }
 
-/// Writes an `auth.json` that contains only the API key.
-pub fn login_with_api_key(codex_home: &Path, api_key: &str) -> std::io::Result<()> {
+/// Stores credentials whose active part is only the API key. Stored accounts
+/// are kept.
+pub fn login_with_api_key(
+    codex_home: &Path,
+    api_key: &str,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<()> {
+    let store = credential_store(codex_home, credential_store_mode);
     let auth_dot_json = AuthDotJson {
         openai_api_key: Some(api_key.to_string()),
         tokens: None,
         last_refresh: None,
+        active_account: None,
+        accounts: stored_accounts(store.as_ref()),
+        provider_api_keys: stored_provider_api_keys(store.as_ref()),
+        mcp_oauth: stored_mcp_oauth_credentials(store.as_ref()),
+    };
+    store.save(&auth_dot_json)
+}
+
+/// Stored accounts in `store`, so that a fresh login does not discard them.
+/// Returns an empty list if nothing is stored or the store is unreadable.
+pub fn stored_accounts(store: &dyn CredentialStore) -> Vec<StoredAccount> {
+    store
+        .load()
+        .ok()
+        .flatten()
+        .map(|auth| auth.accounts)
+        .unwrap_or_default()
+}
+
+/// Provider API keys in `store`, so that a fresh login does not discard them.
+/// Returns an empty map if nothing is stored or the store is unreadable.
+pub fn stored_provider_api_keys(store: &dyn CredentialStore) -> BTreeMap<String, String> {
+    store
+        .load()
+        .ok()
+        .flatten()
+        .map(|auth| auth.provider_api_keys)
+        .unwrap_or_default()
+}
+
+/// List the stored accounts.
+pub fn list_accounts(
+    codex_home: &Path,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<Vec<AccountSummary>> {
+    let Some(auth_dot_json) = credential_store(codex_home, credential_store_mode).load()? else {
+        return Ok(Vec::new());
+    };
+    let active = auth_dot_json.active_account.as_deref();
+    Ok(auth_dot_json
+        .accounts
+        .iter()
+        .map(|account| AccountSummary {
+            name: account.name.clone(),
+            mode: account.mode(),
+            email: account
+                .tokens
+                .as_ref()
+                .and_then(|tokens| tokens.id_token.email.clone()),
+            active: active == Some(account.name.as_str()),
+        })
+        .collect())
+}
+
+/// Save the active credentials as the stored account `name`, replacing any
+/// existing account with that name, and mark it active.
+pub fn save_account(
+    codex_home: &Path,
+    name: &str,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<()> {
+    let store = credential_store(codex_home, credential_store_mode);
+    let Some(mut auth_dot_json) = store.load()? else {
+        return Err(std::io::Error::other("not logged in"));
+    };
+    if auth_dot_json.openai_api_key.is_none() && auth_dot_json.tokens.is_none() {
+        return Err(std::io::Error::other("not logged in"));
+    }
+
+    let account = StoredAccount {
+        name: name.to_string(),
+        openai_api_key: auth_dot_json.openai_api_key.clone(),
+        tokens: auth_dot_json.tokens.clone(),
+        last_refresh: auth_dot_json.last_refresh,
+    };
+    match auth_dot_json.accounts.iter_mut().find(|a| a.name == name) {
+        Some(existing) => *existing = account,
+        None => auth_dot_json.accounts.push(account),
+    }
+    auth_dot_json.active_account = Some(name.to_string());
+    store.save(&auth_dot_json)
+}
+
+/// Make the stored account `name` the active one. The credentials of the
+/// previously active account are saved back first so that tokens refreshed
+/// while it was active are not lost.
+pub fn switch_account(
+    codex_home: &Path,
+    name: &str,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<()> {
+    let store = credential_store(codex_home, credential_store_mode);
+    let mut auth_dot_json = store.load()?.unwrap_or_default();
+    let Some(target) = auth_dot_json
+        .accounts
+        .iter()
+        .find(|a| a.name == name)
+        .cloned()
+    else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no stored account named `{name}`"),
+        ));
+    };
+
+    if let Some(current) = auth_dot_json.active_account.clone()
+        && let Some(entry) = auth_dot_json
+            .accounts
+            .iter_mut()
+            .find(|a| a.name == current)
+    {
+        entry.openai_api_key = auth_dot_json.openai_api_key.clone();
+        entry.tokens = auth_dot_json.tokens.clone();
+        entry.last_refresh = auth_dot_json.last_refresh;
+    }
+
+    auth_dot_json.openai_api_key = target.openai_api_key;
+    auth_dot_json.tokens = target.tokens;
+    auth_dot_json.last_refresh = target.last_refresh;
+    auth_dot_json.active_account = Some(target.name);
+    store.save(&auth_dot_json)
+}
+
+/// Store `api_key` for the model provider `provider_id`, replacing any key
+/// already stored for it. Providers that use OpenAI auth are rejected since
+/// their credentials come from `codex login`.
+pub fn set_provider_api_key(
+    codex_home: &Path,
+    model_providers: &HashMap<String, ModelProviderInfo>,
+    provider_id: &str,
+    api_key: &str,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<()> {
+    let Some(provider) = model_providers.get(provider_id) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("no model provider named `{provider_id}`"),
+        ));
     };
-    write_auth_json(&get_auth_file(codex_home), &auth_dot_json)
+    if provider.requires_openai_auth {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("`{provider_id}` uses OpenAI auth; use `codex login` instead"),
+        ));
+    }
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "API key is empty",
+        ));
+    }
+
+    let store = credential_store(codex_home, credential_store_mode);
+    let mut auth_dot_json = store.load()?.unwrap_or_default();
+    auth_dot_json
+        .provider_api_keys
+        .insert(provider_id.to_string(), api_key.to_string());
+    store.save(&auth_dot_json)
 }
 
-fn load_auth(codex_home: &Path) -> std::io::Result<Option<CodexAuth>> {
-    let auth_file = get_auth_file(codex_home);
+/// Remove the stored API key for `provider_id`. Returns `Ok(true)` if a key
+/// was removed.
+pub fn remove_provider_api_key(
+    codex_home: &Path,
+    provider_id: &str,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<bool> {
+    let store = credential_store(codex_home, credential_store_mode);
+    let Some(mut auth_dot_json) = store.load()? else {
+        return Ok(false);
+    };
+    if auth_dot_json
+        .provider_api_keys
+        .remove(provider_id)
+        .is_none()
+    {
+        return Ok(false);
+    }
+    store.save(&auth_dot_json)?;
+    Ok(true)
+}
+
+/// The stored API key for `provider_id`, if any.
+pub fn read_provider_api_key(
+    codex_home: &Path,
+    provider_id: &str,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<Option<String>> {
+    let store = credential_store(codex_home, credential_store_mode);
+    Ok(store
+        .load()?
+        .and_then(|mut auth_dot_json| auth_dot_json.provider_api_keys.remove(provider_id)))
+}
+
+fn load_auth(
+    codex_home: &Path,
+    credential_store_mode: CredentialStoreMode,
+) -> std::io::Result<Option<CodexAuth>> {
+    let store = credential_store(codex_home, credential_store_mode);
     let client = crate::default_client::create_client();
-    let auth_dot_json = match try_read_auth_json(&auth_file) {
-        Ok(auth) => auth,
-        Err(e) => {
-            return Err(e);
-        }
+    let Some(auth_dot_json) = store.load()? else {
+        return Ok(None);
     };
 
     let AuthDotJson {
         openai_api_key: auth_json_api_key,
         tokens,
         last_refresh,
+        ..
     } = auth_dot_json;
 
     // Prefer AuthMode.ApiKey if it's set in the auth.json.
     if let Some(api_key) = &auth_json_api_key {
         return Ok(Some(CodexAuth::from_api_key_with_client(api_key, client)));
     }
+    // Only provider API keys are stored: not logged in to OpenAI.
+    if tokens.is_none() {
+        return Ok(None);
+    }
 
     Ok(Some(CodexAuth {
         api_key: None,
         mode: AuthMode::ChatGPT,
-        auth_file,
+        store,
+        external: None,
         auth_dot_json: Arc::new(Mutex::new(Some(AuthDotJson {
             openai_api_key: None,
             tokens,
             last_refresh,
+            active_account: None,
+            accounts: Vec::new(),
+            provider_api_keys: BTreeMap::new(),
+            mcp_oauth: BTreeMap::new(),
         }))),
         client,
     }))
@@ -284,12 +546,14 @@ pub fn write_auth_json(auth_file: &Path, auth_dot_json: &AuthDotJson) -> std::io
 }
 
 async fn update_tokens(
-    auth_file: &Path,
+    store: &dyn CredentialStore,
     id_token: String,
     access_token: Option<String>,
     refresh_token: Option<String>,
 ) -> std::io::Result<AuthDotJson> {
-    let mut auth_dot_json = try_read_auth_json(auth_file)?;
+    let mut auth_dot_json = store
+        .load()?
+        .ok_or_else(|| std::io::Error::other("credentials are no longer stored"))?;
 
     let tokens = auth_dot_json.tokens.get_or_insert_with(TokenData::default);
     tokens.id_token = parse_id_token(&id_token).map_err(std::io::Error::other)?;
@@ -300,14 +564,14 @@ async fn update_tokens(
         tokens.refresh_token = refresh_token;
     }
     auth_dot_json.last_refresh = Some(Utc::now());
-    write_auth_json(auth_file, &auth_dot_json)?;
+    store.save(&auth_dot_json)?;
     Ok(auth_dot_json)
 }
 
 async fn try_refresh_token(
     refresh_token: String,
     client: &reqwest::Client,
-) -> std::io::Result<RefreshResponse> {
+) -> Result<RefreshResponse, RefreshTokenError> {
     let refresh_request = RefreshRequest {
         client_id: CLIENT_ID,
         grant_type: "refresh_token",
@@ -324,17 +588,42 @@ async fn try_refresh_token(
         .await
         .map_err(std::io::Error::other)?;
 
-    if response.status().is_success() {
+    let status = response.status();
+    if status.is_success() {
         let refresh_response = response
             .json::<RefreshResponse>()
             .await
             .map_err(std::io::Error::other)?;
         Ok(refresh_response)
-    } else {
-        Err(std::io::Error::other(format!(
-            "Failed to refresh token: {}",
-            response.status()
+    } else if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        // The token endpoint rejects expired, revoked, or already-used
+        // refresh tokens with a 4xx; retrying will not help.
+        Err(RefreshTokenError::Permanent(format!(
+            "Your ChatGPT login has expired or was revoked ({status})"
         )))
+    } else {
+        Err(std::io::Error::other(format!("Failed to refresh token: {status}")).into())
+    }
+}
+
+/// Why refreshing the ChatGPT tokens failed.
+#[derive(Debug, Error)]
+pub enum RefreshTokenError {
+    /// The refresh token was rejected. Only logging in again can fix this.
+    #[error("{0}")]
+    Permanent(String),
+
+    /// A network or server-side failure that may succeed when retried.
+    #[error(transparent)]
+    Transient(#[from] std::io::Error),
+}
+
+impl From<RefreshTokenError> for std::io::Error {
+    fn from(err: RefreshTokenError) -> Self {
+        match err {
+            RefreshTokenError::Permanent(message) => std::io::Error::other(message),
+            RefreshTokenError::Transient(err) => err,
+        }
     }
 }
 
@@ -353,8 +642,9 @@ struct RefreshResponse {
     refresh_token: Option<String>,
 }
 
-/// Expected structure for $CODEX_HOME/auth.json.
-#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+/// Expected structure for $CODEX_HOME/auth.json, or the equivalent entry in the
+/// OS credential store.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
 pub struct AuthDotJson {
     #[serde(rename = "OPENAI_API_KEY")]
     pub openai_api_key: Option<String>,
@@ -364,6 +654,65 @@ pub struct AuthDotJson {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_refresh: Option<DateTime<Utc>>,
+
+    /// Name of the stored account whose credentials are currently at the top
+    /// level of this file, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_account: Option<String>,
+
+    /// Credentials saved with `codex auth save` that can be switched to with
+    /// `codex auth use`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<StoredAccount>,
+
+    /// API keys for entries in `model_providers`, keyed by provider id, saved
+    /// with `codex auth set-key`. They are independent of the active account.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provider_api_keys: BTreeMap<String, String>,
+
+    /// OAuth tokens for MCP servers, keyed by server name, saved with
+    /// `codex mcp login`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mcp_oauth: BTreeMap<String, McpOAuthCredentials>,
+}
+
+/// A named set of credentials kept in `auth.json` alongside the active ones.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StoredAccount {
+    pub name: String,
+
+    #[serde(
+        rename = "OPENAI_API_KEY",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub openai_api_key: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<TokenData>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_refresh: Option<DateTime<Utc>>,
+}
+
+impl StoredAccount {
+    fn mode(&self) -> AuthMode {
+        // Mirrors `load_auth`: an API key takes precedence over tokens.
+        if self.openai_api_key.is_some() {
+            AuthMode::ApiKey
+        } else {
+            AuthMode::ChatGPT
+        }
+    }
+}
+
+/// Summary of a stored account, suitable for display.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountSummary {
+    pub name: String,
+    pub mode: AuthMode,
+    pub email: Option<String>,
+    pub active: bool,
 }
 
 // Shared constant for token refresh (client id used for oauth token refresh flow)
@@ -380,6 +729,7 @@ struct CachedAuth {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model_provider_info::built_in_model_providers;
     use crate::token_data::IdTokenInfo;
     use crate::token_data::KnownPlan;
     use crate::token_data::PlanType;
@@ -390,6 +740,7 @@ mod tests {
     use tempfile::tempdir;
 
     const LAST_REFRESH: &str = "2025-08-06T20:41:36.232376Z";
+    const FILE: CredentialStoreMode = CredentialStoreMode::File;
 
     #[tokio::test]
     async fn roundtrip_auth_dot_json() {
@@ -430,7 +781,8 @@ mod tests {
         )
         .unwrap();
 
-        super::login_with_api_key(dir.path(), "sk-new").expect("login_with_api_key should succeed");
+        super::login_with_api_key(dir.path(), "sk-new", CredentialStoreMode::File)
+            .expect("login_with_api_key should succeed");
 
         let auth = super::try_read_auth_json(&auth_path).expect("auth.json should parse");
         assert_eq!(auth.openai_api_key.as_deref(), Some("sk-new"));
@@ -453,9 +805,10 @@ mod tests {
             api_key,
             mode,
             auth_dot_json,
-            auth_file: _,
             ..
-        } = super::load_auth(codex_home.path()).unwrap().unwrap();
+        } = super::load_auth(codex_home.path(), CredentialStoreMode::File)
+            .unwrap()
+            .unwrap();
         assert_eq!(None, api_key);
         assert_eq!(AuthMode::ChatGPT, mode);
 
@@ -479,6 +832,10 @@ mod tests {
                         .unwrap()
                         .with_timezone(&Utc)
                 ),
+                active_account: None,
+                accounts: Vec::new(),
+                provider_api_keys: BTreeMap::new(),
+                mcp_oauth: BTreeMap::new(),
             },
             auth_dot_json
         )
@@ -494,7 +851,9 @@ mod tests {
         )
         .unwrap();
 
-        let auth = super::load_auth(dir.path()).unwrap().unwrap();
+        let auth = super::load_auth(dir.path(), CredentialStoreMode::File)
+            .unwrap()
+            .unwrap();
         assert_eq!(auth.mode, AuthMode::ApiKey);
         assert_eq!(auth.api_key, Some("sk-test-key".to_string()));
 
@@ -508,15 +867,136 @@ mod tests {
             openai_api_key: Some("sk-test-key".to_string()),
             tokens: None,
             last_refresh: None,
+            active_account: None,
+            accounts: Vec::new(),
+            provider_api_keys: BTreeMap::new(),
+            mcp_oauth: BTreeMap::new(),
         };
         write_auth_json(&get_auth_file(dir.path()), &auth_dot_json)?;
         assert!(dir.path().join("auth.json").exists());
-        let removed = logout(dir.path())?;
+        let removed = logout(dir.path(), CredentialStoreMode::File)?;
         assert!(removed);
         assert!(!dir.path().join("auth.json").exists());
         Ok(())
     }
 
+    #[test]
+    fn save_and_switch_accounts() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        login_with_api_key(dir.path(), "sk-personal", FILE)?;
+        save_account(dir.path(), "personal", FILE)?;
+        login_with_api_key(dir.path(), "sk-work", FILE)?;
+        save_account(dir.path(), "work", FILE)?;
+
+        switch_account(dir.path(), "personal", FILE)?;
+        let auth = try_read_auth_json(&get_auth_file(dir.path()))?;
+        assert_eq!(auth.openai_api_key.as_deref(), Some("sk-personal"));
+
+        assert_eq!(
+            list_accounts(dir.path(), FILE)?,
+            vec![
+                AccountSummary {
+                    name: "personal".to_string(),
+                    mode: AuthMode::ApiKey,
+                    email: None,
+                    active: true,
+                },
+                AccountSummary {
+                    name: "work".to_string(),
+                    mode: AuthMode::ApiKey,
+                    email: None,
+                    active: false,
+                },
+            ]
+        );
+
+        let err = switch_account(dir.path(), "missing", FILE).expect_err("unknown account");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn provider_api_keys_survive_login() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        let providers = built_in_model_providers();
+        set_provider_api_key(dir.path(), &providers, "oss", " sk-local\n", FILE)?;
+
+        // Only a provider key is stored: not logged in to OpenAI.
+        assert!(load_auth(dir.path(), FILE)?.is_none());
+
+        login_with_api_key(dir.path(), "sk-openai", FILE)?;
+        assert_eq!(
+            read_provider_api_key(dir.path(), "oss", FILE)?.as_deref(),
+            Some("sk-local")
+        );
+
+        let err = set_provider_api_key(dir.path(), &providers, "openai", "sk-x", FILE)
+            .expect_err("openai uses codex login");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let err = set_provider_api_key(dir.path(), &providers, "missing", "sk-x", FILE)
+            .expect_err("unknown provider");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        assert!(remove_provider_api_key(dir.path(), "oss", FILE)?);
+        assert!(!remove_provider_api_key(dir.path(), "oss", FILE)?);
+        assert_eq!(read_provider_api_key(dir.path(), "oss", FILE)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn switch_account_saves_back_active_credentials() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        login_with_api_key(dir.path(), "sk-old", FILE)?;
+        save_account(dir.path(), "personal", FILE)?;
+        login_with_api_key(dir.path(), "sk-work", FILE)?;
+        save_account(dir.path(), "work", FILE)?;
+        switch_account(dir.path(), "personal", FILE)?;
+
+        // Simulate the active credentials changing while `personal` is active.
+        let auth_file = get_auth_file(dir.path());
+        let mut auth = try_read_auth_json(&auth_file)?;
+        auth.openai_api_key = Some("sk-rotated".to_string());
+        write_auth_json(&auth_file, &auth)?;
+
+        switch_account(dir.path(), "work", FILE)?;
+        switch_account(dir.path(), "personal", FILE)?;
+        let auth = try_read_auth_json(&auth_file)?;
+        assert_eq!(auth.openai_api_key.as_deref(), Some("sk-rotated"));
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_delay_tracks_access_token_expiry() {
+        let now = Utc::now();
+        let b64 = |b: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(b);
+        let expires_in_an_hour = json!({ "exp": (now + chrono::Duration::hours(1)).timestamp() });
+        let access_token = format!(
+            "{}.{}.{}",
+            b64(br#"{"alg":"none"}"#),
+            b64(expires_in_an_hour.to_string().as_bytes()),
+            b64(b"sig")
+        );
+        let tokens = TokenData {
+            access_token,
+            ..TokenData::default()
+        };
+        let delay = refresh_delay(&tokens, None, now).expect("delay");
+        assert!(delay <= Duration::from_secs(3600) - REFRESH_MARGIN);
+        assert!(delay > Duration::from_secs(3500) - REFRESH_MARGIN);
+
+        // Already inside the refresh margin: refresh now.
+        let soon = now + chrono::Duration::hours(1) - chrono::Duration::minutes(1);
+        assert_eq!(refresh_delay(&tokens, None, soon), Some(Duration::ZERO));
+
+        // Opaque access tokens fall back to the age of the last refresh.
+        let opaque = TokenData::default();
+        assert_eq!(refresh_delay(&opaque, None, now), None);
+        assert_eq!(
+            refresh_delay(&opaque, Some(now - chrono::Duration::days(30)), now),
+            Some(Duration::ZERO)
+        );
+    }
+
     struct AuthFileParams {
         openai_api_key: Option<String>,
         chatgpt_plan_type: String,
@@ -565,6 +1045,21 @@ mod tests {
     }
 }
 
+/// Time from `now` until `tokens` are due for a refresh: shortly before the
+/// access token expires, or [`REFRESH_FALLBACK_DAYS`] after `last_refresh`
+/// when the expiry cannot be read.
+fn refresh_delay(
+    tokens: &TokenData,
+    last_refresh: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    let refresh_at = match parse_jwt_expiration(&tokens.access_token) {
+        Some(expires_at) => expires_at - chrono::Duration::from_std(REFRESH_MARGIN).ok()?,
+        None => last_refresh? + chrono::Duration::days(REFRESH_FALLBACK_DAYS),
+    };
+    Some((refresh_at - now).to_std().unwrap_or(Duration::ZERO))
+}
+
 /// Central manager providing a single source of truth for auth.json derived
 /// authentication data. It loads once (or on preference change) and then
 /// hands out cloned `CodexAuth` values so the rest of the program has a
@@ -576,7 +1071,17 @@ mod tests {
 #[derive(Debug)]
 pub struct AuthManager {
     codex_home: PathBuf,
+    credential_store_mode: CredentialStoreMode,
     inner: RwLock<CachedAuth>,
+    external: Option<Arc<ExternalAuthProvider>>,
+    /// Serializes refreshes: refresh tokens are single-use, so concurrent
+    /// refreshes with the same token would make all but one fail.
+    refresh_lock: tokio::sync::Mutex<()>,
+    /// Refresh token the token endpoint rejected, so that the background task
+    /// does not retry it until the user logs in again.
+    rejected_refresh_token: Mutex<Option<String>>,
+    login_required_tx: broadcast::Sender<String>,
+    refresh_task_started: AtomicBool,
 }
 
 impl AuthManager {
@@ -584,21 +1089,52 @@ impl AuthManager {
     /// preferred auth method. Errors loading auth are swallowed; `auth()` will
     /// simply return `None` in that case so callers can treat it as an
     /// unauthenticated state.
-    pub fn new(codex_home: PathBuf) -> Self {
-        let auth = CodexAuth::from_codex_home(&codex_home).ok().flatten();
+    pub fn new(codex_home: PathBuf, credential_store_mode: CredentialStoreMode) -> Self {
+        let auth = CodexAuth::from_codex_home(&codex_home, credential_store_mode)
+            .ok()
+            .flatten();
+        Self::with_auth(codex_home, credential_store_mode, auth)
+    }
+
+    fn with_auth(
+        codex_home: PathBuf,
+        credential_store_mode: CredentialStoreMode,
+        auth: Option<CodexAuth>,
+    ) -> Self {
+        let (login_required_tx, _) = broadcast::channel(16);
         Self {
             codex_home,
+            credential_store_mode,
             inner: RwLock::new(CachedAuth { auth }),
+            external: None,
+            refresh_lock: tokio::sync::Mutex::new(()),
+            rejected_refresh_token: Mutex::new(None),
+            login_required_tx,
+            refresh_task_started: AtomicBool::new(false),
+        }
+    }
+
+    /// Create a shared manager for `config`. When `external_auth_command` is
+    /// set, tokens come from that command instead of the credential store.
+    pub fn shared_from_config(config: &Config) -> Arc<Self> {
+        let mut manager = Self::new(config.codex_home.clone(), config.credential_store);
+        if let Some(command) = config.external_auth_command.clone() {
+            let external = Arc::new(ExternalAuthProvider::new(command));
+            manager.inner = RwLock::new(CachedAuth {
+                auth: Some(CodexAuth::from_external_auth(external.clone())),
+            });
+            manager.external = Some(external);
         }
+        Arc::new(manager)
     }
 
     /// Create an AuthManager with a specific CodexAuth, for testing only.
     pub fn from_auth_for_testing(auth: CodexAuth) -> Arc<Self> {
-        let cached = CachedAuth { auth: Some(auth) };
-        Arc::new(Self {
-            codex_home: PathBuf::new(),
-            inner: RwLock::new(cached),
-        })
+        Arc::new(Self::with_auth(
+            PathBuf::new(),
+            CredentialStoreMode::File,
+            Some(auth),
+        ))
     }
 
     /// Current cached auth (clone). May be `None` if not logged in or load failed.
@@ -609,7 +1145,12 @@ impl AuthManager {
     /// Force a reload of the auth information from auth.json. Returns
     /// whether the auth value changed.
     pub fn reload(&self) -> bool {
-        let new_auth = CodexAuth::from_codex_home(&self.codex_home).ok().flatten();
+        if self.external.is_some() {
+            return false;
+        }
+        let new_auth = CodexAuth::from_codex_home(&self.codex_home, self.credential_store_mode)
+            .ok()
+            .flatten();
         if let Ok(mut guard) = self.inner.write() {
             let changed = !AuthManager::auths_equal(&guard.auth, &new_auth);
             guard.auth = new_auth;
@@ -628,33 +1169,134 @@ impl AuthManager {
     }
 
     /// Convenience constructor returning an `Arc` wrapper.
-    pub fn shared(codex_home: PathBuf) -> Arc<Self> {
-        Arc::new(Self::new(codex_home))
+    pub fn shared(codex_home: PathBuf, credential_store_mode: CredentialStoreMode) -> Arc<Self> {
+        Arc::new(Self::new(codex_home, credential_store_mode))
     }
 
-    /// Attempt to refresh the current auth token (if any). On success, reload
-    /// the auth state from disk so other components observe refreshed token.
-    pub async fn refresh_token(&self) -> std::io::Result<Option<String>> {
-        let auth = match self.auth() {
-            Some(a) => a,
-            None => return Ok(None),
+    /// Attempt to refresh the current auth token (if any), retrying transient
+    /// failures with backoff. On success, reload the auth state from the
+    /// credential store so other components observe the refreshed token. If
+    /// the refresh token is rejected, subscribers of
+    /// [`AuthManager::subscribe_login_required`] are notified.
+    pub async fn refresh_token(&self) -> Result<Option<String>, RefreshTokenError> {
+        if let Some(external) = &self.external {
+            external.invalidate().await;
+            return Ok(Some(external.token().await?));
+        }
+        let stale_token = match self.auth() {
+            Some(auth) if auth.mode == AuthMode::ChatGPT => auth.get_current_token_data(),
+            _ => return Ok(None),
         };
-        match auth.refresh_token().await {
-            Ok(token) => {
-                // Reload to pick up persisted changes.
-                self.reload();
-                Ok(Some(token))
+
+        let _guard = self.refresh_lock.lock().await;
+        // Another caller may have refreshed while we waited for the lock.
+        self.reload();
+        let Some(auth) = self.auth() else {
+            return Ok(None);
+        };
+        let current_token = auth.get_current_token_data();
+        if let Some(current) = &current_token
+            && stale_token.as_ref().map(|t| &t.access_token) != Some(&current.access_token)
+        {
+            return Ok(Some(current.access_token.clone()));
+        }
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match auth.try_refresh().await {
+                Ok(token) => {
+                    // Reload to pick up persisted changes.
+                    self.reload();
+                    return Ok(Some(token));
+                }
+                Err(RefreshTokenError::Permanent(message)) => {
+                    if let Ok(mut rejected) = self.rejected_refresh_token.lock() {
+                        *rejected = current_token.map(|t| t.refresh_token);
+                    }
+                    // Nobody may be subscribed yet, e.g. before the first
+                    // session starts.
+                    let _ = self.login_required_tx.send(message.clone());
+                    return Err(RefreshTokenError::Permanent(message));
+                }
+                Err(RefreshTokenError::Transient(err)) if attempt < MAX_REFRESH_ATTEMPTS => {
+                    warn!("token refresh attempt {attempt} failed: {err}");
+                    tokio::time::sleep(backoff(attempt)).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Receive a message each time a refresh fails because the user must log
+    /// in again.
+    pub fn subscribe_login_required(&self) -> broadcast::Receiver<String> {
+        self.login_required_tx.subscribe()
+    }
+
+    /// Start a background task that refreshes ChatGPT tokens shortly before
+    /// they expire, so that requests do not fail with a 401 mid-turn. Only the
+    /// first call starts a task; it exits once the manager is dropped.
+    pub fn spawn_refresh_task(self: &Arc<Self>) {
+        if self.refresh_task_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                let Some(this) = manager.upgrade() else {
+                    return;
+                };
+                let sleep_for = match this.time_until_refresh() {
+                    Some(delay) if delay.is_zero() => {
+                        if let Err(err) = this.refresh_token().await {
+                            warn!("background token refresh failed: {err}");
+                        }
+                        REFRESH_CHECK_INTERVAL
+                    }
+                    Some(delay) => delay.min(REFRESH_CHECK_INTERVAL),
+                    None => REFRESH_CHECK_INTERVAL,
+                };
+                drop(this);
+                tokio::time::sleep(sleep_for).await;
             }
-            Err(e) => Err(e),
+        });
+    }
+
+    /// How long until the current ChatGPT tokens should be refreshed, or
+    /// `None` if there is nothing to refresh.
+    fn time_until_refresh(&self) -> Option<Duration> {
+        if self.external.is_some() {
+            return None;
+        }
+        let auth = self.auth()?;
+        if auth.mode != AuthMode::ChatGPT {
+            return None;
+        }
+        let auth_dot_json = auth.get_current_auth_json()?;
+        let tokens = auth_dot_json.tokens?;
+        if let Ok(rejected) = self.rejected_refresh_token.lock()
+            && rejected.as_ref() == Some(&tokens.refresh_token)
+        {
+            return None;
         }
+        refresh_delay(&tokens, auth_dot_json.last_refresh, Utc::now())
+    }
+
+    /// Make the stored account `name` active and reload the cached auth so
+    /// callers immediately observe the new credentials.
+    pub fn switch_account(&self, name: &str) -> std::io::Result<()> {
+        switch_account(&self.codex_home, name, self.credential_store_mode)?;
+        self.reload();
+        Ok(())
     }
 
-    /// Log out by deleting the on‑disk auth.json (if present). Returns Ok(true)
-    /// if a file was removed, Ok(false) if no auth file existed. On success,
+    /// Log out by deleting the stored credentials (if present). Returns Ok(true)
+    /// if credentials were removed, Ok(false) if none existed. On success,
     /// reloads the in‑memory auth cache so callers immediately observe the
     /// unauthenticated state.
     pub fn logout(&self) -> std::io::Result<bool> {
-        let removed = super::auth::logout(&self.codex_home)?;
+        let removed = super::auth::logout(&self.codex_home, self.credential_store_mode)?;
         // Always reload to clear any cached auth (even if file absent).
         self.reload();
         Ok(removed)
@@ -0,0 +1,248 @@
+//! Committing each patch the agent applies to git (`auto_commit`), so that
+//! its work can be reviewed and reverted commit by commit.
+//!
+//! Each patch becomes one commit of just the files it touched, stacked on the
+//! previous one. By default the commits go on a branch of the session's own,
+//! `codex/<session id>`, created from `HEAD` before the first commit.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use tokio::sync::Mutex;
+
+use crate::config_types::AutoCommit;
+use crate::git_info::run_git;
+use crate::protocol::FileChange;
+
+/// Upper bound on each git command; `commit` may run hooks.
+const GIT_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Files named in the subject of a commit message before they are counted
+/// instead.
+const SUBJECT_MAX_FILES: usize = 3;
+
+pub(crate) struct AutoCommitter {
+    new_branch: bool,
+    conversation_id: ConversationId,
+    /// The session's branch, once it has been created or switched to.
+    branch: Mutex<Option<String>>,
+}
+
+impl AutoCommitter {
+    /// `None` unless `auto_commit` is enabled.
+    pub(crate) fn new(config: &AutoCommit, conversation_id: ConversationId) -> Option<Self> {
+        config.enabled.then(|| Self {
+            new_branch: config.new_branch,
+            conversation_id,
+            branch: Mutex::new(None),
+        })
+    }
+
+    /// Commits the files changed by a patch applied in `cwd`. Returns a notice
+    /// describing the commit, or `None` if `cwd` is not in a git repository or
+    /// the patch left nothing to commit.
+    pub(crate) async fn commit_patch(
+        &self,
+        cwd: &Path,
+        changes: &HashMap<PathBuf, FileChange>,
+    ) -> Result<Option<String>, String> {
+        let Ok(root) = git(cwd, &["rev-parse", "--show-toplevel"]).await else {
+            return Ok(None);
+        };
+        let root = PathBuf::from(root);
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for (path, change) in changes {
+            paths.push(cwd.join(path));
+            if let FileChange::Update {
+                move_path: Some(dest),
+                ..
+            } = change
+            {
+                paths.push(cwd.join(dest));
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        let pathspecs: Vec<String> = paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+
+        let branch = if self.new_branch {
+            Some(self.switch_to_session_branch(&root).await?)
+        } else {
+            None
+        };
+
+        let mut add = vec!["add", "--all", "--"];
+        add.extend(pathspecs.iter().map(String::as_str));
+        git(&root, &add).await?;
+
+        let mut staged = vec!["diff", "--cached", "--name-only", "--"];
+        staged.extend(pathspecs.iter().map(String::as_str));
+        if git(&root, &staged).await?.is_empty() {
+            return Ok(None);
+        }
+
+        let subject = commit_subject(&root, &paths);
+        let trailer = format!("Codex-Session: {}", self.conversation_id);
+        let mut commit = vec!["commit", "--quiet", "-m", &subject, "-m", &trailer, "--"];
+        commit.extend(pathspecs.iter().map(String::as_str));
+        git(&root, &commit).await?;
+
+        let sha = git(&root, &["rev-parse", "--short", "HEAD"]).await?;
+        Ok(Some(match branch {
+            Some(branch) => format!("Committed {sha} on {branch}: {subject}"),
+            None => format!("Committed {sha}: {subject}"),
+        }))
+    }
+
+    /// Creates `codex/<session id>` from `HEAD` and switches to it, the first
+    /// time; the uncommitted changes in the working tree are carried over.
+    async fn switch_to_session_branch(&self, root: &Path) -> Result<String, String> {
+        let mut branch = self.branch.lock().await;
+        if let Some(name) = branch.as_ref() {
+            return Ok(name.clone());
+        }
+        let name = format!("codex/{}", self.conversation_id);
+        let current = git(root, &["branch", "--show-current"]).await?;
+        if current != name {
+            let exists = git(
+                root,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{name}"),
+                ],
+            )
+            .await
+            .is_ok();
+            if exists {
+                git(root, &["switch", "--quiet", &name]).await?;
+            } else {
+                git(root, &["switch", "--quiet", "--create", &name]).await?;
+            }
+        }
+        *branch = Some(name.clone());
+        Ok(name)
+    }
+}
+
+/// `codex: update a.rs, b.rs`, with paths relative to the repository root.
+fn commit_subject(root: &Path, paths: &[PathBuf]) -> String {
+    let names: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    if names.len() > SUBJECT_MAX_FILES {
+        format!("codex: update {} files", names.len())
+    } else {
+        format!("codex: update {}", names.join(", "))
+    }
+}
+
+/// Runs git in `cwd` with this module's time limit.
+async fn git(cwd: &Path, args: &[&str]) -> Result<String, String> {
+    run_git(cwd, args, GIT_COMMAND_TIMEOUT).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "--quiet", "--initial-branch=main"],
+            vec!["config", "user.name", "Test User"],
+            vec!["config", "user.email", "test@example.com"],
+        ] {
+            git(dir, &args).await.expect("git setup");
+        }
+        std::fs::write(dir.join("README.md"), "hello\n").expect("write");
+        git(dir, &["add", "README.md"]).await.expect("add");
+        git(dir, &["commit", "--quiet", "-m", "init"])
+            .await
+            .expect("commit");
+    }
+
+    fn added(path: &Path, content: &str) -> HashMap<PathBuf, FileChange> {
+        HashMap::from([(
+            path.to_path_buf(),
+            FileChange::Add {
+                content: content.to_string(),
+            },
+        )])
+    }
+
+    #[tokio::test]
+    async fn commits_each_patch_on_the_session_branch() {
+        let repo = TempDir::new().expect("tempdir");
+        let root = repo.path().canonicalize().expect("canonicalize");
+        init_repo(&root).await;
+        let conversation_id = ConversationId::new();
+        let committer = AutoCommitter::new(
+            &AutoCommit {
+                enabled: true,
+                new_branch: true,
+            },
+            conversation_id,
+        )
+        .expect("enabled");
+
+        // A file the patch did not touch stays uncommitted.
+        std::fs::write(root.join("scratch.txt"), "wip\n").expect("write");
+        for name in ["a.rs", "b.rs"] {
+            std::fs::write(root.join(name), "fn main() {}\n").expect("write");
+            let notice = committer
+                .commit_patch(&root, &added(&root.join(name), "fn main() {}\n"))
+                .await
+                .expect("commit")
+                .expect("notice");
+            assert!(
+                notice.ends_with(&format!("codex: update {name}")),
+                "{notice}"
+            );
+        }
+
+        let branch = git(&root, &["branch", "--show-current"]).await.unwrap();
+        assert_eq!(branch, format!("codex/{conversation_id}"));
+        let log = git(&root, &["log", "--format=%s", "main.."]).await.unwrap();
+        assert_eq!(log, "codex: update b.rs\ncodex: update a.rs");
+        let body = git(&root, &["log", "-1", "--format=%b"]).await.unwrap();
+        assert_eq!(body, format!("Codex-Session: {conversation_id}"));
+        let status = git(&root, &["status", "--porcelain"]).await.unwrap();
+        assert_eq!(status, "?? scratch.txt");
+    }
+
+    #[tokio::test]
+    async fn does_nothing_outside_a_repository() {
+        let dir = TempDir::new().expect("tempdir");
+        let committer = AutoCommitter::new(
+            &AutoCommit {
+                enabled: true,
+                new_branch: false,
+            },
+            ConversationId::new(),
+        )
+        .expect("enabled");
+        let path = dir.path().join("a.rs");
+        std::fs::write(&path, "").expect("write");
+        let notice = committer
+            .commit_patch(dir.path(), &added(&path, ""))
+            .await
+            .expect("no error");
+        assert_eq!(notice, None);
+    }
+}
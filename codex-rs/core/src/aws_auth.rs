@@ -0,0 +1,511 @@
+//! AWS credentials and Signature Version 4 request signing for providers with
+//! `wire_api = "bedrock"`.
+//!
+//! Credentials are looked up like the AWS CLI does, stopping at the first
+//! source that has them:
+//!   1. `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`.
+//!   2. The profile in `~/.aws/credentials` and `~/.aws/config`: static keys
+//!      or a `credential_process` (which also covers SSO through
+//!      `aws configure export-credentials`).
+//!   3. The ECS container credentials endpoint.
+//!   4. The EC2 instance metadata service (IMDSv2).
+//!
+//! Temporary credentials are cached until shortly before they expire.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use serde::Deserialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::error::CodexErr;
+use crate::error::EnvVarError;
+use crate::error::Result;
+use crate::model_provider_info::AwsSettings;
+
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Service name that Bedrock requests are signed for.
+const SERVICE: &str = "bedrock";
+
+/// Cached credentials are refreshed this long before they expire.
+const EXPIRATION_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+
+static CACHE: LazyLock<Mutex<HashMap<String, AwsCredentials>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct AwsCredentials {
+    pub(crate) access_key_id: String,
+    pub(crate) secret_access_key: String,
+    pub(crate) session_token: Option<String>,
+    pub(crate) expiration: Option<DateTime<Utc>>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("expiration", &self.expiration)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The JSON returned by `credential_process`, the container endpoint and
+/// IMDS. The session token is `SessionToken` in the first and `Token` in the
+/// others.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CredentialsJson {
+    access_key_id: String,
+    secret_access_key: String,
+    #[serde(alias = "Token")]
+    session_token: Option<String>,
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl From<CredentialsJson> for AwsCredentials {
+    fn from(json: CredentialsJson) -> Self {
+        Self {
+            access_key_id: json.access_key_id,
+            secret_access_key: json.secret_access_key,
+            session_token: json.session_token,
+            expiration: json.expiration,
+        }
+    }
+}
+
+fn profile_name(settings: Option<&AwsSettings>) -> String {
+    settings
+        .and_then(|settings| settings.profile.clone())
+        .or_else(|| non_empty_env("AWS_PROFILE"))
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// The region requests are sent to: the provider's `aws.region`, then
+/// `AWS_REGION`, `AWS_DEFAULT_REGION` and the profile's `region`.
+pub(crate) fn resolve_region(settings: Option<&AwsSettings>) -> String {
+    settings
+        .and_then(|settings| settings.region.clone())
+        .or_else(|| non_empty_env("AWS_REGION"))
+        .or_else(|| non_empty_env("AWS_DEFAULT_REGION"))
+        .or_else(|| {
+            let profile = profile_name(settings);
+            read_profile(&config_file_path()?, &profile, true)?.remove("region")
+        })
+        .unwrap_or_else(|| DEFAULT_REGION.to_string())
+}
+
+pub(crate) async fn load_credentials(
+    client: &reqwest::Client,
+    settings: Option<&AwsSettings>,
+) -> Result<AwsCredentials> {
+    if let (Some(access_key_id), Some(secret_access_key)) = (
+        non_empty_env("AWS_ACCESS_KEY_ID"),
+        non_empty_env("AWS_SECRET_ACCESS_KEY"),
+    ) {
+        return Ok(AwsCredentials {
+            access_key_id,
+            secret_access_key,
+            session_token: non_empty_env("AWS_SESSION_TOKEN"),
+            expiration: None,
+        });
+    }
+
+    let profile = profile_name(settings);
+    if let Some(credentials) = cached(&profile) {
+        return Ok(credentials);
+    }
+    let credentials = match credentials_from_profile(&profile).await? {
+        Some(credentials) => Some(credentials),
+        None => match credentials_from_container(client).await? {
+            Some(credentials) => Some(credentials),
+            None => credentials_from_imds(client).await,
+        },
+    };
+    let Some(credentials) = credentials else {
+        return Err(CodexErr::EnvVar(EnvVarError {
+            var: "AWS_ACCESS_KEY_ID".to_string(),
+            instructions: Some(format!(
+                "No AWS credentials were found for profile `{profile}`. Set AWS_ACCESS_KEY_ID \
+                 and AWS_SECRET_ACCESS_KEY, or configure the profile in ~/.aws/credentials."
+            )),
+        }));
+    };
+    if credentials.expiration.is_some() {
+        CACHE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(profile, credentials.clone());
+    }
+    Ok(credentials)
+}
+
+fn cached(profile: &str) -> Option<AwsCredentials> {
+    let cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    let credentials = cache.get(profile)?;
+    let expiration = credentials.expiration?;
+    (expiration - Utc::now())
+        .to_std()
+        .is_ok_and(|left| left > EXPIRATION_MARGIN)
+        .then(|| credentials.clone())
+}
+
+async fn credentials_from_profile(profile: &str) -> Result<Option<AwsCredentials>> {
+    let credentials_section = credentials_file_path()
+        .and_then(|path| read_profile(&path, profile, false))
+        .unwrap_or_default();
+    let config_section = config_file_path()
+        .and_then(|path| read_profile(&path, profile, true))
+        .unwrap_or_default();
+
+    for section in [&credentials_section, &config_section] {
+        if let (Some(access_key_id), Some(secret_access_key)) = (
+            section.get("aws_access_key_id"),
+            section.get("aws_secret_access_key"),
+        ) {
+            return Ok(Some(AwsCredentials {
+                access_key_id: access_key_id.clone(),
+                secret_access_key: secret_access_key.clone(),
+                session_token: section.get("aws_session_token").cloned(),
+                expiration: None,
+            }));
+        }
+    }
+
+    let Some(command) = credentials_section
+        .get("credential_process")
+        .or_else(|| config_section.get("credential_process"))
+    else {
+        return Ok(None);
+    };
+    let Some(argv) = shlex::split(command) else {
+        return Err(CodexErr::Io(std::io::Error::other(format!(
+            "invalid credential_process for AWS profile `{profile}`"
+        ))));
+    };
+    let Some((program, args)) = argv.split_first() else {
+        return Ok(None);
+    };
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(CodexErr::Io(std::io::Error::other(format!(
+            "credential_process for AWS profile `{profile}` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    let json: CredentialsJson = serde_json::from_slice(&output.stdout)?;
+    Ok(Some(json.into()))
+}
+
+async fn credentials_from_container(client: &reqwest::Client) -> Result<Option<AwsCredentials>> {
+    let url = if let Some(relative) = non_empty_env("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI") {
+        format!("http://169.254.170.2{relative}")
+    } else if let Some(full) = non_empty_env("AWS_CONTAINER_CREDENTIALS_FULL_URI") {
+        full
+    } else {
+        return Ok(None);
+    };
+    let mut request = client.get(url).timeout(METADATA_TIMEOUT);
+    if let Some(token) = non_empty_env("AWS_CONTAINER_AUTHORIZATION_TOKEN") {
+        request = request.header(reqwest::header::AUTHORIZATION, token);
+    }
+    let json: CredentialsJson = request.send().await?.error_for_status()?.json().await?;
+    Ok(Some(json.into()))
+}
+
+/// Instance role credentials, or `None` when not running on EC2.
+async fn credentials_from_imds(client: &reqwest::Client) -> Option<AwsCredentials> {
+    if non_empty_env("AWS_EC2_METADATA_DISABLED").is_some_and(|v| v.eq_ignore_ascii_case("true")) {
+        return None;
+    }
+    const BASE: &str = "http://169.254.169.254/latest";
+    let token = client
+        .put(format!("{BASE}/api/token"))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", "21600")
+        .timeout(METADATA_TIMEOUT)
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let get = |path: String| {
+        client
+            .get(format!("{BASE}/meta-data/iam/security-credentials/{path}"))
+            .header("X-aws-ec2-metadata-token", &token)
+            .timeout(METADATA_TIMEOUT)
+            .send()
+    };
+    let roles = get(String::new()).await.ok()?.text().await.ok()?;
+    let role = roles.lines().next()?.trim().to_string();
+    let json: CredentialsJson = get(role).await.ok()?.json().await.ok()?;
+    Some(json.into())
+}
+
+fn credentials_file_path() -> Option<PathBuf> {
+    non_empty_env("AWS_SHARED_CREDENTIALS_FILE")
+        .map(PathBuf::from)
+        .or_else(|| Some(dirs::home_dir()?.join(".aws").join("credentials")))
+}
+
+fn config_file_path() -> Option<PathBuf> {
+    non_empty_env("AWS_CONFIG_FILE")
+        .map(PathBuf::from)
+        .or_else(|| Some(dirs::home_dir()?.join(".aws").join("config")))
+}
+
+fn read_profile(
+    path: &std::path::Path,
+    profile: &str,
+    is_config_file: bool,
+) -> Option<HashMap<String, String>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    parse_profile(&contents, profile, is_config_file)
+}
+
+/// The keys of `profile` in an AWS INI file. In the config file, profiles
+/// other than `default` are written `[profile name]`.
+fn parse_profile(
+    contents: &str,
+    profile: &str,
+    is_config_file: bool,
+) -> Option<HashMap<String, String>> {
+    let wanted = if is_config_file && profile != "default" {
+        format!("profile {profile}")
+    } else {
+        profile.to_string()
+    };
+    let mut section: Option<HashMap<String, String>> = None;
+    let mut in_section = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == wanted;
+            if in_section {
+                section.get_or_insert_with(HashMap::new);
+            }
+            continue;
+        }
+        if in_section
+            && let Some((key, value)) = line.split_once('=')
+            && let Some(section) = section.as_mut()
+        {
+            section.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    section
+}
+
+fn non_empty_env(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Percent-encodes everything but the unreserved characters, as SigV4
+/// requires.
+pub(crate) fn uri_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    #[expect(clippy::expect_used)]
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Signs a request with SigV4. Returns the headers to add to it, which must
+/// be sent along with `headers` exactly as given.
+pub(crate) fn sign_request(
+    credentials: &AwsCredentials,
+    region: &str,
+    method: &str,
+    url: &reqwest::Url,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let mut host = url.host_str().unwrap_or_default().to_string();
+    if let Some(port) = url.port() {
+        host.push_str(&format!(":{port}"));
+    }
+    let mut canonical_headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    canonical_headers.push(("host".to_string(), host));
+    canonical_headers.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        canonical_headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    canonical_headers.sort();
+    let signed_headers = canonical_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    // Services other than S3 encode each path segment a second time.
+    let canonical_uri = url
+        .path()
+        .split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let canonical_request = format!(
+        "{method}\n{canonical_uri}\n{canonical_query}\n{}\n{signed_headers}\n{}",
+        canonical_headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect::<String>(),
+        hex_sha256(body)
+    );
+    let scope = format!("{date}/{region}/{SERVICE}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex_sha256(canonical_request.as_bytes())
+    );
+
+    let key = [date.as_str(), region, SERVICE, "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part),
+        );
+    let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+    let mut signed = vec![
+        ("x-amz-date".to_string(), amz_date),
+        (
+            "authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+                 Signature={signature}",
+                credentials.access_key_id
+            ),
+        ),
+    ];
+    if let Some(token) = &credentials.session_token {
+        signed.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_profiles_from_credentials_and_config_files() {
+        let credentials = "[default]\naws_access_key_id = AKIDDEFAULT\n\n[work]\n\
+                           aws_access_key_id = AKIDWORK\naws_secret_access_key = secret\n";
+        assert_eq!(
+            parse_profile(credentials, "work", false),
+            Some(HashMap::from([
+                ("aws_access_key_id".to_string(), "AKIDWORK".to_string()),
+                ("aws_secret_access_key".to_string(), "secret".to_string()),
+            ]))
+        );
+        assert_eq!(parse_profile(credentials, "missing", false), None);
+
+        let config =
+            "# comment\n[profile work]\nregion = eu-west-1\n[default]\nregion = us-west-2\n";
+        assert_eq!(
+            parse_profile(config, "work", true).and_then(|mut p| p.remove("region")),
+            Some("eu-west-1".to_string())
+        );
+        assert_eq!(
+            parse_profile(config, "default", true).and_then(|mut p| p.remove("region")),
+            Some("us-west-2".to_string())
+        );
+    }
+
+    #[test]
+    fn signs_requests_with_sigv4() {
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+            expiration: None,
+        };
+        let url = reqwest::Url::parse(
+            "https://bedrock-runtime.us-east-1.amazonaws.com/model/anthropic.claude-3-5-sonnet-20240620-v1%3A0/converse-stream",
+        )
+        .expect("url");
+        let now = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+
+        let signed = sign_request(
+            &credentials,
+            "us-east-1",
+            "POST",
+            &url,
+            &[("content-type", "application/json")],
+            br#"{"messages":[]}"#,
+            now,
+        );
+
+        assert_eq!(
+            signed,
+            vec![
+                ("x-amz-date".to_string(), "20250101T000000Z".to_string()),
+                (
+                    "authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20250101/us-east-1/bedrock/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date, \
+                     Signature=93a4648721167348c8e34545194969c21d2b0b8b5ab434024d1ccd11c366d4fe"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+}
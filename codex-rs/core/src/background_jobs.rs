@@ -0,0 +1,529 @@
+//! Background jobs: shell commands started with `run_in_background: true`
+//! keep running while the model continues its turn. The model polls their
+//! output with the `job_result` tool and stops them with `job_kill`.
+//!
+//! Jobs keep running across turns, so the model can pick one up in a later
+//! turn, and are killed when the session ends; the results of finished jobs
+//! stay available for the rest of the session. Each job is
+//! time-boxed by its `timeout_ms`, which defaults to
+//! [`DEFAULT_BACKGROUND_JOB_TIMEOUT_MS`] instead of the short foreground
+//! default.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::BackgroundJobStatus;
+
+pub(crate) const JOB_RESULT_TOOL_NAME: &str = "job_result";
+pub(crate) const JOB_KILL_TOOL_NAME: &str = "job_kill";
+
+/// Time box for a background job whose command does not set `timeout_ms`.
+pub(crate) const DEFAULT_BACKGROUND_JOB_TIMEOUT_MS: u64 = 30 * 60 * 1000;
+
+/// Upper bound on how long a single `job_result` call waits for a job.
+const MAX_WAIT_MS: u64 = 10 * 60 * 1000;
+
+/// How long a killed job gets to report its outcome.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// How much of a running job's most recent output is kept for polling.
+const LIVE_OUTPUT_CAPACITY: usize = 32 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JobResultArgs {
+    #[serde(default)]
+    pub(crate) job_id: Option<String>,
+    #[serde(default)]
+    pub(crate) wait_ms: Option<u64>,
+}
+
+impl JobResultArgs {
+    pub(crate) fn wait(&self) -> Duration {
+        Duration::from_millis(self.wait_ms.unwrap_or(0).min(MAX_WAIT_MS))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct JobKillArgs {
+    pub(crate) job_id: String,
+}
+
+/// The tail of a running job's output. Each poll returns what was written
+/// since the previous one; output older than [`LIVE_OUTPUT_CAPACITY`] bytes is
+/// dropped.
+#[derive(Debug, Default)]
+pub(crate) struct LiveOutput {
+    state: StdMutex<LiveOutputState>,
+}
+
+#[derive(Debug, Default)]
+struct LiveOutputState {
+    buf: Vec<u8>,
+    /// Bytes dropped from the front of `buf`.
+    dropped: usize,
+    /// Bytes returned by [`LiveOutput::take_new`] so far, counting dropped ones.
+    read: usize,
+}
+
+impl LiveOutput {
+    pub(crate) fn push(&self, chunk: &[u8]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.buf.extend_from_slice(chunk);
+        let excess = state.buf.len().saturating_sub(LIVE_OUTPUT_CAPACITY);
+        if excess > 0 {
+            state.buf.drain(..excess);
+            state.dropped += excess;
+        }
+    }
+
+    /// Output written since the previous call.
+    pub(crate) fn take_new(&self) -> String {
+        let Ok(mut state) = self.state.lock() else {
+            return String::new();
+        };
+        let skipped = state.dropped.saturating_sub(state.read);
+        let start = state.read.max(state.dropped) - state.dropped;
+        let mut new = String::new();
+        if skipped > 0 {
+            new.push_str(&format!("[... {skipped} bytes omitted ...]\n"));
+        }
+        new.push_str(&String::from_utf8_lossy(&state.buf[start..]));
+        state.read = state.dropped + state.buf.len();
+        new
+    }
+
+    /// All the output that is still kept.
+    pub(crate) fn snapshot(&self) -> String {
+        let Ok(state) = self.state.lock() else {
+            return String::new();
+        };
+        let text = String::from_utf8_lossy(&state.buf);
+        if state.dropped > 0 {
+            format!("[... {} bytes omitted ...]\n{text}", state.dropped)
+        } else {
+            text.into_owned()
+        }
+    }
+}
+
+/// Handed to a job when it is spawned: the job records its output in
+/// `output` and stops its command once `kill` is cancelled.
+#[derive(Clone)]
+pub(crate) struct JobControl {
+    pub(crate) kill: CancellationToken,
+    pub(crate) output: Arc<LiveOutput>,
+}
+
+/// What a finished job reports back to the model.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JobOutcome {
+    pub(crate) status: BackgroundJobStatus,
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) duration: Duration,
+    /// Output formatted for the model.
+    pub(crate) output: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct JobReport {
+    pub(crate) job_id: String,
+    pub(crate) command: Vec<String>,
+    pub(crate) status: BackgroundJobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_seconds: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) output: Option<String>,
+}
+
+struct BackgroundJob {
+    command: Vec<String>,
+    outcome: watch::Receiver<Option<JobOutcome>>,
+    abort: AbortHandle,
+    kill: CancellationToken,
+    live_output: Arc<LiveOutput>,
+}
+
+impl BackgroundJob {
+    /// The job's outcome, if it has one. A job whose task ended without
+    /// reporting (it panicked or was aborted) counts as failed.
+    fn outcome(&self) -> Option<JobOutcome> {
+        let outcome = self.outcome.borrow().clone();
+        if outcome.is_none() && self.abort.is_finished() {
+            return Some(JobOutcome {
+                status: BackgroundJobStatus::Failed,
+                exit_code: None,
+                duration: Duration::ZERO,
+                output: "the job ended without reporting a result".to_string(),
+            });
+        }
+        outcome
+    }
+
+    fn report(&self, job_id: &str, include_output: bool) -> JobReport {
+        match self.outcome() {
+            Some(outcome) => JobReport {
+                job_id: job_id.to_string(),
+                command: self.command.clone(),
+                status: outcome.status,
+                exit_code: outcome.exit_code,
+                // round to 1 decimal place
+                duration_seconds: Some((outcome.duration.as_secs_f32() * 10.0).round() / 10.0),
+                output: include_output.then_some(outcome.output),
+            },
+            None => JobReport {
+                job_id: job_id.to_string(),
+                command: self.command.clone(),
+                status: BackgroundJobStatus::Running,
+                exit_code: None,
+                duration_seconds: None,
+                output: include_output.then(|| self.live_output.take_new()),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct BackgroundJobManager {
+    next_job_id: AtomicU64,
+    jobs: Mutex<IndexMap<String, BackgroundJob>>,
+}
+
+impl BackgroundJobManager {
+    /// Reserves the id for the next job so that it can be announced before
+    /// the job is spawned.
+    pub(crate) fn next_job_id(&self) -> String {
+        let id = self.next_job_id.fetch_add(1, Ordering::Relaxed) + 1;
+        format!("job-{id}")
+    }
+
+    pub(crate) async fn spawn<F, Fut>(&self, job_id: String, command: Vec<String>, job: F)
+    where
+        F: FnOnce(JobControl) -> Fut,
+        Fut: Future<Output = JobOutcome> + Send + 'static,
+    {
+        let control = JobControl {
+            kill: CancellationToken::new(),
+            output: Arc::new(LiveOutput::default()),
+        };
+        let (tx, rx) = watch::channel(None);
+        let kill = control.kill.clone();
+        let live_output = control.output.clone();
+        let job = job(control);
+        let handle = tokio::spawn(async move {
+            let outcome = job.await;
+            tx.send_replace(Some(outcome));
+        });
+        self.jobs.lock().await.insert(
+            job_id,
+            BackgroundJob {
+                command,
+                outcome: rx,
+                abort: handle.abort_handle(),
+                kill,
+                live_output,
+            },
+        );
+    }
+
+    /// Reports on `job_id`, waiting up to `wait` for it to finish if it is
+    /// still running. Returns `None` for an unknown job.
+    pub(crate) async fn report(&self, job_id: &str, wait: Duration) -> Option<JobReport> {
+        let mut outcome = {
+            let jobs = self.jobs.lock().await;
+            let job = jobs.get(job_id)?;
+            job.outcome.clone()
+        };
+        if !wait.is_zero() && outcome.borrow().is_none() {
+            // An error means the job's task went away; `report` below says so.
+            let _ = tokio::time::timeout(wait, outcome.wait_for(Option::is_some)).await;
+        }
+        let jobs = self.jobs.lock().await;
+        jobs.get(job_id).map(|job| job.report(job_id, true))
+    }
+
+    /// Kills `job_id` if it is still running and reports on it. Returns
+    /// `None` for an unknown job.
+    pub(crate) async fn kill(&self, job_id: &str) -> Option<JobReport> {
+        let mut outcome = {
+            let jobs = self.jobs.lock().await;
+            let job = jobs.get(job_id)?;
+            job.kill.cancel();
+            job.outcome.clone()
+        };
+        let _ = tokio::time::timeout(KILL_GRACE_PERIOD, outcome.wait_for(Option::is_some)).await;
+        let jobs = self.jobs.lock().await;
+        jobs.get(job_id).map(|job| job.report(job_id, true))
+    }
+
+    /// Kills every job that is still running and waits for them to report.
+    /// Called when the session shuts down.
+    pub(crate) async fn kill_running(&self) {
+        let mut running = Vec::new();
+        for job in self.jobs.lock().await.values() {
+            if job.outcome.borrow().is_none() && !job.abort.is_finished() {
+                job.kill.cancel();
+                running.push(job.outcome.clone());
+            }
+        }
+        let deadline = tokio::time::Instant::now() + KILL_GRACE_PERIOD;
+        for mut outcome in running {
+            let _ = tokio::time::timeout_at(deadline, outcome.wait_for(Option::is_some)).await;
+        }
+    }
+
+    /// Status of every job in the session, oldest first, without output.
+    pub(crate) async fn list(&self) -> Vec<JobReport> {
+        let jobs = self.jobs.lock().await;
+        jobs.iter()
+            .map(|(job_id, job)| job.report(job_id, false))
+            .collect()
+    }
+}
+
+impl Drop for BackgroundJobManager {
+    fn drop(&mut self) {
+        // Commands are spawned with `kill_on_drop`, so aborting the tasks
+        // also kills any processes that are still running.
+        for job in self.jobs.get_mut().values() {
+            job.abort.abort();
+        }
+    }
+}
+
+pub(crate) fn create_job_result_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "job_id".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The id returned when the job was started. Omit to list every job and its status."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "wait_ms".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "How long to wait for a running job to finish before reporting, in milliseconds (at most {MAX_WAIT_MS}). Defaults to 0."
+            )),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: JOB_RESULT_TOOL_NAME.to_string(),
+        description: "Returns the status of a background job started with the shell tool's \
+                      `run_in_background` option. While the job runs, returns the output it \
+                      wrote since the previous call; once it has finished, returns its exit code \
+                      and output."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false),
+        },
+    })
+}
+
+pub(crate) fn create_job_kill_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "job_id".to_string(),
+        JsonSchema::String {
+            description: Some("The id returned when the job was started.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: JOB_KILL_TOOL_NAME.to_string(),
+        description: "Stops a running background job and returns its final status and output."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["job_id".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn outcome(output: &str) -> JobOutcome {
+        JobOutcome {
+            status: BackgroundJobStatus::Completed,
+            exit_code: Some(0),
+            duration: Duration::from_millis(1500),
+            output: output.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn report_waits_for_the_job_to_finish() {
+        let manager = BackgroundJobManager::default();
+        let job_id = manager.next_job_id();
+        assert_eq!(job_id, "job-1");
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+        manager
+            .spawn(job_id.clone(), vec!["make".to_string()], |_| async move {
+                let _ = release_rx.await;
+                outcome("done")
+            })
+            .await;
+
+        let running = manager.report(&job_id, Duration::ZERO).await;
+        assert_eq!(
+            running.map(|report| report.status),
+            Some(BackgroundJobStatus::Running)
+        );
+
+        let _ = release_tx.send(());
+        let finished = manager.report(&job_id, Duration::from_secs(5)).await;
+        assert_eq!(
+            finished,
+            Some(JobReport {
+                job_id: "job-1".to_string(),
+                command: vec!["make".to_string()],
+                status: BackgroundJobStatus::Completed,
+                exit_code: Some(0),
+                duration_seconds: Some(1.5),
+                output: Some("done".to_string()),
+            })
+        );
+        assert_eq!(manager.report("job-2", Duration::ZERO).await, None);
+    }
+
+    #[tokio::test]
+    async fn list_omits_output() {
+        let manager = BackgroundJobManager::default();
+        for command in ["a", "b"] {
+            let job_id = manager.next_job_id();
+            manager
+                .spawn(job_id.clone(), vec![command.to_string()], |_| async {
+                    outcome("out")
+                })
+                .await;
+            manager.report(&job_id, Duration::from_secs(5)).await;
+        }
+
+        let listed = manager.list().await;
+        assert_eq!(
+            listed
+                .iter()
+                .map(|report| (report.job_id.as_str(), report.output.is_none()))
+                .collect::<Vec<_>>(),
+            vec![("job-1", true), ("job-2", true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn running_jobs_report_new_output_and_can_be_killed() {
+        let manager = BackgroundJobManager::default();
+        let job_id = manager.next_job_id();
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel::<()>();
+        manager
+            .spawn(
+                job_id.clone(),
+                vec!["serve".to_string()],
+                |control| async move {
+                    control.output.push(b"listening\n");
+                    let _ = started_tx.send(());
+                    control.kill.cancelled().await;
+                    JobOutcome {
+                        status: BackgroundJobStatus::Killed,
+                        exit_code: None,
+                        duration: Duration::from_secs(2),
+                        output: control.output.snapshot(),
+                    }
+                },
+            )
+            .await;
+        let _ = started_rx.await;
+
+        let polled = manager.report(&job_id, Duration::ZERO).await;
+        assert_eq!(
+            polled.and_then(|report| report.output),
+            Some("listening\n".to_string())
+        );
+        let polled_again = manager.report(&job_id, Duration::ZERO).await;
+        assert_eq!(
+            polled_again.and_then(|report| report.output),
+            Some(String::new())
+        );
+
+        let killed = manager.kill(&job_id).await;
+        assert_eq!(
+            killed.map(|report| (report.status, report.output)),
+            Some((BackgroundJobStatus::Killed, Some("listening\n".to_string())))
+        );
+        assert_eq!(manager.kill("job-2").await, None);
+    }
+
+    #[tokio::test]
+    async fn kill_running_stops_every_running_job() {
+        let manager = BackgroundJobManager::default();
+        for command in ["a", "b"] {
+            manager
+                .spawn(
+                    manager.next_job_id(),
+                    vec![command.to_string()],
+                    |control| async move {
+                        control.kill.cancelled().await;
+                        JobOutcome {
+                            status: BackgroundJobStatus::Killed,
+                            exit_code: None,
+                            duration: Duration::ZERO,
+                            output: String::new(),
+                        }
+                    },
+                )
+                .await;
+        }
+
+        manager.kill_running().await;
+        assert_eq!(
+            manager
+                .list()
+                .await
+                .into_iter()
+                .map(|report| report.status)
+                .collect::<Vec<_>>(),
+            vec![BackgroundJobStatus::Killed, BackgroundJobStatus::Killed]
+        );
+    }
+
+    #[test]
+    fn live_output_keeps_the_tail() {
+        let output = LiveOutput::default();
+        output.push(&vec![b'a'; LIVE_OUTPUT_CAPACITY]);
+        output.push(b"bb");
+        let new = output.take_new();
+        assert!(new.starts_with("[... 2 bytes omitted ...]\n"), "{new}");
+        assert!(new.ends_with("abb"), "{new}");
+        assert_eq!(output.take_new(), "");
+    }
+}
@@ -0,0 +1,829 @@
+//! Client for the Converse API of Amazon Bedrock (`wire_api = "bedrock"`).
+//!
+//! Requests go to `converse-stream` and are signed with SigV4 using the
+//! standard AWS credential chain (see [`crate::aws_auth`]), unless the
+//! provider has a Bedrock API key, which is sent as a bearer token. The
+//! response is an AWS event stream, a binary framing of JSON events that is
+//! decoded here and mapped onto [`ResponseEvent`]s.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use chrono::Utc;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use tracing::warn;
+
+use crate::ModelProviderInfo;
+use crate::auth::CodexAuth;
+use crate::aws_auth::load_credentials;
+use crate::aws_auth::resolve_region;
+use crate::aws_auth::sign_request;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::openai_tools::create_tools_json_for_bedrock_api;
+use crate::protocol::TokenUsage;
+use crate::provider_failover::EndpointFailover;
+use crate::util::backoff;
+use crate::wire_log;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+
+pub(crate) async fn stream_bedrock_converse(
+    prompt: &Prompt,
+    config: &Config,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    failover: Option<&Arc<EndpointFailover>>,
+    provider_auth: &Option<CodexAuth>,
+) -> Result<ResponseStream> {
+    let model = &config.model_family.slug;
+    let payload = build_payload(prompt, config)?;
+    let body = serde_json::to_vec(&payload)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(provider_auth, model),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let (provider, endpoint) = match failover {
+            Some(failover) => {
+                let (provider, endpoint) = failover.select(provider, client).await;
+                (provider, Some(endpoint))
+            }
+            None => (Cow::Borrowed(provider), None),
+        };
+        let req_builder =
+            create_request_builder(&provider, client, provider_auth, model, &body).await?;
+
+        wire_log::log_request(&req_builder);
+        let res = req_builder.send().await;
+        if let Ok(resp) = &res {
+            wire_log::log_response(resp);
+        }
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                if let Some(endpoint) = endpoint {
+                    endpoint.succeeded();
+                }
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = wire_log::tee(resp.bytes_stream()).map_err(CodexErr::Reqwest);
+                tokio::spawn(process_bedrock_stream(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                let switched = status.is_server_error()
+                    && endpoint.is_some_and(|endpoint| {
+                        endpoint.failed(&format!("the endpoint returned {status}"))
+                    });
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+                if switched {
+                    continue;
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                let switched = endpoint.is_some_and(|endpoint| {
+                    endpoint.failed(&format!("could not reach the endpoint: {e}"))
+                });
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                if switched {
+                    continue;
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// A request carrying `body`, authenticated with the provider's API key if
+/// it has one and signed with AWS credentials otherwise. Signing is redone
+/// for every attempt, as signatures expire and credentials may rotate.
+async fn create_request_builder(
+    provider: &ModelProviderInfo,
+    client: &reqwest::Client,
+    provider_auth: &Option<CodexAuth>,
+    model: &str,
+    body: &[u8],
+) -> Result<reqwest::RequestBuilder> {
+    const CONTENT_TYPE: &str = "application/json";
+
+    if provider.env_key.is_some() || provider_auth.is_some() {
+        return Ok(provider
+            .create_request_builder(client, provider_auth, model)
+            .await?
+            .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(body.to_vec()));
+    }
+
+    let url = reqwest::Url::parse(&provider.get_full_url(&None, model))
+        .map_err(|e| CodexErr::Io(std::io::Error::other(format!("invalid Bedrock URL: {e}"))))?;
+    let credentials = load_credentials(client, provider.aws.as_ref()).await?;
+    let region = resolve_region(provider.aws.as_ref());
+    let signed = sign_request(
+        &credentials,
+        &region,
+        "POST",
+        &url,
+        &[("content-type", CONTENT_TYPE)],
+        body,
+        Utc::now(),
+    );
+
+    let mut builder = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE)
+        .body(body.to_vec());
+    for (name, value) in signed {
+        builder = builder.header(name, value);
+    }
+    Ok(provider.apply_http_headers(builder))
+}
+
+fn build_payload(prompt: &Prompt, config: &Config) -> Result<Value> {
+    let mut system = vec![
+        json!({"text": prompt.get_full_instructions_with_output_schema(&config.model_family)}),
+    ];
+    let mut messages = build_messages(&prompt.get_formatted_input());
+    if supports_cache_points(&config.model_family.slug) {
+        // The point after the system prompt also covers the tools, which
+        // come before it; the one after the last message caches the
+        // conversation so far.
+        system.push(cache_point());
+        if let Some(content) = messages
+            .last_mut()
+            .and_then(|message| message["content"].as_array_mut())
+        {
+            content.push(cache_point());
+        }
+    }
+    let mut payload = json!({
+        "system": system,
+        "messages": messages,
+    });
+
+    let tools_json = create_tools_json_for_bedrock_api(&prompt.tools)?;
+    if !tools_json.is_empty() {
+        payload["toolConfig"] = json!({"tools": tools_json});
+    }
+    let mut inference_config = serde_json::Map::new();
+    if let Some(max_tokens) = config.model_max_output_tokens {
+        inference_config.insert("maxTokens".to_string(), json!(max_tokens));
+    }
+    if let Some(temperature) = config.model_temperature {
+        inference_config.insert("temperature".to_string(), json!(temperature));
+    }
+    if !inference_config.is_empty() {
+        payload["inferenceConfig"] = Value::Object(inference_config);
+    }
+
+    Ok(payload)
+}
+
+fn cache_point() -> Value {
+    json!({"cachePoint": {"type": "default"}})
+}
+
+/// Whether the Bedrock model accepts `cachePoint` blocks; others reject
+/// requests that contain them.
+fn supports_cache_points(model: &str) -> bool {
+    [
+        "anthropic.claude-3-5-haiku",
+        "anthropic.claude-3-7-sonnet",
+        "anthropic.claude-sonnet-4",
+        "anthropic.claude-opus-4",
+        "amazon.nova-",
+    ]
+    .iter()
+    .any(|prefix| model.contains(prefix))
+}
+
+/// Maps the conversation onto Converse messages. Consecutive items of the
+/// same role are merged into one message, since the API expects user and
+/// assistant messages to alternate.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<(&'static str, Vec<Value>)> = Vec::new();
+    let mut push = |role: &'static str, block: Value| match messages.last_mut() {
+        Some((last_role, blocks)) if *last_role == role => blocks.push(block),
+        _ => messages.push((role, vec![block])),
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for content_item in content {
+                    match content_item {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push(role, json!({"text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push(role, image_block(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push(
+                    "assistant",
+                    json!({"toolUse": {"toolUseId": call_id, "name": name, "input": input}}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let Some(call_id) = call_id.as_ref().or(id.as_ref()) else {
+                    continue;
+                };
+                push(
+                    "assistant",
+                    json!({"toolUse": {"toolUseId": call_id, "name": "local_shell", "input": action}}),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                push(
+                    "assistant",
+                    json!({"toolUse": {"toolUseId": call_id, "name": name, "input": {"input": input}}}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let status = if output.success == Some(false) {
+                    "error"
+                } else {
+                    "success"
+                };
+                push("user", tool_result(call_id, &output.content, status));
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push("user", tool_result(call_id, output, "success"));
+            }
+            // Reasoning is not sent back to Bedrock.
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+
+    messages
+        .into_iter()
+        .map(|(role, content)| json!({"role": role, "content": content}))
+        .collect()
+}
+
+fn tool_result(call_id: &str, output: &str, status: &str) -> Value {
+    // Blank text blocks are rejected.
+    let text = if output.trim().is_empty() {
+        "(no output)"
+    } else {
+        output
+    };
+    json!({"toolResult": {
+        "toolUseId": call_id,
+        "content": [{"text": text}],
+        "status": status,
+    }})
+}
+
+/// Inline bytes for data URLs. Converse cannot fetch images, so other URLs
+/// are given to the model as text.
+fn image_block(image_url: &str) -> Value {
+    if let Some(data_url) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = data_url.split_once(";base64,")
+        && let Some(format) = media_type.strip_prefix("image/")
+    {
+        return json!({"image": {"format": format, "source": {"bytes": data}}});
+    }
+    json!({"text": format!("[image: {image_url}]")})
+}
+
+/// A message of an AWS event stream: a prelude with the total and header
+/// lengths and its CRC, the headers, the payload and a CRC of the message.
+/// The CRCs are not checked; the transport already guards against
+/// corruption.
+#[derive(Debug, PartialEq)]
+struct EventStreamMessage {
+    /// Headers with string values, such as `:event-type`.
+    headers: HashMap<String, String>,
+    payload: Vec<u8>,
+}
+
+#[derive(Default)]
+struct EventStreamDecoder {
+    buf: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// The next complete message, if one has been received.
+    fn next_message(&mut self) -> std::result::Result<Option<EventStreamMessage>, String> {
+        const PRELUDE_LEN: usize = 12;
+        const CRC_LEN: usize = 4;
+        if self.buf.len() < PRELUDE_LEN {
+            return Ok(None);
+        }
+        let read_u32 = |at: usize| {
+            u32::from_be_bytes([
+                self.buf[at],
+                self.buf[at + 1],
+                self.buf[at + 2],
+                self.buf[at + 3],
+            ]) as usize
+        };
+        let total_len = read_u32(0);
+        let headers_len = read_u32(4);
+        if total_len < PRELUDE_LEN + CRC_LEN || headers_len > total_len - PRELUDE_LEN - CRC_LEN {
+            return Err(format!(
+                "malformed event stream message: length {total_len}, headers {headers_len}"
+            ));
+        }
+        if self.buf.len() < total_len {
+            return Ok(None);
+        }
+
+        let message: Vec<u8> = self.buf.drain(..total_len).collect();
+        let headers_end = PRELUDE_LEN + headers_len;
+        let headers = parse_headers(&message[PRELUDE_LEN..headers_end])
+            .ok_or_else(|| "malformed event stream headers".to_string())?;
+        Ok(Some(EventStreamMessage {
+            headers,
+            payload: message[headers_end..total_len - CRC_LEN].to_vec(),
+        }))
+    }
+}
+
+fn parse_headers(mut bytes: &[u8]) -> Option<HashMap<String, String>> {
+    fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        if bytes.len() < n {
+            return None;
+        }
+        let (head, tail) = bytes.split_at(n);
+        *bytes = tail;
+        Some(head)
+    }
+
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = *take(&mut bytes, 1)?.first()? as usize;
+        let name = String::from_utf8_lossy(take(&mut bytes, name_len)?).into_owned();
+        let value_type = *take(&mut bytes, 1)?.first()?;
+        let value_len = match value_type {
+            // bool true, bool false
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            // long, timestamp
+            5 | 8 => 8,
+            // byte array, string
+            6 | 7 => {
+                let len = take(&mut bytes, 2)?;
+                u16::from_be_bytes([len[0], len[1]]) as usize
+            }
+            // uuid
+            9 => 16,
+            _ => return None,
+        };
+        let value = take(&mut bytes, value_len)?;
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).into_owned());
+        }
+    }
+    Some(headers)
+}
+
+/// A content block being streamed, by its index in the message.
+enum StreamedBlock {
+    Text(String),
+    Reasoning(String),
+    ToolUse {
+        id: String,
+        name: String,
+        /// Streamed as partial JSON; empty when the input is `{}`.
+        input_json: String,
+    },
+}
+
+impl StreamedBlock {
+    fn into_item(self) -> Option<ResponseItem> {
+        match self {
+            Self::Text(text) if text.is_empty() => None,
+            Self::Text(text) => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            Self::Reasoning(text) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText { text }],
+                content: None,
+                encrypted_content: None,
+            }),
+            Self::ToolUse {
+                id,
+                name,
+                input_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if input_json.trim().is_empty() {
+                    "{}".to_string()
+                } else {
+                    input_json
+                },
+                call_id: id,
+            }),
+        }
+    }
+}
+
+async fn process_bedrock_stream<S>(
+    mut stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut decoder = EventStreamDecoder::default();
+    let mut blocks: HashMap<u64, StreamedBlock> = HashMap::new();
+    let mut stopped = false;
+    let mut usage: Option<TokenUsage> = None;
+
+    loop {
+        let bytes = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(bytes))) => bytes,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for the event stream".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+        decoder.push(&bytes);
+
+        loop {
+            let message = match decoder.next_message() {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = tx_event.send(Err(CodexErr::Stream(e, None))).await;
+                    return;
+                }
+            };
+            let event: Value = serde_json::from_slice(&message.payload).unwrap_or_default();
+            trace!("bedrock received event: {:?} {event:?}", message.headers);
+
+            let header = |name: &str| message.headers.get(name).map(String::as_str);
+            if header(":message-type") == Some("exception") {
+                let kind = header(":exception-type").unwrap_or("exception");
+                let detail = event["message"].as_str().unwrap_or_default();
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(format!("{kind}: {detail}"), None)))
+                    .await;
+                return;
+            }
+
+            match header(":event-type").unwrap_or_default() {
+                "messageStart" => {
+                    let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+                }
+                "contentBlockStart" => {
+                    let tool_use = &event["start"]["toolUse"];
+                    if let Some(index) = event["contentBlockIndex"].as_u64()
+                        && tool_use.is_object()
+                    {
+                        blocks.insert(
+                            index,
+                            StreamedBlock::ToolUse {
+                                id: tool_use["toolUseId"].as_str().unwrap_or_default().into(),
+                                name: tool_use["name"].as_str().unwrap_or_default().into(),
+                                input_json: String::new(),
+                            },
+                        );
+                    }
+                }
+                "contentBlockDelta" => {
+                    let Some(index) = event["contentBlockIndex"].as_u64() else {
+                        continue;
+                    };
+                    let delta = &event["delta"];
+                    let forward = if let Some(text) = delta["text"].as_str() {
+                        if let StreamedBlock::Text(buf) = blocks
+                            .entry(index)
+                            .or_insert_with(|| StreamedBlock::Text(String::new()))
+                        {
+                            buf.push_str(text);
+                        }
+                        Some(ResponseEvent::OutputTextDelta(text.to_string()))
+                    } else if let Some(text) = delta["reasoningContent"]["text"].as_str() {
+                        if let StreamedBlock::Reasoning(buf) = blocks
+                            .entry(index)
+                            .or_insert_with(|| StreamedBlock::Reasoning(String::new()))
+                        {
+                            buf.push_str(text);
+                        }
+                        Some(ResponseEvent::ReasoningSummaryDelta(text.to_string()))
+                    } else if let Some(input) = delta["toolUse"]["input"].as_str() {
+                        if let Some(StreamedBlock::ToolUse { input_json, .. }) =
+                            blocks.get_mut(&index)
+                        {
+                            input_json.push_str(input);
+                        }
+                        None
+                    } else {
+                        None
+                    };
+                    if let Some(forward) = forward
+                        && tx_event.send(Ok(forward)).await.is_err()
+                    {
+                        return;
+                    }
+                }
+                "contentBlockStop" => {
+                    if let Some(item) = event["contentBlockIndex"]
+                        .as_u64()
+                        .and_then(|i| blocks.remove(&i))
+                        .and_then(StreamedBlock::into_item)
+                    {
+                        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                    }
+                }
+                "messageStop" => {
+                    stopped = true;
+                    if event["stopReason"].as_str() == Some("max_tokens") {
+                        warn!(
+                            "Bedrock response stopped at max_tokens; raise model_max_output_tokens"
+                        );
+                    }
+                }
+                "metadata" => usage = token_usage(&event["usage"]),
+                _ => {}
+            }
+        }
+    }
+
+    let _ = tx_event
+        .send(if stopped {
+            Ok(ResponseEvent::Completed {
+                response_id: String::new(),
+                token_usage: usage,
+            })
+        } else {
+            Err(CodexErr::Stream(
+                "stream closed before messageStop".into(),
+                None,
+            ))
+        })
+        .await;
+}
+
+/// `inputTokens` excludes tokens read from or written to the prompt cache,
+/// whereas `TokenUsage` includes them.
+fn token_usage(usage: &Value) -> Option<TokenUsage> {
+    let count = |key: &str| usage[key].as_u64().unwrap_or_default();
+    let output_tokens = usage["outputTokens"].as_u64()?;
+    let input_tokens =
+        count("inputTokens") + count("cacheReadInputTokens") + count("cacheWriteInputTokens");
+    Some(TokenUsage {
+        input_tokens,
+        cached_input_tokens: count("cacheReadInputTokens"),
+        output_tokens,
+        reasoning_output_tokens: 0,
+        total_tokens: input_tokens + output_tokens,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    /// Encodes an event the way Bedrock frames it, with zeroed CRCs.
+    fn frame(event_type: &str, payload: Value) -> Vec<u8> {
+        let mut headers = Vec::new();
+        for (name, value) in [(":message-type", "event"), (":event-type", event_type)] {
+            headers.push(name.len() as u8);
+            headers.extend_from_slice(name.as_bytes());
+            headers.push(7);
+            headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            headers.extend_from_slice(value.as_bytes());
+        }
+        let payload = payload.to_string().into_bytes();
+        let total_len = 12 + headers.len() + payload.len() + 4;
+        let mut message = Vec::new();
+        message.extend_from_slice(&(total_len as u32).to_be_bytes());
+        message.extend_from_slice(&(headers.len() as u32).to_be_bytes());
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&headers);
+        message.extend_from_slice(&payload);
+        message.extend_from_slice(&[0; 4]);
+        message
+    }
+
+    #[test]
+    fn maps_tool_calls_to_tool_use_and_results() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "list files".to_string(),
+                }],
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "tooluse_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "tooluse_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: String::new(),
+                    success: Some(false),
+                },
+            },
+        ];
+
+        assert_eq!(
+            build_messages(&input),
+            vec![
+                json!({"role": "user", "content": [{"text": "list files"}]}),
+                json!({"role": "assistant", "content": [
+                    {"toolUse": {"toolUseId": "tooluse_1", "name": "shell", "input": {"command": ["ls"]}}},
+                ]}),
+                json!({"role": "user", "content": [
+                    {"toolResult": {"toolUseId": "tooluse_1", "content": [{"text": "(no output)"}], "status": "error"}},
+                ]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn cache_points_only_for_models_that_support_them() {
+        assert!(supports_cache_points(
+            "us.anthropic.claude-sonnet-4-20250514-v1:0"
+        ));
+        assert!(supports_cache_points("amazon.nova-pro-v1:0"));
+        assert!(!supports_cache_points(
+            "anthropic.claude-3-sonnet-20240229-v1:0"
+        ));
+        assert!(!supports_cache_points("meta.llama3-70b-instruct-v1:0"));
+    }
+
+    #[test]
+    fn decodes_messages_split_across_chunks() {
+        let bytes = frame("messageStop", json!({"stopReason": "end_turn"}));
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes[..10]);
+        assert_eq!(decoder.next_message(), Ok(None));
+        decoder.push(&bytes[10..]);
+        assert_eq!(
+            decoder.next_message(),
+            Ok(Some(EventStreamMessage {
+                headers: HashMap::from([
+                    (":message-type".to_string(), "event".to_string()),
+                    (":event-type".to_string(), "messageStop".to_string()),
+                ]),
+                payload: br#"{"stopReason":"end_turn"}"#.to_vec(),
+            }))
+        );
+        assert_eq!(decoder.next_message(), Ok(None));
+    }
+
+    #[tokio::test]
+    async fn maps_streamed_events() {
+        let body: Vec<u8> = [
+            frame("messageStart", json!({"role": "assistant"})),
+            frame("contentBlockDelta", json!({"contentBlockIndex": 0, "delta": {"text": "Running ls."}})),
+            frame("contentBlockStop", json!({"contentBlockIndex": 0})),
+            frame("contentBlockStart", json!({"contentBlockIndex": 1, "start": {"toolUse": {"toolUseId": "tooluse_1", "name": "shell"}}})),
+            frame("contentBlockDelta", json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "{\"command\":[\"ls\"]}"}}})),
+            frame("contentBlockStop", json!({"contentBlockIndex": 1})),
+            frame("messageStop", json!({"stopReason": "tool_use"})),
+            frame("metadata", json!({"usage": {"inputTokens": 20, "cacheReadInputTokens": 80, "outputTokens": 30, "totalTokens": 130}})),
+        ]
+        .concat();
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        let (tx, mut rx) = mpsc::channel(16);
+        process_bedrock_stream(stream, tx, Duration::from_secs(5)).await;
+
+        let mut received = Vec::new();
+        while let Some(event) = rx.recv().await {
+            received.push(format!("{:?}", event.expect("event")));
+        }
+        let expected = [
+            ResponseEvent::Created,
+            ResponseEvent::OutputTextDelta("Running ls.".to_string()),
+            ResponseEvent::OutputItemDone(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Running ls.".to_string(),
+                }],
+            }),
+            ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "tooluse_1".to_string(),
+            }),
+            ResponseEvent::Completed {
+                response_id: String::new(),
+                token_usage: Some(TokenUsage {
+                    input_tokens: 100,
+                    cached_input_tokens: 80,
+                    output_tokens: 30,
+                    reasoning_output_tokens: 0,
+                    total_tokens: 130,
+                }),
+            },
+        ]
+        .map(|event| format!("{event:?}"));
+        assert_eq!(received, expected);
+    }
+}
@@ -0,0 +1,460 @@
+//! Journal of the files each turn changed through `apply_patch` (and
+//! `write_file`, which is applied as a patch), so that the last turn can be
+//! rolled back without git ([`Op::RevertTurn`]), as can all the turns since
+//! a checkpoint ([`Op::RestoreCheckpoint`]).
+//!
+//! For every patch the journal keeps the content of each file before and
+//! after it was applied. A revert restores the "before" content, unless a
+//! file no longer has the "after" content, i.e. it changed since; such
+//! conflicts are reported and nothing is reverted unless the revert is
+//! forced.
+//!
+//! Each patch's changes are also persisted in the rollout, so that a resumed
+//! session rebuilds the journal with [`ChangeJournal::replay_changes`] and
+//! friends, which touch no files.
+//!
+//! [`Op::RevertTurn`]: crate::protocol::Op::RevertTurn
+//! [`Op::RestoreCheckpoint`]: crate::protocol::Op::RestoreCheckpoint
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tokio::sync::Mutex;
+
+use crate::protocol::FileChange;
+use crate::protocol::FileChangesItem;
+use crate::protocol::FileVersionsItem;
+use crate::protocol::TurnRevertedEvent;
+
+/// Turns kept in the journal; older ones can no longer be reverted.
+const MAX_JOURNALED_TURNS: usize = 20;
+
+#[derive(Default)]
+pub(crate) struct ChangeJournal {
+    state: Mutex<JournalState>,
+}
+
+#[derive(Default)]
+struct JournalState {
+    turns: Vec<TurnChanges>,
+    /// Sequence number of the next journaled turn.
+    next_seq: u64,
+    /// Turns numbered below this were dropped from the journal.
+    dropped_before: u64,
+}
+
+struct TurnChanges {
+    seq: u64,
+    turn_id: String,
+    /// Set by [`ChangeJournal::mark`]: later changes of the same turn are
+    /// journaled separately.
+    sealed: bool,
+    /// The files as the turn found and left them.
+    files: Vec<FileVersionsItem>,
+}
+
+/// A point in the journal that [`ChangeJournal::revert_to`] rolls back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct JournalMark(u64);
+
+/// The files a patch is about to change, as they are before it is applied.
+pub(crate) struct PatchSnapshot {
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl PatchSnapshot {
+    /// Reads the files `changes`, a patch to be applied in `cwd`, touches.
+    pub(crate) async fn capture(cwd: &Path, changes: &HashMap<PathBuf, FileChange>) -> Self {
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for (path, change) in changes {
+            paths.push(cwd.join(path));
+            if let FileChange::Update {
+                move_path: Some(dest),
+                ..
+            } = change
+            {
+                paths.push(cwd.join(dest));
+            }
+        }
+        paths.sort();
+        paths.dedup();
+
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            let content = read_if_exists(&path).await;
+            files.push((path, content));
+        }
+        Self { files }
+    }
+
+    /// The files the patch touches.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter().map(|(path, _)| path)
+    }
+}
+
+impl ChangeJournal {
+    /// Records the changes of a patch that `turn_id` applied, given the
+    /// files as they were before it. Returns the changes, to be persisted in
+    /// the rollout.
+    pub(crate) async fn record(&self, turn_id: &str, snapshot: PatchSnapshot) -> FileChangesItem {
+        let mut files = Vec::with_capacity(snapshot.files.len());
+        for (path, before) in snapshot.files {
+            let after = read_if_exists(&path).await;
+            files.push(FileVersionsItem {
+                path,
+                before,
+                after,
+            });
+        }
+        let changes = FileChangesItem {
+            turn_id: turn_id.to_string(),
+            files,
+        };
+        self.state.lock().await.add(&changes);
+        changes
+    }
+
+    /// Marks the current state of the files so that the changes recorded
+    /// from now on can be rolled back with [`ChangeJournal::revert_to`].
+    pub(crate) async fn mark(&self) -> JournalMark {
+        self.state.lock().await.mark()
+    }
+
+    /// Restores the files changed by the last turn that changed any. When
+    /// some of them changed since and `force` is not set, nothing is
+    /// restored and the conflicts are reported instead.
+    pub(crate) async fn revert_last_turn(&self, force: bool) -> io::Result<TurnRevertedEvent> {
+        let mut state = self.state.lock().await;
+        let Some(turn) = state.turns.pop() else {
+            return Ok(TurnRevertedEvent {
+                turn_id: None,
+                reverted: Vec::new(),
+                conflicts: Vec::new(),
+            });
+        };
+
+        let conflicts = find_conflicts(std::slice::from_ref(&turn)).await;
+        if !conflicts.is_empty() && !force {
+            let turn_id = turn.turn_id.clone();
+            state.turns.push(turn);
+            return Ok(TurnRevertedEvent {
+                turn_id: Some(turn_id),
+                reverted: Vec::new(),
+                conflicts,
+            });
+        }
+
+        let reverted = restore_before(std::slice::from_ref(&turn)).await?;
+        Ok(TurnRevertedEvent {
+            turn_id: Some(turn.turn_id),
+            reverted,
+            conflicts,
+        })
+    }
+
+    /// Restores the files changed since `mark` to how they were then.
+    /// Returns the restored files and the conflicts, files that changed
+    /// outside the journal since; when there are any and `force` is not
+    /// set, nothing is restored.
+    pub(crate) async fn revert_to(
+        &self,
+        mark: JournalMark,
+        force: bool,
+    ) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let mut state = self.state.lock().await;
+        if state.dropped_before > mark.0 {
+            return Err(io::Error::other(format!(
+                "only the changes of the last {MAX_JOURNALED_TURNS} turns can be rolled back"
+            )));
+        }
+        let first = state.turns.partition_point(|turn| turn.seq < mark.0);
+        let conflicts = find_conflicts(&state.turns[first..]).await;
+        if !conflicts.is_empty() && !force {
+            return Ok((Vec::new(), conflicts));
+        }
+        let turns: Vec<TurnChanges> = state.turns.drain(first..).collect();
+        let reverted = restore_before(&turns).await?;
+        Ok((reverted, conflicts))
+    }
+
+    /// Replaces the journal with one rebuilt from a rollout.
+    pub(crate) async fn restore(&self, replayed: ChangeJournal) {
+        *self.state.lock().await = replayed.state.into_inner();
+    }
+
+    /// Replays changes recorded with [`ChangeJournal::record`].
+    pub(crate) fn replay_changes(&mut self, changes: &FileChangesItem) {
+        self.state.get_mut().add(changes);
+    }
+
+    /// Replays a [`ChangeJournal::mark`].
+    pub(crate) fn replay_mark(&mut self) -> JournalMark {
+        self.state.get_mut().mark()
+    }
+
+    /// Replays a [`ChangeJournal::revert_last_turn`] that had `outcome`.
+    pub(crate) fn replay_revert_last_turn(&mut self, outcome: &TurnRevertedEvent) {
+        let state = self.state.get_mut();
+        let reverted = outcome.conflicts.is_empty() || !outcome.reverted.is_empty();
+        if reverted
+            && outcome.turn_id.is_some()
+            && state.turns.last().map(|turn| &turn.turn_id) == outcome.turn_id.as_ref()
+        {
+            state.turns.pop();
+        }
+    }
+
+    /// Replays a successful [`ChangeJournal::revert_to`].
+    pub(crate) fn replay_revert_to(&mut self, mark: JournalMark) {
+        let state = self.state.get_mut();
+        let first = state.turns.partition_point(|turn| turn.seq < mark.0);
+        state.turns.truncate(first);
+    }
+}
+
+impl JournalState {
+    fn add(&mut self, changes: &FileChangesItem) {
+        if self
+            .turns
+            .last()
+            .is_none_or(|turn| turn.turn_id != changes.turn_id || turn.sealed)
+        {
+            let seq = self.next_seq;
+            self.next_seq += 1;
+            self.turns.push(TurnChanges {
+                seq,
+                turn_id: changes.turn_id.clone(),
+                sealed: false,
+                files: Vec::new(),
+            });
+            if self.turns.len() > MAX_JOURNALED_TURNS {
+                let dropped = self.turns.remove(0);
+                self.dropped_before = dropped.seq + 1;
+            }
+        }
+        let Some(turn) = self.turns.last_mut() else {
+            return;
+        };
+        for file in &changes.files {
+            // A file patched twice in a turn is reverted to how the turn
+            // found it.
+            match turn.files.iter_mut().find(|known| known.path == file.path) {
+                Some(known) => known.after = file.after.clone(),
+                None => turn.files.push(file.clone()),
+            }
+        }
+    }
+
+    fn mark(&mut self) -> JournalMark {
+        if let Some(turn) = self.turns.last_mut() {
+            turn.sealed = true;
+        }
+        JournalMark(self.next_seq)
+    }
+}
+
+/// The files of `turns`, oldest first, whose content is no longer what the
+/// last of those turns to change them left.
+async fn find_conflicts(turns: &[TurnChanges]) -> Vec<PathBuf> {
+    let mut checked: Vec<&Path> = Vec::new();
+    let mut conflicts = Vec::new();
+    for file in turns.iter().rev().flat_map(|turn| turn.files.iter()) {
+        if checked.contains(&file.path.as_path()) {
+            continue;
+        }
+        checked.push(&file.path);
+        if read_if_exists(&file.path).await != file.after {
+            conflicts.push(file.path.clone());
+        }
+    }
+    conflicts.sort();
+    conflicts
+}
+
+/// Restores the files of `turns`, oldest first, to how the first of those
+/// turns to change them found them. Returns the restored files.
+async fn restore_before(turns: &[TurnChanges]) -> io::Result<Vec<PathBuf>> {
+    let mut reverted: Vec<PathBuf> = Vec::new();
+    for file in turns.iter().rev().flat_map(|turn| turn.files.iter().rev()) {
+        match &file.before {
+            Some(content) => {
+                if let Some(parent) = file.path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&file.path, content).await?;
+            }
+            None => match tokio::fs::remove_file(&file.path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            },
+        }
+        if !reverted.contains(&file.path) {
+            reverted.push(file.path.clone());
+        }
+    }
+    reverted.reverse();
+    Ok(reverted)
+}
+
+async fn read_if_exists(path: &Path) -> Option<Vec<u8>> {
+    tokio::fs::read(path).await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn update(path: &Path) -> HashMap<PathBuf, FileChange> {
+        HashMap::from([(
+            path.to_path_buf(),
+            FileChange::Update {
+                unified_diff: String::new(),
+                move_path: None,
+            },
+        )])
+    }
+
+    fn add(path: &Path) -> HashMap<PathBuf, FileChange> {
+        HashMap::from([(
+            path.to_path_buf(),
+            FileChange::Add {
+                content: String::new(),
+            },
+        )])
+    }
+
+    /// Applies a "patch" by writing `content` to `path`, journaling it.
+    async fn patch(
+        journal: &ChangeJournal,
+        turn_id: &str,
+        changes: HashMap<PathBuf, FileChange>,
+        path: &Path,
+        content: &str,
+    ) -> FileChangesItem {
+        let snapshot = PatchSnapshot::capture(Path::new("/"), &changes).await;
+        std::fs::write(path, content).expect("write");
+        journal.record(turn_id, snapshot).await
+    }
+
+    #[tokio::test]
+    async fn reverts_the_last_turn_only() {
+        let dir = TempDir::new().expect("tempdir");
+        let lib = dir.path().join("lib.rs");
+        let new = dir.path().join("new.rs");
+        std::fs::write(&lib, "v0").expect("write");
+        let journal = ChangeJournal::default();
+
+        patch(&journal, "1", update(&lib), &lib, "v1").await;
+        patch(&journal, "2", update(&lib), &lib, "v2").await;
+        patch(&journal, "2", update(&lib), &lib, "v3").await;
+        patch(&journal, "2", add(&new), &new, "new").await;
+
+        let event = journal.revert_last_turn(false).await.expect("revert");
+        assert_eq!(
+            event,
+            TurnRevertedEvent {
+                turn_id: Some("2".to_string()),
+                reverted: vec![lib.clone(), new.clone()],
+                conflicts: Vec::new(),
+            }
+        );
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v1");
+        assert!(!new.exists());
+
+        let event = journal.revert_last_turn(false).await.expect("revert");
+        assert_eq!(event.turn_id.as_deref(), Some("1"));
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v0");
+
+        let event = journal.revert_last_turn(false).await.expect("revert");
+        assert_eq!(event.turn_id, None);
+    }
+
+    #[tokio::test]
+    async fn files_changed_since_are_conflicts() {
+        let dir = TempDir::new().expect("tempdir");
+        let lib = dir.path().join("lib.rs");
+        std::fs::write(&lib, "v0").expect("write");
+        let journal = ChangeJournal::default();
+        patch(&journal, "1", update(&lib), &lib, "v1").await;
+        std::fs::write(&lib, "edited by hand").expect("write");
+
+        let event = journal.revert_last_turn(false).await.expect("revert");
+        assert_eq!(event.reverted, Vec::<PathBuf>::new());
+        assert_eq!(event.conflicts, vec![lib.clone()]);
+        assert_eq!(
+            std::fs::read_to_string(&lib).expect("read"),
+            "edited by hand"
+        );
+
+        let event = journal.revert_last_turn(true).await.expect("revert");
+        assert_eq!(event.reverted, vec![lib.clone()]);
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v0");
+    }
+
+    #[tokio::test]
+    async fn reverts_every_turn_since_a_mark() {
+        let dir = TempDir::new().expect("tempdir");
+        let lib = dir.path().join("lib.rs");
+        let new = dir.path().join("new.rs");
+        std::fs::write(&lib, "v0").expect("write");
+        let journal = ChangeJournal::default();
+
+        patch(&journal, "1", update(&lib), &lib, "v1").await;
+        let mark = journal.mark().await;
+        // The rest of turn 1 comes after the mark.
+        patch(&journal, "1", update(&lib), &lib, "v2").await;
+        patch(&journal, "2", add(&new), &new, "new").await;
+        patch(&journal, "3", update(&lib), &lib, "v3").await;
+
+        std::fs::write(&new, "edited by hand").expect("write");
+        let (reverted, conflicts) = journal.revert_to(mark, false).await.expect("revert");
+        assert_eq!(reverted, Vec::<PathBuf>::new());
+        assert_eq!(conflicts, vec![new.clone()]);
+
+        let (mut reverted, _) = journal.revert_to(mark, true).await.expect("revert");
+        reverted.sort();
+        assert_eq!(reverted, vec![lib.clone(), new.clone()]);
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v1");
+        assert!(!new.exists());
+
+        let event = journal.revert_last_turn(false).await.expect("revert");
+        assert_eq!(event.turn_id.as_deref(), Some("1"));
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v0");
+    }
+
+    #[tokio::test]
+    async fn replayed_journal_rolls_back_like_the_original() {
+        let dir = TempDir::new().expect("tempdir");
+        let lib = dir.path().join("lib.rs");
+        std::fs::write(&lib, "v0").expect("write");
+        let journal = ChangeJournal::default();
+        let first = patch(&journal, "1", update(&lib), &lib, "v1").await;
+        let second = patch(&journal, "2", update(&lib), &lib, "v2").await;
+        let third = patch(&journal, "3", update(&lib), &lib, "v3").await;
+
+        let mut replayed = ChangeJournal::default();
+        replayed.replay_changes(&first);
+        let mark = replayed.replay_mark();
+        replayed.replay_changes(&second);
+        replayed.replay_changes(&third);
+        replayed.replay_revert_last_turn(&TurnRevertedEvent {
+            turn_id: Some("3".to_string()),
+            reverted: vec![lib.clone()],
+            conflicts: Vec::new(),
+        });
+        // Replaying touches no files; the original revert left "v2".
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v3");
+        std::fs::write(&lib, "v2").expect("write");
+
+        let (reverted, conflicts) = replayed.revert_to(mark, false).await.expect("revert");
+        assert_eq!(reverted, vec![lib.clone()]);
+        assert_eq!(conflicts, Vec::<PathBuf>::new());
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v1");
+    }
+}
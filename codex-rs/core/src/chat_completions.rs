@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::Bytes;
@@ -16,6 +18,8 @@ use tracing::debug;
 use tracing::trace;
 
 use crate::ModelProviderInfo;
+use crate::auth::CodexAuth;
+use crate::client_common::OUTPUT_SCHEMA_NAME;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::client_common::ResponseStream;
@@ -23,7 +27,10 @@ use crate::error::CodexErr;
 use crate::error::Result;
 use crate::model_family::ModelFamily;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::provider_failover::EndpointFailover;
 use crate::util::backoff;
+use crate::wire_log;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ReasoningItemContent;
 use codex_protocol::models::ResponseItem;
@@ -32,15 +39,12 @@ use codex_protocol::models::ResponseItem;
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
     model_family: &ModelFamily,
+    temperature: Option<f32>,
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
+    failover: Option<&Arc<EndpointFailover>>,
+    provider_auth: &Option<CodexAuth>,
 ) -> Result<ResponseStream> {
-    if prompt.output_schema.is_some() {
-        return Err(CodexErr::UnsupportedOperation(
-            "output_schema is not supported for Chat Completions API".to_string(),
-        ));
-    }
-
     // Build messages array
     let mut messages = Vec::<serde_json::Value>::new();
 
@@ -274,16 +278,31 @@ pub(crate) async fn stream_chat_completions(
     }
 
     let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
-    let payload = json!({
+    let mut payload = json!({
         "model": model_family.slug,
         "messages": messages,
         "stream": true,
+        // Usage, including cached prompt tokens, arrives in a last chunk.
+        "stream_options": {"include_usage": true},
         "tools": tools_json,
     });
+    if let Some(temperature) = temperature {
+        payload["temperature"] = json!(temperature);
+    }
+    if let Some(schema) = &prompt.output_schema {
+        payload["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": OUTPUT_SCHEMA_NAME,
+                "strict": true,
+                "schema": schema,
+            },
+        });
+    }
 
     debug!(
         "POST to {}: {}",
-        provider.get_full_url(&None),
+        provider.get_full_url(provider_auth, &model_family.slug),
         serde_json::to_string_pretty(&payload).unwrap_or_default()
     );
 
@@ -292,18 +311,33 @@ pub(crate) async fn stream_chat_completions(
     loop {
         attempt += 1;
 
-        let req_builder = provider.create_request_builder(client, &None).await?;
+        let (provider, endpoint) = match failover {
+            Some(failover) => {
+                let (provider, endpoint) = failover.select(provider, client).await;
+                (provider, Some(endpoint))
+            }
+            None => (Cow::Borrowed(provider), None),
+        };
+        let req_builder = provider
+            .create_request_builder(client, provider_auth, &model_family.slug)
+            .await?;
 
-        let res = req_builder
+        let req_builder = req_builder
             .header(reqwest::header::ACCEPT, "text/event-stream")
-            .json(&payload)
-            .send()
-            .await;
+            .json(&payload);
+        wire_log::log_request(&req_builder);
+        let res = req_builder.send().await;
+        if let Ok(resp) = &res {
+            wire_log::log_response(resp);
+        }
 
         match res {
             Ok(resp) if resp.status().is_success() => {
+                if let Some(endpoint) = endpoint {
+                    endpoint.succeeded();
+                }
                 let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
-                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                let stream = wire_log::tee(resp.bytes_stream()).map_err(CodexErr::Reqwest);
                 tokio::spawn(process_chat_sse(
                     stream,
                     tx_event,
@@ -318,9 +352,16 @@ pub(crate) async fn stream_chat_completions(
                     return Err(CodexErr::UnexpectedStatus(status, body));
                 }
 
+                let switched = status.is_server_error()
+                    && endpoint.is_some_and(|endpoint| {
+                        endpoint.failed(&format!("the endpoint returned {status}"))
+                    });
                 if attempt > max_retries {
                     return Err(CodexErr::RetryLimit(status));
                 }
+                if switched {
+                    continue;
+                }
 
                 let retry_after_secs = res
                     .headers()
@@ -334,9 +375,15 @@ pub(crate) async fn stream_chat_completions(
                 tokio::time::sleep(delay).await;
             }
             Err(e) => {
+                let switched = endpoint.is_some_and(|endpoint| {
+                    endpoint.failed(&format!("could not reach the endpoint: {e}"))
+                });
                 if attempt > max_retries {
                     return Err(e.into());
                 }
+                if switched {
+                    continue;
+                }
                 let delay = backoff(attempt);
                 tokio::time::sleep(delay).await;
             }
@@ -372,6 +419,9 @@ async fn process_chat_sse<S>(
     let mut fn_call_state = FunctionCallState::default();
     let mut assistant_text = String::new();
     let mut reasoning_text = String::new();
+    // Set once a choice has finished; only the usage chunk follows.
+    let mut finished = false;
+    let mut token_usage = None;
 
     loop {
         let sse = match timeout(idle_timeout, stream.next()).await {
@@ -387,7 +437,18 @@ async fn process_chat_sse<S>(
                 let _ = tx_event
                     .send(Ok(ResponseEvent::Completed {
                         response_id: String::new(),
-                        token_usage: None,
+                        token_usage,
+                    }))
+                    .await;
+                return;
+            }
+            Err(_) if finished => {
+                // The answer is complete; do not fail the turn over a
+                // missing usage chunk.
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id: String::new(),
+                        token_usage,
                     }))
                     .await;
                 return;
@@ -407,7 +468,7 @@ async fn process_chat_sse<S>(
         if sse.data.trim() == "[DONE]" {
             // Emit any finalized items before closing so downstream consumers receive
             // terminal events for both assistant content and raw reasoning.
-            if !assistant_text.is_empty() {
+            if !finished && !assistant_text.is_empty() {
                 let item = ResponseItem::Message {
                     role: "assistant".to_string(),
                     content: vec![ContentItem::OutputText {
@@ -418,7 +479,7 @@ async fn process_chat_sse<S>(
                 let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
             }
 
-            if !reasoning_text.is_empty() {
+            if !finished && !reasoning_text.is_empty() {
                 let item = ResponseItem::Reasoning {
                     id: String::new(),
                     summary: Vec::new(),
@@ -433,7 +494,7 @@ async fn process_chat_sse<S>(
             let _ = tx_event
                 .send(Ok(ResponseEvent::Completed {
                     response_id: String::new(),
-                    token_usage: None,
+                    token_usage,
                 }))
                 .await;
             return;
@@ -446,6 +507,13 @@ async fn process_chat_sse<S>(
         };
         trace!("chat_completions received SSE chunk: {chunk:?}");
 
+        if let Some(usage) = chunk.get("usage").and_then(chat_token_usage) {
+            token_usage = Some(usage);
+        }
+        if finished {
+            continue;
+        }
+
         let choice_opt = chunk.get("choices").and_then(|c| c.get(0));
 
         if let Some(choice) = choice_opt {
@@ -606,23 +674,40 @@ async fn process_chat_sse<S>(
                     _ => {}
                 }
 
-                // Emit Completed regardless of reason so the agent can advance.
-                let _ = tx_event
-                    .send(Ok(ResponseEvent::Completed {
-                        response_id: String::new(),
-                        token_usage: None,
-                    }))
-                    .await;
-
-                // Prepare for potential next turn (should not happen in same stream).
-                // fn_call_state = FunctionCallState::default();
-
-                return; // End processing for this SSE stream.
+                // Completed is emitted once the usage chunk or `[DONE]`
+                // arrives, whatever the reason, so the agent can advance.
+                finished = true;
             }
         }
     }
 }
 
+/// Token usage from the `usage` object of a Chat Completions chunk.
+fn chat_token_usage(usage: &serde_json::Value) -> Option<TokenUsage> {
+    let input_tokens = usage.get("prompt_tokens")?.as_u64()?;
+    let output_tokens = usage
+        .get("completion_tokens")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or_default();
+    let detail = |details: &str, key: &str| {
+        usage
+            .get(details)
+            .and_then(|details| details.get(key))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or_default()
+    };
+    Some(TokenUsage {
+        input_tokens,
+        cached_input_tokens: detail("prompt_tokens_details", "cached_tokens"),
+        output_tokens,
+        reasoning_output_tokens: detail("completion_tokens_details", "reasoning_tokens"),
+        total_tokens: usage
+            .get("total_tokens")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(input_tokens + output_tokens),
+    })
+}
+
 /// Optional client-side aggregation helper
 ///
 /// Stream adapter that merges the incremental `OutputItemDone` chunks coming from
@@ -787,7 +872,9 @@ where
                         token_usage,
                     })));
                 }
-                Poll::Ready(Some(Ok(ResponseEvent::Created))) => {
+                Poll::Ready(Some(Ok(
+                    ResponseEvent::Created | ResponseEvent::BackgroundResponseCreated { .. },
+                ))) => {
                     // These events are exclusive to the Responses API and
                     // will never appear in a Chat Completions stream.
                     continue;
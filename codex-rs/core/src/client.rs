@@ -1,15 +1,21 @@
+use std::borrow::Cow;
 use std::io::BufRead;
 use std::path::Path;
 use std::sync::OnceLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use crate::AuthManager;
 use crate::auth::CodexAuth;
+use crate::auth::RefreshTokenError;
+use crate::auth::read_provider_api_key;
 use bytes::Bytes;
 use codex_protocol::mcp_protocol::AuthMode;
 use codex_protocol::mcp_protocol::ConversationId;
 use eventsource_stream::Eventsource;
 use futures::prelude::*;
+use futures::stream::BoxStream;
 use regex_lite::Regex;
 use reqwest::StatusCode;
 use reqwest::header::HeaderMap;
@@ -23,6 +29,8 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::anthropic::stream_anthropic_messages;
+use crate::bedrock::stream_bedrock_converse;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -32,12 +40,18 @@ use crate::client_common::ResponsesApiRequest;
 use crate::client_common::create_reasoning_param_for_request;
 use crate::client_common::create_text_param_for_request;
 use crate::config::Config;
+use crate::cost::TokenPrices;
+use crate::cost::prices_for;
 use crate::default_client::create_client;
+use crate::default_client::create_provider_client;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::gemini::stream_gemini;
 use crate::model_family::ModelFamily;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::WireApi;
 use crate::openai_model_info::get_model_info;
@@ -45,8 +59,14 @@ use crate::openai_tools::create_tools_json_for_responses_api;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::RateLimitWindow;
 use crate::protocol::TokenUsage;
+use crate::protocol::TurnOverrides;
+use crate::provider_failover::EndpointFailover;
+use crate::provider_failover::EndpointSwitch;
+use crate::provider_throttle::ProviderThrottle;
+use crate::provider_throttle::estimate_prompt_tokens;
 use crate::token_data::PlanType;
 use crate::util::backoff;
+use crate::wire_log;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use codex_protocol::models::ResponseItem;
@@ -75,9 +95,17 @@ pub struct ModelClient {
     auth_manager: Option<Arc<AuthManager>>,
     client: reqwest::Client,
     provider: ModelProviderInfo,
+    /// API key saved with `codex auth set-key` for the configured provider.
+    provider_auth: Option<CodexAuth>,
     conversation_id: ConversationId,
     effort: Option<ReasoningEffortConfig>,
     summary: ReasoningSummaryConfig,
+    /// Set when the provider has fallback base URLs.
+    failover: Option<Arc<EndpointFailover>>,
+    /// Sequence number of the first endpoint switch not yet reported.
+    reported_switches: Arc<AtomicU64>,
+    /// Set when the provider has client-side rate limits.
+    throttle: Option<Arc<ProviderThrottle>>,
 }
 
 impl ModelClient {
@@ -89,17 +117,73 @@ impl ModelClient {
         summary: ReasoningSummaryConfig,
         conversation_id: ConversationId,
     ) -> Self {
-        let client = create_client();
+        let client = create_provider_client(config.network_proxy.as_ref(), &provider)
+            .unwrap_or_else(|err| {
+                tracing::error!("{err}; using an HTTP client without the provider's settings");
+                create_client()
+            });
+        let provider_auth = stored_provider_auth(&config, &provider);
+        let throttle = ProviderThrottle::for_provider(&provider);
+        // A background response is stored by the endpoint that created it,
+        // so requests must not move while one may be in flight.
+        let failover = if config.responses_background {
+            None
+        } else {
+            EndpointFailover::for_provider(&provider)
+        };
+        let reported_switches = Arc::new(AtomicU64::new(
+            failover
+                .as_ref()
+                .map_or(0, |failover| failover.next_switch_seq()),
+        ));
 
         Self {
             config,
             auth_manager,
             client,
             provider,
+            provider_auth,
             conversation_id,
             effort,
             summary,
+            failover,
+            reported_switches,
+            throttle,
+        }
+    }
+
+    /// Endpoint switches of this client's provider that have not been
+    /// reported by this session yet.
+    pub(crate) fn take_endpoint_switches(&self) -> Vec<EndpointSwitch> {
+        let Some(failover) = &self.failover else {
+            return Vec::new();
+        };
+        let switches = failover.switches_since(self.reported_switches.load(Ordering::Relaxed));
+        if let Some(last) = switches.last() {
+            self.reported_switches
+                .store(last.seq + 1, Ordering::Relaxed);
         }
+        switches
+    }
+
+    /// Clients for the configured `provider_fallbacks`, in order, with this
+    /// client's model and reasoning settings.
+    pub(crate) fn fallback_clients(&self) -> Vec<ModelClient> {
+        self.config
+            .provider_fallbacks
+            .iter()
+            .filter_map(|id| self.config.model_providers.get(id))
+            .map(|provider| {
+                Self::new(
+                    Arc::clone(&self.config),
+                    self.auth_manager.clone(),
+                    provider.clone(),
+                    self.effort,
+                    self.summary,
+                    self.conversation_id,
+                )
+            })
+            .collect()
     }
 
     pub fn get_model_context_window(&self) -> Option<u64> {
@@ -108,16 +192,108 @@ impl ModelClient {
             .or_else(|| get_model_info(&self.config.model_family).map(|info| info.context_window))
     }
 
+    /// Token usage that triggers auto-compaction: the lower of the token
+    /// limit and `auto_compact.threshold_percent` of the context window.
     pub fn get_auto_compact_token_limit(&self) -> Option<i64> {
-        self.config.model_auto_compact_token_limit.or_else(|| {
+        let token_limit = self.config.model_auto_compact_token_limit.or_else(|| {
             get_model_info(&self.config.model_family).and_then(|info| info.auto_compact_token_limit)
-        })
+        });
+        let threshold_limit = self
+            .config
+            .auto_compact
+            .threshold_percent
+            .zip(self.get_model_context_window())
+            .map(|(percent, window)| (window / 100 * u64::from(percent.min(100))) as i64);
+        match (token_limit, threshold_limit) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (limit, None) | (None, limit) => limit,
+        }
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
-    /// specialised helpers are private to avoid accidental misuse.
+    /// Number of recent user turns that auto-compaction keeps verbatim.
+    pub(crate) fn auto_compact_keep_recent_turns(&self) -> usize {
+        self.config.auto_compact.keep_recent_turns
+    }
+
+    /// The client that writes auto-compaction summaries: this one, or one
+    /// for `auto_compact.model` on the same provider.
+    pub(crate) fn auto_compact_client(&self) -> ModelClient {
+        let Some(model) = &self.config.auto_compact.model else {
+            return self.clone();
+        };
+        let mut config = (*self.config).clone();
+        config.model = model.clone();
+        config.model_family =
+            find_family_for_model(model).unwrap_or_else(|| derive_default_model_family(model));
+        config.model_context_window =
+            get_model_info(&config.model_family).map(|info| info.context_window);
+        Self::new(
+            Arc::new(config),
+            self.auth_manager.clone(),
+            self.provider.clone(),
+            self.effort,
+            self.summary,
+            self.conversation_id,
+        )
+    }
+
+    /// Configured prices of this client's model on its provider.
+    pub(crate) fn token_prices(&self) -> Option<TokenPrices> {
+        let provider_id = if self.provider == self.config.model_provider {
+            Some(self.config.model_provider_id.as_str())
+        } else {
+            self.config
+                .model_providers
+                .iter()
+                .find(|(_, provider)| **provider == self.provider)
+                .map(|(id, _)| id.as_str())
+        };
+        prices_for(
+            &self.config.pricing,
+            provider_id.unwrap_or_default(),
+            &self.config.model,
+        )
+    }
+
+    /// Whether the provider's wire API constrains responses to an output
+    /// schema itself. Other wire APIs get the schema as instructions.
+    pub(crate) fn enforces_output_schema(&self) -> bool {
+        match self.provider.wire_api {
+            WireApi::Responses | WireApi::Chat => true,
+            WireApi::Anthropic | WireApi::Gemini | WireApi::Bedrock => false,
+        }
+    }
+
+    /// Streams a response to `prompt`, first waiting for the provider's
+    /// client-side rate limits if it has any.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        let dispatch = wire_log::scope(
+            self.conversation_id,
+            &self.provider,
+            self.dispatch_stream(prompt),
+        );
+        let Some(throttle) = &self.throttle else {
+            return dispatch.await;
+        };
+        let permit = throttle.acquire(estimate_prompt_tokens(prompt)).await;
+        let stream = dispatch.await?;
+        Ok(permit.hold_for(stream))
+    }
+
+    /// Dispatches to the implementation of the provider's wire API.  Public callers always invoke `stream()` – the
+    /// specialised helpers are private to avoid accidental misuse.
+    async fn dispatch_stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        if let Some(response_id) = &prompt.resume_response_id {
+            return match self.provider.wire_api {
+                WireApi::Responses => self.resume_background_response(response_id).await,
+                WireApi::Chat | WireApi::Anthropic | WireApi::Gemini | WireApi::Bedrock => {
+                    Err(CodexErr::UnsupportedOperation(
+                        "background responses require the Responses API".to_string(),
+                    ))
+                }
+            };
+        }
+
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
             WireApi::Chat => {
@@ -125,8 +301,11 @@ impl ModelClient {
                 let response_stream = stream_chat_completions(
                     prompt,
                     &self.config.model_family,
+                    self.config.model_temperature,
                     &self.client,
                     &self.provider,
+                    self.failover.as_ref(),
+                    &self.provider_auth,
                 )
                 .await?;
 
@@ -155,6 +334,41 @@ impl ModelClient {
 
                 Ok(ResponseStream { rx_event: rx })
             }
+            WireApi::Anthropic => {
+                stream_anthropic_messages(
+                    prompt,
+                    &self.config,
+                    self.effort,
+                    &self.client,
+                    &self.provider,
+                    self.failover.as_ref(),
+                    &self.provider_auth,
+                )
+                .await
+            }
+            WireApi::Gemini => {
+                stream_gemini(
+                    prompt,
+                    &self.config,
+                    self.effort,
+                    &self.client,
+                    &self.provider,
+                    self.failover.as_ref(),
+                    &self.provider_auth,
+                )
+                .await
+            }
+            WireApi::Bedrock => {
+                stream_bedrock_converse(
+                    prompt,
+                    &self.config,
+                    &self.client,
+                    &self.provider,
+                    self.failover.as_ref(),
+                    &self.provider_auth,
+                )
+                .await
+            }
         }
     }
 
@@ -209,6 +423,8 @@ impl ModelClient {
         //
         // For Azure, we send `store: true` and preserve reasoning item IDs.
         let azure_workaround = self.provider.is_azure_responses_endpoint();
+        // Background responses must be stored so they can be picked up again.
+        let background = self.config.responses_background;
 
         let payload = ResponsesApiRequest {
             model: &self.config.model,
@@ -216,13 +432,17 @@ impl ModelClient {
             input: &input_with_instructions,
             tools: &tools_json,
             tool_choice: "auto",
-            parallel_tool_calls: false,
+            // Read-only calls can only run together when the model may make
+            // several at once.
+            parallel_tool_calls: self.config.max_parallel_tool_calls > 1,
             reasoning,
-            store: azure_workaround,
+            store: azure_workaround || background,
             stream: true,
+            background,
             include,
             prompt_cache_key: Some(self.conversation_id.to_string()),
             text,
+            temperature: self.config.model_temperature,
         };
 
         let mut payload_json = serde_json::to_value(&payload)?;
@@ -232,22 +452,38 @@ impl ModelClient {
 
         let max_attempts = self.provider.request_max_retries();
         for attempt in 0..=max_attempts {
+            let (provider, endpoint) = match &self.failover {
+                Some(failover) => {
+                    let (provider, endpoint) = failover.select(&self.provider, &self.client).await;
+                    (provider, Some(endpoint))
+                }
+                None => (Cow::Borrowed(&self.provider), None),
+            };
             match self
-                .attempt_stream_responses(&payload_json, &auth_manager)
+                .attempt_stream_responses(&provider, &payload_json, &auth_manager)
                 .await
             {
                 Ok(stream) => {
+                    if let Some(endpoint) = endpoint {
+                        endpoint.succeeded();
+                    }
                     return Ok(stream);
                 }
                 Err(StreamAttemptError::Fatal(e)) => {
                     return Err(e);
                 }
                 Err(retryable_attempt_error) => {
+                    let switched = match (&endpoint, retryable_attempt_error.outage()) {
+                        (Some(endpoint), Some(reason)) => endpoint.failed(&reason),
+                        _ => false,
+                    };
                     if attempt == max_attempts {
                         return Err(retryable_attempt_error.into_error());
                     }
 
-                    tokio::time::sleep(retryable_attempt_error.delay(attempt)).await;
+                    if !switched {
+                        tokio::time::sleep(retryable_attempt_error.delay(attempt)).await;
+                    }
                 }
             }
         }
@@ -258,21 +494,21 @@ impl ModelClient {
     /// Single attempt to start a streaming Responses API call.
     async fn attempt_stream_responses(
         &self,
+        provider: &ModelProviderInfo,
         payload_json: &Value,
         auth_manager: &Option<Arc<AuthManager>>,
     ) -> std::result::Result<ResponseStream, StreamAttemptError> {
         // Always fetch the latest auth in case a prior attempt refreshed the token.
-        let auth = auth_manager.as_ref().and_then(|m| m.auth());
+        let auth = self.current_auth();
 
         trace!(
             "POST to {}: {:?}",
-            self.provider.get_full_url(&auth),
+            provider.get_full_url(&auth, &self.config.model),
             serde_json::to_string(payload_json)
         );
 
-        let mut req_builder = self
-            .provider
-            .create_request_builder(&self.client, &auth)
+        let mut req_builder = provider
+            .create_request_builder(&self.client, &auth, &self.config.model)
             .await
             .map_err(StreamAttemptError::Fatal)?;
 
@@ -291,8 +527,10 @@ impl ModelClient {
             req_builder = req_builder.header("chatgpt-account-id", account_id);
         }
 
+        wire_log::log_request(&req_builder);
         let res = req_builder.send().await;
         if let Ok(resp) = &res {
+            wire_log::log_response(resp);
             trace!(
                 "Response status: {}, cf-ray: {}",
                 resp.status(),
@@ -317,12 +555,26 @@ impl ModelClient {
                 }
 
                 // spawn task to process SSE
-                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
-                tokio::spawn(process_sse(
-                    stream,
-                    tx_event,
-                    self.provider.stream_idle_timeout(),
-                ));
+                let stream = wire_log::tee(resp.bytes_stream()).map_err(CodexErr::Reqwest);
+                if self.config.responses_background {
+                    // Reconnects happen in the spawned task, so the wire log
+                    // has to go along.
+                    tokio::spawn(wire_log::scope(
+                        self.conversation_id,
+                        &self.provider,
+                        self.clone().forward_background_response(
+                            stream.boxed(),
+                            tx_event,
+                            StreamCursor::default(),
+                        ),
+                    ));
+                } else {
+                    tokio::spawn(process_sse(
+                        stream,
+                        tx_event,
+                        self.provider.stream_idle_timeout(),
+                    ));
+                }
 
                 Ok(ResponseStream { rx_event })
             }
@@ -340,8 +592,14 @@ impl ModelClient {
                 if status == StatusCode::UNAUTHORIZED
                     && let Some(manager) = auth_manager.as_ref()
                     && manager.auth().is_some()
+                    && let Err(RefreshTokenError::Permanent(_)) = manager.refresh_token().await
                 {
-                    let _ = manager.refresh_token().await;
+                    // Retrying cannot succeed until the user logs in again,
+                    // which the auth manager has already announced.
+                    let body = res.text().await.unwrap_or_default();
+                    return Err(StreamAttemptError::Fatal(CodexErr::UnexpectedStatus(
+                        status, body,
+                    )));
                 }
 
                 // The OpenAI Responses endpoint returns structured JSON bodies even for 4xx/5xx
@@ -395,10 +653,196 @@ impl ModelClient {
         }
     }
 
+    /// Streams the background response `response_id` from its first event.
+    /// The response may have been started by another process.
+    async fn resume_background_response(&self, response_id: &str) -> Result<ResponseStream> {
+        let max_attempts = self.provider.request_max_retries();
+        for attempt in 0..=max_attempts {
+            match self.attempt_open_response_stream(response_id, None).await {
+                Ok(stream) => {
+                    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                    let cursor = StreamCursor {
+                        response_id: Some(response_id.to_string()),
+                        sequence_number: None,
+                    };
+                    tokio::spawn(wire_log::scope(
+                        self.conversation_id,
+                        &self.provider,
+                        self.clone()
+                            .forward_background_response(stream, tx_event, cursor),
+                    ));
+                    return Ok(ResponseStream { rx_event });
+                }
+                Err(StreamAttemptError::Fatal(e)) => {
+                    return Err(e);
+                }
+                Err(retryable_attempt_error) => {
+                    if attempt == max_attempts {
+                        return Err(retryable_attempt_error.into_error());
+                    }
+
+                    tokio::time::sleep(retryable_attempt_error.delay(attempt)).await;
+                }
+            }
+        }
+
+        unreachable!("resume_background_response should always return");
+    }
+
+    /// Single attempt to open the event stream of a stored response.
+    async fn attempt_open_response_stream(
+        &self,
+        response_id: &str,
+        starting_after: Option<u64>,
+    ) -> std::result::Result<BoxStream<'static, Result<Bytes>>, StreamAttemptError> {
+        let auth = self.current_auth();
+
+        let mut req_builder = self
+            .provider
+            .create_response_stream_request_builder(
+                &self.client,
+                &auth,
+                response_id,
+                starting_after,
+            )
+            .await
+            .map_err(StreamAttemptError::Fatal)?;
+
+        req_builder = req_builder
+            .header("OpenAI-Beta", "responses=experimental")
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+
+        if let Some(auth) = auth.as_ref()
+            && auth.mode == AuthMode::ChatGPT
+            && let Some(account_id) = auth.get_account_id()
+        {
+            req_builder = req_builder.header("chatgpt-account-id", account_id);
+        }
+
+        wire_log::log_request(&req_builder);
+        let res = req_builder.send().await;
+        if let Ok(resp) = &res {
+            wire_log::log_response(resp);
+        }
+        match res {
+            Ok(resp) if resp.status().is_success() => Ok(wire_log::tee(resp.bytes_stream())
+                .map_err(CodexErr::Reqwest)
+                .boxed()),
+            Ok(resp) => {
+                let status = resp.status();
+                if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                    return Err(StreamAttemptError::RetryableHttpError {
+                        status,
+                        retry_after: None,
+                    });
+                }
+                let body = resp.text().await.unwrap_or_default();
+                Err(StreamAttemptError::Fatal(CodexErr::UnexpectedStatus(
+                    status, body,
+                )))
+            }
+            Err(e) => Err(StreamAttemptError::RetryableTransportError(e.into())),
+        }
+    }
+
+    /// Forwards the events of a background response to `tx_event`. If the
+    /// stream drops before the response finishes, reconnects and continues
+    /// after the last event received. The reconnect budget is
+    /// `stream_max_retries` and is reset whenever a connection makes progress.
+    async fn forward_background_response(
+        self,
+        mut stream: BoxStream<'static, Result<Bytes>>,
+        tx_event: mpsc::Sender<Result<ResponseEvent>>,
+        mut cursor: StreamCursor,
+    ) {
+        let idle_timeout = self.provider.stream_idle_timeout();
+        let max_reconnects = self.provider.stream_max_retries();
+        let mut reconnects = 0;
+        loop {
+            let last_sequence_number = cursor.sequence_number;
+            let Err(err) =
+                process_sse_events(stream, &tx_event, idle_timeout, Some(&mut cursor)).await
+            else {
+                return;
+            };
+            if cursor.sequence_number != last_sequence_number {
+                reconnects = 0;
+            }
+            let Some(response_id) = cursor.response_id.clone() else {
+                // Disconnected before the response was created, so there
+                // is nothing to reconnect to.
+                let _ = tx_event.send(Err(err)).await;
+                return;
+            };
+
+            loop {
+                if reconnects >= max_reconnects || tx_event.is_closed() {
+                    let _ = tx_event.send(Err(err)).await;
+                    return;
+                }
+                reconnects += 1;
+                let delay = backoff(reconnects);
+                warn!(
+                    "background response {response_id} disconnected ({err}); reconnecting ({reconnects}/{max_reconnects} in {delay:?})..."
+                );
+                tokio::time::sleep(delay).await;
+
+                match self
+                    .attempt_open_response_stream(&response_id, cursor.sequence_number)
+                    .await
+                {
+                    Ok(resumed) => {
+                        stream = resumed;
+                        break;
+                    }
+                    Err(StreamAttemptError::Fatal(e)) => {
+                        let _ = tx_event.send(Err(e)).await;
+                        return;
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+
+    /// Auth for the next request: the stored provider API key if there is
+    /// one, otherwise the latest auth from the auth manager.
+    fn current_auth(&self) -> Option<CodexAuth> {
+        match &self.provider_auth {
+            Some(provider_auth) => Some(provider_auth.clone()),
+            None => self.auth_manager.as_ref().and_then(|m| m.auth()),
+        }
+    }
+
     pub fn get_provider(&self) -> ModelProviderInfo {
         self.provider.clone()
     }
 
+    /// A client for a single turn with `overrides` applied on top of this
+    /// client's settings.
+    pub(crate) fn with_turn_overrides(&self, overrides: &TurnOverrides) -> ModelClient {
+        let mut config = (*self.config).clone();
+        if overrides.verbosity.is_some() {
+            config.model_verbosity = overrides.verbosity;
+        }
+        if overrides.temperature.is_some() {
+            config.model_temperature = overrides.temperature;
+        }
+        Self::new(
+            Arc::new(config),
+            self.auth_manager.clone(),
+            self.provider.clone(),
+            overrides.effort.or(self.effort),
+            self.summary,
+            self.conversation_id,
+        )
+    }
+
+    /// Key of the provider in `model_providers`.
+    pub fn get_provider_id(&self) -> String {
+        self.config.model_provider_id.clone()
+    }
+
     /// Returns the currently configured model slug.
     pub fn get_model(&self) -> String {
         self.config.model.clone()
@@ -450,6 +894,20 @@ impl StreamAttemptError {
         }
     }
 
+    /// Describes the failure if it suggests the endpoint itself is down
+    /// rather than busy, which is what endpoint failover reacts to.
+    fn outage(&self) -> Option<String> {
+        match self {
+            Self::RetryableHttpError { status, .. } if status.is_server_error() => {
+                Some(format!("the endpoint returned {status}"))
+            }
+            Self::RetryableHttpError { .. } | Self::Fatal(_) => None,
+            Self::RetryableTransportError(error) => {
+                Some(format!("could not reach the endpoint: {error}"))
+            }
+        }
+    }
+
     fn into_error(self) -> CodexErr {
         match self {
             Self::RetryableHttpError { status, .. } => {
@@ -472,6 +930,15 @@ struct SseEvent {
     response: Option<Value>,
     item: Option<Value>,
     delta: Option<String>,
+    sequence_number: Option<u64>,
+}
+
+/// Position in the event stream of a background response, used to pick the
+/// stream up again after a disconnect.
+#[derive(Debug, Default)]
+struct StreamCursor {
+    response_id: Option<String>,
+    sequence_number: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -517,6 +984,28 @@ struct ResponseCompletedOutputTokensDetails {
     reasoning_tokens: u64,
 }
 
+/// Auth built from the API key stored for the configured provider, if any.
+/// Providers that use OpenAI auth always go through the auth manager.
+fn stored_provider_auth(config: &Config, provider: &ModelProviderInfo) -> Option<CodexAuth> {
+    if provider.requires_openai_auth {
+        return None;
+    }
+    match read_provider_api_key(
+        &config.codex_home,
+        &config.model_provider_id,
+        config.credential_store,
+    ) {
+        Ok(api_key) => api_key.map(|key| CodexAuth::from_api_key(&key)),
+        Err(err) => {
+            warn!(
+                "failed to read stored API key for provider {}: {err}",
+                config.model_provider_id
+            );
+            None
+        }
+    }
+}
+
 fn attach_item_ids(payload_json: &mut Value, original_items: &[ResponseItem]) {
     let Some(input_value) = payload_json.get_mut("input") else {
         return;
@@ -607,6 +1096,24 @@ async fn process_sse<S>(
     idle_timeout: Duration,
 ) where
     S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    if let Err(err) = process_sse_events(stream, &tx_event, idle_timeout, None).await {
+        let _ = tx_event.send(Err(err)).await;
+    }
+}
+
+/// Forwards events to `tx_event` until the response completes or fails, or
+/// the receiver hangs up. Returns an error only when the stream itself broke
+/// off first, so that background responses can reconnect. `cursor` is set for
+/// background responses and records where to reconnect from.
+async fn process_sse_events<S>(
+    stream: S,
+    tx_event: &mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    mut cursor: Option<&mut StreamCursor>,
+) -> Result<()>
+where
+    S: Stream<Item = Result<Bytes>> + Unpin,
 {
     let mut stream = stream.eventsource();
 
@@ -620,9 +1127,7 @@ async fn process_sse<S>(
             Ok(Some(Ok(sse))) => sse,
             Ok(Some(Err(e))) => {
                 debug!("SSE Error: {e:#}");
-                let event = CodexErr::Stream(e.to_string(), None);
-                let _ = tx_event.send(Err(event)).await;
-                return;
+                return Err(CodexErr::Stream(e.to_string(), None));
             }
             Ok(None) => {
                 match response_completed {
@@ -636,25 +1141,25 @@ async fn process_sse<S>(
                         };
                         let _ = tx_event.send(Ok(event)).await;
                     }
-                    None => {
-                        let _ = tx_event
-                            .send(Err(response_error.unwrap_or(CodexErr::Stream(
+                    None => match response_error {
+                        Some(err) => {
+                            let _ = tx_event.send(Err(err)).await;
+                        }
+                        None => {
+                            return Err(CodexErr::Stream(
                                 "stream closed before response.completed".into(),
                                 None,
-                            ))))
-                            .await;
-                    }
+                            ));
+                        }
+                    },
                 }
-                return;
+                return Ok(());
             }
             Err(_) => {
-                let _ = tx_event
-                    .send(Err(CodexErr::Stream(
-                        "idle timeout waiting for SSE".into(),
-                        None,
-                    )))
-                    .await;
-                return;
+                return Err(CodexErr::Stream(
+                    "idle timeout waiting for SSE".into(),
+                    None,
+                ));
             }
         };
 
@@ -669,6 +1174,12 @@ async fn process_sse<S>(
             }
         };
 
+        if let Some(cursor) = cursor.as_deref_mut()
+            && let Some(sequence_number) = event.sequence_number
+        {
+            cursor.sequence_number = Some(sequence_number);
+        }
+
         match event.kind.as_str() {
             // Individual output item finalised. Forward immediately so the
             // rest of the agent can stream assistant text/functions *live*
@@ -697,14 +1208,14 @@ async fn process_sse<S>(
 
                 let event = ResponseEvent::OutputItemDone(item);
                 if tx_event.send(Ok(event)).await.is_err() {
-                    return;
+                    return Ok(());
                 }
             }
             "response.output_text.delta" => {
                 if let Some(delta) = event.delta {
                     let event = ResponseEvent::OutputTextDelta(delta);
                     if tx_event.send(Ok(event)).await.is_err() {
-                        return;
+                        return Ok(());
                     }
                 }
             }
@@ -712,7 +1223,7 @@ async fn process_sse<S>(
                 if let Some(delta) = event.delta {
                     let event = ResponseEvent::ReasoningSummaryDelta(delta);
                     if tx_event.send(Ok(event)).await.is_err() {
-                        return;
+                        return Ok(());
                     }
                 }
             }
@@ -720,13 +1231,25 @@ async fn process_sse<S>(
                 if let Some(delta) = event.delta {
                     let event = ResponseEvent::ReasoningContentDelta(delta);
                     if tx_event.send(Ok(event)).await.is_err() {
-                        return;
+                        return Ok(());
                     }
                 }
             }
             "response.created" => {
-                if event.response.is_some() {
+                if let Some(resp_val) = event.response {
                     let _ = tx_event.send(Ok(ResponseEvent::Created {})).await;
+
+                    if let Some(cursor) = cursor.as_deref_mut()
+                        && let Some(response_id) = resp_val.get("id").and_then(Value::as_str)
+                    {
+                        cursor.response_id = Some(response_id.to_string());
+                        let event = ResponseEvent::BackgroundResponseCreated {
+                            response_id: response_id.to_string(),
+                        };
+                        if tx_event.send(Ok(event)).await.is_err() {
+                            return Ok(());
+                        }
+                    }
                 }
             }
             "response.failed" => {
@@ -785,7 +1308,7 @@ async fn process_sse<S>(
                             .to_string();
                         let ev = ResponseEvent::WebSearchCallBegin { call_id };
                         if tx_event.send(Ok(ev)).await.is_err() {
-                            return;
+                            return Ok(());
                         }
                     }
                 }
@@ -794,7 +1317,7 @@ async fn process_sse<S>(
                 // Boundary between reasoning summary sections (e.g., titles).
                 let event = ResponseEvent::ReasoningSummaryPartAdded;
                 if tx_event.send(Ok(event)).await.is_err() {
-                    return;
+                    return Ok(());
                 }
             }
             "response.reasoning_summary_text.done" => {}
@@ -967,15 +1490,23 @@ mod tests {
         let provider = ModelProviderInfo {
             name: "test".to_string(),
             base_url: Some("https://test.com".to_string()),
+            fallback_base_urls: None,
             env_key: Some("TEST_API_KEY".to_string()),
             env_key_instructions: None,
             wire_api: WireApi::Responses,
             query_params: None,
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: Some(0),
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
 
@@ -1027,15 +1558,23 @@ mod tests {
         let provider = ModelProviderInfo {
             name: "test".to_string(),
             base_url: Some("https://test.com".to_string()),
+            fallback_base_urls: None,
             env_key: Some("TEST_API_KEY".to_string()),
             env_key_instructions: None,
             wire_api: WireApi::Responses,
             query_params: None,
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: Some(0),
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
 
@@ -1061,15 +1600,23 @@ mod tests {
         let provider = ModelProviderInfo {
             name: "test".to_string(),
             base_url: Some("https://test.com".to_string()),
+            fallback_base_urls: None,
             env_key: Some("TEST_API_KEY".to_string()),
             env_key_instructions: None,
             wire_api: WireApi::Responses,
             query_params: None,
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: Some(0),
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
 
@@ -1089,6 +1636,58 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn background_stream_tracks_cursor_and_returns_disconnect() {
+        let created = json!({
+            "type": "response.created",
+            "sequence_number": 0,
+            "response": { "id": "resp_bg" }
+        })
+        .to_string();
+        let item = json!({
+            "type": "response.output_item.done",
+            "sequence_number": 1,
+            "item": {
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "output_text", "text": "Hello"}]
+            }
+        })
+        .to_string();
+        let body = format!(
+            "event: response.created\ndata: {created}\n\nevent: response.output_item.done\ndata: {item}\n\n"
+        );
+
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        let stream = ReaderStream::new(std::io::Cursor::new(body)).map_err(CodexErr::Io);
+        let mut cursor = StreamCursor::default();
+        let result =
+            process_sse_events(stream, &tx, Duration::from_secs(1), Some(&mut cursor)).await;
+        drop(tx);
+
+        match result {
+            Err(CodexErr::Stream(msg, None)) => {
+                assert_eq!(msg, "stream closed before response.completed")
+            }
+            other => panic!("expected disconnect, got {other:?}"),
+        }
+        assert_eq!(cursor.response_id.as_deref(), Some("resp_bg"));
+        assert_eq!(cursor.sequence_number, Some(1));
+
+        let mut events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            events.push(ev.expect("event"));
+        }
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ResponseEvent::Created,
+                ResponseEvent::BackgroundResponseCreated { response_id },
+                ResponseEvent::OutputItemDone(_),
+            ] if response_id == "resp_bg"
+        ));
+    }
+
     // ────────────────────────────
     // Table-driven test from `main`
     // ────────────────────────────
@@ -1166,15 +1765,23 @@ mod tests {
             let provider = ModelProviderInfo {
                 name: "test".to_string(),
                 base_url: Some("https://test.com".to_string()),
+                fallback_base_urls: None,
                 env_key: Some("TEST_API_KEY".to_string()),
                 env_key_instructions: None,
                 wire_api: WireApi::Responses,
                 query_params: None,
                 http_headers: None,
                 env_http_headers: None,
+                safety_settings: None,
+                aws: None,
                 request_max_retries: Some(0),
                 stream_max_retries: Some(0),
                 stream_idle_timeout_ms: Some(1000),
+                requests_per_minute: None,
+                tokens_per_minute: None,
+                max_concurrent_streams: None,
+                ca_certificates: None,
+                danger_accept_invalid_certs: None,
                 requires_openai_auth: false,
             };
 
@@ -22,6 +22,10 @@ use tokio::sync::mpsc;
 pub const REVIEW_PROMPT: &str = include_str!("../review_prompt.md");
 
 /// API request payload for a single model turn
+/// Name the output schema is sent under to APIs that constrain the
+/// response to it.
+pub(crate) const OUTPUT_SCHEMA_NAME: &str = "codex_output_schema";
+
 #[derive(Default, Debug, Clone)]
 pub struct Prompt {
     /// Conversation context input items.
@@ -36,6 +40,10 @@ pub struct Prompt {
 
     /// Optional the output schema for the model's response.
     pub output_schema: Option<Value>,
+
+    /// When set, stream the events of this in-flight background response
+    /// instead of creating a new response from `input`.
+    pub(crate) resume_response_id: Option<String>,
 }
 
 impl Prompt {
@@ -63,6 +71,21 @@ impl Prompt {
         }
     }
 
+    /// The full instructions, plus the output schema for wire APIs that
+    /// cannot constrain the response to it themselves.
+    pub(crate) fn get_full_instructions_with_output_schema<'a>(
+        &'a self,
+        model: &'a ModelFamily,
+    ) -> Cow<'a, str> {
+        let instructions = self.get_full_instructions(model);
+        match &self.output_schema {
+            Some(schema) => Cow::Owned(format!(
+                "{instructions}\n\nYour final message must be only a JSON value that matches this JSON schema, without any other text or code fences:\n{schema}"
+            )),
+            None => instructions,
+        }
+    }
+
     pub(crate) fn get_formatted_input(&self) -> Vec<ResponseItem> {
         self.input.clone()
     }
@@ -71,6 +94,11 @@ impl Prompt {
 #[derive(Debug)]
 pub enum ResponseEvent {
     Created,
+    /// A background response was created; it can be resumed by this id if
+    /// the stream is lost.
+    BackgroundResponseCreated {
+        response_id: String,
+    },
     OutputItemDone(ResponseItem),
     Completed {
         response_id: String,
@@ -153,11 +181,16 @@ pub(crate) struct ResponsesApiRequest<'a> {
     pub(crate) reasoning: Option<Reasoning>,
     pub(crate) store: bool,
     pub(crate) stream: bool,
+    /// Keep generating server-side even if the stream disconnects.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) background: bool,
     pub(crate) include: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) prompt_cache_key: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) text: Option<TextControls>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) temperature: Option<f32>,
 }
 
 pub(crate) fn create_reasoning_param_for_request(
@@ -189,7 +222,7 @@ pub(crate) fn create_text_param_for_request(
             r#type: TextFormatType::JsonSchema,
             strict: true,
             schema: schema.clone(),
-            name: "codex_output_schema".to_string(),
+            name: OUTPUT_SCHEMA_NAME.to_string(),
         }),
     })
 }
@@ -283,8 +316,10 @@ mod tests {
             reasoning: None,
             store: false,
             stream: true,
+            background: false,
             include: vec![],
             prompt_cache_key: None,
+            temperature: None,
             text: Some(TextControls {
                 verbosity: Some(OpenAiVerbosity::Low),
                 format: None,
@@ -324,8 +359,10 @@ mod tests {
             reasoning: None,
             store: false,
             stream: true,
+            background: false,
             include: vec![],
             prompt_cache_key: None,
+            temperature: None,
             text: Some(text_controls),
         };
 
@@ -360,8 +397,10 @@ mod tests {
             reasoning: None,
             store: false,
             stream: true,
+            background: false,
             include: vec![],
             prompt_cache_key: None,
+            temperature: None,
             text: None,
         };
 
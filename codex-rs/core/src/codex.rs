@@ -1,15 +1,18 @@
 use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::client_common::REVIEW_PROMPT;
 use crate::event_mapping::map_response_item_to_event_messages;
 use crate::function_tool::FunctionCallError;
+use crate::plugins::PluginHost;
 use crate::review_format::format_review_findings_block;
 use crate::user_notification::UserNotifier;
 use async_channel::Receiver;
@@ -32,12 +35,18 @@ use serde::Serialize;
 use serde_json;
 use serde_json::Value;
 use tokio::sync::Mutex;
+use tokio::sync::Notify;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tracing::Instrument;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
+use tracing::info_span;
 use tracing::trace;
 use tracing::warn;
+use uuid::Uuid;
 
 use crate::ModelProviderInfo;
 use crate::apply_patch;
@@ -45,12 +54,34 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::auto_commit::AutoCommitter;
+use crate::background_jobs::BackgroundJobManager;
+use crate::background_jobs::DEFAULT_BACKGROUND_JOB_TIMEOUT_MS;
+use crate::background_jobs::JOB_KILL_TOOL_NAME;
+use crate::background_jobs::JOB_RESULT_TOOL_NAME;
+use crate::background_jobs::JobControl;
+use crate::background_jobs::JobKillArgs;
+use crate::background_jobs::JobOutcome;
+use crate::background_jobs::JobResultArgs;
+use crate::change_journal::ChangeJournal;
+use crate::change_journal::PatchSnapshot;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::ExecTimeout;
+use crate::config_types::McpSamplingApproval;
+use crate::config_types::ResourceLimits;
+use crate::config_types::SandboxResourceLimits;
 use crate::config_types::ShellEnvironmentPolicy;
+use crate::config_types::WebSearchBackend;
+use crate::context_pruning;
 use crate::conversation_history::ConversationHistory;
+use crate::conversation_history::reencode_for_provider;
+use crate::credential_store::credential_store;
+use crate::custom_tools::CustomToolOutput;
+use crate::custom_tools::CustomTools;
+use crate::custom_tools::argument_env;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -68,25 +99,70 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::extensions::ExtensionStatus;
+use crate::extensions::extension_prompts;
+use crate::fetch_url::FETCH_URL_TOOL_NAME;
+use crate::fetch_url::FetchUrlArgs;
+use crate::file_tools::LIST_DIR_TOOL_NAME;
+use crate::file_tools::ListDirArgs;
+use crate::file_tools::READ_FILE_TOOL_NAME;
+use crate::file_tools::ReadFileArgs;
+use crate::file_tools::WRITE_FILE_TOOL_NAME;
+use crate::file_tools::WriteFileArgs;
+use crate::file_tools::check_readable;
+use crate::file_tools::list_dir;
+use crate::file_tools::read_file;
+use crate::file_tools::write_file_patch;
+use crate::lifecycle_hooks::HookEvent;
+use crate::lifecycle_hooks::HookOutcome;
+use crate::lifecycle_hooks::LifecycleHooks;
+use crate::mcp_connection_manager::LazyStartConfig;
+use crate::mcp_connection_manager::MCP_TOOLS_CACHE_FILE;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_connection_manager::workspace_roots;
+use crate::mcp_sampling::MCP_SAMPLING_CHANNEL_CAPACITY;
+use crate::mcp_sampling::McpSamplingRequest;
+use crate::mcp_sampling::handle_sampling_request;
 use crate::mcp_tool_call::handle_mcp_tool_call;
+use crate::memory::REMEMBER_TOOL_NAME;
+use crate::memory::RememberArgs;
+use crate::memory::memory_path;
+use crate::memory::remember;
 use crate::model_family::find_family_for_model;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::otel;
+use crate::output_overflow::OutputOverflowStore;
+use crate::output_overflow::READ_OUTPUT_TOOL_NAME;
+use crate::output_overflow::ReadOutputArgs;
+use crate::output_overflow::format_page;
+use crate::output_schema;
+use crate::output_schema::MAX_OUTPUT_SCHEMA_RETRIES;
+use crate::parallel_tools::is_read_only_tool_call;
 use crate::parse_command::parse_command;
+use crate::patch_risk::assess_patch_risk;
 use crate::plan_tool::handle_update_plan;
+use crate::post_patch_hooks::run_post_patch_hooks;
 use crate::project_doc::get_user_instructions;
 use crate::protocol::AgentMessageDeltaEvent;
 use crate::protocol::AgentReasoningDeltaEvent;
 use crate::protocol::AgentReasoningRawContentDeltaEvent;
 use crate::protocol::AgentReasoningSectionBreakEvent;
+use crate::protocol::AnnotationAddedEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::BackgroundJobEvent;
+use crate::protocol::BackgroundJobStatus;
+use crate::protocol::BackgroundResponseStartedEvent;
+use crate::protocol::CheckpointCreatedEvent;
+use crate::protocol::CheckpointRestoredEvent;
+use crate::protocol::ContextUsageEvent;
 use crate::protocol::ErrorEvent;
+use crate::protocol::ErrorKind;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecApprovalRequestEvent;
@@ -95,36 +171,88 @@ use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::LoginRequiredEvent;
+use crate::protocol::McpSamplingApprovalRequestEvent;
+use crate::protocol::McpServerStatus;
+use crate::protocol::McpServerStatusEvent;
+use crate::protocol::ModelChangedEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
+use crate::protocol::PatchRisk;
+use crate::protocol::PatchRiskLevel;
+use crate::protocol::ProviderEndpointSwitchedEvent;
+use crate::protocol::ProviderSwitchedEvent;
+use crate::protocol::QueuedUserInput;
+use crate::protocol::QueuedUserInputEvent;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReviewDecision;
 use crate::protocol::ReviewOutputEvent;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SecretRequestEvent;
+use crate::protocol::SecretValue;
 use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
 use crate::protocol::TokenCountEvent;
 use crate::protocol::TokenUsage;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::TurnOverrides;
 use crate::protocol::WebSearchBeginEvent;
+use crate::protocol::WebSearchEndEvent;
+use crate::provider_fallback::ProviderFallbacks;
+use crate::provider_fallback::is_provider_outage;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
+use crate::rollout::SessionLocked;
+use crate::rollout::lock::as_session_locked;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::assess_sandbox_rule;
+use crate::safety::get_platform_sandbox;
+use crate::safety::sandbox_capability;
+use crate::sandbox_audit;
+use crate::sandbox_audit::AuditApproval;
+use crate::sandbox_audit::SandboxAuditRecord;
+use crate::sandbox_rules::evaluate_sandbox_rules;
+use crate::sandbox_rules::missing_denied_path;
+use crate::search_code::SEARCH_CODE_TOOL_NAME;
+use crate::search_code::SearchCodeArgs;
+use crate::search_code::search_code;
+use crate::secrets::redact_secrets;
+use crate::secrets::validate_secret_name;
+use crate::session_log::SESSION_SPAN;
 use crate::shell;
 use crate::state::ActiveTurn;
+use crate::state::Checkpoint;
+use crate::state::PatchEdit;
+use crate::state::QueuedInput;
 use crate::state::SessionServices;
+use crate::task_failure::REPORT_FAILURE_TOOL_NAME;
+use crate::task_failure::ReportFailureArgs;
+use crate::task_failure::create_report_failure_tool;
+use crate::tasks::BackgroundResponseTask;
 use crate::tasks::CompactTask;
 use crate::tasks::RegularTask;
 use crate::tasks::ReviewTask;
+use crate::token_count::context_budget;
+use crate::token_count::count_prompt_tokens;
+use crate::tool_git::GIT_TOOL_NAME;
+use crate::tool_git::GitRisk;
+use crate::tool_git::GitToolArgs;
+use crate::tool_git::classify_git_command;
+use crate::tool_git::consequence_preview;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
+use crate::url_attachments::UrlAttachments;
 use crate::user_instructions::UserInstructions;
 use crate::user_notification::UserNotification;
 use crate::util::backoff;
+use crate::web_search::WEB_SEARCH_TOOL_NAME;
+use crate::web_search::WebSearch;
+use crate::web_search::WebSearchArgs;
+use crate::wire_log::set_wire_logging;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use codex_protocol::custom_prompts::CustomPrompt;
@@ -138,6 +266,7 @@ use codex_protocol::protocol::InitialHistory;
 
 pub mod compact;
 use self::compact::build_compacted_history;
+use self::compact::build_rolling_compacted_history;
 use self::compact::collect_user_messages;
 
 /// The high-level interface to the Codex system.
@@ -189,10 +318,12 @@ impl Codex {
             base_instructions: config.base_instructions.clone(),
             approval_policy: config.approval_policy,
             sandbox_policy: config.sandbox_policy.clone(),
-            notify: UserNotifier::new(config.notify.clone()),
+            notify: UserNotifier::new(config.notify.clone(), &config.webhooks),
             cwd: config.cwd.clone(),
         };
 
+        let (tx_mcp_sampling, rx_mcp_sampling) = mpsc::channel(MCP_SAMPLING_CHANNEL_CAPACITY);
+
         // Generate a unique ID for the lifetime of this Codex session.
         let (session, turn_context) = Session::new(
             configure_session,
@@ -200,16 +331,25 @@ impl Codex {
             auth_manager.clone(),
             tx_event.clone(),
             conversation_history,
+            tx_mcp_sampling,
         )
         .await
-        .map_err(|e| {
-            error!("Failed to create session: {e:#}");
-            CodexErr::InternalAgentDied
+        .map_err(|e| match e.downcast::<SessionLocked>() {
+            Ok(locked) => CodexErr::SessionLocked(locked),
+            Err(e) => {
+                error!("Failed to create session: {e:#}");
+                CodexErr::InternalAgentDied
+            }
         })?;
         let conversation_id = session.conversation_id;
 
-        // This task will run until Op::Shutdown is received.
-        tokio::spawn(submission_loop(session, turn_context, config, rx_sub));
+        // This task will run until Op::Shutdown is received. Its span routes
+        // what the session logs to the session's own log.
+        let span = info_span!(SESSION_SPAN, conversation_id = %conversation_id);
+        tokio::spawn(
+            submission_loop(session, turn_context, config, rx_sub, rx_mcp_sampling)
+                .instrument(span),
+        );
         let codex = Codex {
             next_id: AtomicU64::new(0),
             tx_sub,
@@ -251,6 +391,12 @@ impl Codex {
             .map_err(|_| CodexErr::InternalAgentDied)?;
         Ok(event)
     }
+
+    /// The receiving end of the event channel, for fanning events out to
+    /// subscriptions.
+    pub(crate) fn event_receiver(&self) -> Receiver<Event> {
+        self.rx_event.clone()
+    }
 }
 
 use crate::state::SessionState;
@@ -265,12 +411,17 @@ pub(crate) struct Session {
     pub(crate) active_turn: Mutex<Option<ActiveTurn>>,
     services: SessionServices,
     next_internal_sub_id: AtomicU64,
+    /// Wakes the submission loop to start the next queued input once a task
+    /// finishes.
+    queued_input_ready: Notify,
 }
 
 /// The context needed for a single turn of the conversation.
 #[derive(Debug)]
 pub(crate) struct TurnContext {
     pub(crate) client: ModelClient,
+    /// Providers to continue on when `client`'s provider is unavailable.
+    pub(crate) provider_fallbacks: ProviderFallbacks,
     /// The session's current working directory. All relative paths provided by
     /// the model as well as sandbox policies are resolved against this path
     /// instead of `std::env::current_dir()`.
@@ -280,17 +431,50 @@ pub(crate) struct TurnContext {
     pub(crate) approval_policy: AskForApproval,
     pub(crate) sandbox_policy: SandboxPolicy,
     pub(crate) shell_environment_policy: ShellEnvironmentPolicy,
+    pub(crate) sandbox_resource_limits: SandboxResourceLimits,
+    pub(crate) exec_timeout: ExecTimeout,
     pub(crate) tools_config: ToolsConfig,
     pub(crate) is_review_mode: bool,
     pub(crate) final_output_json_schema: Option<Value>,
 }
 
 impl TurnContext {
+    /// This context with `overrides` applied, for a single turn.
+    fn with_overrides(&self, overrides: &TurnOverrides) -> TurnContext {
+        let client = self.client.with_turn_overrides(overrides);
+        TurnContext {
+            provider_fallbacks: ProviderFallbacks::new(client.fallback_clients()),
+            client,
+            cwd: self.cwd.clone(),
+            base_instructions: self.base_instructions.clone(),
+            user_instructions: self.user_instructions.clone(),
+            approval_policy: self.approval_policy,
+            sandbox_policy: self.sandbox_policy.clone(),
+            shell_environment_policy: self.shell_environment_policy.clone(),
+            sandbox_resource_limits: self.sandbox_resource_limits,
+            exec_timeout: self.exec_timeout,
+            tools_config: self.tools_config.clone(),
+            is_review_mode: self.is_review_mode,
+            final_output_json_schema: self.final_output_json_schema.clone(),
+        }
+    }
+
+    /// The client for the provider requests currently go to.
+    fn active_client(&self) -> &ModelClient {
+        self.provider_fallbacks.active().unwrap_or(&self.client)
+    }
+
     fn resolve_path(&self, path: Option<String>) -> PathBuf {
         path.as_ref()
             .map(PathBuf::from)
             .map_or_else(|| self.cwd.clone(), |p| self.cwd.join(p))
     }
+
+    /// Limits for commands run under this turn's sandbox policy.
+    fn resource_limits(&self) -> ResourceLimits {
+        self.sandbox_resource_limits
+            .for_policy(&self.sandbox_policy)
+    }
 }
 
 /// Configure the model session.
@@ -327,6 +511,14 @@ struct ConfigureSession {
     cwd: PathBuf,
 }
 
+/// The session state a resumed or forked session rebuilds from its rollout.
+struct ReconstructedSession {
+    history: Vec<ResponseItem>,
+    checkpoints: HashMap<String, Checkpoint>,
+    change_journal: ChangeJournal,
+    changed_paths: BTreeSet<PathBuf>,
+}
+
 impl Session {
     async fn new(
         configure_session: ConfigureSession,
@@ -334,6 +526,7 @@ impl Session {
         auth_manager: Arc<AuthManager>,
         tx_event: Sender<Event>,
         initial_history: InitialHistory,
+        tx_mcp_sampling: mpsc::Sender<McpSamplingRequest>,
     ) -> anyhow::Result<(Arc<Self>, TurnContext)> {
         let ConfigureSession {
             provider,
@@ -352,6 +545,12 @@ impl Session {
             return Err(anyhow::anyhow!("cwd is not absolute: {cwd:?}"));
         }
 
+        let (plugins, plugin_load_errors) = PluginHost::load(config.plugins.clone()).await;
+        let user_instructions = plugins
+            .extend_instructions(user_instructions, cwd.clone())
+            .await;
+        let notify = notify.with_plugins(plugins.clone());
+
         let (conversation_id, rollout_params) = match &initial_history {
             InitialHistory::New | InitialHistory::Forked(_) => {
                 let conversation_id = ConversationId::default();
@@ -380,6 +579,13 @@ impl Session {
         let mcp_fut = McpConnectionManager::new(
             config.mcp_servers.clone(),
             config.use_experimental_use_rmcp_client,
+            credential_store(&config.codex_home, config.credential_store),
+            config.mcp_lazy_start.then(|| LazyStartConfig {
+                tools_cache: config.codex_home.join(MCP_TOOLS_CACHE_FILE),
+                idle_timeout: config.mcp_idle_timeout,
+            }),
+            workspace_roots(&config.cwd, &config.sandbox_policy),
+            (config.mcp_sampling.approval != McpSamplingApproval::Deny).then_some(tx_mcp_sampling),
         );
         let default_shell_fut = shell::default_user_shell();
         let history_meta_fut = crate::message_history::history_metadata(&config);
@@ -389,6 +595,9 @@ impl Session {
             tokio::join!(rollout_fut, mcp_fut, default_shell_fut, history_meta_fut);
 
         let rollout_recorder = rollout_recorder.map_err(|e| {
+            if let Some(locked) = as_session_locked(&e) {
+                return anyhow::Error::new(locked.clone());
+            }
             error!("failed to initialize rollout recorder: {e:#}");
             anyhow::anyhow!("failed to initialize rollout recorder: {e:#}")
         })?;
@@ -404,12 +613,40 @@ impl Session {
                 error!("{message}");
                 post_session_configured_error_events.push(Event {
                     id: INITIAL_SUBMIT_ID.to_owned(),
-                    msg: EventMsg::Error(ErrorEvent { message }),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message,
+                        kind: ErrorKind::Other,
+                    }),
                 });
                 (McpConnectionManager::default(), Default::default())
             }
         };
 
+        for message in plugin_load_errors {
+            error!("{message}");
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message,
+                    kind: ErrorKind::Other,
+                }),
+            });
+        }
+
+        for extension in &config.extensions {
+            if let ExtensionStatus::Unusable(problem) = &extension.status {
+                let message = format!("Extension `{}` is not loaded: {problem}", extension.name);
+                error!("{message}");
+                post_session_configured_error_events.push(Event {
+                    id: INITIAL_SUBMIT_ID.to_owned(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message,
+                        kind: ErrorKind::Other,
+                    }),
+                });
+            }
+        }
+
         // Surface individual client start-up failures to the user.
         if !failed_clients.is_empty() {
             for (server_name, err) in failed_clients {
@@ -417,11 +654,34 @@ impl Session {
                 error!("{message}");
                 post_session_configured_error_events.push(Event {
                     id: INITIAL_SUBMIT_ID.to_owned(),
-                    msg: EventMsg::Error(ErrorEvent { message }),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message,
+                        kind: ErrorKind::Other,
+                    }),
+                });
+                post_session_configured_error_events.push(Event {
+                    id: INITIAL_SUBMIT_ID.to_owned(),
+                    msg: EventMsg::McpServerStatus(McpServerStatusEvent {
+                        server: server_name,
+                        status: McpServerStatus::Failed,
+                        message: format!("failed to start: {err:#}"),
+                    }),
                 });
             }
         }
 
+        if config.wire_log
+            && let Err(e) = set_wire_logging(&config.codex_home, conversation_id, true)
+        {
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: format!("Failed to start the wire log: {e}"),
+                    kind: ErrorKind::Other,
+                }),
+            });
+        }
+
         // Now that the conversation id is final (may have been updated by resume),
         // construct the model client.
         let client = ModelClient::new(
@@ -433,14 +693,21 @@ impl Session {
             conversation_id,
         );
         let turn_context = TurnContext {
+            provider_fallbacks: ProviderFallbacks::new(client.fallback_clients()),
             client,
             tools_config: ToolsConfig::new(&ToolsConfigParams {
                 model_family: &config.model_family,
                 include_plan_tool: config.include_plan_tool,
                 include_apply_patch_tool: config.include_apply_patch_tool,
                 include_web_search_request: config.tools_web_search_request,
+                web_search_backend: config.web_search.backend,
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
+                include_git_tool: config.include_git_tool,
+                include_fetch_url_tool: config.include_fetch_url_tool,
+                include_file_tools: config.include_file_tools,
+                include_search_code_tool: config.include_search_code_tool,
+                include_memory_tool: config.memory.enabled,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
             }),
             user_instructions,
@@ -448,6 +715,8 @@ impl Session {
             approval_policy,
             sandbox_policy,
             shell_environment_policy: config.shell_environment_policy.clone(),
+            sandbox_resource_limits: config.sandbox_resource_limits,
+            exec_timeout: config.exec_timeout,
             cwd,
             is_review_mode: false,
             final_output_json_schema: None,
@@ -461,6 +730,38 @@ impl Session {
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            context_pruning: config.context_pruning,
+            escalate_patch_risk: config.escalate_patch_risk,
+            sandbox_rules: config.sandbox_rules.clone(),
+            sandbox_audit: config.sandbox_audit.then(|| config.codex_home.clone()),
+            url_attachments: UrlAttachments::new(
+                config.url_attachments.clone(),
+                config.network_proxy.clone(),
+                config
+                    .model_provider
+                    .ca_certificates
+                    .clone()
+                    .unwrap_or_default(),
+            ),
+            custom_tools: CustomTools::new(config.custom_tools.clone()),
+            web_search: WebSearch::new(config.web_search.clone()),
+            session_budget_usd: config.session_budget_usd,
+            max_turns: config.max_turns,
+            max_runtime: config.max_runtime,
+            report_failure_tool: config.include_report_failure_tool,
+            max_parallel_tool_calls: config.max_parallel_tool_calls,
+            auto_commit: AutoCommitter::new(&config.auto_commit, conversation_id),
+            change_journal: ChangeJournal::default(),
+            post_patch_hooks: config.hooks.post_patch.clone(),
+            lifecycle_hooks: LifecycleHooks::new(
+                &config.hooks,
+                conversation_id,
+                turn_context.cwd.clone(),
+            ),
+            memory: config.memory.enabled.then(|| config.codex_home.clone()),
+            plugins,
+            background_jobs: BackgroundJobManager::default(),
+            output_overflow: Arc::default(),
         };
 
         let sess = Arc::new(Session {
@@ -470,6 +771,28 @@ impl Session {
             active_turn: Mutex::new(None),
             services,
             next_internal_sub_id: AtomicU64::new(0),
+            queued_input_ready: Notify::new(),
+        });
+
+        auth_manager.spawn_refresh_task();
+        let mut login_required = auth_manager.subscribe_login_required();
+        let weak_sess = Arc::downgrade(&sess);
+        tokio::spawn(async move {
+            loop {
+                let message = match login_required.recv().await {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(sess) = weak_sess.upgrade() else {
+                    break;
+                };
+                sess.send_event(Event {
+                    id: INITIAL_SUBMIT_ID.to_owned(),
+                    msg: EventMsg::LoginRequired(LoginRequiredEvent { message }),
+                })
+                .await;
+            }
         });
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
@@ -488,12 +811,20 @@ impl Session {
                 history_entry_count,
                 initial_messages,
                 rollout_path,
+                sandbox_capability: Some(sandbox_capability(&turn_context.sandbox_policy)),
             }),
         })
         .chain(post_session_configured_error_events.into_iter());
         for event in events {
             sess.send_event(event).await;
         }
+        sess.notify_hooks(
+            INITIAL_SUBMIT_ID,
+            HookEvent::SessionStart {
+                model: &turn_context.client.get_model(),
+            },
+        )
+        .await;
 
         Ok((sess, turn_context))
     }
@@ -521,10 +852,20 @@ impl Session {
                 let persist = matches!(conversation_history, InitialHistory::Forked(_));
 
                 // Always add response items to conversation history
-                let reconstructed_history =
-                    self.reconstruct_history_from_rollout(turn_context, &rollout_items);
-                if !reconstructed_history.is_empty() {
-                    self.record_into_history(&reconstructed_history).await;
+                let reconstructed = self.reconstruct_from_rollout(turn_context, &rollout_items);
+                if !reconstructed.history.is_empty() {
+                    self.record_into_history(&reconstructed.history).await;
+                }
+                // So that turns and checkpoints from before the resume can
+                // still be rolled back.
+                self.services
+                    .change_journal
+                    .restore(reconstructed.change_journal)
+                    .await;
+                {
+                    let mut state = self.state.lock().await;
+                    state.checkpoints = reconstructed.checkpoints;
+                    state.changed_paths = reconstructed.changed_paths;
                 }
 
                 // If persisting, persist all rollout items as-is (recorder filters)
@@ -537,9 +878,15 @@ impl Session {
 
     /// Persist the event to rollout and send it to clients.
     pub(crate) async fn send_event(&self, event: Event) {
+        if let EventMsg::Error(error) = &event.msg {
+            otel::record_error(error.kind);
+        }
         // Persist the event into rollout (recorder filters as needed)
         let rollout_items = vec![RolloutItem::EventMsg(event.msg.clone())];
         self.persist_rollout_items(&rollout_items).await;
+        self.services
+            .notifier
+            .forward_event(self.conversation_id, &event);
         if let Err(e) = self.tx_event.send(event).await {
             error!("failed to send tool call event: {e}");
         }
@@ -570,17 +917,75 @@ impl Session {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
         }
 
+        self.notifier()
+            .notify(&UserNotification::ApprovalRequested {
+                turn_id: event_id.clone(),
+                call_id: call_id.clone(),
+                summary: shlex::try_join(command.iter().map(String::as_str))
+                    .unwrap_or_else(|_| command.join(" ")),
+            });
         let event = Event {
-            id: event_id,
+            id: event_id.clone(),
             msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
-                call_id,
-                command,
+                call_id: call_id.clone(),
+                command: command.clone(),
                 cwd,
                 reason,
             }),
         };
         self.send_event(event).await;
-        rx_approve.await.unwrap_or_default()
+        let decision = rx_approve.await.unwrap_or_default();
+        self.review_decision_with_hooks(
+            &event_id,
+            &call_id,
+            Some(command.as_slice()),
+            None,
+            None,
+            decision,
+        )
+        .await
+    }
+
+    /// Runs the approval hooks on the user's `decision`, which a hook can turn
+    /// from an approval into a denial.
+    pub(crate) async fn review_decision_with_hooks(
+        &self,
+        sub_id: &str,
+        call_id: &str,
+        command: Option<&[String]>,
+        files: Option<&[PathBuf]>,
+        mcp_server: Option<&str>,
+        decision: ReviewDecision,
+    ) -> ReviewDecision {
+        let outcome = self
+            .run_hooks(
+                sub_id,
+                HookEvent::Approval {
+                    turn_id: sub_id,
+                    call_id,
+                    command,
+                    files,
+                    mcp_server,
+                    decision,
+                },
+            )
+            .await;
+        for message in outcome.messages {
+            self.notify_background_event(sub_id, message).await;
+        }
+        match outcome.blocked {
+            Some(reason)
+                if matches!(
+                    decision,
+                    ReviewDecision::Approved | ReviewDecision::ApprovedForSession
+                ) =>
+            {
+                self.notify_background_event(sub_id, format!("Approval {reason}"))
+                    .await;
+                ReviewDecision::Denied
+            }
+            _ => decision,
+        }
     }
 
     pub async fn request_patch_approval(
@@ -590,6 +995,7 @@ impl Session {
         action: &ApplyPatchAction,
         reason: Option<String>,
         grant_root: Option<PathBuf>,
+        risk: Option<PatchRisk>,
     ) -> oneshot::Receiver<ReviewDecision> {
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
@@ -608,19 +1014,90 @@ impl Session {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
         }
 
+        let changes = convert_apply_patch_to_protocol(action);
+        let mut paths: Vec<String> = changes
+            .keys()
+            .map(|path| path.display().to_string())
+            .collect();
+        paths.sort();
+        self.notifier()
+            .notify(&UserNotification::ApprovalRequested {
+                turn_id: event_id.clone(),
+                call_id: call_id.clone(),
+                summary: format!("edit {}", paths.join(", ")),
+            });
         let event = Event {
             id: event_id,
             msg: EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                 call_id,
-                changes: convert_apply_patch_to_protocol(action),
+                changes,
                 reason,
                 grant_root,
+                risk,
             }),
         };
         self.send_event(event).await;
         rx_approve
     }
 
+    /// Asks the user whether an MCP server may sample the model, unless they
+    /// already allowed the server for the session. Without a running turn
+    /// there is nobody to ask and the request is denied.
+    pub(crate) async fn request_mcp_sampling_approval(
+        &self,
+        event: McpSamplingApprovalRequestEvent,
+    ) -> ReviewDecision {
+        let server = event.server.clone();
+        if self
+            .state
+            .lock()
+            .await
+            .mcp_sampling_servers
+            .contains(&server)
+        {
+            return ReviewDecision::ApprovedForSession;
+        }
+
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let event_id = format!("mcp-sampling-{}", Uuid::new_v4());
+        let turn_id = {
+            let mut active = self.active_turn.lock().await;
+            let Some(at) = active.as_mut() else {
+                return ReviewDecision::Denied;
+            };
+            let Some(turn_id) = at.tasks.keys().next().cloned() else {
+                return ReviewDecision::Denied;
+            };
+            let mut ts = at.turn_state.lock().await;
+            ts.insert_pending_approval(event_id.clone(), tx_approve);
+            turn_id
+        };
+
+        self.send_event(Event {
+            id: event_id.clone(),
+            msg: EventMsg::McpSamplingApprovalRequest(event),
+        })
+        .await;
+        let decision = rx_approve.await.unwrap_or_default();
+        let decision = self
+            .review_decision_with_hooks(&turn_id, &event_id, None, None, Some(&server), decision)
+            .await;
+        if decision == ReviewDecision::ApprovedForSession {
+            self.state.lock().await.mcp_sampling_servers.insert(server);
+        }
+        decision
+    }
+
+    /// Tokens MCP servers have used through sampling this session.
+    pub(crate) async fn mcp_sampling_tokens(&self) -> u64 {
+        self.state.lock().await.mcp_sampling_tokens
+    }
+
+    pub(crate) async fn add_mcp_sampling_tokens(&self, tokens: u64) {
+        let mut state = self.state.lock().await;
+        state.mcp_sampling_tokens = state.mcp_sampling_tokens.saturating_add(tokens);
+    }
+
     pub async fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut active = self.active_turn.lock().await;
@@ -642,6 +1119,85 @@ impl Session {
         }
     }
 
+    /// Approves the pending patch request `sub_id` with an edited patch, which
+    /// is applied in place of the proposed one.
+    pub async fn notify_patch_edit(&self, sub_id: &str, patch: String, note: Option<String>) {
+        {
+            let mut active = self.active_turn.lock().await;
+            if let Some(at) = active.as_mut() {
+                let mut ts = at.turn_state.lock().await;
+                ts.insert_patch_edit(sub_id.to_string(), PatchEdit { patch, note });
+            }
+        }
+        self.notify_approval(sub_id, ReviewDecision::Approved).await;
+    }
+
+    /// The edited patch the user approved for the request `sub_id`, if any.
+    pub(crate) async fn take_patch_edit(&self, sub_id: &str) -> Option<PatchEdit> {
+        let mut active = self.active_turn.lock().await;
+        let at = active.as_mut()?;
+        let mut ts = at.turn_state.lock().await;
+        ts.take_patch_edit(sub_id)
+    }
+
+    /// Asks the user for the secret `name`. Returns `None` if the user
+    /// declined or the turn was interrupted.
+    async fn request_secret(
+        &self,
+        sub_id: String,
+        call_id: String,
+        name: String,
+        reason: Option<String>,
+    ) -> Option<SecretValue> {
+        let (tx_secret, rx_secret) = oneshot::channel();
+        let event_id = sub_id.clone();
+        let prev_entry = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.insert_pending_secret(sub_id, tx_secret)
+                }
+                None => None,
+            }
+        };
+        if prev_entry.is_some() {
+            warn!("Overwriting existing pending secret request for sub_id: {event_id}");
+        }
+
+        let event = Event {
+            id: event_id,
+            msg: EventMsg::SecretRequest(SecretRequestEvent {
+                call_id,
+                name,
+                reason,
+            }),
+        };
+        self.send_event(event).await;
+        rx_secret.await.ok().flatten()
+    }
+
+    pub async fn notify_secret(&self, sub_id: &str, value: Option<SecretValue>) {
+        let entry = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.remove_pending_secret(sub_id)
+                }
+                None => None,
+            }
+        };
+        match entry {
+            Some(tx_secret) => {
+                tx_secret.send(value).ok();
+            }
+            None => {
+                warn!("No pending secret request found for sub_id: {sub_id}");
+            }
+        }
+    }
+
     pub async fn add_approved_command(&self, cmd: Vec<String>) {
         let mut state = self.state.lock().await;
         state.add_approved_command(cmd);
@@ -654,31 +1210,81 @@ impl Session {
         self.persist_rollout_response_items(items).await;
     }
 
-    fn reconstruct_history_from_rollout(
+    fn reconstruct_from_rollout(
         &self,
         turn_context: &TurnContext,
         rollout_items: &[RolloutItem],
-    ) -> Vec<ResponseItem> {
+    ) -> ReconstructedSession {
         let mut history = ConversationHistory::new();
+        let mut checkpoints: HashMap<String, Checkpoint> = HashMap::new();
+        let mut change_journal = ChangeJournal::default();
+        let mut changed_paths = BTreeSet::new();
         for item in rollout_items {
             match item {
                 RolloutItem::ResponseItem(response_item) => {
                     history.record_items(std::iter::once(response_item));
                 }
+                RolloutItem::FileChanges(changes) => {
+                    change_journal.replay_changes(changes);
+                    changed_paths.extend(changes.files.iter().map(|file| file.path.clone()));
+                }
                 RolloutItem::Compacted(compacted) => {
                     let snapshot = history.contents();
-                    let user_messages = collect_user_messages(&snapshot);
-                    let rebuilt = build_compacted_history(
-                        self.build_initial_context(turn_context),
-                        &user_messages,
-                        &compacted.message,
-                    );
+                    let initial_context = self.build_initial_context(turn_context);
+                    let rebuilt = match compacted.retained_items {
+                        Some(retained_items) => build_rolling_compacted_history(
+                            initial_context,
+                            &snapshot,
+                            retained_items,
+                            &compacted.message,
+                        ),
+                        None => build_compacted_history(
+                            initial_context,
+                            &collect_user_messages(&snapshot),
+                            &compacted.message,
+                        ),
+                    };
                     history.replace(rebuilt);
                 }
+                RolloutItem::EventMsg(EventMsg::ContextPruned(pruned)) => {
+                    let mut snapshot = history.contents();
+                    context_pruning::apply_pruning(&mut snapshot, &pruned.items);
+                    history.replace(snapshot);
+                }
+                RolloutItem::EventMsg(EventMsg::ModelChanged(changed))
+                    if changed.provider != changed.previous_provider =>
+                {
+                    history.replace(reencode_for_provider(history.contents()));
+                }
+                RolloutItem::EventMsg(EventMsg::CheckpointCreated(created)) => {
+                    let checkpoint = Checkpoint {
+                        history: history.contents(),
+                        journal_mark: change_journal.replay_mark(),
+                    };
+                    checkpoints.insert(created.name.clone(), checkpoint);
+                }
+                RolloutItem::EventMsg(EventMsg::CheckpointRestored(restored))
+                    if restored.restored =>
+                {
+                    if let Some(checkpoint) = checkpoints.get(&restored.name).cloned() {
+                        history.replace(checkpoint.history);
+                        change_journal.replay_revert_to(checkpoint.journal_mark);
+                        checkpoints
+                            .retain(|_, later| later.journal_mark <= checkpoint.journal_mark);
+                    }
+                }
+                RolloutItem::EventMsg(EventMsg::TurnReverted(reverted)) => {
+                    change_journal.replay_revert_last_turn(reverted);
+                }
                 _ => {}
             }
         }
-        history.contents()
+        ReconstructedSession {
+            history: history.contents(),
+            checkpoints,
+            change_journal,
+            changed_paths,
+        }
     }
 
     /// Append ResponseItems to the in-memory conversation history only.
@@ -727,6 +1333,33 @@ impl Session {
         }
     }
 
+    /// Try to get back under the auto-compaction limit by pruning tool outputs
+    /// that are least relevant to the current task. Returns `true` if anything
+    /// was pruned; otherwise the caller should compact instead.
+    async fn prune_context(&self, sub_id: &str, tokens_to_free: u64) -> bool {
+        let Some(scorer) = context_pruning::scorer_for(self.services.context_pruning) else {
+            return false;
+        };
+        let event = {
+            let mut state = self.state.lock().await;
+            let mut items = state.history_snapshot();
+            let Some(event) =
+                context_pruning::plan_pruning(&items, scorer.as_ref(), tokens_to_free)
+            else {
+                return false;
+            };
+            context_pruning::apply_pruning(&mut items, &event.items);
+            state.replace_history(items);
+            event
+        };
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::ContextPruned(event),
+        })
+        .await;
+        true
+    }
+
     pub(crate) async fn history_snapshot(&self) -> Vec<ResponseItem> {
         let state = self.state.lock().await;
         state.history_snapshot()
@@ -736,14 +1369,21 @@ impl Session {
         &self,
         sub_id: &str,
         turn_context: &TurnContext,
+        client: &ModelClient,
         token_usage: Option<&TokenUsage>,
     ) {
         {
             let mut state = self.state.lock().await;
             if let Some(token_usage) = token_usage {
+                otel::record_token_usage(
+                    &client.get_model(),
+                    &client.get_provider().name,
+                    token_usage,
+                );
                 state.update_token_info_from_usage(
                     token_usage,
                     turn_context.client.get_model_context_window(),
+                    client.token_prices(),
                 );
             }
         }
@@ -758,14 +1398,31 @@ impl Session {
         self.send_token_count_event(sub_id).await;
     }
 
+    async fn update_estimated_prompt_tokens(&self, sub_id: &str, tokens: u64) {
+        {
+            let mut state = self.state.lock().await;
+            state.set_estimated_prompt_tokens(tokens);
+        }
+        self.send_token_count_event(sub_id).await;
+    }
+
+    async fn estimated_cost_usd(&self) -> Option<f64> {
+        self.state.lock().await.estimated_cost_usd()
+    }
+
     async fn send_token_count_event(&self, sub_id: &str) {
-        let (info, rate_limits) = {
+        let (info, rate_limits, estimated_prompt_tokens) = {
             let state = self.state.lock().await;
-            state.token_info_and_rate_limits()
+            let (info, rate_limits) = state.token_info_and_rate_limits();
+            (info, rate_limits, state.estimated_prompt_tokens)
         };
         let event = Event {
             id: sub_id.to_string(),
-            msg: EventMsg::TokenCount(TokenCountEvent { info, rate_limits }),
+            msg: EventMsg::TokenCount(TokenCountEvent {
+                info,
+                rate_limits,
+                estimated_prompt_tokens,
+            }),
         };
         self.send_event(event).await;
     }
@@ -804,6 +1461,7 @@ impl Session {
             command_for_display,
             cwd,
             apply_patch,
+            approval: _,
         } = exec_command_context;
         let msg = match apply_patch {
             Some(ApplyPatchCommandContext {
@@ -811,6 +1469,7 @@ impl Session {
                 changes,
             }) => {
                 turn_diff_tracker.on_patch_begin(&changes);
+                turn_diff_tracker.add_patch_risk(assess_patch_risk(&changes, &cwd));
 
                 EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
                     call_id,
@@ -842,6 +1501,7 @@ impl Session {
         call_id: &str,
         output: &ExecToolCallOutput,
         is_apply_patch: bool,
+        sandbox_denied: bool,
     ) {
         let ExecToolCallOutput {
             stdout,
@@ -854,7 +1514,7 @@ impl Session {
         // Send full stdout/stderr to clients; do not truncate.
         let stdout = stdout.text.clone();
         let stderr = stderr.text.clone();
-        let formatted_output = format_exec_output_str(output);
+        let formatted_output = format_exec_output_str(output, None);
         let aggregated_output: String = aggregated_output.text.clone();
 
         let msg = if is_apply_patch {
@@ -873,6 +1533,7 @@ impl Session {
                 exit_code: *exit_code,
                 duration: *duration,
                 formatted_output,
+                sandbox_denied,
             })
         };
 
@@ -887,7 +1548,10 @@ impl Session {
         if is_apply_patch {
             let unified_diff = turn_diff_tracker.get_unified_diff();
             if let Ok(Some(unified_diff)) = unified_diff {
-                let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
+                let msg = EventMsg::TurnDiff(TurnDiffEvent {
+                    unified_diff,
+                    risk: turn_diff_tracker.patch_risk(),
+                });
                 let event = Event {
                     id: sub_id.into(),
                     msg,
@@ -913,7 +1577,14 @@ impl Session {
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
 
-        let result = process_exec_tool_call(
+        let patch_snapshot = match &begin_ctx.apply_patch {
+            Some(apply_patch) => {
+                Some(PatchSnapshot::capture(&begin_ctx.cwd, &apply_patch.changes).await)
+            }
+            None => None,
+        };
+
+        let mut result = process_exec_tool_call(
             exec_args.params,
             exec_args.sandbox_type,
             exec_args.sandbox_policy,
@@ -923,6 +1594,24 @@ impl Session {
         )
         .await;
 
+        // Formatters run before the patch is journaled or committed, so that
+        // both see the formatted files.
+        if let Some(apply_patch) = &begin_ctx.apply_patch
+            && let Ok(output) = &mut result
+            && output.exit_code == 0
+            && let Some(report) = run_post_patch_hooks(
+                &self.services.post_patch_hooks,
+                &begin_ctx.cwd,
+                &apply_patch.changes,
+            )
+            .await
+        {
+            for stream in [&mut output.stdout, &mut output.aggregated_output] {
+                stream.text.push_str(&report);
+                stream.text.push('\n');
+            }
+        }
+
         let output_stderr;
         let borrowed: &ExecToolCallOutput = match &result {
             Ok(output) => output,
@@ -945,15 +1634,104 @@ impl Session {
             &call_id,
             borrowed,
             is_apply_patch,
+            matches!(result, Err(CodexErr::Sandbox(SandboxErr::Denied { .. }))),
         )
         .await;
 
+        if let Some(snapshot) = patch_snapshot
+            && borrowed.exit_code == 0
+        {
+            self.state
+                .lock()
+                .await
+                .changed_paths
+                .extend(snapshot.paths().cloned());
+            let changes = self.services.change_journal.record(&sub_id, snapshot).await;
+            self.persist_rollout_items(&[RolloutItem::FileChanges(changes)])
+                .await;
+        }
+
+        if let Some(committer) = &self.services.auto_commit
+            && let Some(apply_patch) = &begin_ctx.apply_patch
+            && borrowed.exit_code == 0
+        {
+            match committer
+                .commit_patch(&begin_ctx.cwd, &apply_patch.changes)
+                .await
+            {
+                Ok(Some(notice)) => self.notify_background_event(&sub_id, notice).await,
+                Ok(None) => {}
+                Err(e) => {
+                    self.notify_background_event(&sub_id, format!("Auto-commit failed: {e}"))
+                        .await;
+                }
+            }
+        }
+
+        if let Some(codex_home) = &self.services.sandbox_audit {
+            let record = SandboxAuditRecord::executed(
+                self.conversation_id,
+                call_id,
+                begin_ctx.command_for_display,
+                begin_ctx.cwd,
+                exec_args.sandbox_type,
+                exec_args.sandbox_policy.clone(),
+                begin_ctx.approval,
+                &result,
+            )
+            .redacted(&self.state.lock().await.secrets);
+            sandbox_audit::append_record(codex_home, &record).await;
+        }
+
         result
     }
 
+    /// Records a command that was kept from running in the sandbox audit log.
+    async fn audit_blocked_exec(
+        &self,
+        call_id: &str,
+        command: &[String],
+        cwd: &Path,
+        sandbox_policy: &SandboxPolicy,
+        approval: AuditApproval,
+        reason: Option<String>,
+    ) {
+        if let Some(codex_home) = &self.services.sandbox_audit {
+            let record = SandboxAuditRecord::blocked(
+                self.conversation_id,
+                call_id.to_string(),
+                command.to_vec(),
+                cwd.to_path_buf(),
+                sandbox_policy.clone(),
+                approval,
+                reason,
+            );
+            sandbox_audit::append_record(codex_home, &record).await;
+        }
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
+    /// Runs the `[hooks]` configured for `event`, telling the user about the
+    /// hooks that failed.
+    pub(crate) async fn run_hooks(&self, sub_id: &str, event: HookEvent<'_>) -> HookOutcome {
+        let outcome = self.services.lifecycle_hooks.run(&event).await;
+        for failure in &outcome.failures {
+            self.notify_background_event(sub_id, failure.clone()).await;
+        }
+        outcome
+    }
+
+    /// Runs the hooks for an event they cannot veto, showing their notes to
+    /// the user.
+    pub(crate) async fn notify_hooks(&self, sub_id: &str, event: HookEvent<'_>) {
+        let outcome = self.run_hooks(sub_id, event).await;
+        for message in outcome.messages {
+            self.notify_background_event(sub_id, message).await;
+        }
+    }
+
     async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
         let event = Event {
             id: sub_id.to_string(),
@@ -997,29 +1775,146 @@ impl Session {
         }
     }
 
-    pub async fn get_pending_input(&self) -> Vec<ResponseInputItem> {
-        let mut active = self.active_turn.lock().await;
-        match active.as_mut() {
-            Some(at) => {
-                let mut ts = at.turn_state.lock().await;
-                ts.take_pending_input()
-            }
-            None => Vec::with_capacity(0),
+    /// Queues `input` to start a turn once the running task finishes.
+    /// Returns the input if there was no task running to wait for.
+    async fn queue_input(&self, sub_id: &str, input: Vec<InputItem>) -> Result<(), Vec<InputItem>> {
+        // Hold the turn while queueing so a task finishing meanwhile sees the
+        // new input when it checks the queue.
+        let active = self.active_turn.lock().await;
+        if active.is_none() {
+            return Err(input);
         }
+        let queued = {
+            let mut state = self.state.lock().await;
+            state.queue_input(sub_id.to_string(), input);
+            state.queued_input_snapshot()
+        };
+        drop(active);
+        self.send_queued_input_event(sub_id, queued, None).await;
+        Ok(())
     }
 
-    pub async fn call_tool(
+    /// Takes input out of the queue; `submitted` tells clients whether it
+    /// is about to be sent to the model or was dropped.
+    async fn remove_queued_input(
         &self,
-        server: &str,
-        tool: &str,
-        arguments: Option<serde_json::Value>,
-    ) -> anyhow::Result<CallToolResult> {
+        sub_id: &str,
+        id: u64,
+        submitted: bool,
+    ) -> Option<QueuedInput> {
+        let (removed, queued) = {
+            let mut state = self.state.lock().await;
+            let removed = state.remove_queued_input(id)?;
+            (removed, state.queued_input_snapshot())
+        };
+        let submitted = submitted.then(|| removed.to_protocol());
+        self.send_queued_input_event(sub_id, queued, submitted)
+            .await;
+        Some(removed)
+    }
+
+    /// Drops all queued input, e.g. because the user interrupted the task
+    /// it was waiting for or asked for it with [`Op::ClearQueuedUserInput`].
+    async fn clear_queued_input(&self, sub_id: &str) {
+        let cleared = {
+            let mut state = self.state.lock().await;
+            !std::mem::take(&mut state.queued_input).is_empty()
+        };
+        if cleared {
+            self.send_queued_input_event(sub_id, Vec::new(), None).await;
+        }
+    }
+
+    /// Drops the input queued by `submission_ids`, e.g. the turns of a batch
+    /// that a client cancels.
+    async fn remove_queued_submissions(&self, sub_id: &str, submission_ids: &[String]) {
+        let queued = {
+            let mut state = self.state.lock().await;
+            state
+                .remove_queued_submissions(submission_ids)
+                .then(|| state.queued_input_snapshot())
+        };
+        if let Some(queued) = queued {
+            self.send_queued_input_event(sub_id, queued, None).await;
+        }
+    }
+
+    /// Starts a turn with the oldest queued input if no task is running.
+    async fn submit_next_queued_input(self: &Arc<Self>, turn_context: Arc<TurnContext>) {
+        let next = {
+            let active = self.active_turn.lock().await;
+            if active.is_some() {
+                return;
+            }
+            self.state.lock().await.queued_input.pop_front()
+        };
+        let Some(next) = next else {
+            return;
+        };
+        let queued = self.state.lock().await.queued_input_snapshot();
+        self.send_queued_input_event(&next.sub_id, queued, Some(next.to_protocol()))
+            .await;
+        self.spawn_task(turn_context, next.sub_id, next.items, RegularTask)
+            .await;
+    }
+
+    async fn send_queued_input_event(
+        &self,
+        sub_id: &str,
+        queued: Vec<QueuedUserInput>,
+        submitted: Option<QueuedUserInput>,
+    ) {
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::QueuedUserInput(QueuedUserInputEvent { queued, submitted }),
+        })
+        .await;
+    }
+
+    /// Lets the submission loop start the next queued input, if any.
+    pub(crate) fn wake_queued_input(&self) {
+        self.queued_input_ready.notify_one();
+    }
+
+    pub async fn get_pending_input(&self) -> Vec<ResponseInputItem> {
+        let mut active = self.active_turn.lock().await;
+        match active.as_mut() {
+            Some(at) => {
+                let mut ts = at.turn_state.lock().await;
+                ts.take_pending_input()
+            }
+            None => Vec::with_capacity(0),
+        }
+    }
+
+    pub async fn call_tool(
+        &self,
+        server: &str,
+        tool: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> anyhow::Result<CallToolResult> {
         self.services
             .mcp_connection_manager
             .call_tool(server, tool, arguments)
             .await
     }
 
+    /// Tells MCP servers about a new working directory or sandbox roots.
+    async fn update_mcp_roots(&self, turn_context: &TurnContext) {
+        self.services
+            .mcp_connection_manager
+            .set_roots(workspace_roots(
+                &turn_context.cwd,
+                &turn_context.sandbox_policy,
+            ))
+            .await;
+    }
+
+    /// Health changes of MCP servers that have not been reported yet.
+    pub(crate) fn take_mcp_status_changes(&self) -> Vec<McpServerStatusEvent> {
+        self.services.mcp_connection_manager.take_status_changes()
+    }
+
     pub async fn interrupt_task(self: &Arc<Self>) {
         info!("interrupt received: abort current task, if any");
         self.abort_all_tasks(TurnAbortReason::Interrupted).await;
@@ -1042,6 +1937,11 @@ impl Session {
         &self.services.notifier
     }
 
+    /// Risk level at which patches always need approval.
+    pub(crate) fn escalate_patch_risk(&self) -> Option<PatchRiskLevel> {
+        self.services.escalate_patch_risk
+    }
+
     fn user_shell(&self) -> &shell::Shell {
         &self.services.user_shell
     }
@@ -1064,6 +1964,8 @@ pub(crate) struct ExecCommandContext {
     pub(crate) command_for_display: Vec<String>,
     pub(crate) cwd: PathBuf,
     pub(crate) apply_patch: Option<ApplyPatchCommandContext>,
+    /// How the command was approved, for the sandbox audit log.
+    pub(crate) approval: AuditApproval,
 }
 
 #[derive(Clone, Debug)]
@@ -1077,27 +1979,68 @@ async fn submission_loop(
     turn_context: TurnContext,
     config: Arc<Config>,
     rx_sub: Receiver<Submission>,
+    mut rx_mcp_sampling: mpsc::Receiver<McpSamplingRequest>,
 ) {
     // Wrap once to avoid cloning TurnContext for each task.
     let mut turn_context = Arc::new(turn_context);
     // To break out of this loop, send Op::Shutdown.
-    while let Ok(sub) = rx_sub.recv().await {
+    loop {
+        let sub = tokio::select! {
+            sub = rx_sub.recv() => match sub {
+                Ok(sub) => sub,
+                Err(_) => break,
+            },
+            _ = sess.queued_input_ready.notified() => {
+                sess.submit_next_queued_input(Arc::clone(&turn_context)).await;
+                continue;
+            }
+            Some(request) = rx_mcp_sampling.recv() => {
+                // Sampling waits on the user and the model; keep taking
+                // submissions meanwhile.
+                tokio::spawn(handle_sampling_request(
+                    Arc::clone(&sess),
+                    Arc::clone(&turn_context),
+                    config.mcp_sampling,
+                    request,
+                ));
+                continue;
+            }
+        };
         debug!(?sub, "Submission");
         match sub.op {
             Op::Interrupt => {
                 sess.interrupt_task().await;
+                sess.clear_queued_input(&sub.id).await;
             }
             Op::OverrideTurnContext {
                 cwd,
                 approval_policy,
                 sandbox_policy,
                 model,
+                provider,
                 effort,
                 summary,
             } => {
                 // Recalculate the persistent turn context with provided overrides.
                 let prev = Arc::clone(&turn_context);
-                let provider = prev.client.get_provider();
+                let previous_provider_id = prev.client.get_provider_id();
+                let (provider_id, provider) = match provider {
+                    Some(id) => match config.model_providers.get(&id) {
+                        Some(info) => (id, info.clone()),
+                        None => {
+                            let event = Event {
+                                id: sub.id,
+                                msg: EventMsg::Error(ErrorEvent {
+                                    message: format!("Model provider `{id}` not found"),
+                                    kind: ErrorKind::Other,
+                                }),
+                            };
+                            sess.send_event(event).await;
+                            continue;
+                        }
+                    },
+                    None => (previous_provider_id.clone(), prev.client.get_provider()),
+                };
 
                 // Effective model + family
                 let (effective_model, effective_family) = if let Some(ref m) = model {
@@ -1118,6 +2061,8 @@ async fn submission_loop(
                 let mut updated_config = (*config).clone();
                 updated_config.model = effective_model.clone();
                 updated_config.model_family = effective_family.clone();
+                updated_config.model_provider_id = provider_id.clone();
+                updated_config.model_provider = provider.clone();
                 if let Some(model_info) = get_model_info(&effective_family) {
                     updated_config.model_context_window = Some(model_info.context_window);
                 }
@@ -1142,12 +2087,19 @@ async fn submission_loop(
                     include_plan_tool: config.include_plan_tool,
                     include_apply_patch_tool: config.include_apply_patch_tool,
                     include_web_search_request: config.tools_web_search_request,
+                    web_search_backend: config.web_search.backend,
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
+                    include_git_tool: config.include_git_tool,
+                    include_fetch_url_tool: config.include_fetch_url_tool,
+                    include_file_tools: config.include_file_tools,
+                    include_search_code_tool: config.include_search_code_tool,
+                    include_memory_tool: config.memory.enabled,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
                 });
 
                 let new_turn_context = TurnContext {
+                    provider_fallbacks: ProviderFallbacks::new(client.fallback_clients()),
                     client,
                     tools_config,
                     user_instructions: prev.user_instructions.clone(),
@@ -1155,6 +2107,8 @@ async fn submission_loop(
                     approval_policy: new_approval_policy,
                     sandbox_policy: new_sandbox_policy.clone(),
                     shell_environment_policy: prev.shell_environment_policy.clone(),
+                    sandbox_resource_limits: prev.sandbox_resource_limits,
+                    exec_timeout: prev.exec_timeout,
                     cwd: new_cwd.clone(),
                     is_review_mode: false,
                     final_output_json_schema: None,
@@ -1162,6 +2116,27 @@ async fn submission_loop(
 
                 // Install the new persistent context for subsequent tasks/turns.
                 turn_context = Arc::new(new_turn_context);
+                sess.update_mcp_roots(&turn_context).await;
+
+                // Items produced by the previous provider mean nothing to the
+                // new one; the switch is recorded so resume can do the same.
+                if provider_id != previous_provider_id {
+                    let items = reencode_for_provider(sess.history_snapshot().await);
+                    sess.replace_history(items).await;
+                }
+                let previous_model = prev.client.get_model();
+                if effective_model != previous_model || provider_id != previous_provider_id {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::ModelChanged(ModelChangedEvent {
+                            model: effective_model,
+                            provider: provider_id,
+                            previous_model,
+                            previous_provider: previous_provider_id,
+                        }),
+                    };
+                    sess.send_event(event).await;
+                }
 
                 // Optionally persist changes to model / effort
                 if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
@@ -1183,6 +2158,49 @@ async fn submission_loop(
                         .await;
                 }
             }
+            Op::QueueUserInput { items } => {
+                if let Err(items) = sess.queue_input(&sub.id, items).await {
+                    // no task to wait for, start the turn right away
+                    sess.spawn_task(Arc::clone(&turn_context), sub.id, items, RegularTask)
+                        .await;
+                }
+            }
+            Op::UserInputWithOverrides { items, overrides } => {
+                if sess.active_turn.lock().await.is_some() {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: "Settings for a single turn cannot be applied while a task is running.".to_string(),
+                            kind: ErrorKind::Other,
+                        }),
+                    };
+                    sess.send_event(event).await;
+                } else {
+                    // The session's own context stays in place for later turns.
+                    let turn_context = Arc::new(turn_context.with_overrides(&overrides));
+                    sess.spawn_task(turn_context, sub.id, items, RegularTask)
+                        .await;
+                }
+            }
+            Op::RemoveQueuedUserInput { id } => {
+                sess.remove_queued_input(&sub.id, id, false).await;
+            }
+            Op::ClearQueuedUserInput => {
+                sess.clear_queued_input(&sub.id).await;
+            }
+            Op::RemoveQueuedSubmissions { submission_ids } => {
+                sess.remove_queued_submissions(&sub.id, &submission_ids)
+                    .await;
+            }
+            Op::SteerQueuedUserInput { id } => {
+                if let Some(queued) = sess.remove_queued_input(&sub.id, id, true).await
+                    && let Err(items) = sess.inject_input(queued.items).await
+                {
+                    // the task finished meanwhile, start a turn instead
+                    sess.spawn_task(Arc::clone(&turn_context), queued.sub_id, items, RegularTask)
+                        .await;
+                }
+            }
             Op::UserTurn {
                 items,
                 cwd,
@@ -1223,15 +2241,22 @@ async fn submission_loop(
                     );
 
                     let fresh_turn_context = TurnContext {
+                        provider_fallbacks: ProviderFallbacks::new(client.fallback_clients()),
                         client,
                         tools_config: ToolsConfig::new(&ToolsConfigParams {
                             model_family: &model_family,
                             include_plan_tool: config.include_plan_tool,
                             include_apply_patch_tool: config.include_apply_patch_tool,
                             include_web_search_request: config.tools_web_search_request,
+                            web_search_backend: config.web_search.backend,
                             use_streamable_shell_tool: config
                                 .use_experimental_streamable_shell_tool,
                             include_view_image_tool: config.include_view_image_tool,
+                            include_git_tool: config.include_git_tool,
+                            include_fetch_url_tool: config.include_fetch_url_tool,
+                            include_file_tools: config.include_file_tools,
+                            include_search_code_tool: config.include_search_code_tool,
+                            include_memory_tool: config.memory.enabled,
                             experimental_unified_exec_tool: config
                                 .use_experimental_unified_exec_tool,
                         }),
@@ -1240,6 +2265,8 @@ async fn submission_loop(
                         approval_policy,
                         sandbox_policy,
                         shell_environment_policy: turn_context.shell_environment_policy.clone(),
+                        sandbox_resource_limits: turn_context.sandbox_resource_limits,
+                        exec_timeout: turn_context.exec_timeout,
                         cwd,
                         is_review_mode: false,
                         final_output_json_schema,
@@ -1255,6 +2282,7 @@ async fn submission_loop(
 
                     // Install the new persistent context for subsequent tasks/turns.
                     turn_context = Arc::new(fresh_turn_context);
+                    sess.update_mcp_roots(&turn_context).await;
 
                     // no current task, spawn a new one with the per-turn context
                     sess.spawn_task(Arc::clone(&turn_context), sub.id, items, RegularTask)
@@ -1273,6 +2301,16 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other).await,
             },
+            Op::PatchApprovalEdited { id, patch, note } => {
+                sess.notify_patch_edit(&id, patch, note).await;
+            }
+            Op::McpSamplingApproval { id, decision } => match decision {
+                ReviewDecision::Abort => {
+                    sess.interrupt_task().await;
+                }
+                other => sess.notify_approval(&id, other).await,
+            },
+            Op::ProvideSecret { id, value, .. } => sess.notify_secret(&id, value).await,
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1330,15 +2368,58 @@ async fn submission_loop(
                 };
                 sess.send_event(event).await;
             }
+            Op::GetContextUsage => {
+                let input = sess.history_snapshot().await;
+                let prompt = build_turn_prompt(&sess, turn_context.as_ref(), input, None);
+                let tokens = count_prompt_tokens(&prompt, &turn_context.client.get_model_family());
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::ContextUsage(ContextUsageEvent {
+                        total_tokens: tokens.total(),
+                        model_context_window: turn_context.client.get_model_context_window(),
+                        parts: tokens.into_parts(),
+                    }),
+                };
+                sess.send_event(event).await;
+            }
+            Op::GetTokenUsage => {
+                sess.send_token_count_event(&sub.id).await;
+            }
+            Op::SetWireLogging { enabled } => {
+                match set_wire_logging(&config.codex_home, sess.conversation_id, enabled) {
+                    Ok(Some(path)) => {
+                        let message = format!(
+                            "Wire logging on: model requests and responses are written to {}",
+                            path.display()
+                        );
+                        sess.notify_background_event(&sub.id, message).await;
+                    }
+                    Ok(None) => {
+                        sess.notify_background_event(&sub.id, "Wire logging off")
+                            .await;
+                    }
+                    Err(e) => {
+                        let event = Event {
+                            id: sub.id.clone(),
+                            msg: EventMsg::Error(ErrorEvent {
+                                message: format!("Failed to start the wire log: {e}"),
+                                kind: ErrorKind::Other,
+                            }),
+                        };
+                        sess.send_event(event).await;
+                    }
+                }
+            }
             Op::ListCustomPrompts => {
                 let sub_id = sub.id.clone();
 
-                let custom_prompts: Vec<CustomPrompt> =
+                let mut custom_prompts: Vec<CustomPrompt> =
                     if let Some(dir) = crate::custom_prompts::default_prompts_dir() {
                         crate::custom_prompts::discover_prompts_in(&dir).await
                     } else {
                         Vec::new()
                     };
+                custom_prompts.extend(extension_prompts(&config.extensions).await);
 
                 let event = Event {
                     id: sub_id,
@@ -1348,6 +2429,99 @@ async fn submission_loop(
                 };
                 sess.send_event(event).await;
             }
+            Op::ResumeBackgroundResponse { response_id } => {
+                sess.spawn_task(
+                    Arc::clone(&turn_context),
+                    sub.id,
+                    Vec::new(),
+                    BackgroundResponseTask { response_id },
+                )
+                .await;
+            }
+            Op::RevertTurn { force } => {
+                let msg = if sess.active_turn.lock().await.is_some() {
+                    EventMsg::Error(ErrorEvent {
+                        message: "A turn cannot be reverted while a task is running.".to_string(),
+                        kind: ErrorKind::Other,
+                    })
+                } else {
+                    match sess.services.change_journal.revert_last_turn(force).await {
+                        Ok(event) => EventMsg::TurnReverted(event),
+                        Err(e) => EventMsg::Error(ErrorEvent {
+                            message: format!("Failed to revert the last turn: {e}"),
+                            kind: ErrorKind::Other,
+                        }),
+                    }
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::CreateCheckpoint { name } => {
+                let name = name.trim().to_string();
+                let msg = if name.is_empty() {
+                    EventMsg::Error(ErrorEvent {
+                        message: "A checkpoint needs a name.".to_string(),
+                        kind: ErrorKind::Other,
+                    })
+                } else {
+                    let checkpoint = Checkpoint {
+                        history: sess.history_snapshot().await,
+                        journal_mark: sess.services.change_journal.mark().await,
+                    };
+                    let mut state = sess.state.lock().await;
+                    state.checkpoints.insert(name.clone(), checkpoint);
+                    EventMsg::CheckpointCreated(CheckpointCreatedEvent { name })
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::RestoreCheckpoint { name, force } => {
+                let msg = if sess.active_turn.lock().await.is_some() {
+                    EventMsg::Error(ErrorEvent {
+                        message: "A checkpoint cannot be restored while a task is running."
+                            .to_string(),
+                        kind: ErrorKind::Other,
+                    })
+                } else {
+                    restore_checkpoint(&sess, name, force).await
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::CreatePullRequest => {
+                let sess = sess.clone();
+                let cwd = turn_context.cwd.clone();
+                let integrations = config.integrations.clone();
+                let sub_id = sub.id.clone();
+                tokio::spawn(async move {
+                    let items = sess.history_snapshot().await;
+                    let draft = crate::pull_request::draft_pull_request(
+                        &collect_user_messages(&items),
+                        get_last_assistant_message_from_turn(&items).as_deref(),
+                        sess.conversation_id,
+                    );
+                    let changed_paths: Vec<PathBuf> = sess
+                        .state
+                        .lock()
+                        .await
+                        .changed_paths
+                        .iter()
+                        .cloned()
+                        .collect();
+                    let msg = match crate::pull_request::create_pull_request(
+                        &integrations,
+                        &cwd,
+                        &draft,
+                        &changed_paths,
+                    )
+                    .await
+                    {
+                        Ok(event) => EventMsg::PullRequestCreated(event),
+                        Err(message) => EventMsg::Error(ErrorEvent {
+                            message: format!("Could not open a pull request: {message}"),
+                            kind: ErrorKind::Other,
+                        }),
+                    };
+                    sess.send_event(Event { id: sub_id, msg }).await;
+                });
+            }
             Op::Compact => {
                 // Attempt to inject input into current task
                 if let Err(items) = sess
@@ -1362,7 +2536,12 @@ async fn submission_loop(
             }
             Op::Shutdown => {
                 sess.abort_all_tasks(TurnAbortReason::Interrupted).await;
+                sess.services.background_jobs.kill_running().await;
                 info!("Shutting down Codex instance");
+                sess.notify_hooks(&sub.id, HookEvent::SessionEnd).await;
+                if let Err(e) = set_wire_logging(&config.codex_home, sess.conversation_id, false) {
+                    warn!("failed to close the wire log: {e}");
+                }
 
                 // Gracefully flush and shutdown rollout recorder on session end so tests
                 // that inspect the rollout file do not race with the background writer.
@@ -1378,6 +2557,7 @@ async fn submission_loop(
                         id: sub.id.clone(),
                         msg: EventMsg::Error(ErrorEvent {
                             message: "Failed to shutdown rollout recorder".to_string(),
+                            kind: ErrorKind::Other,
                         }),
                     };
                     sess.send_event(event).await;
@@ -1417,6 +2597,13 @@ async fn submission_loop(
                 };
                 sess.send_event(event).await;
             }
+            Op::AddAnnotation { target, comment } => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::AnnotationAdded(AnnotationAddedEvent { target, comment }),
+                };
+                sess.send_event(event).await;
+            }
             Op::Review { review_request } => {
                 spawn_review_thread(
                     sess.clone(),
@@ -1435,6 +2622,47 @@ async fn submission_loop(
     debug!("Agent loop exited");
 }
 
+/// Rolls the conversation and the file changes back to the checkpoint
+/// `name`. Checkpoints made after it are forgotten, since the file changes
+/// they would restore are gone.
+async fn restore_checkpoint(sess: &Session, name: String, force: bool) -> EventMsg {
+    let checkpoint = sess.state.lock().await.checkpoints.get(&name).cloned();
+    let Some(checkpoint) = checkpoint else {
+        return EventMsg::Error(ErrorEvent {
+            message: format!("No checkpoint named `{name}`."),
+            kind: ErrorKind::Other,
+        });
+    };
+    let (reverted, conflicts) = match sess
+        .services
+        .change_journal
+        .revert_to(checkpoint.journal_mark, force)
+        .await
+    {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            return EventMsg::Error(ErrorEvent {
+                message: format!("Failed to restore checkpoint `{name}`: {e}"),
+                kind: ErrorKind::Other,
+            });
+        }
+    };
+    let restored = force || conflicts.is_empty();
+    if restored {
+        let mut state = sess.state.lock().await;
+        state.replace_history(checkpoint.history);
+        state
+            .checkpoints
+            .retain(|_, later| later.journal_mark <= checkpoint.journal_mark);
+    }
+    EventMsg::CheckpointRestored(CheckpointRestoredEvent {
+        name,
+        restored,
+        reverted,
+        conflicts,
+    })
+}
+
 /// Spawn a review thread using the given prompt.
 async fn spawn_review_thread(
     sess: Arc<Session>,
@@ -1451,8 +2679,14 @@ async fn spawn_review_thread(
         include_plan_tool: false,
         include_apply_patch_tool: config.include_apply_patch_tool,
         include_web_search_request: false,
+        web_search_backend: WebSearchBackend::Native,
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
+        include_git_tool: false,
+        include_fetch_url_tool: false,
+        include_file_tools: false,
+        include_search_code_tool: false,
+        include_memory_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
     });
 
@@ -1483,6 +2717,7 @@ async fn spawn_review_thread(
     );
 
     let review_turn_context = TurnContext {
+        provider_fallbacks: ProviderFallbacks::new(client.fallback_clients()),
         client,
         tools_config,
         user_instructions: None,
@@ -1490,6 +2725,8 @@ async fn spawn_review_thread(
         approval_policy: parent_turn_context.approval_policy,
         sandbox_policy: parent_turn_context.sandbox_policy.clone(),
         shell_environment_policy: parent_turn_context.shell_environment_policy.clone(),
+        sandbox_resource_limits: parent_turn_context.sandbox_resource_limits,
+        exec_timeout: parent_turn_context.exec_timeout,
         cwd: parent_turn_context.cwd.clone(),
         is_review_mode: true,
         final_output_json_schema: None,
@@ -1539,6 +2776,43 @@ pub(crate) async fn run_task(
     if input.is_empty() {
         return None;
     }
+    let span = turn_span(&turn_context, &sub_id);
+    run_task_from(sess, turn_context, sub_id, input, None)
+        .instrument(span)
+        .await
+}
+
+/// Runs a task whose first model response is the already running background
+/// response `response_id` rather than a new request.
+pub(crate) async fn run_background_response_task(
+    sess: Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    sub_id: String,
+    response_id: String,
+) -> Option<String> {
+    let span = turn_span(&turn_context, &sub_id);
+    run_task_from(sess, turn_context, sub_id, Vec::new(), Some(response_id))
+        .instrument(span)
+        .await
+}
+
+/// The span a task runs in, see [`crate::otel`].
+fn turn_span(turn_context: &TurnContext, sub_id: &str) -> tracing::Span {
+    info_span!(
+        "turn",
+        sub_id,
+        model = %turn_context.client.get_model(),
+        review = turn_context.is_review_mode,
+    )
+}
+
+async fn run_task_from(
+    sess: Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    sub_id: String,
+    input: Vec<InputItem>,
+    mut resume_response_id: Option<String>,
+) -> Option<String> {
     let event = Event {
         id: sub_id.clone(),
         msg: EventMsg::TaskStarted(TaskStartedEvent {
@@ -1547,19 +2821,27 @@ pub(crate) async fn run_task(
     };
     sess.send_event(event).await;
 
-    let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     // For review threads, keep an isolated in-memory history so the
     // model sees a fresh conversation without the parent session's history.
     // For normal turns, continue recording to the session history as before.
     let is_review_mode = turn_context.is_review_mode;
     let mut review_thread_history: Vec<ResponseItem> = Vec::new();
-    if is_review_mode {
-        // Seed review threads with environment context so the model knows the working directory.
-        review_thread_history.extend(sess.build_initial_context(turn_context.as_ref()));
-        review_thread_history.push(initial_input_for_turn.into());
-    } else {
-        sess.record_input_and_rollout_usermsg(&initial_input_for_turn)
-            .await;
+    // A resumed background response was prompted by input that the
+    // original session already recorded.
+    if !input.is_empty() {
+        let (input, notices) = sess.services.url_attachments.expand(input).await;
+        for notice in notices {
+            sess.notify_background_event(&sub_id, notice).await;
+        }
+        let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
+        if is_review_mode {
+            // Seed review threads with environment context so the model knows the working directory.
+            review_thread_history.extend(sess.build_initial_context(turn_context.as_ref()));
+            review_thread_history.push(initial_input_for_turn.into());
+        } else {
+            sess.record_input_and_rollout_usermsg(&initial_input_for_turn)
+                .await;
+        }
     }
 
     let mut last_agent_message: Option<String> = None;
@@ -1567,8 +2849,27 @@ pub(crate) async fn run_task(
     // many turns, from the perspective of the user, it is a single turn.
     let mut turn_diff_tracker = TurnDiffTracker::new();
     let mut auto_compact_recently_attempted = false;
+    let mut context_recently_pruned = false;
+    let mut output_schema_retries = 0;
+
+    let task_started = Instant::now();
+    let mut turns: u32 = 0;
 
     loop {
+        // A task that has used up its budget sends no more requests.
+        if let Some(message) = exceeded_budget(&sess, turns, task_started).await {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message,
+                    kind: ErrorKind::BudgetExceeded,
+                }),
+            };
+            sess.send_event(event).await;
+            break;
+        }
+        turns += 1;
+
         // Note that pending_input would be something like a message the user
         // submitted through the UI while the model was running. Though the UI
         // may support this, the model might not.
@@ -1612,12 +2913,68 @@ pub(crate) async fn run_task(
                 })
             })
             .collect();
+        let prompt = build_turn_prompt(
+            &sess,
+            turn_context.as_ref(),
+            turn_input,
+            resume_response_id.take(),
+        );
+
+        // Count the request locally so an oversized context is trimmed
+        // before sending it, rather than failing at the provider.
+        let estimated_tokens =
+            count_prompt_tokens(&prompt, &turn_context.client.get_model_family()).total();
+        sess.update_estimated_prompt_tokens(&sub_id, estimated_tokens)
+            .await;
+        let context_window = turn_context.client.get_model_context_window();
+        if let Some(budget) = context_budget(context_window)
+            && estimated_tokens >= budget
+        {
+            if !is_review_mode
+                && !context_recently_pruned
+                && !auto_compact_recently_attempted
+                && prompt.resume_response_id.is_none()
+            {
+                sess.notify_background_event(
+                    &sub_id,
+                    format!(
+                        "The next request is about {estimated_tokens} tokens, over the context budget of {budget}; trimming the conversation first."
+                    ),
+                )
+                .await;
+                if sess
+                    .prune_context(
+                        &sub_id,
+                        context_pruning::tokens_to_free(estimated_tokens, budget),
+                    )
+                    .await
+                {
+                    context_recently_pruned = true;
+                } else {
+                    auto_compact_recently_attempted = true;
+                    compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone()).await;
+                }
+                continue;
+            }
+            if let Some(window) = context_window
+                && estimated_tokens > window
+            {
+                sess.notify_background_event(
+                    &sub_id,
+                    format!(
+                        "The next request is about {estimated_tokens} tokens, more than the model's context window of {window}."
+                    ),
+                )
+                .await;
+            }
+        }
+
         match run_turn(
             &sess,
             turn_context.as_ref(),
             &mut turn_diff_tracker,
             sub_id.clone(),
-            turn_input,
+            prompt,
         )
         .await
         {
@@ -1741,33 +3098,94 @@ pub(crate) async fn run_task(
                 }
 
                 if token_limit_reached {
-                    if auto_compact_recently_attempted {
-                        let limit_str = limit.to_string();
-                        let current_tokens = total_usage_tokens
-                            .map(|tokens| tokens.to_string())
-                            .unwrap_or_else(|| "unknown".to_string());
-                        let event = Event {
-                            id: sub_id.clone(),
-                            msg: EventMsg::Error(ErrorEvent {
-                                message: format!(
-                                    "Conversation is still above the token limit after automatic summarization (limit {limit_str}, current {current_tokens}). Please start a new session or trim your input."
-                                ),
-                            }),
-                        };
-                        sess.send_event(event).await;
-                        break;
+                    // Pruning is cheaper than compaction and keeps the full
+                    // conversation, so try it first. If the next turn is
+                    // still over the limit, compaction takes over.
+                    if !is_review_mode
+                        && !context_recently_pruned
+                        && !auto_compact_recently_attempted
+                        && let Some(tokens) = total_usage_tokens
+                        && sess
+                            .prune_context(
+                                &sub_id,
+                                context_pruning::tokens_to_free(tokens, limit as u64),
+                            )
+                            .await
+                    {
+                        context_recently_pruned = true;
+                    } else {
+                        if auto_compact_recently_attempted {
+                            let limit_str = limit.to_string();
+                            let current_tokens = total_usage_tokens
+                                .map(|tokens| tokens.to_string())
+                                .unwrap_or_else(|| "unknown".to_string());
+                            let event = Event {
+                                id: sub_id.clone(),
+                                msg: EventMsg::Error(ErrorEvent {
+                                    message: format!(
+                                        "Conversation is still above the token limit after automatic summarization (limit {limit_str}, current {current_tokens}). Please start a new session or trim your input."
+                                    ),
+                                    kind: ErrorKind::Other,
+                                }),
+                            };
+                            sess.send_event(event).await;
+                            break;
+                        }
+                        auto_compact_recently_attempted = true;
+                        compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone())
+                            .await;
+                        continue;
                     }
-                    auto_compact_recently_attempted = true;
-                    compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone()).await;
-                    continue;
+                } else {
+                    auto_compact_recently_attempted = false;
+                    context_recently_pruned = false;
                 }
 
-                auto_compact_recently_attempted = false;
-
                 if responses.is_empty() {
                     last_agent_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
+                    if !is_review_mode
+                        && !turn_context.active_client().enforces_output_schema()
+                        && let Some(schema) = &turn_context.final_output_json_schema
+                        && let Some(message) = &last_agent_message
+                    {
+                        match output_schema::check_final_message(message, schema) {
+                            Ok(json) => last_agent_message = Some(json.to_string()),
+                            Err(error) if output_schema_retries < MAX_OUTPUT_SCHEMA_RETRIES => {
+                                output_schema_retries += 1;
+                                sess.notify_background_event(
+                                    &sub_id,
+                                    format!(
+                                        "The final message does not match the output schema ({error}); asking the model to correct it."
+                                    ),
+                                )
+                                .await;
+                                let correction = ResponseItem::Message {
+                                    id: None,
+                                    role: "user".to_string(),
+                                    content: vec![ContentItem::InputText {
+                                        text: output_schema::retry_instructions(&error),
+                                    }],
+                                };
+                                sess.record_conversation_items(std::slice::from_ref(&correction))
+                                    .await;
+                                continue;
+                            }
+                            Err(error) => {
+                                let event = Event {
+                                    id: sub_id.clone(),
+                                    msg: EventMsg::Error(ErrorEvent {
+                                        message: format!(
+                                            "The final message does not match the output schema: {error}"
+                                        ),
+                                        kind: ErrorKind::Other,
+                                    }),
+                                };
+                                sess.send_event(event).await;
+                            }
+                        }
+                    }
                     sess.notifier()
                         .notify(&UserNotification::AgentTurnComplete {
                             turn_id: sub_id.clone(),
@@ -1784,6 +3202,7 @@ pub(crate) async fn run_task(
                     id: sub_id.clone(),
                     msg: EventMsg::Error(ErrorEvent {
                         message: e.to_string(),
+                        kind: e.error_kind(),
                     }),
                 };
                 sess.send_event(event).await;
@@ -1837,28 +3256,83 @@ fn parse_review_output_event(text: &str) -> ReviewOutputEvent {
     }
 }
 
-async fn run_turn(
+/// The prompt for a model request with `input` as the conversation.
+fn build_turn_prompt(
     sess: &Session,
     turn_context: &TurnContext,
-    turn_diff_tracker: &mut TurnDiffTracker,
-    sub_id: String,
     input: Vec<ResponseItem>,
-) -> CodexResult<TurnRunResult> {
-    let tools = get_openai_tools(
+    resume_response_id: Option<String>,
+) -> Prompt {
+    let mut tools = get_openai_tools(
         &turn_context.tools_config,
         Some(sess.services.mcp_connection_manager.list_all_tools()),
     );
+    tools.extend(sess.services.plugins.openai_tools());
+    tools.extend(sess.services.custom_tools.openai_tools());
+    if sess.services.report_failure_tool {
+        tools.push(create_report_failure_tool());
+    }
 
-    let prompt = Prompt {
+    Prompt {
         input,
         tools,
         base_instructions_override: turn_context.base_instructions.clone(),
         output_schema: turn_context.final_output_json_schema.clone(),
-    };
+        resume_response_id,
+    }
+}
+
+/// Why the task may send no more requests, if it has used up one of its
+/// budgets: `max_turns` model requests, `max_runtime` since it started, or the
+/// session's `session_budget_usd`.
+async fn exceeded_budget(sess: &Session, turns: u32, task_started: Instant) -> Option<String> {
+    if let Some(max_turns) = sess.services.max_turns
+        && turns >= max_turns
+    {
+        return Some(format!(
+            "This task has made {turns} model requests, reaching its limit of {max_turns} (`max_turns`); stopping."
+        ));
+    }
+    if let Some(max_runtime) = sess.services.max_runtime
+        && task_started.elapsed() >= max_runtime
+    {
+        return Some(format!(
+            "This task has run for {}s, reaching its limit of {}s (`max_runtime_secs`); stopping.",
+            task_started.elapsed().as_secs(),
+            max_runtime.as_secs()
+        ));
+    }
+    if let Some(budget) = sess.services.session_budget_usd
+        && let Some(spent) = sess.estimated_cost_usd().await
+        && spent >= budget
+    {
+        return Some(format!(
+            "This session has spent about ${spent:.2}, reaching its budget of ${budget:.2}. Start a new session, with a higher `session_budget_usd` if needed, to continue."
+        ));
+    }
+    None
+}
 
+async fn run_turn(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    prompt: Prompt,
+) -> CodexResult<TurnRunResult> {
     let mut retries = 0;
     loop {
-        match try_run_turn(sess, turn_context, turn_diff_tracker, &sub_id, &prompt).await {
+        let client = turn_context.active_client();
+        let span = info_span!(
+            "model_request",
+            model = %client.get_model(),
+            provider = %client.get_provider().name,
+            attempt = retries + 1,
+        );
+        match try_run_turn(sess, turn_context, turn_diff_tracker, &sub_id, &prompt)
+            .instrument(span)
+            .await
+        {
             Ok(output) => return Ok(output),
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
@@ -1872,7 +3346,10 @@ async fn run_turn(
             Err(CodexErr::UsageNotIncluded) => return Err(CodexErr::UsageNotIncluded),
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
-                let max_retries = turn_context.client.get_provider().stream_max_retries();
+                let max_retries = turn_context
+                    .active_client()
+                    .get_provider()
+                    .stream_max_retries();
                 if retries < max_retries {
                     retries += 1;
                     let delay = match e {
@@ -1896,7 +3373,29 @@ async fn run_turn(
 
                     tokio::time::sleep(delay).await;
                 } else {
-                    return Err(e);
+                    // Out of retries: continue on the next fallback provider
+                    // if this one looks unavailable.
+                    let from = turn_context.active_client().get_provider().name;
+                    let next = if is_provider_outage(&e) {
+                        turn_context.provider_fallbacks.advance()
+                    } else {
+                        None
+                    };
+                    let Some(next) = next else {
+                        return Err(e);
+                    };
+                    let to = next.get_provider().name;
+                    warn!("provider {from} unavailable - continuing the turn on {to}: {e}");
+                    sess.send_event(Event {
+                        id: sub_id.clone(),
+                        msg: EventMsg::ProviderSwitched(ProviderSwitchedEvent {
+                            from,
+                            to,
+                            reason: e.to_string(),
+                        }),
+                    })
+                    .await;
+                    retries = 0;
                 }
             }
         }
@@ -1990,9 +3489,35 @@ async fn try_run_turn(
         summary: turn_context.client.get_reasoning_summary(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
-    let mut stream = turn_context.client.clone().stream(&prompt).await?;
+    let client = turn_context.active_client();
+    let stream = client.clone().stream(&prompt).await;
+    // Report switches even when every endpoint failed.
+    for switch in client.take_endpoint_switches() {
+        sess.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::ProviderEndpointSwitched(ProviderEndpointSwitchedEvent {
+                provider: client.get_provider().name,
+                from: switch.from,
+                to: switch.to,
+                reason: switch.reason,
+            }),
+        })
+        .await;
+    }
+    // Restarts that finished in the background since the last tool call.
+    for status in sess.take_mcp_status_changes() {
+        sess.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::McpServerStatus(status),
+        })
+        .await;
+    }
+    let mut stream = stream?;
 
     let mut output = Vec::new();
+    // Read-only tool calls waiting to run together with the ones that follow.
+    let mut parallel_calls = Vec::new();
+    let max_parallel_tool_calls = sess.services.max_parallel_tool_calls;
 
     loop {
         // Poll the next item from the model stream. We must inspect *both* Ok and Err
@@ -2019,7 +3544,29 @@ async fn try_run_turn(
 
         match event {
             ResponseEvent::Created => {}
+            ResponseEvent::BackgroundResponseCreated { response_id } => {
+                let event = Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::BackgroundResponseStarted(BackgroundResponseStartedEvent {
+                        response_id,
+                    }),
+                };
+                sess.send_event(event).await;
+            }
             ResponseEvent::OutputItemDone(item) => {
+                if max_parallel_tool_calls > 1 && is_read_only_tool_call(&item) {
+                    parallel_calls.push(item);
+                    continue;
+                }
+                run_parallel_tool_calls(
+                    sess,
+                    turn_context,
+                    sub_id,
+                    std::mem::take(&mut parallel_calls),
+                    max_parallel_tool_calls,
+                    &mut output,
+                )
+                .await?;
                 let response = handle_response_item(
                     sess,
                     turn_context,
@@ -2048,12 +3595,29 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
-                sess.update_token_usage_info(sub_id, turn_context, token_usage.as_ref())
-                    .await;
+                run_parallel_tool_calls(
+                    sess,
+                    turn_context,
+                    sub_id,
+                    std::mem::take(&mut parallel_calls),
+                    max_parallel_tool_calls,
+                    &mut output,
+                )
+                .await?;
+                sess.update_token_usage_info(
+                    sub_id,
+                    turn_context,
+                    turn_context.active_client(),
+                    token_usage.as_ref(),
+                )
+                .await;
 
                 let unified_diff = turn_diff_tracker.get_unified_diff();
                 if let Ok(Some(unified_diff)) = unified_diff {
-                    let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
+                    let msg = EventMsg::TurnDiff(TurnDiffEvent {
+                        unified_diff,
+                        risk: turn_diff_tracker.patch_risk(),
+                    });
                     let event = Event {
                         id: sub_id.to_string(),
                         msg,
@@ -2110,12 +3674,233 @@ async fn try_run_turn(
     }
 }
 
+/// Runs read-only tool calls, up to `limit` at a time, and records their
+/// results in `output` in the order the model made the calls.
+async fn run_parallel_tool_calls(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    calls: Vec<ResponseItem>,
+    limit: usize,
+    output: &mut Vec<ProcessedResponseItem>,
+) -> CodexResult<()> {
+    if calls.is_empty() {
+        return Ok(());
+    }
+    let results: Vec<_> = futures::stream::iter(calls)
+        .map(|item| async move {
+            // Read-only calls never change files, so they have no diff to track.
+            let mut turn_diff_tracker = TurnDiffTracker::new();
+            let response = handle_response_item(
+                sess,
+                turn_context,
+                &mut turn_diff_tracker,
+                sub_id,
+                item.clone(),
+            )
+            .await;
+            (item, response)
+        })
+        .buffered(limit)
+        .collect()
+        .await;
+    for (item, response) in results {
+        output.push(ProcessedResponseItem {
+            item,
+            response: response?,
+        });
+    }
+    Ok(())
+}
+
+/// Handles an output item, running the `pre_tool_call` and `post_tool_call`
+/// hooks around tool calls.
 async fn handle_response_item(
     sess: &Session,
     turn_context: &TurnContext,
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: &str,
     item: ResponseItem,
+) -> CodexResult<Option<ResponseInputItem>> {
+    let Some(call) = ToolCallSummary::from_item(&item) else {
+        return dispatch_response_item(sess, turn_context, turn_diff_tracker, sub_id, item).await;
+    };
+    let span = info_span!("tool_call", tool = %call.tool, call_id = %call.call_id);
+    run_tool_call(sess, turn_context, turn_diff_tracker, sub_id, item, call)
+        .instrument(span)
+        .await
+}
+
+/// Runs a tool call with the configured tool call hooks around it.
+async fn run_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: &str,
+    item: ResponseItem,
+    call: ToolCallSummary,
+) -> CodexResult<Option<ResponseInputItem>> {
+    if !sess.services.lifecycle_hooks.has_tool_call_hooks() {
+        return dispatch_response_item(sess, turn_context, turn_diff_tracker, sub_id, item).await;
+    }
+
+    let pre = sess
+        .run_hooks(
+            sub_id,
+            HookEvent::PreToolCall {
+                turn_id: sub_id,
+                call_id: &call.call_id,
+                tool: &call.tool,
+                arguments: &call.arguments,
+            },
+        )
+        .await;
+    if let Some(reason) = pre.blocked {
+        return Ok(Some(call.output(reason)));
+    }
+
+    let mut output =
+        dispatch_response_item(sess, turn_context, turn_diff_tracker, sub_id, item).await?;
+    let mut messages = pre.messages;
+    if let Some(output) = &output {
+        let (content, success) = tool_output_text(output);
+        let post = sess
+            .run_hooks(
+                sub_id,
+                HookEvent::PostToolCall {
+                    turn_id: sub_id,
+                    call_id: &call.call_id,
+                    tool: &call.tool,
+                    arguments: &call.arguments,
+                    output: &content,
+                    success,
+                },
+            )
+            .await;
+        messages.extend(post.messages);
+    }
+    if let Some(output) = &mut output
+        && !messages.is_empty()
+    {
+        append_to_tool_output(output, &messages.join("\n"));
+    }
+    Ok(output)
+}
+
+/// A tool call, as the tool call hooks see it.
+struct ToolCallSummary {
+    call_id: String,
+    tool: String,
+    arguments: String,
+    custom: bool,
+}
+
+impl ToolCallSummary {
+    fn from_item(item: &ResponseItem) -> Option<Self> {
+        match item {
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => Some(Self {
+                call_id: call_id.clone(),
+                tool: name.clone(),
+                arguments: arguments.clone(),
+                custom: false,
+            }),
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => Some(Self {
+                call_id: call_id.clone(),
+                tool: name.clone(),
+                arguments: input.clone(),
+                custom: true,
+            }),
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => Some(Self {
+                call_id: call_id.clone().or_else(|| id.clone())?,
+                tool: "local_shell".to_string(),
+                arguments: serde_json::to_string(action).unwrap_or_default(),
+                custom: false,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The output reporting to the model that the call did not run.
+    fn output(&self, reason: String) -> ResponseInputItem {
+        if self.custom {
+            ResponseInputItem::CustomToolCallOutput {
+                call_id: self.call_id.clone(),
+                output: reason,
+            }
+        } else {
+            ResponseInputItem::FunctionCallOutput {
+                call_id: self.call_id.clone(),
+                output: FunctionCallOutputPayload {
+                    content: reason,
+                    success: Some(false),
+                },
+            }
+        }
+    }
+}
+
+/// The text of a tool call's output and whether the call succeeded.
+fn tool_output_text(output: &ResponseInputItem) -> (String, bool) {
+    match output {
+        ResponseInputItem::FunctionCallOutput { output, .. } => {
+            (output.content.clone(), output.success.unwrap_or(true))
+        }
+        ResponseInputItem::CustomToolCallOutput { output, .. } => (output.clone(), true),
+        ResponseInputItem::McpToolCallOutput { result, .. } => match result {
+            Ok(result) => (
+                serde_json::to_string(&result.content).unwrap_or_default(),
+                !result.is_error.unwrap_or(false),
+            ),
+            Err(e) => (e.clone(), false),
+        },
+        ResponseInputItem::Message { .. } => (String::new(), true),
+    }
+}
+
+/// Adds the notes of tool call hooks to the output the model sees.
+fn append_to_tool_output(output: &mut ResponseInputItem, note: &str) {
+    match output {
+        ResponseInputItem::FunctionCallOutput { output, .. } => {
+            output.content.push_str(&format!("\n\n{note}"));
+        }
+        ResponseInputItem::CustomToolCallOutput { output, .. } => {
+            output.push_str(&format!("\n\n{note}"));
+        }
+        ResponseInputItem::McpToolCallOutput { result, .. } => match result {
+            Ok(result) => result.content.push(mcp_types::ContentBlock::TextContent(
+                mcp_types::TextContent {
+                    annotations: None,
+                    text: note.to_string(),
+                    r#type: "text".to_string(),
+                },
+            )),
+            Err(e) => e.push_str(&format!("\n\n{note}")),
+        },
+        ResponseInputItem::Message { .. } => {}
+    }
+}
+
+async fn dispatch_response_item(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: &str,
+    item: ResponseItem,
 ) -> CodexResult<Option<ResponseInputItem>> {
     debug!(?item, "Output item");
     let output = match item {
@@ -2126,7 +3911,24 @@ async fn handle_response_item(
             ..
         } => {
             info!("FunctionCall: {name}({arguments})");
-            if let Some((server, tool_name)) =
+            if sess.services.plugins.has_tool(&name) {
+                let output = match sess
+                    .services
+                    .plugins
+                    .call_tool(&name, arguments, turn_context.cwd.clone())
+                    .await
+                {
+                    Ok(content) => FunctionCallOutputPayload {
+                        content,
+                        success: Some(true),
+                    },
+                    Err(FunctionCallError::RespondToModel(msg)) => FunctionCallOutputPayload {
+                        content: msg,
+                        success: Some(false),
+                    },
+                };
+                Some(ResponseInputItem::FunctionCallOutput { call_id, output })
+            } else if let Some((server, tool_name)) =
                 sess.services.mcp_connection_manager.parse_tool_name(&name)
             {
                 let resp = handle_mcp_tool_call(
@@ -2178,6 +3980,9 @@ async fn handle_response_item(
                 timeout_ms: action.timeout_ms,
                 with_escalated_permissions: None,
                 justification: None,
+                secrets: Vec::new(),
+                background: false,
+                tty: false,
             };
             let effective_call_id = match (call_id, id) {
                 (Some(call_id), _) => call_id,
@@ -2194,7 +3999,7 @@ async fn handle_response_item(
                 }
             };
 
-            let exec_params = to_exec_params(params, turn_context);
+            let exec_params = to_exec_params(params, turn_context, ExecMode::Foreground);
             {
                 let result = handle_container_exec_with_params(
                     exec_params,
@@ -2203,6 +4008,7 @@ async fn handle_response_item(
                     turn_diff_tracker,
                     sub_id.to_string(),
                     effective_call_id.clone(),
+                    ExecMode::Foreground,
                 )
                 .await;
 
@@ -2280,6 +4086,76 @@ async fn handle_response_item(
     Ok(output)
 }
 
+/// Applies `[[sandbox.rules]]` to a command started by the `exec_command` or
+/// `unified_exec` tools, which do not go through the shell tool's sandbox and
+/// approval flow: a `deny` rule rejects the command and an `ask` rule asks the
+/// user first. Input written to an already running session is not checked.
+async fn check_session_command_rules(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: &str,
+    command: Vec<String>,
+) -> Result<(), FunctionCallError> {
+    let rule_outcome = evaluate_sandbox_rules(
+        &sess.services.sandbox_rules,
+        &command,
+        &turn_context.cwd,
+        &turn_context.sandbox_policy,
+    );
+    let reason = match assess_sandbox_rule(
+        &rule_outcome,
+        turn_context.approval_policy,
+        &turn_context.sandbox_policy,
+        false,
+    ) {
+        Some(SafetyCheck::Reject { reason }) => reason,
+        Some(SafetyCheck::AskUser) => {
+            let decision = sess
+                .request_command_approval(
+                    sub_id.to_string(),
+                    call_id.to_string(),
+                    command.clone(),
+                    turn_context.cwd.clone(),
+                    None,
+                )
+                .await;
+            match decision {
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => return Ok(()),
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    sess.audit_blocked_exec(
+                        call_id,
+                        &command,
+                        &turn_context.cwd,
+                        &turn_context.sandbox_policy,
+                        AuditApproval::Denied,
+                        None,
+                    )
+                    .await;
+                    return Err(FunctionCallError::RespondToModel(
+                        "exec command rejected by user".to_string(),
+                    ));
+                }
+            }
+        }
+        // Allow rules only skip approval for sandboxed commands, and these
+        // tools run unsandboxed either way.
+        Some(SafetyCheck::AutoApprove { .. }) | None => return Ok(()),
+    };
+    sess.audit_blocked_exec(
+        call_id,
+        &command,
+        &turn_context.cwd,
+        &turn_context.sandbox_policy,
+        AuditApproval::Rejected,
+        Some(reason.clone()),
+    )
+    .await;
+    Err(FunctionCallError::RespondToModel(format!(
+        "exec command rejected: {reason:?}"
+    )))
+}
+
 async fn handle_unified_exec_tool_call(
     sess: &Session,
     session_id: Option<String>,
@@ -2342,16 +4218,40 @@ async fn handle_function_call(
 ) -> Result<String, FunctionCallError> {
     match name.as_str() {
         "container.exec" | "shell" => {
-            let params = parse_container_exec_arguments(arguments, turn_context, &call_id)?;
-            handle_container_exec_with_params(
+            let (mut params, secret_names, mode) =
+                parse_container_exec_arguments(arguments, turn_context, &call_id)?;
+            let secrets = resolve_secrets(
+                sess,
+                turn_context,
+                &sub_id,
+                &call_id,
+                secret_names,
+                params.justification.clone(),
+            )
+            .await?;
+            params.env.extend(
+                secrets
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.expose().to_string())),
+            );
+            let result = handle_container_exec_with_params(
                 params,
                 sess,
                 turn_context,
                 turn_diff_tracker,
                 sub_id,
                 call_id,
+                mode,
             )
-            .await
+            .await;
+            if secrets.is_empty() {
+                return result;
+            }
+            result
+                .map(|output| redact_secrets(&output, &secrets))
+                .map_err(|FunctionCallError::RespondToModel(output)| {
+                    FunctionCallError::RespondToModel(redact_secrets(&output, &secrets))
+                })
         }
         "unified_exec" => {
             #[derive(Deserialize)]
@@ -2369,6 +4269,16 @@ async fn handle_function_call(
                 ))
             })?;
 
+            if args.session_id.is_none() {
+                check_session_command_rules(
+                    sess,
+                    turn_context,
+                    &sub_id,
+                    &call_id,
+                    args.input.clone(),
+                )
+                .await?;
+            }
             handle_unified_exec_tool_call(sess, args.session_id, args.input, args.timeout_ms).await
         }
         "view_image" => {
@@ -2398,6 +4308,9 @@ async fn handle_function_call(
                     "failed to parse function arguments: {e:?}"
                 ))
             })?;
+            if args.dry_run {
+                return dry_run_apply_patch(&args.input, &turn_context.cwd);
+            }
             let exec_params = ExecParams {
                 command: vec!["apply_patch".to_string(), args.input.clone()],
                 cwd: turn_context.cwd.clone(),
@@ -2405,6 +4318,7 @@ async fn handle_function_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                resource_limits: turn_context.resource_limits(),
             };
             handle_container_exec_with_params(
                 exec_params,
@@ -2413,10 +4327,146 @@ async fn handle_function_call(
                 turn_diff_tracker,
                 sub_id,
                 call_id,
+                ExecMode::Foreground,
             )
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        JOB_RESULT_TOOL_NAME => handle_job_result(sess, arguments).await,
+        JOB_KILL_TOOL_NAME => handle_job_kill(sess, arguments).await,
+        READ_OUTPUT_TOOL_NAME => handle_read_output(sess, arguments).await,
+        GIT_TOOL_NAME => {
+            let args: GitToolArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            handle_git_tool_call(args, sess, turn_context, turn_diff_tracker, sub_id, call_id).await
+        }
+        WEB_SEARCH_TOOL_NAME => {
+            let args: WebSearchArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            handle_web_search(sess, args, &sub_id, call_id).await
+        }
+        READ_FILE_TOOL_NAME => {
+            let args: ReadFileArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let path = turn_context.resolve_path(Some(args.path.clone()));
+            check_readable(&path, &turn_context.sandbox_policy, &turn_context.cwd)
+                .and_then(|path| read_file(&path, &args))
+                .map_err(FunctionCallError::RespondToModel)
+        }
+        LIST_DIR_TOOL_NAME => {
+            let args: ListDirArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let path = turn_context.resolve_path(Some(args.path.clone()));
+            check_readable(&path, &turn_context.sandbox_policy, &turn_context.cwd)
+                .and_then(|path| list_dir(&path, &args))
+                .map_err(FunctionCallError::RespondToModel)
+        }
+        WRITE_FILE_TOOL_NAME => {
+            let args: WriteFileArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let path = turn_context.resolve_path(Some(args.path.clone()));
+            let patch =
+                write_file_patch(&path, &args).map_err(FunctionCallError::RespondToModel)?;
+            // Written as an apply_patch call so the same sandbox checks,
+            // approvals and diff tracking apply.
+            let exec_params = ExecParams {
+                command: vec!["apply_patch".to_string(), patch],
+                cwd: turn_context.cwd.clone(),
+                timeout_ms: None,
+                env: HashMap::new(),
+                with_escalated_permissions: None,
+                justification: None,
+                resource_limits: turn_context.resource_limits(),
+            };
+            handle_container_exec_with_params(
+                exec_params,
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                ExecMode::Foreground,
+            )
+            .await
+        }
+        REMEMBER_TOOL_NAME => {
+            let args: RememberArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let Some(codex_home) = &sess.services.memory else {
+                return Err(FunctionCallError::RespondToModel(
+                    "project memory is disabled".to_string(),
+                ));
+            };
+            remember(codex_home, &turn_context.cwd, &args.note)
+                .await
+                .map_err(|e| {
+                    FunctionCallError::RespondToModel(format!("could not save the note: {e}"))
+                })?;
+            Ok(format!(
+                "Saved. Future sessions in this project will see the note in {}.",
+                memory_path(codex_home, &turn_context.cwd).display()
+            ))
+        }
+        REPORT_FAILURE_TOOL_NAME if sess.services.report_failure_tool => {
+            let args: ReportFailureArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let active = sess.active_turn.lock().await;
+            if let Some(at) = active.as_ref() {
+                at.turn_state.lock().await.report_failure(args.reason);
+            }
+            Ok("Recorded: the task will be reported as failed. End your turn with a short summary."
+                .to_string())
+        }
+        SEARCH_CODE_TOOL_NAME => {
+            let args: SearchCodeArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let cwd = turn_context.cwd.clone();
+            tokio::task::spawn_blocking(move || search_code(&cwd, &args))
+                .await
+                .map_err(|e| FunctionCallError::RespondToModel(format!("search_code failed: {e}")))?
+                .map_err(FunctionCallError::RespondToModel)
+        }
+        FETCH_URL_TOOL_NAME => {
+            let args: FetchUrlArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            sess.services
+                .url_attachments
+                .fetch_page(&args.url, &turn_context.sandbox_policy)
+                .await
+                .map_err(|err| {
+                    FunctionCallError::RespondToModel(format!(
+                        "could not fetch {}: {err}",
+                        args.url
+                    ))
+                })
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params: ExecCommandParams = serde_json::from_str(&arguments).map_err(|e| {
@@ -2424,6 +4474,19 @@ async fn handle_function_call(
                     "failed to parse function arguments: {e:?}"
                 ))
             })?;
+            let flag = if exec_params.login { "-lc" } else { "-c" };
+            check_session_command_rules(
+                sess,
+                turn_context,
+                &sub_id,
+                &call_id,
+                vec![
+                    exec_params.shell.clone(),
+                    flag.to_string(),
+                    exec_params.cmd.clone(),
+                ],
+            )
+            .await?;
             let result = sess
                 .services
                 .session_manager
@@ -2449,7 +4512,22 @@ async fn handle_function_call(
                 .await
                 .map_err(FunctionCallError::RespondToModel)?;
 
-            Ok(result.to_text_output())
+            // Sessions started with the shell tool's `tty` option may have
+            // been given secrets.
+            let state = sess.state.lock().await;
+            Ok(redact_secrets(&result.to_text_output(), &state.secrets))
+        }
+        _ if sess.services.custom_tools.has_tool(&name) => {
+            handle_configured_tool(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                name,
+                arguments,
+                call_id,
+            )
+            .await
         }
         _ => Err(FunctionCallError::RespondToModel(format!(
             "unsupported call: {name}"
@@ -2457,6 +4535,215 @@ async fn handle_function_call(
     }
 }
 
+/// Runs a tool defined under `[tools.custom]`, shown to clients like a shell
+/// command. Unless the tool opts out with `sandbox = false`, the command goes
+/// through the same sandbox, rules and approval checks as a `shell` call.
+async fn handle_configured_tool(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    name: String,
+    arguments: String,
+    call_id: String,
+) -> Result<String, FunctionCallError> {
+    let arguments: serde_json::Value = if arguments.trim().is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::from_str(&arguments).map_err(|e| {
+            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+        })?
+    };
+    let custom_tools = &sess.services.custom_tools;
+    custom_tools
+        .check_arguments(&name, &arguments)
+        .map_err(|e| FunctionCallError::RespondToModel(format!("invalid arguments: {e}")))?;
+    let Some((command, cwd)) = custom_tools.command(&name, &turn_context.cwd) else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "unsupported call: {name}"
+        )));
+    };
+    let mut env = create_env(&turn_context.shell_environment_policy);
+    env.extend(argument_env(&arguments));
+
+    if custom_tools.runs_in_sandbox(&name) {
+        let params = ExecParams {
+            command,
+            cwd,
+            timeout_ms: Some(custom_tools.timeout(&name).as_millis() as u64),
+            env,
+            with_escalated_permissions: None,
+            justification: None,
+            resource_limits: turn_context.resource_limits(),
+        };
+        return handle_container_exec_with_params(
+            params,
+            sess,
+            turn_context,
+            turn_diff_tracker,
+            sub_id,
+            call_id,
+            ExecMode::Foreground,
+        )
+        .await;
+    }
+
+    let approval = if !custom_tools.needs_approval(&name) {
+        AuditApproval::Trusted
+    } else if sess
+        .state
+        .lock()
+        .await
+        .approved_commands_ref()
+        .contains(&command)
+    {
+        AuditApproval::Auto
+    } else if turn_context.approval_policy == AskForApproval::Never {
+        let reason = format!(
+            "custom tool {name} runs outside the sandbox and needs approval, but the approval policy is never"
+        );
+        sess.audit_blocked_exec(
+            &call_id,
+            &command,
+            &cwd,
+            &turn_context.sandbox_policy,
+            AuditApproval::Rejected,
+            Some(reason.clone()),
+        )
+        .await;
+        return Err(FunctionCallError::RespondToModel(format!(
+            "exec command rejected: {reason}"
+        )));
+    } else {
+        let decision = sess
+            .request_command_approval(
+                sub_id.clone(),
+                call_id.clone(),
+                command.clone(),
+                cwd.clone(),
+                Some(format!("custom tool {name} runs outside the sandbox")),
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved => AuditApproval::Approved,
+            ReviewDecision::ApprovedForSession => {
+                sess.add_approved_command(command.clone()).await;
+                AuditApproval::ApprovedForSession
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                sess.audit_blocked_exec(
+                    &call_id,
+                    &command,
+                    &cwd,
+                    &turn_context.sandbox_policy,
+                    AuditApproval::Denied,
+                    None,
+                )
+                .await;
+                return Err(FunctionCallError::RespondToModel(
+                    "exec command rejected by user".to_string(),
+                ));
+            }
+        }
+    };
+
+    sess.on_exec_command_begin(
+        turn_diff_tracker,
+        ExecCommandContext {
+            sub_id: sub_id.clone(),
+            call_id: call_id.clone(),
+            command_for_display: command.clone(),
+            cwd: cwd.clone(),
+            apply_patch: None,
+            approval,
+        },
+    )
+    .await;
+    let output = match custom_tools
+        .run(&name, &command, &cwd, env, &arguments)
+        .await
+    {
+        Ok(output) => output,
+        Err(message) => CustomToolOutput {
+            stdout: String::new(),
+            stderr: message,
+            exit_code: -1,
+            duration: Duration::ZERO,
+        },
+    };
+    let aggregated_output = format!("{}{}", output.stdout, output.stderr);
+    let exec_output = ExecToolCallOutput {
+        exit_code: output.exit_code,
+        stdout: StreamOutput::new(output.stdout),
+        stderr: StreamOutput::new(output.stderr),
+        aggregated_output: StreamOutput::new(aggregated_output),
+        duration: output.duration,
+        timed_out: false,
+    };
+    sess.on_exec_command_end(
+        turn_diff_tracker,
+        &sub_id,
+        &call_id,
+        &exec_output,
+        false,
+        false,
+    )
+    .await;
+    let content = format_exec_output(
+        &exec_output,
+        Some((&sess.services.output_overflow, call_id.as_str())),
+    );
+    let exit_code = exec_output.exit_code;
+    if let Some(codex_home) = &sess.services.sandbox_audit {
+        let record = SandboxAuditRecord::executed(
+            sess.conversation_id,
+            call_id,
+            command,
+            cwd,
+            SandboxType::None,
+            turn_context.sandbox_policy.clone(),
+            approval,
+            &Ok(exec_output),
+        )
+        .redacted(&sess.state.lock().await.secrets);
+        sandbox_audit::append_record(codex_home, &record).await;
+    }
+    if exit_code == 0 {
+        Ok(content)
+    } else {
+        Err(FunctionCallError::RespondToModel(content))
+    }
+}
+
+/// Runs a search for the `web_search` function tool, announcing it with the
+/// same events as a search run by the provider.
+async fn handle_web_search(
+    sess: &Session,
+    args: WebSearchArgs,
+    sub_id: &str,
+    call_id: String,
+) -> Result<String, FunctionCallError> {
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::WebSearchBegin(WebSearchBeginEvent {
+            call_id: call_id.clone(),
+        }),
+    })
+    .await;
+    let result = sess.services.web_search.search(&args.query).await;
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::WebSearchEnd(WebSearchEndEvent {
+            call_id,
+            query: args.query,
+        }),
+    })
+    .await;
+    result
+        .map(|results| results.to_string())
+        .map_err(FunctionCallError::RespondToModel)
+}
+
 async fn handle_custom_tool_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2476,6 +4763,7 @@ async fn handle_custom_tool_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                resource_limits: turn_context.resource_limits(),
             };
 
             handle_container_exec_with_params(
@@ -2485,6 +4773,7 @@ async fn handle_custom_tool_call(
                 turn_diff_tracker,
                 sub_id,
                 call_id,
+                ExecMode::Foreground,
             )
             .await
         }
@@ -2497,27 +4786,128 @@ async fn handle_custom_tool_call(
     }
 }
 
-fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> ExecParams {
+/// Checks where a patch would apply, without approval or a sandbox since
+/// nothing is written, and reports it to the model.
+fn dry_run_apply_patch(patch: &str, cwd: &Path) -> Result<String, FunctionCallError> {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    match codex_apply_patch::dry_run_patch(patch, cwd, &mut stdout, &mut stderr) {
+        Ok(()) => Ok(String::from_utf8_lossy(&stdout).into_owned()),
+        Err(_) => Err(FunctionCallError::RespondToModel(
+            String::from_utf8_lossy(&stderr).into_owned(),
+        )),
+    }
+}
+
+fn to_exec_params(
+    params: ShellToolCallParams,
+    turn_context: &TurnContext,
+    mode: ExecMode,
+) -> ExecParams {
+    let exec_timeout = turn_context.exec_timeout;
+    let timeout_ms = match mode {
+        ExecMode::Foreground => {
+            Some(exec_timeout.resolve(params.timeout_ms, exec_timeout.default_ms))
+        }
+        ExecMode::Background => {
+            Some(exec_timeout.resolve(params.timeout_ms, DEFAULT_BACKGROUND_JOB_TIMEOUT_MS))
+        }
+        // For a TTY command `timeout_ms` is how long to wait for output, not
+        // a time limit.
+        ExecMode::Tty => params.timeout_ms,
+    };
     ExecParams {
         command: params.command,
         cwd: turn_context.resolve_path(params.workdir.clone()),
-        timeout_ms: params.timeout_ms,
+        timeout_ms,
         env: create_env(&turn_context.shell_environment_policy),
         with_escalated_permissions: params.with_escalated_permissions,
         justification: params.justification,
+        resource_limits: turn_context.resource_limits(),
     }
 }
 
+/// How an approved shell command is run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExecMode {
+    /// Wait for the command and return its output.
+    Foreground,
+    /// Start it as a background job and return the job id.
+    Background,
+    /// Run it on a pseudo-terminal and return its output so far, leaving it
+    /// open for `write_stdin` if it is still running.
+    Tty,
+}
+
+/// Parses the arguments of the shell tool into the exec params, the names of
+/// the secrets the command needs, and how to run it.
 fn parse_container_exec_arguments(
     arguments: String,
     turn_context: &TurnContext,
     _call_id: &str,
-) -> Result<ExecParams, FunctionCallError> {
-    serde_json::from_str::<ShellToolCallParams>(&arguments)
-        .map(|p| to_exec_params(p, turn_context))
-        .map_err(|e| {
-            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
-        })
+) -> Result<(ExecParams, Vec<String>, ExecMode), FunctionCallError> {
+    let mut p = serde_json::from_str::<ShellToolCallParams>(&arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let mode = match (p.background, p.tty) {
+        (false, false) => ExecMode::Foreground,
+        (true, false) => ExecMode::Background,
+        (false, true) => ExecMode::Tty,
+        (true, true) => {
+            return Err(FunctionCallError::RespondToModel(
+                "`run_in_background` and `tty` cannot be combined; use `tty` and poll the session with `write_stdin` instead".to_string(),
+            ));
+        }
+    };
+    let secrets = std::mem::take(&mut p.secrets);
+    Ok((to_exec_params(p, turn_context, mode), secrets, mode))
+}
+
+/// Looks up the secrets a shell command needs, asking the user for the ones
+/// that were not supplied earlier in the session.
+async fn resolve_secrets(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: &str,
+    names: Vec<String>,
+    reason: Option<String>,
+) -> Result<HashMap<String, SecretValue>, FunctionCallError> {
+    let mut secrets = HashMap::new();
+    for name in names {
+        validate_secret_name(&name).map_err(FunctionCallError::RespondToModel)?;
+        let known = sess.state.lock().await.secret(&name);
+        let value = match known {
+            Some(value) => value,
+            None if turn_context.approval_policy == AskForApproval::Never => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "secret `{name}` is unavailable: the user cannot be asked for it under the current approval policy"
+                )));
+            }
+            None => {
+                let value = sess
+                    .request_secret(
+                        sub_id.to_string(),
+                        call_id.to_string(),
+                        name.clone(),
+                        reason.clone(),
+                    )
+                    .await;
+                let Some(value) = value else {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "the user declined to provide secret `{name}`"
+                    )));
+                };
+                sess.state
+                    .lock()
+                    .await
+                    .add_secret(name.clone(), value.clone());
+                value
+            }
+        };
+        secrets.insert(name, value);
+    }
+    Ok(secrets)
 }
 
 pub struct ExecInvokeArgs<'a> {
@@ -2547,6 +4937,9 @@ fn maybe_translate_shell_command(
     params
 }
 
+/// Runs a command through the approval and sandbox flow. `mode` decides
+/// whether an approved command runs in the foreground, as a background job,
+/// or on a pseudo-terminal; patches always run in the foreground.
 async fn handle_container_exec_with_params(
     params: ExecParams,
     sess: &Session,
@@ -2554,6 +4947,7 @@ async fn handle_container_exec_with_params(
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
     call_id: String,
+    mode: ExecMode,
 ) -> Result<String, FunctionCallError> {
     if params.with_escalated_permissions.unwrap_or(false)
         && !matches!(turn_context.approval_policy, AskForApproval::OnRequest)
@@ -2589,10 +4983,11 @@ async fn handle_container_exec_with_params(
         MaybeApplyPatchVerified::NotApplyPatch => None,
     };
 
-    let (params, safety, command_for_display) = match &apply_patch_exec {
+    let (params, safety, command_for_display, sandbox_policy_override) = match &apply_patch_exec {
         Some(ApplyPatchExec {
             action: ApplyPatchAction { patch, cwd, .. },
             user_explicitly_approved_this_action,
+            ..
         }) => {
             let path_to_codex = std::env::current_exe()
                 .ok()
@@ -2614,42 +5009,107 @@ async fn handle_container_exec_with_params(
                 env: HashMap::new(),
                 with_escalated_permissions: params.with_escalated_permissions,
                 justification: params.justification.clone(),
+                resource_limits: params.resource_limits,
             };
             let safety = if *user_explicitly_approved_this_action {
                 SafetyCheck::AutoApprove {
                     sandbox_type: SandboxType::None,
                 }
             } else {
-                assess_safety_for_untrusted_command(
+                match assess_safety_for_untrusted_command(
                     turn_context.approval_policy,
                     &turn_context.sandbox_policy,
                     params.with_escalated_permissions.unwrap_or(false),
-                )
+                ) {
+                    // The Codex executable that applies the patch is not
+                    // available inside the container, so the platform sandbox
+                    // enforces the same writable roots on the host instead.
+                    SafetyCheck::AutoApprove {
+                        sandbox_type: SandboxType::Container,
+                    } => match get_platform_sandbox() {
+                        Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+                        None => SafetyCheck::AskUser,
+                    },
+                    safety => safety,
+                }
             };
             (
                 params,
                 safety,
                 vec!["apply_patch".to_string(), patch.clone()],
+                None,
             )
         }
         None => {
-            let safety = {
-                let state = sess.state.lock().await;
-                assess_command_safety(
+            let rule_outcome = evaluate_sandbox_rules(
+                &sess.services.sandbox_rules,
+                &params.command,
+                &params.cwd,
+                &turn_context.sandbox_policy,
+            );
+            let sandbox_policy_override =
+                rule_outcome.sandbox_policy_override(&turn_context.sandbox_policy);
+            let rule_safety = assess_sandbox_rule(
+                &rule_outcome,
+                turn_context.approval_policy,
+                sandbox_policy_override
+                    .as_ref()
+                    .unwrap_or(&turn_context.sandbox_policy),
+                params.with_escalated_permissions.unwrap_or(false),
+            );
+            let safety = match rule_safety {
+                Some(safety) => safety,
+                None => {
+                    let state = sess.state.lock().await;
+                    assess_command_safety(
+                        &params.command,
+                        turn_context.approval_policy,
+                        &turn_context.sandbox_policy,
+                        state.approved_commands_ref(),
+                        params.with_escalated_permissions.unwrap_or(false),
+                    )
+                }
+            };
+            // The Linux and Windows sandboxes only protect denied paths that
+            // exist, so a command naming a missing one could create it.
+            let safety = if let SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::LinuxSeccomp | SandboxType::WindowsRestrictedToken,
+            } = safety
+                && let Some(path) = missing_denied_path(
                     &params.command,
-                    turn_context.approval_policy,
-                    &turn_context.sandbox_policy,
-                    state.approved_commands_ref(),
-                    params.with_escalated_permissions.unwrap_or(false),
-                )
+                    &params.cwd,
+                    sandbox_policy_override
+                        .as_ref()
+                        .unwrap_or(&turn_context.sandbox_policy),
+                ) {
+                SafetyCheck::Reject {
+                    reason: format!(
+                        "{} is on the sandbox deny list and the sandbox cannot stop the command from creating it",
+                        path.display()
+                    ),
+                }
+            } else {
+                safety
             };
             let command_for_display = params.command.clone();
-            (params, safety, command_for_display)
+            (params, safety, command_for_display, sandbox_policy_override)
         }
     };
 
-    let sandbox_type = match safety {
-        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+    let sandbox_policy = sandbox_policy_override
+        .as_ref()
+        .unwrap_or(&turn_context.sandbox_policy);
+    let (sandbox_type, approval) = match safety {
+        SafetyCheck::AutoApprove { sandbox_type } => {
+            let approval = match &apply_patch_exec {
+                Some(ApplyPatchExec {
+                    user_explicitly_approved_this_action: true,
+                    ..
+                }) => AuditApproval::Approved,
+                _ => AuditApproval::Auto,
+            };
+            (sandbox_type, approval)
+        }
         SafetyCheck::AskUser => {
             let decision = sess
                 .request_command_approval(
@@ -2660,30 +5120,52 @@ async fn handle_container_exec_with_params(
                     params.justification.clone(),
                 )
                 .await;
-            match decision {
-                ReviewDecision::Approved => (),
+            let approval = match decision {
+                ReviewDecision::Approved => AuditApproval::Approved,
                 ReviewDecision::ApprovedForSession => {
                     sess.add_approved_command(params.command.clone()).await;
+                    AuditApproval::ApprovedForSession
                 }
                 ReviewDecision::Denied | ReviewDecision::Abort => {
+                    sess.audit_blocked_exec(
+                        &call_id,
+                        &command_for_display,
+                        &params.cwd,
+                        sandbox_policy,
+                        AuditApproval::Denied,
+                        None,
+                    )
+                    .await;
                     return Err(FunctionCallError::RespondToModel(
                         "exec command rejected by user".to_string(),
                     ));
                 }
-            }
+            };
             // No sandboxing is applied because the user has given
             // explicit approval. Often, we end up in this case because
             // the command cannot be run in a sandbox, such as
             // installing a new dependency that requires network access.
-            SandboxType::None
+            (SandboxType::None, approval)
         }
         SafetyCheck::Reject { reason } => {
+            sess.audit_blocked_exec(
+                &call_id,
+                &command_for_display,
+                &params.cwd,
+                sandbox_policy,
+                AuditApproval::Rejected,
+                Some(reason.clone()),
+            )
+            .await;
             return Err(FunctionCallError::RespondToModel(format!(
                 "exec command rejected: {reason:?}"
             )));
         }
     };
 
+    let patch_note = apply_patch_exec
+        .as_ref()
+        .and_then(|apply_patch_exec| apply_patch_exec.note.clone());
     let exec_command_context = ExecCommandContext {
         sub_id: sub_id.clone(),
         call_id: call_id.clone(),
@@ -2693,14 +5175,44 @@ async fn handle_container_exec_with_params(
             |ApplyPatchExec {
                  action,
                  user_explicitly_approved_this_action,
+                 ..
              }| ApplyPatchCommandContext {
                 user_explicitly_approved_this_action,
                 changes: convert_apply_patch_to_protocol(&action),
             },
         ),
+        approval,
     };
 
     let params = maybe_translate_shell_command(params, sess, turn_context);
+    if exec_command_context.apply_patch.is_none() {
+        match mode {
+            ExecMode::Foreground => {}
+            ExecMode::Background => {
+                return Ok(start_background_job(
+                    sess,
+                    exec_command_context,
+                    params,
+                    sandbox_type,
+                    sandbox_policy.clone(),
+                    turn_context.cwd.clone(),
+                )
+                .await);
+            }
+            ExecMode::Tty => {
+                return run_exec_in_pty(
+                    sess,
+                    turn_diff_tracker,
+                    exec_command_context,
+                    params,
+                    sandbox_type,
+                    sandbox_policy,
+                    &turn_context.cwd,
+                )
+                .await;
+            }
+        }
+    }
     let output_result = sess
         .run_exec_with_events(
             turn_diff_tracker,
@@ -2708,7 +5220,7 @@ async fn handle_container_exec_with_params(
             ExecInvokeArgs {
                 params: params.clone(),
                 sandbox_type,
-                sandbox_policy: &turn_context.sandbox_policy,
+                sandbox_policy,
                 sandbox_cwd: &turn_context.cwd,
                 codex_linux_sandbox_exe: &sess.services.codex_linux_sandbox_exe,
                 stdout_stream: if exec_command_context.apply_patch.is_some() {
@@ -2727,7 +5239,13 @@ async fn handle_container_exec_with_params(
     match output_result {
         Ok(output) => {
             let ExecToolCallOutput { exit_code, .. } = &output;
-            let content = format_exec_output(&output);
+            let mut content = format_exec_output(
+                &output,
+                Some((&sess.services.output_overflow, call_id.as_str())),
+            );
+            if let Some(note) = patch_note {
+                content.push_str(&format!("\n\n{note}"));
+            }
             if *exit_code == 0 {
                 Ok(content)
             } else {
@@ -2752,6 +5270,517 @@ async fn handle_container_exec_with_params(
     }
 }
 
+/// Starts an approved command as a background job. The job reports its
+/// status to clients and writes its sandbox audit record when it finishes or
+/// is killed; the model polls it with the `job_result` tool and stops it with
+/// `job_kill`.
+async fn start_background_job(
+    sess: &Session,
+    ctx: ExecCommandContext,
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: SandboxPolicy,
+    sandbox_cwd: PathBuf,
+) -> String {
+    let jobs = &sess.services.background_jobs;
+    let job_id = jobs.next_job_id();
+    sess.send_event(Event {
+        id: ctx.sub_id.clone(),
+        msg: EventMsg::BackgroundJob(BackgroundJobEvent {
+            job_id: job_id.clone(),
+            command: ctx.command_for_display.clone(),
+            status: BackgroundJobStatus::Running,
+            exit_code: None,
+            duration_ms: None,
+        }),
+    })
+    .await;
+
+    let command = ctx.command_for_display.clone();
+    let job = {
+        let job_id = job_id.clone();
+        let tx_event = sess.tx_event.clone();
+        let codex_linux_sandbox_exe = sess.services.codex_linux_sandbox_exe.clone();
+        let audit_codex_home = sess.services.sandbox_audit.clone();
+        let secrets = sess.state.lock().await.secrets.clone();
+        let output_overflow = Arc::clone(&sess.services.output_overflow);
+        let conversation_id = sess.conversation_id;
+        move |control: JobControl| async move {
+            // Output deltas are collected for `job_result` polls rather than
+            // forwarded to clients.
+            let (delta_tx, delta_rx) = async_channel::unbounded::<Event>();
+            let collector = {
+                let output = control.output.clone();
+                tokio::spawn(async move {
+                    while let Ok(event) = delta_rx.recv().await {
+                        if let EventMsg::ExecCommandOutputDelta(delta) = event.msg {
+                            output.push(&delta.chunk);
+                        }
+                    }
+                })
+            };
+            let stdout_stream = StdoutStream {
+                sub_id: ctx.sub_id.clone(),
+                call_id: ctx.call_id.clone(),
+                tx_event: delta_tx,
+            };
+
+            let start = std::time::Instant::now();
+            let exec = process_exec_tool_call(
+                params,
+                sandbox_type,
+                &sandbox_policy,
+                &sandbox_cwd,
+                &codex_linux_sandbox_exe,
+                Some(stdout_stream),
+            );
+            // Dropping the exec future kills the command.
+            let (result, outcome) = tokio::select! {
+                result = exec => {
+                    let outcome = background_job_outcome(
+                        &result,
+                        start.elapsed(),
+                        (&output_overflow, job_id.as_str()),
+                    );
+                    (result, outcome)
+                }
+                _ = control.kill.cancelled() => {
+                    let output = control.output.snapshot();
+                    let duration = start.elapsed();
+                    let result = Ok(ExecToolCallOutput {
+                        exit_code: KILLED_EXIT_CODE,
+                        stdout: StreamOutput::new(String::new()),
+                        stderr: StreamOutput::new(String::new()),
+                        aggregated_output: StreamOutput::new(output.clone()),
+                        duration,
+                        timed_out: false,
+                    });
+                    let outcome = JobOutcome {
+                        status: BackgroundJobStatus::Killed,
+                        exit_code: None,
+                        duration,
+                        output,
+                    };
+                    (result, outcome)
+                }
+            };
+            // A finished job reports its full output, so deltas that are
+            // still queued are not needed.
+            collector.abort();
+
+            if let Some(codex_home) = audit_codex_home {
+                let record = SandboxAuditRecord::executed(
+                    conversation_id,
+                    ctx.call_id,
+                    ctx.command_for_display.clone(),
+                    ctx.cwd,
+                    sandbox_type,
+                    sandbox_policy,
+                    ctx.approval,
+                    &result,
+                )
+                .redacted(&secrets);
+                sandbox_audit::append_record(&codex_home, &record).await;
+            }
+
+            let event = Event {
+                id: ctx.sub_id,
+                msg: EventMsg::BackgroundJob(BackgroundJobEvent {
+                    job_id,
+                    command: ctx.command_for_display,
+                    status: outcome.status,
+                    exit_code: outcome.exit_code,
+                    duration_ms: Some(outcome.duration.as_millis() as u64),
+                }),
+            };
+            if let Err(e) = tx_event.send(event).await {
+                debug!("failed to send background job event: {e}");
+            }
+            outcome
+        }
+    };
+    jobs.spawn(job_id.clone(), command, job).await;
+
+    format!(
+        "started background job {job_id}; call `{JOB_RESULT_TOOL_NAME}` with this job id to poll its output or get its result, or `{JOB_KILL_TOOL_NAME}` to stop it. It keeps running across turns until the session ends."
+    )
+}
+
+/// Exit code recorded in the sandbox audit log for a killed background job,
+/// as a shell reports a command that died from `SIGKILL`.
+const KILLED_EXIT_CODE: i32 = 128 + 9;
+
+fn background_job_outcome(
+    result: &CodexResult<ExecToolCallOutput>,
+    duration: Duration,
+    overflow: (&OutputOverflowStore, &str),
+) -> JobOutcome {
+    match result {
+        Ok(output) => JobOutcome {
+            status: if output.exit_code == 0 {
+                BackgroundJobStatus::Completed
+            } else {
+                BackgroundJobStatus::Failed
+            },
+            exit_code: Some(output.exit_code),
+            duration,
+            output: format_exec_output_str(output, Some(overflow)),
+        },
+        Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => JobOutcome {
+            status: BackgroundJobStatus::TimedOut,
+            exit_code: None,
+            duration,
+            output: format_exec_output_str(output, Some(overflow)),
+        },
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => JobOutcome {
+            status: BackgroundJobStatus::Failed,
+            exit_code: Some(output.exit_code),
+            duration,
+            output: format!(
+                "the sandbox blocked this command; run it in the foreground to ask for approval to run it without the sandbox\n{}",
+                format_exec_output_str(output, Some(overflow))
+            ),
+        },
+        Err(e) => JobOutcome {
+            status: BackgroundJobStatus::Failed,
+            exit_code: None,
+            duration,
+            output: format!("execution error: {e:?}"),
+        },
+    }
+}
+
+/// How long a `tty` command runs before its output so far is returned, when
+/// the call does not set `timeout_ms`.
+#[cfg(unix)]
+const TTY_DEFAULT_YIELD_TIME_MS: u64 = 10_000;
+
+/// Cap on the output returned for a `tty` command, in tokens.
+#[cfg(unix)]
+const TTY_MAX_OUTPUT_TOKENS: u64 = 10_000;
+
+/// Runs an approved command on a pseudo-terminal. Output collected within
+/// `timeout_ms` is returned; a command that is still running after that is
+/// kept as an exec session that the model drives with `write_stdin`.
+#[cfg(unix)]
+async fn run_exec_in_pty(
+    sess: &Session,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    ctx: ExecCommandContext,
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_cwd: &Path,
+) -> Result<String, FunctionCallError> {
+    if matches!(
+        sandbox_type,
+        SandboxType::WindowsRestrictedToken | SandboxType::Container
+    ) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "`tty` is not supported with the {sandbox_type:?} sandbox; run the command without it"
+        )));
+    }
+
+    let yield_time_ms = params.timeout_ms.unwrap_or(TTY_DEFAULT_YIELD_TIME_MS);
+    sess.on_exec_command_begin(turn_diff_tracker, ctx.clone())
+        .await;
+    let result = match crate::exec::spawn_exec_in_pty(
+        params,
+        sandbox_type,
+        sandbox_policy,
+        sandbox_cwd,
+        &sess.services.codex_linux_sandbox_exe,
+    )
+    .await
+    {
+        Ok((child, master)) => {
+            sess.services
+                .session_manager
+                .handle_pty_child(child, master, yield_time_ms, TTY_MAX_OUTPUT_TOKENS)
+                .await
+        }
+        Err(e) => Err(format!("execution error: {e:?}")),
+    };
+
+    // The terminal merges stdout and stderr, so all output is reported as
+    // stdout. A command that is still running is reported as exit code 0.
+    let exec_output = match &result {
+        Ok(output) => ExecToolCallOutput {
+            exit_code: output.exit_code().unwrap_or(0),
+            stdout: StreamOutput::new(output.output().to_string()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(output.output().to_string()),
+            duration: output.wall_time(),
+            timed_out: false,
+        },
+        Err(err) => ExecToolCallOutput {
+            exit_code: -1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(err.clone()),
+            aggregated_output: StreamOutput::new(err.clone()),
+            duration: Duration::default(),
+            timed_out: false,
+        },
+    };
+    sess.on_exec_command_end(
+        turn_diff_tracker,
+        &ctx.sub_id,
+        &ctx.call_id,
+        &exec_output,
+        false,
+        false,
+    )
+    .await;
+
+    if let Some(codex_home) = &sess.services.sandbox_audit {
+        let record = SandboxAuditRecord::executed(
+            sess.conversation_id,
+            ctx.call_id,
+            ctx.command_for_display,
+            ctx.cwd,
+            sandbox_type,
+            sandbox_policy.clone(),
+            ctx.approval,
+            &Ok(exec_output),
+        )
+        .redacted(&sess.state.lock().await.secrets);
+        sandbox_audit::append_record(codex_home, &record).await;
+    }
+
+    result
+        .map(|output| output.to_text_output())
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+#[cfg(not(unix))]
+async fn run_exec_in_pty(
+    _sess: &Session,
+    _turn_diff_tracker: &mut TurnDiffTracker,
+    _ctx: ExecCommandContext,
+    _params: ExecParams,
+    _sandbox_type: SandboxType,
+    _sandbox_policy: &SandboxPolicy,
+    _sandbox_cwd: &Path,
+) -> Result<String, FunctionCallError> {
+    Err(FunctionCallError::RespondToModel(
+        "`tty` is not supported on this platform; run the command without it".to_string(),
+    ))
+}
+
+async fn handle_job_result(sess: &Session, arguments: String) -> Result<String, FunctionCallError> {
+    let args: JobResultArgs = serde_json::from_str(&arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let jobs = &sess.services.background_jobs;
+    let serialized = match &args.job_id {
+        Some(job_id) => {
+            let Some(mut report) = jobs.report(job_id, args.wait()).await else {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "unknown background job: {job_id}"
+                )));
+            };
+            // The command may have been given secrets when it was started.
+            if let Some(output) = &report.output {
+                let state = sess.state.lock().await;
+                report.output = Some(redact_secrets(output, &state.secrets));
+            }
+            serde_json::to_string(&report)
+        }
+        None => serde_json::to_string(&jobs.list().await),
+    };
+    serialized.map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to serialize job result: {e:?}"))
+    })
+}
+
+async fn handle_read_output(
+    sess: &Session,
+    arguments: String,
+) -> Result<String, FunctionCallError> {
+    let args: ReadOutputArgs = serde_json::from_str(&arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let Some(path) = sess.services.output_overflow.path(&args.output_id) else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "unknown output id: {}; only truncated outputs from this session are saved",
+            args.output_id
+        )));
+    };
+    let text = tokio::fs::read_to_string(&path).await.map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to read saved output: {e}"))
+    })?;
+    let page = format_page(&text, &args, MODEL_FORMAT_MAX_BYTES);
+    // The command may have been given secrets when it was run.
+    let state = sess.state.lock().await;
+    Ok(redact_secrets(&page, &state.secrets))
+}
+
+async fn handle_job_kill(sess: &Session, arguments: String) -> Result<String, FunctionCallError> {
+    let args: JobKillArgs = serde_json::from_str(&arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let Some(mut report) = sess.services.background_jobs.kill(&args.job_id).await else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "unknown background job: {}",
+            args.job_id
+        )));
+    };
+    if let Some(output) = &report.output {
+        let state = sess.state.lock().await;
+        report.output = Some(redact_secrets(output, &state.secrets));
+    }
+    serde_json::to_string(&report).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to serialize job result: {e:?}"))
+    })
+}
+
+/// Run the `git` tool. High-risk subcommands always ask the user, regardless
+/// of the approval policy or commands approved earlier in the session, and an
+/// approval is never remembered for the rest of the session.
+async fn handle_git_tool_call(
+    args: GitToolArgs,
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+) -> Result<String, FunctionCallError> {
+    let risk = classify_git_command(&args.args).map_err(FunctionCallError::RespondToModel)?;
+    let cwd = turn_context.resolve_path(args.workdir);
+    let params = ExecParams {
+        command: std::iter::once("git".to_string())
+            .chain(args.args.iter().cloned())
+            .collect(),
+        cwd,
+        timeout_ms: args.timeout_ms,
+        env: create_env(&turn_context.shell_environment_policy),
+        with_escalated_permissions: None,
+        justification: args.justification,
+        resource_limits: turn_context.resource_limits(),
+    };
+
+    let (sandbox_type, approval) = match risk {
+        GitRisk::Read => (
+            get_platform_sandbox().unwrap_or(SandboxType::None),
+            AuditApproval::Auto,
+        ),
+        GitRisk::Write => {
+            return handle_container_exec_with_params(
+                params,
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                ExecMode::Foreground,
+            )
+            .await;
+        }
+        GitRisk::HighRisk { consequence } => {
+            if turn_context.approval_policy == AskForApproval::Never {
+                let reason = format!(
+                    "git command rejected: {consequence} This requires user approval, which the approval policy {:?} does not allow.",
+                    turn_context.approval_policy
+                );
+                sess.audit_blocked_exec(
+                    &call_id,
+                    &params.command,
+                    &params.cwd,
+                    &turn_context.sandbox_policy,
+                    AuditApproval::Rejected,
+                    Some(reason.clone()),
+                )
+                .await;
+                return Err(FunctionCallError::RespondToModel(reason));
+            }
+            let mut reason = consequence_preview(&args.args, &params.cwd, &consequence).await;
+            if let Some(justification) = &params.justification {
+                reason = format!("{justification}\n\n{reason}");
+            }
+            let decision = sess
+                .request_command_approval(
+                    sub_id.clone(),
+                    call_id.clone(),
+                    params.command.clone(),
+                    params.cwd.clone(),
+                    Some(reason),
+                )
+                .await;
+            match decision {
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    sess.audit_blocked_exec(
+                        &call_id,
+                        &params.command,
+                        &params.cwd,
+                        &turn_context.sandbox_policy,
+                        AuditApproval::Denied,
+                        None,
+                    )
+                    .await;
+                    return Err(FunctionCallError::RespondToModel(
+                        "git command rejected by user".to_string(),
+                    ));
+                }
+            }
+            // Pushing needs the network and the other high-risk subcommands
+            // write to `.git`, so run unsandboxed once the user has approved.
+            // The approval is never remembered for the session.
+            (SandboxType::None, AuditApproval::Approved)
+        }
+    };
+
+    let exec_command_context = ExecCommandContext {
+        sub_id: sub_id.clone(),
+        call_id: call_id.clone(),
+        command_for_display: params.command.clone(),
+        cwd: params.cwd.clone(),
+        apply_patch: None,
+        approval,
+    };
+    let output_result = sess
+        .run_exec_with_events(
+            turn_diff_tracker,
+            exec_command_context,
+            ExecInvokeArgs {
+                params,
+                sandbox_type,
+                sandbox_policy: &turn_context.sandbox_policy,
+                sandbox_cwd: &turn_context.cwd,
+                codex_linux_sandbox_exe: &sess.services.codex_linux_sandbox_exe,
+                stdout_stream: Some(StdoutStream {
+                    sub_id,
+                    call_id: call_id.clone(),
+                    tx_event: sess.tx_event.clone(),
+                }),
+            },
+        )
+        .await;
+
+    match output_result {
+        Ok(output) => {
+            let content = format_exec_output(
+                &output,
+                Some((&sess.services.output_overflow, call_id.as_str())),
+            );
+            if output.exit_code == 0 {
+                Ok(content)
+            } else {
+                Err(FunctionCallError::RespondToModel(content))
+            }
+        }
+        Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => {
+            Err(FunctionCallError::RespondToModel(format_exec_output(
+                &output,
+                Some((&sess.services.output_overflow, call_id.as_str())),
+            )))
+        }
+        Err(e) => Err(FunctionCallError::RespondToModel(format!(
+            "execution error: {e:?}"
+        ))),
+    }
+}
+
 async fn handle_sandbox_error(
     turn_diff_tracker: &mut TurnDiffTracker,
     params: ExecParams,
@@ -2766,7 +5795,10 @@ async fn handle_sandbox_error(
     let cwd = exec_command_context.cwd.clone();
 
     if let SandboxErr::Timeout { output } = &error {
-        let content = format_exec_output(output);
+        let content = format_exec_output(
+            output,
+            Some((&sess.services.output_overflow, call_id.as_str())),
+        );
         return Err(FunctionCallError::RespondToModel(content));
     }
 
@@ -2805,6 +5837,10 @@ async fn handle_sandbox_error(
 
     match decision {
         ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+            let approval = match decision {
+                ReviewDecision::ApprovedForSession => AuditApproval::ApprovedForSession,
+                _ => AuditApproval::Approved,
+            };
             // Persist this command as pre‑approved for the
             // remainder of the session so future
             // executions skip the sandbox directly.
@@ -2819,7 +5855,10 @@ async fn handle_sandbox_error(
             let retry_output_result = sess
                 .run_exec_with_events(
                     turn_diff_tracker,
-                    exec_command_context.clone(),
+                    ExecCommandContext {
+                        approval,
+                        ..exec_command_context.clone()
+                    },
                     ExecInvokeArgs {
                         params,
                         sandbox_type: SandboxType::None,
@@ -2842,7 +5881,10 @@ async fn handle_sandbox_error(
             match retry_output_result {
                 Ok(retry_output) => {
                     let ExecToolCallOutput { exit_code, .. } = &retry_output;
-                    let content = format_exec_output(&retry_output);
+                    let content = format_exec_output(
+                        &retry_output,
+                        Some((&sess.services.output_overflow, call_id.as_str())),
+                    );
                     if *exit_code == 0 {
                         Ok(content)
                     } else {
@@ -2863,7 +5905,12 @@ async fn handle_sandbox_error(
     }
 }
 
-fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
+/// `overflow` is where the full output is saved, and under which id, when it
+/// is truncated.
+fn format_exec_output_str(
+    exec_output: &ExecToolCallOutput,
+    overflow: Option<(&OutputOverflowStore, &str)>,
+) -> String {
     let ExecToolCallOutput {
         aggregated_output, ..
     } = exec_output;
@@ -2876,7 +5923,7 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
 
     if exec_output.timed_out {
         prefixed_str = format!(
-            "command timed out after {} milliseconds\n",
+            "command timed out after {} milliseconds; if it needs more time, run it again with a larger `timeout_ms`\n",
             exec_output.duration.as_millis()
         ) + s;
         s = &prefixed_str;
@@ -2904,7 +5951,19 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
     } else {
         String::new()
     };
-    let marker = format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n");
+    let saved_as = overflow.and_then(|(store, output_id)| match store.save(output_id, s) {
+        Ok(()) => Some(output_id),
+        Err(e) => {
+            warn!("failed to save full output of {output_id}: {e}");
+            None
+        }
+    });
+    let marker = match saved_as {
+        Some(output_id) => format!(
+            "\n[... omitted {omitted} of {total_lines} lines; the full output is saved as `{output_id}`, page through it with `{READ_OUTPUT_TOOL_NAME}` ...]\n\n"
+        ),
+        None => format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n"),
+    };
 
     // Byte budgets for head/tail around the marker
     let mut head_budget = MODEL_FORMAT_HEAD_BYTES.min(MODEL_FORMAT_MAX_BYTES);
@@ -2976,7 +6035,10 @@ fn take_last_bytes_at_char_boundary(s: &str, maxb: usize) -> &str {
 }
 
 /// Exec output is a pre-serialized JSON payload
-fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
+fn format_exec_output(
+    exec_output: &ExecToolCallOutput,
+    overflow: Option<(&OutputOverflowStore, &str)>,
+) -> String {
     let ExecToolCallOutput {
         exit_code,
         duration,
@@ -2998,7 +6060,7 @@ fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
     // round to 1 decimal place
     let duration_seconds = ((duration.as_secs_f32()) * 10.0).round() / 10.0;
 
-    let formatted_output = format_exec_output_str(exec_output);
+    let formatted_output = format_exec_output_str(exec_output, overflow);
 
     let payload = ExecOutput {
         output: &formatted_output,
@@ -3130,6 +6192,8 @@ mod tests {
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
     use crate::protocol::CompactedItem;
+    use crate::protocol::FileChangesItem;
+    use crate::protocol::FileVersionsItem;
     use crate::protocol::InitialHistory;
     use crate::protocol::ResumedHistory;
     use crate::state::TaskKind;
@@ -3141,6 +6205,7 @@ mod tests {
     use pretty_assertions::assert_eq;
     use serde::Deserialize;
     use serde_json::json;
+    use std::collections::BTreeMap;
     use std::path::PathBuf;
     use std::sync::Arc;
     use std::time::Duration as StdDuration;
@@ -3152,9 +6217,40 @@ mod tests {
         let (session, turn_context) = make_session_and_context();
         let (rollout_items, expected) = sample_rollout(&session, &turn_context);
 
-        let reconstructed = session.reconstruct_history_from_rollout(&turn_context, &rollout_items);
+        let reconstructed = session.reconstruct_from_rollout(&turn_context, &rollout_items);
+
+        assert_eq!(expected, reconstructed.history);
+    }
+
+    #[test]
+    fn reconstruct_from_rollout_rebuilds_checkpoints_and_changed_files() {
+        let (session, turn_context) = make_session_and_context();
+        let (mut rollout_items, expected) = sample_rollout(&session, &turn_context);
+        rollout_items.push(RolloutItem::EventMsg(EventMsg::CheckpointCreated(
+            CheckpointCreatedEvent {
+                name: "before".to_string(),
+            },
+        )));
+        rollout_items.push(RolloutItem::FileChanges(FileChangesItem {
+            turn_id: "7".to_string(),
+            files: vec![FileVersionsItem {
+                path: PathBuf::from("/repo/lib.rs"),
+                before: Some(b"v0".to_vec()),
+                after: Some(b"v1".to_vec()),
+            }],
+        }));
 
-        assert_eq!(expected, reconstructed);
+        let reconstructed = session.reconstruct_from_rollout(&turn_context, &rollout_items);
+
+        let checkpoint = reconstructed
+            .checkpoints
+            .get("before")
+            .expect("checkpoint rebuilt");
+        assert_eq!(expected, checkpoint.history);
+        assert_eq!(
+            BTreeSet::from([PathBuf::from("/repo/lib.rs")]),
+            reconstructed.changed_paths
+        );
     }
 
     #[test]
@@ -3228,7 +6324,7 @@ mod tests {
             timed_out: false,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
 
         // Expect elision marker with correct counts
         let omitted = 400 - MODEL_FORMAT_MAX_LINES; // 144
@@ -3254,6 +6350,34 @@ mod tests {
         assert!(tail.ends_with(&expected_tail), "tail mismatch");
     }
 
+    #[test]
+    fn model_truncation_saves_full_output() {
+        let lines: Vec<String> = (1..=400).map(|i| format!("line{i}")).collect();
+        let full = lines.join("\n");
+        let exec = ExecToolCallOutput {
+            exit_code: 0,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(full.clone()),
+            duration: StdDuration::from_secs(1),
+            timed_out: false,
+        };
+        let store = OutputOverflowStore::default();
+
+        let out = format_exec_output_str(&exec, Some((&store, "call_1")));
+
+        let omitted = 400 - MODEL_FORMAT_MAX_LINES;
+        assert!(
+            out.contains(&format!(
+                "[... omitted {omitted} of 400 lines; the full output is saved as `call_1`, page through it with `read_output` ...]"
+            )),
+            "missing marker: {out}"
+        );
+        let saved = std::fs::read_to_string(store.path("call_1").expect("output saved"))
+            .expect("read saved output");
+        assert_eq!(saved, full);
+    }
+
     #[test]
     fn model_truncation_respects_byte_budget() {
         // Construct a large output (about 100kB) so byte budget dominates
@@ -3271,7 +6395,7 @@ mod tests {
             timed_out: false,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
         assert!(out.len() <= MODEL_FORMAT_MAX_BYTES, "exceeds byte budget");
         assert!(out.contains("omitted"), "should contain elision marker");
 
@@ -3302,11 +6426,11 @@ mod tests {
             timed_out: true,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
 
         assert_eq!(
             out,
-            "command timed out after 1000 milliseconds\nCommand output"
+            "command timed out after 1000 milliseconds; if it needs more time, run it again with a larger `timeout_ms`\nCommand output"
         );
     }
 
@@ -3394,18 +6518,27 @@ mod tests {
             include_plan_tool: config.include_plan_tool,
             include_apply_patch_tool: config.include_apply_patch_tool,
             include_web_search_request: config.tools_web_search_request,
+            web_search_backend: config.web_search.backend,
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
+            include_git_tool: config.include_git_tool,
+            include_fetch_url_tool: config.include_fetch_url_tool,
+            include_file_tools: config.include_file_tools,
+            include_search_code_tool: config.include_search_code_tool,
+            include_memory_tool: config.memory.enabled,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
         });
         let turn_context = TurnContext {
             client,
+            provider_fallbacks: ProviderFallbacks::default(),
             cwd: config.cwd.clone(),
             base_instructions: config.base_instructions.clone(),
             user_instructions: config.user_instructions.clone(),
             approval_policy: config.approval_policy,
             sandbox_policy: config.sandbox_policy.clone(),
             shell_environment_policy: config.shell_environment_policy.clone(),
+            sandbox_resource_limits: config.sandbox_resource_limits,
+            exec_timeout: config.exec_timeout,
             tools_config,
             is_review_mode: false,
             final_output_json_schema: None,
@@ -3419,6 +6552,38 @@ mod tests {
             codex_linux_sandbox_exe: None,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            context_pruning: config.context_pruning,
+            escalate_patch_risk: config.escalate_patch_risk,
+            sandbox_rules: config.sandbox_rules.clone(),
+            sandbox_audit: None,
+            url_attachments: UrlAttachments::new(
+                config.url_attachments.clone(),
+                config.network_proxy.clone(),
+                config
+                    .model_provider
+                    .ca_certificates
+                    .clone()
+                    .unwrap_or_default(),
+            ),
+            custom_tools: CustomTools::new(config.custom_tools.clone()),
+            web_search: WebSearch::new(config.web_search.clone()),
+            session_budget_usd: config.session_budget_usd,
+            max_turns: config.max_turns,
+            max_runtime: config.max_runtime,
+            report_failure_tool: config.include_report_failure_tool,
+            max_parallel_tool_calls: config.max_parallel_tool_calls,
+            auto_commit: None,
+            change_journal: ChangeJournal::default(),
+            post_patch_hooks: BTreeMap::new(),
+            lifecycle_hooks: LifecycleHooks::new(
+                &config.hooks,
+                conversation_id,
+                config.cwd.clone(),
+            ),
+            memory: None,
+            plugins: PluginHost::default(),
+            background_jobs: BackgroundJobManager::default(),
+            output_overflow: Arc::default(),
         };
         let session = Session {
             conversation_id,
@@ -3427,6 +6592,7 @@ mod tests {
             active_turn: Mutex::new(None),
             services,
             next_internal_sub_id: AtomicU64::new(0),
+            queued_input_ready: Notify::new(),
         };
         (session, turn_context)
     }
@@ -3461,18 +6627,27 @@ mod tests {
             include_plan_tool: config.include_plan_tool,
             include_apply_patch_tool: config.include_apply_patch_tool,
             include_web_search_request: config.tools_web_search_request,
+            web_search_backend: config.web_search.backend,
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
+            include_git_tool: config.include_git_tool,
+            include_fetch_url_tool: config.include_fetch_url_tool,
+            include_file_tools: config.include_file_tools,
+            include_search_code_tool: config.include_search_code_tool,
+            include_memory_tool: config.memory.enabled,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
         });
         let turn_context = Arc::new(TurnContext {
             client,
+            provider_fallbacks: ProviderFallbacks::default(),
             cwd: config.cwd.clone(),
             base_instructions: config.base_instructions.clone(),
             user_instructions: config.user_instructions.clone(),
             approval_policy: config.approval_policy,
             sandbox_policy: config.sandbox_policy.clone(),
             shell_environment_policy: config.shell_environment_policy.clone(),
+            sandbox_resource_limits: config.sandbox_resource_limits,
+            exec_timeout: config.exec_timeout,
             tools_config,
             is_review_mode: false,
             final_output_json_schema: None,
@@ -3486,6 +6661,38 @@ mod tests {
             codex_linux_sandbox_exe: None,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            context_pruning: config.context_pruning,
+            escalate_patch_risk: config.escalate_patch_risk,
+            sandbox_rules: config.sandbox_rules.clone(),
+            sandbox_audit: None,
+            url_attachments: UrlAttachments::new(
+                config.url_attachments.clone(),
+                config.network_proxy.clone(),
+                config
+                    .model_provider
+                    .ca_certificates
+                    .clone()
+                    .unwrap_or_default(),
+            ),
+            custom_tools: CustomTools::new(config.custom_tools.clone()),
+            web_search: WebSearch::new(config.web_search.clone()),
+            session_budget_usd: config.session_budget_usd,
+            max_turns: config.max_turns,
+            max_runtime: config.max_runtime,
+            report_failure_tool: config.include_report_failure_tool,
+            max_parallel_tool_calls: config.max_parallel_tool_calls,
+            auto_commit: None,
+            change_journal: ChangeJournal::default(),
+            post_patch_hooks: BTreeMap::new(),
+            lifecycle_hooks: LifecycleHooks::new(
+                &config.hooks,
+                conversation_id,
+                config.cwd.clone(),
+            ),
+            memory: None,
+            plugins: PluginHost::default(),
+            background_jobs: BackgroundJobManager::default(),
+            output_overflow: Arc::default(),
         };
         let session = Arc::new(Session {
             conversation_id,
@@ -3494,6 +6701,7 @@ mod tests {
             active_turn: Mutex::new(None),
             services,
             next_internal_sub_id: AtomicU64::new(0),
+            queued_input_ready: Notify::new(),
         });
         (session, turn_context, rx_event)
     }
@@ -3643,6 +6851,7 @@ mod tests {
         live_history.replace(rebuilt1);
         rollout_items.push(RolloutItem::Compacted(CompactedItem {
             message: summary1.to_string(),
+            retained_items: None,
         }));
 
         let user2 = ResponseItem::Message {
@@ -3667,15 +6876,17 @@ mod tests {
 
         let summary2 = "summary two";
         let snapshot2 = live_history.contents();
-        let user_messages2 = collect_user_messages(&snapshot2);
-        let rebuilt2 = build_compacted_history(
+        // Only the first turn is summarized; the second is kept as it is.
+        let rebuilt2 = build_rolling_compacted_history(
             session.build_initial_context(turn_context),
-            &user_messages2,
+            &snapshot2,
+            2,
             summary2,
         );
         live_history.replace(rebuilt2);
         rollout_items.push(RolloutItem::Compacted(CompactedItem {
             message: summary2.to_string(),
+            retained_items: Some(2),
         }));
 
         let user3 = ResponseItem::Message {
@@ -3732,6 +6943,7 @@ mod tests {
             env: HashMap::new(),
             with_escalated_permissions: Some(true),
             justification: Some("test".to_string()),
+            resource_limits: turn_context.resource_limits(),
         };
 
         let params2 = ExecParams {
@@ -3751,6 +6963,7 @@ mod tests {
             &mut turn_diff_tracker,
             sub_id,
             call_id,
+            ExecMode::Foreground,
         )
         .await;
 
@@ -3776,6 +6989,7 @@ mod tests {
             &mut turn_diff_tracker,
             "test-sub".to_string(),
             "test-call-2".to_string(),
+            ExecMode::Foreground,
         )
         .await;
 
@@ -4,6 +4,7 @@ use super::Session;
 use super::TurnContext;
 use super::get_last_assistant_message_from_turn;
 use crate::Prompt;
+use crate::client::ModelClient;
 use crate::client_common::ResponseEvent;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -27,6 +28,8 @@ use futures::prelude::*;
 
 pub const SUMMARIZATION_PROMPT: &str = include_str!("../../templates/compact/prompt.md");
 const COMPACT_USER_MESSAGE_MAX_TOKENS: usize = 20_000;
+/// How the rendered `history_bridge.md` template starts.
+const HISTORY_BRIDGE_PREFIX: &str = "You were originally given instructions from a user";
 
 #[derive(Template)]
 #[template(path = "compact/history_bridge.md", escape = "none")]
@@ -35,15 +38,27 @@ struct HistoryBridgeTemplate<'a> {
     summary_text: &'a str,
 }
 
+/// Compacts the conversation when it has outgrown the context window. The
+/// most recent turns are kept verbatim and only the turns before them are
+/// summarized, merging any earlier summary into the new one; when there are
+/// not enough turns for that, the whole conversation is summarized.
 pub(crate) async fn run_inline_auto_compact_task(
     sess: Arc<Session>,
     turn_context: Arc<TurnContext>,
 ) {
     let sub_id = sess.next_internal_sub_id();
-    let input = vec![InputItem::Text {
-        text: SUMMARIZATION_PROMPT.to_string(),
-    }];
-    run_compact_task_inner(sess, turn_context, sub_id, input).await;
+    let history = sess.history_snapshot().await;
+    let keep_recent_turns = turn_context.client.auto_compact_keep_recent_turns();
+    match recent_turns_start(&history, keep_recent_turns) {
+        Some(split) => run_rolling_compaction(sess, turn_context, sub_id, history, split).await,
+        None => {
+            let input = vec![InputItem::Text {
+                text: SUMMARIZATION_PROMPT.to_string(),
+            }];
+            let client = turn_context.client.auto_compact_client();
+            run_compact_task_inner(sess, turn_context, &client, sub_id, input).await;
+        }
+    }
 }
 
 pub(crate) async fn run_compact_task(
@@ -59,13 +74,15 @@ pub(crate) async fn run_compact_task(
         }),
     };
     sess.send_event(start_event).await;
-    run_compact_task_inner(sess.clone(), turn_context, sub_id.clone(), input).await;
+    let client = turn_context.client.clone();
+    run_compact_task_inner(sess.clone(), turn_context, &client, sub_id.clone(), input).await;
     None
 }
 
 async fn run_compact_task_inner(
     sess: Arc<Session>,
     turn_context: Arc<TurnContext>,
+    client: &ModelClient,
     sub_id: String,
     input: Vec<InputItem>,
 ) {
@@ -78,8 +95,90 @@ async fn run_compact_task_inner(
         input: turn_input,
         ..Default::default()
     };
+    if !summarize(&sess, turn_context.as_ref(), client, &sub_id, &prompt).await {
+        return;
+    }
+
+    let history_snapshot = sess.history_snapshot().await;
+    let summary_text = get_last_assistant_message_from_turn(&history_snapshot).unwrap_or_default();
+    let user_messages = collect_user_messages(&history_snapshot);
+    let initial_context = sess.build_initial_context(turn_context.as_ref());
+    let new_history = build_compacted_history(initial_context, &user_messages, &summary_text);
+    sess.replace_history(new_history).await;
+
+    let rollout_item = RolloutItem::Compacted(CompactedItem {
+        message: summary_text.clone(),
+        retained_items: None,
+    });
+    sess.persist_rollout_items(&[rollout_item]).await;
+
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::AgentMessage(AgentMessageEvent {
+            message: "Compact task completed".to_string(),
+        }),
+    };
+    sess.send_event(event).await;
+}
 
-    let max_retries = turn_context.client.get_provider().stream_max_retries();
+/// Summarizes `history[..split]` into a memory block and keeps the turns
+/// from `split` on as they are.
+async fn run_rolling_compaction(
+    sess: Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    sub_id: String,
+    history: Vec<ResponseItem>,
+    split: usize,
+) {
+    let summarize_input: ResponseInputItem = ResponseInputItem::from(vec![InputItem::Text {
+        text: SUMMARIZATION_PROMPT.to_string(),
+    }]);
+    let mut input = history[..split].to_vec();
+    input.push(summarize_input.into());
+    let prompt = Prompt {
+        input,
+        ..Default::default()
+    };
+    let client = turn_context.client.auto_compact_client();
+    if !summarize(&sess, turn_context.as_ref(), &client, &sub_id, &prompt).await {
+        return;
+    }
+
+    // The summary was recorded after the items in `history`.
+    let after = sess.history_snapshot().await;
+    let summary_text =
+        get_last_assistant_message_from_turn(after.get(history.len()..).unwrap_or_default())
+            .unwrap_or_default();
+    let retained_items = history.len() - split;
+    let initial_context = sess.build_initial_context(turn_context.as_ref());
+    let new_history =
+        build_rolling_compacted_history(initial_context, &history, retained_items, &summary_text);
+    sess.replace_history(new_history).await;
+
+    let rollout_item = RolloutItem::Compacted(CompactedItem {
+        message: summary_text,
+        retained_items: Some(retained_items),
+    });
+    sess.persist_rollout_items(&[rollout_item]).await;
+    sess.notify_background_event(
+        &sub_id,
+        format!(
+            "Summarized earlier turns to free up context; kept the last {retained_items} items."
+        ),
+    )
+    .await;
+}
+
+/// Streams the summary for `prompt` into the history, retrying stream
+/// errors. Returns false, after reporting any error, if no summary arrived.
+async fn summarize(
+    sess: &Session,
+    turn_context: &TurnContext,
+    client: &ModelClient,
+    sub_id: &str,
+    prompt: &Prompt,
+) -> bool {
+    let max_retries = client.get_provider().stream_max_retries();
     let mut retries = 0;
 
     let rollout_item = RolloutItem::TurnContext(TurnContextItem {
@@ -93,22 +192,21 @@ async fn run_compact_task_inner(
     sess.persist_rollout_items(&[rollout_item]).await;
 
     loop {
-        let attempt_result =
-            drain_to_completed(&sess, turn_context.as_ref(), &sub_id, &prompt).await;
+        let attempt_result = drain_to_completed(sess, turn_context, client, sub_id, prompt).await;
 
         match attempt_result {
             Ok(()) => {
-                break;
+                return true;
             }
             Err(CodexErr::Interrupted) => {
-                return;
+                return false;
             }
             Err(e) => {
                 if retries < max_retries {
                     retries += 1;
                     let delay = backoff(retries);
                     sess.notify_stream_error(
-                        &sub_id,
+                        sub_id,
                         format!(
                             "stream error: {e}; retrying {retries}/{max_retries} in {delay:?}…"
                         ),
@@ -118,37 +216,18 @@ async fn run_compact_task_inner(
                     continue;
                 } else {
                     let event = Event {
-                        id: sub_id.clone(),
+                        id: sub_id.to_string(),
                         msg: EventMsg::Error(ErrorEvent {
                             message: e.to_string(),
+                            kind: e.error_kind(),
                         }),
                     };
                     sess.send_event(event).await;
-                    return;
+                    return false;
                 }
             }
         }
     }
-
-    let history_snapshot = sess.history_snapshot().await;
-    let summary_text = get_last_assistant_message_from_turn(&history_snapshot).unwrap_or_default();
-    let user_messages = collect_user_messages(&history_snapshot);
-    let initial_context = sess.build_initial_context(turn_context.as_ref());
-    let new_history = build_compacted_history(initial_context, &user_messages, &summary_text);
-    sess.replace_history(new_history).await;
-
-    let rollout_item = RolloutItem::Compacted(CompactedItem {
-        message: summary_text.clone(),
-    });
-    sess.persist_rollout_items(&[rollout_item]).await;
-
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::AgentMessage(AgentMessageEvent {
-            message: "Compact task completed".to_string(),
-        }),
-    };
-    sess.send_event(event).await;
 }
 
 pub fn content_items_to_text(content: &[ContentItem]) -> Option<String> {
@@ -227,13 +306,65 @@ pub(crate) fn build_compacted_history(
     history
 }
 
+/// Where the last `keep_recent_turns` user turns of `items` begin, or `None`
+/// if there are no older turns to summarize.
+pub(crate) fn recent_turns_start(
+    items: &[ResponseItem],
+    keep_recent_turns: usize,
+) -> Option<usize> {
+    if keep_recent_turns == 0 {
+        return None;
+    }
+    let turn_starts: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| match item {
+            ResponseItem::Message { role, content, .. } if role == "user" => {
+                content_items_to_text(content).is_some_and(|text| {
+                    !is_session_prefix_message(&text) && !is_history_bridge(&text)
+                })
+            }
+            _ => false,
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    if turn_starts.len() <= keep_recent_turns {
+        return None;
+    }
+    Some(turn_starts[turn_starts.len() - keep_recent_turns])
+}
+
+/// History after summarizing all but the last `retained_items` of `items`
+/// into a memory block. Earlier memory blocks are left out of it, as the new
+/// summary already covers them.
+pub(crate) fn build_rolling_compacted_history(
+    initial_context: Vec<ResponseItem>,
+    items: &[ResponseItem],
+    retained_items: usize,
+    summary_text: &str,
+) -> Vec<ResponseItem> {
+    let (older, recent) = items.split_at(items.len().saturating_sub(retained_items));
+    let user_messages: Vec<String> = collect_user_messages(older)
+        .into_iter()
+        .filter(|text| !is_history_bridge(text))
+        .collect();
+    let mut history = build_compacted_history(initial_context, &user_messages, summary_text);
+    history.extend_from_slice(recent);
+    history
+}
+
+fn is_history_bridge(text: &str) -> bool {
+    text.starts_with(HISTORY_BRIDGE_PREFIX)
+}
+
 async fn drain_to_completed(
     sess: &Session,
     turn_context: &TurnContext,
+    client: &ModelClient,
     sub_id: &str,
     prompt: &Prompt,
 ) -> CodexResult<()> {
-    let mut stream = turn_context.client.clone().stream(prompt).await?;
+    let mut stream = client.stream(prompt).await?;
     loop {
         let maybe_event = stream.next().await;
         let Some(event) = maybe_event else {
@@ -250,7 +381,7 @@ async fn drain_to_completed(
                 sess.update_rate_limits(sub_id, snapshot).await;
             }
             Ok(ResponseEvent::Completed { token_usage, .. }) => {
-                sess.update_token_usage_info(sub_id, turn_context, token_usage.as_ref())
+                sess.update_token_usage_info(sub_id, turn_context, client, token_usage.as_ref())
                     .await;
                 return Ok(());
             }
@@ -389,4 +520,69 @@ mod tests {
             "bridge should include the provided summary text"
         );
     }
+
+    fn user(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn assistant(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn recent_turns_start_skips_context_and_memory_blocks() {
+        let bridge = build_compacted_history(Vec::new(), &["first".to_string()], "SUMMARY");
+        let mut items = vec![user("<environment_context>cwd</environment_context>")];
+        items.extend(bridge);
+        items.extend([
+            user("second"),
+            assistant("done"),
+            user("third"),
+            assistant("done"),
+        ]);
+
+        assert_eq!(recent_turns_start(&items, 1), Some(4));
+        assert_eq!(recent_turns_start(&items, 2), None);
+        assert_eq!(recent_turns_start(&items, 0), None);
+    }
+
+    #[test]
+    fn rolling_compaction_keeps_recent_items_and_folds_in_old_memory() {
+        let old_bridge = build_compacted_history(Vec::new(), &["first".to_string()], "OLD");
+        let mut items = old_bridge;
+        items.extend([
+            user("second"),
+            assistant("done"),
+            user("third"),
+            assistant("ok"),
+        ]);
+
+        let history = build_rolling_compacted_history(Vec::new(), &items, 2, "NEW");
+
+        assert_eq!(history.len(), 3);
+        let ResponseItem::Message { content, .. } = &history[0] else {
+            panic!("expected a memory block, got {:?}", history[0]);
+        };
+        let memory = content_items_to_text(content).unwrap_or_default();
+        assert!(is_history_bridge(&memory));
+        assert!(memory.contains("second"));
+        assert!(memory.contains("NEW"));
+        assert!(!memory.contains("OLD"), "old memory should not be nested");
+        assert_eq!(
+            collect_user_messages(&history[1..]),
+            vec!["third".to_string()]
+        );
+    }
 }
@@ -1,18 +1,38 @@
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+use tracing::warn;
+
 use crate::codex::Codex;
+use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
+use crate::event_subscription::EVENT_REPLAY_CAPACITY;
+use crate::event_subscription::EventHub;
+use crate::event_subscription::EventSubscription;
+use crate::event_subscription::EventSubscriptionError;
+use crate::event_subscription::SubscribeOptions;
 use crate::protocol::Event;
 use crate::protocol::Op;
 use crate::protocol::Submission;
 
 pub struct CodexConversation {
     codex: Codex,
+    events: Arc<EventHub>,
+    /// The subscription behind [`CodexConversation::next_event`], created on
+    /// first use.
+    next_event_subscription: Mutex<Option<EventSubscription>>,
 }
 
 /// Conduit for the bidirectional stream of messages that compose a conversation
 /// in Codex.
 impl CodexConversation {
     pub(crate) fn new(codex: Codex) -> Self {
-        Self { codex }
+        let events = Arc::new(EventHub::new(codex.event_receiver(), EVENT_REPLAY_CAPACITY));
+        Self {
+            codex,
+            events,
+            next_event_subscription: Mutex::new(None),
+        }
     }
 
     pub async fn submit(&self, op: Op) -> CodexResult<String> {
@@ -24,7 +44,32 @@ impl CodexConversation {
         self.codex.submit_with_id(sub).await
     }
 
+    /// The next event of the conversation. Every caller shares one
+    /// subscription, so each event is returned once; use
+    /// [`CodexConversation::events`] for an independent stream.
     pub async fn next_event(&self) -> CodexResult<Event> {
-        self.codex.next_event().await
+        let mut subscription = self.next_event_subscription.lock().await;
+        let subscription = subscription.get_or_insert_with(|| {
+            self.events
+                .subscribe(SubscribeOptions::default().replay_from(0))
+        });
+        loop {
+            match subscription.next().await {
+                Ok(event) => return Ok(event.event),
+                Err(EventSubscriptionError::Lagged { missed }) => {
+                    warn!("skipped {missed} events that are no longer kept");
+                }
+                Err(EventSubscriptionError::Closed) => return Err(CodexErr::InternalAgentDied),
+            }
+        }
+    }
+
+    /// Subscribes to the events the conversation emits from now on.
+    pub fn events(&self) -> EventSubscription {
+        self.subscribe(SubscribeOptions::default())
+    }
+
+    pub fn subscribe(&self, options: SubscribeOptions) -> EventSubscription {
+        self.events.subscribe(options)
     }
 }
@@ -1,22 +1,58 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::AuthConfig;
+use crate::config_types::AutoCommit;
+use crate::config_types::AutoCompact;
+use crate::config_types::ContextPruningStrategy;
+use crate::config_types::CredentialStoreMode;
+use crate::config_types::CustomToolConfig;
+use crate::config_types::DiffRenderer;
+use crate::config_types::EditorMode;
+use crate::config_types::ExecTimeout;
+use crate::config_types::ExtensionSettings;
 use crate::config_types::History;
+use crate::config_types::Hooks;
+use crate::config_types::Integrations;
+use crate::config_types::McpSampling;
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::config_types::Memory;
+use crate::config_types::ModelPricing;
+use crate::config_types::ModelProviderSelection;
+use crate::config_types::NetworkToml;
 use crate::config_types::Notifications;
+use crate::config_types::PluginConfig;
+use crate::config_types::ProxyConfig;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::RolloutDurability;
+use crate::config_types::SandboxContainer;
+use crate::config_types::SandboxResourceLimits;
+use crate::config_types::SandboxRule;
+use crate::config_types::SandboxToml;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
+use crate::config_types::StreamCoalescing;
 use crate::config_types::Tui;
+use crate::config_types::TuiKeys;
+use crate::config_types::TuiTheme;
 use crate::config_types::UriBasedFileOpener;
+use crate::config_types::UrlAttachments;
+use crate::config_types::WebSearch;
+use crate::config_types::WebhookConfig;
+use crate::custom_tools::validate_custom_tools;
+use crate::extensions::Extension;
+use crate::extensions::discover_extensions;
+use crate::extensions::extension_servers;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::derive_default_model_family;
 use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
+use crate::network_proxy::NETWORK_ALLOWLIST_UNSUPPORTED;
 use crate::openai_model_info::get_model_info;
 use crate::protocol::AskForApproval;
+use crate::protocol::PatchRiskLevel;
 use crate::protocol::SandboxPolicy;
 use anyhow::Context;
 use codex_protocol::config_types::ReasoningEffort;
@@ -31,6 +67,7 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use tempfile::NamedTempFile;
 use toml::Value as TomlValue;
 use toml_edit::Array as TomlArray;
@@ -49,6 +86,8 @@ pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
 pub(crate) const CONFIG_TOML_FILE: &str = "config.toml";
 
+const DEFAULT_MCP_IDLE_TIMEOUT_SEC: u64 = 10 * 60;
+
 /// Application configuration loaded from disk and merged with overrides.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -69,19 +108,91 @@ pub struct Config {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// When and how the conversation is compacted automatically.
+    pub auto_compact: AutoCompact,
+
+    /// Whether and where each applied patch is committed to git.
+    pub auto_commit: AutoCommit,
+
+    /// Code hosts that pull requests are opened on.
+    pub integrations: Integrations,
+
+    /// Commands run after applied patches, such as formatters.
+    pub hooks: Hooks,
+
+    /// Notes the agent keeps about each project across sessions.
+    pub memory: Memory,
+
+    /// Prices used to estimate the cost of a session.
+    pub pricing: Vec<ModelPricing>,
+
+    /// Spend in US dollars after which a session stops sending requests.
+    pub session_budget_usd: Option<f64>,
+
+    /// Model requests after which a task stops sending requests.
+    pub max_turns: Option<u32>,
+
+    /// Time after which a task stops sending requests.
+    pub max_runtime: Option<Duration>,
+
+    /// Offer the model the `report_failure` tool, with which it declares that
+    /// it could not complete the task. Set by `codex exec`.
+    pub include_report_failure_tool: bool,
+
+    /// Strategy used to prune history before falling back to auto-compaction.
+    pub context_pruning: ContextPruningStrategy,
+
+    /// Run Responses API requests in background mode so that they survive
+    /// stream disconnects and can be resumed by response id.
+    pub responses_background: bool,
+
+    /// Log raw model requests and responses of each session, with
+    /// credentials redacted, under `CODEX_HOME/log/wire`.
+    pub wire_log: bool,
+
+    /// Most read-only tool calls from one model response that run at the
+    /// same time. `1` runs every call on its own.
+    pub max_parallel_tool_calls: usize,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Keys into the model_providers map tried in order when
+    /// `model_provider` keeps failing with rate limits or server errors.
+    pub provider_fallbacks: Vec<String>,
+
     /// Approval policy for executing commands.
     pub approval_policy: AskForApproval,
 
+    /// Patches scored at or above this risk level always ask for approval,
+    /// even when the approval policy would apply them automatically.
+    pub escalate_patch_risk: Option<PatchRiskLevel>,
+
+    /// Per-command overrides of the approval and sandbox policy.
+    pub sandbox_rules: Vec<SandboxRule>,
+
+    /// Record every command and its sandbox in `log/sandbox-audit.jsonl`.
+    pub sandbox_audit: bool,
+
     pub sandbox_policy: SandboxPolicy,
 
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// Limits applied to commands spawned by the shell tool, by sandbox mode.
+    pub sandbox_resource_limits: SandboxResourceLimits,
+
+    /// Time limits for commands spawned by the shell tool.
+    pub exec_timeout: ExecTimeout,
+
+    /// How `@https://…` mentions and attached URLs are fetched.
+    pub url_attachments: UrlAttachments,
+
+    /// Where the `web_search` tool gets its results from.
+    pub web_search: WebSearch,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -99,7 +210,8 @@ pub struct Config {
 
     /// Optional external notifier command. When set, Codex will spawn this
     /// program after each completed *turn* (i.e. when the agent finishes
-    /// processing a user submission). The value must be the full command
+    /// processing a user submission) and whenever it waits for the user to
+    /// approve a command or a patch. The value must be the full command
     /// broken into argv tokens **without** the trailing JSON argument - Codex
     /// appends one extra argument containing a JSON payload describing the
     /// event.
@@ -119,10 +231,49 @@ pub struct Config {
     /// If unset the feature is disabled.
     pub notify: Option<Vec<String>>,
 
-    /// TUI notifications preference. When set, the TUI will send OSC 9 notifications on approvals
+    /// Command that prints a bearer token for model requests. When set, it is
+    /// used instead of the credentials stored by `codex login`.
+    pub external_auth_command: Option<Vec<String>>,
+
+    /// Where login credentials are stored.
+    pub credential_store: CredentialStoreMode,
+
+    /// Proxy for requests to model providers, overriding the proxy
+    /// environment variables.
+    pub network_proxy: Option<ProxyConfig>,
+
+    /// Endpoints that receive protocol events as signed JSON payloads.
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Plugin libraries loaded at startup.
+    pub plugins: Vec<PluginConfig>,
+
+    /// Extensions installed under `$CODEX_HOME/extensions`, enabled or not.
+    /// The MCP servers of the enabled ones are part of `mcp_servers`.
+    pub extensions: Vec<Extension>,
+
+    /// TUI notifications preference. When set, the TUI posts desktop notifications on approvals
     /// and turn completions when not focused.
     pub tui_notifications: Notifications,
 
+    /// External program used by the TUI to render diffs, if configured.
+    pub tui_diff_renderer: Option<DiffRenderer>,
+
+    /// Theme settings for the TUI.
+    pub tui_theme: TuiTheme,
+
+    /// Key bindings for the TUI.
+    pub tui_keys: TuiKeys,
+
+    /// How the TUI composer is edited.
+    pub tui_editor_mode: EditorMode,
+
+    /// Whether the TUI captures the mouse.
+    pub tui_mouse: bool,
+
+    /// Batching of streamed model output in the TUI and `codex exec`.
+    pub stream_coalescing: StreamCoalescing,
+
     /// The directory that should be treated as the current working directory
     /// for the session. All relative paths inside the business-logic layer are
     /// resolved against this path.
@@ -131,6 +282,17 @@ pub struct Config {
     /// Definition for MCP servers that Codex can reach out to for tool calls.
     pub mcp_servers: HashMap<String, McpServerConfig>,
 
+    /// Start stdio MCP servers when one of their tools is first called
+    /// instead of at session start.
+    pub mcp_lazy_start: bool,
+
+    /// With `mcp_lazy_start`, stop stdio MCP servers that have not been
+    /// called for this long. `None` keeps them running.
+    pub mcp_idle_timeout: Option<Duration>,
+
+    /// Whether and how much MCP servers may sample the session's model.
+    pub mcp_sampling: McpSampling,
+
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
@@ -144,6 +306,9 @@ pub struct Config {
     /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
     pub history: History,
 
+    /// How session rollouts are written to disk.
+    pub rollout: RolloutDurability,
+
     /// Optional URI-based file opener. If set, citations to files in the model
     /// output will be hyperlinked using the specified URI scheme.
     pub file_opener: UriBasedFileOpener,
@@ -167,6 +332,10 @@ pub struct Config {
     /// Optional verbosity control for GPT-5 models (Responses API `text.verbosity`).
     pub model_verbosity: Option<Verbosity>,
 
+    /// Sampling temperature for model requests. Not configurable; set for
+    /// single turns by `Op::UserInputWithOverrides`.
+    pub model_temperature: Option<f32>,
+
     /// Base URL for requests to ChatGPT (as opposed to the OpenAI API).
     pub chatgpt_base_url: String,
 
@@ -192,6 +361,23 @@ pub struct Config {
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
 
+    /// Include the `git` tool, which requires explicit approval for
+    /// subcommands that rewrite history, discard work, or affect remotes.
+    pub include_git_tool: bool,
+
+    /// Include the `fetch_url` tool, which downloads a page as text within
+    /// the sandbox network policy.
+    pub include_fetch_url_tool: bool,
+
+    /// Include the `read_file`, `list_dir` and `write_file` tools.
+    pub include_file_tools: bool,
+
+    /// Include the `search_code` tool, a gitignore-aware regex search.
+    pub include_search_code_tool: bool,
+
+    /// Tools defined in `[tools.custom.<name>]` that run a command.
+    pub custom_tools: BTreeMap<String, CustomToolConfig>,
+
     /// The active profile name used to derive this `Config` (if any).
     pub active_profile: Option<String>,
 
@@ -340,11 +526,22 @@ pub fn write_global_mcp_servers(
                         entry["env"] = TomlItem::Table(env_table);
                     }
                 }
-                McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+                McpServerTransportConfig::StreamableHttp {
+                    url,
+                    bearer_token,
+                    headers,
+                } => {
                     entry["url"] = toml_edit::value(url.clone());
-                    if let Some(token) = bearer_token {
-                        entry["bearer_token"] = toml_edit::value(token.clone());
-                    }
+                    write_http_auth(&mut entry, bearer_token.as_ref(), headers.as_ref());
+                }
+                McpServerTransportConfig::Sse {
+                    url,
+                    bearer_token,
+                    headers,
+                } => {
+                    entry["type"] = toml_edit::value("sse");
+                    entry["url"] = toml_edit::value(url.clone());
+                    write_http_auth(&mut entry, bearer_token.as_ref(), headers.as_ref());
                 }
             }
 
@@ -356,6 +553,26 @@ pub fn write_global_mcp_servers(
                 entry["tool_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
             }
 
+            if let Some(tools) = &config.enabled_tools {
+                entry["enabled_tools"] = TomlItem::Value(
+                    tools
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<TomlArray>()
+                        .into(),
+                );
+            }
+
+            if let Some(tools) = &config.disabled_tools {
+                entry["disabled_tools"] = TomlItem::Value(
+                    tools
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<TomlArray>()
+                        .into(),
+                );
+            }
+
             doc["mcp_servers"][name.as_str()] = TomlItem::Table(entry);
         }
     }
@@ -368,6 +585,28 @@ pub fn write_global_mcp_servers(
     Ok(())
 }
 
+fn write_http_auth(
+    entry: &mut TomlTable,
+    bearer_token: Option<&String>,
+    headers: Option<&HashMap<String, String>>,
+) {
+    if let Some(token) = bearer_token {
+        entry["bearer_token"] = toml_edit::value(token.clone());
+    }
+    if let Some(headers) = headers
+        && !headers.is_empty()
+    {
+        let mut headers_table = TomlTable::new();
+        headers_table.set_implicit(false);
+        let mut pairs: Vec<_> = headers.iter().collect();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (key, value) in pairs {
+            headers_table.insert(key, toml_edit::value(value.clone()));
+        }
+        entry["headers"] = TomlItem::Table(headers_table);
+    }
+}
+
 fn set_project_trusted_inner(doc: &mut DocumentMut, project_path: &Path) -> anyhow::Result<()> {
     // Ensure we render a human-friendly structure:
     //
@@ -621,8 +860,13 @@ pub struct ConfigToml {
     /// Review model override used by the `/review` feature.
     pub review_model: Option<String>,
 
-    /// Provider to use from the model_providers map.
-    pub model_provider: Option<String>,
+    /// Provider to use from the model_providers map, or an ordered list
+    /// whose later entries are fallbacks for the first.
+    pub model_provider: Option<ModelProviderSelection>,
+
+    /// Providers from the model_providers map to fall back to, in order,
+    /// when the selected provider is unavailable.
+    pub provider_fallbacks: Option<Vec<String>>,
 
     /// Size of the context window for the model, in tokens.
     pub model_context_window: Option<u64>,
@@ -633,22 +877,94 @@ pub struct ConfigToml {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// When and how the conversation is compacted automatically.
+    pub auto_compact: Option<AutoCompact>,
+
+    /// Commit each applied patch to git.
+    pub auto_commit: Option<AutoCommit>,
+
+    /// Code hosts that pull requests are opened on.
+    #[serde(default)]
+    pub integrations: Integrations,
+
+    /// Commands run after applied patches, such as formatters.
+    #[serde(default)]
+    pub hooks: Hooks,
+
+    /// Notes the agent keeps about each project across sessions.
+    #[serde(default)]
+    pub memory: Memory,
+
+    /// Prices used to estimate the cost of a session, per model and
+    /// optionally per provider.
+    #[serde(default)]
+    pub pricing: Vec<ModelPricing>,
+
+    /// Stop a session once its estimated cost reaches this many US dollars.
+    pub session_budget_usd: Option<f64>,
+
+    /// Stop a task once it has made this many model requests.
+    pub max_turns: Option<u32>,
+
+    /// Stop a task once it has run for this many seconds.
+    pub max_runtime_secs: Option<u64>,
+
+    /// Strategy used to prune history before falling back to auto-compaction.
+    /// Defaults to `relevance`.
+    pub context_pruning: Option<ContextPruningStrategy>,
+
+    /// Submit Responses API requests in background mode. Defaults to `false`.
+    pub responses_background: Option<bool>,
+
+    /// Log raw model requests and responses. Defaults to `false`.
+    pub wire_log: Option<bool>,
+
+    /// Most read-only tool calls from one model response that run at the
+    /// same time. Defaults to `1`.
+    pub max_parallel_tool_calls: Option<usize>,
+
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
+    /// Patches scored at or above this risk level (`low`, `medium`, `high`)
+    /// always ask for approval. Unset by default.
+    pub escalate_patch_risk: Option<PatchRiskLevel>,
+
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// Limits applied to commands spawned by the shell tool, by sandbox mode.
+    pub sandbox_resource_limits: Option<SandboxResourceLimits>,
+
+    /// Time limits for commands spawned by the shell tool.
+    pub exec_timeout: Option<ExecTimeout>,
+
+    /// How `@https://…` mentions and attached URLs are fetched.
+    pub url_attachments: Option<UrlAttachments>,
+
+    /// Backend and limits of the `web_search` tool.
+    pub web_search: Option<WebSearch>,
+
     /// Sandbox mode to use.
     pub sandbox_mode: Option<SandboxMode>,
 
     /// Sandbox configuration to apply if `sandbox` is `WorkspaceWrite`.
     pub sandbox_workspace_write: Option<SandboxWorkspaceWrite>,
 
+    /// Sandbox configuration to apply if `sandbox` is `Container`.
+    pub sandbox_container: Option<SandboxContainer>,
+
+    /// Sandbox settings that apply in every mode, such as per-command rules.
+    pub sandbox: Option<SandboxToml>,
+
     /// Optional external command to spawn for end-user notifications.
     #[serde(default)]
     pub notify: Option<Vec<String>>,
 
+    /// Command to run to obtain a bearer token for model requests, e.g.
+    /// `["corp-sso", "token", "--audience", "codex"]`.
+    pub external_auth_command: Option<Vec<String>>,
+
     /// System instructions.
     pub instructions: Option<String>,
 
@@ -656,6 +972,18 @@ pub struct ConfigToml {
     #[serde(default)]
     pub mcp_servers: HashMap<String, McpServerConfig>,
 
+    /// Start stdio MCP servers on first use rather than at session start.
+    /// Defaults to `false`.
+    pub mcp_lazy_start: Option<bool>,
+
+    /// Seconds after which an idle, lazily started MCP server is stopped.
+    /// Defaults to 600; `0` keeps servers running.
+    pub mcp_idle_timeout_sec: Option<u64>,
+
+    /// Approval and token budget for `sampling/createMessage` requests from
+    /// MCP servers.
+    pub mcp_sampling: Option<McpSampling>,
+
     /// User-defined provider entries that extend/override the built-in list.
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
@@ -674,6 +1002,10 @@ pub struct ConfigToml {
     #[serde(default)]
     pub history: Option<History>,
 
+    /// How session rollouts are written to disk.
+    #[serde(default)]
+    pub rollout: Option<RolloutDurability>,
+
     /// Optional URI-based file opener. If set, citations to files in the model
     /// output will be hyperlinked using the specified URI scheme.
     pub file_opener: Option<UriBasedFileOpener>,
@@ -681,6 +1013,28 @@ pub struct ConfigToml {
     /// Collection of settings that are specific to the TUI.
     pub tui: Option<Tui>,
 
+    /// Batching of streamed model output in the TUI and `codex exec`.
+    pub stream_coalescing: Option<StreamCoalescing>,
+
+    /// Settings for how login credentials are stored.
+    pub auth: Option<AuthConfig>,
+
+    /// Network settings of the model provider clients.
+    pub network: Option<NetworkToml>,
+
+    /// Endpoints that receive protocol events.
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Plugin libraries loaded at startup.
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+
+    /// Settings of the extensions installed under `$CODEX_HOME/extensions`,
+    /// by name.
+    #[serde(default)]
+    pub extensions: HashMap<String, ExtensionSettings>,
+
     /// When set to `true`, `AgentReasoning` events will be hidden from the
     /// UI/output. Defaults to `false`.
     pub hide_agent_reasoning: Option<bool>,
@@ -757,6 +1111,26 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `git` tool that gates high-risk subcommands behind approval.
+    #[serde(default)]
+    pub git: Option<bool>,
+
+    /// Enable the `fetch_url` tool that downloads a page as readable text.
+    #[serde(default)]
+    pub fetch_url: Option<bool>,
+
+    /// Enable the structured `read_file`, `list_dir` and `write_file` tools.
+    #[serde(default)]
+    pub file_tools: Option<bool>,
+
+    /// Enable the `search_code` tool that searches file contents with a regex.
+    #[serde(default)]
+    pub search_code: Option<bool>,
+
+    /// Tools that run a command, keyed by tool name.
+    #[serde(default)]
+    pub custom: BTreeMap<String, CustomToolConfig>,
 }
 
 impl From<ToolsToml> for Tools {
@@ -770,11 +1144,14 @@ impl From<ToolsToml> for Tools {
 
 impl ConfigToml {
     /// Derive the effective sandbox policy from the configuration.
-    fn derive_sandbox_policy(&self, sandbox_mode_override: Option<SandboxMode>) -> SandboxPolicy {
+    fn derive_sandbox_policy(
+        &self,
+        sandbox_mode_override: Option<SandboxMode>,
+    ) -> std::io::Result<SandboxPolicy> {
         let resolved_sandbox_mode = sandbox_mode_override
             .or(self.sandbox_mode)
             .unwrap_or_default();
-        match resolved_sandbox_mode {
+        let sandbox_policy = match resolved_sandbox_mode {
             SandboxMode::ReadOnly => SandboxPolicy::new_read_only_policy(),
             SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
                 Some(SandboxWorkspaceWrite {
@@ -782,16 +1159,47 @@ impl ConfigToml {
                     network_access,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
+                    network_allowlist,
+                    deny,
                 }) => SandboxPolicy::WorkspaceWrite {
                     writable_roots: writable_roots.clone(),
                     network_access: *network_access,
                     exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
                     exclude_slash_tmp: *exclude_slash_tmp,
+                    network_allowlist: network_allowlist.clone(),
+                    deny: deny.clone(),
                 },
                 None => SandboxPolicy::new_workspace_write_policy(),
             },
             SandboxMode::DangerFullAccess => SandboxPolicy::DangerFullAccess,
+            SandboxMode::Container => {
+                let SandboxContainer {
+                    image,
+                    runtime,
+                    writable_roots,
+                    network_access,
+                } = self.sandbox_container.clone().unwrap_or_default();
+                let Some(image) = image.filter(|image| !image.is_empty()) else {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "sandbox_mode = \"container\" requires `image` in [sandbox_container]",
+                    ));
+                };
+                SandboxPolicy::Container {
+                    image,
+                    runtime,
+                    writable_roots,
+                    network_access,
+                }
+            }
+        };
+        if cfg!(target_os = "linux") && !sandbox_policy.network_allowlist().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                NETWORK_ALLOWLIST_UNSUPPORTED,
+            ));
         }
+        Ok(sandbox_policy)
     }
 
     pub fn is_cwd_trusted(&self, resolved_cwd: &Path) -> bool {
@@ -907,7 +1315,7 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
-        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode);
+        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode)?;
 
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
@@ -915,10 +1323,15 @@ impl Config {
             model_providers.entry(key).or_insert(provider);
         }
 
-        let model_provider_id = model_provider
+        let (model_provider_id, chained_fallbacks) = match model_provider
+            .map(ModelProviderSelection::Single)
             .or(config_profile.model_provider)
             .or(cfg.model_provider)
-            .unwrap_or_else(|| "openai".to_string());
+        {
+            Some(selection) => selection.split(),
+            None => (None, Vec::new()),
+        };
+        let model_provider_id = model_provider_id.unwrap_or_else(|| "openai".to_string());
         let model_provider = model_providers
             .get(&model_provider_id)
             .ok_or_else(|| {
@@ -928,8 +1341,29 @@ impl Config {
                 )
             })?
             .clone();
+        let provider_fallbacks = if chained_fallbacks.is_empty() {
+            config_profile
+                .provider_fallbacks
+                .or(cfg.provider_fallbacks)
+                .unwrap_or_default()
+        } else {
+            chained_fallbacks
+        };
+        if let Some(unknown) = provider_fallbacks
+            .iter()
+            .find(|id| !model_providers.contains_key(*id))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Fallback model provider `{unknown}` not found"),
+            ));
+        }
 
-        let shell_environment_policy = cfg.shell_environment_policy.into();
+        let shell_environment_policy = match config_profile.shell_environment_policy {
+            Some(overrides) => cfg.shell_environment_policy.merge(overrides),
+            None => cfg.shell_environment_policy,
+        }
+        .into();
 
         let resolved_cwd = {
             use std::env;
@@ -960,6 +1394,30 @@ impl Config {
             .or(cfg.tools.as_ref().and_then(|t| t.view_image))
             .unwrap_or(true);
 
+        let include_git_tool = cfg.tools.as_ref().and_then(|t| t.git).unwrap_or(false);
+        let include_fetch_url_tool = cfg
+            .tools
+            .as_ref()
+            .and_then(|t| t.fetch_url)
+            .unwrap_or(false);
+        let include_file_tools = cfg
+            .tools
+            .as_ref()
+            .and_then(|t| t.file_tools)
+            .unwrap_or(false);
+        let include_search_code_tool = cfg
+            .tools
+            .as_ref()
+            .and_then(|t| t.search_code)
+            .unwrap_or(false);
+        let custom_tools = cfg
+            .tools
+            .as_ref()
+            .map(|t| t.custom.clone())
+            .unwrap_or_default();
+        validate_custom_tools(&custom_tools)
+            .map_err(|message| std::io::Error::new(std::io::ErrorKind::InvalidInput, message))?;
+
         let model = model
             .or(config_profile.model)
             .or(cfg.model)
@@ -1006,6 +1464,22 @@ impl Config {
             .or(cfg.review_model)
             .unwrap_or_else(default_review_model);
 
+        let network_proxy = cfg.network.and_then(|network| network.proxy);
+        if let Some(proxy) = &network_proxy {
+            reqwest::Proxy::all(&proxy.url).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid network.proxy.url `{}`: {e}", proxy.url),
+                )
+            })?;
+        }
+
+        let extensions = discover_extensions(&codex_home, &cfg.extensions);
+        let mut mcp_servers = cfg.mcp_servers;
+        for (name, server) in extension_servers(&extensions) {
+            mcp_servers.entry(name).or_insert(server);
+        }
+
         let config = Self {
             model,
             review_model,
@@ -1013,23 +1487,60 @@ impl Config {
             model_context_window,
             model_max_output_tokens,
             model_auto_compact_token_limit,
+            auto_compact: cfg.auto_compact.unwrap_or_default(),
+            auto_commit: cfg.auto_commit.unwrap_or_default(),
+            integrations: cfg.integrations,
+            hooks: cfg.hooks,
+            memory: cfg.memory,
+            pricing: cfg.pricing,
+            session_budget_usd: cfg.session_budget_usd,
+            max_turns: cfg.max_turns,
+            max_runtime: cfg.max_runtime_secs.map(Duration::from_secs),
+            include_report_failure_tool: false,
+            context_pruning: cfg.context_pruning.unwrap_or_default(),
+            responses_background: cfg.responses_background.unwrap_or(false),
+            wire_log: cfg.wire_log.unwrap_or(false),
+            max_parallel_tool_calls: cfg.max_parallel_tool_calls.unwrap_or(1).max(1),
             model_provider_id,
             model_provider,
+            provider_fallbacks,
             cwd: resolved_cwd,
+            escalate_patch_risk: cfg.escalate_patch_risk,
+            sandbox_audit: cfg
+                .sandbox
+                .as_ref()
+                .and_then(|sandbox| sandbox.audit)
+                .unwrap_or(true),
+            sandbox_rules: cfg.sandbox.map(|sandbox| sandbox.rules).unwrap_or_default(),
             approval_policy: approval_policy
                 .or(config_profile.approval_policy)
                 .or(cfg.approval_policy)
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
             shell_environment_policy,
+            sandbox_resource_limits: cfg.sandbox_resource_limits.unwrap_or_default(),
+            exec_timeout: cfg.exec_timeout.unwrap_or_default(),
+            url_attachments: cfg.url_attachments.unwrap_or_default(),
+            web_search: cfg.web_search.unwrap_or_default(),
             notify: cfg.notify,
+            external_auth_command: cfg.external_auth_command,
             user_instructions,
             base_instructions,
-            mcp_servers: cfg.mcp_servers,
+            mcp_servers,
+            mcp_lazy_start: cfg.mcp_lazy_start.unwrap_or(false),
+            mcp_idle_timeout: match cfg
+                .mcp_idle_timeout_sec
+                .unwrap_or(DEFAULT_MCP_IDLE_TIMEOUT_SEC)
+            {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            mcp_sampling: cfg.mcp_sampling.unwrap_or_default(),
             model_providers,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
             codex_home,
             history,
+            rollout: cfg.rollout.unwrap_or_default(),
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             codex_linux_sandbox_exe,
 
@@ -1046,6 +1557,7 @@ impl Config {
                 .or(cfg.model_reasoning_summary)
                 .unwrap_or_default(),
             model_verbosity: config_profile.model_verbosity.or(cfg.model_verbosity),
+            model_temperature: None,
             chatgpt_base_url: config_profile
                 .chatgpt_base_url
                 .or(cfg.chatgpt_base_url)
@@ -1061,6 +1573,11 @@ impl Config {
                 .unwrap_or(false),
             use_experimental_use_rmcp_client: cfg.experimental_use_rmcp_client.unwrap_or(false),
             include_view_image_tool,
+            include_git_tool,
+            include_fetch_url_tool,
+            include_file_tools,
+            include_search_code_tool,
+            custom_tools,
             active_profile: active_profile_name,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
             tui_notifications: cfg
@@ -1068,6 +1585,25 @@ impl Config {
                 .as_ref()
                 .map(|t| t.notifications.clone())
                 .unwrap_or_default(),
+            tui_diff_renderer: cfg.tui.as_ref().and_then(|t| t.diff_renderer.clone()),
+            tui_theme: cfg
+                .tui
+                .as_ref()
+                .map(|t| t.theme.clone())
+                .unwrap_or_default(),
+            tui_keys: cfg.tui.as_ref().map(|t| t.keys.clone()).unwrap_or_default(),
+            tui_editor_mode: cfg.tui.as_ref().map(|t| t.editor_mode).unwrap_or_default(),
+            tui_mouse: cfg.tui.as_ref().is_some_and(|t| t.mouse),
+            stream_coalescing: cfg.stream_coalescing.unwrap_or_default(),
+            credential_store: cfg
+                .auth
+                .as_ref()
+                .map(|auth| auth.credential_store)
+                .unwrap_or_default(),
+            network_proxy,
+            webhooks: cfg.webhooks,
+            plugins: cfg.plugins,
+            extensions,
         };
         Ok(config)
     }
@@ -1177,13 +1713,16 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
+    use crate::config_types::EnvironmentVariablePattern;
     use crate::config_types::HistoryPersistence;
     use crate::config_types::Notifications;
+    use crate::config_types::ShellEnvironmentPolicyInherit;
+    use codex_protocol::config_types::ContainerRuntime;
 
     use super::*;
     use pretty_assertions::assert_eq;
 
-    use std::time::Duration;
+    use Duration;
     use tempfile::TempDir;
 
     #[test]
@@ -1218,6 +1757,135 @@ persistence = "none"
         );
     }
 
+    #[test]
+    fn rollout_durability_fills_in_defaults() {
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+[rollout]
+flush_every_item = false
+fsync = true
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        assert_eq!(
+            Some(RolloutDurability {
+                flush_every_item: false,
+                fsync: true,
+                ..RolloutDurability::default()
+            }),
+            cfg.rollout
+        );
+
+        let zero_interval = toml::from_str::<ConfigToml>(
+            r#"
+[rollout]
+flush_interval_ms = 0
+"#,
+        );
+        assert!(zero_interval.is_err());
+    }
+
+    #[test]
+    fn auto_compact_fills_in_defaults() {
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+[auto_compact]
+threshold_percent = 80
+model = "gpt-5-mini"
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        assert_eq!(
+            Some(AutoCompact {
+                threshold_percent: Some(80),
+                model: Some("gpt-5-mini".to_string()),
+                keep_recent_turns: 2,
+            }),
+            cfg.auto_compact
+        );
+    }
+
+    #[test]
+    fn profile_overrides_shell_environment_policy_fields() -> std::io::Result<()> {
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+[shell_environment_policy]
+inherit = "core"
+exclude = ["NPM_*"]
+
+[profiles.aws]
+shell_environment_policy = { include = ["AWS_PROFILE", "AWS_REGION"] }
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let codex_home = TempDir::new()?;
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                config_profile: Some("aws".to_string()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+
+        let policy = config.shell_environment_policy;
+        assert_eq!(policy.inherit, ShellEnvironmentPolicyInherit::Core);
+        assert_eq!(
+            policy.exclude,
+            vec![EnvironmentVariablePattern::new_case_insensitive("NPM_*")]
+        );
+        assert_eq!(
+            policy.include,
+            vec![
+                EnvironmentVariablePattern::new_case_insensitive("AWS_PROFILE"),
+                EnvironmentVariablePattern::new_case_insensitive("AWS_REGION"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn model_provider_list_sets_fallbacks() -> std::io::Result<()> {
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+model_provider = ["oss", "openai"]
+provider_fallbacks = ["oss"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let codex_home = TempDir::new()?;
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(config.model_provider_id, "oss");
+        assert_eq!(config.provider_fallbacks, vec!["openai".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_fallback_provider_is_an_error() {
+        let cfg = toml::from_str::<ConfigToml>(r#"provider_fallbacks = ["missing"]"#)
+            .expect("TOML deserialization should succeed");
+        let codex_home = TempDir::new().expect("tempdir");
+
+        let err = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect_err("unknown fallback should be rejected");
+
+        assert_eq!(
+            err.to_string(),
+            "Fallback model provider `missing` not found"
+        );
+    }
+
     #[test]
     fn tui_config_missing_notifications_field_defaults_to_disabled() {
         let cfg = r#"
@@ -1231,6 +1899,66 @@ persistence = "none"
         assert_eq!(tui.notifications, Notifications::Enabled(false));
     }
 
+    #[test]
+    fn tui_theme_palettes_are_parsed() {
+        let cfg = r##"
+[tui.theme]
+name = "mine"
+
+[tui.theme.palettes.mine]
+base = "light"
+diff_add = "#859900"
+"##;
+
+        let parsed = toml::from_str::<ConfigToml>(cfg).expect("TUI theme should parse");
+        let theme = parsed.tui.expect("config should include tui section").theme;
+
+        assert_eq!(theme.name.as_deref(), Some("mine"));
+        assert_eq!(
+            theme.palettes.get("mine"),
+            Some(&crate::config_types::ThemePalette {
+                base: Some("light".to_string()),
+                diff_add: Some("#859900".to_string()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn tui_editor_mode_is_parsed() {
+        let parsed = toml::from_str::<ConfigToml>("[tui]\neditor_mode = \"vim\"\n")
+            .expect("TUI editor mode should parse");
+        let tui = parsed.tui.expect("config should include tui section");
+        assert_eq!(tui.editor_mode, EditorMode::Vim);
+
+        let parsed = toml::from_str::<ConfigToml>("[tui]\n").expect("empty tui section");
+        assert_eq!(
+            parsed.tui.expect("tui section").editor_mode,
+            EditorMode::Default
+        );
+    }
+
+    #[test]
+    fn tui_mouse_is_opt_in() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert!(!config.tui_mouse);
+
+        let cfg = toml::from_str::<ConfigToml>("[tui]\nmouse = true\n")
+            .expect("TUI mouse setting should parse");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert!(config.tui_mouse);
+        Ok(())
+    }
+
     #[test]
     fn test_sandbox_config_parsing() {
         let sandbox_full_access = r#"
@@ -1244,7 +1972,9 @@ network_access = false  # This should be ignored.
         let sandbox_mode_override = None;
         assert_eq!(
             SandboxPolicy::DangerFullAccess,
-            sandbox_full_access_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_full_access_cfg
+                .derive_sandbox_policy(sandbox_mode_override)
+                .expect("sandbox policy should derive")
         );
 
         let sandbox_read_only = r#"
@@ -1259,7 +1989,9 @@ network_access = true  # This should be ignored.
         let sandbox_mode_override = None;
         assert_eq!(
             SandboxPolicy::ReadOnly,
-            sandbox_read_only_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_read_only_cfg
+                .derive_sandbox_policy(sandbox_mode_override)
+                .expect("sandbox policy should derive")
         );
 
         let sandbox_workspace_write = r#"
@@ -1271,19 +2003,64 @@ writable_roots = [
 ]
 exclude_tmpdir_env_var = true
 exclude_slash_tmp = true
+network_allowlist = ["crates.io", "10.0.0.0/8"]
 "#;
 
         let sandbox_workspace_write_cfg = toml::from_str::<ConfigToml>(sandbox_workspace_write)
             .expect("TOML deserialization should succeed");
         let sandbox_mode_override = None;
+        let derived = sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override);
+        if cfg!(target_os = "linux") {
+            let err = derived.expect_err("the Linux sandbox cannot enforce an allowlist");
+            assert!(err.to_string().contains("network_allowlist"), "{err}");
+        } else {
+            assert_eq!(
+                SandboxPolicy::WorkspaceWrite {
+                    writable_roots: vec![PathBuf::from("/my/workspace")],
+                    network_access: false,
+                    exclude_tmpdir_env_var: true,
+                    exclude_slash_tmp: true,
+                    network_allowlist: vec!["crates.io".to_string(), "10.0.0.0/8".to_string()],
+                    deny: Vec::new(),
+                },
+                derived.expect("sandbox policy should derive")
+            );
+        }
+
+        let sandbox_container = r#"
+sandbox_mode = "container"
+
+[sandbox_container]
+image = "rust:1.90"
+runtime = "podman"
+writable_roots = ["/my/cache"]
+"#;
+
+        let sandbox_container_cfg = toml::from_str::<ConfigToml>(sandbox_container)
+            .expect("TOML deserialization should succeed");
+        let sandbox_mode_override = None;
         assert_eq!(
-            SandboxPolicy::WorkspaceWrite {
-                writable_roots: vec![PathBuf::from("/my/workspace")],
+            SandboxPolicy::Container {
+                image: "rust:1.90".to_string(),
+                runtime: Some(ContainerRuntime::Podman),
+                writable_roots: vec![PathBuf::from("/my/cache")],
                 network_access: false,
-                exclude_tmpdir_env_var: true,
-                exclude_slash_tmp: true,
             },
-            sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override)
+            sandbox_container_cfg
+                .derive_sandbox_policy(sandbox_mode_override)
+                .expect("sandbox policy should derive")
+        );
+
+        let sandbox_container_without_image = r#"
+sandbox_mode = "container"
+"#;
+        let sandbox_container_without_image_cfg =
+            toml::from_str::<ConfigToml>(sandbox_container_without_image)
+                .expect("TOML deserialization should succeed");
+        assert!(
+            sandbox_container_without_image_cfg
+                .derive_sandbox_policy(None)
+                .is_err()
         );
     }
 
@@ -1312,6 +2089,8 @@ exclude_slash_tmp = true
                 },
                 startup_timeout_sec: Some(Duration::from_secs(3)),
                 tool_timeout_sec: Some(Duration::from_secs(5)),
+                enabled_tools: None,
+                disabled_tools: None,
             },
         );
 
@@ -1378,6 +2157,8 @@ startup_timeout_ms = 2500
                 },
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
             },
         )]);
 
@@ -1425,9 +2206,12 @@ ZIG_VAR = "3"
                 transport: McpServerTransportConfig::StreamableHttp {
                     url: "https://example.com/mcp".to_string(),
                     bearer_token: Some("secret-token".to_string()),
+                    headers: None,
                 },
                 startup_timeout_sec: Some(Duration::from_secs(2)),
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
             },
         )]);
 
@@ -1447,7 +2231,9 @@ startup_timeout_sec = 2.0
         let loaded = load_global_mcp_servers(codex_home.path())?;
         let docs = loaded.get("docs").expect("docs entry");
         match &docs.transport {
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+            McpServerTransportConfig::StreamableHttp {
+                url, bearer_token, ..
+            } => {
                 assert_eq!(url, "https://example.com/mcp");
                 assert_eq!(bearer_token.as_deref(), Some("secret-token"));
             }
@@ -1461,9 +2247,12 @@ startup_timeout_sec = 2.0
                 transport: McpServerTransportConfig::StreamableHttp {
                     url: "https://example.com/mcp".to_string(),
                     bearer_token: None,
+                    headers: None,
                 },
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
             },
         );
         write_global_mcp_servers(codex_home.path(), &servers)?;
@@ -1479,7 +2268,9 @@ url = "https://example.com/mcp"
         let loaded = load_global_mcp_servers(codex_home.path())?;
         let docs = loaded.get("docs").expect("docs entry");
         match &docs.transport {
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+            McpServerTransportConfig::StreamableHttp {
+                url, bearer_token, ..
+            } => {
                 assert_eq!(url, "https://example.com/mcp");
                 assert!(bearer_token.is_none());
             }
@@ -1708,15 +2499,23 @@ model_verbosity = "high"
         let openai_chat_completions_provider = ModelProviderInfo {
             name: "OpenAI using Chat Completions".to_string(),
             base_url: Some("https://api.openai.com/v1".to_string()),
+            fallback_base_urls: None,
             env_key: Some("OPENAI_API_KEY".to_string()),
             wire_api: crate::WireApi::Chat,
             env_key_instructions: None,
             query_params: None,
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: Some(4),
             stream_max_retries: Some(10),
             stream_idle_timeout_ms: Some(300_000),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
         let model_provider_map = {
@@ -1777,19 +2576,46 @@ model_verbosity = "high"
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
                 model_auto_compact_token_limit: None,
+                auto_compact: AutoCompact::default(),
+                auto_commit: AutoCommit::default(),
+                integrations: Integrations::default(),
+                hooks: Hooks::default(),
+                memory: Memory::default(),
+                pricing: Vec::new(),
+                session_budget_usd: None,
+                max_turns: None,
+                max_runtime: None,
+                include_report_failure_tool: false,
+                context_pruning: ContextPruningStrategy::default(),
+                responses_background: false,
+                wire_log: false,
+                max_parallel_tool_calls: 1,
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
+                provider_fallbacks: Vec::new(),
                 approval_policy: AskForApproval::Never,
+                escalate_patch_risk: None,
+                sandbox_rules: Vec::new(),
+                sandbox_audit: true,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
+                sandbox_resource_limits: SandboxResourceLimits::default(),
+                exec_timeout: ExecTimeout::default(),
+                url_attachments: UrlAttachments::default(),
+                web_search: WebSearch::default(),
                 user_instructions: None,
                 notify: None,
+                external_auth_command: None,
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
+                mcp_lazy_start: false,
+                mcp_idle_timeout: Some(Duration::from_secs(600)),
+                mcp_sampling: McpSampling::default(),
                 model_providers: fixture.model_provider_map.clone(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
                 codex_home: fixture.codex_home(),
                 history: History::default(),
+                rollout: RolloutDurability::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 codex_linux_sandbox_exe: None,
                 hide_agent_reasoning: false,
@@ -1797,6 +2623,7 @@ model_verbosity = "high"
                 model_reasoning_effort: Some(ReasoningEffort::High),
                 model_reasoning_summary: ReasoningSummary::Detailed,
                 model_verbosity: None,
+                model_temperature: None,
                 chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
                 base_instructions: None,
                 include_plan_tool: false,
@@ -1806,9 +2633,25 @@ model_verbosity = "high"
                 use_experimental_unified_exec_tool: false,
                 use_experimental_use_rmcp_client: false,
                 include_view_image_tool: true,
+                include_git_tool: false,
+                include_fetch_url_tool: false,
+                include_file_tools: false,
+                include_search_code_tool: false,
+                custom_tools: BTreeMap::new(),
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
                 tui_notifications: Default::default(),
+                tui_diff_renderer: None,
+                tui_theme: TuiTheme::default(),
+                tui_keys: TuiKeys::default(),
+                tui_editor_mode: EditorMode::default(),
+                tui_mouse: false,
+                stream_coalescing: StreamCoalescing::default(),
+                credential_store: CredentialStoreMode::File,
+                network_proxy: None,
+                webhooks: Vec::new(),
+                plugins: Vec::new(),
+                extensions: Vec::new(),
             },
             o3_profile_config
         );
@@ -1836,19 +2679,46 @@ model_verbosity = "high"
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
             model_auto_compact_token_limit: None,
+            auto_compact: AutoCompact::default(),
+            auto_commit: AutoCommit::default(),
+            integrations: Integrations::default(),
+            hooks: Hooks::default(),
+            memory: Memory::default(),
+            pricing: Vec::new(),
+            session_budget_usd: None,
+            max_turns: None,
+            max_runtime: None,
+            include_report_failure_tool: false,
+            context_pruning: ContextPruningStrategy::default(),
+            responses_background: false,
+            wire_log: false,
+            max_parallel_tool_calls: 1,
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
+            provider_fallbacks: Vec::new(),
             approval_policy: AskForApproval::UnlessTrusted,
+            escalate_patch_risk: None,
+            sandbox_rules: Vec::new(),
+            sandbox_audit: true,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            sandbox_resource_limits: SandboxResourceLimits::default(),
+            exec_timeout: ExecTimeout::default(),
+            url_attachments: UrlAttachments::default(),
+            web_search: WebSearch::default(),
             user_instructions: None,
             notify: None,
+            external_auth_command: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
+            mcp_lazy_start: false,
+            mcp_idle_timeout: Some(Duration::from_secs(600)),
+            mcp_sampling: McpSampling::default(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
+            rollout: RolloutDurability::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
@@ -1856,6 +2726,7 @@ model_verbosity = "high"
             model_reasoning_effort: None,
             model_reasoning_summary: ReasoningSummary::default(),
             model_verbosity: None,
+            model_temperature: None,
             chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
             base_instructions: None,
             include_plan_tool: false,
@@ -1865,9 +2736,25 @@ model_verbosity = "high"
             use_experimental_unified_exec_tool: false,
             use_experimental_use_rmcp_client: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            custom_tools: BTreeMap::new(),
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
+            tui_diff_renderer: None,
+            tui_theme: TuiTheme::default(),
+            tui_keys: TuiKeys::default(),
+            tui_editor_mode: EditorMode::default(),
+            tui_mouse: false,
+            stream_coalescing: StreamCoalescing::default(),
+            credential_store: CredentialStoreMode::File,
+            network_proxy: None,
+            webhooks: Vec::new(),
+            plugins: Vec::new(),
+            extensions: Vec::new(),
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -1910,19 +2797,46 @@ model_verbosity = "high"
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
             model_auto_compact_token_limit: None,
+            auto_compact: AutoCompact::default(),
+            auto_commit: AutoCommit::default(),
+            integrations: Integrations::default(),
+            hooks: Hooks::default(),
+            memory: Memory::default(),
+            pricing: Vec::new(),
+            session_budget_usd: None,
+            max_turns: None,
+            max_runtime: None,
+            include_report_failure_tool: false,
+            context_pruning: ContextPruningStrategy::default(),
+            responses_background: false,
+            wire_log: false,
+            max_parallel_tool_calls: 1,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            provider_fallbacks: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
+            escalate_patch_risk: None,
+            sandbox_rules: Vec::new(),
+            sandbox_audit: true,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            sandbox_resource_limits: SandboxResourceLimits::default(),
+            exec_timeout: ExecTimeout::default(),
+            url_attachments: UrlAttachments::default(),
+            web_search: WebSearch::default(),
             user_instructions: None,
             notify: None,
+            external_auth_command: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
+            mcp_lazy_start: false,
+            mcp_idle_timeout: Some(Duration::from_secs(600)),
+            mcp_sampling: McpSampling::default(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
+            rollout: RolloutDurability::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
@@ -1930,6 +2844,7 @@ model_verbosity = "high"
             model_reasoning_effort: None,
             model_reasoning_summary: ReasoningSummary::default(),
             model_verbosity: None,
+            model_temperature: None,
             chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
             base_instructions: None,
             include_plan_tool: false,
@@ -1939,9 +2854,25 @@ model_verbosity = "high"
             use_experimental_unified_exec_tool: false,
             use_experimental_use_rmcp_client: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            custom_tools: BTreeMap::new(),
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
+            tui_diff_renderer: None,
+            tui_theme: TuiTheme::default(),
+            tui_keys: TuiKeys::default(),
+            tui_editor_mode: EditorMode::default(),
+            tui_mouse: false,
+            stream_coalescing: StreamCoalescing::default(),
+            credential_store: CredentialStoreMode::File,
+            network_proxy: None,
+            webhooks: Vec::new(),
+            plugins: Vec::new(),
+            extensions: Vec::new(),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -1970,19 +2901,46 @@ model_verbosity = "high"
             model_context_window: Some(272_000),
             model_max_output_tokens: Some(128_000),
             model_auto_compact_token_limit: None,
+            auto_compact: AutoCompact::default(),
+            auto_commit: AutoCommit::default(),
+            integrations: Integrations::default(),
+            hooks: Hooks::default(),
+            memory: Memory::default(),
+            pricing: Vec::new(),
+            session_budget_usd: None,
+            max_turns: None,
+            max_runtime: None,
+            include_report_failure_tool: false,
+            context_pruning: ContextPruningStrategy::default(),
+            responses_background: false,
+            wire_log: false,
+            max_parallel_tool_calls: 1,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            provider_fallbacks: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
+            escalate_patch_risk: None,
+            sandbox_rules: Vec::new(),
+            sandbox_audit: true,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            sandbox_resource_limits: SandboxResourceLimits::default(),
+            exec_timeout: ExecTimeout::default(),
+            url_attachments: UrlAttachments::default(),
+            web_search: WebSearch::default(),
             user_instructions: None,
             notify: None,
+            external_auth_command: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
+            mcp_lazy_start: false,
+            mcp_idle_timeout: Some(Duration::from_secs(600)),
+            mcp_sampling: McpSampling::default(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
+            rollout: RolloutDurability::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
@@ -1990,6 +2948,7 @@ model_verbosity = "high"
             model_reasoning_effort: Some(ReasoningEffort::High),
             model_reasoning_summary: ReasoningSummary::Detailed,
             model_verbosity: Some(Verbosity::High),
+            model_temperature: None,
             chatgpt_base_url: "https://chatgpt.com/backend-api/".to_string(),
             base_instructions: None,
             include_plan_tool: false,
@@ -1999,9 +2958,25 @@ model_verbosity = "high"
             use_experimental_unified_exec_tool: false,
             use_experimental_use_rmcp_client: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            custom_tools: BTreeMap::new(),
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
             tui_notifications: Default::default(),
+            tui_diff_renderer: None,
+            tui_theme: TuiTheme::default(),
+            tui_keys: TuiKeys::default(),
+            tui_editor_mode: EditorMode::default(),
+            tui_mouse: false,
+            stream_coalescing: StreamCoalescing::default(),
+            credential_store: CredentialStoreMode::File,
+            network_proxy: None,
+            webhooks: Vec::new(),
+            plugins: Vec::new(),
+            extensions: Vec::new(),
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
@@ -2108,6 +3083,7 @@ trust_level = "trusted"
 
 #[cfg(test)]
 mod notifications_tests {
+    use crate::config_types::NotificationMethod;
     use crate::config_types::Notifications;
     use serde::Deserialize;
 
@@ -2147,4 +3123,25 @@ mod notifications_tests {
             Notifications::Custom(ref v) if v == &vec!["foo".to_string()]
         ));
     }
+
+    #[test]
+    fn test_tui_notifications_table() {
+        let toml = r#"
+            [tui.notifications]
+            types = ["approval-requested"]
+            method = "native"
+            min_turn_duration_secs = 30
+        "#;
+        let parsed: RootTomlTest = toml::from_str(toml).expect("deserialize [tui.notifications]");
+        let notifications = parsed.tui.notifications;
+
+        assert!(notifications.allows("approval-requested"));
+        assert!(!notifications.allows("agent-turn-complete"));
+        assert_eq!(notifications.method(), NotificationMethod::Native);
+        assert_eq!(
+            notifications.min_turn_duration(),
+            std::time::Duration::from_secs(30)
+        );
+        assert!(!Notifications::default().allows("approval-requested"));
+    }
 }
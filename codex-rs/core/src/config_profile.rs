@@ -1,6 +1,8 @@
 use serde::Deserialize;
 use std::path::PathBuf;
 
+use crate::config_types::ModelProviderSelection;
+use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::protocol::AskForApproval;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
@@ -12,21 +14,31 @@ use codex_protocol::config_types::Verbosity;
 pub struct ConfigProfile {
     pub model: Option<String>,
     /// The key in the `model_providers` map identifying the
-    /// [`ModelProviderInfo`] to use.
-    pub model_provider: Option<String>,
+    /// [`ModelProviderInfo`] to use, or an ordered list of keys whose later
+    /// entries are fallbacks.
+    pub model_provider: Option<ModelProviderSelection>,
+    /// Overrides the top-level `provider_fallbacks`.
+    pub provider_fallbacks: Option<Vec<String>>,
     pub approval_policy: Option<AskForApproval>,
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
     pub model_verbosity: Option<Verbosity>,
     pub chatgpt_base_url: Option<String>,
     pub experimental_instructions_file: Option<PathBuf>,
+    /// Overrides the fields it sets of the top-level
+    /// `shell_environment_policy`.
+    pub shell_environment_policy: Option<ShellEnvironmentPolicyToml>,
 }
 
 impl From<ConfigProfile> for codex_protocol::mcp_protocol::Profile {
     fn from(config_profile: ConfigProfile) -> Self {
         Self {
             model: config_profile.model,
-            model_provider: config_profile.model_provider,
+            model_provider: config_profile
+                .model_provider
+                .as_ref()
+                .and_then(ModelProviderSelection::primary)
+                .map(str::to_string),
             approval_policy: config_profile.approval_policy,
             model_reasoning_effort: config_profile.model_reasoning_effort,
             model_reasoning_summary: config_profile.model_reasoning_summary,
@@ -0,0 +1,339 @@
+use crate::config_types::ResourceLimits;
+use crate::protocol::SandboxPolicy;
+use crate::spawn::CODEX_SANDBOX_ENV_VAR;
+use crate::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use crate::spawn::StdioPolicy;
+use crate::spawn::spawn_child_async;
+use codex_protocol::config_types::ContainerRuntime;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::process::Child;
+use tokio::process::Command;
+
+/// Variables that describe the host rather than the command, so they are not
+/// forwarded into the container, where the image provides its own values.
+const HOST_ONLY_ENV_VARS: &[&str] = &[
+    "HOME", "LOGNAME", "OLDPWD", "PATH", "PWD", "SHELL", "TMPDIR", "USER",
+];
+
+/// A container started for a single shell tool call.
+///
+/// The runtime client does not stop the container when the client itself is
+/// killed (e.g. on timeout), so [`SpawnedContainer::remove`] must be called
+/// once the command has finished.
+pub struct SpawnedContainer {
+    runtime_path: PathBuf,
+    name: String,
+}
+
+impl SpawnedContainer {
+    /// Force-removes the container. Errors are ignored because the container
+    /// is normally already gone thanks to `--rm`.
+    pub async fn remove(self) {
+        let _ = Command::new(&self.runtime_path)
+            .args(["rm", "--force", &self.name])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+    }
+}
+
+/// Spawn a shell tool command in a throwaway container created from the image
+/// of a [`SandboxPolicy::Container`] policy.
+///
+/// The writable roots are bind-mounted at the same paths as on the host (with
+/// `.git` mounted read-only) so that paths in the command and its output mean
+/// the same thing inside and outside the container.
+pub async fn spawn_command_under_container(
+    command: Vec<String>,
+    command_cwd: PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    resource_limits: &ResourceLimits,
+    stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
+) -> io::Result<(Child, SpawnedContainer)> {
+    let SandboxPolicy::Container { image, runtime, .. } = sandbox_policy else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "container sandbox requires a container sandbox policy",
+        ));
+    };
+    let (runtime, runtime_path) = resolve_runtime(*runtime)?;
+    let name = format!("codex-exec-{}", uuid::Uuid::new_v4());
+
+    let mut args = create_container_run_args(
+        runtime,
+        &name,
+        &command_cwd,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        resource_limits,
+    )?;
+    args.extend(host_user_args(runtime));
+    args.extend(forwarded_env_args(&env));
+    args.push(image.clone());
+    args.extend(command);
+
+    // Resource limits are enforced by the runtime inside the container, not
+    // as rlimits on the client process.
+    let child = spawn_child_async(
+        runtime_path.clone(),
+        args,
+        None,
+        command_cwd,
+        sandbox_policy,
+        &ResourceLimits::default(),
+        stdio_policy,
+        env,
+    )
+    .await?;
+    Ok((child, SpawnedContainer { runtime_path, name }))
+}
+
+/// Finds the container engine to use: `runtime` when configured, otherwise
+/// `docker` and then `podman` from `PATH`.
+pub fn resolve_runtime(
+    runtime: Option<ContainerRuntime>,
+) -> io::Result<(ContainerRuntime, PathBuf)> {
+    let candidates = match runtime {
+        Some(runtime) => vec![runtime],
+        None => vec![ContainerRuntime::Docker, ContainerRuntime::Podman],
+    };
+    for candidate in &candidates {
+        if let Ok(path) = which::which(candidate.to_string()) {
+            return Ok((*candidate, path));
+        }
+    }
+    let message = match runtime {
+        Some(runtime) => format!("`{runtime}` was not found on PATH"),
+        None => "neither `docker` nor `podman` was found on PATH".to_string(),
+    };
+    Err(io::Error::new(io::ErrorKind::NotFound, message))
+}
+
+/// Converts the sandbox policy into the `run` arguments of the container
+/// runtime, up to but excluding the image.
+fn create_container_run_args(
+    runtime: ContainerRuntime,
+    name: &str,
+    command_cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    resource_limits: &ResourceLimits,
+) -> io::Result<Vec<String>> {
+    let mut args = vec![
+        "run".to_string(),
+        "--rm".to_string(),
+        // Reaps zombies and forwards signals to the command.
+        "--init".to_string(),
+        "--name".to_string(),
+        name.to_string(),
+    ];
+
+    if !sandbox_policy.has_full_network_access() {
+        args.push("--network".to_string());
+        args.push("none".to_string());
+    }
+
+    for writable_root in sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd) {
+        // The runtime would create a missing source directory owned by root.
+        if !writable_root.root.exists() {
+            continue;
+        }
+        args.push("--mount".to_string());
+        args.push(bind_mount(&writable_root.root, false)?);
+        for subpath in &writable_root.read_only_subpaths {
+            args.push("--mount".to_string());
+            args.push(bind_mount(subpath, true)?);
+        }
+    }
+
+    args.push("--workdir".to_string());
+    args.push(command_cwd.to_string_lossy().into_owned());
+
+    if let Some(memory_bytes) = resource_limits.memory_bytes {
+        args.push("--memory".to_string());
+        args.push(memory_bytes.to_string());
+    }
+    if let Some(max_processes) = resource_limits.max_processes {
+        args.push("--pids-limit".to_string());
+        args.push(max_processes.to_string());
+    }
+    if let Some(cpu_time_secs) = resource_limits.cpu_time_secs {
+        args.push("--ulimit".to_string());
+        args.push(format!("cpu={cpu_time_secs}:{cpu_time_secs}"));
+    }
+
+    args.push("--env".to_string());
+    args.push(format!("{CODEX_SANDBOX_ENV_VAR}={runtime}"));
+    if !sandbox_policy.has_full_network_access() {
+        args.push("--env".to_string());
+        args.push(format!("{CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR}=1"));
+    }
+
+    Ok(args)
+}
+
+/// `--mount` options are comma-separated, so a path containing a comma would
+/// be split into other options (e.g. `/work/a,readonly=false`). Such paths are
+/// rejected rather than escaped because Docker and Podman quote differently.
+fn bind_mount(path: &Path, read_only: bool) -> io::Result<String> {
+    let path = path.to_string_lossy();
+    if path.contains(',') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot mount `{path}` in the container sandbox: the path contains a comma"),
+        ));
+    }
+    let mut mount = format!("type=bind,source={path},target={path}");
+    if read_only {
+        mount.push_str(",readonly");
+    }
+    Ok(mount)
+}
+
+/// Runs the command as the host user so files it creates in the workspace are
+/// not owned by root.
+#[cfg(unix)]
+fn host_user_args(runtime: ContainerRuntime) -> Vec<String> {
+    match runtime {
+        ContainerRuntime::Docker => {
+            // SAFETY: `getuid` and `getgid` take no arguments and cannot fail.
+            let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+            vec!["--user".to_string(), format!("{uid}:{gid}")]
+        }
+        // Rootless Podman already maps root in the container to the host
+        // user; `keep-id` additionally keeps the same uid inside.
+        ContainerRuntime::Podman => vec!["--userns".to_string(), "keep-id".to_string()],
+    }
+}
+
+#[cfg(not(unix))]
+fn host_user_args(_runtime: ContainerRuntime) -> Vec<String> {
+    Vec::new()
+}
+
+/// Forwards the shell environment into the container by name only, so values
+/// such as tokens do not show up in the runtime's command line.
+fn forwarded_env_args(env: &HashMap<String, String>) -> Vec<String> {
+    let mut names: Vec<&String> = env
+        .keys()
+        .filter(|name| !HOST_ONLY_ENV_VARS.contains(&name.as_str()))
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .flat_map(|name| ["--env".to_string(), name.clone()])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn run_args_mount_workspace_and_disable_network() {
+        let workspace = TempDir::new().expect("tempdir");
+        let cwd = workspace.path();
+        std::fs::create_dir(cwd.join(".git")).expect("create .git");
+        let policy = SandboxPolicy::Container {
+            image: "rust:1.90".to_string(),
+            runtime: None,
+            writable_roots: vec![PathBuf::from("/does/not/exist")],
+            network_access: false,
+        };
+        let limits = ResourceLimits {
+            memory_bytes: Some(1 << 30),
+            max_processes: Some(256),
+            ..Default::default()
+        };
+
+        let args = create_container_run_args(
+            ContainerRuntime::Docker,
+            "codex-exec-test",
+            cwd,
+            &policy,
+            cwd,
+            &limits,
+        )
+        .expect("run args");
+
+        let cwd = cwd.to_string_lossy();
+        assert_eq!(
+            args,
+            vec![
+                "run".to_string(),
+                "--rm".to_string(),
+                "--init".to_string(),
+                "--name".to_string(),
+                "codex-exec-test".to_string(),
+                "--network".to_string(),
+                "none".to_string(),
+                "--mount".to_string(),
+                format!("type=bind,source={cwd},target={cwd}"),
+                "--mount".to_string(),
+                format!("type=bind,source={cwd}/.git,target={cwd}/.git,readonly"),
+                "--workdir".to_string(),
+                cwd.to_string(),
+                "--memory".to_string(),
+                "1073741824".to_string(),
+                "--pids-limit".to_string(),
+                "256".to_string(),
+                "--env".to_string(),
+                "CODEX_SANDBOX=docker".to_string(),
+                "--env".to_string(),
+                "CODEX_SANDBOX_NETWORK_DISABLED=1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_with_commas_are_not_mounted() {
+        let workspace = TempDir::new().expect("tempdir");
+        let cwd = workspace.path().join("a,readonly=false");
+        std::fs::create_dir(&cwd).expect("create workspace");
+        let policy = SandboxPolicy::Container {
+            image: "rust:1.90".to_string(),
+            runtime: None,
+            writable_roots: Vec::new(),
+            network_access: false,
+        };
+
+        let err = create_container_run_args(
+            ContainerRuntime::Docker,
+            "codex-exec-test",
+            &cwd,
+            &policy,
+            &cwd,
+            &ResourceLimits::default(),
+        )
+        .expect_err("comma in the workspace path");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn env_is_forwarded_by_name_without_host_paths() {
+        let env = HashMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("RUST_LOG".to_string(), "debug".to_string()),
+            ("API_TOKEN".to_string(), "secret".to_string()),
+        ]);
+        assert_eq!(
+            forwarded_env_args(&env),
+            vec![
+                "--env".to_string(),
+                "API_TOKEN".to_string(),
+                "--env".to_string(),
+                "RUST_LOG".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Relevance-scored pruning of conversation history.
+//!
+//! When a turn pushes the conversation over the auto-compaction token limit we
+//! first try to make room by replacing tool outputs that are unlikely to matter
+//! for the current task with a short placeholder. Only if that cannot free
+//! enough tokens do we fall back to summarizing the whole conversation.
+//!
+//! Every decision is reported as a [`ContextPrunedEvent`], which is persisted in
+//! the rollout so it can be audited when the model appears to have "forgotten"
+//! something, and replayed when the session is resumed.
+
+use std::collections::HashSet;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+
+use crate::codex::compact::is_session_prefix_message;
+use crate::config_types::ContextPruningStrategy;
+use crate::protocol::ContextPrunedEvent;
+use crate::protocol::PrunedContextItem;
+
+pub(crate) const PRUNED_OUTPUT_PLACEHOLDER: &str =
+    "[output pruned from context to stay under the token limit]";
+
+/// Pruning aims to bring usage down to this percentage of the limit so that
+/// the very next turn does not immediately trip it again.
+const PRUNE_TARGET_PERCENT: u64 = 80;
+
+/// The most recent items are never pruned: the model is usually still acting
+/// on them.
+const PROTECTED_TAIL_ITEMS: usize = 4;
+
+/// Outputs shorter than this are not worth replacing with a placeholder.
+const MIN_PRUNABLE_BYTES: usize = 256;
+
+const PREVIEW_MAX_CHARS: usize = 80;
+
+/// Task terms shorter than this are mostly stop words.
+const MIN_TERM_CHARS: usize = 4;
+
+/// A tool output that may be pruned, along with the context a scorer needs to
+/// rank it.
+pub(crate) struct PruneCandidate<'a> {
+    pub(crate) output: &'a str,
+    /// Position of the output in the history; 0 is the oldest item.
+    pub(crate) position: usize,
+    pub(crate) history_len: usize,
+}
+
+impl PruneCandidate<'_> {
+    /// 0.0 for the oldest item in the history, approaching 1.0 for the newest.
+    fn recency(&self) -> f64 {
+        self.position as f64 / self.history_len.max(1) as f64
+    }
+}
+
+/// Ranks prune candidates. Lower scores are pruned first.
+pub(crate) trait RelevanceScorer: Send + Sync {
+    /// Name recorded in [`ContextPrunedEvent::strategy`].
+    fn name(&self) -> &'static str;
+
+    /// `task_terms` are the normalized words of the most recent user message.
+    fn score(&self, candidate: &PruneCandidate<'_>, task_terms: &HashSet<String>) -> f64;
+}
+
+/// Chronological pruning: the oldest outputs go first.
+pub(crate) struct RecencyScorer;
+
+impl RelevanceScorer for RecencyScorer {
+    fn name(&self) -> &'static str {
+        "recency"
+    }
+
+    fn score(&self, candidate: &PruneCandidate<'_>, _task_terms: &HashSet<String>) -> f64 {
+        candidate.recency()
+    }
+}
+
+/// Blends recency with how many of the current task's terms appear in the
+/// output, so that stale outputs unrelated to the task are pruned first.
+pub(crate) struct KeywordRelevanceScorer;
+
+impl RelevanceScorer for KeywordRelevanceScorer {
+    fn name(&self) -> &'static str {
+        "relevance"
+    }
+
+    fn score(&self, candidate: &PruneCandidate<'_>, task_terms: &HashSet<String>) -> f64 {
+        let overlap = if task_terms.is_empty() {
+            0.0
+        } else {
+            let output_terms = terms(candidate.output);
+            let matched = task_terms
+                .iter()
+                .filter(|term| output_terms.contains(*term))
+                .count();
+            matched as f64 / task_terms.len() as f64
+        };
+        0.5 * candidate.recency() + 0.5 * overlap
+    }
+}
+
+pub(crate) fn scorer_for(strategy: ContextPruningStrategy) -> Option<Box<dyn RelevanceScorer>> {
+    match strategy {
+        ContextPruningStrategy::Relevance => Some(Box::new(KeywordRelevanceScorer)),
+        ContextPruningStrategy::Recency => Some(Box::new(RecencyScorer)),
+        ContextPruningStrategy::Disabled => None,
+    }
+}
+
+/// Number of tokens that must be freed to bring `current_tokens` down to the
+/// pruning target for `limit`.
+pub(crate) fn tokens_to_free(current_tokens: u64, limit: u64) -> u64 {
+    current_tokens.saturating_sub(limit / 100 * PRUNE_TARGET_PERCENT)
+}
+
+/// Choose the lowest-scoring tool outputs whose combined size covers
+/// `tokens_to_free`. Returns `None` if pruning cannot free enough, in which
+/// case the caller should fall back to compaction.
+pub(crate) fn plan_pruning(
+    items: &[ResponseItem],
+    scorer: &dyn RelevanceScorer,
+    tokens_to_free: u64,
+) -> Option<ContextPrunedEvent> {
+    if tokens_to_free == 0 {
+        return None;
+    }
+
+    let task_terms = current_task_terms(items);
+    let prunable_len = items.len().saturating_sub(PROTECTED_TAIL_ITEMS);
+    let mut candidates: Vec<PrunedContextItem> = items[..prunable_len]
+        .iter()
+        .enumerate()
+        .filter_map(|(position, item)| {
+            let (call_id, output) = tool_output(item)?;
+            if output.len() < MIN_PRUNABLE_BYTES {
+                return None;
+            }
+            let candidate = PruneCandidate {
+                output,
+                position,
+                history_len: items.len(),
+            };
+            Some(PrunedContextItem {
+                call_id: call_id.to_string(),
+                score: scorer.score(&candidate, &task_terms),
+                approx_tokens: (output.len() as u64).div_ceil(4),
+                preview: preview(output),
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+
+    let mut approx_tokens_freed = 0;
+    let mut pruned = Vec::new();
+    for candidate in candidates {
+        if approx_tokens_freed >= tokens_to_free {
+            break;
+        }
+        approx_tokens_freed += candidate.approx_tokens;
+        pruned.push(candidate);
+    }
+    if approx_tokens_freed < tokens_to_free {
+        return None;
+    }
+
+    Some(ContextPrunedEvent {
+        strategy: scorer.name().to_string(),
+        approx_tokens_freed,
+        items: pruned,
+    })
+}
+
+/// Replace the outputs listed in `pruned` with [`PRUNED_OUTPUT_PLACEHOLDER`].
+pub(crate) fn apply_pruning(items: &mut [ResponseItem], pruned: &[PrunedContextItem]) {
+    let call_ids: HashSet<&str> = pruned.iter().map(|item| item.call_id.as_str()).collect();
+    for item in items.iter_mut() {
+        match item {
+            ResponseItem::FunctionCallOutput { call_id, output }
+                if call_ids.contains(call_id.as_str()) =>
+            {
+                output.content = PRUNED_OUTPUT_PLACEHOLDER.to_string();
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output }
+                if call_ids.contains(call_id.as_str()) =>
+            {
+                *output = PRUNED_OUTPUT_PLACEHOLDER.to_string();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn tool_output(item: &ResponseItem) -> Option<(&str, &str)> {
+    match item {
+        ResponseItem::FunctionCallOutput { call_id, output } => {
+            Some((call_id.as_str(), output.content.as_str()))
+        }
+        ResponseItem::CustomToolCallOutput { call_id, output } => {
+            Some((call_id.as_str(), output.as_str()))
+        }
+        _ => None,
+    }
+}
+
+/// Terms of the most recent user message, ignoring the user instructions and
+/// environment context that prefix every session.
+fn current_task_terms(items: &[ResponseItem]) -> HashSet<String> {
+    items
+        .iter()
+        .rev()
+        .find_map(|item| match item {
+            ResponseItem::Message { role, content, .. } if role == "user" => {
+                let text = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        ContentItem::InputText { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                (!is_session_prefix_message(&text)).then(|| terms(&text))
+            }
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().count() >= MIN_TERM_CHARS)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn preview(output: &str) -> String {
+    let first_line = output.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    let mut preview: String = first_line.trim().chars().take(PREVIEW_MAX_CHARS).collect();
+    if first_line.trim().chars().count() > PREVIEW_MAX_CHARS {
+        preview.push('…');
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn user_msg(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn call_output(call_id: &str, content: &str) -> ResponseItem {
+        ResponseItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: content.to_string(),
+                success: Some(true),
+            },
+        }
+    }
+
+    fn history() -> Vec<ResponseItem> {
+        vec![
+            user_msg("fix the parser bug in tokenizer"),
+            call_output("old-related", &"tokenizer parser state\n".repeat(40)),
+            call_output("old-unrelated", &"npm install progress\n".repeat(40)),
+            call_output("recent-unrelated", &"download complete\n".repeat(40)),
+            // Protected tail.
+            user_msg("now fix the tokenizer parser"),
+            call_output("tail-1", &"x".repeat(1000)),
+            call_output("tail-2", &"y".repeat(1000)),
+            call_output("tail-3", &"z".repeat(1000)),
+        ]
+    }
+
+    fn pruned_ids(event: &ContextPrunedEvent) -> Vec<&str> {
+        event
+            .items
+            .iter()
+            .map(|item| item.call_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn relevance_keeps_outputs_related_to_current_task() {
+        let event = plan_pruning(&history(), &KeywordRelevanceScorer, 300).expect("pruning");
+        assert_eq!(event.strategy, "relevance");
+        assert_eq!(
+            pruned_ids(&event),
+            vec!["old-unrelated", "recent-unrelated"]
+        );
+    }
+
+    #[test]
+    fn recency_prunes_oldest_first() {
+        let event = plan_pruning(&history(), &RecencyScorer, 300).expect("pruning");
+        assert_eq!(pruned_ids(&event), vec!["old-related", "old-unrelated"]);
+    }
+
+    #[test]
+    fn returns_none_when_not_enough_can_be_freed() {
+        assert_eq!(plan_pruning(&history(), &RecencyScorer, 10_000), None);
+    }
+
+    #[test]
+    fn apply_pruning_replaces_only_selected_outputs() {
+        let mut items = history();
+        let event = plan_pruning(&items, &RecencyScorer, 100).expect("pruning");
+        apply_pruning(&mut items, &event.items);
+
+        assert_eq!(
+            items[1],
+            call_output("old-related", PRUNED_OUTPUT_PLACEHOLDER)
+        );
+        assert_eq!(items[2], history()[2]);
+    }
+}
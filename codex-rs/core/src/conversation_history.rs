@@ -37,6 +37,64 @@ impl ConversationHistory {
     }
 }
 
+/// Prepares `items` for a model provider other than the one that produced
+/// them. Item ids and reasoning (often encrypted) only mean something to the
+/// provider that issued them, and web searches ran on its servers, so those
+/// are dropped; messages, tool calls and their outputs are kept, and each
+/// client encodes them in its own wire format.
+pub(crate) fn reencode_for_provider(items: Vec<ResponseItem>) -> Vec<ResponseItem> {
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => None,
+            ResponseItem::Message { role, content, .. } => Some(ResponseItem::Message {
+                id: None,
+                role,
+                content,
+            }),
+            ResponseItem::LocalShellCall {
+                call_id,
+                status,
+                action,
+                ..
+            } => Some(ResponseItem::LocalShellCall {
+                id: None,
+                call_id,
+                status,
+                action,
+            }),
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments,
+                call_id,
+            }),
+            ResponseItem::CustomToolCall {
+                status,
+                call_id,
+                name,
+                input,
+                ..
+            } => Some(ResponseItem::CustomToolCall {
+                id: None,
+                status,
+                call_id,
+                name,
+                input,
+            }),
+            item @ (ResponseItem::FunctionCallOutput { .. }
+            | ResponseItem::CustomToolCallOutput { .. }) => Some(item),
+        })
+        .collect()
+}
+
 /// Anything that is not a system message or "reasoning" message is considered
 /// an API message.
 fn is_api_message(message: &ResponseItem) -> bool {
@@ -78,6 +136,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn reencoding_drops_provider_specific_items_and_ids() {
+        let items = vec![
+            user_msg("hi"),
+            ResponseItem::Reasoning {
+                id: "rs_1".to_string(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some("opaque".to_string()),
+            },
+            ResponseItem::FunctionCall {
+                id: Some("fc_1".to_string()),
+                name: "shell".to_string(),
+                arguments: "{}".to_string(),
+                call_id: "call_1".to_string(),
+            },
+            ResponseItem::Message {
+                id: Some("msg_1".to_string()),
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "hello".to_string(),
+                }],
+            },
+        ];
+
+        assert_eq!(
+            reencode_for_provider(items),
+            vec![
+                user_msg("hi"),
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: "{}".to_string(),
+                    call_id: "call_1".to_string(),
+                },
+                assistant_msg("hello"),
+            ]
+        );
+    }
+
     #[test]
     fn filters_non_api_messages() {
         let mut h = ConversationHistory::default();
@@ -159,6 +159,31 @@ impl ConversationManager {
 
         self.finalize_spawn(codex, conversation_id).await
     }
+
+    /// Start a new conversation with the complete history of the rollout at
+    /// `path`, e.g. to continue a session that another process has open.
+    pub async fn fork_conversation_from_rollout(
+        &self,
+        config: Config,
+        path: PathBuf,
+    ) -> CodexResult<NewConversation> {
+        let items = RolloutRecorder::get_rollout_history(&path)
+            .await?
+            .get_rollout_items();
+        let history = if items.is_empty() {
+            InitialHistory::New
+        } else {
+            InitialHistory::Forked(items)
+        };
+
+        let auth_manager = self.auth_manager.clone();
+        let CodexSpawnOk {
+            codex,
+            conversation_id,
+        } = Codex::spawn(config, auth_manager, history).await?;
+
+        self.finalize_spawn(codex, conversation_id).await
+    }
 }
 
 /// Return a prefix of `items` obtained by cutting strictly before the nth user message
@@ -0,0 +1,136 @@
+//! Estimates of what model usage costs.
+//!
+//! Providers do not report prices, so costs come from the `[[pricing]]`
+//! table in the config. Models without an entry have no estimate rather
+//! than a guessed one.
+
+use std::collections::BTreeMap;
+
+use codex_protocol::protocol::TokenUsage;
+
+use crate::config_types::ModelPricing;
+
+/// Prices of a model in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenPrices {
+    pub input: f64,
+    /// Defaults to the input price when the provider does not discount cached
+    /// input.
+    pub cached_input: f64,
+    pub output: f64,
+}
+
+impl TokenPrices {
+    /// Cost of `usage` in US dollars. Reasoning tokens are billed as output
+    /// and are already part of `output_tokens`.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let uncached = usage.input_tokens.saturating_sub(usage.cached_input_tokens);
+        (uncached as f64 * self.input
+            + usage.cached_input_tokens as f64 * self.cached_input
+            + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+impl From<&ModelPricing> for TokenPrices {
+    fn from(pricing: &ModelPricing) -> Self {
+        Self {
+            input: pricing.input,
+            cached_input: pricing.cached_input.unwrap_or(pricing.input),
+            output: pricing.output,
+        }
+    }
+}
+
+/// Prices of `model` on the provider keyed `provider_id`. An entry for that
+/// provider wins over one that applies to any provider.
+pub fn prices_for(pricing: &[ModelPricing], provider_id: &str, model: &str) -> Option<TokenPrices> {
+    let for_model = || pricing.iter().filter(|entry| entry.model == model);
+    for_model()
+        .find(|entry| entry.provider.as_deref() == Some(provider_id))
+        .or_else(|| for_model().find(|entry| entry.provider.is_none()))
+        .map(TokenPrices::from)
+}
+
+/// Estimated cost of usage recorded per model. Models without prices add
+/// nothing; `None` when none of them has prices.
+pub fn cost_by_model(
+    tokens_by_model: &BTreeMap<String, TokenUsage>,
+    pricing: &[ModelPricing],
+    provider_id: &str,
+) -> Option<f64> {
+    tokens_by_model
+        .iter()
+        .filter_map(|(model, usage)| {
+            prices_for(pricing, provider_id, model).map(|prices| prices.cost(usage))
+        })
+        .reduce(|total, cost| total + cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn pricing(model: &str, provider: Option<&str>, input: f64) -> ModelPricing {
+        ModelPricing {
+            model: model.to_string(),
+            provider: provider.map(str::to_string),
+            input,
+            cached_input: None,
+            output: 10.0,
+        }
+    }
+
+    #[test]
+    fn provider_specific_prices_win() {
+        let table = vec![
+            pricing("gpt-5", None, 1.25),
+            pricing("gpt-5", Some("azure"), 1.5),
+        ];
+
+        assert_eq!(
+            prices_for(&table, "azure", "gpt-5").map(|prices| prices.input),
+            Some(1.5)
+        );
+        assert_eq!(
+            prices_for(&table, "openai", "gpt-5").map(|prices| prices.input),
+            Some(1.25)
+        );
+        assert_eq!(prices_for(&table, "openai", "o3"), None);
+    }
+
+    #[test]
+    fn cached_input_defaults_to_the_input_price() {
+        let prices = TokenPrices::from(&pricing("gpt-5", None, 2.0));
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 500_000,
+            output_tokens: 100_000,
+            reasoning_output_tokens: 50_000,
+            total_tokens: 1_100_000,
+        };
+
+        assert_eq!(prices.cached_input, 2.0);
+        assert_eq!(prices.cost(&usage), 3.0);
+    }
+
+    #[test]
+    fn unpriced_models_add_nothing() {
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            total_tokens: 1_000_000,
+            ..Default::default()
+        };
+        let by_model = BTreeMap::from([
+            ("gpt-5".to_string(), usage.clone()),
+            ("local-llama".to_string(), usage),
+        ]);
+
+        assert_eq!(
+            cost_by_model(&by_model, &[pricing("gpt-5", None, 1.25)], "openai"),
+            Some(1.25)
+        );
+        assert_eq!(cost_by_model(&by_model, &[], "openai"), None);
+    }
+}
@@ -0,0 +1,421 @@
+//! Where the contents of `auth.json` are persisted.
+//!
+//! By default credentials live in `CODEX_HOME/auth.json`. Setting
+//! `auth.credential_store = "keyring"` keeps them in the operating system's
+//! credential store instead: the macOS Keychain, the Windows Credential
+//! Manager, or the Secret Service (with the kernel keyring as a cache) on
+//! Linux.
+
+use std::fmt::Debug;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::auth::AuthDotJson;
+use crate::auth::get_auth_file;
+use crate::auth::try_read_auth_json;
+use crate::auth::write_auth_json;
+use crate::config_types::CredentialStoreMode;
+
+/// Service name under which credentials are saved in the OS keyring.
+const KEYRING_SERVICE: &str = "Codex Auth";
+
+/// Largest piece of `auth.json` saved in one keyring entry. Windows stores
+/// secrets as UTF-16 and refuses more than 2560 bytes; 1200 bytes of UTF-8 are
+/// at most 1200 UTF-16 code units.
+const KEYRING_CHUNK_BYTES: usize = 1200;
+
+/// Start of the entry that records how many chunks the document has.
+const CHUNKS_HEADER_PREFIX: &str = "chunks:";
+
+/// Backend that loads and saves the credentials for one `CODEX_HOME`.
+pub trait CredentialStore: Debug + Send + Sync {
+    /// Returns `Ok(None)` if no credentials are stored.
+    fn load(&self) -> std::io::Result<Option<AuthDotJson>>;
+
+    fn save(&self, auth_dot_json: &AuthDotJson) -> std::io::Result<()>;
+
+    /// Returns `Ok(true)` if stored credentials were removed.
+    fn delete(&self) -> std::io::Result<bool>;
+}
+
+/// Create the store selected by `mode` for `codex_home`.
+pub fn credential_store(codex_home: &Path, mode: CredentialStoreMode) -> Arc<dyn CredentialStore> {
+    match mode {
+        CredentialStoreMode::File => Arc::new(FileCredentialStore::new(get_auth_file(codex_home))),
+        CredentialStoreMode::Keyring => Arc::new(KeyringCredentialStore::new(codex_home)),
+    }
+}
+
+/// Plaintext `auth.json`, readable only by the current user on Unix.
+#[derive(Debug)]
+pub struct FileCredentialStore {
+    auth_file: PathBuf,
+}
+
+impl FileCredentialStore {
+    pub fn new(auth_file: PathBuf) -> Self {
+        Self { auth_file }
+    }
+}
+
+impl CredentialStore for FileCredentialStore {
+    fn load(&self) -> std::io::Result<Option<AuthDotJson>> {
+        match try_read_auth_json(&self.auth_file) {
+            Ok(auth_dot_json) => Ok(Some(auth_dot_json)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn save(&self, auth_dot_json: &AuthDotJson) -> std::io::Result<()> {
+        write_auth_json(&self.auth_file, auth_dot_json)
+    }
+
+    fn delete(&self) -> std::io::Result<bool> {
+        remove_file_if_exists(&self.auth_file)
+    }
+}
+
+/// The OS credential store, keyed by the `CODEX_HOME` the credentials belong
+/// to so that separate homes keep separate credentials.
+///
+/// Windows limits a secret to 2560 bytes, less than `auth.json` takes once it
+/// holds ChatGPT tokens, so the document is split into chunks of at most
+/// [`KEYRING_CHUNK_BYTES`]. The entry for the home holds `chunks:<n>` and the
+/// chunks are saved under `<home>#1` to `<home>#<n>`. An entry that holds the
+/// whole document, as saved by earlier versions, is still read.
+///
+/// A plaintext `auth.json` left over from before the keyring was enabled is
+/// moved into the keyring the first time it is loaded, and removed whenever
+/// credentials are saved or deleted.
+#[derive(Debug)]
+pub struct KeyringCredentialStore {
+    account: String,
+    auth_file: PathBuf,
+    keyring: Arc<dyn Keyring>,
+}
+
+impl KeyringCredentialStore {
+    pub fn new(codex_home: &Path) -> Self {
+        Self::with_keyring(codex_home, Arc::new(OsKeyring))
+    }
+
+    fn with_keyring(codex_home: &Path, keyring: Arc<dyn Keyring>) -> Self {
+        let codex_home = codex_home
+            .canonicalize()
+            .unwrap_or_else(|_| codex_home.to_path_buf());
+        Self {
+            account: codex_home.to_string_lossy().into_owned(),
+            auth_file: get_auth_file(&codex_home),
+            keyring,
+        }
+    }
+
+    fn chunk_account(&self, index: usize) -> String {
+        format!("{}#{index}", self.account)
+    }
+
+    /// Number of chunks the saved document is split into, or `None` when the
+    /// entry holds the whole document or nothing.
+    fn chunk_count(header: Option<&str>) -> Option<usize> {
+        header?.strip_prefix(CHUNKS_HEADER_PREFIX)?.parse().ok()
+    }
+}
+
+impl CredentialStore for KeyringCredentialStore {
+    fn load(&self) -> std::io::Result<Option<AuthDotJson>> {
+        let Some(header) = self.keyring.get(&self.account)? else {
+            let legacy = FileCredentialStore::new(self.auth_file.clone()).load()?;
+            if let Some(auth_dot_json) = &legacy {
+                self.save(auth_dot_json)?;
+            }
+            return Ok(legacy);
+        };
+        let json = match Self::chunk_count(Some(&header)) {
+            Some(count) => {
+                let mut json = String::new();
+                for index in 1..=count {
+                    let chunk = self
+                        .keyring
+                        .get(&self.chunk_account(index))?
+                        .ok_or_else(|| {
+                            std::io::Error::other(format!(
+                                "OS credential store: chunk {index} of {count} is missing"
+                            ))
+                        })?;
+                    json.push_str(&chunk);
+                }
+                json
+            }
+            None => header,
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    fn save(&self, auth_dot_json: &AuthDotJson) -> std::io::Result<()> {
+        let json = serde_json::to_string(auth_dot_json)?;
+        let previous_count = Self::chunk_count(self.keyring.get(&self.account)?.as_deref());
+        let chunks = split_into_chunks(&json, KEYRING_CHUNK_BYTES);
+        for (index, chunk) in chunks.iter().enumerate() {
+            self.keyring.set(&self.chunk_account(index + 1), chunk)?;
+        }
+        // The header is written last so that a failed save leaves the
+        // previous credentials readable.
+        self.keyring.set(
+            &self.account,
+            &format!("{CHUNKS_HEADER_PREFIX}{}", chunks.len()),
+        )?;
+        for index in chunks.len() + 1..=previous_count.unwrap_or(0) {
+            self.keyring.delete(&self.chunk_account(index))?;
+        }
+        remove_file_if_exists(&self.auth_file)?;
+        Ok(())
+    }
+
+    fn delete(&self) -> std::io::Result<bool> {
+        let count = Self::chunk_count(self.keyring.get(&self.account)?.as_deref());
+        for index in 1..=count.unwrap_or(0) {
+            self.keyring.delete(&self.chunk_account(index))?;
+        }
+        let removed_entry = self.keyring.delete(&self.account)?;
+        let removed_file = remove_file_if_exists(&self.auth_file)?;
+        Ok(removed_entry || removed_file)
+    }
+}
+
+/// Secrets saved in the OS keyring under [`KEYRING_SERVICE`], by account.
+trait Keyring: Debug + Send + Sync {
+    fn get(&self, account: &str) -> std::io::Result<Option<String>>;
+
+    fn set(&self, account: &str, secret: &str) -> std::io::Result<()>;
+
+    /// Returns `Ok(true)` if a secret was removed.
+    fn delete(&self, account: &str) -> std::io::Result<bool>;
+}
+
+#[derive(Debug)]
+struct OsKeyring;
+
+impl OsKeyring {
+    fn entry(account: &str) -> std::io::Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, account).map_err(keyring_error)
+    }
+}
+
+impl Keyring for OsKeyring {
+    fn get(&self, account: &str) -> std::io::Result<Option<String>> {
+        match Self::entry(account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+
+    fn set(&self, account: &str, secret: &str) -> std::io::Result<()> {
+        Self::entry(account)?
+            .set_password(secret)
+            .map_err(keyring_error)
+    }
+
+    fn delete(&self, account: &str) -> std::io::Result<bool> {
+        match Self::entry(account)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+}
+
+/// Splits `text` into pieces of at most `max_bytes`, on character boundaries.
+fn split_into_chunks(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max_bytes);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn keyring_error(err: keyring::Error) -> std::io::Error {
+    std::io::Error::other(format!("OS credential store: {err}"))
+}
+
+fn remove_file_if_exists(path: &Path) -> std::io::Result<bool> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_data::TokenData;
+    use crate::token_data::parse_id_token;
+    use base64::Engine;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    fn api_key_auth(api_key: &str) -> AuthDotJson {
+        AuthDotJson {
+            openai_api_key: Some(api_key.to_string()),
+            tokens: None,
+            last_refresh: None,
+            active_account: None,
+            accounts: Vec::new(),
+            provider_api_keys: BTreeMap::new(),
+            mcp_oauth: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn file_store_roundtrip() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        let store = credential_store(dir.path(), CredentialStoreMode::File);
+        assert_eq!(store.load()?, None);
+
+        store.save(&api_key_auth("sk-test"))?;
+        assert_eq!(store.load()?, Some(api_key_auth("sk-test")));
+
+        assert!(store.delete()?);
+        assert!(!store.delete()?);
+        assert_eq!(store.load()?, None);
+        Ok(())
+    }
+
+    /// In-memory keyring whose entries persist across calls, with the
+    /// Windows limit on the size of a secret.
+    #[derive(Debug, Default)]
+    struct MemoryKeyring {
+        entries: Mutex<BTreeMap<String, String>>,
+    }
+
+    impl Keyring for MemoryKeyring {
+        fn get(&self, account: &str) -> std::io::Result<Option<String>> {
+            Ok(self.entries.lock().expect("lock").get(account).cloned())
+        }
+
+        fn set(&self, account: &str, secret: &str) -> std::io::Result<()> {
+            if secret.encode_utf16().count() * 2 > WINDOWS_MAX_SECRET_BYTES {
+                return Err(std::io::Error::other("secret too long"));
+            }
+            self.entries
+                .lock()
+                .expect("lock")
+                .insert(account.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, account: &str) -> std::io::Result<bool> {
+            Ok(self.entries.lock().expect("lock").remove(account).is_some())
+        }
+    }
+
+    const WINDOWS_MAX_SECRET_BYTES: usize = 2560;
+
+    /// A fake JWT of about `len` bytes.
+    fn jwt(claims: serde_json::Value, len: usize) -> String {
+        let encode = |value: &serde_json::Value| {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value.to_string())
+        };
+        let header = encode(&serde_json::json!({"alg": "RS256", "typ": "JWT"}));
+        let payload = encode(&claims);
+        let signature = "s".repeat(len.saturating_sub(header.len() + payload.len() + 2));
+        format!("{header}.{payload}.{signature}")
+    }
+
+    /// ChatGPT credentials with tokens of the sizes the auth server issues.
+    fn chatgpt_auth() -> AuthDotJson {
+        let id_token = jwt(
+            serde_json::json!({
+                "email": "user@example.com",
+                "https://api.openai.com/auth": {"chatgpt_plan_type": "pro"},
+            }),
+            1800,
+        );
+        AuthDotJson {
+            openai_api_key: None,
+            tokens: Some(TokenData {
+                id_token: parse_id_token(&id_token).expect("parse id token"),
+                access_token: jwt(serde_json::json!({"scp": ["openid", "email"]}), 2400),
+                refresh_token: format!("rt_{}", "r".repeat(1200)),
+                account_id: Some("account-123".to_string()),
+            }),
+            last_refresh: None,
+            active_account: None,
+            accounts: Vec::new(),
+            provider_api_keys: BTreeMap::new(),
+            mcp_oauth: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn keyring_store_chunks_large_credentials() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        let keyring = Arc::new(MemoryKeyring::default());
+        let store = KeyringCredentialStore::with_keyring(dir.path(), keyring.clone());
+        let auth = chatgpt_auth();
+        assert!(serde_json::to_string(&auth)?.len() > WINDOWS_MAX_SECRET_BYTES);
+
+        store.save(&auth)?;
+        assert_eq!(store.load()?, Some(auth));
+        assert!(keyring.entries.lock().expect("lock").len() > 2);
+
+        // Saving something smaller removes the chunks it no longer needs.
+        store.save(&api_key_auth("sk-test"))?;
+        assert_eq!(store.load()?, Some(api_key_auth("sk-test")));
+        assert_eq!(keyring.entries.lock().expect("lock").len(), 2);
+
+        assert!(store.delete()?);
+        assert!(keyring.entries.lock().expect("lock").is_empty());
+        assert_eq!(store.load()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn keyring_store_reads_unchunked_entries() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        let keyring = Arc::new(MemoryKeyring::default());
+        let store = KeyringCredentialStore::with_keyring(dir.path(), keyring.clone());
+        let json = serde_json::to_string(&api_key_auth("sk-old"))?;
+        keyring.set(&store.account, &json)?;
+
+        assert_eq!(store.load()?, Some(api_key_auth("sk-old")));
+        Ok(())
+    }
+
+    #[test]
+    fn keyring_store_migrates_plaintext_auth_json() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        let auth_file = get_auth_file(dir.path());
+        write_auth_json(&auth_file, &chatgpt_auth())?;
+        let keyring = Arc::new(MemoryKeyring::default());
+
+        let store = KeyringCredentialStore::with_keyring(dir.path(), keyring.clone());
+        assert_eq!(store.load()?, Some(chatgpt_auth()));
+        assert!(!auth_file.exists());
+
+        // A new store over the same keyring reads the migrated credentials
+        // back, now that the file is gone.
+        let store = KeyringCredentialStore::with_keyring(dir.path(), keyring);
+        assert_eq!(store.load()?, Some(chatgpt_auth()));
+        Ok(())
+    }
+
+    #[test]
+    fn chunks_split_on_character_boundaries() {
+        assert_eq!(split_into_chunks("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(split_into_chunks("aéé", 3), vec!["aé", "é"]);
+        assert_eq!(split_into_chunks("", 4), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,550 @@
+//! Tools defined in the config under `[tools.custom.<name>]`.
+//!
+//! Each one maps a function tool to a command, so project scripts such as a
+//! deploy or a database migration can be offered to the model without writing
+//! an MCP server. The command receives the call's arguments, once they match
+//! the tool's `parameters` schema, as `CODEX_TOOL_ARG_<NAME>` environment
+//! variables and as JSON in `CODEX_TOOL_ARGS`; they are never part of the
+//! command line.
+//!
+//! By default a command runs like a `shell` call: in the sandbox, subject to
+//! the sandbox rules and the approval policy, with the arguments in the
+//! environment only. A tool with `sandbox = false` runs outside the sandbox,
+//! where it also gets the arguments on stdin, after the user approves it
+//! unless it also sets `approval = false`.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde_json::Value;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::error;
+
+use crate::background_jobs::JOB_KILL_TOOL_NAME;
+use crate::background_jobs::JOB_RESULT_TOOL_NAME;
+use crate::config_types::CustomToolConfig;
+use crate::exec_command::EXEC_COMMAND_TOOL_NAME;
+use crate::exec_command::WRITE_STDIN_TOOL_NAME;
+use crate::fetch_url::FETCH_URL_TOOL_NAME;
+use crate::file_tools::LIST_DIR_TOOL_NAME;
+use crate::file_tools::READ_FILE_TOOL_NAME;
+use crate::file_tools::WRITE_FILE_TOOL_NAME;
+use crate::memory::REMEMBER_TOOL_NAME;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::plugin_tool_to_openai_tool;
+use crate::output_overflow::READ_OUTPUT_TOOL_NAME;
+use crate::search_code::SEARCH_CODE_TOOL_NAME;
+use crate::task_failure::REPORT_FAILURE_TOOL_NAME;
+use crate::tool_git::GIT_TOOL_NAME;
+use crate::web_search::WEB_SEARCH_TOOL_NAME;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Exit code reported when the command was killed after its timeout.
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// At most this much of each of stdout and stderr is kept.
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Names of the built-in tools, which custom tools cannot replace.
+const BUILTIN_TOOL_NAMES: &[&str] = &[
+    "shell",
+    "apply_patch",
+    "update_plan",
+    "unified_exec",
+    "view_image",
+    EXEC_COMMAND_TOOL_NAME,
+    WRITE_STDIN_TOOL_NAME,
+    READ_FILE_TOOL_NAME,
+    LIST_DIR_TOOL_NAME,
+    WRITE_FILE_TOOL_NAME,
+    GIT_TOOL_NAME,
+    WEB_SEARCH_TOOL_NAME,
+    FETCH_URL_TOOL_NAME,
+    SEARCH_CODE_TOOL_NAME,
+    REMEMBER_TOOL_NAME,
+    REPORT_FAILURE_TOOL_NAME,
+    READ_OUTPUT_TOOL_NAME,
+    JOB_RESULT_TOOL_NAME,
+    JOB_KILL_TOOL_NAME,
+];
+
+/// Checks the names and commands of custom tools when the config loads.
+pub fn validate_custom_tools(tools: &BTreeMap<String, CustomToolConfig>) -> Result<(), String> {
+    for (name, tool) in tools {
+        if !is_valid_name(name) {
+            return Err(format!(
+                "custom tool name {name:?} must be 1-64 letters, digits, `_` or `-`"
+            ));
+        }
+        if BUILTIN_TOOL_NAMES.contains(&name.as_str()) {
+            return Err(format!(
+                "custom tool {name:?} has the name of a built-in tool"
+            ));
+        }
+        if tool.command.is_empty() {
+            return Err(format!("custom tool {name:?} has an empty command"));
+        }
+        if let Some(parameters) = &tool.parameters
+            && !parameters.is_object()
+        {
+            return Err(format!(
+                "the parameters of custom tool {name:?} must be a JSON schema object"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// What a custom tool command did.
+#[derive(Debug)]
+pub(crate) struct CustomToolOutput {
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+    pub(crate) exit_code: i32,
+    pub(crate) duration: Duration,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct CustomTools {
+    tools: BTreeMap<String, CustomToolConfig>,
+}
+
+impl CustomTools {
+    pub(crate) fn new(tools: BTreeMap<String, CustomToolConfig>) -> Self {
+        Self { tools }
+    }
+
+    /// Function tool definitions for the model, in name order.
+    pub(crate) fn openai_tools(&self) -> Vec<OpenAiTool> {
+        self.tools
+            .iter()
+            .filter_map(|(name, tool)| {
+                let parameters = tool
+                    .parameters
+                    .clone()
+                    .unwrap_or_else(|| serde_json::json!({"type": "object"}));
+                match plugin_tool_to_openai_tool(name.clone(), tool.description.clone(), parameters)
+                {
+                    Ok(tool) => Some(OpenAiTool::Function(tool)),
+                    Err(e) => {
+                        error!("Failed to convert {name:?} custom tool: {e:?}");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn has_tool(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    /// Checks `arguments` against the `parameters` schema of tool `name`,
+    /// since the model is not bound to follow it.
+    pub(crate) fn check_arguments(&self, name: &str, arguments: &Value) -> Result<(), String> {
+        let Some(tool) = self.tools.get(name) else {
+            return Err(format!("unsupported call: {name}"));
+        };
+        match &tool.parameters {
+            Some(parameters) => check_schema(parameters, arguments, "arguments"),
+            None => check_schema(
+                &serde_json::json!({"type": "object"}),
+                arguments,
+                "arguments",
+            ),
+        }
+    }
+
+    /// The command of tool `name` and its working directory.
+    pub(crate) fn command(&self, name: &str, cwd: &Path) -> Option<(Vec<String>, PathBuf)> {
+        let tool = self.tools.get(name)?;
+        let command = tool.command.clone();
+        let cwd = match &tool.cwd {
+            Some(dir) => cwd.join(dir),
+            None => cwd.to_path_buf(),
+        };
+        Some((command, cwd))
+    }
+
+    /// Whether tool `name` runs like a `shell` call, in the sandbox and
+    /// subject to the approval policy.
+    pub(crate) fn runs_in_sandbox(&self, name: &str) -> bool {
+        self.tools.get(name).is_none_or(|tool| tool.sandbox)
+    }
+
+    /// Whether tool `name`, when it runs outside the sandbox, needs the
+    /// user's approval first.
+    pub(crate) fn needs_approval(&self, name: &str) -> bool {
+        self.tools.get(name).is_none_or(|tool| tool.approval)
+    }
+
+    /// Time after which the command of tool `name` is killed.
+    pub(crate) fn timeout(&self, name: &str) -> Duration {
+        self.tools
+            .get(name)
+            .and_then(|tool| tool.timeout_ms)
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    /// Runs the command of tool `name` in `cwd` with `arguments`, outside the
+    /// sandbox. `env` is the command's whole environment, including
+    /// [`argument_env`].
+    pub(crate) async fn run(
+        &self,
+        name: &str,
+        command: &[String],
+        cwd: &Path,
+        env: HashMap<String, String>,
+        arguments: &Value,
+    ) -> Result<CustomToolOutput, String> {
+        if !self.tools.contains_key(name) {
+            return Err(format!("unsupported call: {name}"));
+        }
+        let Some((program, args)) = command.split_first() else {
+            return Err(format!("custom tool {name} has an empty command"));
+        };
+        let mut child = Command::new(program);
+        child
+            .args(args)
+            .current_dir(cwd)
+            .env_clear()
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let start = Instant::now();
+        let mut child = child
+            .spawn()
+            .map_err(|e| format!("failed to start {program}: {e}"))?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let arguments_json = arguments.to_string();
+        // Stdin is written while the output is read, so a command that prints
+        // a lot before reading its arguments does not block on a full pipe.
+        let write_stdin = async move {
+            if let Some(mut stdin) = stdin {
+                // Commands that do not read stdin close it early; that is fine.
+                let _ = stdin.write_all(arguments_json.as_bytes()).await;
+            }
+        };
+        let run = async {
+            let ((), stdout, stderr) =
+                tokio::join!(write_stdin, read_capped(stdout), read_capped(stderr));
+            let status = child.wait().await?;
+            Ok::<_, std::io::Error>((stdout, stderr, status))
+        };
+        let timeout = self.timeout(name);
+        let (stdout, stderr, exit_code) = match tokio::time::timeout(timeout, run).await {
+            Ok(output) => {
+                let (stdout, stderr, status) =
+                    output.map_err(|e| format!("failed to run {program}: {e}"))?;
+                (stdout, stderr, status.code().unwrap_or(-1))
+            }
+            Err(_) => (
+                String::new(),
+                format!("command timed out after {} ms", timeout.as_millis()),
+                TIMEOUT_EXIT_CODE,
+            ),
+        };
+        Ok(CustomToolOutput {
+            stdout,
+            stderr,
+            exit_code,
+            duration: start.elapsed(),
+        })
+    }
+}
+
+/// The environment variables that carry `arguments` to a custom tool's
+/// command: `CODEX_TOOL_ARGS` and one `CODEX_TOOL_ARG_<NAME>` per argument.
+pub(crate) fn argument_env(arguments: &Value) -> HashMap<String, String> {
+    let mut env = HashMap::from([("CODEX_TOOL_ARGS".to_string(), arguments.to_string())]);
+    if let Value::Object(map) = arguments {
+        for (key, value) in map {
+            env.insert(argument_env_var(key), argument_text(value));
+        }
+    }
+    env
+}
+
+/// Reads `reader` to the end, keeping at most [`MAX_OUTPUT_BYTES`].
+async fn read_capped<R: AsyncRead + Unpin>(reader: Option<R>) -> String {
+    let Some(mut reader) = reader else {
+        return String::new();
+    };
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buf = [0u8; 8192];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                // The rest is still read, so the command does not block.
+                let room = MAX_OUTPUT_BYTES.saturating_sub(kept.len());
+                kept.extend_from_slice(&buf[..n.min(room)]);
+                truncated |= n > room;
+            }
+        }
+    }
+    let mut text = String::from_utf8_lossy(&kept).into_owned();
+    if truncated {
+        text.push_str(&format!(
+            "\n[output truncated after {MAX_OUTPUT_BYTES} bytes]\n"
+        ));
+    }
+    text
+}
+
+/// Checks the `type`, `enum`, `required`, `properties` and `items` keywords
+/// of `schema`; other keywords are not enforced.
+fn check_schema(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let type_matches = match schema.get("type") {
+        Some(Value::String(expected)) => has_type(value, expected),
+        Some(Value::Array(expected)) => expected
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|expected| has_type(value, expected)),
+        _ => true,
+    };
+    if !type_matches {
+        return Err(format!(
+            "`{path}` must be of type {}",
+            schema.get("type").map(Value::to_string).unwrap_or_default()
+        ));
+    }
+    if let Some(Value::Array(allowed)) = schema.get("enum")
+        && !allowed.contains(value)
+    {
+        return Err(format!(
+            "`{path}` must be one of {}",
+            Value::Array(allowed.clone())
+        ));
+    }
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Array(required)) = schema.get("required")
+                && let Some(key) = required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .find(|key| !map.contains_key(*key))
+            {
+                return Err(format!("`{path}` is missing the required property `{key}`"));
+            }
+            if let Some(Value::Object(properties)) = schema.get("properties") {
+                for (key, property) in properties {
+                    if let Some(value) = map.get(key) {
+                        check_schema(property, value, &format!("{path}.{key}"))?;
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check_schema(item_schema, item, &format!("{path}[{index}]"))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Strings as they are, everything else as JSON.
+fn argument_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn argument_env_var(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("CODEX_TOOL_ARG_{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_types::ShellEnvironmentPolicy;
+    use crate::exec_env::create_env;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn env(arguments: &Value) -> HashMap<String, String> {
+        let mut env = create_env(&ShellEnvironmentPolicy::default());
+        env.extend(argument_env(arguments));
+        env
+    }
+
+    fn tool(command: &[&str]) -> CustomToolConfig {
+        CustomToolConfig {
+            description: "Deploys the app".to_string(),
+            parameters: Some(json!({
+                "type": "object",
+                "properties": {"env": {"type": "string"}},
+                "required": ["env"]
+            })),
+            command: command.iter().map(|part| (*part).to_string()).collect(),
+            cwd: None,
+            timeout_ms: None,
+            sandbox: false,
+            approval: false,
+        }
+    }
+
+    #[test]
+    fn arguments_are_checked_against_the_schema() {
+        let mut deploy = tool(&["./deploy.sh"]);
+        deploy.parameters = Some(json!({
+            "type": "object",
+            "properties": {
+                "env": {"type": "string", "enum": ["staging", "production"]},
+                "replicas": {"type": "integer"}
+            },
+            "required": ["env"]
+        }));
+        let tools = CustomTools::new(BTreeMap::from([("deploy".to_string(), deploy)]));
+
+        assert_eq!(
+            tools.check_arguments("deploy", &json!({"env": "staging", "replicas": 3})),
+            Ok(())
+        );
+        assert_eq!(
+            tools.check_arguments("deploy", &json!({"replicas": 3})),
+            Err("`arguments` is missing the required property `env`".to_string())
+        );
+        assert_eq!(
+            tools.check_arguments("deploy", &json!({"env": "--help"})),
+            Err("`arguments.env` must be one of [\"staging\",\"production\"]".to_string())
+        );
+        assert_eq!(
+            tools.check_arguments("deploy", &json!({"env": "staging", "replicas": "3"})),
+            Err("`arguments.replicas` must be of type \"integer\"".to_string())
+        );
+        assert_eq!(argument_env_var("dry-run"), "CODEX_TOOL_ARG_DRY_RUN");
+    }
+
+    #[test]
+    fn invalid_definitions_are_rejected() {
+        let valid = BTreeMap::from([("deploy".to_string(), tool(&["./deploy.sh"]))]);
+        assert_eq!(validate_custom_tools(&valid), Ok(()));
+
+        let bad_name = BTreeMap::from([("db migrate".to_string(), tool(&["make"]))]);
+        assert!(validate_custom_tools(&bad_name).is_err());
+        let empty_command = BTreeMap::from([("deploy".to_string(), tool(&[]))]);
+        assert!(validate_custom_tools(&empty_command).is_err());
+        for builtin in ["shell", "apply_patch", "read_file", "write_file"] {
+            let shadowing = BTreeMap::from([(builtin.to_string(), tool(&["make"]))]);
+            assert!(validate_custom_tools(&shadowing).is_err(), "{builtin}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn commands_receive_arguments_on_stdin_and_in_env() {
+        let tools = CustomTools::new(BTreeMap::from([(
+            "deploy".to_string(),
+            tool(&["sh", "-c", "echo \"$CODEX_TOOL_ARG_ENV {env}\"; cat"]),
+        )]));
+        let arguments = json!({"env": "staging"});
+        let cwd = std::env::temp_dir();
+        let (command, cwd) = tools.command("deploy", &cwd).expect("deploy is defined");
+
+        let output = tools
+            .run("deploy", &command, &cwd, env(&arguments), &arguments)
+            .await
+            .expect("run deploy");
+
+        assert_eq!(output.exit_code, 0);
+        assert_eq!(output.stdout, "staging {env}\n{\"env\":\"staging\"}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn large_arguments_do_not_block_a_command_that_prints_first() {
+        let mut deploy = tool(&["sh", "-c", "head -c 200000 /dev/zero; cat >/dev/null"]);
+        deploy.timeout_ms = Some(10_000);
+        let tools = CustomTools::new(BTreeMap::from([("deploy".to_string(), deploy)]));
+        let arguments = json!({"env": "x".repeat(200_000)});
+        let cwd = std::env::temp_dir();
+        let (command, cwd) = tools.command("deploy", &cwd).expect("deploy is defined");
+
+        let output = tools
+            .run("deploy", &command, &cwd, env(&json!({})), &arguments)
+            .await
+            .expect("run deploy");
+
+        assert_eq!(output.exit_code, 0, "{}", output.stderr);
+        assert_eq!(output.stdout.len(), 200_000);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn output_is_capped() {
+        let tools = CustomTools::new(BTreeMap::from([(
+            "deploy".to_string(),
+            tool(&["sh", "-c", "head -c 3000000 /dev/zero"]),
+        )]));
+        let arguments = json!({"env": "staging"});
+        let cwd = std::env::temp_dir();
+        let (command, cwd) = tools.command("deploy", &cwd).expect("deploy is defined");
+
+        let output = tools
+            .run("deploy", &command, &cwd, env(&arguments), &arguments)
+            .await
+            .expect("run deploy");
+
+        assert_eq!(output.exit_code, 0);
+        assert!(
+            output.stdout.ends_with(&format!(
+                "[output truncated after {MAX_OUTPUT_BYTES} bytes]\n"
+            )),
+            "stdout should be truncated"
+        );
+        assert!(output.stdout.len() < MAX_OUTPUT_BYTES + 100);
+    }
+}
@@ -1,5 +1,12 @@
+use crate::config_types::ProxyConfig;
+use crate::model_provider_info::ModelProviderInfo;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
+use reqwest::Certificate;
+use reqwest::ClientBuilder;
+use reqwest::NoProxy;
+use reqwest::Proxy;
 use reqwest::header::HeaderValue;
+use std::path::PathBuf;
 use std::sync::LazyLock;
 use std::sync::Mutex;
 
@@ -106,6 +113,60 @@ fn sanitize_user_agent(candidate: String, fallback: &str) -> String {
 
 /// Create a reqwest client with default `originator` and `User-Agent` headers set.
 pub fn create_client() -> reqwest::Client {
+    client_builder()
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
+/// Create a client for requests to `provider`: the default headers plus the
+/// configured proxy and the provider's TLS settings.
+pub fn create_provider_client(
+    proxy: Option<&ProxyConfig>,
+    provider: &ModelProviderInfo,
+) -> Result<reqwest::Client, String> {
+    let mut builder = with_network_settings(
+        client_builder(),
+        proxy,
+        provider.ca_certificates.as_deref().unwrap_or_default(),
+    )?;
+    if provider.danger_accept_invalid_certs == Some(true) {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    builder
+        .build()
+        .map_err(|e| format!("failed to build HTTP client: {e}"))
+}
+
+/// Routes `builder` through `proxy`, if any, and trusts the certificates in
+/// the `ca_certificates` PEM files in addition to the system roots.
+pub(crate) fn with_network_settings(
+    mut builder: ClientBuilder,
+    proxy: Option<&ProxyConfig>,
+    ca_certificates: &[PathBuf],
+) -> Result<ClientBuilder, String> {
+    // The sandbox forbids the network anyway, so there is no proxy to use.
+    if let Some(proxy) = proxy
+        && !is_sandboxed()
+    {
+        let no_proxy = NoProxy::from_string(&proxy.no_proxy.join(","));
+        let proxy = Proxy::all(&proxy.url)
+            .map_err(|e| format!("invalid proxy URL `{}`: {e}", proxy.url))?
+            .no_proxy(no_proxy);
+        builder = builder.proxy(proxy);
+    }
+    for path in ca_certificates {
+        let pem = std::fs::read(path)
+            .map_err(|e| format!("failed to read CA certificate {}: {e}", path.display()))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("invalid CA certificate {}: {e}", path.display()))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+fn client_builder() -> ClientBuilder {
     use reqwest::header::HeaderMap;
 
     let mut headers = HeaderMap::new();
@@ -119,8 +180,7 @@ pub fn create_client() -> reqwest::Client {
     if is_sandboxed() {
         builder = builder.no_proxy();
     }
-
-    builder.build().unwrap_or_else(|_| reqwest::Client::new())
+    builder
 }
 
 fn is_sandboxed() -> bool {
@@ -186,6 +246,31 @@ mod tests {
         assert_eq!(ua_header.to_str().unwrap(), expected_ua);
     }
 
+    #[test]
+    fn test_provider_client_reports_bad_settings() {
+        let mut provider = crate::built_in_model_providers()["openai"].clone();
+        provider.ca_certificates = Some(vec!["/nonexistent/corp-ca.pem".into()]);
+        let err = create_provider_client(None, &provider).expect_err("missing CA file");
+        assert!(
+            err.starts_with("failed to read CA certificate /nonexistent/corp-ca.pem"),
+            "{err}"
+        );
+
+        provider.ca_certificates = None;
+        provider.danger_accept_invalid_certs = Some(true);
+        let proxy = ProxyConfig {
+            url: "http://proxy.corp.example:3128".to_string(),
+            no_proxy: vec!["localhost".to_string()],
+        };
+        assert!(create_provider_client(Some(&proxy), &provider).is_ok());
+
+        let proxy = ProxyConfig {
+            url: "not a url".to_string(),
+            no_proxy: Vec::new(),
+        };
+        assert!(create_provider_client(Some(&proxy), &provider).is_err());
+    }
+
     #[test]
     fn test_invalid_suffix_is_sanitized() {
         let prefix = "codex_cli_rs/0.0.0";
@@ -45,12 +45,14 @@ impl EnvironmentContext {
                 Some(SandboxPolicy::DangerFullAccess) => Some(SandboxMode::DangerFullAccess),
                 Some(SandboxPolicy::ReadOnly) => Some(SandboxMode::ReadOnly),
                 Some(SandboxPolicy::WorkspaceWrite { .. }) => Some(SandboxMode::WorkspaceWrite),
+                Some(SandboxPolicy::Container { .. }) => Some(SandboxMode::Container),
                 None => None,
             },
             network_access: match sandbox_policy {
                 Some(SandboxPolicy::DangerFullAccess) => Some(NetworkAccess::Enabled),
                 Some(SandboxPolicy::ReadOnly) => Some(NetworkAccess::Restricted),
-                Some(SandboxPolicy::WorkspaceWrite { network_access, .. }) => {
+                Some(SandboxPolicy::WorkspaceWrite { network_access, .. })
+                | Some(SandboxPolicy::Container { network_access, .. }) => {
                     if network_access {
                         Some(NetworkAccess::Enabled)
                     } else {
@@ -60,7 +62,8 @@ impl EnvironmentContext {
                 None => None,
             },
             writable_roots: match sandbox_policy {
-                Some(SandboxPolicy::WorkspaceWrite { writable_roots, .. }) => {
+                Some(SandboxPolicy::WorkspaceWrite { writable_roots, .. })
+                | Some(SandboxPolicy::Container { writable_roots, .. }) => {
                     if writable_roots.is_empty() {
                         None
                     } else {
@@ -186,6 +189,8 @@ mod tests {
             network_access,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            network_allowlist: Vec::new(),
+            deny: Vec::new(),
         }
     }
 
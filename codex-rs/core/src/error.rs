@@ -1,7 +1,9 @@
 use crate::exec::ExecToolCallOutput;
+use crate::rollout::SessionLocked;
 use crate::token_data::KnownPlan;
 use crate::token_data::PlanType;
 use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::protocol::ErrorKind;
 use codex_protocol::protocol::RateLimitSnapshot;
 use reqwest::StatusCode;
 use serde_json;
@@ -61,6 +63,10 @@ pub enum CodexErr {
     #[error("session configured event was not the first event in the stream")]
     SessionConfiguredNotFirstEvent,
 
+    /// Returned when resuming a session that another process has open.
+    #[error("{0}")]
+    SessionLocked(SessionLocked),
+
     /// Returned by run_command_stream when the spawned child process timed out (10s).
     #[error("timeout waiting for child process to exit")]
     Timeout,
@@ -250,6 +256,33 @@ impl CodexErr {
     pub fn downcast_ref<T: std::any::Any>(&self) -> Option<&T> {
         (self as &dyn std::any::Any).downcast_ref::<T>()
     }
+
+    /// What failed, as reported to clients in [`ErrorEvent::kind`].
+    ///
+    /// [`ErrorEvent::kind`]: codex_protocol::protocol::ErrorEvent::kind
+    pub fn error_kind(&self) -> ErrorKind {
+        match self {
+            CodexErr::UnexpectedStatus(status, _)
+                if *status == StatusCode::UNAUTHORIZED || *status == StatusCode::FORBIDDEN =>
+            {
+                ErrorKind::Auth
+            }
+            CodexErr::EnvVar(_) => ErrorKind::Auth,
+            CodexErr::Stream(..)
+            | CodexErr::UnexpectedStatus(..)
+            | CodexErr::UsageLimitReached(_)
+            | CodexErr::UsageNotIncluded
+            | CodexErr::InternalServerError
+            | CodexErr::RetryLimit(_)
+            | CodexErr::Reqwest(_) => ErrorKind::Provider,
+            // A command that ran too long was not stopped by the sandbox.
+            CodexErr::Sandbox(SandboxErr::Timeout { .. }) => ErrorKind::Other,
+            CodexErr::Sandbox(_) | CodexErr::LandlockSandboxExecutableNotProvided => {
+                ErrorKind::Sandbox
+            }
+            _ => ErrorKind::Other,
+        }
+    }
 }
 
 pub fn get_error_message_ui(e: &CodexErr) -> String {
@@ -323,6 +356,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn error_kind_tells_auth_from_other_provider_errors() {
+        assert_eq!(
+            CodexErr::UnexpectedStatus(StatusCode::UNAUTHORIZED, String::new()).error_kind(),
+            ErrorKind::Auth
+        );
+        assert_eq!(
+            CodexErr::UnexpectedStatus(StatusCode::BAD_REQUEST, String::new()).error_kind(),
+            ErrorKind::Provider
+        );
+        assert_eq!(
+            CodexErr::RetryLimit(StatusCode::TOO_MANY_REQUESTS).error_kind(),
+            ErrorKind::Provider
+        );
+        assert_eq!(
+            CodexErr::Sandbox(SandboxErr::LandlockRestrict).error_kind(),
+            ErrorKind::Sandbox
+        );
+        assert_eq!(CodexErr::Interrupted.error_kind(), ErrorKind::Other);
+    }
+
     #[test]
     fn usage_limit_reached_error_formats_team_plan() {
         let err = UsageLimitReachedError {
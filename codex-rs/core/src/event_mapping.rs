@@ -1,3 +1,4 @@
+use crate::file_attachments::FILE_ATTACHMENT_OPEN_TAG;
 use crate::protocol::AgentMessageEvent;
 use crate::protocol::AgentReasoningEvent;
 use crate::protocol::AgentReasoningRawContentEvent;
@@ -5,6 +6,7 @@ use crate::protocol::EventMsg;
 use crate::protocol::InputMessageKind;
 use crate::protocol::UserMessageEvent;
 use crate::protocol::WebSearchEndEvent;
+use crate::url_attachments::URL_ATTACHMENT_OPEN_TAG;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ReasoningItemContent;
 use codex_protocol::models::ReasoningItemReasoningSummary;
@@ -32,6 +34,11 @@ pub(crate) fn map_response_item_to_event_messages(
 
             for content_item in content.iter() {
                 match content_item {
+                    // Fetched URL contents and attached files are for the
+                    // model only.
+                    ContentItem::InputText { text }
+                        if text.starts_with(URL_ATTACHMENT_OPEN_TAG)
+                            || text.starts_with(FILE_ATTACHMENT_OPEN_TAG) => {}
                     ContentItem::InputText { text } => {
                         if kind.is_none() {
                             let trimmed = text.trim_start();
@@ -0,0 +1,489 @@
+//! Typed, sequenced subscriptions to the events of a conversation.
+//!
+//! Every event a conversation emits gets a sequence number, starting at 0.
+//! The most recent [`EVENT_REPLAY_CAPACITY`] events are kept so that a
+//! subscription can start from an earlier sequence number, for example to
+//! resume where a previous subscription stopped. Any number of subscriptions
+//! can read the same events:
+//!
+//! ```ignore
+//! let mut patches = conversation.events().filter::<PatchApplyEndEvent>();
+//! while let Ok(patch) = patches.next().await {
+//!     println!("patch {} applied: {}", patch.seq, patch.payload.success);
+//! }
+//! ```
+//!
+//! By default a subscription applies backpressure: the conversation does not
+//! get more than [`EVENT_REPLAY_CAPACITY`] events ahead of it, so other
+//! subscriptions wait for it to catch up. A subscription created with
+//! [`LagPolicy::Skip`] never holds the others back; if it falls behind, it
+//! reports how many events it missed and continues with the oldest event
+//! that is still kept.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+
+use async_channel::Receiver;
+use thiserror::Error;
+use tokio::sync::Notify;
+
+use crate::protocol;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+
+/// How many recent events are kept for replay, and how far the conversation
+/// may get ahead of a subscription that applies backpressure.
+pub const EVENT_REPLAY_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct SequencedEvent {
+    pub seq: u64,
+    pub event: Event,
+}
+
+/// An event whose payload is a `T`.
+#[derive(Debug, Clone)]
+pub struct TypedEvent<T> {
+    pub seq: u64,
+    /// The id of the submission the event belongs to.
+    pub id: String,
+    pub payload: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum EventSubscriptionError {
+    /// The subscription fell behind and these events are no longer kept. The
+    /// next call continues with the oldest event that is.
+    #[error("subscription lagged behind and missed {missed} events")]
+    Lagged { missed: u64 },
+
+    /// The conversation ended and every event has been read.
+    #[error("the conversation has no more events")]
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Hold the conversation back until this subscription catches up.
+    #[default]
+    Block,
+    /// Let the conversation move on and report the events that were missed.
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubscribeOptions {
+    /// Sequence number of the first event to read. `None` starts with the
+    /// next event the conversation emits.
+    pub replay_from: Option<u64>,
+    pub lag_policy: LagPolicy,
+}
+
+impl SubscribeOptions {
+    pub fn replay_from(mut self, seq: u64) -> Self {
+        self.replay_from = Some(seq);
+        self
+    }
+
+    pub fn lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+        self.lag_policy = lag_policy;
+        self
+    }
+}
+
+/// The payload of one kind of [`EventMsg`], for [`EventSubscription::filter`].
+pub trait EventPayload: Sized {
+    fn from_msg(msg: EventMsg) -> Option<Self>;
+}
+
+macro_rules! event_payloads {
+    ($($variant:ident($payload:ty)),* $(,)?) => {
+        $(
+            impl EventPayload for $payload {
+                fn from_msg(msg: EventMsg) -> Option<Self> {
+                    match msg {
+                        EventMsg::$variant(payload) => Some(payload),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+event_payloads! {
+    Error(protocol::ErrorEvent),
+    TaskStarted(protocol::TaskStartedEvent),
+    TaskComplete(protocol::TaskCompleteEvent),
+    TokenCount(protocol::TokenCountEvent),
+    AgentMessage(protocol::AgentMessageEvent),
+    UserMessage(protocol::UserMessageEvent),
+    AgentMessageDelta(protocol::AgentMessageDeltaEvent),
+    AgentReasoning(protocol::AgentReasoningEvent),
+    AgentReasoningDelta(protocol::AgentReasoningDeltaEvent),
+    AgentReasoningRawContent(protocol::AgentReasoningRawContentEvent),
+    AgentReasoningRawContentDelta(protocol::AgentReasoningRawContentDeltaEvent),
+    AgentReasoningSectionBreak(protocol::AgentReasoningSectionBreakEvent),
+    SessionConfigured(protocol::SessionConfiguredEvent),
+    McpToolCallBegin(protocol::McpToolCallBeginEvent),
+    McpToolCallEnd(protocol::McpToolCallEndEvent),
+    WebSearchBegin(protocol::WebSearchBeginEvent),
+    WebSearchEnd(protocol::WebSearchEndEvent),
+    ExecCommandBegin(protocol::ExecCommandBeginEvent),
+    ExecCommandOutputDelta(protocol::ExecCommandOutputDeltaEvent),
+    ExecCommandEnd(protocol::ExecCommandEndEvent),
+    ExecApprovalRequest(protocol::ExecApprovalRequestEvent),
+    ApplyPatchApprovalRequest(protocol::ApplyPatchApprovalRequestEvent),
+    SecretRequest(protocol::SecretRequestEvent),
+    McpSamplingApprovalRequest(protocol::McpSamplingApprovalRequestEvent),
+    BackgroundEvent(protocol::BackgroundEventEvent),
+    StreamError(protocol::StreamErrorEvent),
+    PatchApplyBegin(protocol::PatchApplyBeginEvent),
+    PatchApplyEnd(protocol::PatchApplyEndEvent),
+    TurnDiff(protocol::TurnDiffEvent),
+    GetHistoryEntryResponse(protocol::GetHistoryEntryResponseEvent),
+    McpListToolsResponse(protocol::McpListToolsResponseEvent),
+    ListCustomPromptsResponse(protocol::ListCustomPromptsResponseEvent),
+    ContextUsage(protocol::ContextUsageEvent),
+    PlanUpdate(crate::plan_tool::UpdatePlanArgs),
+    TurnAborted(protocol::TurnAbortedEvent),
+    ConversationPath(protocol::ConversationPathResponseEvent),
+    EnteredReviewMode(protocol::ReviewRequest),
+    ExitedReviewMode(protocol::ExitedReviewModeEvent),
+    AnnotationAdded(protocol::AnnotationAddedEvent),
+    ContextPruned(protocol::ContextPrunedEvent),
+    LoginRequired(protocol::LoginRequiredEvent),
+    BackgroundResponseStarted(protocol::BackgroundResponseStartedEvent),
+    BackgroundJob(protocol::BackgroundJobEvent),
+    ProviderEndpointSwitched(protocol::ProviderEndpointSwitchedEvent),
+    ProviderSwitched(protocol::ProviderSwitchedEvent),
+    McpServerStatus(protocol::McpServerStatusEvent),
+    PullRequestCreated(protocol::PullRequestCreatedEvent),
+    TurnReverted(protocol::TurnRevertedEvent),
+    CheckpointCreated(protocol::CheckpointCreatedEvent),
+    CheckpointRestored(protocol::CheckpointRestoredEvent),
+    ModelChanged(protocol::ModelChangedEvent),
+}
+
+/// Reads the events of a conversation from the core event channel, numbers
+/// them and fans them out to subscriptions.
+pub(crate) struct EventHub {
+    source: Receiver<Event>,
+    capacity: usize,
+    state: StdMutex<HubState>,
+    /// Held by the subscription that is reading the next event from `source`.
+    pull: tokio::sync::Mutex<()>,
+    /// Notified whenever a subscription moves forward or goes away.
+    advanced: Notify,
+}
+
+#[derive(Default)]
+struct HubState {
+    /// The most recent events, oldest first.
+    events: VecDeque<SequencedEvent>,
+    next_seq: u64,
+    /// Next sequence number of each subscription that applies backpressure.
+    blocking: HashMap<u64, u64>,
+    next_subscription_id: u64,
+    closed: bool,
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Read {
+    Event(SequencedEvent),
+    Lagged { first_seq: u64 },
+    Pending,
+}
+
+impl HubState {
+    fn first_seq(&self) -> u64 {
+        self.next_seq - self.events.len() as u64
+    }
+
+    fn read(&self, seq: u64) -> Read {
+        let first_seq = self.first_seq();
+        if seq < first_seq {
+            Read::Lagged { first_seq }
+        } else if let Some(event) = self.events.get((seq - first_seq) as usize) {
+            Read::Event(event.clone())
+        } else {
+            Read::Pending
+        }
+    }
+
+    /// Whether another event can be read without dropping one that a blocking
+    /// subscription has yet to read.
+    fn has_room(&self, capacity: usize) -> bool {
+        self.blocking
+            .values()
+            .all(|&seq| self.next_seq - seq < capacity as u64)
+    }
+}
+
+impl EventHub {
+    pub(crate) fn new(source: Receiver<Event>, capacity: usize) -> Self {
+        Self {
+            source,
+            capacity,
+            state: StdMutex::new(HubState::default()),
+            pull: tokio::sync::Mutex::new(()),
+            advanced: Notify::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, HubState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn subscribe(self: &Arc<Self>, options: SubscribeOptions) -> EventSubscription {
+        let mut state = self.state();
+        let next_seq = options
+            .replay_from
+            .map_or(state.next_seq, |seq| seq.min(state.next_seq));
+        let id = state.next_subscription_id;
+        state.next_subscription_id += 1;
+        if options.lag_policy == LagPolicy::Block {
+            let first_seq = state.first_seq();
+            state.blocking.insert(id, next_seq.max(first_seq));
+        }
+        EventSubscription {
+            hub: Arc::clone(self),
+            id,
+            next_seq,
+        }
+    }
+
+    /// Reads the event with sequence number `seq` from the source, unless
+    /// another subscription already did, once every blocking subscription
+    /// leaves room for it.
+    async fn pull(&self, seq: u64) {
+        let _pull = self.pull.lock().await;
+        loop {
+            let advanced = self.advanced.notified();
+            tokio::pin!(advanced);
+            advanced.as_mut().enable();
+            {
+                let state = self.state();
+                if state.next_seq > seq || state.closed {
+                    return;
+                }
+                if state.has_room(self.capacity) {
+                    break;
+                }
+            }
+            advanced.await;
+        }
+
+        let received = self.source.recv().await;
+        let mut state = self.state();
+        match received {
+            Ok(event) => {
+                let seq = state.next_seq;
+                state.events.push_back(SequencedEvent { seq, event });
+                state.next_seq += 1;
+                if state.events.len() > self.capacity {
+                    state.events.pop_front();
+                }
+            }
+            Err(_) => state.closed = true,
+        }
+    }
+}
+
+/// A subscription to every event of a conversation, in order.
+pub struct EventSubscription {
+    hub: Arc<EventHub>,
+    id: u64,
+    next_seq: u64,
+}
+
+impl EventSubscription {
+    /// Sequence number of the next event this subscription reads. Pass it to
+    /// [`SubscribeOptions::replay_from`] to resume from here later.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    pub async fn next(&mut self) -> Result<SequencedEvent, EventSubscriptionError> {
+        loop {
+            let read = {
+                let state = self.hub.state();
+                match state.read(self.next_seq) {
+                    Read::Pending if state.closed => return Err(EventSubscriptionError::Closed),
+                    read => read,
+                }
+            };
+            match read {
+                Read::Event(event) => {
+                    self.advance_to(event.seq + 1);
+                    return Ok(event);
+                }
+                Read::Lagged { first_seq } => {
+                    let missed = first_seq - self.next_seq;
+                    self.advance_to(first_seq);
+                    return Err(EventSubscriptionError::Lagged { missed });
+                }
+                Read::Pending => self.hub.pull(self.next_seq).await,
+            }
+        }
+    }
+
+    /// Narrows the subscription to the events whose payload is a `T`.
+    pub fn filter<T: EventPayload>(self) -> TypedSubscription<T> {
+        TypedSubscription {
+            inner: self,
+            _payload: PhantomData,
+        }
+    }
+
+    fn advance_to(&mut self, next_seq: u64) {
+        self.next_seq = next_seq;
+        if let Some(seq) = self.hub.state().blocking.get_mut(&self.id) {
+            *seq = next_seq;
+        }
+        self.hub.advanced.notify_waiters();
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.hub.state().blocking.remove(&self.id);
+        self.hub.advanced.notify_waiters();
+    }
+}
+
+/// A subscription to the events of a conversation whose payload is a `T`.
+pub struct TypedSubscription<T> {
+    inner: EventSubscription,
+    _payload: PhantomData<fn() -> T>,
+}
+
+impl<T: EventPayload> TypedSubscription<T> {
+    /// Sequence number of the next event this subscription looks at.
+    pub fn next_seq(&self) -> u64 {
+        self.inner.next_seq()
+    }
+
+    pub async fn next(&mut self) -> Result<TypedEvent<T>, EventSubscriptionError> {
+        loop {
+            let SequencedEvent { seq, event } = self.inner.next().await?;
+            if let Some(payload) = T::from_msg(event.msg) {
+                return Ok(TypedEvent {
+                    seq,
+                    id: event.id,
+                    payload,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::BackgroundEventEvent;
+    use crate::protocol::TaskCompleteEvent;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn task_complete(message: &str) -> Event {
+        Event {
+            id: "1".to_string(),
+            msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: Some(message.to_string()),
+                failure: None,
+            }),
+        }
+    }
+
+    fn background(message: &str) -> Event {
+        Event {
+            id: "1".to_string(),
+            msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+                message: message.to_string(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn subscriptions_share_events_and_replay_from_seq() {
+        let (tx, rx) = async_channel::unbounded();
+        let hub = Arc::new(EventHub::new(rx, EVENT_REPLAY_CAPACITY));
+        let mut live = hub.subscribe(SubscribeOptions::default());
+        for event in [background("a"), task_complete("done"), background("b")] {
+            tx.send(event).await.expect("send event");
+        }
+        drop(tx);
+
+        let mut seqs = Vec::new();
+        while let Ok(event) = live.next().await {
+            seqs.push(event.seq);
+        }
+        assert_eq!(seqs, vec![0, 1, 2]);
+        assert_eq!(
+            live.next().await.err(),
+            Some(EventSubscriptionError::Closed)
+        );
+
+        let mut completions = hub
+            .subscribe(SubscribeOptions::default().replay_from(1))
+            .filter::<TaskCompleteEvent>();
+        let completed = completions.next().await.expect("task complete");
+        assert_eq!(
+            (completed.seq, completed.payload.last_agent_message),
+            (1, Some("done".to_string()))
+        );
+        assert_eq!(
+            completions.next().await.err(),
+            Some(EventSubscriptionError::Closed)
+        );
+    }
+
+    #[tokio::test]
+    async fn blocking_subscription_holds_back_the_others() {
+        let (tx, rx) = async_channel::unbounded();
+        let hub = Arc::new(EventHub::new(rx, 2));
+        let mut slow = hub.subscribe(SubscribeOptions::default());
+        let mut fast = hub.subscribe(SubscribeOptions::default());
+        for message in ["a", "b", "c"] {
+            tx.send(background(message)).await.expect("send event");
+        }
+
+        assert_eq!(fast.next().await.map(|e| e.seq), Ok(0));
+        assert_eq!(fast.next().await.map(|e| e.seq), Ok(1));
+        // Reading the third event would drop one `slow` has not read yet.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), fast.next())
+                .await
+                .is_err()
+        );
+
+        assert_eq!(slow.next().await.map(|e| e.seq), Ok(0));
+        assert_eq!(fast.next().await.map(|e| e.seq), Ok(2));
+    }
+
+    #[tokio::test]
+    async fn skipping_subscription_reports_missed_events() {
+        let (tx, rx) = async_channel::unbounded();
+        let hub = Arc::new(EventHub::new(rx, 2));
+        let mut skipping = hub.subscribe(SubscribeOptions::default().lag_policy(LagPolicy::Skip));
+        let mut reader = hub.subscribe(SubscribeOptions::default());
+        for message in ["a", "b", "c", "d"] {
+            tx.send(background(message)).await.expect("send event");
+        }
+        for seq in 0..4 {
+            assert_eq!(reader.next().await.map(|e| e.seq), Ok(seq));
+        }
+
+        assert_eq!(
+            skipping.next().await.err(),
+            Some(EventSubscriptionError::Lagged { missed: 2 })
+        );
+        assert_eq!(skipping.next().await.map(|e| e.seq), Ok(2));
+    }
+}
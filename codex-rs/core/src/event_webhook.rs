@@ -0,0 +1,381 @@
+//! Forwarding of protocol events to user-configured webhooks.
+//!
+//! Each `[[webhooks]]` entry in `config.toml` receives the events that pass its
+//! filter as JSON `POST` requests. When `secret_env` is set, the body is signed
+//! with HMAC-SHA256 and the signature is sent in the `X-Codex-Signature-256`
+//! header as `sha256=<hex>`, so receivers can verify that a payload came from
+//! Codex.
+//!
+//! Deliveries happen on a background task in the order events were emitted.
+//! A slow or unreachable endpoint never blocks the session: if too many
+//! deliveries are pending, new events for that endpoint are dropped.
+
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::protocol::Event;
+use codex_protocol::protocol::EventMsg;
+use hmac::Hmac;
+use hmac::Mac;
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::config_types::EventSeverity;
+use crate::config_types::WebhookConfig;
+use crate::default_client::create_client;
+
+pub(crate) const EVENT_HEADER: &str = "X-Codex-Event";
+pub(crate) const SIGNATURE_HEADER: &str = "X-Codex-Signature-256";
+
+/// Pending deliveries per session before new events are dropped.
+const MAX_PENDING_DELIVERIES: usize = 1024;
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub(crate) struct EventWebhooks {
+    webhooks: Vec<Webhook>,
+    tx: mpsc::Sender<Delivery>,
+}
+
+#[derive(Debug)]
+struct Webhook {
+    config: WebhookConfig,
+    secret: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+struct Delivery {
+    url: String,
+    event_type: String,
+    body: String,
+    signature: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    conversation_id: ConversationId,
+    severity: EventSeverity,
+    timestamp: String,
+    event: &'a Event,
+}
+
+impl EventWebhooks {
+    /// Returns `None` if no usable webhooks are configured. Must be called
+    /// from within a Tokio runtime.
+    pub(crate) fn new(configs: &[WebhookConfig]) -> Option<Self> {
+        let webhooks: Vec<Webhook> = configs
+            .iter()
+            .filter_map(|config| {
+                let secret = match &config.secret_env {
+                    Some(var) => match std::env::var(var) {
+                        Ok(secret) => Some(secret.into_bytes()),
+                        Err(_) => {
+                            // Sending unsigned payloads to an endpoint that
+                            // expects signatures would silently weaken it.
+                            warn!(
+                                "webhook {} disabled: environment variable {var} is not set",
+                                config.url
+                            );
+                            return None;
+                        }
+                    },
+                    None => None,
+                };
+                Some(Webhook {
+                    config: config.clone(),
+                    secret,
+                })
+            })
+            .collect();
+        if webhooks.is_empty() {
+            return None;
+        }
+        Some(Self::spawn(webhooks))
+    }
+
+    fn spawn(webhooks: Vec<Webhook>) -> Self {
+        let (tx, rx) = mpsc::channel(MAX_PENDING_DELIVERIES);
+        tokio::spawn(deliver(rx));
+        Self { webhooks, tx }
+    }
+
+    /// Queue `event` for every webhook whose filter it passes.
+    pub(crate) fn dispatch(&self, conversation_id: ConversationId, event: &Event) {
+        let event_type = event.msg.to_string();
+        let severity = event_severity(&event.msg);
+        let conversation = conversation_id.to_string();
+        let matching: Vec<&Webhook> = self
+            .webhooks
+            .iter()
+            .filter(|webhook| {
+                matches_filter(
+                    &webhook.config,
+                    &event_type,
+                    &conversation,
+                    severity,
+                    &event.msg,
+                )
+            })
+            .collect();
+        if matching.is_empty() {
+            return;
+        }
+
+        let payload = WebhookPayload {
+            conversation_id,
+            severity,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            event,
+        };
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!("failed to serialize webhook payload: {err}");
+                return;
+            }
+        };
+        for webhook in matching {
+            let delivery = Delivery {
+                url: webhook.config.url.clone(),
+                event_type: event_type.clone(),
+                body: body.clone(),
+                signature: webhook.secret.as_deref().map(|secret| sign(secret, &body)),
+            };
+            if self.tx.try_send(delivery).is_err() {
+                warn!(
+                    "dropping {event_type} event for webhook {}: too many pending deliveries",
+                    webhook.config.url
+                );
+            }
+        }
+    }
+}
+
+async fn deliver(mut rx: mpsc::Receiver<Delivery>) {
+    let client = create_client();
+    while let Some(delivery) = rx.recv().await {
+        let mut request = client
+            .post(&delivery.url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event_type)
+            .body(delivery.body);
+        if let Some(signature) = &delivery.signature {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        match request.send().await {
+            Ok(response) if !response.status().is_success() => {
+                warn!(
+                    "webhook {} rejected {} event: {}",
+                    delivery.url,
+                    delivery.event_type,
+                    response.status()
+                );
+            }
+            Ok(_) => {}
+            Err(err) => {
+                warn!("failed to deliver event to webhook {}: {err}", delivery.url);
+            }
+        }
+    }
+}
+
+fn matches_filter(
+    config: &WebhookConfig,
+    event_type: &str,
+    conversation_id: &str,
+    severity: EventSeverity,
+    msg: &EventMsg,
+) -> bool {
+    let type_matches = if config.event_types.is_empty() {
+        !is_streaming_delta(msg)
+    } else {
+        config.event_types.iter().any(|t| t == event_type)
+    };
+    let conversation_matches = config.conversation_ids.is_empty()
+        || config
+            .conversation_ids
+            .iter()
+            .any(|id| id == conversation_id);
+    type_matches && conversation_matches && severity >= config.min_severity
+}
+
+fn event_severity(msg: &EventMsg) -> EventSeverity {
+    match msg {
+        EventMsg::Error(_) | EventMsg::LoginRequired(_) => EventSeverity::Error,
+        EventMsg::StreamError(_)
+        | EventMsg::TurnAborted(_)
+        | EventMsg::ExecApprovalRequest(_)
+        | EventMsg::ApplyPatchApprovalRequest(_)
+        | EventMsg::SecretRequest(_) => EventSeverity::Warning,
+        _ => EventSeverity::Info,
+    }
+}
+
+/// Deltas are emitted for every streamed token, so they are only forwarded
+/// when a webhook asks for them by type.
+fn is_streaming_delta(msg: &EventMsg) -> bool {
+    matches!(
+        msg,
+        EventMsg::AgentMessageDelta(_)
+            | EventMsg::AgentReasoningDelta(_)
+            | EventMsg::AgentReasoningRawContentDelta(_)
+            | EventMsg::ExecCommandOutputDelta(_)
+    )
+}
+
+fn sign(secret: &[u8], body: &str) -> String {
+    #[expect(clippy::expect_used)]
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("sha256={digest}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::AgentMessageDeltaEvent;
+    use codex_protocol::protocol::ErrorEvent;
+    use codex_protocol::protocol::ErrorKind;
+    use codex_protocol::protocol::TaskCompleteEvent;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+
+    fn webhook(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            url: url.to_string(),
+            event_types: Vec::new(),
+            conversation_ids: Vec::new(),
+            min_severity: EventSeverity::Info,
+            secret_env: None,
+        }
+    }
+
+    fn passes(config: &WebhookConfig, msg: EventMsg) -> bool {
+        let event_type = msg.to_string();
+        matches_filter(config, &event_type, "conv-1", event_severity(&msg), &msg)
+    }
+
+    fn error_msg() -> EventMsg {
+        EventMsg::Error(ErrorEvent {
+            message: "boom".to_string(),
+            kind: ErrorKind::Other,
+        })
+    }
+
+    fn task_complete_msg() -> EventMsg {
+        EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: None,
+            failure: None,
+        })
+    }
+
+    fn delta_msg() -> EventMsg {
+        EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+            delta: "x".to_string(),
+        })
+    }
+
+    #[test]
+    fn filters_by_type_conversation_and_severity() {
+        let all = webhook("http://example.invalid");
+        assert!(passes(&all, task_complete_msg()));
+        assert!(!passes(&all, delta_msg()));
+
+        let deltas = WebhookConfig {
+            event_types: vec!["agent_message_delta".to_string()],
+            ..webhook("http://example.invalid")
+        };
+        assert!(passes(&deltas, delta_msg()));
+        assert!(!passes(&deltas, task_complete_msg()));
+
+        let errors = WebhookConfig {
+            min_severity: EventSeverity::Warning,
+            ..webhook("http://example.invalid")
+        };
+        assert!(passes(&errors, error_msg()));
+        assert!(!passes(&errors, task_complete_msg()));
+
+        let other_conversation = WebhookConfig {
+            conversation_ids: vec!["conv-2".to_string()],
+            ..webhook("http://example.invalid")
+        };
+        assert!(!passes(&other_conversation, error_msg()));
+    }
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        assert_eq!(
+            sign(b"secret", "hello"),
+            "sha256=88aab3ede8d3adf94d26ab90d3bafd4a2083070c3bcce9c014ee04a443847c0b"
+        );
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header(EVENT_HEADER, "task_complete"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let webhooks = EventWebhooks::spawn(vec![Webhook {
+            config: webhook(&server.uri()),
+            secret: Some(b"secret".to_vec()),
+        }]);
+
+        let conversation_id = ConversationId::default();
+        webhooks.dispatch(
+            conversation_id,
+            &Event {
+                id: "sub-1".to_string(),
+                msg: task_complete_msg(),
+            },
+        );
+        webhooks.dispatch(
+            conversation_id,
+            &Event {
+                id: "sub-1".to_string(),
+                msg: delta_msg(),
+            },
+        );
+
+        let mut requests = Vec::new();
+        for _ in 0..50 {
+            requests = server.received_requests().await.unwrap_or_default();
+            if !requests.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        let body = String::from_utf8(request.body.clone()).expect("utf-8 body");
+        assert_eq!(
+            request
+                .headers
+                .get(SIGNATURE_HEADER)
+                .and_then(|v| v.to_str().ok()),
+            Some(sign(b"secret", &body).as_str())
+        );
+        let payload: serde_json::Value = serde_json::from_str(&body).expect("json body");
+        assert_eq!(payload["conversation_id"], conversation_id.to_string());
+        assert_eq!(payload["severity"], "info");
+        assert_eq!(payload["event"]["msg"]["type"], "task_complete");
+    }
+}
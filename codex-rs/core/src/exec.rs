@@ -6,6 +6,9 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -14,7 +17,11 @@ use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
+use tokio::sync::Notify;
+use tracing::Instrument;
 
+use crate::config_types::ResourceLimits;
+use crate::container_sandbox::spawn_command_under_container;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
@@ -27,6 +34,7 @@ use crate::protocol::SandboxPolicy;
 use crate::seatbelt::spawn_command_under_seatbelt;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
+use crate::windows_sandbox::spawn_command_under_windows_sandbox;
 
 const DEFAULT_TIMEOUT_MS: u64 = 10_000;
 
@@ -41,9 +49,17 @@ const EXEC_TIMEOUT_EXIT_CODE: i32 = 124; // conventional timeout exit code
 const READ_CHUNK_SIZE: usize = 8192; // bytes per read
 const AGGREGATE_BUFFER_INITIAL_CAPACITY: usize = 8 * 1024; // 8 KiB
 
-/// Limit the number of ExecCommandOutputDelta events emitted per exec call.
-/// Aggregation still collects full output; only the live event stream is capped.
-pub(crate) const MAX_EXEC_OUTPUT_DELTAS_PER_CALL: usize = 10_000;
+/// Minimum time between two ExecCommandOutputDelta events for the same
+/// stream; output read in between is coalesced into the next delta.
+const EXEC_OUTPUT_DELTA_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A delta is sent early once this much output is pending.
+const MAX_EXEC_OUTPUT_DELTA_BYTES: usize = 8 * 1024;
+
+/// Limit the bytes streamed as ExecCommandOutputDelta events per stream of an
+/// exec call. Aggregation still collects full output; only the live event
+/// stream is capped.
+const MAX_EXEC_OUTPUT_STREAMED_BYTES: usize = 1024 * 1024;
 
 #[derive(Clone, Debug)]
 pub struct ExecParams {
@@ -53,6 +69,7 @@ pub struct ExecParams {
     pub env: HashMap<String, String>,
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
+    pub resource_limits: ResourceLimits,
 }
 
 impl ExecParams {
@@ -70,6 +87,12 @@ pub enum SandboxType {
 
     /// Only available on Linux.
     LinuxSeccomp,
+
+    /// Only available on Windows.
+    WindowsRestrictedToken,
+
+    /// Docker or Podman container; used with `SandboxPolicy::Container`.
+    Container,
 }
 
 #[derive(Clone)]
@@ -86,10 +109,41 @@ pub async fn process_exec_tool_call(
     sandbox_cwd: &Path,
     codex_linux_sandbox_exe: &Option<PathBuf>,
     stdout_stream: Option<StdoutStream>,
+) -> Result<ExecToolCallOutput> {
+    // The command itself is left out: it may contain secrets.
+    let span = tracing::info_span!(
+        "sandbox_exec",
+        sandbox = ?sandbox_type,
+        exit_code = tracing::field::Empty,
+    );
+    let result = run_exec_tool_call(
+        params,
+        sandbox_type,
+        sandbox_policy,
+        sandbox_cwd,
+        codex_linux_sandbox_exe,
+        stdout_stream,
+    )
+    .instrument(span.clone())
+    .await;
+    if let Ok(output) = &result {
+        span.record("exit_code", output.exit_code);
+    }
+    result
+}
+
+async fn run_exec_tool_call(
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_cwd: &Path,
+    codex_linux_sandbox_exe: &Option<PathBuf>,
+    stdout_stream: Option<StdoutStream>,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
 
     let timeout_duration = params.timeout_duration();
+    let resource_limits = params.resource_limits;
 
     let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
     {
@@ -106,11 +160,18 @@ pub async fn process_exec_tool_call(
                 command_cwd,
                 sandbox_policy,
                 sandbox_cwd,
+                &resource_limits,
                 StdioPolicy::RedirectForShellTool,
                 env,
             )
             .await?;
-            consume_truncated_output(child, timeout_duration, stdout_stream.clone()).await
+            consume_truncated_output(
+                child,
+                timeout_duration,
+                resource_limits.max_output_bytes,
+                stdout_stream.clone(),
+            )
+            .await
         }
         SandboxType::LinuxSeccomp => {
             let ExecParams {
@@ -129,17 +190,96 @@ pub async fn process_exec_tool_call(
                 command_cwd,
                 sandbox_policy,
                 sandbox_cwd,
+                &resource_limits,
                 StdioPolicy::RedirectForShellTool,
                 env,
             )
             .await?;
 
-            consume_truncated_output(child, timeout_duration, stdout_stream).await
+            consume_truncated_output(
+                child,
+                timeout_duration,
+                resource_limits.max_output_bytes,
+                stdout_stream,
+            )
+            .await
+        }
+        SandboxType::WindowsRestrictedToken => {
+            let ExecParams {
+                command,
+                cwd: command_cwd,
+                env,
+                ..
+            } = params;
+            let child = spawn_command_under_windows_sandbox(
+                command,
+                command_cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                &resource_limits,
+                StdioPolicy::RedirectForShellTool,
+                env,
+            )
+            .await?;
+            consume_truncated_output(
+                child,
+                timeout_duration,
+                resource_limits.max_output_bytes,
+                stdout_stream,
+            )
+            .await
+        }
+        SandboxType::Container => {
+            let ExecParams {
+                command,
+                cwd: command_cwd,
+                env,
+                ..
+            } = params;
+            let (child, container) = spawn_command_under_container(
+                command,
+                command_cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                &resource_limits,
+                StdioPolicy::RedirectForShellTool,
+                env,
+            )
+            .await?;
+            let output = consume_truncated_output(
+                child,
+                timeout_duration,
+                resource_limits.max_output_bytes,
+                stdout_stream,
+            )
+            .await;
+            container.remove().await;
+            output
         }
     };
     let duration = start.elapsed();
     match raw_output_result {
         Ok(raw_output) => {
+            if raw_output.output_limit_exceeded
+                && let Some(limit) = resource_limits.max_output_bytes
+            {
+                let note = format!(
+                    "\n[command killed: output exceeded the {limit}-byte limit (sandbox_resource_limits.max_output_bytes)]\n"
+                );
+                let mut stderr = raw_output.stderr.from_utf8_lossy();
+                stderr.text.push_str(&note);
+                let mut aggregated_output = raw_output.aggregated_output.from_utf8_lossy();
+                aggregated_output.text.push_str(&note);
+                return Ok(ExecToolCallOutput {
+                    exit_code: EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE,
+                    stdout: raw_output.stdout.from_utf8_lossy(),
+                    stderr,
+                    aggregated_output,
+                    duration,
+                    timed_out: false,
+                });
+            }
+
             #[allow(unused_mut)]
             let mut timed_out = raw_output.timed_out;
 
@@ -192,6 +332,123 @@ pub async fn process_exec_tool_call(
     }
 }
 
+/// Spawns `params.command` under `sandbox_type` with a new pseudo-terminal as
+/// its stdin, stdout, and stderr, for interactive commands that need a TTY.
+/// Returns the child and the master side of the terminal. The command's
+/// timeout does not apply; the caller decides how long to wait for it.
+#[cfg(unix)]
+pub(crate) async fn spawn_exec_in_pty(
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_cwd: &Path,
+    codex_linux_sandbox_exe: &Option<PathBuf>,
+) -> Result<(Child, std::os::fd::OwnedFd)> {
+    use std::os::fd::AsRawFd;
+
+    let (master, slave) = open_pty()?;
+    let stdio_policy = StdioPolicy::Pty(slave.as_raw_fd());
+    let ExecParams {
+        command,
+        cwd,
+        env,
+        resource_limits,
+        ..
+    } = params;
+    let child = match sandbox_type {
+        SandboxType::None => {
+            let (program, args) = command.split_first().ok_or_else(|| {
+                CodexErr::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "command args are empty",
+                ))
+            })?;
+            spawn_child_async(
+                PathBuf::from(program),
+                args.into(),
+                None,
+                cwd,
+                sandbox_policy,
+                &resource_limits,
+                stdio_policy,
+                env,
+            )
+            .await?
+        }
+        SandboxType::MacosSeatbelt => {
+            spawn_command_under_seatbelt(
+                command,
+                cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                &resource_limits,
+                stdio_policy,
+                env,
+            )
+            .await?
+        }
+        SandboxType::LinuxSeccomp => {
+            let codex_linux_sandbox_exe = codex_linux_sandbox_exe
+                .as_ref()
+                .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+            spawn_command_under_linux_sandbox(
+                codex_linux_sandbox_exe,
+                command,
+                cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                &resource_limits,
+                stdio_policy,
+                env,
+            )
+            .await?
+        }
+        SandboxType::WindowsRestrictedToken | SandboxType::Container => {
+            return Err(CodexErr::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{sandbox_type:?} sandbox does not support terminal sessions"),
+            )));
+        }
+    };
+    // The child holds its own copies of the slave; dropping ours lets reads
+    // from the master end once the child and its descendants exit.
+    drop(slave);
+    Ok((child, master))
+}
+
+#[cfg(unix)]
+fn open_pty() -> io::Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd)> {
+    use std::os::fd::FromRawFd;
+
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let size = libc::winsize {
+        ws_row: 24,
+        ws_col: 80,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let rc = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut::<libc::termios>(),
+            &size,
+        )
+    };
+    if rc == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openpty succeeded, so both fds are open and owned by us.
+    Ok(unsafe {
+        (
+            std::os::fd::OwnedFd::from_raw_fd(master),
+            std::os::fd::OwnedFd::from_raw_fd(slave),
+        )
+    })
+}
+
 /// We don't have a fully deterministic way to tell if our command failed
 /// because of the sandbox - a command in the user's zshrc file might hit an
 /// error, but the command itself might fail or succeed for other reasons.
@@ -224,6 +481,7 @@ struct RawExecToolCallOutput {
     pub stderr: StreamOutput<Vec<u8>>,
     pub aggregated_output: StreamOutput<Vec<u8>>,
     pub timed_out: bool,
+    pub output_limit_exceeded: bool,
 }
 
 impl StreamOutput<String> {
@@ -266,7 +524,11 @@ async fn exec(
 ) -> Result<RawExecToolCallOutput> {
     let timeout = params.timeout_duration();
     let ExecParams {
-        command, cwd, env, ..
+        command,
+        cwd,
+        env,
+        resource_limits,
+        ..
     } = params;
 
     let (program, args) = command.split_first().ok_or_else(|| {
@@ -282,18 +544,56 @@ async fn exec(
         arg0,
         cwd,
         sandbox_policy,
+        &resource_limits,
         StdioPolicy::RedirectForShellTool,
         env,
     )
     .await?;
-    consume_truncated_output(child, timeout, stdout_stream).await
+    consume_truncated_output(
+        child,
+        timeout,
+        resource_limits.max_output_bytes,
+        stdout_stream,
+    )
+    .await
+}
+
+/// Shared count of stdout and stderr bytes used to enforce
+/// [`ResourceLimits::max_output_bytes`].
+struct OutputBudget {
+    limit: u64,
+    used: AtomicU64,
+    exceeded: Notify,
+}
+
+impl OutputBudget {
+    /// Records `bytes` of output. Returns `false` once the limit is exceeded.
+    fn consume(&self, bytes: usize) -> bool {
+        let bytes = bytes as u64;
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if used > self.limit {
+            self.exceeded.notify_one();
+            false
+        } else {
+            true
+        }
+    }
+}
+
+async fn wait_for_output_limit(budget: Option<&OutputBudget>) {
+    match budget {
+        Some(budget) => budget.exceeded.notified().await,
+        None => std::future::pending().await,
+    }
 }
 
 /// Consumes the output of a child process, truncating it so it is suitable for
-/// use as the output of a `shell` tool call. Also enforces specified timeout.
+/// use as the output of a `shell` tool call. Also enforces specified timeout
+/// and, if set, kills the child once it prints more than `max_output_bytes`.
 async fn consume_truncated_output(
     mut child: Child,
     timeout: Duration,
+    max_output_bytes: Option<u64>,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<RawExecToolCallOutput> {
     // Both stdout and stderr were configured with `Stdio::piped()`
@@ -312,20 +612,30 @@ async fn consume_truncated_output(
     })?;
 
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
+    let output_budget = max_output_bytes.map(|limit| {
+        Arc::new(OutputBudget {
+            limit,
+            used: AtomicU64::new(0),
+            exceeded: Notify::new(),
+        })
+    });
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
         stdout_stream.clone(),
         false,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
         stdout_stream.clone(),
         true,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
 
+    let mut output_limit_exceeded = false;
     let (exit_status, timed_out) = tokio::select! {
         result = tokio::time::timeout(timeout, child.wait()) => {
             match result {
@@ -345,6 +655,11 @@ async fn consume_truncated_output(
             child.start_kill()?;
             (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
         }
+        _ = wait_for_output_limit(output_budget.as_deref()) => {
+            child.start_kill()?;
+            output_limit_exceeded = true;
+            (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
+        }
     };
 
     let stdout = stdout_handle.await??;
@@ -367,6 +682,7 @@ async fn consume_truncated_output(
         stderr,
         aggregated_output,
         timed_out,
+        output_limit_exceeded,
     })
 }
 
@@ -375,39 +691,42 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     stream: Option<StdoutStream>,
     is_stderr: bool,
     aggregate_tx: Option<Sender<Vec<u8>>>,
+    output_budget: Option<Arc<OutputBudget>>,
 ) -> io::Result<StreamOutput<Vec<u8>>> {
     let mut buf = Vec::with_capacity(AGGREGATE_BUFFER_INITIAL_CAPACITY);
     let mut tmp = [0u8; READ_CHUNK_SIZE];
-    let mut emitted_deltas: usize = 0;
+    let mut deltas = stream.map(|stream| OutputDeltas::new(stream, is_stderr));
 
     // No caps: append all bytes
 
     loop {
-        let n = reader.read(&mut tmp).await?;
+        // While output is waiting to be streamed, wake up in time to send it
+        // even if the command prints nothing more.
+        let flush_in = deltas.as_ref().and_then(OutputDeltas::flush_in);
+        let n = match flush_in {
+            Some(flush_in) => match tokio::time::timeout(flush_in, reader.read(&mut tmp)).await {
+                Ok(n) => n?,
+                Err(_) => {
+                    if let Some(deltas) = &mut deltas {
+                        deltas.flush().await;
+                    }
+                    continue;
+                }
+            },
+            None => reader.read(&mut tmp).await?,
+        };
         if n == 0 {
             break;
         }
-
-        if let Some(stream) = &stream
-            && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
+        if let Some(budget) = &output_budget
+            && !budget.consume(n)
         {
-            let chunk = tmp[..n].to_vec();
-            let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
-                call_id: stream.call_id.clone(),
-                stream: if is_stderr {
-                    ExecOutputStream::Stderr
-                } else {
-                    ExecOutputStream::Stdout
-                },
-                chunk,
-            });
-            let event = Event {
-                id: stream.sub_id.clone(),
-                msg,
-            };
-            #[allow(clippy::let_unit_value)]
-            let _ = stream.tx_event.send(event).await;
-            emitted_deltas += 1;
+            // The child is being killed; stop buffering its output.
+            break;
+        }
+
+        if let Some(deltas) = &mut deltas {
+            deltas.push(&tmp[..n]).await;
         }
 
         if let Some(tx) = &aggregate_tx {
@@ -418,12 +737,84 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         // Continue reading to EOF to avoid back-pressure
     }
 
+    if let Some(deltas) = &mut deltas {
+        deltas.flush().await;
+    }
+
     Ok(StreamOutput {
         text: buf,
         truncated_after_lines: None,
     })
 }
 
+/// Turns the output of one stream of a command into ExecCommandOutputDelta
+/// events, at most one every [`EXEC_OUTPUT_DELTA_INTERVAL`] and no more than
+/// [`MAX_EXEC_OUTPUT_STREAMED_BYTES`] in total.
+struct OutputDeltas {
+    stream: StdoutStream,
+    is_stderr: bool,
+    pending: Vec<u8>,
+    last_sent: Option<Instant>,
+    streamed: usize,
+}
+
+impl OutputDeltas {
+    fn new(stream: StdoutStream, is_stderr: bool) -> Self {
+        Self {
+            stream,
+            is_stderr,
+            pending: Vec::new(),
+            last_sent: None,
+            streamed: 0,
+        }
+    }
+
+    /// How long until pending output is due, or `None` if nothing is pending.
+    fn flush_in(&self) -> Option<Duration> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(self.last_sent.map_or(Duration::ZERO, |last_sent| {
+            EXEC_OUTPUT_DELTA_INTERVAL.saturating_sub(last_sent.elapsed())
+        }))
+    }
+
+    async fn push(&mut self, chunk: &[u8]) {
+        let room = MAX_EXEC_OUTPUT_STREAMED_BYTES - self.streamed - self.pending.len();
+        self.pending
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if self.pending.len() >= MAX_EXEC_OUTPUT_DELTA_BYTES
+            || self.flush_in().is_some_and(|flush_in| flush_in.is_zero())
+        {
+            self.flush().await;
+        }
+    }
+
+    async fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let chunk = std::mem::take(&mut self.pending);
+        self.streamed += chunk.len();
+        self.last_sent = Some(Instant::now());
+        let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+            call_id: self.stream.call_id.clone(),
+            stream: if self.is_stderr {
+                ExecOutputStream::Stderr
+            } else {
+                ExecOutputStream::Stdout
+            },
+            chunk,
+        });
+        let event = Event {
+            id: self.stream.sub_id.clone(),
+            msg,
+        };
+        #[allow(clippy::let_unit_value)]
+        let _ = self.stream.tx_event.send(event).await;
+    }
+}
+
 #[cfg(unix)]
 fn synthetic_exit_status(code: i32) -> ExitStatus {
     use std::os::unix::process::ExitStatusExt;
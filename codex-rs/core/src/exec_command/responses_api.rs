@@ -59,7 +59,10 @@ pub fn create_write_stdin_tool_for_responses_api() -> ResponsesApiTool {
     properties.insert(
         "session_id".to_string(),
         JsonSchema::Number {
-            description: Some("The ID of the exec_command session.".to_string()),
+            description: Some(
+                "The ID of the session, as returned by exec_command or by shell with tty."
+                    .to_string(),
+            ),
         },
     );
     properties.insert(
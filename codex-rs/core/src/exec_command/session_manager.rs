@@ -37,6 +37,22 @@ pub struct ExecCommandOutput {
 }
 
 impl ExecCommandOutput {
+    /// The exit code, or `None` while the process is still running.
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        match self.exit_status {
+            ExitStatus::Exited(code) => Some(code),
+            ExitStatus::Ongoing(_) => None,
+        }
+    }
+
+    pub(crate) fn output(&self) -> &str {
+        &self.output
+    }
+
+    pub(crate) fn wall_time(&self) -> Duration {
+        self.wall_time
+    }
+
     pub(crate) fn to_text_output(&self) -> String {
         let wall_time_secs = self.wall_time.as_secs_f32();
         let termination_status = match self.exit_status {
@@ -79,7 +95,7 @@ impl SessionManager {
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
         );
 
-        let (session, mut output_rx, mut exit_rx) = create_exec_command_session(params.clone())
+        let (session, output_rx, exit_rx) = create_exec_command_session(params.clone())
             .await
             .map_err(|err| {
                 format!(
@@ -88,18 +104,76 @@ impl SessionManager {
                 )
             })?;
 
+        self.register_and_collect(
+            session_id,
+            session,
+            output_rx,
+            exit_rx,
+            params.yield_time_ms,
+            params.max_output_tokens,
+        )
+        .await
+    }
+
+    /// Registers a command that was already spawned on a pseudo-terminal (see
+    /// [`crate::exec::spawn_exec_in_pty`]) as a session and collects its
+    /// output the same way as [`Self::handle_exec_command_request`], so that
+    /// it can be driven with `write_stdin` afterwards.
+    #[cfg(unix)]
+    pub(crate) async fn handle_pty_child(
+        &self,
+        child: tokio::process::Child,
+        master: std::os::fd::OwnedFd,
+        yield_time_ms: u64,
+        max_output_tokens: u64,
+    ) -> Result<ExecCommandOutput, String> {
+        let session_id = SessionId(
+            self.next_session_id
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
+        );
+
+        let (session, output_rx, exit_rx) =
+            create_pty_child_session(child, master).map_err(|err| {
+                format!(
+                    "failed to create exec command session for session id {}: {err}",
+                    session_id.0
+                )
+            })?;
+
+        self.register_and_collect(
+            session_id,
+            session,
+            output_rx,
+            exit_rx,
+            yield_time_ms,
+            max_output_tokens,
+        )
+        .await
+    }
+
+    /// Stores `session` and collects its output until either `yield_time_ms`
+    /// expires or the process exits.
+    async fn register_and_collect(
+        &self,
+        session_id: SessionId,
+        session: ExecCommandSession,
+        mut output_rx: tokio::sync::broadcast::Receiver<Vec<u8>>,
+        mut exit_rx: oneshot::Receiver<i32>,
+        yield_time_ms: u64,
+        max_output_tokens: u64,
+    ) -> Result<ExecCommandOutput, String> {
         // Insert into session map.
         self.sessions.lock().await.insert(session_id, session);
 
         // Collect output until either timeout expires or process exits.
         // Do not cap during collection; truncate at the end if needed.
         // Use a modest initial capacity to avoid large preallocation.
-        let cap_bytes_u64 = params.max_output_tokens.saturating_mul(4);
+        let cap_bytes_u64 = max_output_tokens.saturating_mul(4);
         let cap_bytes: usize = cap_bytes_u64.min(usize::MAX as u64) as usize;
         let mut collected: Vec<u8> = Vec::with_capacity(4096);
 
         let start_time = Instant::now();
-        let deadline = start_time + Duration::from_millis(params.yield_time_ms);
+        let deadline = start_time + Duration::from_millis(yield_time_ms);
         let mut exit_code: Option<i32> = None;
 
         loop {
@@ -340,6 +414,108 @@ async fn create_exec_command_session(
     Ok((session, initial_output_rx, exit_rx))
 }
 
+/// Wraps a child spawned on a pseudo-terminal by the shell tool in the same
+/// session plumbing as [`create_exec_command_session`].
+#[cfg(unix)]
+fn create_pty_child_session(
+    mut child: tokio::process::Child,
+    master: std::os::fd::OwnedFd,
+) -> anyhow::Result<(
+    ExecCommandSession,
+    tokio::sync::broadcast::Receiver<Vec<u8>>,
+    oneshot::Receiver<i32>,
+)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child
+        .id()
+        .ok_or_else(|| anyhow::anyhow!("child exited before it could be tracked"))?;
+    let killer = Box::new(ProcessGroupKiller { pid });
+
+    let (writer_tx, mut writer_rx) = mpsc::channel::<Vec<u8>>(128);
+    let (output_tx, _) = tokio::sync::broadcast::channel::<Vec<u8>>(256);
+    let mut reader = std::fs::File::from(master.try_clone()?);
+    let output_tx_clone = output_tx.clone();
+    let reader_handle = tokio::task::spawn_blocking(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let _ = output_tx_clone.send(buf[..n].to_vec());
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                // Linux reports EIO once every process holding the slave has
+                // exited, which is the terminal's end of file.
+                Err(_) => break,
+            }
+        }
+    });
+
+    let writer = Arc::new(StdMutex::new(std::fs::File::from(master)));
+    let writer_handle = tokio::spawn(async move {
+        while let Some(bytes) = writer_rx.recv().await {
+            let writer = writer.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                if let Ok(mut guard) = writer.lock() {
+                    use std::io::Write;
+                    let _ = guard.write_all(&bytes);
+                    let _ = guard.flush();
+                }
+            })
+            .await;
+        }
+    });
+
+    let (exit_tx, exit_rx) = oneshot::channel::<i32>();
+    let exit_status = Arc::new(AtomicBool::new(false));
+    let wait_exit_status = exit_status.clone();
+    let wait_handle = tokio::spawn(async move {
+        let code = match child.wait().await {
+            Ok(status) => status
+                .code()
+                .or_else(|| status.signal().map(|signal| 128 + signal))
+                .unwrap_or(-1),
+            Err(_) => -1,
+        };
+        wait_exit_status.store(true, std::sync::atomic::Ordering::SeqCst);
+        let _ = exit_tx.send(code);
+    });
+
+    let (session, initial_output_rx) = ExecCommandSession::new(
+        writer_tx,
+        output_tx,
+        killer,
+        reader_handle,
+        writer_handle,
+        wait_handle,
+        exit_status,
+    );
+    Ok((session, initial_output_rx, exit_rx))
+}
+
+/// Kills a PTY child along with everything it started. The child leads its
+/// own session, so its pid is also its process group id.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+struct ProcessGroupKiller {
+    pid: u32,
+}
+
+#[cfg(unix)]
+impl portable_pty::ChildKiller for ProcessGroupKiller {
+    fn kill(&mut self) -> std::io::Result<()> {
+        if unsafe { libc::killpg(self.pid as libc::pid_t, libc::SIGKILL) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn clone_killer(&self) -> Box<dyn portable_pty::ChildKiller + Send + Sync> {
+        Box::new(*self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -453,6 +629,71 @@ PY"#
         );
     }
 
+    /// A command spawned on a pseudo-terminal by the shell tool sees a TTY
+    /// and can be fed input with [`SessionManager::handle_write_stdin_request()`].
+    #[cfg(unix)]
+    #[allow(clippy::print_stderr)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pty_child_reads_input_from_the_terminal() {
+        use crate::exec::ExecParams;
+        use crate::exec::SandboxType;
+        use crate::exec::spawn_exec_in_pty;
+        use crate::exec_command::exec_command_params::WriteStdinParams;
+        use crate::protocol::SandboxPolicy;
+
+        let cwd = std::env::temp_dir();
+        let params = ExecParams {
+            command: vec![
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                "test -t 0 && echo is-a-tty; read line; echo got-$line".to_string(),
+            ],
+            cwd: cwd.clone(),
+            timeout_ms: None,
+            env: HashMap::new(),
+            with_escalated_permissions: None,
+            justification: None,
+            resource_limits: Default::default(),
+        };
+        let (child, master) = match spawn_exec_in_pty(
+            params,
+            SandboxType::None,
+            &SandboxPolicy::DangerFullAccess,
+            &cwd,
+            &None,
+        )
+        .await
+        {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                eprintln!("skipping test due to restricted PTY: {e}");
+                return;
+            }
+        };
+
+        let session_manager = SessionManager::default();
+        let first = session_manager
+            .handle_pty_child(child, master, 1_000, 1_000)
+            .await
+            .expect("pty child should be registered");
+        assert!(first.output.contains("is-a-tty"), "{first:?}");
+        let session_id = match first.exit_status {
+            ExitStatus::Ongoing(id) => id,
+            _ => panic!("expected the command to wait for input"),
+        };
+
+        let second = session_manager
+            .handle_write_stdin_request(WriteStdinParams {
+                session_id,
+                chars: "hello\n".to_string(),
+                yield_time_ms: 2_000,
+                max_output_tokens: 1_000,
+            })
+            .await
+            .expect("write stdin should succeed");
+        assert!(second.output.contains("got-hello"), "{second:?}");
+    }
+
     #[cfg(unix)]
     fn extract_monotonic_numbers(s: &str) -> Vec<i64> {
         s.lines()
@@ -4,6 +4,20 @@ use crate::config_types::ShellEnvironmentPolicyInherit;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+/// Variables dropped unless `ignore_default_excludes` is set, because they
+/// commonly hold credentials.
+const DEFAULT_EXCLUDES: &[&str] = &[
+    "*KEY*",
+    "*SECRET*",
+    "*TOKEN*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*CREDENTIAL*",
+    "AWS_*",
+    "AZURE_CLIENT_*",
+    "DATABASE_URL",
+];
+
 /// Construct an environment map based on the rules in the specified policy. The
 /// resulting map can be passed directly to `Command::envs()` after calling
 /// `env_clear()` to ensure no unintended variables are leaked to the spawned
@@ -42,17 +56,17 @@ where
 
     // Step 2 – Apply the default exclude if not disabled.
     if !policy.ignore_default_excludes {
-        let default_excludes = vec![
-            EnvironmentVariablePattern::new_case_insensitive("*KEY*"),
-            EnvironmentVariablePattern::new_case_insensitive("*SECRET*"),
-            EnvironmentVariablePattern::new_case_insensitive("*TOKEN*"),
-        ];
-        env_map.retain(|k, _| !matches_any(k, &default_excludes));
+        let default_excludes: Vec<EnvironmentVariablePattern> = DEFAULT_EXCLUDES
+            .iter()
+            .map(|pattern| EnvironmentVariablePattern::new_case_insensitive(pattern))
+            .collect();
+        env_map
+            .retain(|k, _| !matches_any(k, &default_excludes) || matches_any(k, &policy.include));
     }
 
     // Step 3 – Apply custom excludes.
     if !policy.exclude.is_empty() {
-        env_map.retain(|k, _| !matches_any(k, &policy.exclude));
+        env_map.retain(|k, _| !matches_any(k, &policy.exclude) || matches_any(k, &policy.include));
     }
 
     // Step 4 – Apply user-provided overrides.
@@ -191,4 +205,34 @@ mod tests {
         };
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_include_keeps_excluded_vars() {
+        let vars = make_vars(&[
+            ("PATH", "/usr/bin"),
+            ("AWS_ACCESS_KEY_ID", "AKIA"),
+            ("AWS_REGION", "us-east-1"),
+            ("DB_PASSWORD", "hunter2"),
+            ("GITHUB_TOKEN", "ghp"),
+            ("NPM_CONFIG_CACHE", "/tmp/npm"),
+        ]);
+
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::All,
+            exclude: vec![EnvironmentVariablePattern::new_case_insensitive("NPM_*")],
+            include: vec![
+                EnvironmentVariablePattern::new_case_insensitive("AWS_REGION"),
+                EnvironmentVariablePattern::new_case_insensitive("GITHUB_TOKEN"),
+            ],
+            ..Default::default()
+        };
+
+        let result = populate_env(vars, &policy);
+        let expected: HashMap<String, String> = hashmap! {
+            "PATH".to_string() => "/usr/bin".to_string(),
+            "AWS_REGION".to_string() => "us-east-1".to_string(),
+            "GITHUB_TOKEN".to_string() => "ghp".to_string(),
+        };
+        assert_eq!(result, expected);
+    }
 }
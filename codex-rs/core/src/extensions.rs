@@ -0,0 +1,380 @@
+//! Extensions: packages installed under `$CODEX_HOME/extensions/<name>/`
+//! that contribute to every session, each described by an `extension.toml`
+//! manifest:
+//!
+//! ```toml
+//! name = "jira"
+//! version = "1.2.0"
+//! description = "Search and update Jira issues"
+//! min_codex_version = "0.40.0"
+//!
+//! # An MCP server whose tools are offered as `ext_jira__<tool>`.
+//! [server]
+//! command = "./bin/jira-mcp" # relative to the extension's directory
+//! args = ["--stdio"]
+//! ```
+//!
+//! Markdown files in the extension's `prompts/` directory are custom prompts,
+//! available as `/jira:<file name>` slash commands.
+//!
+//! Extensions are enabled unless `[extensions.<name>] enabled = false` is set
+//! in `config.toml` (`codex extensions disable <name>`).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::custom_prompts::CustomPrompt;
+use serde::Deserialize;
+use toml_edit::DocumentMut;
+
+use crate::config::CONFIG_TOML_FILE;
+use crate::config_types::ExtensionSettings;
+use crate::config_types::McpServerConfig;
+use crate::config_types::McpServerTransportConfig;
+use crate::custom_prompts::discover_prompts_in;
+
+pub const EXTENSION_MANIFEST_FILE: &str = "extension.toml";
+
+/// Prefix of the MCP server an extension contributes, which namespaces its
+/// tools.
+const EXTENSION_SERVER_PREFIX: &str = "ext_";
+
+/// Separates the extension name from the prompt name in slash commands.
+const EXTENSION_PROMPT_DELIMITER: &str = ":";
+
+pub fn extensions_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join("extensions")
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ExtensionManifest {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    /// Oldest Codex version the extension works with.
+    pub min_codex_version: Option<String>,
+    /// MCP server providing the extension's tools.
+    pub server: Option<McpServerConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtensionStatus {
+    Enabled,
+    Disabled,
+    /// The extension is enabled but cannot be used, e.g. because its
+    /// manifest is invalid or it needs a newer Codex.
+    Unusable(String),
+}
+
+/// An extension found under `$CODEX_HOME/extensions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extension {
+    /// The name of the extension's directory.
+    pub name: String,
+    pub dir: PathBuf,
+    /// `None` when the manifest could not be read.
+    pub manifest: Option<ExtensionManifest>,
+    pub status: ExtensionStatus,
+}
+
+impl Extension {
+    pub fn is_enabled(&self) -> bool {
+        self.status == ExtensionStatus::Enabled
+    }
+
+    /// The name the extension's MCP server is registered under.
+    pub fn server_name(&self) -> String {
+        format!("{EXTENSION_SERVER_PREFIX}{}", self.name)
+    }
+
+    /// The extension's MCP server, with a relative command resolved against
+    /// the extension's directory.
+    pub fn server(&self) -> Option<McpServerConfig> {
+        let mut server = self.manifest.as_ref()?.server.clone()?;
+        if let McpServerTransportConfig::Stdio { command, .. } = &mut server.transport
+            && (command.starts_with("./") || command.starts_with("../"))
+        {
+            *command = self.dir.join(&*command).to_string_lossy().into_owned();
+        }
+        Some(server)
+    }
+
+    fn prompts_dir(&self) -> PathBuf {
+        self.dir.join("prompts")
+    }
+}
+
+/// Finds the extensions under `$CODEX_HOME/extensions`, sorted by name, and
+/// checks that they can be used with this version of Codex.
+pub fn discover_extensions(
+    codex_home: &Path,
+    settings: &HashMap<String, ExtensionSettings>,
+) -> Vec<Extension> {
+    let Ok(entries) = std::fs::read_dir(extensions_dir(codex_home)) else {
+        return Vec::new();
+    };
+    let mut extensions: Vec<Extension> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| {
+            let dir = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let enabled = settings.get(&name).is_none_or(|settings| settings.enabled);
+            load_extension(name, dir, enabled)
+        })
+        .collect();
+    extensions.sort_by(|a, b| a.name.cmp(&b.name));
+    extensions
+}
+
+fn load_extension(name: String, dir: PathBuf, enabled: bool) -> Extension {
+    let manifest = std::fs::read_to_string(dir.join(EXTENSION_MANIFEST_FILE))
+        .map_err(|e| format!("cannot read {EXTENSION_MANIFEST_FILE}: {e}"))
+        .and_then(|contents| {
+            toml::from_str::<ExtensionManifest>(&contents)
+                .map_err(|e| format!("invalid {EXTENSION_MANIFEST_FILE}: {e}"))
+        });
+    let (manifest, problem) = match manifest {
+        Ok(manifest) => {
+            let problem = check_manifest(&name, &manifest, env!("CARGO_PKG_VERSION")).err();
+            (Some(manifest), problem)
+        }
+        Err(problem) => (None, Some(problem)),
+    };
+    let status = match problem {
+        _ if !enabled => ExtensionStatus::Disabled,
+        Some(problem) => ExtensionStatus::Unusable(problem),
+        None => ExtensionStatus::Enabled,
+    };
+    Extension {
+        name,
+        dir,
+        manifest,
+        status,
+    }
+}
+
+fn check_manifest(
+    dir_name: &str,
+    manifest: &ExtensionManifest,
+    codex_version: &str,
+) -> Result<(), String> {
+    if manifest.name != dir_name {
+        return Err(format!(
+            "its manifest names it `{}`, but it is installed as `{dir_name}`",
+            manifest.name
+        ));
+    }
+    if !dir_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("names may only contain letters, digits, `-` and `_`".to_string());
+    }
+    if let Some(required) = &manifest.min_codex_version {
+        let Some(required_parts) = parse_version(required) else {
+            return Err(format!("invalid min_codex_version `{required}`"));
+        };
+        // Development builds are versioned 0.0.0 and accept every extension.
+        if let Some(current) = parse_version(codex_version)
+            && current != [0, 0, 0]
+            && current < required_parts
+        {
+            return Err(format!(
+                "requires Codex {required} or newer (this is {codex_version})"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `major.minor.patch`, ignoring any pre-release or build suffix.
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let version = [
+        parts.next()??,
+        parts.next().unwrap_or(Some(0))?,
+        parts.next().unwrap_or(Some(0))?,
+    ];
+    parts.next().is_none().then_some(version)
+}
+
+/// The MCP servers of the enabled extensions, by server name.
+pub fn extension_servers(extensions: &[Extension]) -> Vec<(String, McpServerConfig)> {
+    extensions
+        .iter()
+        .filter(|extension| extension.is_enabled())
+        .filter_map(|extension| Some((extension.server_name(), extension.server()?)))
+        .collect()
+}
+
+/// The custom prompts of the enabled extensions, named `<extension>:<prompt>`.
+pub async fn extension_prompts(extensions: &[Extension]) -> Vec<CustomPrompt> {
+    let mut prompts = Vec::new();
+    for extension in extensions.iter().filter(|extension| extension.is_enabled()) {
+        for mut prompt in discover_prompts_in(&extension.prompts_dir()).await {
+            prompt.name = format!(
+                "{}{EXTENSION_PROMPT_DELIMITER}{}",
+                extension.name, prompt.name
+            );
+            prompts.push(prompt);
+        }
+    }
+    prompts
+}
+
+/// Enables or disables the extension `name` in `config.toml`.
+pub fn set_extension_enabled(codex_home: &Path, name: &str, enabled: bool) -> std::io::Result<()> {
+    let config_path = codex_home.join(CONFIG_TOML_FILE);
+    let mut doc = match std::fs::read_to_string(&config_path) {
+        Ok(contents) => contents
+            .parse::<DocumentMut>()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+        Err(e) => return Err(e),
+    };
+
+    if enabled {
+        // Enabled is the default: drop the setting rather than spell it out.
+        if let Some(extensions) = doc
+            .get_mut("extensions")
+            .and_then(toml_edit::Item::as_table_mut)
+        {
+            extensions.remove(name);
+            if extensions.is_empty() {
+                doc.as_table_mut().remove("extensions");
+            }
+        }
+    } else {
+        if !doc.contains_key("extensions") {
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            doc["extensions"] = toml_edit::Item::Table(table);
+        }
+        if !doc["extensions"]
+            .get(name)
+            .is_some_and(toml_edit::Item::is_table)
+        {
+            doc["extensions"][name] = toml_edit::Item::Table(toml_edit::Table::new());
+        }
+        doc["extensions"][name]["enabled"] = toml_edit::value(false);
+    }
+
+    std::fs::create_dir_all(codex_home)?;
+    let tmp_file = tempfile::NamedTempFile::new_in(codex_home)?;
+    std::fs::write(tmp_file.path(), doc.to_string())?;
+    tmp_file.persist(config_path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn install(codex_home: &Path, name: &str, manifest: &str) -> PathBuf {
+        let dir = extensions_dir(codex_home).join(name);
+        std::fs::create_dir_all(dir.join("prompts")).expect("mkdir");
+        std::fs::write(dir.join(EXTENSION_MANIFEST_FILE), manifest).expect("write manifest");
+        dir
+    }
+
+    #[tokio::test]
+    async fn discovers_extensions_and_their_contributions() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let jira = install(
+            codex_home.path(),
+            "jira",
+            r#"
+name = "jira"
+version = "1.2.0"
+
+[server]
+command = "./bin/jira-mcp"
+args = ["--stdio"]
+"#,
+        );
+        std::fs::write(jira.join("prompts/triage.md"), "Triage $1").expect("write prompt");
+        install(
+            codex_home.path(),
+            "off",
+            "name = \"off\"\nversion = \"1.0.0\"\n[server]\ncommand = \"off-mcp\"\n",
+        );
+        install(codex_home.path(), "broken", "name = ");
+
+        let settings = HashMap::from([("off".to_string(), ExtensionSettings { enabled: false })]);
+        let extensions = discover_extensions(codex_home.path(), &settings);
+        let statuses: Vec<(&str, bool)> = extensions
+            .iter()
+            .map(|extension| (extension.name.as_str(), extension.is_enabled()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![("broken", false), ("jira", true), ("off", false)]
+        );
+        assert!(matches!(extensions[0].status, ExtensionStatus::Unusable(_)));
+
+        let servers = extension_servers(&extensions);
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].0, "ext_jira");
+        assert_eq!(
+            servers[0].1.transport,
+            McpServerTransportConfig::Stdio {
+                command: jira.join("./bin/jira-mcp").to_string_lossy().into_owned(),
+                args: vec!["--stdio".to_string()],
+                env: None,
+            }
+        );
+
+        let prompts = extension_prompts(&extensions).await;
+        let names: Vec<&str> = prompts.iter().map(|prompt| prompt.name.as_str()).collect();
+        assert_eq!(names, vec!["jira:triage"]);
+    }
+
+    #[test]
+    fn manifests_are_checked_against_the_codex_version() {
+        let manifest = |name: &str, min_codex_version: Option<&str>| ExtensionManifest {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            description: None,
+            min_codex_version: min_codex_version.map(str::to_string),
+            server: None,
+        };
+
+        assert_eq!(
+            check_manifest("a", &manifest("a", Some("0.40")), "0.41.2"),
+            Ok(())
+        );
+        assert_eq!(
+            check_manifest("a", &manifest("a", Some("0.42.0")), "0.41.2"),
+            Err("requires Codex 0.42.0 or newer (this is 0.41.2)".to_string())
+        );
+        assert_eq!(
+            check_manifest("a", &manifest("a", Some("9.0.0")), "0.0.0"),
+            Ok(())
+        );
+        assert!(check_manifest("a", &manifest("a", Some("latest")), "0.41.2").is_err());
+        assert!(check_manifest("a", &manifest("b", None), "0.41.2").is_err());
+    }
+
+    #[test]
+    fn enabling_and_disabling_edits_config_toml() {
+        let codex_home = TempDir::new().expect("tempdir");
+        std::fs::write(codex_home.path().join(CONFIG_TOML_FILE), "model = \"o3\"\n")
+            .expect("write config");
+
+        set_extension_enabled(codex_home.path(), "jira", false).expect("disable");
+        let contents =
+            std::fs::read_to_string(codex_home.path().join(CONFIG_TOML_FILE)).expect("read");
+        assert!(contents.starts_with("model = \"o3\"\n"));
+        assert!(contents.contains("[extensions.jira]\nenabled = false\n"));
+
+        set_extension_enabled(codex_home.path(), "jira", true).expect("enable");
+        let contents =
+            std::fs::read_to_string(codex_home.path().join(CONFIG_TOML_FILE)).expect("read");
+        assert_eq!(contents, "model = \"o3\"\n");
+    }
+}
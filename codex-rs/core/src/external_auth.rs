@@ -0,0 +1,178 @@
+//! Bearer tokens obtained from a user-configured command.
+//!
+//! When `external_auth_command` is set, Codex runs that command to obtain the
+//! token sent with model requests instead of using `auth.json`. This lets
+//! organizations that front the API with their own OAuth issue short-lived
+//! tokens rather than distributing static API keys.
+//!
+//! The command must print either a bare token, or a JSON object such as
+//! `{"token": "...", "expires_in": 3600}` where `expires_in` is the token's
+//! lifetime in seconds. The token is cached and the command is run again
+//! shortly before it expires, or when a request is rejected as unauthorized.
+
+use std::fmt;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Lifetime assumed for tokens printed without an `expires_in`.
+const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(300);
+
+/// Tokens are refreshed this long before they expire so that a request is
+/// never sent with a token that lapses in flight.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Upper bound on how long the command may run, e.g. while it waits for an
+/// interactive SSO login in the browser.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub(crate) struct ExternalAuthProvider {
+    command: Vec<String>,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+struct CachedToken {
+    token: String,
+    refresh_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenOutput {
+    #[serde(alias = "access_token")]
+    token: String,
+    expires_in: Option<u64>,
+}
+
+impl fmt::Debug for ExternalAuthProvider {
+    /// Never prints the cached token.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalAuthProvider")
+            .field("command", &self.command)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExternalAuthProvider {
+    pub(crate) fn new(command: Vec<String>) -> Self {
+        Self {
+            command,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The current token, running the command if there is no cached token or
+    /// it is about to expire.
+    pub(crate) async fn token(&self) -> std::io::Result<String> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref()
+            && Instant::now() < token.refresh_at
+        {
+            return Ok(token.token.clone());
+        }
+
+        let (token, ttl) = self.run_command().await?;
+        *cached = Some(CachedToken {
+            token: token.clone(),
+            refresh_at: Instant::now() + ttl.saturating_sub(REFRESH_MARGIN),
+        });
+        Ok(token)
+    }
+
+    /// Drop the cached token so the next request runs the command again.
+    pub(crate) async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+
+    async fn run_command(&self) -> std::io::Result<(String, Duration)> {
+        let Some((program, args)) = self.command.split_first() else {
+            return Err(std::io::Error::other("external_auth_command is empty"));
+        };
+        let output = tokio::time::timeout(
+            COMMAND_TIMEOUT,
+            Command::new(program)
+                .args(args)
+                .stdin(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .map_err(|_| {
+            std::io::Error::other(format!(
+                "external_auth_command `{program}` timed out after {}s",
+                COMMAND_TIMEOUT.as_secs()
+            ))
+        })??;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(std::io::Error::other(format!(
+                "external_auth_command `{program}` failed ({}): {}",
+                output.status,
+                stderr.trim()
+            )));
+        }
+        parse_token_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+fn parse_token_output(stdout: &str) -> std::io::Result<(String, Duration)> {
+    let stdout = stdout.trim();
+    let (token, ttl) = if stdout.starts_with('{') {
+        let output: TokenOutput = serde_json::from_str(stdout)?;
+        let ttl = output
+            .expires_in
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TOKEN_TTL);
+        (output.token, ttl)
+    } else {
+        (stdout.to_string(), DEFAULT_TOKEN_TTL)
+    };
+
+    if token.is_empty() || token.contains(char::is_whitespace) {
+        return Err(std::io::Error::other(
+            "external_auth_command must print a single token",
+        ));
+    }
+    Ok((token, ttl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_bare_and_json_tokens() {
+        assert_eq!(
+            parse_token_output("tok-123\n").unwrap(),
+            ("tok-123".to_string(), DEFAULT_TOKEN_TTL)
+        );
+        assert_eq!(
+            parse_token_output(r#"{"access_token": "tok-456", "expires_in": 3600}"#).unwrap(),
+            ("tok-456".to_string(), Duration::from_secs(3600))
+        );
+        assert!(parse_token_output("").is_err());
+        assert!(parse_token_output("two words").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn caches_token_until_invalidated() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("count");
+        // Prints a different token each time it runs.
+        let script = format!(
+            "echo x >> {path}; echo \"tok-$(wc -l < {path} | tr -d ' ')\"",
+            path = counter.display()
+        );
+        let provider = ExternalAuthProvider::new(vec!["sh".to_string(), "-c".to_string(), script]);
+
+        assert_eq!(provider.token().await.unwrap(), "tok-1");
+        assert_eq!(provider.token().await.unwrap(), "tok-1");
+        provider.invalidate().await;
+        assert_eq!(provider.token().await.unwrap(), "tok-2");
+    }
+}
@@ -0,0 +1,107 @@
+//! The `fetch_url` tool: downloads a page and returns it as readable text so
+//! the model can read documentation without the user pasting it in.
+//!
+//! Fetching shares its limits, domain policy and per-session cache with URL
+//! attachments. On top of that, the sandbox network policy applies: without
+//! network access only hosts on the `network_allowlist` can be fetched, and
+//! every redirect is checked before it is followed.
+
+use std::collections::BTreeMap;
+
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::network_proxy::allowlist_allows_host;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const FETCH_URL_TOOL_NAME: &str = "fetch_url";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FetchUrlArgs {
+    pub(crate) url: String,
+}
+
+pub(crate) fn create_fetch_url_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "url".to_string(),
+        JsonSchema::String {
+            description: Some("The http or https URL to fetch".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: FETCH_URL_TOOL_NAME.to_string(),
+        description: "Downloads a web page or raw text file and returns its content, with HTML \
+                      reduced to markdown. Long pages are truncated. Use it to read documentation \
+                      or other pages the user refers to."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["url".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+/// Checks that the sandbox network policy lets commands reach the host of
+/// `url`, so the tool is no way around it.
+pub(crate) fn check_sandbox_network(
+    url: &str,
+    sandbox_policy: &SandboxPolicy,
+) -> Result<(), String> {
+    if sandbox_policy.has_full_network_access() {
+        return Ok(());
+    }
+    let url = Url::parse(url).map_err(|e| format!("invalid URL: {e}"))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "URL has no host".to_string())?;
+    if allowlist_allows_host(sandbox_policy.network_allowlist(), host) {
+        return Ok(());
+    }
+    Err(format!(
+        "the sandbox policy does not allow network access to {host}; ask the user to add it to the network_allowlist or to allow network access"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn workspace_write(network_access: bool, network_allowlist: &[&str]) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: Vec::new(),
+            network_access,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            network_allowlist: network_allowlist.iter().map(|e| (*e).to_string()).collect(),
+            deny: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sandbox_network_policy_limits_fetches() {
+        let docs = "https://docs.rs/tokio/latest/tokio/";
+
+        assert_eq!(
+            check_sandbox_network(docs, &SandboxPolicy::DangerFullAccess),
+            Ok(())
+        );
+        assert_eq!(
+            check_sandbox_network(docs, &workspace_write(true, &[])),
+            Ok(())
+        );
+        assert_eq!(
+            check_sandbox_network(docs, &workspace_write(false, &["docs.rs"])),
+            Ok(())
+        );
+        assert!(check_sandbox_network(docs, &workspace_write(false, &["crates.io"])).is_err());
+        assert!(check_sandbox_network(docs, &SandboxPolicy::ReadOnly).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Attaching local files, or piped stdin, to a turn (`codex exec --attach`).
+//!
+//! Images are sent as images. Any other content is added to the prompt
+//! inside a `<file_attachment>` block: text as is, and binary data, which
+//! cannot be passed as text, base64-encoded.
+
+use base64::Engine;
+use codex_protocol::protocol::InputItem;
+
+/// Opening tag of the text item that carries an attachment. Used to keep
+/// attachments out of the user message shown in the UI.
+pub(crate) const FILE_ATTACHMENT_OPEN_TAG: &str = "<file_attachment";
+
+/// Content beyond this many bytes is left out of a text or binary attachment.
+pub const MAX_FILE_ATTACHMENT_BYTES: usize = 256 * 1024;
+
+/// The input item attaching `bytes`, the contents of the file `name`.
+pub fn file_attachment_item(name: &str, bytes: &[u8]) -> InputItem {
+    if let Some(mime) = image_mime_type(bytes) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        return InputItem::Image {
+            image_url: format!("data:{mime};base64,{encoded}"),
+        };
+    }
+
+    let truncated = bytes.len() > MAX_FILE_ATTACHMENT_BYTES;
+    let mut open = format!(
+        "{FILE_ATTACHMENT_OPEN_TAG} name=\"{}\"",
+        name.replace('"', "&quot;")
+    );
+    let body = match std::str::from_utf8(bytes) {
+        Ok(text) if !text.contains('\0') => {
+            let mut end = text.len().min(MAX_FILE_ATTACHMENT_BYTES);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text[..end].to_string()
+        }
+        _ => {
+            open.push_str(&format!(" encoding=\"base64\" bytes=\"{}\"", bytes.len()));
+            let kept = &bytes[..bytes.len().min(MAX_FILE_ATTACHMENT_BYTES)];
+            base64::engine::general_purpose::STANDARD.encode(kept)
+        }
+    };
+    if truncated {
+        open.push_str(" truncated=\"true\"");
+    }
+    InputItem::Text {
+        text: format!("{open}>\n{}\n</file_attachment>", body.trim_end()),
+    }
+}
+
+/// The MIME type of `bytes` if they start like an image the models accept.
+fn image_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn text_of(item: InputItem) -> String {
+        match item {
+            InputItem::Text { text } => text,
+            other => panic!("expected text, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn text_is_attached_as_is() {
+        let text = text_of(file_attachment_item("stdin", b"diff --git a/x b/x\n"));
+        assert_eq!(
+            text,
+            "<file_attachment name=\"stdin\">\ndiff --git a/x b/x\n</file_attachment>"
+        );
+    }
+
+    #[test]
+    fn binary_data_is_base64_encoded() {
+        let text = text_of(file_attachment_item("data.bin", &[0, 159, 146, 150]));
+        assert_eq!(
+            text,
+            "<file_attachment name=\"data.bin\" encoding=\"base64\" bytes=\"4\">\nAJ+Slg==\n</file_attachment>"
+        );
+    }
+
+    #[test]
+    fn long_text_is_truncated_on_a_char_boundary() {
+        let bytes = "é".repeat(MAX_FILE_ATTACHMENT_BYTES).into_bytes();
+        let text = text_of(file_attachment_item("long.txt", &bytes));
+        assert!(text.starts_with("<file_attachment name=\"long.txt\" truncated=\"true\">\n"));
+        assert!(text.len() < MAX_FILE_ATTACHMENT_BYTES + 100);
+    }
+
+    #[test]
+    fn images_are_sent_as_images() {
+        let item = file_attachment_item("shot.png", b"\x89PNG\r\n\x1a\n....");
+        let InputItem::Image { image_url } = item else {
+            panic!("expected an image");
+        };
+        assert!(image_url.starts_with("data:image/png;base64,"));
+    }
+}
@@ -0,0 +1,542 @@
+//! Structured file tools: `read_file`, `list_dir` and `write_file`.
+//!
+//! They spare the model from spawning `cat`, `ls` or `sed`, which behave
+//! differently across platforms and may not be available in a restricted
+//! sandbox. Reads and listings run in-process, outside the sandbox, so
+//! [`check_readable`] confines them first: unless the policy is
+//! `danger-full-access`, they only reach the working directory and the
+//! writable roots, after resolving symlinks, and never a path on the `deny`
+//! list. Writes are turned into an `apply_patch` call that adds the file, so
+//! they go through the same sandbox checks, approvals and turn diff as any
+//! other edit.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const READ_FILE_TOOL_NAME: &str = "read_file";
+pub(crate) const LIST_DIR_TOOL_NAME: &str = "list_dir";
+pub(crate) const WRITE_FILE_TOOL_NAME: &str = "write_file";
+
+/// Files larger than this are not read at all.
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Lines returned by `read_file` when the model does not ask for a limit.
+const DEFAULT_READ_LINES: usize = 2000;
+
+/// Upper bound on the text `read_file` returns in one call.
+const MAX_READ_OUTPUT_BYTES: usize = 100 * 1024;
+
+/// Lines longer than this are cut short.
+const MAX_LINE_CHARS: usize = 2000;
+
+const DEFAULT_LIST_DEPTH: usize = 1;
+const MAX_LIST_DEPTH: usize = 5;
+const MAX_LIST_ENTRIES: usize = 1000;
+
+/// Directories `list_dir` shows but does not descend into.
+const OPAQUE_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadFileArgs {
+    pub(crate) path: String,
+    /// 1-based line to start at.
+    #[serde(default)]
+    pub(crate) offset: Option<usize>,
+    #[serde(default)]
+    pub(crate) limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListDirArgs {
+    pub(crate) path: String,
+    #[serde(default)]
+    pub(crate) depth: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct WriteFileArgs {
+    pub(crate) path: String,
+    pub(crate) content: String,
+}
+
+pub(crate) fn create_file_tools() -> Vec<OpenAiTool> {
+    let string_property = |description: &str| JsonSchema::String {
+        description: Some(description.to_string()),
+    };
+
+    let mut read_properties = BTreeMap::new();
+    read_properties.insert(
+        "path".to_string(),
+        string_property("Path of the file, absolute or relative to the working directory"),
+    );
+    read_properties.insert(
+        "offset".to_string(),
+        JsonSchema::Number {
+            description: Some("1-based line number to start reading at (default: 1)".to_string()),
+        },
+    );
+    read_properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Maximum number of lines to read (default: {DEFAULT_READ_LINES})"
+            )),
+        },
+    );
+
+    let mut list_properties = BTreeMap::new();
+    list_properties.insert(
+        "path".to_string(),
+        string_property("Path of the directory, absolute or relative to the working directory"),
+    );
+    list_properties.insert(
+        "depth".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "How many levels of subdirectories to list (default: {DEFAULT_LIST_DEPTH}, at most {MAX_LIST_DEPTH})"
+            )),
+        },
+    );
+
+    let mut write_properties = BTreeMap::new();
+    write_properties.insert(
+        "path".to_string(),
+        string_property("Path of the file, absolute or relative to the working directory"),
+    );
+    write_properties.insert(
+        "content".to_string(),
+        string_property("The complete new content of the file"),
+    );
+
+    let tool = |name: &str, description: &str, properties, required: &[&str]| {
+        OpenAiTool::Function(ResponsesApiTool {
+            name: name.to_string(),
+            description: description.to_string(),
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties,
+                required: Some(required.iter().map(|name| (*name).to_string()).collect()),
+                additional_properties: Some(false),
+            },
+        })
+    };
+    vec![
+        tool(
+            READ_FILE_TOOL_NAME,
+            "Reads a text file and returns its lines prefixed with their line numbers. Detects \
+             UTF-8, UTF-16 and Latin-1 encodings. Use `offset` and `limit` to page through \
+             long files.",
+            read_properties,
+            &["path"],
+        ),
+        tool(
+            LIST_DIR_TOOL_NAME,
+            "Lists the entries of a directory, with subdirectories marked by a trailing `/` \
+             and file sizes in bytes.",
+            list_properties,
+            &["path"],
+        ),
+        tool(
+            WRITE_FILE_TOOL_NAME,
+            "Creates a file or replaces its content, creating parent directories as needed. \
+             Subject to the same sandbox and approval rules as apply_patch. Prefer apply_patch \
+             for small edits to existing files.",
+            write_properties,
+            &["path", "content"],
+        ),
+    ]
+}
+
+/// Resolves `path`, following symlinks, and checks that the file tools may
+/// read it under `sandbox_policy`: it must lie in `cwd` or a writable root and
+/// not be protected by the `deny` list. Returns the resolved path.
+pub(crate) fn check_readable(
+    path: &Path,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Result<PathBuf, String> {
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    if matches!(sandbox_policy, SandboxPolicy::DangerFullAccess) {
+        return Ok(resolved);
+    }
+    let mut roots = vec![cwd.to_path_buf()];
+    roots.extend(
+        sandbox_policy
+            .get_writable_roots_with_cwd(cwd)
+            .into_iter()
+            .map(|writable_root| writable_root.root),
+    );
+    let inside_roots = roots.iter().any(|root| {
+        root.canonicalize()
+            .is_ok_and(|root| resolved.starts_with(root))
+    });
+    if !inside_roots {
+        return Err(if resolved == path {
+            format!(
+                "{} is outside the working directory and the sandbox's writable roots",
+                path.display()
+            )
+        } else {
+            format!(
+                "{} resolves to {}, outside the working directory and the sandbox's writable roots",
+                path.display(),
+                resolved.display()
+            )
+        });
+    }
+    if sandbox_policy.is_path_denied(path, cwd) || sandbox_policy.is_path_denied(&resolved, cwd) {
+        return Err(format!(
+            "{} is protected by the sandbox's deny list",
+            path.display()
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Reads the lines of `path` that `args` asks for, numbered like `cat -n`.
+pub(crate) fn read_file(path: &Path, args: &ReadFileArgs) -> Result<String, String> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    if metadata.is_dir() {
+        return Err(format!(
+            "{} is a directory; use {LIST_DIR_TOOL_NAME} instead",
+            path.display()
+        ));
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(format!(
+            "{} is {} bytes, more than the {MAX_FILE_BYTES} bytes read_file reads",
+            path.display(),
+            metadata.len()
+        ));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let text = decode_text(&bytes)
+        .ok_or_else(|| format!("{} looks like a binary file", path.display()))?;
+
+    let offset = args.offset.unwrap_or(1).max(1);
+    let limit = args.limit.unwrap_or(DEFAULT_READ_LINES).max(1);
+    let total_lines = text.lines().count();
+    if offset > total_lines.max(1) {
+        return Err(format!(
+            "offset {offset} is past the end of the file ({total_lines} lines)"
+        ));
+    }
+
+    let mut output = String::new();
+    let mut next_line = offset;
+    for (index, line) in text.lines().enumerate().skip(offset - 1).take(limit) {
+        let line = match line.char_indices().nth(MAX_LINE_CHARS) {
+            Some((cut, _)) => format!("{}…", &line[..cut]),
+            None => line.to_string(),
+        };
+        let numbered = format!("{:>6}\t{line}\n", index + 1);
+        if output.len() + numbered.len() > MAX_READ_OUTPUT_BYTES && !output.is_empty() {
+            break;
+        }
+        output.push_str(&numbered);
+        next_line = index + 2;
+    }
+    if next_line <= total_lines {
+        let _ = write!(
+            output,
+            "[{} more lines; continue with offset {next_line}]",
+            total_lines + 1 - next_line
+        );
+    }
+    Ok(output)
+}
+
+/// Lists `path` down to `depth` levels as an indented tree.
+pub(crate) fn list_dir(path: &Path, args: &ListDirArgs) -> Result<String, String> {
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    let depth = args
+        .depth
+        .unwrap_or(DEFAULT_LIST_DEPTH)
+        .clamp(1, MAX_LIST_DEPTH);
+    let mut output = format!("{}/\n", path.display());
+    let mut remaining = MAX_LIST_ENTRIES;
+    list_entries(path, 1, depth, &mut remaining, &mut output)?;
+    if remaining == 0 {
+        let _ = writeln!(
+            output,
+            "[stopped after {MAX_LIST_ENTRIES} entries; list a subdirectory for more]"
+        );
+    }
+    Ok(output)
+}
+
+fn list_entries(
+    dir: &Path,
+    level: usize,
+    depth: usize,
+    remaining: &mut usize,
+    output: &mut String,
+) -> Result<(), String> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| format!("cannot list {}: {e}", dir.display()))?
+        .filter_map(Result::ok)
+        .collect();
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    let indent = "  ".repeat(level);
+    for entry in entries {
+        if *remaining == 0 {
+            return Ok(());
+        }
+        *remaining -= 1;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            let _ = writeln!(output, "{indent}{name}/");
+            if level < depth && !OPAQUE_DIRS.contains(&name.as_str()) {
+                list_entries(&entry.path(), level + 1, depth, remaining, output)?;
+            }
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(entry.path())
+                .map(|target| target.display().to_string())
+                .unwrap_or_default();
+            let _ = writeln!(output, "{indent}{name} -> {target}");
+        } else {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or_default();
+            let _ = writeln!(output, "{indent}{name} ({size} bytes)");
+        }
+    }
+    Ok(())
+}
+
+/// The `apply_patch` input that writes `args.content` to `args.path`. Files
+/// that are not UTF-8 text are refused, since the patch would re-encode them.
+pub(crate) fn write_file_patch(path: &Path, args: &WriteFileArgs) -> Result<String, String> {
+    if args.path.trim().is_empty() || args.path.contains(['\n', '\r']) {
+        return Err("`path` must be a single-line file path".to_string());
+    }
+    if path.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    if let Ok(existing) = std::fs::read(path)
+        && std::str::from_utf8(&existing).is_err()
+    {
+        return Err(format!(
+            "{} is not UTF-8 text; write_file only writes UTF-8",
+            path.display()
+        ));
+    }
+    let mut patch = format!("*** Begin Patch\n*** Add File: {}\n", args.path.trim());
+    for line in args.content.lines() {
+        patch.push('+');
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    patch.push_str("*** End Patch\n");
+    Ok(patch)
+}
+
+/// Decodes `bytes` as text: UTF-8 or UTF-16 with a byte order mark, UTF-8,
+/// or Latin-1. Returns `None` for binary content.
+fn decode_text(bytes: &[u8]) -> Option<String> {
+    if let Some(rest) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return Some(String::from_utf8_lossy(rest).into_owned());
+    }
+    let utf16 = |rest: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        return Some(utf16(rest, u16::from_le_bytes));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        return Some(utf16(rest, u16::from_be_bytes));
+    }
+    if bytes.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text.to_string()),
+        Err(_) => Some(bytes.iter().map(|&byte| char::from(byte)).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn read_args(offset: Option<usize>, limit: Option<usize>) -> ReadFileArgs {
+        ReadFileArgs {
+            path: String::new(),
+            offset,
+            limit,
+        }
+    }
+
+    #[test]
+    fn reads_numbered_pages_of_lines() {
+        let dir = TempDir::new().expect("create temp dir");
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "one\ntwo\nthree\n").expect("write file");
+
+        assert_eq!(
+            read_file(&path, &read_args(None, None)),
+            Ok("     1\tone\n     2\ttwo\n     3\tthree\n".to_string())
+        );
+        assert_eq!(
+            read_file(&path, &read_args(Some(2), Some(1))),
+            Ok("     2\ttwo\n[1 more lines; continue with offset 3]".to_string())
+        );
+        assert!(read_file(&path, &read_args(Some(9), None)).is_err());
+    }
+
+    #[test]
+    fn detects_encodings_and_binary_files() {
+        assert_eq!(
+            decode_text(b"\xef\xbb\xbfcaf\xc3\xa9"),
+            Some("café".to_string())
+        );
+        assert_eq!(decode_text(b"\xff\xfeh\0i\0"), Some("hi".to_string()));
+        assert_eq!(decode_text(b"caf\xe9"), Some("café".to_string()));
+        assert_eq!(decode_text(b"\x7fELF\0\x02"), None);
+    }
+
+    #[test]
+    fn lists_nested_entries_without_descending_into_git() {
+        let dir = TempDir::new().expect("create temp dir");
+        std::fs::create_dir_all(dir.path().join("src")).expect("create src");
+        std::fs::create_dir_all(dir.path().join(".git/objects")).expect("create .git");
+        std::fs::write(dir.path().join("src/lib.rs"), "fn main() {}\n").expect("write file");
+        std::fs::write(dir.path().join("Cargo.toml"), "").expect("write file");
+
+        let listing = list_dir(
+            dir.path(),
+            &ListDirArgs {
+                path: String::new(),
+                depth: Some(3),
+            },
+        )
+        .expect("list dir");
+
+        assert_eq!(
+            listing,
+            format!(
+                "{}/\n  .git/\n  Cargo.toml (0 bytes)\n  src/\n    lib.rs (13 bytes)\n",
+                dir.path().display()
+            )
+        );
+    }
+
+    #[test]
+    fn reads_are_confined_to_the_sandbox_roots() {
+        let dir = TempDir::new().expect("create temp dir");
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir_all(workspace.join("secrets")).expect("create secrets");
+        std::fs::write(workspace.join("notes.txt"), "notes").expect("write file");
+        std::fs::write(workspace.join("secrets/key.txt"), "key").expect("write file");
+        std::fs::write(dir.path().join("outside.txt"), "outside").expect("write file");
+        let read_only = SandboxPolicy::ReadOnly;
+
+        assert_eq!(
+            check_readable(&workspace.join("notes.txt"), &read_only, &workspace),
+            Ok(workspace
+                .join("notes.txt")
+                .canonicalize()
+                .expect("canonicalize"))
+        );
+        assert!(check_readable(&dir.path().join("outside.txt"), &read_only, &workspace).is_err());
+        assert!(check_readable(&workspace.join("../outside.txt"), &read_only, &workspace).is_err());
+        assert!(
+            check_readable(
+                &dir.path().join("outside.txt"),
+                &SandboxPolicy::DangerFullAccess,
+                &workspace
+            )
+            .is_ok()
+        );
+
+        let deny_secrets = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            network_allowlist: vec![],
+            deny: vec!["secrets/".to_string()],
+        };
+        assert!(check_readable(&workspace.join("notes.txt"), &deny_secrets, &workspace).is_ok());
+        assert!(
+            check_readable(
+                &workspace.join("secrets/key.txt"),
+                &deny_secrets,
+                &workspace
+            )
+            .is_err()
+        );
+        assert!(check_readable(&workspace.join("secrets"), &deny_secrets, &workspace).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_sandbox_roots_are_refused() {
+        let dir = TempDir::new().expect("create temp dir");
+        let workspace = dir.path().join("workspace");
+        std::fs::create_dir_all(&workspace).expect("create workspace");
+        std::fs::create_dir_all(dir.path().join("elsewhere")).expect("create elsewhere");
+        std::fs::write(dir.path().join("elsewhere/passwords"), "hunter2").expect("write file");
+        std::os::unix::fs::symlink(dir.path().join("elsewhere"), workspace.join("link"))
+            .expect("create symlink");
+
+        let error = check_readable(
+            &workspace.join("link/passwords"),
+            &SandboxPolicy::ReadOnly,
+            &workspace,
+        )
+        .expect_err("the link leads outside the workspace");
+        assert!(error.contains("resolves to"), "{error}");
+        assert!(
+            check_readable(
+                &workspace.join("link"),
+                &SandboxPolicy::ReadOnly,
+                &workspace
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn writes_become_add_file_patches() {
+        let dir = TempDir::new().expect("create temp dir");
+        let args = WriteFileArgs {
+            path: "docs/notes.md".to_string(),
+            content: "# Notes\n\n- one\n".to_string(),
+        };
+
+        assert_eq!(
+            write_file_patch(&dir.path().join("docs/notes.md"), &args),
+            Ok("*** Begin Patch\n*** Add File: docs/notes.md\n+# Notes\n+\n+- one\n*** End Patch\n".to_string())
+        );
+
+        let binary = dir.path().join("image.bin");
+        std::fs::write(&binary, b"\xff\xd8\xff").expect("write file");
+        let args = WriteFileArgs {
+            path: "image.bin".to_string(),
+            content: "text".to_string(),
+        };
+        assert!(write_file_patch(&binary, &args).is_err());
+    }
+}
@@ -0,0 +1,626 @@
+//! Client for Google's Gemini API (`wire_api = "gemini"`).
+//!
+//! The conversation is mapped onto `streamGenerateContent` requests: the
+//! instructions become the `systemInstruction`, tool calls and their outputs
+//! become `functionCall` and `functionResponse` parts, and the provider's
+//! `safety_settings` are passed through. Gemini identifies function calls by
+//! name only, so calls get a generated id when the response has none.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use uuid::Uuid;
+
+use crate::ModelProviderInfo;
+use crate::auth::CodexAuth;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::openai_tools::create_tools_json_for_gemini_api;
+use crate::protocol::TokenUsage;
+use crate::provider_failover::EndpointFailover;
+use crate::util::backoff;
+use crate::wire_log;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+
+/// Finish reasons for which Gemini withholds the rest of the response.
+const BLOCKED_FINISH_REASONS: [&str; 5] = [
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+];
+
+pub(crate) async fn stream_gemini(
+    prompt: &Prompt,
+    config: &Config,
+    effort: Option<ReasoningEffortConfig>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    failover: Option<&Arc<EndpointFailover>>,
+    provider_auth: &Option<CodexAuth>,
+) -> Result<ResponseStream> {
+    let payload = build_payload(prompt, config, effort, provider)?;
+    let model = &config.model_family.slug;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(provider_auth, model),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let (provider, endpoint) = match failover {
+            Some(failover) => {
+                let (provider, endpoint) = failover.select(provider, client).await;
+                (provider, Some(endpoint))
+            }
+            None => (Cow::Borrowed(provider), None),
+        };
+        let req_builder = provider
+            .create_request_builder(client, provider_auth, model)
+            .await?;
+
+        let req_builder = req_builder
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload);
+        wire_log::log_request(&req_builder);
+        let res = req_builder.send().await;
+        if let Ok(resp) = &res {
+            wire_log::log_response(resp);
+        }
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                if let Some(endpoint) = endpoint {
+                    endpoint.succeeded();
+                }
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = wire_log::tee(resp.bytes_stream()).map_err(CodexErr::Reqwest);
+                tokio::spawn(process_gemini_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                let switched = status.is_server_error()
+                    && endpoint.is_some_and(|endpoint| {
+                        endpoint.failed(&format!("the endpoint returned {status}"))
+                    });
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+                if switched {
+                    continue;
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                let switched = endpoint.is_some_and(|endpoint| {
+                    endpoint.failed(&format!("could not reach the endpoint: {e}"))
+                });
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                if switched {
+                    continue;
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+fn build_payload(
+    prompt: &Prompt,
+    config: &Config,
+    effort: Option<ReasoningEffortConfig>,
+    provider: &ModelProviderInfo,
+) -> Result<Value> {
+    let model_family = &config.model_family;
+    let mut payload = json!({
+        "systemInstruction": {
+            "parts": [{"text": prompt.get_full_instructions_with_output_schema(model_family)}],
+        },
+        "contents": build_contents(&prompt.get_formatted_input()),
+    });
+
+    let function_declarations = create_tools_json_for_gemini_api(&prompt.tools)?;
+    if !function_declarations.is_empty() {
+        payload["tools"] = json!([{"functionDeclarations": function_declarations}]);
+    }
+
+    if let Some(safety_settings) = &provider.safety_settings {
+        payload["safetySettings"] = serde_json::to_value(safety_settings)?;
+    }
+
+    let mut generation_config = serde_json::Map::new();
+    if let Some(max_output_tokens) = config.model_max_output_tokens {
+        generation_config.insert("maxOutputTokens".to_string(), json!(max_output_tokens));
+    }
+    if let Some(temperature) = config.model_temperature {
+        generation_config.insert("temperature".to_string(), json!(temperature));
+    }
+    if model_family.supports_reasoning_summaries {
+        let mut thinking_config = json!({"includeThoughts": true});
+        if let Some(budget) = thinking_budget(effort) {
+            thinking_config["thinkingBudget"] = json!(budget);
+        }
+        generation_config.insert("thinkingConfig".to_string(), thinking_config);
+    }
+    if !generation_config.is_empty() {
+        payload["generationConfig"] = Value::Object(generation_config);
+    }
+
+    Ok(payload)
+}
+
+/// Thinking budget for `effort`; without one Gemini picks a budget itself.
+fn thinking_budget(effort: Option<ReasoningEffortConfig>) -> Option<u64> {
+    Some(match effort? {
+        ReasoningEffortConfig::Minimal => 512,
+        ReasoningEffortConfig::Low => 2_048,
+        ReasoningEffortConfig::Medium => 8_192,
+        ReasoningEffortConfig::High => 24_576,
+    })
+}
+
+/// Maps the conversation onto Gemini contents. Consecutive items of the same
+/// role are merged into one content, as Gemini expects turns to alternate.
+fn build_contents(input: &[ResponseItem]) -> Vec<Value> {
+    let mut contents: Vec<(&'static str, Vec<Value>)> = Vec::new();
+    let mut push = |role: &'static str, part: Value| match contents.last_mut() {
+        Some((last_role, parts)) if *last_role == role => parts.push(part),
+        _ => contents.push((role, vec![part])),
+    };
+    // Function responses are matched to their calls by name.
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" { "model" } else { "user" };
+                for content_item in content {
+                    match content_item {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push(role, json!({"text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push(role, image_part(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let args = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                call_names.insert(call_id.as_str(), name.as_str());
+                push(
+                    "model",
+                    json!({"functionCall": {"name": name, "args": args}}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                if let Some(call_id) = call_id.as_ref().or(id.as_ref()) {
+                    call_names.insert(call_id.as_str(), "local_shell");
+                }
+                push(
+                    "model",
+                    json!({"functionCall": {"name": "local_shell", "args": action}}),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                call_names.insert(call_id.as_str(), name.as_str());
+                push(
+                    "model",
+                    json!({"functionCall": {"name": name, "args": {"input": input}}}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let Some(name) = call_names.get(call_id.as_str()) else {
+                    continue;
+                };
+                let response = if output.success == Some(false) {
+                    json!({"error": output.content})
+                } else {
+                    json!({"output": output.content})
+                };
+                push(
+                    "user",
+                    json!({"functionResponse": {"name": name, "response": response}}),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                let Some(name) = call_names.get(call_id.as_str()) else {
+                    continue;
+                };
+                push(
+                    "user",
+                    json!({"functionResponse": {"name": name, "response": {"output": output}}}),
+                );
+            }
+            // Thoughts are not sent back to Gemini.
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+
+    contents
+        .into_iter()
+        .map(|(role, parts)| json!({"role": role, "parts": parts}))
+        .collect()
+}
+
+/// Inline data for data URLs. Gemini only fetches files it hosts, so other
+/// URLs are given to the model as text.
+fn image_part(image_url: &str) -> Value {
+    if let Some(data_url) = image_url.strip_prefix("data:")
+        && let Some((mime_type, data)) = data_url.split_once(";base64,")
+    {
+        return json!({"inlineData": {"mimeType": mime_type, "data": data}});
+    }
+    json!({"text": format!("[image: {image_url}]")})
+}
+
+/// SSE processor for `streamGenerateContent?alt=sse`. Each event is a
+/// partial response; the stream ends when the connection closes.
+async fn process_gemini_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut created = false;
+    let mut response_id = String::new();
+    let mut usage_metadata = Value::Null;
+    let mut assistant_text = String::new();
+    let mut thought_text = String::new();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let chunk: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("gemini received SSE chunk: {chunk:?}");
+
+        if let Some(message) = chunk["error"]["message"].as_str() {
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(message.to_string(), None)))
+                .await;
+            return;
+        }
+        if let Some(reason) = chunk["promptFeedback"]["blockReason"].as_str() {
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(
+                    format!("Gemini blocked the prompt ({reason})"),
+                    None,
+                )))
+                .await;
+            return;
+        }
+
+        if !created {
+            created = true;
+            let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+        }
+        if let Some(id) = chunk["responseId"].as_str() {
+            response_id = id.to_string();
+        }
+        if chunk["usageMetadata"].is_object() {
+            usage_metadata = chunk["usageMetadata"].clone();
+        }
+
+        let candidate = &chunk["candidates"][0];
+        let parts = candidate["content"]["parts"].as_array();
+        for part in parts.into_iter().flatten() {
+            let text = part["text"].as_str().unwrap_or_default();
+            if part["thought"].as_bool() == Some(true) {
+                if !text.is_empty() {
+                    thought_text.push_str(text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::ReasoningSummaryDelta(text.to_string())))
+                        .await;
+                }
+                continue;
+            }
+
+            // Thoughts come first, so they are complete once anything else
+            // arrives.
+            flush_thoughts(&tx_event, &mut thought_text).await;
+            if !text.is_empty() {
+                assistant_text.push_str(text);
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::OutputTextDelta(text.to_string())))
+                    .await;
+            }
+            if let Some(function_call) = part["functionCall"].as_object() {
+                flush_text(&tx_event, &mut assistant_text).await;
+                let call_id = function_call
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("call_{}", Uuid::new_v4()));
+                let item = ResponseItem::FunctionCall {
+                    id: None,
+                    name: function_call
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    arguments: function_call
+                        .get("args")
+                        .map_or_else(|| "{}".to_string(), Value::to_string),
+                    call_id,
+                };
+                let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+            }
+        }
+
+        if let Some(reason) = candidate["finishReason"].as_str()
+            && BLOCKED_FINISH_REASONS.contains(&reason)
+        {
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(
+                    format!("Gemini blocked the response ({reason}); see safety_settings"),
+                    None,
+                )))
+                .await;
+            return;
+        }
+    }
+
+    flush_thoughts(&tx_event, &mut thought_text).await;
+    flush_text(&tx_event, &mut assistant_text).await;
+    let _ = tx_event
+        .send(Ok(ResponseEvent::Completed {
+            response_id,
+            token_usage: token_usage(&usage_metadata),
+        }))
+        .await;
+}
+
+async fn flush_thoughts(tx_event: &mpsc::Sender<Result<ResponseEvent>>, thought_text: &mut String) {
+    if thought_text.is_empty() {
+        return;
+    }
+    let item = ResponseItem::Reasoning {
+        id: String::new(),
+        summary: vec![ReasoningItemReasoningSummary::SummaryText {
+            text: std::mem::take(thought_text),
+        }],
+        content: None,
+        encrypted_content: None,
+    };
+    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+}
+
+async fn flush_text(tx_event: &mpsc::Sender<Result<ResponseEvent>>, assistant_text: &mut String) {
+    if assistant_text.is_empty() {
+        return;
+    }
+    let item = ResponseItem::Message {
+        id: None,
+        role: "assistant".to_string(),
+        content: vec![ContentItem::OutputText {
+            text: std::mem::take(assistant_text),
+        }],
+    };
+    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+}
+
+/// `promptTokenCount` already includes cached tokens, whereas thoughts are
+/// counted apart from `candidatesTokenCount`.
+fn token_usage(usage_metadata: &Value) -> Option<TokenUsage> {
+    let count = |key: &str| usage_metadata[key].as_u64().unwrap_or_default();
+    let input_tokens = usage_metadata["promptTokenCount"].as_u64()?;
+    let reasoning_output_tokens = count("thoughtsTokenCount");
+    let output_tokens = count("candidatesTokenCount") + reasoning_output_tokens;
+    Some(TokenUsage {
+        input_tokens,
+        cached_input_tokens: count("cachedContentTokenCount"),
+        output_tokens,
+        reasoning_output_tokens,
+        total_tokens: input_tokens + output_tokens,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn maps_function_calls_to_named_responses() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "list files".to_string(),
+                }],
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Running ls.".to_string(),
+                }],
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "call_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "Cargo.toml".to_string(),
+                    success: Some(true),
+                },
+            },
+        ];
+
+        assert_eq!(
+            build_contents(&input),
+            vec![
+                json!({"role": "user", "parts": [{"text": "list files"}]}),
+                json!({"role": "model", "parts": [
+                    {"text": "Running ls."},
+                    {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}},
+                ]}),
+                json!({"role": "user", "parts": [
+                    {"functionResponse": {"name": "shell", "response": {"output": "Cargo.toml"}}},
+                ]}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_streamed_chunks() {
+        let chunks = [
+            json!({"responseId": "resp_1", "candidates": [{"content": {"role": "model", "parts": [{"text": "Check.", "thought": true}]}}]}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"text": "Running ls."}]}}]}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [{"functionCall": {"id": "fc_1", "name": "shell", "args": {"command": ["ls"]}}}]}, "finishReason": "STOP"}],
+                   "usageMetadata": {"promptTokenCount": 100, "cachedContentTokenCount": 80, "candidatesTokenCount": 30, "thoughtsTokenCount": 10, "totalTokenCount": 140}}),
+        ];
+        let body: String = chunks
+            .iter()
+            .map(|chunk| format!("data: {chunk}\r\n\r\n"))
+            .collect();
+        let stream = futures::stream::iter(vec![Ok(Bytes::from(body))]);
+        let (tx, mut rx) = mpsc::channel(16);
+        process_gemini_sse(stream, tx, Duration::from_secs(5)).await;
+
+        let mut received = Vec::new();
+        while let Some(event) = rx.recv().await {
+            received.push(format!("{:?}", event.expect("event")));
+        }
+        let expected = [
+            ResponseEvent::Created,
+            ResponseEvent::ReasoningSummaryDelta("Check.".to_string()),
+            ResponseEvent::OutputItemDone(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                    text: "Check.".to_string(),
+                }],
+                content: None,
+                encrypted_content: None,
+            }),
+            ResponseEvent::OutputTextDelta("Running ls.".to_string()),
+            ResponseEvent::OutputItemDone(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Running ls.".to_string(),
+                }],
+            }),
+            ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "fc_1".to_string(),
+            }),
+            ResponseEvent::Completed {
+                response_id: "resp_1".to_string(),
+                token_usage: Some(TokenUsage {
+                    input_tokens: 100,
+                    cached_input_tokens: 80,
+                    output_tokens: 40,
+                    reasoning_output_tokens: 10,
+                    total_tokens: 140,
+                }),
+            },
+        ]
+        .map(|event| format!("{event:?}"));
+        assert_eq!(received, expected);
+    }
+}
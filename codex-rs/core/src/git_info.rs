@@ -41,6 +41,18 @@ pub fn get_git_repo_root(base_dir: &Path) -> Option<PathBuf> {
     None
 }
 
+/// A file-name-safe key for the project containing `cwd`: its Git
+/// repository root or, outside a repository, `cwd` itself. Used to store
+/// per-project data under `CODEX_HOME`.
+pub(crate) fn project_key(cwd: &Path) -> String {
+    let root = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+    let root = root.canonicalize().unwrap_or(root);
+    root.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
 /// Timeout for git commands to prevent freezing on large repositories
 const GIT_COMMAND_TIMEOUT: TokioDuration = TokioDuration::from_secs(5);
 
@@ -192,6 +204,33 @@ async fn run_git_command_with_timeout(args: &[&str], cwd: &Path) -> Option<std::
     }
 }
 
+/// Runs git in `cwd`, giving up after `limit`. Returns its trimmed stdout, or
+/// why it failed, e.g. "`git push` failed: <stderr>". Unlike the lookups in
+/// this file, which quietly give up, this is for commands whose failure is
+/// reported, such as `commit` or `push`.
+pub(crate) async fn run_git(
+    cwd: &Path,
+    args: &[&str],
+    limit: TokioDuration,
+) -> Result<String, String> {
+    let what = args.first().copied().unwrap_or_default();
+    let output = timeout(
+        limit,
+        Command::new("git").args(args).current_dir(cwd).output(),
+    )
+    .await
+    .map_err(|_| format!("`git {what}` timed out"))?
+    .map_err(|e| format!("failed to run git: {e}"))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "`git {what}` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
 async fn get_git_remotes(cwd: &Path) -> Option<Vec<String>> {
     let output = run_git_command_with_timeout(&["remote"], cwd).await?;
     if !output.status.success() {
@@ -215,7 +254,7 @@ async fn get_git_remotes(cwd: &Path) -> Option<Vec<String>> {
 /// 1) The symbolic ref at `refs/remotes/<remote>/HEAD` for the first remote (origin prioritized)
 /// 2) `git remote show <remote>` parsed for "HEAD branch: <name>"
 /// 3) Local fallback to existing `main` or `master` if present
-async fn get_default_branch(cwd: &Path) -> Option<String> {
+pub(crate) async fn get_default_branch(cwd: &Path) -> Option<String> {
     // Prefer the first remote (with origin prioritized)
     let remotes = get_git_remotes(cwd).await.unwrap_or_default();
     for remote in remotes {
@@ -1,3 +1,5 @@
+use crate::config_types::ResourceLimits;
+use crate::network_proxy::NETWORK_ALLOWLIST_UNSUPPORTED;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -13,18 +15,29 @@ use tokio::process::Child;
 /// helper accepts a list of `--sandbox-permission`/`-s` flags mirroring the
 /// public CLI. We convert the internal [`SandboxPolicy`] representation into
 /// the equivalent CLI options.
+#[allow(clippy::too_many_arguments)]
 pub async fn spawn_command_under_linux_sandbox<P>(
     codex_linux_sandbox_exe: P,
     command: Vec<String>,
     command_cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
+    resource_limits: &ResourceLimits,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
 ) -> std::io::Result<Child>
 where
     P: AsRef<Path>,
 {
+    // Landlock network rules only filter on the port, so they cannot keep
+    // commands from reaching arbitrary hosts around the allowlist proxy.
+    // Refuse to run rather than pretend to enforce the allowlist.
+    if !sandbox_policy.network_allowlist().is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            NETWORK_ALLOWLIST_UNSUPPORTED,
+        ));
+    }
     let args = create_linux_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd);
     let arg0 = Some("codex-linux-sandbox");
     spawn_child_async(
@@ -33,6 +46,7 @@ where
         arg0,
         command_cwd,
         sandbox_policy,
+        resource_limits,
         stdio_policy,
         env,
     )
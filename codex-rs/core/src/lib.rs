@@ -5,9 +5,15 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod anthropic;
 mod apply_patch;
 pub mod auth;
+mod auto_commit;
+mod aws_auth;
+mod background_jobs;
 pub mod bash;
+mod bedrock;
+mod change_journal;
 mod chat_completions;
 mod client;
 mod client_common;
@@ -20,32 +26,60 @@ pub mod config;
 pub mod config_edit;
 pub mod config_profile;
 pub mod config_types;
+pub mod container_sandbox;
+mod context_pruning;
 mod conversation_history;
+pub mod cost;
+pub mod credential_store;
 pub mod custom_prompts;
+pub mod custom_tools;
 mod environment_context;
 pub mod error;
+mod event_webhook;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+pub mod extensions;
+mod external_auth;
+mod fetch_url;
+pub mod file_attachments;
+mod file_tools;
 mod flags;
+mod gemini;
 pub mod git_info;
 pub mod landlock;
+mod lifecycle_hooks;
 mod mcp_connection_manager;
+pub mod mcp_oauth;
+mod mcp_sampling;
 mod mcp_tool_call;
+pub mod memory;
 mod message_history;
 mod model_provider_info;
+mod network_proxy;
 pub mod parse_command;
+mod post_patch_hooks;
+mod provider_failover;
+mod provider_fallback;
+mod provider_throttle;
+mod pull_request;
 mod truncate;
 mod unified_exec;
+mod url_attachments;
 mod user_instructions;
+mod web_search;
+pub use model_provider_info::AwsSettings;
 pub use model_provider_info::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 pub use model_provider_info::ModelProviderInfo;
+pub use model_provider_info::SafetySetting;
 pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
 pub use model_provider_info::create_oss_provider_with_base_url;
 mod conversation_manager;
 mod event_mapping;
+pub mod event_subscription;
 pub mod review_format;
+pub mod review_target;
 pub use codex_protocol::protocol::InitialHistory;
 pub use conversation_manager::ConversationManager;
 pub use conversation_manager::NewConversation;
@@ -56,33 +90,56 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+pub mod otel;
+mod output_overflow;
+mod output_schema;
+mod parallel_tools;
+mod patch_risk;
 pub mod plan_tool;
+pub mod plugins;
 pub mod project_doc;
 mod rollout;
 pub(crate) mod safety;
+pub mod sandbox_audit;
+mod sandbox_rules;
+mod search_code;
 pub mod seatbelt;
+mod secrets;
+pub mod session_log;
 pub mod shell;
 pub mod spawn;
+pub mod survey;
+mod task_failure;
+pub mod team_report;
 pub mod terminal;
+mod token_count;
 mod tool_apply_patch;
+mod tool_git;
 pub mod turn_diff_tracker;
+pub mod wire_log;
+pub mod worktree;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
 pub use rollout::SESSIONS_SUBDIR;
+pub use rollout::SessionLockHolder;
+pub use rollout::SessionLocked;
 pub use rollout::SessionMeta;
 pub use rollout::find_conversation_path_by_id_str;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
+pub use rollout::session_lock_status;
 mod function_tool;
 mod state;
 mod tasks;
 mod user_notification;
 pub mod util;
+pub mod windows_sandbox;
 
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
 pub use command_safety::is_safe_command;
 pub use safety::get_platform_sandbox;
+pub use safety::sandbox_capability;
 // Re-export the protocol types from the standalone `codex-protocol` crate so existing
 // `codex_core::protocol::...` references continue to work across the workspace.
 pub use codex_protocol::protocol;
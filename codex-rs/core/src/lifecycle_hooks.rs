@@ -0,0 +1,423 @@
+//! User-configured commands run at points of a session (`[hooks]`): when it
+//! starts and ends, around each turn and tool call, and on approval
+//! decisions. They add custom audit, metrics and policy logic without
+//! changing Codex.
+//!
+//! Each hook receives the event as a JSON object on stdin, e.g.
+//! `{"event":"pre_tool_call","session_id":"…","cwd":"…","turn_id":"1",
+//! "call_id":"…","tool":"shell","arguments":"…"}`, and may answer on stdout
+//! with a JSON object (see [`LifecycleHook`]). A hook that fails to run,
+//! exits unsuccessfully or times out is reported and otherwise ignored,
+//! unless it is `fail_closed`: then its failure vetoes events that can be
+//! vetoed.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+use wildmatch::WildMatchPattern;
+
+use crate::config_types::Hooks;
+use crate::config_types::LifecycleHook;
+use crate::post_patch_hooks::DEFAULT_HOOK_TIMEOUT;
+use crate::protocol::ReviewDecision;
+
+/// A session event hooks run on, as they receive it.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum HookEvent<'a> {
+    SessionStart {
+        model: &'a str,
+    },
+    SessionEnd,
+    TurnStart {
+        turn_id: &'a str,
+    },
+    TurnEnd {
+        turn_id: &'a str,
+        last_agent_message: Option<&'a str>,
+    },
+    PreToolCall {
+        turn_id: &'a str,
+        call_id: &'a str,
+        tool: &'a str,
+        arguments: &'a str,
+    },
+    PostToolCall {
+        turn_id: &'a str,
+        call_id: &'a str,
+        tool: &'a str,
+        arguments: &'a str,
+        output: &'a str,
+        success: bool,
+    },
+    Approval {
+        turn_id: &'a str,
+        call_id: &'a str,
+        /// The command, for command approvals.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        command: Option<&'a [String]>,
+        /// The files the patch changes, for patch approvals.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        files: Option<&'a [PathBuf]>,
+        /// The MCP server asking to sample the model, for MCP sampling
+        /// approvals.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        mcp_server: Option<&'a str>,
+        decision: ReviewDecision,
+    },
+}
+
+impl HookEvent<'_> {
+    /// The tool the event is about, for tool call events.
+    fn tool(&self) -> Option<&str> {
+        match self {
+            HookEvent::PreToolCall { tool, .. } | HookEvent::PostToolCall { tool, .. } => {
+                Some(*tool)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HookPayload<'a> {
+    session_id: String,
+    cwd: &'a Path,
+    #[serde(flatten)]
+    event: &'a HookEvent<'a>,
+}
+
+#[derive(Deserialize, Default)]
+struct HookResponse {
+    decision: Option<HookDecision>,
+    reason: Option<String>,
+    message: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum HookDecision {
+    Allow,
+    Block,
+}
+
+/// What the hooks for an event answered.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct HookOutcome {
+    /// Why a hook vetoed the event. Only pre-tool-call and approval hooks can
+    /// veto; the hooks after the vetoing one do not run.
+    pub(crate) blocked: Option<String>,
+    /// Notes the hooks added.
+    pub(crate) messages: Vec<String>,
+    /// Hooks that failed, which are otherwise ignored unless they are
+    /// `fail_closed`.
+    pub(crate) failures: Vec<String>,
+}
+
+pub(crate) struct LifecycleHooks {
+    hooks: Hooks,
+    conversation_id: ConversationId,
+    cwd: PathBuf,
+}
+
+impl LifecycleHooks {
+    pub(crate) fn new(hooks: &Hooks, conversation_id: ConversationId, cwd: PathBuf) -> Self {
+        Self {
+            hooks: hooks.clone(),
+            conversation_id,
+            cwd,
+        }
+    }
+
+    fn hooks_for(&self, event: &HookEvent<'_>) -> &BTreeMap<String, LifecycleHook> {
+        match event {
+            HookEvent::SessionStart { .. } => &self.hooks.session_start,
+            HookEvent::SessionEnd => &self.hooks.session_end,
+            HookEvent::TurnStart { .. } => &self.hooks.turn_start,
+            HookEvent::TurnEnd { .. } => &self.hooks.turn_end,
+            HookEvent::PreToolCall { .. } => &self.hooks.pre_tool_call,
+            HookEvent::PostToolCall { .. } => &self.hooks.post_tool_call,
+            HookEvent::Approval { .. } => &self.hooks.approval,
+        }
+    }
+
+    /// Whether any hook runs on tool calls.
+    pub(crate) fn has_tool_call_hooks(&self) -> bool {
+        !self.hooks.pre_tool_call.is_empty() || !self.hooks.post_tool_call.is_empty()
+    }
+
+    /// Runs the hooks configured for `event`, in name order.
+    pub(crate) async fn run(&self, event: &HookEvent<'_>) -> HookOutcome {
+        let mut outcome = HookOutcome::default();
+        let hooks = self.hooks_for(event);
+        if hooks.is_empty() {
+            return outcome;
+        }
+        let payload = HookPayload {
+            session_id: self.conversation_id.to_string(),
+            cwd: &self.cwd,
+            event,
+        };
+        let payload = match serde_json::to_vec(&payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                outcome
+                    .failures
+                    .push(format!("failed to serialize hook payload: {e}"));
+                return outcome;
+            }
+        };
+        let can_block = matches!(
+            event,
+            HookEvent::PreToolCall { .. } | HookEvent::Approval { .. }
+        );
+
+        for (name, hook) in hooks {
+            if let Some(tool) = event.tool()
+                && !matches_tool(hook, tool)
+            {
+                continue;
+            }
+            let response = match run_hook(hook, &self.cwd, &payload).await {
+                Ok(response) => response,
+                Err(e) => {
+                    if can_block && hook.fail_closed {
+                        outcome.blocked = Some(format!("blocked because hook `{name}` {e}"));
+                        break;
+                    }
+                    outcome.failures.push(format!("Hook `{name}` {e}"));
+                    continue;
+                }
+            };
+            if let Some(message) = response.message {
+                outcome.messages.push(message);
+            }
+            if can_block && response.decision == Some(HookDecision::Block) {
+                outcome.blocked = Some(match response.reason {
+                    Some(reason) => format!("blocked by hook `{name}`: {reason}"),
+                    None => format!("blocked by hook `{name}`"),
+                });
+                break;
+            }
+        }
+        outcome
+    }
+}
+
+fn matches_tool(hook: &LifecycleHook, tool: &str) -> bool {
+    hook.tools.is_empty()
+        || hook
+            .tools
+            .iter()
+            .any(|pattern| WildMatchPattern::<'*', '?'>::new(pattern).matches(tool))
+}
+
+/// Runs `hook` with `payload` on stdin and parses its answer. The error
+/// completes the sentence "Hook `name` …".
+async fn run_hook(
+    hook: &LifecycleHook,
+    cwd: &Path,
+    payload: &[u8],
+) -> Result<HookResponse, String> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err("has no command".to_string());
+    };
+    let hook_timeout = hook
+        .timeout_ms
+        .map_or(DEFAULT_HOOK_TIMEOUT, Duration::from_millis);
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("could not run: {e}"))?;
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook that does not read its input may close stdin early.
+            let _ = stdin.write_all(payload).await;
+        }
+        child.wait_with_output().await
+    };
+    let output = match timeout(hook_timeout, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("could not run: {e}")),
+        Err(_) => {
+            return Err(format!("timed out after {} ms", hook_timeout.as_millis()));
+        }
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let status = match output.status.code() {
+            Some(code) => format!("exit code {code}"),
+            None => "killed by a signal".to_string(),
+        };
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            format!("failed ({status})")
+        } else {
+            format!("failed ({status}): {stderr}")
+        });
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(HookResponse::default());
+    }
+    serde_json::from_str(stdout.trim()).map_err(|e| format!("answered with invalid JSON: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn hook(script: &str, tools: &[&str]) -> LifecycleHook {
+        LifecycleHook {
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            tools: tools.iter().map(ToString::to_string).collect(),
+            timeout_ms: None,
+            fail_closed: false,
+        }
+    }
+
+    fn pre_tool_call(tool: &str) -> HookEvent<'_> {
+        HookEvent::PreToolCall {
+            turn_id: "1",
+            call_id: "call-1",
+            tool,
+            arguments: r#"{"command":["ls"]}"#,
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_receive_the_event_and_can_block() {
+        let dir = TempDir::new().expect("tempdir");
+        let log = dir.path().join("events.jsonl");
+        let hooks = Hooks {
+            pre_tool_call: BTreeMap::from([
+                (
+                    "a-audit".to_string(),
+                    hook(&format!("cat >> '{}'; echo", log.display()), &[]),
+                ),
+                (
+                    "b-policy".to_string(),
+                    hook(
+                        r#"echo '{"decision":"block","reason":"no shell today","message":"see policy"}'"#,
+                        &["shell"],
+                    ),
+                ),
+                ("c-never".to_string(), hook("exit 1", &[])),
+            ]),
+            ..Hooks::default()
+        };
+        let hooks = LifecycleHooks::new(&hooks, ConversationId::new(), dir.path().to_path_buf());
+
+        let outcome = hooks.run(&pre_tool_call("shell")).await;
+        assert_eq!(
+            outcome,
+            HookOutcome {
+                blocked: Some("blocked by hook `b-policy`: no shell today".to_string()),
+                messages: vec!["see policy".to_string()],
+                failures: Vec::new(),
+            }
+        );
+
+        let payload: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&log).expect("read").trim())
+                .expect("json payload");
+        assert_eq!(payload["event"], "pre_tool_call");
+        assert_eq!(payload["tool"], "shell");
+        assert_eq!(payload["call_id"], "call-1");
+
+        // `b-policy` only runs for `shell`, so `c-never` runs and fails.
+        let outcome = hooks.run(&pre_tool_call("read_file")).await;
+        assert_eq!(outcome.blocked, None);
+        assert_eq!(
+            outcome.failures,
+            vec!["Hook `c-never` failed (exit code 1)".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_vetoable_events_can_be_blocked() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks {
+            turn_start: BTreeMap::from([(
+                "block".to_string(),
+                hook(r#"echo '{"decision":"block"}'"#, &[]),
+            )]),
+            ..Hooks::default()
+        };
+        let hooks = LifecycleHooks::new(&hooks, ConversationId::new(), dir.path().to_path_buf());
+
+        let outcome = hooks.run(&HookEvent::TurnStart { turn_id: "1" }).await;
+        assert_eq!(outcome, HookOutcome::default());
+    }
+
+    #[tokio::test]
+    async fn fail_closed_hooks_block_when_they_fail() {
+        let dir = TempDir::new().expect("tempdir");
+        let hooks = Hooks {
+            approval: BTreeMap::from([
+                (
+                    "a-policy".to_string(),
+                    LifecycleHook {
+                        fail_closed: true,
+                        ..hook("exit 3", &[])
+                    },
+                ),
+                ("b-never".to_string(), hook("exit 1", &[])),
+            ]),
+            turn_start: BTreeMap::from([(
+                "policy".to_string(),
+                LifecycleHook {
+                    fail_closed: true,
+                    ..hook("exit 3", &[])
+                },
+            )]),
+            ..Hooks::default()
+        };
+        let hooks = LifecycleHooks::new(&hooks, ConversationId::new(), dir.path().to_path_buf());
+
+        let outcome = hooks
+            .run(&HookEvent::Approval {
+                turn_id: "1",
+                call_id: "call-1",
+                command: None,
+                files: None,
+                mcp_server: Some("docs"),
+                decision: ReviewDecision::Approved,
+            })
+            .await;
+        assert_eq!(
+            outcome,
+            HookOutcome {
+                blocked: Some("blocked because hook `a-policy` failed (exit code 3)".to_string()),
+                messages: Vec::new(),
+                failures: Vec::new(),
+            }
+        );
+
+        // Events that cannot be blocked only report the failure.
+        let outcome = hooks.run(&HookEvent::TurnStart { turn_id: "1" }).await;
+        assert_eq!(outcome.blocked, None);
+        assert_eq!(
+            outcome.failures,
+            vec!["Hook `policy` failed (exit code 3)".to_string()]
+        );
+    }
+}
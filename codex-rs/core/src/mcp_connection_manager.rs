@@ -5,31 +5,55 @@
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
 //! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//!
+//! With lazy start enabled, stdio servers whose tools are known from an
+//! earlier session are not launched until one of their tools is called, and
+//! are stopped again once they have been idle for a while.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
 use codex_mcp_client::McpClient;
 use codex_rmcp_client::RmcpClient;
+use codex_rmcp_client::SamplingHandler;
+use futures::FutureExt;
 use mcp_types::ClientCapabilities;
+use mcp_types::ClientCapabilitiesRoots;
 use mcp_types::Implementation;
+use mcp_types::Root;
 use mcp_types::Tool;
-
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
 use serde_json::json;
 use sha1::Digest;
 use sha1::Sha1;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc;
 use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
 
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::credential_store::CredentialStore;
+use crate::mcp_oauth::McpOAuthTokenSource;
+use crate::mcp_sampling::McpSamplingRequest;
+use crate::mcp_sampling::sampling_handler;
+use crate::protocol::McpServerStatus;
+use crate::protocol::McpServerStatusEvent;
+use crate::protocol::SandboxPolicy;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -87,10 +111,407 @@ struct ToolInfo {
     tool: Tool,
 }
 
+/// Where the tools of lazily started servers are remembered between
+/// sessions, relative to `CODEX_HOME`.
+pub(crate) const MCP_TOOLS_CACHE_FILE: &str = "mcp_tools_cache.json";
+
+/// Settings for starting stdio servers on first use.
+pub(crate) struct LazyStartConfig {
+    /// File with the tools each server offered when it last ran.
+    pub(crate) tools_cache: PathBuf,
+    /// Stop a server after it has not been called for this long.
+    pub(crate) idle_timeout: Option<Duration>,
+}
+
+/// Consecutive timed-out calls after which a server counts as unresponsive.
+const MAX_CONSECUTIVE_TIMEOUTS: u32 = 3;
+
+/// Restart attempts before a crashed server is given up on.
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+
+/// Delay before the first restart attempt, doubled after every failed one.
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Health changes that have not been reported to the user yet.
+type StatusChanges = Arc<StdMutex<Vec<McpServerStatusEvent>>>;
+
+type SharedRoots = Arc<StdMutex<Vec<Root>>>;
+
+fn current_roots(roots: &SharedRoots) -> Vec<Root> {
+    roots.lock().map(|roots| roots.clone()).unwrap_or_default()
+}
+
+/// How a client answers the requests its server sends.
+#[derive(Clone)]
+struct ServerRequests {
+    /// Returned for `roots/list`.
+    roots: Vec<Root>,
+    /// Answers `sampling/createMessage`; `None` when sampling is off.
+    sampling: Option<SamplingHandler>,
+}
+
+/// The roots offered to MCP servers: the working directory and any extra
+/// writable roots of the sandbox policy, as `file://` URIs.
+pub(crate) fn workspace_roots(cwd: &Path, sandbox_policy: &SandboxPolicy) -> Vec<Root> {
+    let mut dirs = vec![cwd.to_path_buf()];
+    if let SandboxPolicy::WorkspaceWrite { writable_roots, .. } = sandbox_policy {
+        for root in writable_roots {
+            if !dirs.contains(root) {
+                dirs.push(root.clone());
+            }
+        }
+    }
+    dirs.iter()
+        .filter_map(|dir| {
+            let uri = Url::from_file_path(dir).ok()?;
+            Some(Root {
+                name: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned()),
+                uri: uri.to_string(),
+            })
+        })
+        .collect()
+}
+
 struct ManagedClient {
-    client: McpClientAdapter,
+    server_name: String,
+    state: Mutex<ClientState>,
+    /// Starts the server again when it is not running. `None` for HTTP
+    /// servers, which reconnect on their own.
+    launcher: Option<StdioLauncher>,
+    /// Set when lazy start is on, see [`LazyStartConfig::tools_cache`].
+    tools_cache: Option<PathBuf>,
     startup_timeout: Duration,
     tool_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    status_changes: StatusChanges,
+    /// Shared with the manager, so restarted servers get the current roots.
+    roots: SharedRoots,
+    sampling: Option<SamplingHandler>,
+}
+
+#[derive(Default)]
+struct ClientState {
+    /// `None` while the server is not running.
+    client: Option<McpClientAdapter>,
+    /// Number of calls so far, so an idle timer can tell whether the server
+    /// was used after it was set.
+    calls: u64,
+    health: Health,
+    /// Calls in a row that timed out.
+    timeouts: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Health {
+    #[default]
+    Healthy,
+    /// Calls keep timing out but the server cannot be restarted.
+    Degraded,
+    /// The server crashed or stopped answering; a restart is pending.
+    Restarting,
+    /// Every restart attempt failed.
+    Failed,
+}
+
+/// How a tool call went, as far as the health of the server is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallOutcome {
+    Succeeded,
+    TimedOut,
+    /// The server closed the connection, usually because it exited.
+    Exited,
+    /// Any other error, e.g. one the tool reported for bad arguments.
+    Failed,
+}
+
+impl ManagedClient {
+    fn server_requests(&self) -> ServerRequests {
+        ServerRequests {
+            roots: current_roots(&self.roots),
+            sampling: self.sampling.clone(),
+        }
+    }
+
+    /// Returns the client, starting the server first if it is not running.
+    async fn acquire(self: &Arc<Self>) -> Result<McpClientAdapter> {
+        let server_name = &self.server_name;
+        let mut state = self.state.lock().await;
+        match state.health {
+            Health::Restarting => {
+                return Err(anyhow!(
+                    "MCP server '{server_name}' stopped working and is being restarted; try again shortly"
+                ));
+            }
+            Health::Failed => {
+                return Err(anyhow!(
+                    "MCP server '{server_name}' stopped working and could not be restarted"
+                ));
+            }
+            Health::Healthy | Health::Degraded => {}
+        }
+        state.calls += 1;
+        if let Some(client) = &state.client {
+            if self.launcher.is_none() || !client.is_closed().await {
+                return Ok(client.clone());
+            }
+            // The server exited while nobody was calling it.
+            info!("MCP server '{server_name}' exited; starting it again");
+            state.client = None;
+        }
+        let launcher = self
+            .launcher
+            .as_ref()
+            .ok_or_else(|| anyhow!("MCP server '{server_name}' is not running"))?;
+        info!("starting MCP server '{server_name}'");
+        let client = match launcher
+            .start(self.startup_timeout, self.server_requests())
+            .await
+        {
+            Ok(client) => client,
+            Err(e) => {
+                state.health = Health::Restarting;
+                drop(state);
+                self.report(
+                    McpServerStatus::Degraded,
+                    format!("failed to start: {e:#}; retrying"),
+                );
+                self.spawn_restart();
+                return Err(e);
+            }
+        };
+        state.client = Some(client.clone());
+
+        // Keep the cached tools current for the next session.
+        if let Some(tools_cache) = self.tools_cache.clone() {
+            let refresh_client = client.clone();
+            let fingerprint = launcher.fingerprint();
+            let server_name = server_name.clone();
+            let startup_timeout = self.startup_timeout;
+            tokio::spawn(async move {
+                match refresh_client.list_tools(None, Some(startup_timeout)).await {
+                    Ok(result) => {
+                        ToolsCache::store(&tools_cache, [(server_name, fingerprint, result.tools)])
+                    }
+                    Err(e) => warn!("Failed to list tools for MCP server '{server_name}': {e:#}"),
+                }
+            });
+        }
+        Ok(client)
+    }
+
+    /// Updates the health of the server after a call. A server that exited
+    /// or keeps timing out is restarted in the background.
+    async fn record_call(self: &Arc<Self>, outcome: CallOutcome) {
+        let mut state = self.state.lock().await;
+        let problem = match outcome {
+            CallOutcome::Succeeded => {
+                state.timeouts = 0;
+                if state.health == Health::Degraded {
+                    state.health = Health::Healthy;
+                    drop(state);
+                    self.report(McpServerStatus::Healthy, "responding again".to_string());
+                }
+                return;
+            }
+            CallOutcome::Failed => return,
+            CallOutcome::TimedOut => {
+                state.timeouts += 1;
+                if state.timeouts < MAX_CONSECUTIVE_TIMEOUTS {
+                    return;
+                }
+                format!("{} calls in a row timed out", state.timeouts)
+            }
+            CallOutcome::Exited => "the server exited".to_string(),
+        };
+        if state.health != Health::Healthy {
+            return;
+        }
+        state.timeouts = 0;
+        if self.launcher.is_none() {
+            state.health = Health::Degraded;
+            drop(state);
+            self.report(McpServerStatus::Degraded, problem);
+            return;
+        }
+        // Dropping the client kills a server that is still running.
+        state.client = None;
+        state.health = Health::Restarting;
+        drop(state);
+        self.report(
+            McpServerStatus::Degraded,
+            format!("{problem}; restarting it"),
+        );
+        self.spawn_restart();
+    }
+
+    /// Starts the server again, backing off between attempts.
+    fn spawn_restart(self: &Arc<Self>) {
+        let Some(launcher) = self.launcher.clone() else {
+            return;
+        };
+        let managed = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut backoff = INITIAL_RESTART_BACKOFF;
+            let mut last_error = String::new();
+            for attempt in 1..=MAX_RESTART_ATTEMPTS {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                match launcher
+                    .start(managed.startup_timeout, managed.server_requests())
+                    .await
+                {
+                    Ok(client) => {
+                        {
+                            let mut state = managed.state.lock().await;
+                            state.client = Some(client);
+                            state.health = Health::Healthy;
+                        }
+                        managed.report(
+                            McpServerStatus::Healthy,
+                            format!("restarted (attempt {attempt})"),
+                        );
+                        managed.schedule_idle_stop().await;
+                        return;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to restart MCP server '{}' (attempt {attempt}): {e:#}",
+                            managed.server_name
+                        );
+                        last_error = format!("{e:#}");
+                    }
+                }
+            }
+            managed.state.lock().await.health = Health::Failed;
+            managed.report(
+                McpServerStatus::Failed,
+                format!("gave up after {MAX_RESTART_ATTEMPTS} restart attempts: {last_error}"),
+            );
+        });
+    }
+
+    fn report(&self, status: McpServerStatus, message: String) {
+        info!("MCP server '{}' is {status:?}: {message}", self.server_name);
+        if let Ok(mut changes) = self.status_changes.lock() {
+            changes.push(McpServerStatusEvent {
+                server: self.server_name.clone(),
+                status,
+                message,
+            });
+        }
+    }
+
+    /// Stops the server once it has been idle for `idle_timeout`, unless it
+    /// is called again in the meantime.
+    async fn schedule_idle_stop(self: &Arc<Self>) {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return;
+        };
+        let calls = self.state.lock().await.calls;
+        let managed = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(idle_timeout).await;
+            let mut state = managed.state.lock().await;
+            // A clone held elsewhere means a call is still running.
+            let idle = state.calls == calls
+                && state
+                    .client
+                    .as_ref()
+                    .is_some_and(|client| !client.is_shared());
+            if idle {
+                // Dropping the last handle kills the server process.
+                state.client = None;
+                info!("stopped idle MCP server '{}'", managed.server_name);
+            }
+        });
+    }
+}
+
+/// What is needed to start a stdio server again.
+#[derive(Clone)]
+struct StdioLauncher {
+    use_rmcp_client: bool,
+    command: String,
+    args: Vec<String>,
+    env: Option<HashMap<String, String>>,
+}
+
+impl StdioLauncher {
+    async fn start(
+        &self,
+        startup_timeout: Duration,
+        requests: ServerRequests,
+    ) -> Result<McpClientAdapter> {
+        McpClientAdapter::new_stdio_client(
+            self.use_rmcp_client,
+            self.command.clone().into(),
+            self.args.iter().map(Into::into).collect(),
+            self.env.clone(),
+            initialize_params(requests.sampling.is_some()),
+            startup_timeout,
+            requests,
+        )
+        .await
+    }
+
+    /// Identifies the server command, so cached tools are dropped when the
+    /// configuration changes.
+    fn fingerprint(&self) -> String {
+        let env: Option<BTreeMap<&String, &String>> =
+            self.env.as_ref().map(|env| env.iter().collect());
+        let mut hasher = Sha1::new();
+        hasher.update(format!("{:?}\0{:?}\0{env:?}", self.command, self.args).as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// The tools of lazily started servers, as of the last time each ran.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ToolsCache {
+    #[serde(default)]
+    servers: BTreeMap<String, CachedTools>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedTools {
+    fingerprint: String,
+    tools: Vec<Tool>,
+}
+
+impl ToolsCache {
+    /// Reads the cache; a missing or unreadable file is an empty cache.
+    fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn tools(&self, server_name: &str, launcher: &StdioLauncher) -> Option<Vec<Tool>> {
+        self.servers
+            .get(server_name)
+            .filter(|cached| cached.fingerprint == launcher.fingerprint())
+            .map(|cached| cached.tools.clone())
+    }
+
+    /// Records `(server name, fingerprint, tools)` entries in the cache at
+    /// `path`, keeping the other servers.
+    fn store(path: &Path, entries: impl IntoIterator<Item = (String, String, Vec<Tool>)>) {
+        let mut cache = Self::load(path);
+        for (server_name, fingerprint, tools) in entries {
+            cache
+                .servers
+                .insert(server_name, CachedTools { fingerprint, tools });
+        }
+        let result = serde_json::to_string(&cache)
+            .map_err(std::io::Error::other)
+            .and_then(|contents| std::fs::write(path, contents));
+        if let Err(e) = result {
+            warn!("Failed to write MCP tools cache {}: {e}", path.display());
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -107,32 +528,97 @@ impl McpClientAdapter {
         env: Option<HashMap<String, String>>,
         params: mcp_types::InitializeRequestParams,
         startup_timeout: Duration,
+        requests: ServerRequests,
     ) -> Result<Self> {
         info!(
             "new_stdio_client use_rmcp_client: {use_rmcp_client} program: {program:?} args: {args:?} env: {env:?} params: {params:?} startup_timeout: {startup_timeout:?}"
         );
+        let ServerRequests { roots, sampling } = requests;
         if use_rmcp_client {
-            let client = Arc::new(RmcpClient::new_stdio_client(program, args, env).await?);
+            let mut client = RmcpClient::new_stdio_client(program, args, env)
+                .await?
+                .with_roots(roots);
+            if let Some(sampling) = sampling {
+                client = client.with_sampling_handler(sampling);
+            }
+            let client = Arc::new(client);
             client.initialize(params, Some(startup_timeout)).await?;
             Ok(McpClientAdapter::Rmcp(client))
         } else {
-            let client = Arc::new(McpClient::new_stdio_client(program, args, env).await?);
+            let mut client = McpClient::new_stdio_client(program, args, env)
+                .await?
+                .with_roots(roots);
+            if let Some(sampling) = sampling {
+                client = client.with_sampling_handler(sampling);
+            }
+            let client = Arc::new(client);
             client.initialize(params, Some(startup_timeout)).await?;
             Ok(McpClientAdapter::Legacy(client))
         }
     }
 
-    async fn new_streamable_http_client(
-        url: String,
-        bearer_token: Option<String>,
+    /// HTTP servers always use the rmcp client; the legacy client only
+    /// speaks stdio.
+    async fn new_http_client(
+        transport: McpServerTransportConfig,
+        oauth: Option<Arc<McpOAuthTokenSource>>,
         params: mcp_types::InitializeRequestParams,
         startup_timeout: Duration,
+        requests: ServerRequests,
     ) -> Result<Self> {
-        let client = Arc::new(RmcpClient::new_streamable_http_client(url, bearer_token)?);
+        let client = match transport {
+            McpServerTransportConfig::StreamableHttp {
+                url,
+                bearer_token,
+                headers,
+            } => RmcpClient::new_streamable_http_client(url, bearer_token, headers)?,
+            McpServerTransportConfig::Sse {
+                url,
+                bearer_token,
+                headers,
+            } => RmcpClient::new_sse_client(url, bearer_token, headers)?,
+            McpServerTransportConfig::Stdio { .. } => {
+                return Err(anyhow!("not an HTTP transport"));
+            }
+        };
+        let client = match oauth {
+            Some(oauth) => client.with_bearer_token_source(Arc::new(move || {
+                let oauth = Arc::clone(&oauth);
+                async move { oauth.access_token().await }.boxed()
+            })),
+            None => client,
+        };
+        let mut client = client.with_roots(requests.roots);
+        if let Some(sampling) = requests.sampling {
+            client = client.with_sampling_handler(sampling);
+        }
+        let client = Arc::new(client);
         client.initialize(params, Some(startup_timeout)).await?;
         Ok(McpClientAdapter::Rmcp(client))
     }
 
+    async fn set_roots(&self, roots: Vec<Root>) -> Result<()> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.set_roots(roots).await,
+            McpClientAdapter::Rmcp(client) => client.set_roots(roots).await,
+        }
+    }
+
+    async fn is_closed(&self) -> bool {
+        match self {
+            McpClientAdapter::Legacy(client) => client.is_closed(),
+            McpClientAdapter::Rmcp(client) => client.is_closed().await,
+        }
+    }
+
+    /// Whether a call holds a clone of the client.
+    fn is_shared(&self) -> bool {
+        match self {
+            McpClientAdapter::Legacy(client) => Arc::strong_count(client) > 1,
+            McpClientAdapter::Rmcp(client) => Arc::strong_count(client) > 1,
+        }
+    }
+
     async fn list_tools(
         &self,
         params: Option<mcp_types::ListToolsRequestParams>,
@@ -164,10 +650,17 @@ pub(crate) struct McpConnectionManager {
     ///
     /// The server name originates from the keys of the `mcp_servers` map in
     /// the user configuration.
-    clients: HashMap<String, ManagedClient>,
+    clients: HashMap<String, Arc<ManagedClient>>,
 
     /// Fully qualified tool name -> tool instance.
     tools: HashMap<String, ToolInfo>,
+
+    /// Servers that crashed, were restarted or gave up, for the session to
+    /// report.
+    status_changes: StatusChanges,
+
+    /// Directories servers may operate on, see [`workspace_roots`].
+    roots: SharedRoots,
 }
 
 impl McpConnectionManager {
@@ -179,18 +672,38 @@ impl McpConnectionManager {
     ///
     /// Servers that fail to start are reported in `ClientStartErrors`: the
     /// user should be informed about these errors.
+    /// * `credential_store` – Where tokens from `codex mcp login` are kept.
+    /// * `lazy_start` – When set, stdio servers with cached tools are not
+    ///   spawned until one of their tools is called.
+    /// * `roots` – Directories servers may operate on, see [`workspace_roots`].
+    /// * `sampling` – Where `sampling/createMessage` requests are sent; when
+    ///   `None`, sampling is not offered to servers.
     pub async fn new(
         mcp_servers: HashMap<String, McpServerConfig>,
         use_rmcp_client: bool,
+        credential_store: Arc<dyn CredentialStore>,
+        lazy_start: Option<LazyStartConfig>,
+        roots: Vec<Root>,
+        sampling: Option<mpsc::Sender<McpSamplingRequest>>,
     ) -> Result<(Self, ClientStartErrors)> {
         // Early exit if no servers are configured.
         if mcp_servers.is_empty() {
             return Ok((Self::default(), ClientStartErrors::default()));
         }
 
+        let tools_cache = lazy_start
+            .as_ref()
+            .map(|lazy| ToolsCache::load(&lazy.tools_cache))
+            .unwrap_or_default();
+
         // Launch all configured servers concurrently.
         let mut join_set = JoinSet::new();
         let mut errors = ClientStartErrors::new();
+        let mut clients: HashMap<String, Arc<ManagedClient>> = HashMap::new();
+        let mut cached_tools: Vec<ToolInfo> = Vec::new();
+        let mut tool_filters: HashMap<String, McpServerConfig> = HashMap::new();
+        let status_changes = StatusChanges::default();
+        let shared_roots: SharedRoots = Arc::new(StdMutex::new(roots));
 
         for (server_name, cfg) in mcp_servers {
             // Validate server name before spawning
@@ -203,44 +716,81 @@ impl McpConnectionManager {
                 continue;
             }
 
-            if matches!(
-                cfg.transport,
-                McpServerTransportConfig::StreamableHttp { .. }
-            ) && !use_rmcp_client
-            {
-                info!(
-                    "skipping MCP server `{}` configured with url because rmcp client is disabled",
-                    server_name
-                );
-                continue;
+            if cfg.enabled_tools.is_some() || cfg.disabled_tools.is_some() {
+                tool_filters.insert(server_name.clone(), cfg.clone());
             }
 
             let startup_timeout = cfg.startup_timeout_sec.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
             let tool_timeout = cfg.tool_timeout_sec.unwrap_or(DEFAULT_TOOL_TIMEOUT);
 
+            let launcher = match &cfg.transport {
+                McpServerTransportConfig::Stdio { command, args, env } => Some(StdioLauncher {
+                    use_rmcp_client,
+                    command: command.clone(),
+                    args: args.clone(),
+                    env: env.clone(),
+                }),
+                _ => None,
+            };
+            // Only stdio servers start lazily; they are the ones with a
+            // launcher.
+            let lazy = lazy_start.as_ref().filter(|_| launcher.is_some());
+            let managed = ManagedClient {
+                server_name: server_name.clone(),
+                state: Mutex::default(),
+                tools_cache: lazy.map(|lazy| lazy.tools_cache.clone()),
+                idle_timeout: lazy.and_then(|lazy| lazy.idle_timeout),
+                launcher,
+                startup_timeout,
+                tool_timeout: Some(tool_timeout),
+                status_changes: Arc::clone(&status_changes),
+                roots: Arc::clone(&shared_roots),
+                sampling: sampling
+                    .as_ref()
+                    .map(|requests| sampling_handler(server_name.clone(), requests.clone())),
+            };
+
+            // Servers whose tools are known start when one is first called.
+            if let Some(launcher) = &managed.launcher
+                && managed.tools_cache.is_some()
+                && let Some(tools) = tools_cache.tools(&server_name, launcher)
+            {
+                cached_tools.extend(tools.into_iter().map(|tool| ToolInfo {
+                    server_name: server_name.clone(),
+                    tool_name: tool.name.clone(),
+                    tool,
+                }));
+                clients.insert(server_name, Arc::new(managed));
+                continue;
+            }
+
+            // HTTP servers without a configured bearer token use the OAuth
+            // tokens stored for them, if any.
+            let (oauth, suggest_login) = match &cfg.transport {
+                McpServerTransportConfig::StreamableHttp {
+                    url,
+                    bearer_token: None,
+                    ..
+                }
+                | McpServerTransportConfig::Sse {
+                    url,
+                    bearer_token: None,
+                    ..
+                } => {
+                    let oauth =
+                        McpOAuthTokenSource::load(Arc::clone(&credential_store), &server_name, url)
+                            .map(Arc::new);
+                    let suggest_login = oauth.is_none();
+                    (oauth, suggest_login)
+                }
+                _ => (None, false),
+            };
+
             let use_rmcp_client_flag = use_rmcp_client;
+            let requests = managed.server_requests();
             join_set.spawn(async move {
                 let McpServerConfig { transport, .. } = cfg;
-                let params = mcp_types::InitializeRequestParams {
-                    capabilities: ClientCapabilities {
-                        experimental: None,
-                        roots: None,
-                        sampling: None,
-                        // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
-                        // indicates this should be an empty object.
-                        elicitation: Some(json!({})),
-                    },
-                    client_info: Implementation {
-                        name: "codex-mcp-client".to_owned(),
-                        version: env!("CARGO_PKG_VERSION").to_owned(),
-                        title: Some("Codex".into()),
-                        // This field is used by Codex when it is an MCP
-                        // server: it should not be used when Codex is
-                        // an MCP client.
-                        user_agent: None,
-                    },
-                    protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                };
+                let params = initialize_params(requests.sampling.is_some());
 
                 let client = match transport {
                     McpServerTransportConfig::Stdio { command, args, env } => {
@@ -253,29 +803,40 @@ impl McpConnectionManager {
                             env,
                             params.clone(),
                             startup_timeout,
+                            requests,
                         )
                         .await
                     }
-                    McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
-                        McpClientAdapter::new_streamable_http_client(
-                            url,
-                            bearer_token,
+                    transport @ (McpServerTransportConfig::StreamableHttp { .. }
+                    | McpServerTransportConfig::Sse { .. }) => {
+                        McpClientAdapter::new_http_client(
+                            transport,
+                            oauth,
                             params,
                             startup_timeout,
+                            requests,
                         )
                         .await
                     }
                 }
-                .map(|c| (c, startup_timeout));
+                .map_err(|err| {
+                    if suggest_login {
+                        anyhow!(
+                            "{err:#}; if the server requires signing in, run `codex mcp login {server_name}`"
+                        )
+                    } else {
+                        err
+                    }
+                });
 
-                ((server_name, tool_timeout), client)
+                (server_name, managed, client)
             });
         }
 
-        let mut clients: HashMap<String, ManagedClient> = HashMap::with_capacity(join_set.len());
+        let mut started = Vec::with_capacity(join_set.len());
 
         while let Some(res) = join_set.join_next().await {
-            let ((server_name, tool_timeout), client_res) = match res {
+            let (server_name, mut managed, client_res) = match res {
                 Ok(result) => result,
                 Err(e) => {
                     warn!("Task panic when starting MCP server: {e:#}");
@@ -284,15 +845,10 @@ impl McpConnectionManager {
             };
 
             match client_res {
-                Ok((client, startup_timeout)) => {
-                    clients.insert(
-                        server_name,
-                        ManagedClient {
-                            client,
-                            startup_timeout,
-                            tool_timeout: Some(tool_timeout),
-                        },
-                    );
+                Ok(client) => {
+                    started.push((server_name.clone(), client.clone(), managed.startup_timeout));
+                    managed.state.get_mut().client = Some(client);
+                    clients.insert(server_name, Arc::new(managed));
                 }
                 Err(e) => {
                     errors.insert(server_name, e);
@@ -300,7 +856,7 @@ impl McpConnectionManager {
             }
         }
 
-        let all_tools = match list_all_tools(&clients).await {
+        let mut all_tools = match list_all_tools(started).await {
             Ok(tools) => tools,
             Err(e) => {
                 warn!("Failed to list tools from some MCP servers: {e:#}");
@@ -308,9 +864,57 @@ impl McpConnectionManager {
             }
         };
 
+        // Remember the tools of servers that can start lazily, so the next
+        // session does not have to spawn them.
+        if let Some(lazy) = &lazy_start {
+            let mut entries: HashMap<&str, (String, Vec<Tool>)> = HashMap::new();
+            for tool in &all_tools {
+                if let Some(launcher) = clients
+                    .get(&tool.server_name)
+                    .filter(|managed| managed.tools_cache.is_some())
+                    .and_then(|managed| managed.launcher.as_ref())
+                {
+                    entries
+                        .entry(tool.server_name.as_str())
+                        .or_insert_with(|| (launcher.fingerprint(), Vec::new()))
+                        .1
+                        .push(tool.tool.clone());
+                }
+            }
+            if !entries.is_empty() {
+                ToolsCache::store(
+                    &lazy.tools_cache,
+                    entries
+                        .into_iter()
+                        .map(|(name, (fingerprint, tools))| (name.to_string(), fingerprint, tools)),
+                );
+            }
+            for managed in clients.values() {
+                if managed.state.lock().await.client.is_some() {
+                    managed.schedule_idle_stop().await;
+                }
+            }
+        }
+
+        all_tools.extend(cached_tools);
+        // The cache keeps every tool, so changing the filters takes effect
+        // without starting the server.
+        all_tools.retain(|tool| {
+            tool_filters
+                .get(&tool.server_name)
+                .is_none_or(|cfg| cfg.is_tool_enabled(&tool.tool_name))
+        });
         let tools = qualify_tools(all_tools);
 
-        Ok((Self { clients, tools }, errors))
+        Ok((
+            Self {
+                clients,
+                tools,
+                status_changes,
+                roots: shared_roots,
+            },
+            errors,
+        ))
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
@@ -322,7 +926,8 @@ impl McpConnectionManager {
             .collect()
     }
 
-    /// Invoke the tool indicated by the (server, tool) pair.
+    /// Invoke the tool indicated by the (server, tool) pair, starting the
+    /// server first if it is not running.
     pub async fn call_tool(
         &self,
         server: &str,
@@ -333,13 +938,54 @@ impl McpConnectionManager {
             .clients
             .get(server)
             .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
-        let client = managed.client.clone();
+        let client = managed
+            .acquire()
+            .await
+            .with_context(|| format!("failed to start MCP server `{server}`"))?;
         let timeout = managed.tool_timeout;
 
-        client
+        let start = Instant::now();
+        let result = client
             .call_tool(tool.to_string(), arguments, timeout)
             .await
-            .with_context(|| format!("tool call failed for `{server}/{tool}`"))
+            .with_context(|| format!("tool call failed for `{server}/{tool}`"));
+        let outcome = match &result {
+            Ok(_) => CallOutcome::Succeeded,
+            Err(_) if client.is_closed().await => CallOutcome::Exited,
+            Err(_) if timeout.is_some_and(|timeout| start.elapsed() >= timeout) => {
+                CallOutcome::TimedOut
+            }
+            Err(_) => CallOutcome::Failed,
+        };
+        drop(client);
+        managed.record_call(outcome).await;
+        managed.schedule_idle_stop().await;
+        result
+    }
+
+    /// Replaces the roots and tells the running servers. Servers started
+    /// later get the new roots right away.
+    pub(crate) async fn set_roots(&self, roots: Vec<Root>) {
+        match self.roots.lock() {
+            Ok(mut current) if *current != roots => *current = roots.clone(),
+            _ => return,
+        }
+        for (server_name, managed) in &self.clients {
+            let client = managed.state.lock().await.client.clone();
+            if let Some(client) = client
+                && let Err(e) = client.set_roots(roots.clone()).await
+            {
+                warn!("Failed to update the roots of MCP server '{server_name}': {e:#}");
+            }
+        }
+    }
+
+    /// Health changes of servers since the last call, oldest first.
+    pub(crate) fn take_status_changes(&self) -> Vec<McpServerStatusEvent> {
+        self.status_changes
+            .lock()
+            .map(|mut changes| std::mem::take(&mut *changes))
+            .unwrap_or_default()
     }
 
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
@@ -349,21 +995,48 @@ impl McpConnectionManager {
     }
 }
 
+/// Parameters of the `initialize` request; `sampling` says whether the
+/// client answers `sampling/createMessage`.
+fn initialize_params(sampling: bool) -> mcp_types::InitializeRequestParams {
+    mcp_types::InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: Some(ClientCapabilitiesRoots {
+                list_changed: Some(true),
+            }),
+            sampling: sampling.then(|| json!({})),
+            // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
+            // indicates this should be an empty object.
+            elicitation: Some(json!({})),
+        },
+        client_info: Implementation {
+            name: "codex-mcp-client".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            title: Some("Codex".into()),
+            // This field is used by Codex when it is an MCP
+            // server: it should not be used when Codex is
+            // an MCP client.
+            user_agent: None,
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    }
+}
+
 /// Query every server for its available tools and return a single map that
 /// contains **all** tools. Each key is the fully-qualified name for the tool.
-async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<ToolInfo>> {
+async fn list_all_tools(
+    clients: Vec<(String, McpClientAdapter, Duration)>,
+) -> Result<Vec<ToolInfo>> {
     let mut join_set = JoinSet::new();
+    let server_count = clients.len();
 
     // Spawn one task per server so we can query them concurrently. This
     // keeps the overall latency roughly at the slowest server instead of
     // the cumulative latency.
-    for (server_name, managed_client) in clients {
-        let server_name_cloned = server_name.clone();
-        let client_clone = managed_client.client.clone();
-        let startup_timeout = managed_client.startup_timeout;
+    for (server_name, client, startup_timeout) in clients {
         join_set.spawn(async move {
-            let res = client_clone.list_tools(None, Some(startup_timeout)).await;
-            (server_name_cloned, res)
+            let res = client.list_tools(None, Some(startup_timeout)).await;
+            (server_name, res)
         });
     }
 
@@ -397,7 +1070,7 @@ async fn list_all_tools(clients: &HashMap<String, ManagedClient>) -> Result<Vec<
     info!(
         "aggregated {} tools from {} servers",
         aggregated.len(),
-        clients.len()
+        server_count
     );
 
     Ok(aggregated)
@@ -496,4 +1169,98 @@ mod tests {
             "my_server__yet_another_e1c3987bd9c50b826cbe1687966f79f0c602d19ca"
         );
     }
+
+    #[test]
+    fn tools_cache_is_keyed_by_server_command() {
+        let codex_home = tempfile::TempDir::new().expect("create temp dir");
+        let path = codex_home.path().join(MCP_TOOLS_CACHE_FILE);
+        let launcher = StdioLauncher {
+            use_rmcp_client: false,
+            command: "docs-server".to_string(),
+            args: vec!["--stdio".to_string()],
+            env: None,
+        };
+        let tool = create_test_tool("docs", "search").tool;
+
+        assert!(ToolsCache::load(&path).tools("docs", &launcher).is_none());
+
+        ToolsCache::store(
+            &path,
+            [(
+                "docs".to_string(),
+                launcher.fingerprint(),
+                vec![tool.clone()],
+            )],
+        );
+        let cache = ToolsCache::load(&path);
+        assert_eq!(cache.tools("docs", &launcher), Some(vec![tool]));
+
+        let changed = StdioLauncher {
+            args: vec!["--stdio".to_string(), "--verbose".to_string()],
+            ..launcher
+        };
+        assert!(cache.tools("docs", &changed).is_none());
+    }
+
+    #[tokio::test]
+    async fn repeated_timeouts_degrade_a_server_until_it_answers() {
+        let managed = Arc::new(ManagedClient {
+            server_name: "docs".to_string(),
+            state: Mutex::default(),
+            launcher: None,
+            tools_cache: None,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
+            tool_timeout: Some(DEFAULT_TOOL_TIMEOUT),
+            idle_timeout: None,
+            status_changes: StatusChanges::default(),
+            roots: SharedRoots::default(),
+            sampling: None,
+        });
+        let statuses = |managed: &ManagedClient| -> Vec<McpServerStatus> {
+            let mut changes = managed.status_changes.lock().expect("lock status changes");
+            changes.drain(..).map(|change| change.status).collect()
+        };
+
+        managed.record_call(CallOutcome::TimedOut).await;
+        managed.record_call(CallOutcome::Failed).await;
+        managed.record_call(CallOutcome::TimedOut).await;
+        assert_eq!(statuses(&managed), Vec::new());
+
+        managed.record_call(CallOutcome::TimedOut).await;
+        assert_eq!(statuses(&managed), vec![McpServerStatus::Degraded]);
+        assert_eq!(managed.state.lock().await.health, Health::Degraded);
+
+        managed.record_call(CallOutcome::Succeeded).await;
+        assert_eq!(statuses(&managed), vec![McpServerStatus::Healthy]);
+        assert_eq!(managed.state.lock().await.health, Health::Healthy);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn workspace_roots_include_cwd_and_writable_roots() {
+        let cwd = PathBuf::from("/home/dev/app");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![PathBuf::from("/home/dev/app"), PathBuf::from("/srv/data")],
+            network_access: false,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            network_allowlist: Vec::new(),
+            deny: Vec::new(),
+        };
+
+        assert_eq!(
+            workspace_roots(&cwd, &policy),
+            vec![
+                Root {
+                    name: Some("app".to_string()),
+                    uri: "file:///home/dev/app".to_string(),
+                },
+                Root {
+                    name: Some("data".to_string()),
+                    uri: "file:///srv/data".to_string(),
+                },
+            ]
+        );
+        assert_eq!(workspace_roots(&cwd, &SandboxPolicy::ReadOnly).len(), 1);
+    }
 }
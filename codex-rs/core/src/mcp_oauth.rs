@@ -0,0 +1,544 @@
+//! OAuth for MCP servers reached over HTTP.
+//!
+//! Follows the authorization flow of the MCP specification
+//! (https://modelcontextprotocol.io/specification/2025-06-18/basic/authorization):
+//! the authorization server is found through the MCP server's protected
+//! resource metadata, Codex registers itself as a public client when the
+//! server supports dynamic client registration, and the user signs in in the
+//! browser with PKCE. `codex mcp login` drives the interactive part; this
+//! module holds the protocol steps, token storage and refresh.
+//!
+//! Tokens are stored with the other credentials in the configured
+//! [`CredentialStore`], keyed by server name.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::credential_store::CredentialStore;
+use crate::default_client::get_codex_user_agent;
+
+/// Access tokens are refreshed when they expire within this margin.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What is stored for a server after signing in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct McpOAuthCredentials {
+    /// The MCP server URL the tokens were issued for.
+    pub resource: String,
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub token_endpoint: String,
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl McpOAuthCredentials {
+    fn expires_soon(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| {
+            expires_at - Utc::now() < chrono::Duration::from_std(REFRESH_MARGIN).unwrap_or_default()
+        })
+    }
+}
+
+/// Authorization server metadata (RFC 8414), reduced to what the flow needs.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct AuthorizationServerMetadata {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    #[serde(default)]
+    pub registration_endpoint: Option<String>,
+    #[serde(default)]
+    pub scopes_supported: Option<Vec<String>>,
+}
+
+/// A client registered with an authorization server.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RegisteredClient {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProtectedResourceMetadata {
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(get_codex_user_agent())
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
+/// Finds the authorization server of the MCP server at `server_url`.
+///
+/// Servers that predate protected resource metadata are assumed to be their
+/// own authorization server, with the default `/authorize`, `/token` and
+/// `/register` endpoints when they publish no metadata either.
+pub async fn discover_authorization_server(
+    client: &reqwest::Client,
+    server_url: &str,
+) -> io::Result<AuthorizationServerMetadata> {
+    let server_url = parse_url(server_url)?;
+    let mut issuer = origin(&server_url);
+    for url in well_known_urls(&server_url, "oauth-protected-resource") {
+        if let Some(metadata) = get_json::<ProtectedResourceMetadata>(client, &url).await
+            && let Some(authorization_server) = metadata.authorization_servers.into_iter().next()
+        {
+            issuer = parse_url(&authorization_server)?;
+            break;
+        }
+    }
+
+    let candidates = well_known_urls(&issuer, "oauth-authorization-server")
+        .into_iter()
+        .chain(well_known_urls(&issuer, "openid-configuration"));
+    for url in candidates {
+        if let Some(metadata) = get_json::<AuthorizationServerMetadata>(client, &url).await {
+            return Ok(metadata);
+        }
+    }
+
+    let base = origin(&issuer);
+    let endpoint = |path: &str| {
+        base.join(path)
+            .map(|url| url.to_string())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    };
+    Ok(AuthorizationServerMetadata {
+        authorization_endpoint: endpoint("/authorize")?,
+        token_endpoint: endpoint("/token")?,
+        registration_endpoint: Some(endpoint("/register")?),
+        scopes_supported: None,
+    })
+}
+
+/// Registers Codex as a public client that redirects to `redirect_uri`
+/// (dynamic client registration, RFC 7591).
+pub async fn register_client(
+    client: &reqwest::Client,
+    metadata: &AuthorizationServerMetadata,
+    redirect_uri: &str,
+) -> io::Result<RegisteredClient> {
+    let Some(registration_endpoint) = &metadata.registration_endpoint else {
+        return Err(io::Error::other(
+            "the authorization server does not support dynamic client registration",
+        ));
+    };
+    let response = client
+        .post(registration_endpoint)
+        .json(&serde_json::json!({
+            "client_name": "Codex",
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        }))
+        .send()
+        .await
+        .map_err(io::Error::other)?;
+    if !response.status().is_success() {
+        return Err(io::Error::other(format!(
+            "client registration failed with status {}",
+            response.status()
+        )));
+    }
+    response.json().await.map_err(io::Error::other)
+}
+
+/// Parameters of the browser sign-in.
+pub struct AuthorizationRequest<'a> {
+    pub client_id: &'a str,
+    pub redirect_uri: &'a str,
+    pub code_challenge: &'a str,
+    pub state: &'a str,
+    /// The MCP server URL (RFC 8707 resource indicator).
+    pub resource: &'a str,
+}
+
+/// The URL to open in the browser to sign in.
+pub fn authorization_url(
+    metadata: &AuthorizationServerMetadata,
+    request: &AuthorizationRequest<'_>,
+) -> io::Result<String> {
+    let mut url = parse_url(&metadata.authorization_endpoint)?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", request.client_id)
+            .append_pair("redirect_uri", request.redirect_uri)
+            .append_pair("code_challenge", request.code_challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", request.state)
+            .append_pair("resource", request.resource);
+        if let Some(scopes) = &metadata.scopes_supported
+            && !scopes.is_empty()
+        {
+            query.append_pair("scope", &scopes.join(" "));
+        }
+    }
+    Ok(url.to_string())
+}
+
+/// Exchanges the authorization `code` from the redirect for tokens.
+pub async fn exchange_code(
+    client: &reqwest::Client,
+    metadata: &AuthorizationServerMetadata,
+    registered: &RegisteredClient,
+    request: &AuthorizationRequest<'_>,
+    code: &str,
+    code_verifier: &str,
+) -> io::Result<McpOAuthCredentials> {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", request.redirect_uri),
+        ("client_id", registered.client_id.as_str()),
+        ("code_verifier", code_verifier),
+        ("resource", request.resource),
+    ];
+    if let Some(secret) = &registered.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+    let tokens = request_tokens(client, &metadata.token_endpoint, &form).await?;
+    Ok(McpOAuthCredentials {
+        resource: request.resource.to_string(),
+        client_id: registered.client_id.clone(),
+        client_secret: registered.client_secret.clone(),
+        token_endpoint: metadata.token_endpoint.clone(),
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_at: expires_at(tokens.expires_in),
+    })
+}
+
+/// Gets a new access token with the refresh token of `credentials`.
+pub async fn refresh_credentials(
+    client: &reqwest::Client,
+    credentials: &McpOAuthCredentials,
+) -> io::Result<McpOAuthCredentials> {
+    let Some(refresh_token) = &credentials.refresh_token else {
+        return Err(io::Error::other("no refresh token; sign in again"));
+    };
+    let mut form = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+        ("client_id", credentials.client_id.as_str()),
+        ("resource", credentials.resource.as_str()),
+    ];
+    if let Some(secret) = &credentials.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+    let tokens = request_tokens(client, &credentials.token_endpoint, &form).await?;
+    Ok(McpOAuthCredentials {
+        access_token: tokens.access_token,
+        // Servers that do not rotate refresh tokens omit them.
+        refresh_token: tokens
+            .refresh_token
+            .or_else(|| credentials.refresh_token.clone()),
+        expires_at: expires_at(tokens.expires_in),
+        ..credentials.clone()
+    })
+}
+
+async fn request_tokens(
+    client: &reqwest::Client,
+    token_endpoint: &str,
+    form: &[(&str, &str)],
+) -> io::Result<TokenResponse> {
+    let response = client
+        .post(token_endpoint)
+        .form(form)
+        .send()
+        .await
+        .map_err(io::Error::other)?;
+    if !response.status().is_success() {
+        return Err(io::Error::other(format!(
+            "token endpoint returned status {}",
+            response.status()
+        )));
+    }
+    response.json().await.map_err(io::Error::other)
+}
+
+/// The stored credentials for `server`, if any.
+pub fn load_mcp_oauth_credentials(
+    store: &dyn CredentialStore,
+    server: &str,
+) -> io::Result<Option<McpOAuthCredentials>> {
+    Ok(store
+        .load()?
+        .and_then(|mut auth_dot_json| auth_dot_json.mcp_oauth.remove(server)))
+}
+
+pub fn save_mcp_oauth_credentials(
+    store: &dyn CredentialStore,
+    server: &str,
+    credentials: McpOAuthCredentials,
+) -> io::Result<()> {
+    let mut auth_dot_json = store.load()?.unwrap_or_default();
+    auth_dot_json
+        .mcp_oauth
+        .insert(server.to_string(), credentials);
+    store.save(&auth_dot_json)
+}
+
+/// Returns `Ok(true)` if credentials for `server` were removed.
+pub fn remove_mcp_oauth_credentials(store: &dyn CredentialStore, server: &str) -> io::Result<bool> {
+    let Some(mut auth_dot_json) = store.load()? else {
+        return Ok(false);
+    };
+    if auth_dot_json.mcp_oauth.remove(server).is_none() {
+        return Ok(false);
+    }
+    store.save(&auth_dot_json)?;
+    Ok(true)
+}
+
+/// MCP credentials in `store`, so that a fresh login does not discard them.
+/// Returns an empty map if nothing is stored or the store is unreadable.
+pub fn stored_mcp_oauth_credentials(
+    store: &dyn CredentialStore,
+) -> BTreeMap<String, McpOAuthCredentials> {
+    store
+        .load()
+        .ok()
+        .flatten()
+        .map(|auth| auth.mcp_oauth)
+        .unwrap_or_default()
+}
+
+/// Supplies the access token of one server for a session, refreshing it
+/// shortly before it expires and saving the refreshed tokens.
+pub(crate) struct McpOAuthTokenSource {
+    store: Arc<dyn CredentialStore>,
+    server: String,
+    client: reqwest::Client,
+    credentials: Mutex<McpOAuthCredentials>,
+}
+
+impl McpOAuthTokenSource {
+    /// Returns `None` unless credentials for `server` at `url` are stored.
+    pub(crate) fn load(store: Arc<dyn CredentialStore>, server: &str, url: &str) -> Option<Self> {
+        let credentials = match load_mcp_oauth_credentials(store.as_ref(), server) {
+            Ok(Some(credentials)) => credentials,
+            Ok(None) => return None,
+            Err(err) => {
+                warn!("failed to load OAuth credentials for MCP server {server}: {err}");
+                return None;
+            }
+        };
+        // Tokens for another URL are of no use if the server entry changed.
+        if credentials.resource != url {
+            return None;
+        }
+        Some(Self {
+            store,
+            server: server.to_string(),
+            client: http_client(),
+            credentials: Mutex::new(credentials),
+        })
+    }
+
+    pub(crate) async fn access_token(&self) -> Option<String> {
+        let mut credentials = self.credentials.lock().await;
+        if credentials.expires_soon() && credentials.refresh_token.is_some() {
+            match refresh_credentials(&self.client, &credentials).await {
+                Ok(refreshed) => {
+                    if let Err(err) = save_mcp_oauth_credentials(
+                        self.store.as_ref(),
+                        &self.server,
+                        refreshed.clone(),
+                    ) {
+                        warn!(
+                            "failed to save refreshed tokens for MCP server {}: {err}",
+                            self.server
+                        );
+                    }
+                    *credentials = refreshed;
+                }
+                Err(err) => {
+                    warn!(
+                        "failed to refresh tokens for MCP server {}: {err}",
+                        self.server
+                    );
+                }
+            }
+        }
+        Some(credentials.access_token.clone())
+    }
+}
+
+fn expires_at(expires_in: Option<i64>) -> Option<DateTime<Utc>> {
+    expires_in.map(|seconds| Utc::now() + chrono::Duration::seconds(seconds))
+}
+
+fn parse_url(url: &str) -> io::Result<Url> {
+    Url::parse(url).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+fn origin(url: &Url) -> Url {
+    let mut origin = url.clone();
+    origin.set_path("/");
+    origin.set_query(None);
+    origin.set_fragment(None);
+    origin
+}
+
+/// Well-known metadata URLs for `url`, most specific first: the suffix is
+/// inserted between the origin and the path (RFC 8414 section 3), then tried
+/// at the root.
+fn well_known_urls(url: &Url, suffix: &str) -> Vec<String> {
+    let path = url.path().trim_end_matches('/');
+    let root = origin(url);
+    let mut urls = Vec::new();
+    if !path.is_empty()
+        && let Ok(with_path) = root.join(&format!("/.well-known/{suffix}{path}"))
+    {
+        urls.push(with_path.to_string());
+    }
+    if let Ok(at_root) = root.join(&format!("/.well-known/{suffix}")) {
+        urls.push(at_root.to_string());
+    }
+    urls
+}
+
+/// `None` when the document is missing or not the expected JSON.
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Option<T> {
+    let response = client.get(url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_string_contains;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    #[test]
+    fn well_known_urls_insert_the_suffix_before_the_path() {
+        let url = Url::parse("https://mcp.example.com/v1/mcp").expect("url");
+
+        assert_eq!(
+            well_known_urls(&url, "oauth-protected-resource"),
+            vec![
+                "https://mcp.example.com/.well-known/oauth-protected-resource/v1/mcp".to_string(),
+                "https://mcp.example.com/.well-known/oauth-protected-resource".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn discovers_the_authorization_server_of_a_resource() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/oauth-protected-resource/mcp"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "resource": format!("{}/mcp", server.uri()),
+                "authorization_servers": [format!("{}/auth", server.uri())]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/oauth-authorization-server/auth"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": format!("{}/auth", server.uri()),
+                "authorization_endpoint": format!("{}/auth/authorize", server.uri()),
+                "token_endpoint": format!("{}/auth/token", server.uri()),
+                "registration_endpoint": format!("{}/auth/register", server.uri())
+            })))
+            .mount(&server)
+            .await;
+
+        let metadata =
+            discover_authorization_server(&http_client(), &format!("{}/mcp", server.uri()))
+                .await
+                .expect("discover");
+
+        assert_eq!(
+            metadata,
+            AuthorizationServerMetadata {
+                authorization_endpoint: format!("{}/auth/authorize", server.uri()),
+                token_endpoint: format!("{}/auth/token", server.uri()),
+                registration_endpoint: Some(format!("{}/auth/register", server.uri())),
+                scopes_supported: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_the_refresh_token_when_none_is_returned() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=refresh-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "access-2",
+                "token_type": "Bearer",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let credentials = McpOAuthCredentials {
+            resource: "https://mcp.example.com/mcp".to_string(),
+            client_id: "codex".to_string(),
+            client_secret: None,
+            token_endpoint: format!("{}/token", server.uri()),
+            access_token: "access-1".to_string(),
+            refresh_token: Some("refresh-1".to_string()),
+            expires_at: Some(Utc::now()),
+        };
+        assert!(credentials.expires_soon());
+
+        let refreshed = refresh_credentials(&http_client(), &credentials)
+            .await
+            .expect("refresh");
+
+        assert_eq!(refreshed.access_token, "access-2");
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(!refreshed.expires_soon());
+    }
+}
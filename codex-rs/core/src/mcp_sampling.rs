@@ -0,0 +1,314 @@
+//! Sampling for MCP servers: `sampling/createMessage` requests are answered
+//! with the session's model, provider and auth, so servers that want a
+//! completion do not need API keys of their own.
+//!
+//! Requests reach the session through a channel and are checked against
+//! `[mcp_sampling]`: unless `approval = "allow"`, the user approves each
+//! request (or every request of a server for the rest of the session), and
+//! all servers share a per-session token budget.
+
+use std::sync::Arc;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_rmcp_client::SamplingHandler;
+use futures::FutureExt;
+use futures::StreamExt;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
+use mcp_types::CreateMessageResultContent;
+use mcp_types::Role;
+use mcp_types::SamplingMessageContent;
+use mcp_types::TextContent;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tracing::info;
+
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config_types::McpSampling;
+use crate::config_types::McpSamplingApproval;
+use crate::protocol::AskForApproval;
+use crate::protocol::McpSamplingApprovalRequestEvent;
+use crate::protocol::ReviewDecision;
+
+/// Instructions for requests that do not bring a system prompt.
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful assistant.";
+
+/// Capacity of the channel sampling requests reach the session through.
+pub(crate) const MCP_SAMPLING_CHANNEL_CAPACITY: usize = 16;
+
+/// A `sampling/createMessage` request from an MCP server, waiting for the
+/// session to answer it.
+pub(crate) struct McpSamplingRequest {
+    pub(crate) server: String,
+    pub(crate) params: CreateMessageRequestParams,
+    pub(crate) reply: oneshot::Sender<Result<CreateMessageResult, String>>,
+}
+
+/// The handler given to the client of `server`: it forwards the server's
+/// requests to the session over `requests`.
+pub(crate) fn sampling_handler(
+    server: String,
+    requests: mpsc::Sender<McpSamplingRequest>,
+) -> SamplingHandler {
+    Arc::new(move |params| {
+        let server = server.clone();
+        let requests = requests.clone();
+        async move {
+            let (reply, rx) = oneshot::channel();
+            let request = McpSamplingRequest {
+                server,
+                params,
+                reply,
+            };
+            if requests.send(request).await.is_err() {
+                return Err("the Codex session has ended".to_string());
+            }
+            rx.await
+                .unwrap_or_else(|_| Err("the Codex session has ended".to_string()))
+        }
+        .boxed()
+    })
+}
+
+/// Answers `request` with the model of `turn_context`, if the settings, the
+/// user and the budget allow it.
+pub(crate) async fn handle_sampling_request(
+    sess: Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    settings: McpSampling,
+    request: McpSamplingRequest,
+) {
+    let McpSamplingRequest {
+        server,
+        params,
+        reply,
+    } = request;
+    let result = sample(&sess, &turn_context, settings, &server, params).await;
+    if let Err(e) = &result {
+        info!("refused sampling request from MCP server '{server}': {e}");
+    }
+    let _ = reply.send(result);
+}
+
+async fn sample(
+    sess: &Session,
+    turn_context: &TurnContext,
+    settings: McpSampling,
+    server: &str,
+    params: CreateMessageRequestParams,
+) -> Result<CreateMessageResult, String> {
+    let used = sess.mcp_sampling_tokens().await;
+    let remaining = settings.max_session_tokens.saturating_sub(used);
+    let requested = u64::try_from(params.max_tokens).unwrap_or(0);
+    if requested > remaining {
+        return Err(format!(
+            "the sampling budget is spent: {remaining} of {} tokens left, {requested} requested",
+            settings.max_session_tokens
+        ));
+    }
+
+    let input = sampling_input(&params)?;
+    match settings.approval {
+        McpSamplingApproval::Allow => {}
+        McpSamplingApproval::Deny => return Err("sampling is disabled".to_string()),
+        McpSamplingApproval::Ask if turn_context.approval_policy == AskForApproval::Never => {
+            return Err("sampling needs approval, but the user cannot be asked".to_string());
+        }
+        McpSamplingApproval::Ask => {
+            let event = McpSamplingApprovalRequestEvent {
+                server: server.to_string(),
+                prompt: prompt_text(&params),
+                system_prompt: params.system_prompt.clone(),
+                max_tokens: params.max_tokens,
+            };
+            match sess.request_mcp_sampling_approval(event).await {
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    return Err("the user declined the sampling request".to_string());
+                }
+            }
+        }
+    }
+
+    let prompt = Prompt {
+        input,
+        base_instructions_override: Some(
+            params
+                .system_prompt
+                .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string()),
+        ),
+        ..Prompt::default()
+    };
+    let client = &turn_context.client;
+    let mut stream = client
+        .stream(&prompt)
+        .await
+        .map_err(|e| format!("model request failed: {e}"))?;
+    let mut text = String::new();
+    loop {
+        match stream.next().await {
+            Some(Ok(ResponseEvent::OutputItemDone(ResponseItem::Message { content, .. }))) => {
+                for item in content {
+                    if let ContentItem::OutputText { text: part } = item {
+                        text.push_str(&part);
+                    }
+                }
+            }
+            Some(Ok(ResponseEvent::Completed { token_usage, .. })) => {
+                let tokens = token_usage.map_or(requested, |usage| usage.total_tokens);
+                sess.add_mcp_sampling_tokens(tokens).await;
+                break;
+            }
+            Some(Ok(_)) => {}
+            Some(Err(e)) => return Err(format!("model request failed: {e}")),
+            None => return Err("the model stream ended early".to_string()),
+        }
+    }
+
+    Ok(CreateMessageResult {
+        content: CreateMessageResultContent::TextContent(TextContent {
+            annotations: None,
+            text,
+            r#type: "text".to_string(),
+        }),
+        model: client.get_model(),
+        role: Role::Assistant,
+        stop_reason: Some("endTurn".to_string()),
+    })
+}
+
+/// The messages of a request as model input. Text and images are supported.
+fn sampling_input(params: &CreateMessageRequestParams) -> Result<Vec<ResponseItem>, String> {
+    params
+        .messages
+        .iter()
+        .map(|message| {
+            let (role, content) = match (&message.role, &message.content) {
+                // Audio parses as image content too; tell them apart by type.
+                (_, SamplingMessageContent::ImageContent(content)) if content.r#type != "image" => {
+                    return Err(format!("{} content is not supported", content.r#type));
+                }
+                (Role::User, SamplingMessageContent::TextContent(content)) => (
+                    "user",
+                    ContentItem::InputText {
+                        text: content.text.clone(),
+                    },
+                ),
+                (Role::Assistant, SamplingMessageContent::TextContent(content)) => (
+                    "assistant",
+                    ContentItem::OutputText {
+                        text: content.text.clone(),
+                    },
+                ),
+                (Role::User, SamplingMessageContent::ImageContent(image)) => (
+                    "user",
+                    ContentItem::InputImage {
+                        image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+                    },
+                ),
+                (Role::Assistant, SamplingMessageContent::ImageContent(_)) => {
+                    return Err("images are only supported in user messages".to_string());
+                }
+                (_, SamplingMessageContent::AudioContent(_)) => {
+                    return Err("audio content is not supported".to_string());
+                }
+            };
+            Ok(ResponseItem::Message {
+                id: None,
+                role: role.to_string(),
+                content: vec![content],
+            })
+        })
+        .collect()
+}
+
+/// The messages of a request as text, for the user to review.
+fn prompt_text(params: &CreateMessageRequestParams) -> String {
+    params
+        .messages
+        .iter()
+        .map(|message| {
+            let role = match message.role {
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            };
+            let content = match &message.content {
+                SamplingMessageContent::TextContent(content) => content.text.as_str(),
+                SamplingMessageContent::ImageContent(_) => "[image]",
+                SamplingMessageContent::AudioContent(_) => "[audio]",
+            };
+            format!("{role}: {content}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn params(messages: serde_json::Value) -> CreateMessageRequestParams {
+        serde_json::from_value(json!({"maxTokens": 200, "messages": messages}))
+            .expect("valid sampling params")
+    }
+
+    #[test]
+    fn messages_become_model_input() {
+        let params = params(json!([
+            {"role": "user", "content": {"type": "text", "text": "Summarize this diff"}},
+            {"role": "assistant", "content": {"type": "text", "text": "Which diff?"}},
+            {"role": "user", "content": {"type": "image", "data": "iVBORw0K", "mimeType": "image/png"}}
+        ]));
+
+        let input = sampling_input(&params).expect("supported content");
+
+        assert_eq!(
+            input,
+            vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: "Summarize this diff".to_string(),
+                    }],
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Which diff?".to_string(),
+                    }],
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputImage {
+                        image_url: "data:image/png;base64,iVBORw0K".to_string(),
+                    }],
+                },
+            ]
+        );
+        assert_eq!(
+            prompt_text(&params),
+            "user: Summarize this diff\nassistant: Which diff?\nuser: [image]"
+        );
+    }
+
+    #[test]
+    fn audio_is_rejected() {
+        let params = params(json!([
+            {"role": "user", "content": {"type": "audio", "data": "UklGR", "mimeType": "audio/wav"}}
+        ]));
+
+        assert_eq!(
+            sampling_input(&params),
+            Err("audio content is not supported".to_string())
+        );
+    }
+}
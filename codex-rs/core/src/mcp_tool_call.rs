@@ -59,6 +59,9 @@ pub(crate) async fn handle_mcp_tool_call(
         .call_tool(&server, &tool_name, arguments_value.clone())
         .await
         .map_err(|e| format!("tool call error: {e}"));
+    for status in sess.take_mcp_status_changes() {
+        notify_mcp_tool_call_event(sess, sub_id, EventMsg::McpServerStatus(status)).await;
+    }
     let tool_call_end_event = EventMsg::McpToolCallEnd(McpToolCallEndEvent {
         call_id: call_id.clone(),
         invocation,
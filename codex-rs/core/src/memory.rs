@@ -0,0 +1,178 @@
+//! Project memory: notes about a project that outlive the session that
+//! learned them, so conventions do not have to be taught again.
+//!
+//! The notes of each project are a Markdown file,
+//! `CODEX_HOME/memory/<project>.md`, keyed like [`crate::survey`] by the
+//! repository root. With `[memory] enabled = true` the agent adds notes with
+//! the `remember` tool and the file is appended to the user instructions of
+//! later sessions in the project. Users can edit the file too, e.g. with
+//! `/memory edit` in the TUI.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::git_info::project_key;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const REMEMBER_TOOL_NAME: &str = "remember";
+
+/// Marks where older notes were left out of the instructions.
+const OMITTED_NOTES_MARKER: &str = "(older notes omitted)\n";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RememberArgs {
+    pub(crate) note: String,
+}
+
+pub(crate) fn create_remember_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "note".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One self-contained fact, e.g. `Run tests with just test, not cargo test`"
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: REMEMBER_TOOL_NAME.to_string(),
+        description: "Saves a note about this project that is shown to future sessions in it. \
+                      Use it for lasting conventions, commands and preferences the user teaches \
+                      you or that took effort to discover, not for details of the current task. \
+                      Never store secrets."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["note".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+/// Where the notes for the project containing `cwd` are stored.
+pub fn memory_path(codex_home: &Path, cwd: &Path) -> PathBuf {
+    codex_home
+        .join("memory")
+        .join(format!("{}.md", project_key(cwd)))
+}
+
+/// Appends `note` as a list item to the notes of the project containing
+/// `cwd`.
+pub(crate) async fn remember(codex_home: &Path, cwd: &Path, note: &str) -> io::Result<()> {
+    let note = note.split_whitespace().collect::<Vec<_>>().join(" ");
+    if note.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the note is empty",
+        ));
+    }
+    let path = memory_path(codex_home, cwd);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await?;
+    // Start on a new line even if the file was edited by hand.
+    let needs_newline = tokio::fs::read(&path)
+        .await
+        .is_ok_and(|contents| contents.last().is_some_and(|&byte| byte != b'\n'));
+    let entry = if needs_newline {
+        format!("\n- {note}\n")
+    } else {
+        format!("- {note}\n")
+    };
+    file.write_all(entry.as_bytes()).await?;
+    file.flush().await
+}
+
+/// Reads the notes for `cwd`, if any, for inclusion in the instructions.
+/// When they exceed `max_bytes`, the most recent whole lines that fit are
+/// kept.
+pub(crate) async fn read_memory(codex_home: &Path, cwd: &Path, max_bytes: usize) -> Option<String> {
+    let contents = tokio::fs::read_to_string(memory_path(codex_home, cwd))
+        .await
+        .ok()?;
+    let contents = contents.trim();
+    if contents.is_empty() || max_bytes == 0 {
+        return None;
+    }
+    if contents.len() <= max_bytes {
+        return Some(contents.to_string());
+    }
+    let mut start = contents.len() - max_bytes;
+    while !contents.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &contents[start..];
+    let tail = match tail.find('\n') {
+        Some(newline) => &tail[newline + 1..],
+        None => tail,
+    };
+    Some(format!("{OMITTED_NOTES_MARKER}{tail}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn notes_are_appended_and_read_back() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let project = TempDir::new().expect("tempdir");
+        assert_eq!(
+            read_memory(codex_home.path(), project.path(), 1024).await,
+            None
+        );
+
+        remember(codex_home.path(), project.path(), "Use `just fmt`.")
+            .await
+            .expect("remember");
+        std::fs::write(
+            memory_path(codex_home.path(), project.path()),
+            "- Use `just fmt`.\n- Added by hand",
+        )
+        .expect("edit");
+        remember(codex_home.path(), project.path(), "Tests need\n  Docker.")
+            .await
+            .expect("remember");
+        assert!(
+            remember(codex_home.path(), project.path(), "  ")
+                .await
+                .is_err()
+        );
+
+        assert_eq!(
+            read_memory(codex_home.path(), project.path(), 1024).await,
+            Some("- Use `just fmt`.\n- Added by hand\n- Tests need Docker.".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_notes_keep_the_most_recent_lines() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let project = TempDir::new().expect("tempdir");
+        let path = memory_path(codex_home.path(), project.path());
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        std::fs::write(&path, "- first note\n- second note\n- third note\n").expect("write");
+
+        assert_eq!(
+            read_memory(codex_home.path(), project.path(), 20).await,
+            Some(format!("{OMITTED_NOTES_MARKER}- third note"))
+        );
+    }
+}
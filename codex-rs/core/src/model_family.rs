@@ -112,6 +112,22 @@ pub fn find_family_for_model(slug: &str) -> Option<ModelFamily> {
             supports_reasoning_summaries: true,
             needs_special_apply_patch_instructions: true,
         )
+    } else if slug.starts_with("claude") {
+        // Extended thinking arrived with Claude 3.7.
+        let supports_thinking = !slug.starts_with("claude-3-") || slug.starts_with("claude-3-7");
+        model_family!(
+            slug, "claude",
+            supports_reasoning_summaries: supports_thinking,
+            apply_patch_tool_type: Some(ApplyPatchToolType::Function),
+        )
+    } else if slug.starts_with("gemini") {
+        // Thinking arrived with Gemini 2.5.
+        let supports_thinking = !slug.starts_with("gemini-1") && !slug.starts_with("gemini-2.0");
+        model_family!(
+            slug, "gemini",
+            supports_reasoning_summaries: supports_thinking,
+            apply_patch_tool_type: Some(ApplyPatchToolType::Function),
+        )
     } else {
         None
     }
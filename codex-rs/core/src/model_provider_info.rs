@@ -11,6 +11,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::env::VarError;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use crate::error::EnvVarError;
@@ -37,6 +38,36 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// Anthropic's Messages API at `/v1/messages`.
+    Anthropic,
+
+    /// Google's Gemini API at `/v1beta/models/{model}:streamGenerateContent`.
+    Gemini,
+
+    /// The Converse API of Amazon Bedrock at `/model/{model}/converse-stream`,
+    /// with requests signed using AWS credentials.
+    Bedrock,
+}
+
+/// Where providers with `wire_api = "bedrock"` find AWS settings that are
+/// otherwise taken from the environment and `~/.aws/config`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct AwsSettings {
+    /// e.g. `us-east-1`.
+    pub region: Option<String>,
+    /// Profile in `~/.aws/credentials` and `~/.aws/config`.
+    pub profile: Option<String>,
+}
+
+/// A Gemini safety setting, sent as is with every request. See
+/// <https://ai.google.dev/gemini-api/docs/safety-settings>.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct SafetySetting {
+    /// e.g. `HARM_CATEGORY_DANGEROUS_CONTENT`.
+    pub category: String,
+    /// e.g. `BLOCK_ONLY_HIGH`.
+    pub threshold: String,
 }
 
 /// Serializable representation of a provider definition.
@@ -46,6 +77,10 @@ pub struct ModelProviderInfo {
     pub name: String,
     /// Base URL for the provider's OpenAI-compatible API.
     pub base_url: Option<String>,
+    /// More base URLs to fail over to, in order, when requests to the active
+    /// one keep failing with connection errors or server errors. Only used
+    /// together with `base_url`, which stays the preferred endpoint.
+    pub fallback_base_urls: Option<Vec<String>>,
     /// Environment variable that stores the user's API key for this provider.
     pub env_key: Option<String>,
 
@@ -70,6 +105,12 @@ pub struct ModelProviderInfo {
     /// value is empty, the header will not be included in the request.
     pub env_http_headers: Option<HashMap<String, String>>,
 
+    /// Safety settings for providers with `wire_api = "gemini"`.
+    pub safety_settings: Option<Vec<SafetySetting>>,
+
+    /// AWS region and profile for providers with `wire_api = "bedrock"`.
+    pub aws: Option<AwsSettings>,
+
     /// Maximum number of times to retry a failed HTTP request to this provider.
     pub request_max_retries: Option<u64>,
 
@@ -80,10 +121,33 @@ pub struct ModelProviderInfo {
     /// the connection as lost.
     pub stream_idle_timeout_ms: Option<u64>,
 
+    /// Most requests to send to this provider per minute. Shared by every
+    /// conversation in the process.
+    pub requests_per_minute: Option<u32>,
+
+    /// Most tokens to use with this provider per minute, estimated from the
+    /// prompt and corrected with the usage each response reports. Shared by
+    /// every conversation in the process.
+    pub tokens_per_minute: Option<u64>,
+
+    /// Most responses to stream from this provider at once. Shared by every
+    /// conversation in the process.
+    pub max_concurrent_streams: Option<u32>,
+
+    /// PEM files with root certificates to trust for this provider in
+    /// addition to the system ones, e.g. the CA of a proxy that inspects TLS
+    /// traffic.
+    pub ca_certificates: Option<Vec<PathBuf>>,
+
+    /// Accept any TLS certificate from this provider, including self-signed
+    /// and expired ones. Only for debugging; prefer `ca_certificates`.
+    pub danger_accept_invalid_certs: Option<bool>,
+
     /// Does this provider require an OpenAI API Key or ChatGPT login token? If true,
     /// user is presented with login screen on first run, and login preference and token/key
     /// are stored in auth.json. If false (which is the default), login screen is skipped,
-    /// and API key (if needed) comes from the "env_key" environment variable.
+    /// and API key (if needed) comes from the "env_key" environment variable or
+    /// from a key saved with `codex auth set-key`.
     #[serde(default)]
     pub requires_openai_auth: bool,
 }
@@ -92,7 +156,9 @@ impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available, or the
+    ///     `x-api-key` (Anthropic) or `x-goog-api-key` (Gemini) header.
+    ///     Bedrock requests without an API key are signed by the caller.
     ///   • Auth token for OAuth.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
@@ -101,23 +167,48 @@ impl ModelProviderInfo {
         &'a self,
         client: &'a reqwest::Client,
         auth: &Option<CodexAuth>,
+        model: &str,
     ) -> crate::error::Result<reqwest::RequestBuilder> {
-        let effective_auth = match self.api_key() {
-            Ok(Some(key)) => Some(CodexAuth::from_api_key(&key)),
-            Ok(None) => auth.clone(),
-            Err(err) => {
-                if auth.is_some() {
-                    auth.clone()
-                } else {
-                    return Err(err);
-                }
-            }
-        };
+        let effective_auth = self.effective_auth(auth)?;
 
-        let url = self.get_full_url(&effective_auth);
+        let url = self.get_full_url(&effective_auth, model);
 
         let mut builder = client.post(url);
 
+        if let Some(auth) = effective_auth.as_ref() {
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                WireApi::Anthropic => builder.header("x-api-key", token),
+                WireApi::Gemini => builder.header("x-goog-api-key", token),
+                WireApi::Responses | WireApi::Chat | WireApi::Bedrock => builder.bearer_auth(token),
+            };
+        }
+
+        Ok(self.apply_http_headers(builder))
+    }
+
+    /// Construct a `GET` RequestBuilder that streams the events of the stored
+    /// Responses API response `response_id`, skipping events up to and
+    /// including `starting_after`. Headers and auth are applied as in
+    /// [`ModelProviderInfo::create_request_builder`].
+    pub async fn create_response_stream_request_builder<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        auth: &Option<CodexAuth>,
+        response_id: &str,
+        starting_after: Option<u64>,
+    ) -> crate::error::Result<reqwest::RequestBuilder> {
+        let effective_auth = self.effective_auth(auth)?;
+
+        let base_url = self.get_base_url(&effective_auth);
+        let query_string = self.get_query_string();
+        let url = format!("{base_url}/responses/{response_id}{query_string}");
+
+        let mut builder = client.get(url).query(&[("stream", "true")]);
+        if let Some(sequence_number) = starting_after {
+            builder = builder.query(&[("starting_after", sequence_number)]);
+        }
+
         if let Some(auth) = effective_auth.as_ref() {
             builder = builder.bearer_auth(auth.get_token().await?);
         }
@@ -125,6 +216,22 @@ impl ModelProviderInfo {
         Ok(self.apply_http_headers(builder))
     }
 
+    /// The provider's own API key takes precedence over `auth`. A missing
+    /// `env_key` is only an error when there is no `auth` to fall back to.
+    fn effective_auth(&self, auth: &Option<CodexAuth>) -> crate::error::Result<Option<CodexAuth>> {
+        match self.api_key() {
+            Ok(Some(key)) => Ok(Some(CodexAuth::from_api_key(&key))),
+            Ok(None) => Ok(auth.clone()),
+            Err(err) => {
+                if auth.is_some() {
+                    Ok(auth.clone())
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
     fn get_query_string(&self) -> String {
         self.query_params
             .as_ref()
@@ -138,8 +245,16 @@ impl ModelProviderInfo {
             })
     }
 
-    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
+    fn get_base_url(&self, auth: &Option<CodexAuth>) -> String {
+        if self.wire_api == WireApi::Bedrock && self.base_url.is_none() {
+            let region = crate::aws_auth::resolve_region(self.aws.as_ref());
+            return format!("https://bedrock-runtime.{region}.amazonaws.com");
+        }
+        let default_base_url = if self.wire_api == WireApi::Anthropic {
+            "https://api.anthropic.com/v1"
+        } else if self.wire_api == WireApi::Gemini {
+            "https://generativelanguage.googleapis.com/v1beta"
+        } else if matches!(
             auth,
             Some(CodexAuth {
                 mode: AuthMode::ChatGPT,
@@ -150,15 +265,29 @@ impl ModelProviderInfo {
         } else {
             "https://api.openai.com/v1"
         };
-        let query_string = self.get_query_string();
-        let base_url = self
-            .base_url
+        self.base_url
             .clone()
-            .unwrap_or(default_base_url.to_string());
+            .unwrap_or(default_base_url.to_string())
+    }
+
+    /// The URL that requests for `model` are sent to. Only Gemini has the
+    /// model in the URL.
+    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>, model: &str) -> String {
+        let query_string = self.get_query_string();
+        let base_url = self.get_base_url(auth);
 
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::Anthropic => format!("{base_url}/messages{query_string}"),
+            WireApi::Gemini => format!(
+                "{base_url}/models/{model}:streamGenerateContent?alt=sse{}",
+                query_string.replacen('?', "&", 1)
+            ),
+            WireApi::Bedrock => format!(
+                "{base_url}/model/{}/converse-stream{query_string}",
+                crate::aws_auth::uri_encode(model)
+            ),
         }
     }
 
@@ -180,7 +309,10 @@ impl ModelProviderInfo {
     /// Apply provider-specific HTTP headers (both static and environment-based)
     /// onto an existing `reqwest::RequestBuilder` and return the updated
     /// builder.
-    fn apply_http_headers(&self, mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    pub(crate) fn apply_http_headers(
+        &self,
+        mut builder: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
         if let Some(extra) = &self.http_headers {
             for (k, v) in extra {
                 builder = builder.header(k, v);
@@ -272,6 +404,7 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 base_url: std::env::var("OPENAI_BASE_URL")
                     .ok()
                     .filter(|v| !v.trim().is_empty()),
+                fallback_base_urls: None,
                 env_key: None,
                 env_key_instructions: None,
                 wire_api: WireApi::Responses,
@@ -293,9 +426,16 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                     .collect(),
                 ),
                 // Use global defaults for retry/timeout unless overridden in config.toml.
+                safety_settings: None,
+                aws: None,
                 request_max_retries: None,
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
+                requests_per_minute: None,
+                tokens_per_minute: None,
+                max_concurrent_streams: None,
+                ca_certificates: None,
+                danger_accept_invalid_certs: None,
                 requires_openai_auth: true,
             },
         ),
@@ -331,15 +471,23 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
     ModelProviderInfo {
         name: "gpt-oss".into(),
         base_url: Some(base_url.into()),
+        fallback_base_urls: None,
         env_key: None,
         env_key_instructions: None,
         wire_api: WireApi::Chat,
         query_params: None,
         http_headers: None,
         env_http_headers: None,
+        safety_settings: None,
+        aws: None,
         request_max_retries: None,
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     }
 }
@@ -370,15 +518,23 @@ base_url = "http://localhost:11434/v1"
         let expected_provider = ModelProviderInfo {
             name: "Ollama".into(),
             base_url: Some("http://localhost:11434/v1".into()),
+            fallback_base_urls: None,
             env_key: None,
             env_key_instructions: None,
             wire_api: WireApi::Chat,
             query_params: None,
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: None,
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
 
@@ -397,6 +553,7 @@ query_params = { api-version = "2025-04-01-preview" }
         let expected_provider = ModelProviderInfo {
             name: "Azure".into(),
             base_url: Some("https://xxxxx.openai.azure.com/openai".into()),
+            fallback_base_urls: None,
             env_key: Some("AZURE_OPENAI_API_KEY".into()),
             env_key_instructions: None,
             wire_api: WireApi::Chat,
@@ -405,9 +562,16 @@ query_params = { api-version = "2025-04-01-preview" }
             }),
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: None,
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
 
@@ -427,6 +591,7 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
         let expected_provider = ModelProviderInfo {
             name: "Example".into(),
             base_url: Some("https://example.com".into()),
+            fallback_base_urls: None,
             env_key: Some("API_KEY".into()),
             env_key_instructions: None,
             wire_api: WireApi::Chat,
@@ -437,9 +602,16 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             env_http_headers: Some(maplit::hashmap! {
                 "X-Example-Env-Header".to_string() => "EXAMPLE_ENV_VAR".to_string(),
             }),
+            safety_settings: None,
+            aws: None,
             request_max_retries: None,
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
 
@@ -447,21 +619,90 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
         assert_eq!(expected_provider, provider);
     }
 
+    #[test]
+    fn anthropic_provider_defaults_to_the_messages_endpoint() {
+        let anthropic_provider_toml = r#"
+name = "Anthropic"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "anthropic"
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(anthropic_provider_toml).unwrap();
+        assert_eq!(provider.wire_api, WireApi::Anthropic);
+        assert_eq!(
+            provider.get_full_url(&None, "claude-sonnet-4-5"),
+            "https://api.anthropic.com/v1/messages"
+        );
+    }
+
+    #[test]
+    fn gemini_provider_puts_the_model_in_the_url() {
+        let gemini_provider_toml = r#"
+name = "Gemini"
+env_key = "GEMINI_API_KEY"
+wire_api = "gemini"
+query_params = { trace = "1" }
+safety_settings = [
+    { category = "HARM_CATEGORY_DANGEROUS_CONTENT", threshold = "BLOCK_ONLY_HIGH" },
+]
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(gemini_provider_toml).unwrap();
+        assert_eq!(
+            provider.safety_settings,
+            Some(vec![SafetySetting {
+                category: "HARM_CATEGORY_DANGEROUS_CONTENT".to_string(),
+                threshold: "BLOCK_ONLY_HIGH".to_string(),
+            }])
+        );
+        assert_eq!(
+            provider.get_full_url(&None, "gemini-2.5-pro"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse&trace=1"
+        );
+    }
+
+    #[test]
+    fn bedrock_provider_builds_a_regional_converse_url() {
+        let bedrock_provider_toml = r#"
+name = "Amazon Bedrock"
+wire_api = "bedrock"
+aws = { region = "eu-central-1", profile = "work" }
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(bedrock_provider_toml).unwrap();
+        assert_eq!(
+            provider.aws,
+            Some(AwsSettings {
+                region: Some("eu-central-1".to_string()),
+                profile: Some("work".to_string()),
+            })
+        );
+        assert_eq!(
+            provider.get_full_url(&None, "anthropic.claude-sonnet-4-20250514-v1:0"),
+            "https://bedrock-runtime.eu-central-1.amazonaws.com/model/anthropic.claude-sonnet-4-20250514-v1%3A0/converse-stream"
+        );
+    }
+
     #[test]
     fn detects_azure_responses_base_urls() {
         fn provider_for(base_url: &str) -> ModelProviderInfo {
             ModelProviderInfo {
                 name: "test".into(),
                 base_url: Some(base_url.into()),
+                fallback_base_urls: None,
                 env_key: None,
                 env_key_instructions: None,
                 wire_api: WireApi::Responses,
                 query_params: None,
                 http_headers: None,
                 env_http_headers: None,
+                safety_settings: None,
+                aws: None,
                 request_max_retries: None,
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
+                requests_per_minute: None,
+                tokens_per_minute: None,
+                max_concurrent_streams: None,
+                ca_certificates: None,
+                danger_accept_invalid_certs: None,
                 requires_openai_auth: false,
             }
         }
@@ -485,15 +726,23 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
         let named_provider = ModelProviderInfo {
             name: "Azure".into(),
             base_url: Some("https://example.com".into()),
+            fallback_base_urls: None,
             env_key: None,
             env_key_instructions: None,
             wire_api: WireApi::Responses,
             query_params: None,
             http_headers: None,
             env_http_headers: None,
+            safety_settings: None,
+            aws: None,
             request_max_retries: None,
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
             requires_openai_auth: false,
         };
         assert!(named_provider.is_azure_responses_endpoint());
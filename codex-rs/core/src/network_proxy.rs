@@ -0,0 +1,494 @@
+//! Local HTTP proxy that lets sandboxed commands reach an allowlist of hosts.
+//!
+//! When a `workspace-write` sandbox policy disables general network access but
+//! lists `network_allowlist` entries, Seatbelt only permits TCP connections to
+//! this proxy. The Linux sandbox cannot limit connections to one destination,
+//! so it does not support the allowlist (see [`NETWORK_ALLOWLIST_UNSUPPORTED`]).
+//! Commands find it through the standard `HTTP_PROXY`/`HTTPS_PROXY`/
+//! `ALL_PROXY` variables. The proxy serves `CONNECT` tunnels and plain HTTP
+//! requests, and refuses every destination that is not on the allowlist.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::warn;
+
+use crate::protocol::SandboxPolicy;
+
+/// Environment variables that are pointed at the proxy for sandboxed commands.
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+
+/// Environment variables that would let a command bypass the proxy.
+const NO_PROXY_ENV_VARS: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// Why a policy with a `network_allowlist` is refused on Linux.
+pub(crate) const NETWORK_ALLOWLIST_UNSUPPORTED: &str = "sandbox_workspace_write.network_allowlist is not supported on Linux, where the sandbox cannot restrict connections to the allowlist proxy; remove it, or set network_access = true";
+
+/// Upper bound on the size of a request line plus headers.
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+
+/// Proxies started by this process, keyed by the allowlist they enforce.
+static RUNNING_PROXIES: LazyLock<Mutex<HashMap<Vec<String>, RunningProxy>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct RunningProxy {
+    port: u16,
+    handle: JoinHandle<()>,
+}
+
+/// Starts (or reuses) the proxy for `sandbox_policy` when it restricts network
+/// access to an allowlist, and points the standard proxy variables in `env`
+/// at it. Returns the port the sandbox must allow connections to.
+pub(crate) fn configure_network_proxy(
+    sandbox_policy: &SandboxPolicy,
+    env: &mut HashMap<String, String>,
+) -> std::io::Result<Option<u16>> {
+    let allowlist = sandbox_policy.network_allowlist();
+    if allowlist.is_empty() {
+        return Ok(None);
+    }
+
+    let port = ensure_network_proxy(allowlist)?;
+    let proxy_url = format!("http://{}:{port}", Ipv4Addr::LOCALHOST);
+    for var in PROXY_ENV_VARS {
+        env.insert((*var).to_string(), proxy_url.clone());
+    }
+    for var in NO_PROXY_ENV_VARS {
+        env.remove(*var);
+    }
+    Ok(Some(port))
+}
+
+fn ensure_network_proxy(allowlist: &[String]) -> std::io::Result<u16> {
+    #[expect(clippy::unwrap_used)]
+    let mut proxies = RUNNING_PROXIES.lock().unwrap();
+    if let Some(proxy) = proxies.get(allowlist)
+        && !proxy.handle.is_finished()
+    {
+        return Ok(proxy.port);
+    }
+
+    let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(listener)?;
+    let port = listener.local_addr()?.port();
+    let rules = Arc::new(NetworkAllowlist::new(allowlist));
+    let handle = tokio::spawn(run_proxy(listener, rules));
+    debug!("started sandbox network proxy on port {port} for {allowlist:?}");
+
+    proxies.insert(allowlist.to_vec(), RunningProxy { port, handle });
+    Ok(port)
+}
+
+async fn run_proxy(listener: TcpListener, allowlist: Arc<NetworkAllowlist>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let allowlist = Arc::clone(&allowlist);
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &allowlist).await {
+                        debug!("sandbox network proxy connection failed: {e}");
+                    }
+                });
+            }
+            Err(e) => warn!("sandbox network proxy failed to accept connection: {e}"),
+        }
+    }
+}
+
+async fn handle_connection(
+    mut client: TcpStream,
+    allowlist: &NetworkAllowlist,
+) -> std::io::Result<()> {
+    let (head, buffered_body) = read_request_head(&mut client).await?;
+    let Some(request) = ProxyRequest::parse(&head) else {
+        return respond(&mut client, "400 Bad Request", "malformed proxy request").await;
+    };
+
+    let addrs = resolve_allowed_addrs(allowlist, &request.host, request.port).await;
+    if addrs.is_empty() {
+        debug!(
+            "sandbox network proxy denied {}:{}",
+            request.host, request.port
+        );
+        let message = format!("{} is not in the sandbox network_allowlist", request.host);
+        return respond(&mut client, "403 Forbidden", &message).await;
+    }
+
+    let mut upstream = match TcpStream::connect(&addrs[..]).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let message = format!("failed to connect to {}: {e}", request.host);
+            return respond(&mut client, "502 Bad Gateway", &message).await;
+        }
+    };
+
+    if request.is_connect {
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+    } else {
+        upstream.write_all(&request.forwarded_head).await?;
+    }
+    upstream.write_all(&buffered_body).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+async fn respond(client: &mut TcpStream, status: &str, message: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{message}",
+        message.len()
+    );
+    client.write_all(response.as_bytes()).await?;
+    client.shutdown().await
+}
+
+/// Reads from `stream` until the end of the request headers. Returns the
+/// header block (including the terminating blank line) and any bytes that
+/// were read past it.
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Ok((buf, rest));
+        }
+        if buf.len() > MAX_REQUEST_HEAD_BYTES {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "proxy request head too large",
+            ));
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+}
+
+async fn resolve_allowed_addrs(
+    allowlist: &NetworkAllowlist,
+    host: &str,
+    port: u16,
+) -> Vec<SocketAddr> {
+    let host_allowed = allowlist.allows_domain(host);
+    let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) if !host_allowed && !allowlist.has_networks() => return Vec::new(),
+        Err(_) => match tokio::net::lookup_host((host, port)).await {
+            Ok(addrs) => addrs.collect(),
+            Err(e) => {
+                debug!("sandbox network proxy failed to resolve {host}: {e}");
+                return Vec::new();
+            }
+        },
+    };
+
+    // Connect only to the addresses that were checked so a DNS answer cannot
+    // change between the allowlist check and the connection.
+    addrs
+        .into_iter()
+        .filter(|addr| host_allowed || allowlist.allows_addr(addr.ip()))
+        .collect()
+}
+
+/// A request received by the proxy, reduced to what is needed to forward it.
+#[derive(Debug, PartialEq, Eq)]
+struct ProxyRequest {
+    host: String,
+    port: u16,
+    is_connect: bool,
+    /// Request head rewritten to origin-form for plain HTTP requests. Empty
+    /// for `CONNECT` tunnels.
+    forwarded_head: Vec<u8>,
+}
+
+impl ProxyRequest {
+    fn parse(head: &[u8]) -> Option<Self> {
+        let head = std::str::from_utf8(head).ok()?;
+        let (request_line, headers) = head.split_once("\r\n")?;
+        let mut parts = request_line.split(' ');
+        let method = parts.next()?;
+        let target = parts.next()?;
+        let version = parts.next()?;
+
+        if method.eq_ignore_ascii_case("CONNECT") {
+            let (host, port) = split_host_port(target)?;
+            return Some(Self {
+                host,
+                port: port?,
+                is_connect: true,
+                forwarded_head: Vec::new(),
+            });
+        }
+
+        let rest = target.strip_prefix("http://")?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        let (host, port) = split_host_port(authority)?;
+        Some(Self {
+            host,
+            port: port.unwrap_or(80),
+            is_connect: false,
+            forwarded_head: format!("{method} {path} {version}\r\n{headers}").into_bytes(),
+        })
+    }
+}
+
+/// Splits `host[:port]` (or `[v6]:port`), lowercasing the host.
+fn split_host_port(authority: &str) -> Option<(String, Option<u16>)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if after.is_empty() => (host, None),
+            None => return None,
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let port = match port {
+        Some(port) => Some(port.parse().ok()?),
+        None => None,
+    };
+    Some((host.to_ascii_lowercase(), port))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AllowlistEntry {
+    /// Matches the domain itself and all of its subdomains.
+    Domain(String),
+    /// Matches any address inside the network.
+    Network { addr: IpAddr, prefix_len: u8 },
+}
+
+impl AllowlistEntry {
+    fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim().to_ascii_lowercase();
+        if let Some((addr, prefix_len)) = entry.split_once('/') {
+            let addr: IpAddr = addr.parse().ok()?;
+            let prefix_len: u8 = prefix_len.parse().ok()?;
+            if prefix_len > max_prefix_len(addr) {
+                return None;
+            }
+            return Some(Self::Network { addr, prefix_len });
+        }
+        if let Ok(addr) = entry.parse::<IpAddr>() {
+            return Some(Self::Network {
+                addr,
+                prefix_len: max_prefix_len(addr),
+            });
+        }
+
+        let domain = entry.strip_prefix("*.").unwrap_or(&entry);
+        let domain = domain.trim_end_matches('.');
+        if domain.is_empty()
+            || domain
+                .chars()
+                .any(|c| !(c.is_ascii_alphanumeric() || c == '-' || c == '.'))
+        {
+            return None;
+        }
+        Some(Self::Domain(domain.to_string()))
+    }
+}
+
+fn max_prefix_len(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn network_contains(network: IpAddr, prefix_len: u8, addr: IpAddr) -> bool {
+    match (network, addr.to_canonical()) {
+        (IpAddr::V4(network), IpAddr::V4(addr)) => {
+            let mask = u32::MAX
+                .checked_shl(32 - u32::from(prefix_len))
+                .unwrap_or(0);
+            u32::from(network) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(addr)) => {
+            let mask = u128::MAX
+                .checked_shl(128 - u32::from(prefix_len))
+                .unwrap_or(0);
+            u128::from(network) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether `allowlist` (a `network_allowlist`) lets sandboxed commands reach
+/// `host`, a domain name or an IP address.
+pub(crate) fn allowlist_allows_host(allowlist: &[String], host: &str) -> bool {
+    let allowlist = NetworkAllowlist::new(allowlist);
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<IpAddr>() {
+        Ok(addr) => allowlist.allows_addr(addr),
+        Err(_) => allowlist.allows_domain(host),
+    }
+}
+
+/// Parsed form of `SandboxPolicy::WorkspaceWrite::network_allowlist`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct NetworkAllowlist {
+    entries: Vec<AllowlistEntry>,
+}
+
+impl NetworkAllowlist {
+    fn new(entries: &[String]) -> Self {
+        let entries = entries
+            .iter()
+            .filter_map(|entry| {
+                let parsed = AllowlistEntry::parse(entry);
+                if parsed.is_none() {
+                    warn!("ignoring invalid network_allowlist entry: {entry:?}");
+                }
+                parsed
+            })
+            .collect();
+        Self { entries }
+    }
+
+    fn allows_domain(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.entries.iter().any(|entry| match entry {
+            AllowlistEntry::Domain(domain) => {
+                host == *domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            AllowlistEntry::Network { .. } => false,
+        })
+    }
+
+    fn allows_addr(&self, addr: IpAddr) -> bool {
+        self.entries.iter().any(|entry| match entry {
+            AllowlistEntry::Network {
+                addr: network,
+                prefix_len,
+            } => network_contains(*network, *prefix_len, addr),
+            AllowlistEntry::Domain(_) => false,
+        })
+    }
+
+    fn has_networks(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| matches!(entry, AllowlistEntry::Network { .. }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn allowlist(entries: &[&str]) -> NetworkAllowlist {
+        let entries: Vec<String> = entries.iter().map(|e| (*e).to_string()).collect();
+        NetworkAllowlist::new(&entries)
+    }
+
+    #[test]
+    fn domains_match_themselves_and_subdomains() {
+        let allowlist = allowlist(&["crates.io", "*.github.com"]);
+        assert!(allowlist.allows_domain("crates.io"));
+        assert!(allowlist.allows_domain("static.crates.io"));
+        assert!(allowlist.allows_domain("GitHub.com."));
+        assert!(allowlist.allows_domain("codeload.github.com"));
+        assert!(!allowlist.allows_domain("evilcrates.io"));
+        assert!(!allowlist.allows_domain("crates.io.example.com"));
+    }
+
+    #[test]
+    fn networks_match_contained_addresses() {
+        let allowlist = allowlist(&["10.0.0.0/8", "2001:db8::/32", "192.168.1.7"]);
+        assert!(allowlist.allows_addr("10.20.30.40".parse().unwrap()));
+        assert!(allowlist.allows_addr("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(allowlist.allows_addr("2001:db8::1".parse().unwrap()));
+        assert!(allowlist.allows_addr("192.168.1.7".parse().unwrap()));
+        assert!(!allowlist.allows_addr("192.168.1.8".parse().unwrap()));
+        assert!(!allowlist.allows_addr("11.0.0.1".parse().unwrap()));
+        assert!(!allowlist.allows_domain("10.0.0.1"));
+    }
+
+    #[test]
+    fn invalid_entries_are_ignored() {
+        let allowlist = allowlist(&["10.0.0.0/33", "bad host", "", "example.com"]);
+        assert_eq!(
+            allowlist.entries,
+            vec![AllowlistEntry::Domain("example.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn parses_connect_request() {
+        let request =
+            ProxyRequest::parse(b"CONNECT Index.Crates.io:443 HTTP/1.1\r\nHost: x\r\n\r\n");
+        assert_eq!(
+            request,
+            Some(ProxyRequest {
+                host: "index.crates.io".to_string(),
+                port: 443,
+                is_connect: true,
+                forwarded_head: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn rewrites_plain_http_request_to_origin_form() {
+        let request = ProxyRequest::parse(
+            b"GET http://[::1]:8080/a?b=c HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n",
+        )
+        .expect("request should parse");
+        assert_eq!(request.host, "::1");
+        assert_eq!(request.port, 8080);
+        assert_eq!(
+            String::from_utf8(request.forwarded_head).unwrap(),
+            "GET /a?b=c HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn rejects_https_absolute_form_and_missing_connect_port() {
+        assert_eq!(
+            ProxyRequest::parse(b"GET https://example.com/ HTTP/1.1\r\n\r\n"),
+            None
+        );
+        assert_eq!(
+            ProxyRequest::parse(b"CONNECT example.com HTTP/1.1\r\n\r\n"),
+            None
+        );
+    }
+}
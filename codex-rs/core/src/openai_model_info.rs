@@ -72,6 +72,21 @@ pub(crate) fn get_model_info(model_family: &ModelFamily) -> Option<ModelInfo> {
 
         _ if slug.starts_with("codex-") => Some(ModelInfo::new(272_000, 128_000)),
 
+        // https://docs.anthropic.com/en/docs/about-claude/models/overview
+        _ if slug.starts_with("claude-opus-4") => Some(ModelInfo::new(200_000, 32_000)),
+        _ if slug.starts_with("claude-3-5") => Some(ModelInfo::new(200_000, 8_192)),
+        _ if slug.starts_with("claude-3-") && !slug.starts_with("claude-3-7") => {
+            Some(ModelInfo::new(200_000, 4_096))
+        }
+        _ if slug.starts_with("claude-") => Some(ModelInfo::new(200_000, 64_000)),
+
+        // https://ai.google.dev/gemini-api/docs/models
+        _ if slug.starts_with("gemini-1.5-pro") => Some(ModelInfo::new(2_097_152, 8_192)),
+        _ if slug.starts_with("gemini-1") || slug.starts_with("gemini-2.0") => {
+            Some(ModelInfo::new(1_048_576, 8_192))
+        }
+        _ if slug.starts_with("gemini-") => Some(ModelInfo::new(1_048_576, 65_536)),
+
         _ => None,
     }
 }
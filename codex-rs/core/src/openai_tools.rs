@@ -5,11 +5,25 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::background_jobs::DEFAULT_BACKGROUND_JOB_TIMEOUT_MS;
+use crate::background_jobs::JOB_KILL_TOOL_NAME;
+use crate::background_jobs::JOB_RESULT_TOOL_NAME;
+use crate::background_jobs::create_job_kill_tool;
+use crate::background_jobs::create_job_result_tool;
+use crate::config_types::WebSearchBackend;
+use crate::exec_command::WRITE_STDIN_TOOL_NAME;
+use crate::fetch_url::create_fetch_url_tool;
+use crate::file_tools::create_file_tools;
+use crate::memory::create_remember_tool;
 use crate::model_family::ModelFamily;
+use crate::output_overflow::create_read_output_tool;
 use crate::plan_tool::PLAN_TOOL;
+use crate::search_code::create_search_code_tool;
 use crate::tool_apply_patch::ApplyPatchToolType;
 use crate::tool_apply_patch::create_apply_patch_freeform_tool;
 use crate::tool_apply_patch::create_apply_patch_json_tool;
+use crate::tool_git::create_git_tool;
+use crate::web_search::create_web_search_tool;
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct ResponsesApiTool {
@@ -66,7 +80,13 @@ pub(crate) struct ToolsConfig {
     pub plan_tool: bool,
     pub apply_patch_tool_type: Option<ApplyPatchToolType>,
     pub web_search_request: bool,
+    pub web_search_backend: WebSearchBackend,
     pub include_view_image_tool: bool,
+    pub include_git_tool: bool,
+    pub include_fetch_url_tool: bool,
+    pub include_file_tools: bool,
+    pub include_search_code_tool: bool,
+    pub include_memory_tool: bool,
     pub experimental_unified_exec_tool: bool,
 }
 
@@ -75,8 +95,14 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) include_plan_tool: bool,
     pub(crate) include_apply_patch_tool: bool,
     pub(crate) include_web_search_request: bool,
+    pub(crate) web_search_backend: WebSearchBackend,
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
+    pub(crate) include_git_tool: bool,
+    pub(crate) include_fetch_url_tool: bool,
+    pub(crate) include_file_tools: bool,
+    pub(crate) include_search_code_tool: bool,
+    pub(crate) include_memory_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
 }
 
@@ -87,8 +113,14 @@ impl ToolsConfig {
             include_plan_tool,
             include_apply_patch_tool,
             include_web_search_request,
+            web_search_backend,
             use_streamable_shell_tool,
             include_view_image_tool,
+            include_git_tool,
+            include_fetch_url_tool,
+            include_file_tools,
+            include_search_code_tool,
+            include_memory_tool,
             experimental_unified_exec_tool,
         } = params;
         let shell_type = if *use_streamable_shell_tool {
@@ -116,7 +148,13 @@ impl ToolsConfig {
             plan_tool: *include_plan_tool,
             apply_patch_tool_type,
             web_search_request: *include_web_search_request,
+            web_search_backend: *web_search_backend,
             include_view_image_tool: *include_view_image_tool,
+            include_git_tool: *include_git_tool,
+            include_fetch_url_tool: *include_fetch_url_tool,
+            include_file_tools: *include_file_tools,
+            include_search_code_tool: *include_search_code_tool,
+            include_memory_tool: *include_memory_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
         }
     }
@@ -223,7 +261,7 @@ fn create_shell_tool() -> OpenAiTool {
     properties.insert(
         "timeout_ms".to_string(),
         JsonSchema::Number {
-            description: Some("The timeout for the command in milliseconds".to_string()),
+            description: Some("The time limit for the command in milliseconds. Set it higher for commands known to be slow, such as large builds or full test suites; requests above the maximum configured by the user are capped.".to_string()),
         },
     );
 
@@ -239,6 +277,25 @@ fn create_shell_tool() -> OpenAiTool {
             description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
         },
     );
+    properties.insert(
+        "secrets".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some("Names of credentials the command needs, e.g. [\"NPM_TOKEN\"]. The user is asked for each one, and it is passed to the command as an environment variable of the same name. You never see the values; refer to them as $NAME.".to_string()),
+        },
+    );
+    properties.insert(
+        "run_in_background".to_string(),
+        JsonSchema::Boolean {
+            description: Some(format!("Run a long command (a full test suite, a large build, a dev server or file watcher) as a background job and return its job id immediately instead of waiting. Continue with other work, poll its output or collect its result with `{JOB_RESULT_TOOL_NAME}`, and stop it with `{JOB_KILL_TOOL_NAME}`. Jobs keep running across turns until they finish, time out or the session ends. timeout_ms defaults to {} minutes for background jobs.", DEFAULT_BACKGROUND_JOB_TIMEOUT_MS / 60_000)),
+        },
+    );
+    properties.insert(
+        "tty".to_string(),
+        JsonSchema::Boolean {
+            description: Some(format!("Run the command on a pseudo-terminal, for interactive programs that need a TTY (watch modes, prompts, `git add -p`). Returns the output printed within timeout_ms and, if the command is still running, a session ID; send input or poll for more output with `{WRITE_STDIN_TOOL_NAME}`. Cannot be combined with run_in_background.")),
+        },
+    );
 
     OpenAiTool::Function(ResponsesApiTool {
         name: "shell".to_string(),
@@ -279,6 +336,8 @@ fn create_view_image_tool() -> OpenAiTool {
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
     pub(crate) input: String,
+    #[serde(default)]
+    pub(crate) dry_run: bool,
 }
 
 /// Returns JSON values that are compatible with Function Calling in the
@@ -327,6 +386,82 @@ pub(crate) fn create_tools_json_for_chat_completions_api(
     Ok(tools_json)
 }
 
+/// Rewrites the tools for the Anthropic Messages API, which only takes
+/// function tools, described by their JSON schema.
+pub(crate) fn create_tools_json_for_anthropic_api(
+    tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let responses_api_tools_json = create_tools_json_for_responses_api(tools)?;
+    let tools_json = responses_api_tools_json
+        .into_iter()
+        .filter(|tool| tool.get("type") == Some(&serde_json::Value::String("function".to_string())))
+        .map(|tool| {
+            json!({
+                "name": tool.get("name"),
+                "description": tool.get("description"),
+                "input_schema": tool.get("parameters"),
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    Ok(tools_json)
+}
+
+/// Rewrites the tools as Gemini function declarations. Gemini accepts an
+/// OpenAPI subset of JSON schema without `additionalProperties`.
+pub(crate) fn create_tools_json_for_gemini_api(
+    tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    fn strip_additional_properties(schema: &mut serde_json::Value) {
+        match schema {
+            serde_json::Value::Object(map) => {
+                map.remove("additionalProperties");
+                map.values_mut().for_each(strip_additional_properties);
+            }
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(strip_additional_properties);
+            }
+            _ => {}
+        }
+    }
+
+    let responses_api_tools_json = create_tools_json_for_responses_api(tools)?;
+    let tools_json = responses_api_tools_json
+        .into_iter()
+        .filter(|tool| tool.get("type") == Some(&serde_json::Value::String("function".to_string())))
+        .map(|tool| {
+            let mut parameters = tool.get("parameters").cloned().unwrap_or_default();
+            strip_additional_properties(&mut parameters);
+            json!({
+                "name": tool.get("name"),
+                "description": tool.get("description"),
+                "parameters": parameters,
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    Ok(tools_json)
+}
+
+/// Rewrites the tools as tool specs for the Bedrock Converse API.
+pub(crate) fn create_tools_json_for_bedrock_api(
+    tools: &[OpenAiTool],
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let responses_api_tools_json = create_tools_json_for_responses_api(tools)?;
+    let tools_json = responses_api_tools_json
+        .into_iter()
+        .filter(|tool| tool.get("type") == Some(&serde_json::Value::String("function".to_string())))
+        .map(|tool| {
+            json!({
+                "toolSpec": {
+                    "name": tool.get("name"),
+                    "description": tool.get("description"),
+                    "inputSchema": {"json": tool.get("parameters")},
+                }
+            })
+        })
+        .collect::<Vec<serde_json::Value>>();
+    Ok(tools_json)
+}
+
 pub(crate) fn mcp_tool_to_openai_tool(
     fully_qualified_name: String,
     tool: mcp_types::Tool,
@@ -362,6 +497,28 @@ pub(crate) fn mcp_tool_to_openai_tool(
     })
 }
 
+/// Converts a plugin tool into a function tool, applying the same schema
+/// fixes as for MCP tools.
+pub(crate) fn plugin_tool_to_openai_tool(
+    fully_qualified_name: String,
+    description: String,
+    mut parameters: JsonValue,
+) -> Result<ResponsesApiTool, serde_json::Error> {
+    if let JsonValue::Object(map) = &mut parameters {
+        map.entry("properties")
+            .or_insert_with(|| JsonValue::Object(serde_json::Map::new()));
+    }
+    sanitize_json_schema(&mut parameters);
+    let parameters = serde_json::from_value::<JsonSchema>(parameters)?;
+
+    Ok(ResponsesApiTool {
+        name: fully_qualified_name,
+        description,
+        strict: false,
+        parameters,
+    })
+}
+
 /// Sanitize a JSON Schema (as serde_json::Value) so it can fit our limited
 /// JsonSchema enum. This function:
 /// - Ensures every schema object has a "type". If missing, infers it from
@@ -488,9 +645,16 @@ pub(crate) fn get_openai_tools(
         match &config.shell_type {
             ConfigShellToolType::Default => {
                 tools.push(create_shell_tool());
+                tools.push(create_job_result_tool());
+                tools.push(create_job_kill_tool());
+                tools.push(create_read_output_tool());
+                tools.push(OpenAiTool::Function(
+                    crate::exec_command::create_write_stdin_tool_for_responses_api(),
+                ));
             }
             ConfigShellToolType::Local => {
                 tools.push(OpenAiTool::LocalShell {});
+                tools.push(create_read_output_tool());
             }
             ConfigShellToolType::Streamable => {
                 tools.push(OpenAiTool::Function(
@@ -519,13 +683,38 @@ pub(crate) fn get_openai_tools(
     }
 
     if config.web_search_request {
-        tools.push(OpenAiTool::WebSearch {});
+        match config.web_search_backend {
+            WebSearchBackend::Native => tools.push(OpenAiTool::WebSearch {}),
+            WebSearchBackend::Brave | WebSearchBackend::Searxng => {
+                tools.push(create_web_search_tool());
+            }
+        }
     }
 
     // Include the view_image tool so the agent can attach images to context.
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
+
+    if config.include_git_tool {
+        tools.push(create_git_tool());
+    }
+
+    if config.include_fetch_url_tool {
+        tools.push(create_fetch_url_tool());
+    }
+
+    if config.include_file_tools {
+        tools.extend(create_file_tools());
+    }
+
+    if config.include_search_code_tool {
+        tools.push(create_search_code_tool());
+    }
+
+    if config.include_memory_tool {
+        tools.push(create_remember_tool());
+    }
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
@@ -585,8 +774,14 @@ mod tests {
             include_plan_tool: true,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
@@ -597,6 +792,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn web_search_api_backends_use_a_function_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Brave,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "web_search"]);
+        assert!(matches!(tools[1], OpenAiTool::Function(_)));
+        // Unlike the native tool, it reaches providers without one.
+        let chat_tools =
+            create_tools_json_for_chat_completions_api(&tools).expect("chat completions tools");
+        assert_eq!(chat_tools[1]["function"]["name"], "web_search");
+    }
+
     #[test]
     fn test_get_openai_tools_default_shell() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -605,8 +828,14 @@ mod tests {
             include_plan_tool: true,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
@@ -617,6 +846,156 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_openai_tools_shell_includes_job_tools_and_write_stdin() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
+            experimental_unified_exec_tool: false,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "job_result",
+                "job_kill",
+                "read_output",
+                "write_stdin",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_git_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: true,
+            include_git_tool: true,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "view_image", "git"]);
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_fetch_url_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_git_tool: false,
+            include_fetch_url_tool: true,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "fetch_url"]);
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_file_tools() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: true,
+            include_search_code_tool: false,
+            include_memory_tool: false,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(
+            &tools,
+            &["unified_exec", "read_file", "list_dir", "write_file"],
+        );
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_search_code_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: true,
+            include_memory_tool: false,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "search_code"]);
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_remember_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: true,
+            experimental_unified_exec_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "remember"]);
+    }
+
     #[test]
     fn test_get_openai_tools_mcp_tools() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -625,8 +1004,14 @@ mod tests {
             include_plan_tool: false,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
         let tools = get_openai_tools(
@@ -729,8 +1114,14 @@ mod tests {
             include_plan_tool: false,
             include_apply_patch_tool: false,
             include_web_search_request: false,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -805,8 +1196,14 @@ mod tests {
             include_plan_tool: false,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -866,8 +1263,14 @@ mod tests {
             include_plan_tool: false,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -922,8 +1325,14 @@ mod tests {
             include_plan_tool: false,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
 
@@ -981,8 +1390,14 @@ mod tests {
             include_plan_tool: false,
             include_apply_patch_tool: false,
             include_web_search_request: true,
+            web_search_backend: WebSearchBackend::Native,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_git_tool: false,
+            include_fetch_url_tool: false,
+            include_file_tools: false,
+            include_search_code_tool: false,
+            include_memory_tool: false,
             experimental_unified_exec_tool: true,
         });
 
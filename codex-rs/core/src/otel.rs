@@ -0,0 +1,248 @@
+//! OpenTelemetry export of traces and metrics, so teams can observe how Codex
+//! is used in CI and on developer machines.
+//!
+//! Core records spans with `tracing`: `turn` for a task, `model_request` for
+//! each request to the model, `tool_call` for each tool the model calls and
+//! `sandbox_exec` for each command run. Counters are `tracing` events on the
+//! [`METRICS_TARGET`] target. Binaries add [`layer`] to their subscriber to
+//! export both over OTLP (HTTP/protobuf).
+//!
+//! Export needs the `otel` cargo feature and is configured with the standard
+//! environment variables: it is on when `OTEL_EXPORTER_OTLP_ENDPOINT`, or the
+//! `_TRACES_`/`_METRICS_` variant for one signal, is set. `OTEL_SDK_DISABLED`,
+//! `OTEL_TRACES_EXPORTER=none`, `OTEL_METRICS_EXPORTER=none`,
+//! `OTEL_SERVICE_NAME`, `OTEL_RESOURCE_ATTRIBUTES` and
+//! `OTEL_EXPORTER_OTLP_HEADERS` are honored as well.
+
+use tracing::Level;
+use tracing::Subscriber;
+use tracing_subscriber::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::protocol::ErrorKind;
+use crate::protocol::TokenUsage;
+
+/// Target of the events that carry counters.
+pub const METRICS_TARGET: &str = "codex_otel";
+
+pub type BoxedLayer<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// Flushes and shuts down the exporters when dropped; keep it alive until
+/// the program exits.
+pub struct OtelGuard {
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+    #[cfg(feature = "otel")]
+    meter_provider: Option<opentelemetry_sdk::metrics::SdkMeterProvider>,
+}
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        {
+            if let Some(provider) = self.tracer_provider.take() {
+                let _ = provider.shutdown();
+            }
+            if let Some(provider) = self.meter_provider.take() {
+                let _ = provider.shutdown();
+            }
+        }
+    }
+}
+
+/// A layer exporting spans and counters over OTLP, reported as coming from
+/// `service_name` unless `OTEL_SERVICE_NAME` says otherwise. `None` when the
+/// environment doesn't ask for export or Codex was built without `otel`.
+pub fn layer<S>(service_name: &'static str) -> Option<(BoxedLayer<S>, OtelGuard)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    #[cfg(feature = "otel")]
+    {
+        export::layer(service_name, |name| std::env::var(name).ok())
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = service_name;
+        None
+    }
+}
+
+/// Counts the tokens of one model response.
+pub(crate) fn record_token_usage(model: &str, provider: &str, usage: &TokenUsage) {
+    tracing::event!(
+        target: METRICS_TARGET,
+        Level::INFO,
+        monotonic_counter.codex.tokens.input = usage.input_tokens,
+        monotonic_counter.codex.tokens.cached_input = usage.cached_input_tokens,
+        monotonic_counter.codex.tokens.output = usage.output_tokens,
+        monotonic_counter.codex.tokens.reasoning_output = usage.reasoning_output_tokens,
+        model,
+        provider,
+    );
+}
+
+/// Counts an error reported to the user.
+pub(crate) fn record_error(kind: ErrorKind) {
+    let kind = match kind {
+        ErrorKind::Provider => "provider",
+        ErrorKind::Auth => "auth",
+        ErrorKind::Sandbox => "sandbox",
+        ErrorKind::BudgetExceeded => "budget_exceeded",
+        ErrorKind::Other => "other",
+    };
+    tracing::event!(
+        target: METRICS_TARGET,
+        Level::INFO,
+        monotonic_counter.codex.errors = 1_u64,
+        kind
+    );
+}
+
+#[cfg(feature = "otel")]
+mod export {
+    use opentelemetry::KeyValue;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::MetricExporter;
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing::Level;
+    use tracing::Subscriber;
+    use tracing_opentelemetry::MetricsLayer;
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::filter::Targets;
+    use tracing_subscriber::registry::LookupSpan;
+
+    use super::BoxedLayer;
+    use super::METRICS_TARGET;
+    use super::OtelGuard;
+
+    // The subscriber this layer is for is not installed yet, so setup
+    // failures can only go to stderr.
+    #[allow(clippy::print_stderr)]
+    pub(super) fn layer<S>(
+        service_name: &'static str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<(BoxedLayer<S>, OtelGuard)>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        let export_traces = signal_enabled("TRACES", &env);
+        let export_metrics = signal_enabled("METRICS", &env);
+        if !export_traces && !export_metrics {
+            return None;
+        }
+
+        let mut resource = Resource::builder()
+            .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")));
+        if env("OTEL_SERVICE_NAME").is_none() {
+            resource = resource.with_service_name(service_name);
+        }
+        let resource = resource.build();
+
+        let tracer_provider = export_traces
+            .then(|| match SpanExporter::builder().with_http().build() {
+                Ok(exporter) => Some(
+                    SdkTracerProvider::builder()
+                        .with_batch_exporter(exporter)
+                        .with_resource(resource.clone())
+                        .build(),
+                ),
+                Err(e) => {
+                    eprintln!("failed to set up OpenTelemetry trace export: {e}");
+                    None
+                }
+            })
+            .flatten();
+        let meter_provider = export_metrics
+            .then(|| match MetricExporter::builder().with_http().build() {
+                Ok(exporter) => Some(
+                    SdkMeterProvider::builder()
+                        .with_periodic_exporter(exporter)
+                        .with_resource(resource)
+                        .build(),
+                ),
+                Err(e) => {
+                    eprintln!("failed to set up OpenTelemetry metric export: {e}");
+                    None
+                }
+            })
+            .flatten();
+        if tracer_provider.is_none() && meter_provider.is_none() {
+            return None;
+        }
+
+        let trace_layer = tracer_provider
+            .as_ref()
+            .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("codex")));
+        let metrics_layer = meter_provider
+            .as_ref()
+            .map(|provider| MetricsLayer::new(provider.clone()));
+        // Only what Codex records itself, not the spans of its dependencies.
+        let targets = Targets::new()
+            .with_target("codex_core", Level::INFO)
+            .with_target(METRICS_TARGET, Level::INFO);
+        let layer = Layer::and_then(trace_layer, metrics_layer).with_filter(targets);
+        Some((
+            Box::new(layer),
+            OtelGuard {
+                tracer_provider,
+                meter_provider,
+            },
+        ))
+    }
+
+    /// Whether the environment asks for `signal` (`TRACES` or `METRICS`) to
+    /// be exported.
+    fn signal_enabled(signal: &str, env: impl Fn(&str) -> Option<String>) -> bool {
+        let is = |name: &str, value: &str| {
+            env(name).is_some_and(|v| v.trim().eq_ignore_ascii_case(value))
+        };
+        let is_set = |name: &str| env(name).is_some_and(|v| !v.trim().is_empty());
+        if is("OTEL_SDK_DISABLED", "true") || is(&format!("OTEL_{signal}_EXPORTER"), "none") {
+            return false;
+        }
+        is_set("OTEL_EXPORTER_OTLP_ENDPOINT")
+            || is_set(&format!("OTEL_EXPORTER_OTLP_{signal}_ENDPOINT"))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::collections::HashMap;
+
+        fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            move |name| vars.get(name).cloned()
+        }
+
+        #[test]
+        fn signals_follow_the_standard_environment_variables() {
+            assert!(!signal_enabled("TRACES", env(&[])));
+
+            let endpoint = ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318");
+            assert!(signal_enabled("TRACES", env(&[endpoint])));
+            assert!(signal_enabled("METRICS", env(&[endpoint])));
+            assert!(!signal_enabled(
+                "METRICS",
+                env(&[endpoint, ("OTEL_METRICS_EXPORTER", "none")])
+            ));
+            assert!(!signal_enabled(
+                "TRACES",
+                env(&[endpoint, ("OTEL_SDK_DISABLED", "TRUE")])
+            ));
+
+            let traces_only = (
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                "http://localhost:4318/v1/traces",
+            );
+            assert!(signal_enabled("TRACES", env(&[traces_only])));
+            assert!(!signal_enabled("METRICS", env(&[traces_only])));
+        }
+    }
+}
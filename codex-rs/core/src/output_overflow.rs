@@ -0,0 +1,219 @@
+//! Full output of commands whose output was too long to show the model.
+//!
+//! The model sees the head and tail of a long output with the middle elided.
+//! The full text is saved to a temporary file under the id of the tool call
+//! (or background job) that produced it, and the model pages through it with
+//! the `read_output` tool. The files are removed when the session ends.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use serde::Deserialize;
+use tempfile::TempDir;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const READ_OUTPUT_TOOL_NAME: &str = "read_output";
+
+/// Lines returned by a `read_output` call that does not set `limit`.
+const DEFAULT_PAGE_LINES: usize = 200;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadOutputArgs {
+    pub(crate) output_id: String,
+    /// First line to return, starting at 1.
+    #[serde(default)]
+    pub(crate) offset: Option<usize>,
+    #[serde(default)]
+    pub(crate) limit: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct OutputOverflowStore {
+    state: Mutex<OverflowState>,
+}
+
+#[derive(Debug, Default)]
+struct OverflowState {
+    /// Created on the first save.
+    dir: Option<TempDir>,
+    files: HashMap<String, PathBuf>,
+}
+
+impl OutputOverflowStore {
+    /// Saves the full `output` under `output_id`. Saving an id again keeps
+    /// the first output, since the same call is formatted for both the
+    /// client and the model.
+    pub(crate) fn save(&self, output_id: &str, output: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.files.contains_key(output_id) {
+            return Ok(());
+        }
+        let dir = match &state.dir {
+            Some(dir) => dir.path().to_path_buf(),
+            None => {
+                let dir = tempfile::Builder::new().prefix("codex-output-").tempdir()?;
+                let path = dir.path().to_path_buf();
+                state.dir = Some(dir);
+                path
+            }
+        };
+        // Ids come from the model, so they are not used as file names.
+        let path = dir.join(format!("{}.log", state.files.len() + 1));
+        std::fs::write(&path, output)?;
+        state.files.insert(output_id.to_string(), path);
+        Ok(())
+    }
+
+    pub(crate) fn path(&self, output_id: &str) -> Option<PathBuf> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .files
+            .get(output_id)
+            .cloned()
+    }
+}
+
+/// The lines of `text` selected by `args`, capped at `max_bytes`, with a
+/// header giving their position and a hint for reading on.
+pub(crate) fn format_page(text: &str, args: &ReadOutputArgs, max_bytes: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let total = lines.len();
+    let start = args.offset.unwrap_or(1).max(1) - 1;
+    if start >= total {
+        return format!(
+            "offset {} is past the end of `{}`, which has {total} lines",
+            start + 1,
+            args.output_id
+        );
+    }
+    let limit = args.limit.unwrap_or(DEFAULT_PAGE_LINES).max(1);
+
+    let mut body = String::new();
+    let mut end = start;
+    for line in &lines[start..(start + limit).min(total)] {
+        if end > start && body.len() + line.len() + 1 > max_bytes {
+            break;
+        }
+        if end > start {
+            body.push('\n');
+        }
+        body.push_str(take_prefix(line, max_bytes));
+        end += 1;
+    }
+
+    let mut page = format!("[lines {}-{end} of {total}]\n{body}", start + 1);
+    if end < total {
+        page.push_str(&format!(
+            "\n[... {} more lines; continue with offset {}]",
+            total - end,
+            end + 1
+        ));
+    }
+    page
+}
+
+fn take_prefix(s: &str, max_bytes: usize) -> &str {
+    let mut end = s.len().min(max_bytes);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+pub(crate) fn create_read_output_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "output_id".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The id given in the truncation notice of a command's output.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "offset".to_string(),
+        JsonSchema::Number {
+            description: Some("First line to return, starting at 1. Defaults to 1.".to_string()),
+        },
+    );
+    properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Number of lines to return. Defaults to {DEFAULT_PAGE_LINES}."
+            )),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_OUTPUT_TOOL_NAME.to_string(),
+        description: "Returns lines from the full output of a command whose output was \
+                      truncated. Use it to read the omitted middle of long logs instead of \
+                      running the command again."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["output_id".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(offset: Option<usize>, limit: Option<usize>) -> ReadOutputArgs {
+        ReadOutputArgs {
+            output_id: "call_1".to_string(),
+            offset,
+            limit,
+        }
+    }
+
+    #[test]
+    fn saves_output_once_per_id() {
+        let store = OutputOverflowStore::default();
+        store.save("call_1", "first").expect("save");
+        store.save("call_1", "second").expect("save again");
+        let path = store.path("call_1").expect("saved");
+        assert_eq!(std::fs::read_to_string(path).expect("read"), "first");
+        assert_eq!(store.path("call_2"), None);
+    }
+
+    #[test]
+    fn pages_through_lines() {
+        let text = (1..=10)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(
+            format_page(&text, &args(Some(4), Some(3)), 1024),
+            "[lines 4-6 of 10]\nline 4\nline 5\nline 6\n[... 4 more lines; continue with offset 7]"
+        );
+        assert_eq!(
+            format_page(&text, &args(Some(9), None), 1024),
+            "[lines 9-10 of 10]\nline 9\nline 10"
+        );
+        // The byte cap ends the page early.
+        assert_eq!(
+            format_page(&text, &args(None, None), 14),
+            "[lines 1-2 of 10]\nline 1\nline 2\n[... 8 more lines; continue with offset 3]"
+        );
+        assert_eq!(
+            format_page(&text, &args(Some(11), None), 1024),
+            "offset 11 is past the end of `call_1`, which has 10 lines"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Checks the final message of a turn against the JSON schema the user asked
+//! for (`codex exec --output-schema` or the `outputSchema` of an MCP turn).
+//!
+//! The Responses and Chat Completions APIs constrain the output themselves.
+//! Other wire APIs only get the schema as instructions, so the final
+//! message is validated here and the model is asked to try again when it
+//! does not match.
+//!
+//! The validator covers the keywords structured-output schemas use: `type`,
+//! `properties`, `required`, `additionalProperties`, `items`, `enum`,
+//! `const`, `allOf`, `anyOf`, `oneOf` and the length and range bounds.
+//! Keywords it does not know, including `$ref`, are ignored.
+
+use serde_json::Map;
+use serde_json::Value;
+
+/// How many times the model is asked to correct a final message that does
+/// not match the schema before the turn gives up.
+pub(crate) const MAX_OUTPUT_SCHEMA_RETRIES: usize = 2;
+
+/// The JSON in `message`, without the Markdown code fence models sometimes
+/// wrap it in.
+pub(crate) fn extract_json(message: &str) -> &str {
+    let trimmed = message.trim();
+    let Some(fenced) = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    else {
+        return trimmed;
+    };
+    // The first line holds the info string, e.g. `json`.
+    match fenced.split_once('\n') {
+        Some((_, body)) => body.trim(),
+        None => trimmed,
+    }
+}
+
+/// Checks that `message` is JSON matching `schema`, and returns the JSON.
+pub(crate) fn check_final_message<'a>(message: &'a str, schema: &Value) -> Result<&'a str, String> {
+    let json = extract_json(message);
+    let value: Value = serde_json::from_str(json)
+        .map_err(|err| format!("the message is not valid JSON: {err}"))?;
+    validate(&value, schema, "$")?;
+    Ok(json)
+}
+
+/// The message that asks the model to correct its final answer.
+pub(crate) fn retry_instructions(error: &str) -> String {
+    format!(
+        "Your final message does not match the required JSON schema: {error}. Reply again with only a JSON value that matches the schema, without any other text."
+    )
+}
+
+fn validate(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(false) => return Err(format!("{path} is not allowed")),
+        Value::Object(schema) => schema,
+        _ => return Ok(()),
+    };
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+        return Err(format!(
+            "{path} should be {}, not {}",
+            types.join(" or "),
+            type_name(value)
+        ));
+    }
+    if let Some(Value::Array(options)) = schema.get("enum")
+        && !options.contains(value)
+    {
+        return Err(format!(
+            "{path} should be one of {}",
+            Value::from(options.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        return Err(format!("{path} should be {expected}"));
+    }
+
+    if let Some(Value::Array(schemas)) = schema.get("allOf") {
+        for schema in schemas {
+            validate(value, schema, path)?;
+        }
+    }
+    if let Some(Value::Array(schemas)) = schema.get("anyOf")
+        && !schemas
+            .iter()
+            .any(|schema| validate(value, schema, path).is_ok())
+    {
+        return Err(format!("{path} does not match any of the allowed schemas"));
+    }
+    if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+        let matching = schemas
+            .iter()
+            .filter(|schema| validate(value, schema, path).is_ok())
+            .count();
+        if matching != 1 {
+            return Err(format!(
+                "{path} should match exactly one of the allowed schemas, but matches {matching}"
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(object) => validate_object(object, schema, path),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item, item_schema, &format!("{path}[{index}]"))?;
+                }
+            }
+            check_bounds(
+                items.len() as f64,
+                schema,
+                "minItems",
+                "maxItems",
+                &format!("{path} item count"),
+            )
+        }
+        Value::String(text) => check_bounds(
+            text.chars().count() as f64,
+            schema,
+            "minLength",
+            "maxLength",
+            &format!("{path} length"),
+        ),
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            check_bounds(number, schema, "minimum", "maximum", path)?;
+            if let Some(bound) = schema.get("exclusiveMinimum").and_then(Value::as_f64)
+                && number <= bound
+            {
+                return Err(format!("{path} should be greater than {bound}"));
+            }
+            if let Some(bound) = schema.get("exclusiveMaximum").and_then(Value::as_f64)
+                && number >= bound
+            {
+                return Err(format!("{path} should be less than {bound}"));
+            }
+            Ok(())
+        }
+        Value::Bool(_) | Value::Null => Ok(()),
+    }
+}
+
+fn validate_object(
+    object: &Map<String, Value>,
+    schema: &Map<String, Value>,
+    path: &str,
+) -> Result<(), String> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                return Err(format!("{path} is missing the required property `{name}`"));
+            }
+        }
+    }
+    for (name, value) in object {
+        let property_path = format!("{path}.{name}");
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(property_schema) => validate(value, property_schema, &property_path)?,
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    return Err(format!("{path} has the unexpected property `{name}`"));
+                }
+                Some(additional) => validate(value, additional, &property_path)?,
+                None => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+fn check_bounds(
+    amount: f64,
+    schema: &Map<String, Value>,
+    min_key: &str,
+    max_key: &str,
+    what: &str,
+) -> Result<(), String> {
+    if let Some(min) = schema.get(min_key).and_then(Value::as_f64)
+        && amount < min
+    {
+        return Err(format!("{what} should be at least {min}"));
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_f64)
+        && amount > max
+    {
+        return Err(format!("{what} should be at most {max}"));
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64()
+                || value.is_u64()
+                || value.as_f64().is_some_and(|number| number.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "an object",
+        Value::Array(_) => "an array",
+        Value::String(_) => "a string",
+        Value::Bool(_) => "a boolean",
+        Value::Null => "null",
+        Value::Number(_) => "a number",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "status": {"type": "string", "enum": ["pass", "fail"]},
+                "failures": {
+                    "type": "array",
+                    "items": {"type": "integer", "minimum": 0},
+                    "maxItems": 2
+                },
+                "note": {"type": ["string", "null"]}
+            },
+            "required": ["status", "failures"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn accepts_matching_json_inside_a_code_fence() {
+        let message = "```json\n{\"status\": \"fail\", \"failures\": [3], \"note\": null}\n```";
+
+        assert_eq!(
+            check_final_message(message, &schema()),
+            Ok("{\"status\": \"fail\", \"failures\": [3], \"note\": null}")
+        );
+    }
+
+    #[test]
+    fn reports_the_first_mismatch_with_its_path() {
+        let cases = [
+            ("not json", "the message is not valid JSON"),
+            (
+                r#"{"status": "pass"}"#,
+                "$ is missing the required property `failures`",
+            ),
+            (
+                r#"{"status": "skipped", "failures": []}"#,
+                r#"$.status should be one of ["pass","fail"]"#,
+            ),
+            (
+                r#"{"status": "fail", "failures": [1, -2]}"#,
+                "$.failures[1] should be at least 0",
+            ),
+            (
+                r#"{"status": "fail", "failures": [1, 2, 3]}"#,
+                "$.failures item count should be at most 2",
+            ),
+            (
+                r#"{"status": "pass", "failures": [], "extra": 1}"#,
+                "$ has the unexpected property `extra`",
+            ),
+            (
+                r#"{"status": "pass", "failures": [], "note": 5}"#,
+                "$.note should be string or null, not a number",
+            ),
+        ];
+        for (message, expected) in cases {
+            let error = check_final_message(message, &schema()).expect_err(message);
+            assert!(error.starts_with(expected), "{error}");
+        }
+    }
+
+    #[test]
+    fn any_of_and_one_of_need_a_matching_branch() {
+        let schema = json!({
+            "anyOf": [{"type": "string"}, {"type": "integer"}],
+            "oneOf": [{"type": "number"}, {"type": "integer"}]
+        });
+
+        assert!(validate(&json!(1.5), &schema, "$").is_err());
+        assert_eq!(
+            validate(&json!(2), &schema, "$"),
+            Err("$ should match exactly one of the allowed schemas, but matches 2".to_string())
+        );
+        assert_eq!(
+            validate(&json!("x"), &schema, "$"),
+            Err("$ should match exactly one of the allowed schemas, but matches 0".to_string())
+        );
+    }
+}
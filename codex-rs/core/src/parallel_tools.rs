@@ -0,0 +1,111 @@
+//! Which tool calls may run at the same time as others from the same model
+//! response.
+//!
+//! Only calls that cannot change anything qualify: file reads, listings and
+//! code searches, shell commands that are known to be safe, read-only `git`
+//! subcommands, web searches and page fetches. They never ask for approval or
+//! touch the turn diff, so running them together only changes how their begin
+//! and end events interleave; their results are still recorded in the order
+//! the model made the calls.
+
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+
+use crate::command_safety::is_safe_command::is_known_safe_command;
+use crate::fetch_url::FETCH_URL_TOOL_NAME;
+use crate::file_tools::LIST_DIR_TOOL_NAME;
+use crate::file_tools::READ_FILE_TOOL_NAME;
+use crate::search_code::SEARCH_CODE_TOOL_NAME;
+use crate::tool_git::GIT_TOOL_NAME;
+use crate::tool_git::GitRisk;
+use crate::tool_git::GitToolArgs;
+use crate::tool_git::classify_git_command;
+use crate::web_search::WEB_SEARCH_TOOL_NAME;
+
+/// Whether `item` is a tool call that only reads, and so may run in parallel
+/// with its neighbours.
+pub(crate) fn is_read_only_tool_call(item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            "shell" | "container.exec" => serde_json::from_str::<ShellToolCallParams>(arguments)
+                .is_ok_and(|params| is_read_only_shell_call(&params)),
+            GIT_TOOL_NAME => serde_json::from_str::<GitToolArgs>(arguments)
+                .is_ok_and(|args| classify_git_command(&args.args) == Ok(GitRisk::Read)),
+            READ_FILE_TOOL_NAME
+            | LIST_DIR_TOOL_NAME
+            | SEARCH_CODE_TOOL_NAME
+            | WEB_SEARCH_TOOL_NAME
+            | FETCH_URL_TOOL_NAME => true,
+            _ => false,
+        },
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => is_known_safe_command(&action.command),
+        _ => false,
+    }
+}
+
+fn is_read_only_shell_call(params: &ShellToolCallParams) -> bool {
+    !params.with_escalated_permissions.unwrap_or(false)
+        && params.secrets.is_empty()
+        && !params.background
+        && !params.tty
+        && is_known_safe_command(&params.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn function_call(name: &str, arguments: serde_json::Value) -> ResponseItem {
+        ResponseItem::FunctionCall {
+            id: None,
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+            call_id: "call_1".to_string(),
+        }
+    }
+
+    #[test]
+    fn safe_commands_and_git_reads_run_in_parallel() {
+        let read_only = [
+            function_call("shell", json!({"command": ["cat", "src/lib.rs"]})),
+            function_call(
+                "shell",
+                json!({"command": ["bash", "-lc", "rg -n parallel src"]}),
+            ),
+            function_call(GIT_TOOL_NAME, json!({"args": ["log", "--oneline", "-5"]})),
+            function_call(WEB_SEARCH_TOOL_NAME, json!({"query": "tokio select"})),
+        ];
+        for item in &read_only {
+            assert!(is_read_only_tool_call(item), "{item:?}");
+        }
+    }
+
+    #[test]
+    fn calls_that_can_change_anything_run_alone() {
+        let serial = [
+            function_call("shell", json!({"command": ["rm", "-rf", "target"]})),
+            function_call(
+                "shell",
+                json!({"command": ["cat", "a.txt"], "with_escalated_permissions": true}),
+            ),
+            function_call(
+                "shell",
+                json!({"command": ["cat", "a.txt"], "secrets": ["NPM_TOKEN"]}),
+            ),
+            function_call("shell", json!({"command": ["ls"], "tty": true})),
+            function_call(GIT_TOOL_NAME, json!({"args": ["commit", "-m", "wip"]})),
+            function_call("apply_patch", json!({"input": "*** Begin Patch"})),
+            function_call("update_plan", json!({"plan": []})),
+        ];
+        for item in &serial {
+            assert!(!is_read_only_tool_call(item), "{item:?}");
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Heuristic risk scoring for proposed patches.
+//!
+//! The score is shown next to patch approval prompts and in the turn diff so
+//! that reviewers know where to look, and `escalate_patch_risk` uses it to
+//! force an approval prompt for patches that would otherwise be applied
+//! automatically.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::protocol::FileChange;
+use crate::protocol::PatchRisk;
+use crate::protocol::PatchRiskLevel;
+
+/// Patches deleting at least this many lines in total are flagged.
+const LARGE_DELETION_LINES: usize = 200;
+
+/// Path segments that mark security-sensitive code.
+const SENSITIVE_SEGMENTS: &[&str] = &[
+    "cert",
+    "certs",
+    "credential",
+    "credentials",
+    "jwt",
+    "keyring",
+    "login",
+    "oauth",
+    "passwd",
+    "password",
+    "passwords",
+    "secret",
+    "secrets",
+    "ssl",
+    "tls",
+];
+
+const MIGRATION_SEGMENTS: &[&str] = &["migrate", "migration", "migrations"];
+
+const TEST_DIRS: &[&str] = &["__tests__", "spec", "specs", "test", "tests"];
+
+/// Lines that declare a test in common languages. Removing more of them than
+/// are added means tests are being deleted.
+const TEST_MARKERS: &[&str] = &[
+    "#[test]",
+    "#[tokio::test",
+    "def test_",
+    "func Test",
+    "it(",
+    "test(",
+    "@Test",
+];
+
+/// Score `changes` (keyed by absolute path) proposed relative to `cwd`.
+pub(crate) fn assess_patch_risk(changes: &HashMap<PathBuf, FileChange>, cwd: &Path) -> PatchRisk {
+    let mut risk = PatchRisk::default();
+    let mut paths: Vec<&PathBuf> = changes.keys().collect();
+    paths.sort();
+
+    let mut deleted_lines = 0;
+    for path in paths {
+        let change = &changes[path];
+        let mut touched = vec![relative_path(path, cwd)];
+        if let FileChange::Update {
+            move_path: Some(dest),
+            ..
+        } = change
+        {
+            touched.push(relative_path(dest, cwd));
+        }
+
+        for rel in &touched {
+            let segments = path_segments(rel);
+            if is_ci_config(rel, &segments) {
+                risk.add(
+                    PatchRiskLevel::High,
+                    format!("modifies CI configuration: {rel}"),
+                );
+            }
+            if segments.iter().any(|s| is_sensitive_segment(s)) {
+                risk.add(
+                    PatchRiskLevel::High,
+                    format!("touches auth or crypto code: {rel}"),
+                );
+            }
+            if segments
+                .iter()
+                .any(|s| MIGRATION_SEGMENTS.contains(&s.as_str()))
+            {
+                risk.add(
+                    PatchRiskLevel::Medium,
+                    format!("modifies database migrations: {rel}"),
+                );
+            }
+        }
+
+        let rel = &touched[0];
+        match change {
+            FileChange::Add { .. } => {}
+            FileChange::Delete { content } => {
+                deleted_lines += content.lines().count();
+                if is_test_file(rel) {
+                    risk.add(PatchRiskLevel::High, format!("deletes test file: {rel}"));
+                }
+            }
+            FileChange::Update { unified_diff, .. } => {
+                let (added, removed) = diff_lines(unified_diff);
+                deleted_lines += removed.len();
+                let removed_tests = count_test_markers(&removed);
+                let added_tests = count_test_markers(&added);
+                if removed_tests > added_tests {
+                    risk.add(
+                        PatchRiskLevel::High,
+                        format!("removes {} test(s) from {rel}", removed_tests - added_tests),
+                    );
+                }
+            }
+        }
+    }
+
+    if deleted_lines >= LARGE_DELETION_LINES {
+        risk.add(
+            PatchRiskLevel::Medium,
+            format!("deletes {deleted_lines} lines"),
+        );
+    }
+    risk
+}
+
+fn relative_path(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Lowercased path split into words, so that `src/auth_service.rs` yields
+/// `src`, `auth`, `service` and `rs`.
+fn path_segments(rel: &str) -> Vec<String> {
+    rel.to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_sensitive_segment(segment: &str) -> bool {
+    // Covers auth, authn, authz, authentication, authorization, ...
+    segment.starts_with("auth")
+        || segment.contains("crypt")
+        || SENSITIVE_SEGMENTS.contains(&segment)
+}
+
+fn is_ci_config(rel: &str, segments: &[String]) -> bool {
+    let lower = rel.to_ascii_lowercase();
+    lower.starts_with(".github/workflows/")
+        || lower.starts_with(".circleci/")
+        || lower.starts_with(".buildkite/")
+        || matches!(
+            lower.as_str(),
+            ".gitlab-ci.yml" | ".travis.yml" | "azure-pipelines.yml" | "jenkinsfile"
+        )
+        || segments.last().is_some_and(|s| s == "jenkinsfile")
+}
+
+fn is_test_file(rel: &str) -> bool {
+    let lower = rel.to_ascii_lowercase();
+    let mut components: Vec<&str> = lower.split('/').collect();
+    let Some(file_name) = components.pop() else {
+        return false;
+    };
+    if components.iter().any(|c| TEST_DIRS.contains(c)) {
+        return true;
+    }
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
+}
+
+/// Added and removed lines of a unified diff, without the `+`/`-` prefix.
+fn diff_lines(unified_diff: &str) -> (Vec<&str>, Vec<&str>) {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for line in unified_diff.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix('+') {
+            added.push(rest);
+        } else if let Some(rest) = line.strip_prefix('-') {
+            removed.push(rest);
+        }
+    }
+    (added, removed)
+}
+
+fn count_test_markers(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .filter(|line| {
+            let line = line.trim_start();
+            TEST_MARKERS.iter().any(|marker| line.starts_with(marker))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn update(unified_diff: &str) -> FileChange {
+        FileChange::Update {
+            unified_diff: unified_diff.to_string(),
+            move_path: None,
+        }
+    }
+
+    fn assess(changes: Vec<(&str, FileChange)>) -> PatchRisk {
+        let cwd = Path::new("/repo");
+        let changes = changes
+            .into_iter()
+            .map(|(path, change)| (cwd.join(path), change))
+            .collect();
+        assess_patch_risk(&changes, cwd)
+    }
+
+    #[test]
+    fn plain_edit_is_low_risk() {
+        let risk = assess(vec![("src/lib.rs", update("@@ -1 +1 @@\n-a\n+b\n"))]);
+        assert_eq!(risk, PatchRisk::default());
+    }
+
+    #[test]
+    fn flags_sensitive_paths_and_ci() {
+        let risk = assess(vec![
+            ("src/auth_service.rs", update("@@ -1 +1 @@\n-a\n+b\n")),
+            (
+                ".github/workflows/ci.yml",
+                FileChange::Add {
+                    content: "on: push\n".to_string(),
+                },
+            ),
+            (
+                "db/migrations/0002_users.sql",
+                FileChange::Add {
+                    content: "ALTER TABLE users;\n".to_string(),
+                },
+            ),
+        ]);
+        assert_eq!(
+            risk,
+            PatchRisk {
+                level: PatchRiskLevel::High,
+                reasons: vec![
+                    "modifies CI configuration: .github/workflows/ci.yml".to_string(),
+                    "modifies database migrations: db/migrations/0002_users.sql".to_string(),
+                    "touches auth or crypto code: src/auth_service.rs".to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn flags_deleted_tests_and_large_deletions() {
+        let big_file = "line\n".repeat(LARGE_DELETION_LINES);
+        let risk = assess(vec![
+            ("tests/parser.rs", FileChange::Delete { content: big_file }),
+            (
+                "src/lib.rs",
+                update("@@ -1,3 +1 @@\n-#[test]\n-fn it_works() {}\n context\n"),
+            ),
+        ]);
+        assert_eq!(
+            risk,
+            PatchRisk {
+                level: PatchRiskLevel::High,
+                reasons: vec![
+                    "removes 1 test(s) from src/lib.rs".to_string(),
+                    "deletes test file: tests/parser.rs".to_string(),
+                    format!("deletes {} lines", LARGE_DELETION_LINES + 2),
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Loads plugins written against `codex_plugin_api` and adapts what they
+//! contribute to the session: tools, instructions and notification channels.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use codex_plugin_api::ContextRequest;
+use codex_plugin_api::Notification;
+use codex_plugin_api::NotificationChannel;
+use codex_plugin_api::PLUGIN_DECLARATION_SYMBOL;
+use codex_plugin_api::Plugin;
+use codex_plugin_api::PluginDeclaration;
+use codex_plugin_api::PluginTool;
+use codex_plugin_api::ToolCall;
+use codex_plugin_api::check_declaration;
+use tracing::error;
+use tracing::warn;
+
+use crate::config_types::PluginConfig;
+use crate::function_tool::FunctionCallError;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::plugin_tool_to_openai_tool;
+
+/// Separates the plugin name from the tool name, as for MCP tools.
+const PLUGIN_TOOL_NAME_DELIMITER: &str = "__";
+
+/// Separates plugin context from the rest of the user instructions.
+const PLUGIN_CONTEXT_SEPARATOR: &str = "\n\n--- plugin context ---\n\n";
+
+static BUILTIN_PLUGINS: Mutex<Vec<Arc<dyn Plugin>>> = Mutex::new(Vec::new());
+
+/// Plugins loaded from dynamic libraries, by path. Libraries are never
+/// unloaded because the plugins keep pointing into them.
+static LIBRARY_PLUGINS: OnceLock<Mutex<HashMap<PathBuf, Arc<dyn Plugin>>>> = OnceLock::new();
+
+/// Registers a plugin that is linked into this binary, e.g. behind a Cargo
+/// feature of a custom Codex build. Must be called before conversations are
+/// created; sessions started afterwards pick it up.
+pub fn register_builtin_plugin(plugin: Arc<dyn Plugin>) {
+    match BUILTIN_PLUGINS.lock() {
+        Ok(mut plugins) => plugins.push(plugin),
+        Err(poisoned) => poisoned.into_inner().push(plugin),
+    }
+}
+
+/// What the plugins enabled for a session contribute to it.
+#[derive(Default, Clone)]
+pub(crate) struct PluginHost {
+    plugins: Vec<Arc<dyn Plugin>>,
+    tools: HashMap<String, Arc<dyn PluginTool>>,
+    channels: Vec<Arc<dyn NotificationChannel>>,
+}
+
+impl fmt::Debug for PluginHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plugins: Vec<&str> = self.plugins.iter().map(|plugin| plugin.name()).collect();
+        f.debug_struct("PluginHost")
+            .field("plugins", &plugins)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PluginHost {
+    /// Collects the builtin plugins and loads the enabled plugin libraries.
+    /// Returns the libraries that could not be loaded alongside the host so
+    /// the session can report them.
+    pub(crate) async fn load(configs: Vec<PluginConfig>) -> (Self, Vec<String>) {
+        tokio::task::spawn_blocking(move || Self::load_blocking(&configs))
+            .await
+            .unwrap_or_else(|e| {
+                (
+                    Self::default(),
+                    vec![format!("failed to load plugins: {e}")],
+                )
+            })
+    }
+
+    fn load_blocking(configs: &[PluginConfig]) -> (Self, Vec<String>) {
+        let mut plugins = match BUILTIN_PLUGINS.lock() {
+            Ok(plugins) => plugins.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        let mut errors = Vec::new();
+        for config in configs.iter().filter(|config| config.enabled) {
+            match load_library_plugin(&config.path) {
+                Ok(plugin) => plugins.push(plugin),
+                Err(err) => errors.push(format!(
+                    "failed to load plugin `{}`: {err}",
+                    config.path.display()
+                )),
+            }
+        }
+        (Self::from_plugins(plugins), errors)
+    }
+
+    fn from_plugins(plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        let mut tools = HashMap::new();
+        let mut channels = Vec::new();
+        for plugin in &plugins {
+            for tool in plugin.tools() {
+                let name = format!(
+                    "{}{PLUGIN_TOOL_NAME_DELIMITER}{}",
+                    plugin.name(),
+                    tool.name()
+                );
+                if tools.insert(name.clone(), Arc::from(tool)).is_some() {
+                    warn!("plugin tool `{name}` is defined more than once; keeping the last one");
+                }
+            }
+            channels.extend(plugin.notification_channels().into_iter().map(Arc::from));
+        }
+        Self {
+            plugins,
+            tools,
+            channels,
+        }
+    }
+
+    /// Appends the text of every context provider to `instructions`.
+    pub(crate) async fn extend_instructions(
+        &self,
+        instructions: Option<String>,
+        cwd: PathBuf,
+    ) -> Option<String> {
+        if self.plugins.is_empty() {
+            return instructions;
+        }
+        let plugins = self.plugins.clone();
+        let context = tokio::task::spawn_blocking(move || {
+            let request = ContextRequest::new(cwd);
+            plugins
+                .iter()
+                .flat_map(|plugin| plugin.context_providers())
+                .filter_map(|provider| provider.provide(&request))
+                .filter(|text| !text.trim().is_empty())
+                .collect::<Vec<String>>()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            error!("plugin context provider panicked: {e}");
+            Vec::new()
+        });
+        if context.is_empty() {
+            return instructions;
+        }
+        let context = context.join("\n\n");
+        match instructions {
+            Some(instructions) => {
+                Some(format!("{instructions}{PLUGIN_CONTEXT_SEPARATOR}{context}"))
+            }
+            None => Some(context),
+        }
+    }
+
+    /// Function tool definitions for the model, sorted by name to keep the
+    /// prompt stable.
+    pub(crate) fn openai_tools(&self) -> Vec<OpenAiTool> {
+        let mut names: Vec<&String> = self.tools.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let tool = &self.tools[name];
+                match plugin_tool_to_openai_tool(
+                    name.clone(),
+                    tool.description().to_string(),
+                    tool.parameters(),
+                ) {
+                    Ok(tool) => Some(OpenAiTool::Function(tool)),
+                    Err(e) => {
+                        error!("Failed to convert {name:?} plugin tool: {e:?}");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn has_tool(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    /// Runs a plugin tool on a blocking thread.
+    pub(crate) async fn call_tool(
+        &self,
+        name: &str,
+        arguments: String,
+        cwd: PathBuf,
+    ) -> Result<String, FunctionCallError> {
+        let Some(tool) = self.tools.get(name).cloned() else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported call: {name}"
+            )));
+        };
+        let arguments = if arguments.trim().is_empty() {
+            serde_json::Value::Object(serde_json::Map::new())
+        } else {
+            serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?
+        };
+        tokio::task::spawn_blocking(move || tool.call(ToolCall::new(arguments, cwd)))
+            .await
+            .map_err(|e| FunctionCallError::RespondToModel(format!("plugin tool panicked: {e}")))?
+            .map_err(FunctionCallError::RespondToModel)
+    }
+
+    /// Delivers `notification` to every plugin channel without waiting.
+    pub(crate) fn notify(&self, notification: Notification) {
+        if self.channels.is_empty() {
+            return;
+        }
+        let channels = self.channels.clone();
+        tokio::task::spawn_blocking(move || {
+            for channel in channels {
+                channel.notify(&notification);
+            }
+        });
+    }
+}
+
+fn load_library_plugin(path: &Path) -> Result<Arc<dyn Plugin>, String> {
+    let mut loaded = match LIBRARY_PLUGINS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+    {
+        Ok(loaded) => loaded,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if let Some(plugin) = loaded.get(path) {
+        return Ok(plugin.clone());
+    }
+
+    // SAFETY: loading a library runs its initializers; plugins are trusted
+    // code configured by the user.
+    let library = unsafe { libloading::Library::new(path) }.map_err(|e| e.to_string())?;
+    // SAFETY: `declare_plugin!` exports the symbol as a `PluginDeclaration`.
+    // Only the version fields are read until the handshake succeeds.
+    let declaration: &PluginDeclaration = unsafe {
+        let symbol = library
+            .get::<*const PluginDeclaration>(PLUGIN_DECLARATION_SYMBOL.as_bytes())
+            .map_err(|e| e.to_string())?;
+        &**symbol
+    };
+    check_declaration(declaration)?;
+    let plugin: Arc<dyn Plugin> = Arc::from((declaration.create)());
+
+    // Keep the code of the plugin mapped for the rest of the process.
+    std::mem::forget(library);
+    loaded.insert(path.to_path_buf(), plugin.clone());
+    Ok(plugin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_plugin_api::ContextProvider;
+    use pretty_assertions::assert_eq;
+
+    struct EchoTool;
+
+    impl PluginTool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Returns its `text` argument."
+        }
+
+        fn parameters(&self) -> serde_json::Value {
+            serde_json::json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"],
+            })
+        }
+
+        fn call(&self, call: ToolCall) -> Result<String, String> {
+            call.arguments["text"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| "missing text".to_string())
+        }
+    }
+
+    struct Conventions;
+
+    impl ContextProvider for Conventions {
+        fn name(&self) -> &str {
+            "conventions"
+        }
+
+        fn provide(&self, _request: &ContextRequest) -> Option<String> {
+            Some("Use tabs.".to_string())
+        }
+    }
+
+    struct TestPlugin;
+
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn tools(&self) -> Vec<Box<dyn PluginTool>> {
+            vec![Box::new(EchoTool)]
+        }
+
+        fn context_providers(&self) -> Vec<Box<dyn ContextProvider>> {
+            vec![Box::new(Conventions)]
+        }
+    }
+
+    #[tokio::test]
+    async fn tools_are_namespaced_by_plugin() {
+        let host = PluginHost::from_plugins(vec![Arc::new(TestPlugin)]);
+        assert!(host.has_tool("test__echo"));
+        assert!(!host.has_tool("echo"));
+
+        let output = host
+            .call_tool(
+                "test__echo",
+                r#"{"text":"hi"}"#.to_string(),
+                PathBuf::from("/"),
+            )
+            .await;
+        assert_eq!(output, Ok("hi".to_string()));
+
+        let error = host
+            .call_tool("test__echo", "{}".to_string(), PathBuf::from("/"))
+            .await;
+        assert_eq!(
+            error,
+            Err(FunctionCallError::RespondToModel(
+                "missing text".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn context_is_appended_to_instructions() {
+        let host = PluginHost::from_plugins(vec![Arc::new(TestPlugin)]);
+        assert_eq!(
+            host.extend_instructions(Some("Be brief.".to_string()), PathBuf::from("/"))
+                .await,
+            Some(format!("Be brief.{PLUGIN_CONTEXT_SEPARATOR}Use tabs."))
+        );
+        assert_eq!(
+            host.extend_instructions(None, PathBuf::from("/")).await,
+            Some("Use tabs.".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_library_is_reported() {
+        let (host, errors) = PluginHost::load(vec![PluginConfig {
+            path: PathBuf::from("/does/not/exist/libplugin.so"),
+            enabled: true,
+        }])
+        .await;
+        assert!(host.tools.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("failed to load plugin `/does/not/exist/libplugin.so`"));
+    }
+}
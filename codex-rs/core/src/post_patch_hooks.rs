@@ -0,0 +1,221 @@
+//! Formatters and linters run on the files each applied patch touched
+//! (`[hooks.post_patch]`), so that the agent's edits follow the repository's
+//! formatting without spending turns on it.
+//!
+//! Hooks run in name order after the patch is applied, before it is
+//! journaled or committed. What they report back to the model: hooks that
+//! failed, with their output, and files they changed, which the model has to
+//! re-read before patching them again.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::time::timeout;
+use wildmatch::WildMatchPattern;
+
+use crate::config_types::PostPatchHook;
+use crate::protocol::FileChange;
+use crate::truncate::truncate_middle;
+
+pub(crate) const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Output of a failed hook kept for the model.
+const HOOK_OUTPUT_MAX_BYTES: usize = 4 * 1024;
+
+/// Runs `hooks` on the files `changes`, a patch applied in `cwd`, added or
+/// updated. Returns a report for the model, or `None` when every hook that
+/// ran succeeded without changing any file.
+pub(crate) async fn run_post_patch_hooks(
+    hooks: &BTreeMap<String, PostPatchHook>,
+    cwd: &Path,
+    changes: &HashMap<PathBuf, FileChange>,
+) -> Option<String> {
+    if hooks.is_empty() {
+        return None;
+    }
+    let mut touched: Vec<PathBuf> = changes
+        .iter()
+        .filter_map(|(path, change)| match change {
+            FileChange::Add { .. } => Some(cwd.join(path)),
+            FileChange::Update { move_path, .. } => {
+                Some(cwd.join(move_path.as_ref().unwrap_or(path)))
+            }
+            FileChange::Delete { .. } => None,
+        })
+        .collect();
+    touched.sort();
+    touched.dedup();
+
+    let mut report: Vec<String> = Vec::new();
+    for (name, hook) in hooks {
+        let files: Vec<PathBuf> = touched
+            .iter()
+            .map(|path| path.strip_prefix(cwd).unwrap_or(path).to_path_buf())
+            .filter(|path| matches_files(hook, path))
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+        if let Some(line) = run_hook(name, hook, cwd, &files).await {
+            report.push(line);
+        }
+    }
+    (!report.is_empty()).then(|| report.join("\n"))
+}
+
+fn matches_files(hook: &PostPatchHook, path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    hook.files.is_empty()
+        || hook
+            .files
+            .iter()
+            .any(|pattern| WildMatchPattern::<'*', '?'>::new(pattern).matches(&path))
+}
+
+/// Runs one hook on `files`, paths relative to `cwd`. Returns what to tell
+/// the model, if anything.
+async fn run_hook(
+    name: &str,
+    hook: &PostPatchHook,
+    cwd: &Path,
+    files: &[PathBuf],
+) -> Option<String> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Some(format!("Post-patch hook `{name}` has no command."));
+    };
+    let mut before = Vec::with_capacity(files.len());
+    for file in files {
+        before.push(tokio::fs::read(cwd.join(file)).await.ok());
+    }
+
+    let hook_timeout = hook
+        .timeout_ms
+        .map_or(DEFAULT_HOOK_TIMEOUT, Duration::from_millis);
+    let child = Command::new(program)
+        .args(args)
+        .args(files)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match timeout(hook_timeout, child).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Some(format!("Post-patch hook `{name}` could not run: {e}")),
+        Err(_) => {
+            return Some(format!(
+                "Post-patch hook `{name}` timed out after {} ms.",
+                hook_timeout.as_millis()
+            ));
+        }
+    };
+
+    if !output.status.success() {
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        let (text, _) = truncate_middle(text.trim(), HOOK_OUTPUT_MAX_BYTES);
+        let status = match output.status.code() {
+            Some(code) => format!("exit code {code}"),
+            None => "killed by a signal".to_string(),
+        };
+        return Some(format!(
+            "Post-patch hook `{name}` failed ({status}):\n{text}"
+        ));
+    }
+
+    let mut changed = Vec::new();
+    for (file, before) in files.iter().zip(before) {
+        if tokio::fs::read(cwd.join(file)).await.ok() != before {
+            changed.push(file.display().to_string());
+        }
+    }
+    (!changed.is_empty()).then(|| {
+        format!(
+            "Post-patch hook `{name}` changed {}; re-read before editing again.",
+            changed.join(", ")
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn hook(command: &[&str], files: &[&str]) -> PostPatchHook {
+        PostPatchHook {
+            command: command.iter().map(ToString::to_string).collect(),
+            files: files.iter().map(ToString::to_string).collect(),
+            timeout_ms: None,
+        }
+    }
+
+    fn added(paths: &[&str]) -> HashMap<PathBuf, FileChange> {
+        paths
+            .iter()
+            .map(|path| {
+                (
+                    PathBuf::from(path),
+                    FileChange::Add {
+                        content: String::new(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn hooks_run_on_matching_files_and_report_changes() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").expect("write");
+        std::fs::write(dir.path().join("b.md"), "# b\n").expect("write");
+        // Stands in for a formatter: appends to each file it is given.
+        let hooks = BTreeMap::from([(
+            "fmt".to_string(),
+            hook(
+                &["sh", "-c", "for f; do echo '// fmt' >> \"$f\"; done", "sh"],
+                &["*.rs"],
+            ),
+        )]);
+
+        let report = run_post_patch_hooks(&hooks, dir.path(), &added(&["a.rs", "b.md"])).await;
+
+        assert_eq!(
+            report.as_deref(),
+            Some("Post-patch hook `fmt` changed a.rs; re-read before editing again.")
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("a.rs")).expect("read"),
+            "fn a() {}\n// fmt\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b.md")).expect("read"),
+            "# b\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_hooks_report_their_output() {
+        let dir = TempDir::new().expect("tempdir");
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").expect("write");
+        let hooks = BTreeMap::from([
+            (
+                "lint".to_string(),
+                hook(&["sh", "-c", "echo \"bad: $1\" >&2; exit 3", "sh"], &[]),
+            ),
+            ("noop".to_string(), hook(&["true"], &[])),
+        ]);
+
+        let report = run_post_patch_hooks(&hooks, dir.path(), &added(&["a.rs"])).await;
+
+        assert_eq!(
+            report.as_deref(),
+            Some("Post-patch hook `lint` failed (exit code 3):\nbad: a.rs")
+        );
+    }
+}
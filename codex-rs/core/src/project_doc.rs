@@ -11,23 +11,46 @@
 //!     current working directory (inclusive) and concatenate their contents in
 //!     that order.
 //! 3.  We do **not** walk past the Git root.
+//!
+//! A file may start with a frontmatter block (see [`Frontmatter`]) that
+//! limits it to some working directories, orders it by priority or caps its
+//! size, and may pull in other files with `@include <path>` lines.
 
 use crate::config::Config;
+use crate::memory::read_memory;
+use crate::survey::read_survey;
+use crate::token_count::count_tokens;
+use std::cmp::Reverse;
+use std::io::Read;
+use std::path::Path;
 use std::path::PathBuf;
-use tokio::io::AsyncReadExt;
 use tracing::error;
+use wildmatch::WildMatch;
 
 /// Currently, we only match the filename `AGENTS.md` exactly.
 const CANDIDATE_FILENAMES: &[&str] = &["AGENTS.md"];
 
+/// A line starting with this directive is replaced by the file it names.
+const INCLUDE_DIRECTIVE: &str = "@include ";
+
+/// How deeply `@include`s may nest.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
 /// When both `Config::instructions` and the project doc are present, they will
 /// be concatenated with the following separator.
 const PROJECT_DOC_SEPARATOR: &str = "\n\n--- project-doc ---\n\n";
 
-/// Combines `Config::instructions` and `AGENTS.md` (if present) into a single
-/// string of instructions.
-pub(crate) async fn get_user_instructions(config: &Config) -> Option<String> {
-    match read_project_docs(config).await {
+/// Separator placed before a stored repository survey (see [`crate::survey`]).
+const SURVEY_SEPARATOR: &str = "\n\n--- repository-survey ---\n\n";
+
+/// Separator placed before the project's notes (see [`crate::memory`]).
+const MEMORY_SEPARATOR: &str = "\n\n--- project-memory ---\n\n";
+
+/// Combines `Config::instructions`, `AGENTS.md` (if present), the stored
+/// repository survey and the project memory (if any) into a single string of
+/// instructions.
+pub async fn get_user_instructions(config: &Config) -> Option<String> {
+    let instructions = match read_project_docs(config).await {
         Ok(Some(project_doc)) => match &config.user_instructions {
             Some(original_instructions) => Some(format!(
                 "{original_instructions}{PROJECT_DOC_SEPARATOR}{project_doc}"
@@ -39,6 +62,25 @@ pub(crate) async fn get_user_instructions(config: &Config) -> Option<String> {
             error!("error trying to find project doc: {e:#}");
             config.user_instructions.clone()
         }
+    };
+
+    let instructions = match read_survey(&config.codex_home, &config.cwd).await {
+        Some(survey) => match instructions {
+            Some(instructions) => Some(format!("{instructions}{SURVEY_SEPARATOR}{survey}")),
+            None => Some(survey),
+        },
+        None => instructions,
+    };
+
+    if !config.memory.enabled {
+        return instructions;
+    }
+    match read_memory(&config.codex_home, &config.cwd, config.memory.max_bytes).await {
+        Some(memory) => match instructions {
+            Some(instructions) => Some(format!("{instructions}{MEMORY_SEPARATOR}{memory}")),
+            None => Some(memory),
+        },
+        None => instructions,
     }
 }
 
@@ -55,50 +97,334 @@ pub async fn read_project_docs(config: &Config) -> std::io::Result<Option<String
         return Ok(None);
     }
 
-    let paths = discover_project_doc_paths(config)?;
-    if paths.is_empty() {
-        return Ok(None);
-    }
+    let docs = load_project_docs(config)?;
 
-    let mut remaining: u64 = max_total as u64;
-    let mut parts: Vec<String> = Vec::new();
-
-    for p in paths {
+    // Spend the budget on the highest-priority docs first; among docs of the
+    // same priority, those closer to the repository root come first.
+    let mut by_priority: Vec<usize> = (0..docs.len()).collect();
+    by_priority.sort_by_key(|&i| Reverse(docs[i].priority));
+    let mut remaining = max_total;
+    let mut kept: Vec<Option<&str>> = vec![None; docs.len()];
+    for i in by_priority {
         if remaining == 0 {
             break;
         }
+        let doc = &docs[i];
+        let text = truncate_to_bytes(&doc.contents, remaining);
+        if text.len() < doc.contents.len() {
+            tracing::warn!(
+                "Project doc `{}` exceeds remaining budget ({} bytes) - truncating.",
+                doc.path.display(),
+                remaining,
+            );
+        }
+        remaining -= text.len();
+        kept[i] = Some(text);
+    }
 
-        let file = match tokio::fs::File::open(&p).await {
-            Ok(f) => f,
+    let parts: Vec<&str> = kept
+        .into_iter()
+        .flatten()
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    if parts.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(parts.join("\n\n")))
+    }
+}
+
+/// An AGENTS.md file that applies to the working directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectDoc {
+    pub path: PathBuf,
+    /// From the frontmatter; higher-priority docs come later and are the last
+    /// to be truncated.
+    pub priority: i64,
+    /// The doc without its frontmatter, with `@include`s expanded.
+    pub contents: String,
+}
+
+/// Settings in the frontmatter of an AGENTS.md file:
+///
+/// ```text
+/// ---
+/// scope: ["services/api/**", "libs/**"]
+/// priority: 10
+/// max_tokens: 2000
+/// ---
+/// ```
+#[derive(Debug, Default, PartialEq)]
+struct Frontmatter {
+    /// Globs, relative to the doc's directory, of the working directories
+    /// the doc applies to. Empty means everywhere below the doc.
+    scope: Vec<String>,
+    priority: i64,
+    max_tokens: Option<u64>,
+}
+
+impl Frontmatter {
+    /// Whether a doc in `doc_dir` applies when working in `cwd`. A glob
+    /// ending in `/**` also matches the directory it starts from.
+    fn applies_to(&self, doc_dir: &Path, cwd: &Path) -> bool {
+        if self.scope.is_empty() {
+            return true;
+        }
+        let relative = cwd
+            .strip_prefix(doc_dir)
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        self.scope.iter().any(|glob| {
+            WildMatch::new(glob).matches(&relative)
+                || glob
+                    .strip_suffix("/**")
+                    .is_some_and(|base| WildMatch::new(base).matches(&relative))
+        })
+    }
+}
+
+/// Loads the AGENTS.md files from the repository root down to the working
+/// directory that apply to it, in the order they are merged: by priority,
+/// then from the root down.
+pub fn load_project_docs(config: &Config) -> std::io::Result<Vec<ProjectDoc>> {
+    let cwd = config
+        .cwd
+        .canonicalize()
+        .unwrap_or_else(|_| config.cwd.clone());
+    let include_root = include_root(&cwd);
+    let max_bytes = config.project_doc_max_bytes;
+    let mut docs = Vec::new();
+    for path in discover_project_doc_paths(config)? {
+        let text = match read_capped(&path, max_bytes) {
+            Ok(text) => text,
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
             Err(e) => return Err(e),
         };
+        let (frontmatter, body) = parse_frontmatter(&text, &path);
+        let doc_dir = path.parent().unwrap_or(Path::new("/"));
+        if !frontmatter.applies_to(doc_dir, &cwd) {
+            continue;
+        }
+        let mut stack = vec![path.canonicalize().unwrap_or_else(|_| path.clone())];
+        let includes = Includes {
+            root: &include_root,
+            max_bytes,
+        };
+        let mut contents = expand_includes(body, doc_dir, &includes, &mut stack);
+        if let Some(max_tokens) = frontmatter.max_tokens {
+            contents = truncate_to_tokens(&contents, max_tokens);
+        }
+        docs.push(ProjectDoc {
+            path,
+            priority: frontmatter.priority,
+            contents,
+        });
+    }
+    docs.sort_by_key(|doc| doc.priority);
+    Ok(docs)
+}
+
+/// Splits `text` into its frontmatter, if it starts with one, and the rest.
+/// Unknown keys and invalid values are ignored with a warning.
+fn parse_frontmatter<'a>(text: &'a str, path: &Path) -> (Frontmatter, &'a str) {
+    let mut frontmatter = Frontmatter::default();
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (frontmatter, text);
+    };
+    let Some((header, body)) = rest
+        .split_once("\n---\n")
+        .or_else(|| rest.split_once("\r\n---\r\n"))
+        .or_else(|| rest.strip_suffix("\n---").map(|header| (header, "")))
+    else {
+        return (frontmatter, text);
+    };
+
+    // Whether the lines are items of a block list of scope globs.
+    let mut in_scope_list = false;
+    for line in header.lines() {
+        let line = line.trim_end();
+        if let Some(item) = line.trim_start().strip_prefix("- ")
+            && in_scope_list
+        {
+            frontmatter.scope.push(unquote(item).to_string());
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        in_scope_list = key == "scope" && value.is_empty();
+        match key {
+            "scope" if value.starts_with('[') && value.ends_with(']') => {
+                frontmatter.scope = value[1..value.len() - 1]
+                    .split(',')
+                    .map(unquote)
+                    .filter(|glob| !glob.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "scope" if !value.is_empty() => frontmatter.scope = vec![unquote(value).to_string()],
+            "scope" => {}
+            "priority" => match value.parse() {
+                Ok(priority) => frontmatter.priority = priority,
+                Err(_) => warn_frontmatter(path, key, value),
+            },
+            "max_tokens" => match value.parse() {
+                Ok(max_tokens) => frontmatter.max_tokens = Some(max_tokens),
+                Err(_) => warn_frontmatter(path, key, value),
+            },
+            _ => tracing::warn!(
+                "Ignoring unknown frontmatter key `{key}` in `{}`.",
+                path.display()
+            ),
+        }
+    }
+    (frontmatter, body)
+}
 
-        let size = file.metadata().await?.len();
-        let mut reader = tokio::io::BufReader::new(file).take(remaining);
-        let mut data: Vec<u8> = Vec::new();
-        reader.read_to_end(&mut data).await?;
+fn warn_frontmatter(path: &Path, key: &str, value: &str) {
+    tracing::warn!(
+        "Ignoring invalid frontmatter value `{key}: {value}` in `{}`.",
+        path.display()
+    );
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        })
+        .unwrap_or(value)
+}
+
+/// Reads at most `max_bytes` bytes of `path`, so a huge file (or a device
+/// such as `/dev/zero`) cannot exhaust memory.
+fn read_capped(path: &Path, max_bytes: usize) -> std::io::Result<String> {
+    let mut data = Vec::new();
+    std::fs::File::open(path)?
+        .take(max_bytes as u64)
+        .read_to_end(&mut data)?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// The directory `@include`s must stay within: the Git root of `cwd`, or
+/// `cwd` itself outside a repository.
+fn include_root(cwd: &Path) -> PathBuf {
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(cwd)
+        .to_path_buf()
+}
+
+/// Limits on the files that `@include` lines may pull in.
+struct Includes<'a> {
+    /// Canonical directory that included files must be inside.
+    root: &'a Path,
+    /// `project_doc_max_bytes`: no more is read from a file, and no more
+    /// files are included once a doc has grown past it.
+    max_bytes: usize,
+}
 
-        if size > remaining {
+/// Replaces each `@include <path>` line outside code fences with the file
+/// it names, relative to `dir`, recursively. `stack` holds the files being
+/// expanded, to break include cycles.
+fn expand_includes(
+    text: &str,
+    dir: &Path,
+    includes: &Includes,
+    stack: &mut Vec<PathBuf>,
+) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let Some(target) = trimmed
+            .strip_prefix(INCLUDE_DIRECTIVE)
+            .filter(|_| !in_fence)
+        else {
+            expanded.push_str(line);
+            continue;
+        };
+        let path = dir.join(unquote(target));
+        let canonical = match path.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(e) => {
+                tracing::warn!("Cannot include `{}`: {e}", path.display());
+                continue;
+            }
+        };
+        if !canonical.starts_with(includes.root) {
             tracing::warn!(
-                "Project doc `{}` exceeds remaining budget ({} bytes) - truncating.",
-                p.display(),
-                remaining,
+                "Not including `{}`: it is outside `{}`.",
+                path.display(),
+                includes.root.display()
             );
+            continue;
         }
-
-        let text = String::from_utf8_lossy(&data).to_string();
-        if !text.trim().is_empty() {
-            parts.push(text);
-            remaining = remaining.saturating_sub(data.len() as u64);
+        if expanded.len() >= includes.max_bytes {
+            tracing::warn!(
+                "Not including `{}`: the doc already exceeds project_doc_max_bytes.",
+                path.display()
+            );
+            continue;
         }
+        if stack.contains(&canonical) || stack.len() > MAX_INCLUDE_DEPTH {
+            tracing::warn!(
+                "Not including `{}`: include cycle or too deeply nested.",
+                path.display()
+            );
+            continue;
+        }
+        let included = match read_capped(&canonical, includes.max_bytes) {
+            Ok(included) => included,
+            Err(e) => {
+                tracing::warn!("Cannot include `{}`: {e}", path.display());
+                continue;
+            }
+        };
+        let (_, body) = parse_frontmatter(&included, &path);
+        stack.push(canonical);
+        let body = expand_includes(body, path.parent().unwrap_or(dir), includes, stack);
+        stack.pop();
+        expanded.push_str(body.trim_end_matches('\n'));
+        expanded.push('\n');
     }
+    expanded
+}
 
-    if parts.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(parts.join("\n\n")))
+/// The longest prefix of `text` of at most `max_bytes` bytes that ends on a
+/// character boundary.
+fn truncate_to_bytes(text: &str, max_bytes: usize) -> &str {
+    let mut end = text.len().min(max_bytes);
+    while !text.is_char_boundary(end) {
+        end -= 1;
     }
+    &text[..end]
+}
+
+/// The whole lines of `text` that fit in an estimated `max_tokens` tokens.
+fn truncate_to_tokens(text: &str, max_tokens: u64) -> String {
+    let mut truncated = String::new();
+    let mut tokens = 0;
+    for line in text.split_inclusive('\n') {
+        tokens += count_tokens(line);
+        if tokens > max_tokens {
+            break;
+        }
+        truncated.push_str(line);
+    }
+    truncated
 }
 
 /// Discover the list of AGENTS.md files using the same search rules as
@@ -307,6 +633,47 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    /// A stored survey for the repository is appended after the project doc.
+    #[tokio::test]
+    async fn appends_stored_survey() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("AGENTS.md"), "proj doc").unwrap();
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let mut cfg = make_config(&tmp, 4096, None);
+        cfg.codex_home = codex_home.path().to_path_buf();
+
+        let survey = crate::survey::survey_path(&cfg.codex_home, &cfg.cwd);
+        fs::create_dir_all(survey.parent().unwrap()).unwrap();
+        fs::write(&survey, "# Architecture overview\n").unwrap();
+
+        let res = get_user_instructions(&cfg).await.expect("instructions");
+        assert_eq!(
+            res,
+            format!("proj doc{SURVEY_SEPARATOR}# Architecture overview")
+        );
+    }
+
+    /// With memory enabled, the project's notes come last.
+    #[tokio::test]
+    async fn appends_project_memory_when_enabled() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        fs::write(tmp.path().join("AGENTS.md"), "proj doc").unwrap();
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let mut cfg = make_config(&tmp, 4096, None);
+        cfg.codex_home = codex_home.path().to_path_buf();
+
+        let memory = crate::memory::memory_path(&cfg.codex_home, &cfg.cwd);
+        fs::create_dir_all(memory.parent().unwrap()).unwrap();
+        fs::write(&memory, "- Use pnpm.\n").unwrap();
+
+        let res = get_user_instructions(&cfg).await.expect("instructions");
+        assert_eq!(res, "proj doc");
+
+        cfg.memory.enabled = true;
+        let res = get_user_instructions(&cfg).await.expect("instructions");
+        assert_eq!(res, format!("proj doc{MEMORY_SEPARATOR}- Use pnpm."));
+    }
+
     /// If there are existing system instructions but the project doc is
     /// missing we expect the original instructions to be returned unchanged.
     #[tokio::test]
@@ -347,4 +714,114 @@ mod tests {
         let res = get_user_instructions(&cfg).await.expect("doc expected");
         assert_eq!(res, "root doc\n\ncrate doc");
     }
+
+    /// Frontmatter scopes a doc to working directories and orders docs by
+    /// priority; `@include` lines pull in other files.
+    #[tokio::test]
+    async fn honors_frontmatter_and_includes() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        std::fs::write(repo.path().join(".git"), "gitdir: /path/to/git\n").unwrap();
+        fs::create_dir_all(repo.path().join("docs")).unwrap();
+        fs::write(repo.path().join("docs/style.md"), "Use tabs.\n").unwrap();
+        fs::write(
+            repo.path().join("AGENTS.md"),
+            "---\npriority: 5\n---\nroot doc\n@include docs/style.md\n```\n@include docs/style.md\n```\n",
+        )
+        .unwrap();
+        let api = repo.path().join("services/api");
+        std::fs::create_dir_all(&api).unwrap();
+        fs::write(
+            repo.path().join("services/AGENTS.md"),
+            "---\nscope:\n  - \"api/**\"\n---\nservices doc\n",
+        )
+        .unwrap();
+
+        let mut cfg = make_config(&repo, 4096, None);
+        cfg.cwd = api;
+        let res = get_user_instructions(&cfg).await.expect("doc expected");
+        assert_eq!(
+            res,
+            "services doc\n\n\nroot doc\nUse tabs.\n```\n@include docs/style.md\n```\n"
+        );
+
+        cfg.cwd = repo.path().join("services");
+        let docs = load_project_docs(&cfg).expect("docs");
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].priority, 5);
+    }
+
+    /// `max_tokens` keeps the whole lines of a doc that fit.
+    #[test]
+    fn max_tokens_truncates_whole_lines() {
+        let text = "---\nmax_tokens: 3\npriority: high\n---\nfirst line\nsecond line\n";
+        let (frontmatter, body) = parse_frontmatter(text, Path::new("AGENTS.md"));
+        assert_eq!(
+            frontmatter,
+            Frontmatter {
+                scope: Vec::new(),
+                priority: 0,
+                max_tokens: Some(3),
+            }
+        );
+        assert_eq!(truncate_to_tokens(body, 3), "first line\n");
+    }
+
+    /// Include cycles are broken instead of recursing forever.
+    #[test]
+    fn include_cycles_are_skipped() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("a.md"), "a\n@include b.md\n").unwrap();
+        fs::write(dir.path().join("b.md"), "b\n@include a.md\n").unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let includes = Includes {
+            root: &root,
+            max_bytes: 4096,
+        };
+        let mut stack = vec![dir.path().join("a.md").canonicalize().unwrap()];
+        assert_eq!(
+            expand_includes("a\n@include b.md\n", dir.path(), &includes, &mut stack),
+            "a\nb\n"
+        );
+    }
+
+    /// Files outside the repository are never included, even via `..` or an
+    /// absolute path.
+    #[test]
+    fn includes_stay_inside_the_repository() {
+        let outside = tempfile::tempdir().expect("tempdir");
+        fs::write(outside.path().join("secret.txt"), "secret\n").unwrap();
+        let repo = outside.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(repo.join("style.md"), "style\n").unwrap();
+        let root = include_root(&repo.canonicalize().unwrap());
+        let includes = Includes {
+            root: &root,
+            max_bytes: 4096,
+        };
+
+        let text = format!(
+            "@include style.md\n@include ../secret.txt\n@include {}\n",
+            outside.path().join("secret.txt").display()
+        );
+        assert_eq!(
+            expand_includes(&text, &repo, &includes, &mut Vec::new()),
+            "style\n"
+        );
+    }
+
+    /// Included files are read up to `project_doc_max_bytes`.
+    #[test]
+    fn includes_are_read_up_to_the_byte_limit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("big.md"), "x".repeat(100)).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let includes = Includes {
+            root: &root,
+            max_bytes: 10,
+        };
+        assert_eq!(
+            expand_includes("@include big.md\n", dir.path(), &includes, &mut Vec::new()),
+            format!("{}\n", "x".repeat(10))
+        );
+    }
 }
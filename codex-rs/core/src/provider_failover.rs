@@ -0,0 +1,288 @@
+//! Failover between the base URLs of a model provider.
+//!
+//! A provider with `fallback_base_urls` sends requests to its `base_url` until
+//! [`FAILOVER_THRESHOLD`] requests in a row fail with a connection error or a
+//! server error, then moves on to the next URL. Once requests have moved away
+//! from the primary URL they stay on the fallback (sticky) until a health
+//! check shows the primary is reachable again; the check runs at most every
+//! [`RECOVERY_PROBE_INTERVAL`].
+//!
+//! The state is shared by every session in the process that uses the same
+//! URLs, so an outage is detected once rather than by each session.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::model_provider_info::ModelProviderInfo;
+
+/// Consecutive failed requests after which requests move to the next URL.
+const FAILOVER_THRESHOLD: u32 = 2;
+
+/// How often a provider that failed over checks whether its primary URL is
+/// healthy again.
+const RECOVERY_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Switches kept for sessions that have yet to report them.
+const MAX_SWITCH_HISTORY: usize = 16;
+
+static FAILOVERS: LazyLock<Mutex<HashMap<Vec<String>, Arc<EndpointFailover>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EndpointSwitch {
+    pub(crate) seq: u64,
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) reason: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct EndpointFailover {
+    base_urls: Vec<String>,
+    state: Mutex<FailoverState>,
+}
+
+#[derive(Debug, Default)]
+struct FailoverState {
+    active: usize,
+    consecutive_failures: u32,
+    next_probe_at: Option<Instant>,
+    switches: VecDeque<EndpointSwitch>,
+    next_switch_seq: u64,
+}
+
+/// The endpoint a request was sent to, for reporting how it went.
+pub(crate) struct Endpoint {
+    failover: Arc<EndpointFailover>,
+    index: usize,
+}
+
+impl Endpoint {
+    pub(crate) fn succeeded(&self) {
+        self.failover.record_success(self.index);
+    }
+
+    /// Records a failed request. Returns whether the next request goes to a
+    /// different URL, in which case there is no point in backing off.
+    pub(crate) fn failed(&self, reason: &str) -> bool {
+        self.failover.record_failure(self.index, reason)
+    }
+}
+
+impl EndpointFailover {
+    /// The failover state for `provider`, or `None` if it has no fallback
+    /// URLs.
+    pub(crate) fn for_provider(provider: &ModelProviderInfo) -> Option<Arc<Self>> {
+        let primary = provider.base_url.as_ref()?;
+        let fallbacks = provider
+            .fallback_base_urls
+            .as_ref()
+            .filter(|urls| !urls.is_empty())?;
+        let base_urls: Vec<String> = std::iter::once(primary).chain(fallbacks).cloned().collect();
+        let mut failovers = FAILOVERS.lock().unwrap_or_else(PoisonError::into_inner);
+        Some(Arc::clone(
+            failovers
+                .entry(base_urls.clone())
+                .or_insert_with(|| Arc::new(Self::new(base_urls))),
+        ))
+    }
+
+    fn new(base_urls: Vec<String>) -> Self {
+        Self {
+            base_urls,
+            state: Mutex::new(FailoverState::default()),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, FailoverState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// `provider` pointed at the URL the next request should go to. Checks
+    /// whether the primary URL has recovered first, when that is due.
+    pub(crate) async fn select<'a>(
+        self: &Arc<Self>,
+        provider: &'a ModelProviderInfo,
+        client: &reqwest::Client,
+    ) -> (Cow<'a, ModelProviderInfo>, Endpoint) {
+        let probe = {
+            let mut state = self.state();
+            let now = Instant::now();
+            let due = state.active != 0 && state.next_probe_at.is_none_or(|at| now >= at);
+            if due {
+                state.next_probe_at = Some(now + RECOVERY_PROBE_INTERVAL);
+            }
+            due
+        };
+        if probe && is_healthy(client, &self.base_urls[0]).await {
+            let mut state = self.state();
+            if state.active != 0 {
+                self.switch(&mut state, 0, "the primary endpoint is reachable again");
+            }
+        }
+
+        let index = self.state().active;
+        let provider = if index == 0 {
+            Cow::Borrowed(provider)
+        } else {
+            Cow::Owned(ModelProviderInfo {
+                base_url: Some(self.base_urls[index].clone()),
+                ..provider.clone()
+            })
+        };
+        let endpoint = Endpoint {
+            failover: Arc::clone(self),
+            index,
+        };
+        (provider, endpoint)
+    }
+
+    fn record_success(&self, index: usize) {
+        let mut state = self.state();
+        if state.active == index {
+            state.consecutive_failures = 0;
+        }
+    }
+
+    fn record_failure(&self, index: usize, reason: &str) -> bool {
+        let mut state = self.state();
+        if state.active != index {
+            // Another request already moved on.
+            return true;
+        }
+        state.consecutive_failures += 1;
+        if state.consecutive_failures < FAILOVER_THRESHOLD {
+            return false;
+        }
+        let next = (index + 1) % self.base_urls.len();
+        self.switch(&mut state, next, reason);
+        true
+    }
+
+    fn switch(&self, state: &mut FailoverState, to: usize, reason: &str) {
+        let from = state.active;
+        state.active = to;
+        state.consecutive_failures = 0;
+        if to != 0 {
+            state.next_probe_at = Some(Instant::now() + RECOVERY_PROBE_INTERVAL);
+        }
+        let seq = state.next_switch_seq;
+        state.next_switch_seq += 1;
+        state.switches.push_back(EndpointSwitch {
+            seq,
+            from: self.base_urls[from].clone(),
+            to: self.base_urls[to].clone(),
+            reason: reason.to_string(),
+        });
+        if state.switches.len() > MAX_SWITCH_HISTORY {
+            state.switches.pop_front();
+        }
+    }
+
+    /// Sequence number the next switch will get.
+    pub(crate) fn next_switch_seq(&self) -> u64 {
+        self.state().next_switch_seq
+    }
+
+    /// Switches numbered `seq` or later that are still kept, oldest first.
+    pub(crate) fn switches_since(&self, seq: u64) -> Vec<EndpointSwitch> {
+        self.state()
+            .switches
+            .iter()
+            .filter(|switch| switch.seq >= seq)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Any HTTP response short of a server error means the endpoint is up, even
+/// one that rejects the unauthenticated request.
+async fn is_healthy(client: &reqwest::Client, base_url: &str) -> bool {
+    match client
+        .get(format!("{base_url}/models"))
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => !response.status().is_server_error(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WireApi;
+    use pretty_assertions::assert_eq;
+
+    fn provider(base_urls: &[&str]) -> ModelProviderInfo {
+        ModelProviderInfo {
+            name: "test".to_string(),
+            base_url: Some(base_urls[0].to_string()),
+            fallback_base_urls: Some(base_urls[1..].iter().map(ToString::to_string).collect()),
+            env_key: None,
+            env_key_instructions: None,
+            wire_api: WireApi::Chat,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            safety_settings: None,
+            aws: None,
+            request_max_retries: None,
+            stream_max_retries: None,
+            stream_idle_timeout_ms: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            max_concurrent_streams: None,
+            ca_certificates: None,
+            danger_accept_invalid_certs: None,
+            requires_openai_auth: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_after_repeated_failures() {
+        let provider = provider(&["http://us.invalid/v1", "http://eu.invalid/v1"]);
+        let failover = EndpointFailover::for_provider(&provider).expect("fallbacks configured");
+        let client = reqwest::Client::new();
+
+        let (selected, endpoint) = failover.select(&provider, &client).await;
+        assert_eq!(selected.base_url.as_deref(), Some("http://us.invalid/v1"));
+        assert!(!endpoint.failed("503 Service Unavailable"));
+        assert!(endpoint.failed("503 Service Unavailable"));
+
+        // The fallback is used until the primary is probed again.
+        let (selected, endpoint) = failover.select(&provider, &client).await;
+        assert_eq!(selected.base_url.as_deref(), Some("http://eu.invalid/v1"));
+        endpoint.succeeded();
+        assert_eq!(
+            failover.switches_since(0),
+            vec![EndpointSwitch {
+                seq: 0,
+                from: "http://us.invalid/v1".to_string(),
+                to: "http://eu.invalid/v1".to_string(),
+                reason: "503 Service Unavailable".to_string(),
+            }]
+        );
+        assert_eq!(failover.switches_since(1), Vec::new());
+    }
+
+    #[test]
+    fn providers_without_fallbacks_do_not_fail_over() {
+        let mut provider = provider(&["http://primary.invalid/v1"]);
+        assert!(EndpointFailover::for_provider(&provider).is_none());
+        provider.base_url = None;
+        provider.fallback_base_urls = Some(vec!["http://eu.invalid/v1".to_string()]);
+        assert!(EndpointFailover::for_provider(&provider).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+//! Failover between model providers.
+//!
+//! When a turn has used up its retries against a provider that keeps
+//! answering with rate limits or server errors, it continues on the next
+//! provider in `provider_fallbacks`. The switch lasts as long as the turn
+//! context, so the model requests that follow in the same turn do not wait
+//! out the failing provider again.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use reqwest::StatusCode;
+
+use crate::client::ModelClient;
+use crate::error::CodexErr;
+
+#[derive(Debug, Default)]
+pub(crate) struct ProviderFallbacks {
+    clients: Vec<ModelClient>,
+    /// How many fallbacks have been switched to; 0 while on the primary.
+    active: AtomicUsize,
+}
+
+impl ProviderFallbacks {
+    pub(crate) fn new(clients: Vec<ModelClient>) -> Self {
+        Self {
+            clients,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// The fallback client in use, or `None` while on the primary provider.
+    pub(crate) fn active(&self) -> Option<&ModelClient> {
+        match self.active.load(Ordering::Relaxed) {
+            0 => None,
+            n => self.clients.get(n - 1),
+        }
+    }
+
+    /// Moves to the next fallback and returns it, or returns `None` when the
+    /// chain is used up.
+    pub(crate) fn advance(&self) -> Option<&ModelClient> {
+        let next = self.active.load(Ordering::Relaxed);
+        let client = self.clients.get(next)?;
+        self.active.store(next + 1, Ordering::Relaxed);
+        Some(client)
+    }
+}
+
+/// Whether `err` means the provider itself is unavailable, as opposed to a
+/// problem with the request or the account.
+pub(crate) fn is_provider_outage(err: &CodexErr) -> bool {
+    match err {
+        CodexErr::RetryLimit(_)
+        | CodexErr::InternalServerError
+        | CodexErr::Stream(..)
+        | CodexErr::Timeout => true,
+        CodexErr::UnexpectedStatus(status, _) => {
+            *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        }
+        CodexErr::Reqwest(err) => err.is_connect() || err.is_timeout(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn outages_are_rate_limits_server_errors_and_disconnects() {
+        let cases = [
+            (CodexErr::RetryLimit(StatusCode::TOO_MANY_REQUESTS), true),
+            (CodexErr::InternalServerError, true),
+            (CodexErr::Stream("closed".to_string(), None), true),
+            (
+                CodexErr::UnexpectedStatus(StatusCode::BAD_GATEWAY, String::new()),
+                true,
+            ),
+            (
+                CodexErr::UnexpectedStatus(StatusCode::TOO_MANY_REQUESTS, String::new()),
+                true,
+            ),
+            (
+                CodexErr::UnexpectedStatus(StatusCode::BAD_REQUEST, String::new()),
+                false,
+            ),
+            (CodexErr::UsageNotIncluded, false),
+            (CodexErr::Interrupted, false),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(is_provider_outage(&err), expected, "{err}");
+        }
+    }
+
+    #[test]
+    fn an_empty_chain_never_advances() {
+        let fallbacks = ProviderFallbacks::default();
+        assert!(fallbacks.active().is_none());
+        assert!(fallbacks.advance().is_none());
+        assert!(fallbacks.active().is_none());
+    }
+}
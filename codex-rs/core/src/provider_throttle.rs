@@ -0,0 +1,294 @@
+//! Client-side rate limits for model providers.
+//!
+//! A provider with `requests_per_minute`, `tokens_per_minute` or
+//! `max_concurrent_streams` makes each model request wait until it fits
+//! within those limits instead of sending it and getting a 429 back. The
+//! minute budgets are token buckets that refill continuously, so a full
+//! minute's worth can be spent in a burst.
+//!
+//! The state is shared by every conversation in the process that uses the
+//! same provider, so sessions running side by side (for example under the
+//! MCP server) stay under the limits together.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::model_provider_info::ModelProviderInfo;
+
+static THROTTLES: LazyLock<Mutex<HashMap<ThrottleKey, Arc<ProviderThrottle>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Providers share a throttle when they name the same endpoint and limits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ThrottleKey {
+    name: String,
+    base_url: Option<String>,
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u64>,
+    max_concurrent_streams: Option<u32>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ProviderThrottle {
+    buckets: Mutex<Buckets>,
+    streams: Option<Arc<Semaphore>>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+}
+
+/// Holds a provider's concurrency slot, if it has one, until the stream it
+/// is attached to ends.
+pub(crate) struct ThrottlePermit {
+    throttle: Arc<ProviderThrottle>,
+    _stream_permit: Option<OwnedSemaphorePermit>,
+    estimated_tokens: u64,
+}
+
+impl ProviderThrottle {
+    /// The shared throttle for `provider`, or `None` if it sets no limits.
+    pub(crate) fn for_provider(provider: &ModelProviderInfo) -> Option<Arc<Self>> {
+        if provider.requests_per_minute.is_none()
+            && provider.tokens_per_minute.is_none()
+            && provider.max_concurrent_streams.is_none()
+        {
+            return None;
+        }
+        let key = ThrottleKey {
+            name: provider.name.clone(),
+            base_url: provider.base_url.clone(),
+            requests_per_minute: provider.requests_per_minute,
+            tokens_per_minute: provider.tokens_per_minute,
+            max_concurrent_streams: provider.max_concurrent_streams,
+        };
+        let mut throttles = THROTTLES.lock().unwrap_or_else(PoisonError::into_inner);
+        Some(Arc::clone(throttles.entry(key).or_insert_with(|| {
+            Arc::new(Self::new(
+                provider.requests_per_minute,
+                provider.tokens_per_minute,
+                provider.max_concurrent_streams,
+            ))
+        })))
+    }
+
+    fn new(
+        requests_per_minute: Option<u32>,
+        tokens_per_minute: Option<u64>,
+        max_concurrent_streams: Option<u32>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            buckets: Mutex::new(Buckets {
+                requests: requests_per_minute.map(|rpm| TokenBucket::per_minute(rpm.into(), now)),
+                tokens: tokens_per_minute.map(|tpm| TokenBucket::per_minute(tpm, now)),
+            }),
+            streams: max_concurrent_streams
+                .map(|max| Arc::new(Semaphore::new(max.max(1) as usize))),
+        }
+    }
+
+    /// Waits until a request of about `estimated_tokens` input tokens may be
+    /// sent, and takes its share of the limits.
+    pub(crate) async fn acquire(self: &Arc<Self>, estimated_tokens: u64) -> ThrottlePermit {
+        let stream_permit = match &self.streams {
+            // The semaphore is never closed.
+            Some(streams) => Arc::clone(streams).acquire_owned().await.ok(),
+            None => None,
+        };
+        loop {
+            let wait = self.try_take(estimated_tokens, Instant::now());
+            if wait.is_zero() {
+                break;
+            }
+            debug!("provider rate limit reached; waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+        ThrottlePermit {
+            throttle: Arc::clone(self),
+            _stream_permit: stream_permit,
+            estimated_tokens,
+        }
+    }
+
+    /// Takes one request and `tokens` from the buckets if both have enough,
+    /// returning zero; otherwise takes nothing and returns how long to wait.
+    fn try_take(&self, tokens: u64, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let Buckets {
+            requests,
+            tokens: token_bucket,
+        } = &mut *buckets;
+        let wait = [
+            requests.as_mut().map(|bucket| bucket.wait_for(1, now)),
+            token_bucket
+                .as_mut()
+                .map(|bucket| bucket.wait_for(tokens, now)),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or_default();
+        if wait.is_zero() {
+            if let Some(bucket) = requests {
+                bucket.take(1);
+            }
+            if let Some(bucket) = token_bucket {
+                bucket.take(tokens);
+            }
+        }
+        wait
+    }
+
+    /// Corrects the token budget once a response reports what it really
+    /// used, including output tokens.
+    fn settle(&self, estimated_tokens: u64, actual_tokens: u64) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(bucket) = &mut buckets.tokens {
+            bucket.adjust(actual_tokens as f64 - estimated_tokens as f64);
+        }
+    }
+}
+
+impl ThrottlePermit {
+    /// Keeps the permit until `stream` ends, and settles the token budget
+    /// with the usage the response reports.
+    pub(crate) fn hold_for(self, mut stream: ResponseStream) -> ResponseStream {
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            while let Some(event) = stream.rx_event.recv().await {
+                if let Ok(ResponseEvent::Completed {
+                    token_usage: Some(usage),
+                    ..
+                }) = &event
+                {
+                    self.throttle
+                        .settle(self.estimated_tokens, usage.total_tokens);
+                }
+                if tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+            // The concurrency slot is released when this task ends.
+        });
+        ResponseStream { rx_event: rx }
+    }
+}
+
+/// Rough input size of `prompt` in tokens, at about four bytes per token.
+pub(crate) fn estimate_prompt_tokens(prompt: &Prompt) -> u64 {
+    serde_json::to_string(&prompt.input).map_or(0, |json| (json.len() as u64).div_ceil(4))
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    /// Also the amount refilled per minute.
+    capacity: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(per_minute: u64, now: Instant) -> Self {
+        let capacity = per_minute.max(1) as f64;
+        Self {
+            capacity,
+            available: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until `amount` is available. Amounts above the capacity
+    /// only wait for a full bucket, so an oversized request is not stuck
+    /// forever.
+    fn wait_for(&mut self, amount: u64, now: Instant) -> Duration {
+        self.refill(now);
+        let missing = (amount as f64).min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing * 60.0 / self.capacity)
+        }
+    }
+
+    fn take(&mut self, amount: u64) {
+        self.available -= (amount as f64).min(self.capacity);
+    }
+
+    /// Takes `amount` more (or gives it back when negative); the balance may
+    /// go below zero, which delays the next requests.
+    fn adjust(&mut self, amount: f64) {
+        self.available = (self.available - amount).min(self.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn request_bucket_refills_over_the_minute() {
+        let throttle = ProviderThrottle::new(Some(2), None, None);
+        let start = Instant::now();
+
+        assert_eq!(throttle.try_take(0, start), Duration::ZERO);
+        assert_eq!(throttle.try_take(0, start), Duration::ZERO);
+        assert_eq!(throttle.try_take(0, start), Duration::from_secs(30));
+        assert_eq!(
+            throttle.try_take(0, start + Duration::from_secs(30)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn token_bucket_waits_for_every_limit_and_settles_usage() {
+        let throttle = ProviderThrottle::new(Some(100), Some(600), None);
+        let start = Instant::now();
+
+        assert_eq!(throttle.try_take(500, start), Duration::ZERO);
+        // 100 tokens left, 200 missing at 10 tokens a second.
+        assert_eq!(throttle.try_take(300, start), Duration::from_secs(20));
+
+        // The response used 100 tokens less than estimated.
+        throttle.settle(500, 400);
+        assert_eq!(throttle.try_take(200, start), Duration::ZERO);
+
+        // Oversized requests wait for a full bucket rather than forever.
+        assert_eq!(throttle.try_take(10_000, start), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn concurrent_streams_wait_for_a_free_slot() {
+        let throttle = Arc::new(ProviderThrottle::new(None, None, Some(1)));
+        let first = throttle.acquire(0).await;
+
+        let waiting = tokio::time::timeout(Duration::from_millis(50), throttle.acquire(0)).await;
+        assert!(waiting.is_err());
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_millis(50), throttle.acquire(0)).await;
+        assert!(second.is_ok());
+    }
+}
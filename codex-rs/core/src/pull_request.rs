@@ -0,0 +1,494 @@
+//! Opening a pull request (GitHub) or merge request (GitLab) for the work of a
+//! session ([`Op::CreatePullRequest`]).
+//!
+//! The current branch, or a new one if the default branch is checked out, is
+//! pushed to `origin` with the session's uncommitted changes committed (other
+//! changes in the working tree are left alone), and the request
+//! is created through the host's API as configured under
+//! `[integrations.github]` or `[integrations.gitlab]`. Its title and body
+//! summarize the session.
+//!
+//! [`Op::CreatePullRequest`]: crate::protocol::Op::CreatePullRequest
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use serde_json::json;
+
+use crate::config_types::ForgeIntegration;
+use crate::config_types::Integrations;
+use crate::default_client::create_client;
+use crate::git_info::get_default_branch;
+use crate::git_info::run_git;
+use crate::protocol::PullRequestCreatedEvent;
+
+/// Upper bound on each git command; `push` talks to the remote.
+const GIT_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest title, in characters, taken from the first request of the session.
+const TITLE_MAX_CHARS: usize = 72;
+
+const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+const DEFAULT_GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PullRequestDraft {
+    pub(crate) title: String,
+    pub(crate) body: String,
+}
+
+/// A title and body summarizing a session: what the user asked for and the
+/// agent's final message.
+pub(crate) fn draft_pull_request(
+    user_messages: &[String],
+    last_agent_message: Option<&str>,
+    conversation_id: ConversationId,
+) -> PullRequestDraft {
+    let title = user_messages
+        .first()
+        .and_then(|message| message.lines().map(str::trim).find(|line| !line.is_empty()))
+        .map(|line| {
+            if line.chars().count() > TITLE_MAX_CHARS {
+                let truncated: String = line.chars().take(TITLE_MAX_CHARS - 1).collect();
+                format!("{}…", truncated.trim_end())
+            } else {
+                line.to_string()
+            }
+        })
+        .unwrap_or_else(|| "Changes from a Codex session".to_string());
+
+    let mut body = String::new();
+    if let Some(summary) = last_agent_message.map(str::trim).filter(|s| !s.is_empty()) {
+        body.push_str("## Summary\n\n");
+        body.push_str(summary);
+        body.push_str("\n\n");
+    }
+    if !user_messages.is_empty() {
+        body.push_str("## Requests\n\n");
+        for message in user_messages {
+            let first_line = message.lines().next().unwrap_or_default().trim();
+            body.push_str(&format!("- {first_line}\n"));
+        }
+        body.push('\n');
+    }
+    body.push_str(&format!("Codex session: `{conversation_id}`\n"));
+    PullRequestDraft { title, body }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Forge {
+    GitHub,
+    GitLab,
+}
+
+/// Where `origin` points: a host and a repository path such as `owner/repo`.
+#[derive(Debug, PartialEq, Eq)]
+struct Remote {
+    host: String,
+    path: String,
+}
+
+/// Parses `git@host:owner/repo.git`, `ssh://git@host/owner/repo` and
+/// `https://host/owner/repo.git` remote URLs.
+fn parse_remote_url(url: &str) -> Option<Remote> {
+    let url = url.trim();
+    let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        // Drop a port, e.g. `ssh://git@host:2222/owner/repo`.
+        (host.split(':').next()?, path)
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        (
+            authority
+                .rsplit_once('@')
+                .map_or(authority, |(_, host)| host),
+            path,
+        )
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if host.is_empty() || !path.contains('/') {
+        return None;
+    }
+    Some(Remote {
+        host: host.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// The host `remote` is on, by its name or, failing that, by which
+/// integration is configured.
+fn choose_forge(integrations: &Integrations, remote: &Remote) -> Option<(Forge, ForgeIntegration)> {
+    let github = || {
+        (
+            Forge::GitHub,
+            integrations.github.clone().unwrap_or_default(),
+        )
+    };
+    let gitlab = || {
+        (
+            Forge::GitLab,
+            integrations.gitlab.clone().unwrap_or_default(),
+        )
+    };
+    if remote.host.contains("gitlab") {
+        Some(gitlab())
+    } else if remote.host.contains("github") {
+        Some(github())
+    } else if integrations.gitlab.is_some() && integrations.github.is_none() {
+        Some(gitlab())
+    } else if integrations.github.is_some() {
+        Some(github())
+    } else {
+        None
+    }
+}
+
+/// The API base URL of the host `remote` is on. Only `github.com` and
+/// `gitlab.com` have a default: the access token is never sent to the public
+/// API for a repository on another host.
+fn api_url(forge: Forge, settings: &ForgeIntegration, remote: &Remote) -> Result<String, String> {
+    if let Some(api_url) = &settings.api_url {
+        return Ok(api_url.trim_end_matches('/').to_string());
+    }
+    match forge {
+        Forge::GitHub if remote.host == "github.com" => Ok(DEFAULT_GITHUB_API_URL.to_string()),
+        Forge::GitLab if remote.host == "gitlab.com" => Ok(DEFAULT_GITLAB_API_URL.to_string()),
+        Forge::GitHub => Err(format!(
+            "set `api_url` under `[integrations.github]` to the API of `{}`",
+            remote.host
+        )),
+        Forge::GitLab => Err(format!(
+            "set `api_url` under `[integrations.gitlab]` to the API of `{}`",
+            remote.host
+        )),
+    }
+}
+
+/// Pushes the work in `cwd` and opens a pull request for it. Of the
+/// uncommitted changes, only those to `session_paths`, the files the session
+/// changed, are committed first.
+pub(crate) async fn create_pull_request(
+    integrations: &Integrations,
+    cwd: &Path,
+    draft: &PullRequestDraft,
+    session_paths: &[PathBuf],
+) -> Result<PullRequestCreatedEvent, String> {
+    let remote_url = git(cwd, &["remote", "get-url", "origin"])
+        .await
+        .map_err(|_| "the repository has no `origin` remote".to_string())?;
+    let remote = parse_remote_url(&remote_url)
+        .ok_or_else(|| format!("cannot tell the repository from the remote URL `{remote_url}`"))?;
+    let (forge, settings) = choose_forge(integrations, &remote).ok_or_else(|| {
+        format!(
+            "`{}` is not GitHub or GitLab; configure `[integrations.github]` or `[integrations.gitlab]`",
+            remote.host
+        )
+    })?;
+    let api_url = api_url(forge, &settings, &remote)?;
+    let token_env = settings.token_env.clone().unwrap_or_else(|| {
+        match forge {
+            Forge::GitHub => "GITHUB_TOKEN",
+            Forge::GitLab => "GITLAB_TOKEN",
+        }
+        .to_string()
+    });
+    let token = std::env::var(&token_env)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| format!("set `{token_env}` to an access token to open pull requests"))?;
+
+    let base = match settings.base_branch.clone() {
+        Some(base) => base,
+        None => get_default_branch(cwd)
+            .await
+            .unwrap_or_else(|| "main".to_string()),
+    };
+    let current = git(cwd, &["branch", "--show-current"]).await?;
+    let branch = if current.is_empty() || current == base {
+        let branch = format!("codex/{}", branch_slug(&draft.title));
+        git(cwd, &["switch", "--quiet", "--create", &branch]).await?;
+        branch
+    } else {
+        current
+    };
+    commit_session_changes(cwd, session_paths, &draft.title).await?;
+    git(
+        cwd,
+        &["push", "--quiet", "--set-upstream", "origin", &branch],
+    )
+    .await?;
+
+    let url = match forge {
+        Forge::GitHub => {
+            let response = create_client()
+                .post(format!("{api_url}/repos/{}/pulls", remote.path))
+                .bearer_auth(&token)
+                .header("Accept", "application/vnd.github+json")
+                .json(&json!({
+                    "title": draft.title,
+                    "body": draft.body,
+                    "head": branch,
+                    "base": base,
+                    "draft": settings.draft,
+                }))
+                .send()
+                .await;
+            created_url(response, "html_url").await?
+        }
+        Forge::GitLab => {
+            let title = if settings.draft {
+                format!("Draft: {}", draft.title)
+            } else {
+                draft.title.clone()
+            };
+            let response = create_client()
+                .post(format!(
+                    "{api_url}/projects/{}/merge_requests",
+                    remote.path.replace('/', "%2F")
+                ))
+                .header("PRIVATE-TOKEN", &token)
+                .json(&json!({
+                    "title": title,
+                    "description": draft.body,
+                    "source_branch": branch,
+                    "target_branch": base,
+                }))
+                .send()
+                .await;
+            created_url(response, "web_url").await?
+        }
+    };
+
+    Ok(PullRequestCreatedEvent {
+        url,
+        branch,
+        title: draft.title.clone(),
+    })
+}
+
+/// Commits the uncommitted changes to `paths` in the repository of `cwd`,
+/// leaving any other changes, staged or not, uncommitted.
+async fn commit_session_changes(
+    cwd: &Path,
+    paths: &[PathBuf],
+    message: &str,
+) -> Result<(), String> {
+    let mut pathspecs: Vec<String> = Vec::new();
+    for path in paths {
+        let pathspec = path.to_string_lossy().into_owned();
+        // A file the session created and deleted again is unknown to git.
+        if path.exists()
+            || git(cwd, &["ls-files", "--error-unmatch", "--", &pathspec])
+                .await
+                .is_ok()
+        {
+            pathspecs.push(pathspec);
+        }
+    }
+    if pathspecs.is_empty() {
+        return Ok(());
+    }
+    let mut add = vec!["add", "--all", "--"];
+    add.extend(pathspecs.iter().map(String::as_str));
+    git(cwd, &add).await?;
+
+    let mut staged = vec!["diff", "--cached", "--name-only", "--"];
+    staged.extend(pathspecs.iter().map(String::as_str));
+    if git(cwd, &staged).await?.is_empty() {
+        return Ok(());
+    }
+    let mut commit = vec!["commit", "--quiet", "-m", message, "--"];
+    commit.extend(pathspecs.iter().map(String::as_str));
+    git(cwd, &commit).await?;
+    Ok(())
+}
+
+/// The URL, under `field`, of the pull request an API call created.
+async fn created_url(
+    response: reqwest::Result<reqwest::Response>,
+    field: &str,
+) -> Result<String, String> {
+    let response = response.map_err(|e| format!("request failed: {e}"))?;
+    let status = response.status();
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("unexpected response ({status}): {e}"))?;
+    if !status.is_success() {
+        let message = body
+            .get("message")
+            .map(|message| match message {
+                serde_json::Value::String(message) => message.clone(),
+                other => other.to_string(),
+            })
+            .unwrap_or_else(|| body.to_string());
+        return Err(format!("{status}: {message}"));
+    }
+    body.get(field)
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| format!("the response has no `{field}`"))
+}
+
+/// `fix the flaky test!` becomes `fix-the-flaky-test`.
+fn branch_slug(title: &str) -> String {
+    let slug: String = title
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .take(6)
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "changes".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Runs git in `cwd` with this module's time limit.
+async fn git(cwd: &Path, args: &[&str]) -> Result<String, String> {
+    run_git(cwd, args, GIT_COMMAND_TIMEOUT).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn remote(host: &str, path: &str) -> Option<Remote> {
+        Some(Remote {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    #[test]
+    fn parses_ssh_and_https_remote_urls() {
+        assert_eq!(
+            parse_remote_url("git@github.com:openai/codex.git"),
+            remote("github.com", "openai/codex")
+        );
+        assert_eq!(
+            parse_remote_url("https://gitlab.example.com/group/sub/project.git\n"),
+            remote("gitlab.example.com", "group/sub/project")
+        );
+        assert_eq!(
+            parse_remote_url("ssh://git@git.corp:2222/team/repo"),
+            remote("git.corp", "team/repo")
+        );
+        assert_eq!(parse_remote_url("/srv/git/repo.git"), None);
+    }
+
+    #[test]
+    fn forge_follows_the_host_then_the_configuration() {
+        let none = Integrations::default();
+        let gitlab_only = Integrations {
+            github: None,
+            gitlab: Some(ForgeIntegration::default()),
+        };
+        let forge = |integrations: &Integrations, host: &str| {
+            choose_forge(integrations, &remote(host, "a/b").unwrap()).map(|(forge, _)| forge)
+        };
+        assert_eq!(forge(&none, "github.com"), Some(Forge::GitHub));
+        assert_eq!(forge(&none, "gitlab.com"), Some(Forge::GitLab));
+        assert_eq!(forge(&none, "git.corp"), None);
+        assert_eq!(forge(&gitlab_only, "git.corp"), Some(Forge::GitLab));
+    }
+
+    #[test]
+    fn draft_summarizes_the_session() {
+        let conversation_id = ConversationId::new();
+        let draft = draft_pull_request(
+            &[
+                "Fix the flaky retry test\nIt fails on CI.".to_string(),
+                "Also update the changelog".to_string(),
+            ],
+            Some("Made the retry delay deterministic."),
+            conversation_id,
+        );
+        assert_eq!(draft.title, "Fix the flaky retry test");
+        assert_eq!(
+            draft.body,
+            format!(
+                "## Summary\n\nMade the retry delay deterministic.\n\n## Requests\n\n- Fix the flaky retry test\n- Also update the changelog\n\nCodex session: `{conversation_id}`\n"
+            )
+        );
+        assert_eq!(branch_slug(&draft.title), "fix-the-flaky-retry-test");
+    }
+
+    #[test]
+    fn api_url_is_required_off_the_public_hosts() {
+        let settings = ForgeIntegration::default();
+        let github = remote("github.com", "a/b").unwrap();
+        let corp = remote("github.corp", "a/b").unwrap();
+        assert_eq!(
+            api_url(Forge::GitHub, &settings, &github),
+            Ok(DEFAULT_GITHUB_API_URL.to_string())
+        );
+        assert_eq!(
+            api_url(Forge::GitHub, &settings, &corp),
+            Err(
+                "set `api_url` under `[integrations.github]` to the API of `github.corp`"
+                    .to_string()
+            )
+        );
+        let settings = ForgeIntegration {
+            api_url: Some("https://github.corp/api/v3/".to_string()),
+            ..ForgeIntegration::default()
+        };
+        assert_eq!(
+            api_url(Forge::GitHub, &settings, &corp),
+            Ok("https://github.corp/api/v3".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn only_the_session_changes_are_committed() {
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let cwd = dir.path();
+        for args in [
+            vec!["init", "--quiet", "--initial-branch=main"],
+            vec!["config", "user.name", "Test User"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["commit", "--quiet", "--allow-empty", "-m", "init"],
+        ] {
+            git(cwd, &args).await.expect("git setup");
+        }
+        std::fs::write(cwd.join("session.txt"), "from the session\n").expect("write");
+        std::fs::write(cwd.join("user.txt"), "from the user\n").expect("write");
+
+        commit_session_changes(
+            cwd,
+            &[cwd.join("session.txt"), cwd.join("created-and-deleted.txt")],
+            "Session changes",
+        )
+        .await
+        .expect("commit");
+
+        assert_eq!(
+            git(cwd, &["show", "--name-only", "--format=%s", "HEAD"])
+                .await
+                .expect("show"),
+            "Session changes\n\nsession.txt"
+        );
+        assert_eq!(
+            git(cwd, &["status", "--porcelain"]).await.expect("status"),
+            "?? user.txt"
+        );
+    }
+}
@@ -1,4 +1,12 @@
+use std::path::Path;
+
+use serde_json::Value;
+use serde_json::json;
+
 use crate::protocol::ReviewFinding;
+use crate::protocol::ReviewOutputEvent;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
 
 // Note: We keep this module UI-agnostic. It returns plain strings that
 // higher layers (e.g., TUI) may style as needed.
@@ -53,3 +61,107 @@ pub fn format_review_findings_block(
 
     lines.join("\n")
 }
+
+/// Convert a review into a SARIF 2.1.0 log, for code scanning tools.
+///
+/// Paths under `root` are written relative to it (`%SRCROOT%`). Priorities
+/// map to levels: P0 and P1 are errors, P2 warnings and P3 notes.
+pub fn review_to_sarif(review: &ReviewOutputEvent, root: &Path) -> Value {
+    let results: Vec<Value> = review
+        .findings
+        .iter()
+        .map(|finding| {
+            let path = &finding.code_location.absolute_file_path;
+            let artifact = match path.strip_prefix(root) {
+                Ok(relative) => json!({
+                    "uri": relative.to_string_lossy().replace('\\', "/"),
+                    "uriBaseId": "%SRCROOT%",
+                }),
+                Err(_) => json!({ "uri": format!("file://{}", path.display()) }),
+            };
+            let range = &finding.code_location.line_range;
+            let start = range.start.max(1);
+            let level = match finding.priority {
+                0 | 1 => "error",
+                2 => "warning",
+                _ => "note",
+            };
+            json!({
+                "ruleId": format!("codex-review/p{}", finding.priority.clamp(0, 3)),
+                "level": level,
+                "message": { "text": format!("{}\n\n{}", finding.title, finding.body) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact,
+                        "region": { "startLine": start, "endLine": range.end.max(start) },
+                    },
+                }],
+                "properties": {
+                    "priority": finding.priority,
+                    "confidence": finding.confidence_score,
+                },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "codex-review",
+                    "informationUri": "https://github.com/openai/codex",
+                },
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": format!("file://{}/", root.display()) },
+            },
+            "results": results,
+            "properties": {
+                "overallCorrectness": review.overall_correctness,
+                "overallExplanation": review.overall_explanation,
+            },
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ReviewCodeLocation;
+    use crate::protocol::ReviewLineRange;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn sarif_results_have_relative_locations_and_levels() {
+        let review = ReviewOutputEvent {
+            findings: vec![ReviewFinding {
+                title: "[P1] Off by one".to_string(),
+                body: "The loop skips the last item.".to_string(),
+                confidence_score: 0.8,
+                priority: 1,
+                code_location: ReviewCodeLocation {
+                    absolute_file_path: PathBuf::from("/repo/src/lib.rs"),
+                    line_range: ReviewLineRange { start: 10, end: 12 },
+                },
+            }],
+            overall_correctness: "patch is incorrect".to_string(),
+            overall_explanation: "One bug.".to_string(),
+            overall_confidence_score: 0.7,
+        };
+
+        let sarif = review_to_sarif(&review, Path::new("/repo"));
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["level"], "error");
+        assert_eq!(result["ruleId"], "codex-review/p1");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "src/lib.rs", "uriBaseId": "%SRCROOT%" },
+                "region": { "startLine": 10, "endLine": 12 },
+            })
+        );
+    }
+}
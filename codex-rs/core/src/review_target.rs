@@ -0,0 +1,143 @@
+//! Review prompts for the targets clients can name instead of writing a
+//! prompt themselves: `codex review`, the `startReview` MCP request and
+//! `/review <target>` in the TUI.
+//!
+//! The reviewer inspects local changes with git itself. A pull request's diff
+//! is fetched up front with the GitHub CLI (`gh`), since the review usually
+//! runs in a sandbox without network access.
+
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::protocol::ReviewRequest;
+use crate::protocol::ReviewTarget;
+
+/// Upper bound on `gh pr diff`, which talks to GitHub.
+const GH_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Largest pull request diff, in bytes, included in the prompt.
+const PR_DIFF_MAX_BYTES: usize = 256 * 1024;
+
+/// Reads a target as written on the command line: nothing or `uncommitted`
+/// for the working tree, `staged` or `--staged` for the index, a URL for a
+/// pull request, and anything else as a revision range.
+pub fn parse_review_target(arg: &str) -> ReviewTarget {
+    match arg.trim() {
+        "" | "uncommitted" => ReviewTarget::UncommittedChanges,
+        "staged" | "--staged" | "--cached" => ReviewTarget::StagedChanges,
+        url if url.starts_with("https://") || url.starts_with("http://") => {
+            ReviewTarget::PullRequest {
+                url: url.to_string(),
+            }
+        }
+        range => ReviewTarget::Range {
+            range: range.to_string(),
+        },
+    }
+}
+
+/// Builds the review request for `target` in the repository at `cwd`.
+pub async fn review_request(target: &ReviewTarget, cwd: &Path) -> io::Result<ReviewRequest> {
+    let prompt = match target {
+        ReviewTarget::UncommittedChanges => "Review the current code changes (staged, unstaged, and untracked files) and provide prioritized findings.".to_string(),
+        ReviewTarget::StagedChanges => "Review the code changes staged for commit; see them with `git diff --cached`. Ignore unstaged and untracked changes. Provide prioritized, actionable findings.".to_string(),
+        ReviewTarget::Range { range } if range.contains("..") => format!(
+            "Review the code changes in the revision range {range}; see them with `git diff {range}` and the commits with `git log {range}`. Provide prioritized, actionable findings."
+        ),
+        ReviewTarget::Range { range } => format!(
+            "Review the code changes introduced by commit {range}; see them with `git show {range}`. Provide prioritized, actionable findings."
+        ),
+        ReviewTarget::PullRequest { url } => {
+            let diff = pull_request_diff(url, cwd).await?;
+            format!(
+                "Review the code changes of the pull request {url}. Its diff is below; the repository in the working directory may not have its commits checked out. Provide prioritized, actionable findings.\n\n```diff\n{diff}\n```"
+            )
+        }
+    };
+    Ok(ReviewRequest {
+        prompt,
+        user_facing_hint: target.to_string(),
+    })
+}
+
+async fn pull_request_diff(url: &str, cwd: &Path) -> io::Result<String> {
+    let output = timeout(
+        GH_COMMAND_TIMEOUT,
+        Command::new("gh")
+            .args(["pr", "diff", url])
+            .current_dir(cwd)
+            .output(),
+    )
+    .await
+    .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "`gh pr diff` timed out"))?
+    .map_err(|e| io::Error::new(e.kind(), format!("failed to run `gh pr diff`: {e}")))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "`gh pr diff` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let diff = String::from_utf8_lossy(&output.stdout);
+    let diff = diff.trim_end();
+    if diff.len() <= PR_DIFF_MAX_BYTES {
+        return Ok(diff.to_string());
+    }
+    let mut end = PR_DIFF_MAX_BYTES;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    Ok(format!("{}\n… (diff truncated)", &diff[..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn targets_are_parsed_from_command_line_arguments() {
+        assert_eq!(parse_review_target(""), ReviewTarget::UncommittedChanges);
+        assert_eq!(parse_review_target("--staged"), ReviewTarget::StagedChanges);
+        assert_eq!(
+            parse_review_target("main..HEAD"),
+            ReviewTarget::Range {
+                range: "main..HEAD".to_string()
+            }
+        );
+        assert_eq!(
+            parse_review_target("https://github.com/openai/codex/pull/1"),
+            ReviewTarget::PullRequest {
+                url: "https://github.com/openai/codex/pull/1".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn ranges_and_commits_get_different_prompts() {
+        let cwd = Path::new(".");
+        let range = review_request(
+            &ReviewTarget::Range {
+                range: "main...HEAD".to_string(),
+            },
+            cwd,
+        )
+        .await
+        .expect("request");
+        assert!(range.prompt.contains("`git diff main...HEAD`"));
+        assert_eq!(range.user_facing_hint, "changes in main...HEAD");
+
+        let commit = review_request(
+            &ReviewTarget::Range {
+                range: "abc123".to_string(),
+            },
+            cwd,
+        )
+        .await
+        .expect("request");
+        assert!(commit.prompt.contains("`git show abc123`"));
+    }
+}
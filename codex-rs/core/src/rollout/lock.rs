@@ -0,0 +1,186 @@
+//! Advisory locks that keep two processes from writing to the same session.
+//!
+//! A process holds the lock of every rollout it records, new or resumed, until
+//! the session shuts down. Lock files live under [`SESSION_LOCKS_SUBDIR`] in
+//! the Codex home, named after the rollout file, so they are never mistaken
+//! for rollouts. The lock file describes its holder so that another process
+//! can say who has the session open.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::fs::TryLockError;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::default_client::ORIGINATOR;
+
+pub const SESSION_LOCKS_SUBDIR: &str = "session_locks";
+
+/// The process that has a session open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionLockHolder {
+    pub pid: u32,
+    /// The client, e.g. `codex_cli_rs` or an IDE extension.
+    pub originator: String,
+    pub acquired_at: DateTime<Utc>,
+}
+
+/// Another process is recording the session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionLocked {
+    pub rollout_path: PathBuf,
+    /// `None` when the holder could not be read, e.g. while it was being
+    /// written.
+    pub holder: Option<SessionLockHolder>,
+}
+
+impl std::fmt::Display for SessionLocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "session is open in another Codex process")?;
+        if let Some(SessionLockHolder {
+            pid,
+            originator,
+            acquired_at,
+        }) = &self.holder
+        {
+            write!(
+                f,
+                " ({originator}, pid {pid}, since {})",
+                acquired_at.format("%Y-%m-%d %H:%M:%S UTC")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SessionLocked {}
+
+/// Exclusive lock on a rollout, released when dropped.
+#[derive(Debug)]
+pub(crate) struct SessionLock {
+    _file: File,
+}
+
+impl SessionLock {
+    /// Locks the rollout at `rollout_path`. Fails with an error of kind
+    /// [`io::ErrorKind::WouldBlock`] wrapping [`SessionLocked`] when another
+    /// process holds the lock.
+    pub(crate) fn acquire(codex_home: &Path, rollout_path: &Path) -> io::Result<Self> {
+        let mut file = open_lock_file(codex_home, rollout_path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    SessionLocked {
+                        rollout_path: rollout_path.to_path_buf(),
+                        holder: read_holder(&mut file),
+                    },
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        let holder = SessionLockHolder {
+            pid: std::process::id(),
+            originator: ORIGINATOR.value.clone(),
+            acquired_at: Utc::now(),
+        };
+        file.set_len(0)?;
+        file.write_all(&serde_json::to_vec(&holder)?)?;
+        file.flush()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Extracts the [`SessionLocked`] error from an error returned while opening
+/// a rollout.
+pub fn as_session_locked(error: &io::Error) -> Option<&SessionLocked> {
+    error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<SessionLocked>())
+}
+
+/// Checks whether another process has the rollout at `rollout_path` open,
+/// without keeping it locked.
+pub fn session_lock_status(
+    codex_home: &Path,
+    rollout_path: &Path,
+) -> io::Result<Option<SessionLocked>> {
+    match SessionLock::acquire(codex_home, rollout_path) {
+        Ok(_lock) => Ok(None),
+        Err(e) => match as_session_locked(&e) {
+            Some(locked) => Ok(Some(locked.clone())),
+            None => Err(e),
+        },
+    }
+}
+
+fn open_lock_file(codex_home: &Path, rollout_path: &Path) -> io::Result<File> {
+    let name = rollout_path
+        .file_stem()
+        .ok_or_else(|| io::Error::other(format!("invalid rollout path {rollout_path:?}")))?;
+    let dir = codex_home.join(SESSION_LOCKS_SUBDIR);
+    std::fs::create_dir_all(&dir)?;
+    let mut path = dir.join(name);
+    path.set_extension("lock");
+    // The file is never removed: a process that opened it just before its
+    // removal could lock the orphaned file while another locks a new one.
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+fn read_holder(file: &mut File) -> Option<SessionLockHolder> {
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    // On Windows the lock also blocks reading the holder.
+    #[cfg(unix)]
+    #[test]
+    fn second_lock_reports_the_holder() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let rollout = codex_home
+            .path()
+            .join("sessions/2025/01/01/rollout-2025-01-01T00-00-00-abc.jsonl");
+
+        let lock = SessionLock::acquire(codex_home.path(), &rollout).expect("first lock");
+        let err = SessionLock::acquire(codex_home.path(), &rollout).expect_err("already locked");
+        let locked = as_session_locked(&err).expect("SessionLocked error");
+        assert_eq!(locked.rollout_path, rollout);
+        let holder = locked.holder.as_ref().expect("holder is recorded");
+        assert_eq!(holder.pid, std::process::id());
+        assert_eq!(
+            session_lock_status(codex_home.path(), &rollout)
+                .expect("status")
+                .map(|locked| locked.holder),
+            Some(Some(holder.clone()))
+        );
+
+        drop(lock);
+        assert_eq!(
+            session_lock_status(codex_home.path(), &rollout).expect("status"),
+            None
+        );
+        SessionLock::acquire(codex_home.path(), &rollout).expect("lock is free again");
+    }
+}
@@ -4,11 +4,15 @@ pub const SESSIONS_SUBDIR: &str = "sessions";
 pub const ARCHIVED_SESSIONS_SUBDIR: &str = "archived_sessions";
 
 pub mod list;
+pub mod lock;
 pub(crate) mod policy;
 pub mod recorder;
 
 pub use codex_protocol::protocol::SessionMeta;
 pub use list::find_conversation_path_by_id_str;
+pub use lock::SessionLockHolder;
+pub use lock::SessionLocked;
+pub use lock::session_lock_status;
 pub use recorder::RolloutRecorder;
 pub use recorder::RolloutRecorderParams;
 
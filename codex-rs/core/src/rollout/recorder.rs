@@ -5,6 +5,7 @@ use std::fs::{self};
 use std::io::Error as IoError;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use codex_protocol::mcp_protocol::ConversationId;
 use serde_json::Value;
@@ -15,6 +16,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::mpsc::{self};
 use tokio::sync::oneshot;
+use tokio::time::MissedTickBehavior;
 use tracing::info;
 use tracing::warn;
 
@@ -22,8 +24,10 @@ use super::SESSIONS_SUBDIR;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
+use super::lock::SessionLock;
 use super::policy::is_persisted_response_item;
 use crate::config::Config;
+use crate::config_types::RolloutDurability;
 use crate::default_client::ORIGINATOR;
 use crate::git_info::collect_git_info;
 use codex_protocol::protocol::InitialHistory;
@@ -33,8 +37,8 @@ use codex_protocol::protocol::RolloutLine;
 use codex_protocol::protocol::SessionMeta;
 use codex_protocol::protocol::SessionMetaLine;
 
-/// Records all [`ResponseItem`]s for a session and flushes them to disk after
-/// every update.
+/// Records all [`ResponseItem`]s for a session and writes them to disk as
+/// configured by [`RolloutDurability`]: by default after every update.
 ///
 /// Rollouts are recorded as JSONL and can be inspected with tools such as:
 ///
@@ -96,8 +100,12 @@ impl RolloutRecorder {
     /// Attempt to create a new [`RolloutRecorder`]. If the sessions directory
     /// cannot be created or the rollout file cannot be opened we return the
     /// error so the caller can decide whether to disable persistence.
+    ///
+    /// The rollout stays locked until the recorder shuts down; resuming a
+    /// rollout that another process has open fails with an error wrapping
+    /// [`super::SessionLocked`].
     pub async fn new(config: &Config, params: RolloutRecorderParams) -> std::io::Result<Self> {
-        let (file, rollout_path, meta) = match params {
+        let (file, rollout_path, meta, lock) = match params {
             RolloutRecorderParams::Create {
                 conversation_id,
                 instructions,
@@ -108,6 +116,7 @@ impl RolloutRecorder {
                     conversation_id: session_id,
                     timestamp,
                 } = create_log_file(config, conversation_id)?;
+                let lock = SessionLock::acquire(&config.codex_home, &path)?;
 
                 let timestamp_format: &[FormatItem] = format_description!(
                     "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
@@ -128,16 +137,24 @@ impl RolloutRecorder {
                         cli_version: env!("CARGO_PKG_VERSION").to_string(),
                         instructions,
                     }),
+                    lock,
+                )
+            }
+            RolloutRecorderParams::Resume { path } => {
+                // Lock before touching the file: its tail may still be being
+                // written by the holder.
+                let lock = SessionLock::acquire(&config.codex_home, &path)?;
+                repair_torn_tail(&path).await?;
+                (
+                    tokio::fs::OpenOptions::new()
+                        .append(true)
+                        .open(&path)
+                        .await?,
+                    path,
+                    None,
+                    lock,
                 )
             }
-            RolloutRecorderParams::Resume { path } => (
-                tokio::fs::OpenOptions::new()
-                    .append(true)
-                    .open(&path)
-                    .await?,
-                path,
-                None,
-            ),
         };
 
         // Clone the cwd for the spawned task to collect git info asynchronously
@@ -151,7 +168,7 @@ impl RolloutRecorder {
         // Spawn a Tokio task that owns the file handle and performs async
         // writes. Using `tokio::fs::File` keeps everything on the async I/O
         // driver instead of blocking the runtime.
-        tokio::task::spawn(rollout_writer(file, rx, meta, cwd));
+        tokio::task::spawn(rollout_writer(file, rx, meta, cwd, config.rollout, lock));
 
         Ok(Self { tx, rollout_path })
     }
@@ -227,6 +244,9 @@ impl RolloutRecorder {
                     RolloutItem::TurnContext(item) => {
                         items.push(RolloutItem::TurnContext(item));
                     }
+                    RolloutItem::FileChanges(item) => {
+                        items.push(RolloutItem::FileChanges(item));
+                    }
                     RolloutItem::EventMsg(_ev) => {
                         items.push(RolloutItem::EventMsg(_ev));
                     }
@@ -329,13 +349,36 @@ fn create_log_file(
     })
 }
 
+/// If a crash left a partially written last line in the rollout file, cut it
+/// off so that lines appended on resume are not glued onto it.
+async fn repair_torn_tail(path: &Path) -> std::io::Result<()> {
+    let contents = tokio::fs::read(path).await?;
+    if contents.is_empty() || contents.ends_with(b"\n") {
+        return Ok(());
+    }
+    let keep = contents
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |idx| idx + 1);
+    warn!(
+        "dropping {} bytes of a partially written line at the end of {path:?}",
+        contents.len() - keep
+    );
+    let file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    file.set_len(keep as u64).await?;
+    file.sync_all().await
+}
+
 async fn rollout_writer(
     file: tokio::fs::File,
     mut rx: mpsc::Receiver<RolloutCmd>,
     mut meta: Option<SessionMeta>,
     cwd: std::path::PathBuf,
+    durability: RolloutDurability,
+    // Held until the writer stops.
+    lock: SessionLock,
 ) -> std::io::Result<()> {
-    let mut writer = JsonlWriter { file };
+    let mut writer = JsonlWriter::new(file, durability);
 
     // If we have a meta, collect git info asynchronously and write meta first
     if let Some(session_meta) = meta.take() {
@@ -351,8 +394,24 @@ async fn rollout_writer(
             .await?;
     }
 
+    // Buffered items are written on this interval when they are not written
+    // one at a time.
+    let mut flush_interval =
+        tokio::time::interval(Duration::from_millis(durability.flush_interval_ms.get()));
+    flush_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
     // Process rollout commands
-    while let Some(cmd) = rx.recv().await {
+    loop {
+        let cmd = tokio::select! {
+            cmd = rx.recv() => cmd,
+            _ = flush_interval.tick(), if !durability.flush_every_item => {
+                writer.flush().await?;
+                continue;
+            }
+        };
+        let Some(cmd) = cmd else {
+            break;
+        };
         match cmd {
             RolloutCmd::AddItems(items) => {
                 for item in items {
@@ -363,26 +422,50 @@ async fn rollout_writer(
             }
             RolloutCmd::Flush { ack } => {
                 // Ensure underlying file is flushed and then ack.
-                if let Err(e) = writer.file.flush().await {
+                if let Err(e) = writer.flush().await {
                     let _ = ack.send(());
                     return Err(e);
                 }
                 let _ = ack.send(());
             }
             RolloutCmd::Shutdown { ack } => {
+                let result = writer.flush().await;
+                // Unlock before acknowledging, so that the session can be
+                // resumed as soon as it shut down.
+                drop(lock);
                 let _ = ack.send(());
+                return result;
             }
         }
     }
 
-    Ok(())
+    writer.flush().await
 }
 
 struct JsonlWriter {
     file: tokio::fs::File,
+    durability: RolloutDurability,
+    /// Serialized lines that have not been written to the file yet.
+    pending: Vec<u8>,
+    /// Whether data was written since the file was last synced.
+    unsynced: bool,
+    items_written: u64,
+    /// Set once `crash_after_items` is reached; nothing is written after that.
+    crashed: bool,
 }
 
 impl JsonlWriter {
+    fn new(file: tokio::fs::File, durability: RolloutDurability) -> Self {
+        Self {
+            file,
+            durability,
+            pending: Vec::new(),
+            unsynced: false,
+            items_written: 0,
+            crashed: false,
+        }
+    }
+
     async fn write_rollout_item(&mut self, rollout_item: RolloutItem) -> std::io::Result<()> {
         let timestamp_format: &[FormatItem] = format_description!(
             "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
@@ -398,10 +481,127 @@ impl JsonlWriter {
         self.write_line(&line).await
     }
     async fn write_line(&mut self, item: &impl serde::Serialize) -> std::io::Result<()> {
+        if self.crashed {
+            return Ok(());
+        }
         let mut json = serde_json::to_string(item)?;
+        if self
+            .durability
+            .crash_after_items
+            .is_some_and(|limit| self.items_written >= limit)
+        {
+            return self.simulate_crash(json.as_bytes()).await;
+        }
         json.push('\n');
-        self.file.write_all(json.as_bytes()).await?;
-        self.file.flush().await?;
+        self.pending.extend_from_slice(json.as_bytes());
+        self.items_written += 1;
+        if self.durability.flush_every_item {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Writes buffered lines to the file and, with `fsync` enabled, syncs it.
+    async fn flush(&mut self) -> std::io::Result<()> {
+        if self.crashed {
+            return Ok(());
+        }
+        if !self.pending.is_empty() {
+            self.file.write_all(&self.pending).await?;
+            self.pending.clear();
+            self.file.flush().await?;
+            self.unsynced = true;
+        }
+        if self.durability.fsync && self.unsynced {
+            self.file.sync_data().await?;
+            self.unsynced = false;
+        }
         Ok(())
     }
+
+    /// Crash-consistency test mode: drop buffered lines and leave the first
+    /// half of `line` in the file without a trailing newline, as a crash in
+    /// the middle of a write would.
+    async fn simulate_crash(&mut self, line: &[u8]) -> std::io::Result<()> {
+        warn!(
+            "rollout.crash_after_items reached after {} items; simulating a crash",
+            self.items_written
+        );
+        self.crashed = true;
+        self.pending.clear();
+        self.file.write_all(&line[..line.len() / 2]).await?;
+        self.file.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    async fn open_writer(path: &Path, durability: RolloutDurability) -> JsonlWriter {
+        let file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .await
+            .expect("open rollout file");
+        JsonlWriter::new(file, durability)
+    }
+
+    #[tokio::test]
+    async fn buffered_items_are_written_on_flush() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join("rollout.jsonl");
+        let mut writer = open_writer(
+            &path,
+            RolloutDurability {
+                flush_every_item: false,
+                fsync: true,
+                ..RolloutDurability::default()
+            },
+        )
+        .await;
+
+        writer.write_line(&json!({"n": 1})).await.expect("write");
+        writer.write_line(&json!({"n": 2})).await.expect("write");
+        assert_eq!(tokio::fs::read_to_string(&path).await.expect("read"), "");
+
+        writer.flush().await.expect("flush");
+        assert_eq!(
+            tokio::fs::read_to_string(&path).await.expect("read"),
+            "{\"n\":1}\n{\"n\":2}\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn simulated_crash_leaves_a_torn_line_that_resume_removes() {
+        let dir = TempDir::new().expect("tempdir");
+        let path = dir.path().join("rollout.jsonl");
+        let mut writer = open_writer(
+            &path,
+            RolloutDurability {
+                crash_after_items: Some(1),
+                ..RolloutDurability::default()
+            },
+        )
+        .await;
+
+        writer.write_line(&json!({"n": 1})).await.expect("write");
+        writer.write_line(&json!({"n": 2})).await.expect("write");
+        writer.write_line(&json!({"n": 3})).await.expect("write");
+        writer.flush().await.expect("flush");
+        assert_eq!(
+            tokio::fs::read_to_string(&path).await.expect("read"),
+            "{\"n\":1}\n{\"n"
+        );
+
+        repair_torn_tail(&path).await.expect("repair");
+        assert_eq!(
+            tokio::fs::read_to_string(&path).await.expect("read"),
+            "{\"n\":1}\n"
+        );
+    }
 }
@@ -10,8 +10,11 @@ use crate::exec::SandboxType;
 
 use crate::command_safety::is_dangerous_command::command_might_be_dangerous;
 use crate::command_safety::is_safe_command::is_known_safe_command;
+use crate::container_sandbox::resolve_runtime;
 use crate::protocol::AskForApproval;
+use crate::protocol::SandboxCapability;
 use crate::protocol::SandboxPolicy;
+use crate::sandbox_rules::SandboxRuleOutcome;
 
 #[derive(Debug, PartialEq)]
 pub enum SafetyCheck {
@@ -32,6 +35,17 @@ pub fn assess_patch_safety(
         };
     }
 
+    // Paths on the `deny` list stay read-only even if the user would approve
+    // the patch.
+    if let Some(path) = first_denied_path(action, sandbox_policy, cwd) {
+        return SafetyCheck::Reject {
+            reason: format!(
+                "{} is read-only: it matches sandbox_workspace_write.deny",
+                path.display()
+            ),
+        };
+    }
+
     match policy {
         AskForApproval::OnFailure | AskForApproval::Never | AskForApproval::OnRequest => {
             // Continue to see if this can be auto-approved.
@@ -124,6 +138,47 @@ pub fn assess_command_safety(
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
 
+/// Applies the outcome of `[[sandbox.rules]]` to a shell command. Returns
+/// `None` when no rule decides the command, in which case the regular checks
+/// apply. `sandbox_policy` is the policy the command will run with, including
+/// any network access granted by the rule.
+pub(crate) fn assess_sandbox_rule(
+    outcome: &SandboxRuleOutcome,
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    with_escalated_permissions: bool,
+) -> Option<SafetyCheck> {
+    match outcome {
+        SandboxRuleOutcome::NoMatch => None,
+        SandboxRuleOutcome::Deny { rule } => Some(SafetyCheck::Reject {
+            reason: format!("denied by sandbox rule `{rule}`"),
+        }),
+        SandboxRuleOutcome::Ask { rule } => {
+            if approval_policy == AskForApproval::Never {
+                Some(SafetyCheck::Reject {
+                    reason: format!(
+                        "sandbox rule `{rule}` requires approval; rejected by user approval settings"
+                    ),
+                })
+            } else {
+                Some(SafetyCheck::AskUser)
+            }
+        }
+        // An allow rule only runs the command in the sandbox without asking;
+        // it never grants more than the sandbox (plus network) allows.
+        SandboxRuleOutcome::Allow { .. } => {
+            if with_escalated_permissions
+                || approval_policy == AskForApproval::UnlessTrusted
+                || sandbox_policy == &SandboxPolicy::DangerFullAccess
+            {
+                return None;
+            }
+            get_sandbox_for_policy(sandbox_policy)
+                .map(|sandbox_type| SafetyCheck::AutoApprove { sandbox_type })
+        }
+    }
+}
+
 pub(crate) fn assess_safety_for_untrusted_command(
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
@@ -144,11 +199,13 @@ pub(crate) fn assess_safety_for_untrusted_command(
         | (OnRequest, DangerFullAccess) => SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         },
-        (OnRequest, ReadOnly) | (OnRequest, WorkspaceWrite { .. }) => {
+        (OnRequest, ReadOnly)
+        | (OnRequest, WorkspaceWrite { .. })
+        | (OnRequest, Container { .. }) => {
             if with_escalated_permissions {
                 SafetyCheck::AskUser
             } else {
-                match get_platform_sandbox() {
+                match get_sandbox_for_policy(sandbox_policy) {
                     Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
                     // Fall back to asking since the command is untrusted and
                     // we do not have a sandbox available
@@ -158,9 +215,11 @@ pub(crate) fn assess_safety_for_untrusted_command(
         }
         (Never, ReadOnly)
         | (Never, WorkspaceWrite { .. })
+        | (Never, Container { .. })
         | (OnFailure, ReadOnly)
-        | (OnFailure, WorkspaceWrite { .. }) => {
-            match get_platform_sandbox() {
+        | (OnFailure, WorkspaceWrite { .. })
+        | (OnFailure, Container { .. }) => {
+            match get_sandbox_for_policy(sandbox_policy) {
                 Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
                 None => {
                     if matches!(approval_policy, OnFailure) {
@@ -188,11 +247,89 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
         Some(SandboxType::MacosSeatbelt)
     } else if cfg!(target_os = "linux") {
         Some(SandboxType::LinuxSeccomp)
+    } else if cfg!(target_os = "windows") && codex_windows_sandbox::detect().is_ok() {
+        Some(SandboxType::WindowsRestrictedToken)
     } else {
         None
     }
 }
 
+/// Sandbox that enforces `sandbox_policy` for shell commands.
+fn get_sandbox_for_policy(sandbox_policy: &SandboxPolicy) -> Option<SandboxType> {
+    match sandbox_policy {
+        SandboxPolicy::Container { .. } => Some(SandboxType::Container),
+        _ => get_platform_sandbox(),
+    }
+}
+
+/// Describes how the sandbox policy is enforced on this machine, for
+/// reporting to clients in `SessionConfigured`.
+pub fn sandbox_capability(sandbox_policy: &SandboxPolicy) -> SandboxCapability {
+    if let SandboxPolicy::Container { runtime, .. } = sandbox_policy {
+        return match resolve_runtime(*runtime) {
+            Ok((runtime, _)) => SandboxCapability::Container { runtime },
+            Err(err) => SandboxCapability::Unavailable {
+                reason: err.to_string(),
+            },
+        };
+    }
+    match get_platform_sandbox() {
+        Some(SandboxType::MacosSeatbelt) => SandboxCapability::Seatbelt,
+        Some(SandboxType::LinuxSeccomp) => SandboxCapability::Landlock,
+        Some(SandboxType::WindowsRestrictedToken) => SandboxCapability::WindowsRestrictedToken,
+        Some(SandboxType::None) | Some(SandboxType::Container) | None => {
+            SandboxCapability::Unavailable {
+                reason: if cfg!(target_os = "windows") {
+                    codex_windows_sandbox::detect()
+                        .err()
+                        .unwrap_or_else(|| "the Windows sandbox is unavailable".to_string())
+                } else {
+                    "no sandbox is supported on this platform".to_string()
+                },
+            }
+        }
+    }
+}
+
+/// Returns the first path written by `action` that is on the workspace-write
+/// `deny` list.
+fn first_denied_path(
+    action: &ApplyPatchAction,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Option<PathBuf> {
+    action
+        .changes()
+        .iter()
+        .flat_map(|(path, change)| {
+            let move_path = match change {
+                ApplyPatchFileChange::Update { move_path, .. } => move_path.as_ref(),
+                ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => None,
+            };
+            std::iter::once(path).chain(move_path)
+        })
+        .find(|path| {
+            normalize(&cwd.join(path)).is_some_and(|abs| sandbox_policy.is_path_denied(&abs, cwd))
+        })
+        .cloned()
+}
+
+// Normalize a path by removing `.` and resolving `..` without touching the
+// filesystem (works even if the file does not exist).
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => { /* skip */ }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    Some(out)
+}
+
 fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     sandbox_policy: &SandboxPolicy,
@@ -206,24 +343,10 @@ fn is_write_patch_constrained_to_writable_paths(
         SandboxPolicy::DangerFullAccess => {
             return true;
         }
-        SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
-    };
-
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
+        SandboxPolicy::WorkspaceWrite { .. } | SandboxPolicy::Container { .. } => {
+            sandbox_policy.get_writable_roots_with_cwd(cwd)
         }
-        Some(out)
-    }
+    };
 
     // Determine whether `path` is inside **any** writable root. Both `path`
     // and roots are converted to absolute, normalized forms before the
@@ -293,6 +416,8 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            network_allowlist: Vec::new(),
+            deny: Vec::new(),
         };
 
         assert!(is_write_patch_constrained_to_writable_paths(
@@ -314,6 +439,8 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            network_allowlist: Vec::new(),
+            deny: Vec::new(),
         };
         assert!(is_write_patch_constrained_to_writable_paths(
             &add_outside,
@@ -322,6 +449,34 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn patches_to_denied_paths_are_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            network_allowlist: Vec::new(),
+            deny: vec!["secrets/".to_string(), "*.pem".to_string()],
+        };
+
+        let add_key = ApplyPatchAction::new_add_for_test(&cwd.join("certs/server.pem"), "".into());
+        assert_eq!(
+            assess_patch_safety(&add_key, AskForApproval::OnRequest, &policy, &cwd),
+            SafetyCheck::Reject {
+                reason: format!(
+                    "{} is read-only: it matches sandbox_workspace_write.deny",
+                    cwd.join("certs/server.pem").display()
+                ),
+            }
+        );
+
+        let add_source = ApplyPatchAction::new_add_for_test(&cwd.join("src/main.rs"), "".into());
+        assert_eq!(first_denied_path(&add_source, &policy, &cwd), None);
+    }
+
     #[test]
     fn test_request_escalated_privileges() {
         // Should not be a trusted command
@@ -414,4 +569,74 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn untrusted_command_runs_in_container_under_container_policy() {
+        let command = vec!["cargo".to_string(), "test".to_string()];
+        let sandbox_policy = SandboxPolicy::Container {
+            image: "rust:1.90".to_string(),
+            runtime: None,
+            writable_roots: vec![],
+            network_access: false,
+        };
+        let approved: HashSet<Vec<String>> = HashSet::new();
+
+        let safety_check = assess_command_safety(
+            &command,
+            AskForApproval::OnRequest,
+            &sandbox_policy,
+            &approved,
+            false,
+        );
+
+        assert_eq!(
+            safety_check,
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::Container
+            }
+        );
+    }
+
+    #[test]
+    fn sandbox_rule_ask_and_deny_override_approval_policy() {
+        let sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        let ask = SandboxRuleOutcome::Ask {
+            rule: "git push".to_string(),
+        };
+        assert_eq!(
+            assess_sandbox_rule(&ask, AskForApproval::OnRequest, &sandbox_policy, false),
+            Some(SafetyCheck::AskUser)
+        );
+        assert_eq!(
+            assess_sandbox_rule(&ask, AskForApproval::Never, &sandbox_policy, false),
+            Some(SafetyCheck::Reject {
+                reason:
+                    "sandbox rule `git push` requires approval; rejected by user approval settings"
+                        .to_string()
+            })
+        );
+
+        let deny = SandboxRuleOutcome::Deny {
+            rule: "rm -rf".to_string(),
+        };
+        assert_eq!(
+            assess_sandbox_rule(
+                &deny,
+                AskForApproval::OnRequest,
+                &SandboxPolicy::DangerFullAccess,
+                true
+            ),
+            Some(SafetyCheck::Reject {
+                reason: "denied by sandbox rule `rm -rf`".to_string()
+            })
+        );
+
+        let allow = SandboxRuleOutcome::Allow {
+            network_access: true,
+        };
+        assert_eq!(
+            assess_sandbox_rule(&allow, AskForApproval::OnRequest, &sandbox_policy, true),
+            None
+        );
+    }
 }
@@ -0,0 +1,500 @@
+//! Append-only audit log of every command Codex executed or refused to
+//! execute, for teams that need an account of what the agent actually did.
+//!
+//! The log is stored at `~/.codex/log/sandbox-audit.jsonl` with one
+//! [`SandboxAuditRecord`] per line. Like the message history, each record is
+//! written with a single `write(2)` on a file opened with `O_APPEND`, so
+//! concurrent sessions do not interleave lines.
+//!
+//! Only the command line, its sandbox and approval are recorded. Output and
+//! environment variables (which may hold secrets) are not, except for the
+//! lines that look like they report an operation the sandbox refused, with
+//! the session's secrets redacted.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::error::CodexErr;
+use crate::error::SandboxErr;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::SandboxType;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::SecretValue;
+use crate::secrets::redact_secrets;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Filename of the audit log inside `~/.codex/log`.
+const SANDBOX_AUDIT_FILENAME: &str = "sandbox-audit.jsonl";
+
+/// Phrases that tools print when the sandbox refuses a file or network
+/// operation. The sandboxes do not report what they refused, so output lines
+/// are matched against these instead; the same phrases also appear in
+/// failures that have nothing to do with the sandbox.
+const DENIAL_MARKERS: &[&str] = &[
+    "Permission denied",
+    "Operation not permitted",
+    "Read-only file system",
+    "Could not resolve host",
+    "Temporary failure in name resolution",
+    "Network is unreachable",
+];
+
+/// At most this many denial lines are kept per record, so a command that
+/// fails on every file of a large tree does not bloat the log.
+const MAX_DENIALS_PER_RECORD: usize = 20;
+
+/// How the command came to run, or why it did not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditApproval {
+    /// Allowed without asking by the approval policy, a sandbox rule or an
+    /// approval given earlier in the session.
+    Auto,
+    /// Approved by the user for this invocation.
+    Approved,
+    /// Approved by the user for the rest of the session.
+    ApprovedForSession,
+    /// Allowed without asking because the config trusts the command: a custom
+    /// tool with `sandbox` and `approval` turned off.
+    Trusted,
+    /// Declined by the user; the command did not run.
+    Denied,
+    /// Refused by the approval policy or a sandbox rule; the command did not
+    /// run.
+    Rejected,
+}
+
+impl AuditApproval {
+    /// Whether the command was kept from running.
+    pub fn is_blocked(self) -> bool {
+        matches!(self, AuditApproval::Denied | AuditApproval::Rejected)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SandboxAuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: ConversationId,
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    /// Mechanism that enforced `sandbox_policy`; `none` when the command ran
+    /// unsandboxed.
+    pub sandbox: String,
+    pub sandbox_policy: SandboxPolicy,
+    pub approval: AuditApproval,
+    /// Why the command was rejected without asking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// The command failed in a way attributed to the sandbox.
+    #[serde(default)]
+    pub sandbox_denied: bool,
+    /// Output lines that look like they report a path or network operation
+    /// the sandbox refused. This is a heuristic based on the wording of the
+    /// output, which the command controls.
+    #[serde(default, alias = "denials", skip_serializing_if = "Vec::is_empty")]
+    pub suspected_denials: Vec<String>,
+}
+
+impl SandboxAuditRecord {
+    /// Record for a command that was kept from running.
+    pub(crate) fn blocked(
+        session_id: ConversationId,
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        sandbox_policy: SandboxPolicy,
+        approval: AuditApproval,
+        reason: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            call_id,
+            command,
+            cwd,
+            sandbox: sandbox_name(SandboxType::None).to_string(),
+            sandbox_policy,
+            approval,
+            reason,
+            exit_code: None,
+            duration_ms: None,
+            sandbox_denied: false,
+            suspected_denials: Vec::new(),
+        }
+    }
+
+    /// Record for a command that ran, built from the result of the exec.
+    #[expect(clippy::too_many_arguments)]
+    pub(crate) fn executed(
+        session_id: ConversationId,
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        sandbox_type: SandboxType,
+        sandbox_policy: SandboxPolicy,
+        approval: AuditApproval,
+        result: &crate::error::Result<ExecToolCallOutput>,
+    ) -> Self {
+        let output = match result {
+            Ok(output) => Some(output),
+            Err(CodexErr::Sandbox(SandboxErr::Denied { output }))
+            | Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => Some(output.as_ref()),
+            Err(_) => None,
+        };
+        let suspected_denials = match output {
+            Some(output) if sandbox_type != SandboxType::None => denial_lines(&output.stderr.text),
+            _ => Vec::new(),
+        };
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            call_id,
+            command,
+            cwd,
+            sandbox: sandbox_name(sandbox_type).to_string(),
+            sandbox_policy,
+            approval,
+            reason: None,
+            exit_code: Some(output.map_or(-1, |output| output.exit_code)),
+            duration_ms: output.map(|output| output.duration.as_millis() as u64),
+            sandbox_denied: matches!(result, Err(CodexErr::Sandbox(SandboxErr::Denied { .. }))),
+            suspected_denials,
+        }
+    }
+
+    /// Replaces the session's secrets in the output lines kept in the record.
+    pub(crate) fn redacted(mut self, secrets: &HashMap<String, SecretValue>) -> Self {
+        for line in &mut self.suspected_denials {
+            *line = redact_secrets(line, secrets);
+        }
+        self
+    }
+
+    /// The command as a single shell-quoted line, for display.
+    pub fn command_line(&self) -> String {
+        shlex::try_join(self.command.iter().map(String::as_str))
+            .unwrap_or_else(|_| self.command.join(" "))
+    }
+}
+
+/// Which records [`read_audit_log`] returns.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub session_id: Option<ConversationId>,
+    /// Only records at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only commands that did not run or that the sandbox refused.
+    pub blocked_only: bool,
+    /// Only the most recent `limit` matching records.
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &SandboxAuditRecord) -> bool {
+        self.session_id
+            .is_none_or(|session_id| record.session_id == session_id)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && (!self.blocked_only
+                || record.approval.is_blocked()
+                || record.sandbox_denied
+                || !record.suspected_denials.is_empty())
+    }
+}
+
+pub fn audit_log_path(codex_home: &Path) -> PathBuf {
+    codex_home.join("log").join(SANDBOX_AUDIT_FILENAME)
+}
+
+/// Appends `record` to the audit log. Failures are logged rather than
+/// returned: a full disk should not stop the agent from working.
+pub(crate) async fn append_record(codex_home: &Path, record: &SandboxAuditRecord) {
+    let path = audit_log_path(codex_home);
+    let mut line = match serde_json::to_string(record) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("failed to serialize sandbox audit record: {e}");
+            return;
+        }
+    };
+    line.push('\n');
+
+    let result = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        {
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+        file.write_all(line.as_bytes())
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("failed to write sandbox audit log: {e}"),
+        Err(e) => warn!("failed to write sandbox audit log: {e}"),
+    }
+}
+
+/// Reads the records matching `query`, oldest first. A missing log yields no
+/// records; lines that cannot be parsed are skipped.
+pub fn read_audit_log(
+    codex_home: &Path,
+    query: &AuditQuery,
+) -> std::io::Result<Vec<SandboxAuditRecord>> {
+    let file = match std::fs::File::open(audit_log_path(codex_home)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<SandboxAuditRecord>(&line) {
+            Ok(record) if query.matches(&record) => records.push(record),
+            Ok(_) => {}
+            Err(e) => warn!("skipping malformed sandbox audit record: {e}"),
+        }
+    }
+    if let Some(limit) = query.limit {
+        let skip = records.len().saturating_sub(limit);
+        records.drain(..skip);
+    }
+    Ok(records)
+}
+
+/// Parses the `--since` argument of `codex audit` and `codex report`: an
+/// RFC 3339 timestamp or an age such as `30m`, `12h`, `7d` or `2w`.
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    let invalid = || {
+        format!(
+            "invalid time `{value}`: expected an RFC 3339 timestamp or an age like 30m, 12h, 7d or 2w"
+        )
+    };
+    let split = value.len().saturating_sub(1);
+    let (amount, unit) = value.split_at_checked(split).ok_or_else(invalid)?;
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let age = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    Ok(Utc::now() - age)
+}
+
+fn sandbox_name(sandbox_type: SandboxType) -> &'static str {
+    match sandbox_type {
+        SandboxType::None => "none",
+        SandboxType::MacosSeatbelt => "seatbelt",
+        SandboxType::LinuxSeccomp => "landlock",
+        SandboxType::WindowsRestrictedToken => "windows-restricted-token",
+        SandboxType::Container => "container",
+    }
+}
+
+fn denial_lines(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter(|line| DENIAL_MARKERS.iter().any(|marker| line.contains(marker)))
+        .map(|line| line.trim().to_string())
+        .take(MAX_DENIALS_PER_RECORD)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::StreamOutput;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn denied_output(stderr: &str) -> ExecToolCallOutput {
+        ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(stderr.to_string()),
+            aggregated_output: StreamOutput::new(stderr.to_string()),
+            duration: std::time::Duration::from_millis(12),
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn sandbox_denials_are_extracted_from_stderr() {
+        let result = Err(CodexErr::Sandbox(SandboxErr::Denied {
+            output: Box::new(denied_output(
+                "compiling\ntouch: cannot touch '/etc/hosts': Operation not permitted\ncurl: (6) Could not resolve host: example.com\n",
+            )),
+        }));
+        let record = SandboxAuditRecord::executed(
+            ConversationId::new(),
+            "call-1".to_string(),
+            vec!["bash".to_string(), "-lc".to_string(), "make".to_string()],
+            PathBuf::from("/repo"),
+            SandboxType::LinuxSeccomp,
+            SandboxPolicy::new_read_only_policy(),
+            AuditApproval::Auto,
+            &result,
+        );
+
+        assert!(record.sandbox_denied);
+        assert_eq!(record.exit_code, Some(1));
+        assert_eq!(record.duration_ms, Some(12));
+        assert_eq!(
+            record.suspected_denials,
+            vec![
+                "touch: cannot touch '/etc/hosts': Operation not permitted".to_string(),
+                "curl: (6) Could not resolve host: example.com".to_string(),
+            ]
+        );
+        assert_eq!(record.command_line(), "bash -lc make");
+    }
+
+    #[test]
+    fn secrets_are_redacted_from_suspected_denials() {
+        let result = Err(CodexErr::Sandbox(SandboxErr::Denied {
+            output: Box::new(denied_output(
+                "curl: (6) Could not resolve host: api.example.com?token=hunter2-token\n",
+            )),
+        }));
+        let secrets = HashMap::from([(
+            "API_TOKEN".to_string(),
+            SecretValue::new("hunter2-token".to_string()),
+        )]);
+        let record = SandboxAuditRecord::executed(
+            ConversationId::new(),
+            "call-1".to_string(),
+            vec!["curl".to_string(), "$URL".to_string()],
+            PathBuf::from("/repo"),
+            SandboxType::LinuxSeccomp,
+            SandboxPolicy::new_read_only_policy(),
+            AuditApproval::Auto,
+            &result,
+        )
+        .redacted(&secrets);
+
+        assert_eq!(
+            record.suspected_denials,
+            vec![
+                "curl: (6) Could not resolve host: api.example.com?token=[redacted secret API_TOKEN]"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn older_records_with_denials_still_parse() {
+        let line = r#"{"timestamp":"2025-01-01T00:00:00Z","session_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","call_id":"c","command":["ls"],"cwd":"/repo","sandbox":"landlock","sandbox_policy":{"mode":"read-only"},"approval":"auto","sandbox_denied":true,"denials":["ls: Permission denied"]}"#;
+        let record: SandboxAuditRecord = serde_json::from_str(line).expect("parse record");
+        assert_eq!(
+            record.suspected_denials,
+            vec!["ls: Permission denied".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_round_trip_and_can_be_filtered() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let session = ConversationId::new();
+        let ran = SandboxAuditRecord::executed(
+            session,
+            "call-1".to_string(),
+            vec!["ls".to_string()],
+            PathBuf::from("/repo"),
+            SandboxType::MacosSeatbelt,
+            SandboxPolicy::new_read_only_policy(),
+            AuditApproval::Auto,
+            &Ok(ExecToolCallOutput {
+                exit_code: 0,
+                ..denied_output("")
+            }),
+        );
+        let rejected = SandboxAuditRecord::blocked(
+            session,
+            "call-2".to_string(),
+            vec!["rm".to_string(), "-rf".to_string(), "/".to_string()],
+            PathBuf::from("/repo"),
+            SandboxPolicy::new_read_only_policy(),
+            AuditApproval::Denied,
+            None,
+        );
+        let other_session = SandboxAuditRecord {
+            session_id: ConversationId::new(),
+            ..ran.clone()
+        };
+        for record in [&ran, &rejected, &other_session] {
+            append_record(codex_home.path(), record).await;
+        }
+
+        let all = read_audit_log(codex_home.path(), &AuditQuery::default()).expect("read");
+        assert_eq!(all, vec![ran.clone(), rejected.clone(), other_session]);
+
+        let query = AuditQuery {
+            session_id: Some(session),
+            blocked_only: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            read_audit_log(codex_home.path(), &query).expect("read"),
+            vec![rejected]
+        );
+
+        let query = AuditQuery {
+            session_id: Some(session),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            read_audit_log(codex_home.path(), &query).expect("read")[0].call_id,
+            "call-2"
+        );
+    }
+
+    #[test]
+    fn since_accepts_timestamps_and_ages() {
+        assert_eq!(
+            parse_since("2025-01-02T03:04:05Z").map(|t| t.to_rfc3339()),
+            Ok("2025-01-02T03:04:05+00:00".to_string())
+        );
+        let an_hour_ago = parse_since("1h").expect("age");
+        assert!(Utc::now() - an_hour_ago >= Duration::hours(1));
+        let a_week_ago = parse_since("1w").expect("age");
+        assert!(Utc::now() - a_week_ago >= Duration::weeks(1));
+        assert!(parse_since("1y").is_err());
+        assert!(parse_since("yesterday").is_err());
+    }
+}
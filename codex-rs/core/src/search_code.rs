@@ -0,0 +1,359 @@
+//! The `search_code` tool: a regex search over the workspace that returns
+//! structured matches, so the model does not have to build `rg` command
+//! lines and parse their output.
+//!
+//! Files are walked with the same library ripgrep uses, so `.gitignore`,
+//! `.ignore` and global git excludes are honored. Binary files, files over
+//! [`MAX_FILE_BYTES`] and the `.git` directory are skipped.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use regex_lite::Regex;
+use regex_lite::RegexBuilder;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const SEARCH_CODE_TOOL_NAME: &str = "search_code";
+
+/// Files larger than this are not searched.
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+const DEFAULT_MAX_RESULTS: usize = 50;
+const MAX_RESULTS: usize = 200;
+const MAX_CONTEXT_LINES: usize = 5;
+
+/// Matched and context lines longer than this are cut short.
+const MAX_LINE_CHARS: usize = 500;
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct SearchCodeArgs {
+    pub(crate) pattern: String,
+    #[serde(default)]
+    pub(crate) path: Option<String>,
+    #[serde(default)]
+    pub(crate) glob: Option<String>,
+    #[serde(default)]
+    pub(crate) case_insensitive: bool,
+    #[serde(default)]
+    pub(crate) fixed_strings: bool,
+    #[serde(default)]
+    pub(crate) context: Option<usize>,
+    #[serde(default)]
+    pub(crate) max_results: Option<usize>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct SearchMatch {
+    path: String,
+    line: usize,
+    text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct SearchResults {
+    matches: Vec<SearchMatch>,
+    /// More matches exist than were returned.
+    truncated: bool,
+}
+
+pub(crate) fn create_search_code_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some("Regular expression to search for".to_string()),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "File or directory to search, relative to the working directory (default: the working directory)"
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "glob".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Only search files matching this glob, e.g. \"*.rs\" or \"src/**/*.ts\""
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "case_insensitive".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Match case-insensitively (default: false)".to_string()),
+        },
+    );
+    properties.insert(
+        "fixed_strings".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Treat `pattern` as a literal string rather than a regex (default: false)"
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "context".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Lines of context to return before and after each match (default: 0, at most {MAX_CONTEXT_LINES})"
+            )),
+        },
+    );
+    properties.insert(
+        "max_results".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Maximum number of matches to return (default: {DEFAULT_MAX_RESULTS}, at most {MAX_RESULTS})"
+            )),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SEARCH_CODE_TOOL_NAME.to_string(),
+        description: "Searches file contents for a regular expression, honoring .gitignore, and \
+                      returns JSON with the path, 1-based line number and text of each match, \
+                      plus optional context lines. Prefer it over running grep or rg in the \
+                      shell."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["pattern".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+/// Runs the search `args` describes under `cwd` and returns the matches as
+/// JSON. Blocks while walking the tree.
+pub(crate) fn search_code(cwd: &Path, args: &SearchCodeArgs) -> Result<String, String> {
+    let regex = build_regex(args)?;
+    let root = match &args.path {
+        Some(path) => cwd.join(path),
+        None => cwd.to_path_buf(),
+    };
+    if !root.exists() {
+        return Err(format!("{} does not exist", root.display()));
+    }
+    let context = args.context.unwrap_or(0).min(MAX_CONTEXT_LINES);
+    let max_results = args
+        .max_results
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+        .clamp(1, MAX_RESULTS);
+
+    let mut walk_builder = WalkBuilder::new(&root);
+    walk_builder
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    if let Some(glob) = &args.glob {
+        let mut overrides = OverrideBuilder::new(&root);
+        overrides
+            .add(glob)
+            .map_err(|e| format!("invalid glob {glob:?}: {e}"))?;
+        let overrides = overrides
+            .build()
+            .map_err(|e| format!("invalid glob {glob:?}: {e}"))?;
+        walk_builder.overrides(overrides);
+    }
+
+    let mut files: Vec<PathBuf> = walk_builder
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .map(ignore::DirEntry::into_path)
+        .collect();
+    // Deterministic order, so repeated searches give the same results.
+    files.sort();
+
+    let mut results = SearchResults {
+        matches: Vec::new(),
+        truncated: false,
+    };
+    for file in files {
+        if results.truncated {
+            break;
+        }
+        let Some(text) = read_text(&file) else {
+            continue;
+        };
+        let display_path = file
+            .strip_prefix(cwd)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            if !regex.is_match(line) {
+                continue;
+            }
+            if results.matches.len() == max_results {
+                results.truncated = true;
+                break;
+            }
+            let context_lines = |range: std::ops::Range<usize>| {
+                lines[range]
+                    .iter()
+                    .map(|line| shorten(line))
+                    .collect::<Vec<_>>()
+            };
+            results.matches.push(SearchMatch {
+                path: display_path.clone(),
+                line: index + 1,
+                text: shorten(line),
+                before: context_lines(index.saturating_sub(context)..index),
+                after: context_lines(index + 1..(index + 1 + context).min(lines.len())),
+            });
+        }
+    }
+    serde_json::to_string(&results).map_err(|e| e.to_string())
+}
+
+fn build_regex(args: &SearchCodeArgs) -> Result<Regex, String> {
+    if args.pattern.is_empty() {
+        return Err("`pattern` must not be empty".to_string());
+    }
+    let pattern = if args.fixed_strings {
+        regex_lite::escape(&args.pattern)
+    } else {
+        args.pattern.clone()
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(args.case_insensitive)
+        .build()
+        .map_err(|e| format!("invalid pattern: {e}"))
+}
+
+/// The content of `path`, or `None` for large, unreadable or binary files.
+fn read_text(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    // Same heuristic as ripgrep: a NUL byte means binary.
+    if bytes.contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn shorten(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().expect("create temp dir");
+        let write = |path: &str, content: &[u8]| {
+            let path = dir.path().join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).expect("create dirs");
+            }
+            std::fs::write(path, content).expect("write file");
+        };
+        write(".gitignore", b"target/\n");
+        write("src/lib.rs", b"mod parser;\n\nfn parse_args() {}\n");
+        write("src/parser.rs", b"// Parser\npub fn parse() {}\n");
+        write("target/debug/out.rs", b"fn parse() {}\n");
+        write("data.bin", b"parse\0\x01");
+        dir
+    }
+
+    fn search(dir: &TempDir, args: SearchCodeArgs) -> Value {
+        let output = search_code(dir.path(), &args).expect("search");
+        serde_json::from_str(&output).expect("valid JSON")
+    }
+
+    #[test]
+    fn returns_matches_with_context_and_honors_gitignore() {
+        let dir = workspace();
+
+        let results = search(
+            &dir,
+            SearchCodeArgs {
+                pattern: r"fn parse\w*\(".to_string(),
+                context: Some(1),
+                ..SearchCodeArgs::default()
+            },
+        );
+
+        assert_eq!(
+            results,
+            json!({
+                "matches": [
+                    {"path": "src/lib.rs", "line": 3, "text": "fn parse_args() {}", "before": [""]},
+                    {"path": "src/parser.rs", "line": 2, "text": "pub fn parse() {}", "before": ["// Parser"]}
+                ],
+                "truncated": false
+            })
+        );
+    }
+
+    #[test]
+    fn filters_by_glob_and_truncates() {
+        let dir = workspace();
+
+        let results = search(
+            &dir,
+            SearchCodeArgs {
+                pattern: "PARSE".to_string(),
+                glob: Some("parser.rs".to_string()),
+                case_insensitive: true,
+                fixed_strings: true,
+                max_results: Some(1),
+                ..SearchCodeArgs::default()
+            },
+        );
+
+        assert_eq!(
+            results,
+            json!({
+                "matches": [{"path": "src/parser.rs", "line": 1, "text": "// Parser"}],
+                "truncated": true
+            })
+        );
+        assert!(
+            search_code(
+                dir.path(),
+                &SearchCodeArgs {
+                    pattern: "(".to_string(),
+                    ..SearchCodeArgs::default()
+                }
+            )
+            .is_err()
+        );
+    }
+}
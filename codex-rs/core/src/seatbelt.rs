@@ -3,6 +3,8 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::config_types::ResourceLimits;
+use crate::network_proxy::configure_network_proxy;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
@@ -21,10 +23,17 @@ pub async fn spawn_command_under_seatbelt(
     command_cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
+    resource_limits: &ResourceLimits,
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
-    let args = create_seatbelt_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let network_proxy_port = configure_network_proxy(sandbox_policy, &mut env)?;
+    let args = create_seatbelt_command_args(
+        command,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        network_proxy_port,
+    );
     let arg0 = None;
     env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
     spawn_child_async(
@@ -33,6 +42,7 @@ pub async fn spawn_command_under_seatbelt(
         arg0,
         command_cwd,
         sandbox_policy,
+        resource_limits,
         stdio_policy,
         env,
     )
@@ -43,6 +53,7 @@ fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
+    network_proxy_port: Option<u16>,
 ) -> Vec<String> {
     let (file_write_policy, extra_cli_args) = {
         if sandbox_policy.has_full_disk_write_access() {
@@ -66,7 +77,7 @@ fn create_seatbelt_command_args(
                     canonical_root.to_string_lossy()
                 ));
 
-                if wr.read_only_subpaths.is_empty() {
+                if wr.read_only_subpaths.is_empty() && wr.read_only_patterns.is_empty() {
                     writable_folder_policies.push(format!("(subpath (param \"{root_param}\"))"));
                 } else {
                     // Add parameters for each read-only subpath and generate
@@ -74,12 +85,24 @@ fn create_seatbelt_command_args(
                     let mut require_parts: Vec<String> = Vec::new();
                     require_parts.push(format!("(subpath (param \"{root_param}\"))"));
                     for (subpath_index, ro) in wr.read_only_subpaths.iter().enumerate() {
-                        let canonical_ro = ro.canonicalize().unwrap_or_else(|_| ro.clone());
+                        // Subpaths from the deny list may not exist yet, so
+                        // rebase them onto the canonical root instead.
+                        let canonical_ro = ro.canonicalize().unwrap_or_else(|_| {
+                            ro.strip_prefix(&wr.root)
+                                .map(|relative| canonical_root.join(relative))
+                                .unwrap_or_else(|_| ro.clone())
+                        });
                         let ro_param = format!("WRITABLE_ROOT_{index}_RO_{subpath_index}");
                         cli_args.push(format!("-D{ro_param}={}", canonical_ro.to_string_lossy()));
                         require_parts
                             .push(format!("(require-not (subpath (param \"{ro_param}\")))"));
                     }
+                    for pattern in &wr.read_only_patterns {
+                        require_parts.push(format!(
+                            "(require-not (regex #\"{}\"))",
+                            read_only_pattern_regex(&canonical_root, pattern)
+                        ));
+                    }
                     let policy_component = format!("(require-all {} )", require_parts.join(" "));
                     writable_folder_policies.push(policy_component);
                 }
@@ -105,9 +128,13 @@ fn create_seatbelt_command_args(
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
     let network_policy = if sandbox_policy.has_full_network_access() {
-        "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)"
+        "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)".to_string()
+    } else if let Some(port) = network_proxy_port {
+        // Only the local allowlist proxy is reachable; it decides which
+        // remote hosts the command may talk to.
+        format!("(allow network-outbound (remote ip \"localhost:{port}\"))\n(allow system-socket)")
     } else {
-        ""
+        String::new()
     };
 
     let full_policy = format!(
@@ -121,10 +148,43 @@ fn create_seatbelt_command_args(
     seatbelt_args
 }
 
+/// Translates a `deny` wildcard pattern under `root` into a Seatbelt regex
+/// that also covers everything beneath a matching folder.
+fn read_only_pattern_regex(root: &Path, pattern: &str) -> String {
+    let escape = |text: &str| {
+        text.chars()
+            .map(|c| match c {
+                // A quote would end the SBPL string; let it match any
+                // character instead.
+                '"' => ".".to_string(),
+                c if "\\.+*?()|[]{}^$".contains(c) => format!("\\{c}"),
+                c => c.to_string(),
+            })
+            .collect::<String>()
+    };
+    let body: String = pattern
+        .split_inclusive(['*', '?'])
+        .map(|part| match part.strip_suffix('*') {
+            Some(literal) => format!("{}[^/]*", escape(literal)),
+            None => match part.strip_suffix('?') {
+                Some(literal) => format!("{}[^/]", escape(literal)),
+                None => escape(part),
+            },
+        })
+        .collect();
+    let root = escape(&root.to_string_lossy());
+    if pattern.contains('/') {
+        format!("^{root}/{body}(/.*)?$")
+    } else {
+        format!("^{root}/(.*/)?{body}(/.*)?$")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::MACOS_SEATBELT_BASE_POLICY;
     use super::create_seatbelt_command_args;
+    use super::read_only_pattern_regex;
     use crate::protocol::SandboxPolicy;
     use pretty_assertions::assert_eq;
     use std::fs;
@@ -158,12 +218,15 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            network_allowlist: Vec::new(),
+            deny: Vec::new(),
         };
 
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             &cwd,
+            None,
         );
 
         // Build the expected policy text using a raw string for readability.
@@ -233,12 +296,15 @@ mod tests {
             network_access: false,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            network_allowlist: Vec::new(),
+            deny: Vec::new(),
         };
 
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             root_with_git.as_path(),
+            None,
         );
 
         let tmpdir_env_var = std::env::var("TMPDIR")
@@ -301,6 +367,29 @@ mod tests {
         assert_eq!(expected_args, args);
     }
 
+    #[test]
+    fn create_seatbelt_args_only_allows_network_proxy() {
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            network_allowlist: vec!["crates.io".to_string()],
+            deny: Vec::new(),
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string()],
+            &policy,
+            Path::new("/"),
+            Some(4242),
+        );
+
+        let full_policy = &args[1];
+        assert!(full_policy.contains(r#"(allow network-outbound (remote ip "localhost:4242"))"#));
+        assert!(!full_policy.contains("(allow network-inbound)"));
+    }
+
     struct PopulatedTmp {
         root_with_git: PathBuf,
         root_without_git: PathBuf,
@@ -330,4 +419,16 @@ mod tests {
             root_without_git_canon,
         }
     }
+
+    #[test]
+    fn deny_patterns_become_regexes() {
+        assert_eq!(
+            read_only_pattern_regex(Path::new("/Users/me/my.repo"), "*.pem"),
+            r"^/Users/me/my\.repo/(.*/)?[^/]*\.pem(/.*)?$"
+        );
+        assert_eq!(
+            read_only_pattern_regex(Path::new("/repo"), "config/?.env"),
+            r"^/repo/config/[^/]\.env(/.*)?$"
+        );
+    }
 }
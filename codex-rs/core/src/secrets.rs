@@ -0,0 +1,80 @@
+//! Secrets the user supplies on request of the agent, e.g. `NPM_TOKEN`.
+//!
+//! Values are only placed in the environment of the command that asked for
+//! them. They are redacted from the command's output before it is returned to
+//! the model, so they never reach the model context or the rollout.
+
+use std::collections::HashMap;
+
+use crate::protocol::SecretValue;
+
+/// Secrets shorter than this are not redacted: replacing every occurrence of
+/// e.g. `1` in the output would make it useless and protects nothing.
+const MIN_REDACTED_SECRET_LEN: usize = 4;
+
+/// Secrets are passed as environment variables, so their names must be valid
+/// variable names.
+pub(crate) fn validate_secret_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid secret name `{name}`: secrets are passed as environment variables, so names must match [A-Za-z_][A-Za-z0-9_]*"
+        ))
+    }
+}
+
+/// Replaces every occurrence of a secret value in `text` with a placeholder
+/// naming the secret.
+pub(crate) fn redact_secrets(text: &str, secrets: &HashMap<String, SecretValue>) -> String {
+    let mut secrets: Vec<(&String, &str)> = secrets
+        .iter()
+        .map(|(name, value)| (name, value.expose()))
+        .filter(|(_, value)| value.len() >= MIN_REDACTED_SECRET_LEN)
+        .collect();
+    // Longer values first, in case one secret contains another.
+    secrets.sort_by(|(_, a), (_, b)| b.len().cmp(&a.len()));
+
+    let mut text = text.to_string();
+    for (name, value) in secrets {
+        if text.contains(value) {
+            text = text.replace(value, &format!("[redacted secret {name}]"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn secret_names_must_be_environment_variable_names() {
+        assert_eq!(validate_secret_name("NPM_TOKEN"), Ok(()));
+        assert_eq!(validate_secret_name("_private2"), Ok(()));
+        assert!(validate_secret_name("").is_err());
+        assert!(validate_secret_name("2FA").is_err());
+        assert!(validate_secret_name("NPM TOKEN").is_err());
+    }
+
+    #[test]
+    fn output_is_redacted() {
+        let secrets = HashMap::from([
+            (
+                "NPM_TOKEN".to_string(),
+                SecretValue::new("npm_abc123".to_string()),
+            ),
+            ("PIN".to_string(), SecretValue::new("12".to_string())),
+        ]);
+        assert_eq!(
+            redact_secrets("token=npm_abc123 after 12 retries", &secrets),
+            "token=[redacted secret NPM_TOKEN] after 12 retries"
+        );
+    }
+}
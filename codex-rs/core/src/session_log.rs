@@ -0,0 +1,352 @@
+//! Structured logs of each session, for finding out afterwards what happened
+//! in a conversation.
+//!
+//! Every `tracing` event recorded while a session runs is appended as a JSON
+//! line to `CODEX_HOME/log/sessions/<conversation id>.jsonl`, with its
+//! timestamp, level, target, message and fields. An event belongs to the
+//! session whose [`SESSION_SPAN`] it is recorded in; `Codex::spawn` runs each
+//! session inside one. Programs add [`layer`] to their subscriber to write the
+//! logs. The level is process wide and can be changed while running, see
+//! [`set_log_level`].
+//!
+//! A log that grows past [`MAX_FILE_BYTES`] is moved to
+//! `<conversation id>.1.jsonl`, the previous one to `.2.jsonl`, and so on up
+//! to [`MAX_ROTATED_FILES`]. Logs not written to for [`MAX_AGE`] are deleted
+//! when a program installs the layer.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use chrono::SecondsFormat;
+use chrono::Utc;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::mcp_protocol::LogLevel;
+use serde_json::Map;
+use serde_json::Value;
+use tracing::Event;
+use tracing::Level;
+use tracing::Metadata;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::FilterFn;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Size at which a session log is rotated.
+pub const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Rotated logs kept per session.
+pub const MAX_ROTATED_FILES: usize = 3;
+
+/// Age after which a log that is no longer written to is deleted.
+pub const MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Name of the span a session runs in. Its `conversation_id` field names the
+/// log the events inside it go to.
+pub(crate) const SESSION_SPAN: &str = "session";
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// Level of the session logs.
+pub fn log_level() -> LogLevel {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => LogLevel::Error,
+        1 => LogLevel::Warn,
+        2 => LogLevel::Info,
+        3 => LogLevel::Debug,
+        _ => LogLevel::Trace,
+    }
+}
+
+/// Changes the level of the session logs for the rest of the process.
+pub fn set_log_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    // Filters cache which callsites they are interested in.
+    tracing::callsite::rebuild_interest_cache();
+}
+
+/// Directory the session logs are written to.
+pub fn sessions_log_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join("log").join("sessions")
+}
+
+/// File the log of `conversation_id` is written to.
+pub fn session_log_path(codex_home: &Path, conversation_id: ConversationId) -> PathBuf {
+    sessions_log_dir(codex_home).join(format!("{conversation_id}.jsonl"))
+}
+
+/// The layer writing session logs under `codex_home`. Deletes the logs that
+/// are older than [`MAX_AGE`] first.
+pub fn layer<S>(codex_home: &Path) -> io::Result<impl Layer<S> + use<S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let dir = sessions_log_dir(codex_home);
+    fs::create_dir_all(&dir)?;
+    remove_old_logs(&dir, SystemTime::now());
+    let layer = SessionLogLayer {
+        dir,
+        files: Mutex::new(HashMap::new()),
+    };
+    Ok(layer.with_filter(FilterFn::new(is_recorded)))
+}
+
+/// Session spans, and events at or above the current level.
+fn is_recorded(metadata: &Metadata<'_>) -> bool {
+    if metadata.is_span() {
+        return metadata.name() == SESSION_SPAN && metadata.target().starts_with("codex_core");
+    }
+    *metadata.level() <= tracing_level(log_level())
+}
+
+fn tracing_level(level: LogLevel) -> Level {
+    match level {
+        LogLevel::Error => Level::ERROR,
+        LogLevel::Warn => Level::WARN,
+        LogLevel::Info => Level::INFO,
+        LogLevel::Debug => Level::DEBUG,
+        LogLevel::Trace => Level::TRACE,
+    }
+}
+
+struct SessionLogLayer {
+    dir: PathBuf,
+    /// Open logs by conversation id.
+    files: Mutex<HashMap<String, LogFile>>,
+}
+
+struct LogFile {
+    file: File,
+    len: u64,
+}
+
+/// Conversation id of a session span, kept in its extensions.
+struct SessionId(String);
+
+impl<S> Layer<S> for SessionLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != SESSION_SPAN {
+            return;
+        }
+        let mut fields = JsonFields::default();
+        attrs.record(&mut fields);
+        if let Some(Value::String(conversation_id)) = fields.0.remove("conversation_id")
+            && let Some(span) = ctx.span(id)
+        {
+            span.extensions_mut().insert(SessionId(conversation_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(conversation_id) = ctx.event_scope(event).and_then(|scope| {
+            scope
+                .into_iter()
+                .find_map(|span| span.extensions().get::<SessionId>().map(|id| id.0.clone()))
+        }) else {
+            return;
+        };
+
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        let mut entry = Map::new();
+        entry.insert(
+            "timestamp".to_string(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+                .into(),
+        );
+        entry.insert("level".to_string(), metadata.level().as_str().into());
+        entry.insert("target".to_string(), metadata.target().into());
+        if let Some(message) = fields.0.remove("message") {
+            entry.insert("message".to_string(), message);
+        }
+        if !fields.0.is_empty() {
+            entry.insert("fields".to_string(), Value::Object(fields.0));
+        }
+        let mut line = Value::Object(entry).to_string();
+        line.push('\n');
+        // Errors are dropped: logging them here would come right back.
+        self.append(&conversation_id, line.as_bytes());
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id)
+            && let Some(SessionId(conversation_id)) = span.extensions().get::<SessionId>()
+        {
+            self.files
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(conversation_id);
+        }
+    }
+}
+
+impl SessionLogLayer {
+    fn append(&self, conversation_id: &str, line: &[u8]) {
+        let mut files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        let full = files
+            .get(conversation_id)
+            .is_some_and(|log| log.len + line.len() as u64 > MAX_FILE_BYTES);
+        if full {
+            files.remove(conversation_id);
+            rotate(&self.dir, conversation_id);
+        }
+        let log = match files.entry(conversation_id.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let Ok(log) = open_log(&self.dir.join(format!("{conversation_id}.jsonl"))) else {
+                    return;
+                };
+                entry.insert(log)
+            }
+        };
+        if log.file.write_all(line).is_ok() {
+            log.len += line.len() as u64;
+        }
+    }
+}
+
+fn open_log(path: &Path) -> io::Result<LogFile> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    // Logs may quote prompts and command output.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)?;
+    let len = file.metadata()?.len();
+    Ok(LogFile { file, len })
+}
+
+/// Moves `<id>.jsonl` to `<id>.1.jsonl`, `<id>.1.jsonl` to `<id>.2.jsonl` and
+/// so on, dropping the oldest.
+fn rotate(dir: &Path, conversation_id: &str) {
+    let path = |n: usize| match n {
+        0 => dir.join(format!("{conversation_id}.jsonl")),
+        n => dir.join(format!("{conversation_id}.{n}.jsonl")),
+    };
+    let _ = fs::remove_file(path(MAX_ROTATED_FILES));
+    for n in (0..MAX_ROTATED_FILES).rev() {
+        let _ = fs::rename(path(n), path(n + 1));
+    }
+}
+
+fn remove_old_logs(dir: &Path, now: SystemTime) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified + MAX_AGE < now);
+        if expired && path.extension().is_some_and(|ext| ext == "jsonl") {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// The fields of a span or event as JSON values.
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn events_go_to_the_log_of_their_session() {
+        let home = TempDir::new().expect("tempdir");
+        let subscriber = tracing_subscriber::registry().with(layer(home.path()).expect("layer"));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!(SESSION_SPAN, conversation_id = "abc").in_scope(|| {
+                tracing::info!(turns = 2, "hello");
+                tracing::trace!("too detailed");
+            });
+            tracing::info!("outside of any session");
+        });
+
+        let log =
+            fs::read_to_string(sessions_log_dir(home.path()).join("abc.jsonl")).expect("read log");
+        let lines: Vec<Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "hello");
+        assert_eq!(lines[0]["fields"]["turns"], 2);
+    }
+
+    #[test]
+    fn rotation_shifts_older_logs() {
+        let dir = TempDir::new().expect("tempdir");
+        fs::write(dir.path().join("abc.jsonl"), "current").expect("write");
+        fs::write(dir.path().join("abc.1.jsonl"), "previous").expect("write");
+
+        rotate(dir.path(), "abc");
+
+        assert!(!dir.path().join("abc.jsonl").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("abc.1.jsonl")).expect("read"),
+            "current"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("abc.2.jsonl")).expect("read"),
+            "previous"
+        );
+    }
+}
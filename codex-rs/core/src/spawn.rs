@@ -5,6 +5,7 @@ use tokio::process::Child;
 use tokio::process::Command;
 use tracing::trace;
 
+use crate::config_types::ResourceLimits;
 use crate::protocol::SandboxPolicy;
 
 /// Experimental environment variable that will be set to some non-empty value
@@ -26,6 +27,11 @@ pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 pub enum StdioPolicy {
     RedirectForShellTool,
     Inherit,
+    /// Connects stdin, stdout, and stderr to this pseudo-terminal slave and
+    /// makes it the child's controlling terminal. The caller keeps the fd
+    /// open until the spawn returns.
+    #[cfg(unix)]
+    Pty(std::os::fd::RawFd),
 }
 
 /// Spawns the appropriate child process for the ExecParams and SandboxPolicy,
@@ -35,17 +41,19 @@ pub enum StdioPolicy {
 /// For now, we take `SandboxPolicy` as a parameter to spawn_child() because
 /// we need to determine whether to set the
 /// `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn spawn_child_async(
     program: PathBuf,
     args: Vec<String>,
     #[cfg_attr(not(unix), allow(unused_variables))] arg0: Option<&str>,
     cwd: PathBuf,
     sandbox_policy: &SandboxPolicy,
+    resource_limits: &ResourceLimits,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
     trace!(
-        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
+        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {resource_limits:?} {stdio_policy:?} {env:?}"
     );
 
     let mut cmd = Command::new(&program);
@@ -85,6 +93,14 @@ pub(crate) async fn spawn_child_async(
         });
     }
 
+    #[cfg(unix)]
+    if *resource_limits != ResourceLimits::default() {
+        let resource_limits = *resource_limits;
+        unsafe {
+            cmd.pre_exec(move || apply_resource_limits(&resource_limits));
+        }
+    }
+
     match stdio_policy {
         StdioPolicy::RedirectForShellTool => {
             // Do not create a file descriptor for stdin because otherwise some
@@ -101,7 +117,59 @@ pub(crate) async fn spawn_child_async(
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit());
         }
+        #[cfg(unix)]
+        StdioPolicy::Pty(slave) => {
+            // SAFETY: the caller keeps `slave` open for the duration of this call.
+            let slave = unsafe { std::os::fd::BorrowedFd::borrow_raw(slave) };
+            cmd.stdin(Stdio::from(slave.try_clone_to_owned()?))
+                .stdout(Stdio::from(slave.try_clone_to_owned()?))
+                .stderr(Stdio::from(slave.try_clone_to_owned()?));
+            unsafe {
+                cmd.pre_exec(|| {
+                    // Interactive programs need a controlling terminal for job
+                    // control and to read passwords, which takes a new session.
+                    if libc::setsid() == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
     }
 
     cmd.kill_on_drop(true).spawn()
 }
+
+/// Lowers the rlimits of the current process to `limits`. Runs between fork
+/// and exec, so it only makes async-signal-safe calls. Limits above the
+/// current hard limit are clamped to it, since raising it needs privileges.
+#[cfg(unix)]
+fn apply_resource_limits(limits: &ResourceLimits) -> std::io::Result<()> {
+    let set_limit = |resource, value: Option<u64>| -> std::io::Result<()> {
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::getrlimit(resource, &mut limit) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let value = value.min(limit.rlim_max);
+        limit.rlim_cur = value;
+        limit.rlim_max = value;
+        if unsafe { libc::setrlimit(resource, &limit) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    };
+
+    set_limit(libc::RLIMIT_CPU, limits.cpu_time_secs)?;
+    set_limit(libc::RLIMIT_AS, limits.memory_bytes)?;
+    set_limit(libc::RLIMIT_NPROC, limits.max_processes)?;
+    Ok(())
+}
@@ -3,7 +3,10 @@ mod session;
 mod turn;
 
 pub(crate) use service::SessionServices;
+pub(crate) use session::Checkpoint;
+pub(crate) use session::QueuedInput;
 pub(crate) use session::SessionState;
 pub(crate) use turn::ActiveTurn;
+pub(crate) use turn::PatchEdit;
 pub(crate) use turn::RunningTask;
 pub(crate) use turn::TaskKind;
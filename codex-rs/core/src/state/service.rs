@@ -1,9 +1,25 @@
 use crate::RolloutRecorder;
+use crate::auto_commit::AutoCommitter;
+use crate::background_jobs::BackgroundJobManager;
+use crate::change_journal::ChangeJournal;
+use crate::config_types::ContextPruningStrategy;
+use crate::config_types::PostPatchHook;
+use crate::config_types::SandboxRule;
+use crate::custom_tools::CustomTools;
 use crate::exec_command::ExecSessionManager;
+use crate::lifecycle_hooks::LifecycleHooks;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::output_overflow::OutputOverflowStore;
+use crate::plugins::PluginHost;
+use crate::protocol::PatchRiskLevel;
 use crate::unified_exec::UnifiedExecSessionManager;
+use crate::url_attachments::UrlAttachments;
 use crate::user_notification::UserNotifier;
+use crate::web_search::WebSearch;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 pub(crate) struct SessionServices {
@@ -15,4 +31,36 @@ pub(crate) struct SessionServices {
     pub(crate) codex_linux_sandbox_exe: Option<PathBuf>,
     pub(crate) user_shell: crate::shell::Shell,
     pub(crate) show_raw_agent_reasoning: bool,
+    pub(crate) context_pruning: ContextPruningStrategy,
+    pub(crate) escalate_patch_risk: Option<PatchRiskLevel>,
+    pub(crate) sandbox_rules: Vec<SandboxRule>,
+    /// `CODEX_HOME` when the sandbox audit log is enabled.
+    pub(crate) sandbox_audit: Option<PathBuf>,
+    pub(crate) url_attachments: UrlAttachments,
+    pub(crate) web_search: WebSearch,
+    pub(crate) plugins: PluginHost,
+    pub(crate) custom_tools: CustomTools,
+    pub(crate) background_jobs: BackgroundJobManager,
+    /// Full output of commands that was truncated for the model.
+    pub(crate) output_overflow: Arc<OutputOverflowStore>,
+    /// Estimated spend in US dollars at which tasks stop sending requests.
+    pub(crate) session_budget_usd: Option<f64>,
+    /// Model requests after which a task stops.
+    pub(crate) max_turns: Option<u32>,
+    /// Time after which a task stops sending requests.
+    pub(crate) max_runtime: Option<Duration>,
+    /// Whether the model is offered the `report_failure` tool.
+    pub(crate) report_failure_tool: bool,
+    /// Most read-only tool calls from one response that run at once.
+    pub(crate) max_parallel_tool_calls: usize,
+    /// Commits each applied patch, when `auto_commit` is enabled.
+    pub(crate) auto_commit: Option<AutoCommitter>,
+    /// Every file change applied in the session, for rolling turns back.
+    pub(crate) change_journal: ChangeJournal,
+    /// Formatters and linters run on the files of each applied patch.
+    pub(crate) post_patch_hooks: BTreeMap<String, PostPatchHook>,
+    /// Commands run on session, turn, tool call and approval events.
+    pub(crate) lifecycle_hooks: LifecycleHooks,
+    /// `CODEX_HOME` when project memory is enabled.
+    pub(crate) memory: Option<PathBuf>,
 }
@@ -1,11 +1,20 @@
 //! Session-wide mutable state.
 
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::path::PathBuf;
 
 use codex_protocol::models::ResponseItem;
 
+use crate::change_journal::JournalMark;
 use crate::conversation_history::ConversationHistory;
+use crate::cost::TokenPrices;
+use crate::protocol::InputItem;
+use crate::protocol::QueuedUserInput;
 use crate::protocol::RateLimitSnapshot;
+use crate::protocol::SecretValue;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 
@@ -16,6 +25,53 @@ pub(crate) struct SessionState {
     pub(crate) history: ConversationHistory,
     pub(crate) token_info: Option<TokenUsageInfo>,
     pub(crate) latest_rate_limits: Option<RateLimitSnapshot>,
+    /// Locally counted input tokens of the latest model request.
+    pub(crate) estimated_prompt_tokens: Option<u64>,
+    /// Secrets the user supplied, by name. Kept in memory only.
+    pub(crate) secrets: HashMap<String, SecretValue>,
+    /// Tokens MCP servers have used through sampling.
+    pub(crate) mcp_sampling_tokens: u64,
+    /// MCP servers the user let sample the model for the rest of the session.
+    pub(crate) mcp_sampling_servers: HashSet<String>,
+    /// User input waiting for the running task to finish, oldest first.
+    pub(crate) queued_input: VecDeque<QueuedInput>,
+    next_queued_input_id: u64,
+    /// Checkpoints created with `Op::CreateCheckpoint`, by name.
+    pub(crate) checkpoints: HashMap<String, Checkpoint>,
+    /// Files the session's patches changed, which `Op::CreatePullRequest`
+    /// commits.
+    pub(crate) changed_paths: BTreeSet<PathBuf>,
+}
+
+/// The conversation and file changes of the session at a checkpoint.
+#[derive(Clone)]
+pub(crate) struct Checkpoint {
+    pub(crate) history: Vec<ResponseItem>,
+    pub(crate) journal_mark: JournalMark,
+}
+
+/// User input queued with `Op::QueueUserInput`.
+pub(crate) struct QueuedInput {
+    pub(crate) id: u64,
+    /// The submission that queued the input; the turn it starts reports its
+    /// events under this id.
+    pub(crate) sub_id: String,
+    pub(crate) items: Vec<InputItem>,
+}
+
+impl QueuedInput {
+    pub(crate) fn to_protocol(&self) -> QueuedUserInput {
+        let text = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                InputItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        QueuedUserInput { id: self.id, text }
+    }
 }
 
 impl SessionState {
@@ -53,28 +109,86 @@ impl SessionState {
         &self.approved_commands
     }
 
+    // Secret helpers
+    pub(crate) fn secret(&self, name: &str) -> Option<SecretValue> {
+        self.secrets.get(name).cloned()
+    }
+
+    pub(crate) fn add_secret(&mut self, name: String, value: SecretValue) {
+        self.secrets.insert(name, value);
+    }
+
     // Token/rate limit helpers
     pub(crate) fn update_token_info_from_usage(
         &mut self,
         usage: &TokenUsage,
         model_context_window: Option<u64>,
+        prices: Option<TokenPrices>,
     ) {
         self.token_info = TokenUsageInfo::new_or_append(
             &self.token_info,
             &Some(usage.clone()),
             model_context_window,
         );
+        // Usage of models without prices adds nothing, so the estimate
+        // covers only the priced part of the session.
+        if let Some(prices) = prices
+            && let Some(info) = &mut self.token_info
+        {
+            info.estimated_cost_usd =
+                Some(info.estimated_cost_usd.unwrap_or_default() + prices.cost(usage));
+        }
+    }
+
+    pub(crate) fn estimated_cost_usd(&self) -> Option<f64> {
+        self.token_info
+            .as_ref()
+            .and_then(|info| info.estimated_cost_usd)
     }
 
     pub(crate) fn set_rate_limits(&mut self, snapshot: RateLimitSnapshot) {
         self.latest_rate_limits = Some(snapshot);
     }
 
+    pub(crate) fn set_estimated_prompt_tokens(&mut self, tokens: u64) {
+        self.estimated_prompt_tokens = Some(tokens);
+    }
+
     pub(crate) fn token_info_and_rate_limits(
         &self,
     ) -> (Option<TokenUsageInfo>, Option<RateLimitSnapshot>) {
         (self.token_info.clone(), self.latest_rate_limits.clone())
     }
 
+    // Queued input helpers
+    pub(crate) fn queue_input(&mut self, sub_id: String, items: Vec<InputItem>) {
+        self.next_queued_input_id += 1;
+        self.queued_input.push_back(QueuedInput {
+            id: self.next_queued_input_id,
+            sub_id,
+            items,
+        });
+    }
+
+    pub(crate) fn remove_queued_input(&mut self, id: u64) -> Option<QueuedInput> {
+        let idx = self.queued_input.iter().position(|input| input.id == id)?;
+        self.queued_input.remove(idx)
+    }
+
+    /// Drops the input queued by `submission_ids`. Returns whether any was.
+    pub(crate) fn remove_queued_submissions(&mut self, submission_ids: &[String]) -> bool {
+        let before = self.queued_input.len();
+        self.queued_input
+            .retain(|input| !submission_ids.contains(&input.sub_id));
+        self.queued_input.len() != before
+    }
+
+    pub(crate) fn queued_input_snapshot(&self) -> Vec<QueuedUserInput> {
+        self.queued_input
+            .iter()
+            .map(QueuedInput::to_protocol)
+            .collect()
+    }
+
     // Pending input/approval moved to TurnState.
 }
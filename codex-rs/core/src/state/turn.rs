@@ -10,6 +10,7 @@ use codex_protocol::models::ResponseInputItem;
 use tokio::sync::oneshot;
 
 use crate::protocol::ReviewDecision;
+use crate::protocol::SecretValue;
 use crate::tasks::SessionTask;
 
 /// Metadata about the currently running turn.
@@ -60,7 +61,20 @@ impl ActiveTurn {
 #[derive(Default)]
 pub(crate) struct TurnState {
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    pending_secrets: HashMap<String, oneshot::Sender<Option<SecretValue>>>,
     pending_input: Vec<ResponseInputItem>,
+    patch_edits: HashMap<String, PatchEdit>,
+    /// Why the model said it could not complete the task, with the
+    /// `report_failure` tool.
+    reported_failure: Option<String>,
+}
+
+/// A patch the user edited before approving it, to be applied instead of the
+/// one the model proposed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PatchEdit {
+    pub(crate) patch: String,
+    pub(crate) note: Option<String>,
 }
 
 impl TurnState {
@@ -79,9 +93,42 @@ impl TurnState {
         self.pending_approvals.remove(key)
     }
 
+    pub(crate) fn insert_pending_secret(
+        &mut self,
+        key: String,
+        tx: oneshot::Sender<Option<SecretValue>>,
+    ) -> Option<oneshot::Sender<Option<SecretValue>>> {
+        self.pending_secrets.insert(key, tx)
+    }
+
+    pub(crate) fn remove_pending_secret(
+        &mut self,
+        key: &str,
+    ) -> Option<oneshot::Sender<Option<SecretValue>>> {
+        self.pending_secrets.remove(key)
+    }
+
+    pub(crate) fn insert_patch_edit(&mut self, key: String, edit: PatchEdit) {
+        self.patch_edits.insert(key, edit);
+    }
+
+    pub(crate) fn take_patch_edit(&mut self, key: &str) -> Option<PatchEdit> {
+        self.patch_edits.remove(key)
+    }
+
+    pub(crate) fn report_failure(&mut self, reason: String) {
+        self.reported_failure = Some(reason);
+    }
+
+    pub(crate) fn take_reported_failure(&mut self) -> Option<String> {
+        self.reported_failure.take()
+    }
+
     pub(crate) fn clear_pending(&mut self) {
         self.pending_approvals.clear();
+        self.pending_secrets.clear();
         self.pending_input.clear();
+        self.patch_edits.clear();
     }
 
     pub(crate) fn push_pending_input(&mut self, input: ResponseInputItem) {
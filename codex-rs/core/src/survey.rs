@@ -0,0 +1,146 @@
+//! Repository surveys produced by `codex survey`.
+//!
+//! A survey is a Markdown architecture overview of a repository written by the
+//! agent under a bounded token budget. It is stored per repository under
+//! `CODEX_HOME/artifacts/surveys/` and, once present, is appended to the user
+//! instructions of later sessions started in the same repository so they do
+//! not have to rediscover the layout.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::git_info::project_key;
+
+/// Instructions sent as the user turn of a survey.
+pub const SURVEY_PROMPT: &str = include_str!("../templates/survey/prompt.md");
+
+/// Sent mid-task once the survey's token budget is spent.
+pub const SURVEY_WRAP_UP_PROMPT: &str = include_str!("../templates/survey/wrap_up.md");
+
+/// Default token budget for `codex survey`.
+pub const DEFAULT_SURVEY_TOKEN_BUDGET: u64 = 150_000;
+
+/// Once the wrap-up prompt has been sent, the survey may overrun its budget by
+/// this percentage while the model writes the document before it is stopped.
+const WRAP_UP_ALLOWANCE_PERCENT: u64 = 25;
+
+/// Surveys larger than this are truncated when used as session context.
+const SURVEY_CONTEXT_MAX_BYTES: usize = 32 * 1024;
+
+/// Build the survey prompt, optionally narrowing it to `focus`.
+pub fn build_survey_prompt(focus: Option<&str>) -> String {
+    match focus.map(str::trim).filter(|focus| !focus.is_empty()) {
+        Some(focus) => format!("{SURVEY_PROMPT}\nPay particular attention to: {focus}\n"),
+        None => SURVEY_PROMPT.to_string(),
+    }
+}
+
+/// What the survey runner should do after observing token usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurveyBudgetAction {
+    /// Ask the model to stop exploring and write the overview.
+    WrapUp,
+    /// The wrap-up allowance is exhausted too; interrupt the task.
+    Stop,
+}
+
+/// Tracks token usage of a survey against its budget.
+#[derive(Debug)]
+pub struct SurveyBudget {
+    max_tokens: u64,
+    wrap_up_sent: bool,
+    stopped: bool,
+}
+
+impl SurveyBudget {
+    pub fn new(max_tokens: u64) -> Self {
+        Self {
+            max_tokens,
+            wrap_up_sent: false,
+            stopped: false,
+        }
+    }
+
+    /// Record the blended total of tokens used so far. Each action is returned
+    /// at most once.
+    pub fn observe(&mut self, tokens_used: u64) -> Option<SurveyBudgetAction> {
+        let hard_limit = self
+            .max_tokens
+            .saturating_add(self.max_tokens / 100 * WRAP_UP_ALLOWANCE_PERCENT);
+        if !self.stopped && self.wrap_up_sent && tokens_used >= hard_limit {
+            self.stopped = true;
+            Some(SurveyBudgetAction::Stop)
+        } else if !self.wrap_up_sent && tokens_used >= self.max_tokens {
+            self.wrap_up_sent = true;
+            Some(SurveyBudgetAction::WrapUp)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where the survey for the repository containing `cwd` is stored. Outside a
+/// Git repository, `cwd` itself identifies the survey.
+pub fn survey_path(codex_home: &Path, cwd: &Path) -> PathBuf {
+    codex_home
+        .join("artifacts")
+        .join("surveys")
+        .join(format!("{}.md", project_key(cwd)))
+}
+
+/// Read the stored survey for `cwd`, if any, truncated to a size suitable for
+/// inclusion in the instructions.
+pub(crate) async fn read_survey(codex_home: &Path, cwd: &Path) -> Option<String> {
+    let contents = tokio::fs::read_to_string(survey_path(codex_home, cwd))
+        .await
+        .ok()?;
+    let contents = contents.trim();
+    if contents.is_empty() {
+        return None;
+    }
+    let mut end = contents.len().min(SURVEY_CONTEXT_MAX_BYTES);
+    while !contents.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(contents[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn survey_path_is_keyed_by_repo_root() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        std::fs::create_dir(repo.path().join(".git")).unwrap();
+        std::fs::create_dir_all(repo.path().join("src/nested")).unwrap();
+
+        assert_eq!(
+            survey_path(codex_home.path(), &repo.path().join("src/nested")),
+            survey_path(codex_home.path(), repo.path()),
+        );
+        assert!(
+            survey_path(codex_home.path(), repo.path())
+                .starts_with(codex_home.path().join("artifacts").join("surveys"))
+        );
+    }
+
+    #[test]
+    fn budget_wraps_up_then_stops() {
+        let mut budget = SurveyBudget::new(1_000);
+        assert_eq!(budget.observe(999), None);
+        assert_eq!(budget.observe(1_000), Some(SurveyBudgetAction::WrapUp));
+        assert_eq!(budget.observe(1_100), None);
+        assert_eq!(budget.observe(1_250), Some(SurveyBudgetAction::Stop));
+        assert_eq!(budget.observe(2_000), None);
+    }
+
+    #[test]
+    fn focus_is_appended_to_prompt() {
+        assert_eq!(build_survey_prompt(Some("  ")), SURVEY_PROMPT);
+        assert!(build_survey_prompt(Some("the storage layer")).ends_with("the storage layer\n"));
+    }
+}
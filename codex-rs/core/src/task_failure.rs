@@ -0,0 +1,49 @@
+//! The `report_failure` tool, with which the model declares that it could not
+//! complete the task.
+//!
+//! Whether a task succeeded is up to the model: a failed command may be
+//! expected, or worked around later in the turn. `codex exec` offers this tool
+//! so that automation can tell a finished task from an abandoned one; the
+//! reason is reported in the `failure` of the turn's `TaskComplete` event.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const REPORT_FAILURE_TOOL_NAME: &str = "report_failure";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReportFailureArgs {
+    pub(crate) reason: String,
+}
+
+pub(crate) fn create_report_failure_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "reason".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Why the task could not be completed, e.g. `The tests need a database that is not running`"
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: REPORT_FAILURE_TOOL_NAME.to_string(),
+        description: "Reports that you could not complete the task, so that the automation \
+                      running you treats it as failed. Call it once, before your final message, \
+                      only when you are giving up; do not call it for errors you worked around."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["reason".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::codex::TurnContext;
+use crate::codex::run_background_response_task;
+use crate::protocol::InputItem;
+use crate::state::TaskKind;
+
+use super::SessionTask;
+use super::SessionTaskContext;
+
+/// Picks up a background response that is still running on the provider.
+#[derive(Clone)]
+pub(crate) struct BackgroundResponseTask {
+    pub(crate) response_id: String,
+}
+
+#[async_trait]
+impl SessionTask for BackgroundResponseTask {
+    fn kind(&self) -> TaskKind {
+        TaskKind::Regular
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        session: Arc<SessionTaskContext>,
+        ctx: Arc<TurnContext>,
+        sub_id: String,
+        _input: Vec<InputItem>,
+    ) -> Option<String> {
+        let sess = session.clone_session();
+        run_background_response_task(sess, ctx, sub_id, self.response_id.clone()).await
+    }
+}
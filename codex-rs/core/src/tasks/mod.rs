@@ -1,3 +1,4 @@
+mod background;
 mod compact;
 mod regular;
 mod review;
@@ -5,10 +6,12 @@ mod review;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use tracing::Instrument;
 use tracing::trace;
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::lifecycle_hooks::HookEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::InputItem;
@@ -19,6 +22,7 @@ use crate::state::ActiveTurn;
 use crate::state::RunningTask;
 use crate::state::TaskKind;
 
+pub(crate) use background::BackgroundResponseTask;
 pub(crate) use compact::CompactTask;
 pub(crate) use regular::RegularTask;
 pub(crate) use review::ReviewTask;
@@ -74,14 +78,25 @@ impl Session {
             let ctx = Arc::clone(&turn_context);
             let task_for_run = Arc::clone(&task);
             let sub_clone = sub_id.clone();
-            tokio::spawn(async move {
-                let last_agent_message = task_for_run
-                    .run(Arc::clone(&session_ctx), ctx, sub_clone.clone(), input)
+            tokio::spawn(
+                async move {
+                    let sess = session_ctx.clone_session();
+                    sess.notify_hooks(
+                        &sub_clone,
+                        HookEvent::TurnStart {
+                            turn_id: &sub_clone,
+                        },
+                    )
                     .await;
-                // Emit completion uniformly from spawn site so all tasks share the same lifecycle.
-                let sess = session_ctx.clone_session();
-                sess.on_task_finished(sub_clone, last_agent_message).await;
-            })
+                    let last_agent_message = task_for_run
+                        .run(Arc::clone(&session_ctx), ctx, sub_clone.clone(), input)
+                        .await;
+                    // Emit completion uniformly from spawn site so all tasks share the same lifecycle.
+                    sess.on_task_finished(sub_clone, last_agent_message).await;
+                }
+                // Keep the task in the session's span, see `crate::session_log`.
+                .in_current_span(),
+            )
             .abort_handle()
         };
 
@@ -105,17 +120,36 @@ impl Session {
         last_agent_message: Option<String>,
     ) {
         let mut active = self.active_turn.lock().await;
-        if let Some(at) = active.as_mut()
+        let mut failure = None;
+        let turn_ended = if let Some(at) = active.as_mut()
             && at.remove_task(&sub_id)
         {
+            failure = at.turn_state.lock().await.take_reported_failure();
             *active = None;
-        }
+            true
+        } else {
+            false
+        };
         drop(active);
+        self.notify_hooks(
+            &sub_id,
+            HookEvent::TurnEnd {
+                turn_id: &sub_id,
+                last_agent_message: last_agent_message.as_deref(),
+            },
+        )
+        .await;
         let event = Event {
             id: sub_id,
-            msg: EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
+            msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message,
+                failure,
+            }),
         };
         self.send_event(event).await;
+        if turn_ended {
+            self.wake_queued_input();
+        }
     }
 
     async fn register_new_active_task(&self, sub_id: String, task: RunningTask) {
@@ -0,0 +1,1089 @@
+//! Summary of what Codex did over a period, for sharing with a team: the
+//! sessions that were active, the patches they applied, the commands and
+//! tests they ran, the tokens they used and why tool calls failed.
+//!
+//! The report is built from the session rollouts under `~/.codex/sessions`
+//! (and `~/.codex/archived_sessions`). Only items recorded after `since`
+//! count, so a long session that started earlier contributes just its recent
+//! work.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::TokenUsage;
+use codex_protocol::protocol::TurnAbortReason;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+pub use crate::cost::TokenPrices;
+use crate::rollout::ARCHIVED_SESSIONS_SUBDIR;
+use crate::rollout::SESSIONS_SUBDIR;
+
+/// Exit code Codex reports for a command that ran out of time.
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Commands that run a test suite. A command counts as a test run when one of
+/// these appears in it as whole words.
+const TEST_COMMANDS: &[&str] = &[
+    "cargo test",
+    "cargo nextest",
+    "pytest",
+    "go test",
+    "npm test",
+    "npm run test",
+    "yarn test",
+    "pnpm test",
+    "bun test",
+    "jest",
+    "vitest",
+    "mocha",
+    "rspec",
+    "mvn test",
+    "gradle test",
+    "./gradlew test",
+    "make test",
+    "ctest",
+    "tox",
+    "phpunit",
+    "mix test",
+    "dotnet test",
+    "swift test",
+    "bazel test",
+];
+
+/// Longest task or outcome text shown for a session in the Markdown report.
+const MAX_SUMMARY_CHARS: usize = 160;
+
+/// Why a tool call did not do what the agent asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The command ran and exited with a non-zero code.
+    CommandFailed,
+    /// The command ran out of time.
+    TimedOut,
+    /// The sandbox kept the command from doing its work.
+    SandboxDenied,
+    /// A patch did not apply.
+    PatchFailed,
+    /// The user declined the command or patch.
+    RejectedByUser,
+    /// The approval policy or a sandbox rule refused the command or patch.
+    RejectedByPolicy,
+    /// The model called a tool with arguments that could not be parsed.
+    InvalidToolCall,
+    /// The user interrupted a turn.
+    Interrupted,
+}
+
+impl FailureCategory {
+    fn label(self) -> &'static str {
+        match self {
+            FailureCategory::CommandFailed => "Command exited with an error",
+            FailureCategory::TimedOut => "Command timed out",
+            FailureCategory::SandboxDenied => "Blocked by the sandbox",
+            FailureCategory::PatchFailed => "Patch did not apply",
+            FailureCategory::RejectedByUser => "Rejected by the user",
+            FailureCategory::RejectedByPolicy => "Rejected by policy",
+            FailureCategory::InvalidToolCall => "Invalid tool call",
+            FailureCategory::Interrupted => "Turn interrupted",
+        }
+    }
+}
+
+/// What one session did within the report's period.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionReport {
+    pub id: Option<ConversationId>,
+    pub path: PathBuf,
+    pub started_at: Option<String>,
+    pub cwd: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// The first message the user sent in the session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// The last message the agent sent within the period.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+    pub turns: usize,
+    pub patches_applied: usize,
+    pub patches_failed: usize,
+    pub files_changed: BTreeSet<String>,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub commands_run: usize,
+    pub commands_failed: usize,
+    pub test_runs: usize,
+    pub test_runs_failed: usize,
+    /// Test commands that failed and later passed in the session.
+    pub tests_fixed: Vec<String>,
+    pub tokens: TokenUsage,
+    pub tokens_by_model: BTreeMap<String, TokenUsage>,
+    pub failures: BTreeMap<FailureCategory, usize>,
+}
+
+/// Totals over every session in a report.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportTotals {
+    pub sessions: usize,
+    pub turns: usize,
+    pub patches_applied: usize,
+    pub patches_failed: usize,
+    pub files_changed: usize,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub commands_run: usize,
+    pub commands_failed: usize,
+    pub test_runs: usize,
+    pub test_runs_failed: usize,
+    pub tests_fixed: usize,
+    pub tokens: TokenUsage,
+    pub tokens_by_model: BTreeMap<String, TokenUsage>,
+    pub failures: BTreeMap<FailureCategory, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamReport {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub totals: ReportTotals,
+    /// Sessions that were active in the period, oldest first.
+    pub sessions: Vec<SessionReport>,
+}
+
+/// Builds a report of the sessions in `codex_home` that were active after
+/// `since`. With `prices`, the report includes an estimated cost.
+pub fn build_report(
+    codex_home: &Path,
+    since: DateTime<Utc>,
+    prices: Option<&TokenPrices>,
+) -> std::io::Result<TeamReport> {
+    let until = Utc::now();
+    let mut paths = Vec::new();
+    for subdir in [SESSIONS_SUBDIR, ARCHIVED_SESSIONS_SUBDIR] {
+        collect_rollout_files(&codex_home.join(subdir), since.into(), &mut paths)?;
+    }
+
+    let mut sessions = Vec::new();
+    for path in paths {
+        match summarize_rollout(&path, since) {
+            Ok(Some(session)) => sessions.push(session),
+            Ok(None) => {}
+            Err(e) => warn!("skipping rollout {}: {e}", path.display()),
+        }
+    }
+    sessions.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(TeamReport::new(since, until, sessions, prices))
+}
+
+impl TeamReport {
+    fn new(
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        sessions: Vec<SessionReport>,
+        prices: Option<&TokenPrices>,
+    ) -> Self {
+        let mut totals = ReportTotals {
+            sessions: sessions.len(),
+            ..Default::default()
+        };
+        for session in &sessions {
+            totals.turns += session.turns;
+            totals.patches_applied += session.patches_applied;
+            totals.patches_failed += session.patches_failed;
+            totals.files_changed += session.files_changed.len();
+            totals.lines_added += session.lines_added;
+            totals.lines_removed += session.lines_removed;
+            totals.commands_run += session.commands_run;
+            totals.commands_failed += session.commands_failed;
+            totals.test_runs += session.test_runs;
+            totals.test_runs_failed += session.test_runs_failed;
+            totals.tests_fixed += session.tests_fixed.len();
+            add_usage(&mut totals.tokens, &session.tokens);
+            for (model, usage) in &session.tokens_by_model {
+                add_usage(
+                    totals.tokens_by_model.entry(model.clone()).or_default(),
+                    usage,
+                );
+            }
+            for (category, count) in &session.failures {
+                *totals.failures.entry(*category).or_default() += count;
+            }
+        }
+        totals.estimated_cost_usd = prices.map(|prices| prices.cost(&totals.tokens));
+        Self {
+            since,
+            until,
+            totals,
+            sessions,
+        }
+    }
+
+    /// Renders the report as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let totals = &self.totals;
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Codex report: {} to {}\n",
+            self.since.format("%Y-%m-%d"),
+            self.until.format("%Y-%m-%d")
+        );
+
+        let _ = writeln!(out, "## Summary\n");
+        let _ = writeln!(out, "| | |\n|---|---|");
+        let _ = writeln!(out, "| Sessions | {} |", format_count(totals.sessions));
+        let _ = writeln!(out, "| Turns | {} |", format_count(totals.turns));
+        let _ = writeln!(
+            out,
+            "| Patches applied | {}{} |",
+            format_count(totals.patches_applied),
+            failed_suffix(totals.patches_failed)
+        );
+        let _ = writeln!(
+            out,
+            "| Files changed | {} (+{} / -{} lines) |",
+            format_count(totals.files_changed),
+            format_count(totals.lines_added),
+            format_count(totals.lines_removed)
+        );
+        let _ = writeln!(
+            out,
+            "| Commands run | {}{} |",
+            format_count(totals.commands_run),
+            failed_suffix(totals.commands_failed)
+        );
+        let _ = writeln!(
+            out,
+            "| Test runs | {}{} |",
+            format_count(totals.test_runs),
+            failed_suffix(totals.test_runs_failed)
+        );
+        let _ = writeln!(
+            out,
+            "| Failing tests fixed | {} |",
+            format_count(totals.tests_fixed)
+        );
+        let _ = writeln!(
+            out,
+            "| Tokens | {} |",
+            format_count(totals.tokens.total_tokens)
+        );
+        if let Some(cost) = totals.estimated_cost_usd {
+            let _ = writeln!(out, "| Estimated cost | ${cost:.2} |");
+        }
+
+        if !totals.tokens_by_model.is_empty() {
+            let _ = writeln!(out, "\n## Tokens by model\n");
+            let _ = writeln!(
+                out,
+                "| Model | Input | Cached input | Output | Total |\n|---|---:|---:|---:|---:|"
+            );
+            for (model, usage) in &totals.tokens_by_model {
+                let _ = writeln!(
+                    out,
+                    "| {model} | {} | {} | {} | {} |",
+                    format_count(usage.input_tokens),
+                    format_count(usage.cached_input_tokens),
+                    format_count(usage.output_tokens),
+                    format_count(usage.total_tokens)
+                );
+            }
+        }
+
+        if !totals.failures.is_empty() {
+            let _ = writeln!(out, "\n## Failures\n");
+            let _ = writeln!(out, "| Category | Count |\n|---|---:|");
+            let mut failures: Vec<_> = totals.failures.iter().collect();
+            failures.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            for (category, count) in failures {
+                let _ = writeln!(out, "| {} | {} |", category.label(), format_count(*count));
+            }
+        }
+
+        if totals.tests_fixed > 0 {
+            let _ = writeln!(out, "\n## Failing tests fixed\n");
+            for session in &self.sessions {
+                for command in &session.tests_fixed {
+                    let _ = writeln!(out, "- `{command}` in {}", session_location(session));
+                }
+            }
+        }
+
+        let _ = writeln!(out, "\n## Sessions\n");
+        if self.sessions.is_empty() {
+            let _ = writeln!(out, "No sessions were active in this period.");
+        }
+        for session in &self.sessions {
+            let started_at = session
+                .started_at
+                .as_deref()
+                .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                .map(|ts| ts.with_timezone(&Utc).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "unknown time".to_string());
+            let _ = writeln!(out, "### {started_at} · {}\n", session_location(session));
+            if let Some(task) = &session.task {
+                let _ = writeln!(out, "- **Task:** {}", one_line(task));
+            }
+            if let Some(outcome) = &session.outcome {
+                let _ = writeln!(out, "- **Outcome:** {}", one_line(outcome));
+            }
+            let _ = writeln!(
+                out,
+                "- {} turns, {} patches applied, {} files changed, {} commands run{}, {} tokens",
+                format_count(session.turns),
+                format_count(session.patches_applied),
+                format_count(session.files_changed.len()),
+                format_count(session.commands_run),
+                failed_suffix(session.commands_failed),
+                format_count(session.tokens.total_tokens)
+            );
+            if !session.tests_fixed.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "- Fixed failing tests: {}",
+                    session
+                        .tests_fixed
+                        .iter()
+                        .map(|command| format!("`{command}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            if let Some(id) = &session.id {
+                let _ = writeln!(out, "- Session `{id}`");
+            }
+            out.push('\n');
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+        out
+    }
+}
+
+/// Adds the rollout files under `dir` modified after `since` to `out`.
+fn collect_rollout_files(
+    dir: &Path,
+    since: SystemTime,
+    out: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_rollout_files(&path, since, out)?;
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !file_type.is_file() || !name.starts_with("rollout-") || !name.ends_with(".jsonl") {
+            continue;
+        }
+        // A file last written before `since` cannot hold anything newer.
+        let modified = entry.metadata().and_then(|metadata| metadata.modified());
+        if modified.is_ok_and(|modified| modified < since) {
+            continue;
+        }
+        out.push(path);
+    }
+    Ok(())
+}
+
+/// A tool call waiting for its output.
+enum PendingCall {
+    Command { line: String, is_test: bool },
+    Patch(PatchChange),
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct PatchChange {
+    files: Vec<String>,
+    lines_added: u64,
+    lines_removed: u64,
+}
+
+#[derive(Debug, PartialEq)]
+enum CallOutcome {
+    Succeeded,
+    Failed(FailureCategory),
+    /// Still running in the background or on a terminal.
+    Started,
+}
+
+/// Summarizes the part of the rollout at `path` recorded after `since`.
+/// Returns `None` when nothing happened in the session in that period.
+fn summarize_rollout(path: &Path, since: DateTime<Utc>) -> std::io::Result<Option<SessionReport>> {
+    let file = std::fs::File::open(path)?;
+    let mut session = SessionReport {
+        path: path.to_path_buf(),
+        ..Default::default()
+    };
+    let mut active = false;
+    let mut model = String::from("unknown");
+    let mut last_total = TokenUsage::default();
+    let mut pending: HashMap<String, PendingCall> = HashMap::new();
+    let mut failing_tests: HashSet<String> = HashSet::new();
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(RolloutLine { timestamp, item }) = serde_json::from_str::<RolloutLine>(&line) else {
+            // Lines written by newer versions or torn by a crash.
+            continue;
+        };
+        let in_period = DateTime::parse_from_rfc3339(&timestamp)
+            .map_or(true, |timestamp| timestamp.with_timezone(&Utc) >= since);
+
+        match item {
+            RolloutItem::SessionMeta(meta) => {
+                session.id = Some(meta.meta.id);
+                session.started_at = Some(meta.meta.timestamp);
+                session.cwd = Some(meta.meta.cwd);
+                session.branch = meta.git.and_then(|git| git.branch);
+            }
+            RolloutItem::TurnContext(context) => model = context.model,
+            RolloutItem::Compacted(_) | RolloutItem::FileChanges(_) => {}
+            RolloutItem::EventMsg(EventMsg::UserMessage(message)) => {
+                if matches!(
+                    message.kind,
+                    Some(InputMessageKind::UserInstructions | InputMessageKind::EnvironmentContext)
+                ) {
+                    continue;
+                }
+                if session.task.is_none() {
+                    session.task = Some(message.message);
+                }
+                if in_period {
+                    active = true;
+                    session.turns += 1;
+                }
+            }
+            RolloutItem::EventMsg(EventMsg::AgentMessage(message)) => {
+                if in_period {
+                    active = true;
+                    session.outcome = Some(message.message);
+                }
+            }
+            RolloutItem::EventMsg(EventMsg::TokenCount(event)) => {
+                let Some(info) = event.info else {
+                    continue;
+                };
+                let total = info.total_token_usage;
+                if in_period {
+                    let delta = usage_delta(&total, &last_total);
+                    add_usage(&mut session.tokens, &delta);
+                    add_usage(
+                        session.tokens_by_model.entry(model.clone()).or_default(),
+                        &delta,
+                    );
+                }
+                last_total = total;
+            }
+            RolloutItem::EventMsg(EventMsg::TurnAborted(event)) => {
+                if in_period && event.reason == TurnAbortReason::Interrupted {
+                    active = true;
+                    *session
+                        .failures
+                        .entry(FailureCategory::Interrupted)
+                        .or_default() += 1;
+                }
+            }
+            RolloutItem::EventMsg(_) => {}
+            RolloutItem::ResponseItem(item) => {
+                if let Some((call_id, call)) = pending_call(&item) {
+                    if in_period {
+                        active = true;
+                        pending.insert(call_id, call);
+                    }
+                    continue;
+                }
+                let (call_id, output) = match &item {
+                    ResponseItem::FunctionCallOutput { call_id, output } => {
+                        (call_id, output.content.as_str())
+                    }
+                    ResponseItem::CustomToolCallOutput { call_id, output } => {
+                        (call_id, output.as_str())
+                    }
+                    _ => continue,
+                };
+                let Some(call) = pending.remove(call_id) else {
+                    continue;
+                };
+                record_outcome(
+                    &mut session,
+                    &mut failing_tests,
+                    call,
+                    classify_output(output),
+                );
+            }
+        }
+    }
+
+    Ok(active.then_some(session))
+}
+
+/// The command or patch that `item` asks to run, keyed by its call id.
+fn pending_call(item: &ResponseItem) -> Option<(String, PendingCall)> {
+    match item {
+        ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        } => match name.as_str() {
+            "shell" | "container.exec" => {
+                let params: ShellToolCallParams = serde_json::from_str(arguments).ok()?;
+                Some((call_id.clone(), command_call(&params.command)))
+            }
+            "apply_patch" => {
+                #[derive(Deserialize)]
+                struct ApplyPatchArgs {
+                    input: String,
+                }
+                let args: ApplyPatchArgs = serde_json::from_str(arguments).ok()?;
+                Some((
+                    call_id.clone(),
+                    PendingCall::Patch(parse_patch(&args.input)),
+                ))
+            }
+            _ => None,
+        },
+        ResponseItem::CustomToolCall {
+            name,
+            input,
+            call_id,
+            ..
+        } if name == "apply_patch" => {
+            Some((call_id.clone(), PendingCall::Patch(parse_patch(input))))
+        }
+        ResponseItem::LocalShellCall {
+            id,
+            call_id,
+            action: LocalShellAction::Exec(action),
+            ..
+        } => {
+            let call_id = call_id.clone().or_else(|| id.clone())?;
+            Some((call_id, command_call(&action.command)))
+        }
+        _ => None,
+    }
+}
+
+fn command_call(command: &[String]) -> PendingCall {
+    let line = match command {
+        [shell, flag, script] if is_shell(shell) && (flag == "-lc" || flag == "-c") => {
+            script.clone()
+        }
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    };
+    // The model sometimes applies patches through the shell.
+    if let Some(start) = line.find("*** Begin Patch") {
+        return PendingCall::Patch(parse_patch(&line[start..]));
+    }
+    let is_test = is_test_command(&line);
+    PendingCall::Command { line, is_test }
+}
+
+fn is_shell(program: &str) -> bool {
+    let name = program.rsplit('/').next().unwrap_or(program);
+    matches!(name, "bash" | "sh" | "zsh")
+}
+
+fn is_test_command(line: &str) -> bool {
+    TEST_COMMANDS
+        .iter()
+        .any(|marker| contains_words(line, marker))
+}
+
+/// Whether `needle` appears in `haystack` delimited by the start or end of
+/// the string, whitespace or shell punctuation.
+fn contains_words(haystack: &str, needle: &str) -> bool {
+    let is_boundary = |c: char| c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')');
+    haystack.match_indices(needle).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + needle.len()..].chars().next();
+        before.is_none_or(is_boundary) && after.is_none_or(is_boundary)
+    })
+}
+
+fn parse_patch(patch: &str) -> PatchChange {
+    let mut change = PatchChange::default();
+    for line in patch.lines() {
+        let path = [
+            "*** Add File: ",
+            "*** Update File: ",
+            "*** Delete File: ",
+            "*** Move to: ",
+        ]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix));
+        if let Some(path) = path {
+            change.files.push(path.trim().to_string());
+        } else if line.starts_with("***") {
+            continue;
+        } else if line.starts_with('+') {
+            change.lines_added += 1;
+        } else if line.starts_with('-') {
+            change.lines_removed += 1;
+        }
+    }
+    change
+}
+
+/// Works out from the text Codex returned to the model whether a command or
+/// patch did its job.
+fn classify_output(output: &str) -> CallOutcome {
+    #[derive(Deserialize)]
+    struct ExecOutput {
+        metadata: ExecMetadata,
+    }
+    #[derive(Deserialize)]
+    struct ExecMetadata {
+        exit_code: i32,
+    }
+
+    if let Ok(ExecOutput { metadata }) = serde_json::from_str::<ExecOutput>(output) {
+        return match metadata.exit_code {
+            0 => CallOutcome::Succeeded,
+            TIMEOUT_EXIT_CODE => CallOutcome::Failed(FailureCategory::TimedOut),
+            _ => CallOutcome::Failed(FailureCategory::CommandFailed),
+        };
+    }
+    // Commands run on a terminal report their status in the text.
+    if let Some(code) = output
+        .lines()
+        .find_map(|line| line.strip_prefix("Process exited with code "))
+        .and_then(|code| code.trim().parse::<i32>().ok())
+    {
+        return if code == 0 {
+            CallOutcome::Succeeded
+        } else {
+            CallOutcome::Failed(FailureCategory::CommandFailed)
+        };
+    }
+    if output.starts_with("started background job") || output.contains("Process running with") {
+        return CallOutcome::Started;
+    }
+    if output.contains("rejected by user") {
+        CallOutcome::Failed(FailureCategory::RejectedByUser)
+    } else if output.starts_with("exec command rejected") || output.starts_with("patch rejected") {
+        CallOutcome::Failed(FailureCategory::RejectedByPolicy)
+    } else if output.starts_with("failed in sandbox") {
+        CallOutcome::Failed(FailureCategory::SandboxDenied)
+    } else if output.starts_with("failed to parse function arguments") {
+        CallOutcome::Failed(FailureCategory::InvalidToolCall)
+    } else if output.starts_with("execution error") || output.starts_with("error:") {
+        CallOutcome::Failed(FailureCategory::CommandFailed)
+    } else {
+        CallOutcome::Succeeded
+    }
+}
+
+fn record_outcome(
+    session: &mut SessionReport,
+    failing_tests: &mut HashSet<String>,
+    call: PendingCall,
+    outcome: CallOutcome,
+) {
+    match call {
+        PendingCall::Patch(change) => match outcome {
+            CallOutcome::Succeeded | CallOutcome::Started => {
+                session.patches_applied += 1;
+                session.files_changed.extend(change.files);
+                session.lines_added += change.lines_added;
+                session.lines_removed += change.lines_removed;
+            }
+            CallOutcome::Failed(category) => {
+                session.patches_failed += 1;
+                let category = match category {
+                    FailureCategory::CommandFailed => FailureCategory::PatchFailed,
+                    category => category,
+                };
+                *session.failures.entry(category).or_default() += 1;
+            }
+        },
+        PendingCall::Command { line, is_test } => {
+            session.commands_run += 1;
+            if is_test {
+                session.test_runs += 1;
+            }
+            match outcome {
+                CallOutcome::Succeeded => {
+                    if is_test
+                        && failing_tests.remove(&line)
+                        && !session.tests_fixed.contains(&line)
+                    {
+                        session.tests_fixed.push(line);
+                    }
+                }
+                CallOutcome::Failed(category) => {
+                    session.commands_failed += 1;
+                    *session.failures.entry(category).or_default() += 1;
+                    if is_test {
+                        session.test_runs_failed += 1;
+                        if category == FailureCategory::CommandFailed {
+                            failing_tests.insert(line);
+                        }
+                    }
+                }
+                CallOutcome::Started => {}
+            }
+        }
+    }
+}
+
+fn add_usage(total: &mut TokenUsage, usage: &TokenUsage) {
+    total.input_tokens += usage.input_tokens;
+    total.cached_input_tokens += usage.cached_input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.reasoning_output_tokens += usage.reasoning_output_tokens;
+    total.total_tokens += usage.total_tokens;
+}
+
+/// Tokens used between two cumulative totals of a session.
+fn usage_delta(current: &TokenUsage, previous: &TokenUsage) -> TokenUsage {
+    TokenUsage {
+        input_tokens: current.input_tokens.saturating_sub(previous.input_tokens),
+        cached_input_tokens: current
+            .cached_input_tokens
+            .saturating_sub(previous.cached_input_tokens),
+        output_tokens: current.output_tokens.saturating_sub(previous.output_tokens),
+        reasoning_output_tokens: current
+            .reasoning_output_tokens
+            .saturating_sub(previous.reasoning_output_tokens),
+        total_tokens: current.total_tokens.saturating_sub(previous.total_tokens),
+    }
+}
+
+fn session_location(session: &SessionReport) -> String {
+    let project = session
+        .cwd
+        .as_deref()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown project".to_string());
+    match &session.branch {
+        Some(branch) => format!("{project} ({branch})"),
+        None => project,
+    }
+}
+
+fn failed_suffix(failed: usize) -> String {
+    if failed == 0 {
+        String::new()
+    } else {
+        format!(" ({} failed)", format_count(failed))
+    }
+}
+
+/// First line of `text`, shortened to [`MAX_SUMMARY_CHARS`].
+fn one_line(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    if line.chars().count() <= MAX_SUMMARY_CHARS {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_SUMMARY_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+fn format_count(count: impl std::fmt::Display) -> String {
+    let digits = count.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn line(timestamp: &str, item: serde_json::Value) -> String {
+        let mut line = json!({ "timestamp": timestamp });
+        if let (Some(line), Some(item)) = (line.as_object_mut(), item.as_object()) {
+            line.extend(item.clone());
+        }
+        line.to_string()
+    }
+
+    fn exec_output(exit_code: i32) -> String {
+        json!({ "output": "", "metadata": { "exit_code": exit_code, "duration_seconds": 0.1 } })
+            .to_string()
+    }
+
+    fn token_count(total_tokens: u64) -> serde_json::Value {
+        let usage = json!({
+            "input_tokens": total_tokens,
+            "cached_input_tokens": 0,
+            "output_tokens": 0,
+            "reasoning_output_tokens": 0,
+            "total_tokens": total_tokens,
+        });
+        json!({
+            "type": "event_msg",
+            "payload": {
+                "type": "token_count",
+                "info": {
+                    "total_token_usage": usage,
+                    "last_token_usage": usage,
+                    "model_context_window": null,
+                },
+                "rate_limits": null,
+            },
+        })
+    }
+
+    fn shell_call(call_id: &str, script: &str) -> serde_json::Value {
+        json!({
+            "type": "response_item",
+            "payload": {
+                "type": "function_call",
+                "name": "shell",
+                "arguments": json!({ "command": ["bash", "-lc", script] }).to_string(),
+                "call_id": call_id,
+            },
+        })
+    }
+
+    fn call_output(call_id: &str, output: String) -> serde_json::Value {
+        json!({
+            "type": "response_item",
+            "payload": { "type": "function_call_output", "call_id": call_id, "output": output },
+        })
+    }
+
+    #[test]
+    fn summarizes_work_recorded_after_since() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("rollout.jsonl");
+        let lines = [
+            line(
+                "2026-10-01T09:00:00.000Z",
+                json!({
+                    "type": "session_meta",
+                    "payload": {
+                        "id": "0199f2a4-5d1c-7bb0-9d7c-1f1e6f1c2a3b",
+                        "timestamp": "2026-10-01T09:00:00.000Z",
+                        "cwd": "/work/repo",
+                        "originator": "codex_cli_rs",
+                        "cli_version": "0.0.0",
+                        "instructions": null,
+                        "git": { "branch": "main" },
+                    },
+                }),
+            ),
+            line(
+                "2026-10-01T09:00:01.000Z",
+                json!({
+                    "type": "event_msg",
+                    "payload": { "type": "user_message", "message": "Fix the flaky parser test" },
+                }),
+            ),
+            line("2026-10-01T09:05:00.000Z", token_count(100)),
+            line(
+                "2026-10-10T09:00:00.000Z",
+                json!({
+                    "type": "turn_context",
+                    "payload": {
+                        "cwd": "/work/repo",
+                        "approval_policy": "on-request",
+                        "sandbox_policy": { "mode": "danger-full-access" },
+                        "model": "gpt-5",
+                        "summary": "auto",
+                    },
+                }),
+            ),
+            line(
+                "2026-10-10T09:00:01.000Z",
+                shell_call("c1", "cargo test -p parser"),
+            ),
+            line(
+                "2026-10-10T09:00:02.000Z",
+                call_output("c1", exec_output(101)),
+            ),
+            line(
+                "2026-10-10T09:00:03.000Z",
+                json!({
+                    "type": "response_item",
+                    "payload": {
+                        "type": "custom_tool_call",
+                        "call_id": "c2",
+                        "name": "apply_patch",
+                        "input": "*** Begin Patch\n*** Update File: src/lib.rs\n@@\n-old\n+new\n+more\n*** End Patch",
+                    },
+                }),
+            ),
+            line(
+                "2026-10-10T09:00:04.000Z",
+                json!({
+                    "type": "response_item",
+                    "payload": { "type": "custom_tool_call_output", "call_id": "c2", "output": exec_output(0) },
+                }),
+            ),
+            line(
+                "2026-10-10T09:00:05.000Z",
+                shell_call("c3", "cargo test -p parser"),
+            ),
+            line(
+                "2026-10-10T09:00:06.000Z",
+                call_output("c3", exec_output(0)),
+            ),
+            line("2026-10-10T09:00:07.000Z", shell_call("c4", "rm -rf /")),
+            line(
+                "2026-10-10T09:00:08.000Z",
+                call_output("c4", "exec command rejected by user".to_string()),
+            ),
+            line("2026-10-10T09:00:09.000Z", token_count(1_100)),
+            line(
+                "2026-10-10T09:00:10.000Z",
+                json!({
+                    "type": "event_msg",
+                    "payload": { "type": "agent_message", "message": "The parser test passes now." },
+                }),
+            ),
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write rollout");
+
+        let since = DateTime::parse_from_rfc3339("2026-10-05T00:00:00Z")
+            .expect("timestamp")
+            .with_timezone(&Utc);
+        let prices = TokenPrices {
+            input: 2.0,
+            cached_input: 2.0,
+            output: 10.0,
+        };
+        let session = summarize_rollout(&path, since)
+            .expect("read rollout")
+            .expect("session was active after since");
+        assert_eq!(session.branch.as_deref(), Some("main"));
+        assert_eq!(session.task.as_deref(), Some("Fix the flaky parser test"));
+        assert_eq!(
+            session.outcome.as_deref(),
+            Some("The parser test passes now.")
+        );
+        assert_eq!(session.turns, 0);
+        assert_eq!(
+            (
+                session.patches_applied,
+                session.lines_added,
+                session.lines_removed
+            ),
+            (1, 2, 1)
+        );
+        assert_eq!(
+            session.files_changed.iter().collect::<Vec<_>>(),
+            vec!["src/lib.rs"]
+        );
+        assert_eq!((session.commands_run, session.commands_failed), (3, 2));
+        assert_eq!((session.test_runs, session.test_runs_failed), (2, 1));
+        assert_eq!(
+            session.tests_fixed,
+            vec!["cargo test -p parser".to_string()]
+        );
+        assert_eq!(session.tokens.total_tokens, 1_000);
+        assert_eq!(
+            session
+                .tokens_by_model
+                .iter()
+                .map(|(model, usage)| (model.as_str(), usage.total_tokens))
+                .collect::<Vec<_>>(),
+            vec![("gpt-5", 1_000)]
+        );
+        assert_eq!(
+            session.failures,
+            BTreeMap::from([
+                (FailureCategory::CommandFailed, 1),
+                (FailureCategory::RejectedByUser, 1),
+            ])
+        );
+
+        let report = TeamReport::new(since, since, vec![session], Some(&prices));
+        assert_eq!(report.totals.estimated_cost_usd, Some(0.002));
+
+        let markdown = report.to_markdown();
+        assert!(
+            markdown.contains("| Failing tests fixed | 1 |"),
+            "{markdown}"
+        );
+        assert!(
+            markdown.contains("- `cargo test -p parser` in repo (main)"),
+            "{markdown}"
+        );
+        assert!(
+            markdown.contains("| gpt-5 | 1,000 | 0 | 0 | 1,000 |"),
+            "{markdown}"
+        );
+    }
+
+    #[test]
+    fn classifies_tool_output() {
+        assert_eq!(classify_output(&exec_output(0)), CallOutcome::Succeeded);
+        assert_eq!(
+            classify_output(&exec_output(124)),
+            CallOutcome::Failed(FailureCategory::TimedOut)
+        );
+        assert_eq!(
+            classify_output("failed in sandbox LinuxSeccomp with execution error: Denied"),
+            CallOutcome::Failed(FailureCategory::SandboxDenied)
+        );
+        assert_eq!(
+            classify_output("patch rejected: writes outside the workspace"),
+            CallOutcome::Failed(FailureCategory::RejectedByPolicy)
+        );
+        assert_eq!(
+            classify_output("started background job job-1; call `job_result`"),
+            CallOutcome::Started
+        );
+        assert_eq!(
+            classify_output("Wall time: 0.100 seconds\nProcess exited with code 2\nOutput:\n"),
+            CallOutcome::Failed(FailureCategory::CommandFailed)
+        );
+    }
+
+    #[test]
+    fn detects_test_commands_as_whole_words() {
+        assert!(is_test_command("cd crates/foo && cargo test --all"));
+        assert!(is_test_command("pytest -k parser"));
+        assert!(!is_test_command("cat pytest.ini"));
+        assert!(!is_test_command("cargo testing"));
+        assert_eq!(format_count(1_234_567u64), "1,234,567");
+    }
+}
@@ -0,0 +1,280 @@
+//! Local estimates of prompt token counts.
+//!
+//! Providers only report token usage after a request has been sent, which
+//! is too late to avoid overflowing the context window. No tokenizer
+//! vocabulary ships with Codex, so counts are estimated from how BPE
+//! tokenizers such as `o200k_base` split text: short words are one token
+//! together with the space before them, long words, numbers and runs of
+//! punctuation split into several, and most non-ASCII characters are a
+//! token each. The estimate errs on the high side for code and prose.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+
+use crate::client_common::Prompt;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_responses_api;
+use crate::protocol::ContextUsagePart;
+use crate::protocol::ENVIRONMENT_CONTEXT_OPEN_TAG;
+use crate::protocol::USER_INSTRUCTIONS_OPEN_TAG;
+
+/// ASCII letters covered by one token in a run of letters.
+const LETTERS_PER_TOKEN: usize = 7;
+
+/// Digits covered by one token; tokenizers split numbers into groups of
+/// three.
+const DIGITS_PER_TOKEN: usize = 3;
+
+/// Punctuation characters covered by one token, e.g. `()` or `");`.
+const PUNCTUATION_PER_TOKEN: usize = 2;
+
+/// Role and framing tokens added for every conversation item.
+const ITEM_OVERHEAD_TOKENS: u64 = 4;
+
+/// Flat estimate for an attached image, whose cost depends on its size.
+const IMAGE_TOKENS: u64 = 765;
+
+/// Estimated number of tokens in `text`.
+pub(crate) fn count_tokens(text: &str) -> u64 {
+    let mut tokens = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        tokens += if c.is_ascii_alphabetic() {
+            take_run(&mut chars, char::is_ascii_alphabetic).div_ceil(LETTERS_PER_TOKEN)
+        } else if c.is_ascii_digit() {
+            take_run(&mut chars, char::is_ascii_digit).div_ceil(DIGITS_PER_TOKEN)
+        } else if c == ' ' && chars.peek().is_none_or(|next| !next.is_whitespace()) {
+            // A single space belongs to the word that follows it.
+            0
+        } else if c.is_whitespace() {
+            // Indentation and blank lines are one token per run.
+            take_run(&mut chars, |c| c.is_whitespace());
+            1
+        } else if c.is_ascii() {
+            take_run(&mut chars, char::is_ascii_punctuation).div_ceil(PUNCTUATION_PER_TOKEN)
+        } else {
+            1
+        };
+    }
+    tokens as u64
+}
+
+/// Consumes the characters that continue a run started by the character
+/// just taken, and returns the length of the run.
+fn take_run(chars: &mut Peekable<Chars>, belongs: fn(&char) -> bool) -> usize {
+    let mut len = 1;
+    while chars.next_if(belongs).is_some() {
+        len += 1;
+    }
+    len
+}
+
+/// Share of the context window a request may fill before the conversation
+/// is trimmed. The rest is left for the response and for the error of the
+/// estimate.
+const CONTEXT_BUDGET_PERCENT: u64 = 90;
+
+/// How many input tokens a request may have before the conversation is
+/// trimmed, if the model's context window is known.
+pub(crate) fn context_budget(model_context_window: Option<u64>) -> Option<u64> {
+    model_context_window.map(|window| window / 100 * CONTEXT_BUDGET_PERCENT)
+}
+
+/// Estimated tokens of a prompt, by the part of the context they come from.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct PromptTokens {
+    pub(crate) instructions: u64,
+    pub(crate) tools: u64,
+    pub(crate) user_instructions: u64,
+    pub(crate) environment_context: u64,
+    pub(crate) messages: u64,
+    pub(crate) tool_calls: u64,
+    pub(crate) reasoning: u64,
+}
+
+impl PromptTokens {
+    pub(crate) fn total(&self) -> u64 {
+        self.instructions
+            + self.tools
+            + self.user_instructions
+            + self.environment_context
+            + self.messages
+            + self.tool_calls
+            + self.reasoning
+    }
+
+    /// The non-empty parts, in the order they appear in the prompt.
+    pub(crate) fn into_parts(self) -> Vec<ContextUsagePart> {
+        [
+            ("System instructions", self.instructions),
+            ("Tool definitions", self.tools),
+            ("AGENTS.md instructions", self.user_instructions),
+            ("Environment context", self.environment_context),
+            ("Messages", self.messages),
+            ("Tool calls and output", self.tool_calls),
+            ("Reasoning", self.reasoning),
+        ]
+        .into_iter()
+        .filter(|(_, tokens)| *tokens > 0)
+        .map(|(name, tokens)| ContextUsagePart {
+            name: name.to_string(),
+            tokens,
+        })
+        .collect()
+    }
+}
+
+/// Estimated tokens of the request that `prompt` would send to a model of
+/// `model_family`.
+pub(crate) fn count_prompt_tokens(prompt: &Prompt, model_family: &ModelFamily) -> PromptTokens {
+    let mut counts = PromptTokens {
+        instructions: count_tokens(&prompt.get_full_instructions(model_family)),
+        tools: create_tools_json_for_responses_api(&prompt.tools)
+            .ok()
+            .filter(|tools| !tools.is_empty())
+            .and_then(|tools| serde_json::to_string(&tools).ok())
+            .map_or(0, |json| count_tokens(&json)),
+        ..Default::default()
+    };
+    for item in &prompt.input {
+        let tokens = ITEM_OVERHEAD_TOKENS + count_item_tokens(item);
+        let part = match item {
+            ResponseItem::Message { content, .. } => match content.first() {
+                Some(ContentItem::InputText { text })
+                    if text.starts_with(USER_INSTRUCTIONS_OPEN_TAG) =>
+                {
+                    &mut counts.user_instructions
+                }
+                Some(ContentItem::InputText { text })
+                    if text.starts_with(ENVIRONMENT_CONTEXT_OPEN_TAG) =>
+                {
+                    &mut counts.environment_context
+                }
+                _ => &mut counts.messages,
+            },
+            ResponseItem::Reasoning { .. } => &mut counts.reasoning,
+            _ => &mut counts.tool_calls,
+        };
+        *part += tokens;
+    }
+    counts
+}
+
+fn count_item_tokens(item: &ResponseItem) -> u64 {
+    match item {
+        ResponseItem::Message { content, .. } => content
+            .iter()
+            .map(|content| match content {
+                ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                    count_tokens(text)
+                }
+                ContentItem::InputImage { .. } => IMAGE_TOKENS,
+            })
+            .sum(),
+        ResponseItem::Reasoning {
+            summary, content, ..
+        } => {
+            let summary: u64 = summary
+                .iter()
+                .map(|ReasoningItemReasoningSummary::SummaryText { text }| count_tokens(text))
+                .sum();
+            let content: u64 = content
+                .iter()
+                .flatten()
+                .map(|content| match content {
+                    ReasoningItemContent::ReasoningText { text }
+                    | ReasoningItemContent::Text { text } => count_tokens(text),
+                })
+                .sum();
+            summary + content
+        }
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => count_tokens(name) + count_tokens(arguments),
+        ResponseItem::FunctionCallOutput { output, .. } => count_tokens(&output.content),
+        ResponseItem::CustomToolCall { name, input, .. } => {
+            count_tokens(name) + count_tokens(input)
+        }
+        ResponseItem::CustomToolCallOutput { output, .. } => count_tokens(output),
+        ResponseItem::LocalShellCall { action, .. } => {
+            serde_json::to_string(action).map_or(0, |json| count_tokens(&json))
+        }
+        ResponseItem::WebSearchCall { action, .. } => {
+            serde_json::to_string(action).map_or(0, |json| count_tokens(&json))
+        }
+        ResponseItem::Other => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn counts_words_numbers_and_punctuation() {
+        assert_eq!(count_tokens(""), 0);
+        assert_eq!(count_tokens("hello world"), 2);
+        assert_eq!(count_tokens("internationalization"), 3);
+        assert_eq!(count_tokens("12345678"), 3);
+        assert_eq!(count_tokens("fn main() {\n    println!(\"hi\");\n}"), 13);
+        assert_eq!(count_tokens("你好"), 2);
+    }
+
+    #[test]
+    fn budget_leaves_room_for_the_response() {
+        assert_eq!(context_budget(Some(200_000)), Some(180_000));
+        assert_eq!(context_budget(None), None);
+    }
+
+    #[test]
+    fn splits_the_prompt_by_context_part() {
+        let text = |role: &str, text: &str| ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        };
+        let prompt = Prompt {
+            input: vec![
+                text(
+                    "user",
+                    "<user_instructions>\n\nrun tests\n\n</user_instructions>",
+                ),
+                text("user", "<environment_context>cwd</environment_context>"),
+                text("user", "hello world"),
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: "{}".to_string(),
+                    call_id: "call_1".to_string(),
+                },
+            ],
+            base_instructions_override: Some("be brief".to_string()),
+            ..Default::default()
+        };
+        let family = crate::model_family::find_family_for_model("gpt-5").expect("known model");
+
+        let counts = count_prompt_tokens(&prompt, &family);
+
+        assert_eq!(
+            counts,
+            PromptTokens {
+                instructions: 2,
+                tools: 0,
+                user_instructions: ITEM_OVERHEAD_TOKENS + 16,
+                environment_context: ITEM_OVERHEAD_TOKENS + 13,
+                messages: ITEM_OVERHEAD_TOKENS + 2,
+                tool_calls: ITEM_OVERHEAD_TOKENS + 2,
+                reasoning: 0,
+            }
+        );
+        assert_eq!(counts.total(), 51);
+    }
+}
@@ -1,4 +1,6 @@
 use base64::Engine;
+use chrono::DateTime;
+use chrono::Utc;
 use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
@@ -101,6 +103,21 @@ pub fn parse_id_token(id_token: &str) -> Result<IdTokenInfo, IdTokenInfoError> {
     })
 }
 
+#[derive(Deserialize)]
+struct ExpirationClaims {
+    exp: Option<i64>,
+}
+
+/// The `exp` claim of a JWT such as the access token, if it has one.
+pub(crate) fn parse_jwt_expiration(jwt: &str) -> Option<DateTime<Utc>> {
+    let payload_b64 = jwt.split('.').nth(1)?;
+    let payload_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload_b64)
+        .ok()?;
+    let claims: ExpirationClaims = serde_json::from_slice(&payload_bytes).ok()?;
+    DateTime::from_timestamp(claims.exp?, 0)
+}
+
 fn deserialize_id_token<'de, D>(deserializer: D) -> Result<IdTokenInfo, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -153,6 +170,25 @@ mod tests {
         assert_eq!(info.get_chatgpt_plan_type().as_deref(), Some("Pro"));
     }
 
+    #[test]
+    fn parses_jwt_expiration() {
+        fn b64url_no_pad(bytes: &[u8]) -> String {
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+        }
+        let payload = b64url_no_pad(br#"{"exp": 1700000000}"#);
+        let jwt = format!(
+            "{}.{payload}.{}",
+            b64url_no_pad(b"{}"),
+            b64url_no_pad(b"sig")
+        );
+
+        assert_eq!(
+            parse_jwt_expiration(&jwt),
+            DateTime::from_timestamp(1_700_000_000, 0)
+        );
+        assert_eq!(parse_jwt_expiration("not-a-jwt"), None);
+    }
+
     #[test]
     fn id_token_info_handles_missing_fields() {
         #[derive(Serialize)]
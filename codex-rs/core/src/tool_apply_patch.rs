@@ -40,6 +40,15 @@ pub(crate) fn create_apply_patch_json_tool() -> OpenAiTool {
             description: Some(r#"The entire contents of the apply_patch command"#.to_string()),
         },
     );
+    properties.insert(
+        "dry_run".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Only check where each hunk would apply and report it, without changing any file."
+                    .to_string(),
+            ),
+        },
+    );
 
     OpenAiTool::Function(ResponsesApiTool {
         name: "apply_patch".to_string(),
@@ -110,6 +119,8 @@ It is important to remember:
 - You must include a header with your intended action (Add/Delete/Update)
 - You must prefix new lines with `+` even when creating a new file
 - File references can only be relative, NEVER ABSOLUTE.
+
+Hunks whose context has drifted are merged into the lines that are there now, and hunks that cannot be found are skipped; the result lists both, so re-read those files and patch again if needed. Set `dry_run` to get that report without changing anything.
 "#
         .to_string(),
         strict: false,
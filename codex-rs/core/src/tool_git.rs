@@ -0,0 +1,482 @@
+//! The `git` tool: structured access to git that is gated by what the
+//! subcommand can do rather than by the generic shell approval rules.
+//!
+//! Read-only subcommands run freely. Subcommands that rewrite history,
+//! discard uncommitted work, or affect remotes always require explicit user
+//! approval, and the approval request includes a preview of what would be
+//! lost or published. Everything else goes through the same approval flow as
+//! a shell command.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const GIT_TOOL_NAME: &str = "git";
+
+/// Each section of a consequence preview lists at most this many lines.
+const PREVIEW_MAX_LINES: usize = 20;
+
+/// Upper bound on each git command run to build a preview.
+const PREVIEW_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GitToolArgs {
+    pub(crate) args: Vec<String>,
+    #[serde(default)]
+    pub(crate) workdir: Option<String>,
+    #[serde(default, alias = "timeout")]
+    pub(crate) timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub(crate) justification: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GitRisk {
+    /// Inspects the repository without changing it.
+    Read,
+    /// Changes the local repository in a way that can be undone. Subject to
+    /// the approval policy like any other command.
+    Write,
+    /// Rewrites history, discards work, or affects a remote. Always requires
+    /// explicit approval.
+    HighRisk { consequence: String },
+}
+
+pub(crate) fn create_git_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "args".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Arguments to git, starting with the subcommand, e.g. [\"log\", \"--oneline\", \"-5\"]"
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to run git in".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for the command in milliseconds".to_string()),
+        },
+    );
+    properties.insert(
+        "justification".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "1-sentence explanation of why the command is needed, shown to the user when approval is required."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: GIT_TOOL_NAME.to_string(),
+        description: "Runs a git command and returns its output. Read-only subcommands (status, \
+                      diff, log, show, ...) run without approval. Subcommands that rewrite \
+                      history, discard uncommitted changes, or affect remotes (push, reset \
+                      --hard, clean -f, rebase, filter-branch, ...) always require user approval."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["args".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+/// Classify a git invocation by what it can do. `args` excludes the leading
+/// `git`. Returns an error message for the model if the invocation is not
+/// accepted at all.
+pub(crate) fn classify_git_command(args: &[String]) -> Result<GitRisk, String> {
+    let Some((subcommand, rest)) = args.split_first() else {
+        return Err("`args` must start with a git subcommand".to_string());
+    };
+    if subcommand.starts_with('-') {
+        // Global options such as `-c alias.x=!cmd` or `--exec-path` could
+        // change what the subcommand does.
+        return Err(format!(
+            "`args` must start with a git subcommand, not `{subcommand}`; use `workdir` instead of `-C`"
+        ));
+    }
+
+    let has = |flags: &[&str]| rest.iter().any(|arg| flags.contains(&arg.as_str()));
+    let has_short = |flag: char| {
+        rest.iter()
+            .any(|arg| arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains(flag))
+    };
+    let first = rest.first().map(String::as_str);
+    let high_risk = |consequence: &str| {
+        Ok(GitRisk::HighRisk {
+            consequence: consequence.to_string(),
+        })
+    };
+
+    match subcommand.as_str() {
+        "status" | "blame" | "rev-parse" | "ls-files" | "ls-tree" | "describe" | "shortlog"
+        | "cat-file" | "merge-base" | "show-ref" | "for-each-ref" | "name-rev" | "rev-list"
+        | "count-objects" | "cherry" => Ok(GitRisk::Read),
+        "diff" | "log" | "show" | "whatchanged" => {
+            // `--output=<file>` (or `-o <file>`) writes the output to a file.
+            if rest
+                .iter()
+                .any(|arg| arg.starts_with("--output") || arg.starts_with("-o"))
+            {
+                Ok(GitRisk::Write)
+            } else {
+                Ok(GitRisk::Read)
+            }
+        }
+        "grep" => {
+            // `--open-files-in-pager` runs an arbitrary program.
+            if rest
+                .iter()
+                .any(|arg| arg.starts_with("-O") || arg.starts_with("--open-files-in-pager"))
+            {
+                Ok(GitRisk::Write)
+            } else {
+                Ok(GitRisk::Read)
+            }
+        }
+        "push" => {
+            if has(&["--force", "-f", "--force-with-lease", "--mirror"])
+                || has_short('f')
+                || rest
+                    .iter()
+                    .any(|arg| arg.starts_with("--force-with-lease") || arg.starts_with('+'))
+            {
+                high_risk("Force-pushes, overwriting history on the remote.")
+            } else if has(&["--delete", "-d", "--prune"])
+                || rest.iter().any(|arg| arg.starts_with(':'))
+            {
+                high_risk("Deletes refs on the remote.")
+            } else {
+                high_risk("Publishes local commits to the remote.")
+            }
+        }
+        "reset" if has(&["--hard", "--merge", "--keep"]) => {
+            high_risk("Discards uncommitted changes to tracked files and moves the current branch.")
+        }
+        "clean" if has(&["--force"]) || has_short('f') => {
+            if has_short('x') || has_short('X') {
+                high_risk("Permanently deletes untracked files, including ignored files.")
+            } else {
+                high_risk("Permanently deletes untracked files.")
+            }
+        }
+        "filter-branch" | "filter-repo" => {
+            high_risk("Rewrites the history of every matching commit.")
+        }
+        "rebase" if !has(&["--abort", "--continue", "--quit", "--show-current-patch"]) => {
+            high_risk("Rewrites the commits of the current branch.")
+        }
+        "commit" if has(&["--amend"]) => high_risk("Replaces the last commit."),
+        "branch" if has(&["-D"]) || (has(&["-d", "--delete"]) && has(&["-f", "--force"])) => {
+            high_risk("Force-deletes branches; unmerged commits become unreachable.")
+        }
+        "checkout" | "switch" if has(&["-f", "--force", "--discard-changes"]) => {
+            high_risk("Discards uncommitted changes in the working tree.")
+        }
+        "checkout" if checkout_has_paths(rest) => {
+            high_risk("Discards uncommitted changes to the given paths.")
+        }
+        "restore" if !has(&["--staged", "-S"]) || has(&["--worktree", "-W"]) => {
+            high_risk("Discards uncommitted changes to the given paths.")
+        }
+        "stash" if matches!(first, Some("drop" | "clear")) => {
+            high_risk("Permanently drops stashed changes.")
+        }
+        "reflog" if matches!(first, Some("expire" | "delete")) => {
+            high_risk("Removes reflog entries needed to recover lost commits.")
+        }
+        "update-ref" if has(&["-d"]) => high_risk("Deletes a ref."),
+        "remote" if matches!(first, Some("remove" | "rm" | "rename" | "set-url")) => {
+            high_risk("Changes where the repository fetches from and pushes to.")
+        }
+        "branch" | "tag" => {
+            let modifies = has(&[
+                "-d",
+                "--delete",
+                "-m",
+                "-M",
+                "--move",
+                "-c",
+                "-C",
+                "--copy",
+                "-f",
+                "--force",
+                "-u",
+                "--set-upstream-to",
+                "--unset-upstream",
+            ]);
+            let lists = rest.iter().all(|arg| arg.starts_with('-'))
+                || has(&["-l", "--list", "--show-current"]);
+            if lists && !modifies {
+                Ok(GitRisk::Read)
+            } else {
+                Ok(GitRisk::Write)
+            }
+        }
+        "stash" if matches!(first, Some("list" | "show")) => Ok(GitRisk::Read),
+        "reflog" if matches!(first, None | Some("show")) => Ok(GitRisk::Read),
+        "worktree" if first == Some("list") => Ok(GitRisk::Read),
+        "remote" if matches!(first, None | Some("-v" | "--verbose" | "show" | "get-url")) => {
+            Ok(GitRisk::Read)
+        }
+        "config" if has(&["--get", "--get-all", "--get-regexp", "--list", "-l"]) => {
+            Ok(GitRisk::Read)
+        }
+        "add" | "am" | "apply" | "bisect" | "checkout" | "cherry-pick" | "clone" | "commit"
+        | "config" | "fetch" | "gc" | "init" | "merge" | "mv" | "notes" | "pull" | "rebase"
+        | "remote" | "reset" | "restore" | "revert" | "rm" | "stash" | "switch" | "clean"
+        | "reflog" | "update-ref" | "worktree" | "submodule" => Ok(GitRisk::Write),
+        _ => high_risk("Unrecognized git subcommand; it may be an alias that runs anything."),
+    }
+}
+
+/// Whether `git checkout` is given paths, whose uncommitted changes it
+/// overwrites. Without `--`, git takes every operand after the first as a
+/// path, and the first one too when it is not a revision, which can only be
+/// guessed from its shape here.
+fn checkout_has_paths(args: &[String]) -> bool {
+    let mut operands = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" | "--pathspec-from-file" => return true,
+            "-b" | "-B" | "--orphan" | "--conflict" => {
+                args.next();
+            }
+            arg if arg.starts_with("--pathspec-from-file=") => return true,
+            arg if arg.starts_with('-') => {}
+            arg => operands.push(arg),
+        }
+    }
+    operands.len() > 1
+        || operands.iter().any(|operand| {
+            *operand == "."
+                || operand.starts_with("./")
+                || operand.starts_with("../")
+                || operand.starts_with('/')
+                || operand.starts_with(':')
+                || operand.contains(['*', '?', '['])
+        })
+}
+
+/// Build the text shown to the user when approving a high-risk command:
+/// `consequence`, followed by what would actually be lost or published when
+/// that can be determined from the local repository.
+pub(crate) async fn consequence_preview(args: &[String], cwd: &Path, consequence: &str) -> String {
+    let mut preview = consequence.to_string();
+    let rest = args.get(1..).unwrap_or_default();
+    match args.first().map(String::as_str) {
+        Some("push") => {
+            append_section(
+                &mut preview,
+                "Commits not yet on the upstream branch:",
+                run_git(&["log", "--oneline", "@{upstream}..HEAD"], cwd).await,
+            );
+        }
+        Some("reset") => {
+            append_section(
+                &mut preview,
+                "Uncommitted changes that will be lost:",
+                run_git(&["status", "--short", "--untracked-files=no"], cwd).await,
+            );
+            if let Some(target) = rest.iter().find(|arg| !arg.starts_with('-')) {
+                let range = format!("{target}..HEAD");
+                append_section(
+                    &mut preview,
+                    "Commits that will no longer be on the current branch:",
+                    run_git(&["log", "--oneline", &range], cwd).await,
+                );
+            }
+        }
+        Some("clean") => {
+            let mut dry_run: Vec<&str> = vec!["clean"];
+            dry_run.extend(
+                rest.iter()
+                    .map(String::as_str)
+                    .filter(|arg| !matches!(*arg, "-i" | "--interactive")),
+            );
+            dry_run.push("--dry-run");
+            append_section(
+                &mut preview,
+                "Files that will be deleted:",
+                run_git(&dry_run, cwd).await,
+            );
+        }
+        Some("checkout" | "restore" | "switch") => {
+            append_section(
+                &mut preview,
+                "Uncommitted changes that may be lost:",
+                run_git(&["status", "--short", "--untracked-files=no"], cwd).await,
+            );
+        }
+        Some("rebase" | "commit") => {
+            append_section(
+                &mut preview,
+                "Current branch:",
+                run_git(&["log", "--oneline", "-5"], cwd).await,
+            );
+        }
+        _ => {}
+    }
+    preview
+}
+
+fn append_section(preview: &mut String, heading: &str, output: Option<String>) {
+    let Some(output) = output else {
+        return;
+    };
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return;
+    }
+    preview.push_str("\n\n");
+    preview.push_str(heading);
+    for line in lines.iter().take(PREVIEW_MAX_LINES) {
+        preview.push_str("\n  ");
+        preview.push_str(line);
+    }
+    if lines.len() > PREVIEW_MAX_LINES {
+        let more = lines.len() - PREVIEW_MAX_LINES;
+        preview.push_str(&format!("\n  … and {more} more"));
+    }
+}
+
+async fn run_git(args: &[&str], cwd: &Path) -> Option<String> {
+    let output = timeout(
+        PREVIEW_COMMAND_TIMEOUT,
+        Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn classify(command: &str) -> Result<GitRisk, String> {
+        let args: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+        classify_git_command(&args)
+    }
+
+    fn is_high_risk(command: &str) -> bool {
+        matches!(classify(command), Ok(GitRisk::HighRisk { .. }))
+    }
+
+    #[test]
+    fn classifies_subcommands() {
+        for command in [
+            "status",
+            "diff --stat",
+            "log --oneline -5",
+            "show HEAD~1",
+            "branch",
+            "branch -a",
+            "tag -l v*",
+            "stash list",
+            "remote -v",
+            "config --get user.name",
+        ] {
+            assert_eq!(classify(command), Ok(GitRisk::Read), "{command}");
+        }
+
+        for command in [
+            "add .",
+            "commit -m msg",
+            "checkout main",
+            "checkout -b topic origin/main",
+            "checkout feature/login",
+            "branch topic",
+            "branch -d topic",
+            "stash",
+            "fetch origin",
+            "reset HEAD~1",
+            "diff --output=out.patch",
+            "show --output=out.patch HEAD",
+            "log -o out.patch",
+        ] {
+            assert_eq!(classify(command), Ok(GitRisk::Write), "{command}");
+        }
+
+        for command in [
+            "push",
+            "push --force origin main",
+            "push origin :topic",
+            "reset --hard HEAD~2",
+            "clean -fdx",
+            "clean --force",
+            "filter-branch --tree-filter true",
+            "rebase -i main",
+            "commit --amend",
+            "branch -D topic",
+            "checkout -- src/lib.rs",
+            "checkout HEAD~1 src/lib.rs",
+            "checkout main src",
+            "checkout .",
+            "checkout ./src/lib.rs",
+            "checkout *.rs",
+            "checkout --pathspec-from-file=paths.txt",
+            "restore src/lib.rs",
+            "stash drop",
+            "remote set-url origin x",
+            "my-alias",
+        ] {
+            assert!(is_high_risk(command), "{command}");
+        }
+
+        assert!(classify("").is_err());
+        assert!(classify("-c alias.st=!rm status").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn preview_lists_files_clean_would_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let init = std::process::Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(init.success());
+        std::fs::write(dir.path().join("scratch.txt"), "tmp").unwrap();
+
+        let args = vec!["clean".to_string(), "-fd".to_string()];
+        let preview = consequence_preview(&args, dir.path(), "Deletes files.").await;
+
+        assert_eq!(
+            preview,
+            "Deletes files.\n\nFiles that will be deleted:\n  Would remove scratch.txt"
+        );
+        assert!(dir.path().join("scratch.txt").exists());
+    }
+}
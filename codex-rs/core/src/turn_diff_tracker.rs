@@ -11,6 +11,7 @@ use sha1::digest::Output;
 use uuid::Uuid;
 
 use crate::protocol::FileChange;
+use crate::protocol::PatchRisk;
 
 const ZERO_OID: &str = "0000000000000000000000000000000000000000";
 const DEV_NULL: &str = "/dev/null";
@@ -40,6 +41,8 @@ pub struct TurnDiffTracker {
     temp_name_to_current_path: HashMap<String, PathBuf>,
     /// Cache of known git worktree roots to avoid repeated filesystem walks.
     git_root_cache: Vec<PathBuf>,
+    /// Combined risk of the patches seen so far.
+    patch_risk: PatchRisk,
 }
 
 impl TurnDiffTracker {
@@ -47,6 +50,16 @@ impl TurnDiffTracker {
         Self::default()
     }
 
+    pub fn add_patch_risk(&mut self, risk: PatchRisk) {
+        self.patch_risk.merge(risk);
+    }
+
+    /// Combined risk of the patches seen this turn, or `None` if none of them
+    /// looked risky.
+    pub fn patch_risk(&self) -> Option<PatchRisk> {
+        self.patch_risk.clone().into_option()
+    }
+
     /// Front-run apply patch calls to track the starting contents of any modified files.
     /// - Creates an in-memory baseline snapshot for files that already exist on disk when first seen.
     /// - For additions, we intentionally do not create a baseline snapshot so that diffs are proper additions.
@@ -0,0 +1,921 @@
+//! Attaching web pages and raw files to a turn.
+//!
+//! Users reference a URL either by mentioning it as `@https://…` in their
+//! message or through an explicit `InputItem::Url` (`codex exec --attach-url`).
+//! Before the turn starts every referenced URL is fetched, HTML is reduced to
+//! markdown, and the result is appended to the user message inside a
+//! `<url_attachment>` block. Fetched pages are cached for the lifetime of the
+//! session so repeated mentions do not hit the network again.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_protocol::protocol::InputItem;
+use reqwest::Url;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::header::LOCATION;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::config_types::ProxyConfig;
+use crate::config_types::UrlAttachments as UrlAttachmentsConfig;
+use crate::default_client::get_codex_user_agent;
+use crate::default_client::with_network_settings;
+use crate::fetch_url::check_sandbox_network;
+use crate::protocol::SandboxPolicy;
+
+/// Opening tag of the text item that carries an attachment. Used to keep
+/// attachments out of the user message shown in the UI.
+pub(crate) const URL_ATTACHMENT_OPEN_TAG: &str = "<url_attachment";
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_REDIRECTS: usize = 5;
+
+/// Elements whose content is never useful as text.
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "svg", "template", "iframe", "nav",
+];
+
+/// Characters stripped from the end of an `@https://…` mention so trailing
+/// punctuation in prose is not treated as part of the URL.
+const TRAILING_PUNCTUATION: &[char] =
+    &['.', ',', ';', ':', '!', '?', ')', ']', '}', '>', '"', '\''];
+
+#[derive(Debug)]
+struct Attachment {
+    title: Option<String>,
+    text: String,
+    truncated: bool,
+    /// Every URL requested to get the page, redirects included.
+    hops: Vec<Url>,
+}
+
+pub(crate) struct UrlAttachments {
+    config: UrlAttachmentsConfig,
+    /// `[network.proxy]`, the only proxy pages are fetched through.
+    proxy: Option<ProxyConfig>,
+    /// Extra root certificates, those of the model provider.
+    ca_certificates: Vec<PathBuf>,
+    client: reqwest::Client,
+    cache: Mutex<HashMap<String, Arc<Attachment>>>,
+}
+
+impl UrlAttachments {
+    pub(crate) fn new(
+        config: UrlAttachmentsConfig,
+        proxy: Option<ProxyConfig>,
+        ca_certificates: Vec<PathBuf>,
+    ) -> Self {
+        let client = with_network_settings(base_client_builder(), proxy.as_ref(), &ca_certificates)
+            .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+            .unwrap_or_else(|err| {
+                warn!("failed to apply the network settings to URL attachments: {err}");
+                base_client_builder()
+                    .build()
+                    .unwrap_or_else(|_| reqwest::Client::new())
+            });
+        Self {
+            config,
+            proxy,
+            ca_certificates,
+            client,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fetches every URL referenced by `input` and appends the attachments to
+    /// it. Returns the expanded input and one notice per URL describing what
+    /// was attached or why it was not.
+    pub(crate) async fn expand(&self, input: Vec<InputItem>) -> (Vec<InputItem>, Vec<String>) {
+        if !self.config.enabled {
+            return (input, Vec::new());
+        }
+
+        let mut items = Vec::with_capacity(input.len());
+        let mut mentioned: Vec<String> = Vec::new();
+        let mut explicit: Vec<String> = Vec::new();
+        for item in input {
+            match item {
+                InputItem::Url { url } => {
+                    if !explicit.contains(&url) {
+                        explicit.push(url);
+                    }
+                }
+                InputItem::Text { ref text } => {
+                    for url in mentioned_urls(text) {
+                        if !mentioned.iter().any(|u| u == url) {
+                            mentioned.push(url.to_string());
+                        }
+                    }
+                    items.push(item);
+                }
+                other => items.push(other),
+            }
+        }
+        mentioned.retain(|url| !explicit.contains(url));
+
+        let mut notices = Vec::new();
+        for (url, is_explicit) in explicit
+            .into_iter()
+            .map(|url| (url, true))
+            .chain(mentioned.into_iter().map(|url| (url, false)))
+        {
+            match self.attachment(&url, None).await {
+                Ok(attachment) => {
+                    let truncated = if attachment.truncated {
+                        ", truncated"
+                    } else {
+                        ""
+                    };
+                    notices.push(format!(
+                        "Attached {url} ({} characters{truncated})",
+                        attachment.text.chars().count()
+                    ));
+                    items.push(InputItem::Text {
+                        text: format_attachment(&url, &attachment),
+                    });
+                }
+                Err(err) => {
+                    notices.push(format!("Could not attach {url}: {err}"));
+                    // Mentions are already part of the message text; explicit
+                    // URLs would otherwise be lost entirely.
+                    if is_explicit {
+                        items.push(InputItem::Text { text: url });
+                    }
+                }
+            }
+        }
+        (items, notices)
+    }
+
+    /// Fetches `url` for the `fetch_url` tool. Uses the same size limit,
+    /// domain policy and cache as attachments, even when attaching mentioned
+    /// URLs is disabled. Every hop, redirects included, must also be
+    /// reachable under `sandbox_policy`.
+    pub(crate) async fn fetch_page(
+        &self,
+        url: &str,
+        sandbox_policy: &SandboxPolicy,
+    ) -> Result<String, String> {
+        let attachment = self.attachment(url, Some(sandbox_policy)).await?;
+        Ok(format_attachment(url, &attachment))
+    }
+
+    async fn attachment(
+        &self,
+        url: &str,
+        sandbox_policy: Option<&SandboxPolicy>,
+    ) -> Result<Arc<Attachment>, String> {
+        let cached = self.cache.lock().await.get(url).cloned();
+        if let Some(cached) = cached {
+            // A page attached by the user may have been reached through
+            // hosts the sandbox does not allow.
+            if let Some(sandbox_policy) = sandbox_policy {
+                for hop in &cached.hops {
+                    check_sandbox_network(hop.as_str(), sandbox_policy)?;
+                }
+            }
+            return Ok(cached);
+        }
+        let attachment = Arc::new(self.fetch(url, sandbox_policy).await?);
+        self.cache
+            .lock()
+            .await
+            .insert(url.to_string(), attachment.clone());
+        Ok(attachment)
+    }
+
+    async fn fetch(
+        &self,
+        url: &str,
+        sandbox_policy: Option<&SandboxPolicy>,
+    ) -> Result<Attachment, String> {
+        let mut current = Url::parse(url).map_err(|e| format!("invalid URL: {e}"))?;
+        let mut hops = Vec::new();
+        for _ in 0..=MAX_REDIRECTS {
+            if let Some(sandbox_policy) = sandbox_policy {
+                check_sandbox_network(current.as_str(), sandbox_policy)?;
+            }
+            hops.push(current.clone());
+            let client = self.check_destination(&current).await?;
+            let response = client
+                .get(current.clone())
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or_else(|| format!("server returned {status} without a location"))?;
+                current = current
+                    .join(location)
+                    .map_err(|e| format!("invalid redirect location: {e}"))?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(format!("server returned {status}"));
+            }
+            let mut attachment = read_attachment(response, &current, self.config.max_bytes).await?;
+            attachment.hops = hops;
+            return Ok(attachment);
+        }
+        Err("too many redirects".to_string())
+    }
+
+    /// Applies the domain policy to `url` and refuses hosts on the local
+    /// network unless they were explicitly allowed. Returns the client to
+    /// fetch `url` with: for other host names, one that connects only to the
+    /// addresses checked here, so a second DNS answer cannot point the request
+    /// at the local network.
+    async fn check_destination(&self, url: &Url) -> Result<reqwest::Client, String> {
+        check_url_policy(url, &self.config)?;
+        let host = url_host(url).ok_or_else(|| "URL has no host".to_string())?;
+        if matches_domain(&host, &self.config.allowed_domains) || host.parse::<IpAddr>().is_ok() {
+            return Ok(self.client.clone());
+        }
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+            .await
+            .map_err(|e| format!("could not resolve {host}: {e}"))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(format!("could not resolve {host}"));
+        }
+        if addrs.iter().any(|addr| is_local_address(addr.ip())) {
+            return Err(format!(
+                "{host} resolves to a local address; add it to url_attachments.allowed_domains to attach it"
+            ));
+        }
+        with_network_settings(
+            base_client_builder(),
+            self.proxy.as_ref(),
+            &self.ca_certificates,
+        )?
+        .resolve_to_addrs(&host, &addrs)
+        .build()
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Redirects are followed manually so every hop is checked against the
+/// domain policy. Proxies named by environment variables are ignored: a proxy
+/// resolves host names itself, so the addresses checked here would not be the
+/// ones connected to. A `[network.proxy]` is applied on top, and then resolves
+/// the hosts it is used for.
+fn base_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .user_agent(get_codex_user_agent())
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(FETCH_TIMEOUT)
+        .no_proxy()
+}
+
+/// Checks the parts of the policy that do not need DNS: scheme, denied and
+/// allowed domains, and literal local addresses.
+fn check_url_policy(url: &Url, config: &UrlAttachmentsConfig) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("only http and https URLs can be attached".to_string());
+    }
+    let host = url_host(url).ok_or_else(|| "URL has no host".to_string())?;
+    if matches_domain(&host, &config.denied_domains) {
+        return Err(format!(
+            "{host} is listed in url_attachments.denied_domains"
+        ));
+    }
+    if !config.allowed_domains.is_empty() {
+        return if matches_domain(&host, &config.allowed_domains) {
+            Ok(())
+        } else {
+            Err(format!(
+                "{host} is not listed in url_attachments.allowed_domains"
+            ))
+        };
+    }
+    let is_local = host == "localhost"
+        || host.ends_with(".localhost")
+        || host.parse::<IpAddr>().is_ok_and(is_local_address);
+    if is_local {
+        return Err(format!(
+            "{host} is a local address; add it to url_attachments.allowed_domains to attach it"
+        ));
+    }
+    Ok(())
+}
+
+/// Lowercase host without the brackets around IPv6 literals.
+fn url_host(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(
+        host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase(),
+    )
+}
+
+/// True when `host` equals one of `domains` or is a subdomain of one.
+fn matches_domain(host: &str, domains: &[String]) -> bool {
+    domains.iter().any(|domain| {
+        let domain = domain
+            .trim()
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .to_ascii_lowercase();
+        !domain.is_empty()
+            && (host == domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    })
+}
+
+fn is_local_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Carrier-grade NAT (100.64.0.0/10).
+                || (a == 100 && (b & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                // Unique local (fc00::/7) and link-local (fe80::/10).
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || ip
+                    .to_ipv4_mapped()
+                    .is_some_and(|ip| is_local_address(IpAddr::V4(ip)))
+        }
+    }
+}
+
+/// URLs mentioned as `@http://…` or `@https://…` in `text`.
+fn mentioned_urls(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace().filter_map(|token| {
+        let url = token.strip_prefix('@')?;
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return None;
+        }
+        Some(url.trim_end_matches(TRAILING_PUNCTUATION))
+    })
+}
+
+enum ContentKind {
+    Html,
+    Text,
+}
+
+impl ContentKind {
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match essence.as_str() {
+            "text/html" | "application/xhtml+xml" => Some(Self::Html),
+            // Raw files are frequently served without a content type.
+            "" => Some(Self::Text),
+            "application/json"
+            | "application/xml"
+            | "application/javascript"
+            | "application/x-yaml"
+            | "application/yaml"
+            | "application/toml" => Some(Self::Text),
+            _ if essence.starts_with("text/")
+                || essence.ends_with("+json")
+                || essence.ends_with("+xml") =>
+            {
+                Some(Self::Text)
+            }
+            _ => None,
+        }
+    }
+}
+
+async fn read_attachment(
+    mut response: reqwest::Response,
+    url: &Url,
+    max_bytes: u64,
+) -> Result<Attachment, String> {
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let kind = ContentKind::from_content_type(&content_type)
+        .ok_or_else(|| format!("unsupported content type {content_type}"))?;
+
+    let limit = usize::try_from(max_bytes).unwrap_or(usize::MAX);
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        let remaining = limit.saturating_sub(body.len());
+        if chunk.len() > remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let body = String::from_utf8_lossy(&body);
+    let (title, text) = match kind {
+        ContentKind::Html => (extract_title(&body), html_to_markdown(&body, Some(url))),
+        ContentKind::Text => (None, body.into_owned()),
+    };
+    Ok(Attachment {
+        title,
+        text,
+        truncated,
+        hops: Vec::new(),
+    })
+}
+
+fn format_attachment(url: &str, attachment: &Attachment) -> String {
+    let mut open = format!(
+        "{URL_ATTACHMENT_OPEN_TAG} url=\"{}\"",
+        url.replace('"', "&quot;")
+    );
+    if let Some(title) = &attachment.title {
+        open.push_str(&format!(" title=\"{}\"", title.replace('"', "&quot;")));
+    }
+    if attachment.truncated {
+        open.push_str(" truncated=\"true\"");
+    }
+    format!("{open}>\n{}\n</url_attachment>", attachment.text.trim())
+}
+
+fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let content_start = start + lower[start..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</title")?;
+    let title = decode_entities(&html[content_start..content_end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// Reduces an HTML document to markdown: headings, paragraphs, lists, links
+/// and code blocks survive, scripts, styles and navigation are dropped.
+fn html_to_markdown(html: &str, base: Option<&Url>) -> String {
+    let mut writer = MarkdownWriter {
+        base,
+        ..MarkdownWriter::default()
+    };
+    let mut rest = html;
+    while let Some(lt) = rest.find('<') {
+        writer.text(&rest[..lt]);
+        rest = &rest[lt..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            // Doctype, processing instruction or a stray `<`.
+            continue;
+        }
+        if !closing && !tag.ends_with('/') && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            rest = skip_element(rest, &name);
+            continue;
+        }
+        writer.tag(&name, closing, tag);
+    }
+    writer.text(rest);
+    writer.finish()
+}
+
+/// Returns the input following the closing tag of `name`.
+fn skip_element<'a>(rest: &'a str, name: &str) -> &'a str {
+    let close = format!("</{name}");
+    // ASCII lowercasing keeps byte offsets intact.
+    match rest.to_ascii_lowercase().find(&close) {
+        Some(start) => {
+            let after = &rest[start..];
+            after.find('>').map_or("", |gt| &after[gt + 1..])
+        }
+        None => "",
+    }
+}
+
+#[derive(Default)]
+struct MarkdownWriter<'a> {
+    base: Option<&'a Url>,
+    out: String,
+    pre_depth: usize,
+    list_depth: usize,
+    /// Start offset in `out` and target of every open `<a>`.
+    links: Vec<(usize, Option<String>)>,
+}
+
+impl MarkdownWriter<'_> {
+    fn text(&mut self, raw: &str) {
+        let text = decode_entities(raw);
+        if self.pre_depth > 0 {
+            self.out.push_str(&text);
+            return;
+        }
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+                    self.out.push(' ');
+                }
+            } else {
+                self.out.push(c);
+            }
+        }
+    }
+
+    fn tag(&mut self, name: &str, closing: bool, raw: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.block_break();
+                if !closing {
+                    let level = usize::from(name.as_bytes()[1] - b'0');
+                    self.out.push_str(&"#".repeat(level));
+                    self.out.push(' ');
+                }
+            }
+            "p" | "section" | "article" | "main" | "header" | "footer" | "table" | "blockquote"
+            | "figure" | "dl" => self.block_break(),
+            "div" | "tr" | "dt" | "dd" => self.line_break(),
+            "ul" | "ol" => {
+                if closing {
+                    self.list_depth = self.list_depth.saturating_sub(1);
+                } else {
+                    self.list_depth += 1;
+                }
+                if self.list_depth == 0 {
+                    self.block_break();
+                } else {
+                    self.line_break();
+                }
+            }
+            "li" if !closing => {
+                self.line_break();
+                self.out
+                    .push_str(&"  ".repeat(self.list_depth.saturating_sub(1)));
+                self.out.push_str("- ");
+            }
+            "br" => {
+                self.trim_trailing_spaces();
+                self.out.push('\n');
+            }
+            "hr" => {
+                self.block_break();
+                self.out.push_str("---");
+                self.block_break();
+            }
+            "td" | "th" if closing => self.out.push_str(" | "),
+            "pre" => {
+                if closing {
+                    if self.pre_depth > 0 {
+                        self.pre_depth -= 1;
+                        if !self.out.ends_with('\n') {
+                            self.out.push('\n');
+                        }
+                        self.out.push_str("```");
+                        self.block_break();
+                    }
+                } else {
+                    self.block_break();
+                    self.out.push_str("```\n");
+                    self.pre_depth += 1;
+                }
+            }
+            "code" if self.pre_depth == 0 => self.out.push('`'),
+            "a" => {
+                if closing {
+                    self.close_link();
+                } else {
+                    let href = attribute(raw, "href").and_then(|href| self.resolve_link(&href));
+                    self.links.push((self.out.len(), href));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve_link(&self, href: &str) -> Option<String> {
+        if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+            return None;
+        }
+        match self.base {
+            Some(base) => base.join(href).ok().map(String::from),
+            None => Some(href.to_string()),
+        }
+    }
+
+    fn close_link(&mut self) {
+        let Some((start, Some(href))) = self.links.pop() else {
+            return;
+        };
+        let Some(link_text) = self.out.get(start..) else {
+            return;
+        };
+        let start = start + (link_text.len() - link_text.trim_start().len());
+        let link_text = self.out[start..].trim_end().to_string();
+        if link_text.is_empty() || link_text.contains('\n') {
+            return;
+        }
+        self.out.truncate(start);
+        self.out.push_str(&format!("[{link_text}]({href})"));
+    }
+
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(trimmed);
+    }
+
+    fn line_break(&mut self) {
+        self.trim_trailing_spaces();
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn block_break(&mut self) {
+        self.line_break();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut result = String::with_capacity(self.out.len());
+        let mut blank_lines = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+        result.trim().to_string()
+    }
+}
+
+/// Value of attribute `name` in the raw tag contents, e.g. `a href="…"`.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets intact.
+    let lower = tag.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(pos) = lower[search_from..].find(name) {
+        let start = search_from + pos;
+        search_from = start + name.len();
+        if !lower[..start].ends_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+        let Some(value) = tag[search_from..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split_ascii_whitespace().next().unwrap_or_default(),
+        };
+        return Some(decode_entities(value).into_owned());
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            return char::from_u32(code);
+        }
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn config(allowed: &[&str], denied: &[&str]) -> UrlAttachmentsConfig {
+        UrlAttachmentsConfig {
+            allowed_domains: allowed.iter().map(ToString::to_string).collect(),
+            denied_domains: denied.iter().map(ToString::to_string).collect(),
+            ..UrlAttachmentsConfig::default()
+        }
+    }
+
+    fn check(url: &str, config: &UrlAttachmentsConfig) -> Result<(), String> {
+        check_url_policy(&Url::parse(url).unwrap(), config)
+    }
+
+    #[tokio::test]
+    async fn fetch_page_checks_redirects_against_the_sandbox_allowlist() {
+        use wiremock::Mock;
+        use wiremock::MockServer;
+        use wiremock::ResponseTemplate;
+        use wiremock::matchers::method;
+        use wiremock::matchers::path;
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/docs"))
+            .respond_with(
+                ResponseTemplate::new(302).insert_header("location", "http://example.com/leak"),
+            )
+            .mount(&server)
+            .await;
+        let attachments =
+            UrlAttachments::new(config(&["127.0.0.1", "example.com"], &[]), None, Vec::new());
+        let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: Vec::new(),
+            network_access: false,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            network_allowlist: vec!["127.0.0.1".to_string()],
+            deny: Vec::new(),
+        };
+
+        let err = attachments
+            .fetch_page(&format!("{}/docs", server.uri()), &sandbox_policy)
+            .await
+            .expect_err("the redirect leaves the allowlist");
+        assert!(
+            err.contains("does not allow network access to example.com"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_are_fetched_through_the_configured_proxy() {
+        use wiremock::Mock;
+        use wiremock::MockServer;
+        use wiremock::ResponseTemplate;
+        use wiremock::matchers::method;
+        use wiremock::matchers::path;
+
+        // The proxy receives requests for other hosts in absolute form.
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("via the proxy", "text/plain"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+        let attachments = UrlAttachments::new(
+            config(&["example.com"], &[]),
+            Some(ProxyConfig {
+                url: proxy.uri(),
+                no_proxy: Vec::new(),
+            }),
+            Vec::new(),
+        );
+
+        let page = attachments
+            .fetch_page("http://example.com/page", &SandboxPolicy::DangerFullAccess)
+            .await
+            .expect("fetch through the proxy");
+        assert!(page.contains("via the proxy"), "{page}");
+    }
+
+    #[test]
+    fn mentions_strip_trailing_punctuation() {
+        let urls: Vec<&str> = mentioned_urls(
+            "See @https://docs.rs/tokio. Also @http://example.com/a?b=1), but not @src/main.rs or https://x.y",
+        )
+        .collect();
+        assert_eq!(
+            urls,
+            vec!["https://docs.rs/tokio", "http://example.com/a?b=1"]
+        );
+    }
+
+    #[test]
+    fn policy_applies_domain_lists_and_blocks_local_hosts() {
+        let open = config(&[], &["evil.com"]);
+        assert!(check("https://docs.rs/x", &open).is_ok());
+        assert!(check("https://api.evil.com/x", &open).is_err());
+        assert!(check("https://notevil.com/x", &open).is_ok());
+        assert!(check("ftp://docs.rs/x", &open).is_err());
+        assert!(check("http://localhost:8080/", &open).is_err());
+        assert!(check("http://192.168.1.10/", &open).is_err());
+        assert!(check("http://[::1]/", &open).is_err());
+
+        let restricted = config(&["docs.rs", "127.0.0.1"], &[]);
+        assert!(check("https://docs.rs/x", &restricted).is_ok());
+        assert!(check("https://github.com/x", &restricted).is_err());
+        assert!(check("http://127.0.0.1:3000/", &restricted).is_ok());
+    }
+
+    #[test]
+    fn html_is_reduced_to_markdown() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Ignored &amp; gone</title><style>body { color: red }</style></head>
+<body>
+  <nav><a href="/">Home</a></nav>
+  <h1>Getting  started</h1>
+  <p>Install with <code>cargo add foo</code> &mdash; see the
+     <a href="/guide#setup">setup guide</a>.</p>
+  <ul><li>one</li><li>two<ul><li>nested</li></ul></li></ul>
+  <pre><code>fn main() {
+    println!("&lt;hi&gt;");
+}</code></pre>
+  <script>alert("x")</script>
+  <!-- comment -->
+  <p>Done</p>
+</body></html>"#;
+        let base = Url::parse("https://example.com/docs/").unwrap();
+        assert_eq!(
+            html_to_markdown(html, Some(&base)),
+            "# Getting started\n\nInstall with `cargo add foo` — see the [setup guide](https://example.com/guide#setup).\n\n- one\n- two\n  - nested\n\n```\nfn main() {\n    println!(\"<hi>\");\n}\n```\n\nDone"
+        );
+    }
+
+    #[test]
+    fn title_and_entities_are_decoded() {
+        assert_eq!(
+            extract_title("<html><head><TITLE>\n  A &amp; B &#8212; C&#x21;\n</title>"),
+            Some("A & B — C!".to_string())
+        );
+        assert_eq!(decode_entities("a &unknown; & b"), "a &unknown; & b");
+    }
+}
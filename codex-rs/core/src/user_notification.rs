@@ -1,10 +1,18 @@
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::protocol::Event;
 use serde::Serialize;
 use tracing::error;
 use tracing::warn;
 
+use crate::config_types::WebhookConfig;
+use crate::event_webhook::EventWebhooks;
+use crate::plugins::PluginHost;
+
 #[derive(Debug, Default)]
 pub(crate) struct UserNotifier {
     notify_command: Option<Vec<String>>,
+    webhooks: Option<EventWebhooks>,
+    plugins: PluginHost,
 }
 
 impl UserNotifier {
@@ -14,6 +22,19 @@ impl UserNotifier {
         {
             self.invoke_notify(notify_command, notification)
         }
+        self.plugins.notify(notification.clone().into());
+    }
+
+    /// Also deliver notifications to the channels of `plugins`.
+    pub(crate) fn with_plugins(self, plugins: PluginHost) -> Self {
+        Self { plugins, ..self }
+    }
+
+    /// Forward a protocol event to the configured webhooks, if any.
+    pub(crate) fn forward_event(&self, conversation_id: ConversationId, event: &Event) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch(conversation_id, event);
+        }
     }
 
     fn invoke_notify(&self, notify_command: &[String], notification: &UserNotification) {
@@ -34,9 +55,11 @@ impl UserNotifier {
         }
     }
 
-    pub(crate) fn new(notify: Option<Vec<String>>) -> Self {
+    pub(crate) fn new(notify: Option<Vec<String>>, webhooks: &[WebhookConfig]) -> Self {
         Self {
             notify_command: notify,
+            webhooks: EventWebhooks::new(webhooks),
+            plugins: PluginHost::default(),
         }
     }
 }
@@ -57,6 +80,43 @@ pub(crate) enum UserNotification {
         /// The last message sent by the assistant in the turn.
         last_assistant_message: Option<String>,
     },
+
+    /// The agent is waiting for the user to approve a command or a patch.
+    #[serde(rename_all = "kebab-case")]
+    ApprovalRequested {
+        turn_id: String,
+
+        call_id: String,
+
+        /// What is to be approved, such as the command line or the files
+        /// a patch edits.
+        summary: String,
+    },
+}
+
+impl From<UserNotification> for codex_plugin_api::Notification {
+    fn from(notification: UserNotification) -> Self {
+        match notification {
+            UserNotification::AgentTurnComplete {
+                turn_id,
+                input_messages,
+                last_assistant_message,
+            } => Self::AgentTurnComplete {
+                turn_id,
+                input_messages,
+                last_assistant_message,
+            },
+            UserNotification::ApprovalRequested {
+                turn_id,
+                call_id,
+                summary,
+            } => Self::ApprovalRequested {
+                turn_id,
+                call_id,
+                summary,
+            },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -80,4 +140,19 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_approval_notification() -> Result<()> {
+        let notification = UserNotification::ApprovalRequested {
+            turn_id: "7".to_string(),
+            call_id: "call-1".to_string(),
+            summary: "cargo publish".to_string(),
+        };
+        let serialized = serde_json::to_string(&notification)?;
+        assert_eq!(
+            serialized,
+            r#"{"type":"approval-requested","turn-id":"7","call-id":"call-1","summary":"cargo publish"}"#
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,284 @@
+//! The `web_search` function tool, for providers without a native search
+//! tool or users who want results from a particular search API.
+//!
+//! With `[web_search] backend = "native"` Codex sends the Responses API's own
+//! `web_search` tool and the provider runs the search. The other backends
+//! expose a `web_search` function instead: Codex queries the configured
+//! search API and returns the results to the model as text. Results are
+//! cached per query for the rest of the session unless `cache` is off.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::config_types::WebSearch as WebSearchConfig;
+use crate::config_types::WebSearchBackend;
+use crate::default_client::get_codex_user_agent;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const WEB_SEARCH_TOOL_NAME: &str = "web_search";
+
+const BRAVE_SEARCH_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct WebSearchArgs {
+    pub(crate) query: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SearchResult {
+    title: String,
+    url: String,
+    snippet: String,
+}
+
+pub(crate) fn create_web_search_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "query".to_string(),
+        JsonSchema::String {
+            description: Some("What to search the web for".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: WEB_SEARCH_TOOL_NAME.to_string(),
+        description: "Searches the web and returns the title, URL and a snippet of the top \
+                      results. Use it to look up documentation, error messages or anything \
+                      else that is not in the workspace."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["query".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+pub(crate) struct WebSearch {
+    config: WebSearchConfig,
+    client: reqwest::Client,
+    cache: Mutex<HashMap<String, Arc<String>>>,
+}
+
+impl WebSearch {
+    pub(crate) fn new(config: WebSearchConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(get_codex_user_agent())
+            .timeout(SEARCH_TIMEOUT)
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        Self {
+            config,
+            client,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `query` against the configured backend and returns the results
+    /// formatted for the model.
+    pub(crate) async fn search(&self, query: &str) -> Result<Arc<String>, String> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err("`query` must not be empty".to_string());
+        }
+        if self.config.cache
+            && let Some(cached) = self.cache.lock().await.get(query)
+        {
+            return Ok(cached.clone());
+        }
+        let results = match self.config.backend {
+            WebSearchBackend::Native => {
+                return Err(
+                    "web search runs on the provider's side with the native backend".to_string(),
+                );
+            }
+            WebSearchBackend::Brave => self.search_brave(query).await?,
+            WebSearchBackend::Searxng => self.search_searxng(query).await?,
+        };
+        let formatted = Arc::new(format_results(query, &results));
+        if self.config.cache {
+            self.cache
+                .lock()
+                .await
+                .insert(query.to_string(), formatted.clone());
+        }
+        Ok(formatted)
+    }
+
+    async fn search_brave(&self, query: &str) -> Result<Vec<SearchResult>, String> {
+        let url = self.config.url.as_deref().unwrap_or(BRAVE_SEARCH_URL);
+        let env_var = self
+            .config
+            .api_key_env
+            .as_deref()
+            .unwrap_or("BRAVE_API_KEY");
+        let api_key = std::env::var(env_var)
+            .map_err(|_| format!("set {env_var} to the Brave Search API key"))?;
+        let count = self.config.max_results.to_string();
+        let body = self
+            .get_json(
+                self.client
+                    .get(url)
+                    .query(&[("q", query), ("count", count.as_str())])
+                    .header("X-Subscription-Token", api_key),
+            )
+            .await?;
+        Ok(parse_results(&body["web"]["results"], "description"))
+    }
+
+    async fn search_searxng(&self, query: &str) -> Result<Vec<SearchResult>, String> {
+        let base_url = self
+            .config
+            .url
+            .as_deref()
+            .ok_or_else(|| "set web_search.url to the SearXNG instance".to_string())?;
+        let url = format!("{}/search", base_url.trim_end_matches('/'));
+        let mut request = self
+            .client
+            .get(url)
+            .query(&[("q", query), ("format", "json")]);
+        if let Some(env_var) = &self.config.api_key_env {
+            let api_key = std::env::var(env_var)
+                .map_err(|_| format!("set {env_var} to the SearXNG API key"))?;
+            request = request.bearer_auth(api_key);
+        }
+        let body = self.get_json(request).await?;
+        let mut results = parse_results(&body["results"], "content");
+        results.truncate(self.config.max_results);
+        Ok(results)
+    }
+
+    async fn get_json(&self, request: reqwest::RequestBuilder) -> Result<Value, String> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("search request failed: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("search API returned {status}"));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| format!("search API returned invalid JSON: {e}"))
+    }
+}
+
+/// Reads the `title`, `url` and snippet of each entry of `results`.
+fn parse_results(results: &Value, snippet_key: &str) -> Vec<SearchResult> {
+    let text = |result: &Value, key: &str| {
+        result
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    results
+        .as_array()
+        .map(|results| {
+            results
+                .iter()
+                .map(|result| SearchResult {
+                    title: text(result, "title"),
+                    url: text(result, "url"),
+                    snippet: text(result, snippet_key),
+                })
+                .filter(|result| !result.url.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn format_results(query: &str, results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return format!("No results for \"{query}\".");
+    }
+    let mut text = format!("Results for \"{query}\":\n");
+    for (index, result) in results.iter().enumerate() {
+        let _ = write!(
+            text,
+            "\n{}. {}\n   {}\n",
+            index + 1,
+            result.title,
+            result.url
+        );
+        if !result.snippet.is_empty() {
+            let _ = writeln!(text, "   {}", result.snippet);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+    use wiremock::matchers::query_param;
+
+    #[test]
+    fn formats_results_with_snippets() {
+        let body = json!({"web": {"results": [
+            {"title": "Tokio", "url": "https://tokio.rs", "description": "An async runtime."},
+            {"title": "No URL", "description": "Skipped."},
+            {"title": "Docs", "url": "https://docs.rs/tokio"}
+        ]}});
+
+        let results = parse_results(&body["web"]["results"], "description");
+
+        assert_eq!(
+            format_results("tokio", &results),
+            "Results for \"tokio\":\n\n1. Tokio\n   https://tokio.rs\n   An async runtime.\n\n2. Docs\n   https://docs.rs/tokio\n"
+        );
+        assert_eq!(format_results("x", &[]), "No results for \"x\".");
+    }
+
+    #[tokio::test]
+    async fn searxng_results_are_limited_and_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("q", "rust async"))
+            .and(query_param("format", "json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"results": [
+                {"title": "One", "url": "https://one.example", "content": "First."},
+                {"title": "Two", "url": "https://two.example", "content": "Second."}
+            ]})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let search = WebSearch::new(WebSearchConfig {
+            backend: WebSearchBackend::Searxng,
+            url: Some(server.uri()),
+            max_results: 1,
+            ..WebSearchConfig::default()
+        });
+
+        let first = search.search("rust async").await.expect("search");
+        let second = search.search(" rust async ").await.expect("cached search");
+
+        assert_eq!(
+            first.as_str(),
+            "Results for \"rust async\":\n\n1. One\n   https://one.example\n   First.\n"
+        );
+        assert_eq!(first, second);
+    }
+}
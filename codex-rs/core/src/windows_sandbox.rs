@@ -0,0 +1,89 @@
+use crate::config_types::ResourceLimits;
+use crate::protocol::SandboxPolicy;
+use crate::spawn::StdioPolicy;
+use crate::spawn::spawn_child_async;
+use codex_windows_sandbox::CODEX_WINDOWS_SANDBOX_ARG1;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use tokio::process::Child;
+
+/// Spawn a shell tool command under the Windows restricted-token sandbox.
+///
+/// The launcher is the current Codex executable invoked with
+/// [`CODEX_WINDOWS_SANDBOX_ARG1`]. It grants the sandbox write access to the
+/// policy's writable roots and then runs the command with a write-restricted
+/// token, so writes anywhere else fail with "access denied".
+pub async fn spawn_command_under_windows_sandbox(
+    command: Vec<String>,
+    command_cwd: PathBuf,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    resource_limits: &ResourceLimits,
+    stdio_policy: StdioPolicy,
+    env: HashMap<String, String>,
+) -> std::io::Result<Child> {
+    let codex_exe = std::env::current_exe()?;
+    let args = create_windows_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    spawn_child_async(
+        codex_exe,
+        args,
+        None,
+        command_cwd,
+        sandbox_policy,
+        resource_limits,
+        stdio_policy,
+        env,
+    )
+    .await
+}
+
+/// Converts the sandbox policy into the arguments of the Windows sandbox
+/// launcher.
+fn create_windows_sandbox_command_args(
+    command: Vec<String>,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+) -> Vec<String> {
+    #[expect(clippy::expect_used)]
+    let sandbox_policy_json =
+        serde_json::to_string(sandbox_policy).expect("Failed to serialize SandboxPolicy to JSON");
+
+    let mut windows_cmd = vec![
+        CODEX_WINDOWS_SANDBOX_ARG1.to_string(),
+        sandbox_policy_cwd.to_string_lossy().into_owned(),
+        sandbox_policy_json,
+        // Separator so that command arguments starting with `-` are not parsed as
+        // options of the launcher itself.
+        "--".to_string(),
+    ];
+    windows_cmd.extend(command);
+    windows_cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn launcher_args_carry_policy_and_command() {
+        let args = create_windows_sandbox_command_args(
+            vec!["cmd.exe".to_string(), "/c".to_string(), "dir".to_string()],
+            &SandboxPolicy::ReadOnly,
+            Path::new("/workspace"),
+        );
+        assert_eq!(
+            args,
+            vec![
+                CODEX_WINDOWS_SANDBOX_ARG1.to_string(),
+                "/workspace".to_string(),
+                r#"{"mode":"read-only"}"#.to_string(),
+                "--".to_string(),
+                "cmd.exe".to_string(),
+                "/c".to_string(),
+                "dir".to_string(),
+            ]
+        );
+    }
+}
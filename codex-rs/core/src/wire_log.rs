@@ -0,0 +1,352 @@
+//! Opt-in logging of the raw requests sent to model providers and the
+//! responses they stream back, for debugging provider incompatibilities.
+//!
+//! While wire logging is on for a conversation, every model request appends
+//! JSON lines to `CODEX_HOME/log/wire/<conversation id>.jsonl`: the request
+//! with its headers and body, the response status and headers, and each
+//! chunk of the response body as it arrives. Credentials in headers and query
+//! parameters are replaced with `[REDACTED]`: those whose names mention a key,
+//! token, secret or auth, cookies, and every header the provider's config
+//! adds. Bodies of error responses are
+//! not logged here; they are part of the error the request fails with.
+//!
+//! The wire API implementations do not know which conversation they serve,
+//! so `ModelClient` runs each request inside [`scope`] and the logging hooks
+//! pick the log up from the task.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use bytes::Bytes;
+use codex_protocol::mcp_protocol::ConversationId;
+use futures::Stream;
+use futures::StreamExt;
+use reqwest::RequestBuilder;
+use reqwest::Response;
+use reqwest::Url;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use serde_json::json;
+use tracing::warn;
+
+use crate::model_provider_info::ModelProviderInfo;
+
+/// Headers and query parameters whose lowercased names contain one of these
+/// are taken to carry credentials, e.g. `authorization`, `x-api-key` or
+/// `access_token`.
+const SECRET_NAME_PARTS: &[&str] = &[
+    "auth",
+    "key",
+    "token",
+    "secret",
+    "password",
+    "credential",
+    "signature",
+    "session",
+];
+
+/// Other headers whose values are credentials.
+const REDACTED_HEADERS: &[&str] = &["cookie", "set-cookie"];
+
+/// Other query parameters whose values are credentials, e.g. the signature
+/// of an Azure SAS URL.
+const REDACTED_QUERY_PARAMS: &[&str] = &["sig"];
+
+const REDACTED: &str = "[REDACTED]";
+
+static WIRE_LOGS: LazyLock<Mutex<HashMap<ConversationId, Arc<WireLog>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+tokio::task_local! {
+    static CURRENT: Option<Arc<LogScope>>;
+}
+
+#[derive(Debug)]
+pub(crate) struct WireLog {
+    file: Mutex<File>,
+}
+
+/// The wire log of the conversation a request is sent for, and what to
+/// redact in it.
+#[derive(Debug)]
+struct LogScope {
+    log: Arc<WireLog>,
+    /// Lowercased names of the headers the provider's config adds with
+    /// `http_headers` and `env_http_headers`. Their values are always
+    /// redacted: the config is where custom credentials are set.
+    provider_headers: Vec<String>,
+}
+
+/// File the wire log of `conversation_id` is written to.
+pub fn wire_log_path(codex_home: &Path, conversation_id: ConversationId) -> PathBuf {
+    codex_home
+        .join("log")
+        .join("wire")
+        .join(format!("{conversation_id}.jsonl"))
+}
+
+/// Turns wire logging for `conversation_id` on or off, and returns the log
+/// file while it is on. Turning it on again keeps appending to the same file.
+pub fn set_wire_logging(
+    codex_home: &Path,
+    conversation_id: ConversationId,
+    enabled: bool,
+) -> std::io::Result<Option<PathBuf>> {
+    let mut logs = WIRE_LOGS.lock().unwrap_or_else(PoisonError::into_inner);
+    if !enabled {
+        logs.remove(&conversation_id);
+        return Ok(None);
+    }
+
+    let path = wire_log_path(codex_home, conversation_id);
+    if let Entry::Vacant(entry) = logs.entry(conversation_id) {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(&path)?;
+        entry.insert(Arc::new(WireLog {
+            file: Mutex::new(file),
+        }));
+    }
+    Ok(Some(path))
+}
+
+/// Runs `fut`, which sends a model request to `provider`, with the wire log
+/// of `conversation_id` available to the logging hooks.
+pub(crate) fn scope<F: Future>(
+    conversation_id: ConversationId,
+    provider: &ModelProviderInfo,
+    fut: F,
+) -> impl Future<Output = F::Output> + use<F> {
+    let log = WIRE_LOGS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&conversation_id)
+        .cloned();
+    let scope = log.map(|log| {
+        let provider_headers = provider
+            .http_headers
+            .iter()
+            .chain(provider.env_http_headers.iter())
+            .flat_map(HashMap::keys)
+            .map(|name| name.to_ascii_lowercase())
+            .collect();
+        Arc::new(LogScope {
+            log,
+            provider_headers,
+        })
+    });
+    CURRENT.scope(scope, fut)
+}
+
+fn current() -> Option<Arc<LogScope>> {
+    CURRENT.try_with(Clone::clone).ok().flatten()
+}
+
+/// Logs the request `builder` is about to send.
+pub(crate) fn log_request(builder: &RequestBuilder) {
+    let Some(scope) = current() else {
+        return;
+    };
+    let Some(request) = builder.try_clone().and_then(|builder| builder.build().ok()) else {
+        return;
+    };
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map_or(Value::Null, body_value);
+    scope.log.write(json!({
+        "type": "request",
+        "method": request.method().as_str(),
+        "url": redact_url(request.url()),
+        "headers": redact_headers(request.headers(), &scope.provider_headers),
+        "body": body,
+    }));
+}
+
+/// Logs the status and headers of `response`.
+pub(crate) fn log_response(response: &Response) {
+    let Some(scope) = current() else {
+        return;
+    };
+    scope.log.write(json!({
+        "type": "response",
+        "status": response.status().as_u16(),
+        "headers": redact_headers(response.headers(), &scope.provider_headers),
+    }));
+}
+
+/// Logs each chunk of a response body as `stream` yields it.
+pub(crate) fn tee<S, E>(stream: S) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    let scope = current();
+    stream.inspect(move |chunk| {
+        if let (Some(scope), Ok(chunk)) = (&scope, chunk) {
+            scope.log.write(json!({
+                "type": "response_chunk",
+                "data": String::from_utf8_lossy(chunk),
+            }));
+        }
+    })
+}
+
+impl WireLog {
+    fn write(&self, mut entry: Value) {
+        if let Value::Object(entry) = &mut entry {
+            entry.insert(
+                "timestamp".to_string(),
+                Value::String(chrono::Utc::now().to_rfc3339()),
+            );
+        }
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = writeln!(file, "{entry}") {
+            warn!("failed to write wire log: {e}");
+        }
+    }
+}
+
+fn body_value(body: &[u8]) -> Value {
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// `headers` as a JSON object, with the values of credentials and of
+/// `provider_headers` redacted.
+fn redact_headers(headers: &HeaderMap, provider_headers: &[String]) -> Value {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            // `HeaderName`s are lowercase.
+            let name_str = name.as_str();
+            let value = if is_secret_name(name_str)
+                || REDACTED_HEADERS.contains(&name_str)
+                || provider_headers.iter().any(|header| header == name_str)
+            {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.as_str().to_string(), Value::String(value))
+        })
+        .collect();
+    Value::Object(headers)
+}
+
+fn redact_url(url: &Url) -> String {
+    if url.query().is_none() {
+        return url.to_string();
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_secret_name(&name)
+                || REDACTED_QUERY_PARAMS.contains(&name.to_ascii_lowercase().as_str())
+            {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_provider_info::built_in_model_providers;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn logs_requests_with_credentials_redacted() {
+        let codex_home = TempDir::new().expect("create temp dir");
+        let conversation_id = ConversationId::default();
+        let path = set_wire_logging(codex_home.path(), conversation_id, true)
+            .expect("enable wire logging")
+            .expect("log path");
+
+        let client = reqwest::Client::new();
+        let provider = ModelProviderInfo {
+            http_headers: Some(HashMap::from([(
+                "X-Tenant".to_string(),
+                "tenant-secret".to_string(),
+            )])),
+            env_http_headers: Some(HashMap::from([(
+                "X-Org".to_string(),
+                "CODEX_ORG_HEADER".to_string(),
+            )])),
+            ..built_in_model_providers()["openai"].clone()
+        };
+        scope(conversation_id, &provider, async {
+            log_request(
+                &client
+                    .post("https://example.com/v1/models?key=secret&alt=sse&sig=sk-secret")
+                    .bearer_auth("sk-secret")
+                    .header("x-api-key", "sk-secret")
+                    .header("X-Tenant", "tenant-secret")
+                    .header("X-Org", "org-secret")
+                    .header("X-Session-Id", "sk-secret")
+                    .json(&json!({"model": "gpt-5"})),
+            );
+        })
+        .await;
+        set_wire_logging(codex_home.path(), conversation_id, false).expect("disable wire logging");
+        // Requests outside a scope or after logging is off are not logged.
+        log_request(&client.post("https://example.com/ignored"));
+        scope(conversation_id, &provider, async {
+            log_request(&client.post("https://example.com/ignored"));
+        })
+        .await;
+
+        let contents = std::fs::read_to_string(path).expect("read wire log");
+        let entries: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid JSON line"))
+            .collect();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry["type"], "request");
+        assert_eq!(
+            entry["url"],
+            "https://example.com/v1/models?key=%5BREDACTED%5D&alt=sse&sig=%5BREDACTED%5D"
+        );
+        assert_eq!(entry["headers"]["authorization"], REDACTED);
+        assert_eq!(entry["headers"]["x-api-key"], REDACTED);
+        assert_eq!(entry["headers"]["x-tenant"], REDACTED);
+        assert_eq!(entry["headers"]["x-org"], REDACTED);
+        assert_eq!(entry["headers"]["x-session-id"], REDACTED);
+        assert_eq!(entry["headers"]["content-type"], "application/json");
+        assert_eq!(entry["body"], json!({"model": "gpt-5"}));
+        assert!(!contents.contains("sk-secret"));
+        assert!(!contents.contains("tenant-secret"));
+        assert!(!contents.contains("org-secret"));
+    }
+}
@@ -0,0 +1,343 @@
+//! Git worktrees that keep a session's changes off the user's working tree
+//! (`--worktree`).
+//!
+//! Each worktree is checked out under `$CODEX_HOME/worktrees/<name>` on a new
+//! branch, `codex/<name>`, created from the repository's `HEAD`. When the
+//! session ends its work can be merged back, pushed for a pull request, or
+//! discarded with the worktree.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config_types::Integrations;
+use crate::git_info::run_git;
+use crate::pull_request::PullRequestDraft;
+use crate::pull_request::create_pull_request;
+
+/// Upper bound on each git command; `push` talks to the remote.
+const GIT_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A worktree created for a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    /// Directory name under `$CODEX_HOME/worktrees`, e.g. `codex-1a2b3c4d`.
+    pub name: String,
+    /// Where the worktree is checked out.
+    pub path: PathBuf,
+    /// The branch checked out in the worktree.
+    pub branch: String,
+    /// The main checkout of the repository the worktree belongs to.
+    pub repo_root: PathBuf,
+}
+
+pub fn worktrees_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join("worktrees")
+}
+
+/// Creates a worktree, on a new branch from `HEAD`, of the repository that
+/// contains `cwd`.
+pub async fn create_worktree(codex_home: &Path, cwd: &Path) -> io::Result<Worktree> {
+    let repo_root = PathBuf::from(git(cwd, &["rev-parse", "--show-toplevel"]).await?);
+    let repo_name = repo_root
+        .file_name()
+        .map(|name| branch_safe(&name.to_string_lossy()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "repo".to_string());
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let name = format!("{repo_name}-{}", &id[..8]);
+    let path = worktrees_dir(codex_home).join(&name);
+    let branch = format!("codex/{name}");
+
+    std::fs::create_dir_all(worktrees_dir(codex_home))?;
+    let path_arg = path.to_string_lossy();
+    git(
+        &repo_root,
+        &[
+            "worktree", "add", "--quiet", "-b", &branch, &path_arg, "HEAD",
+        ],
+    )
+    .await?;
+    Ok(Worktree {
+        name,
+        path,
+        branch,
+        repo_root,
+    })
+}
+
+/// The worktrees under `$CODEX_HOME/worktrees`, by name. Directories that are
+/// no longer git worktrees are left out.
+pub async fn list_worktrees(codex_home: &Path) -> io::Result<Vec<Worktree>> {
+    let entries = match std::fs::read_dir(worktrees_dir(codex_home)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut worktrees: Vec<Worktree> = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        if entry.path().is_dir()
+            && let Ok(worktree) = open_worktree(entry.path()).await
+        {
+            worktrees.push(worktree);
+        }
+    }
+    worktrees.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(worktrees)
+}
+
+/// The worktree called `name`.
+pub async fn find_worktree(codex_home: &Path, name: &str) -> io::Result<Worktree> {
+    let path = worktrees_dir(codex_home).join(name);
+    if !path.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no worktree named `{name}`"),
+        ));
+    }
+    open_worktree(path).await
+}
+
+async fn open_worktree(path: PathBuf) -> io::Result<Worktree> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let branch = git(&path, &["branch", "--show-current"]).await?;
+    let common_dir = git(
+        &path,
+        &["rev-parse", "--path-format=absolute", "--git-common-dir"],
+    )
+    .await?;
+    let repo_root = Path::new(&common_dir)
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| io::Error::other(format!("{} is not a worktree", path.display())))?;
+    Ok(Worktree {
+        name,
+        path,
+        branch,
+        repo_root,
+    })
+}
+
+impl Worktree {
+    /// The directory in the worktree that corresponds to `cwd` in the main
+    /// checkout.
+    pub fn path_for(&self, cwd: &Path) -> PathBuf {
+        let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+        let repo_root = self
+            .repo_root
+            .canonicalize()
+            .unwrap_or_else(|_| self.repo_root.clone());
+        match cwd.strip_prefix(&repo_root) {
+            Ok(relative) => self.path.join(relative),
+            Err(_) => self.path.clone(),
+        }
+    }
+
+    /// Whether the worktree has uncommitted changes, or commits that the
+    /// branch checked out in the main checkout does not.
+    pub async fn has_changes(&self) -> io::Result<bool> {
+        if !git(&self.path, &["status", "--porcelain"])
+            .await?
+            .is_empty()
+        {
+            return Ok(true);
+        }
+        let range = format!("HEAD..{}", self.branch);
+        let ahead = git(&self.repo_root, &["rev-list", "--count", &range]).await?;
+        Ok(ahead != "0")
+    }
+
+    /// Commits all changes in the worktree, which only the session worked
+    /// in. Returns whether there were any.
+    pub async fn commit_all(&self, message: &str) -> io::Result<bool> {
+        if git(&self.path, &["status", "--porcelain"])
+            .await?
+            .is_empty()
+        {
+            return Ok(false);
+        }
+        git(&self.path, &["add", "--all"]).await?;
+        git(&self.path, &["commit", "--quiet", "-m", message]).await?;
+        Ok(true)
+    }
+
+    /// Commits any remaining changes and merges the worktree's branch into the
+    /// branch checked out in the main checkout, which is returned.
+    pub async fn merge(&self) -> io::Result<String> {
+        self.commit_all(&self.commit_message()).await?;
+        let target = git(&self.repo_root, &["branch", "--show-current"]).await?;
+        git(&self.repo_root, &["merge", "--no-edit", &self.branch]).await?;
+        Ok(target)
+    }
+
+    /// Commits any remaining changes, pushes the branch to `origin` and opens
+    /// a pull request for it as configured under `[integrations]`, titled
+    /// after the branch's commits. Returns the pull request's URL.
+    pub async fn open_pull_request(&self, integrations: &Integrations) -> io::Result<String> {
+        self.commit_all(&self.commit_message()).await?;
+        let range = format!("HEAD..{}", self.branch);
+        let subjects = git(
+            &self.repo_root,
+            &["log", "--reverse", "--format=%s", &range],
+        )
+        .await?;
+        let draft = pull_request_draft(&subjects, &self.commit_message());
+        let created = create_pull_request(integrations, &self.path, &draft, &[])
+            .await
+            .map_err(io::Error::other)?;
+        Ok(created.url)
+    }
+
+    /// Removes the worktree, discarding its uncommitted changes, and deletes
+    /// its branch.
+    pub async fn remove(&self) -> io::Result<()> {
+        let path_arg = self.path.to_string_lossy();
+        git(
+            &self.repo_root,
+            &["worktree", "remove", "--force", &path_arg],
+        )
+        .await?;
+        git(&self.repo_root, &["branch", "--quiet", "-D", &self.branch]).await?;
+        Ok(())
+    }
+
+    fn commit_message(&self) -> String {
+        format!("codex: changes from worktree {}", self.name)
+    }
+}
+
+/// `name` with characters that are not allowed, or not allowed everywhere,
+/// in branch names replaced or dropped: `.my repo` becomes `my-repo`.
+fn branch_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_start_matches('-')
+        .to_string()
+}
+
+/// A pull request for the commits `subjects`, one per line, titled after the
+/// first one.
+fn pull_request_draft(subjects: &str, fallback_title: &str) -> PullRequestDraft {
+    let subjects: Vec<&str> = subjects.lines().filter(|s| !s.is_empty()).collect();
+    let title = subjects
+        .first()
+        .copied()
+        .unwrap_or(fallback_title)
+        .to_string();
+    let mut body = String::from("## Commits\n\n");
+    for subject in &subjects {
+        body.push_str(&format!("- {subject}\n"));
+    }
+    PullRequestDraft { title, body }
+}
+
+/// Runs git in `cwd` with this module's time limit.
+async fn git(cwd: &Path, args: &[&str]) -> io::Result<String> {
+    run_git(cwd, args, GIT_COMMAND_TIMEOUT)
+        .await
+        .map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "--quiet", "--initial-branch=main"],
+            vec!["config", "user.name", "Test User"],
+            vec!["config", "user.email", "test@example.com"],
+        ] {
+            git(dir, &args).await.expect("git setup");
+        }
+        std::fs::create_dir(dir.join("src")).expect("mkdir");
+        std::fs::write(dir.join("src/lib.rs"), "").expect("write");
+        git(dir, &["add", "."]).await.expect("add");
+        git(dir, &["commit", "--quiet", "-m", "init"])
+            .await
+            .expect("commit");
+    }
+
+    #[tokio::test]
+    async fn worktree_changes_merge_back_without_touching_the_checkout() {
+        let repo = TempDir::new().expect("tempdir");
+        let codex_home = TempDir::new().expect("tempdir");
+        init_repo(repo.path()).await;
+
+        let worktree = create_worktree(codex_home.path(), &repo.path().join("src"))
+            .await
+            .expect("create worktree");
+        assert!(worktree.branch.starts_with("codex/"));
+        assert_eq!(
+            worktree.path_for(&repo.path().join("src")),
+            worktree.path.join("src")
+        );
+        assert_eq!(
+            list_worktrees(codex_home.path()).await.expect("list"),
+            vec![
+                find_worktree(codex_home.path(), &worktree.name)
+                    .await
+                    .expect("find")
+            ]
+        );
+        assert!(!worktree.has_changes().await.expect("status"));
+
+        std::fs::write(worktree.path.join("src/lib.rs"), "pub fn f() {}\n").expect("write");
+        assert!(worktree.has_changes().await.expect("status"));
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("src/lib.rs")).expect("read"),
+            ""
+        );
+
+        assert_eq!(worktree.merge().await.expect("merge"), "main");
+        assert_eq!(
+            std::fs::read_to_string(repo.path().join("src/lib.rs")).expect("read"),
+            "pub fn f() {}\n"
+        );
+
+        worktree.remove().await.expect("remove");
+        assert!(!worktree.path.exists());
+        assert_eq!(
+            list_worktrees(codex_home.path()).await.expect("list"),
+            Vec::new()
+        );
+    }
+
+    #[tokio::test]
+    async fn creating_a_worktree_outside_a_repository_fails() {
+        let dir = TempDir::new().expect("tempdir");
+        let codex_home = TempDir::new().expect("tempdir");
+        assert!(
+            create_worktree(codex_home.path(), dir.path())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn worktree_names_are_valid_branch_names() {
+        assert_eq!(branch_safe(".tmpZrb00A"), "tmpZrb00A");
+        assert_eq!(branch_safe("my repo:v2..3"), "my-repo-v2--3");
+    }
+
+    #[test]
+    fn pull_requests_are_titled_after_the_first_commit() {
+        let draft = pull_request_draft("Add the parser\nFix a typo\n", "fallback");
+        assert_eq!(draft.title, "Add the parser");
+        assert_eq!(draft.body, "## Commits\n\n- Add the parser\n- Fix a typo\n");
+        assert_eq!(pull_request_draft("", "fallback").title, "fallback");
+    }
+}
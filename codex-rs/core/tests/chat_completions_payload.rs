@@ -46,15 +46,23 @@ async fn run_request(input: Vec<ResponseItem>) -> Value {
     let provider = ModelProviderInfo {
         name: "mock".into(),
         base_url: Some(format!("{}/v1", server.uri())),
+        fallback_base_urls: None,
         env_key: None,
         env_key_instructions: None,
         wire_api: WireApi::Chat,
         query_params: None,
         http_headers: None,
         env_http_headers: None,
+        safety_settings: None,
+        aws: None,
         request_max_retries: Some(0),
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     };
 
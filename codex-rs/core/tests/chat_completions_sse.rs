@@ -39,15 +39,23 @@ async fn run_stream(sse_body: &str) -> Vec<ResponseEvent> {
     let provider = ModelProviderInfo {
         name: "mock".into(),
         base_url: Some(format!("{}/v1", server.uri())),
+        fallback_base_urls: None,
         env_key: None,
         env_key_instructions: None,
         wire_api: WireApi::Chat,
         query_params: None,
         http_headers: None,
         env_http_headers: None,
+        safety_settings: None,
+        aws: None,
         request_max_retries: Some(0),
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     };
 
@@ -318,3 +326,41 @@ async fn streams_reasoning_before_tool_call() {
 
     assert!(matches!(events[3], ResponseEvent::Completed { .. }));
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reports_usage_from_the_final_chunk() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{} ,\"finish_reason\":\"stop\"}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":1200,\"completion_tokens\":30,\"total_tokens\":1230,\"prompt_tokens_details\":{\"cached_tokens\":1024}}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    let events = run_stream(sse).await;
+    assert_eq!(events.len(), 3, "unexpected events: {events:?}");
+
+    match &events[1] {
+        ResponseEvent::OutputItemDone(item) => assert_message(item, "ok"),
+        other => panic!("expected message item, got {other:?}"),
+    }
+
+    match &events[2] {
+        ResponseEvent::Completed {
+            token_usage: Some(usage),
+            ..
+        } => {
+            assert_eq!(usage.input_tokens, 1200);
+            assert_eq!(usage.cached_input_tokens, 1024);
+            assert_eq!(usage.output_tokens, 30);
+            assert_eq!(usage.total_tokens, 1230);
+        }
+        other => panic!("expected completed with usage, got {other:?}"),
+    }
+}
@@ -531,7 +531,8 @@ async fn prefers_apikey_when_config_prefers_apikey_even_with_chatgpt_tokens() {
     let mut config = load_default_config_for_test(&codex_home);
     config.model_provider = model_provider;
 
-    let auth_manager = match CodexAuth::from_codex_home(codex_home.path()) {
+    let auth_manager = match CodexAuth::from_codex_home(codex_home.path(), config.credential_store)
+    {
         Ok(Some(auth)) => codex_core::AuthManager::from_auth_for_testing(auth),
         Ok(None) => panic!("No CodexAuth found in codex_home"),
         Err(e) => panic!("Failed to load CodexAuth: {e}"),
@@ -644,15 +645,23 @@ async fn azure_responses_request_includes_store_and_reasoning_ids() {
     let provider = ModelProviderInfo {
         name: "azure".into(),
         base_url: Some(format!("{}/openai", server.uri())),
+        fallback_base_urls: None,
         env_key: None,
         env_key_instructions: None,
         wire_api: WireApi::Responses,
         query_params: None,
         http_headers: None,
         env_http_headers: None,
+        safety_settings: None,
+        aws: None,
         request_max_retries: Some(0),
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     };
 
@@ -802,12 +811,17 @@ async fn token_count_includes_rate_limits_snapshot() {
         .await
         .unwrap();
 
-    let first_token_event =
-        wait_for_event(&codex, |msg| matches!(msg, EventMsg::TokenCount(_))).await;
-    let rate_limit_only = match first_token_event {
+    let first_token_event = wait_for_event(
+        &codex,
+        |msg| matches!(msg, EventMsg::TokenCount(ev) if ev.rate_limits.is_some()),
+    )
+    .await;
+    let mut rate_limit_only = match first_token_event {
         EventMsg::TokenCount(ev) => ev,
         _ => unreachable!(),
     };
+    // The prompt is counted locally before it is sent.
+    assert!(rate_limit_only.estimated_prompt_tokens.take().is_some());
 
     let rate_limit_json = serde_json::to_value(&rate_limit_only).unwrap();
     pretty_assertions::assert_eq!(
@@ -834,10 +848,11 @@ async fn token_count_includes_rate_limits_snapshot() {
         |msg| matches!(msg, EventMsg::TokenCount(ev) if ev.info.is_some()),
     )
     .await;
-    let final_payload = match token_event {
+    let mut final_payload = match token_event {
         EventMsg::TokenCount(ev) => ev,
         _ => unreachable!(),
     };
+    assert!(final_payload.estimated_prompt_tokens.take().is_some());
     // Assert full JSON for the final token count event (usage + rate limits)
     let final_json = serde_json::to_value(&final_payload).unwrap();
     pretty_assertions::assert_eq!(
@@ -953,10 +968,15 @@ async fn usage_limit_error_emits_rate_limit_event() -> anyhow::Result<()> {
         .await
         .expect("submission should succeed while emitting usage limit error events");
 
-    let token_event = wait_for_event(&codex, |msg| matches!(msg, EventMsg::TokenCount(_))).await;
-    let EventMsg::TokenCount(event) = token_event else {
+    let token_event = wait_for_event(
+        &codex,
+        |msg| matches!(msg, EventMsg::TokenCount(ev) if ev.rate_limits.is_some()),
+    )
+    .await;
+    let EventMsg::TokenCount(mut event) = token_event else {
         unreachable!();
     };
+    assert!(event.estimated_prompt_tokens.take().is_some());
 
     let event_json = serde_json::to_value(&event).expect("serialize token count event");
     pretty_assertions::assert_eq!(
@@ -1015,6 +1035,7 @@ async fn azure_overrides_assign_properties_used_for_responses_url() {
         name: "custom".to_string(),
         base_url: Some(format!("{}/openai", server.uri())),
         // Reuse the existing environment variable to avoid using unsafe code
+        fallback_base_urls: None,
         env_key: Some(existing_env_var_with_random_value.to_string()),
         query_params: Some(std::collections::HashMap::from([(
             "api-version".to_string(),
@@ -1027,9 +1048,16 @@ async fn azure_overrides_assign_properties_used_for_responses_url() {
             "Value".to_string(),
         )])),
         env_http_headers: None,
+        safety_settings: None,
+        aws: None,
         request_max_retries: None,
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     };
 
@@ -1092,6 +1120,7 @@ async fn env_var_overrides_loaded_auth() {
         name: "custom".to_string(),
         base_url: Some(format!("{}/openai", server.uri())),
         // Reuse the existing environment variable to avoid using unsafe code
+        fallback_base_urls: None,
         env_key: Some(existing_env_var_with_random_value.to_string()),
         query_params: Some(std::collections::HashMap::from([(
             "api-version".to_string(),
@@ -1104,9 +1133,16 @@ async fn env_var_overrides_loaded_auth() {
             "Value".to_string(),
         )])),
         env_http_headers: None,
+        safety_settings: None,
+        aws: None,
         request_max_retries: None,
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     };
 
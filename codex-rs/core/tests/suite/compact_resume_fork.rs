@@ -66,6 +66,9 @@ async fn compact_resume_and_fork_preserve_model_history_view() {
         "compact+resume test expects base path {base_path:?} to exist",
     );
 
+    // The rollout can be resumed once the conversation recording it is
+    // shut down.
+    shutdown_conversation(&base).await;
     let resumed = resume_conversation(&manager, &config, base_path).await;
     user_turn(&resumed, "AFTER_RESUME").await;
     let resumed_path = fetch_conversation_path(&resumed, "resumed conversation").await;
@@ -522,6 +525,9 @@ async fn compact_resume_after_second_compaction_preserves_history() {
         "second compact test expects base path {base_path:?} to exist",
     );
 
+    // The rollout can be resumed once the conversation recording it is
+    // shut down.
+    shutdown_conversation(&base).await;
     let resumed = resume_conversation(&manager, &config, base_path).await;
     user_turn(&resumed, "AFTER_RESUME").await;
     let resumed_path = fetch_conversation_path(&resumed, "resumed conversation").await;
@@ -541,6 +547,7 @@ async fn compact_resume_after_second_compaction_preserves_history() {
         "second compact test expects forked path {forked_path:?} to exist",
     );
 
+    shutdown_conversation(&forked).await;
     let resumed_again = resume_conversation(&manager, &config, forked_path).await;
     user_turn(&resumed_again, AFTER_SECOND_RESUME).await;
 
@@ -808,6 +815,14 @@ async fn fetch_conversation_path(
     }
 }
 
+async fn shutdown_conversation(conversation: &Arc<CodexConversation>) {
+    conversation
+        .submit(Op::Shutdown)
+        .await
+        .expect("request shutdown");
+    wait_for_event(conversation, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+}
+
 async fn resume_conversation(
     manager: &ConversationManager,
     config: &Config,
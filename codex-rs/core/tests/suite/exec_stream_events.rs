@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use async_channel::Receiver;
+use codex_core::config_types::ResourceLimits;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
 use codex_core::exec::ExecParams;
@@ -57,6 +58,7 @@ async fn test_exec_stdout_stream_events_echo() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -109,6 +111,7 @@ async fn test_exec_stderr_stream_events_echo() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -147,6 +150,59 @@ async fn test_exec_stderr_stream_events_echo() {
     assert_eq!(String::from_utf8_lossy(&err), "oops\n");
 }
 
+#[tokio::test]
+async fn test_exec_stream_events_coalesce_quick_output() {
+    let (tx, rx) = async_channel::unbounded::<Event>();
+
+    let stdout_stream = StdoutStream {
+        sub_id: "test-sub".to_string(),
+        call_id: "call-3".to_string(),
+        tx_event: tx,
+    };
+
+    // The first line is streamed right away; the next two arrive within the
+    // delta interval and are sent together.
+    let cmd = vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        "printf 'a\\n'; sleep 0.01; printf 'b\\n'; sleep 0.01; printf 'c\\n'".to_string(),
+    ];
+
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let params = ExecParams {
+        command: cmd,
+        cwd: cwd.clone(),
+        timeout_ms: Some(5_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: ResourceLimits::default(),
+    };
+
+    let policy = SandboxPolicy::new_read_only_policy();
+
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::None,
+        &policy,
+        cwd.as_path(),
+        &None,
+        Some(stdout_stream),
+    )
+    .await
+    .expect("process_exec_tool_call");
+    assert_eq!(result.stdout.text, "a\nb\nc\n");
+
+    let mut chunks = Vec::new();
+    while let Ok(ev) = rx.try_recv() {
+        if let EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent { chunk, .. }) = ev.msg
+        {
+            chunks.push(String::from_utf8_lossy(&chunk).into_owned());
+        }
+    }
+    assert_eq!(chunks, vec!["a\n".to_string(), "b\nc\n".to_string()]);
+}
+
 #[tokio::test]
 async fn test_aggregated_output_interleaves_in_order() {
     // Spawn a shell that alternates stdout and stderr with sleeps to enforce order.
@@ -164,6 +220,7 @@ async fn test_aggregated_output_interleaves_in_order() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -202,6 +259,7 @@ async fn test_exec_timeout_returns_partial_output() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -227,3 +285,49 @@ async fn test_exec_timeout_returns_partial_output() {
     assert!(output.duration >= Duration::from_millis(200));
     assert!(output.timed_out);
 }
+
+#[tokio::test]
+async fn test_exec_kills_command_exceeding_max_output_bytes() {
+    let cmd = vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        "while :; do echo spam; done".to_string(),
+    ];
+
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let params = ExecParams {
+        command: cmd,
+        cwd: cwd.clone(),
+        timeout_ms: Some(10_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: ResourceLimits {
+            max_output_bytes: Some(4096),
+            ..ResourceLimits::default()
+        },
+    };
+
+    let policy = SandboxPolicy::new_read_only_policy();
+
+    let output = process_exec_tool_call(
+        params,
+        SandboxType::None,
+        &policy,
+        cwd.as_path(),
+        &None,
+        None,
+    )
+    .await
+    .expect("output limit should not be reported as an error");
+
+    assert_eq!(output.exit_code, 137);
+    assert!(!output.timed_out);
+    assert!(output.stdout.text.len() <= 4096 + 8192);
+    assert!(
+        output
+            .aggregated_output
+            .text
+            .contains("output exceeded the 4096-byte limit")
+    );
+}
@@ -1,5 +1,7 @@
 #![cfg(not(target_os = "windows"))]
 
+use codex_core::ModelProviderInfo;
+use codex_core::WireApi;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -16,6 +18,11 @@ use responses::ev_assistant_message;
 use responses::ev_completed;
 use responses::sse;
 use responses::start_mock_server;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
 
 const SCHEMA: &str = r#"
 {
@@ -104,3 +111,109 @@ async fn codex_returns_json_result(model: String) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// An Anthropic Messages API stream whose only output is `text`.
+fn anthropic_text_sse(text: &str) -> String {
+    [
+        serde_json::json!({"type": "message_start", "message": {"id": "msg_1", "usage": {"input_tokens": 10, "output_tokens": 1}}}),
+        serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": text}}),
+        serde_json::json!({"type": "content_block_stop", "index": 0}),
+        serde_json::json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 5}}),
+        serde_json::json!({"type": "message_stop"}),
+    ]
+    .iter()
+    .map(|event| format!("event: {}\ndata: {event}\n\n", event["type"].as_str().unwrap_or_default()))
+    .collect()
+}
+
+async fn mount_anthropic_response_once(server: &MockServer, text: &str) {
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(anthropic_text_sse(text), "text/event-stream"),
+        )
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn final_message_is_validated_and_retried_without_native_support() -> anyhow::Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let server = start_mock_server().await;
+    mount_anthropic_response_once(&server, "Here you go: no JSON.").await;
+    mount_anthropic_response_once(
+        &server,
+        "```json\n{\"explanation\": \"e\", \"final_answer\": \"f\"}\n```",
+    )
+    .await;
+
+    let provider = ModelProviderInfo {
+        name: "mock-anthropic".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        fallback_base_urls: None,
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: WireApi::Anthropic,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        safety_settings: None,
+        aws: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(2_000),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
+        requires_openai_auth: false,
+    };
+    let TestCodex { codex, cwd, .. } = test_codex()
+        .with_config(move |config| config.model_provider = provider)
+        .build(&server)
+        .await?;
+
+    codex
+        .submit(Op::UserTurn {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+            final_output_json_schema: Some(serde_json::from_str(SCHEMA)?),
+            cwd: cwd.path().to_path_buf(),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            model: "claude-sonnet-4-5".to_string(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+        .await?;
+
+    let EventMsg::TaskComplete(complete) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        anyhow::bail!("expected task complete event");
+    };
+    assert_eq!(
+        complete.last_agent_message.as_deref(),
+        Some(r#"{"explanation": "e", "final_answer": "f"}"#)
+    );
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2);
+    let first: serde_json::Value = serde_json::from_slice(&requests[0].body)?;
+    let system = first["system"][0]["text"].as_str().unwrap_or_default();
+    assert!(system.contains("\"final_answer\""), "{system}");
+    let retry = String::from_utf8_lossy(&requests[1].body);
+    assert!(
+        retry.contains("does not match the required JSON schema"),
+        "{retry}"
+    );
+
+    Ok(())
+}
@@ -13,10 +13,14 @@ mod json_result;
 mod live_cli;
 mod model_overrides;
 mod prompt_caching;
+mod provider_fallback;
+#[cfg(not(target_os = "windows"))]
+mod queued_user_input;
 mod review;
 mod rmcp_client;
 mod rollout_list_find;
 mod seatbelt;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
+mod task_budget;
 mod user_notification;
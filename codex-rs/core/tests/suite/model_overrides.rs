@@ -1,9 +1,18 @@
 use codex_core::CodexAuth;
 use codex_core::ConversationManager;
 use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::ModelChangedEvent;
 use codex_core::protocol::Op;
+use codex_core::protocol::TurnOverrides;
 use codex_core::protocol_config_types::ReasoningEffort;
 use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
 use core_test_support::wait_for_event;
 use pretty_assertions::assert_eq;
 use tempfile::TempDir;
@@ -36,6 +45,7 @@ async fn override_turn_context_does_not_persist_when_config_exists() {
             approval_policy: None,
             sandbox_policy: None,
             model: Some("o3".to_string()),
+            provider: None,
             effort: Some(Some(ReasoningEffort::High)),
             summary: None,
         })
@@ -76,6 +86,7 @@ async fn override_turn_context_does_not_create_config_file() {
             approval_policy: None,
             sandbox_policy: None,
             model: Some("o3".to_string()),
+            provider: None,
             effort: Some(Some(ReasoningEffort::Medium)),
             summary: None,
         })
@@ -90,3 +101,123 @@ async fn override_turn_context_does_not_create_config_file() {
         "override should not create config.toml"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn override_turn_context_switches_provider_and_records_it() {
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    let other = config.model_provider.clone();
+    config.model_providers.insert("other".to_string(), other);
+    let previous_model = config.model.clone();
+    let previous_provider = config.model_provider_id.clone();
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create conversation")
+        .conversation;
+
+    codex
+        .submit(Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model: Some("o3".to_string()),
+            provider: Some("other".to_string()),
+            effort: None,
+            summary: None,
+        })
+        .await
+        .expect("submit override");
+    let EventMsg::ModelChanged(changed) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::ModelChanged(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        changed,
+        ModelChangedEvent {
+            model: "o3".to_string(),
+            provider: "other".to_string(),
+            previous_model,
+            previous_provider,
+        }
+    );
+
+    codex
+        .submit(Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model: None,
+            provider: Some("missing".to_string()),
+            effort: None,
+            summary: None,
+        })
+        .await
+        .expect("submit override");
+    let EventMsg::Error(error) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(error.message, "Model provider `missing` not found");
+
+    codex.submit(Op::Shutdown).await.expect("request shutdown");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn turn_overrides_apply_to_a_single_turn() {
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![ev_completed("resp_0")]),
+            sse(vec![ev_completed("resp_1")]),
+        ],
+    )
+    .await;
+    let TestCodex { codex, .. } = test_codex()
+        .with_config(|config| config.model_reasoning_effort = Some(ReasoningEffort::Low))
+        .build(&server)
+        .await
+        .expect("create conversation");
+
+    codex
+        .submit(Op::UserInputWithOverrides {
+            items: vec![InputItem::Text {
+                text: "think hard".to_string(),
+            }],
+            overrides: TurnOverrides {
+                effort: Some(ReasoningEffort::High),
+                verbosity: None,
+                temperature: Some(0.5),
+            },
+        })
+        .await
+        .expect("submit input");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "and now quickly".to_string(),
+            }],
+        })
+        .await
+        .expect("submit input");
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.expect("requests");
+    let bodies: Vec<serde_json::Value> = requests
+        .iter()
+        .map(|request| request.body_json().expect("json body"))
+        .collect();
+    assert_eq!(bodies.len(), 2);
+    assert_eq!(bodies[0]["reasoning"]["effort"], "high");
+    assert_eq!(bodies[0]["temperature"], 0.5);
+    assert_eq!(bodies[1]["reasoning"]["effort"], "low");
+    assert!(bodies[1].get("temperature").is_none());
+}
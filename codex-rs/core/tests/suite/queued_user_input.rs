@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::QueuedUserInputEvent;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+/// Input queued while a tool runs waits for the task to finish, then starts
+/// the next turn on its own.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn queued_input_starts_a_turn_after_the_running_task() {
+    let args = json!({
+        "command": ["bash", "-lc", "sleep 1"],
+        "timeout_ms": 10_000
+    })
+    .to_string();
+
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call_sleep", "shell", &args),
+                ev_completed("resp_0"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg_1", "slept"),
+                ev_completed("resp_1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg_2", "follow up done"),
+                ev_completed("resp_2"),
+            ]),
+        ],
+    )
+    .await;
+
+    let codex = test_codex().build(&server).await.unwrap().codex;
+    let wait_timeout = Duration::from_secs(10);
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "start sleep".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::ExecCommandBegin(_)),
+        wait_timeout,
+    )
+    .await;
+
+    codex
+        .submit(Op::QueueUserInput {
+            items: vec![InputItem::Text {
+                text: "queued follow up".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let EventMsg::QueuedUserInput(QueuedUserInputEvent { queued, submitted }) =
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::QueuedUserInput(_)),
+            wait_timeout,
+        )
+        .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(submitted, None);
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].text, "queued follow up");
+    let id = queued[0].id;
+
+    // The first task completes before the queued input leaves the queue.
+    wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        wait_timeout,
+    )
+    .await;
+    let EventMsg::QueuedUserInput(QueuedUserInputEvent { queued, submitted }) =
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::QueuedUserInput(_)),
+            wait_timeout,
+        )
+        .await
+    else {
+        unreachable!();
+    };
+    assert!(queued.is_empty());
+    assert_eq!(submitted.map(|input| input.id), Some(id));
+
+    wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        wait_timeout,
+    )
+    .await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let last_body = String::from_utf8_lossy(&requests[2].body);
+    assert!(last_body.contains("queued follow up"));
+    let second_body = String::from_utf8_lossy(&requests[1].body);
+    assert!(!second_body.contains("queued follow up"));
+}
+
+/// Removing the queued input of some submissions leaves the input that other
+/// submissions queued in place.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn removing_queued_submissions_keeps_other_queued_input() {
+    let args = json!({
+        "command": ["bash", "-lc", "sleep 1"],
+        "timeout_ms": 10_000
+    })
+    .to_string();
+
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call_sleep", "shell", &args),
+                ev_completed("resp_0"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg_1", "slept"),
+                ev_completed("resp_1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg_2", "kept input done"),
+                ev_completed("resp_2"),
+            ]),
+        ],
+    )
+    .await;
+
+    let codex = test_codex().build(&server).await.unwrap().codex;
+    let wait_timeout = Duration::from_secs(10);
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "start sleep".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::ExecCommandBegin(_)),
+        wait_timeout,
+    )
+    .await;
+
+    let mut submission_ids = Vec::new();
+    for text in ["cancelled input", "kept input"] {
+        let submission_id = codex
+            .submit(Op::QueueUserInput {
+                items: vec![InputItem::Text { text: text.into() }],
+            })
+            .await
+            .unwrap();
+        submission_ids.push(submission_id);
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::QueuedUserInput(_)),
+            wait_timeout,
+        )
+        .await;
+    }
+
+    codex
+        .submit(Op::RemoveQueuedSubmissions {
+            submission_ids: vec![submission_ids[0].clone()],
+        })
+        .await
+        .unwrap();
+    let EventMsg::QueuedUserInput(QueuedUserInputEvent { queued, submitted }) =
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::QueuedUserInput(_)),
+            wait_timeout,
+        )
+        .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(submitted, None);
+    assert_eq!(
+        queued
+            .iter()
+            .map(|input| input.text.as_str())
+            .collect::<Vec<_>>(),
+        vec!["kept input"]
+    );
+
+    for _ in 0..2 {
+        wait_for_event_with_timeout(
+            &codex,
+            |ev| matches!(ev, EventMsg::TaskComplete(_)),
+            wait_timeout,
+        )
+        .await;
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let last_body = String::from_utf8_lossy(&requests[2].body);
+    assert!(last_body.contains("kept input"));
+    assert!(!last_body.contains("cancelled input"));
+}
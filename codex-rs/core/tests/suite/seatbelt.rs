@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::config_types::ResourceLimits;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::CODEX_SANDBOX_ENV_VAR;
@@ -80,6 +81,8 @@ async fn if_parent_of_repo_is_writable_then_dot_git_folder_is_writable() {
         network_access: false,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        network_allowlist: Vec::new(),
+        deny: Vec::new(),
     };
 
     test_scenario
@@ -106,6 +109,8 @@ async fn if_git_repo_is_writable_root_then_dot_git_folder_is_read_only() {
         network_access: false,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        network_allowlist: Vec::new(),
+        deny: Vec::new(),
     };
 
     test_scenario
@@ -184,6 +189,7 @@ async fn python_getpwuid_works_under_seatbelt() {
         command_cwd,
         &policy,
         sandbox_cwd.as_path(),
+        &ResourceLimits::default(),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
     )
@@ -229,6 +235,7 @@ async fn touch(path: &Path, policy: &SandboxPolicy) -> bool {
         command_cwd,
         policy,
         sandbox_cwd.as_path(),
+        &ResourceLimits::default(),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
     )
@@ -71,6 +71,7 @@ async fn retries_on_early_close() {
         // Environment variable that should exist in the test environment.
         // ModelClient will return an error if the environment variable for the
         // provider is not set.
+        fallback_base_urls: None,
         env_key: Some("PATH".into()),
         env_key_instructions: None,
         wire_api: WireApi::Responses,
@@ -78,9 +79,16 @@ async fn retries_on_early_close() {
         http_headers: None,
         env_http_headers: None,
         // exercise retry path: first attempt yields incomplete stream, so allow 1 retry
+        safety_settings: None,
+        aws: None,
         request_max_retries: Some(0),
         stream_max_retries: Some(1),
         stream_idle_timeout_ms: Some(2000),
+        requests_per_minute: None,
+        tokens_per_minute: None,
+        max_concurrent_streams: None,
+        ca_certificates: None,
+        danger_accept_invalid_certs: None,
         requires_openai_auth: false,
     };
 
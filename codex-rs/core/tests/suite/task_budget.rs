@@ -0,0 +1,80 @@
+#![cfg(not(target_os = "windows"))]
+
+use std::time::Duration;
+
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::ErrorKind;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+/// A task that keeps calling tools stops once it reaches `max_turns`,
+/// reporting that its budget is exceeded instead of sending more requests.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn task_stops_after_max_turns() {
+    let args = json!({
+        "command": ["bash", "-lc", "true"],
+        "timeout_ms": 10_000
+    })
+    .to_string();
+
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call_0", "shell", &args),
+                ev_completed("resp_0"),
+            ]),
+            sse(vec![
+                ev_function_call("call_1", "shell", &args),
+                ev_completed("resp_1"),
+            ]),
+        ],
+    )
+    .await;
+
+    let codex = test_codex()
+        .with_config(|config| config.max_turns = Some(2))
+        .build(&server)
+        .await
+        .unwrap()
+        .codex;
+    let wait_timeout = Duration::from_secs(10);
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "loop forever".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::Error(ErrorEvent { message, kind }) =
+        wait_for_event_with_timeout(&codex, |ev| matches!(ev, EventMsg::Error(_)), wait_timeout)
+            .await
+    else {
+        unreachable!();
+    };
+    assert_eq!(kind, ErrorKind::BudgetExceeded);
+    assert!(message.contains("`max_turns`"), "message: {message}");
+    wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        wait_timeout,
+    )
+    .await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+}
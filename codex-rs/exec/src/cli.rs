@@ -1,6 +1,7 @@
 use clap::Parser;
 use clap::ValueEnum;
 use codex_common::CliConfigOverrides;
+use codex_core::survey::DEFAULT_SURVEY_TOKEN_BUDGET;
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
@@ -14,6 +15,15 @@ pub struct Cli {
     #[arg(long = "image", short = 'i', value_name = "FILE", value_delimiter = ',', num_args = 1..)]
     pub images: Vec<PathBuf>,
 
+    /// Web page(s) or raw file(s) to fetch and attach to the initial prompt.
+    #[arg(long = "attach-url", value_name = "URL")]
+    pub attach_urls: Vec<String>,
+
+    /// File(s) whose contents are added to the prompt; `-` reads stdin, e.g.
+    /// `git diff | codex exec --attach - "review this"`.
+    #[arg(long = "attach", value_name = "FILE")]
+    pub attach: Vec<PathBuf>,
+
     /// Model the agent should use.
     #[arg(long, short = 'm')]
     pub model: Option<String>,
@@ -63,25 +73,60 @@ pub struct Cli {
     #[arg(long = "color", value_enum, default_value_t = Color::Auto)]
     pub color: Color,
 
-    /// Print events to stdout as JSONL.
-    #[arg(
-        long = "json",
-        default_value_t = false,
-        conflicts_with = "experimental_json"
-    )]
+    /// Print events to stdout as JSONL, in the schema of
+    /// `codex_protocol::exec_events`.
+    #[arg(long = "json", alias = "experimental-json", default_value_t = false)]
     pub json: bool,
 
-    #[arg(
-        long = "experimental-json",
-        default_value_t = false,
-        conflicts_with = "json"
-    )]
-    pub experimental_json: bool,
-
     /// Whether to include the plan tool in the conversation.
     #[arg(long = "include-plan-tool", default_value_t = false)]
     pub include_plan_tool: bool,
 
+    /// Continue the session with this id (UUID) instead of starting a new one;
+    /// the same as the `resume` subcommand.
+    #[arg(long = "resume", value_name = "SESSION_ID", conflicts_with = "last")]
+    pub resume: Option<String>,
+
+    /// Continue the most recent recorded session instead of starting a new one.
+    #[arg(long = "last", default_value_t = false)]
+    pub last: bool,
+
+    /// Stop the run, exiting with status 6, if it has not finished after this
+    /// many seconds.
+    #[arg(long = "timeout", value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Stop, exiting with status 7, once the agent has made this many model
+    /// requests.
+    #[arg(long = "max-turns", value_name = "N")]
+    pub max_turns: Option<u32>,
+
+    /// Stop, exiting with status 7, before the first model request made after
+    /// this many seconds. Unlike `--timeout`, a running request or command is
+    /// not interrupted.
+    #[arg(long = "max-runtime", value_name = "SECONDS")]
+    pub max_runtime: Option<u64>,
+
+    /// Stop, exiting with status 7, once the estimated cost of the session
+    /// reaches this many US dollars (see `pricing` in config.toml).
+    #[arg(long = "max-cost", value_name = "USD")]
+    pub max_cost: Option<f64>,
+
+    /// Commit each patch the agent applies, on a `codex/<session id>` branch
+    /// unless `auto_commit.new_branch = false` is configured.
+    #[arg(long = "auto-commit", default_value_t = false)]
+    pub auto_commit: bool,
+
+    /// Run in a new git worktree and branch of the repository, leaving the
+    /// working tree untouched. Manage the result with `codex worktree`.
+    #[arg(long = "worktree", default_value_t = false)]
+    pub worktree: bool,
+
+    /// After a successful run, push the branch and open a pull request with a
+    /// summary of the session (see `integrations` in config.toml).
+    #[arg(long = "create-pr", default_value_t = false)]
+    pub create_pr: bool,
+
     /// Specifies file where the last message from the agent should be written.
     #[arg(long = "output-last-message")]
     pub last_message_file: Option<PathBuf>,
@@ -96,6 +141,10 @@ pub struct Cli {
 pub enum Command {
     /// Resume a previous session by id or pick the most recent with --last.
     Resume(ResumeArgs),
+
+    /// Explore the repository under a token budget and save an architecture
+    /// overview that later sessions load as context.
+    Survey(SurveyArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -112,6 +161,27 @@ pub struct ResumeArgs {
     /// Prompt to send after resuming the session. If `-` is used, read from stdin.
     #[arg(value_name = "PROMPT")]
     pub prompt: Option<String>,
+
+    /// Continue from a background response (see `responses_background`) that
+    /// is still running, instead of sending a prompt.
+    #[arg(
+        long = "response-id",
+        value_name = "RESPONSE_ID",
+        conflicts_with = "prompt"
+    )]
+    pub response_id: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct SurveyArgs {
+    /// Token budget for the survey. Once it is spent the agent is asked to
+    /// write up what it has found.
+    #[arg(long = "max-tokens", value_name = "TOKENS", default_value_t = DEFAULT_SURVEY_TOKEN_BUDGET)]
+    pub max_tokens: u64,
+
+    /// Optional area of the repository to pay particular attention to.
+    #[arg(value_name = "FOCUS")]
+    pub focus: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
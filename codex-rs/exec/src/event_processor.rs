@@ -1,8 +1,10 @@
 use std::path::Path;
+use std::time::Instant;
 
 use codex_core::config::Config;
 use codex_core::protocol::Event;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_protocol::exec_events::ExitReason;
 
 pub(crate) enum CodexStatus {
     Running,
@@ -21,6 +23,17 @@ pub(crate) trait EventProcessor {
 
     /// Handle a single event emitted by the agent.
     fn process_event(&mut self, event: Event) -> CodexStatus;
+
+    /// When streamed output held back for batching is due to be printed.
+    fn flush_deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Print streamed output held back for batching.
+    fn flush_pending_output(&mut self) {}
+
+    /// Report why the process is about to exit.
+    fn print_exit_status(&mut self, _reason: ExitReason) {}
 }
 
 pub(crate) fn handle_last_message(last_agent_message: Option<&str>, output_file: &Path) {
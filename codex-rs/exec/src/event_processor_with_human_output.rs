@@ -1,5 +1,6 @@
 use codex_common::elapsed::format_duration;
 use codex_common::elapsed::format_elapsed;
+use codex_common::stream_coalescer::DeltaCoalescer;
 use codex_core::config::Config;
 use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::protocol::AgentMessageDeltaEvent;
@@ -7,18 +8,30 @@ use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
+use codex_core::protocol::AnnotationAddedEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BackgroundJobEvent;
+use codex_core::protocol::BackgroundJobStatus;
+use codex_core::protocol::BackgroundResponseStartedEvent;
+use codex_core::protocol::ContextPrunedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::LoginRequiredEvent;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpServerStatus;
+use codex_core::protocol::McpServerStatusEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::ProviderEndpointSwitchedEvent;
+use codex_core::protocol::ProviderSwitchedEvent;
+use codex_core::protocol::PullRequestCreatedEvent;
+use codex_core::protocol::SandboxCapability;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
@@ -33,6 +46,7 @@ use shlex::try_join;
 use std::collections::HashMap;
 use std::io::Write;
 use std::path::PathBuf;
+use std::time::Duration;
 use std::time::Instant;
 
 use crate::event_processor::CodexStatus;
@@ -66,6 +80,8 @@ pub(crate) struct EventProcessorWithHumanOutput {
     reasoning_started: bool,
     raw_reasoning_started: bool,
     last_message_path: Option<PathBuf>,
+    /// Holds back streamed answer and reasoning text per `stream_coalescing`.
+    delta_coalescer: DeltaCoalescer,
 }
 
 impl EventProcessorWithHumanOutput {
@@ -94,6 +110,7 @@ impl EventProcessorWithHumanOutput {
                 reasoning_started: false,
                 raw_reasoning_started: false,
                 last_message_path,
+                delta_coalescer: DeltaCoalescer::new(&config.stream_coalescing),
             }
         } else {
             Self {
@@ -112,11 +129,22 @@ impl EventProcessorWithHumanOutput {
                 reasoning_started: false,
                 raw_reasoning_started: false,
                 last_message_path,
+                delta_coalescer: DeltaCoalescer::new(&config.stream_coalescing),
             }
         }
     }
 }
 
+impl EventProcessorWithHumanOutput {
+    fn print_delta(&mut self, delta: &str) {
+        if let Some(text) = self.delta_coalescer.push(delta, Instant::now()) {
+            print!("{text}");
+            #[expect(clippy::expect_used)]
+            std::io::stdout().flush().expect("could not flush stdout");
+        }
+    }
+}
+
 struct ExecCommandBegin {
     command: Vec<String>,
 }
@@ -170,8 +198,16 @@ impl EventProcessor for EventProcessorWithHumanOutput {
 
     fn process_event(&mut self, event: Event) -> CodexStatus {
         let Event { id: _, msg } = event;
+        if !matches!(
+            msg,
+            EventMsg::AgentMessageDelta(_)
+                | EventMsg::AgentReasoningDelta(_)
+                | EventMsg::AgentReasoningRawContentDelta(_)
+        ) {
+            self.flush_pending_output();
+        }
         match msg {
-            EventMsg::Error(ErrorEvent { message }) => {
+            EventMsg::Error(ErrorEvent { message, .. }) => {
                 let prefix = "ERROR:".style(self.red);
                 ts_println!(self, "{prefix} {message}");
             }
@@ -184,7 +220,13 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::TaskStarted(_) => {
                 // Ignore.
             }
-            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message,
+                failure,
+            }) => {
+                if let Some(reason) = failure {
+                    ts_println!(self, "{} {reason}", "task failed:".style(self.red));
+                }
                 if let Some(output_file) = self.last_message_path.as_deref() {
                     handle_last_message(last_agent_message.as_deref(), output_file);
                 }
@@ -201,18 +243,18 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             }
             EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
                 if !self.answer_started {
+                    self.flush_pending_output();
                     ts_println!(self, "{}\n", "codex".style(self.italic).style(self.magenta));
                     self.answer_started = true;
                 }
-                print!("{delta}");
-                #[expect(clippy::expect_used)]
-                std::io::stdout().flush().expect("could not flush stdout");
+                self.print_delta(&delta);
             }
             EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }) => {
                 if !self.show_agent_reasoning {
                     return CodexStatus::Running;
                 }
                 if !self.reasoning_started {
+                    self.flush_pending_output();
                     ts_println!(
                         self,
                         "{}\n",
@@ -220,9 +262,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     );
                     self.reasoning_started = true;
                 }
-                print!("{delta}");
-                #[expect(clippy::expect_used)]
-                std::io::stdout().flush().expect("could not flush stdout");
+                self.print_delta(&delta);
             }
             EventMsg::AgentReasoningSectionBreak(_) => {
                 if !self.show_agent_reasoning {
@@ -254,9 +294,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 if !self.raw_reasoning_started {
                     self.raw_reasoning_started = true;
                 }
-                print!("{delta}");
-                #[expect(clippy::expect_used)]
-                std::io::stdout().flush().expect("could not flush stdout");
+                self.print_delta(&delta);
             }
             EventMsg::AgentMessage(AgentMessageEvent { message }) => {
                 // if answer_started is false, this means we haven't received any
@@ -491,15 +529,26 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     println!("{}", line.style(self.dimmed));
                 }
             }
-            EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => {
+            EventMsg::TurnDiff(TurnDiffEvent { unified_diff, risk }) => {
                 ts_println!(self, "{}", "turn diff:".style(self.magenta));
+                if let Some(risk) = risk {
+                    let summary = format!("risk: {} ({})", risk.level, risk.reasons.join("; "));
+                    println!("{}", summary.style(self.red));
+                }
                 println!("{unified_diff}");
             }
-            EventMsg::ExecApprovalRequest(_) => {
-                // Should we exit?
+            EventMsg::ExecApprovalRequest(_) | EventMsg::ApplyPatchApprovalRequest(_) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(
+                    self,
+                    "{prefix} approval required, but `codex exec` runs non-interactively; stopping."
+                );
+            }
+            EventMsg::SecretRequest(_) => {
+                // Never sent: exec runs with an approval policy of `never`.
             }
-            EventMsg::ApplyPatchApprovalRequest(_) => {
-                // Should we exit?
+            EventMsg::McpSamplingApprovalRequest(_) => {
+                // Never sent: exec runs with an approval policy of `never`.
             }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
@@ -525,6 +574,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     history_entry_count: _,
                     initial_messages: _,
                     rollout_path: _,
+                    sandbox_capability,
                 } = session_configured_event;
 
                 ts_println!(
@@ -535,6 +585,14 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 );
 
                 ts_println!(self, "model: {}", model);
+                if let Some(SandboxCapability::Unavailable { reason }) = sandbox_capability {
+                    ts_println!(
+                        self,
+                        "{} {}",
+                        "sandbox unavailable:".style(self.red),
+                        reason
+                    );
+                }
                 println!();
             }
             EventMsg::PlanUpdate(plan_update_event) => {
@@ -580,6 +638,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ListCustomPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::ContextUsage(_) => {
+                // Currently ignored in exec output.
+            }
             EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
                 TurnAbortReason::Interrupted => {
                     ts_println!(self, "task interrupted");
@@ -593,12 +654,131 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             },
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
+            EventMsg::QueuedUserInput(_) => {}
+            EventMsg::TurnReverted(_) => {}
+            EventMsg::CheckpointCreated(_) => {}
+            EventMsg::CheckpointRestored(_) => {}
+            EventMsg::ModelChanged(_) => {}
             EventMsg::UserMessage(_) => {}
             EventMsg::EnteredReviewMode(_) => {}
             EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::AnnotationAdded(AnnotationAddedEvent { target, comment }) => {
+                ts_println!(
+                    self,
+                    "{} {target}: {comment}",
+                    "annotation".style(self.magenta)
+                );
+            }
+            EventMsg::ContextPruned(ContextPrunedEvent {
+                strategy,
+                approx_tokens_freed,
+                items,
+            }) => {
+                ts_println!(
+                    self,
+                    "{} {} items (~{approx_tokens_freed} tokens) using {strategy} strategy",
+                    "context pruned".style(self.dimmed),
+                    items.len()
+                );
+            }
+            EventMsg::LoginRequired(LoginRequiredEvent { message }) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(
+                    self,
+                    "{prefix} {message}. Run `codex login` to sign in again."
+                );
+            }
+            EventMsg::BackgroundResponseStarted(BackgroundResponseStartedEvent { response_id }) => {
+                ts_println!(
+                    self,
+                    "{} {response_id}",
+                    "background response".style(self.dimmed)
+                );
+            }
+            EventMsg::PullRequestCreated(PullRequestCreatedEvent { url, branch, .. }) => {
+                ts_println!(
+                    self,
+                    "{} {url} (from {branch})",
+                    "pull request".style(self.green)
+                );
+            }
+            EventMsg::BackgroundJob(BackgroundJobEvent {
+                job_id,
+                command,
+                status,
+                exit_code,
+                duration_ms,
+            }) => {
+                let command = escape_command(&command);
+                let (outcome, style) = match status {
+                    BackgroundJobStatus::Running => ("started".to_string(), self.dimmed),
+                    BackgroundJobStatus::Completed => ("succeeded".to_string(), self.green),
+                    BackgroundJobStatus::Failed => match exit_code {
+                        Some(exit_code) => (format!("exited {exit_code}"), self.red),
+                        None => ("failed".to_string(), self.red),
+                    },
+                    BackgroundJobStatus::TimedOut => ("timed out".to_string(), self.red),
+                    BackgroundJobStatus::Killed => ("killed".to_string(), self.dimmed),
+                };
+                let duration = duration_ms
+                    .map(|ms| format!(" in {}", format_duration(Duration::from_millis(ms))))
+                    .unwrap_or_default();
+                ts_println!(
+                    self,
+                    "{} {job_id} {outcome}{duration}: {command}",
+                    "background job".style(style)
+                );
+            }
+            EventMsg::ProviderEndpointSwitched(ProviderEndpointSwitchedEvent {
+                provider,
+                from,
+                to,
+                reason,
+            }) => {
+                ts_println!(
+                    self,
+                    "{} {provider} requests moved from {from} to {to}: {reason}",
+                    "endpoint switched".style(self.magenta)
+                );
+            }
+            EventMsg::ProviderSwitched(ProviderSwitchedEvent { from, to, reason }) => {
+                ts_println!(
+                    self,
+                    "{} moved from {from} to {to}: {reason}",
+                    "provider switched".style(self.magenta)
+                );
+            }
+            EventMsg::McpServerStatus(McpServerStatusEvent {
+                server,
+                status,
+                message,
+            }) => {
+                let (label, style) = match status {
+                    McpServerStatus::Healthy => ("healthy", self.green),
+                    McpServerStatus::Degraded => ("degraded", self.magenta),
+                    McpServerStatus::Failed => ("failed", self.red),
+                };
+                ts_println!(
+                    self,
+                    "{} {server} {label}: {message}",
+                    "mcp server".style(style)
+                );
+            }
         }
         CodexStatus::Running
     }
+
+    fn flush_deadline(&self) -> Option<Instant> {
+        self.delta_coalescer.deadline()
+    }
+
+    fn flush_pending_output(&mut self) {
+        if let Some(text) = self.delta_coalescer.take() {
+            print!("{text}");
+            #[expect(clippy::expect_used)]
+            std::io::stdout().flush().expect("could not flush stdout");
+        }
+    }
 }
 
 fn escape_command(command: &[String]) -> String {
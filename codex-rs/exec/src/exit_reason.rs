@@ -0,0 +1,76 @@
+//! Why a `codex exec` run failed, worked out from the events of the session.
+
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::ErrorKind;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_protocol::exec_events::ExitReason;
+
+/// Tracks why the run failed, the first failure winning.
+///
+/// A failed command does not fail the run by itself: the agent may expect it
+/// or work around it. The task fails when the model says so with the
+/// `report_failure` tool, or when the turn ends with an error.
+#[derive(Debug)]
+pub struct ExitReasonTracker {
+    reason: ExitReason,
+    /// Whether the last command of the current turn was blocked by the
+    /// sandbox. A turn that ends right after that means the agent could not
+    /// get past the sandbox.
+    last_command_sandbox_denied: bool,
+}
+
+impl Default for ExitReasonTracker {
+    fn default() -> Self {
+        Self {
+            reason: ExitReason::Success,
+            last_command_sandbox_denied: false,
+        }
+    }
+}
+
+impl ExitReasonTracker {
+    pub fn reason(&self) -> ExitReason {
+        self.reason
+    }
+
+    /// Record `reason` as why the run failed, unless an earlier failure was.
+    pub fn fail(&mut self, reason: ExitReason) {
+        if self.reason == ExitReason::Success {
+            self.reason = reason;
+        }
+    }
+
+    pub fn observe(&mut self, msg: &EventMsg) {
+        match msg {
+            EventMsg::Error(ErrorEvent { kind, .. }) => self.fail(exit_reason_for_error(*kind)),
+            EventMsg::LoginRequired(_) => self.fail(ExitReason::ProviderError),
+            EventMsg::ExecApprovalRequest(_)
+            | EventMsg::ApplyPatchApprovalRequest(_)
+            | EventMsg::SecretRequest(_)
+            | EventMsg::McpSamplingApprovalRequest(_) => self.fail(ExitReason::ApprovalRequired),
+            EventMsg::TaskStarted(_) => self.last_command_sandbox_denied = false,
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent { sandbox_denied, .. }) => {
+                self.last_command_sandbox_denied = *sandbox_denied;
+            }
+            EventMsg::TaskComplete(TaskCompleteEvent { failure, .. }) => {
+                if std::mem::take(&mut self.last_command_sandbox_denied) {
+                    self.fail(ExitReason::SandboxDenied);
+                } else if failure.is_some() {
+                    self.fail(ExitReason::TaskFailed);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn exit_reason_for_error(kind: ErrorKind) -> ExitReason {
+    match kind {
+        ErrorKind::Provider | ErrorKind::Auth => ExitReason::ProviderError,
+        ErrorKind::Sandbox => ExitReason::SandboxDenied,
+        ErrorKind::BudgetExceeded => ExitReason::BudgetExceeded,
+        ErrorKind::Other => ExitReason::TaskFailed,
+    }
+}
@@ -2,20 +2,28 @@ mod cli;
 mod event_processor;
 mod event_processor_with_human_output;
 pub mod event_processor_with_json_output;
-pub mod exec_events;
-pub mod experimental_event_processor_with_json_output;
+pub mod exit_reason;
 
 use std::io::IsTerminal;
 use std::io::Read;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 pub use cli::Cli;
+pub use cli::Command as ExecCommand;
+pub use cli::ResumeArgs;
+pub use cli::SurveyArgs;
 use codex_core::AuthManager;
 use codex_core::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 use codex_core::ConversationManager;
 use codex_core::NewConversation;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::file_attachments::file_attachment_item;
 use codex_core::git_info::get_git_repo_root;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
@@ -23,26 +31,38 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TokenCountEvent;
+use codex_core::survey::SURVEY_WRAP_UP_PROMPT;
+use codex_core::survey::SurveyBudget;
+use codex_core::survey::SurveyBudgetAction;
+use codex_core::survey::build_survey_prompt;
+use codex_core::survey::survey_path;
+use codex_core::worktree::Worktree;
+use codex_core::worktree::create_worktree;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
+pub use codex_protocol::exec_events;
+use codex_protocol::exec_events::ExitReason;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
-use experimental_event_processor_with_json_output::ExperimentalEventProcessorWithJsonOutput;
 use serde_json::Value;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
 
-use crate::cli::Command as ExecCommand;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
 use crate::event_processor_with_json_output::EventProcessorWithJsonOutput;
+use crate::exit_reason::ExitReasonTracker;
 use codex_core::find_conversation_path_by_id_str;
 
 pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
     let Cli {
         command,
         images,
+        attach_urls,
+        attach,
         model: model_cli_arg,
         oss,
         config_profile,
@@ -53,22 +73,62 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         color,
         last_message_file,
         json: json_mode,
-        experimental_json,
         sandbox_mode: sandbox_mode_cli_arg,
         prompt,
         output_schema: output_schema_path,
         include_plan_tool,
+        resume,
+        last,
+        timeout,
+        max_turns,
+        max_runtime,
+        max_cost,
+        auto_commit,
+        worktree,
+        create_pr,
         config_overrides,
     } = cli;
 
+    // `--resume <SESSION_ID>` and `--last` are shorthands for the `resume`
+    // subcommand, so scripts can continue a session in one command.
+    let command = match command {
+        None if resume.is_some() || last => Some(ExecCommand::Resume(ResumeArgs {
+            session_id: resume,
+            last,
+            prompt: None,
+            response_id: None,
+        })),
+        Some(_) if resume.is_some() || last => {
+            eprintln!("--resume and --last cannot be combined with a subcommand.");
+            std::process::exit(1);
+        }
+        command => command,
+    };
+
+    let resume_response_id = match &command {
+        Some(ExecCommand::Resume(args)) => args.response_id.clone(),
+        _ => None,
+    };
+
     // Determine the prompt source (parent or subcommand) and read from stdin if needed.
     let prompt_arg = match &command {
+        // Picking up a background response does not send a new prompt.
+        Some(ExecCommand::Resume(args)) if args.response_id.is_some() => Some(String::new()),
         // Allow prompt before the subcommand by falling back to the parent-level prompt
         // when the Resume subcommand did not provide its own prompt.
         Some(ExecCommand::Resume(args)) => args.prompt.clone().or(prompt),
+        Some(ExecCommand::Survey(args)) => Some(build_survey_prompt(args.focus.as_deref())),
         None => prompt,
     };
 
+    // Stdin can only be read once: for an attachment or for the prompt.
+    let attach_stdin = attach.iter().any(|path| path.as_os_str() == "-");
+    if attach_stdin && prompt_arg.as_deref().is_none_or(|p| p == "-") {
+        eprintln!("`--attach -` reads stdin, so pass the prompt as an argument.");
+        std::process::exit(1);
+    }
+    let attachments = load_attachments(&attach);
+
     let prompt = match prompt_arg {
         Some(p) if p != "-" => p,
         // Either `-` was passed or no positional arg.
@@ -116,24 +176,37 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
 
     // TODO(mbolin): Take a more thoughtful approach to logging.
     let default_level = "error";
-    let _ = tracing_subscriber::fmt()
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(stderr_with_ansi)
+        .with_writer(std::io::stderr)
         // Fallback to the `default_level` log filter if the environment
         // variable is not set _or_ contains an invalid value
-        .with_env_filter(
+        .with_filter(
             EnvFilter::try_from_default_env()
                 .or_else(|_| EnvFilter::try_new(default_level))
                 .unwrap_or_else(|_| EnvFilter::new(default_level)),
-        )
-        .with_ansi(stderr_with_ansi)
-        .with_writer(std::io::stderr)
+        );
+    // The log filter applies to stderr only, not to what is exported.
+    let session_log_layer = find_codex_home()
+        .ok()
+        .and_then(|codex_home| codex_core::session_log::layer(&codex_home).ok());
+    let (otel_layer, otel_guard) = codex_core::otel::layer("codex-exec").unzip();
+    let _ = tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(session_log_layer)
+        .with(otel_layer)
         .try_init();
 
+    // Surveys only read the repository, so default them to a read-only sandbox.
+    let is_survey = matches!(command, Some(ExecCommand::Survey(_)));
     let sandbox_mode = if full_auto {
         Some(SandboxMode::WorkspaceWrite)
     } else if dangerously_bypass_approvals_and_sandbox {
         Some(SandboxMode::DangerFullAccess)
     } else {
-        sandbox_mode_cli_arg.map(Into::<SandboxMode>::into)
+        sandbox_mode_cli_arg
+            .map(Into::<SandboxMode>::into)
+            .or(is_survey.then_some(SandboxMode::ReadOnly))
     };
 
     // When using `--oss`, let the bootstrapper pick the model (defaulting to
@@ -153,6 +226,21 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         None // No specific model provider override.
     };
 
+    // With `--worktree`, the session runs in a new worktree of the repository.
+    let (worktree, cwd) = if worktree {
+        let cwd = match cwd {
+            Some(cwd) => cwd,
+            None => std::env::current_dir()?,
+        };
+        let worktree = create_worktree(&find_codex_home()?, &cwd)
+            .await
+            .map_err(|e| anyhow::anyhow!("--worktree needs a git repository: {e}"))?;
+        let session_cwd = worktree.path_for(&cwd);
+        (Some(worktree), Some(session_cwd))
+    } else {
+        (None, cwd)
+    };
+
     // Load configuration and determine approval policy
     let overrides = ConfigOverrides {
         model,
@@ -181,23 +269,44 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
-    let mut event_processor: Box<dyn EventProcessor> = match (json_mode, experimental_json) {
-        (_, true) => Box::new(ExperimentalEventProcessorWithJsonOutput::new(
-            last_message_file.clone(),
-        )),
-        (true, _) => {
-            eprintln!(
-                "The existing `--json` output format is being deprecated. Please try the new format using `--experimental-json`."
-            );
+    let mut config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    // Automation needs to know when the agent gave up on the task.
+    config.include_report_failure_tool = true;
+    if let Some(max_turns) = max_turns {
+        config.max_turns = Some(max_turns);
+    }
+    if let Some(seconds) = max_runtime {
+        config.max_runtime = Some(Duration::from_secs(seconds));
+    }
+    if let Some(max_cost) = max_cost {
+        config.session_budget_usd = Some(max_cost);
+    }
+    if auto_commit {
+        config.auto_commit.enabled = true;
+    }
 
-            Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone()))
+    // A survey writes its final message to the per-repository survey file
+    // unless an explicit output file was requested.
+    let mut survey_budget = None;
+    let mut last_message_file = last_message_file;
+    if let Some(ExecCommand::Survey(args)) = &command {
+        survey_budget = Some(SurveyBudget::new(args.max_tokens));
+        if last_message_file.is_none() {
+            let path = survey_path(&config.codex_home, &config.cwd);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            last_message_file = Some(path);
         }
-        _ => Box::new(EventProcessorWithHumanOutput::create_with_ansi(
+    }
+    let mut event_processor: Box<dyn EventProcessor> = if json_mode {
+        Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone()))
+    } else {
+        Box::new(EventProcessorWithHumanOutput::create_with_ansi(
             stdout_with_ansi,
             &config,
             last_message_file.clone(),
-        )),
+        ))
     };
 
     if oss {
@@ -218,8 +327,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         std::process::exit(1);
     }
 
-    let conversation_manager =
-        ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+    let conversation_manager = ConversationManager::new(AuthManager::shared_from_config(&config));
 
     // Handle resume subcommand by resolving a rollout path and using explicit resume API.
     let NewConversation {
@@ -234,7 +342,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 .resume_conversation_from_rollout(
                     config.clone(),
                     path,
-                    AuthManager::shared(config.codex_home.clone()),
+                    AuthManager::shared_from_config(&config),
                 )
                 .await?
         } else {
@@ -302,63 +410,189 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         info!("Sent images with event ID: {initial_images_event_id}");
         while let Ok(event) = conversation.next_event().await {
             if event.id == initial_images_event_id
-                && matches!(
-                    event.msg,
-                    EventMsg::TaskComplete(TaskCompleteEvent {
-                        last_agent_message: _,
-                    })
-                )
+                && matches!(event.msg, EventMsg::TaskComplete(TaskCompleteEvent { .. }))
             {
                 break;
             }
         }
     }
 
-    // Send the prompt.
-    let items: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
-    let initial_prompt_task_id = conversation
-        .submit(Op::UserTurn {
-            items,
-            cwd: default_cwd,
-            approval_policy: default_approval_policy,
-            sandbox_policy: default_sandbox_policy,
-            model: default_model,
-            effort: default_effort,
-            summary: default_summary,
-            final_output_json_schema: output_schema,
-        })
-        .await?;
+    // Send the prompt, or pick up the background response instead.
+    let initial_prompt_task_id = if let Some(response_id) = resume_response_id {
+        conversation
+            .submit(Op::ResumeBackgroundResponse { response_id })
+            .await?
+    } else {
+        let mut items: Vec<InputItem> = vec![InputItem::Text { text: prompt }];
+        items.extend(attachments);
+        items.extend(attach_urls.into_iter().map(|url| InputItem::Url { url }));
+        conversation
+            .submit(Op::UserTurn {
+                items,
+                cwd: default_cwd,
+                approval_policy: default_approval_policy,
+                sandbox_policy: default_sandbox_policy,
+                model: default_model,
+                effort: default_effort,
+                summary: default_summary,
+                final_output_json_schema: output_schema,
+            })
+            .await?
+    };
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
+    // Interrupt the run once `--timeout` has passed.
+    let timed_out = Arc::new(AtomicBool::new(false));
+    if let Some(seconds) = timeout {
+        let conversation = conversation.clone();
+        let timed_out = timed_out.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(seconds)).await;
+            timed_out.store(true, Ordering::Relaxed);
+            conversation.submit(Op::Interrupt).await.ok();
+        });
+    }
+
     // Run the loop until the task is complete.
-    // Track whether a fatal error was reported by the server so we can
-    // exit with a non-zero status for automation-friendly signaling.
-    let mut error_seen = false;
-    while let Some(event) = rx.recv().await {
-        if matches!(event.msg, EventMsg::Error(_)) {
-            error_seen = true;
+    // Track why the run failed, the first failure winning, so we can exit
+    // with a status that tells automation what went wrong.
+    let mut exit_reason = ExitReasonTracker::default();
+    let mut stopping = false;
+    // With `--create-pr`, whether the pull request has been asked for.
+    let mut pull_request_requested = false;
+    loop {
+        let event = match event_processor.flush_deadline() {
+            Some(deadline) => match tokio::time::timeout_at(deadline.into(), rx.recv()).await {
+                Ok(event) => event,
+                Err(_) => {
+                    event_processor.flush_pending_output();
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        let Some(event) = event else {
+            break;
+        };
+        exit_reason.observe(&event.msg);
+        // Nobody is there to answer an approval request: stop instead of
+        // waiting forever.
+        if matches!(
+            event.msg,
+            EventMsg::ExecApprovalRequest(_)
+                | EventMsg::ApplyPatchApprovalRequest(_)
+                | EventMsg::SecretRequest(_)
+                | EventMsg::McpSamplingApprovalRequest(_)
+        ) && !stopping
+        {
+            stopping = true;
+            conversation.submit(Op::Interrupt).await?;
+        }
+        if let (
+            Some(budget),
+            EventMsg::TokenCount(TokenCountEvent {
+                info: Some(info), ..
+            }),
+        ) = (survey_budget.as_mut(), &event.msg)
+        {
+            match budget.observe(info.total_token_usage.blended_total()) {
+                Some(SurveyBudgetAction::WrapUp) => {
+                    let items = vec![InputItem::Text {
+                        text: SURVEY_WRAP_UP_PROMPT.to_string(),
+                    }];
+                    conversation.submit(Op::UserInput { items }).await?;
+                }
+                Some(SurveyBudgetAction::Stop) => {
+                    eprintln!("Survey exceeded its token budget; stopping.");
+                    exit_reason.fail(ExitReason::TaskFailed);
+                    stopping = true;
+                    conversation.submit(Op::Interrupt).await?;
+                }
+                None => {}
+            }
+        }
+        if matches!(event.msg, EventMsg::TurnAborted(_)) {
+            if timed_out.load(Ordering::Relaxed) {
+                eprintln!(
+                    "Run exceeded its timeout of {}s; stopping.",
+                    timeout.unwrap_or_default()
+                );
+                exit_reason.fail(ExitReason::Timeout);
+                stopping = true;
+            }
+            if stopping {
+                conversation.submit(Op::Shutdown).await?;
+            }
+        }
+        if pull_request_requested
+            && matches!(
+                event.msg,
+                EventMsg::PullRequestCreated(_) | EventMsg::Error(_)
+            )
+        {
+            conversation.submit(Op::Shutdown).await?;
         }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
             CodexStatus::InitiateShutdown => {
-                conversation.submit(Op::Shutdown).await?;
+                if create_pr
+                    && !pull_request_requested
+                    && exit_reason.reason() == ExitReason::Success
+                {
+                    pull_request_requested = true;
+                    conversation.submit(Op::CreatePullRequest).await?;
+                } else {
+                    conversation.submit(Op::Shutdown).await?;
+                }
             }
             CodexStatus::Shutdown => {
                 break;
             }
         }
     }
-    if error_seen {
-        std::process::exit(1);
+    event_processor.flush_pending_output();
+    if let Some(worktree) = &worktree {
+        report_worktree(worktree).await;
+    }
+    let exit_reason = exit_reason.reason();
+    event_processor.print_exit_status(exit_reason);
+    if exit_reason != ExitReason::Success {
+        // Exiting skips destructors; export what was recorded first.
+        drop(otel_guard);
+        std::process::exit(exit_reason.code());
+    }
+    if let (Some(_), Some(path)) = (&survey_budget, &last_message_file) {
+        eprintln!("Survey saved to {}", path.display());
     }
 
     Ok(())
 }
 
+/// Removes a `--worktree` worktree the session left unchanged, or tells the
+/// user where its changes are.
+async fn report_worktree(worktree: &Worktree) {
+    match worktree.has_changes().await {
+        Ok(false) => {
+            if let Err(e) = worktree.remove().await {
+                eprintln!("Failed to remove worktree {}: {e}", worktree.path.display());
+            }
+        }
+        Ok(true) => eprintln!(
+            "Changes are on branch {} in {}. Run `codex worktree merge {}` to merge them, `codex worktree pr {}` to open a pull request, or `codex worktree remove {}` to discard them.",
+            worktree.branch,
+            worktree.path.display(),
+            worktree.name,
+            worktree.name,
+            worktree.name
+        ),
+        Err(e) => eprintln!("Failed to check worktree {}: {e}", worktree.path.display()),
+    }
+}
+
 async fn resolve_resume_path(
     config: &Config,
-    args: &crate::cli::ResumeArgs,
+    args: &ResumeArgs,
 ) -> anyhow::Result<Option<PathBuf>> {
     if args.last {
         match codex_core::RolloutRecorder::list_conversations(&config.codex_home, 1, None).await {
@@ -369,13 +603,39 @@ async fn resolve_resume_path(
             }
         }
     } else if let Some(id_str) = args.session_id.as_deref() {
-        let path = find_conversation_path_by_id_str(&config.codex_home, id_str).await?;
-        Ok(path)
+        // Starting over would silently drop the history the caller asked for.
+        match find_conversation_path_by_id_str(&config.codex_home, id_str).await? {
+            Some(path) => Ok(Some(path)),
+            None => anyhow::bail!("No recorded session with id {id_str}"),
+        }
     } else {
         Ok(None)
     }
 }
 
+/// Reads the files given with `--attach`, where `-` is stdin.
+fn load_attachments(paths: &[PathBuf]) -> Vec<InputItem> {
+    paths
+        .iter()
+        .map(|path| {
+            let (name, contents) = if path.as_os_str() == "-" {
+                let mut buffer = Vec::new();
+                let read = std::io::stdin().read_to_end(&mut buffer).map(|_| buffer);
+                ("stdin".to_string(), read)
+            } else {
+                (path.display().to_string(), std::fs::read(path))
+            };
+            match contents {
+                Ok(bytes) => file_attachment_item(&name, &bytes),
+                Err(err) => {
+                    eprintln!("Failed to read attachment {name}: {err}");
+                    std::process::exit(1);
+                }
+            }
+        })
+        .collect()
+}
+
 fn load_output_schema(path: Option<PathBuf>) -> Option<Value> {
     let path = path?;
 
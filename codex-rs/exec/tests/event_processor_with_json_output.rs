@@ -5,9 +5,13 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpToolCallBeginEvent;
+use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
 use codex_core::protocol::SessionConfiguredEvent;
+use codex_exec::event_processor_with_json_output::EventProcessorWithJsonOutput;
 use codex_exec::exec_events::AssistantMessageItem;
 use codex_exec::exec_events::CommandExecutionItem;
 use codex_exec::exec_events::CommandExecutionStatus;
@@ -15,19 +19,24 @@ use codex_exec::exec_events::ConversationErrorEvent;
 use codex_exec::exec_events::ConversationEvent;
 use codex_exec::exec_events::ConversationItem;
 use codex_exec::exec_events::ConversationItemDetails;
+use codex_exec::exec_events::ExitReason;
 use codex_exec::exec_events::ItemCompletedEvent;
 use codex_exec::exec_events::ItemStartedEvent;
 use codex_exec::exec_events::ItemUpdatedEvent;
+use codex_exec::exec_events::McpToolCallItem;
+use codex_exec::exec_events::McpToolCallStatus;
 use codex_exec::exec_events::PatchApplyStatus;
 use codex_exec::exec_events::PatchChangeKind;
 use codex_exec::exec_events::ReasoningItem;
 use codex_exec::exec_events::SessionCreatedEvent;
+use codex_exec::exec_events::SessionEndedEvent;
 use codex_exec::exec_events::TodoItem as ExecTodoItem;
 use codex_exec::exec_events::TodoListItem as ExecTodoListItem;
 use codex_exec::exec_events::TurnCompletedEvent;
+use codex_exec::exec_events::TurnDiffEvent;
 use codex_exec::exec_events::TurnStartedEvent;
 use codex_exec::exec_events::Usage;
-use codex_exec::experimental_event_processor_with_json_output::ExperimentalEventProcessorWithJsonOutput;
+use codex_exec::exec_events::WebSearchItem;
 use pretty_assertions::assert_eq;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -41,7 +50,7 @@ fn event(id: &str, msg: EventMsg) -> Event {
 
 #[test]
 fn session_configured_produces_session_created_event() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
     let session_id = codex_protocol::mcp_protocol::ConversationId::from_string(
         "67e55044-10b1-426f-9247-bb680e5fe0c8",
     )
@@ -57,6 +66,7 @@ fn session_configured_produces_session_created_event() {
             history_entry_count: 0,
             initial_messages: None,
             rollout_path,
+            sandbox_capability: None,
         }),
     );
     let out = ep.collect_conversation_events(&ev);
@@ -70,7 +80,7 @@ fn session_configured_produces_session_created_event() {
 
 #[test]
 fn task_started_produces_turn_started_event() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
     let out = ep.collect_conversation_events(&event(
         "t1",
         EventMsg::TaskStarted(codex_core::protocol::TaskStartedEvent {
@@ -90,7 +100,7 @@ fn plan_update_emits_todo_list_started_updated_and_completed() {
     use codex_core::plan_tool::StepStatus;
     use codex_core::plan_tool::UpdatePlanArgs;
 
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // First plan update => item.started (todo_list)
     let first = event(
@@ -175,6 +185,7 @@ fn plan_update_emits_todo_list_started_updated_and_completed() {
         "p3",
         EventMsg::TaskComplete(codex_core::protocol::TaskCompleteEvent {
             last_agent_message: None,
+            failure: None,
         }),
     );
     let out_complete = ep.collect_conversation_events(&complete);
@@ -200,6 +211,7 @@ fn plan_update_emits_todo_list_started_updated_and_completed() {
             }),
             ConversationEvent::TurnCompleted(TurnCompletedEvent {
                 usage: Usage::default(),
+                final_message: None,
             }),
         ]
     );
@@ -211,7 +223,7 @@ fn plan_update_after_complete_starts_new_todo_list_with_new_id() {
     use codex_core::plan_tool::StepStatus;
     use codex_core::plan_tool::UpdatePlanArgs;
 
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // First turn: start + complete
     let start = event(
@@ -229,6 +241,7 @@ fn plan_update_after_complete_starts_new_todo_list_with_new_id() {
         "t2",
         EventMsg::TaskComplete(codex_core::protocol::TaskCompleteEvent {
             last_agent_message: None,
+            failure: None,
         }),
     );
     let _ = ep.collect_conversation_events(&complete);
@@ -256,7 +269,7 @@ fn plan_update_after_complete_starts_new_todo_list_with_new_id() {
 
 #[test]
 fn agent_reasoning_produces_item_completed_reasoning() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
     let ev = event(
         "e1",
         EventMsg::AgentReasoning(AgentReasoningEvent {
@@ -279,7 +292,7 @@ fn agent_reasoning_produces_item_completed_reasoning() {
 
 #[test]
 fn agent_message_produces_item_completed_assistant_message() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
     let ev = event(
         "e1",
         EventMsg::AgentMessage(AgentMessageEvent {
@@ -302,11 +315,12 @@ fn agent_message_produces_item_completed_assistant_message() {
 
 #[test]
 fn error_event_produces_error() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
     let out = ep.collect_conversation_events(&event(
         "e1",
         EventMsg::Error(codex_core::protocol::ErrorEvent {
             message: "boom".to_string(),
+            kind: codex_core::protocol::ErrorKind::Other,
         }),
     ));
     assert_eq!(
@@ -319,7 +333,7 @@ fn error_event_produces_error() {
 
 #[test]
 fn stream_error_event_produces_error() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
     let out = ep.collect_conversation_events(&event(
         "e1",
         EventMsg::StreamError(codex_core::protocol::StreamErrorEvent {
@@ -336,7 +350,7 @@ fn stream_error_event_produces_error() {
 
 #[test]
 fn exec_command_end_success_produces_completed_command_item() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // Begin -> no output
     let begin = event(
@@ -375,6 +389,7 @@ fn exec_command_end_success_produces_completed_command_item() {
             exit_code: 0,
             duration: Duration::from_millis(5),
             formatted_output: String::new(),
+            sandbox_denied: false,
         }),
     );
     let out_ok = ep.collect_conversation_events(&end_ok);
@@ -396,7 +411,7 @@ fn exec_command_end_success_produces_completed_command_item() {
 
 #[test]
 fn exec_command_end_failure_produces_failed_command_item() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // Begin -> no output
     let begin = event(
@@ -434,6 +449,7 @@ fn exec_command_end_failure_produces_failed_command_item() {
             exit_code: 1,
             duration: Duration::from_millis(2),
             formatted_output: String::new(),
+            sandbox_denied: false,
         }),
     );
     let out_fail = ep.collect_conversation_events(&end_fail);
@@ -455,7 +471,7 @@ fn exec_command_end_failure_produces_failed_command_item() {
 
 #[test]
 fn exec_command_end_without_begin_is_ignored() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // End event arrives without a prior Begin; should produce no conversation events.
     let end_only = event(
@@ -468,6 +484,7 @@ fn exec_command_end_without_begin_is_ignored() {
             exit_code: 0,
             duration: Duration::from_millis(1),
             formatted_output: String::new(),
+            sandbox_denied: false,
         }),
     );
     let out = ep.collect_conversation_events(&end_only);
@@ -476,7 +493,7 @@ fn exec_command_end_without_begin_is_ignored() {
 
 #[test]
 fn patch_apply_success_produces_item_completed_patchapply() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // Prepare a patch with multiple kinds of changes
     let mut changes = std::collections::HashMap::new();
@@ -558,7 +575,7 @@ fn patch_apply_success_produces_item_completed_patchapply() {
 
 #[test]
 fn patch_apply_failure_produces_item_completed_patchapply_failed() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     let mut changes = std::collections::HashMap::new();
     changes.insert(
@@ -612,7 +629,7 @@ fn patch_apply_failure_produces_item_completed_patchapply_failed() {
 
 #[test]
 fn task_complete_produces_turn_completed_with_usage() {
-    let mut ep = ExperimentalEventProcessorWithJsonOutput::new(None);
+    let mut ep = EventProcessorWithJsonOutput::new(None);
 
     // First, feed a TokenCount event with known totals.
     let usage = codex_core::protocol::TokenUsage {
@@ -626,12 +643,14 @@ fn task_complete_produces_turn_completed_with_usage() {
         total_token_usage: usage.clone(),
         last_token_usage: usage,
         model_context_window: None,
+        estimated_cost_usd: None,
     };
     let token_count_event = event(
         "e1",
         EventMsg::TokenCount(codex_core::protocol::TokenCountEvent {
             info: Some(info),
             rate_limits: None,
+            estimated_prompt_tokens: None,
         }),
     );
     assert!(
@@ -644,6 +663,7 @@ fn task_complete_produces_turn_completed_with_usage() {
         "e2",
         EventMsg::TaskComplete(codex_core::protocol::TaskCompleteEvent {
             last_agent_message: Some("done".to_string()),
+            failure: None,
         }),
     );
     let out = ep.collect_conversation_events(&complete_event);
@@ -655,6 +675,129 @@ fn task_complete_produces_turn_completed_with_usage() {
                 cached_input_tokens: 200,
                 output_tokens: 345,
             },
+            final_message: Some("done".to_string()),
         })]
     );
 }
+
+#[test]
+fn mcp_tool_call_produces_started_and_completed_items() {
+    let mut ep = EventProcessorWithJsonOutput::new(None);
+    let invocation = McpInvocation {
+        server: "docs".to_string(),
+        tool: "search".to_string(),
+        arguments: None,
+    };
+    let begin = event(
+        "m1",
+        EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
+            call_id: "call-1".to_string(),
+            invocation: invocation.clone(),
+        }),
+    );
+    assert_eq!(
+        ep.collect_conversation_events(&begin),
+        vec![ConversationEvent::ItemStarted(ItemStartedEvent {
+            item: ConversationItem {
+                id: "item_0".to_string(),
+                details: ConversationItemDetails::McpToolCall(McpToolCallItem {
+                    server: "docs".to_string(),
+                    tool: "search".to_string(),
+                    status: McpToolCallStatus::InProgress,
+                }),
+            },
+        })]
+    );
+
+    let end = event(
+        "m2",
+        EventMsg::McpToolCallEnd(McpToolCallEndEvent {
+            call_id: "call-1".to_string(),
+            invocation,
+            duration: Duration::from_millis(5),
+            result: Err("server unavailable".to_string()),
+        }),
+    );
+    assert_eq!(
+        ep.collect_conversation_events(&end),
+        vec![ConversationEvent::ItemCompleted(ItemCompletedEvent {
+            item: ConversationItem {
+                id: "item_0".to_string(),
+                details: ConversationItemDetails::McpToolCall(McpToolCallItem {
+                    server: "docs".to_string(),
+                    tool: "search".to_string(),
+                    status: McpToolCallStatus::Failed,
+                }),
+            },
+        })]
+    );
+}
+
+#[test]
+fn web_search_and_turn_diff_are_reported() {
+    let mut ep = EventProcessorWithJsonOutput::new(None);
+    let search = event(
+        "w1",
+        EventMsg::WebSearchEnd(codex_core::protocol::WebSearchEndEvent {
+            call_id: "ws-1".to_string(),
+            query: "rust jsonl".to_string(),
+        }),
+    );
+    assert_eq!(
+        ep.collect_conversation_events(&search),
+        vec![ConversationEvent::ItemCompleted(ItemCompletedEvent {
+            item: ConversationItem {
+                id: "item_0".to_string(),
+                details: ConversationItemDetails::WebSearch(WebSearchItem {
+                    query: "rust jsonl".to_string(),
+                }),
+            },
+        })]
+    );
+
+    let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n".to_string();
+    let turn_diff = event(
+        "d1",
+        EventMsg::TurnDiff(codex_core::protocol::TurnDiffEvent {
+            unified_diff: diff.clone(),
+            risk: None,
+        }),
+    );
+    assert_eq!(
+        ep.collect_conversation_events(&turn_diff),
+        vec![ConversationEvent::TurnDiff(TurnDiffEvent {
+            unified_diff: diff
+        })]
+    );
+}
+
+#[test]
+fn session_ended_serializes_with_exit_code() {
+    let ended = EventProcessorWithJsonOutput::session_ended_event(ExitReason::SandboxDenied);
+    assert_eq!(
+        ended,
+        ConversationEvent::SessionEnded(SessionEndedEvent {
+            exit_code: 5,
+            reason: ExitReason::SandboxDenied,
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(&ended).expect("serialize"),
+        serde_json::json!({ "type": "session.ended", "exit_code": 5, "reason": "sandbox_denied" })
+    );
+}
+
+#[test]
+fn exit_reasons_have_distinct_codes() {
+    let reasons = [
+        ExitReason::Success,
+        ExitReason::TaskFailed,
+        ExitReason::ApprovalRequired,
+        ExitReason::ProviderError,
+        ExitReason::SandboxDenied,
+        ExitReason::Timeout,
+        ExitReason::BudgetExceeded,
+    ];
+    let codes: Vec<i32> = reasons.iter().map(|reason| reason.code()).collect();
+    assert_eq!(codes, vec![0, 1, 3, 4, 5, 6, 7]);
+}
@@ -0,0 +1,59 @@
+#![cfg(not(target_os = "windows"))]
+#![allow(clippy::expect_used, clippy::unwrap_used)]
+
+use core_test_support::responses;
+use core_test_support::test_codex_exec::test_codex_exec;
+use wiremock::matchers::any;
+
+/// Piped stdin and files given with `--attach` reach the model, binary
+/// files base64-encoded.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exec_attaches_stdin_and_files_to_the_prompt() -> anyhow::Result<()> {
+    let test = test_codex_exec();
+    let binary_path = test.cwd_path().join("blob.bin");
+    std::fs::write(&binary_path, [0u8, 159, 146, 150])?;
+
+    let server = responses::start_mock_server().await;
+    let body = responses::sse(vec![
+        responses::ev_assistant_message("m1", "looks good"),
+        responses::ev_completed("resp1"),
+    ]);
+    responses::mount_sse_once(&server, any(), body).await;
+
+    test.cmd_with_server(&server)
+        .arg("--skip-git-repo-check")
+        .arg("--attach")
+        .arg("-")
+        .arg("--attach")
+        .arg(&binary_path)
+        .arg("review this diff")
+        .write_stdin("diff --git a/src/lib.rs b/src/lib.rs\n+fn added() {}\n")
+        .assert()
+        .success();
+
+    let requests = server
+        .received_requests()
+        .await
+        .expect("failed to capture requests");
+    assert_eq!(requests.len(), 1, "expected exactly one request");
+    let body = String::from_utf8_lossy(&requests[0].body);
+    assert!(body.contains("review this diff"));
+    assert!(body.contains(
+        r#"<file_attachment name=\"stdin\">\ndiff --git a/src/lib.rs b/src/lib.rs\n+fn added() {}\n</file_attachment>"#
+    ));
+    assert!(body.contains(r#"encoding=\"base64\" bytes=\"4\">\nAJ+Slg==\n</file_attachment>"#));
+
+    Ok(())
+}
+
+#[test]
+fn exec_rejects_stdin_attachment_without_a_prompt_argument() {
+    let test = test_codex_exec();
+    test.cmd()
+        .arg("--skip-git-repo-check")
+        .arg("--attach")
+        .arg("-")
+        .write_stdin("piped")
+        .assert()
+        .code(1);
+}
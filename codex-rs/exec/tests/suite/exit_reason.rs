@@ -0,0 +1,156 @@
+#![allow(clippy::expect_used, clippy::unwrap_used)]
+
+use std::time::Duration;
+
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TaskStartedEvent;
+use codex_exec::exec_events::ExitReason;
+use codex_exec::exit_reason::ExitReasonTracker;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex_exec::test_codex_exec;
+
+fn command_end(exit_code: i32, sandbox_denied: bool) -> EventMsg {
+    EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+        call_id: "call".to_string(),
+        stdout: String::new(),
+        stderr: String::new(),
+        aggregated_output: String::new(),
+        exit_code,
+        duration: Duration::ZERO,
+        formatted_output: String::new(),
+        sandbox_denied,
+    })
+}
+
+fn run(events: &[EventMsg], failure: Option<&str>) -> ExitReason {
+    let mut tracker = ExitReasonTracker::default();
+    let mut events = events.iter().collect::<Vec<_>>();
+    let started = EventMsg::TaskStarted(TaskStartedEvent {
+        model_context_window: None,
+    });
+    let complete = EventMsg::TaskComplete(TaskCompleteEvent {
+        last_agent_message: None,
+        failure: failure.map(str::to_string),
+    });
+    events.insert(0, &started);
+    events.push(&complete);
+    for event in events {
+        tracker.observe(event);
+    }
+    tracker.reason()
+}
+
+#[test]
+fn turn_ending_after_a_sandbox_denial_is_sandbox_denied() {
+    assert_eq!(
+        run(&[command_end(1, true)], None),
+        ExitReason::SandboxDenied
+    );
+}
+
+#[test]
+fn failed_commands_do_not_fail_the_run() {
+    assert_eq!(run(&[command_end(2, false)], None), ExitReason::Success);
+}
+
+#[test]
+fn reported_failure_is_task_failed() {
+    assert_eq!(
+        run(&[command_end(0, false)], Some("the tests need a database")),
+        ExitReason::TaskFailed
+    );
+}
+
+#[test]
+fn recovered_failures_do_not_fail_the_run() {
+    assert_eq!(
+        run(&[command_end(1, true), command_end(0, false)], None),
+        ExitReason::Success
+    );
+}
+
+/// The agent runs a command that fails, reports that it cannot complete the
+/// task, and ends its turn.
+#[cfg(not(target_os = "windows"))]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exits_with_task_failed_when_the_agent_reports_failure() -> anyhow::Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let test = test_codex_exec();
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call-1", "shell", r#"{"command":["false"]}"#),
+                ev_completed("resp-1"),
+            ]),
+            sse(vec![
+                ev_function_call(
+                    "call-2",
+                    "report_failure",
+                    r#"{"reason":"the command keeps failing"}"#,
+                ),
+                ev_completed("resp-2"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-1", "The command failed."),
+                ev_completed("resp-3"),
+            ]),
+        ],
+    )
+    .await;
+
+    test.cmd_with_server(&server)
+        .arg("--skip-git-repo-check")
+        .arg("-s")
+        .arg("danger-full-access")
+        .arg("run false")
+        .assert()
+        .code(1);
+
+    Ok(())
+}
+
+/// The agent runs a command that fails and ends its turn without reporting
+/// a failure: the failure was expected.
+#[cfg(not(target_os = "windows"))]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exits_with_success_when_a_failed_command_is_not_reported() -> anyhow::Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let test = test_codex_exec();
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call-1", "shell", r#"{"command":["false"]}"#),
+                ev_completed("resp-1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-1", "`false` exits with 1, as expected."),
+                ev_completed("resp-2"),
+            ]),
+        ],
+    )
+    .await;
+
+    test.cmd_with_server(&server)
+        .arg("--skip-git-repo-check")
+        .arg("-s")
+        .arg("danger-full-access")
+        .arg("run false")
+        .assert()
+        .code(0);
+
+    Ok(())
+}
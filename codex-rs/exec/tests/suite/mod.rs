@@ -1,5 +1,7 @@
 // Aggregates all former standalone integration tests as modules.
 mod apply_patch;
+mod attach;
+mod exit_reason;
 mod output_schema;
 mod resume;
 mod sandbox;
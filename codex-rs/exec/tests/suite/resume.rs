@@ -266,3 +266,74 @@ fn exec_resume_preserves_cli_configuration_overrides() -> anyhow::Result<()> {
     assert!(content.contains(&marker2));
     Ok(())
 }
+
+/// The session id printed by `--json` can be passed to `--resume` to run the
+/// next instruction in the same session.
+#[test]
+fn exec_resume_flag_continues_json_session() -> anyhow::Result<()> {
+    let home = TempDir::new()?;
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/cli_responses_fixture.sse");
+    let run = |args: &[&str]| -> anyhow::Result<String> {
+        let output = Command::cargo_bin("codex-exec")
+            .context("should find binary for codex-exec")?
+            .env("CODEX_HOME", home.path())
+            .env("OPENAI_API_KEY", "dummy")
+            .env("CODEX_RS_SSE_FIXTURE", &fixture)
+            .env("OPENAI_BASE_URL", "http://unused.local")
+            .arg("--skip-git-repo-check")
+            .arg("--json")
+            .arg("-C")
+            .arg(env!("CARGO_MANIFEST_DIR"))
+            .args(args)
+            .output()
+            .context("codex-exec should run")?;
+        assert!(output.status.success(), "run failed: {output:?}");
+        let stdout = String::from_utf8(output.stdout)?;
+        let session_id = stdout
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .find(|event| event["type"] == "session.created")
+            .and_then(|event| event["session_id"].as_str().map(ToString::to_string))
+            .expect("no session.created event");
+        Ok(session_id)
+    };
+
+    let marker = format!("resume-flag-{}", Uuid::new_v4());
+    let session_id = run(&[&format!("echo {marker}")])?;
+
+    let marker2 = format!("resume-flag-2-{}", Uuid::new_v4());
+    let resumed_id = run(&["--resume", &session_id, &format!("echo {marker2}")])?;
+    assert_eq!(resumed_id, session_id);
+
+    let marker3 = format!("resume-flag-3-{}", Uuid::new_v4());
+    let last_id = run(&["--last", &format!("echo {marker3}")])?;
+    assert_eq!(last_id, session_id);
+
+    let sessions_dir = home.path().join("sessions");
+    let path = find_session_file_containing_marker(&sessions_dir, &marker3)
+        .expect("no session file containing marker3");
+    let content = std::fs::read_to_string(&path)?;
+    assert!(content.contains(&marker));
+    assert!(content.contains(&marker2));
+    Ok(())
+}
+
+#[test]
+fn exec_resume_unknown_session_id_fails() -> anyhow::Result<()> {
+    let home = TempDir::new()?;
+    Command::cargo_bin("codex-exec")
+        .context("should find binary for codex-exec")?
+        .env("CODEX_HOME", home.path())
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", "http://unused.local")
+        .arg("--skip-git-repo-check")
+        .arg("-C")
+        .arg(env!("CARGO_MANIFEST_DIR"))
+        .arg("--resume")
+        .arg(Uuid::new_v4().to_string())
+        .arg("continue")
+        .assert()
+        .failure();
+    Ok(())
+}
@@ -1,4 +1,5 @@
 #![cfg(unix)]
+use codex_core::config_types::ResourceLimits;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::spawn::StdioPolicy;
 use std::collections::HashMap;
@@ -25,6 +26,7 @@ async fn spawn_command_under_sandbox(
         command_cwd,
         sandbox_policy,
         sandbox_cwd,
+        &ResourceLimits::default(),
         stdio_policy,
         env,
     )
@@ -48,6 +50,7 @@ async fn spawn_command_under_sandbox(
         command_cwd,
         sandbox_policy,
         sandbox_cwd,
+        &ResourceLimits::default(),
         stdio_policy,
         env,
     )
@@ -72,6 +75,8 @@ async fn python_multiprocessing_lock_works_under_sandbox() {
         network_access: false,
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
+        network_allowlist: Vec::new(),
+        deny: Vec::new(),
     };
 
     let python_code = r#"import multiprocessing
@@ -132,6 +137,8 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
         network_access: false,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        network_allowlist: Vec::new(),
+        deny: Vec::new(),
     };
 
     // Attempt to write inside the command cwd, which is outside of the sandbox policy cwd.
@@ -5,8 +5,8 @@ use core_test_support::responses;
 use core_test_support::test_codex_exec::test_codex_exec;
 use wiremock::matchers::any;
 
-/// Verify that when the server reports an error, `codex-exec` exits with a
-/// non-zero status code so automation can detect failures.
+/// Verify that when the server reports an error, `codex-exec` exits with the
+/// provider error status code so automation can detect failures.
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn exits_non_zero_when_server_reports_error() -> anyhow::Result<()> {
     let test = test_codex_exec();
@@ -23,12 +23,29 @@ async fn exits_non_zero_when_server_reports_error() -> anyhow::Result<()> {
     })]);
     responses::mount_sse_once(&server, any(), body).await;
 
-    test.cmd_with_server(&server)
+    let output = test
+        .cmd_with_server(&server)
         .arg("--skip-git-repo-check")
         .arg("tell me something")
-        .arg("--experimental-json")
+        .arg("--json")
         .assert()
-        .code(1);
+        .code(4)
+        .get_output()
+        .stdout
+        .clone();
+
+    // The JSONL stream ends by reporting the exit code.
+    let stdout = String::from_utf8(output)?;
+    let last: serde_json::Value =
+        serde_json::from_str(stdout.lines().last().expect("no JSONL output"))?;
+    assert_eq!(
+        last,
+        serde_json::json!({
+            "type": "session.ended",
+            "exit_code": 4,
+            "reason": "provider_error",
+        })
+    );
 
     Ok(())
 }
@@ -7,6 +7,7 @@ use nucleo_matcher::pattern::CaseMatching;
 use nucleo_matcher::pattern::Normalization;
 use nucleo_matcher::pattern::Pattern;
 use serde::Serialize;
+use std::borrow::Cow;
 use std::cell::UnsafeCell;
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
@@ -19,13 +20,18 @@ use std::sync::atomic::Ordering;
 use tokio::process::Command;
 
 mod cli;
+mod symbols;
 
 pub use cli::Cli;
+pub use symbols::SymbolMatch;
+pub use symbols::SymbolSearchResults;
+pub use symbols::run_symbol_search;
 
 /// A single match result returned from the search.
 ///
 /// * `score` – Relevance score returned by `nucleo_matcher`.
 /// * `path`  – Path to the matched file (relative to the search directory).
+///   Directories, when requested, end with `/`.
 /// * `indices` – Optional list of character indices that matched the query.
 ///   These are only filled when the caller of [`run`] sets
 ///   `compute_indices` to `true`.  The indices vector follows the
@@ -105,6 +111,7 @@ pub async fn run_main<T: Reporter>(
         threads,
         cancel_flag,
         compute_indices,
+        false,
     )?;
     let match_count = matches.len();
     let matches_truncated = total_match_count > match_count;
@@ -120,7 +127,9 @@ pub async fn run_main<T: Reporter>(
 }
 
 /// The worker threads will periodically check `cancel_flag` to see if they
-/// should stop processing files.
+/// should stop processing files. Directories are matched as well when
+/// `include_dirs` is set.
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     pattern_text: &str,
     limit: NonZero<usize>,
@@ -129,6 +138,7 @@ pub fn run(
     threads: NonZero<usize>,
     cancel_flag: Arc<AtomicBool>,
     compute_indices: bool,
+    include_dirs: bool,
 ) -> anyhow::Result<FileSearchResults> {
     let pattern = create_pattern(pattern_text);
     // Create one BestMatchesList per worker thread so that each worker can
@@ -186,8 +196,8 @@ pub fn run(
         let cancel = cancel_flag.clone();
 
         Box::new(move |entry| {
-            if let Some(path) = get_file_path(&entry, search_directory) {
-                best_list.insert(path);
+            if let Some(path) = get_file_path(&entry, search_directory, include_dirs) {
+                best_list.insert(&path);
             }
 
             processed += 1;
@@ -202,18 +212,23 @@ pub fn run(
     fn get_file_path<'a>(
         entry_result: &'a Result<ignore::DirEntry, ignore::Error>,
         search_directory: &std::path::Path,
-    ) -> Option<&'a str> {
+        include_dirs: bool,
+    ) -> Option<Cow<'a, str>> {
         let entry = match entry_result {
             Ok(e) => e,
             Err(_) => return None,
         };
-        if entry.file_type().is_some_and(|ft| ft.is_dir()) {
+        let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+        if is_dir && !include_dirs {
             return None;
         }
         let path = entry.path();
-        match path.strip_prefix(search_directory) {
-            Ok(rel_path) => rel_path.to_str(),
-            Err(_) => None,
+        let rel_path = path.strip_prefix(search_directory).ok()?.to_str()?;
+        match (is_dir, rel_path) {
+            // The search directory itself.
+            (true, "") => None,
+            (true, dir) => Some(Cow::Owned(format!("{dir}/"))),
+            (false, file) => Some(Cow::Borrowed(file)),
         }
     }
 
@@ -403,4 +418,33 @@ mod tests {
 
         assert_eq!(matches, expected);
     }
+
+    #[test]
+    fn directories_match_with_a_trailing_slash_when_requested() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("widgets")).expect("mkdir");
+        std::fs::write(dir.path().join("widgets/button.rs"), "").expect("write");
+        let search = |include_dirs| {
+            let results = run(
+                "widgets",
+                NonZero::new(10).expect("non-zero"),
+                dir.path(),
+                Vec::new(),
+                NonZero::new(1).expect("non-zero"),
+                Arc::new(AtomicBool::new(false)),
+                false,
+                include_dirs,
+            )
+            .expect("search");
+            let mut paths: Vec<String> = results.matches.into_iter().map(|m| m.path).collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(search(false), vec!["widgets/button.rs".to_string()]);
+        assert_eq!(
+            search(true),
+            vec!["widgets/".to_string(), "widgets/button.rs".to_string()]
+        );
+    }
 }
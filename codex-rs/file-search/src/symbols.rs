@@ -0,0 +1,226 @@
+//! Lightweight symbol index used for `@`-style symbol completion.
+//!
+//! This is intentionally not a language server: it walks the same set of
+//! files as [`crate::run`] (respecting `.gitignore` and friends) and picks up
+//! identifiers that follow common definition keywords (`fn`, `struct`,
+//! `class`, `def`, ...). The resulting names are fuzzy-matched with the same
+//! `nucleo_matcher` pattern used for file paths so that ranking feels
+//! consistent between the two kinds of completion.
+
+use ignore::WalkBuilder;
+use nucleo_matcher::Matcher;
+use nucleo_matcher::Utf32Str;
+use serde::Serialize;
+use std::num::NonZero;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use crate::create_pattern;
+
+/// Files larger than this are skipped; they are almost always generated or
+/// vendored and would dominate the scan time.
+const MAX_FILE_SIZE_BYTES: u64 = 512 * 1024;
+
+/// Keywords that introduce a named definition in the languages we care most
+/// about. The identifier immediately following the keyword is recorded.
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "struct",
+    "enum",
+    "trait",
+    "type",
+    "mod",
+    "union",
+    "class",
+    "interface",
+    "def",
+    "function",
+    "func",
+];
+
+/// A single symbol match returned from [`run_symbol_search`].
+///
+/// * `name` – The identifier that was matched.
+/// * `kind` – The definition keyword that introduced the symbol.
+/// * `path` – Path to the file (relative to the search directory).
+/// * `line` – 1-based line number of the definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolMatch {
+    pub score: u32,
+    pub name: String,
+    pub kind: String,
+    pub path: String,
+    pub line: usize,
+}
+
+pub struct SymbolSearchResults {
+    pub matches: Vec<SymbolMatch>,
+    pub total_match_count: usize,
+}
+
+/// Scan `search_directory` for symbol definitions whose name fuzzy-matches
+/// `pattern_text`. The walk stops early if `cancel_flag` is set.
+pub fn run_symbol_search(
+    pattern_text: &str,
+    limit: NonZero<usize>,
+    search_directory: &Path,
+    cancel_flag: Arc<AtomicBool>,
+) -> anyhow::Result<SymbolSearchResults> {
+    let pattern = create_pattern(pattern_text);
+    let mut matcher = Matcher::new(nucleo_matcher::Config::DEFAULT);
+    let mut utf32buf = Vec::<char>::new();
+    let mut matches: Vec<SymbolMatch> = Vec::new();
+
+    let walker = WalkBuilder::new(search_directory)
+        .hidden(false)
+        .require_git(false)
+        .build();
+
+    for entry in walker {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return Ok(SymbolSearchResults {
+                matches: Vec::new(),
+                total_match_count: 0,
+            });
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        if entry
+            .metadata()
+            .map(|m| m.len() > MAX_FILE_SIZE_BYTES)
+            .unwrap_or(true)
+        {
+            continue;
+        }
+        let Some(rel_path) = entry
+            .path()
+            .strip_prefix(search_directory)
+            .ok()
+            .and_then(|p| p.to_str())
+        else {
+            continue;
+        };
+        // Non-UTF-8 files are treated as binary and skipped.
+        let Ok(contents) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+
+        for (line_idx, line) in contents.lines().enumerate() {
+            for (kind, name) in extract_definitions(line) {
+                let haystack: Utf32Str<'_> = Utf32Str::new(name, &mut utf32buf);
+                if let Some(score) = pattern.score(haystack, &mut matcher) {
+                    matches.push(SymbolMatch {
+                        score,
+                        name: name.to_string(),
+                        kind: kind.to_string(),
+                        path: rel_path.to_string(),
+                        line: line_idx + 1,
+                    });
+                }
+            }
+        }
+    }
+
+    let total_match_count = matches.len();
+    sort_symbol_matches(&mut matches);
+    matches.truncate(limit.get());
+
+    Ok(SymbolSearchResults {
+        matches,
+        total_match_count,
+    })
+}
+
+/// Sort by descending score, then by name, path and line so that results are
+/// stable across runs.
+fn sort_symbol_matches(matches: &mut [SymbolMatch]) {
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+}
+
+/// Return `(keyword, identifier)` pairs for every definition found on `line`.
+fn extract_definitions(line: &str) -> Vec<(&str, &str)> {
+    let mut out = Vec::new();
+    let mut tokens = line
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| !t.is_empty())
+        .peekable();
+    while let Some(token) = tokens.next() {
+        if let Some(keyword) = DEFINITION_KEYWORDS.iter().find(|k| **k == token)
+            && let Some(next) = tokens.peek()
+            && is_identifier(next)
+        {
+            out.push((*keyword, *next));
+        }
+    }
+    out
+}
+
+fn is_identifier(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && !DEFINITION_KEYWORDS.contains(&token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn extracts_definitions_from_common_languages() {
+        assert_eq!(
+            extract_definitions("pub(crate) async fn run_search(x: u32) {"),
+            vec![("fn", "run_search")]
+        );
+        assert_eq!(
+            extract_definitions("class Widget(Base):"),
+            vec![("class", "Widget")]
+        );
+        assert_eq!(
+            extract_definitions("export function renderTree() {}"),
+            vec![("function", "renderTree")]
+        );
+        assert_eq!(extract_definitions("let fn = 1;"), Vec::new());
+    }
+
+    #[test]
+    fn finds_symbols_in_directory() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("lib.rs"),
+            "struct Config;\n\nfn load_config() {}\n",
+        )?;
+        std::fs::write(dir.path().join("util.py"), "def unrelated():\n    pass\n")?;
+
+        let results = run_symbol_search(
+            "config",
+            NonZero::new(10).ok_or_else(|| anyhow::anyhow!("limit"))?,
+            dir.path(),
+            Arc::new(AtomicBool::new(false)),
+        )?;
+
+        let names: Vec<(&str, usize)> = results
+            .matches
+            .iter()
+            .map(|m| (m.name.as_str(), m.line))
+            .collect();
+        assert_eq!(results.total_match_count, 2);
+        assert!(names.contains(&("Config", 1)));
+        assert!(names.contains(&("load_config", 3)));
+        Ok(())
+    }
+}
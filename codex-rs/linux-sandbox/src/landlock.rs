@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::ffi::CString;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -6,6 +7,7 @@ use codex_core::error::CodexErr;
 use codex_core::error::Result;
 use codex_core::error::SandboxErr;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::WritableRoot;
 
 use landlock::ABI;
 use landlock::Access;
@@ -36,12 +38,21 @@ pub(crate) fn apply_sandbox_policy_to_current_thread(
     }
 
     if !sandbox_policy.has_full_disk_write_access() {
-        let writable_roots = sandbox_policy
-            .get_writable_roots_with_cwd(cwd)
-            .into_iter()
-            .map(|writable_root| writable_root.root)
-            .collect();
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
+        if matches!(sandbox_policy, SandboxPolicy::WorkspaceWrite { deny, .. } if !deny.is_empty())
+        {
+            let read_only_paths: Vec<PathBuf> = writable_roots
+                .iter()
+                .flat_map(WritableRoot::existing_read_only_paths)
+                .collect();
+            remount_read_only(&read_only_paths)?;
+        }
+        install_filesystem_landlock_rules_on_current_thread(
+            writable_roots
+                .into_iter()
+                .map(|writable_root| writable_root.root)
+                .collect(),
+        )?;
     }
 
     // TODO(ragona): Add appropriate restrictions if
@@ -50,6 +61,98 @@ pub(crate) fn apply_sandbox_policy_to_current_thread(
     Ok(())
 }
 
+/// Landlock can only grant access beneath a path, so it cannot keep parts of
+/// a writable root read-only. Instead, enter a new user and mount namespace
+/// and bind-mount each of `paths` read-only over itself. This needs a
+/// single-threaded process and unprivileged user namespaces; if either is
+/// missing the command is not run rather than run without the deny list.
+///
+/// Only paths that exist now can be mounted over. Codex refuses to run
+/// commands that name a missing denied path, but a command can still create
+/// one under a name it computes itself.
+fn remount_read_only(paths: &[PathBuf]) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let deny_list_error = |e: std::io::Error| {
+        CodexErr::Io(std::io::Error::new(
+            e.kind(),
+            format!("failed to enforce sandbox_workspace_write.deny: {e}"),
+        ))
+    };
+    // SAFETY: `geteuid` and `getegid` take no arguments and cannot fail.
+    let uid = unsafe { libc::geteuid() };
+    let gid = unsafe { libc::getegid() };
+    // SAFETY: `unshare` only takes flags and affects the calling process,
+    // which is single-threaded here; failure is reported through `errno`.
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS) } != 0 {
+        return Err(deny_list_error(std::io::Error::last_os_error()));
+    }
+    // Map the caller's ids to themselves so the command does not run as root
+    // inside the namespace.
+    std::fs::write("/proc/self/setgroups", "deny").map_err(deny_list_error)?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1")).map_err(deny_list_error)?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1")).map_err(deny_list_error)?;
+
+    let mount = |source: Option<&CString>, target: &CString, flags: libc::c_ulong| {
+        // SAFETY: `source` and `target` are NUL-terminated strings that
+        // outlive the call, and the file-system type and data may be null
+        // for bind mounts and remounts.
+        let result = unsafe {
+            libc::mount(
+                source.map_or(std::ptr::null(), |source| source.as_ptr()),
+                target.as_ptr(),
+                std::ptr::null(),
+                flags,
+                std::ptr::null(),
+            )
+        };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(deny_list_error(std::io::Error::last_os_error()))
+        }
+    };
+    let to_cstring = |path: &Path| {
+        CString::new(path.as_os_str().as_bytes()).map_err(|e| deny_list_error(e.into()))
+    };
+
+    // Keep the bind mounts below from propagating back to the host.
+    mount(
+        None,
+        &to_cstring(Path::new("/"))?,
+        libc::MS_REC | libc::MS_PRIVATE,
+    )?;
+    for path in paths {
+        let target = to_cstring(path)?;
+        mount(Some(&target), &target, libc::MS_BIND | libc::MS_REC)?;
+        // Flags such as `nosuid` on the underlying mount are locked in a user
+        // namespace and must be repeated when remounting.
+        // SAFETY: `statvfs` is plain data for which all zeroes is valid, and
+        // the call only writes to it through a pointer that outlives it.
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(target.as_ptr(), &mut stat) } != 0 {
+            return Err(deny_list_error(std::io::Error::last_os_error()));
+        }
+        let locked_flags = [
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+        ]
+        .into_iter()
+        .filter(|(st_flag, _)| stat.f_flag & st_flag != 0)
+        .fold(0, |flags, (_, ms_flag)| flags | ms_flag);
+        mount(
+            None,
+            &target,
+            libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked_flags,
+        )?;
+    }
+    Ok(())
+}
+
 /// Installs Landlock file-system rules on the current thread allowing read
 /// access to the entire file-system while restricting write access to
 /// `/dev/null` and the provided list of `writable_roots`.
@@ -1,6 +1,9 @@
+mod mcp_oauth;
 mod pkce;
 mod server;
 
+pub use mcp_oauth::McpLoginOptions;
+pub use mcp_oauth::login_mcp_server;
 pub use server::LoginServer;
 pub use server::ServerOptions;
 pub use server::ShutdownHandle;
@@ -17,5 +20,10 @@ pub use codex_core::auth::login_with_api_key;
 pub use codex_core::auth::logout;
 pub use codex_core::auth::try_read_auth_json;
 pub use codex_core::auth::write_auth_json;
+pub use codex_core::config_types::CredentialStoreMode;
+pub use codex_core::credential_store::CredentialStore;
+pub use codex_core::credential_store::FileCredentialStore;
+pub use codex_core::credential_store::KeyringCredentialStore;
+pub use codex_core::credential_store::credential_store;
 pub use codex_core::token_data::TokenData;
 pub use codex_protocol::mcp_protocol::AuthMode;
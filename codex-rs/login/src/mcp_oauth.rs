@@ -0,0 +1,148 @@
+//! Browser sign-in for MCP servers that require OAuth, used by
+//! `codex mcp login`. The protocol steps live in
+//! [`codex_core::mcp_oauth`]; this adds the local redirect handler.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use codex_core::config_types::CredentialStoreMode;
+use codex_core::credential_store::credential_store;
+use codex_core::mcp_oauth::AuthorizationRequest;
+use codex_core::mcp_oauth::authorization_url;
+use codex_core::mcp_oauth::discover_authorization_server;
+use codex_core::mcp_oauth::exchange_code;
+use codex_core::mcp_oauth::http_client;
+use codex_core::mcp_oauth::register_client;
+use codex_core::mcp_oauth::save_mcp_oauth_credentials;
+use tiny_http::Response;
+use tiny_http::Server;
+
+use crate::pkce::generate_pkce;
+use crate::server::generate_state;
+
+/// How long to wait for the browser to come back with an authorization code.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone)]
+pub struct McpLoginOptions {
+    pub codex_home: PathBuf,
+    pub credential_store_mode: CredentialStoreMode,
+    pub server_name: String,
+    /// URL of the MCP server.
+    pub url: String,
+    pub open_browser: bool,
+}
+
+/// Signs in to the MCP server in the browser and stores the tokens under
+/// the server's name.
+pub async fn login_mcp_server(opts: McpLoginOptions) -> io::Result<()> {
+    let client = http_client();
+    let metadata = discover_authorization_server(&client, &opts.url).await?;
+
+    // The redirect URI must be known before registering, so bind first. Any
+    // free port will do since the client is registered for this login only.
+    let server = Server::http("127.0.0.1:0").map_err(io::Error::other)?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .ok_or_else(|| io::Error::other("unable to determine the redirect server port"))?;
+    let redirect_uri = format!("http://127.0.0.1:{port}/callback");
+    let registered = register_client(&client, &metadata, &redirect_uri).await?;
+
+    let pkce = generate_pkce();
+    let state = generate_state();
+    let request = AuthorizationRequest {
+        client_id: &registered.client_id,
+        redirect_uri: &redirect_uri,
+        code_challenge: &pkce.code_challenge,
+        state: &state,
+        resource: &opts.url,
+    };
+    let auth_url = authorization_url(&metadata, &request)?;
+    eprintln!(
+        "Sign in to MCP server `{}` in your browser:\n\n{auth_url}\n",
+        opts.server_name
+    );
+    if opts.open_browser {
+        let _ = webbrowser::open(&auth_url);
+    }
+
+    let expected_state = state.clone();
+    let server_name = opts.server_name.clone();
+    let code =
+        tokio::task::spawn_blocking(move || wait_for_code(&server, &expected_state, &server_name))
+            .await
+            .map_err(|err| io::Error::other(format!("redirect handler failed: {err}")))??;
+
+    let credentials = exchange_code(
+        &client,
+        &metadata,
+        &registered,
+        &request,
+        &code,
+        &pkce.code_verifier,
+    )
+    .await?;
+    let store = credential_store(&opts.codex_home, opts.credential_store_mode);
+    save_mcp_oauth_credentials(store.as_ref(), &opts.server_name, credentials)
+}
+
+/// Serves redirects until one arrives for `/callback` and returns its code.
+fn wait_for_code(server: &Server, expected_state: &str, server_name: &str) -> io::Result<String> {
+    let deadline = Instant::now() + LOGIN_TIMEOUT;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out waiting for the browser sign-in",
+            ));
+        }
+        let Some(request) = server.recv_timeout(remaining)? else {
+            continue;
+        };
+        let Ok(url) = url::Url::parse(&format!("http://127.0.0.1{}", request.url())) else {
+            let _ = request.respond(Response::from_string("Bad Request").with_status_code(400));
+            continue;
+        };
+        if url.path() != "/callback" {
+            let _ = request.respond(Response::from_string("Not Found").with_status_code(404));
+            continue;
+        }
+
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        let result = if params.get("state").map(String::as_str) != Some(expected_state) {
+            Err("State mismatch".to_string())
+        } else if let Some(error) = params.get("error") {
+            Err(match params.get("error_description") {
+                Some(description) => format!("{error}: {description}"),
+                None => error.clone(),
+            })
+        } else {
+            params
+                .get("code")
+                .filter(|code| !code.is_empty())
+                .cloned()
+                .ok_or_else(|| "Missing authorization code".to_string())
+        };
+        return match result {
+            Ok(code) => {
+                let _ = request.respond(Response::from_string(format!(
+                    "Signed in to MCP server `{server_name}`. You can close this tab."
+                )));
+                Ok(code)
+            }
+            Err(message) => {
+                let _ = request.respond(
+                    Response::from_string(format!("Sign-in failed: {message}"))
+                        .with_status_code(400),
+                );
+                Err(io::Error::other(format!("sign-in failed: {message}")))
+            }
+        };
+    }
+}
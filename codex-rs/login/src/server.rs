@@ -15,7 +15,8 @@ use crate::pkce::generate_pkce;
 use base64::Engine;
 use chrono::Utc;
 use codex_core::auth::AuthDotJson;
-use codex_core::auth::get_auth_file;
+use codex_core::config_types::CredentialStoreMode;
+use codex_core::credential_store::credential_store;
 use codex_core::default_client::ORIGINATOR;
 use codex_core::token_data::TokenData;
 use codex_core::token_data::parse_id_token;
@@ -32,6 +33,7 @@ const DEFAULT_PORT: u16 = 1455;
 #[derive(Debug, Clone)]
 pub struct ServerOptions {
     pub codex_home: PathBuf,
+    pub credential_store_mode: CredentialStoreMode,
     pub client_id: String,
     pub issuer: String,
     pub port: u16,
@@ -40,9 +42,14 @@ pub struct ServerOptions {
 }
 
 impl ServerOptions {
-    pub fn new(codex_home: PathBuf, client_id: String) -> Self {
+    pub fn new(
+        codex_home: PathBuf,
+        client_id: String,
+        credential_store_mode: CredentialStoreMode,
+    ) -> Self {
         Self {
             codex_home,
+            credential_store_mode,
             client_id,
             issuer: DEFAULT_ISSUER.to_string(),
             port: DEFAULT_PORT,
@@ -237,6 +244,7 @@ async fn process_request(
                         .ok();
                     if let Err(err) = persist_tokens_async(
                         &opts.codex_home,
+                        opts.credential_store_mode,
                         api_key.clone(),
                         tokens.id_token.clone(),
                         tokens.access_token.clone(),
@@ -325,7 +333,7 @@ fn build_authorize_url(
     format!("{issuer}/oauth/authorize?{qs}")
 }
 
-fn generate_state() -> String {
+pub(crate) fn generate_state() -> String {
     let mut bytes = [0u8; 32];
     rand::rng().fill_bytes(&mut bytes);
     base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
@@ -445,6 +453,7 @@ async fn exchange_code_for_tokens(
 
 async fn persist_tokens_async(
     codex_home: &Path,
+    credential_store_mode: CredentialStoreMode,
     api_key: Option<String>,
     id_token: String,
     access_token: String,
@@ -453,12 +462,7 @@ async fn persist_tokens_async(
     // Reuse existing synchronous logic but run it off the async runtime.
     let codex_home = codex_home.to_path_buf();
     tokio::task::spawn_blocking(move || {
-        let auth_file = get_auth_file(&codex_home);
-        if let Some(parent) = auth_file.parent()
-            && !parent.exists()
-        {
-            std::fs::create_dir_all(parent).map_err(io::Error::other)?;
-        }
+        let store = credential_store(&codex_home, credential_store_mode);
 
         let mut tokens = TokenData {
             id_token: parse_id_token(&id_token).map_err(io::Error::other)?,
@@ -476,8 +480,12 @@ async fn persist_tokens_async(
             openai_api_key: api_key,
             tokens: Some(tokens),
             last_refresh: Some(Utc::now()),
+            active_account: None,
+            accounts: codex_core::auth::stored_accounts(store.as_ref()),
+            provider_api_keys: codex_core::auth::stored_provider_api_keys(store.as_ref()),
+            mcp_oauth: codex_core::mcp_oauth::stored_mcp_oauth_credentials(store.as_ref()),
         };
-        codex_core::auth::write_auth_json(&auth_file, &auth)
+        store.save(&auth)
     })
     .await
     .map_err(|e| io::Error::other(format!("persist task failed: {e}")))?
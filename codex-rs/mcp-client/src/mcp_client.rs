@@ -13,7 +13,11 @@
 
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -23,20 +27,29 @@ use anyhow::Result;
 use anyhow::anyhow;
 use mcp_types::CallToolRequest;
 use mcp_types::CallToolRequestParams;
+use mcp_types::CreateMessageRequest;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
 use mcp_types::InitializeRequest;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializedNotification;
 use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
 use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListRootsRequest;
+use mcp_types::ListRootsResult;
 use mcp_types::ListToolsRequest;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
 use mcp_types::RequestId;
+use mcp_types::Root;
+use mcp_types::RootsListChangedNotification;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tokio::io::AsyncBufReadExt;
@@ -56,6 +69,26 @@ use tracing::warn;
 /// client API and the IO tasks.
 const CHANNEL_CAPACITY: usize = 128;
 
+/// JSON-RPC error code for requests the client does not implement.
+const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32601;
+
+/// JSON-RPC error code for requests whose parameters do not parse.
+const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
+
+/// JSON-RPC error code for sampling requests that were refused, e.g. by the
+/// user.
+const SAMPLING_REJECTED_ERROR_CODE: i64 = -1;
+
+/// Answers `sampling/createMessage` requests from the server, or explains
+/// why a request was refused.
+pub type SamplingHandler = Arc<
+    dyn Fn(
+            CreateMessageRequestParams,
+        ) -> Pin<Box<dyn Future<Output = Result<CreateMessageResult, String>> + Send>>
+        + Send
+        + Sync,
+>;
+
 /// Internal representation of a pending request sender.
 type PendingSender = oneshot::Sender<JSONRPCMessage>;
 
@@ -76,6 +109,15 @@ pub struct McpClient {
 
     /// Monotonically increasing counter used to generate request IDs.
     id_counter: AtomicI64,
+
+    /// Set once the server closed its STDOUT, usually because it exited.
+    closed: Arc<AtomicBool>,
+
+    /// Directories the server may operate on, returned for `roots/list`.
+    roots: Arc<RwLock<Vec<Root>>>,
+
+    /// Answers `sampling/createMessage`; without one such requests fail.
+    sampling: Arc<RwLock<Option<SamplingHandler>>>,
 }
 
 impl McpClient {
@@ -111,6 +153,9 @@ impl McpClient {
 
         let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
         let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+        let roots: Arc<RwLock<Vec<Root>>> = Arc::default();
+        let sampling: Arc<RwLock<Option<SamplingHandler>>> = Arc::default();
 
         // Spawn writer task. It listens on the `outgoing_rx` channel and
         // writes messages to the child's STDIN.
@@ -141,6 +186,10 @@ impl McpClient {
         // STDOUT and dispatches responses to the pending map.
         let reader_handle = {
             let pending = pending.clone();
+            let closed = closed.clone();
+            let roots = roots.clone();
+            let sampling = sampling.clone();
+            let outgoing_tx = outgoing_tx.clone();
             let mut lines = BufReader::new(stdout).lines();
 
             tokio::spawn(async move {
@@ -157,16 +206,34 @@ impl McpClient {
                             // For now we only log server-initiated notifications.
                             info!("<- notification: {}", line);
                         }
-                        Ok(other) => {
-                            // Batch responses and requests are currently not
-                            // expected from the server – log and ignore.
-                            info!("<- unhandled message: {:?}", other);
+                        Ok(JSONRPCMessage::Request(request))
+                            if request.method == CreateMessageRequest::METHOD =>
+                        {
+                            // Sampling can wait on the user and the model, so
+                            // it must not hold up the responses behind it.
+                            let handler = sampling.read().ok().and_then(|h| h.clone());
+                            let outgoing_tx = outgoing_tx.clone();
+                            tokio::spawn(async move {
+                                let reply = Self::handle_sampling_request(request, handler).await;
+                                let _ = outgoing_tx.send(reply).await;
+                            });
+                        }
+                        Ok(JSONRPCMessage::Request(request)) => {
+                            let reply = Self::handle_server_request(request, &roots);
+                            if outgoing_tx.send(reply).await.is_err() {
+                                break;
+                            }
                         }
                         Err(e) => {
                             error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
                         }
                     }
                 }
+                // No more replies can arrive: fail the requests still
+                // waiting for one instead of letting them run into their
+                // timeouts.
+                closed.store(true, Ordering::SeqCst);
+                pending.lock().await.clear();
             })
         };
 
@@ -181,9 +248,44 @@ impl McpClient {
             outgoing_tx,
             pending,
             id_counter: AtomicI64::new(1),
+            closed,
+            roots,
+            sampling,
         })
     }
 
+    /// Lets the server request completions with `sampling/createMessage`.
+    /// Set before [`initialize`](Self::initialize) and advertise the
+    /// `sampling` capability there.
+    pub fn with_sampling_handler(self, handler: SamplingHandler) -> Self {
+        if let Ok(mut current) = self.sampling.write() {
+            *current = Some(handler);
+        }
+        self
+    }
+
+    /// Sets the roots advertised to the server before [`initialize`](Self::initialize).
+    pub fn with_roots(self, roots: Vec<Root>) -> Self {
+        if let Ok(mut current) = self.roots.write() {
+            *current = roots;
+        }
+        self
+    }
+
+    /// Replaces the roots and tells the server they changed.
+    pub async fn set_roots(&self, roots: Vec<Root>) -> Result<()> {
+        if let Ok(mut current) = self.roots.write() {
+            *current = roots;
+        }
+        self.send_notification::<RootsListChangedNotification>(None)
+            .await
+    }
+
+    /// Whether the server closed the connection, e.g. because it exited.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
     /// Send an arbitrary MCP request and await the typed result.
     ///
     /// If `timeout` is `None` the call waits indefinitely. If `Some(duration)`
@@ -199,6 +301,10 @@ impl McpClient {
         R::Params: Serialize,
         R::Result: DeserializeOwned,
     {
+        if self.is_closed() {
+            return Err(anyhow!("MCP server closed the connection"));
+        }
+
         // Create a new unique ID.
         let id = self.id_counter.fetch_add(1, Ordering::SeqCst);
         let request_id = RequestId::Integer(id);
@@ -346,6 +452,75 @@ impl McpClient {
         self.send_request::<CallToolRequest>(params, timeout).await
     }
 
+    /// Internal helper: answer a `sampling/createMessage` request with
+    /// `handler`.
+    async fn handle_sampling_request(
+        request: JSONRPCRequest,
+        handler: Option<SamplingHandler>,
+    ) -> JSONRPCMessage {
+        let error = |code: i64, message: String| {
+            JSONRPCMessage::Error(JSONRPCError {
+                id: request.id.clone(),
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                error: JSONRPCErrorError {
+                    code,
+                    data: None,
+                    message,
+                },
+            })
+        };
+        let Some(handler) = handler else {
+            return error(
+                METHOD_NOT_FOUND_ERROR_CODE,
+                "sampling is not supported".to_string(),
+            );
+        };
+        let params = match request
+            .params
+            .clone()
+            .map(serde_json::from_value::<CreateMessageRequestParams>)
+        {
+            Some(Ok(params)) => params,
+            Some(Err(e)) => return error(INVALID_PARAMS_ERROR_CODE, e.to_string()),
+            None => {
+                return error(
+                    INVALID_PARAMS_ERROR_CODE,
+                    "missing sampling parameters".to_string(),
+                );
+            }
+        };
+        match handler(params).await {
+            Ok(result) => JSONRPCMessage::Response(JSONRPCResponse {
+                id: request.id.clone(),
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                result: result.into(),
+            }),
+            Err(message) => error(SAMPLING_REJECTED_ERROR_CODE, message),
+        }
+    }
+
+    /// Internal helper: answer a request the *server* sent. Only `roots/list`
+    /// is supported.
+    fn handle_server_request(request: JSONRPCRequest, roots: &RwLock<Vec<Root>>) -> JSONRPCMessage {
+        if request.method != ListRootsRequest::METHOD {
+            return JSONRPCMessage::Error(JSONRPCError {
+                id: request.id,
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                error: JSONRPCErrorError {
+                    code: METHOD_NOT_FOUND_ERROR_CODE,
+                    data: None,
+                    message: format!("method not supported: {}", request.method),
+                },
+            });
+        }
+        let roots = roots.read().map(|roots| roots.clone()).unwrap_or_default();
+        JSONRPCMessage::Response(JSONRPCResponse {
+            id: request.id,
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            result: ListRootsResult { roots }.into(),
+        })
+    }
+
     /// Internal helper: route a JSON-RPC *response* object to the pending map.
     async fn dispatch_response(
         resp: JSONRPCResponse,
@@ -473,4 +648,63 @@ mod tests {
         assert!(mcp_server_env.contains_key("PATH"));
         assert_eq!(Some(&env_var_new_value), mcp_server_env.get(env_var));
     }
+
+    #[test]
+    fn server_requests_for_roots_are_answered() {
+        let roots = RwLock::new(vec![Root {
+            name: Some("app".to_owned()),
+            uri: "file:///home/dev/app".to_owned(),
+        }]);
+        let request = |method: &str| JSONRPCRequest {
+            id: RequestId::Integer(7),
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            method: method.to_owned(),
+            params: None,
+        };
+
+        let reply = McpClient::handle_server_request(request("roots/list"), &roots);
+        let JSONRPCMessage::Response(response) = reply else {
+            panic!("expected a response, got {reply:?}");
+        };
+        assert_eq!(response.id, RequestId::Integer(7));
+        assert_eq!(
+            response.result,
+            serde_json::json!({"roots": [{"name": "app", "uri": "file:///home/dev/app"}]})
+        );
+
+        let reply = McpClient::handle_server_request(request("sampling/createMessage"), &roots);
+        assert!(matches!(
+            reply,
+            JSONRPCMessage::Error(JSONRPCError { error, .. }) if error.code == METHOD_NOT_FOUND_ERROR_CODE
+        ));
+    }
+
+    #[tokio::test]
+    async fn sampling_requests_go_to_the_handler() {
+        let request = JSONRPCRequest {
+            id: RequestId::Integer(3),
+            jsonrpc: JSONRPC_VERSION.to_owned(),
+            method: CreateMessageRequest::METHOD.to_owned(),
+            params: Some(serde_json::json!({
+                "maxTokens": 100,
+                "messages": [{"role": "user", "content": {"type": "text", "text": "hi"}}]
+            })),
+        };
+        let handler: SamplingHandler = Arc::new(|params: CreateMessageRequestParams| {
+            Box::pin(async move { Err(format!("{} tokens is over budget", params.max_tokens)) })
+        });
+
+        let reply = McpClient::handle_sampling_request(request.clone(), Some(handler)).await;
+        assert!(matches!(
+            reply,
+            JSONRPCMessage::Error(JSONRPCError { error, .. })
+                if error.code == SAMPLING_REJECTED_ERROR_CODE && error.message == "100 tokens is over budget"
+        ));
+
+        let reply = McpClient::handle_sampling_request(request, None).await;
+        assert!(matches!(
+            reply,
+            JSONRPCMessage::Error(JSONRPCError { error, .. }) if error.code == METHOD_NOT_FOUND_ERROR_CODE
+        ));
+    }
 }
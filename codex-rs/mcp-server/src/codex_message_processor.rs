@@ -3,6 +3,11 @@ use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::json_to_toml::json_to_toml;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotification;
+use crate::turn_queue::TurnQueues;
+use crate::turn_queue::cancel_pending_turns;
+use crate::turn_queue::enqueue_turns;
+use crate::turn_queue::on_turn_finished;
+use crate::turn_queue::on_turn_started;
 use codex_core::AuthManager;
 use codex_core::CodexConversation;
 use codex_core::ConversationManager;
@@ -11,9 +16,10 @@ use codex_core::NewConversation;
 use codex_core::RolloutRecorder;
 use codex_core::SessionMeta;
 use codex_core::auth::CLIENT_ID;
-use codex_core::auth::get_auth_file;
+use codex_core::auth::list_accounts;
 use codex_core::auth::login_with_api_key;
-use codex_core::auth::try_read_auth_json;
+use codex_core::auth::remove_provider_api_key;
+use codex_core::auth::set_provider_api_key;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::ConfigToml;
@@ -21,22 +27,39 @@ use codex_core::config::load_config_as_toml;
 use codex_core::config_edit::CONFIG_KEY_EFFORT;
 use codex_core::config_edit::CONFIG_KEY_MODEL;
 use codex_core::config_edit::persist_overrides_and_clear_if_none;
+use codex_core::credential_store::credential_store;
 use codex_core::default_client::get_codex_user_agent;
+use codex_core::event_subscription::EventSubscriptionError;
+use codex_core::event_subscription::LagPolicy;
+use codex_core::event_subscription::SequencedEvent;
+use codex_core::event_subscription::SubscribeOptions;
 use codex_core::exec::ExecParams;
 use codex_core::exec_env::create_env;
 use codex_core::get_platform_sandbox;
 use codex_core::git_info::git_diff_to_remote;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
+use codex_core::protocol::ExitedReviewModeEvent;
 use codex_core::protocol::InputItem as CoreInputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SecretRequestEvent;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TokenCountEvent;
+use codex_core::protocol::TurnRevertedEvent;
+use codex_core::review_target::review_request;
+use codex_core::session_log;
+use codex_core::wire_log::set_wire_logging;
 use codex_login::ServerOptions as LoginServerOptions;
 use codex_login::ShutdownHandle;
 use codex_login::run_login_server;
 use codex_protocol::mcp_protocol::APPLY_PATCH_APPROVAL_METHOD;
+use codex_protocol::mcp_protocol::AccountInfo;
+use codex_protocol::mcp_protocol::AddAnnotationParams;
+use codex_protocol::mcp_protocol::AddAnnotationResponse;
 use codex_protocol::mcp_protocol::AddConversationListenerParams;
 use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
 use codex_protocol::mcp_protocol::ApplyPatchApprovalParams;
@@ -44,7 +67,13 @@ use codex_protocol::mcp_protocol::ApplyPatchApprovalResponse;
 use codex_protocol::mcp_protocol::ArchiveConversationParams;
 use codex_protocol::mcp_protocol::ArchiveConversationResponse;
 use codex_protocol::mcp_protocol::AuthStatusChangeNotification;
+use codex_protocol::mcp_protocol::CancelQueuedTurnsParams;
+use codex_protocol::mcp_protocol::CancelQueuedTurnsResponse;
 use codex_protocol::mcp_protocol::ClientRequest;
+use codex_protocol::mcp_protocol::CompleteWorkspacePathsParams;
+use codex_protocol::mcp_protocol::CompleteWorkspacePathsResponse;
+use codex_protocol::mcp_protocol::CompleteWorkspaceSymbolsParams;
+use codex_protocol::mcp_protocol::CompleteWorkspaceSymbolsResponse;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::mcp_protocol::ConversationSummary;
 use codex_protocol::mcp_protocol::EXEC_COMMAND_APPROVAL_METHOD;
@@ -52,12 +81,15 @@ use codex_protocol::mcp_protocol::ExecArbitraryCommandResponse;
 use codex_protocol::mcp_protocol::ExecCommandApprovalParams;
 use codex_protocol::mcp_protocol::ExecCommandApprovalResponse;
 use codex_protocol::mcp_protocol::ExecOneOffCommandParams;
+use codex_protocol::mcp_protocol::GetConversationStatsParams;
+use codex_protocol::mcp_protocol::GetConversationStatsResponse;
 use codex_protocol::mcp_protocol::GetUserAgentResponse;
 use codex_protocol::mcp_protocol::GetUserSavedConfigResponse;
 use codex_protocol::mcp_protocol::GitDiffToRemoteResponse;
 use codex_protocol::mcp_protocol::InputItem as WireInputItem;
 use codex_protocol::mcp_protocol::InterruptConversationParams;
 use codex_protocol::mcp_protocol::InterruptConversationResponse;
+use codex_protocol::mcp_protocol::ListAccountsResponse;
 use codex_protocol::mcp_protocol::ListConversationsParams;
 use codex_protocol::mcp_protocol::ListConversationsResponse;
 use codex_protocol::mcp_protocol::LoginApiKeyParams;
@@ -66,18 +98,41 @@ use codex_protocol::mcp_protocol::LoginChatGptCompleteNotification;
 use codex_protocol::mcp_protocol::LoginChatGptResponse;
 use codex_protocol::mcp_protocol::NewConversationParams;
 use codex_protocol::mcp_protocol::NewConversationResponse;
+use codex_protocol::mcp_protocol::QueueUserTurnsParams;
+use codex_protocol::mcp_protocol::QueueUserTurnsResponse;
 use codex_protocol::mcp_protocol::RemoveConversationListenerParams;
 use codex_protocol::mcp_protocol::RemoveConversationSubscriptionResponse;
+use codex_protocol::mcp_protocol::RemoveProviderApiKeyParams;
+use codex_protocol::mcp_protocol::RemoveProviderApiKeyResponse;
 use codex_protocol::mcp_protocol::ResumeConversationParams;
+use codex_protocol::mcp_protocol::RevertTurnParams;
+use codex_protocol::mcp_protocol::RevertTurnResponse;
+use codex_protocol::mcp_protocol::SECRET_REQUEST_METHOD;
+use codex_protocol::mcp_protocol::SecretRequestParams;
+use codex_protocol::mcp_protocol::SecretRequestResponse;
 use codex_protocol::mcp_protocol::SendUserMessageParams;
 use codex_protocol::mcp_protocol::SendUserMessageResponse;
 use codex_protocol::mcp_protocol::SendUserTurnParams;
 use codex_protocol::mcp_protocol::SendUserTurnResponse;
 use codex_protocol::mcp_protocol::ServerNotification;
+use codex_protocol::mcp_protocol::SetConversationModelParams;
+use codex_protocol::mcp_protocol::SetConversationModelResponse;
 use codex_protocol::mcp_protocol::SetDefaultModelParams;
 use codex_protocol::mcp_protocol::SetDefaultModelResponse;
+use codex_protocol::mcp_protocol::SetLogLevelParams;
+use codex_protocol::mcp_protocol::SetLogLevelResponse;
+use codex_protocol::mcp_protocol::SetProviderApiKeyParams;
+use codex_protocol::mcp_protocol::SetProviderApiKeyResponse;
+use codex_protocol::mcp_protocol::SetWireLoggingParams;
+use codex_protocol::mcp_protocol::SetWireLoggingResponse;
+use codex_protocol::mcp_protocol::StartReviewParams;
+use codex_protocol::mcp_protocol::StartReviewResponse;
+use codex_protocol::mcp_protocol::SwitchAccountParams;
+use codex_protocol::mcp_protocol::SwitchAccountResponse;
 use codex_protocol::mcp_protocol::UserInfoResponse;
 use codex_protocol::mcp_protocol::UserSavedConfig;
+use codex_protocol::mcp_protocol::WorkspacePathMatch;
+use codex_protocol::mcp_protocol::WorkspaceSymbolMatch;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::InputMessageKind;
@@ -86,8 +141,10 @@ use mcp_types::JSONRPCErrorError;
 use mcp_types::RequestId;
 use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 use tokio::select;
 use tokio::sync::Mutex;
@@ -100,6 +157,11 @@ use uuid::Uuid;
 // Duration before a ChatGPT login attempt is abandoned.
 const LOGIN_CHATGPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
+// Defaults for workspace completion requests; these mirror the TUI `@` picker.
+const DEFAULT_COMPLETION_LIMIT: NonZeroUsize = NonZeroUsize::new(8).unwrap();
+const MAX_COMPLETION_LIMIT: usize = 200;
+const COMPLETION_SEARCH_THREADS: NonZeroUsize = NonZeroUsize::new(2).unwrap();
+
 struct ActiveLogin {
     shutdown_handle: ShutdownHandle,
     login_id: Uuid,
@@ -122,6 +184,8 @@ pub(crate) struct CodexMessageProcessor {
     active_login: Arc<Mutex<Option<ActiveLogin>>>,
     // Queue of pending interrupt requests per conversation. We reply when TurnAborted arrives.
     pending_interrupts: Arc<Mutex<HashMap<ConversationId, Vec<RequestId>>>>,
+    // Turns submitted via `queueUserTurns` that are waiting for the previous turn to finish.
+    turn_queues: TurnQueues,
 }
 
 impl CodexMessageProcessor {
@@ -141,6 +205,7 @@ impl CodexMessageProcessor {
             conversation_listeners: HashMap::new(),
             active_login: Arc::new(Mutex::new(None)),
             pending_interrupts: Arc::new(Mutex::new(HashMap::new())),
+            turn_queues: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -167,6 +232,33 @@ impl CodexMessageProcessor {
             ClientRequest::SendUserTurn { request_id, params } => {
                 self.send_user_turn(request_id, params).await;
             }
+            ClientRequest::QueueUserTurns { request_id, params } => {
+                self.queue_user_turns(request_id, params).await;
+            }
+            ClientRequest::CancelQueuedTurns { request_id, params } => {
+                self.cancel_queued_turns(request_id, params).await;
+            }
+            ClientRequest::AddAnnotation { request_id, params } => {
+                self.add_annotation(request_id, params).await;
+            }
+            ClientRequest::RevertTurn { request_id, params } => {
+                self.revert_turn(request_id, params).await;
+            }
+            ClientRequest::StartReview { request_id, params } => {
+                self.start_review(request_id, params).await;
+            }
+            ClientRequest::GetConversationStats { request_id, params } => {
+                self.get_conversation_stats(request_id, params).await;
+            }
+            ClientRequest::SetConversationModel { request_id, params } => {
+                self.set_conversation_model(request_id, params).await;
+            }
+            ClientRequest::SetWireLogging { request_id, params } => {
+                self.set_wire_logging(request_id, params).await;
+            }
+            ClientRequest::SetLogLevel { request_id, params } => {
+                self.set_log_level(request_id, params).await;
+            }
             ClientRequest::InterruptConversation { request_id, params } => {
                 self.interrupt_conversation(request_id, params).await;
             }
@@ -194,6 +286,18 @@ impl CodexMessageProcessor {
             ClientRequest::GetAuthStatus { request_id, params } => {
                 self.get_auth_status(request_id, params).await;
             }
+            ClientRequest::ListAccounts { request_id } => {
+                self.list_accounts(request_id).await;
+            }
+            ClientRequest::SwitchAccount { request_id, params } => {
+                self.switch_account(request_id, params).await;
+            }
+            ClientRequest::SetProviderApiKey { request_id, params } => {
+                self.set_provider_api_key(request_id, params).await;
+            }
+            ClientRequest::RemoveProviderApiKey { request_id, params } => {
+                self.remove_provider_api_key(request_id, params).await;
+            }
             ClientRequest::GetUserSavedConfig { request_id } => {
                 self.get_user_saved_config(request_id).await;
             }
@@ -209,6 +313,12 @@ impl CodexMessageProcessor {
             ClientRequest::ExecOneOffCommand { request_id, params } => {
                 self.exec_one_off_command(request_id, params).await;
             }
+            ClientRequest::CompleteWorkspacePaths { request_id, params } => {
+                self.complete_workspace_paths(request_id, params).await;
+            }
+            ClientRequest::CompleteWorkspaceSymbols { request_id, params } => {
+                self.complete_workspace_symbols(request_id, params).await;
+            }
         }
     }
 
@@ -220,7 +330,11 @@ impl CodexMessageProcessor {
             }
         }
 
-        match login_with_api_key(&self.config.codex_home, &params.api_key) {
+        match login_with_api_key(
+            &self.config.codex_home,
+            &params.api_key,
+            self.config.credential_store,
+        ) {
             Ok(()) => {
                 self.auth_manager.reload();
                 self.outgoing
@@ -250,7 +364,11 @@ impl CodexMessageProcessor {
 
         let opts = LoginServerOptions {
             open_browser: false,
-            ..LoginServerOptions::new(config.codex_home.clone(), CLIENT_ID.to_string())
+            ..LoginServerOptions::new(
+                config.codex_home.clone(),
+                CLIENT_ID.to_string(),
+                config.credential_store,
+            )
         };
 
         enum LoginChatGptReply {
@@ -408,6 +526,123 @@ impl CodexMessageProcessor {
             .await;
     }
 
+    async fn list_accounts(&self, request_id: RequestId) {
+        match list_accounts(&self.config.codex_home, self.config.credential_store) {
+            Ok(accounts) => {
+                let accounts = accounts
+                    .into_iter()
+                    .map(|account| AccountInfo {
+                        name: account.name,
+                        auth_method: account.mode,
+                        email: account.email,
+                        active: account.active,
+                    })
+                    .collect();
+                self.outgoing
+                    .send_response(request_id, ListAccountsResponse { accounts })
+                    .await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to read stored accounts: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    async fn switch_account(&mut self, request_id: RequestId, params: SwitchAccountParams) {
+        {
+            // A login in progress would overwrite the account we switch to.
+            let mut guard = self.active_login.lock().await;
+            if let Some(active) = guard.take() {
+                active.drop();
+            }
+        }
+
+        if let Err(err) = self.auth_manager.switch_account(&params.name) {
+            let code = if err.kind() == std::io::ErrorKind::NotFound {
+                INVALID_REQUEST_ERROR_CODE
+            } else {
+                INTERNAL_ERROR_CODE
+            };
+            let error = JSONRPCErrorError {
+                code,
+                message: format!("failed to switch account: {err}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        }
+
+        let auth_method = self.auth_manager.auth().map(|auth| auth.mode);
+        self.outgoing
+            .send_response(request_id, SwitchAccountResponse { auth_method })
+            .await;
+
+        let payload = AuthStatusChangeNotification { auth_method };
+        self.outgoing
+            .send_server_notification(ServerNotification::AuthStatusChange(payload))
+            .await;
+    }
+
+    async fn set_provider_api_key(&self, request_id: RequestId, params: SetProviderApiKeyParams) {
+        match set_provider_api_key(
+            &self.config.codex_home,
+            &self.config.model_providers,
+            &params.provider_id,
+            &params.api_key,
+            self.config.credential_store,
+        ) {
+            Ok(()) => {
+                self.outgoing
+                    .send_response(request_id, SetProviderApiKeyResponse {})
+                    .await;
+            }
+            Err(err) => {
+                let code = if err.kind() == std::io::ErrorKind::InvalidInput {
+                    INVALID_REQUEST_ERROR_CODE
+                } else {
+                    INTERNAL_ERROR_CODE
+                };
+                let error = JSONRPCErrorError {
+                    code,
+                    message: format!("failed to save provider api key: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    async fn remove_provider_api_key(
+        &self,
+        request_id: RequestId,
+        params: RemoveProviderApiKeyParams,
+    ) {
+        match remove_provider_api_key(
+            &self.config.codex_home,
+            &params.provider_id,
+            self.config.credential_store,
+        ) {
+            Ok(removed) => {
+                self.outgoing
+                    .send_response(request_id, RemoveProviderApiKeyResponse { removed })
+                    .await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to remove provider api key: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
     async fn get_auth_status(
         &self,
         request_id: RequestId,
@@ -505,12 +740,12 @@ impl CodexMessageProcessor {
     }
 
     async fn get_user_info(&self, request_id: RequestId) {
-        // Read alleged user email from auth.json (best-effort; not verified).
-        let auth_path = get_auth_file(&self.config.codex_home);
-        let alleged_user_email = match try_read_auth_json(&auth_path) {
-            Ok(auth) => auth.tokens.and_then(|t| t.id_token.email),
-            Err(_) => None,
-        };
+        // Read alleged user email from the stored credentials (best-effort; not verified).
+        let alleged_user_email =
+            match credential_store(&self.config.codex_home, self.config.credential_store).load() {
+                Ok(Some(auth)) => auth.tokens.and_then(|t| t.id_token.email),
+                Ok(None) | Err(_) => None,
+            };
 
         let response = UserInfoResponse { alleged_user_email };
         self.outgoing.send_response(request_id, response).await;
@@ -565,7 +800,12 @@ impl CodexMessageProcessor {
 
         let cwd = params.cwd.unwrap_or_else(|| self.config.cwd.clone());
         let env = create_env(&self.config.shell_environment_policy);
-        let timeout_ms = params.timeout_ms;
+        let timeout_ms = params
+            .timeout_ms
+            .or(Some(self.config.exec_timeout.default_ms));
+        let effective_policy = params
+            .sandbox_policy
+            .unwrap_or_else(|| self.config.sandbox_policy.clone());
         let exec_params = ExecParams {
             command: params.command,
             cwd,
@@ -573,16 +813,19 @@ impl CodexMessageProcessor {
             env,
             with_escalated_permissions: None,
             justification: None,
+            resource_limits: self
+                .config
+                .sandbox_resource_limits
+                .for_policy(&effective_policy),
         };
 
-        let effective_policy = params
-            .sandbox_policy
-            .unwrap_or_else(|| self.config.sandbox_policy.clone());
-
         let sandbox_type = match &effective_policy {
             codex_core::protocol::SandboxPolicy::DangerFullAccess => {
                 codex_core::exec::SandboxType::None
             }
+            codex_core::protocol::SandboxPolicy::Container { .. } => {
+                codex_core::exec::SandboxType::Container
+            }
             _ => get_platform_sandbox().unwrap_or(codex_core::exec::SandboxType::None),
         };
         tracing::debug!("Sandbox type: {sandbox_type:?}");
@@ -622,6 +865,127 @@ impl CodexMessageProcessor {
         });
     }
 
+    async fn complete_workspace_paths(
+        &self,
+        request_id: RequestId,
+        params: CompleteWorkspacePathsParams,
+    ) {
+        let CompleteWorkspacePathsParams { prefix, cwd, limit } = params;
+        let search_dir = cwd.unwrap_or_else(|| self.config.cwd.clone());
+        let limit = completion_limit(limit);
+        let outgoing = self.outgoing.clone();
+
+        // The walk is CPU/IO bound, so keep it off the async executor.
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                codex_file_search::run(
+                    &prefix,
+                    limit,
+                    &search_dir,
+                    Vec::new(),
+                    COMPLETION_SEARCH_THREADS,
+                    Arc::new(AtomicBool::new(false)),
+                    true,
+                    false,
+                )
+            })
+            .await;
+            match result {
+                Ok(Ok(results)) => {
+                    let matches = results
+                        .matches
+                        .into_iter()
+                        .map(|m| WorkspacePathMatch {
+                            path: m.path,
+                            score: m.score,
+                            indices: m.indices.unwrap_or_default(),
+                        })
+                        .collect();
+                    let response = CompleteWorkspacePathsResponse {
+                        matches,
+                        total_match_count: results.total_match_count,
+                    };
+                    outgoing.send_response(request_id, response).await;
+                }
+                Ok(Err(err)) => {
+                    let error = JSONRPCErrorError {
+                        code: INTERNAL_ERROR_CODE,
+                        message: format!("file search failed: {err}"),
+                        data: None,
+                    };
+                    outgoing.send_error(request_id, error).await;
+                }
+                Err(err) => {
+                    let error = JSONRPCErrorError {
+                        code: INTERNAL_ERROR_CODE,
+                        message: format!("file search task failed: {err}"),
+                        data: None,
+                    };
+                    outgoing.send_error(request_id, error).await;
+                }
+            }
+        });
+    }
+
+    async fn complete_workspace_symbols(
+        &self,
+        request_id: RequestId,
+        params: CompleteWorkspaceSymbolsParams,
+    ) {
+        let CompleteWorkspaceSymbolsParams { prefix, cwd, limit } = params;
+        let search_dir = cwd.unwrap_or_else(|| self.config.cwd.clone());
+        let limit = completion_limit(limit);
+        let outgoing = self.outgoing.clone();
+
+        tokio::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                codex_file_search::run_symbol_search(
+                    &prefix,
+                    limit,
+                    &search_dir,
+                    Arc::new(AtomicBool::new(false)),
+                )
+            })
+            .await;
+            match result {
+                Ok(Ok(results)) => {
+                    let matches = results
+                        .matches
+                        .into_iter()
+                        .map(|m| WorkspaceSymbolMatch {
+                            name: m.name,
+                            kind: m.kind,
+                            path: m.path,
+                            line: m.line,
+                            score: m.score,
+                        })
+                        .collect();
+                    let response = CompleteWorkspaceSymbolsResponse {
+                        matches,
+                        total_match_count: results.total_match_count,
+                    };
+                    outgoing.send_response(request_id, response).await;
+                }
+                Ok(Err(err)) => {
+                    let error = JSONRPCErrorError {
+                        code: INTERNAL_ERROR_CODE,
+                        message: format!("symbol search failed: {err}"),
+                        data: None,
+                    };
+                    outgoing.send_error(request_id, error).await;
+                }
+                Err(err) => {
+                    let error = JSONRPCErrorError {
+                        code: INTERNAL_ERROR_CODE,
+                        message: format!("symbol search task failed: {err}"),
+                        data: None,
+                    };
+                    outgoing.send_error(request_id, error).await;
+                }
+            }
+        });
+    }
+
     async fn process_new_conversation(&self, request_id: RequestId, params: NewConversationParams) {
         let config = match derive_config_from_params(params, self.codex_linux_sandbox_exe.clone()) {
             Ok(config) => config,
@@ -928,6 +1292,7 @@ impl CodexMessageProcessor {
         let SendUserMessageParams {
             conversation_id,
             items,
+            turn_options,
         } = params;
         let Ok(conversation) = self
             .conversation_manager
@@ -949,15 +1314,21 @@ impl CodexMessageProcessor {
                 WireInputItem::Text { text } => CoreInputItem::Text { text },
                 WireInputItem::Image { image_url } => CoreInputItem::Image { image_url },
                 WireInputItem::LocalImage { path } => CoreInputItem::LocalImage { path },
+                WireInputItem::Url { url } => CoreInputItem::Url { url },
             })
             .collect();
 
         // Submit user input to the conversation.
-        let _ = conversation
-            .submit(Op::UserInput {
+        let op = match turn_options {
+            Some(overrides) => Op::UserInputWithOverrides {
                 items: mapped_items,
-            })
-            .await;
+                overrides,
+            },
+            None => Op::UserInput {
+                items: mapped_items,
+            },
+        };
+        let _ = conversation.submit(op).await;
 
         // Acknowledge with an empty result.
         self.outgoing
@@ -975,6 +1346,7 @@ impl CodexMessageProcessor {
             model,
             effort,
             summary,
+            output_schema,
         } = params;
 
         let Ok(conversation) = self
@@ -997,6 +1369,7 @@ impl CodexMessageProcessor {
                 WireInputItem::Text { text } => CoreInputItem::Text { text },
                 WireInputItem::Image { image_url } => CoreInputItem::Image { image_url },
                 WireInputItem::LocalImage { path } => CoreInputItem::LocalImage { path },
+                WireInputItem::Url { url } => CoreInputItem::Url { url },
             })
             .collect();
 
@@ -1009,7 +1382,7 @@ impl CodexMessageProcessor {
                 model,
                 effort,
                 summary,
-                final_output_json_schema: None,
+                final_output_json_schema: output_schema,
             })
             .await;
 
@@ -1018,12 +1391,22 @@ impl CodexMessageProcessor {
             .await;
     }
 
-    async fn interrupt_conversation(
-        &mut self,
-        request_id: RequestId,
-        params: InterruptConversationParams,
-    ) {
-        let InterruptConversationParams { conversation_id } = params;
+    async fn queue_user_turns(&self, request_id: RequestId, params: QueueUserTurnsParams) {
+        let QueueUserTurnsParams {
+            conversation_id,
+            turns,
+        } = params;
+
+        if turns.is_empty() || turns.iter().any(Vec::is_empty) {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: "turns must be non-empty and each turn must contain input".to_string(),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        }
+
         let Ok(conversation) = self
             .conversation_manager
             .get_conversation(conversation_id)
@@ -1038,22 +1421,30 @@ impl CodexMessageProcessor {
             return;
         };
 
-        // Record the pending interrupt so we can reply when TurnAborted arrives.
-        {
-            let mut map = self.pending_interrupts.lock().await;
-            map.entry(conversation_id).or_default().push(request_id);
-        }
+        let turns = turns.into_iter().map(map_wire_input_items).collect();
+        let queue_id = Uuid::new_v4();
+        let queued_count = enqueue_turns(
+            &self.turn_queues,
+            conversation_id,
+            &conversation,
+            queue_id,
+            turns,
+        )
+        .await;
 
-        // Submit the interrupt; we'll respond upon TurnAborted.
-        let _ = conversation.submit(Op::Interrupt).await;
+        self.outgoing
+            .send_response(
+                request_id,
+                QueueUserTurnsResponse {
+                    queue_id,
+                    queued_count,
+                },
+            )
+            .await;
     }
 
-    async fn add_conversation_listener(
-        &mut self,
-        request_id: RequestId,
-        params: AddConversationListenerParams,
-    ) {
-        let AddConversationListenerParams { conversation_id } = params;
+    async fn cancel_queued_turns(&self, request_id: RequestId, params: CancelQueuedTurnsParams) {
+        let CancelQueuedTurnsParams { conversation_id } = params;
         let Ok(conversation) = self
             .conversation_manager
             .get_conversation(conversation_id)
@@ -1067,57 +1458,503 @@ impl CodexMessageProcessor {
             self.outgoing.send_error(request_id, error).await;
             return;
         };
+        let cancelled_count =
+            cancel_pending_turns(&self.turn_queues, conversation_id, &conversation).await;
+        self.outgoing
+            .send_response(request_id, CancelQueuedTurnsResponse { cancelled_count })
+            .await;
+    }
 
-        let subscription_id = Uuid::new_v4();
-        let (cancel_tx, mut cancel_rx) = oneshot::channel();
-        self.conversation_listeners
-            .insert(subscription_id, cancel_tx);
-        let outgoing_for_task = self.outgoing.clone();
-        let pending_interrupts = self.pending_interrupts.clone();
-        tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = &mut cancel_rx => {
-                        // User has unsubscribed, so exit this task.
-                        break;
-                    }
-                    event = conversation.next_event() => {
-                        let event = match event {
-                            Ok(event) => event,
-                            Err(err) => {
-                                tracing::warn!("conversation.next_event() failed with: {err}");
-                                break;
-                            }
-                        };
-
-                        // For now, we send a notification for every event,
-                        // JSON-serializing the `Event` as-is, but these should
-                        // be migrated to be variants of `ServerNotification`
-                        // instead.
-                        let method = format!("codex/event/{}", event.msg);
-                        let mut params = match serde_json::to_value(event.clone()) {
-                            Ok(serde_json::Value::Object(map)) => map,
-                            Ok(_) => {
-                                error!("event did not serialize to an object");
-                                continue;
-                            }
-                            Err(err) => {
-                                error!("failed to serialize event: {err}");
-                                continue;
-                            }
-                        };
-                        params.insert("conversationId".to_string(), conversation_id.to_string().into());
+    async fn add_annotation(&self, request_id: RequestId, params: AddAnnotationParams) {
+        let AddAnnotationParams {
+            conversation_id,
+            target,
+            comment,
+        } = params;
 
-                        outgoing_for_task.send_notification(OutgoingNotification {
-                            method,
-                            params: Some(params.into()),
-                        })
-                        .await;
+        if comment.trim().is_empty() {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: "comment must not be empty".to_string(),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        }
 
-                        apply_bespoke_event_handling(event.clone(), conversation_id, conversation.clone(), outgoing_for_task.clone(), pending_interrupts.clone()).await;
-                    }
-                }
-            }
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        if let Err(err) = conversation
+            .submit(Op::AddAnnotation { target, comment })
+            .await
+        {
+            let error = JSONRPCErrorError {
+                code: INTERNAL_ERROR_CODE,
+                message: format!("failed to add annotation: {err}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        }
+
+        self.outgoing
+            .send_response(request_id, AddAnnotationResponse {})
+            .await;
+    }
+
+    async fn revert_turn(&self, request_id: RequestId, params: RevertTurnParams) {
+        let RevertTurnParams {
+            conversation_id,
+            force,
+        } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        // Subscribe before submitting so the reply cannot be missed.
+        let mut events =
+            conversation.subscribe(SubscribeOptions::default().lag_policy(LagPolicy::Skip));
+        let sub_id = match conversation.submit(Op::RevertTurn { force }).await {
+            Ok(sub_id) => sub_id,
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to revert turn: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        let outgoing = self.outgoing.clone();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.next().await {
+                    Ok(SequencedEvent { event, .. }) if event.id == sub_id => event,
+                    Ok(_) | Err(EventSubscriptionError::Lagged { .. }) => continue,
+                    Err(EventSubscriptionError::Closed) => {
+                        let error = JSONRPCErrorError {
+                            code: INTERNAL_ERROR_CODE,
+                            message: format!("conversation ended: {conversation_id}"),
+                            data: None,
+                        };
+                        outgoing.send_error(request_id, error).await;
+                        return;
+                    }
+                };
+                match event.msg {
+                    EventMsg::TurnReverted(TurnRevertedEvent {
+                        turn_id,
+                        reverted,
+                        conflicts,
+                    }) => {
+                        let response = RevertTurnResponse {
+                            turn_id,
+                            reverted,
+                            conflicts,
+                        };
+                        outgoing.send_response(request_id, response).await;
+                    }
+                    EventMsg::Error(ErrorEvent { message, .. }) => {
+                        let error = JSONRPCErrorError {
+                            code: INTERNAL_ERROR_CODE,
+                            message,
+                            data: None,
+                        };
+                        outgoing.send_error(request_id, error).await;
+                    }
+                    _ => continue,
+                }
+                return;
+            }
+        });
+    }
+
+    async fn start_review(&self, request_id: RequestId, params: StartReviewParams) {
+        let StartReviewParams {
+            conversation_id,
+            target,
+        } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+        let review_request = match review_request(&target, &self.config.cwd).await {
+            Ok(review_request) => review_request,
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INVALID_REQUEST_ERROR_CODE,
+                    message: format!("failed to prepare the review: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        // Subscribe before submitting so the reply cannot be missed.
+        let mut events =
+            conversation.subscribe(SubscribeOptions::default().lag_policy(LagPolicy::Skip));
+        let sub_id = match conversation.submit(Op::Review { review_request }).await {
+            Ok(sub_id) => sub_id,
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to start the review: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        let outgoing = self.outgoing.clone();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.next().await {
+                    Ok(SequencedEvent { event, .. }) if event.id == sub_id => event,
+                    Ok(_) | Err(EventSubscriptionError::Lagged { .. }) => continue,
+                    Err(EventSubscriptionError::Closed) => {
+                        let error = JSONRPCErrorError {
+                            code: INTERNAL_ERROR_CODE,
+                            message: format!("conversation ended: {conversation_id}"),
+                            data: None,
+                        };
+                        outgoing.send_error(request_id, error).await;
+                        return;
+                    }
+                };
+                match event.msg {
+                    EventMsg::ExitedReviewMode(ExitedReviewModeEvent { review_output }) => {
+                        let response = StartReviewResponse {
+                            review: review_output,
+                        };
+                        outgoing.send_response(request_id, response).await;
+                    }
+                    EventMsg::Error(ErrorEvent { message, .. }) => {
+                        let error = JSONRPCErrorError {
+                            code: INTERNAL_ERROR_CODE,
+                            message,
+                            data: None,
+                        };
+                        outgoing.send_error(request_id, error).await;
+                    }
+                    _ => continue,
+                }
+                return;
+            }
+        });
+    }
+
+    async fn get_conversation_stats(
+        &self,
+        request_id: RequestId,
+        params: GetConversationStatsParams,
+    ) {
+        let GetConversationStatsParams { conversation_id } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        // Subscribe before submitting so the reply cannot be missed.
+        let mut token_counts = conversation
+            .subscribe(SubscribeOptions::default().lag_policy(LagPolicy::Skip))
+            .filter::<TokenCountEvent>();
+        let sub_id = match conversation.submit(Op::GetTokenUsage).await {
+            Ok(sub_id) => sub_id,
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to get conversation stats: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        // The reply may queue behind a running turn's events, so wait for it
+        // without holding up other requests.
+        let outgoing = self.outgoing.clone();
+        tokio::spawn(async move {
+            loop {
+                match token_counts.next().await {
+                    Ok(event) if event.id == sub_id => {
+                        let info = event.payload.info;
+                        let response = GetConversationStatsResponse {
+                            estimated_cost_usd: info
+                                .as_ref()
+                                .and_then(|info| info.estimated_cost_usd),
+                            token_usage: info.map(|info| info.total_token_usage),
+                        };
+                        outgoing.send_response(request_id, response).await;
+                        return;
+                    }
+                    Ok(_) | Err(EventSubscriptionError::Lagged { .. }) => continue,
+                    Err(EventSubscriptionError::Closed) => {
+                        let error = JSONRPCErrorError {
+                            code: INTERNAL_ERROR_CODE,
+                            message: format!("conversation ended: {conversation_id}"),
+                            data: None,
+                        };
+                        outgoing.send_error(request_id, error).await;
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    async fn set_conversation_model(
+        &self,
+        request_id: RequestId,
+        params: SetConversationModelParams,
+    ) {
+        let SetConversationModelParams {
+            conversation_id,
+            model,
+            provider,
+        } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+        if let Some(provider) = &provider
+            && !self.config.model_providers.contains_key(provider)
+        {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("model provider not found: {provider}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        }
+
+        // Submissions run in order, so the next turn sees the change.
+        let op = Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model,
+            provider,
+            effort: None,
+            summary: None,
+        };
+        match conversation.submit(op).await {
+            Ok(_) => {
+                self.outgoing
+                    .send_response(request_id, SetConversationModelResponse {})
+                    .await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to change the model: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    async fn set_log_level(&self, request_id: RequestId, params: SetLogLevelParams) {
+        session_log::set_log_level(params.level);
+        let log_dir = session_log::sessions_log_dir(&self.config.codex_home);
+        self.outgoing
+            .send_response(request_id, SetLogLevelResponse { log_dir })
+            .await;
+    }
+
+    async fn set_wire_logging(&self, request_id: RequestId, params: SetWireLoggingParams) {
+        let SetWireLoggingParams {
+            conversation_id,
+            enabled,
+        } = params;
+        if self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+            .is_err()
+        {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        }
+
+        match set_wire_logging(&self.config.codex_home, conversation_id, enabled) {
+            Ok(log_path) => {
+                self.outgoing
+                    .send_response(request_id, SetWireLoggingResponse { log_path })
+                    .await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to start the wire log: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    async fn interrupt_conversation(
+        &mut self,
+        request_id: RequestId,
+        params: InterruptConversationParams,
+    ) {
+        let InterruptConversationParams { conversation_id } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        // Record the pending interrupt so we can reply when TurnAborted arrives.
+        {
+            let mut map = self.pending_interrupts.lock().await;
+            map.entry(conversation_id).or_default().push(request_id);
+        }
+
+        // Submit the interrupt; we'll respond upon TurnAborted.
+        let _ = conversation.submit(Op::Interrupt).await;
+    }
+
+    async fn add_conversation_listener(
+        &mut self,
+        request_id: RequestId,
+        params: AddConversationListenerParams,
+    ) {
+        let AddConversationListenerParams { conversation_id } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        let subscription_id = Uuid::new_v4();
+        let (cancel_tx, mut cancel_rx) = oneshot::channel();
+        self.conversation_listeners
+            .insert(subscription_id, cancel_tx);
+        let outgoing_for_task = self.outgoing.clone();
+        let pending_interrupts = self.pending_interrupts.clone();
+        let turn_queues = self.turn_queues.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = &mut cancel_rx => {
+                        // User has unsubscribed, so exit this task.
+                        break;
+                    }
+                    event = conversation.next_event() => {
+                        let event = match event {
+                            Ok(event) => event,
+                            Err(err) => {
+                                tracing::warn!("conversation.next_event() failed with: {err}");
+                                break;
+                            }
+                        };
+
+                        // For now, we send a notification for every event,
+                        // JSON-serializing the `Event` as-is, but these should
+                        // be migrated to be variants of `ServerNotification`
+                        // instead.
+                        let method = format!("codex/event/{}", event.msg);
+                        let mut params = match serde_json::to_value(event.clone()) {
+                            Ok(serde_json::Value::Object(map)) => map,
+                            Ok(_) => {
+                                error!("event did not serialize to an object");
+                                continue;
+                            }
+                            Err(err) => {
+                                error!("failed to serialize event: {err}");
+                                continue;
+                            }
+                        };
+                        params.insert("conversationId".to_string(), conversation_id.to_string().into());
+
+                        outgoing_for_task.send_notification(OutgoingNotification {
+                            method,
+                            params: Some(params.into()),
+                        })
+                        .await;
+
+                        apply_bespoke_event_handling(event.clone(), conversation_id, conversation.clone(), outgoing_for_task.clone(), pending_interrupts.clone(), turn_queues.clone()).await;
+                    }
+                }
+            }
         });
         let response = AddConversationSubscriptionResponse { subscription_id };
         self.outgoing.send_response(request_id, response).await;
@@ -1175,6 +2012,7 @@ async fn apply_bespoke_event_handling(
     conversation: Arc<CodexConversation>,
     outgoing: Arc<OutgoingMessageSender>,
     pending_interrupts: Arc<Mutex<HashMap<ConversationId, Vec<RequestId>>>>,
+    turn_queues: TurnQueues,
 ) {
     let Event { id: event_id, msg } = event;
     match msg {
@@ -1183,6 +2021,7 @@ async fn apply_bespoke_event_handling(
             changes,
             reason,
             grant_root,
+            risk,
         }) => {
             let params = ApplyPatchApprovalParams {
                 conversation_id,
@@ -1190,6 +2029,7 @@ async fn apply_bespoke_event_handling(
                 file_changes: changes,
                 reason,
                 grant_root,
+                risk,
             };
             let value = serde_json::to_value(&params).unwrap_or_default();
             let rx = outgoing
@@ -1223,8 +2063,53 @@ async fn apply_bespoke_event_handling(
                 on_exec_approval_response(event_id, rx, conversation).await;
             });
         }
+        EventMsg::SecretRequest(SecretRequestEvent {
+            call_id,
+            name,
+            reason,
+        }) => {
+            let params = SecretRequestParams {
+                conversation_id,
+                call_id,
+                name: name.clone(),
+                reason,
+            };
+            let value = serde_json::to_value(&params).unwrap_or_default();
+            let rx = outgoing
+                .send_request(SECRET_REQUEST_METHOD, Some(value))
+                .await;
+            tokio::spawn(async move {
+                on_secret_response(event_id, name, rx, conversation).await;
+            });
+        }
+        EventMsg::TaskStarted(_) => {
+            on_turn_started(&turn_queues, conversation_id, &event_id).await;
+        }
+        EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message, ..
+        }) => {
+            on_turn_finished(
+                &turn_queues,
+                conversation_id,
+                &outgoing,
+                &event_id,
+                last_agent_message,
+                None,
+            )
+            .await;
+        }
         // If this is a TurnAborted, reply to any pending interrupt requests.
         EventMsg::TurnAborted(turn_aborted_event) => {
+            on_turn_finished(
+                &turn_queues,
+                conversation_id,
+                &outgoing,
+                &event_id,
+                None,
+                Some(turn_aborted_event.reason.clone()),
+            )
+            .await;
+
             let pending = {
                 let mut map = pending_interrupts.lock().await;
                 map.remove(&conversation_id).unwrap_or_default()
@@ -1238,6 +2123,20 @@ async fn apply_bespoke_event_handling(
                 }
             }
         }
+        EventMsg::McpSamplingApprovalRequest(_) => {
+            // The app server protocol has no request for this yet; decline
+            // so the MCP server is not left waiting. Clients that want
+            // sampling can set `mcp_sampling.approval = "allow"`.
+            if let Err(err) = conversation
+                .submit(Op::McpSamplingApproval {
+                    id: event_id,
+                    decision: ReviewDecision::Denied,
+                })
+                .await
+            {
+                error!("failed to decline sampling request: {err}");
+            }
+        }
 
         _ => {}
     }
@@ -1362,6 +2261,59 @@ async fn on_exec_approval_response(
     }
 }
 
+async fn on_secret_response(
+    event_id: String,
+    name: String,
+    receiver: oneshot::Receiver<mcp_types::Result>,
+    conversation: Arc<CodexConversation>,
+) {
+    // A failed request or malformed response declines the request, so the
+    // turn does not wait forever.
+    let value = match receiver.await {
+        Ok(value) => serde_json::from_value::<SecretRequestResponse>(value)
+            .map(|response| response.value)
+            .unwrap_or_else(|err| {
+                error!("failed to deserialize SecretRequestResponse: {err}");
+                None
+            }),
+        Err(err) => {
+            error!("request failed: {err:?}");
+            None
+        }
+    };
+
+    if let Err(err) = conversation
+        .submit(Op::ProvideSecret {
+            id: event_id,
+            name,
+            value,
+        })
+        .await
+    {
+        error!("failed to submit ProvideSecret: {err}");
+    }
+}
+
+fn map_wire_input_items(items: Vec<WireInputItem>) -> Vec<CoreInputItem> {
+    items
+        .into_iter()
+        .map(|item| match item {
+            WireInputItem::Text { text } => CoreInputItem::Text { text },
+            WireInputItem::Image { image_url } => CoreInputItem::Image { image_url },
+            WireInputItem::LocalImage { path } => CoreInputItem::LocalImage { path },
+            WireInputItem::Url { url } => CoreInputItem::Url { url },
+        })
+        .collect()
+}
+
+/// Clamp a client-supplied completion limit to a sane, non-zero value.
+fn completion_limit(limit: Option<usize>) -> NonZeroUsize {
+    limit
+        .map(|l| l.min(MAX_COMPLETION_LIMIT))
+        .and_then(NonZeroUsize::new)
+        .unwrap_or(DEFAULT_COMPLETION_LIMIT)
+}
+
 fn extract_conversation_summary(
     path: PathBuf,
     head: &[serde_json::Value],
@@ -20,6 +20,8 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SecretRequestEvent;
 use codex_core::protocol::Submission;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -192,6 +194,36 @@ async fn run_codex_tool_session_inner(
                         .await;
                         continue;
                     }
+                    EventMsg::SecretRequest(SecretRequestEvent { name, .. }) => {
+                        // There is no way to prompt for a masked value over
+                        // MCP, so the request is declined and the model is
+                        // told the secret is unavailable.
+                        if let Err(err) = codex
+                            .submit(Op::ProvideSecret {
+                                id: event.id.clone(),
+                                name,
+                                value: None,
+                            })
+                            .await
+                        {
+                            tracing::error!("failed to decline secret request: {err}");
+                        }
+                        continue;
+                    }
+                    EventMsg::McpSamplingApprovalRequest(_) => {
+                        // Approving sampling is not part of the MCP tool
+                        // interface, so the request is declined.
+                        if let Err(err) = codex
+                            .submit(Op::McpSamplingApproval {
+                                id: event.id.clone(),
+                                decision: ReviewDecision::Denied,
+                            })
+                            .await
+                        {
+                            tracing::error!("failed to decline sampling request: {err}");
+                        }
+                        continue;
+                    }
                     EventMsg::Error(err_event) => {
                         // Return a response to conclude the tool call when the Codex session reports an error (e.g., interruption).
                         let result = json!({
@@ -205,6 +237,7 @@ async fn run_codex_tool_session_inner(
                         reason,
                         grant_root,
                         changes,
+                        ..
                     }) => {
                         handle_patch_approval_request(
                             call_id,
@@ -220,7 +253,9 @@ async fn run_codex_tool_session_inner(
                         .await;
                         continue;
                     }
-                    EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                    EventMsg::TaskComplete(TaskCompleteEvent {
+                        last_agent_message, ..
+                    }) => {
                         let text = match last_agent_message {
                             Some(msg) => msg,
                             None => "".to_string(),
@@ -264,6 +299,7 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::McpToolCallEnd(_)
                     | EventMsg::McpListToolsResponse(_)
                     | EventMsg::ListCustomPromptsResponse(_)
+                    | EventMsg::ContextUsage(_)
                     | EventMsg::ExecCommandBegin(_)
                     | EventMsg::ExecCommandOutputDelta(_)
                     | EventMsg::ExecCommandEnd(_)
@@ -281,7 +317,21 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::UserMessage(_)
                     | EventMsg::ShutdownComplete
                     | EventMsg::EnteredReviewMode(_)
-                    | EventMsg::ExitedReviewMode(_) => {
+                    | EventMsg::ExitedReviewMode(_)
+                    | EventMsg::AnnotationAdded(_)
+                    | EventMsg::ContextPruned(_)
+                    | EventMsg::LoginRequired(_)
+                    | EventMsg::BackgroundResponseStarted(_)
+                    | EventMsg::BackgroundJob(_)
+                    | EventMsg::ProviderEndpointSwitched(_)
+                    | EventMsg::ProviderSwitched(_)
+                    | EventMsg::McpServerStatus(_)
+                    | EventMsg::QueuedUserInput(_)
+                    | EventMsg::PullRequestCreated(_)
+                    | EventMsg::TurnReverted(_)
+                    | EventMsg::CheckpointCreated(_)
+                    | EventMsg::CheckpointRestored(_)
+                    | EventMsg::ModelChanged(_) => {
                         // For now, we do not do anything extra for these
                         // events. Note that
                         // send(codex_event_to_notification(&event)) above has
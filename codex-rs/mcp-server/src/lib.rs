@@ -8,6 +8,7 @@ use std::path::PathBuf;
 use codex_common::CliConfigOverrides;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
 
 use mcp_types::JSONRPCMessage;
 use tokio::io::AsyncBufReadExt;
@@ -19,6 +20,7 @@ use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::prelude::*;
 
 mod codex_message_processor;
 mod codex_tool_config;
@@ -29,6 +31,7 @@ mod json_to_toml;
 pub(crate) mod message_processor;
 mod outgoing_message;
 mod patch_approval;
+mod turn_queue;
 
 use crate::message_processor::MessageProcessor;
 use crate::outgoing_message::OutgoingMessage;
@@ -52,9 +55,17 @@ pub async fn run_main(
 ) -> IoResult<()> {
     // Install a simple subscriber so `tracing` output is visible.  Users can
     // control the log level with `RUST_LOG`.
-    tracing_subscriber::fmt()
+    let fmt_layer = tracing_subscriber::fmt::layer()
         .with_writer(std::io::stderr)
-        .with_env_filter(EnvFilter::from_default_env())
+        .with_filter(EnvFilter::from_default_env());
+    let session_log_layer = find_codex_home()
+        .ok()
+        .and_then(|codex_home| codex_core::session_log::layer(&codex_home).ok());
+    let (otel_layer, _otel_guard) = codex_core::otel::layer("codex-mcp-server").unzip();
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(session_log_layer)
+        .with(otel_layer)
         .init();
 
     // Set up channels.
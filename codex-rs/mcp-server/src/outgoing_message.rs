@@ -286,6 +286,7 @@ mod tests {
                 history_entry_count: 1000,
                 initial_messages: None,
                 rollout_path: rollout_file.path().to_path_buf(),
+                sandbox_capability: None,
             }),
         };
 
@@ -321,6 +322,7 @@ mod tests {
             history_entry_count: 1000,
             initial_messages: None,
             rollout_path: rollout_file.path().to_path_buf(),
+            sandbox_capability: None,
         };
         let event = Event {
             id: "1".to_string(),
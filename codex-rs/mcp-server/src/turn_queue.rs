@@ -0,0 +1,176 @@
+//! Bookkeeping for the `queueUserTurns` request.
+//!
+//! The turns themselves are queued in core with [`Op::QueueUserInput`], which
+//! starts each one once the previous turn finishes, whether or not a client
+//! listens to the conversation. The server only remembers which submission
+//! belongs to which batch, so that it can tell listeners when each queued
+//! turn finished (`TaskComplete` or `TurnAborted` for the same submission id).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use codex_core::CodexConversation;
+use codex_core::protocol::InputItem as CoreInputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::TurnAbortReason;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::mcp_protocol::QueuedTurnCompleteNotification;
+use codex_protocol::mcp_protocol::ServerNotification;
+use tokio::sync::Mutex;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::outgoing_message::OutgoingMessageSender;
+
+pub(crate) type TurnQueues = Arc<Mutex<HashMap<ConversationId, TurnQueue>>>;
+
+/// The queued turns of a conversation that have not finished yet.
+#[derive(Default)]
+pub(crate) struct TurnQueue {
+    /// By the id of the submission that queued the turn, which the turn's
+    /// events carry.
+    turns: HashMap<String, QueuedTurn>,
+    /// The submission id of the queued turn that is running, once its
+    /// `TaskStarted` event was seen.
+    running: Option<String>,
+}
+
+struct QueuedTurn {
+    queue_id: Uuid,
+    turn_index: usize,
+}
+
+/// Queues `turns` in the conversation, the first one starting right away if
+/// no turn is running. Returns the number of queued turns that have not
+/// finished.
+pub(crate) async fn enqueue_turns(
+    queues: &TurnQueues,
+    conversation_id: ConversationId,
+    conversation: &CodexConversation,
+    queue_id: Uuid,
+    turns: Vec<Vec<CoreInputItem>>,
+) -> usize {
+    let mut map = queues.lock().await;
+    let queue = map.entry(conversation_id).or_default();
+    for (turn_index, items) in turns.into_iter().enumerate() {
+        match conversation.submit(Op::QueueUserInput { items }).await {
+            Ok(submission_id) => {
+                queue.turns.insert(
+                    submission_id,
+                    QueuedTurn {
+                        queue_id,
+                        turn_index,
+                    },
+                );
+            }
+            Err(err) => {
+                error!("failed to queue turn {turn_index} of {queue_id}: {err}");
+            }
+        }
+    }
+    let len = queue.turns.len();
+    if len == 0 {
+        map.remove(&conversation_id);
+    }
+    len
+}
+
+/// Drops every turn queued with `queueUserTurns` that has not started yet;
+/// input that other clients queued stays. Returns how many turns were
+/// dropped; the server learns that a turn started from the conversation's
+/// events, so without a listener the count includes turns that already ran.
+pub(crate) async fn cancel_pending_turns(
+    queues: &TurnQueues,
+    conversation_id: ConversationId,
+    conversation: &CodexConversation,
+) -> usize {
+    let mut map = queues.lock().await;
+    let Some(queue) = map.get_mut(&conversation_id) else {
+        return 0;
+    };
+    let running = queue.running.clone();
+    let submission_ids: Vec<String> = queue
+        .turns
+        .keys()
+        .filter(|submission_id| Some(*submission_id) != running.as_ref())
+        .cloned()
+        .collect();
+    if submission_ids.is_empty() {
+        return 0;
+    }
+    if let Err(err) = conversation
+        .submit(Op::RemoveQueuedSubmissions {
+            submission_ids: submission_ids.clone(),
+        })
+        .await
+    {
+        error!("failed to cancel queued turns: {err}");
+        return 0;
+    }
+    queue
+        .turns
+        .retain(|submission_id, _| !submission_ids.contains(submission_id));
+    if queue.turns.is_empty() {
+        map.remove(&conversation_id);
+    }
+    submission_ids.len()
+}
+
+/// Called for every `TaskStarted` event.
+pub(crate) async fn on_turn_started(
+    queues: &TurnQueues,
+    conversation_id: ConversationId,
+    event_id: &str,
+) {
+    let mut map = queues.lock().await;
+    if let Some(queue) = map.get_mut(&conversation_id)
+        && queue.turns.contains_key(event_id)
+    {
+        queue.running = Some(event_id.to_string());
+    }
+}
+
+/// Called for every `TaskComplete`/`TurnAborted` event. If the event belongs
+/// to a queued turn, notify the client. Interrupting a turn also drops the
+/// queued turns in core, so that an interrupt stops the whole batch.
+pub(crate) async fn on_turn_finished(
+    queues: &TurnQueues,
+    conversation_id: ConversationId,
+    outgoing: &OutgoingMessageSender,
+    event_id: &str,
+    last_agent_message: Option<String>,
+    abort_reason: Option<TurnAbortReason>,
+) {
+    let notification = {
+        let mut map = queues.lock().await;
+        let Some(queue) = map.get_mut(&conversation_id) else {
+            return;
+        };
+        let Some(finished) = queue.turns.remove(event_id) else {
+            return;
+        };
+        if queue.running.as_deref() == Some(event_id) {
+            queue.running = None;
+        }
+        if abort_reason == Some(TurnAbortReason::Interrupted) {
+            queue.turns.clear();
+        }
+        let remaining = queue.turns.len();
+        if remaining == 0 {
+            map.remove(&conversation_id);
+        }
+
+        QueuedTurnCompleteNotification {
+            conversation_id,
+            queue_id: finished.queue_id,
+            turn_index: finished.turn_index,
+            aborted: abort_reason.is_some(),
+            last_agent_message,
+            remaining,
+        }
+    };
+
+    outgoing
+        .send_server_notification(ServerNotification::QueuedTurnComplete(notification))
+        .await;
+}
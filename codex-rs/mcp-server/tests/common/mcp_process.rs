@@ -12,19 +12,32 @@ use tokio::process::ChildStdout;
 use anyhow::Context;
 use assert_cmd::prelude::*;
 use codex_mcp_server::CodexToolCallParam;
+use codex_protocol::mcp_protocol::AddAnnotationParams;
 use codex_protocol::mcp_protocol::AddConversationListenerParams;
 use codex_protocol::mcp_protocol::ArchiveConversationParams;
 use codex_protocol::mcp_protocol::CancelLoginChatGptParams;
+use codex_protocol::mcp_protocol::CancelQueuedTurnsParams;
+use codex_protocol::mcp_protocol::CompleteWorkspacePathsParams;
+use codex_protocol::mcp_protocol::CompleteWorkspaceSymbolsParams;
 use codex_protocol::mcp_protocol::GetAuthStatusParams;
+use codex_protocol::mcp_protocol::GetConversationStatsParams;
 use codex_protocol::mcp_protocol::InterruptConversationParams;
 use codex_protocol::mcp_protocol::ListConversationsParams;
 use codex_protocol::mcp_protocol::LoginApiKeyParams;
 use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::QueueUserTurnsParams;
 use codex_protocol::mcp_protocol::RemoveConversationListenerParams;
+use codex_protocol::mcp_protocol::RemoveProviderApiKeyParams;
 use codex_protocol::mcp_protocol::ResumeConversationParams;
+use codex_protocol::mcp_protocol::RevertTurnParams;
 use codex_protocol::mcp_protocol::SendUserMessageParams;
 use codex_protocol::mcp_protocol::SendUserTurnParams;
 use codex_protocol::mcp_protocol::SetDefaultModelParams;
+use codex_protocol::mcp_protocol::SetLogLevelParams;
+use codex_protocol::mcp_protocol::SetProviderApiKeyParams;
+use codex_protocol::mcp_protocol::SetWireLoggingParams;
+use codex_protocol::mcp_protocol::StartReviewParams;
+use codex_protocol::mcp_protocol::SwitchAccountParams;
 
 use mcp_types::CallToolRequestParams;
 use mcp_types::ClientCapabilities;
@@ -269,6 +282,78 @@ impl McpProcess {
         self.send_request("sendUserTurn", params).await
     }
 
+    /// Send a `queueUserTurns` JSON-RPC request.
+    pub async fn send_queue_user_turns_request(
+        &mut self,
+        params: QueueUserTurnsParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("queueUserTurns", params).await
+    }
+
+    /// Send a `cancelQueuedTurns` JSON-RPC request.
+    pub async fn send_cancel_queued_turns_request(
+        &mut self,
+        params: CancelQueuedTurnsParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("cancelQueuedTurns", params).await
+    }
+
+    /// Send an `addAnnotation` JSON-RPC request.
+    pub async fn send_add_annotation_request(
+        &mut self,
+        params: AddAnnotationParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("addAnnotation", params).await
+    }
+
+    /// Send a `revertTurn` JSON-RPC request.
+    pub async fn send_revert_turn_request(
+        &mut self,
+        params: RevertTurnParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("revertTurn", params).await
+    }
+
+    /// Send a `startReview` JSON-RPC request.
+    pub async fn send_start_review_request(
+        &mut self,
+        params: StartReviewParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("startReview", params).await
+    }
+
+    /// Send a `getConversationStats` JSON-RPC request.
+    pub async fn send_get_conversation_stats_request(
+        &mut self,
+        params: GetConversationStatsParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("getConversationStats", params).await
+    }
+
+    /// Send a `setWireLogging` JSON-RPC request.
+    pub async fn send_set_wire_logging_request(
+        &mut self,
+        params: SetWireLoggingParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("setWireLogging", params).await
+    }
+
+    /// Send a `setLogLevel` JSON-RPC request.
+    pub async fn send_set_log_level_request(
+        &mut self,
+        params: SetLogLevelParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("setLogLevel", params).await
+    }
+
     /// Send a `interruptConversation` JSON-RPC request.
     pub async fn send_interrupt_conversation_request(
         &mut self,
@@ -287,6 +372,38 @@ impl McpProcess {
         self.send_request("getAuthStatus", params).await
     }
 
+    /// Send a `listAccounts` JSON-RPC request.
+    pub async fn send_list_accounts_request(&mut self) -> anyhow::Result<i64> {
+        self.send_request("listAccounts", None).await
+    }
+
+    /// Send a `switchAccount` JSON-RPC request.
+    pub async fn send_switch_account_request(
+        &mut self,
+        params: SwitchAccountParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("switchAccount", params).await
+    }
+
+    /// Send a `setProviderApiKey` JSON-RPC request.
+    pub async fn send_set_provider_api_key_request(
+        &mut self,
+        params: SetProviderApiKeyParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("setProviderApiKey", params).await
+    }
+
+    /// Send a `removeProviderApiKey` JSON-RPC request.
+    pub async fn send_remove_provider_api_key_request(
+        &mut self,
+        params: RemoveProviderApiKeyParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("removeProviderApiKey", params).await
+    }
+
     /// Send a `getUserSavedConfig` JSON-RPC request.
     pub async fn send_get_user_saved_config_request(&mut self) -> anyhow::Result<i64> {
         self.send_request("getUserSavedConfig", None).await
@@ -357,6 +474,24 @@ impl McpProcess {
         self.send_request("logoutChatGpt", None).await
     }
 
+    /// Send a `completeWorkspacePaths` JSON-RPC request.
+    pub async fn send_complete_workspace_paths_request(
+        &mut self,
+        params: CompleteWorkspacePathsParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("completeWorkspacePaths", params).await
+    }
+
+    /// Send a `completeWorkspaceSymbols` JSON-RPC request.
+    pub async fn send_complete_workspace_symbols_request(
+        &mut self,
+        params: CompleteWorkspaceSymbolsParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("completeWorkspaceSymbols", params).await
+    }
+
     async fn send_request(
         &mut self,
         method: &str,
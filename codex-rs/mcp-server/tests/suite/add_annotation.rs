@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use codex_core::protocol::AnnotationAddedEvent;
+use codex_core::protocol::AnnotationTarget;
+use codex_protocol::mcp_protocol::AddAnnotationParams;
+use codex_protocol::mcp_protocol::AddAnnotationResponse;
+use codex_protocol::mcp_protocol::AddConversationListenerParams;
+use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn add_annotation_emits_event() {
+    let server = create_mock_chat_completions_server(vec![]).await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams::default())
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<_>(new_conv_resp).expect("deserialize newConversation response");
+
+    let add_listener_id = mcp
+        .send_add_conversation_listener_request(AddConversationListenerParams { conversation_id })
+        .await
+        .expect("send addConversationListener");
+    let add_listener_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(add_listener_id)),
+    )
+    .await
+    .expect("addConversationListener timeout")
+    .expect("addConversationListener resp");
+    let AddConversationSubscriptionResponse { subscription_id: _ } =
+        to_response::<_>(add_listener_resp).expect("deserialize addConversationListener response");
+
+    // An empty comment is rejected.
+    let empty_req_id = mcp
+        .send_add_annotation_request(AddAnnotationParams {
+            conversation_id,
+            target: AnnotationTarget::UserMessage { index: 0 },
+            comment: "   ".to_string(),
+        })
+        .await
+        .expect("send addAnnotation");
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(empty_req_id)),
+    )
+    .await
+    .expect("addAnnotation error timeout")
+    .expect("addAnnotation error");
+
+    let req_id = mcp
+        .send_add_annotation_request(AddAnnotationParams {
+            conversation_id,
+            target: AnnotationTarget::AgentMessage { index: 2 },
+            comment: "needs a second look".to_string(),
+        })
+        .await
+        .expect("send addAnnotation");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(req_id)),
+    )
+    .await
+    .expect("addAnnotation timeout")
+    .expect("addAnnotation resp");
+    let AddAnnotationResponse {} =
+        to_response::<_>(resp).expect("deserialize addAnnotation response");
+
+    let notification = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_notification_message("codex/event/annotation_added"),
+    )
+    .await
+    .expect("annotation_added timeout")
+    .expect("annotation_added notification");
+    let params = notification.params.expect("params");
+    let event: AnnotationAddedEvent =
+        serde_json::from_value(params["msg"].clone()).expect("deserialize annotation_added");
+    assert_eq!(
+        event,
+        AnnotationAddedEvent {
+            target: AnnotationTarget::AgentMessage { index: 2 },
+            comment: "needs a second look".to_string(),
+        }
+    );
+}
+
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+"#
+        ),
+    )
+}
@@ -1,10 +1,21 @@
 use std::path::Path;
 
+use codex_core::auth::read_provider_api_key;
+use codex_core::auth::save_account;
+use codex_core::config_types::CredentialStoreMode;
+use codex_protocol::mcp_protocol::AccountInfo;
 use codex_protocol::mcp_protocol::AuthMode;
 use codex_protocol::mcp_protocol::GetAuthStatusParams;
 use codex_protocol::mcp_protocol::GetAuthStatusResponse;
+use codex_protocol::mcp_protocol::ListAccountsResponse;
 use codex_protocol::mcp_protocol::LoginApiKeyParams;
 use codex_protocol::mcp_protocol::LoginApiKeyResponse;
+use codex_protocol::mcp_protocol::RemoveProviderApiKeyParams;
+use codex_protocol::mcp_protocol::RemoveProviderApiKeyResponse;
+use codex_protocol::mcp_protocol::SetProviderApiKeyParams;
+use codex_protocol::mcp_protocol::SetProviderApiKeyResponse;
+use codex_protocol::mcp_protocol::SwitchAccountParams;
+use codex_protocol::mcp_protocol::SwitchAccountResponse;
 use mcp_test_support::McpProcess;
 use mcp_test_support::to_response;
 use mcp_types::JSONRPCResponse;
@@ -221,3 +232,172 @@ async fn get_auth_status_with_api_key_no_include_token() {
     assert_eq!(status.auth_method, Some(AuthMode::ApiKey));
     assert!(status.auth_token.is_none(), "token must be omitted");
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn list_and_switch_accounts() {
+    let codex_home = TempDir::new().unwrap_or_else(|e| panic!("create tempdir: {e}"));
+    create_config_toml(codex_home.path()).unwrap_or_else(|err| panic!("write config.toml: {err}"));
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    login_with_api_key_via_request(&mut mcp, "sk-personal-key").await;
+    save_account(codex_home.path(), "personal", CredentialStoreMode::File)
+        .expect("save personal account");
+    login_with_api_key_via_request(&mut mcp, "sk-work-key").await;
+    save_account(codex_home.path(), "work", CredentialStoreMode::File).expect("save work account");
+
+    let request_id = mcp
+        .send_list_accounts_request()
+        .await
+        .expect("send listAccounts");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("listAccounts timeout")
+    .expect("listAccounts response");
+    let ListAccountsResponse { accounts } = to_response(resp).expect("deserialize accounts");
+    assert_eq!(
+        accounts,
+        vec![
+            AccountInfo {
+                name: "personal".to_string(),
+                auth_method: AuthMode::ApiKey,
+                email: None,
+                active: false,
+            },
+            AccountInfo {
+                name: "work".to_string(),
+                auth_method: AuthMode::ApiKey,
+                email: None,
+                active: true,
+            },
+        ]
+    );
+
+    let request_id = mcp
+        .send_switch_account_request(SwitchAccountParams {
+            name: "personal".to_string(),
+        })
+        .await
+        .expect("send switchAccount");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("switchAccount timeout")
+    .expect("switchAccount response");
+    let switched: SwitchAccountResponse = to_response(resp).expect("deserialize switch");
+    assert_eq!(switched.auth_method, Some(AuthMode::ApiKey));
+
+    let request_id = mcp
+        .send_get_auth_status_request(GetAuthStatusParams {
+            include_token: Some(true),
+            refresh_token: Some(false),
+        })
+        .await
+        .expect("send getAuthStatus");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("getAuthStatus timeout")
+    .expect("getAuthStatus response");
+    let status: GetAuthStatusResponse = to_response(resp).expect("deserialize status");
+    assert_eq!(status.auth_token, Some("sk-personal-key".to_string()));
+
+    let request_id = mcp
+        .send_switch_account_request(SwitchAccountParams {
+            name: "missing".to_string(),
+        })
+        .await
+        .expect("send switchAccount");
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("switchAccount error timeout")
+    .expect("switchAccount error");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn set_and_remove_provider_api_key() {
+    let codex_home = TempDir::new().unwrap_or_else(|e| panic!("create tempdir: {e}"));
+    create_config_toml_custom_provider(codex_home.path(), false)
+        .unwrap_or_else(|err| panic!("write config.toml: {err}"));
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let request_id = mcp
+        .send_set_provider_api_key_request(SetProviderApiKeyParams {
+            provider_id: "mock_provider".to_string(),
+            api_key: "sk-mock-key".to_string(),
+        })
+        .await
+        .expect("send setProviderApiKey");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("setProviderApiKey timeout")
+    .expect("setProviderApiKey response");
+    let _: SetProviderApiKeyResponse = to_response(resp).expect("deserialize set key");
+    assert_eq!(
+        read_provider_api_key(
+            codex_home.path(),
+            "mock_provider",
+            CredentialStoreMode::File
+        )
+        .expect("read provider key"),
+        Some("sk-mock-key".to_string())
+    );
+
+    // Keys for OpenAI come from the regular login flow.
+    let request_id = mcp
+        .send_set_provider_api_key_request(SetProviderApiKeyParams {
+            provider_id: "openai".to_string(),
+            api_key: "sk-openai".to_string(),
+        })
+        .await
+        .expect("send setProviderApiKey");
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("setProviderApiKey error timeout")
+    .expect("setProviderApiKey error");
+
+    let request_id = mcp
+        .send_remove_provider_api_key_request(RemoveProviderApiKeyParams {
+            provider_id: "mock_provider".to_string(),
+        })
+        .await
+        .expect("send removeProviderApiKey");
+    let resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("removeProviderApiKey timeout")
+    .expect("removeProviderApiKey response");
+    let removed: RemoveProviderApiKeyResponse = to_response(resp).expect("deserialize remove");
+    assert_eq!(removed, RemoveProviderApiKeyResponse { removed: true });
+}
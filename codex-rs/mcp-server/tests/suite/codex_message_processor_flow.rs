@@ -118,6 +118,7 @@ async fn test_codex_jsonrpc_conversation_flow() {
             items: vec![codex_protocol::mcp_protocol::InputItem::Text {
                 text: "text".to_string(),
             }],
+            turn_options: None,
         })
         .await
         .expect("send sendUserMessage");
@@ -268,6 +269,7 @@ async fn test_send_user_turn_changes_approval_policy_behavior() {
             items: vec![codex_protocol::mcp_protocol::InputItem::Text {
                 text: "run python".to_string(),
             }],
+            turn_options: None,
         })
         .await
         .expect("send sendUserMessage");
@@ -322,6 +324,7 @@ async fn test_send_user_turn_changes_approval_policy_behavior() {
             model: "mock-model".to_string(),
             effort: Some(ReasoningEffort::Medium),
             summary: ReasoningSummary::Auto,
+            output_schema: None,
         })
         .await
         .expect("send sendUserTurn");
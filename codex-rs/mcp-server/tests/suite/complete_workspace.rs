@@ -0,0 +1,99 @@
+use codex_protocol::mcp_protocol::CompleteWorkspacePathsParams;
+use codex_protocol::mcp_protocol::CompleteWorkspacePathsResponse;
+use codex_protocol::mcp_protocol::CompleteWorkspaceSymbolsParams;
+use codex_protocol::mcp_protocol::CompleteWorkspaceSymbolsResponse;
+use codex_protocol::mcp_protocol::WorkspaceSymbolMatch;
+use mcp_test_support::McpProcess;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn complete_workspace_paths_and_symbols() {
+    let codex_home = TempDir::new().unwrap_or_else(|err| panic!("create tempdir: {err}"));
+    let workspace = TempDir::new().unwrap_or_else(|err| panic!("create tempdir: {err}"));
+    std::fs::create_dir(workspace.path().join("src")).expect("create src");
+    std::fs::write(
+        workspace.path().join("src").join("widget.rs"),
+        "pub struct Widget;\n",
+    )
+    .expect("write widget.rs");
+    std::fs::write(workspace.path().join("README.md"), "# readme\n").expect("write README");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("initialize timeout")
+        .expect("initialize request");
+
+    let request_id = mcp
+        .send_complete_workspace_paths_request(CompleteWorkspacePathsParams {
+            prefix: "widg".to_string(),
+            cwd: Some(workspace.path().to_path_buf()),
+            limit: None,
+        })
+        .await
+        .expect("send completeWorkspacePaths");
+    let response: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("completeWorkspacePaths timeout")
+    .expect("completeWorkspacePaths response");
+    let paths: CompleteWorkspacePathsResponse =
+        to_response(response).expect("deserialize completeWorkspacePaths response");
+    let matched: Vec<String> = paths.matches.into_iter().map(|m| m.path).collect();
+    assert_eq!(
+        matched,
+        vec![
+            std::path::Path::new("src")
+                .join("widget.rs")
+                .display()
+                .to_string()
+        ]
+    );
+
+    let request_id = mcp
+        .send_complete_workspace_symbols_request(CompleteWorkspaceSymbolsParams {
+            prefix: "Widget".to_string(),
+            cwd: Some(workspace.path().to_path_buf()),
+            limit: Some(5),
+        })
+        .await
+        .expect("send completeWorkspaceSymbols");
+    let response: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("completeWorkspaceSymbols timeout")
+    .expect("completeWorkspaceSymbols response");
+    let symbols: CompleteWorkspaceSymbolsResponse =
+        to_response(response).expect("deserialize completeWorkspaceSymbols response");
+    let matches: Vec<WorkspaceSymbolMatch> = symbols
+        .matches
+        .into_iter()
+        .map(|m| WorkspaceSymbolMatch { score: 0, ..m })
+        .collect();
+    assert_eq!(
+        matches,
+        vec![WorkspaceSymbolMatch {
+            name: "Widget".to_string(),
+            kind: "struct".to_string(),
+            path: std::path::Path::new("src")
+                .join("widget.rs")
+                .display()
+                .to_string(),
+            line: 1,
+            score: 0,
+        }]
+    );
+}
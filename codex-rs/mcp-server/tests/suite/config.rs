@@ -91,6 +91,8 @@ async fn get_config_toml_parses_all_fields() {
                 network_access: Some(true),
                 exclude_tmpdir_env_var: Some(true),
                 exclude_slash_tmp: Some(true),
+                network_allowlist: Vec::new(),
+                deny: Vec::new(),
             }),
             model: Some("gpt-5-codex".into()),
             model_reasoning_effort: Some(ReasoningEffort::High),
@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use codex_core::protocol::TokenUsage;
+use codex_protocol::mcp_protocol::AddConversationListenerParams;
+use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
+use codex_protocol::mcp_protocol::GetConversationStatsParams;
+use codex_protocol::mcp_protocol::GetConversationStatsResponse;
+use codex_protocol::mcp_protocol::InputItem;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use codex_protocol::mcp_protocol::SendUserMessageParams;
+use codex_protocol::mcp_protocol::SendUserMessageResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn conversation_stats_report_usage_and_cost() {
+    let message = json!({
+        "choices": [{ "delta": { "content": "Done" }, "finish_reason": "stop" }]
+    });
+    let usage = json!({
+        "choices": [],
+        "usage": {
+            "prompt_tokens": 1_000_000,
+            "completion_tokens": 100_000,
+            "total_tokens": 1_100_000
+        }
+    });
+    let sse = format!("data: {message}\n\ndata: {usage}\n\ndata: DONE\n\n");
+    let server = create_mock_chat_completions_server(vec![sse]).await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams::default())
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<_>(new_conv_resp).expect("deserialize newConversation response");
+
+    // Nothing has been sent to the model yet.
+    let stats_id = mcp
+        .send_get_conversation_stats_request(GetConversationStatsParams { conversation_id })
+        .await
+        .expect("send getConversationStats");
+    let stats_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(stats_id)),
+    )
+    .await
+    .expect("getConversationStats timeout")
+    .expect("getConversationStats resp");
+    let stats: GetConversationStatsResponse =
+        to_response(stats_resp).expect("deserialize getConversationStats response");
+    assert_eq!(
+        stats,
+        GetConversationStatsResponse {
+            token_usage: None,
+            estimated_cost_usd: None,
+        }
+    );
+
+    let add_listener_id = mcp
+        .send_add_conversation_listener_request(AddConversationListenerParams { conversation_id })
+        .await
+        .expect("send addConversationListener");
+    let add_listener_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(add_listener_id)),
+    )
+    .await
+    .expect("addConversationListener timeout")
+    .expect("addConversationListener resp");
+    let AddConversationSubscriptionResponse { subscription_id: _ } =
+        to_response::<_>(add_listener_resp).expect("deserialize addConversationListener response");
+
+    let send_id = mcp
+        .send_send_user_message_request(SendUserMessageParams {
+            conversation_id,
+            items: vec![InputItem::Text {
+                text: "Hello".to_string(),
+            }],
+            turn_options: None,
+        })
+        .await
+        .expect("send sendUserMessage");
+    let send_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(send_id)),
+    )
+    .await
+    .expect("sendUserMessage timeout")
+    .expect("sendUserMessage resp");
+    let _ok: SendUserMessageResponse =
+        to_response(send_resp).expect("deserialize sendUserMessage response");
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_notification_message("codex/event/task_complete"),
+    )
+    .await
+    .expect("task_complete timeout")
+    .expect("task_complete notification");
+
+    let stats_id = mcp
+        .send_get_conversation_stats_request(GetConversationStatsParams { conversation_id })
+        .await
+        .expect("send getConversationStats");
+    let stats_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(stats_id)),
+    )
+    .await
+    .expect("getConversationStats timeout")
+    .expect("getConversationStats resp");
+    let stats: GetConversationStatsResponse =
+        to_response(stats_resp).expect("deserialize getConversationStats response");
+    assert_eq!(
+        stats,
+        GetConversationStatsResponse {
+            token_usage: Some(TokenUsage {
+                input_tokens: 1_000_000,
+                cached_input_tokens: 0,
+                output_tokens: 100_000,
+                reasoning_output_tokens: 0,
+                total_tokens: 1_100_000,
+            }),
+            // 1M input tokens at $2 and 100k output tokens at $10 per million.
+            estimated_cost_usd: Some(3.0),
+        }
+    );
+}
+
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+
+[[pricing]]
+model = "mock-model"
+input = 2.0
+output = 10.0
+"#
+        ),
+    )
+}
@@ -89,6 +89,7 @@ async fn test_conversation_create_and_send_message_ok() {
             items: vec![InputItem::Text {
                 text: "Hello".to_string(),
             }],
+            turn_options: None,
         })
         .await
         .expect("send sendUserMessage");
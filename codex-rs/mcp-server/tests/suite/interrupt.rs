@@ -103,6 +103,7 @@ async fn shell_command_interruption() -> anyhow::Result<()> {
             items: vec![codex_protocol::mcp_protocol::InputItem::Text {
                 text: "run first sleep command".to_string(),
             }],
+            turn_options: None,
         })
         .await?;
     let send_user_resp: JSONRPCResponse = timeout(
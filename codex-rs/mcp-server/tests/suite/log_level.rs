@@ -0,0 +1,44 @@
+use codex_protocol::mcp_protocol::LogLevel;
+use codex_protocol::mcp_protocol::SetLogLevelParams;
+use codex_protocol::mcp_protocol::SetLogLevelResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn set_log_level_reports_the_session_log_directory() {
+    let codex_home = TempDir::new().expect("create temp dir");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let request_id = mcp
+        .send_set_log_level_request(SetLogLevelParams {
+            level: LogLevel::Debug,
+        })
+        .await
+        .expect("send setLogLevel");
+    let response: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await
+    .expect("setLogLevel timeout")
+    .expect("setLogLevel response");
+
+    let SetLogLevelResponse { log_dir } =
+        to_response(response).expect("deserialize setLogLevel response");
+    assert_eq!(log_dir, codex_home.path().join("log").join("sessions"));
+    assert!(log_dir.is_dir(), "the server creates the log directory");
+}
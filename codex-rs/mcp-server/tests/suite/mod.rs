@@ -1,14 +1,22 @@
 // Aggregates all former standalone integration tests as modules.
+mod add_annotation;
 mod archive_conversation;
 mod auth;
 mod codex_message_processor_flow;
 mod codex_tool;
+mod complete_workspace;
 mod config;
+mod conversation_stats;
 mod create_conversation;
 mod interrupt;
 mod list_resume;
+mod log_level;
 mod login;
+mod queue_user_turns;
+mod revert_turn;
 mod send_message;
 mod set_default_model;
+mod start_review;
 mod user_agent;
 mod user_info;
+mod wire_log;
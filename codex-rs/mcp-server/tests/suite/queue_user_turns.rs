@@ -0,0 +1,236 @@
+use std::path::Path;
+
+use codex_protocol::mcp_protocol::AddConversationListenerParams;
+use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
+use codex_protocol::mcp_protocol::CancelQueuedTurnsParams;
+use codex_protocol::mcp_protocol::CancelQueuedTurnsResponse;
+use codex_protocol::mcp_protocol::InputItem;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use codex_protocol::mcp_protocol::QueueUserTurnsParams;
+use codex_protocol::mcp_protocol::QueueUserTurnsResponse;
+use codex_protocol::mcp_protocol::QueuedTurnCompleteNotification;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_final_assistant_message_sse_response;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn queued_turns_run_sequentially() {
+    let responses = vec![
+        create_final_assistant_message_sse_response("first").expect("build mock assistant message"),
+        create_final_assistant_message_sse_response("second")
+            .expect("build mock assistant message"),
+    ];
+    let server = create_mock_chat_completions_server(responses).await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams::default())
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<_>(new_conv_resp).expect("deserialize newConversation response");
+
+    let add_listener_id = mcp
+        .send_add_conversation_listener_request(AddConversationListenerParams { conversation_id })
+        .await
+        .expect("send addConversationListener");
+    let add_listener_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(add_listener_id)),
+    )
+    .await
+    .expect("addConversationListener timeout")
+    .expect("addConversationListener resp");
+    let AddConversationSubscriptionResponse { subscription_id: _ } =
+        to_response::<_>(add_listener_resp).expect("deserialize addConversationListener response");
+
+    let queue_req_id = mcp
+        .send_queue_user_turns_request(QueueUserTurnsParams {
+            conversation_id,
+            turns: vec![
+                vec![InputItem::Text {
+                    text: "one".to_string(),
+                }],
+                vec![InputItem::Text {
+                    text: "two".to_string(),
+                }],
+            ],
+        })
+        .await
+        .expect("send queueUserTurns");
+    let queue_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(queue_req_id)),
+    )
+    .await
+    .expect("queueUserTurns timeout")
+    .expect("queueUserTurns resp");
+    let QueueUserTurnsResponse {
+        queue_id,
+        queued_count,
+    } = to_response::<_>(queue_resp).expect("deserialize queueUserTurns response");
+    assert_eq!(queued_count, 2);
+
+    for (turn_index, message) in ["first", "second"].into_iter().enumerate() {
+        let notification = timeout(
+            DEFAULT_READ_TIMEOUT,
+            mcp.read_stream_until_notification_message("queuedTurnComplete"),
+        )
+        .await
+        .expect("queuedTurnComplete timeout")
+        .expect("queuedTurnComplete notification");
+        let params: QueuedTurnCompleteNotification =
+            serde_json::from_value(notification.params.expect("params"))
+                .expect("deserialize queuedTurnComplete");
+        assert_eq!(
+            params,
+            QueuedTurnCompleteNotification {
+                conversation_id,
+                queue_id,
+                turn_index,
+                aborted: false,
+                last_agent_message: Some(message.to_string()),
+                remaining: 1 - turn_index,
+            }
+        );
+    }
+
+    // Nothing is left to cancel once the queue has drained.
+    let cancel_req_id = mcp
+        .send_cancel_queued_turns_request(CancelQueuedTurnsParams { conversation_id })
+        .await
+        .expect("send cancelQueuedTurns");
+    let cancel_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(cancel_req_id)),
+    )
+    .await
+    .expect("cancelQueuedTurns timeout")
+    .expect("cancelQueuedTurns resp");
+    let CancelQueuedTurnsResponse { cancelled_count } =
+        to_response::<_>(cancel_resp).expect("deserialize cancelQueuedTurns response");
+    assert_eq!(cancelled_count, 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn queued_turns_run_without_a_listener() {
+    let responses = vec![
+        create_final_assistant_message_sse_response("first").expect("build mock assistant message"),
+        create_final_assistant_message_sse_response("second")
+            .expect("build mock assistant message"),
+    ];
+    let server = create_mock_chat_completions_server(responses).await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams::default())
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<_>(new_conv_resp).expect("deserialize newConversation response");
+
+    let queue_req_id = mcp
+        .send_queue_user_turns_request(QueueUserTurnsParams {
+            conversation_id,
+            turns: vec![
+                vec![InputItem::Text {
+                    text: "one".to_string(),
+                }],
+                vec![InputItem::Text {
+                    text: "two".to_string(),
+                }],
+            ],
+        })
+        .await
+        .expect("send queueUserTurns");
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(queue_req_id)),
+    )
+    .await
+    .expect("queueUserTurns timeout")
+    .expect("queueUserTurns resp");
+
+    // Both turns reach the model although nobody listens to the conversation.
+    timeout(DEFAULT_READ_TIMEOUT, async {
+        loop {
+            let requests = server.received_requests().await.unwrap_or_default();
+            if requests.len() == 2 {
+                let last = String::from_utf8_lossy(&requests[1].body).into_owned();
+                assert!(last.contains("two"), "{last}");
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("both queued turns should run");
+}
+
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+"#
+        ),
+    )
+}
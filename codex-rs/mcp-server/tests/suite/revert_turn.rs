@@ -0,0 +1,177 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::mcp_protocol::AddConversationListenerParams;
+use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::mcp_protocol::InputItem;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use codex_protocol::mcp_protocol::RevertTurnParams;
+use codex_protocol::mcp_protocol::RevertTurnResponse;
+use codex_protocol::mcp_protocol::SendUserMessageParams;
+use codex_protocol::mcp_protocol::SendUserMessageResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_apply_patch_sse_response;
+use mcp_test_support::create_final_assistant_message_sse_response;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn revert_turn_restores_patched_files() {
+    let workspace = TempDir::new().expect("create temp dir");
+    let file = workspace.path().join("notes.txt");
+    std::fs::write(&file, "original content\n").expect("write file");
+    let patch = format!(
+        "*** Begin Patch\n*** Update File: {}\n-original content\n+modified content\n*** End Patch",
+        file.display()
+    );
+    let server = create_mock_chat_completions_server(vec![
+        create_apply_patch_sse_response(&patch, "call1").expect("patch response"),
+        create_final_assistant_message_sse_response("Done").expect("final response"),
+    ])
+    .await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams {
+            cwd: Some(workspace.path().to_string_lossy().into_owned()),
+            ..Default::default()
+        })
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<_>(new_conv_resp).expect("deserialize newConversation response");
+
+    let add_listener_id = mcp
+        .send_add_conversation_listener_request(AddConversationListenerParams { conversation_id })
+        .await
+        .expect("send addConversationListener");
+    let add_listener_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(add_listener_id)),
+    )
+    .await
+    .expect("addConversationListener timeout")
+    .expect("addConversationListener resp");
+    let AddConversationSubscriptionResponse { subscription_id: _ } =
+        to_response::<_>(add_listener_resp).expect("deserialize addConversationListener response");
+
+    let send_id = mcp
+        .send_send_user_message_request(SendUserMessageParams {
+            conversation_id,
+            items: vec![InputItem::Text {
+                text: "Edit the notes".to_string(),
+            }],
+            turn_options: None,
+        })
+        .await
+        .expect("send sendUserMessage");
+    let send_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(send_id)),
+    )
+    .await
+    .expect("sendUserMessage timeout")
+    .expect("sendUserMessage resp");
+    let _ok: SendUserMessageResponse =
+        to_response(send_resp).expect("deserialize sendUserMessage response");
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_notification_message("codex/event/task_complete"),
+    )
+    .await
+    .expect("task_complete timeout")
+    .expect("task_complete notification");
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read file"),
+        "modified content\n"
+    );
+
+    let revert = revert_turn(&mut mcp, conversation_id).await;
+    assert!(revert.turn_id.is_some());
+    assert_eq!(revert.reverted, vec![file.clone()]);
+    assert_eq!(revert.conflicts, Vec::<PathBuf>::new());
+    assert_eq!(
+        std::fs::read_to_string(&file).expect("read file"),
+        "original content\n"
+    );
+
+    // Nothing is left to roll back.
+    let revert = revert_turn(&mut mcp, conversation_id).await;
+    assert_eq!(
+        revert,
+        RevertTurnResponse {
+            turn_id: None,
+            reverted: Vec::new(),
+            conflicts: Vec::new(),
+        }
+    );
+}
+
+#[expect(clippy::expect_used)]
+async fn revert_turn(mcp: &mut McpProcess, conversation_id: ConversationId) -> RevertTurnResponse {
+    let revert_id = mcp
+        .send_revert_turn_request(RevertTurnParams {
+            conversation_id,
+            force: false,
+        })
+        .await
+        .expect("send revertTurn");
+    let revert_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(revert_id)),
+    )
+    .await
+    .expect("revertTurn timeout")
+    .expect("revertTurn resp");
+    to_response(revert_resp).expect("deserialize revertTurn response")
+}
+
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+"#
+        ),
+    )
+}
@@ -89,6 +89,7 @@ async fn send_message(message: &str, conversation_id: ConversationId, mcp: &mut
             items: vec![InputItem::Text {
                 text: message.to_string(),
             }],
+            turn_options: None,
         })
         .await
         .expect("send sendUserMessage");
@@ -143,6 +144,7 @@ async fn test_send_message_session_not_found() {
             items: vec![InputItem::Text {
                 text: "ping".to_string(),
             }],
+            turn_options: None,
         })
         .await
         .expect("send sendUserMessage");
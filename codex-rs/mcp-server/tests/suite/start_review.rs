@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use codex_core::protocol::ReviewCodeLocation;
+use codex_core::protocol::ReviewFinding;
+use codex_core::protocol::ReviewLineRange;
+use codex_core::protocol::ReviewOutputEvent;
+use codex_core::protocol::ReviewTarget;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use codex_protocol::mcp_protocol::StartReviewParams;
+use codex_protocol::mcp_protocol::StartReviewResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_final_assistant_message_sse_response;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn start_review_returns_the_findings() {
+    let workspace = TempDir::new().expect("create temp dir");
+    let review = ReviewOutputEvent {
+        findings: vec![ReviewFinding {
+            title: "[P2] Missing newline".to_string(),
+            body: "Add a trailing newline.".to_string(),
+            confidence_score: 0.5,
+            priority: 2,
+            code_location: ReviewCodeLocation {
+                absolute_file_path: workspace.path().join("notes.txt"),
+                line_range: ReviewLineRange { start: 1, end: 1 },
+            },
+        }],
+        overall_correctness: "patch is correct".to_string(),
+        overall_explanation: "Looks fine.".to_string(),
+        overall_confidence_score: 0.9,
+    };
+    let review_json = serde_json::to_string(&review).expect("serialize review");
+    let server = create_mock_chat_completions_server(vec![
+        create_final_assistant_message_sse_response(&review_json).expect("final response"),
+    ])
+    .await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timed out")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams {
+            cwd: Some(workspace.path().to_string_lossy().into_owned()),
+            ..Default::default()
+        })
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<_>(new_conv_resp).expect("deserialize newConversation response");
+
+    let review_id = mcp
+        .send_start_review_request(StartReviewParams {
+            conversation_id,
+            target: ReviewTarget::StagedChanges,
+        })
+        .await
+        .expect("send startReview");
+    let review_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(review_id)),
+    )
+    .await
+    .expect("startReview timeout")
+    .expect("startReview resp");
+    let response: StartReviewResponse =
+        to_response(review_resp).expect("deserialize startReview response");
+    assert_eq!(
+        response,
+        StartReviewResponse {
+            review: Some(review)
+        }
+    );
+}
+
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+review_model = "mock-model"
+approval_policy = "never"
+sandbox_mode = "read-only"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+"#
+        ),
+    )
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -38,6 +39,10 @@ async fn user_info_returns_email_from_auth_json() {
             account_id: None,
         }),
         last_refresh: None,
+        active_account: None,
+        accounts: Vec::new(),
+        provider_api_keys: BTreeMap::new(),
+        mcp_oauth: BTreeMap::new(),
     };
     write_auth_json(&auth_path, &auth).expect("write auth.json");
 
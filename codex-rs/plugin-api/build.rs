@@ -0,0 +1,16 @@
+use std::process::Command;
+
+fn main() {
+    // Plugins exchange trait objects with Codex, whose layout is only stable
+    // within one compiler release, so the handshake compares rustc versions.
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=CODEX_PLUGIN_RUSTC_VERSION={version}");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -0,0 +1,264 @@
+//! Extension points for third-party Codex plugins.
+//!
+//! A plugin implements [`Plugin`] and contributes any of:
+//!
+//! - [`PluginTool`]s the model can call, exposed as function tools named
+//!   `<plugin>__<tool>`;
+//! - [`ContextProvider`]s whose text is appended to the user instructions when
+//!   a session starts;
+//! - [`NotificationChannel`]s that receive the same notifications as the
+//!   `notify` program.
+//!
+//! Plugins are either linked into a custom Codex build and registered with
+//! `codex_core::plugins::register_builtin_plugin`, or built as a `cdylib`
+//! that exports its entry point with [`declare_plugin!`] and is listed under
+//! `[[plugins]]` in `config.toml`.
+//!
+//! Dynamic libraries are checked with [`check_declaration`] before anything
+//! else in them is used: the plugin API version must be compatible (see
+//! [`PluginApiVersion::is_compatible_with`]) and the plugin must have been
+//! built by the same rustc as Codex, because trait objects do not have a
+//! stable layout across compiler releases.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Version of this API. The major version is bumped for breaking changes,
+/// the minor version when items are added.
+pub const PLUGIN_API_VERSION: PluginApiVersion = PluginApiVersion { major: 1, minor: 1 };
+
+/// `rustc --version` of the compiler that built this crate.
+pub const RUSTC_VERSION: &str = env!("CODEX_PLUGIN_RUSTC_VERSION");
+
+/// Name of the symbol [`declare_plugin!`] exports from a dynamic library.
+pub const PLUGIN_DECLARATION_SYMBOL: &str = "CODEX_PLUGIN_DECLARATION";
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl PluginApiVersion {
+    /// Whether a plugin built against `self` can be loaded by a host that
+    /// implements `host`: same major version, and no items newer than the
+    /// host knows about.
+    pub fn is_compatible_with(self, host: PluginApiVersion) -> bool {
+        self.major == host.major && self.minor <= host.minor
+    }
+}
+
+impl fmt::Display for PluginApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Entry point exported by a plugin library. Only `api_version` and
+/// `rustc_version` may be read before [`check_declaration`] succeeds.
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub api_version: PluginApiVersion,
+    pub rustc_version: &'static str,
+    pub create: fn() -> Box<dyn Plugin>,
+}
+
+/// Performs the handshake between a host and a plugin declaration.
+pub fn check_declaration(declaration: &PluginDeclaration) -> Result<(), String> {
+    if !declaration
+        .api_version
+        .is_compatible_with(PLUGIN_API_VERSION)
+    {
+        return Err(format!(
+            "plugin targets API {}, but this Codex provides API {PLUGIN_API_VERSION}",
+            declaration.api_version
+        ));
+    }
+    if declaration.rustc_version != RUSTC_VERSION {
+        return Err(format!(
+            "plugin was built with `{}`, but Codex was built with `{RUSTC_VERSION}`",
+            declaration.rustc_version
+        ));
+    }
+    Ok(())
+}
+
+/// Exports `$constructor` as the entry point of a plugin library.
+///
+/// ```ignore
+/// codex_plugin_api::declare_plugin!(MyPlugin::new);
+/// ```
+#[macro_export]
+macro_rules! declare_plugin {
+    ($constructor:path) => {
+        #[unsafe(no_mangle)]
+        pub static CODEX_PLUGIN_DECLARATION: $crate::PluginDeclaration =
+            $crate::PluginDeclaration {
+                api_version: $crate::PLUGIN_API_VERSION,
+                rustc_version: $crate::RUSTC_VERSION,
+                create: {
+                    fn create() -> ::std::boxed::Box<dyn $crate::Plugin> {
+                        ::std::boxed::Box::new($constructor())
+                    }
+                    create
+                },
+            };
+    };
+}
+
+/// A plugin. Codex creates one instance per process and shares it between
+/// sessions, so implementations must be thread-safe. All methods are called
+/// from blocking threads and may do I/O.
+pub trait Plugin: Send + Sync {
+    /// Identifies the plugin in tool names and logs. Must match
+    /// `[a-zA-Z0-9_-]+`.
+    fn name(&self) -> &str;
+
+    fn tools(&self) -> Vec<Box<dyn PluginTool>> {
+        Vec::new()
+    }
+
+    fn context_providers(&self) -> Vec<Box<dyn ContextProvider>> {
+        Vec::new()
+    }
+
+    fn notification_channels(&self) -> Vec<Box<dyn NotificationChannel>> {
+        Vec::new()
+    }
+}
+
+/// A function tool offered to the model.
+pub trait PluginTool: Send + Sync {
+    /// Must match `[a-zA-Z0-9_-]+`.
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// JSON Schema of the arguments object.
+    fn parameters(&self) -> serde_json::Value;
+
+    /// Runs the tool. Both the `Ok` and the `Err` text are returned to the
+    /// model; `Err` marks the call as failed.
+    fn call(&self, call: ToolCall) -> Result<String, String>;
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    /// Arguments the model passed, already parsed as JSON.
+    pub arguments: serde_json::Value,
+    /// Working directory of the turn.
+    pub cwd: PathBuf,
+}
+
+impl ToolCall {
+    pub fn new(arguments: serde_json::Value, cwd: PathBuf) -> Self {
+        Self { arguments, cwd }
+    }
+}
+
+/// Adds text to the user instructions of new sessions, e.g. team
+/// conventions fetched from an internal service.
+pub trait ContextProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Text to append, or `None` to add nothing for this session.
+    fn provide(&self, request: &ContextRequest) -> Option<String>;
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct ContextRequest {
+    /// Working directory of the session.
+    pub cwd: PathBuf,
+}
+
+impl ContextRequest {
+    pub fn new(cwd: PathBuf) -> Self {
+        Self { cwd }
+    }
+}
+
+/// Receives user notifications, e.g. to post them to a chat service.
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn notify(&self, notification: &Notification);
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    AgentTurnComplete {
+        turn_id: String,
+        /// Messages that the user sent to the agent to initiate the turn.
+        input_messages: Vec<String>,
+        /// The last message sent by the assistant in the turn.
+        last_assistant_message: Option<String>,
+    },
+    /// Since API 1.1.
+    ApprovalRequested {
+        turn_id: String,
+        call_id: String,
+        /// What is to be approved, such as the command line or the files a
+        /// patch edits.
+        summary: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    struct EmptyPlugin;
+
+    impl Plugin for EmptyPlugin {
+        fn name(&self) -> &str {
+            "empty"
+        }
+    }
+
+    #[test]
+    fn api_versions_are_compatible_within_a_major_version() {
+        let host = PluginApiVersion { major: 1, minor: 2 };
+        assert!(PluginApiVersion { major: 1, minor: 0 }.is_compatible_with(host));
+        assert!(PluginApiVersion { major: 1, minor: 2 }.is_compatible_with(host));
+        assert!(!PluginApiVersion { major: 1, minor: 3 }.is_compatible_with(host));
+        assert!(!PluginApiVersion { major: 2, minor: 0 }.is_compatible_with(host));
+    }
+
+    #[test]
+    fn handshake_rejects_other_api_and_compiler_versions() {
+        fn create() -> Box<dyn Plugin> {
+            Box::new(EmptyPlugin)
+        }
+        let declaration = PluginDeclaration {
+            api_version: PLUGIN_API_VERSION,
+            rustc_version: RUSTC_VERSION,
+            create,
+        };
+        assert_eq!(check_declaration(&declaration), Ok(()));
+
+        let newer_api = PluginDeclaration {
+            api_version: PluginApiVersion {
+                major: PLUGIN_API_VERSION.major + 1,
+                minor: 0,
+            },
+            ..declaration
+        };
+        assert!(check_declaration(&newer_api).is_err());
+
+        let other_compiler = PluginDeclaration {
+            rustc_version: "rustc 0.0.0",
+            ..declaration
+        };
+        assert_eq!(
+            check_declaration(&other_compiler),
+            Err(format!(
+                "plugin was built with `rustc 0.0.0`, but Codex was built with `{RUSTC_VERSION}`"
+            ))
+        );
+    }
+}
@@ -30,6 +30,15 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     codex_protocol::mcp_protocol::SendUserMessageResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::SendUserTurnResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::InterruptConversationResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::AddAnnotationResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::RevertTurnResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::StartReviewResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::SetConversationModelResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::GetConversationStatsResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::SetWireLoggingResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::SetLogLevelResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::QueueUserTurnsResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::CancelQueuedTurnsResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GitDiffToRemoteResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::LoginApiKeyParams::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::LoginApiKeyResponse::export_all_to(out_dir)?;
@@ -37,12 +46,19 @@ pub fn generate_ts(out_dir: &Path, prettier: Option<&Path>) -> Result<()> {
     codex_protocol::mcp_protocol::CancelLoginChatGptResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::LogoutChatGptResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GetAuthStatusResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::ListAccountsResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::SwitchAccountResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::SetProviderApiKeyResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::RemoveProviderApiKeyResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ApplyPatchApprovalResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::ExecCommandApprovalResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::SecretRequestResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GetUserSavedConfigResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::SetDefaultModelResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::GetUserAgentResponse::export_all_to(out_dir)?;
     codex_protocol::mcp_protocol::UserInfoResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::CompleteWorkspacePathsResponse::export_all_to(out_dir)?;
+    codex_protocol::mcp_protocol::CompleteWorkspaceSymbolsResponse::export_all_to(out_dir)?;
 
     // All notification types reachable from this enum will be generated by
     // induction, so they do not need to be listed individually.
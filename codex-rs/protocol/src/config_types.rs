@@ -58,4 +58,18 @@ pub enum SandboxMode {
 
     #[serde(rename = "danger-full-access")]
     DangerFullAccess,
+
+    /// Run commands in a Docker or Podman container configured by the
+    /// `[sandbox_container]` table.
+    #[serde(rename = "container")]
+    Container,
+}
+
+/// Container engine used by `sandbox_mode = "container"`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Serialize, Display, TS)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
 }
@@ -1,3 +1,19 @@
+//! Events printed by `codex exec --json`, one JSON object per line.
+//!
+//! This is the stable schema for scripts and CI consuming `codex exec`:
+//! fields and event types may be added, but existing ones keep their names
+//! and meaning. Each line has a `type`:
+//!
+//! - `session.created`: the session id, to continue with `codex exec resume`.
+//! - `turn.started`, `turn.completed` (token usage and the final message)
+//!   and `turn.diff` (the turn's changes as a unified diff).
+//! - `item.started`, `item.updated` and `item.completed` for the items of a
+//!   turn: messages, reasoning, commands, file changes, tool calls, web
+//!   searches and the todo list.
+//! - `error` for errors reported while the session runs.
+//! - `session.ended`, always last, with the process's exit code and why it
+//!   exited; see [`ExitReason`].
+
 use serde::Deserialize;
 use serde::Serialize;
 use ts_rs::TS;
@@ -12,6 +28,8 @@ pub enum ConversationEvent {
     TurnStarted(TurnStartedEvent),
     #[serde(rename = "turn.completed")]
     TurnCompleted(TurnCompletedEvent),
+    #[serde(rename = "turn.diff")]
+    TurnDiff(TurnDiffEvent),
     #[serde(rename = "item.started")]
     ItemStarted(ItemStartedEvent),
     #[serde(rename = "item.updated")]
@@ -20,6 +38,8 @@ pub enum ConversationEvent {
     ItemCompleted(ItemCompletedEvent),
     #[serde(rename = "error")]
     Error(ConversationErrorEvent),
+    #[serde(rename = "session.ended")]
+    SessionEnded(SessionEndedEvent),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
@@ -33,9 +53,66 @@ pub struct TurnStartedEvent {}
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
 pub struct TurnCompletedEvent {
     pub usage: Usage,
+    /// The last message of the turn, `null` if it ended without one.
+    #[serde(default)]
+    pub final_message: Option<String>,
+}
+
+/// The changes made to files so far this turn.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+pub struct TurnDiffEvent {
+    pub unified_diff: String,
+}
+
+/// Printed once the session has shut down, just before the process exits.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+pub struct SessionEndedEvent {
+    /// The exit code of the process, [`ExitReason::code`] of `reason`.
+    pub exit_code: i32,
+    pub reason: ExitReason,
+}
+
+/// Why `codex exec` exited. Each reason has its own exit code, so scripts
+/// can tell failures apart without parsing output. Exit code `2` is left to
+/// invalid command-line arguments.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    /// The task completed.
+    Success,
+    /// The task failed: the agent reported that it could not complete it, or
+    /// an error was reported that none of the other reasons cover.
+    TaskFailed,
+    /// The agent asked for an approval, which cannot be given when running
+    /// non-interactively.
+    ApprovalRequired,
+    /// The model provider failed, or rejected the credentials.
+    ProviderError,
+    /// The agent ended its turn right after the sandbox blocked a command,
+    /// or the sandbox could not run one at all.
+    SandboxDenied,
+    /// The run did not finish within `--timeout`.
+    Timeout,
+    /// The run used up its `--max-turns`, `--max-runtime` or `--max-cost`
+    /// budget.
+    BudgetExceeded,
+}
+
+impl ExitReason {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitReason::Success => 0,
+            ExitReason::TaskFailed => 1,
+            ExitReason::ApprovalRequired => 3,
+            ExitReason::ProviderError => 4,
+            ExitReason::SandboxDenied => 5,
+            ExitReason::Timeout => 6,
+            ExitReason::BudgetExceeded => 7,
+        }
+    }
 }
 
-/// Minimal usage summary for a turn.
+/// Token usage of the session so far, as of the end of a turn.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS, Default)]
 pub struct Usage {
     pub input_tokens: u64,
@@ -162,6 +239,7 @@ pub enum McpToolCallStatus {
     Failed,
 }
 
+/// Call of a tool provided by an MCP server.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
 pub struct McpToolCallItem {
     pub server: String,
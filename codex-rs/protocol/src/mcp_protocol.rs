@@ -6,16 +6,24 @@ use crate::config_types::ReasoningEffort;
 use crate::config_types::ReasoningSummary;
 use crate::config_types::SandboxMode;
 use crate::config_types::Verbosity;
+use crate::protocol::AnnotationTarget;
 use crate::protocol::AskForApproval;
 use crate::protocol::EventMsg;
 use crate::protocol::FileChange;
+use crate::protocol::PatchRisk;
 use crate::protocol::ReviewDecision;
+use crate::protocol::ReviewOutputEvent;
+use crate::protocol::ReviewTarget;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SecretValue;
+use crate::protocol::TokenUsage;
 use crate::protocol::TurnAbortReason;
+use crate::protocol::TurnOverrides;
 use mcp_types::RequestId;
 use serde::Deserialize;
 use serde::Serialize;
 use strum_macros::Display;
+use strum_macros::EnumString;
 use ts_rs::TS;
 use uuid::Uuid;
 
@@ -124,11 +132,71 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: SendUserTurnParams,
     },
+    /// Queue several user turns to run one after another in a conversation.
+    QueueUserTurns {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: QueueUserTurnsParams,
+    },
+    /// Drop any queued turns that have not started yet.
+    CancelQueuedTurns {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: CancelQueuedTurnsParams,
+    },
     InterruptConversation {
         #[serde(rename = "id")]
         request_id: RequestId,
         params: InterruptConversationParams,
     },
+    /// Attach a comment to an earlier message in a conversation; the
+    /// annotation is persisted in the rollout.
+    AddAnnotation {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: AddAnnotationParams,
+    },
+    /// Roll back the file changes of the last turn of a conversation that
+    /// made any.
+    RevertTurn {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: RevertTurnParams,
+    },
+    /// Review a diff or pull request in a conversation; answered once the
+    /// review is done, with its findings.
+    StartReview {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: StartReviewParams,
+    },
+    /// Token usage and estimated cost of a conversation so far.
+    GetConversationStats {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: GetConversationStatsParams,
+    },
+    /// Change the model or provider of a running conversation; later turns
+    /// use it, with the history so far.
+    SetConversationModel {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: SetConversationModelParams,
+    },
+    /// Turn logging of raw model requests and responses on or off for a
+    /// conversation.
+    SetWireLogging {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: SetWireLoggingParams,
+    },
+    /// Change the level of the per-session logs of the server, for all
+    /// conversations.
+    SetLogLevel {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: SetLogLevelParams,
+    },
     AddConversationListener {
         #[serde(rename = "id")]
         request_id: RequestId,
@@ -167,6 +235,26 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: GetAuthStatusParams,
     },
+    ListAccounts {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+    },
+    SwitchAccount {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: SwitchAccountParams,
+    },
+    /// Store or replace the API key for a `model_providers` entry.
+    SetProviderApiKey {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: SetProviderApiKeyParams,
+    },
+    RemoveProviderApiKey {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: RemoveProviderApiKeyParams,
+    },
     GetUserSavedConfig {
         #[serde(rename = "id")]
         request_id: RequestId,
@@ -190,6 +278,20 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: ExecOneOffCommandParams,
     },
+    /// Fuzzy-complete workspace file paths, using the same index as the TUI
+    /// `@` file picker.
+    CompleteWorkspacePaths {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: CompleteWorkspacePathsParams,
+    },
+    /// Fuzzy-complete symbol definitions (functions, types, classes, ...)
+    /// found in the workspace.
+    CompleteWorkspaceSymbols {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: CompleteWorkspaceSymbolsParams,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, TS)]
@@ -403,6 +505,71 @@ pub struct ExecArbitraryCommandResponse {
     pub stderr: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteWorkspacePathsParams {
+    /// Partial path typed by the user (without the leading `@`).
+    pub prefix: String,
+    /// Directory to search. Defaults to the server config cwd.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Maximum number of matches to return; defaults to a small server-side value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspacePathMatch {
+    /// Path relative to the searched directory.
+    pub path: String,
+    pub score: u32,
+    /// Sorted character indices in `path` that matched the prefix, for highlighting.
+    pub indices: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteWorkspacePathsResponse {
+    pub matches: Vec<WorkspacePathMatch>,
+    /// Number of files that matched before `limit` was applied.
+    pub total_match_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteWorkspaceSymbolsParams {
+    /// Partial symbol name typed by the user.
+    pub prefix: String,
+    /// Directory to search. Defaults to the server config cwd.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Maximum number of matches to return; defaults to a small server-side value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSymbolMatch {
+    pub name: String,
+    /// Definition keyword that introduced the symbol, e.g. `fn` or `class`.
+    pub kind: String,
+    /// Path relative to the searched directory.
+    pub path: String,
+    /// 1-based line number of the definition.
+    pub line: usize,
+    pub score: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteWorkspaceSymbolsResponse {
+    pub matches: Vec<WorkspaceSymbolMatch>,
+    /// Number of symbols that matched before `limit` was applied.
+    pub total_match_count: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct GetAuthStatusResponse {
@@ -418,6 +585,64 @@ pub struct GetAuthStatusResponse {
     pub requires_openai_auth: Option<bool>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// Name the account was saved under.
+    pub name: String,
+    pub auth_method: AuthMode,
+    /// Email from the ChatGPT ID token; omitted for API keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Whether these are the credentials currently in use.
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAccountsResponse {
+    pub accounts: Vec<AccountInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchAccountParams {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchAccountResponse {
+    /// Authentication method of the account that is now active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_method: Option<AuthMode>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetProviderApiKeyParams {
+    /// Id of the provider in `model_providers`.
+    pub provider_id: String,
+    pub api_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetProviderApiKeyResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveProviderApiKeyParams {
+    pub provider_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveProviderApiKeyResponse {
+    /// Whether a key was stored for the provider.
+    pub removed: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct GetUserAgentResponse {
@@ -527,6 +752,10 @@ pub struct SandboxSettings {
     pub exclude_tmpdir_env_var: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exclude_slash_tmp: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_allowlist: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -534,6 +763,10 @@ pub struct SandboxSettings {
 pub struct SendUserMessageParams {
     pub conversation_id: ConversationId,
     pub items: Vec<InputItem>,
+    /// Reasoning effort, verbosity or temperature for this message only.
+    /// Rejected with an error event while a task is running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_options: Option<TurnOverrides>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -548,12 +781,176 @@ pub struct SendUserTurnParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<ReasoningEffort>,
     pub summary: ReasoningSummary,
+    /// JSON schema the final assistant message of the turn must match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct SendUserTurnResponse {}
 
+/// Turns are queued in the conversation and run one after the other, each
+/// starting once the previous turn (including one already running) has
+/// finished. Conversation listeners get a `queuedTurnComplete` notification
+/// as each one finishes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueUserTurnsParams {
+    pub conversation_id: ConversationId,
+    /// Each entry is the input for one user turn.
+    pub turns: Vec<Vec<InputItem>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueUserTurnsResponse {
+    /// Identifies this batch in `queuedTurnComplete` notifications.
+    pub queue_id: Uuid,
+    /// Number of turns queued with `queueUserTurns` that have not finished,
+    /// including this batch.
+    pub queued_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelQueuedTurnsParams {
+    pub conversation_id: ConversationId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelQueuedTurnsResponse {
+    /// Number of turns queued with `queueUserTurns` that were removed before
+    /// they started. The running turn (if any) and input queued by other
+    /// clients are not affected; use `interruptConversation` for the former.
+    pub cancelled_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAnnotationParams {
+    pub conversation_id: ConversationId,
+    pub target: AnnotationTarget,
+    pub comment: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAnnotationResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertTurnParams {
+    pub conversation_id: ConversationId,
+    /// Roll back even files that changed since the turn, discarding those
+    /// changes.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertTurnResponse {
+    /// The turn that was rolled back; `None` when no turn had changes left to
+    /// roll back.
+    pub turn_id: Option<String>,
+    /// Files restored to how the turn found them.
+    pub reverted: Vec<PathBuf>,
+    /// Files that changed since the turn. Unless `force` was set, nothing was
+    /// rolled back if there are any.
+    pub conflicts: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct StartReviewParams {
+    pub conversation_id: ConversationId,
+    pub target: ReviewTarget,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct StartReviewResponse {
+    /// The findings; `None` when the review was interrupted or its output
+    /// could not be parsed.
+    pub review: Option<ReviewOutputEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConversationStatsParams {
+    pub conversation_id: ConversationId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConversationStatsResponse {
+    /// Tokens used by the conversation; `None` before its first response.
+    pub token_usage: Option<TokenUsage>,
+    /// Estimated cost in US dollars; `None` unless `pricing` covers the
+    /// conversation's model.
+    pub estimated_cost_usd: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetConversationModelParams {
+    pub conversation_id: ConversationId,
+    /// Model slug; unchanged when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Key of the provider in `model_providers`; unchanged when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetConversationModelResponse {}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetWireLoggingParams {
+    pub conversation_id: ConversationId,
+    pub enabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetWireLoggingResponse {
+    /// File the requests and responses are appended to, while logging is on.
+    pub log_path: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLogLevelParams {
+    pub level: LogLevel,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLogLevelResponse {
+    /// Directory of the session logs, one `<conversation id>.jsonl` each.
+    pub log_dir: PathBuf,
+}
+
+/// Level of the per-session logs; each level includes the ones before it.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, TS, Display, EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct InterruptConversationParams {
@@ -599,12 +996,19 @@ pub enum InputItem {
     LocalImage {
         path: PathBuf,
     },
+
+    /// Web page or raw file attached by the user. Its text is fetched and
+    /// added to the prompt before the turn starts.
+    Url {
+        url: String,
+    },
 }
 
 // TODO(mbolin): Need test to ensure these constants match the enum variants.
 
 pub const APPLY_PATCH_APPROVAL_METHOD: &str = "applyPatchApproval";
 pub const EXEC_COMMAND_APPROVAL_METHOD: &str = "execCommandApproval";
+pub const SECRET_REQUEST_METHOD: &str = "secretRequest";
 
 /// Request initiated from the server and sent to the client.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -622,6 +1026,12 @@ pub enum ServerRequest {
         request_id: RequestId,
         params: ExecCommandApprovalParams,
     },
+    /// Request a secret for a command, e.g. `NPM_TOKEN`.
+    SecretRequest {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: SecretRequestParams,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -638,6 +1048,9 @@ pub struct ApplyPatchApprovalParams {
     /// for the remainder of the session (unclear if this is honored today).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grant_root: Option<PathBuf>,
+    /// Why the patch looks risky, if it does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<PatchRisk>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -657,6 +1070,24 @@ pub struct ExecCommandApprovalResponse {
     pub decision: ReviewDecision,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct SecretRequestParams {
+    pub conversation_id: ConversationId,
+    /// Use to correlate this with [codex_core::protocol::ExecCommandBeginEvent]
+    /// and [codex_core::protocol::ExecCommandEndEvent].
+    pub call_id: String,
+    /// Name of the environment variable the secret is passed in.
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct SecretRequestResponse {
+    /// The secret, or `null` if the user declined to provide it.
+    pub value: Option<SecretValue>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 pub struct ApplyPatchApprovalResponse {
     pub decision: ReviewDecision,
@@ -679,6 +1110,22 @@ pub struct AuthStatusChangeNotification {
     pub auth_method: Option<AuthMode>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedTurnCompleteNotification {
+    pub conversation_id: ConversationId,
+    pub queue_id: Uuid,
+    /// Zero-based position of the finished turn within its batch.
+    pub turn_index: usize,
+    /// True if the turn was aborted; when it was interrupted, the remaining
+    /// queued turns are dropped.
+    pub aborted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_agent_message: Option<String>,
+    /// Turns queued with `queueUserTurns` that have not finished.
+    pub remaining: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS, Display)]
 #[serde(tag = "method", content = "params", rename_all = "camelCase")]
 #[strum(serialize_all = "camelCase")]
@@ -688,6 +1135,9 @@ pub enum ServerNotification {
 
     /// ChatGPT login flow completed
     LoginChatGptComplete(LoginChatGptCompleteNotification),
+
+    /// A turn submitted via `queueUserTurns` finished
+    QueuedTurnComplete(QueuedTurnCompleteNotification),
 }
 
 impl ServerNotification {
@@ -695,6 +1145,7 @@ impl ServerNotification {
         match self {
             ServerNotification::AuthStatusChange(params) => serde_json::to_value(params),
             ServerNotification::LoginChatGptComplete(params) => serde_json::to_value(params),
+            ServerNotification::QueuedTurnComplete(params) => serde_json::to_value(params),
         }
     }
 }
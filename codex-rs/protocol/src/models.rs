@@ -234,6 +234,9 @@ impl From<Vec<InputItem>> for ResponseInputItem {
                             None
                         }
                     },
+                    // URLs that were not expanded into attachments reach the
+                    // model as plain text.
+                    InputItem::Url { url } => Some(ContentItem::InputText { text: url }),
                 })
                 .collect::<Vec<ContentItem>>(),
         }
@@ -254,6 +257,22 @@ pub struct ShellToolCallParams {
     pub with_escalated_permissions: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub justification: Option<String>,
+    /// Names of secrets the user is asked for and that are passed to the
+    /// command as environment variables.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<String>,
+    /// Run the command as a background job and return its job id right away.
+    #[serde(
+        default,
+        rename = "run_in_background",
+        alias = "background",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub background: bool,
+    /// Run the command on a pseudo-terminal and keep it open for input sent
+    /// with the `write_stdin` tool.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tty: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, TS)]
@@ -371,9 +390,24 @@ mod tests {
                 timeout_ms: Some(1000),
                 with_escalated_permissions: None,
                 justification: None,
+                secrets: Vec::new(),
+                background: false,
+                tty: false,
             },
             params
         );
         Ok(())
     }
+
+    #[test]
+    fn deserialize_shell_tool_call_params_run_in_background() -> Result<()> {
+        for json in [
+            r#"{"command": ["npm", "run", "dev"], "run_in_background": true}"#,
+            r#"{"command": ["npm", "run", "dev"], "background": true}"#,
+        ] {
+            let params: ShellToolCallParams = serde_json::from_str(json)?;
+            assert!(params.background, "{json}");
+        }
+        Ok(())
+    }
 }
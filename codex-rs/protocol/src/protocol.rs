@@ -10,8 +10,10 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 
+use crate::config_types::ContainerRuntime;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use crate::config_types::Verbosity;
 use crate::custom_prompts::CustomPrompt;
 use crate::mcp_protocol::ConversationId;
 use crate::message_history::HistoryEntry;
@@ -62,6 +64,38 @@ pub enum Op {
         items: Vec<InputItem>,
     },
 
+    /// Queue user input to start a new turn once the running task finishes,
+    /// or right away when no task is running. The queue is reported via
+    /// [`EventMsg::QueuedUserInput`] whenever it changes.
+    QueueUserInput {
+        /// User input items, see `InputItem`
+        items: Vec<InputItem>,
+    },
+
+    /// Start a turn with settings that apply to that turn only, e.g. high
+    /// reasoning effort for a hard question. Later turns use the session's
+    /// settings again. Rejected while a task is running.
+    UserInputWithOverrides {
+        /// User input items, see `InputItem`
+        items: Vec<InputItem>,
+        overrides: TurnOverrides,
+    },
+
+    /// Drop a queued input, e.g. so the client can edit and resend it.
+    RemoveQueuedUserInput { id: u64 },
+
+    /// Inject a queued input into the running task at its next model
+    /// request instead of waiting for the task to finish, like
+    /// [`Op::UserInput`] sent while a task runs.
+    SteerQueuedUserInput { id: u64 },
+
+    /// Drop all queued input, leaving the running task alone.
+    ClearQueuedUserInput,
+
+    /// Drop the queued input of the given [`Op::QueueUserInput`]
+    /// submissions, leaving input that other submissions queued alone.
+    RemoveQueuedSubmissions { submission_ids: Vec<String> },
+
     /// Similar to [`Op::UserInput`], but contains additional context required
     /// for a turn of a [`crate::codex_conversation::CodexConversation`].
     UserTurn {
@@ -115,6 +149,11 @@ pub enum Op {
         #[serde(skip_serializing_if = "Option::is_none")]
         model: Option<String>,
 
+        /// Updated model provider, by its key in `model_providers`. The
+        /// conversation so far is re-encoded for the new provider.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
+
         /// Updated reasoning effort (honored only for reasoning-capable models).
         ///
         /// Use `Some(Some(_))` to set a specific effort, `Some(None)` to clear
@@ -143,6 +182,37 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve an edited version of a code patch, e.g. with some hunks left
+    /// out. Only `patch` is applied.
+    PatchApprovalEdited {
+        /// The id of the submission we are approving
+        id: String,
+        /// The patch to apply instead, in `apply_patch` format.
+        patch: String,
+        /// What the user left out or changed, reported to the model along
+        /// with the result of applying the patch.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+
+    /// Answer a [`EventMsg::McpSamplingApprovalRequest`].
+    McpSamplingApproval {
+        /// The id of the approval request event
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
+    /// Answer a [`EventMsg::SecretRequest`].
+    ProvideSecret {
+        /// The id of the submission that requested the secret
+        id: String,
+        /// Name of the requested secret.
+        name: String,
+        /// The secret, or `None` if the user declined to provide it.
+        value: Option<SecretValue>,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -163,6 +233,19 @@ pub enum Op {
     /// Reply is delivered via `EventMsg::McpListToolsResponse`.
     ListMcpTools,
 
+    /// Request an estimate of how much of the model's context window the
+    /// conversation uses. Reply is delivered via `EventMsg::ContextUsage`.
+    GetContextUsage,
+
+    /// Request the session's token usage and estimated cost so far. Reply is
+    /// delivered via `EventMsg::TokenCount`.
+    GetTokenUsage,
+
+    /// Turn logging of raw model requests and responses on or off for this
+    /// session. Reply is delivered via `EventMsg::BackgroundEvent`, naming the
+    /// log file when logging is turned on.
+    SetWireLogging { enabled: bool },
+
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
@@ -174,6 +257,50 @@ pub enum Op {
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
+    /// Attach a user comment to an earlier item in the conversation. The
+    /// annotation is recorded in the rollout and echoed back via
+    /// [`EventMsg::AnnotationAdded`].
+    AddAnnotation {
+        target: AnnotationTarget,
+        comment: String,
+    },
+
+    /// Continue a turn from a background response that is still running on
+    /// the provider, e.g. one started by a process that has since exited.
+    /// The response's output is handled as if this session had requested it.
+    ResumeBackgroundResponse { response_id: String },
+
+    /// Push the work of this session and open a pull request for it on the
+    /// code host configured under `[integrations]`. Answered with
+    /// [`EventMsg::PullRequestCreated`] or an error.
+    CreatePullRequest,
+
+    /// Roll back the file changes of the last turn that made any, as
+    /// recorded by the session's change journal. Files that changed since are
+    /// conflicts: unless `force` is set, nothing is rolled back when there are
+    /// any. Answered with [`EventMsg::TurnReverted`], or an error while a
+    /// task is running.
+    RevertTurn {
+        #[serde(default)]
+        force: bool,
+    },
+
+    /// Remember the conversation and the file changes so far under `name`,
+    /// replacing any checkpoint of that name. Answered with
+    /// [`EventMsg::CheckpointCreated`].
+    CreateCheckpoint { name: String },
+
+    /// Go back to the checkpoint `name`: the conversation is truncated to
+    /// where it was and the file changes made since are rolled back. Files
+    /// that changed outside the session since are conflicts, as with
+    /// [`Op::RevertTurn`]. Answered with [`EventMsg::CheckpointRestored`] or
+    /// an error, which it also is while a task is running.
+    RestoreCheckpoint {
+        name: String,
+        #[serde(default)]
+        force: bool,
+    },
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -243,6 +370,44 @@ pub enum SandboxPolicy {
         /// writable roots on UNIX. Defaults to `false`.
         #[serde(default)]
         exclude_slash_tmp: bool,
+
+        /// Hosts that remain reachable when `network_access` is `false`.
+        /// Entries are domain names (which also match their subdomains), IP
+        /// addresses, or CIDR ranges. Traffic is routed through a local proxy
+        /// that only connects to allowlisted destinations.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        network_allowlist: Vec<String>,
+
+        /// Paths under the writable roots that remain read-only, e.g.
+        /// `.git/` or `secrets/`, relative to each writable root. Entries
+        /// with `*` or `?` wildcards are patterns: without a `/` (e.g.
+        /// `*.pem`) they match a file or folder name at any depth, otherwise
+        /// a path relative to the root.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        deny: Vec<String>,
+    },
+
+    /// Commands run inside a throwaway container created from `image`. The
+    /// working directory and `writable_roots` are bind-mounted at the same
+    /// paths; nothing else on the host is visible to the command.
+    #[serde(rename = "container")]
+    Container {
+        /// Image the commands run in, e.g. `rust:1.90`.
+        image: String,
+
+        /// Container engine to use. When unset, `docker` is used if it is on
+        /// `PATH`, then `podman`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        runtime: Option<ContainerRuntime>,
+
+        /// Additional host folders (beyond cwd) to mount read-write.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        writable_roots: Vec<PathBuf>,
+
+        /// When set to `true`, the container is attached to the default
+        /// network. `false` by default.
+        #[serde(default)]
+        network_access: bool,
     },
 }
 
@@ -257,14 +422,19 @@ pub struct WritableRoot {
 
     /// Also absolute paths, by construction.
     pub read_only_subpaths: Vec<PathBuf>,
+
+    /// Wildcard patterns from the `deny` list. Patterns without a `/` match
+    /// the name of any file or folder beneath `root`; others match a path
+    /// relative to `root`. Everything beneath a match is read-only too.
+    pub read_only_patterns: Vec<String>,
 }
 
 impl WritableRoot {
     pub fn is_path_writable(&self, path: &Path) -> bool {
         // Check if the path is under the root.
-        if !path.starts_with(&self.root) {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
             return false;
-        }
+        };
 
         // Check if the path is under any of the read-only subpaths.
         for subpath in &self.read_only_subpaths {
@@ -273,10 +443,95 @@ impl WritableRoot {
             }
         }
 
-        true
+        !self.matches_read_only_pattern(relative)
+    }
+
+    /// Read-only subpaths that exist on disk, plus the files and folders
+    /// matching `read_only_patterns`. Sandboxes that can only protect
+    /// concrete paths use this; note that it walks the whole root when
+    /// patterns are configured.
+    pub fn existing_read_only_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .read_only_subpaths
+            .iter()
+            .filter(|subpath| subpath.exists())
+            .cloned()
+            .collect();
+        if !self.read_only_patterns.is_empty() {
+            self.collect_pattern_matches(&self.root, &mut paths);
+        }
+        paths
+    }
+
+    fn collect_pattern_matches(&self, dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if self.read_only_subpaths.contains(&path) {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            if self.matches_read_only_pattern(relative) {
+                out.push(path);
+            } else if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                // `file_type` does not follow symlinks, so links out of the
+                // root are not walked.
+                self.collect_pattern_matches(&path, out);
+            }
+        }
+    }
+
+    fn matches_read_only_pattern(&self, relative: &Path) -> bool {
+        if self.read_only_patterns.is_empty() {
+            return false;
+        }
+        let components: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        self.read_only_patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                (1..=components.len())
+                    .any(|len| wildcard_match(pattern, &components[..len].join("/")))
+            } else {
+                components
+                    .iter()
+                    .any(|component| wildcard_match(pattern, component))
+            }
+        })
     }
 }
 
+/// Matches `text` against `pattern`, where `*` matches any run of characters
+/// and `?` any single character, neither crossing a `/`.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // matches[j]: whether pattern[..i] matches text[..j].
+    let mut matches = vec![false; text.len() + 1];
+    matches[0] = true;
+    for &p in &pattern {
+        let mut next = vec![false; text.len() + 1];
+        if p == '*' {
+            next[0] = matches[0];
+        }
+        for j in 1..=text.len() {
+            let c = text[j - 1];
+            next[j] = match p {
+                '*' => matches[j] || (next[j - 1] && c != '/'),
+                '?' => matches[j - 1] && c != '/',
+                _ => matches[j - 1] && c == p,
+            };
+        }
+        matches = next;
+    }
+    matches[text.len()]
+}
+
 impl FromStr for SandboxPolicy {
     type Err = serde_json::Error;
 
@@ -300,6 +555,8 @@ impl SandboxPolicy {
             network_access: false,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            network_allowlist: vec![],
+            deny: vec![],
         }
     }
 
@@ -313,6 +570,7 @@ impl SandboxPolicy {
             SandboxPolicy::DangerFullAccess => true,
             SandboxPolicy::ReadOnly => false,
             SandboxPolicy::WorkspaceWrite { .. } => false,
+            SandboxPolicy::Container { .. } => false,
         }
     }
 
@@ -321,6 +579,21 @@ impl SandboxPolicy {
             SandboxPolicy::DangerFullAccess => true,
             SandboxPolicy::ReadOnly => false,
             SandboxPolicy::WorkspaceWrite { network_access, .. } => *network_access,
+            SandboxPolicy::Container { network_access, .. } => *network_access,
+        }
+    }
+
+    /// Returns the hosts that sandboxed commands may still reach through the
+    /// network proxy. Empty when the policy grants full network access or no
+    /// allowlist is configured.
+    pub fn network_allowlist(&self) -> &[String] {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: false,
+                network_allowlist,
+                ..
+            } => network_allowlist,
+            _ => &[],
         }
     }
 
@@ -336,6 +609,8 @@ impl SandboxPolicy {
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
                 network_access: _,
+                network_allowlist: _,
+                deny,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -367,26 +642,100 @@ impl SandboxPolicy {
                     roots.push(PathBuf::from(tmpdir));
                 }
 
-                // For each root, compute subpaths that should remain read-only.
-                roots
-                    .into_iter()
-                    .map(|writable_root| {
-                        let mut subpaths = Vec::new();
-                        let top_level_git = writable_root.join(".git");
-                        if top_level_git.is_dir() {
-                            subpaths.push(top_level_git);
-                        }
-                        WritableRoot {
-                            root: writable_root,
-                            read_only_subpaths: subpaths,
-                        }
-                    })
-                    .collect()
+                with_read_only_subpaths(roots, deny)
             }
+            SandboxPolicy::Container { writable_roots, .. } => {
+                let mut roots: Vec<PathBuf> = writable_roots.clone();
+                roots.push(cwd.to_path_buf());
+                with_read_only_subpaths(roots, &[])
+            }
+        }
+    }
+
+    /// Returns `true` when `path` (absolute and normalized) is read-only
+    /// because of the workspace-write `deny` list, as opposed to lying outside
+    /// the writable roots altogether.
+    pub fn is_path_denied(&self, path: &Path, cwd: &Path) -> bool {
+        let SandboxPolicy::WorkspaceWrite { deny, .. } = self else {
+            return false;
+        };
+        if deny.is_empty() {
+            return false;
         }
+        self.get_writable_roots_with_cwd(cwd)
+            .into_iter()
+            .filter(|writable_root| path.starts_with(&writable_root.root))
+            .any(|writable_root| {
+                let (read_only_subpaths, read_only_patterns) =
+                    resolve_deny_list(&writable_root.root, deny);
+                !WritableRoot {
+                    root: writable_root.root,
+                    read_only_subpaths,
+                    read_only_patterns,
+                }
+                .is_path_writable(path)
+            })
     }
 }
 
+/// For each root, compute subpaths that should remain read-only.
+fn with_read_only_subpaths(roots: Vec<PathBuf>, deny: &[String]) -> Vec<WritableRoot> {
+    roots
+        .into_iter()
+        .map(|writable_root| {
+            let mut subpaths = Vec::new();
+            let top_level_git = writable_root.join(".git");
+            if top_level_git.is_dir() {
+                subpaths.push(top_level_git);
+            }
+            let (denied_subpaths, patterns) = resolve_deny_list(&writable_root, deny);
+            for subpath in denied_subpaths {
+                if !subpaths.contains(&subpath) {
+                    subpaths.push(subpath);
+                }
+            }
+            WritableRoot {
+                root: writable_root,
+                read_only_subpaths: subpaths,
+                read_only_patterns: patterns,
+            }
+        })
+        .collect()
+}
+
+/// Splits `deny` into literal subpaths of `root`, which need not exist yet,
+/// and wildcard patterns.
+fn resolve_deny_list(root: &Path, deny: &[String]) -> (Vec<PathBuf>, Vec<String>) {
+    let mut subpaths = Vec::new();
+    let mut patterns = Vec::new();
+    for entry in deny {
+        let entry = entry.trim_start_matches("./").trim_end_matches('/');
+        if entry.is_empty() {
+            continue;
+        }
+        if entry.contains(['*', '?']) {
+            patterns.push(entry.to_string());
+        } else {
+            subpaths.push(root.join(entry));
+        }
+    }
+    (subpaths, patterns)
+}
+
+/// Model settings for a single turn, see [`Op::UserInputWithOverrides`].
+/// Unset fields keep the session's value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, TS)]
+pub struct TurnOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<ReasoningEffortConfig>,
+    /// Honored only by models that support it (GPT-5).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<Verbosity>,
+    /// Sampling temperature; many reasoning models reject it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+}
+
 /// User input
 #[non_exhaustive]
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -405,6 +754,12 @@ pub enum InputItem {
     LocalImage {
         path: std::path::PathBuf,
     },
+
+    /// Web page or raw file attached by the user. Its text is fetched and
+    /// added to the prompt before the turn starts.
+    Url {
+        url: String,
+    },
 }
 
 /// Event Queue Entry - events from agent
@@ -481,6 +836,14 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// The agent needs a credential to run a command. Answer with
+    /// [`Op::ProvideSecret`].
+    SecretRequest(SecretRequestEvent),
+
+    /// An MCP server wants a completion from the model. Answer with
+    /// [`Op::McpSamplingApproval`].
+    McpSamplingApprovalRequest(McpSamplingApprovalRequestEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that a model stream experienced an error or disconnect
@@ -505,6 +868,9 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// Estimated context window usage, by part of the prompt.
+    ContextUsage(ContextUsageEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
@@ -519,6 +885,55 @@ pub enum EventMsg {
 
     /// Exited review mode with an optional final result to apply.
     ExitedReviewMode(ExitedReviewModeEvent),
+
+    /// A user annotation was attached to an earlier conversation item.
+    AnnotationAdded(AnnotationAddedEvent),
+
+    /// Items were pruned from the model's context to stay under the token
+    /// limit. Recorded so that pruning decisions can be audited later.
+    ContextPruned(ContextPrunedEvent),
+
+    /// The stored ChatGPT credentials could not be refreshed and the user
+    /// must log in again before further requests can succeed.
+    LoginRequired(LoginRequiredEvent),
+
+    /// The model is generating in background mode. The turn can be picked up
+    /// again with [`Op::ResumeBackgroundResponse`] if this session goes away.
+    BackgroundResponseStarted(BackgroundResponseStartedEvent),
+
+    /// A background job started by the shell tool changed status.
+    BackgroundJob(BackgroundJobEvent),
+
+    /// Requests to the model provider moved to another of its base URLs,
+    /// either because the active one kept failing or because the primary one
+    /// is healthy again.
+    ProviderEndpointSwitched(ProviderEndpointSwitchedEvent),
+
+    /// The turn moved to the next model provider in the failover chain
+    /// because the previous one kept failing.
+    ProviderSwitched(ProviderSwitchedEvent),
+
+    /// An MCP server failed, is being restarted, or recovered.
+    McpServerStatus(McpServerStatusEvent),
+
+    /// The queue of user input waiting for the running task changed.
+    QueuedUserInput(QueuedUserInputEvent),
+
+    /// A pull request was opened in response to [`Op::CreatePullRequest`].
+    PullRequestCreated(PullRequestCreatedEvent),
+
+    /// The outcome of [`Op::RevertTurn`].
+    TurnReverted(TurnRevertedEvent),
+
+    /// A checkpoint was created in response to [`Op::CreateCheckpoint`].
+    CheckpointCreated(CheckpointCreatedEvent),
+
+    /// The outcome of [`Op::RestoreCheckpoint`].
+    CheckpointRestored(CheckpointRestoredEvent),
+
+    /// The model or provider of the conversation was changed with
+    /// [`Op::OverrideTurnContext`].
+    ModelChanged(ModelChangedEvent),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -531,11 +946,34 @@ pub struct ExitedReviewModeEvent {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ErrorEvent {
     pub message: String,
+    /// What failed, for clients that react differently to each kind of
+    /// failure, e.g. with the exit code of `codex exec`.
+    #[serde(default)]
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The model provider failed the request, or could not be reached.
+    Provider,
+    /// The model provider rejected the credentials, or there are none.
+    Auth,
+    /// The sandbox could not run a command.
+    Sandbox,
+    /// The task used up its budget of turns, time or cost.
+    BudgetExceeded,
+    #[default]
+    Other,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TaskCompleteEvent {
     pub last_agent_message: Option<String>,
+    /// Why the task could not be completed, when the model said so with the
+    /// `report_failure` tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -543,7 +981,7 @@ pub struct TaskStartedEvent {
     pub model_context_window: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default, TS)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Default, TS)]
 pub struct TokenUsage {
     pub input_tokens: u64,
     pub cached_input_tokens: u64,
@@ -557,6 +995,10 @@ pub struct TokenUsageInfo {
     pub total_token_usage: TokenUsage,
     pub last_token_usage: TokenUsage,
     pub model_context_window: Option<u64>,
+    /// Estimated cost of `total_token_usage` in US dollars, when prices are
+    /// configured for the models used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
 }
 
 impl TokenUsageInfo {
@@ -575,6 +1017,7 @@ impl TokenUsageInfo {
                 total_token_usage: TokenUsage::default(),
                 last_token_usage: TokenUsage::default(),
                 model_context_window,
+                estimated_cost_usd: None,
             },
         };
         if let Some(last) = last {
@@ -593,6 +1036,10 @@ impl TokenUsageInfo {
 pub struct TokenCountEvent {
     pub info: Option<TokenUsageInfo>,
     pub rate_limits: Option<RateLimitSnapshot>,
+    /// Locally counted input tokens of the next request, sent before the
+    /// provider reports actual usage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_prompt_tokens: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -867,6 +1314,206 @@ pub struct ConversationPathResponseEvent {
     pub path: PathBuf,
 }
 
+/// Identifies the conversation item an annotation refers to. Indices are
+/// 0-based and count only messages of the given kind, in the order they
+/// appear in the conversation (including messages replayed on resume).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnotationTarget {
+    UserMessage { index: usize },
+    AgentMessage { index: usize },
+}
+
+impl fmt::Display for AnnotationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnnotationTarget::UserMessage { index } => write!(f, "user message #{}", index + 1),
+            AnnotationTarget::AgentMessage { index } => write!(f, "agent message #{}", index + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct AnnotationAddedEvent {
+    pub target: AnnotationTarget,
+    pub comment: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct ContextPrunedEvent {
+    /// Name of the scoring strategy that ranked the items.
+    pub strategy: String,
+    /// Approximate number of tokens removed from the context.
+    pub approx_tokens_freed: u64,
+    /// Pruned items, in the order they were chosen.
+    pub items: Vec<PrunedContextItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct LoginRequiredEvent {
+    /// Why the refresh failed, e.g. the refresh token expired or was revoked.
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct BackgroundResponseStartedEvent {
+    pub response_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct PullRequestCreatedEvent {
+    /// Web URL of the pull request (GitHub) or merge request (GitLab).
+    pub url: String,
+    /// The branch that was pushed.
+    pub branch: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct TurnRevertedEvent {
+    /// The turn whose changes were, or would have been, rolled back; `None`
+    /// when no turn has changes left to roll back.
+    pub turn_id: Option<String>,
+    /// Files restored to how the turn found them.
+    pub reverted: Vec<PathBuf>,
+    /// Files that changed since the turn. Unless the revert was forced,
+    /// nothing was rolled back if there are any.
+    pub conflicts: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct CheckpointCreatedEvent {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct CheckpointRestoredEvent {
+    pub name: String,
+    /// Whether the conversation and files were restored; `false` when there
+    /// were conflicts and the restore was not forced.
+    pub restored: bool,
+    /// Files rolled back to how they were at the checkpoint.
+    pub reverted: Vec<PathBuf>,
+    /// Files that changed outside the session since the checkpoint.
+    pub conflicts: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct ModelChangedEvent {
+    pub model: String,
+    /// Key of the provider in `model_providers`.
+    pub provider: String,
+    pub previous_model: String,
+    pub previous_provider: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct ProviderEndpointSwitchedEvent {
+    /// Name of the model provider.
+    pub provider: String,
+    pub from: String,
+    pub to: String,
+    /// Why requests moved, e.g. the error the previous endpoint returned.
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct ProviderSwitchedEvent {
+    /// Name of the provider the turn moved away from.
+    pub from: String,
+    /// Name of the provider the turn continues on.
+    pub to: String,
+    /// The last error the previous provider returned.
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct BackgroundJobEvent {
+    pub job_id: String,
+    pub command: Vec<String>,
+    pub status: BackgroundJobStatus,
+    /// Exit code once the job has finished, if the command ran to completion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Wall time of the job once it has finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundJobStatus {
+    Running,
+    /// The command exited with status 0.
+    Completed,
+    /// The command exited with a non-zero status or could not be run.
+    Failed,
+    /// The command was killed when it reached its time box.
+    TimedOut,
+    /// The command was stopped with `job_kill` or when the turn that started
+    /// it ended.
+    Killed,
+}
+
+impl BackgroundJobStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, BackgroundJobStatus::Running)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct McpServerStatusEvent {
+    /// Name of the server in `mcp_servers`.
+    pub server: String,
+    pub status: McpServerStatus,
+    /// What happened, e.g. the error that made the server degraded.
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerStatus {
+    /// The server answers calls again.
+    Healthy,
+    /// The server crashed or stopped answering and is being restarted;
+    /// its tools fail until it is back.
+    Degraded,
+    /// The server could not be started; its tools fail for the rest of the
+    /// session.
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct QueuedUserInputEvent {
+    /// Inputs still waiting, oldest first.
+    pub queued: Vec<QueuedUserInput>,
+    /// The input that just left the queue to start a turn or to steer the
+    /// running one, if that is what changed the queue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submitted: Option<QueuedUserInput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct QueuedUserInput {
+    /// Identifies the input in [`Op::RemoveQueuedUserInput`] and
+    /// [`Op::SteerQueuedUserInput`].
+    pub id: u64,
+    /// The text items of the input, joined by newlines.
+    pub text: String,
+}
+
+/// A tool output whose content was replaced with a placeholder. The tool
+/// call itself stays in the history so call/output pairs remain intact.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct PrunedContextItem {
+    pub call_id: String,
+    /// Relevance score assigned by the strategy; lower is pruned first.
+    pub score: f64,
+    pub approx_tokens: u64,
+    /// Short excerpt of the pruned output.
+    pub preview: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ResumedHistory {
     pub conversation_id: ConversationId,
@@ -941,12 +1588,39 @@ pub enum RolloutItem {
     ResponseItem(ResponseItem),
     Compacted(CompactedItem),
     TurnContext(TurnContextItem),
+    FileChanges(FileChangesItem),
     EventMsg(EventMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
 pub struct CompactedItem {
     pub message: String,
+    /// Set when only older turns were summarized: how many of the latest
+    /// history items were kept as they are. The summarized items stay in the
+    /// rollout before this entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retained_items: Option<usize>,
+}
+
+/// The files a patch changed, as it found and left them, so that a resumed
+/// session can still roll the turn back.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+pub struct FileChangesItem {
+    pub turn_id: String,
+    pub files: Vec<FileVersionsItem>,
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+pub struct FileVersionsItem {
+    pub path: PathBuf,
+    /// `None` where the file did not exist.
+    #[serde_as(as = "Option<serde_with::base64::Base64>")]
+    #[ts(type = "string | null")]
+    pub before: Option<Vec<u8>>,
+    #[serde_as(as = "Option<serde_with::base64::Base64>")]
+    #[ts(type = "string | null")]
+    pub after: Option<Vec<u8>>,
 }
 
 impl From<CompactedItem> for ResponseItem {
@@ -999,6 +1673,32 @@ pub struct ReviewRequest {
     pub user_facing_hint: String,
 }
 
+/// What to review, for clients that let Codex write the review prompt
+/// instead of passing a [`ReviewRequest`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReviewTarget {
+    /// Staged, unstaged and untracked changes.
+    UncommittedChanges,
+    /// Changes staged for the next commit.
+    StagedChanges,
+    /// A git revision range such as `main..HEAD`, or a single commit.
+    Range { range: String },
+    /// A GitHub pull request, by URL.
+    PullRequest { url: String },
+}
+
+impl fmt::Display for ReviewTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReviewTarget::UncommittedChanges => write!(f, "current changes"),
+            ReviewTarget::StagedChanges => write!(f, "staged changes"),
+            ReviewTarget::Range { range } => write!(f, "changes in {range}"),
+            ReviewTarget::PullRequest { url } => write!(f, "pull request {url}"),
+        }
+    }
+}
+
 /// Structured review result produced by a child review session.
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
 pub struct ReviewOutputEvent {
@@ -1072,6 +1772,9 @@ pub struct ExecCommandEndEvent {
     pub duration: Duration,
     /// Formatted output from the command, as seen by the model.
     pub formatted_output: String,
+    /// Whether the sandbox prevented the command from doing its work.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sandbox_denied: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
@@ -1107,6 +1810,55 @@ pub struct ExecApprovalRequestEvent {
     pub reason: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpSamplingApprovalRequestEvent {
+    /// Name of the MCP server asking for the completion.
+    pub server: String,
+    /// The messages the model would be sent, as text.
+    pub prompt: String,
+    /// System prompt the server asked for, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Output tokens the server asked for.
+    pub max_tokens: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SecretRequestEvent {
+    /// Identifier for the exec call that needs the secret.
+    pub call_id: String,
+    /// Name of the environment variable the secret is passed in, e.g.
+    /// `NPM_TOKEN`.
+    pub name: String,
+    /// Why the agent needs the secret.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A secret supplied by the user. It is only ever placed in the environment
+/// of commands and is redacted from `Debug` output so it does not end up in
+/// logs.
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(transparent)]
+#[ts(type = "string")]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretValue(<redacted>)")
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ApplyPatchApprovalRequestEvent {
     /// Responses API call id for the associated patch apply call, if available.
@@ -1118,6 +1870,55 @@ pub struct ApplyPatchApprovalRequestEvent {
     /// When set, the agent is asking the user to allow writes under this root for the remainder of the session.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grant_root: Option<PathBuf>,
+    /// Why the patch looks risky, if it does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<PatchRisk>,
+}
+
+/// Coarse risk of a patch, used to decide how closely it should be reviewed.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, Display, TS,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum PatchRiskLevel {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+/// Risk level of a patch together with the reasons it was assigned.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct PatchRisk {
+    pub level: PatchRiskLevel,
+    pub reasons: Vec<String>,
+}
+
+impl PatchRisk {
+    /// Record `reason`, raising the level to `level` if it is higher.
+    pub fn add(&mut self, level: PatchRiskLevel, reason: String) {
+        self.level = self.level.max(level);
+        if !self.reasons.contains(&reason) {
+            self.reasons.push(reason);
+        }
+    }
+
+    pub fn merge(&mut self, other: PatchRisk) {
+        for reason in other.reasons {
+            self.add(other.level, reason);
+        }
+        self.level = self.level.max(other.level);
+    }
+
+    /// `None` if nothing risky was found.
+    pub fn into_option(self) -> Option<PatchRisk> {
+        if self.reasons.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -1155,6 +1956,10 @@ pub struct PatchApplyEndEvent {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TurnDiffEvent {
     pub unified_diff: String,
+    /// Combined risk of the patches applied so far this turn, if any of them
+    /// looked risky.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<PatchRisk>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -1179,6 +1984,22 @@ pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
 }
 
+/// Response payload for `Op::GetContextUsage`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct ContextUsageEvent {
+    /// Estimated input tokens of the next request.
+    pub total_tokens: u64,
+    pub model_context_window: Option<u64>,
+    /// Non-empty parts of the prompt, in the order they are sent.
+    pub parts: Vec<ContextUsagePart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct ContextUsagePart {
+    pub name: String,
+    pub tokens: u64,
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
 pub struct SessionConfiguredEvent {
     /// Name left as session_id instead of conversation_id for backwards compatibility.
@@ -1203,6 +2024,26 @@ pub struct SessionConfiguredEvent {
     pub initial_messages: Option<Vec<EventMsg>>,
 
     pub rollout_path: PathBuf,
+
+    /// OS sandbox that enforces the sandbox policy on this machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox_capability: Option<SandboxCapability>,
+}
+
+/// OS-level mechanism available to enforce the sandbox policy.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SandboxCapability {
+    /// macOS Seatbelt (`sandbox-exec`).
+    Seatbelt,
+    /// Linux Landlock and seccomp.
+    Landlock,
+    /// Windows write-restricted token. Network access is not restricted.
+    WindowsRestrictedToken,
+    /// Docker or Podman container (`sandbox_mode = "container"`).
+    Container { runtime: ContainerRuntime },
+    /// Commands cannot be sandboxed, so untrusted commands need approval.
+    Unavailable { reason: String },
 }
 
 /// User's decision in response to an ExecApprovalRequest.
@@ -1270,6 +2111,71 @@ mod tests {
     use serde_json::json;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn deny_list_makes_paths_read_only() {
+        let cwd = PathBuf::from("/repo");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            network_allowlist: vec![],
+            deny: vec![
+                "secrets/".to_string(),
+                "*.pem".to_string(),
+                "config/*.env".to_string(),
+            ],
+        };
+        let roots = policy.get_writable_roots_with_cwd(&cwd);
+        let workspace = roots
+            .iter()
+            .find(|root| root.root == cwd)
+            .expect("cwd is a writable root");
+
+        for path in [
+            "/repo/secrets",
+            "/repo/secrets/prod/token",
+            "/repo/certs/server.pem",
+            "/repo/config/prod.env/nested",
+        ] {
+            assert!(!workspace.is_path_writable(Path::new(path)), "{path}");
+            assert!(policy.is_path_denied(Path::new(path), &cwd), "{path}");
+        }
+        for path in [
+            "/repo/src/secrets.rs",
+            "/repo/server.pem.md",
+            "/repo/config/nested/prod.env",
+        ] {
+            assert!(workspace.is_path_writable(Path::new(path)), "{path}");
+            assert!(!policy.is_path_denied(Path::new(path), &cwd), "{path}");
+        }
+    }
+
+    #[test]
+    fn existing_read_only_paths_finds_pattern_matches() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        std::fs::create_dir_all(root.path().join("certs/old"))?;
+        std::fs::write(root.path().join("certs/server.pem"), "")?;
+        std::fs::write(root.path().join("certs/old/ca.pem"), "")?;
+        std::fs::write(root.path().join("certs/README.md"), "")?;
+        let writable_root = WritableRoot {
+            root: root.path().to_path_buf(),
+            read_only_subpaths: vec![root.path().join("secrets")],
+            read_only_patterns: vec!["*.pem".to_string()],
+        };
+
+        let mut paths = writable_root.existing_read_only_paths();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                root.path().join("certs/old/ca.pem"),
+                root.path().join("certs/server.pem"),
+            ]
+        );
+        Ok(())
+    }
+
     /// Serialize Event to verify that its JSON representation has the expected
     /// amount of nesting.
     #[test]
@@ -1286,6 +2192,7 @@ mod tests {
                 history_entry_count: 0,
                 initial_messages: None,
                 rollout_path: rollout_file.path().to_path_buf(),
+                sandbox_capability: None,
             }),
         };
 
@@ -1322,4 +2229,24 @@ mod tests {
         assert_eq!(deserialized, event);
         Ok(())
     }
+
+    #[test]
+    fn secret_value_is_redacted_from_debug_output() -> Result<()> {
+        let op = Op::ProvideSecret {
+            id: "1".to_string(),
+            name: "NPM_TOKEN".to_string(),
+            value: Some(SecretValue::new("npm_abc123".to_string())),
+        };
+        assert!(!format!("{op:?}").contains("npm_abc123"));
+        assert_eq!(
+            json!({
+                "type": "provide_secret",
+                "id": "1",
+                "name": "NPM_TOKEN",
+                "value": "npm_abc123",
+            }),
+            serde_json::to_value(&op)?
+        );
+        Ok(())
+    }
 }
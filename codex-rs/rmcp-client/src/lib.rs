@@ -2,4 +2,6 @@ mod logging_client_handler;
 mod rmcp_client;
 mod utils;
 
+pub use rmcp_client::BearerTokenSource;
 pub use rmcp_client::RmcpClient;
+pub use rmcp_client::SamplingHandler;
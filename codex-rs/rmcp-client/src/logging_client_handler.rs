@@ -1,10 +1,19 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use mcp_types::Root;
 use rmcp::ClientHandler;
 use rmcp::RoleClient;
 use rmcp::model::CancelledNotificationParam;
 use rmcp::model::ClientInfo;
 use rmcp::model::CreateElicitationRequestParam;
 use rmcp::model::CreateElicitationResult;
+use rmcp::model::CreateMessageRequestParam;
+use rmcp::model::CreateMessageResult;
 use rmcp::model::ElicitationAction;
+use rmcp::model::ErrorCode;
+use rmcp::model::ListRootsResult;
 use rmcp::model::LoggingLevel;
 use rmcp::model::LoggingMessageNotificationParam;
 use rmcp::model::ProgressNotificationParam;
@@ -16,14 +25,44 @@ use tracing::error;
 use tracing::info;
 use tracing::warn;
 
-#[derive(Debug, Clone)]
+use crate::rmcp_client::SamplingHandler;
+use crate::utils::convert_to_mcp;
+use crate::utils::convert_to_rmcp;
+
+/// JSON-RPC error code for sampling requests that were refused, e.g. by the
+/// user.
+const SAMPLING_REJECTED_ERROR_CODE: i32 = -1;
+
+#[derive(Clone)]
 pub(crate) struct LoggingClientHandler {
     client_info: ClientInfo,
+    /// Returned when the server asks for `roots/list`.
+    roots: Arc<RwLock<Vec<Root>>>,
+    /// Answers `sampling/createMessage`; without one such requests fail.
+    sampling: Option<SamplingHandler>,
 }
 
 impl LoggingClientHandler {
-    pub(crate) fn new(client_info: ClientInfo) -> Self {
-        Self { client_info }
+    pub(crate) fn new(
+        client_info: ClientInfo,
+        roots: Arc<RwLock<Vec<Root>>>,
+        sampling: Option<SamplingHandler>,
+    ) -> Self {
+        Self {
+            client_info,
+            roots,
+            sampling,
+        }
+    }
+}
+
+impl fmt::Debug for LoggingClientHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggingClientHandler")
+            .field("client_info", &self.client_info)
+            .field("roots", &self.roots)
+            .field("sampling", &self.sampling.is_some())
+            .finish()
     }
 }
 
@@ -44,6 +83,40 @@ impl ClientHandler for LoggingClientHandler {
         })
     }
 
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, rmcp::ErrorData> {
+        let roots = self
+            .roots
+            .read()
+            .map(|roots| roots.clone())
+            .unwrap_or_default();
+        convert_to_rmcp(mcp_types::ListRootsResult { roots })
+            .map_err(|err| rmcp::ErrorData::internal_error(err.to_string(), None))
+    }
+
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, rmcp::ErrorData> {
+        let Some(handler) = &self.sampling else {
+            return Err(rmcp::ErrorData::new(
+                ErrorCode::METHOD_NOT_FOUND,
+                "sampling is not supported",
+                None,
+            ));
+        };
+        let params = convert_to_mcp(params)
+            .map_err(|err| rmcp::ErrorData::invalid_params(err.to_string(), None))?;
+        let result: mcp_types::CreateMessageResult = handler(params).await.map_err(|message| {
+            rmcp::ErrorData::new(ErrorCode(SAMPLING_REJECTED_ERROR_CODE), message, None)
+        })?;
+        convert_to_rmcp(result)
+            .map_err(|err| rmcp::ErrorData::internal_error(err.to_string(), None))
+    }
+
     async fn on_cancelled(
         &self,
         params: CancelledNotificationParam,
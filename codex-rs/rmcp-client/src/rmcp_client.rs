@@ -3,25 +3,35 @@ use std::ffi::OsString;
 use std::io;
 use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::RwLock;
 use std::time::Duration;
 
 use anyhow::Result;
 use anyhow::anyhow;
-use futures::FutureExt;
+use futures::future::BoxFuture;
 use mcp_types::CallToolRequestParams;
 use mcp_types::CallToolResult;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializeResult;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
+use mcp_types::Root;
+use reqwest::header::AUTHORIZATION;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
 use rmcp::model::CallToolRequestParam;
 use rmcp::model::InitializeRequestParam;
 use rmcp::model::PaginatedRequestParam;
 use rmcp::service::RoleClient;
 use rmcp::service::RunningService;
 use rmcp::service::{self};
+use rmcp::transport::SseClientTransport;
 use rmcp::transport::StreamableHttpClientTransport;
 use rmcp::transport::child_process::TokioChildProcess;
+use rmcp::transport::sse_client::SseClientConfig;
 use rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig;
 use tokio::io::AsyncBufReadExt;
 use tokio::io::BufReader;
@@ -38,17 +48,62 @@ use crate::utils::convert_to_rmcp;
 use crate::utils::create_env_for_mcp_server;
 use crate::utils::run_with_timeout;
 
+/// Produces the bearer token for a new HTTP connection, e.g. an OAuth access
+/// token that is refreshed when it is about to expire. Asked before every
+/// request; when the token changes the client reconnects with the new one.
+pub type BearerTokenSource = Arc<dyn Fn() -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+/// Answers `sampling/createMessage` requests from the server, or explains
+/// why a request was refused.
+pub type SamplingHandler = Arc<
+    dyn Fn(CreateMessageRequestParams) -> BoxFuture<'static, Result<CreateMessageResult, String>>
+        + Send
+        + Sync,
+>;
+
 enum PendingTransport {
     ChildProcess(TokioChildProcess),
-    StreamableHttp(StreamableHttpClientTransport<reqwest::Client>),
+    /// Connect with [`RmcpClient::http`].
+    Http,
 }
 
+#[derive(Clone, Copy)]
+enum HttpTransport {
+    StreamableHttp,
+    Sse,
+}
+
+/// How to open a connection to a server reached over HTTP. Unlike a child
+/// process this can be repeated, which is how dropped connections recover.
+struct HttpConnection {
+    transport: HttpTransport,
+    url: String,
+    headers: HeaderMap,
+    bearer_token: Option<String>,
+    token_source: Option<BearerTokenSource>,
+}
+
+impl HttpConnection {
+    async fn current_token(&self) -> Option<String> {
+        match &self.token_source {
+            Some(source) => source().await,
+            None => self.bearer_token.clone(),
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
 enum ClientState {
     Connecting {
         transport: Option<PendingTransport>,
     },
     Ready {
         service: Arc<RunningService<RoleClient, LoggingClientHandler>>,
+        /// Kept to repeat the handshake when reconnecting.
+        params: InitializeRequestParams,
+        timeout: Option<Duration>,
+        /// Bearer token the HTTP connection was opened with.
+        token: Option<String>,
     },
 }
 
@@ -56,6 +111,14 @@ enum ClientState {
 /// https://github.com/modelcontextprotocol/rust-sdk
 pub struct RmcpClient {
     state: Mutex<ClientState>,
+    /// Set for HTTP servers, which are reconnected to when the connection
+    /// has closed, e.g. because the server restarted and dropped the session,
+    /// or when the bearer token has changed.
+    http: Option<HttpConnection>,
+    /// Directories the server may operate on, returned for `roots/list`.
+    roots: Arc<RwLock<Vec<Root>>>,
+    /// Answers `sampling/createMessage` requests.
+    sampling: Option<SamplingHandler>,
 }
 
 impl RmcpClient {
@@ -100,24 +163,96 @@ impl RmcpClient {
             state: Mutex::new(ClientState::Connecting {
                 transport: Some(PendingTransport::ChildProcess(transport)),
             }),
+            http: None,
+            roots: Arc::default(),
+            sampling: None,
         })
     }
 
-    pub fn new_streamable_http_client(url: String, bearer_token: Option<String>) -> Result<Self> {
-        let mut config = StreamableHttpClientTransportConfig::with_uri(url);
-        if let Some(token) = bearer_token {
-            config = config.auth_header(format!("Bearer {token}"));
-        }
+    /// Client for a server using the streamable HTTP transport. The session
+    /// id the server assigns is sent with every later request.
+    /// https://modelcontextprotocol.io/specification/2025-06-18/basic/transports#streamable-http
+    pub fn new_streamable_http_client(
+        url: String,
+        bearer_token: Option<String>,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<Self> {
+        Self::new_http_client(HttpTransport::StreamableHttp, url, bearer_token, headers)
+    }
 
-        let transport = StreamableHttpClientTransport::from_config(config);
+    /// Client for a server using the older HTTP+SSE transport.
+    pub fn new_sse_client(
+        url: String,
+        bearer_token: Option<String>,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<Self> {
+        Self::new_http_client(HttpTransport::Sse, url, bearer_token, headers)
+    }
 
+    fn new_http_client(
+        transport: HttpTransport,
+        url: String,
+        bearer_token: Option<String>,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<Self> {
         Ok(Self {
             state: Mutex::new(ClientState::Connecting {
-                transport: Some(PendingTransport::StreamableHttp(transport)),
+                transport: Some(PendingTransport::Http),
             }),
+            http: Some(HttpConnection {
+                transport,
+                url,
+                headers: header_map(headers)?,
+                bearer_token,
+                token_source: None,
+            }),
+            roots: Arc::default(),
+            sampling: None,
         })
     }
 
+    /// Takes the bearer token of an HTTP client from `source` instead of the
+    /// fixed one it was created with. Has no effect on stdio clients.
+    pub fn with_bearer_token_source(mut self, source: BearerTokenSource) -> Self {
+        if let Some(http) = &mut self.http {
+            http.token_source = Some(source);
+        }
+        self
+    }
+
+    /// Sets the roots advertised to the server before [`initialize`](Self::initialize).
+    pub fn with_roots(self, roots: Vec<Root>) -> Self {
+        if let Ok(mut current) = self.roots.write() {
+            *current = roots;
+        }
+        self
+    }
+
+    /// Lets the server request completions with `sampling/createMessage`.
+    /// Set before [`initialize`](Self::initialize) and advertise the
+    /// `sampling` capability there.
+    pub fn with_sampling_handler(mut self, handler: SamplingHandler) -> Self {
+        self.sampling = Some(handler);
+        self
+    }
+
+    /// Replaces the roots and tells the server they changed.
+    pub async fn set_roots(&self, roots: Vec<Root>) -> Result<()> {
+        if let Ok(mut current) = self.roots.write() {
+            *current = roots;
+        }
+        let service = match &*self.state.lock().await {
+            ClientState::Ready { service, .. } => Arc::clone(service),
+            // The server asks for the roots after the handshake.
+            ClientState::Connecting { .. } => return Ok(()),
+        };
+        service
+            .peer()
+            .notify_roots_list_changed()
+            .await
+            .map_err(|err| anyhow!("failed to send roots/list_changed: {err}"))
+    }
+
     /// Perform the initialization handshake with the MCP server.
     /// https://modelcontextprotocol.io/specification/2025-06-18/basic/lifecycle#initialization
     pub async fn initialize(
@@ -137,26 +272,7 @@ impl RmcpClient {
             }
         };
 
-        let client_info = convert_to_rmcp::<_, InitializeRequestParam>(params.clone())?;
-        let client_handler = LoggingClientHandler::new(client_info);
-        let service_future = match transport {
-            PendingTransport::ChildProcess(transport) => {
-                service::serve_client(client_handler.clone(), transport).boxed()
-            }
-            PendingTransport::StreamableHttp(transport) => {
-                service::serve_client(client_handler, transport).boxed()
-            }
-        };
-
-        let service = match timeout {
-            Some(duration) => time::timeout(duration, service_future)
-                .await
-                .map_err(|_| anyhow!("timed out handshaking with MCP server after {duration:?}"))?
-                .map_err(|err| anyhow!("handshaking with MCP server failed: {err}"))?,
-            None => service_future
-                .await
-                .map_err(|err| anyhow!("handshaking with MCP server failed: {err}"))?,
-        };
+        let (service, token) = self.connect(transport, &params, timeout).await?;
 
         let initialize_result_rmcp = service
             .peer()
@@ -168,6 +284,9 @@ impl RmcpClient {
             let mut guard = self.state.lock().await;
             *guard = ClientState::Ready {
                 service: Arc::new(service),
+                params,
+                timeout,
+                token,
             };
         }
 
@@ -203,11 +322,136 @@ impl RmcpClient {
         convert_call_tool_result(rmcp_result)
     }
 
+    /// Whether the connection to the server is closed, e.g. because the
+    /// server process exited. HTTP connections are reopened on the next call.
+    pub async fn is_closed(&self) -> bool {
+        match &*self.state.lock().await {
+            ClientState::Ready { service, .. } => service.is_transport_closed(),
+            ClientState::Connecting { .. } => false,
+        }
+    }
+
     async fn service(&self) -> Result<Arc<RunningService<RoleClient, LoggingClientHandler>>> {
-        let guard = self.state.lock().await;
-        match &*guard {
-            ClientState::Ready { service } => Ok(Arc::clone(service)),
-            ClientState::Connecting { .. } => Err(anyhow!("MCP client not initialized")),
+        let mut guard = self.state.lock().await;
+        let ClientState::Ready {
+            service,
+            params,
+            timeout,
+            token,
+        } = &mut *guard
+        else {
+            return Err(anyhow!("MCP client not initialized"));
+        };
+        let Some(http) = &self.http else {
+            return Ok(Arc::clone(service));
+        };
+        let closed = service.is_transport_closed();
+        let token_changed = http.token_source.is_some() && http.current_token().await != *token;
+        if closed || token_changed {
+            // The lock is held while reconnecting so concurrent calls wait
+            // for one new connection instead of each opening their own.
+            if closed {
+                info!("MCP connection to {} closed; reconnecting", http.url);
+            } else {
+                info!("bearer token for {} changed; reconnecting", http.url);
+            }
+            let (new_service, new_token) = self
+                .connect(PendingTransport::Http, params, *timeout)
+                .await?;
+            *service = Arc::new(new_service);
+            *token = new_token;
+        }
+        Ok(Arc::clone(service))
+    }
+
+    /// Connects `transport` and performs the initialization handshake.
+    /// Returns the bearer token used for HTTP servers.
+    async fn connect(
+        &self,
+        transport: PendingTransport,
+        params: &InitializeRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<(
+        RunningService<RoleClient, LoggingClientHandler>,
+        Option<String>,
+    )> {
+        let client_info = convert_to_rmcp::<_, InitializeRequestParam>(params.clone())?;
+        let client_handler =
+            LoggingClientHandler::new(client_info, Arc::clone(&self.roots), self.sampling.clone());
+        let http = self.http.as_ref();
+        let service_future = async move {
+            match (transport, http) {
+                (PendingTransport::ChildProcess(transport), _) => {
+                    let service = service::serve_client(client_handler, transport)
+                        .await
+                        .map_err(handshake_failed)?;
+                    Ok((service, None))
+                }
+                (PendingTransport::Http, Some(http)) => {
+                    let token = http.current_token().await;
+                    let mut headers = http.headers.clone();
+                    if let Some(token) = &token {
+                        let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+                            .map_err(|err| anyhow!("invalid bearer token: {err}"))?;
+                        value.set_sensitive(true);
+                        headers.insert(AUTHORIZATION, value);
+                    }
+                    let client = reqwest::Client::builder()
+                        .default_headers(headers)
+                        .build()
+                        .map_err(|err| anyhow!("failed to build HTTP client: {err}"))?;
+                    let service = match http.transport {
+                        HttpTransport::StreamableHttp => {
+                            let transport = StreamableHttpClientTransport::with_client(
+                                client,
+                                StreamableHttpClientTransportConfig::with_uri(http.url.clone()),
+                            );
+                            service::serve_client(client_handler, transport)
+                                .await
+                                .map_err(handshake_failed)?
+                        }
+                        HttpTransport::Sse => {
+                            let config = SseClientConfig {
+                                sse_endpoint: http.url.clone().into(),
+                                ..Default::default()
+                            };
+                            let transport = SseClientTransport::start_with_client(client, config)
+                                .await
+                                .map_err(|err| anyhow!("connecting to MCP server failed: {err}"))?;
+                            service::serve_client(client_handler, transport)
+                                .await
+                                .map_err(handshake_failed)?
+                        }
+                    };
+                    Ok((service, token))
+                }
+                (PendingTransport::Http, None) => Err(anyhow!("no HTTP connection configured")),
+            }
+        };
+
+        match timeout {
+            Some(duration) => time::timeout(duration, service_future)
+                .await
+                .map_err(|_| anyhow!("timed out handshaking with MCP server after {duration:?}"))?,
+            None => service_future.await,
         }
     }
 }
+
+fn handshake_failed(err: impl std::fmt::Display) -> anyhow::Error {
+    anyhow!("handshaking with MCP server failed: {err}")
+}
+
+/// Extra headers sent with every request to an HTTP server.
+fn header_map(headers: Option<HashMap<String, String>>) -> Result<HeaderMap> {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers.unwrap_or_default() {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|err| anyhow!("invalid header name {name:?}: {err}"))?;
+        let mut header_value = HeaderValue::from_str(&value)
+            .map_err(|err| anyhow!("invalid value for header {name}: {err}"))?;
+        header_value.set_sensitive(true);
+        header_map.insert(header_name, header_value);
+    }
+    Ok(header_map)
+}
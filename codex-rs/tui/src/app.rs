@@ -1,11 +1,24 @@
 use crate::app_backtrack::BacktrackState;
+use crate::app_backtrack::user_messages;
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::app_tabs::Tabs;
 use crate::chatwidget::ChatWidget;
+use crate::external_editor;
 use crate::file_search::FileSearchManager;
 use crate::history_cell::HistoryCell;
+use crate::keymap;
+use crate::keymap::KeyAction;
+use crate::keymap::Keymap;
+use crate::mouse;
+use crate::mouse::FileReference;
 use crate::pager_overlay::Overlay;
+use crate::resume_picker::PickerOrigin;
 use crate::resume_picker::ResumeSelection;
+use crate::resume_picker::run_resume_picker;
+use crate::session_locked::run_session_locked_view;
+use crate::theme;
+use crate::theme::Theme;
 use crate::tui;
 use crate::tui::TuiEvent;
 use codex_ansi_escape::ansi_escape_line;
@@ -13,15 +26,23 @@ use codex_core::AuthManager;
 use codex_core::ConversationManager;
 use codex_core::config::Config;
 use codex_core::config::persist_model_selection;
+use codex_core::memory::memory_path;
 use codex_core::model_family::find_family_for_model;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_core::session_lock_status;
 use codex_protocol::mcp_protocol::ConversationId;
 use color_eyre::eyre::Result;
 use color_eyre::eyre::WrapErr;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
+use crossterm::event::MouseButton;
+use crossterm::event::MouseEvent;
+use crossterm::event::MouseEventKind;
+use ratatui::layout::Rect;
 use ratatui::style::Stylize;
 use ratatui::text::Line;
 use std::path::PathBuf;
@@ -57,7 +78,7 @@ pub(crate) struct App {
     // Pager overlay state (Transcript or Static like Diff)
     pub(crate) overlay: Option<Overlay>,
     pub(crate) deferred_history_lines: Vec<Line<'static>>,
-    has_emitted_history_lines: bool,
+    pub(crate) has_emitted_history_lines: bool,
 
     pub(crate) enhanced_keys_supported: bool,
 
@@ -66,6 +87,9 @@ pub(crate) struct App {
 
     // Esc-backtracking state grouped
     pub(crate) backtrack: crate::app_backtrack::BacktrackState,
+
+    /// Open tabs. The front tab's state is `chat_widget` and `transcript_cells`.
+    pub(crate) tabs: Tabs,
 }
 
 impl App {
@@ -86,21 +110,10 @@ impl App {
 
         let enhanced_keys_supported = tui.enhanced_keys_supported();
 
-        let chat_widget = match resume_selection {
-            ResumeSelection::StartFresh | ResumeSelection::Exit => {
-                let init = crate::chatwidget::ChatWidgetInit {
-                    config: config.clone(),
-                    frame_requester: tui.frame_requester(),
-                    app_event_tx: app_event_tx.clone(),
-                    initial_prompt: initial_prompt.clone(),
-                    initial_images: initial_images.clone(),
-                    enhanced_keys_supported,
-                    auth_manager: auth_manager.clone(),
-                };
-                ChatWidget::new(init, conversation_manager.clone())
-            }
-            ResumeSelection::Resume(path) => {
-                let resumed = conversation_manager
+        let resumed = match resume_selection {
+            ResumeSelection::StartFresh | ResumeSelection::Exit => None,
+            ResumeSelection::Resume(path) => Some(
+                conversation_manager
                     .resume_conversation_from_rollout(
                         config.clone(),
                         path.clone(),
@@ -109,23 +122,49 @@ impl App {
                     .await
                     .wrap_err_with(|| {
                         format!("Failed to resume session from {}", path.display())
-                    })?;
-                let init = crate::chatwidget::ChatWidgetInit {
-                    config: config.clone(),
-                    frame_requester: tui.frame_requester(),
-                    app_event_tx: app_event_tx.clone(),
-                    initial_prompt: initial_prompt.clone(),
-                    initial_images: initial_images.clone(),
-                    enhanced_keys_supported,
-                    auth_manager: auth_manager.clone(),
-                };
-                ChatWidget::new_from_existing(
-                    init,
-                    resumed.conversation,
-                    resumed.session_configured,
-                )
+                    })?,
+            ),
+            ResumeSelection::Fork(path) => Some(
+                conversation_manager
+                    .fork_conversation_from_rollout(config.clone(), path.clone())
+                    .await
+                    .wrap_err_with(|| format!("Failed to fork session from {}", path.display()))?,
+            ),
+        };
+        let keymap_error = match Keymap::from_config(&config.tui_keys) {
+            Ok(keymap) => {
+                keymap::init(keymap);
+                None
             }
+            Err(err) => Some(err),
         };
+        let tabs = Tabs::default();
+        let init = crate::chatwidget::ChatWidgetInit {
+            config: config.clone(),
+            frame_requester: tui.frame_requester(),
+            app_event_tx: app_event_tx.for_tab(tabs.active_id()),
+            initial_prompt: initial_prompt.clone(),
+            initial_images: initial_images.clone(),
+            enhanced_keys_supported,
+            auth_manager: auth_manager.clone(),
+        };
+        let mut chat_widget = match resumed {
+            None => ChatWidget::new(init, conversation_manager.clone()),
+            Some(resumed) => ChatWidget::new_from_existing(
+                init,
+                resumed.conversation,
+                resumed.session_configured,
+            ),
+        };
+        match Theme::from_config(&config.tui_theme) {
+            Ok(theme) => theme::set_current(theme),
+            Err(err) => chat_widget.add_error_message(format!("{err}; using the dark theme")),
+        }
+        if let Some(err) = keymap_error {
+            chat_widget.add_error_message(format!(
+                "Invalid [tui.keys]: {err}; using the default key bindings"
+            ));
+        }
 
         let file_search = FileSearchManager::new(config.cwd.clone(), app_event_tx.clone());
 
@@ -144,6 +183,7 @@ impl App {
             has_emitted_history_lines: false,
             commit_anim_running: Arc::new(AtomicBool::new(false)),
             backtrack: BacktrackState::default(),
+            tabs,
         };
 
         let tui_events = tui.event_stream();
@@ -153,7 +193,7 @@ impl App {
 
         while select! {
             Some(event) = app_event_rx.recv() => {
-                app.handle_event(tui, event).await?
+                app.route_event(tui, event).await?
             }
             Some(event) = tui_events.next() => {
                 app.handle_tui_event(tui, event).await?
@@ -171,7 +211,9 @@ impl App {
         tui: &mut tui::Tui,
         event: TuiEvent,
     ) -> Result<bool> {
-        if self.overlay.is_some() {
+        if let TuiEvent::Mouse(mouse_event) = event {
+            self.handle_mouse_event(tui, mouse_event).await?;
+        } else if self.overlay.is_some() {
             let _ = self.handle_backtrack_overlay_event(tui, event).await?;
         } else {
             match event {
@@ -188,34 +230,101 @@ impl App {
                 }
                 TuiEvent::Draw => {
                     self.chat_widget.maybe_post_pending_notification(tui);
+                    self.chat_widget.handle_stream_coalesce_tick();
                     if self
                         .chat_widget
                         .handle_paste_burst_tick(tui.frame_requester())
                     {
                         return Ok(true);
                     }
-                    tui.draw(
-                        self.chat_widget.desired_height(tui.terminal.size()?.width),
-                        |frame| {
-                            frame.render_widget_ref(&self.chat_widget, frame.area());
-                            if let Some((x, y)) = self.chat_widget.cursor_pos(frame.area()) {
-                                frame.set_cursor_position((x, y));
-                            }
-                        },
-                    )?;
+                    let tab_bar = self.tab_bar();
+                    let height = self.chat_widget.desired_height(tui.terminal.size()?.width)
+                        + u16::from(tab_bar.is_some());
+                    tui.draw(height, |frame| {
+                        let mut area = frame.area();
+                        if let Some(tab_bar) = tab_bar {
+                            frame.render_widget_ref(tab_bar, Rect { height: 1, ..area });
+                            area.y += 1;
+                            area.height = area.height.saturating_sub(1);
+                        }
+                        frame.render_widget_ref(&self.chat_widget, area);
+                        if let Some((x, y)) = self.chat_widget.cursor_pos(area) {
+                            frame.set_cursor_position((x, y));
+                        }
+                    })?;
                 }
+                TuiEvent::Mouse(_) => {}
             }
         }
         Ok(true)
     }
 
-    async fn handle_event(&mut self, tui: &mut tui::Tui, event: AppEvent) -> Result<bool> {
+    /// Mouse events, only reported when `tui.mouse` is on. A click on a file
+    /// reference opens it in the editor and a click on a numbered option
+    /// selects it; the wheel scrolls the transcript.
+    async fn handle_mouse_event(
+        &mut self,
+        tui: &mut tui::Tui,
+        mouse_event: MouseEvent,
+    ) -> Result<()> {
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let buf = tui.terminal.last_buffer();
+                if let Some(reference) = mouse::file_reference_at(
+                    buf,
+                    mouse_event.column,
+                    mouse_event.row,
+                    &self.config.cwd,
+                ) {
+                    self.open_file_reference(tui, reference);
+                } else if self.overlay.is_none()
+                    && let Some(number) = mouse::list_item_at(buf, mouse_event.row)
+                {
+                    self.chat_widget.click_list_item(number);
+                }
+            }
+            // Scrolling up from the chat opens the transcript, which the
+            // wheel then scrolls.
+            MouseEventKind::ScrollUp if self.overlay.is_none() => {
+                let _ = tui.enter_alt_screen();
+                self.overlay = Some(Overlay::new_transcript(self.transcript_cells.clone()));
+                tui.frame_requester().schedule_frame();
+            }
+            _ if self.overlay.is_some() => {
+                let _ = self
+                    .handle_backtrack_overlay_event(tui, TuiEvent::Mouse(mouse_event))
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn open_file_reference(&mut self, tui: &mut tui::Tui, reference: FileReference) {
+        let opened = external_editor::open_file(&reference.path, reference.line);
+        let _ = tui.terminal.clear();
+        tui.frame_requester().schedule_frame();
+        if let Err(err) = opened {
+            self.chat_widget.add_error_message(format!(
+                "Failed to open {}: {err}",
+                reference.path.display()
+            ));
+        }
+    }
+
+    pub(crate) async fn handle_event(
+        &mut self,
+        tui: &mut tui::Tui,
+        event: AppEvent,
+    ) -> Result<bool> {
         match event {
+            // Unwrapped by `route_event` before reaching here.
+            AppEvent::Tab { .. } => {}
             AppEvent::NewSession => {
                 let init = crate::chatwidget::ChatWidgetInit {
                     config: self.config.clone(),
                     frame_requester: tui.frame_requester(),
-                    app_event_tx: self.app_event_tx.clone(),
+                    app_event_tx: self.app_event_tx.for_tab(self.tabs.active_id()),
                     initial_prompt: None,
                     initial_images: Vec::new(),
                     enhanced_keys_supported: self.enhanced_keys_supported,
@@ -224,6 +333,9 @@ impl App {
                 self.chat_widget = ChatWidget::new(init, self.server.clone());
                 tui.frame_requester().schedule_frame();
             }
+            AppEvent::OpenResumePicker => {
+                self.open_resume_picker(tui).await;
+            }
             AppEvent::InsertHistoryCell(cell) => {
                 let cell: Arc<dyn HistoryCell> = cell.into();
                 if let Some(Overlay::Transcript(t)) = &mut self.overlay {
@@ -250,30 +362,18 @@ impl App {
                     }
                 }
             }
-            AppEvent::StartCommitAnimation => {
-                if self
-                    .commit_anim_running
-                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
-                    .is_ok()
-                {
-                    let tx = self.app_event_tx.clone();
-                    let running = self.commit_anim_running.clone();
-                    thread::spawn(move || {
-                        while running.load(Ordering::Relaxed) {
-                            thread::sleep(Duration::from_millis(50));
-                            tx.send(AppEvent::CommitTick);
-                        }
-                    });
-                }
-            }
-            AppEvent::StopCommitAnimation => {
-                self.commit_anim_running.store(false, Ordering::Release);
-            }
+            AppEvent::StartCommitAnimation => self.start_commit_animation(),
+            AppEvent::StopCommitAnimation => self.stop_commit_animation(),
             AppEvent::CommitTick => {
                 self.chat_widget.on_commit_tick();
+                self.tabs.on_commit_tick();
             }
             AppEvent::CodexEvent(event) => {
                 self.chat_widget.handle_codex_event(event);
+                if let Some(Overlay::Status(status)) = &mut self.overlay {
+                    status.update(self.chat_widget.status_dashboard());
+                    tui.frame_requester().schedule_frame();
+                }
             }
             AppEvent::ConversationHistory(ev) => {
                 self.on_conversation_history_for_backtrack(tui, ev).await?;
@@ -298,6 +398,18 @@ impl App {
                 ));
                 tui.frame_requester().schedule_frame();
             }
+            AppEvent::ShowDiff(files) => {
+                // Clear the in-progress state left by `/diff`, if any.
+                self.chat_widget.on_diff_complete();
+                let _ = tui.enter_alt_screen();
+                self.overlay = Some(Overlay::new_diff(files));
+                tui.frame_requester().schedule_frame();
+            }
+            AppEvent::ShowStatus => {
+                let _ = tui.enter_alt_screen();
+                self.overlay = Some(Overlay::new_status(self.chat_widget.status_dashboard()));
+                tui.frame_requester().schedule_frame();
+            }
             AppEvent::StartFileSearch(query) => {
                 if !query.is_empty() {
                     self.file_search.on_user_query(query);
@@ -354,6 +466,14 @@ impl App {
             AppEvent::UpdateSandboxPolicy(policy) => {
                 self.chat_widget.set_sandbox_policy(policy);
             }
+            AppEvent::UpdateTheme(name) => match Theme::resolve(&self.config.tui_theme, &name) {
+                Ok(theme) => {
+                    theme::set_current(theme);
+                    self.chat_widget
+                        .add_info_message(format!("Theme changed to {name}"), None);
+                }
+                Err(err) => self.chat_widget.add_error_message(err),
+            },
             AppEvent::OpenReviewBranchPicker(cwd) => {
                 self.chat_widget.show_review_branch_picker(&cwd).await;
             }
@@ -363,10 +483,110 @@ impl App {
             AppEvent::OpenReviewCustomPrompt => {
                 self.chat_widget.show_review_custom_prompt();
             }
+            AppEvent::OpenAnnotationPicker => {
+                let messages = user_messages(&self.transcript_cells);
+                self.chat_widget.show_annotation_picker(messages);
+            }
+            AppEvent::OpenAnnotationPrompt { index, preview } => {
+                self.chat_widget.show_annotation_prompt(index, preview);
+            }
+            AppEvent::EditPatch { id, patch, note } => {
+                self.edit_patch(tui, id, patch, note);
+            }
+            AppEvent::EditMemory => {
+                self.edit_memory(tui);
+            }
         }
         Ok(true)
     }
 
+    /// Opens the notes kept about the current project in the user's editor.
+    /// Sessions read them when they start, so edits apply to new sessions.
+    fn edit_memory(&mut self, tui: &mut tui::Tui) {
+        let path = memory_path(&self.config.codex_home, &self.config.cwd);
+        let edited = match path.parent() {
+            Some(parent) => std::fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+        .and_then(|()| external_editor::open_file(&path, None));
+        let _ = tui.terminal.clear();
+        tui.frame_requester().schedule_frame();
+        match edited {
+            Ok(()) => self.chat_widget.add_info_message(
+                "Updated the project memory".to_string(),
+                Some("new sessions will use it".to_string()),
+            ),
+            Err(err) => self
+                .chat_widget
+                .add_error_message(format!("Failed to edit {}: {err}", path.display())),
+        }
+    }
+
+    /// Opens a proposed patch in the user's editor and applies it as edited.
+    /// Emptying the file rejects the changes.
+    fn edit_patch(&mut self, tui: &mut tui::Tui, id: String, patch: String, note: Option<String>) {
+        let edited = external_editor::edit_text(&patch, ".patch");
+        let _ = tui.terminal.clear();
+        tui.frame_requester().schedule_frame();
+        let op = match edited {
+            Ok(edited) if edited.trim().is_empty() => Op::PatchApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+            Ok(edited) if edited == patch && note.is_none() => Op::PatchApproval {
+                id,
+                decision: ReviewDecision::Approved,
+            },
+            Ok(edited) => {
+                let note = if edited == patch {
+                    note
+                } else {
+                    Some(match note {
+                        Some(note) => {
+                            format!("{note}\nThe user also edited the remaining changes.")
+                        }
+                        None => "The user edited the changes.".to_string(),
+                    })
+                };
+                Op::PatchApprovalEdited {
+                    id,
+                    patch: edited,
+                    note,
+                }
+            }
+            Err(err) => {
+                self.chat_widget
+                    .add_error_message(format!("Failed to edit the patch: {err}"));
+                Op::PatchApproval {
+                    id,
+                    decision: ReviewDecision::Denied,
+                }
+            }
+        };
+        self.chat_widget.submit_op(op);
+    }
+
+    pub(crate) fn start_commit_animation(&self) {
+        if self
+            .commit_anim_running
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            let tx = self.app_event_tx.clone();
+            let running = self.commit_anim_running.clone();
+            thread::spawn(move || {
+                while running.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(50));
+                    tx.send(AppEvent::CommitTick);
+                }
+            });
+        }
+    }
+
+    pub(crate) fn stop_commit_animation(&self) {
+        self.commit_anim_running.store(false, Ordering::Release);
+    }
+
     pub(crate) fn token_usage(&self) -> codex_core::protocol::TokenUsage {
         self.chat_widget.token_usage()
     }
@@ -377,13 +597,14 @@ impl App {
     }
 
     async fn handle_key_event(&mut self, tui: &mut tui::Tui, key_event: KeyEvent) {
+        if key_event.kind == KeyEventKind::Press && self.handle_tab_key(tui, &key_event).await {
+            return;
+        }
         match key_event {
             KeyEvent {
-                code: KeyCode::Char('t'),
-                modifiers: crossterm::event::KeyModifiers::CONTROL,
                 kind: KeyEventKind::Press,
                 ..
-            } => {
+            } if keymap::current().matches(KeyAction::Transcript, &key_event) => {
                 // Enter alternate screen and set viewport to full size.
                 let _ = tui.enter_alt_screen();
                 self.overlay = Some(Overlay::new_transcript(self.transcript_cells.clone()));
@@ -434,6 +655,83 @@ impl App {
             }
         };
     }
+
+    /// Runs the session browser (`/resume`) and switches to the session
+    /// resumed or forked in it. Leaving the browser keeps the current session.
+    async fn open_resume_picker(&mut self, tui: &mut tui::Tui) {
+        let config = self.chat_widget.config_ref().clone();
+        let selection =
+            match run_resume_picker(tui, &config.codex_home, &config.cwd, PickerOrigin::Session)
+                .await
+            {
+                Ok(selection) => selection,
+                Err(e) => {
+                    self.chat_widget
+                        .add_error_message(format!("Failed to list sessions: {e}"));
+                    return;
+                }
+            };
+        // As at startup, a session another process has open can only be
+        // observed or forked.
+        let selection = match selection {
+            ResumeSelection::Resume(path)
+                if self.chat_widget.rollout_path() == Some(path.as_path()) =>
+            {
+                self.chat_widget
+                    .add_info_message("Already in this session".to_string(), None);
+                ResumeSelection::Exit
+            }
+            ResumeSelection::Resume(path) => match session_lock_status(&config.codex_home, &path) {
+                Ok(Some(locked)) => run_session_locked_view(tui, &config.codex_home, locked)
+                    .await
+                    .unwrap_or(ResumeSelection::Exit),
+                _ => ResumeSelection::Resume(path),
+            },
+            other => other,
+        };
+        let resumed = match selection {
+            ResumeSelection::Resume(path) => {
+                self.server
+                    .resume_conversation_from_rollout(
+                        config.clone(),
+                        path,
+                        self.auth_manager.clone(),
+                    )
+                    .await
+            }
+            ResumeSelection::Fork(path) => {
+                self.server
+                    .fork_conversation_from_rollout(config.clone(), path)
+                    .await
+            }
+            ResumeSelection::StartFresh | ResumeSelection::Exit => {
+                tui.frame_requester().schedule_frame();
+                return;
+            }
+        };
+        match resumed {
+            Ok(resumed) => {
+                let init = crate::chatwidget::ChatWidgetInit {
+                    config,
+                    frame_requester: tui.frame_requester(),
+                    app_event_tx: self.app_event_tx.for_tab(self.tabs.active_id()),
+                    initial_prompt: None,
+                    initial_images: Vec::new(),
+                    enhanced_keys_supported: self.enhanced_keys_supported,
+                    auth_manager: self.auth_manager.clone(),
+                };
+                self.chat_widget = ChatWidget::new_from_existing(
+                    init,
+                    resumed.conversation,
+                    resumed.session_configured,
+                );
+            }
+            Err(e) => self
+                .chat_widget
+                .add_error_message(format!("Failed to open session: {e}")),
+        }
+        tui.frame_requester().schedule_frame();
+    }
 }
 
 #[cfg(test)]
@@ -483,6 +781,7 @@ mod tests {
             enhanced_keys_supported: false,
             commit_anim_running: Arc::new(AtomicBool::new(false)),
             backtrack: BacktrackState::default(),
+            tabs: Tabs::default(),
         }
     }
 
@@ -530,6 +829,7 @@ mod tests {
                 history_entry_count: 0,
                 initial_messages: None,
                 rollout_path: PathBuf::new(),
+                sandbox_capability: None,
             };
             Arc::new(new_session_info(
                 app.chat_widget.config_ref(),
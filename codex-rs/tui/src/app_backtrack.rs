@@ -212,7 +212,12 @@ impl App {
         if let Some(overlay) = &mut self.overlay {
             overlay.handle_event(tui, event)?;
             if overlay.is_done() {
+                let was_status = matches!(overlay, Overlay::Status(_));
                 self.close_transcript_overlay(tui);
+                if was_status {
+                    // Leave a snapshot of the dashboard in the scrollback.
+                    self.chat_widget.add_status_output();
+                }
                 tui.frame_requester().schedule_frame();
             }
         }
@@ -372,6 +377,14 @@ pub(crate) fn user_count(cells: &[Arc<dyn crate::history_cell::HistoryCell>]) ->
     user_positions_iter(cells).count()
 }
 
+/// Text of every user message in the current session, oldest first.
+pub(crate) fn user_messages(cells: &[Arc<dyn crate::history_cell::HistoryCell>]) -> Vec<String> {
+    user_positions_iter(cells)
+        .filter_map(|idx| cells[idx].as_any().downcast_ref::<UserHistoryCell>())
+        .map(|cell| cell.message.clone())
+        .collect()
+}
+
 fn nth_user_position(
     cells: &[Arc<dyn crate::history_cell::HistoryCell>],
     nth: usize,
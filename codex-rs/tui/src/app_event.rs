@@ -4,6 +4,8 @@ use codex_core::protocol::ConversationPathResponseEvent;
 use codex_core::protocol::Event;
 use codex_file_search::FileMatch;
 
+use crate::app_tabs::TabId;
+use crate::diff_viewer::DiffFile;
 use crate::history_cell::HistoryCell;
 
 use codex_core::protocol::AskForApproval;
@@ -15,9 +17,19 @@ use codex_core::protocol_config_types::ReasoningEffort;
 pub(crate) enum AppEvent {
     CodexEvent(Event),
 
+    /// An event sent by the conversation in tab `tab`. The app hands it to
+    /// that tab, whether or not the tab is in front.
+    Tab {
+        tab: TabId,
+        event: Box<AppEvent>,
+    },
+
     /// Start a new session.
     NewSession,
 
+    /// Open the session browser to switch to a previous session.
+    OpenResumePicker,
+
     /// Request to exit the application gracefully.
     ExitRequest,
 
@@ -41,6 +53,23 @@ pub(crate) enum AppEvent {
     /// Result of computing a `/diff` command.
     DiffResult(String),
 
+    /// Open the diff viewer on `files`, from `/diff` or a patch approval.
+    ShowDiff(Vec<DiffFile>),
+
+    /// Open the live `/status` dashboard.
+    ShowStatus,
+
+    /// Open `patch`, proposed for the patch approval `id`, in `$EDITOR` and
+    /// apply it as edited. `note` says which changes were already left out.
+    EditPatch {
+        id: String,
+        patch: String,
+        note: Option<String>,
+    },
+
+    /// Open the current project's memory file in `$EDITOR` (`/memory edit`).
+    EditMemory,
+
     InsertHistoryCell(Box<dyn HistoryCell>),
 
     StartCommitAnimation,
@@ -65,6 +94,9 @@ pub(crate) enum AppEvent {
     /// Update the current sandbox policy in the running app and widget.
     UpdateSandboxPolicy(SandboxPolicy),
 
+    /// Switch the TUI to the theme with this name.
+    UpdateTheme(String),
+
     /// Forwarded conversation history snapshot from the current conversation.
     ConversationHistory(ConversationPathResponseEvent),
 
@@ -76,4 +108,13 @@ pub(crate) enum AppEvent {
 
     /// Open the custom prompt option from the review popup.
     OpenReviewCustomPrompt,
+
+    /// Open the picker listing user messages that can be annotated.
+    OpenAnnotationPicker,
+
+    /// Prompt for the comment to attach to the `index`-th user message.
+    OpenAnnotationPrompt {
+        index: usize,
+        preview: String,
+    },
 }
@@ -0,0 +1,381 @@
+//! Tabs: several conversations in one TUI, one of them in front.
+//!
+//! The front tab's widget and transcript are the `App`'s own `chat_widget`
+//! and `transcript_cells`; switching tabs swaps them with a parked
+//! [`BackgroundTab`]. Each tab's widget sends through an `AppEventSender`
+//! tagged with its [`TabId`], so background conversations keep running
+//! through the app's single event loop and their events reach their tab.
+
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+
+use crate::app::App;
+use crate::app_event::AppEvent;
+use crate::chatwidget::ChatWidget;
+use crate::chatwidget::ChatWidgetInit;
+use crate::history_cell::HistoryCell;
+use crate::keymap;
+use crate::keymap::KeyAction;
+use crate::theme;
+use crate::tui;
+
+pub(crate) type TabId = usize;
+
+/// A tab that is not in front.
+pub(crate) struct BackgroundTab {
+    chat_widget: ChatWidget,
+    transcript_cells: Vec<Arc<dyn HistoryCell>>,
+    has_emitted_history_lines: bool,
+    /// History arrived since the tab was last in front.
+    unseen: bool,
+    /// The agent asked for an approval while the tab was in the background.
+    needs_approval: bool,
+}
+
+impl BackgroundTab {
+    fn new(chat_widget: ChatWidget) -> Self {
+        Self {
+            chat_widget,
+            transcript_cells: Vec::new(),
+            has_emitted_history_lines: false,
+            unseen: false,
+            needs_approval: false,
+        }
+    }
+
+    fn handle_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::CodexEvent(event) => {
+                if matches!(
+                    event.msg,
+                    EventMsg::ExecApprovalRequest(_)
+                        | EventMsg::ApplyPatchApprovalRequest(_)
+                        | EventMsg::SecretRequest(_)
+                        | EventMsg::McpSamplingApprovalRequest(_)
+                ) {
+                    self.needs_approval = true;
+                }
+                self.chat_widget.handle_codex_event(event);
+            }
+            AppEvent::InsertHistoryCell(cell) => {
+                self.transcript_cells.push(cell.into());
+                self.has_emitted_history_lines = true;
+                self.unseen = true;
+            }
+            AppEvent::CodexOp(op) => self.chat_widget.submit_op(op),
+            // Everything else answers user input, which only the front tab gets.
+            other => tracing::debug!("ignoring {other:?} from a background tab"),
+        }
+    }
+
+    fn status(&self) -> TabStatus {
+        if self.needs_approval {
+            TabStatus::NeedsApproval
+        } else if self.chat_widget.is_task_running() {
+            TabStatus::Working
+        } else if self.unseen {
+            TabStatus::Unseen
+        } else {
+            TabStatus::Idle
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TabStatus {
+    Idle,
+    Working,
+    /// Finished or produced output while in the background.
+    Unseen,
+    NeedsApproval,
+}
+
+impl TabStatus {
+    fn label(self) -> &'static str {
+        match self {
+            TabStatus::Idle => "idle",
+            TabStatus::Working => "working",
+            TabStatus::Unseen => "new",
+            TabStatus::NeedsApproval => "approval",
+        }
+    }
+}
+
+pub(crate) struct Tabs {
+    /// All tabs, in the order they are shown.
+    order: Vec<TabId>,
+    active: TabId,
+    background: HashMap<TabId, BackgroundTab>,
+    next_id: TabId,
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self {
+            order: vec![0],
+            active: 0,
+            background: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+impl Tabs {
+    pub(crate) fn active_id(&self) -> TabId {
+        self.active
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    fn position(&self, tab: TabId) -> usize {
+        self.order.iter().position(|id| *id == tab).unwrap_or(0)
+    }
+
+    /// The tab `offset` places from the one in front, wrapping around.
+    fn neighbour(&self, offset: isize) -> TabId {
+        let len = self.order.len() as isize;
+        let index = (self.position(self.active) as isize + offset).rem_euclid(len.max(1));
+        self.order
+            .get(index as usize)
+            .copied()
+            .unwrap_or(self.active)
+    }
+
+    /// Advances the streaming animation of background tabs.
+    pub(crate) fn on_commit_tick(&mut self) {
+        for tab in self.background.values_mut() {
+            tab.chat_widget.on_commit_tick();
+        }
+    }
+}
+
+impl App {
+    /// Handles an event from the app channel, passing events tagged with a
+    /// background tab to that tab.
+    pub(crate) async fn route_event(
+        &mut self,
+        tui: &mut tui::Tui,
+        event: AppEvent,
+    ) -> Result<bool> {
+        match event {
+            AppEvent::Tab { tab, event } if tab != self.tabs.active => {
+                match *event {
+                    AppEvent::StartCommitAnimation => self.start_commit_animation(),
+                    AppEvent::StopCommitAnimation => self.stop_commit_animation(),
+                    // Events from a closed tab are dropped.
+                    event => {
+                        if let Some(parked) = self.tabs.background.get_mut(&tab) {
+                            parked.handle_event(event);
+                            tui.frame_requester().schedule_frame();
+                        }
+                    }
+                }
+                Ok(true)
+            }
+            AppEvent::Tab { event, .. } => self.handle_event(tui, *event).await,
+            event => self.handle_event(tui, event).await,
+        }
+    }
+
+    /// Handles the tab key bindings. Returns whether `key_event` was one of
+    /// them.
+    pub(crate) async fn handle_tab_key(
+        &mut self,
+        tui: &mut tui::Tui,
+        key_event: &KeyEvent,
+    ) -> bool {
+        let keymap = keymap::current();
+        if keymap.matches(KeyAction::NewTab, key_event) {
+            self.open_tab(tui);
+        } else if keymap.matches(KeyAction::NextTab, key_event) {
+            self.switch_tab(tui, self.tabs.neighbour(1));
+        } else if keymap.matches(KeyAction::PrevTab, key_event) {
+            self.switch_tab(tui, self.tabs.neighbour(-1));
+        } else if keymap.matches(KeyAction::CloseTab, key_event) {
+            self.close_tab(tui).await;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Starts a new conversation in a tab after the front one.
+    fn open_tab(&mut self, tui: &mut tui::Tui) {
+        let tab = self.tabs.next_id;
+        self.tabs.next_id += 1;
+        let init = ChatWidgetInit {
+            config: self.config.clone(),
+            frame_requester: tui.frame_requester(),
+            app_event_tx: self.app_event_tx.for_tab(tab),
+            initial_prompt: None,
+            initial_images: Vec::new(),
+            enhanced_keys_supported: self.enhanced_keys_supported,
+            auth_manager: self.auth_manager.clone(),
+        };
+        let chat_widget = ChatWidget::new(init, self.server.clone());
+        let position = self.tabs.position(self.tabs.active) + 1;
+        self.tabs.order.insert(position, tab);
+        self.bring_to_front(tui, tab, BackgroundTab::new(chat_widget));
+    }
+
+    fn switch_tab(&mut self, tui: &mut tui::Tui, tab: TabId) {
+        if tab == self.tabs.active {
+            return;
+        }
+        if let Some(parked) = self.tabs.background.remove(&tab) {
+            self.bring_to_front(tui, tab, parked);
+        }
+    }
+
+    /// Closes the front tab and shuts its conversation down. The last tab
+    /// stays open.
+    async fn close_tab(&mut self, tui: &mut tui::Tui) {
+        if self.tabs.len() < 2 {
+            self.chat_widget.add_info_message(
+                "This is the only tab".to_string(),
+                Some("Use /quit to exit".to_string()),
+            );
+            return;
+        }
+        let closing = self.tabs.active;
+        let offset = if self.tabs.position(closing) == 0 {
+            1
+        } else {
+            -1
+        };
+        let next = self.tabs.neighbour(offset);
+        self.tabs.order.retain(|id| *id != closing);
+        self.switch_tab(tui, next);
+        if let Some(closed) = self.tabs.background.remove(&closing) {
+            closed.chat_widget.submit_op(Op::Shutdown);
+            if let Some(id) = closed.chat_widget.conversation_id() {
+                self.server.remove_conversation(&id).await;
+            }
+        }
+    }
+
+    /// Puts `parked` in front as `tab` and parks the tab that was in front.
+    fn bring_to_front(&mut self, tui: &mut tui::Tui, tab: TabId, parked: BackgroundTab) {
+        self.reset_backtrack_state();
+        let BackgroundTab {
+            chat_widget,
+            transcript_cells,
+            has_emitted_history_lines,
+            ..
+        } = parked;
+        let previous = BackgroundTab {
+            chat_widget: mem::replace(&mut self.chat_widget, chat_widget),
+            transcript_cells: mem::replace(&mut self.transcript_cells, transcript_cells),
+            has_emitted_history_lines: mem::replace(
+                &mut self.has_emitted_history_lines,
+                has_emitted_history_lines,
+            ),
+            unseen: false,
+            needs_approval: false,
+        };
+        self.tabs.background.insert(self.tabs.active, previous);
+        self.tabs.active = tab;
+
+        // Replay the tab's transcript so the scrollback above shows this
+        // conversation.
+        let number = self.tabs.position(tab) + 1;
+        tui.insert_history_lines(vec![
+            Line::from(""),
+            format!("── tab {number} ──").dim().into(),
+        ]);
+        self.render_transcript_once(tui);
+        tui.frame_requester().schedule_frame();
+    }
+
+    /// The tab bar shown above the composer when more than one tab is open.
+    pub(crate) fn tab_bar(&self) -> Option<Line<'static>> {
+        if self.tabs.len() < 2 {
+            return None;
+        }
+        let tabs: Vec<(TabStatus, bool)> = self
+            .tabs
+            .order
+            .iter()
+            .map(|tab| match self.tabs.background.get(tab) {
+                Some(parked) => (parked.status(), false),
+                None if self.chat_widget.is_task_running() => (TabStatus::Working, true),
+                None => (TabStatus::Idle, true),
+            })
+            .collect();
+        Some(tab_bar_line(&tabs))
+    }
+}
+
+/// One entry per tab: its number, with the front tab highlighted, and its
+/// status.
+fn tab_bar_line(tabs: &[(TabStatus, bool)]) -> Line<'static> {
+    let theme = theme::current();
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for (index, (status, front)) in tabs.iter().enumerate() {
+        if index > 0 {
+            spans.push("│".dim());
+        }
+        let number = format!(" {} ", index + 1);
+        spans.push(if *front {
+            number.reversed().bold()
+        } else {
+            number.dim()
+        });
+        let style = match status {
+            TabStatus::Working => theme.status_accent,
+            TabStatus::NeedsApproval | TabStatus::Unseen => theme.status_warning,
+            TabStatus::Idle => Style::default().dim(),
+        };
+        spans.push(Span::styled(format!(" {} ", status.label()), style));
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn neighbours_wrap_around() {
+        let tabs = Tabs {
+            order: vec![0, 3, 1],
+            active: 3,
+            background: HashMap::new(),
+            next_id: 4,
+        };
+
+        assert_eq!(tabs.neighbour(1), 1);
+        assert_eq!(tabs.neighbour(-1), 0);
+        assert_eq!(tabs.neighbour(2), 0);
+        assert_eq!(Tabs::default().neighbour(1), 0);
+    }
+
+    #[test]
+    fn tab_bar_lists_each_tab_with_its_status() {
+        let line = tab_bar_line(&[
+            (TabStatus::Working, false),
+            (TabStatus::Idle, true),
+            (TabStatus::NeedsApproval, false),
+        ]);
+        let text: String = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+
+        assert_eq!(text, " 1  working │ 2  idle │ 3  approval ");
+    }
+}
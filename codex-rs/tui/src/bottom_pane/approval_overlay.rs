@@ -8,10 +8,16 @@ use crate::bottom_pane::list_selection_view::HeaderLine;
 use crate::bottom_pane::list_selection_view::ListSelectionView;
 use crate::bottom_pane::list_selection_view::SelectionItem;
 use crate::bottom_pane::list_selection_view::SelectionViewParams;
+use crate::diff_viewer::DiffFile;
 use crate::exec_command::strip_bash_lc_and_escape;
 use crate::history_cell;
+use crate::keymap;
+use crate::keymap::KeyAction;
+use crate::patch_selection::PatchSelection;
+use crate::patch_selection::SelectionRow;
 use crate::text_formatting::truncate_text;
 use codex_core::protocol::Op;
+use codex_core::protocol::PatchRisk;
 use codex_core::protocol::ReviewDecision;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -23,6 +29,9 @@ use ratatui::style::Stylize;
 use ratatui::text::Line;
 use ratatui::text::Span;
 
+/// Lines of a sampling prompt shown before the rest is elided.
+const SAMPLING_PROMPT_MAX_LINES: usize = 8;
+
 /// Request coming from the agent that needs user approval.
 pub(crate) enum ApprovalRequest {
     Exec {
@@ -34,6 +43,18 @@ pub(crate) enum ApprovalRequest {
         id: String,
         reason: Option<String>,
         grant_root: Option<PathBuf>,
+        risk: Option<PatchRisk>,
+        /// The proposed changes, for the diff viewer.
+        files: Vec<DiffFile>,
+        /// The proposed changes, for choosing which of them to apply.
+        selection: PatchSelection,
+    },
+    McpSampling {
+        id: String,
+        server: String,
+        prompt: String,
+        system_prompt: Option<String>,
+        max_tokens: i64,
     },
 }
 
@@ -46,6 +67,10 @@ pub(crate) struct ApprovalOverlay {
     options: Vec<ApprovalOption>,
     current_complete: bool,
     done: bool,
+    /// Whether the list shows the files and hunks of the patch, for choosing
+    /// which to apply, rather than the options.
+    selecting: bool,
+    selection_rows: Vec<SelectionRow>,
 }
 
 impl ApprovalOverlay {
@@ -64,6 +89,8 @@ impl ApprovalOverlay {
             options: Vec::new(),
             current_complete: false,
             done: false,
+            selecting: false,
+            selection_rows: Vec::new(),
         };
         let (options, params) = view.build_options();
         view.options = options;
@@ -78,6 +105,7 @@ impl ApprovalOverlay {
     fn set_current(&mut self, request: ApprovalRequest) {
         self.current = Some(ApprovalRequestState::from(request));
         self.current_complete = false;
+        self.selecting = false;
         let (options, params) = self.build_options();
         self.options = options;
         self.list = ListSelectionView::new(params, self.app_event_tx.clone());
@@ -96,13 +124,20 @@ impl ApprovalOverlay {
         let (options, title) = match &state.variant {
             ApprovalVariant::Exec { .. } => (exec_options(), "Allow command?".to_string()),
             ApprovalVariant::ApplyPatch { .. } => (patch_options(), "Apply changes?".to_string()),
+            ApprovalVariant::McpSampling { server, .. } => (
+                sampling_options(),
+                format!("Let MCP server {server} use the model?"),
+            ),
         };
 
         let items = options
             .iter()
             .map(|opt| SelectionItem {
                 name: opt.label.clone(),
-                description: Some(opt.description.clone()),
+                description: Some(match keymap::current().primary(opt.shortcut) {
+                    Some(key) => format!("({}) {}", key.label(), opt.description),
+                    None => opt.description.clone(),
+                }),
                 is_current: false,
                 actions: Vec::new(),
                 dismiss_on_select: false,
@@ -110,9 +145,16 @@ impl ApprovalOverlay {
             })
             .collect();
 
+        let footer_hint = match &state.variant {
+            ApprovalVariant::ApplyPatch { .. } => {
+                // Short enough for an 80-column terminal.
+                "Press d for diff, s to pick hunks, e to edit, Enter to confirm or Esc to cancel"
+            }
+            _ => "Press Enter to confirm or Esc to cancel",
+        };
         let params = SelectionViewParams {
             title,
-            footer_hint: Some("Press Enter to confirm or Esc to cancel".to_string()),
+            footer_hint: Some(footer_hint.to_string()),
             items,
             header: state.header.clone(),
             ..Default::default()
@@ -136,6 +178,9 @@ impl ApprovalOverlay {
                 (ApprovalVariant::ApplyPatch { id, .. }, decision) => {
                     self.handle_patch_decision(id, decision);
                 }
+                (ApprovalVariant::McpSampling { id, .. }, decision) => {
+                    self.handle_sampling_decision(id, decision);
+                }
             }
         }
 
@@ -162,6 +207,154 @@ impl ApprovalOverlay {
         }));
     }
 
+    /// Lists the files and hunks of the patch for choosing which to apply.
+    fn start_selecting(&mut self) {
+        self.selecting = true;
+        self.rebuild_selection_list(0);
+    }
+
+    fn stop_selecting(&mut self) {
+        self.selecting = false;
+        let (options, params) = self.build_options();
+        self.options = options;
+        self.list = ListSelectionView::new(params, self.app_event_tx.clone());
+    }
+
+    fn rebuild_selection_list(&mut self, cursor: usize) {
+        let Some(ApprovalRequestState {
+            variant: ApprovalVariant::ApplyPatch { selection, .. },
+            header,
+        }) = self.current.as_ref()
+        else {
+            return;
+        };
+        let rows = selection.rows();
+        let items = rows
+            .iter()
+            .map(|row| {
+                let (text, selected) = selection.describe(*row);
+                let mark = match selected {
+                    Some(true) => "[x]",
+                    Some(false) => "[ ]",
+                    None => "[~]",
+                };
+                let indent = match row {
+                    SelectionRow::File(_) => "",
+                    SelectionRow::Hunk(..) => "  ",
+                };
+                SelectionItem {
+                    name: format!("{indent}{mark} {text}"),
+                    description: None,
+                    is_current: false,
+                    actions: Vec::new(),
+                    dismiss_on_select: false,
+                    search_value: None,
+                }
+            })
+            .collect();
+        let params = SelectionViewParams {
+            title: "Choose the changes to apply".to_string(),
+            footer_hint: Some(
+                "Press space to include or leave out, e to edit, Enter to apply or Esc to go back"
+                    .to_string(),
+            ),
+            items,
+            header: header.clone(),
+            ..Default::default()
+        };
+        self.selection_rows = rows;
+        self.list = ListSelectionView::new(params, self.app_event_tx.clone());
+        self.list.select_index(cursor);
+    }
+
+    fn handle_selecting_key(&mut self, key_event: KeyEvent) {
+        let plain = key_event.kind == KeyEventKind::Press
+            && !key_event
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key_event.code {
+            KeyCode::Char(' ') if plain => {
+                let cursor = self.list.selected_index().unwrap_or(0);
+                if let Some(row) = self.selection_rows.get(cursor).copied()
+                    && let Some(ApprovalRequestState {
+                        variant: ApprovalVariant::ApplyPatch { selection, .. },
+                        ..
+                    }) = self.current.as_mut()
+                {
+                    selection.toggle(row);
+                }
+                self.rebuild_selection_list(cursor);
+            }
+            KeyCode::Char('e') if plain => self.edit_patch(),
+            _ => {
+                self.list.handle_key_event(key_event);
+                if self.list.take_last_selected_index().is_some() {
+                    self.apply_patch_selection();
+                }
+            }
+        }
+    }
+
+    /// Applies the changes chosen in the selection list: all of them, some
+    /// of them with a note about the rest, or none.
+    fn apply_patch_selection(&mut self) {
+        let Some(ApprovalRequestState {
+            variant: ApprovalVariant::ApplyPatch { id, selection, .. },
+            ..
+        }) = self.current.as_ref()
+        else {
+            return;
+        };
+        if selection.all_selected() {
+            self.handle_patch_decision(id, ReviewDecision::Approved);
+        } else {
+            match selection.to_patch() {
+                Some(patch) => self
+                    .app_event_tx
+                    .send(AppEvent::CodexOp(Op::PatchApprovalEdited {
+                        id: id.clone(),
+                        patch,
+                        note: selection.note(),
+                    })),
+                None => self.handle_patch_decision(id, ReviewDecision::Denied),
+            }
+        }
+        self.selecting = false;
+        self.current_complete = true;
+        self.advance_queue();
+    }
+
+    /// Opens the chosen changes in `$EDITOR`; the edited patch is applied
+    /// once the editor exits.
+    fn edit_patch(&mut self) {
+        let Some(ApprovalRequestState {
+            variant: ApprovalVariant::ApplyPatch { id, selection, .. },
+            ..
+        }) = self.current.as_ref()
+        else {
+            return;
+        };
+        let Some(patch) = selection.to_patch() else {
+            return;
+        };
+        self.app_event_tx.send(AppEvent::EditPatch {
+            id: id.clone(),
+            patch,
+            note: selection.note(),
+        });
+        self.selecting = false;
+        self.current_complete = true;
+        self.advance_queue();
+    }
+
+    fn handle_sampling_decision(&self, id: &str, decision: ReviewDecision) {
+        self.app_event_tx
+            .send(AppEvent::CodexOp(Op::McpSamplingApproval {
+                id: id.to_string(),
+                decision,
+            }));
+    }
+
     fn advance_queue(&mut self) {
         if let Some(next) = self.queue.pop() {
             self.set_current(next);
@@ -174,25 +367,43 @@ impl ApprovalOverlay {
         if key_event.kind != KeyEventKind::Press {
             return false;
         }
-        let KeyEvent {
-            code: KeyCode::Char(c),
-            modifiers,
-            ..
-        } = key_event
-        else {
-            return false;
+        // Letter shortcuts work regardless of case.
+        let key_event = match key_event.code {
+            KeyCode::Char(c) => KeyEvent {
+                code: KeyCode::Char(c.to_ascii_lowercase()),
+                ..*key_event
+            },
+            _ => *key_event,
+        };
+        let keymap = keymap::current();
+        let plain = !key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let files = match self.current.as_ref() {
+            Some(ApprovalRequestState {
+                variant: ApprovalVariant::ApplyPatch { files, .. },
+                ..
+            }) if plain => Some(files),
+            _ => None,
         };
-        if modifiers.contains(KeyModifiers::CONTROL) || modifiers.contains(KeyModifiers::ALT) {
-            return false;
-        }
-        let lower = c.to_ascii_lowercase();
         if let Some(idx) = self
             .options
             .iter()
-            .position(|opt| opt.shortcut.map(|s| s == lower).unwrap_or(false))
+            .position(|opt| keymap.matches(opt.shortcut, &key_event))
         {
             self.apply_selection(idx);
             true
+        } else if let Some(files) = files
+            && key_event.code == KeyCode::Char('d')
+        {
+            self.app_event_tx.send(AppEvent::ShowDiff(files.clone()));
+            true
+        } else if files.is_some() && key_event.code == KeyCode::Char('s') {
+            self.start_selecting();
+            true
+        } else if files.is_some() && key_event.code == KeyCode::Char('e') {
+            self.edit_patch();
+            true
         } else {
             false
         }
@@ -201,6 +412,10 @@ impl ApprovalOverlay {
 
 impl BottomPaneView for ApprovalOverlay {
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if self.selecting {
+            self.handle_selecting_key(key_event);
+            return;
+        }
         if self.try_handle_shortcut(&key_event) {
             return;
         }
@@ -210,10 +425,33 @@ impl BottomPaneView for ApprovalOverlay {
         }
     }
 
+    fn handle_item_click(&mut self, number: usize) -> bool {
+        // In the change list a click only moves the cursor; space toggles.
+        if self.selecting {
+            if number == 0 || number > self.selection_rows.len() {
+                return false;
+            }
+            self.list.select_index(number - 1);
+            return true;
+        }
+        if !self.list.handle_item_click(number) {
+            return false;
+        }
+        if let Some(idx) = self.list.take_last_selected_index() {
+            self.apply_selection(idx);
+        }
+        true
+    }
+
     fn on_ctrl_c(&mut self) -> CancellationEvent {
         if self.done {
             return CancellationEvent::Handled;
         }
+        // Esc in the selection list goes back to the options.
+        if self.selecting {
+            self.stop_selecting();
+            return CancellationEvent::Handled;
+        }
         if !self.current_complete
             && let Some(state) = self.current.as_ref()
         {
@@ -224,6 +462,9 @@ impl BottomPaneView for ApprovalOverlay {
                 ApprovalVariant::ApplyPatch { id, .. } => {
                     self.handle_patch_decision(id, ReviewDecision::Abort);
                 }
+                ApprovalVariant::McpSampling { id, .. } => {
+                    self.handle_sampling_decision(id, ReviewDecision::Abort);
+                }
             }
         }
         self.queue.clear();
@@ -296,8 +537,18 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                 id,
                 reason,
                 grant_root,
+                risk,
+                files,
+                selection,
             } => {
                 let mut header = Vec::new();
+                if let Some(risk) = risk {
+                    header.push(HeaderLine::Text {
+                        text: format!("Risk: {} ({})", risk.level, risk.reasons.join("; ")),
+                        italic: false,
+                    });
+                    header.push(HeaderLine::Spacer);
+                }
                 if let Some(reason) = reason
                     && !reason.is_empty()
                 {
@@ -318,7 +569,50 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                     header.push(HeaderLine::Spacer);
                 }
                 Self {
-                    variant: ApprovalVariant::ApplyPatch { id },
+                    variant: ApprovalVariant::ApplyPatch {
+                        id,
+                        files,
+                        selection,
+                    },
+                    header,
+                }
+            }
+            ApprovalRequest::McpSampling {
+                id,
+                server,
+                prompt,
+                system_prompt,
+                max_tokens,
+            } => {
+                let mut header = Vec::new();
+                if let Some(system_prompt) = system_prompt
+                    && !system_prompt.is_empty()
+                {
+                    header.push(HeaderLine::Text {
+                        text: format!("System: {}", truncate_text(&system_prompt, 200)),
+                        italic: true,
+                    });
+                }
+                let lines: Vec<&str> = prompt.lines().collect();
+                for line in lines.iter().take(SAMPLING_PROMPT_MAX_LINES) {
+                    header.push(HeaderLine::Text {
+                        text: truncate_text(line, 200),
+                        italic: false,
+                    });
+                }
+                if lines.len() > SAMPLING_PROMPT_MAX_LINES {
+                    header.push(HeaderLine::Text {
+                        text: format!("… {} more lines", lines.len() - SAMPLING_PROMPT_MAX_LINES),
+                        italic: true,
+                    });
+                }
+                header.push(HeaderLine::Text {
+                    text: format!("Up to {max_tokens} tokens"),
+                    italic: true,
+                });
+                header.push(HeaderLine::Spacer);
+                Self {
+                    variant: ApprovalVariant::McpSampling { id, server },
                     header,
                 }
             }
@@ -327,8 +621,19 @@ impl From<ApprovalRequest> for ApprovalRequestState {
 }
 
 enum ApprovalVariant {
-    Exec { id: String, command: Vec<String> },
-    ApplyPatch { id: String },
+    Exec {
+        id: String,
+        command: Vec<String>,
+    },
+    ApplyPatch {
+        id: String,
+        files: Vec<DiffFile>,
+        selection: PatchSelection,
+    },
+    McpSampling {
+        id: String,
+        server: String,
+    },
 }
 
 #[derive(Clone)]
@@ -336,29 +641,29 @@ struct ApprovalOption {
     label: String,
     description: String,
     decision: ReviewDecision,
-    shortcut: Option<char>,
+    shortcut: KeyAction,
 }
 
 fn exec_options() -> Vec<ApprovalOption> {
     vec![
         ApprovalOption {
             label: "Approve and run now".to_string(),
-            description: "(Y) Run this command one time".to_string(),
+            description: "Run this command one time".to_string(),
             decision: ReviewDecision::Approved,
-            shortcut: Some('y'),
+            shortcut: KeyAction::Approve,
         },
         ApprovalOption {
             label: "Always approve this session".to_string(),
-            description: "(A) Automatically approve this command for the rest of the session"
+            description: "Automatically approve this command for the rest of the session"
                 .to_string(),
             decision: ReviewDecision::ApprovedForSession,
-            shortcut: Some('a'),
+            shortcut: KeyAction::ApproveForSession,
         },
         ApprovalOption {
             label: "Cancel".to_string(),
-            description: "(N) Do not run the command".to_string(),
+            description: "Do not run the command".to_string(),
             decision: ReviewDecision::Abort,
-            shortcut: Some('n'),
+            shortcut: KeyAction::Deny,
         },
     ]
 }
@@ -367,15 +672,39 @@ fn patch_options() -> Vec<ApprovalOption> {
     vec![
         ApprovalOption {
             label: "Approve".to_string(),
-            description: "(Y) Apply the proposed changes".to_string(),
+            description: "Apply the proposed changes".to_string(),
             decision: ReviewDecision::Approved,
-            shortcut: Some('y'),
+            shortcut: KeyAction::Approve,
         },
         ApprovalOption {
             label: "Cancel".to_string(),
-            description: "(N) Do not apply the changes".to_string(),
+            description: "Do not apply the changes".to_string(),
             decision: ReviewDecision::Abort,
-            shortcut: Some('n'),
+            shortcut: KeyAction::Deny,
+        },
+    ]
+}
+
+fn sampling_options() -> Vec<ApprovalOption> {
+    vec![
+        ApprovalOption {
+            label: "Approve".to_string(),
+            description: "Send this request to the model".to_string(),
+            decision: ReviewDecision::Approved,
+            shortcut: KeyAction::Approve,
+        },
+        ApprovalOption {
+            label: "Always approve this server this session".to_string(),
+            description: "Send this server's requests without asking for the rest of the session"
+                .to_string(),
+            decision: ReviewDecision::ApprovedForSession,
+            shortcut: KeyAction::ApproveForSession,
+        },
+        ApprovalOption {
+            label: "Deny".to_string(),
+            description: "Tell the server the request was declined".to_string(),
+            decision: ReviewDecision::Denied,
+            shortcut: KeyAction::Deny,
         },
     ]
 }
@@ -465,6 +794,10 @@ fn exec_snippet(command: &[String]) -> String {
 mod tests {
     use super::*;
     use crate::app_event::AppEvent;
+    use codex_core::protocol::FileChange;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::path::Path;
     use tokio::sync::mpsc::unbounded_channel;
 
     fn make_exec_request() -> ApprovalRequest {
@@ -504,6 +837,111 @@ mod tests {
         assert!(saw_op, "expected approval decision to emit an op");
     }
 
+    #[test]
+    fn sampling_requests_can_be_denied() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let request = ApprovalRequest::McpSampling {
+            id: "mcp-sampling-1".to_string(),
+            server: "docs".to_string(),
+            prompt: "user: Summarize the README".to_string(),
+            system_prompt: None,
+            max_tokens: 200,
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+
+        view.handle_key_event(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE));
+
+        let mut decision = None;
+        while let Ok(ev) = rx.try_recv() {
+            if let AppEvent::CodexOp(Op::McpSamplingApproval { id, decision: d }) = ev {
+                assert_eq!(id, "mcp-sampling-1");
+                decision = Some(d);
+            }
+        }
+        assert_eq!(decision, Some(ReviewDecision::Denied));
+        assert!(view.is_complete());
+    }
+
+    #[test]
+    fn d_opens_the_diff_without_deciding() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let files = DiffFile::from_git_diff(
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n",
+        );
+        let request = ApprovalRequest::ApplyPatch {
+            id: "patch-1".to_string(),
+            reason: None,
+            grant_root: None,
+            risk: None,
+            files: files.clone(),
+            selection: PatchSelection::from_changes(&HashMap::new(), Path::new("/")),
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+
+        view.handle_key_event(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+
+        let events: Vec<AppEvent> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert!(
+            matches!(events.as_slice(), [AppEvent::ShowDiff(shown)] if *shown == files),
+            "expected only a ShowDiff event"
+        );
+        assert!(!view.is_complete());
+    }
+
+    #[test]
+    fn chosen_hunks_are_approved_as_an_edited_patch() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let changes = HashMap::from([(
+            PathBuf::from("/repo/a.txt"),
+            FileChange::Update {
+                unified_diff: "@@ -1 +1 @@\n-one\n+uno\n@@ -9 +9 @@\n-nine\n+nueve\n".to_string(),
+                move_path: None,
+            },
+        )]);
+        let request = ApprovalRequest::ApplyPatch {
+            id: "patch-1".to_string(),
+            reason: None,
+            grant_root: None,
+            risk: None,
+            files: DiffFile::from_changes(&changes, Path::new("/repo")),
+            selection: PatchSelection::from_changes(&changes, Path::new("/repo")),
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+
+        // Rows: the file, then its two hunks. Leave out the first hunk.
+        for key in [
+            KeyCode::Char('s'),
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ] {
+            view.handle_key_event(KeyEvent::new(key, KeyModifiers::NONE));
+        }
+
+        let ops: Vec<Op> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|ev| match ev {
+                AppEvent::CodexOp(op) => Some(op),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ops,
+            vec![Op::PatchApprovalEdited {
+                id: "patch-1".to_string(),
+                patch: "*** Begin Patch\n*** Update File: /repo/a.txt\n@@\n-nine\n+nueve\n*** End Patch\n"
+                    .to_string(),
+                note: Some(
+                    "These changes were rejected and not applied:\n- a.txt @@ -1 +1 @@"
+                        .to_string()
+                ),
+            }]
+        );
+        assert!(view.is_complete());
+    }
+
     #[test]
     fn header_includes_command_snippet() {
         let (tx, _rx) = unbounded_channel::<AppEvent>();
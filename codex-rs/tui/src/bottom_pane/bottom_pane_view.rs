@@ -33,6 +33,12 @@ pub(crate) trait BottomPaneView {
         false
     }
 
+    /// Handle a mouse click on the numbered item `number` of the view's list.
+    /// Return true if the click was consumed.
+    fn handle_item_click(&mut self, _number: usize) -> bool {
+        false
+    }
+
     /// Cursor position when this view is active.
     fn cursor_pos(&self, _area: Rect) -> Option<(u16, u16)> {
         None
@@ -1,3 +1,4 @@
+use codex_core::config_types::EditorMode;
 use codex_core::protocol::TokenUsageInfo;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -23,6 +24,9 @@ use super::footer::FooterProps;
 use super::footer::render_footer;
 use super::paste_burst::CharDecision;
 use super::paste_burst::PasteBurst;
+use super::vim::VimMode;
+use super::vim::VimOutcome;
+use super::vim::VimState;
 use crate::bottom_pane::paste_burst::FlushResult;
 use crate::slash_command::SlashCommand;
 use crate::style::user_message_style;
@@ -35,11 +39,14 @@ use crate::bottom_pane::textarea::TextArea;
 use crate::bottom_pane::textarea::TextAreaState;
 use crate::clipboard_paste::normalize_pasted_path;
 use crate::clipboard_paste::pasted_image_format;
+use crate::file_mentions::Mention;
+use crate::file_mentions::split_line_range;
+use crate::keymap;
+use crate::keymap::KeyAction;
 use crate::ui_consts::LIVE_PREFIX_COLS;
 use codex_file_search::FileMatch;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
 use std::time::Instant;
@@ -53,6 +60,8 @@ const LARGE_PASTE_CHAR_THRESHOLD: usize = 1000;
 pub enum InputResult {
     Submitted(String),
     Command(SlashCommand),
+    /// A command followed by arguments, e.g. `/image <path>`.
+    CommandWithArgs(SlashCommand, String),
     None,
 }
 
@@ -75,6 +84,8 @@ pub(crate) struct ChatComposer {
     current_file_query: Option<String>,
     pending_pastes: Vec<(String, String)>,
     token_usage_info: Option<TokenUsageInfo>,
+    running_background_jobs: usize,
+    unhealthy_mcp_servers: Vec<String>,
     has_focus: bool,
     attached_images: Vec<AttachedImage>,
     placeholder_text: String,
@@ -84,6 +95,8 @@ pub(crate) struct ChatComposer {
     // When true, disables paste-burst logic and inserts characters immediately.
     disable_paste_burst: bool,
     custom_prompts: Vec<CustomPrompt>,
+    /// Modal editing state when `tui.editor_mode = "vim"`.
+    vim: Option<VimState>,
 }
 
 /// Popup state – at most one can be visible at any time.
@@ -118,6 +131,8 @@ impl ChatComposer {
             current_file_query: None,
             pending_pastes: Vec::new(),
             token_usage_info: None,
+            running_background_jobs: 0,
+            unhealthy_mcp_servers: Vec::new(),
             has_focus: has_input_focus,
             attached_images: Vec::new(),
             placeholder_text,
@@ -125,6 +140,7 @@ impl ChatComposer {
             paste_burst: PasteBurst::default(),
             disable_paste_burst: false,
             custom_prompts: Vec::new(),
+            vim: None,
         };
         // Apply configuration via the setter to keep side-effects centralized.
         this.set_disable_paste_burst(disable_paste_burst);
@@ -182,6 +198,14 @@ impl ChatComposer {
         self.token_usage_info = token_info;
     }
 
+    pub(crate) fn set_running_background_jobs(&mut self, running: usize) {
+        self.running_background_jobs = running;
+    }
+
+    pub(crate) fn set_unhealthy_mcp_servers(&mut self, servers: Vec<String>) {
+        self.unhealthy_mcp_servers = servers;
+    }
+
     /// Record the history metadata advertised by `SessionConfiguredEvent` so
     /// that the composer can navigate cross-session history.
     pub(crate) fn set_history_metadata(&mut self, log_id: u64, entry_count: usize) {
@@ -256,6 +280,22 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn set_editor_mode(&mut self, mode: EditorMode) {
+        self.vim = match mode {
+            EditorMode::Default => None,
+            EditorMode::Vim => Some(VimState::new()),
+        };
+        self.textarea.set_selection(None);
+    }
+
+    /// True while vim-style editing is in insert mode, where Esc returns to
+    /// normal mode rather than interrupting or backtracking.
+    pub(crate) fn in_vim_insert_mode(&self) -> bool {
+        self.vim
+            .as_ref()
+            .is_some_and(|vim| vim.mode() == VimMode::Insert)
+    }
+
     /// Replace the entire composer content with `text` and reset cursor.
     pub(crate) fn set_text_content(&mut self, text: String) {
         // Clear any existing content, placeholders, and attachments first.
@@ -336,6 +376,13 @@ impl ChatComposer {
             ActivePopup::None => self.handle_key_event_without_popup(key_event),
         };
 
+        if let Some(vim) = self.vim.as_mut() {
+            if matches!(result.0, InputResult::Submitted(_)) {
+                vim.reset(&self.textarea);
+            }
+            self.textarea.set_selection(vim.selection(&self.textarea));
+        }
+
         // Update (or hide/show) popup after processing the key.
         self.sync_command_popup();
         if matches!(self.active_popup, ActivePopup::Command(_)) {
@@ -420,6 +467,15 @@ impl ChatComposer {
                 ..
             } => {
                 if let Some(sel) = popup.selected_item() {
+                    // Text after the command name, e.g. the path in `/image <path>`
+                    // or the whole, possibly multi-line, message after `/high`.
+                    let args = self
+                        .textarea
+                        .text()
+                        .trim()
+                        .split_once(char::is_whitespace)
+                        .map(|(_, args)| args.trim().to_string())
+                        .unwrap_or_default();
                     // Clear textarea so no residual text remains.
                     self.textarea.set_text("");
                     // Capture any needed data from popup before clearing it.
@@ -433,6 +489,9 @@ impl ChatComposer {
                     self.active_popup = ActivePopup::None;
 
                     match sel {
+                        CommandItem::Builtin(cmd) if !args.is_empty() => {
+                            return (InputResult::CommandWithArgs(cmd, args), true);
+                        }
                         CommandItem::Builtin(cmd) => {
                             return (InputResult::Command(cmd), true);
                         }
@@ -506,6 +565,14 @@ impl ChatComposer {
                 self.active_popup = ActivePopup::None;
                 (InputResult::None, true)
             }
+            KeyEvent {
+                code: KeyCode::Char(' '),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                popup.toggle_mark();
+                (InputResult::None, true)
+            }
             KeyEvent {
                 code: KeyCode::Tab, ..
             }
@@ -514,65 +581,40 @@ impl ChatComposer {
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                let Some(sel) = popup.selected_match() else {
-                    self.active_popup = ActivePopup::None;
+                let paths = popup.chosen_paths();
+                self.active_popup = ActivePopup::None;
+                if paths.is_empty() {
                     return (InputResult::None, true);
-                };
+                }
 
-                let sel_path = sel.to_string();
-                // If selected path looks like an image (png/jpeg), attach as image instead of inserting text.
-                let is_image = Self::is_image_path(&sel_path);
-                if is_image {
-                    // Determine dimensions; if that fails fall back to normal path insertion.
-                    let path_buf = PathBuf::from(&sel_path);
-                    if let Ok((w, h)) = image::image_dimensions(&path_buf) {
-                        // Remove the current @token (mirror logic from insert_selected_path without inserting text)
-                        // using the flat text and byte-offset cursor API.
-                        let cursor_offset = self.textarea.cursor();
-                        let text = self.textarea.text();
-                        // Clamp to a valid char boundary to avoid panics when slicing.
-                        let safe_cursor = Self::clamp_to_char_boundary(text, cursor_offset);
-                        let before_cursor = &text[..safe_cursor];
-                        let after_cursor = &text[safe_cursor..];
-
-                        // Determine token boundaries in the full text.
-                        let start_idx = before_cursor
-                            .char_indices()
-                            .rfind(|(_, c)| c.is_whitespace())
-                            .map(|(idx, c)| idx + c.len_utf8())
-                            .unwrap_or(0);
-                        let end_rel_idx = after_cursor
-                            .char_indices()
-                            .find(|(_, c)| c.is_whitespace())
-                            .map(|(idx, _)| idx)
-                            .unwrap_or(after_cursor.len());
-                        let end_idx = safe_cursor + end_rel_idx;
-
-                        self.textarea.replace_range(start_idx..end_idx, "");
-                        self.textarea.set_cursor(start_idx);
-
-                        let format_label = match Path::new(&sel_path)
-                            .extension()
-                            .and_then(|e| e.to_str())
-                            .map(str::to_ascii_lowercase)
-                        {
-                            Some(ext) if ext == "png" => "PNG",
-                            Some(ext) if ext == "jpg" || ext == "jpeg" => "JPEG",
-                            _ => "IMG",
-                        };
-                        self.attach_image(path_buf, w, h, format_label);
-                        // Add a trailing space to keep typing fluid.
-                        self.textarea.insert_str(" ");
+                // A line range typed after the query (`@main:10-40`) applies
+                // when a single file is chosen.
+                let lines = match paths.as_slice() {
+                    [path] if !path.ends_with('/') => Self::current_at_token(&self.textarea)
+                        .and_then(|token| split_line_range(&token).1),
+                    _ => None,
+                };
+                let mut mentions = String::new();
+                let mut images = Vec::new();
+                for path in paths {
+                    // Images are attached rather than mentioned.
+                    if Self::is_image_path(&path)
+                        && let Ok((w, h)) = image::image_dimensions(&path)
+                    {
+                        images.push((PathBuf::from(path), w, h));
                     } else {
-                        // Fallback to plain path insertion if metadata read fails.
-                        self.insert_selected_path(&sel_path);
+                        mentions.push('@');
+                        mentions.push_str(&Mention { path, lines }.label());
+                        mentions.push(' ');
                     }
-                } else {
-                    // Non-image: inserting file path.
-                    self.insert_selected_path(&sel_path);
                 }
-                // No selection: treat Enter as closing the popup/session.
-                self.active_popup = ActivePopup::None;
+                self.replace_current_token(&mentions);
+                for (path, w, h) in images {
+                    let format_label = pasted_image_format(&path).label();
+                    self.attach_image(path, w, h, format_label);
+                    // Add a trailing space to keep typing fluid.
+                    self.textarea.insert_str(" ");
+                }
                 (InputResult::None, true)
             }
             input => self.handle_input_basic(input),
@@ -664,11 +706,15 @@ impl ChatComposer {
             None
         };
 
+        // `@https://…` mentions attach a URL rather than a file.
+        let is_file_mention = |t: &&str| {
+            t.starts_with('@') && !t.starts_with("@http://") && !t.starts_with("@https://")
+        };
         let left_at = token_left
-            .filter(|t| t.starts_with('@'))
+            .filter(is_file_mention)
             .map(|t| t[1..].to_string());
         let right_at = token_right
-            .filter(|t| t.starts_with('@'))
+            .filter(is_file_mention)
             .map(|t| t[1..].to_string());
 
         if at_whitespace {
@@ -686,12 +732,13 @@ impl ChatComposer {
         left_at.or(right_at)
     }
 
-    /// Replace the active `@token` (the one under the cursor) with `path`.
+    /// Replace the active `@token` (the one under the cursor) with
+    /// `replacement` and move the cursor after it.
     ///
     /// The algorithm mirrors `current_at_token` so replacement works no matter
     /// where the cursor is within the token and regardless of how many
     /// `@tokens` exist in the line.
-    fn insert_selected_path(&mut self, path: &str) {
+    fn replace_current_token(&mut self, replacement: &str) {
         let cursor_offset = self.textarea.cursor();
         let text = self.textarea.text();
         // Clamp to a valid char boundary to avoid panics when slicing.
@@ -714,22 +761,23 @@ impl ChatComposer {
             .unwrap_or(after_cursor.len());
         let end_idx = safe_cursor + end_rel_idx;
 
-        // Replace the slice `[start_idx, end_idx)` with the chosen path and a trailing space.
-        let mut new_text =
-            String::with_capacity(text.len() - (end_idx - start_idx) + path.len() + 1);
-        new_text.push_str(&text[..start_idx]);
-        new_text.push_str(path);
-        new_text.push(' ');
-        new_text.push_str(&text[end_idx..]);
-
-        self.textarea.set_text(&new_text);
-        let new_cursor = start_idx.saturating_add(path.len()).saturating_add(1);
-        self.textarea.set_cursor(new_cursor);
+        self.textarea.replace_range(start_idx..end_idx, replacement);
+        self.textarea.set_cursor(start_idx + replacement.len());
     }
 
     /// Handle key event when no popup is visible.
     fn handle_key_event_without_popup(&mut self, key_event: KeyEvent) -> (InputResult, bool) {
-        match key_event {
+        if self.in_vim_insert_mode() && key_event.code == KeyCode::Esc {
+            // Text still held by paste-burst detection is typed before leaving
+            // insert mode.
+            self.handle_paste_burst_flush(Instant::now() + Self::recommended_paste_flush_delay());
+        }
+        if let Some(vim) = self.vim.as_mut()
+            && vim.handle_key(&mut self.textarea, key_event) == VimOutcome::Handled
+        {
+            return (InputResult::None, true);
+        }
+        match Self::apply_keymap(key_event) {
             KeyEvent {
                 code: KeyCode::Char('d'),
                 modifiers: crossterm::event::KeyModifiers::CONTROL,
@@ -841,6 +889,32 @@ impl ChatComposer {
         }
     }
 
+    /// Maps the configured submit and newline keys onto the keys handled
+    /// below: plain Enter submits and Shift+Enter inserts a newline. Enter
+    /// inserts a newline when it is not bound to submit.
+    fn apply_keymap(key_event: KeyEvent) -> KeyEvent {
+        let keymap = keymap::current();
+        let plain_enter = key_event.code == KeyCode::Enter && key_event.modifiers.is_empty();
+        // Steering submits too; the chat widget decides where the message goes.
+        if keymap.matches(KeyAction::Submit, &key_event)
+            || keymap.matches(KeyAction::Steer, &key_event)
+        {
+            KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
+                ..key_event
+            }
+        } else if plain_enter || keymap.matches(KeyAction::Newline, &key_event) {
+            KeyEvent {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::SHIFT,
+                ..key_event
+            }
+        } else {
+            key_event
+        }
+    }
+
     fn handle_paste_burst_flush(&mut self, now: Instant) -> bool {
         match self.paste_burst.flush_if_due(now) {
             FlushResult::Paste(pasted) => {
@@ -1180,7 +1254,7 @@ impl ChatComposer {
     /// Note this is only called when self.active_popup is NOT Command.
     fn sync_file_search_popup(&mut self) {
         // Determine if there is an @token underneath the cursor.
-        let query = match Self::current_at_token(&self.textarea) {
+        let token = match Self::current_at_token(&self.textarea) {
             Some(token) => token,
             None => {
                 self.active_popup = ActivePopup::None;
@@ -1190,10 +1264,13 @@ impl ChatComposer {
         };
 
         // If user dismissed popup for this exact query, don't reopen until text changes.
-        if self.dismissed_file_popup_token.as_ref() == Some(&query) {
+        if self.dismissed_file_popup_token.as_ref() == Some(&token) {
             return;
         }
 
+        // Search for the path alone when a line range follows it.
+        let query = split_line_range(&token).0.to_string();
+
         if !query.is_empty() {
             self.app_event_tx
                 .send(AppEvent::StartFileSearch(query.clone()));
@@ -1264,6 +1341,9 @@ impl WidgetRef for ChatComposer {
                         esc_backtrack_hint: self.esc_backtrack_hint,
                         use_shift_enter_hint: self.use_shift_enter_hint,
                         token_usage_info: self.token_usage_info.as_ref(),
+                        running_background_jobs: self.running_background_jobs,
+                        unhealthy_mcp_servers: &self.unhealthy_mcp_servers,
+                        vim_mode: self.vim.as_ref().map(|vim| vim.mode().label()),
                     },
                 );
             }
@@ -1404,6 +1484,12 @@ mod tests {
             ),
             ("@ hello", 2, None, "@ followed by space"),
             ("test @ world", 6, None, "@ with spaces around"),
+            (
+                "read @https://docs.rs/tokio",
+                10,
+                None,
+                "URL mentions do not search files",
+            ),
         ];
 
         for (input, cursor_pos, expected, description) in test_cases {
@@ -1539,6 +1625,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn vim_mode_edits_in_normal_mode_and_submits() {
+        use crossterm::event::KeyCode;
+        use crossterm::event::KeyEvent;
+        use crossterm::event::KeyModifiers;
+
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            true,
+        );
+        composer.set_editor_mode(codex_core::config_types::EditorMode::Vim);
+        assert!(composer.in_vim_insert_mode());
+
+        composer.handle_paste("hello world".to_string());
+        for code in [KeyCode::Esc, KeyCode::Char('b'), KeyCode::Char('D')] {
+            composer.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        assert!(!composer.in_vim_insert_mode());
+        assert_eq!(composer.textarea.text(), "hello ");
+
+        let (result, _) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(result, InputResult::Submitted("hello".to_string()));
+        assert!(composer.in_vim_insert_mode());
+    }
+
     #[test]
     fn empty_enter_returns_none() {
         use crossterm::event::KeyCode;
@@ -1788,7 +1905,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
-            InputResult::None => panic!("expected Command result for '/init'"),
+            InputResult::None | InputResult::CommandWithArgs(..) => {
+                panic!("expected Command result for '/init'")
+            }
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
     }
@@ -1809,7 +1928,8 @@ mod tests {
             false,
         );
 
-        type_chars_humanlike(&mut composer, &['/', 'c']);
+        // "/c" alone would complete to "/checkpoint".
+        type_chars_humanlike(&mut composer, &['/', 'c', 'o']);
 
         let (_result, _needs_redraw) =
             composer.handle_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
@@ -1846,13 +1966,48 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
-            InputResult::None => panic!("expected Command result for '/mention'"),
+            InputResult::None | InputResult::CommandWithArgs(..) => {
+                panic!("expected Command result for '/mention'")
+            }
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
         composer.insert_str("@");
         assert_eq!(composer.textarea.text(), "@");
     }
 
+    #[test]
+    fn slash_command_arguments_are_passed_along() {
+        use crossterm::event::KeyCode;
+        use crossterm::event::KeyEvent;
+        use crossterm::event::KeyModifiers;
+
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+
+        type_chars_humanlike(
+            &mut composer,
+            &[
+                '/', 'i', 'm', 'a', 'g', 'e', ' ', 'a', ' ', 'b', '.', 'p', 'n', 'g', ' ',
+            ],
+        );
+
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(
+            result,
+            InputResult::CommandWithArgs(SlashCommand::Image, "a b.png".to_string())
+        );
+        assert!(composer.textarea.is_empty(), "composer should be cleared");
+    }
+
     #[test]
     fn test_multiple_pastes_submission() {
         use crossterm::event::KeyCode;
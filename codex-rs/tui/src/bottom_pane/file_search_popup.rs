@@ -21,6 +21,9 @@ pub(crate) struct FileSearchPopup {
     matches: Vec<FileMatch>,
     /// Shared selection/scroll state.
     state: ScrollState,
+    /// Paths marked for insertion, in the order they were marked. They stay
+    /// marked while the query changes.
+    marked: Vec<String>,
 }
 
 impl FileSearchPopup {
@@ -31,6 +34,7 @@ impl FileSearchPopup {
             waiting: true,
             matches: Vec::new(),
             state: ScrollState::new(),
+            marked: Vec::new(),
         }
     }
 
@@ -101,6 +105,29 @@ impl FileSearchPopup {
             .map(|file_match| file_match.path.as_str())
     }
 
+    /// Marks the selected path for insertion, or unmarks it.
+    pub(crate) fn toggle_mark(&mut self) {
+        let Some(path) = self.selected_match().map(str::to_string) else {
+            return;
+        };
+        if let Some(index) = self.marked.iter().position(|marked| *marked == path) {
+            self.marked.remove(index);
+        } else {
+            self.marked.push(path);
+        }
+    }
+
+    /// The paths to insert: the marked ones, or else the selected one.
+    pub(crate) fn chosen_paths(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            self.selected_match()
+                .map(|path| vec![path.to_string()])
+                .unwrap_or_default()
+        } else {
+            self.marked.clone()
+        }
+    }
+
     pub(crate) fn calculate_required_height(&self) -> u16 {
         // Row count depends on whether we already have matches. If no matches
         // yet (e.g. initial search or query with no results) reserve a single
@@ -127,7 +154,7 @@ impl WidgetRef for &FileSearchPopup {
                         .as_ref()
                         .map(|v| v.iter().map(|&i| i as usize).collect()),
                     is_current: false,
-                    description: None,
+                    description: self.marked.contains(&m.path).then(|| "✓".to_string()),
                 })
                 .collect()
         };
@@ -149,3 +176,42 @@ impl WidgetRef for &FileSearchPopup {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn file_match(path: &str) -> FileMatch {
+        FileMatch {
+            score: 0,
+            path: path.to_string(),
+            indices: None,
+        }
+    }
+
+    #[test]
+    fn marked_paths_survive_new_queries() {
+        let mut popup = FileSearchPopup::new();
+        popup.set_query("ma");
+        popup.set_matches(
+            "ma",
+            vec![file_match("src/main.rs"), file_match("Makefile")],
+        );
+        assert_eq!(popup.chosen_paths(), vec!["src/main.rs".to_string()]);
+
+        popup.move_down();
+        popup.toggle_mark();
+        popup.set_query("lib");
+        popup.set_matches("lib", vec![file_match("src/lib.rs")]);
+        popup.toggle_mark();
+
+        assert_eq!(
+            popup.chosen_paths(),
+            vec!["Makefile".to_string(), "src/lib.rs".to_string()]
+        );
+
+        popup.toggle_mark();
+        assert_eq!(popup.chosen_paths(), vec!["Makefile".to_string()]);
+    }
+}
@@ -4,7 +4,6 @@ use crossterm::event::KeyCode;
 use crossterm::event::KeyModifiers;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::style::Stylize;
@@ -13,6 +12,7 @@ use ratatui::text::Span;
 use ratatui::widgets::WidgetRef;
 
 use crate::key_hint;
+use crate::theme;
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct FooterProps<'a> {
@@ -21,6 +21,12 @@ pub(crate) struct FooterProps<'a> {
     pub(crate) esc_backtrack_hint: bool,
     pub(crate) use_shift_enter_hint: bool,
     pub(crate) token_usage_info: Option<&'a TokenUsageInfo>,
+    /// Background jobs started by the agent that are still running.
+    pub(crate) running_background_jobs: usize,
+    /// MCP servers that crashed or stopped answering.
+    pub(crate) unhealthy_mcp_servers: &'a [String],
+    /// The vim editing mode, when `tui.editor_mode = "vim"`.
+    pub(crate) vim_mode: Option<&'static str>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -53,6 +59,16 @@ pub(crate) fn render_footer(area: Rect, buf: &mut Buffer, props: FooterProps<'_>
     };
 
     let mut spans = footer_spans(content);
+    if let Some(mode) = props.vim_mode {
+        spans.insert(0, Span::from(format!("-- {mode} --")).bold());
+        spans.insert(1, "   ".into());
+    }
+    if props.running_background_jobs > 0 {
+        append_background_jobs_spans(&mut spans, props.running_background_jobs);
+    }
+    if !props.unhealthy_mcp_servers.is_empty() {
+        append_unhealthy_mcp_servers_spans(&mut spans, props.unhealthy_mcp_servers);
+    }
     if let Some(token_usage_info) = props.token_usage_info {
         append_token_usage_spans(&mut spans, token_usage_info);
     }
@@ -71,6 +87,23 @@ fn footer_spans(content: FooterContent) -> Vec<Span<'static>> {
     }
 }
 
+fn append_background_jobs_spans(spans: &mut Vec<Span<'static>>, running: usize) {
+    let noun = if running == 1 { "job" } else { "jobs" };
+    spans.push("   ".into());
+    spans.push(Span::styled(
+        format!("{running} background {noun} running"),
+        theme::current().status_accent,
+    ));
+}
+
+fn append_unhealthy_mcp_servers_spans(spans: &mut Vec<Span<'static>>, servers: &[String]) {
+    spans.push("   ".into());
+    spans.push(Span::styled(
+        format!("⚠ MCP: {}", servers.join(", ")),
+        theme::current().status_warning,
+    ));
+}
+
 fn append_token_usage_spans(spans: &mut Vec<Span<'static>>, token_usage_info: &TokenUsageInfo) {
     let token_usage = &token_usage_info.total_token_usage;
     spans.push("   ".into());
@@ -81,6 +114,13 @@ fn append_token_usage_spans(spans: &mut Vec<Span<'static>>, token_usage_info: &T
         ))
         .style(Style::default().add_modifier(Modifier::DIM)),
     );
+    if let Some(cost) = token_usage_info.estimated_cost_usd {
+        spans.push("   ".into());
+        spans.push(
+            Span::from(format!("${cost:.2} spent"))
+                .style(Style::default().add_modifier(Modifier::DIM)),
+        );
+    }
 
     let last_token_usage = &token_usage_info.last_token_usage;
     if let Some(context_window) = token_usage_info.model_context_window {
@@ -91,7 +131,7 @@ fn append_token_usage_spans(spans: &mut Vec<Span<'static>>, token_usage_info: &T
         };
 
         let context_style = if percent_remaining < 20 {
-            Style::default().fg(Color::Yellow)
+            theme::current().status_warning
         } else {
             Style::default().add_modifier(Modifier::DIM)
         };
@@ -334,6 +374,7 @@ mod tests {
             total_token_usage: usage,
             last_token_usage: last,
             model_context_window: Some(context_window),
+            estimated_cost_usd: None,
         }
     }
 
@@ -347,6 +388,9 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 token_usage_info: None,
+                running_background_jobs: 0,
+                unhealthy_mcp_servers: &[],
+                vim_mode: None,
             },
         );
 
@@ -358,6 +402,9 @@ mod tests {
                 esc_backtrack_hint: true,
                 use_shift_enter_hint: true,
                 token_usage_info: Some(&token_usage(4_200, 900, 8_000)),
+                running_background_jobs: 0,
+                unhealthy_mcp_servers: &[],
+                vim_mode: None,
             },
         );
 
@@ -369,6 +416,9 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 token_usage_info: None,
+                running_background_jobs: 0,
+                unhealthy_mcp_servers: &[],
+                vim_mode: None,
             },
         );
 
@@ -380,6 +430,23 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 token_usage_info: None,
+                running_background_jobs: 0,
+                unhealthy_mcp_servers: &[],
+                vim_mode: None,
+            },
+        );
+
+        snapshot_footer(
+            "footer_background_jobs_running",
+            FooterProps {
+                ctrl_c_quit_hint: false,
+                is_task_running: true,
+                esc_backtrack_hint: false,
+                use_shift_enter_hint: false,
+                token_usage_info: None,
+                running_background_jobs: 2,
+                unhealthy_mcp_servers: &[],
+                vim_mode: None,
             },
         );
     }
@@ -232,6 +232,22 @@ impl ListSelectionView {
         self.last_selected_actual_idx.take()
     }
 
+    /// The item under the cursor.
+    pub(crate) fn selected_index(&self) -> Option<usize> {
+        self.state
+            .selected_idx
+            .and_then(|idx| self.filtered_indices.get(idx).copied())
+    }
+
+    /// Moves the cursor to the item `actual_idx`, if it is shown.
+    pub(crate) fn select_index(&mut self, actual_idx: usize) {
+        if let Some(idx) = self.filtered_indices.iter().position(|i| *i == actual_idx) {
+            self.state.selected_idx = Some(idx);
+            let len = self.visible_len();
+            self.state.ensure_visible(len, Self::max_visible_rows(len));
+        }
+    }
+
     fn header_spans_for_width(&self, width: u16) -> Vec<Vec<Span<'static>>> {
         if self.header.is_empty() || width == 0 {
             return Vec::new();
@@ -308,6 +324,15 @@ impl BottomPaneView for ListSelectionView {
         }
     }
 
+    fn handle_item_click(&mut self, number: usize) -> bool {
+        if number == 0 || number > self.visible_len() {
+            return false;
+        }
+        self.state.selected_idx = Some(number - 1);
+        self.accept();
+        true
+    }
+
     fn is_complete(&self) -> bool {
         self.complete
     }
@@ -581,4 +606,22 @@ mod tests {
         let lines = render_lines(&view);
         assert!(lines.contains("▌ filters"));
     }
+
+    #[test]
+    fn clicking_a_numbered_row_accepts_that_item() {
+        let mut view = make_selection_view(None);
+        let area = Rect::new(0, 0, 48, BottomPaneView::desired_height(&view, 48));
+        let mut buf = Buffer::empty(area);
+        view.render(area, &mut buf);
+
+        let numbers: Vec<Option<usize>> = (0..area.height)
+            .map(|row| crate::mouse::list_item_at(&buf, row))
+            .collect();
+        assert_eq!(numbers, vec![None, None, Some(1), Some(2), None, None]);
+
+        assert!(!view.handle_item_click(3));
+        assert!(view.handle_item_click(2));
+        assert_eq!(view.take_last_selected_index(), Some(1));
+        assert!(view.is_complete());
+    }
 }
@@ -2,8 +2,11 @@
 use std::path::PathBuf;
 
 use crate::app_event_sender::AppEventSender;
+use crate::keymap;
+use crate::keymap::KeyAction;
 use crate::tui::FrameRequester;
 use bottom_pane_view::BottomPaneView;
+use codex_core::config_types::EditorMode;
 use codex_core::protocol::TokenUsageInfo;
 use codex_file_search::FileMatch;
 use crossterm::event::KeyCode;
@@ -30,8 +33,10 @@ pub(crate) use list_selection_view::SelectionViewParams;
 mod paste_burst;
 pub mod popup_consts;
 mod scroll_state;
+mod secret_prompt_view;
 mod selection_popup_common;
 mod textarea;
+mod vim;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum CancellationEvent {
@@ -77,20 +82,23 @@ pub(crate) struct BottomPaneParams {
     pub(crate) enhanced_keys_supported: bool,
     pub(crate) placeholder_text: String,
     pub(crate) disable_paste_burst: bool,
+    pub(crate) editor_mode: EditorMode,
 }
 
 impl BottomPane {
     const BOTTOM_PAD_LINES: u16 = 1;
     pub fn new(params: BottomPaneParams) -> Self {
         let enhanced_keys_supported = params.enhanced_keys_supported;
+        let mut composer = ChatComposer::new(
+            params.has_input_focus,
+            params.app_event_tx.clone(),
+            enhanced_keys_supported,
+            params.placeholder_text,
+            params.disable_paste_burst,
+        );
+        composer.set_editor_mode(params.editor_mode);
         Self {
-            composer: ChatComposer::new(
-                params.has_input_focus,
-                params.app_event_tx.clone(),
-                enhanced_keys_supported,
-                params.placeholder_text,
-                params.disable_paste_burst,
-            ),
+            composer,
             view_stack: Vec::new(),
             app_event_tx: params.app_event_tx,
             frame_requester: params.frame_requester,
@@ -195,9 +203,11 @@ impl BottomPane {
             self.request_redraw();
             InputResult::None
         } else {
-            // If a task is running and a status line is visible, allow Esc to
-            // send an interrupt even while the composer has focus.
-            if matches!(key_event.code, crossterm::event::KeyCode::Esc)
+            // If a task is running and a status line is visible, allow the
+            // interrupt key (Esc by default) to send an interrupt even while
+            // the composer has focus.
+            if keymap::current().matches(KeyAction::Interrupt, &key_event)
+                && !(key_event.code == KeyCode::Esc && self.composer.in_vim_insert_mode())
                 && self.is_task_running
                 && let Some(status) = &self.status
             {
@@ -257,6 +267,22 @@ impl BottomPane {
         }
     }
 
+    /// Forwards a click on the numbered list item `number` to the active
+    /// view. Returns true if the view consumed it.
+    pub(crate) fn handle_item_click(&mut self, number: usize) -> bool {
+        let Some(view) = self.view_stack.last_mut() else {
+            return false;
+        };
+        if !view.handle_item_click(number) {
+            return false;
+        }
+        if view.is_complete() {
+            self.on_active_view_complete();
+        }
+        self.request_redraw();
+        true
+    }
+
     pub(crate) fn insert_str(&mut self, text: &str) {
         self.composer.insert_str(text);
         self.request_redraw();
@@ -374,7 +400,10 @@ impl BottomPane {
     /// overlays or popups and not running a task. This is the safe context to
     /// use Esc-Esc for backtracking from the main view.
     pub(crate) fn is_normal_backtrack_mode(&self) -> bool {
-        !self.is_task_running && self.view_stack.is_empty() && !self.composer.popup_active()
+        !self.is_task_running
+            && self.view_stack.is_empty()
+            && !self.composer.popup_active()
+            && !self.composer.in_vim_insert_mode()
     }
 
     /// Update the *context-window remaining* indicator in the composer. This
@@ -384,6 +413,18 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Update the count of running background jobs shown in the footer.
+    pub(crate) fn set_running_background_jobs(&mut self, running: usize) {
+        self.composer.set_running_background_jobs(running);
+        self.request_redraw();
+    }
+
+    /// Update the MCP servers shown as unhealthy in the footer.
+    pub(crate) fn set_unhealthy_mcp_servers(&mut self, servers: Vec<String>) {
+        self.composer.set_unhealthy_mcp_servers(servers);
+        self.request_redraw();
+    }
+
     pub(crate) fn show_view(&mut self, view: Box<dyn BottomPaneView>) {
         self.push_view(view);
     }
@@ -408,6 +449,14 @@ impl BottomPane {
         self.push_view(Box::new(modal));
     }
 
+    /// Called when the agent asks the user for a secret.
+    pub(crate) fn push_secret_request(&mut self, id: String, name: String, reason: Option<String>) {
+        let view =
+            secret_prompt_view::SecretPromptView::new(id, name, reason, self.app_event_tx.clone());
+        self.pause_status_timer_for_modal();
+        self.push_view(Box::new(view));
+    }
+
     fn on_active_view_complete(&mut self) {
         self.resume_status_timer_after_modal();
     }
@@ -533,6 +582,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            editor_mode: EditorMode::Default,
         });
         pane.push_approval_request(exec_request());
         assert_eq!(CancellationEvent::Handled, pane.on_ctrl_c());
@@ -553,6 +603,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            editor_mode: EditorMode::Default,
         });
 
         // Create an approval modal (active view).
@@ -584,6 +635,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            editor_mode: EditorMode::Default,
         });
 
         // Start a running task so the status indicator is active above the composer.
@@ -652,6 +704,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            editor_mode: EditorMode::Default,
         });
 
         // Begin a task: show initial status.
@@ -683,6 +736,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            editor_mode: EditorMode::Default,
         });
 
         // Activate spinner (status view replaces composer) with no live ring.
@@ -734,6 +788,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            editor_mode: EditorMode::Default,
         });
 
         pane.set_task_running(true);
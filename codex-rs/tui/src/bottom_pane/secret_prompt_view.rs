@@ -0,0 +1,235 @@
+use codex_core::protocol::Op;
+use codex_core::protocol::SecretValue;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use ratatui::widgets::Wrap;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+
+use super::CancellationEvent;
+use super::bottom_pane_view::BottomPaneView;
+
+/// Character drawn for every character of the secret.
+const MASK_CHAR: char = '•';
+
+/// Masked single-line input for a secret requested by the agent. The value is
+/// sent straight to the session and never shown or added to the history.
+pub(crate) struct SecretPromptView {
+    id: String,
+    name: String,
+    reason: Option<String>,
+    value: String,
+    app_event_tx: AppEventSender,
+    complete: bool,
+}
+
+impl SecretPromptView {
+    pub(crate) fn new(
+        id: String,
+        name: String,
+        reason: Option<String>,
+        app_event_tx: AppEventSender,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            reason,
+            value: String::new(),
+            app_event_tx,
+            complete: false,
+        }
+    }
+
+    fn send(&mut self, value: Option<SecretValue>) {
+        self.app_event_tx.send(AppEvent::CodexOp(Op::ProvideSecret {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            value,
+        }));
+        self.value.clear();
+        self.complete = true;
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(vec![
+            gutter(),
+            "Codex needs ".bold(),
+            self.name.clone().bold().cyan(),
+            " to run a command".bold(),
+        ])];
+        if let Some(reason) = &self.reason {
+            lines.push(Line::from(vec![gutter(), reason.clone().into()]));
+        }
+        lines.push(Line::from(vec![gutter()]));
+        let masked: String = self.value.chars().map(|_| MASK_CHAR).collect();
+        lines.push(Line::from(vec![gutter(), "> ".dim(), masked.into()]));
+        lines.push(Line::from(vec![gutter()]));
+        lines.push(Line::from(vec![
+            gutter(),
+            "Only the command receives this value; it is not sent to the model.".dim(),
+        ]));
+        lines.push(Line::from(vec![
+            gutter(),
+            "Enter".bold(),
+            " to submit · ".dim(),
+            "Esc".bold(),
+            " to decline".dim(),
+        ]));
+        lines
+    }
+}
+
+impl BottomPaneView for SecretPromptView {
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => {
+                self.send(None);
+            }
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => {
+                if !self.value.is_empty() {
+                    let value = SecretValue::new(std::mem::take(&mut self.value));
+                    self.send(Some(value));
+                }
+            }
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => {
+                self.value.pop();
+            }
+            KeyEvent {
+                code: KeyCode::Char('u'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => {
+                self.value.clear();
+            }
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers,
+                ..
+            } if !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.value.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_ctrl_c(&mut self) -> CancellationEvent {
+        self.send(None);
+        CancellationEvent::Handled
+    }
+
+    fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn desired_height(&self, width: u16) -> u16 {
+        Paragraph::new(self.lines())
+            .wrap(Wrap { trim: false })
+            .line_count(width)
+            .try_into()
+            .unwrap_or(u16::MAX)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(self.lines())
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+
+    fn handle_paste(&mut self, pasted: String) -> bool {
+        let pasted = pasted.trim_end_matches(['\r', '\n']);
+        if pasted.is_empty() {
+            return false;
+        }
+        self.value.push_str(pasted);
+        true
+    }
+}
+
+fn gutter() -> Span<'static> {
+    "▌ ".cyan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_event::AppEvent;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn view() -> (
+        SecretPromptView,
+        tokio::sync::mpsc::UnboundedReceiver<AppEvent>,
+    ) {
+        let (tx, rx) = unbounded_channel::<AppEvent>();
+        let view = SecretPromptView::new(
+            "sub-1".to_string(),
+            "NPM_TOKEN".to_string(),
+            Some("publish the package".to_string()),
+            AppEventSender::new(tx),
+        );
+        (view, rx)
+    }
+
+    fn render_to_string(view: &SecretPromptView) -> String {
+        let area = Rect::new(0, 0, 60, view.desired_height(60));
+        let mut buf = Buffer::empty(area);
+        view.render(area, &mut buf);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn typed_value_is_masked_and_submitted() {
+        let (mut view, mut rx) = view();
+        view.handle_paste("s3cr3t\n".to_string());
+        let rendered = render_to_string(&view);
+        assert!(rendered.contains("••••••"));
+        assert!(!rendered.contains("s3cr3t"));
+
+        view.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(view.is_complete());
+        match rx.try_recv() {
+            Ok(AppEvent::CodexOp(Op::ProvideSecret { id, name, value })) => {
+                assert_eq!(id, "sub-1");
+                assert_eq!(name, "NPM_TOKEN");
+                assert_eq!(value, Some(SecretValue::new("s3cr3t".to_string())));
+            }
+            other => panic!("expected ProvideSecret, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn escape_declines() {
+        let (mut view, mut rx) = view();
+        view.handle_key_event(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        view.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(view.is_complete());
+        match rx.try_recv() {
+            Ok(AppEvent::CodexOp(Op::ProvideSecret { value, .. })) => assert_eq!(value, None),
+            other => panic!("expected ProvideSecret, got {other:?}"),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crossterm::event::KeyModifiers;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::Color;
+use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::widgets::StatefulWidgetRef;
 use ratatui::widgets::WidgetRef;
@@ -26,6 +27,8 @@ pub(crate) struct TextArea {
     wrap_cache: RefCell<Option<WrapCache>>,
     preferred_col: Option<usize>,
     elements: Vec<TextElement>,
+    /// Text highlighted as selected, e.g. in vim's visual mode.
+    selection: Option<Range<usize>>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +51,7 @@ impl TextArea {
             wrap_cache: RefCell::new(None),
             preferred_col: None,
             elements: Vec::new(),
+            selection: None,
         }
     }
 
@@ -57,6 +61,7 @@ impl TextArea {
         self.wrap_cache.replace(None);
         self.preferred_col = None;
         self.elements.clear();
+        self.selection = None;
     }
 
     pub fn text(&self) -> &str {
@@ -76,6 +81,7 @@ impl TextArea {
         }
         self.shift_elements(pos, 0, text.len());
         self.preferred_col = None;
+        self.selection = None;
     }
 
     pub fn replace_range(&mut self, range: std::ops::Range<usize>, text: &str) {
@@ -97,6 +103,7 @@ impl TextArea {
         self.text.replace_range(range, text);
         self.wrap_cache.replace(None);
         self.preferred_col = None;
+        self.selection = None;
         self.update_elements_after_replace(start, end, inserted_len);
 
         // Update the cursor position to account for the edit.
@@ -126,6 +133,15 @@ impl TextArea {
         self.preferred_col = None;
     }
 
+    /// Highlights `selection`; cleared whenever the text changes.
+    pub(crate) fn set_selection(&mut self, selection: Option<Range<usize>>) {
+        self.selection = selection.filter(|range| {
+            range.end <= self.text.len()
+                && self.text.is_char_boundary(range.start)
+                && self.text.is_char_boundary(range.end)
+        });
+    }
+
     pub fn desired_height(&self, width: u16) -> u16 {
         self.wrapped_lines(width).len() as u16
     }
@@ -942,6 +958,20 @@ impl TextArea {
                 let style = Style::default().fg(Color::Cyan);
                 buf.set_string(area.x + x_off, y, styled, style);
             }
+
+            if let Some(selection) = &self.selection {
+                let start = selection.start.max(line_range.start);
+                let end = selection.end.min(line_range.end);
+                if start < end {
+                    let x_off = self.text[line_range.start..start].width() as u16;
+                    let style = Style::default().add_modifier(Modifier::REVERSED);
+                    buf.set_style(
+                        Rect::new(area.x + x_off, y, self.text[start..end].width() as u16, 1)
+                            .intersection(area),
+                        style,
+                    );
+                }
+            }
         }
     }
 }
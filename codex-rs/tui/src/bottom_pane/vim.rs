@@ -0,0 +1,1103 @@
+//! Vim-style modal editing for the composer (`tui.editor_mode = "vim"`).
+//!
+//! The composer starts in insert mode, where keys behave as usual. Esc
+//! switches to normal mode, where keys are counts, motions and operators
+//! acting on the textarea, and `v`/`V` start selecting text in visual mode.
+//! Deleted and yanked text goes to the unnamed register, to `"a`–`"z` when
+//! one is named first, and yanks also to `"0`.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+
+use super::textarea::TextArea;
+
+/// Undo states kept per message.
+const UNDO_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VimMode {
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+impl VimMode {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+            VimMode::Visual => "VISUAL",
+            VimMode::VisualLine => "VISUAL LINE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VimOutcome {
+    /// The key was consumed.
+    Handled,
+    /// The key gets the composer's usual handling: typing in insert mode,
+    /// Enter to submit, arrows, and keys with Ctrl or Alt.
+    PassThrough,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Register {
+    text: String,
+    /// Whole lines, pasted above or below the current line.
+    linewise: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+/// `f`, `F`, `t` or `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FindSpec {
+    forward: bool,
+    till: bool,
+}
+
+/// A key that needs the next key to complete it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    None,
+    Register,
+    G,
+    Find(FindSpec),
+    Replace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward {
+        big: bool,
+    },
+    WordBackward {
+        big: bool,
+    },
+    WordEnd {
+        big: bool,
+    },
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    /// `gg` or `G`: the given 1-based line, or the last one.
+    Line(Option<usize>),
+    Find {
+        spec: FindSpec,
+        ch: char,
+    },
+}
+
+impl Motion {
+    fn linewise(self) -> bool {
+        matches!(self, Motion::Up | Motion::Down | Motion::Line(_))
+    }
+
+    fn inclusive(self) -> bool {
+        matches!(
+            self,
+            Motion::WordEnd { .. } | Motion::LineEnd | Motion::Find { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    text: String,
+    cursor: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct VimState {
+    mode: VimMode,
+    operator: Option<Operator>,
+    pending: Pending,
+    /// Count typed before the operator or motion, e.g. the 2 in `2dw`.
+    count: Option<usize>,
+    /// Count typed after the operator, e.g. the 3 in `d3w`.
+    motion_count: Option<usize>,
+    register: Option<char>,
+    registers: HashMap<char, Register>,
+    visual_anchor: usize,
+    /// The text before the current insert, recorded for undo when it ends.
+    insert_start: Option<Snapshot>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl VimState {
+    pub(crate) fn new() -> Self {
+        Self {
+            mode: VimMode::Insert,
+            operator: None,
+            pending: Pending::None,
+            count: None,
+            motion_count: None,
+            register: None,
+            registers: HashMap::new(),
+            visual_anchor: 0,
+            insert_start: Some(Snapshot {
+                text: String::new(),
+                cursor: 0,
+            }),
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub(crate) fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Back to insert mode with an empty undo history, after the message
+    /// was submitted. Registers are kept.
+    pub(crate) fn reset(&mut self, textarea: &TextArea) {
+        self.mode = VimMode::Insert;
+        self.clear_pending();
+        self.insert_start = Some(snapshot(textarea));
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// The text selected in visual mode.
+    pub(crate) fn selection(&self, textarea: &TextArea) -> Option<Range<usize>> {
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        match self.mode {
+            VimMode::Visual | VimMode::VisualLine => Some(self.visual_range(text, cursor).0),
+            VimMode::Normal | VimMode::Insert => None,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, textarea: &mut TextArea, key: KeyEvent) -> VimOutcome {
+        if key.kind == KeyEventKind::Release {
+            return VimOutcome::PassThrough;
+        }
+        if self.mode == VimMode::Insert {
+            if key.code != KeyCode::Esc {
+                return VimOutcome::PassThrough;
+            }
+            if let Some(start) = self.insert_start.take()
+                && start.text != textarea.text()
+            {
+                self.push_undo(start);
+            }
+            self.mode = VimMode::Normal;
+            let text = textarea.text();
+            let cursor = textarea.cursor();
+            if cursor > line_start(text, cursor) {
+                let pos = prev_char(text, cursor);
+                textarea.set_cursor(pos);
+            }
+            return VimOutcome::Handled;
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if key.code == KeyCode::Char('r') && self.mode == VimMode::Normal {
+                self.clear_pending();
+                self.redo(textarea);
+                return VimOutcome::Handled;
+            }
+            return VimOutcome::PassThrough;
+        }
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            return VimOutcome::PassThrough;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.clear_pending();
+                self.leave_visual(textarea);
+                VimOutcome::Handled
+            }
+            KeyCode::Enter => {
+                self.clear_pending();
+                self.leave_visual(textarea);
+                VimOutcome::PassThrough
+            }
+            KeyCode::Backspace => self.handle_char(textarea, 'h'),
+            KeyCode::Delete => self.handle_char(textarea, 'x'),
+            KeyCode::Char(ch) => self.handle_char(textarea, ch),
+            _ => VimOutcome::PassThrough,
+        }
+    }
+
+    fn handle_char(&mut self, textarea: &mut TextArea, ch: char) -> VimOutcome {
+        match std::mem::replace(&mut self.pending, Pending::None) {
+            Pending::Register => {
+                if ch.is_ascii_alphanumeric() || ch == '"' {
+                    self.register = Some(ch.to_ascii_lowercase());
+                }
+                return VimOutcome::Handled;
+            }
+            Pending::G => {
+                if ch == 'g' {
+                    let line = self.explicit_count().unwrap_or(1);
+                    self.apply_motion(textarea, Motion::Line(Some(line)));
+                } else {
+                    self.clear_pending();
+                }
+                return VimOutcome::Handled;
+            }
+            Pending::Find(spec) => {
+                self.apply_motion(textarea, Motion::Find { spec, ch });
+                return VimOutcome::Handled;
+            }
+            Pending::Replace => {
+                let count = self.take_count();
+                self.replace_chars(textarea, ch, count);
+                return VimOutcome::Handled;
+            }
+            Pending::None => {}
+        }
+
+        if let Some(digit) = ch.to_digit(10)
+            && (digit != 0 || self.current_count().is_some())
+        {
+            let slot = if self.operator.is_some() {
+                &mut self.motion_count
+            } else {
+                &mut self.count
+            };
+            *slot = Some(
+                slot.unwrap_or(0)
+                    .saturating_mul(10)
+                    .saturating_add(digit as usize),
+            );
+            return VimOutcome::Handled;
+        }
+
+        let visual = matches!(self.mode, VimMode::Visual | VimMode::VisualLine);
+        let motion = match ch {
+            'h' => Some(Motion::Left),
+            'l' | ' ' => Some(Motion::Right),
+            'j' => Some(Motion::Down),
+            'k' => Some(Motion::Up),
+            'w' | 'W' => Some(Motion::WordForward { big: ch == 'W' }),
+            'b' | 'B' => Some(Motion::WordBackward { big: ch == 'B' }),
+            'e' | 'E' => Some(Motion::WordEnd { big: ch == 'E' }),
+            '0' => Some(Motion::LineStart),
+            '^' => Some(Motion::FirstNonBlank),
+            '$' => Some(Motion::LineEnd),
+            'G' => Some(Motion::Line(self.explicit_count())),
+            _ => None,
+        };
+        if let Some(motion) = motion {
+            self.apply_motion(textarea, motion);
+            return VimOutcome::Handled;
+        }
+
+        match ch {
+            'f' | 'F' | 't' | 'T' => {
+                self.pending = Pending::Find(FindSpec {
+                    forward: ch.is_ascii_lowercase(),
+                    till: ch.eq_ignore_ascii_case(&'t'),
+                });
+            }
+            'g' => self.pending = Pending::G,
+            '"' if self.operator.is_none() => self.pending = Pending::Register,
+            'd' | 'c' | 'y' => {
+                let op = match ch {
+                    'd' => Operator::Delete,
+                    'c' => Operator::Change,
+                    _ => Operator::Yank,
+                };
+                if visual {
+                    self.visual_operator(textarea, op);
+                } else if self.operator == Some(op) {
+                    self.operator = None;
+                    let count = self.take_count();
+                    self.operate_on_lines(textarea, op, count);
+                } else if self.operator.is_some() {
+                    self.clear_pending();
+                } else {
+                    self.operator = Some(op);
+                }
+            }
+            'x' | 's' | 'X' if visual => {
+                let op = if ch == 's' {
+                    Operator::Change
+                } else {
+                    Operator::Delete
+                };
+                self.visual_operator(textarea, op);
+            }
+            'x' | 's' => {
+                let count = self.take_count();
+                let text = textarea.text();
+                let cursor = textarea.cursor();
+                let end = (0..count).fold(cursor, |pos, _| next_char_in_line(text, pos));
+                let op = if ch == 's' {
+                    Operator::Change
+                } else {
+                    Operator::Delete
+                };
+                self.operate(textarea, op, cursor..end, false);
+            }
+            'X' => {
+                let count = self.take_count();
+                let text = textarea.text();
+                let cursor = textarea.cursor();
+                let start = line_start(text, cursor);
+                let from = (0..count).fold(cursor, |pos, _| {
+                    if pos > start {
+                        prev_char(text, pos)
+                    } else {
+                        pos
+                    }
+                });
+                self.operate(textarea, Operator::Delete, from..cursor, false);
+            }
+            'D' | 'C' => {
+                let op = if ch == 'D' {
+                    Operator::Delete
+                } else {
+                    Operator::Change
+                };
+                self.operator = Some(op);
+                self.apply_motion(textarea, Motion::LineEnd);
+            }
+            'Y' | 'S' => {
+                let op = if ch == 'Y' {
+                    Operator::Yank
+                } else {
+                    Operator::Change
+                };
+                let count = self.take_count();
+                self.operate_on_lines(textarea, op, count);
+            }
+            'p' | 'P' if !visual => {
+                let count = self.take_count();
+                self.paste(textarea, ch == 'P', count);
+            }
+            'r' if !visual => self.pending = Pending::Replace,
+            'J' if !visual => {
+                let count = self.take_count();
+                self.join_lines(textarea, count.max(2) - 1);
+            }
+            'u' if !visual => {
+                self.clear_pending();
+                self.undo(textarea);
+            }
+            'i' | 'a' | 'I' | 'A' | 'o' | 'O' if !visual => {
+                self.clear_pending();
+                self.insert(textarea, ch);
+            }
+            'o' => {
+                let cursor = textarea.cursor();
+                textarea.set_cursor(self.visual_anchor);
+                self.visual_anchor = cursor;
+            }
+            'v' | 'V' => {
+                self.clear_pending();
+                let mode = if ch == 'v' {
+                    VimMode::Visual
+                } else {
+                    VimMode::VisualLine
+                };
+                if self.mode == mode {
+                    self.leave_visual(textarea);
+                } else {
+                    if !visual {
+                        self.visual_anchor = textarea.cursor();
+                    }
+                    self.mode = mode;
+                }
+            }
+            _ => self.clear_pending(),
+        }
+        VimOutcome::Handled
+    }
+
+    fn apply_motion(&mut self, textarea: &mut TextArea, motion: Motion) {
+        let count = self.take_count();
+        let Some(op) = self.operator.take() else {
+            match motion {
+                Motion::Up => (0..count).for_each(|_| textarea.move_cursor_up()),
+                Motion::Down => (0..count).for_each(|_| textarea.move_cursor_down()),
+                _ => {
+                    if let Some(target) =
+                        motion_target(textarea.text(), textarea.cursor(), motion, count)
+                    {
+                        textarea.set_cursor(target);
+                    }
+                }
+            }
+            self.clamp_cursor(textarea);
+            return;
+        };
+
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        // `cw` changes to the end of the word, like `ce`.
+        let motion = match motion {
+            Motion::WordForward { big }
+                if op == Operator::Change
+                    && char_at(text, cursor).is_some_and(|c| !c.is_whitespace()) =>
+            {
+                Motion::WordEnd { big }
+            }
+            motion => motion,
+        };
+        let Some(target) = motion_target(text, cursor, motion, count) else {
+            self.register = None;
+            return;
+        };
+        let (start, end) = (cursor.min(target), cursor.max(target));
+        if motion.linewise() {
+            let range = line_range(text, start, end);
+            self.operate(textarea, op, range, true);
+            return;
+        }
+        let mut end = if motion.inclusive() {
+            inclusive_end(text, end)
+        } else {
+            end
+        };
+        // `dw` on the last word of a line stops at the end of the line.
+        if matches!(motion, Motion::WordForward { .. }) && text[start..end].ends_with('\n') {
+            end = line_end(text, start).max(start);
+        }
+        self.operate(textarea, op, start..end, false);
+    }
+
+    /// `dd`, `cc` and `yy`: `count` lines from the cursor's.
+    fn operate_on_lines(&mut self, textarea: &mut TextArea, op: Operator, count: usize) {
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        let last = lines_down(text, cursor, count.saturating_sub(1));
+        let range = line_range(text, cursor, last);
+        self.operate(textarea, op, range, true);
+    }
+
+    fn visual_operator(&mut self, textarea: &mut TextArea, op: Operator) {
+        let (range, linewise) = self.visual_range(textarea.text(), textarea.cursor());
+        self.mode = VimMode::Normal;
+        self.operate(textarea, op, range, linewise);
+        self.clamp_cursor(textarea);
+    }
+
+    /// Applies `op` to `range`. Linewise ranges span whole lines without
+    /// the final newline.
+    fn operate(
+        &mut self,
+        textarea: &mut TextArea,
+        op: Operator,
+        range: Range<usize>,
+        linewise: bool,
+    ) {
+        // The register named with `"` is used by `store_register` below.
+        let register = self.register;
+        self.clear_pending();
+        self.register = register;
+        if range.is_empty() && !linewise {
+            if op == Operator::Change {
+                self.insert_start = Some(snapshot(textarea));
+                self.mode = VimMode::Insert;
+            }
+            self.register = None;
+            return;
+        }
+
+        let text = textarea.text();
+        let mut yanked = text[range.clone()].to_string();
+        if linewise {
+            yanked.push('\n');
+        }
+        self.store_register(
+            op,
+            Register {
+                text: yanked,
+                linewise,
+            },
+        );
+
+        match op {
+            Operator::Yank => {
+                if !linewise || range.start > textarea.cursor() {
+                    textarea.set_cursor(range.start);
+                }
+            }
+            Operator::Delete => {
+                let before = snapshot(textarea);
+                let range = if linewise {
+                    with_line_break(text, range)
+                } else {
+                    range
+                };
+                textarea.replace_range(range.clone(), "");
+                self.push_undo(before);
+                let pos = range.start.min(textarea.text().len());
+                let pos = if linewise {
+                    first_non_blank(textarea.text(), pos)
+                } else {
+                    pos
+                };
+                textarea.set_cursor(pos);
+                self.clamp_cursor(textarea);
+            }
+            Operator::Change => {
+                self.insert_start = Some(snapshot(textarea));
+                textarea.replace_range(range.clone(), "");
+                textarea.set_cursor(range.start);
+                self.mode = VimMode::Insert;
+            }
+        }
+    }
+
+    fn store_register(&mut self, op: Operator, register: Register) {
+        if let Some(name) = self.register.take()
+            && name != '"'
+        {
+            self.registers.insert(name, register.clone());
+        }
+        if op == Operator::Yank {
+            self.registers.insert('0', register.clone());
+        }
+        self.registers.insert('"', register);
+    }
+
+    fn paste(&mut self, textarea: &mut TextArea, before: bool, count: usize) {
+        let name = self.register.take().unwrap_or('"');
+        let Some(register) = self.registers.get(&name).cloned() else {
+            return;
+        };
+        let undo = snapshot(textarea);
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        let body = register.text.repeat(count);
+        if register.linewise {
+            let (pos, body) = if before {
+                (line_start(text, cursor), body)
+            } else {
+                let body = body.strip_suffix('\n').unwrap_or(&body);
+                (line_end(text, cursor), format!("\n{body}"))
+            };
+            textarea.insert_str_at(pos, &body);
+            let line = if before { pos } else { pos + 1 };
+            textarea.set_cursor(first_non_blank(textarea.text(), line));
+        } else {
+            let pos = if before || cursor >= line_end(text, cursor) {
+                cursor
+            } else {
+                next_char(text, cursor)
+            };
+            textarea.insert_str_at(pos, &body);
+            textarea.set_cursor(prev_char(textarea.text(), pos + body.len()));
+        }
+        self.push_undo(undo);
+        self.clamp_cursor(textarea);
+    }
+
+    /// `r`: replaces `count` characters from the cursor with `ch`.
+    fn replace_chars(&mut self, textarea: &mut TextArea, ch: char, count: usize) {
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        let end = (0..count).fold(cursor, |pos, _| next_char_in_line(text, pos));
+        let replaced = text[cursor..end].chars().count();
+        if replaced < count {
+            return;
+        }
+        let undo = snapshot(textarea);
+        textarea.replace_range(cursor..end, &ch.to_string().repeat(count));
+        self.push_undo(undo);
+        textarea.set_cursor(prev_char(textarea.text(), cursor + ch.len_utf8() * count));
+    }
+
+    /// `J`: joins the next `joins` lines onto the current one.
+    fn join_lines(&mut self, textarea: &mut TextArea, joins: usize) {
+        let undo = snapshot(textarea);
+        let mut changed = false;
+        for _ in 0..joins {
+            let text = textarea.text();
+            let end = line_end(text, textarea.cursor());
+            if end == text.len() {
+                break;
+            }
+            let next = &text[end + 1..];
+            let indent = next.len() - next.trim_start_matches([' ', '\t']).len();
+            textarea.replace_range(end..end + 1 + indent, " ");
+            textarea.set_cursor(end);
+            changed = true;
+        }
+        if changed {
+            self.push_undo(undo);
+        }
+    }
+
+    fn insert(&mut self, textarea: &mut TextArea, ch: char) {
+        self.insert_start = Some(snapshot(textarea));
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        let pos = match ch {
+            'a' => next_char_in_line(text, cursor),
+            'I' => first_non_blank(text, cursor),
+            'A' | 'o' => line_end(text, cursor),
+            'O' => line_start(text, cursor),
+            _ => cursor,
+        };
+        match ch {
+            'o' => {
+                textarea.insert_str_at(pos, "\n");
+                textarea.set_cursor(pos + 1);
+            }
+            'O' => {
+                textarea.insert_str_at(pos, "\n");
+                textarea.set_cursor(pos);
+            }
+            _ => textarea.set_cursor(pos),
+        }
+        self.mode = VimMode::Insert;
+    }
+
+    fn undo(&mut self, textarea: &mut TextArea) {
+        let current = snapshot(textarea);
+        while let Some(previous) = self.undo.pop() {
+            if previous.text != current.text {
+                self.redo.push(current);
+                self.restore(textarea, previous);
+                return;
+            }
+        }
+    }
+
+    fn redo(&mut self, textarea: &mut TextArea) {
+        if let Some(next) = self.redo.pop() {
+            self.undo.push(snapshot(textarea));
+            self.restore(textarea, next);
+        }
+    }
+
+    fn restore(&mut self, textarea: &mut TextArea, state: Snapshot) {
+        textarea.set_text(&state.text);
+        textarea.set_cursor(state.cursor);
+        self.clamp_cursor(textarea);
+    }
+
+    fn push_undo(&mut self, state: Snapshot) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(state);
+        self.redo.clear();
+    }
+
+    fn leave_visual(&mut self, textarea: &mut TextArea) {
+        if matches!(self.mode, VimMode::Visual | VimMode::VisualLine) {
+            self.mode = VimMode::Normal;
+            self.clamp_cursor(textarea);
+        }
+    }
+
+    /// In normal mode the cursor sits on a character, not after the last
+    /// one of a line.
+    fn clamp_cursor(&self, textarea: &mut TextArea) {
+        if self.mode != VimMode::Normal {
+            return;
+        }
+        let text = textarea.text();
+        let cursor = textarea.cursor();
+        let end = line_end(text, cursor);
+        if cursor >= end && end > line_start(text, cursor) {
+            let pos = prev_char(text, end);
+            textarea.set_cursor(pos);
+        }
+    }
+
+    /// The visual selection and whether it is linewise.
+    fn visual_range(&self, text: &str, cursor: usize) -> (Range<usize>, bool) {
+        let anchor =
+            if self.visual_anchor <= text.len() && text.is_char_boundary(self.visual_anchor) {
+                self.visual_anchor
+            } else {
+                cursor
+            };
+        let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+        if self.mode == VimMode::VisualLine {
+            (line_range(text, start, end), true)
+        } else {
+            (start..next_char(text, end), false)
+        }
+    }
+
+    fn current_count(&self) -> Option<usize> {
+        if self.operator.is_some() {
+            self.motion_count
+        } else {
+            self.count
+        }
+    }
+
+    fn explicit_count(&self) -> Option<usize> {
+        match (self.count, self.motion_count) {
+            (None, None) => None,
+            (count, motion_count) => {
+                Some(count.unwrap_or(1).saturating_mul(motion_count.unwrap_or(1)))
+            }
+        }
+    }
+
+    fn take_count(&mut self) -> usize {
+        let count = self.explicit_count().unwrap_or(1);
+        self.count = None;
+        self.motion_count = None;
+        count
+    }
+
+    fn clear_pending(&mut self) {
+        self.operator = None;
+        self.pending = Pending::None;
+        self.count = None;
+        self.motion_count = None;
+        self.register = None;
+    }
+}
+
+fn snapshot(textarea: &TextArea) -> Snapshot {
+    Snapshot {
+        text: textarea.text().to_string(),
+        cursor: textarea.cursor(),
+    }
+}
+
+/// Where `motion` repeated `count` times leads from `pos`, or `None` when
+/// it cannot move (e.g. `fx` without an `x`).
+fn motion_target(text: &str, pos: usize, motion: Motion, count: usize) -> Option<usize> {
+    let repeat = |step: &dyn Fn(usize) -> usize| (0..count).fold(pos, |pos, _| step(pos));
+    let target = match motion {
+        Motion::Left => {
+            let start = line_start(text, pos);
+            repeat(&|pos| {
+                if pos > start {
+                    prev_char(text, pos)
+                } else {
+                    pos
+                }
+            })
+        }
+        Motion::Right => repeat(&|pos| next_char_in_line(text, pos)),
+        Motion::Up => {
+            let mut line = line_start(text, pos);
+            for _ in 0..count {
+                if line == 0 {
+                    break;
+                }
+                line = line_start(text, line - 1);
+            }
+            if line == line_start(text, pos) {
+                return None;
+            }
+            line
+        }
+        Motion::Down => {
+            let line = lines_down(text, pos, count);
+            if line == line_start(text, pos) {
+                return None;
+            }
+            line
+        }
+        Motion::WordForward { big } => repeat(&|pos| word_forward(text, pos, big)),
+        Motion::WordBackward { big } => repeat(&|pos| word_backward(text, pos, big)),
+        Motion::WordEnd { big } => repeat(&|pos| word_end(text, pos, big)),
+        Motion::LineStart => line_start(text, pos),
+        Motion::FirstNonBlank => first_non_blank(text, pos),
+        Motion::LineEnd => {
+            let line = lines_down(text, pos, count - 1);
+            let end = line_end(text, line);
+            if end > line {
+                prev_char(text, end)
+            } else {
+                end
+            }
+        }
+        Motion::Line(line) => {
+            let line = line.map_or(usize::MAX, |line| line.saturating_sub(1));
+            first_non_blank(text, lines_down(text, 0, line))
+        }
+        Motion::Find { spec, ch } => find_in_line(text, pos, spec, ch, count)?,
+    };
+    Some(target)
+}
+
+fn find_in_line(text: &str, pos: usize, spec: FindSpec, ch: char, count: usize) -> Option<usize> {
+    let found = if spec.forward {
+        let from = next_char(text, pos);
+        text[from..line_end(text, pos)]
+            .char_indices()
+            .filter(|(_, c)| *c == ch)
+            .nth(count - 1)
+            .map(|(idx, _)| from + idx)?
+    } else {
+        let start = line_start(text, pos);
+        text[start..pos]
+            .char_indices()
+            .rev()
+            .filter(|(_, c)| *c == ch)
+            .nth(count - 1)
+            .map(|(idx, _)| start + idx)?
+    };
+    Some(match (spec.till, spec.forward) {
+        (false, _) => found,
+        (true, true) => prev_char(text, found),
+        (true, false) => next_char(text, found),
+    })
+}
+
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |idx| idx + 1)
+}
+
+/// The position of the line's `\n`, or the end of the text.
+fn line_end(text: &str, pos: usize) -> usize {
+    text[pos..].find('\n').map_or(text.len(), |idx| pos + idx)
+}
+
+/// The start of the line `n` lines below the one at `pos`, or of the last
+/// line.
+fn lines_down(text: &str, pos: usize, n: usize) -> usize {
+    let mut line = line_start(text, pos);
+    for _ in 0..n {
+        let end = line_end(text, line);
+        if end == text.len() {
+            break;
+        }
+        line = end + 1;
+    }
+    line
+}
+
+/// The lines from the one at `start` to the one at `end`, without the final
+/// newline.
+fn line_range(text: &str, start: usize, end: usize) -> Range<usize> {
+    line_start(text, start)..line_end(text, end)
+}
+
+/// Extends whole lines by the line break that separates them from the rest.
+fn with_line_break(text: &str, range: Range<usize>) -> Range<usize> {
+    if range.end < text.len() {
+        range.start..range.end + 1
+    } else if range.start > 0 {
+        range.start - 1..range.end
+    } else {
+        range
+    }
+}
+
+/// The end of a range that includes the character at `pos`, but not a
+/// line break.
+fn inclusive_end(text: &str, pos: usize) -> usize {
+    next_char_in_line(text, pos)
+}
+
+fn first_non_blank(text: &str, pos: usize) -> usize {
+    let start = line_start(text, pos);
+    let end = line_end(text, pos);
+    text[start..end]
+        .find(|c: char| !c.is_whitespace())
+        .map_or(end, |idx| start + idx)
+}
+
+fn char_at(text: &str, pos: usize) -> Option<char> {
+    text[pos..].chars().next()
+}
+
+fn next_char(text: &str, pos: usize) -> usize {
+    char_at(text, pos).map_or(pos, |c| pos + c.len_utf8())
+}
+
+fn next_char_in_line(text: &str, pos: usize) -> usize {
+    match char_at(text, pos) {
+        Some(c) if c != '\n' => pos + c.len_utf8(),
+        _ => pos,
+    }
+}
+
+fn prev_char(text: &str, pos: usize) -> usize {
+    text[..pos]
+        .chars()
+        .next_back()
+        .map_or(pos, |c| pos - c.len_utf8())
+}
+
+/// Whitespace, word characters and punctuation are 0, 1 and 2. In a WORD
+/// (`big`) everything but whitespace is 1.
+fn char_class(c: char, big: bool) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if big || c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn word_forward(text: &str, pos: usize, big: bool) -> usize {
+    let mut chars = text[pos..].char_indices().peekable();
+    let Some(&(_, first)) = chars.peek() else {
+        return pos;
+    };
+    let class = char_class(first, big);
+    if class != 0 {
+        while chars
+            .next_if(|(_, c)| char_class(*c, big) == class)
+            .is_some()
+        {}
+    }
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    chars.peek().map_or(text.len(), |(idx, _)| pos + idx)
+}
+
+fn word_backward(text: &str, pos: usize, big: bool) -> usize {
+    let mut chars = text[..pos].char_indices().rev().peekable();
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    let Some(&(mut start, first)) = chars.peek() else {
+        return 0;
+    };
+    let class = char_class(first, big);
+    while let Some((idx, _)) = chars.next_if(|(_, c)| char_class(*c, big) == class) {
+        start = idx;
+    }
+    start
+}
+
+fn word_end(text: &str, pos: usize, big: bool) -> usize {
+    let from = next_char(text, pos);
+    let mut chars = text[from..].char_indices().peekable();
+    while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    let Some(&(mut end, first)) = chars.peek() else {
+        return pos;
+    };
+    let class = char_class(first, big);
+    while let Some((idx, _)) = chars.next_if(|(_, c)| char_class(*c, big) == class) {
+        end = idx;
+    }
+    from + end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A textarea holding `text`, in normal mode with the cursor at `cursor`.
+    fn normal(text: &str, cursor: usize) -> (VimState, TextArea) {
+        let mut textarea = TextArea::new();
+        textarea.set_text(text);
+        textarea.set_cursor(cursor);
+        let mut vim = VimState::new();
+        vim.mode = VimMode::Normal;
+        (vim, textarea)
+    }
+
+    fn keys(vim: &mut VimState, textarea: &mut TextArea, keys: &str) {
+        for ch in keys.chars() {
+            let code = if ch == '\u{1b}' {
+                KeyCode::Esc
+            } else {
+                KeyCode::Char(ch)
+            };
+            let outcome = vim.handle_key(textarea, KeyEvent::new(code, KeyModifiers::NONE));
+            if outcome == VimOutcome::PassThrough {
+                textarea.insert_str(&ch.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn motions_move_the_cursor() {
+        let (mut vim, mut textarea) = normal("foo bar.baz\n  qux", 0);
+        keys(&mut vim, &mut textarea, "w");
+        assert_eq!(textarea.cursor(), 4);
+        keys(&mut vim, &mut textarea, "w");
+        assert_eq!(textarea.cursor(), 7);
+        keys(&mut vim, &mut textarea, "0W");
+        assert_eq!(textarea.cursor(), 4);
+        keys(&mut vim, &mut textarea, "e");
+        assert_eq!(textarea.cursor(), 6);
+        keys(&mut vim, &mut textarea, "$");
+        assert_eq!(textarea.cursor(), 10);
+        keys(&mut vim, &mut textarea, "G");
+        assert_eq!(textarea.cursor(), 14);
+        keys(&mut vim, &mut textarea, "gg");
+        assert_eq!(textarea.cursor(), 0);
+        keys(&mut vim, &mut textarea, "fz");
+        assert_eq!(textarea.cursor(), 10);
+        keys(&mut vim, &mut textarea, "Tr");
+        assert_eq!(textarea.cursor(), 7);
+    }
+
+    #[test]
+    fn operators_take_counts_and_motions() {
+        let (mut vim, mut textarea) = normal("one two three four", 0);
+        keys(&mut vim, &mut textarea, "2dw");
+        assert_eq!(textarea.text(), "three four");
+        keys(&mut vim, &mut textarea, "cwfive\u{1b}");
+        assert_eq!(textarea.text(), "five four");
+        assert_eq!(vim.mode(), VimMode::Normal);
+        keys(&mut vim, &mut textarea, "$D");
+        assert_eq!(textarea.text(), "five fou");
+        keys(&mut vim, &mut textarea, "0x");
+        assert_eq!(textarea.text(), "ive fou");
+    }
+
+    #[test]
+    fn lines_are_deleted_and_pasted_with_registers() {
+        let (mut vim, mut textarea) = normal("a\nb\nc", 0);
+        keys(&mut vim, &mut textarea, "\"qdd");
+        assert_eq!(textarea.text(), "b\nc");
+        keys(&mut vim, &mut textarea, "yyjp");
+        assert_eq!(textarea.text(), "b\nc\nb");
+        keys(&mut vim, &mut textarea, "gg\"qP");
+        assert_eq!(textarea.text(), "a\nb\nc\nb");
+        keys(&mut vim, &mut textarea, "u");
+        assert_eq!(textarea.text(), "b\nc\nb");
+        keys(&mut vim, &mut textarea, "uu");
+        assert_eq!(textarea.text(), "a\nb\nc");
+        vim.handle_key(
+            &mut textarea,
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+        );
+        assert_eq!(textarea.text(), "b\nc");
+    }
+
+    #[test]
+    fn visual_mode_selects_and_operates() {
+        let (mut vim, mut textarea) = normal("hello world", 0);
+        keys(&mut vim, &mut textarea, "vl");
+        assert_eq!(vim.selection(&textarea), Some(0..2));
+        keys(&mut vim, &mut textarea, "ey");
+        assert_eq!(vim.mode(), VimMode::Normal);
+        assert_eq!(textarea.cursor(), 0);
+        keys(&mut vim, &mut textarea, "$p");
+        assert_eq!(textarea.text(), "hello worldhello");
+
+        let (mut vim, mut textarea) = normal("a\nb\nc", 2);
+        keys(&mut vim, &mut textarea, "Vjd");
+        assert_eq!(textarea.text(), "a");
+    }
+
+    #[test]
+    fn insert_commands_enter_insert_mode() {
+        let (mut vim, mut textarea) = normal("middle", 2);
+        keys(&mut vim, &mut textarea, "Abc\u{1b}Ia\u{1b}oend\u{1b}");
+        assert_eq!(textarea.text(), "amiddlebc\nend");
+        keys(&mut vim, &mut textarea, "u");
+        assert_eq!(textarea.text(), "amiddlebc");
+        assert_eq!(VimMode::Normal.label(), "NORMAL");
+    }
+}
@@ -1,20 +1,30 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 
 use codex_core::config::Config;
-use codex_core::config_types::Notifications;
+use codex_core::config_types::DiffRendererInput;
 use codex_core::git_info::current_branch_name;
 use codex_core::git_info::local_git_branches;
+use codex_core::memory::memory_path;
 use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
+use codex_core::protocol::AnnotationAddedEvent;
+use codex_core::protocol::AnnotationTarget;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BackgroundJobEvent;
+use codex_core::protocol::BackgroundResponseStartedEvent;
+use codex_core::protocol::CheckpointCreatedEvent;
+use codex_core::protocol::CheckpointRestoredEvent;
+use codex_core::protocol::ContextPrunedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -25,23 +35,43 @@ use codex_core::protocol::ExitedReviewModeEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
+use codex_core::protocol::LoginRequiredEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::McpSamplingApprovalRequestEvent;
+use codex_core::protocol::McpServerStatus;
+use codex_core::protocol::McpServerStatusEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::ModelChangedEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
+use codex_core::protocol::ProviderEndpointSwitchedEvent;
+use codex_core::protocol::ProviderSwitchedEvent;
+use codex_core::protocol::PullRequestCreatedEvent;
+use codex_core::protocol::QueuedUserInputEvent;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::ReviewTarget;
+use codex_core::protocol::SecretRequestEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TokenUsageInfo;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::TurnOverrides;
+use codex_core::protocol::TurnRevertedEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_core::review_target::parse_review_target;
+use codex_core::review_target::review_request;
+use codex_core::session_log::log_level;
+use codex_core::session_log::session_log_path;
+use codex_core::session_log::sessions_log_dir;
+use codex_core::session_log::set_log_level;
 use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::mcp_protocol::LogLevel;
 use codex_protocol::parse_command::ParsedCommand;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -69,21 +99,37 @@ use crate::bottom_pane::SelectionItem;
 use crate::bottom_pane::SelectionViewParams;
 use crate::bottom_pane::custom_prompt_view::CustomPromptView;
 use crate::bottom_pane::popup_consts::STANDARD_POPUP_HINT_LINE;
+use crate::clipboard_copy::CopyMethod;
+use crate::clipboard_copy::copy_to_clipboard;
+use crate::clipboard_paste::normalize_pasted_path;
 use crate::clipboard_paste::paste_image_to_temp_png;
+use crate::clipboard_paste::pasted_image_format;
 use crate::diff_render::display_path_for;
+use crate::diff_viewer::DiffFile;
 use crate::exec_cell::CommandOutput;
 use crate::exec_cell::ExecCell;
 use crate::exec_cell::new_active_exec_command;
+use crate::file_mentions;
 use crate::get_git_diff::get_git_diff;
+use crate::get_git_diff::get_rendered_git_diff;
 use crate::history_cell;
 use crate::history_cell::AgentMessageCell;
 use crate::history_cell::HistoryCell;
 use crate::history_cell::McpToolCallCell;
 use crate::history_cell::PatchEventType;
+use crate::image_thumbnail;
+use crate::keymap;
+use crate::keymap::KeyAction;
 use crate::markdown::append_markdown;
+use crate::markdown::last_code_block;
+use crate::patch_selection::PatchSelection;
+use crate::resume_picker::export_session;
+use crate::resume_picker::session_markdown;
 use crate::slash_command::SlashCommand;
 use crate::status::RateLimitSnapshotDisplay;
+use crate::status::StatusDashboard;
 use crate::text_formatting::truncate_text;
+use crate::theme;
 use crate::tui::FrameRequester;
 mod interrupts;
 use self::interrupts::InterruptManager;
@@ -100,19 +146,13 @@ use codex_common::approval_presets::ApprovalPreset;
 use codex_common::approval_presets::builtin_approval_presets;
 use codex_common::model_presets::ModelPreset;
 use codex_common::model_presets::builtin_model_presets;
+use codex_common::stream_coalescer::DeltaCoalescer;
 use codex_core::AuthManager;
 use codex_core::ConversationManager;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_file_search::FileMatch;
-use codex_git_tooling::CreateGhostCommitOptions;
-use codex_git_tooling::GhostCommit;
-use codex_git_tooling::GitToolingError;
-use codex_git_tooling::create_ghost_commit;
-use codex_git_tooling::restore_ghost_commit;
-
-const MAX_TRACKED_GHOST_COMMITS: usize = 20;
 
 // Track information about an in-flight exec command.
 struct RunningCommand {
@@ -231,6 +271,12 @@ pub(crate) struct ChatWidget {
     // Stream lifecycle controller
     stream_controller: Option<StreamController>,
     running_commands: HashMap<String, RunningCommand>,
+    // Latest status of each background job the agent started, oldest first.
+    background_jobs: Vec<BackgroundJobEvent>,
+    // MCP servers that are being restarted or gave up, by name.
+    unhealthy_mcp_servers: BTreeMap<String, McpServerStatus>,
+    // Whether raw model requests and responses are logged, toggled by `/debug`.
+    wire_logging: bool,
     task_complete_pending: bool,
     // Queue of interruptive UI events deferred during an active write cycle
     interrupts: InterruptManager,
@@ -238,24 +284,31 @@ pub(crate) struct ChatWidget {
     reasoning_buffer: String,
     // Accumulates full reasoning content for transcript-only recording
     full_reasoning_buffer: String,
+    // Hold back streamed answer and reasoning text per `stream_coalescing`
+    answer_coalescer: DeltaCoalescer,
+    reasoning_coalescer: DeltaCoalescer,
     conversation_id: Option<ConversationId>,
+    rollout_path: Option<PathBuf>,
+    // The last complete assistant message, for copying
+    last_agent_message: Option<String>,
     frame_requester: FrameRequester,
     // Whether to include the initial welcome banner on session configured
     show_welcome_banner: bool,
     // When resuming an existing session (selected via resume picker), avoid an
     // immediate redraw on SessionConfigured to prevent a gratuitous UI flicker.
     suppress_session_configured_redraw: bool,
-    // User messages queued while a turn is in progress
-    queued_user_messages: VecDeque<UserMessage>,
+    // User messages queued in core while a turn is in progress
+    queued_user_messages: VecDeque<QueuedUserMessage>,
     // Pending notification to show when unfocused on next Draw
     pending_notification: Option<Notification>,
+    // When the running turn started, to tell long turns apart
+    turn_started_at: Option<Instant>,
     // Simple review mode flag; used to adjust layout and banners.
     is_review_mode: bool,
-    // List of ghost commits corresponding to each turn.
-    ghost_snapshots: Vec<GhostCommit>,
-    ghost_snapshots_disabled: bool,
     // Whether to add a final message separator after the last message
     needs_final_message_separator: bool,
+    // Names of the checkpoints created in this session, oldest first.
+    checkpoints: Vec<String>,
 }
 
 struct UserMessage {
@@ -272,6 +325,15 @@ impl From<String> for UserMessage {
     }
 }
 
+/// A message handed to core's queue, kept to render it once core submits it.
+struct QueuedUserMessage {
+    /// Core's id for the message; `None` until core reports it queued.
+    id: Option<u64>,
+    message: UserMessage,
+    /// Labels of the file mentions inlined into the message.
+    inlined: Vec<String>,
+}
+
 fn create_initial_user_message(text: String, image_paths: Vec<PathBuf>) -> Option<UserMessage> {
     if text.is_empty() && image_paths.is_empty() {
         None
@@ -294,6 +356,7 @@ impl ChatWidget {
         self.bottom_pane
             .set_history_metadata(event.history_log_id, event.history_entry_count);
         self.conversation_id = Some(event.session_id);
+        self.rollout_path = Some(event.rollout_path.clone());
         let initial_messages = event.initial_messages.clone();
         let model_for_header = event.model.clone();
         self.session_header.set_model(&model_for_header);
@@ -316,6 +379,7 @@ impl ChatWidget {
     }
 
     fn on_agent_message(&mut self, message: String) {
+        self.last_agent_message = Some(message.clone());
         // If we have a stream_controller, then the final agent message is redundant and will be a
         // duplicate of what has already been streamed.
         if self.stream_controller.is_none() {
@@ -327,10 +391,20 @@ impl ChatWidget {
     }
 
     fn on_agent_message_delta(&mut self, delta: String) {
-        self.handle_streaming_delta(delta);
+        match self.answer_coalescer.push(&delta, Instant::now()) {
+            Some(delta) => self.handle_streaming_delta(delta),
+            None => self.schedule_coalesced_flush(),
+        }
     }
 
     fn on_agent_reasoning_delta(&mut self, delta: String) {
+        match self.reasoning_coalescer.push(&delta, Instant::now()) {
+            Some(delta) => self.apply_reasoning_delta(delta),
+            None => self.schedule_coalesced_flush(),
+        }
+    }
+
+    fn apply_reasoning_delta(&mut self, delta: String) {
         // For reasoning deltas, do not stream to history. Accumulate the
         // current reasoning block and extract the first bold element
         // (between **/**) as the chunk header. Show this header as status.
@@ -370,6 +444,7 @@ impl ChatWidget {
     // Raw reasoning uses the same flow as summarized reasoning
 
     fn on_task_started(&mut self) {
+        self.turn_started_at = Some(Instant::now());
         self.bottom_pane.clear_ctrl_c_quit_hint();
         self.bottom_pane.set_task_running(true);
         self.full_reasoning_buffer.clear();
@@ -385,12 +460,16 @@ impl ChatWidget {
         self.running_commands.clear();
         self.request_redraw();
 
-        // If there is a queued user message, send exactly one now to begin the next turn.
-        self.maybe_send_next_queued_input();
-        // Emit a notification when the turn completes (suppressed if focused).
-        self.notify(Notification::AgentTurnComplete {
-            response: last_agent_message.unwrap_or_default(),
+        // Emit a notification when a long enough turn completes (suppressed
+        // if focused).
+        let long_enough = self.turn_started_at.take().is_none_or(|started| {
+            started.elapsed() >= self.config.tui_notifications.min_turn_duration()
         });
+        if long_enough {
+            self.notify(Notification::AgentTurnComplete {
+                response: last_agent_message.unwrap_or_default(),
+            });
+        }
     }
 
     pub(crate) fn set_token_info(&mut self, info: Option<TokenUsageInfo>) {
@@ -445,9 +524,6 @@ impl ChatWidget {
         self.finalize_turn();
         self.add_to_history(history_cell::new_error_event(message));
         self.request_redraw();
-
-        // After an error ends the turn, try sending the next queued input.
-        self.maybe_send_next_queued_input();
     }
 
     /// Handle a turn aborted due to user interrupt (Esc).
@@ -468,7 +544,7 @@ impl ChatWidget {
             let queued_text = self
                 .queued_user_messages
                 .iter()
-                .map(|m| m.text.clone())
+                .map(|m| m.message.text.clone())
                 .collect::<Vec<_>>()
                 .join("\n");
             let existing_text = self.bottom_pane.composer_text();
@@ -510,6 +586,28 @@ impl ChatWidget {
         );
     }
 
+    fn on_secret_request(&mut self, id: String, ev: SecretRequestEvent) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_secret_request(id, ev),
+            |s| s.handle_secret_request_now(id2, ev2),
+        );
+    }
+
+    fn on_mcp_sampling_approval_request(
+        &mut self,
+        id: String,
+        ev: McpSamplingApprovalRequestEvent,
+    ) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_mcp_sampling_approval(id, ev),
+            |s| s.handle_mcp_sampling_approval_now(id2, ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -518,9 +616,18 @@ impl ChatWidget {
 
     fn on_exec_command_output_delta(
         &mut self,
-        _ev: codex_core::protocol::ExecCommandOutputDeltaEvent,
+        ev: codex_core::protocol::ExecCommandOutputDeltaEvent,
     ) {
-        // TODO: Handle streaming exec output if/when implemented
+        // Output of a command that is not in the active cell (for example
+        // one whose begin event is still deferred) is only shown once it ends.
+        if let Some(cell) = self
+            .active_cell
+            .as_mut()
+            .and_then(|c| c.as_any_mut().downcast_mut::<ExecCell>())
+            && cell.append_output(&ev.call_id, &ev.chunk)
+        {
+            self.request_redraw();
+        }
     }
 
     fn on_patch_apply_begin(&mut self, event: PatchApplyBeginEvent) {
@@ -530,6 +637,7 @@ impl ChatWidget {
             },
             event.changes,
             &self.config.cwd,
+            self.config.tui_diff_renderer.clone(),
         ));
     }
 
@@ -739,6 +847,36 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    pub(crate) fn handle_secret_request_now(&mut self, id: String, ev: SecretRequestEvent) {
+        self.flush_answer_stream_with_separator();
+        self.notify(Notification::SecretRequested {
+            name: ev.name.clone(),
+        });
+        self.bottom_pane.push_secret_request(id, ev.name, ev.reason);
+        self.request_redraw();
+    }
+
+    pub(crate) fn handle_mcp_sampling_approval_now(
+        &mut self,
+        id: String,
+        ev: McpSamplingApprovalRequestEvent,
+    ) {
+        self.flush_answer_stream_with_separator();
+        self.notify(Notification::SamplingApprovalRequested {
+            server: ev.server.clone(),
+        });
+
+        let request = ApprovalRequest::McpSampling {
+            id,
+            server: ev.server,
+            prompt: ev.prompt,
+            system_prompt: ev.system_prompt,
+            max_tokens: ev.max_tokens,
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_apply_patch_approval_now(
         &mut self,
         id: String,
@@ -749,12 +887,16 @@ impl ChatWidget {
             PatchEventType::ApprovalRequest,
             ev.changes.clone(),
             &self.config.cwd,
+            self.config.tui_diff_renderer.clone(),
         ));
 
         let request = ApprovalRequest::ApplyPatch {
             id,
+            files: DiffFile::from_changes(&ev.changes, &self.config.cwd),
+            selection: PatchSelection::from_changes(&ev.changes, &self.config.cwd),
             reason: ev.reason,
             grant_root: ev.grant_root,
+            risk: ev.risk,
         };
         self.bottom_pane.push_approval_request(request);
         self.request_redraw();
@@ -886,6 +1028,7 @@ impl ChatWidget {
                 enhanced_keys_supported,
                 placeholder_text: placeholder,
                 disable_paste_burst: config.disable_paste_burst,
+                editor_mode: config.tui_editor_mode,
             }),
             active_cell: None,
             config: config.clone(),
@@ -900,19 +1043,26 @@ impl ChatWidget {
             rate_limit_warnings: RateLimitWarningState::default(),
             stream_controller: None,
             running_commands: HashMap::new(),
+            background_jobs: Vec::new(),
+            unhealthy_mcp_servers: BTreeMap::new(),
+            wire_logging: config.wire_log,
             task_complete_pending: false,
             interrupts: InterruptManager::new(),
             reasoning_buffer: String::new(),
             full_reasoning_buffer: String::new(),
+            answer_coalescer: DeltaCoalescer::new(&config.stream_coalescing),
+            reasoning_coalescer: DeltaCoalescer::new(&config.stream_coalescing),
             conversation_id: None,
+            rollout_path: None,
+            last_agent_message: None,
             queued_user_messages: VecDeque::new(),
             show_welcome_banner: true,
             suppress_session_configured_redraw: false,
             pending_notification: None,
+            turn_started_at: None,
             is_review_mode: false,
-            ghost_snapshots: Vec::new(),
-            ghost_snapshots_disabled: true,
             needs_final_message_separator: false,
+            checkpoints: Vec::new(),
         }
     }
 
@@ -948,6 +1098,7 @@ impl ChatWidget {
                 enhanced_keys_supported,
                 placeholder_text: placeholder,
                 disable_paste_burst: config.disable_paste_burst,
+                editor_mode: config.tui_editor_mode,
             }),
             active_cell: None,
             config: config.clone(),
@@ -962,19 +1113,26 @@ impl ChatWidget {
             rate_limit_warnings: RateLimitWarningState::default(),
             stream_controller: None,
             running_commands: HashMap::new(),
+            background_jobs: Vec::new(),
+            unhealthy_mcp_servers: BTreeMap::new(),
+            wire_logging: config.wire_log,
             task_complete_pending: false,
             interrupts: InterruptManager::new(),
             reasoning_buffer: String::new(),
             full_reasoning_buffer: String::new(),
+            answer_coalescer: DeltaCoalescer::new(&config.stream_coalescing),
+            reasoning_coalescer: DeltaCoalescer::new(&config.stream_coalescing),
             conversation_id: None,
+            rollout_path: None,
+            last_agent_message: None,
             queued_user_messages: VecDeque::new(),
             show_welcome_banner: true,
             suppress_session_configured_redraw: true,
             pending_notification: None,
+            turn_started_at: None,
             is_review_mode: false,
-            ghost_snapshots: Vec::new(),
-            ghost_snapshots_disabled: true,
             needs_final_message_separator: false,
+            checkpoints: Vec::new(),
         }
     }
 
@@ -1008,6 +1166,27 @@ impl ChatWidget {
                 }
                 return;
             }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if keymap::current().matches(KeyAction::CopyMessage, &key_event) => {
+                self.copy_last_message();
+                return;
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if keymap::current().matches(KeyAction::CopyCode, &key_event) => {
+                self.copy_last_code_block();
+                return;
+            }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if keymap::current().matches(KeyAction::CopyTranscript, &key_event) => {
+                self.copy_transcript();
+                return;
+            }
             other if other.kind == KeyEventKind::Press => {
                 self.bottom_pane.clear_ctrl_c_quit_hint();
             }
@@ -1020,25 +1199,48 @@ impl ChatWidget {
                 modifiers: KeyModifiers::ALT,
                 kind: KeyEventKind::Press,
                 ..
-            } if !self.queued_user_messages.is_empty() => {
-                // Prefer the most recently queued item.
-                if let Some(user_message) = self.queued_user_messages.pop_back() {
-                    self.bottom_pane.set_composer_text(user_message.text);
+            } if self
+                .queued_user_messages
+                .back()
+                .is_some_and(|queued| queued.id.is_some()) =>
+            {
+                // Prefer the most recently queued item, and take it back from core.
+                if let Some(QueuedUserMessage {
+                    id: Some(id),
+                    message,
+                    ..
+                }) = self.queued_user_messages.pop_back()
+                {
+                    self.submit_op(Op::RemoveQueuedUserInput { id });
+                    self.bottom_pane.set_composer_text(message.text);
                     self.refresh_queued_user_messages();
                     self.request_redraw();
                 }
             }
+            KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            } if self.bottom_pane.is_task_running()
+                && self.bottom_pane.composer_is_empty()
+                && keymap::current().matches(KeyAction::Steer, &key_event) =>
+            {
+                // With nothing to send, steer with the oldest queued message.
+                if let Some(id) = self.queued_user_messages.front().and_then(|q| q.id) {
+                    self.submit_op(Op::SteerQueuedUserInput { id });
+                }
+            }
             _ => {
+                let steer = keymap::current().matches(KeyAction::Steer, &key_event);
                 match self.bottom_pane.handle_key_event(key_event) {
                     InputResult::Submitted(text) => {
-                        // If a task is running, queue the user input to be sent after the turn completes.
                         let user_message = UserMessage {
                             text,
                             image_paths: self.bottom_pane.take_recent_submission_images(),
                         };
-                        if self.bottom_pane.is_task_running() {
-                            self.queued_user_messages.push_back(user_message);
-                            self.refresh_queued_user_messages();
+                        // While a task runs, queue the input in core to start the next
+                        // turn, unless the user asked to steer the running one with it.
+                        if self.bottom_pane.is_task_running() && !steer {
+                            self.queue_user_message(user_message);
                         } else {
                             self.submit_user_message(user_message);
                         }
@@ -1046,6 +1248,9 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::CommandWithArgs(cmd, args) => {
+                        self.dispatch_command_with_args(cmd, args);
+                    }
                     InputResult::None => {}
                 }
             }
@@ -1067,6 +1272,241 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    /// Attaches the image file at `path`, relative to the working directory.
+    fn attach_image_file(&mut self, path: &str) {
+        let path = normalize_pasted_path(path).unwrap_or_else(|| PathBuf::from(path));
+        let path = self.config.cwd.join(path);
+        match image::image_dimensions(&path) {
+            Ok((width, height)) => {
+                let format_label = pasted_image_format(&path).label();
+                self.attach_image(path, width, height, format_label);
+            }
+            Err(err) => {
+                self.add_error_message(format!("Failed to attach {}: {err}", path.display()))
+            }
+        }
+    }
+
+    fn copy_last_message(&mut self) {
+        match self.last_agent_message.clone() {
+            Some(message) => self.copy_text(&message, "the last message"),
+            None => self.add_error_message("There is no message to copy yet.".to_string()),
+        }
+    }
+
+    fn copy_last_code_block(&mut self) {
+        match self.last_agent_message.as_deref().and_then(last_code_block) {
+            Some(code) => self.copy_text(&code, "the last code block"),
+            None => self.add_error_message("The last message has no code block.".to_string()),
+        }
+    }
+
+    fn copy_transcript(&mut self) {
+        let Some(path) = self.rollout_path.clone() else {
+            self.add_error_message("There is no transcript to copy yet.".to_string());
+            return;
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(rollout) => self.copy_text(&session_markdown(&rollout), "the transcript"),
+            Err(err) => self.add_error_message(format!("Failed to read the transcript: {err}")),
+        }
+    }
+
+    fn copy_text(&mut self, text: &str, what: &str) {
+        match copy_to_clipboard(text) {
+            Ok(CopyMethod::System) => self.add_info_message(format!("Copied {what}"), None),
+            Ok(CopyMethod::Osc52) => self.add_info_message(
+                format!("Copied {what}"),
+                Some("sent to the terminal with OSC 52".to_string()),
+            ),
+            Err(err) => self.add_error_message(format!("Failed to copy {what}: {err}")),
+        }
+    }
+
+    /// Writes the transcript as Markdown to `target`, relative to the working
+    /// directory, or to a file named after the session in the working
+    /// directory.
+    fn export_transcript(&mut self, target: Option<&str>) {
+        let Some(rollout_path) = self.rollout_path.clone() else {
+            self.add_error_message("There is no transcript to export yet.".to_string());
+            return;
+        };
+        let target = match target {
+            Some(target) => self.config.cwd.join(target),
+            None => self.config.cwd.clone(),
+        };
+        let tx = self.app_event_tx.clone();
+        tokio::spawn(async move {
+            let cell = match export_session(&rollout_path, &target).await {
+                Ok(path) => history_cell::new_info_event(
+                    format!("Exported the transcript to {}", path.display()),
+                    None,
+                ),
+                Err(err) => {
+                    history_cell::new_error_event(format!("Failed to export the transcript: {err}"))
+                }
+            };
+            tx.send(AppEvent::InsertHistoryCell(Box::new(cell)));
+        });
+    }
+
+    /// Switches the live session to `model`, and to `provider` when given,
+    /// e.g. from `/model claude-sonnet-4-5 anthropic`. Unlike the popup, this
+    /// does not change the configured default.
+    fn switch_model(&mut self, model: String, provider: Option<String>) {
+        if let Some(provider) = &provider
+            && !self.config.model_providers.contains_key(provider)
+        {
+            self.add_error_message(format!("Unknown model provider `{provider}`."));
+            return;
+        }
+        if provider.is_none() {
+            self.add_info_message(format!("Model changed to {model} for this session"), None);
+        }
+        self.submit_op(Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model: Some(model),
+            provider,
+            effort: None,
+            summary: None,
+        });
+    }
+
+    fn on_model_changed(&mut self, event: ModelChangedEvent) {
+        let ModelChangedEvent {
+            model,
+            provider,
+            previous_model,
+            previous_provider,
+        } = event;
+        if let Some(info) = self.config.model_providers.get(&provider) {
+            self.config.model_provider = info.clone();
+        }
+        self.config.model_provider_id = provider.clone();
+        self.app_event_tx.send(AppEvent::UpdateModel(model.clone()));
+        // Model-only changes are announced where they are made.
+        if provider != previous_provider {
+            self.add_info_message(
+                format!("Switched to {model} on {provider} for this session"),
+                Some(format!(
+                    "was {previous_model} on {previous_provider}; its reasoning is not sent along"
+                )),
+            );
+        }
+    }
+
+    /// Starts a review of `target`, e.g. from `/review main..HEAD`. A pull
+    /// request's diff is fetched first, off the UI thread.
+    fn start_review(&mut self, target: ReviewTarget) {
+        let cwd = self.config.cwd.clone();
+        let tx = self.app_event_tx.clone();
+        tokio::spawn(async move {
+            match review_request(&target, &cwd).await {
+                Ok(review_request) => tx.send(AppEvent::CodexOp(Op::Review { review_request })),
+                Err(err) => tx.send(AppEvent::InsertHistoryCell(Box::new(
+                    history_cell::new_error_event(format!("Failed to start the review: {err}")),
+                ))),
+            }
+        });
+    }
+
+    fn dispatch_command_with_args(&mut self, cmd: SlashCommand, args: String) {
+        match cmd {
+            SlashCommand::Image => self.attach_image_file(&args),
+            SlashCommand::Copy => match args.as_str() {
+                "code" => self.copy_last_code_block(),
+                "all" => self.copy_transcript(),
+                _ => self.add_error_message(format!(
+                    "Unknown /copy target `{args}`. Use /copy, /copy code or /copy all."
+                )),
+            },
+            SlashCommand::Export => self.export_transcript(Some(&args)),
+            SlashCommand::Undo => match args.as_str() {
+                "force" if self.bottom_pane.is_task_running() => self.add_error_message(
+                    "'/undo' is disabled while a task is in progress.".to_string(),
+                ),
+                "force" => self.undo_last_turn(true),
+                _ => self.add_error_message(format!(
+                    "Unknown /undo option `{args}`. Use /undo or /undo force."
+                )),
+            },
+            SlashCommand::Model if self.bottom_pane.is_task_running() => self
+                .add_error_message("'/model' is disabled while a task is in progress.".to_string()),
+            SlashCommand::Model => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                [model] => self.switch_model(model.to_string(), None),
+                [model, provider] => {
+                    self.switch_model(model.to_string(), Some(provider.to_string()))
+                }
+                _ => self.add_error_message("Use /model or /model <model> [provider].".to_string()),
+            },
+            SlashCommand::High | SlashCommand::Turn if self.bottom_pane.is_task_running() => self
+                .add_error_message(format!(
+                    "'/{}' is disabled while a task is in progress.",
+                    cmd.command()
+                )),
+            SlashCommand::High => self.submit_user_message_with_overrides(
+                args,
+                TurnOverrides {
+                    effort: Some(ReasoningEffortConfig::High),
+                    ..Default::default()
+                },
+            ),
+            SlashCommand::Turn => match parse_turn_overrides(&args) {
+                Ok((overrides, text)) => self.submit_user_message_with_overrides(text, overrides),
+                Err(err) => self.add_error_message(err),
+            },
+            SlashCommand::Review if self.bottom_pane.is_task_running() => self.add_error_message(
+                "'/review' is disabled while a task is in progress.".to_string(),
+            ),
+            SlashCommand::Review => self.start_review(parse_review_target(&args)),
+            SlashCommand::Checkpoint if self.bottom_pane.is_task_running() => self
+                .add_error_message(
+                    "'/checkpoint' is disabled while a task is in progress.".to_string(),
+                ),
+            SlashCommand::Checkpoint => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                ["restore", name] => self.submit_op(Op::RestoreCheckpoint {
+                    name: name.to_string(),
+                    force: false,
+                }),
+                ["restore", name, "force"] => self.submit_op(Op::RestoreCheckpoint {
+                    name: name.to_string(),
+                    force: true,
+                }),
+                [name] if name != "restore" => self.submit_op(Op::CreateCheckpoint {
+                    name: name.to_string(),
+                }),
+                _ => self.add_error_message(
+                    "Use /checkpoint <name> or /checkpoint restore <name> [force].".to_string(),
+                ),
+            },
+            SlashCommand::Debug => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                ["level"] => self.show_log_level(),
+                ["level", level] => match level.parse::<LogLevel>() {
+                    Ok(level) => {
+                        set_log_level(level);
+                        self.show_log_level();
+                    }
+                    Err(_) => self.add_error_message(format!(
+                        "Unknown log level `{level}`. Use error, warn, info, debug or trace."
+                    )),
+                },
+                _ => self.add_error_message(
+                    "Use /debug, /debug level or /debug level <level>.".to_string(),
+                ),
+            },
+            SlashCommand::Memory => match args.as_str() {
+                "edit" => self.app_event_tx.send(AppEvent::EditMemory),
+                _ => self.add_error_message(format!(
+                    "Unknown /memory option `{args}`. Use /memory or /memory edit."
+                )),
+            },
+            // The other commands take no arguments.
+            _ => self.dispatch_command(cmd),
+        }
+    }
+
     fn dispatch_command(&mut self, cmd: SlashCommand) {
         if !cmd.available_during_task() && self.bottom_pane.is_task_running() {
             let message = format!(
@@ -1078,6 +1518,9 @@ impl ChatWidget {
             return;
         }
         match cmd {
+            SlashCommand::Resume => {
+                self.app_event_tx.send(AppEvent::OpenResumePicker);
+            }
             SlashCommand::New => {
                 self.app_event_tx.send(AppEvent::NewSession);
             }
@@ -1092,29 +1535,77 @@ impl ChatWidget {
             SlashCommand::Review => {
                 self.open_review_popup();
             }
+            SlashCommand::Pr => {
+                self.add_info_message("Opening a pull request…".to_string(), None);
+                self.app_event_tx
+                    .send(AppEvent::CodexOp(Op::CreatePullRequest));
+            }
             SlashCommand::Model => {
                 self.open_model_popup();
             }
             SlashCommand::Approvals => {
                 self.open_approvals_popup();
             }
+            SlashCommand::Theme => {
+                self.open_theme_popup();
+            }
             SlashCommand::Quit => {
                 self.app_event_tx.send(AppEvent::ExitRequest);
             }
             SlashCommand::Logout => {
-                if let Err(e) = codex_core::auth::logout(&self.config.codex_home) {
+                if let Err(e) =
+                    codex_core::auth::logout(&self.config.codex_home, self.config.credential_store)
+                {
                     tracing::error!("failed to logout: {e}");
                 }
                 self.app_event_tx.send(AppEvent::ExitRequest);
             }
             SlashCommand::Undo => {
-                self.undo_last_snapshot();
+                self.undo_last_turn(false);
+            }
+            SlashCommand::High => {
+                self.add_error_message("Use /high <message>.".to_string());
+            }
+            SlashCommand::Turn => {
+                self.add_error_message(
+                    "Use /turn [effort=<level>] [verbosity=<level>] [temperature=<0-2>] <message>."
+                        .to_string(),
+                );
+            }
+            SlashCommand::Checkpoint => {
+                let message = if self.checkpoints.is_empty() {
+                    "No checkpoints yet. Create one with /checkpoint <name>.".to_string()
+                } else {
+                    format!("Checkpoints: {}", self.checkpoints.join(", "))
+                };
+                self.add_info_message(
+                    message,
+                    Some("/checkpoint restore <name> goes back to one".to_string()),
+                );
             }
             SlashCommand::Diff => {
                 self.add_diff_in_progress();
                 let tx = self.app_event_tx.clone();
+                let diff_renderer = self
+                    .config
+                    .tui_diff_renderer
+                    .clone()
+                    .filter(|renderer| renderer.input == DiffRendererInput::Patch);
+                let width = crossterm::terminal::size().map_or(80, |(cols, _)| cols as usize);
                 tokio::spawn(async move {
-                    let text = match get_git_diff().await {
+                    // A configured external renderer takes over the whole diff;
+                    // otherwise the diff opens in the built-in viewer.
+                    let result = match diff_renderer {
+                        Some(renderer) => get_rendered_git_diff(Some(renderer), width).await,
+                        None => match get_git_diff(false).await {
+                            Ok((true, diff)) => {
+                                tx.send(AppEvent::ShowDiff(DiffFile::from_git_diff(&diff)));
+                                return;
+                            }
+                            other => other,
+                        },
+                    };
+                    let text = match result {
                         Ok((is_git_repo, diff_text)) => {
                             if is_git_repo {
                                 diff_text
@@ -1127,15 +1618,53 @@ impl ChatWidget {
                     tx.send(AppEvent::DiffResult(text));
                 });
             }
+            SlashCommand::Copy => {
+                self.copy_last_message();
+            }
+            SlashCommand::Export => {
+                self.export_transcript(None);
+            }
+            SlashCommand::Annotate => {
+                self.app_event_tx.send(AppEvent::OpenAnnotationPicker);
+            }
             SlashCommand::Mention => {
                 self.insert_str("@");
             }
+            SlashCommand::Image => match paste_image_to_temp_png() {
+                Ok((path, info)) => {
+                    self.attach_image(path, info.width, info.height, info.encoded_format.label());
+                }
+                Err(err) => self.add_error_message(format!(
+                    "Failed to paste an image: {err}. Use /image <path> to attach a file."
+                )),
+            },
             SlashCommand::Status => {
-                self.add_status_output();
+                self.app_event_tx.send(AppEvent::ShowStatus);
+            }
+            SlashCommand::Context => {
+                self.submit_op(Op::GetContextUsage);
             }
             SlashCommand::Mcp => {
                 self.add_mcp_output();
             }
+            SlashCommand::Jobs => {
+                self.add_to_history(history_cell::new_jobs_output(&self.background_jobs));
+            }
+            SlashCommand::Memory => {
+                let path = memory_path(&self.config.codex_home, &self.config.cwd);
+                let notes = std::fs::read_to_string(&path).ok();
+                self.add_to_history(history_cell::new_memory_output(
+                    &path,
+                    notes.as_deref(),
+                    self.config.memory.enabled,
+                ));
+            }
+            SlashCommand::Debug => {
+                self.wire_logging = !self.wire_logging;
+                self.submit_op(Op::SetWireLogging {
+                    enabled: self.wire_logging,
+                });
+            }
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => {
                 use codex_core::protocol::EventMsg;
@@ -1171,6 +1700,7 @@ impl ChatWidget {
                         ]),
                         reason: None,
                         grant_root: Some(PathBuf::from("/tmp")),
+                        risk: None,
                     }),
                 }));
             }
@@ -1181,6 +1711,54 @@ impl ChatWidget {
         self.bottom_pane.handle_paste(text);
     }
 
+    /// Selects the numbered option `number` of the open popup, if any.
+    pub(crate) fn click_list_item(&mut self, number: usize) -> bool {
+        self.bottom_pane.handle_item_click(number)
+    }
+
+    /// Draws streamed text held back by the coalescers once it is due.
+    pub(crate) fn handle_stream_coalesce_tick(&mut self) {
+        let now = Instant::now();
+        if let Some(delta) = self.answer_coalescer.take_if_due(now) {
+            self.handle_streaming_delta(delta);
+        }
+        if let Some(delta) = self.reasoning_coalescer.take_if_due(now) {
+            self.apply_reasoning_delta(delta);
+        }
+        self.schedule_coalesced_flush();
+    }
+
+    fn schedule_coalesced_flush(&self) {
+        let deadline = [
+            self.answer_coalescer.deadline(),
+            self.reasoning_coalescer.deadline(),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        if let Some(deadline) = deadline {
+            self.frame_requester
+                .schedule_frame_in(deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+
+    /// Draws held-back streamed text before any other event so the transcript
+    /// keeps its order.
+    fn flush_coalesced_deltas(&mut self, msg: &EventMsg) {
+        if !matches!(msg, EventMsg::AgentMessageDelta(_))
+            && let Some(delta) = self.answer_coalescer.take()
+        {
+            self.handle_streaming_delta(delta);
+        }
+        if !matches!(
+            msg,
+            EventMsg::AgentReasoningDelta(_) | EventMsg::AgentReasoningRawContentDelta(_)
+        ) && let Some(delta) = self.reasoning_coalescer.take()
+        {
+            self.apply_reasoning_delta(delta);
+        }
+    }
+
     // Returns true if caller should skip rendering this frame (a future frame is scheduled).
     pub(crate) fn handle_paste_burst_tick(&mut self, frame_requester: FrameRequester) -> bool {
         if self.bottom_pane.flush_paste_burst_if_due() {
@@ -1220,94 +1798,222 @@ impl ChatWidget {
     }
 
     fn submit_user_message(&mut self, user_message: UserMessage) {
-        let UserMessage { text, image_paths } = user_message;
-        if text.is_empty() && image_paths.is_empty() {
+        self.send_user_message(user_message, |items| Op::UserInput { items });
+    }
+
+    /// Sends `text`, with the images attached in the composer, using
+    /// `overrides` for this turn only.
+    fn submit_user_message_with_overrides(&mut self, text: String, overrides: TurnOverrides) {
+        let user_message = UserMessage {
+            text,
+            image_paths: self.bottom_pane.take_recent_submission_images(),
+        };
+        if user_message.text.is_empty() && user_message.image_paths.is_empty() {
+            self.add_error_message("Type the message to send after the command.".to_string());
+            return;
+        }
+        self.send_user_message(user_message, |items| Op::UserInputWithOverrides {
+            items,
+            overrides,
+        });
+    }
+
+    fn send_user_message(
+        &mut self,
+        user_message: UserMessage,
+        op: impl FnOnce(Vec<InputItem>) -> Op,
+    ) {
+        if user_message.text.is_empty() && user_message.image_paths.is_empty() {
             return;
         }
 
-        self.capture_ghost_snapshot();
+        let (items, inlined) = self.user_input_items(&user_message);
+        self.codex_op_tx.send(op(items)).unwrap_or_else(|e| {
+            tracing::error!("failed to send message: {e}");
+        });
+        self.add_message_to_cross_session_history(&user_message.text);
+        self.add_user_message_to_history(user_message, &inlined);
+    }
+
+    /// Hands the message to core's queue; it shows up in the transcript once
+    /// core submits it, see [`Self::on_queued_user_input`].
+    fn queue_user_message(&mut self, user_message: UserMessage) {
+        if user_message.text.is_empty() && user_message.image_paths.is_empty() {
+            return;
+        }
 
+        let (items, inlined) = self.user_input_items(&user_message);
+        self.codex_op_tx
+            .send(Op::QueueUserInput { items })
+            .unwrap_or_else(|e| {
+                tracing::error!("failed to queue message: {e}");
+            });
+        self.add_message_to_cross_session_history(&user_message.text);
+        self.queued_user_messages.push_back(QueuedUserMessage {
+            id: None,
+            message: user_message,
+            inlined,
+        });
+        self.refresh_queued_user_messages();
+    }
+
+    /// The input items for a message, and the labels of the file mentions
+    /// inlined into it.
+    fn user_input_items(&self, user_message: &UserMessage) -> (Vec<InputItem>, Vec<String>) {
+        let UserMessage { text, image_paths } = user_message;
         let mut items: Vec<InputItem> = Vec::new();
 
         if !text.is_empty() {
             items.push(InputItem::Text { text: text.clone() });
         }
 
-        for path in image_paths {
-            items.push(InputItem::LocalImage { path });
+        let mut inlined = Vec::new();
+        for mention in file_mentions::find_mentions(text, &self.config.cwd) {
+            if let Some(context) = file_mentions::render_mention(&mention, &self.config.cwd) {
+                items.push(InputItem::Text { text: context });
+                inlined.push(mention.label());
+            }
         }
 
-        self.codex_op_tx
-            .send(Op::UserInput { items })
-            .unwrap_or_else(|e| {
-                tracing::error!("failed to send message: {e}");
-            });
+        for path in image_paths {
+            items.push(InputItem::LocalImage { path: path.clone() });
+        }
+        (items, inlined)
+    }
 
-        // Persist the text to cross-session message history.
+    /// Persist the text to cross-session message history.
+    fn add_message_to_cross_session_history(&self, text: &str) {
         if !text.is_empty() {
             self.codex_op_tx
-                .send(Op::AddToHistory { text: text.clone() })
+                .send(Op::AddToHistory {
+                    text: text.to_string(),
+                })
                 .unwrap_or_else(|e| {
                     tracing::error!("failed to send AddHistory op: {e}");
                 });
         }
+    }
 
-        // Only show the text portion in conversation history.
+    fn add_user_message_to_history(&mut self, user_message: UserMessage, inlined: &[String]) {
+        let UserMessage { text, image_paths } = user_message;
         if !text.is_empty() {
             self.add_to_history(history_cell::new_user_prompt(text));
         }
+        if !image_paths.is_empty() {
+            self.add_to_history(image_thumbnail::new_image_attachments(&image_paths));
+        }
+        if !inlined.is_empty() {
+            self.add_to_history(file_mentions::new_inlined_mentions(inlined));
+        }
         self.needs_final_message_separator = false;
     }
 
-    fn capture_ghost_snapshot(&mut self) {
-        if self.ghost_snapshots_disabled {
-            return;
-        }
+    fn show_log_level(&mut self) {
+        let log = match self.conversation_id {
+            Some(id) => session_log_path(&self.config.codex_home, id),
+            None => sessions_log_dir(&self.config.codex_home),
+        };
+        self.add_info_message(
+            format!("Session log level: {}", log_level()),
+            Some(format!("Written to {}", log.display())),
+        );
+    }
 
-        let options = CreateGhostCommitOptions::new(&self.config.cwd);
-        match create_ghost_commit(&options) {
-            Ok(commit) => {
-                self.ghost_snapshots.push(commit);
-                if self.ghost_snapshots.len() > MAX_TRACKED_GHOST_COMMITS {
-                    self.ghost_snapshots.remove(0);
-                }
-            }
-            Err(err) => {
-                self.ghost_snapshots_disabled = true;
-                let (message, hint) = match &err {
-                    GitToolingError::NotAGitRepository { .. } => (
-                        "Snapshots disabled: current directory is not a Git repository."
-                            .to_string(),
-                        None,
-                    ),
-                    _ => (
-                        format!("Snapshots disabled after error: {err}"),
-                        Some(
-                            "Restart Codex after resolving the issue to re-enable snapshots."
-                                .to_string(),
-                        ),
-                    ),
-                };
-                self.add_info_message(message, hint);
-                tracing::warn!("failed to create ghost snapshot: {err}");
-            }
-        }
+    /// Asks core to roll back the file changes of the last turn; the outcome
+    /// arrives as `EventMsg::TurnReverted`.
+    fn undo_last_turn(&mut self, force: bool) {
+        self.submit_op(Op::RevertTurn { force });
     }
 
-    fn undo_last_snapshot(&mut self) {
-        let Some(commit) = self.ghost_snapshots.pop() else {
-            self.add_info_message("No snapshot available to undo.".to_string(), None);
+    fn on_turn_reverted(&mut self, event: TurnRevertedEvent) {
+        let TurnRevertedEvent {
+            turn_id,
+            reverted,
+            conflicts,
+        } = event;
+        if turn_id.is_none() {
+            self.add_info_message("No file changes to undo.".to_string(), None);
             return;
+        }
+        let cwd = self.config.cwd.clone();
+        let display = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&cwd)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
         };
+        if reverted.is_empty() {
+            self.add_error_message(format!(
+                "Not undone: {} changed since the last turn. Run `/undo force` to overwrite.",
+                display(&conflicts)
+            ));
+        } else {
+            self.add_info_message(
+                format!("Undid the last turn's changes to {}", display(&reverted)),
+                (!conflicts.is_empty())
+                    .then(|| format!("overwrote later changes to {}", display(&conflicts))),
+            );
+        }
+    }
+
+    fn on_checkpoint_created(&mut self, event: CheckpointCreatedEvent) {
+        let CheckpointCreatedEvent { name } = event;
+        self.checkpoints.retain(|existing| existing != &name);
+        self.add_info_message(
+            format!("Saved checkpoint `{name}`"),
+            Some(format!("/checkpoint restore {name} goes back to it")),
+        );
+        self.checkpoints.push(name);
+    }
 
-        if let Err(err) = restore_ghost_commit(&self.config.cwd, &commit) {
-            self.add_error_message(format!("Failed to restore snapshot: {err}"));
-            self.ghost_snapshots.push(commit);
+    fn on_checkpoint_restored(&mut self, event: CheckpointRestoredEvent) {
+        let CheckpointRestoredEvent {
+            name,
+            restored,
+            reverted,
+            conflicts,
+        } = event;
+        let cwd = self.config.cwd.clone();
+        let display = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&cwd)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if !restored {
+            self.add_error_message(format!(
+                "Not restored: {} changed outside Codex. Run `/checkpoint restore {name} force` \
+                 to overwrite.",
+                display(&conflicts)
+            ));
             return;
         }
-
-        let short_id: String = commit.id().chars().take(8).collect();
-        self.add_info_message(format!("Restored workspace to snapshot {short_id}"), None);
+        // Checkpoints made after this one are gone.
+        if let Some(position) = self.checkpoints.iter().position(|n| n == &name) {
+            self.checkpoints.truncate(position + 1);
+        }
+        let files = if reverted.is_empty() {
+            "no file changes to revert".to_string()
+        } else {
+            format!("reverted {}", display(&reverted))
+        };
+        self.add_info_message(
+            format!("Restored checkpoint `{name}`; {files}"),
+            (!conflicts.is_empty())
+                .then(|| format!("overwrote later changes to {}", display(&conflicts))),
+        );
     }
 
     /// Replay a subset of initial events into the UI to seed the transcript when
@@ -1344,6 +2050,7 @@ impl ChatWidget {
                 tracing::trace!("handle_codex_event: {:?}", msg);
             }
         }
+        self.flush_coalesced_deltas(&msg);
 
         match msg {
             EventMsg::SessionConfigured(e) => self.on_session_configured(e),
@@ -1362,14 +2069,14 @@ impl ChatWidget {
             }
             EventMsg::AgentReasoningSectionBreak(_) => self.on_reasoning_section_break(),
             EventMsg::TaskStarted(_) => self.on_task_started(),
-            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
-                self.on_task_complete(last_agent_message)
-            }
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message, ..
+            }) => self.on_task_complete(last_agent_message),
             EventMsg::TokenCount(ev) => {
                 self.set_token_info(ev.info);
                 self.on_rate_limit_snapshot(ev.rate_limits);
             }
-            EventMsg::Error(ErrorEvent { message }) => self.on_error(message),
+            EventMsg::Error(ErrorEvent { message, .. }) => self.on_error(message),
             EventMsg::TurnAborted(ev) => match ev.reason {
                 TurnAbortReason::Interrupted => {
                     self.on_interrupted_turn(ev.reason);
@@ -1389,6 +2096,10 @@ impl ChatWidget {
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::SecretRequest(ev) => self.on_secret_request(id.unwrap_or_default(), ev),
+            EventMsg::McpSamplingApprovalRequest(ev) => {
+                self.on_mcp_sampling_approval_request(id.unwrap_or_default(), ev)
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
             EventMsg::PatchApplyBegin(ev) => self.on_patch_apply_begin(ev),
@@ -1401,8 +2112,13 @@ impl ChatWidget {
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::ContextUsage(ev) => {
+                self.add_to_history(history_cell::new_context_usage_output(ev));
+            }
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
-            EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
+            EventMsg::TurnDiff(TurnDiffEvent { unified_diff, .. }) => {
+                self.on_turn_diff(unified_diff)
+            }
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 self.on_background_event(message)
             }
@@ -1420,9 +2136,116 @@ impl ChatWidget {
                 self.on_entered_review_mode(review_request)
             }
             EventMsg::ExitedReviewMode(review) => self.on_exited_review_mode(review),
+            EventMsg::AnnotationAdded(AnnotationAddedEvent { target, comment }) => {
+                self.add_to_history(history_cell::new_annotation(target, comment));
+                self.request_redraw();
+            }
+            EventMsg::ContextPruned(ContextPrunedEvent {
+                strategy,
+                approx_tokens_freed,
+                items,
+            }) => {
+                self.add_info_message(
+                    format!(
+                        "Pruned {} tool outputs (~{approx_tokens_freed} tokens) from context",
+                        items.len()
+                    ),
+                    Some(format!("{strategy} strategy")),
+                );
+            }
+            EventMsg::LoginRequired(LoginRequiredEvent { message }) => {
+                // Unlike `on_error`, this does not end the turn: the request
+                // that triggered the refresh reports its own failure.
+                self.add_to_history(history_cell::new_error_event(format!(
+                    "{message}. Run `codex login` to sign in again."
+                )));
+                self.request_redraw();
+            }
+            EventMsg::BackgroundResponseStarted(BackgroundResponseStartedEvent { response_id }) => {
+                self.add_info_message(
+                    format!("Generating in background mode ({response_id})"),
+                    Some(format!(
+                        "resume from another process with `codex exec resume <SESSION_ID> --response-id {response_id}`"
+                    )),
+                );
+            }
+            EventMsg::BackgroundJob(ev) => self.on_background_job(ev),
+            EventMsg::ProviderEndpointSwitched(ProviderEndpointSwitchedEvent {
+                provider,
+                from,
+                to,
+                reason,
+            }) => {
+                self.add_info_message(
+                    format!("Switched {provider} requests from {from} to {to}"),
+                    Some(reason),
+                );
+            }
+            EventMsg::ProviderSwitched(ProviderSwitchedEvent { from, to, reason }) => {
+                self.add_info_message(format!("Switched from {from} to {to}"), Some(reason));
+            }
+            EventMsg::McpServerStatus(ev) => self.on_mcp_server_status(ev),
+            EventMsg::QueuedUserInput(ev) => self.on_queued_user_input(ev),
+            EventMsg::TurnReverted(ev) => self.on_turn_reverted(ev),
+            EventMsg::CheckpointCreated(ev) => self.on_checkpoint_created(ev),
+            EventMsg::CheckpointRestored(ev) => self.on_checkpoint_restored(ev),
+            EventMsg::ModelChanged(ev) => self.on_model_changed(ev),
+            EventMsg::PullRequestCreated(PullRequestCreatedEvent { url, branch, title }) => {
+                self.add_info_message(
+                    format!("Opened pull request: {url}"),
+                    Some(format!("{title} ({branch})")),
+                );
+            }
         }
     }
 
+    fn on_background_job(&mut self, ev: BackgroundJobEvent) {
+        if ev.status.is_finished() {
+            self.add_to_history(history_cell::new_background_job_finished(&ev));
+        }
+        match self
+            .background_jobs
+            .iter_mut()
+            .find(|job| job.job_id == ev.job_id)
+        {
+            Some(job) => *job = ev,
+            None => self.background_jobs.push(ev),
+        }
+        let running = self
+            .background_jobs
+            .iter()
+            .filter(|job| !job.status.is_finished())
+            .count();
+        self.bottom_pane.set_running_background_jobs(running);
+        self.request_redraw();
+    }
+
+    fn on_mcp_server_status(&mut self, ev: McpServerStatusEvent) {
+        let McpServerStatusEvent {
+            server,
+            status,
+            message,
+        } = ev;
+        match status {
+            McpServerStatus::Healthy => {
+                self.unhealthy_mcp_servers.remove(&server);
+                self.add_info_message(format!("MCP server {server} is back"), Some(message));
+            }
+            McpServerStatus::Degraded | McpServerStatus::Failed => {
+                let state = if status == McpServerStatus::Failed {
+                    "failed"
+                } else {
+                    "degraded"
+                };
+                self.add_error_message(format!("MCP server {server} {state}: {message}"));
+                self.unhealthy_mcp_servers.insert(server, status);
+            }
+        }
+        self.bottom_pane
+            .set_unhealthy_mcp_servers(self.unhealthy_mcp_servers.keys().cloned().collect());
+        self.request_redraw();
+    }
+
     fn on_entered_review_mode(&mut self, review: ReviewRequest) {
         // Enter review mode and emit a concise banner
         self.is_review_mode = true;
@@ -1492,7 +2315,11 @@ impl ChatWidget {
     }
 
     fn notify(&mut self, notification: Notification) {
-        if !notification.allowed_for(&self.config.tui_notifications) {
+        if !self
+            .config
+            .tui_notifications
+            .allows(notification.type_name())
+        {
             return;
         }
         self.pending_notification = Some(notification);
@@ -1501,7 +2328,7 @@ impl ChatWidget {
 
     pub(crate) fn maybe_post_pending_notification(&mut self, tui: &mut crate::tui::Tui) {
         if let Some(notif) = self.pending_notification.take() {
-            tui.notify(notif.display());
+            tui.notify(notif.display(), self.config.tui_notifications.method());
         }
     }
 
@@ -1518,16 +2345,48 @@ impl ChatWidget {
         }
     }
 
-    // If idle and there are queued inputs, submit exactly one to start the next turn.
-    fn maybe_send_next_queued_input(&mut self) {
-        if self.bottom_pane.is_task_running() {
-            return;
+    /// Mirror core's queue: core reports every change, in the order the
+    /// messages were queued.
+    fn on_queued_user_input(&mut self, event: QueuedUserInputEvent) {
+        let QueuedUserInputEvent { queued, submitted } = event;
+
+        // Ids core has not told us about belong to the messages we queued
+        // since, oldest first.
+        for input in queued.iter().chain(submitted.iter()) {
+            if self
+                .queued_user_messages
+                .iter()
+                .any(|q| q.id == Some(input.id))
+            {
+                continue;
+            }
+            if let Some(pending) = self
+                .queued_user_messages
+                .iter_mut()
+                .find(|q| q.id.is_none())
+            {
+                pending.id = Some(input.id);
+            }
         }
-        if let Some(user_message) = self.queued_user_messages.pop_front() {
-            self.submit_user_message(user_message);
+
+        if let Some(submitted) = submitted
+            && let Some(idx) = self
+                .queued_user_messages
+                .iter()
+                .position(|q| q.id == Some(submitted.id))
+            && let Some(QueuedUserMessage {
+                message, inlined, ..
+            }) = self.queued_user_messages.remove(idx)
+        {
+            self.add_user_message_to_history(message, &inlined);
         }
-        // Update the list to reflect the remaining queued messages (if any).
+
+        // Drop messages core no longer holds, e.g. after an interrupt.
+        self.queued_user_messages.retain(|q| {
+            q.id.is_none_or(|id| queued.iter().any(|input| input.id == id))
+        });
         self.refresh_queued_user_messages();
+        self.request_redraw();
     }
 
     /// Rebuild and update the queued user messages from the current queue.
@@ -1535,7 +2394,7 @@ impl ChatWidget {
         let messages: Vec<String> = self
             .queued_user_messages
             .iter()
-            .map(|m| m.text.clone())
+            .map(|m| m.message.text.clone())
             .collect();
         self.bottom_pane.set_queued_user_messages(messages);
     }
@@ -1564,6 +2423,17 @@ impl ChatWidget {
         ));
     }
 
+    /// The `/status` dashboard for the current session.
+    pub(crate) fn status_dashboard(&self) -> StatusDashboard {
+        StatusDashboard::new(
+            &self.config,
+            self.token_info.as_ref(),
+            &self.conversation_id,
+            self.rate_limit_snapshot.as_ref(),
+            &self.unhealthy_mcp_servers,
+        )
+    }
+
     /// Open a popup to choose the model preset (model + reasoning effort).
     pub(crate) fn open_model_popup(&mut self) {
         let current_model = self.config.model.clone();
@@ -1585,6 +2455,7 @@ impl ChatWidget {
                     approval_policy: None,
                     sandbox_policy: None,
                     model: Some(model_slug.clone()),
+                    provider: None,
                     effort: Some(effort),
                     summary: None,
                 }));
@@ -1646,6 +2517,7 @@ impl ChatWidget {
                     approval_policy: Some(approval),
                     sandbox_policy: Some(sandbox.clone()),
                     model: None,
+                    provider: None,
                     effort: None,
                     summary: None,
                 }));
@@ -1670,6 +2542,42 @@ impl ChatWidget {
         });
     }
 
+    /// Open a popup to choose the TUI theme for the rest of the session.
+    pub(crate) fn open_theme_popup(&mut self) {
+        let current = theme::current().name.clone();
+        let items: Vec<SelectionItem> = theme::theme_names(&self.config.tui_theme)
+            .into_iter()
+            .map(|name| {
+                let is_current = name == current;
+                let description = if self.config.tui_theme.palettes.contains_key(&name) {
+                    Some("defined in config.toml".to_string())
+                } else {
+                    None
+                };
+                let theme_name = name.clone();
+                let actions: Vec<SelectionAction> = vec![Box::new(move |tx| {
+                    tx.send(AppEvent::UpdateTheme(theme_name.clone()));
+                })];
+                SelectionItem {
+                    name,
+                    description,
+                    is_current,
+                    actions,
+                    dismiss_on_select: true,
+                    search_value: None,
+                }
+            })
+            .collect();
+
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: "Select Theme".to_string(),
+            subtitle: Some("Applies to new output; set [tui.theme] name to keep it".to_string()),
+            footer_hint: Some(STANDARD_POPUP_HINT_LINE.to_string()),
+            items,
+            ..Default::default()
+        });
+    }
+
     /// Set the approval policy in the widget's config copy.
     pub(crate) fn set_approval_policy(&mut self, policy: AskForApproval) {
         self.config.approval_policy = policy;
@@ -1740,6 +2648,10 @@ impl ChatWidget {
         self.bottom_pane.is_normal_backtrack_mode()
     }
 
+    pub(crate) fn is_task_running(&self) -> bool {
+        self.bottom_pane.is_task_running()
+    }
+
     pub(crate) fn insert_str(&mut self, text: &str) {
         self.bottom_pane.insert_str(text);
     }
@@ -1946,6 +2858,67 @@ impl ChatWidget {
         self.bottom_pane.show_view(Box::new(view));
     }
 
+    pub(crate) fn show_annotation_picker(&mut self, user_messages: Vec<String>) {
+        if user_messages.is_empty() {
+            self.add_info_message("No messages to annotate yet.".to_string(), None);
+            return;
+        }
+
+        // Most recent first: that is almost always what the user wants to comment on.
+        let items: Vec<SelectionItem> = user_messages
+            .into_iter()
+            .enumerate()
+            .rev()
+            .map(|(index, message)| {
+                let preview = message.lines().next().unwrap_or_default().to_string();
+                let search_value = Some(message);
+                let name = format!("#{} {preview}", index + 1);
+                SelectionItem {
+                    name,
+                    description: None,
+                    is_current: false,
+                    actions: vec![Box::new(move |tx: &AppEventSender| {
+                        tx.send(AppEvent::OpenAnnotationPrompt {
+                            index,
+                            preview: preview.clone(),
+                        });
+                    })],
+                    dismiss_on_select: false,
+                    search_value,
+                }
+            })
+            .collect();
+
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: "Select a message to annotate".to_string(),
+            footer_hint: Some(STANDARD_POPUP_HINT_LINE.to_string()),
+            items,
+            is_searchable: true,
+            search_placeholder: Some("Type to search messages".to_string()),
+            ..Default::default()
+        });
+    }
+
+    pub(crate) fn show_annotation_prompt(&mut self, index: usize, preview: String) {
+        let tx = self.app_event_tx.clone();
+        let view = CustomPromptView::new(
+            "Annotate message".to_string(),
+            "Type a comment and press Enter".to_string(),
+            Some(preview),
+            Box::new(move |comment: String| {
+                let comment = comment.trim().to_string();
+                if comment.is_empty() {
+                    return;
+                }
+                tx.send(AppEvent::CodexOp(Op::AddAnnotation {
+                    target: AnnotationTarget::UserMessage { index },
+                    comment,
+                }));
+            }),
+        );
+        self.bottom_pane.show_view(Box::new(view));
+    }
+
     /// Programmatically submit a user text message as if typed in the
     /// composer. The text will be added to conversation history and sent to
     /// the agent.
@@ -1967,6 +2940,10 @@ impl ChatWidget {
         self.conversation_id
     }
 
+    pub(crate) fn rollout_path(&self) -> Option<&Path> {
+        self.rollout_path.as_deref()
+    }
+
     /// Return a reference to the widget's current config (includes any
     /// runtime overrides applied via TUI, e.g., model or approval policy).
     pub(crate) fn config_ref(&self) -> &Config {
@@ -2007,6 +2984,8 @@ enum Notification {
     AgentTurnComplete { response: String },
     ExecApprovalRequested { command: String },
     EditApprovalRequested { cwd: PathBuf, changes: Vec<PathBuf> },
+    SecretRequested { name: String },
+    SamplingApprovalRequested { server: String },
 }
 
 impl Notification {
@@ -2019,6 +2998,10 @@ impl Notification {
             Notification::ExecApprovalRequested { command } => {
                 format!("Approval requested: {}", truncate_text(command, 30))
             }
+            Notification::SecretRequested { name } => format!("Codex needs {name}"),
+            Notification::SamplingApprovalRequested { server } => {
+                format!("MCP server {server} wants to use the model")
+            }
             Notification::EditApprovalRequested { cwd, changes } => {
                 format!(
                     "Codex wants to edit {}",
@@ -2037,14 +3020,9 @@ impl Notification {
         match self {
             Notification::AgentTurnComplete { .. } => "agent-turn-complete",
             Notification::ExecApprovalRequested { .. }
-            | Notification::EditApprovalRequested { .. } => "approval-requested",
-        }
-    }
-
-    fn allowed_for(&self, settings: &Notifications) -> bool {
-        match settings {
-            Notifications::Enabled(enabled) => *enabled,
-            Notifications::Custom(allowed) => allowed.iter().any(|a| a == self.type_name()),
+            | Notification::EditApprovalRequested { .. }
+            | Notification::SecretRequested { .. }
+            | Notification::SamplingApprovalRequested { .. } => "approval-requested",
         }
     }
 
@@ -2076,6 +3054,56 @@ const EXAMPLE_PROMPTS: [&str; 6] = [
     "Improve documentation in @filename",
 ];
 
+/// Reads the leading `effort=`, `verbosity=` and `temperature=` settings of
+/// `/turn` and returns them with the message that follows.
+fn parse_turn_overrides(args: &str) -> Result<(TurnOverrides, String), String> {
+    let mut overrides = TurnOverrides::default();
+    let mut rest = args.trim_start();
+    loop {
+        let (token, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let Some((key, value)) = token.split_once('=') else {
+            break;
+        };
+        match key {
+            "effort" => {
+                overrides.effort = Some(parse_level(key, value, "minimal, low, medium or high")?)
+            }
+            "verbosity" => {
+                overrides.verbosity = Some(parse_level(key, value, "low, medium or high")?)
+            }
+            "temperature" => {
+                let temperature = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|t| (0.0..=2.0).contains(t))
+                    .ok_or_else(|| {
+                        format!("Invalid temperature `{value}`; use a number from 0 to 2.")
+                    })?;
+                overrides.temperature = Some(temperature);
+            }
+            // Not a setting, so the message starts here.
+            _ => break,
+        }
+        rest = tail.trim_start();
+    }
+    if overrides == TurnOverrides::default() {
+        return Err(
+            "Use /turn [effort=<level>] [verbosity=<level>] [temperature=<0-2>] <message>."
+                .to_string(),
+        );
+    }
+    Ok((overrides, rest.to_string()))
+}
+
+fn parse_level<T: serde::de::DeserializeOwned>(
+    key: &str,
+    value: &str,
+    levels: &str,
+) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("Unknown {key} `{value}`; use {levels}."))
+}
+
 // Extract the first bold (Markdown) element in the form **...** from `s`.
 // Returns the inner text if found; otherwise `None`.
 fn extract_first_bold(s: &str) -> Option<String> {
@@ -7,6 +7,7 @@ use codex_core::CodexAuth;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::ConfigToml;
+use codex_core::config_types::StreamCoalescing;
 use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
 use codex_core::plan_tool::UpdatePlanArgs;
@@ -14,18 +15,24 @@ use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningEvent;
+use codex_core::protocol::AnnotationAddedEvent;
+use codex_core::protocol::AnnotationTarget;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::ExecCommandOutputDeltaEvent;
+use codex_core::protocol::ExecOutputStream;
 use codex_core::protocol::ExitedReviewModeEvent;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::QueuedUserInput;
+use codex_core::protocol::QueuedUserInputEvent;
 use codex_core::protocol::ReviewCodeLocation;
 use codex_core::protocol::ReviewFinding;
 use codex_core::protocol::ReviewLineRange;
@@ -165,6 +172,7 @@ fn resumed_initial_messages_render_history() {
             }),
         ]),
         rollout_path: rollout_file.path().to_path_buf(),
+        sandbox_capability: None,
     };
 
     chat.handle_codex_event(Event {
@@ -311,6 +319,7 @@ fn make_chatwidget_manual() -> (
         enhanced_keys_supported: false,
         placeholder_text: "Ask Codex to do anything".to_string(),
         disable_paste_burst: false,
+        editor_mode: cfg.tui_editor_mode,
     });
     let auth_manager = AuthManager::from_auth_for_testing(CodexAuth::from_api_key("test"));
     let widget = ChatWidget {
@@ -327,20 +336,27 @@ fn make_chatwidget_manual() -> (
         rate_limit_warnings: RateLimitWarningState::default(),
         stream_controller: None,
         running_commands: HashMap::new(),
+        background_jobs: Vec::new(),
+        unhealthy_mcp_servers: BTreeMap::new(),
+        wire_logging: cfg.wire_log,
         task_complete_pending: false,
         interrupts: InterruptManager::new(),
         reasoning_buffer: String::new(),
         full_reasoning_buffer: String::new(),
+        answer_coalescer: DeltaCoalescer::new(&cfg.stream_coalescing),
+        reasoning_coalescer: DeltaCoalescer::new(&cfg.stream_coalescing),
         conversation_id: None,
+        rollout_path: None,
+        last_agent_message: None,
         frame_requester: FrameRequester::test_dummy(),
         show_welcome_banner: true,
         queued_user_messages: VecDeque::new(),
         suppress_session_configured_redraw: false,
         pending_notification: None,
+        turn_started_at: None,
         is_review_mode: false,
-        ghost_snapshots: Vec::new(),
-        ghost_snapshots_disabled: false,
         needs_final_message_separator: false,
+        checkpoints: Vec::new(),
     };
     (widget, rx, op_rx)
 }
@@ -564,6 +580,7 @@ fn end_exec(chat: &mut ChatWidget, call_id: &str, stdout: &str, stderr: &str, ex
             exit_code,
             duration: std::time::Duration::from_millis(5),
             formatted_output: aggregated,
+            sandbox_denied: false,
         }),
     });
 }
@@ -617,17 +634,14 @@ fn empty_enter_during_task_does_not_queue() {
 
 #[test]
 fn alt_up_edits_most_recent_queued_message() {
-    let (mut chat, _rx, _op_rx) = make_chatwidget_manual();
+    let (mut chat, _rx, mut op_rx) = make_chatwidget_manual();
 
     // Simulate a running task so messages would normally be queued.
     chat.bottom_pane.set_task_running(true);
 
     // Seed two queued messages.
-    chat.queued_user_messages
-        .push_back(UserMessage::from("first queued".to_string()));
-    chat.queued_user_messages
-        .push_back(UserMessage::from("second queued".to_string()));
-    chat.refresh_queued_user_messages();
+    queue_message(&mut chat, 1, "first queued");
+    queue_message(&mut chat, 2, "second queued");
 
     // Press Alt+Up to edit the most recent (last) queued message.
     chat.handle_key_event(KeyEvent::new(KeyCode::Up, KeyModifiers::ALT));
@@ -640,9 +654,75 @@ fn alt_up_edits_most_recent_queued_message() {
     // And the queue should now contain only the remaining (older) item.
     assert_eq!(chat.queued_user_messages.len(), 1);
     assert_eq!(
-        chat.queued_user_messages.front().unwrap().text,
+        chat.queued_user_messages.front().unwrap().message.text,
         "first queued"
     );
+    // Core is asked to drop the message being edited.
+    assert_eq!(op_rx.try_recv(), Ok(Op::RemoveQueuedUserInput { id: 2 }));
+}
+
+fn queue_message(chat: &mut ChatWidget, id: u64, text: &str) {
+    chat.queued_user_messages.push_back(QueuedUserMessage {
+        id: Some(id),
+        message: UserMessage::from(text.to_string()),
+        inlined: Vec::new(),
+    });
+    chat.refresh_queued_user_messages();
+}
+
+#[test]
+fn submit_during_task_queues_in_core_until_submitted() {
+    let (mut chat, mut rx, mut op_rx) = make_chatwidget_manual();
+    chat.bottom_pane.set_task_running(true);
+
+    chat.bottom_pane.set_composer_text("follow up".to_string());
+    chat.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    assert!(matches!(op_rx.try_recv(), Ok(Op::QueueUserInput { .. })));
+    assert!(drain_insert_history(&mut rx).is_empty());
+    assert_eq!(chat.queued_user_messages.len(), 1);
+
+    let queued = QueuedUserInput {
+        id: 7,
+        text: "follow up".to_string(),
+    };
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::QueuedUserInput(QueuedUserInputEvent {
+            queued: vec![queued.clone()],
+            submitted: None,
+        }),
+    });
+    assert_eq!(chat.queued_user_messages.front().unwrap().id, Some(7));
+
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::QueuedUserInput(QueuedUserInputEvent {
+            queued: Vec::new(),
+            submitted: Some(queued),
+        }),
+    });
+    assert!(chat.queued_user_messages.is_empty());
+    let cells = drain_insert_history(&mut rx);
+    let rendered = cells
+        .iter()
+        .map(|lines| lines_to_single_string(lines))
+        .collect::<String>();
+    assert!(rendered.contains("follow up"), "{rendered}");
+}
+
+#[test]
+fn steer_key_during_task_sends_input_now() {
+    let (mut chat, mut rx, mut op_rx) = make_chatwidget_manual();
+    chat.bottom_pane.set_task_running(true);
+
+    chat.bottom_pane
+        .set_composer_text("try the other file".to_string());
+    chat.handle_key_event(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+
+    assert!(matches!(op_rx.try_recv(), Ok(Op::UserInput { .. })));
+    assert!(chat.queued_user_messages.is_empty());
+    assert!(!drain_insert_history(&mut rx).is_empty());
 }
 
 #[test]
@@ -675,6 +755,46 @@ fn exec_history_cell_shows_working_then_completed() {
     );
 }
 
+#[test]
+fn exec_history_cell_shows_streamed_output_while_running() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+
+    begin_exec(&mut chat, "call-1", "cargo build");
+    for chunk in [
+        "Compiling a\nCompiling b\n",
+        "Downloading 10%\rDownloading 90%",
+    ] {
+        chat.handle_codex_event(Event {
+            id: "call-1".to_string(),
+            msg: EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+                call_id: "call-1".to_string(),
+                stream: ExecOutputStream::Stdout,
+                chunk: chunk.as_bytes().to_vec(),
+            }),
+        });
+    }
+    assert!(drain_insert_history(&mut rx).is_empty());
+
+    let blob = active_blob(&chat);
+    assert!(
+        blob.contains("Compiling b"),
+        "expected live output: {blob:?}"
+    );
+    assert!(
+        blob.contains("Downloading 90%") && !blob.contains("Downloading 10%"),
+        "expected only the latest progress line: {blob:?}"
+    );
+
+    end_exec(&mut chat, "call-1", "Finished\n", "", 0);
+    let cells = drain_insert_history(&mut rx);
+    assert_eq!(cells.len(), 1, "expected finalized exec cell to flush");
+    let blob = lines_to_single_string(&cells[0]);
+    assert!(
+        blob.contains("Finished") && !blob.contains("Compiling"),
+        "expected the final output to replace the live output: {blob:?}"
+    );
+}
+
 #[test]
 fn exec_history_cell_shows_working_then_failed() {
     let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
@@ -828,6 +948,43 @@ fn custom_prompt_enter_empty_does_not_send() {
     assert!(rx.try_recv().is_err(), "no app event should be sent");
 }
 
+#[test]
+fn annotation_prompt_submit_sends_add_annotation_op() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+
+    chat.show_annotation_prompt(1, "fix the tests".to_string());
+    chat.handle_paste("  this broke the build  ".to_string());
+    chat.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    let evt = rx.try_recv().expect("expected one app event");
+    match evt {
+        AppEvent::CodexOp(Op::AddAnnotation { target, comment }) => {
+            assert_eq!(target, AnnotationTarget::UserMessage { index: 1 });
+            assert_eq!(comment, "this broke the build".to_string());
+        }
+        other => panic!("unexpected app event: {other:?}"),
+    }
+}
+
+#[test]
+fn annotation_added_is_rendered_to_history() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    chat.handle_codex_event(Event {
+        id: "sub-1".into(),
+        msg: EventMsg::AnnotationAdded(AnnotationAddedEvent {
+            target: AnnotationTarget::UserMessage { index: 0 },
+            comment: "wrong approach".to_string(),
+        }),
+    });
+
+    let cells = drain_insert_history(&mut rx);
+    assert_eq!(cells.len(), 1);
+    assert_eq!(
+        lines_to_single_string(&cells[0]),
+        "✎ Annotated user message #1: wrong approach\n"
+    );
+}
+
 // Snapshot test: interrupting a running exec finalizes the active cell with a red ✗
 // marker (replacing the spinner) and flushes it into history.
 #[test]
@@ -997,6 +1154,17 @@ fn disabled_slash_command_while_task_running_snapshot() {
     assert_snapshot!(blob);
 }
 
+#[test]
+fn debug_slash_command_toggles_wire_logging() {
+    let (mut chat, _rx, mut op_rx) = make_chatwidget_manual();
+
+    chat.dispatch_command(SlashCommand::Debug);
+    chat.dispatch_command(SlashCommand::Debug);
+
+    assert_eq!(op_rx.try_recv(), Ok(Op::SetWireLogging { enabled: true }));
+    assert_eq!(op_rx.try_recv(), Ok(Op::SetWireLogging { enabled: false }));
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn binary_size_transcript_snapshot() {
     let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
@@ -1233,6 +1401,7 @@ fn approval_modal_patch_snapshot() {
         changes,
         reason: Some("The model wants to apply changes".into()),
         grant_root: Some(PathBuf::from("/tmp")),
+        risk: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-patch".into(),
@@ -1257,11 +1426,8 @@ fn interrupt_restores_queued_messages_into_composer() {
     chat.bottom_pane.set_task_running(true);
 
     // Queue two user messages while the task is running.
-    chat.queued_user_messages
-        .push_back(UserMessage::from("first queued".to_string()));
-    chat.queued_user_messages
-        .push_back(UserMessage::from("second queued".to_string()));
-    chat.refresh_queued_user_messages();
+    queue_message(&mut chat, 1, "first queued");
+    queue_message(&mut chat, 2, "second queued");
 
     // Deliver a TurnAborted event with Interrupted reason (as if Esc was pressed).
     chat.handle_codex_event(Event {
@@ -1296,11 +1462,8 @@ fn interrupt_prepends_queued_messages_before_existing_composer_text() {
     chat.bottom_pane
         .set_composer_text("current draft".to_string());
 
-    chat.queued_user_messages
-        .push_back(UserMessage::from("first queued".to_string()));
-    chat.queued_user_messages
-        .push_back(UserMessage::from("second queued".to_string()));
-    chat.refresh_queued_user_messages();
+    queue_message(&mut chat, 1, "first queued");
+    queue_message(&mut chat, 2, "second queued");
 
     chat.handle_codex_event(Event {
         id: "turn-1".into(),
@@ -1462,6 +1625,7 @@ fn apply_patch_events_emit_history_cells() {
         changes,
         reason: None,
         grant_root: None,
+        risk: None,
     };
     chat.handle_codex_event(Event {
         id: "s1".into(),
@@ -1536,6 +1700,7 @@ fn apply_patch_manual_approval_adjusts_header() {
             changes: proposed_changes,
             reason: None,
             grant_root: None,
+            risk: None,
         }),
     });
     drain_insert_history(&mut rx);
@@ -1583,6 +1748,7 @@ fn apply_patch_manual_flow_snapshot() {
             changes: proposed_changes,
             reason: Some("Manual review required".into()),
             grant_root: None,
+            risk: None,
         }),
     });
     let proposed_lines = drain_insert_history(&mut rx)
@@ -1634,6 +1800,7 @@ fn apply_patch_approval_sends_op_with_submission_id() {
         changes,
         reason: None,
         grant_root: None,
+        risk: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-123".into(),
@@ -1676,6 +1843,7 @@ fn apply_patch_full_flow_integration_like() {
             changes,
             reason: None,
             grant_root: None,
+            risk: None,
         }),
     });
 
@@ -1750,6 +1918,7 @@ fn apply_patch_untrusted_shows_approval_modal() {
             changes,
             reason: None,
             grant_root: None,
+            risk: None,
         }),
     });
 
@@ -1798,6 +1967,7 @@ fn apply_patch_request_shows_diff_summary() {
             changes,
             reason: None,
             grant_root: None,
+            risk: None,
         }),
     });
 
@@ -1910,6 +2080,7 @@ fn multiple_agent_messages_in_single_turn_emit_multiple_headers() {
         id: "s1".into(),
         msg: EventMsg::TaskComplete(TaskCompleteEvent {
             last_agent_message: None,
+            failure: None,
         }),
     });
 
@@ -2019,6 +2190,44 @@ fn deltas_then_same_final_message_are_rendered_snapshot() {
     assert_snapshot!(combined);
 }
 
+#[test]
+fn coalesced_answer_deltas_are_held_until_the_final_message() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    chat.answer_coalescer = DeltaCoalescer::new(&StreamCoalescing {
+        interval_ms: 60_000,
+        max_bytes: None,
+        ssh_only: false,
+    });
+
+    chat.handle_codex_event(Event {
+        id: "s1".into(),
+        msg: EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+            delta: "First line.\n".into(),
+        }),
+    });
+    chat.handle_codex_event(Event {
+        id: "s1".into(),
+        msg: EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+            delta: "Second line.".into(),
+        }),
+    });
+    assert!(chat.stream_controller.is_none());
+    assert!(drain_insert_history(&mut rx).is_empty());
+
+    chat.handle_codex_event(Event {
+        id: "s1".into(),
+        msg: EventMsg::AgentMessage(AgentMessageEvent {
+            message: "First line.\nSecond line.".into(),
+        }),
+    });
+    let combined = drain_insert_history(&mut rx)
+        .iter()
+        .map(|lines| lines_to_single_string(lines))
+        .collect::<String>();
+    assert!(combined.contains("First line."), "{combined}");
+    assert!(combined.contains("Second line."), "{combined}");
+}
+
 // Combined visual snapshot using vt100 for history + direct buffer overlay for UI.
 // This renders the final visual as seen in a terminal: history above, then a blank line,
 // then the exec block, another blank line, the status line, a blank line, and the composer.
@@ -2061,6 +2270,7 @@ fn chatwidget_exec_and_status_layout_vt100_snapshot() {
             exit_code: 0,
             duration: std::time::Duration::from_millis(16000),
             formatted_output: String::new(),
+            sandbox_denied: false,
         }),
     });
     chat.handle_codex_event(Event {
@@ -2180,6 +2390,7 @@ printf 'fenced within fenced\n'
         id: "t1".into(),
         msg: EventMsg::TaskComplete(TaskCompleteEvent {
             last_agent_message: None,
+            failure: None,
         }),
     });
     for lines in drain_insert_history(&mut rx) {
@@ -2188,3 +2399,24 @@ printf 'fenced within fenced\n'
 
     assert_snapshot!(term.backend().vt100().screen().contents());
 }
+
+#[test]
+fn turn_command_reads_leading_settings() {
+    let (overrides, text) =
+        parse_turn_overrides("effort=low temperature=0.2 fix the\ntests").expect("overrides");
+    assert_eq!(
+        overrides,
+        TurnOverrides {
+            effort: Some(ReasoningEffortConfig::Low),
+            verbosity: None,
+            temperature: Some(0.2),
+        }
+    );
+    assert_eq!(text, "fix the\ntests");
+
+    assert_eq!(
+        parse_turn_overrides("verbosity=loud hi").expect_err("invalid verbosity"),
+        "Unknown verbosity `loud`; use low, medium or high."
+    );
+    assert!(parse_turn_overrides("just a message").is_err());
+}
@@ -0,0 +1,89 @@
+//! Copying text from the TUI to the clipboard.
+//!
+//! Locally the system clipboard is used. Over SSH, or when the system
+//! clipboard cannot be reached, the text is sent to the terminal with OSC 52,
+//! which most terminals (and tmux, with `set-clipboard on`) forward to the
+//! clipboard of the machine the user sits at.
+
+use std::io::Write;
+use std::io::stdout;
+
+use base64::Engine;
+
+/// How text reached the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CopyMethod {
+    System,
+    Osc52,
+}
+
+/// Copies `text` to the clipboard.
+pub(crate) fn copy_to_clipboard(text: &str) -> std::io::Result<CopyMethod> {
+    let over_ssh =
+        std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some();
+    if !over_ssh {
+        match copy_with_system_clipboard(text) {
+            Ok(()) => return Ok(CopyMethod::System),
+            Err(err) => tracing::debug!("system clipboard unavailable, using OSC 52: {err}"),
+        }
+    }
+    let inside_tmux = std::env::var_os("TMUX").is_some();
+    let mut out = stdout();
+    out.write_all(osc52_sequence(text, inside_tmux).as_bytes())?;
+    out.flush()?;
+    Ok(CopyMethod::Osc52)
+}
+
+#[cfg(not(target_os = "android"))]
+fn copy_with_system_clipboard(text: &str) -> Result<(), String> {
+    use std::sync::Mutex;
+    use std::sync::OnceLock;
+
+    // On X11 and Wayland the copied text is served by the `Clipboard`, so it
+    // is kept for the life of the process rather than dropped after copying.
+    static CLIPBOARD: OnceLock<Mutex<Option<arboard::Clipboard>>> = OnceLock::new();
+    let mut clipboard = CLIPBOARD
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|e| e.to_string())?;
+    if clipboard.is_none() {
+        *clipboard = Some(arboard::Clipboard::new().map_err(|e| e.to_string())?);
+    }
+    match clipboard.as_mut() {
+        Some(clipboard) => clipboard.set_text(text).map_err(|e| e.to_string()),
+        None => Err("clipboard unavailable".to_string()),
+    }
+}
+
+/// Android/Termux does not support arboard.
+#[cfg(target_os = "android")]
+fn copy_with_system_clipboard(_text: &str) -> Result<(), String> {
+    Err("clipboard copy is unsupported on Android".to_string())
+}
+
+/// The OSC 52 sequence that sets the clipboard to `text`, wrapped for tmux
+/// to pass through when `inside_tmux`.
+fn osc52_sequence(text: &str, inside_tmux: bool) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let sequence = format!("\x1b]52;c;{encoded}\x07");
+    if inside_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn osc52_carries_base64_text() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+}
@@ -373,6 +373,11 @@ where
         Ok(())
     }
 
+    /// The buffer of the last frame drawn, in screen coordinates.
+    pub fn last_buffer(&self) -> &Buffer {
+        &self.buffers[1 - self.current]
+    }
+
     /// Clears the inactive buffer and swaps it with the current buffer
     pub fn swap_buffers(&mut self) {
         self.buffers[1 - self.current].reset();
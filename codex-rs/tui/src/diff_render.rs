@@ -1,6 +1,4 @@
 use diffy::Hunk;
-use ratatui::style::Color;
-use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::style::Stylize;
 use ratatui::text::Line as RtLine;
@@ -10,12 +8,22 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use crate::exec_command::relativize_to_home;
+use crate::external_diff::render_file_change;
 use crate::history_cell::PatchEventType;
+use crate::render::highlight::Language;
+use crate::render::highlight::highlight_spans;
+use crate::render::highlight::language_for_path;
+use crate::render::line_utils::prefix_lines;
+use crate::theme;
+use codex_core::config_types::DiffRenderer;
 use codex_core::git_info::get_git_repo_root;
 use codex_core::protocol::FileChange;
 
 const SPACES_AFTER_LINE_NUMBER: usize = 6;
 
+/// Indentation applied to output from an external diff renderer.
+const EXTERNAL_DIFF_INDENT: &str = "    ";
+
 // Internal representation for diff line rendering
 enum DiffLineType {
     Insert,
@@ -28,6 +36,7 @@ pub(crate) fn create_diff_summary(
     event_type: PatchEventType,
     cwd: &Path,
     wrap_cols: usize,
+    diff_renderer: Option<&DiffRenderer>,
 ) -> Vec<RtLine<'static>> {
     let rows = collect_rows(changes);
     let header_kind = match event_type {
@@ -40,7 +49,7 @@ pub(crate) fn create_diff_summary(
         }
         PatchEventType::ApprovalRequest => HeaderKind::ProposedChange,
     };
-    render_changes_block(rows, wrap_cols, header_kind, cwd)
+    render_changes_block(rows, wrap_cols, header_kind, cwd, diff_renderer)
 }
 
 // Shared row for per-file presentation
@@ -92,6 +101,7 @@ fn render_changes_block(
     wrap_cols: usize,
     header_kind: HeaderKind,
     cwd: &Path,
+    diff_renderer: Option<&DiffRenderer>,
 ) -> Vec<RtLine<'static>> {
     let mut out: Vec<RtLine<'static>> = Vec::new();
     let term_cols = wrap_cols;
@@ -99,9 +109,9 @@ fn render_changes_block(
     fn render_line_count_summary(added: usize, removed: usize) -> Vec<RtSpan<'static>> {
         let mut spans = Vec::new();
         spans.push("(".into());
-        spans.push(format!("+{added}").green());
+        spans.push(RtSpan::styled(format!("+{added}"), style_add()));
         spans.push(" ".into());
-        spans.push(format!("-{removed}").red());
+        spans.push(RtSpan::styled(format!("-{removed}"), style_del()));
         spans.push(")".into());
         spans
     }
@@ -172,15 +182,28 @@ fn render_changes_block(
         return out;
     }
 
+    // Proposed changes are reviewed in the diff viewer, so the history only
+    // lists the files.
+    if matches!(header_kind, HeaderKind::ProposedChange) {
+        if file_count > 1 {
+            for r in &rows {
+                let mut header: Vec<RtSpan<'static>> = vec!["  └ ".dim()];
+                header.extend(render_path(r));
+                header.push(" ".into());
+                header.extend(render_line_count_summary(r.added, r.removed));
+                out.push(RtLine::from(header));
+            }
+        }
+        return out;
+    }
+
     for (idx, r) in rows.into_iter().enumerate() {
         // Insert a blank separator between file chunks (except before the first)
         if idx > 0 {
             out.push("".into());
         }
         // File header line (skip when single-file header already shows the name)
-        let skip_file_header =
-            matches!(header_kind, HeaderKind::ProposedChange | HeaderKind::Edited)
-                && file_count == 1;
+        let skip_file_header = matches!(header_kind, HeaderKind::Edited) && file_count == 1;
         if !skip_file_header {
             let mut header: Vec<RtSpan<'static>> = Vec::new();
             header.push("  └ ".dim());
@@ -190,6 +213,23 @@ fn render_changes_block(
             out.push(RtLine::from(header));
         }
 
+        if let Some(renderer) = diff_renderer
+            && let Some(lines) = render_file_change(
+                renderer,
+                &display_path_for(&r.path, cwd),
+                &r.change,
+                term_cols.saturating_sub(EXTERNAL_DIFF_INDENT.len()),
+            )
+        {
+            out.extend(prefix_lines(
+                lines,
+                EXTERNAL_DIFF_INDENT.into(),
+                EXTERNAL_DIFF_INDENT.into(),
+            ));
+            continue;
+        }
+
+        let language = language_for_path(&r.path);
         match r.change {
             FileChange::Add { content } => {
                 for (i, raw) in content.lines().enumerate() {
@@ -198,6 +238,7 @@ fn render_changes_block(
                         DiffLineType::Insert,
                         raw,
                         term_cols,
+                        language,
                     ));
                 }
             }
@@ -208,6 +249,7 @@ fn render_changes_block(
                         DiffLineType::Delete,
                         raw,
                         term_cols,
+                        language,
                     ));
                 }
             }
@@ -231,6 +273,7 @@ fn render_changes_block(
                                         DiffLineType::Insert,
                                         s,
                                         term_cols,
+                                        language,
                                     ));
                                     new_ln += 1;
                                 }
@@ -241,6 +284,7 @@ fn render_changes_block(
                                         DiffLineType::Delete,
                                         s,
                                         term_cols,
+                                        language,
                                     ));
                                     old_ln += 1;
                                 }
@@ -251,6 +295,7 @@ fn render_changes_block(
                                         DiffLineType::Context,
                                         s,
                                         term_cols,
+                                        language,
                                     ));
                                     old_ln += 1;
                                     new_ln += 1;
@@ -301,6 +346,7 @@ fn push_wrapped_diff_line(
     kind: DiffLineType,
     text: &str,
     term_cols: usize,
+    language: Option<&Language>,
 ) -> Vec<RtLine<'static>> {
     let indent = "    ";
     let ln_str = line_number.to_string();
@@ -320,6 +366,14 @@ fn push_wrapped_diff_line(
         DiffLineType::Context => (' ', style_context()),
     };
     let mut lines: Vec<RtLine<'static>> = Vec::new();
+    // Code keeps the color of its diff kind except where syntax colors it.
+    let code = |chunk: &str| -> Vec<RtSpan<'static>> {
+        let spans = vec![RtSpan::styled(chunk.to_string(), line_style)];
+        match language {
+            Some(language) => highlight_spans(spans, language),
+            None => spans,
+        }
+    };
 
     loop {
         // Fit the content for the current terminal row:
@@ -338,19 +392,19 @@ fn push_wrapped_diff_line(
             // Build gutter (indent + line number + spacing) as a dimmed span
             let gutter = format!("{indent}{ln_str}{}", " ".repeat(gap_after_ln));
             // Content with a sign ('+'/'-'/' ') styled per diff kind
-            let content = format!("{sign_char}{chunk}");
-            lines.push(RtLine::from(vec![
+            let mut spans = vec![
                 RtSpan::styled(gutter, style_gutter()),
-                RtSpan::styled(content, line_style),
-            ]));
+                RtSpan::styled(sign_char.to_string(), line_style),
+            ];
+            spans.extend(code(chunk));
+            lines.push(RtLine::from(spans));
             first = false;
         } else {
             // Continuation lines keep a space for the sign column so content aligns
             let gutter = format!("{indent}{} ", " ".repeat(ln_str.len() + gap_after_ln));
-            lines.push(RtLine::from(vec![
-                RtSpan::styled(gutter, style_gutter()),
-                RtSpan::styled(chunk.to_string(), line_style),
-            ]));
+            let mut spans = vec![RtSpan::styled(gutter, style_gutter())];
+            spans.extend(code(chunk));
+            lines.push(RtLine::from(spans));
         }
         if remaining_text.is_empty() {
             break;
@@ -360,7 +414,7 @@ fn push_wrapped_diff_line(
 }
 
 fn style_gutter() -> Style {
-    Style::default().add_modifier(Modifier::DIM)
+    theme::current().diff_gutter
 }
 
 fn style_context() -> Style {
@@ -368,11 +422,11 @@ fn style_context() -> Style {
 }
 
 fn style_add() -> Style {
-    Style::default().fg(Color::Green)
+    theme::current().diff_add
 }
 
 fn style_del() -> Style {
-    Style::default().fg(Color::Red)
+    theme::current().diff_delete
 }
 
 #[cfg(test)]
@@ -389,7 +443,7 @@ mod tests {
         changes: &HashMap<PathBuf, FileChange>,
         event_type: PatchEventType,
     ) -> Vec<RtLine<'static>> {
-        create_diff_summary(changes, event_type, &PathBuf::from("/"), 80)
+        create_diff_summary(changes, event_type, &PathBuf::from("/"), 80, None)
     }
 
     fn snapshot_lines(name: &str, lines: Vec<RtLine<'static>>, width: u16, height: u16) {
@@ -431,7 +485,12 @@ mod tests {
             },
         );
 
-        let lines = diff_summary_for_tests(&changes, PatchEventType::ApprovalRequest);
+        let lines = diff_summary_for_tests(
+            &changes,
+            PatchEventType::ApplyBegin {
+                auto_approved: true,
+            },
+        );
 
         snapshot_lines("add_details", lines, 80, 10);
     }
@@ -452,7 +511,12 @@ mod tests {
             },
         );
 
-        let lines = diff_summary_for_tests(&changes, PatchEventType::ApprovalRequest);
+        let lines = diff_summary_for_tests(
+            &changes,
+            PatchEventType::ApplyBegin {
+                auto_approved: true,
+            },
+        );
 
         snapshot_lines("update_details_with_rename", lines, 80, 12);
     }
@@ -463,7 +527,7 @@ mod tests {
         let long_line = "this is a very long line that should wrap across multiple terminal columns and continue";
 
         // Call the wrapping function directly so we can precisely control the width
-        let lines = push_wrapped_diff_line(1, DiffLineType::Insert, long_line, 80);
+        let lines = push_wrapped_diff_line(1, DiffLineType::Insert, long_line, 80, None);
 
         // Render into a small terminal to capture the visual layout
         snapshot_lines("wrap_behavior_insert", lines, 90, 8);
@@ -485,7 +549,12 @@ mod tests {
             },
         );
 
-        let lines = diff_summary_for_tests(&changes, PatchEventType::ApprovalRequest);
+        let lines = diff_summary_for_tests(
+            &changes,
+            PatchEventType::ApplyBegin {
+                auto_approved: true,
+            },
+        );
 
         snapshot_lines("single_line_replacement_counts", lines, 80, 8);
     }
@@ -506,7 +575,12 @@ mod tests {
             },
         );
 
-        let lines = diff_summary_for_tests(&changes, PatchEventType::ApprovalRequest);
+        let lines = diff_summary_for_tests(
+            &changes,
+            PatchEventType::ApplyBegin {
+                auto_approved: true,
+            },
+        );
 
         snapshot_lines("blank_context_line", lines, 80, 10);
     }
@@ -528,7 +602,12 @@ mod tests {
             },
         );
 
-        let lines = diff_summary_for_tests(&changes, PatchEventType::ApprovalRequest);
+        let lines = diff_summary_for_tests(
+            &changes,
+            PatchEventType::ApplyBegin {
+                auto_approved: true,
+            },
+        );
 
         // Height is large enough to show both hunks and the separator
         snapshot_lines("vertical_ellipsis_between_hunks", lines, 80, 16);
@@ -618,6 +697,38 @@ mod tests {
         snapshot_lines("apply_multiple_files_block", lines, 80, 14);
     }
 
+    #[test]
+    fn proposed_changes_only_list_files() {
+        let mut changes: HashMap<PathBuf, FileChange> = HashMap::new();
+        changes.insert(
+            PathBuf::from("a.txt"),
+            FileChange::Update {
+                unified_diff: diffy::create_patch("one\n", "one changed\n").to_string(),
+                move_path: None,
+            },
+        );
+        changes.insert(
+            PathBuf::from("b.txt"),
+            FileChange::Add {
+                content: "new\n".to_string(),
+            },
+        );
+
+        let lines: Vec<String> = diff_summary_for_tests(&changes, PatchEventType::ApprovalRequest)
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "• Proposed Change to 2 files (+2 -1)",
+                "  └ a.txt (+1 -1)",
+                "  └ b.txt (+1 -0)",
+            ]
+        );
+    }
+
     #[test]
     fn ui_snapshot_apply_add_block() {
         let mut changes: HashMap<PathBuf, FileChange> = HashMap::new();
@@ -688,6 +799,7 @@ mod tests {
             },
             &PathBuf::from("/"),
             72,
+            None,
         );
 
         // Render with backend width wider than wrap width to avoid Paragraph auto-wrap.
@@ -718,6 +830,7 @@ mod tests {
             },
             &PathBuf::from("/"),
             28,
+            None,
         );
         // Drop the combined header for this text-only snapshot
         if !lines.is_empty() {
@@ -752,6 +865,7 @@ mod tests {
             },
             &cwd,
             80,
+            None,
         );
 
         snapshot_lines("apply_update_block_relativizes_path", lines, 80, 10);
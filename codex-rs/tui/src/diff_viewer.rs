@@ -0,0 +1,673 @@
+//! Diffs as shown by the full-screen diff viewer (`/diff`, and `d` on a patch
+//! approval): parsed into files and hunks, then laid out either unified or
+//! side by side, with the changed part of edited lines emphasized, the code
+//! syntax highlighted and long runs of unchanged lines folded.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::FileChange;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use unicode_width::UnicodeWidthChar;
+
+use crate::diff_render::display_path_for;
+use crate::render::highlight::Language;
+use crate::render::highlight::highlight_spans;
+use crate::render::highlight::language_for_path;
+use crate::theme;
+
+/// Unchanged lines kept next to a change when context is folded.
+const FOLDED_CONTEXT_LINES: usize = 2;
+
+/// Below this width side-by-side columns are too narrow to read, so the
+/// unified layout is used instead.
+const MIN_SIDE_BY_SIDE_WIDTH: u16 = 60;
+
+/// Columns taken by a line number and the space after it.
+const LINE_NUMBER_COLS: usize = 5;
+
+const TAB: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffLayout {
+    Unified,
+    SideBySide,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DiffFile {
+    pub(crate) name: String,
+    hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DiffHunk {
+    old_start: usize,
+    new_start: usize,
+    lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DiffLine {
+    Context(String),
+    Delete(String),
+    Insert(String),
+}
+
+impl DiffFile {
+    /// Files of a patch proposed or applied by the agent, sorted by path.
+    pub(crate) fn from_changes(changes: &HashMap<PathBuf, FileChange>, cwd: &Path) -> Vec<Self> {
+        let mut paths: Vec<&PathBuf> = changes.keys().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let mut name = display_path_for(path, cwd);
+                let hunks = match &changes[path] {
+                    FileChange::Add { content } => vec![DiffHunk {
+                        old_start: 0,
+                        new_start: 1,
+                        lines: content
+                            .lines()
+                            .map(|l| DiffLine::Insert(l.to_string()))
+                            .collect(),
+                    }],
+                    FileChange::Delete { content } => vec![DiffHunk {
+                        old_start: 1,
+                        new_start: 0,
+                        lines: content
+                            .lines()
+                            .map(|l| DiffLine::Delete(l.to_string()))
+                            .collect(),
+                    }],
+                    FileChange::Update {
+                        unified_diff,
+                        move_path,
+                    } => {
+                        if let Some(move_path) = move_path {
+                            name = format!("{name} → {}", display_path_for(move_path, cwd));
+                        }
+                        parse_hunks(unified_diff.lines())
+                    }
+                };
+                Self { name, hunks }
+            })
+            .collect()
+    }
+
+    /// Files of the output of `git diff`, in the order git printed them.
+    pub(crate) fn from_git_diff(diff: &str) -> Vec<Self> {
+        let mut files = Vec::new();
+        let mut lines = diff.lines().peekable();
+        while let Some(line) = lines.next() {
+            let Some(header) = line.strip_prefix("diff --git ") else {
+                continue;
+            };
+            let mut name = header
+                .rsplit_once(" b/")
+                .map_or(header, |(_, new)| new)
+                .to_string();
+            let mut rename_from = None;
+            let mut body = Vec::new();
+            while let Some(line) = lines.next_if(|l| !l.starts_with("diff --git ")) {
+                if let Some(from) = line.strip_prefix("rename from ") {
+                    rename_from = Some(from.to_string());
+                } else if let Some(to) = line.strip_prefix("rename to ")
+                    && let Some(from) = &rename_from
+                {
+                    name = format!("{from} → {to}");
+                } else {
+                    body.push(line);
+                }
+            }
+            files.push(Self {
+                name,
+                hunks: parse_hunks(body.into_iter()),
+            });
+        }
+        files
+    }
+
+    pub(crate) fn line_counts(&self) -> (usize, usize) {
+        self.hunks
+            .iter()
+            .flat_map(|hunk| &hunk.lines)
+            .fold((0, 0), |(added, removed), line| match line {
+                DiffLine::Insert(_) => (added + 1, removed),
+                DiffLine::Delete(_) => (added, removed + 1),
+                DiffLine::Context(_) => (added, removed),
+            })
+    }
+
+    /// The file as lines `width` columns wide: a header, then its hunks.
+    pub(crate) fn render(&self, layout: DiffLayout, width: u16, fold: bool) -> Vec<Line<'static>> {
+        let (added, removed) = self.line_counts();
+        let theme = theme::current();
+        let mut out: Vec<Line<'static>> = vec![Line::from(vec![
+            "▌ ".dim(),
+            self.name.clone().bold(),
+            " (".into(),
+            Span::styled(format!("+{added}"), theme.diff_add),
+            " ".into(),
+            Span::styled(format!("-{removed}"), theme.diff_delete),
+            ")".into(),
+        ])];
+        let language = language_for_path(Path::new(&self.name));
+        let layout = if width < MIN_SIDE_BY_SIDE_WIDTH {
+            DiffLayout::Unified
+        } else {
+            layout
+        };
+        for (idx, hunk) in self.hunks.iter().enumerate() {
+            if idx > 0 {
+                out.push(Line::from("  ⋮".dim()));
+            }
+            for row in hunk_rows(hunk, fold) {
+                match layout {
+                    DiffLayout::Unified => out.extend(unified_lines(&row, language)),
+                    DiffLayout::SideBySide => {
+                        out.push(side_by_side_line(&row, width as usize, language))
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Parses the hunks of a unified diff, skipping file headers and anything
+/// else outside of a hunk.
+fn parse_hunks<'a>(lines: impl Iterator<Item = &'a str>) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let (mut old_remaining, mut new_remaining) = (0usize, 0usize);
+    for line in lines {
+        if old_remaining == 0 && new_remaining == 0 {
+            if let Some((old_start, old_len, new_start, new_len)) = parse_hunk_header(line) {
+                hunks.push(DiffHunk {
+                    old_start,
+                    new_start,
+                    lines: Vec::new(),
+                });
+                old_remaining = old_len;
+                new_remaining = new_len;
+            }
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let (kind, text) = line.split_at(line.len().min(1));
+        match kind {
+            "+" => {
+                new_remaining = new_remaining.saturating_sub(1);
+                hunk.lines.push(DiffLine::Insert(text.to_string()));
+            }
+            "-" => {
+                old_remaining = old_remaining.saturating_sub(1);
+                hunk.lines.push(DiffLine::Delete(text.to_string()));
+            }
+            // "\ No newline at end of file"
+            "\\" => {}
+            // Some tools strip the space of empty context lines.
+            _ => {
+                old_remaining = old_remaining.saturating_sub(1);
+                new_remaining = new_remaining.saturating_sub(1);
+                hunk.lines.push(DiffLine::Context(text.to_string()));
+            }
+        }
+    }
+    hunks
+}
+
+/// `@@ -old_start,old_len +new_start,new_len @@`; lengths default to 1.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?;
+    let (ranges, _) = ranges.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let parse = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = parse(old)?;
+    let (new_start, new_len) = parse(new)?;
+    Some((old_start, old_len, new_start, new_len))
+}
+
+/// One row of a hunk: a line of each side (side by side, deleted lines are
+/// paired with the lines that replace them), or folded context.
+#[derive(Debug, PartialEq)]
+enum Row {
+    Context {
+        old: usize,
+        new: usize,
+        text: String,
+    },
+    Change {
+        old: Option<(usize, Vec<Segment>)>,
+        new: Option<(usize, Vec<Segment>)>,
+    },
+    Folded(usize),
+}
+
+/// Part of a changed line; `emphasized` parts are what actually changed.
+#[derive(Debug, PartialEq)]
+struct Segment {
+    text: String,
+    emphasized: bool,
+}
+
+fn hunk_rows(hunk: &DiffHunk, fold: bool) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let (mut old_ln, mut new_ln) = (hunk.old_start, hunk.new_start);
+    let mut i = 0;
+    while i < hunk.lines.len() {
+        if let DiffLine::Context(_) = &hunk.lines[i] {
+            let start = i;
+            while let Some(DiffLine::Context(_)) = hunk.lines.get(i) {
+                i += 1;
+            }
+            let run = &hunk.lines[start..i];
+            // Keep the unchanged lines next to changes; fold the rest.
+            let keep_before = if start == 0 { 0 } else { FOLDED_CONTEXT_LINES };
+            let keep_after = if i == hunk.lines.len() {
+                0
+            } else {
+                FOLDED_CONTEXT_LINES
+            };
+            let hidden = run.len().saturating_sub(keep_before + keep_after);
+            for (offset, line) in run.iter().enumerate() {
+                if fold && hidden > 1 && offset >= keep_before && offset < keep_before + hidden {
+                    if offset == keep_before {
+                        rows.push(Row::Folded(hidden));
+                    }
+                } else if let DiffLine::Context(text) = line {
+                    rows.push(Row::Context {
+                        old: old_ln,
+                        new: new_ln,
+                        text: text.replace('\t', TAB),
+                    });
+                }
+                old_ln += 1;
+                new_ln += 1;
+            }
+            continue;
+        }
+
+        let mut deleted = Vec::new();
+        while let Some(DiffLine::Delete(text)) = hunk.lines.get(i) {
+            deleted.push(text.replace('\t', TAB));
+            i += 1;
+        }
+        let mut inserted = Vec::new();
+        while let Some(DiffLine::Insert(text)) = hunk.lines.get(i) {
+            inserted.push(text.replace('\t', TAB));
+            i += 1;
+        }
+        for row in 0..deleted.len().max(inserted.len()) {
+            let (old, new) = match (deleted.get(row), inserted.get(row)) {
+                (Some(old), Some(new)) => emphasize(old, new),
+                (Some(old), None) => (plain(old), Vec::new()),
+                (None, Some(new)) => (Vec::new(), plain(new)),
+                (None, None) => (Vec::new(), Vec::new()),
+            };
+            rows.push(Row::Change {
+                old: deleted.get(row).map(|_| (old_ln + row, old)),
+                new: inserted.get(row).map(|_| (new_ln + row, new)),
+            });
+        }
+        old_ln += deleted.len();
+        new_ln += inserted.len();
+    }
+    rows
+}
+
+fn plain(text: &str) -> Vec<Segment> {
+    vec![Segment {
+        text: text.to_string(),
+        emphasized: false,
+    }]
+}
+
+/// Splits a replaced line and its replacement into their common prefix, the
+/// part that changed, and their common suffix. Lines with nothing in common
+/// are left unemphasized.
+fn emphasize(old: &str, new: &str) -> (Vec<Segment>, Vec<Segment>) {
+    let old_chars: Vec<char> = old.chars().collect();
+    let new_chars: Vec<char> = new.chars().collect();
+    let prefix = old_chars
+        .iter()
+        .zip(&new_chars)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_chars[prefix..]
+        .iter()
+        .rev()
+        .zip(new_chars[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix == 0 && suffix == 0 {
+        return (plain(old), plain(new));
+    }
+    let split = |chars: &[char]| -> Vec<Segment> {
+        let parts = [
+            (&chars[..prefix], false),
+            (&chars[prefix..chars.len() - suffix], true),
+            (&chars[chars.len() - suffix..], false),
+        ];
+        parts
+            .into_iter()
+            .filter(|(part, _)| !part.is_empty())
+            .map(|(part, emphasized)| Segment {
+                text: part.iter().collect(),
+                emphasized,
+            })
+            .collect()
+    };
+    (split(&old_chars), split(&new_chars))
+}
+
+fn segment_spans(
+    sign: &str,
+    segments: &[Segment],
+    style: Style,
+    language: Option<&Language>,
+) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::styled(sign.to_string(), style)];
+    let code = segments
+        .iter()
+        .map(|segment| {
+            let style = if segment.emphasized {
+                style.add_modifier(Modifier::REVERSED)
+            } else {
+                style
+            };
+            Span::styled(segment.text.clone(), style)
+        })
+        .collect();
+    spans.extend(code_spans(code, language));
+    spans
+}
+
+/// An unchanged line, after the blank sign column.
+fn context_spans(text: &str, language: Option<&Language>) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::from(" ")];
+    spans.extend(code_spans(vec![text.to_string().into()], language));
+    spans
+}
+
+fn code_spans(spans: Vec<Span<'static>>, language: Option<&Language>) -> Vec<Span<'static>> {
+    match language {
+        Some(language) => highlight_spans(spans, language),
+        None => spans,
+    }
+}
+
+fn folded_line(hidden: usize) -> Line<'static> {
+    Line::from(format!("  ⋯ {hidden} unchanged lines").dim().italic())
+}
+
+fn unified_lines(row: &Row, language: Option<&Language>) -> Vec<Line<'static>> {
+    let theme = theme::current();
+    let gutter = |ln: usize| Span::styled(format!("{ln:>4} "), theme.diff_gutter);
+    match row {
+        Row::Context { new, text, .. } => {
+            let mut spans = vec![gutter(*new)];
+            spans.extend(context_spans(text, language));
+            vec![Line::from(spans)]
+        }
+        Row::Change { old, new } => {
+            let mut lines = Vec::new();
+            if let Some((ln, segments)) = old {
+                let mut spans = vec![gutter(*ln)];
+                spans.extend(segment_spans("-", segments, theme.diff_delete, language));
+                lines.push(Line::from(spans));
+            }
+            if let Some((ln, segments)) = new {
+                let mut spans = vec![gutter(*ln)];
+                spans.extend(segment_spans("+", segments, theme.diff_add, language));
+                lines.push(Line::from(spans));
+            }
+            lines
+        }
+        Row::Folded(hidden) => vec![folded_line(*hidden)],
+    }
+}
+
+fn side_by_side_line(row: &Row, width: usize, language: Option<&Language>) -> Line<'static> {
+    let theme = theme::current();
+    // Each side gets a line number and text; " │ " separates the sides.
+    let side_cols = width.saturating_sub(3) / 2;
+    let text_cols = side_cols.saturating_sub(LINE_NUMBER_COLS);
+    let side = |ln: Option<usize>, spans: Vec<Span<'static>>| -> Vec<Span<'static>> {
+        let gutter = match ln {
+            Some(ln) => format!("{ln:>4} "),
+            None => " ".repeat(LINE_NUMBER_COLS),
+        };
+        let mut out = vec![Span::styled(gutter, theme.diff_gutter)];
+        out.extend(fit_spans(spans, text_cols));
+        out
+    };
+    let (left, right) = match row {
+        Row::Context { old, new, text } => (
+            side(Some(*old), context_spans(text, language)),
+            side(Some(*new), context_spans(text, language)),
+        ),
+        Row::Change { old, new } => (
+            match old {
+                Some((ln, segments)) => side(
+                    Some(*ln),
+                    segment_spans("-", segments, theme.diff_delete, language),
+                ),
+                None => side(None, Vec::new()),
+            },
+            match new {
+                Some((ln, segments)) => side(
+                    Some(*ln),
+                    segment_spans("+", segments, theme.diff_add, language),
+                ),
+                None => side(None, Vec::new()),
+            },
+        ),
+        Row::Folded(hidden) => return folded_line(*hidden),
+    };
+    let mut spans = left;
+    spans.push(" │ ".dim());
+    spans.extend(right);
+    Line::from(spans)
+}
+
+/// Truncates `spans` to `cols` display columns (marking the cut with `…`) and
+/// pads them with spaces to exactly `cols`.
+fn fit_spans(spans: Vec<Span<'static>>, cols: usize) -> Vec<Span<'static>> {
+    let total: usize = spans.iter().map(Span::width).sum();
+    let mut out = Vec::new();
+    if total <= cols {
+        out.extend(spans);
+        out.push(" ".repeat(cols - total).into());
+        return out;
+    }
+    let budget = cols.saturating_sub(1);
+    let mut used = 0;
+    for span in spans {
+        let mut text = String::new();
+        for ch in span.content.chars() {
+            let w = ch.width().unwrap_or(0);
+            if used + w > budget {
+                break;
+            }
+            used += w;
+            text.push(ch);
+        }
+        if !text.is_empty() {
+            out.push(Span::styled(text, span.style));
+        }
+        if used >= budget {
+            break;
+        }
+    }
+    out.push("…".dim());
+    out.push(" ".repeat(budget - used).into());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn text(lines: &[Line<'static>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    const GIT_DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    println!(\"hello\");
++    println!(\"hello, world\");
+ }
+diff --git a/old.txt b/new.txt
+similarity index 100%
+rename from old.txt
+rename to new.txt
+diff --git a/notes.md b/notes.md
+new file mode 100644
+--- /dev/null
++++ b/notes.md
+@@ -0,0 +1 @@
++remember the milk
+";
+
+    #[test]
+    fn git_diffs_are_split_into_files() {
+        let files = DiffFile::from_git_diff(GIT_DIFF);
+
+        let summary: Vec<(String, (usize, usize))> = files
+            .iter()
+            .map(|file| (file.name.clone(), file.line_counts()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/lib.rs".to_string(), (1, 1)),
+                ("old.txt → new.txt".to_string(), (0, 0)),
+                ("notes.md".to_string(), (1, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unified_layout_numbers_lines() {
+        let files = DiffFile::from_git_diff(GIT_DIFF);
+
+        assert_eq!(
+            text(&files[0].render(DiffLayout::Unified, 80, true)),
+            vec![
+                "▌ src/lib.rs (+1 -1)",
+                "   1  fn main() {",
+                "   2 -    println!(\"hello\");",
+                "   2 +    println!(\"hello, world\");",
+                "   3  }",
+            ]
+        );
+    }
+
+    #[test]
+    fn side_by_side_layout_pairs_replaced_lines() {
+        let files = DiffFile::from_git_diff(GIT_DIFF);
+
+        assert_eq!(
+            text(&files[0].render(DiffLayout::SideBySide, 63, true)),
+            vec![
+                "▌ src/lib.rs (+1 -1)",
+                "   1  fn main() {              │    1  fn main() {",
+                "   2 -    println!(\"hello\");   │    2 +    println!(\"hello, wo…",
+                "   3  }                        │    3  }",
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_changed_part_of_a_line_is_emphasized() {
+        let (old, new) = emphasize("let x = 1;", "let x = 42;");
+
+        assert_eq!(
+            new,
+            vec![
+                Segment {
+                    text: "let x = ".to_string(),
+                    emphasized: false,
+                },
+                Segment {
+                    text: "42".to_string(),
+                    emphasized: true,
+                },
+                Segment {
+                    text: ";".to_string(),
+                    emphasized: false,
+                },
+            ]
+        );
+        assert_eq!(old[1].text, "1");
+        assert_eq!(emphasize("abc", "xyz"), (plain("abc"), plain("xyz")));
+    }
+
+    #[test]
+    fn long_unchanged_runs_are_folded() {
+        let lines: Vec<String> = (1..=10).map(|n| format!("line {n}")).collect();
+        let mut changed = lines.clone();
+        changed[0] = "line one".to_string();
+        changed[9] = "line ten".to_string();
+        let patch = diffy::DiffOptions::new()
+            .set_context_len(10)
+            .create_patch(&(lines.join("\n") + "\n"), &(changed.join("\n") + "\n"))
+            .to_string();
+        // Paths are shown relative to the cwd only inside its repository.
+        let cwd = std::env::current_dir().expect("cwd");
+        let changes = HashMap::from([(
+            cwd.join("count.txt"),
+            FileChange::Update {
+                unified_diff: patch,
+                move_path: None,
+            },
+        )]);
+        let file = &DiffFile::from_changes(&changes, &cwd)[0];
+
+        assert_eq!(
+            text(&file.render(DiffLayout::Unified, 80, true)),
+            vec![
+                "▌ count.txt (+2 -2)",
+                "   1 -line 1",
+                "   1 +line one",
+                "   2  line 2",
+                "   3  line 3",
+                "  ⋯ 4 unchanged lines",
+                "   8  line 8",
+                "   9  line 9",
+                "  10 -line 10",
+                "  10 +line ten",
+            ]
+        );
+        assert_eq!(file.render(DiffLayout::Unified, 80, false).len(), 13);
+    }
+}
@@ -3,6 +3,9 @@ use std::time::Instant;
 
 use codex_protocol::parse_command::ParsedCommand;
 
+/// How much of a running command's streamed output is kept for display.
+const LIVE_OUTPUT_MAX_BYTES: usize = 16 * 1024;
+
 #[derive(Clone, Debug)]
 pub(crate) struct CommandOutput {
     pub(crate) exit_code: i32,
@@ -19,6 +22,8 @@ pub(crate) struct ExecCall {
     pub(crate) output: Option<CommandOutput>,
     pub(crate) start_time: Option<Instant>,
     pub(crate) duration: Option<Duration>,
+    /// The tail of the output streamed while the command runs.
+    pub(crate) live_output: Vec<u8>,
 }
 
 #[derive(Debug)]
@@ -44,6 +49,7 @@ impl ExecCell {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         };
         if self.is_exploring_cell() && Self::is_exploring_call(&call) {
             Some(Self {
@@ -67,6 +73,22 @@ impl ExecCell {
         }
     }
 
+    /// Appends output streamed by a running call, keeping only its tail.
+    pub(crate) fn append_output(&mut self, call_id: &str, chunk: &[u8]) -> bool {
+        let Some(call) = self
+            .calls
+            .iter_mut()
+            .rev()
+            .find(|c| c.call_id == call_id && c.output.is_none())
+        else {
+            return false;
+        };
+        call.live_output.extend_from_slice(chunk);
+        let excess = call.live_output.len().saturating_sub(LIVE_OUTPUT_MAX_BYTES);
+        call.live_output.drain(..excess);
+        true
+    }
+
     pub(crate) fn should_flush(&self) -> bool {
         !self.is_exploring_cell() && self.calls.iter().all(|c| c.output.is_some())
     }
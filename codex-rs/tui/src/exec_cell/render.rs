@@ -43,6 +43,7 @@ pub(crate) fn new_active_exec_command(
         output: None,
         start_time: Some(Instant::now()),
         duration: None,
+        live_output: Vec::new(),
     })
 }
 
@@ -357,18 +358,21 @@ impl ExecCell {
             ));
         }
 
-        if let Some(output) = call.output.as_ref() {
-            let raw_output_lines = output_lines(
-                Some(output),
-                OutputLinesParams {
-                    only_err: false,
-                    include_angle_pipe: false,
-                    include_prefix: false,
-                },
-            );
-            let trimmed_output =
-                Self::truncate_lines_middle(&raw_output_lines, layout.output_max_lines);
-
+        let trimmed_output = match call.output.as_ref() {
+            Some(output) => {
+                let raw_output_lines = output_lines(
+                    Some(output),
+                    OutputLinesParams {
+                        only_err: false,
+                        include_angle_pipe: false,
+                        include_prefix: false,
+                    },
+                );
+                Self::truncate_lines_middle(&raw_output_lines, layout.output_max_lines)
+            }
+            None => Self::live_output_lines(&call.live_output, layout.output_max_lines),
+        };
+        if !trimmed_output.is_empty() {
             let mut wrapped_output: Vec<Line<'static>> = Vec::new();
             let output_wrap_width = layout.output_block.wrap_width(width);
             let output_opts =
@@ -392,6 +396,33 @@ impl ExecCell {
         lines
     }
 
+    /// The last `keep` lines of a running command's output. Only the text
+    /// after the last carriage return of a line is shown, as a terminal would
+    /// after a progress bar redraws it.
+    fn live_output_lines(live_output: &[u8], keep: usize) -> Vec<Line<'static>> {
+        let text = String::from_utf8_lossy(live_output);
+        let lines: Vec<&str> = text
+            .lines()
+            .map(|line| {
+                let line = line.trim_end_matches('\r');
+                line.rsplit('\r').next().unwrap_or(line)
+            })
+            .collect();
+        let start = lines.len().saturating_sub(keep);
+        let mut out = Vec::new();
+        if start > 0 {
+            out.push(Self::ellipsis_line(start));
+        }
+        for raw in &lines[start..] {
+            let mut line = ansi_escape_line(raw);
+            line.spans.iter_mut().for_each(|span| {
+                span.style = span.style.add_modifier(Modifier::DIM);
+            });
+            out.push(line);
+        }
+        out
+    }
+
     fn limit_lines_from_start(lines: &[Line<'static>], keep: usize) -> Vec<Line<'static>> {
         if lines.len() <= keep {
             return lines.to_vec();
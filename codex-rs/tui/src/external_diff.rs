@@ -0,0 +1,244 @@
+//! Rendering diffs with a user-configured external program such as `delta` or
+//! `difftastic` (see `[tui].diff_renderer` in `config.toml`).
+//!
+//! Every entry point returns `None` when the program is missing, fails, or
+//! times out so callers can fall back to the built-in renderer.
+
+use std::ffi::OsStr;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use codex_ansi_escape::ansi_escape;
+use codex_core::config_types::DiffRenderer;
+use codex_core::config_types::DiffRendererInput;
+use codex_core::protocol::FileChange;
+use ratatui::text::Line;
+
+/// Placeholder in `diff_renderer.command` replaced with the available columns.
+const WIDTH_PLACEHOLDER: &str = "{width}";
+
+/// Renderers run synchronously while drawing history, so give up quickly.
+const RENDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Renders a single file change for the patch history cell.
+pub(crate) fn render_file_change(
+    renderer: &DiffRenderer,
+    display_path: &str,
+    change: &FileChange,
+    width: usize,
+) -> Option<Vec<Line<'static>>> {
+    let output = match renderer.input {
+        DiffRendererInput::Patch => {
+            let patch = file_change_patch(display_path, change)?;
+            run_renderer(renderer, width, &[], Some(patch))?
+        }
+        DiffRendererInput::Files => {
+            let (old, new) = file_change_contents(change)?;
+            // Keep the original file name so the renderer can detect the
+            // language from the extension.
+            let file_name = Path::new(display_path)
+                .file_name()
+                .map_or_else(|| "file".into(), OsStr::to_os_string);
+            let old_dir = tempfile::tempdir().ok()?;
+            let new_dir = tempfile::tempdir().ok()?;
+            let old_path = old_dir.path().join(&file_name);
+            let new_path = new_dir.path().join(&file_name);
+            std::fs::write(&old_path, old).ok()?;
+            std::fs::write(&new_path, new).ok()?;
+            run_renderer(renderer, width, &[&old_path, &new_path], None)?
+        }
+    };
+    Some(ansi_escape(&output).lines)
+}
+
+/// Renders a multi-file unified diff (used by `/diff`). Only renderers that
+/// read a patch from stdin support this.
+pub(crate) fn render_patch(renderer: &DiffRenderer, patch: &str, width: usize) -> Option<String> {
+    if renderer.input != DiffRendererInput::Patch {
+        return None;
+    }
+    run_renderer(renderer, width, &[], Some(patch.to_string()))
+}
+
+/// Builds a git-style patch for `change` so renderers can show the file name.
+fn file_change_patch(display_path: &str, change: &FileChange) -> Option<String> {
+    let (old_label, new_label, hunks) = match change {
+        FileChange::Add { content } => (
+            "/dev/null".to_string(),
+            format!("b/{display_path}"),
+            diffy::create_patch("", content).to_string(),
+        ),
+        FileChange::Delete { content } => (
+            format!("a/{display_path}"),
+            "/dev/null".to_string(),
+            diffy::create_patch(content, "").to_string(),
+        ),
+        FileChange::Update { unified_diff, .. } => (
+            format!("a/{display_path}"),
+            format!("b/{display_path}"),
+            unified_diff.clone(),
+        ),
+    };
+    // Drop whatever file headers the diff came with and use our own labels.
+    let hunks = &hunks[hunks.find("@@")?..];
+    Some(format!(
+        "diff --git a/{display_path} b/{display_path}\n--- {old_label}\n+++ {new_label}\n{hunks}"
+    ))
+}
+
+/// Reconstructs old and new contents for file-based renderers. Updates only
+/// carry hunks, so the contents are limited to the changed regions.
+fn file_change_contents(change: &FileChange) -> Option<(String, String)> {
+    match change {
+        FileChange::Add { content } => Some((String::new(), content.clone())),
+        FileChange::Delete { content } => Some((content.clone(), String::new())),
+        FileChange::Update { unified_diff, .. } => {
+            let patch = diffy::Patch::from_str(unified_diff).ok()?;
+            let mut old = String::new();
+            let mut new = String::new();
+            for line in patch.hunks().iter().flat_map(diffy::Hunk::lines) {
+                match line {
+                    diffy::Line::Context(text) => {
+                        old.push_str(text);
+                        new.push_str(text);
+                    }
+                    diffy::Line::Delete(text) => old.push_str(text),
+                    diffy::Line::Insert(text) => new.push_str(text),
+                }
+            }
+            Some((old, new))
+        }
+    }
+}
+
+fn run_renderer(
+    renderer: &DiffRenderer,
+    width: usize,
+    extra_args: &[&Path],
+    stdin: Option<String>,
+) -> Option<String> {
+    let (program, args) = renderer.command.split_first()?;
+    let width = width.max(1).to_string();
+    let mut child = Command::new(program)
+        .args(
+            args.iter()
+                .map(|arg| arg.replace(WIDTH_PLACEHOLDER, &width)),
+        )
+        .args(extra_args)
+        .env("COLUMNS", &width)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| tracing::warn!("failed to run diff renderer {program}: {e}"))
+        .ok()?;
+
+    // Feed stdin and drain stdout on helper threads so a large diff cannot
+    // deadlock on full pipe buffers.
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        std::thread::spawn(move || {
+            let _ = pipe.write_all(input.as_bytes());
+        });
+    }
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf);
+        buf
+    });
+
+    let deadline = Instant::now() + RENDER_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                tracing::warn!("diff renderer {program} did not finish in time");
+                return None;
+            }
+        }
+    };
+
+    // diff-style tools commonly exit with 1 when the inputs differ.
+    if !(status.success() || status.code() == Some(1)) {
+        tracing::warn!("diff renderer {program} exited with {status}");
+        return None;
+    }
+    let output = String::from_utf8_lossy(&reader.join().ok()?).into_owned();
+    if output.trim().is_empty() {
+        None
+    } else {
+        Some(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn add_patch_uses_dev_null_and_display_path() {
+        let patch = file_change_patch(
+            "src/new.rs",
+            &FileChange::Add {
+                content: "fn main() {}\n".to_string(),
+            },
+        )
+        .expect("patch");
+        assert_eq!(
+            patch,
+            "diff --git a/src/new.rs b/src/new.rs\n--- /dev/null\n+++ b/src/new.rs\n@@ -0,0 +1 @@\n+fn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn update_contents_are_rebuilt_from_hunks() {
+        let unified_diff = "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n keep\n-old\n+new\n".to_string();
+        let contents = file_change_contents(&FileChange::Update {
+            unified_diff,
+            move_path: None,
+        });
+        assert_eq!(
+            contents,
+            Some(("keep\nold\n".to_string(), "keep\nnew\n".to_string()))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn renderer_receives_width_and_patch_on_stdin() {
+        let renderer = DiffRenderer {
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo width={width}; cat".to_string(),
+            ],
+            input: DiffRendererInput::Patch,
+        };
+        assert_eq!(
+            render_patch(&renderer, "+added\n", 42),
+            Some("width=42\n+added\n".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_renderer_falls_back() {
+        let renderer = DiffRenderer {
+            command: vec!["codex-test-no-such-diff-renderer".to_string()],
+            input: DiffRendererInput::Patch,
+        };
+        assert_eq!(render_patch(&renderer, "+added\n", 80), None);
+    }
+}
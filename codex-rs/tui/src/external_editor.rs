@@ -0,0 +1,136 @@
+//! Editing text in the user's editor (`$VISUAL`, then `$EDITOR`), e.g. a
+//! patch awaiting approval, and opening files referenced in the transcript.
+//!
+//! The editor takes over the terminal, so the TUI's terminal modes are
+//! switched off while it runs and restored afterwards.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::tui;
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Opens `text` in the editor as a temporary file ending in `suffix` and
+/// returns the file's contents once the editor exits.
+pub(crate) fn edit_text(text: &str, suffix: &str) -> io::Result<String> {
+    let file = tempfile::Builder::new()
+        .prefix("codex-")
+        .suffix(suffix)
+        .tempfile()?;
+    std::fs::write(file.path(), text)?;
+
+    run_editor(&[file.path().display().to_string()])?;
+    std::fs::read_to_string(file.path())
+}
+
+/// Opens `path` in the editor, at `line` when given, and waits for it to
+/// exit.
+pub(crate) fn open_file(path: &Path, line: Option<u32>) -> io::Result<()> {
+    let editor = configured_editor()?;
+    run_editor(&file_arguments(&editor, path, line))
+}
+
+/// Runs the configured editor with `args` while the TUI's terminal modes
+/// are off.
+fn run_editor(args: &[String]) -> io::Result<()> {
+    let editor = configured_editor()?;
+    tui::restore()?;
+    let status = Command::new(&editor[0])
+        .args(&editor[1..])
+        .args(args)
+        .status();
+    tui::set_modes()?;
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {status}",
+            editor[0]
+        )));
+    }
+    Ok(())
+}
+
+fn configured_editor() -> io::Result<Vec<String>> {
+    editor_command(
+        std::env::var("VISUAL")
+            .ok()
+            .or_else(|| std::env::var("EDITOR").ok())
+            .as_deref(),
+    )
+}
+
+/// The arguments that open `path` at `line`: `-g path:line` for VS Code
+/// and its forks, `+line path` for terminal editors.
+fn file_arguments(editor: &[String], path: &Path, line: Option<u32>) -> Vec<String> {
+    let path = path.display().to_string();
+    let Some(line) = line else {
+        return vec![path];
+    };
+    let program = editor
+        .first()
+        .and_then(|program| Path::new(program).file_stem())
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    if matches!(program, "code" | "code-insiders" | "cursor" | "codium") {
+        vec!["-g".to_string(), format!("{path}:{line}")]
+    } else {
+        vec![format!("+{line}"), path]
+    }
+}
+
+/// The editor program and its arguments, e.g. `code --wait`.
+fn editor_command(configured: Option<&str>) -> io::Result<Vec<String>> {
+    let configured = configured
+        .map(str::trim)
+        .filter(|editor| !editor.is_empty())
+        .unwrap_or(DEFAULT_EDITOR);
+    match shlex::split(configured) {
+        Some(command) if !command.is_empty() => Ok(command),
+        _ => Err(io::Error::other(format!(
+            "invalid editor command `{configured}`"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn editor_commands_are_split_into_arguments() {
+        assert_eq!(
+            editor_command(Some("code --wait")).ok(),
+            Some(vec!["code".to_string(), "--wait".to_string()])
+        );
+        assert_eq!(
+            editor_command(Some("  ")).ok(),
+            Some(vec![DEFAULT_EDITOR.to_string()])
+        );
+        assert!(editor_command(Some("vim 'unclosed")).is_err());
+    }
+
+    #[test]
+    fn files_open_at_the_referenced_line() {
+        let path = Path::new("src/main.rs");
+        let vim = vec!["vim".to_string()];
+        let code = vec!["/usr/bin/code".to_string(), "--wait".to_string()];
+        assert_eq!(
+            file_arguments(&vim, path, Some(12)),
+            vec!["+12".to_string(), "src/main.rs".to_string()]
+        );
+        assert_eq!(
+            file_arguments(&code, path, Some(12)),
+            vec!["-g".to_string(), "src/main.rs:12".to_string()]
+        );
+        assert_eq!(
+            file_arguments(&vim, path, None),
+            vec!["src/main.rs".to_string()]
+        );
+    }
+}
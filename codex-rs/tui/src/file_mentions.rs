@@ -0,0 +1,218 @@
+//! `@` mentions of workspace files and directories in prompts.
+//!
+//! A mention is `@path`, optionally followed by a line range such as
+//! `@src/main.rs:10-40` or `@src/main.rs:10`. When a prompt is submitted,
+//! each mention naming an existing file or directory is inlined as extra
+//! context: the file's text, or just the mentioned lines, or the directory's
+//! entries.
+
+use std::fs;
+use std::path::Path;
+
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+
+use crate::history_cell::PlainHistoryCell;
+
+/// Files larger than this are cut off when inlined whole.
+const MAX_INLINED_BYTES: usize = 256 * 1024;
+
+/// Directory listings stop after this many entries.
+const MAX_LISTED_ENTRIES: usize = 200;
+
+/// 1-based, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LineRange {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Mention {
+    pub(crate) path: String,
+    pub(crate) lines: Option<LineRange>,
+}
+
+impl Mention {
+    /// The mention as written, without the `@`.
+    pub(crate) fn label(&self) -> String {
+        match self.lines {
+            Some(LineRange { start, end }) if start == end => format!("{}:{start}", self.path),
+            Some(LineRange { start, end }) => format!("{}:{start}-{end}", self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Splits a trailing `:N` or `:N-M` off `token`.
+pub(crate) fn split_line_range(token: &str) -> (&str, Option<LineRange>) {
+    let Some((path, range)) = token.rsplit_once(':') else {
+        return (token, None);
+    };
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    match (start.parse::<usize>(), end.parse::<usize>()) {
+        (Ok(start), Ok(end)) if start >= 1 && start <= end && !path.is_empty() => {
+            (path, Some(LineRange { start, end }))
+        }
+        _ => (token, None),
+    }
+}
+
+/// The mentions in `text` that name an existing file or directory under
+/// `cwd`, in order and without duplicates.
+pub(crate) fn find_mentions(text: &str, cwd: &Path) -> Vec<Mention> {
+    let mut mentions: Vec<Mention> = Vec::new();
+    for token in text.split_whitespace() {
+        let Some(token) = token.strip_prefix('@') else {
+            continue;
+        };
+        let token = token.trim_end_matches([',', ';', ')', '?', '!']);
+        let (path, lines) = split_line_range(token);
+        let mention = Mention {
+            path: path.to_string(),
+            lines,
+        };
+        if !path.is_empty() && cwd.join(path).exists() && !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// The context inlined for `mention`, or `None` when it cannot be read.
+pub(crate) fn render_mention(mention: &Mention, cwd: &Path) -> Option<String> {
+    let full_path = cwd.join(&mention.path);
+    if full_path.is_dir() {
+        return render_directory(&mention.path, &full_path);
+    }
+    let contents = match fs::read(&full_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::warn!(
+                "failed to read mentioned file {}: {err}",
+                full_path.display()
+            );
+            return None;
+        }
+    };
+    let Ok(contents) = String::from_utf8(contents) else {
+        return Some(format!(
+            "<file path=\"{}\">\n(binary file, not included)\n</file>",
+            mention.path
+        ));
+    };
+    let body = match mention.lines {
+        Some(LineRange { start, end }) => contents
+            .lines()
+            .skip(start - 1)
+            .take(end - start + 1)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None if contents.len() > MAX_INLINED_BYTES => {
+            let mut cut = MAX_INLINED_BYTES;
+            while !contents.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            format!(
+                "{}\n(truncated; the file is {} bytes)",
+                &contents[..cut],
+                contents.len()
+            )
+        }
+        None => contents.trim_end_matches('\n').to_string(),
+    };
+    let lines_attr = mention
+        .lines
+        .map(|LineRange { start, end }| format!(" lines=\"{start}-{end}\""))
+        .unwrap_or_default();
+    Some(format!(
+        "<file path=\"{}\"{lines_attr}>\n{body}\n</file>",
+        mention.path
+    ))
+}
+
+fn render_directory(label: &str, dir: &Path) -> Option<String> {
+    let mut entries: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name() != ".git")
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type().is_ok_and(|ft| ft.is_dir()) {
+                format!("{name}/")
+            } else {
+                name
+            }
+        })
+        .collect();
+    entries.sort();
+    let total = entries.len();
+    entries.truncate(MAX_LISTED_ENTRIES);
+    if total > MAX_LISTED_ENTRIES {
+        entries.push(format!("(and {} more)", total - MAX_LISTED_ENTRIES));
+    }
+    Some(format!(
+        "<directory path=\"{label}\">\n{}\n</directory>",
+        entries.join("\n")
+    ))
+}
+
+/// A transcript line naming the mentions inlined into a prompt.
+pub(crate) fn new_inlined_mentions(labels: &[String]) -> PlainHistoryCell {
+    let line: Line<'static> = vec![
+        "  ↳ ".dim(),
+        format!("included {}", labels.join(", ")).dim(),
+    ]
+    .into();
+    PlainHistoryCell::new(vec![line])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn line_ranges_are_split_off() {
+        assert_eq!(
+            split_line_range("src/main.rs:10-40"),
+            ("src/main.rs", Some(LineRange { start: 10, end: 40 }))
+        );
+        assert_eq!(
+            split_line_range("src/main.rs:7"),
+            ("src/main.rs", Some(LineRange { start: 7, end: 7 }))
+        );
+        assert_eq!(
+            split_line_range("src/main.rs:40-10"),
+            ("src/main.rs:40-10", None)
+        );
+        assert_eq!(split_line_range("C:"), ("C:", None));
+        assert_eq!(split_line_range("src/"), ("src/", None));
+    }
+
+    #[test]
+    fn mentions_are_inlined_as_context() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        fs::create_dir_all(cwd.join("src/nested")).expect("mkdir");
+        fs::write(cwd.join("src/lib.rs"), "one\ntwo\nthree\nfour\n").expect("write");
+
+        let mentions = find_mentions(
+            "compare @src/lib.rs:2-3, @src/ and @missing.rs with @src/lib.rs:2-3",
+            cwd,
+        );
+        assert_eq!(
+            mentions.iter().map(Mention::label).collect::<Vec<_>>(),
+            vec!["src/lib.rs:2-3".to_string(), "src/".to_string()]
+        );
+
+        assert_eq!(
+            render_mention(&mentions[0], cwd),
+            Some("<file path=\"src/lib.rs\" lines=\"2-3\">\ntwo\nthree\n</file>".to_string())
+        );
+        assert_eq!(
+            render_mention(&mentions[1], cwd),
+            Some("<directory path=\"src/\">\nlib.rs\nnested/\n</directory>".to_string())
+        );
+    }
+}
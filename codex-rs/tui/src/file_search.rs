@@ -163,6 +163,8 @@ impl FileSearchManager {
         search_state: Arc<Mutex<SearchState>>,
     ) {
         let compute_indices = true;
+        // `@` mentions can name directories as well as files.
+        let include_dirs = true;
         std::thread::spawn(move || {
             let matches = file_search::run(
                 &query,
@@ -172,6 +174,7 @@ impl FileSearchManager {
                 NUM_FILE_SEARCH_THREADS,
                 cancellation_token.clone(),
                 compute_indices,
+                include_dirs,
             )
             .map(|res| res.matches)
             .unwrap_or_default();
@@ -10,19 +10,50 @@ use std::path::Path;
 use std::process::Stdio;
 use tokio::process::Command;
 
+use codex_core::config_types::DiffRenderer;
+use codex_core::config_types::DiffRendererInput;
+
+use crate::external_diff::render_patch;
+
+/// Like [`get_git_diff`], but pipes the diff through `diff_renderer` when one
+/// is configured. Falls back to git's own coloring if the renderer fails.
+pub(crate) async fn get_rendered_git_diff(
+    diff_renderer: Option<DiffRenderer>,
+    width: usize,
+) -> io::Result<(bool, String)> {
+    let Some(diff_renderer) =
+        diff_renderer.filter(|renderer| renderer.input == DiffRendererInput::Patch)
+    else {
+        return get_git_diff(true).await;
+    };
+
+    let (is_git_repo, diff) = get_git_diff(false).await?;
+    if !is_git_repo || diff.trim().is_empty() {
+        return Ok((is_git_repo, diff));
+    }
+    let rendered =
+        tokio::task::spawn_blocking(move || render_patch(&diff_renderer, &diff, width)).await;
+    match rendered {
+        Ok(Some(rendered)) => Ok((true, rendered)),
+        _ => get_git_diff(true).await,
+    }
+}
+
 /// Return value of [`get_git_diff`].
 ///
 /// * `bool` – Whether the current working directory is inside a Git repo.
 /// * `String` – The concatenated diff (may be empty).
-pub(crate) async fn get_git_diff() -> io::Result<(bool, String)> {
+pub(crate) async fn get_git_diff(color: bool) -> io::Result<(bool, String)> {
     // First check if we are inside a Git repository.
     if !inside_git_repo().await? {
         return Ok((false, String::new()));
     }
+    let color_arg = if color { "--color" } else { "--no-color" };
+    let diff_args = ["diff", color_arg];
 
     // Run tracked diff and untracked file listing in parallel.
     let (tracked_diff_res, untracked_output_res) = tokio::join!(
-        run_git_capture_diff(&["diff", "--color"]),
+        run_git_capture_diff(&diff_args),
         run_git_capture_stdout(&["ls-files", "--others", "--exclude-standard"]),
     );
     let tracked_diff = tracked_diff_res?;
@@ -45,7 +76,7 @@ pub(crate) async fn get_git_diff() -> io::Result<(bool, String)> {
         let null_path = null_path.clone();
         let file = file.to_string();
         join_set.spawn(async move {
-            let args = ["diff", "--color", "--no-index", "--", &null_path, &file];
+            let args = ["diff", color_arg, "--no-index", "--", &null_path, &file];
             run_git_capture_diff(&args).await
         });
     }
@@ -18,15 +18,23 @@ use crate::wrapping::word_wrap_line;
 use crate::wrapping::word_wrap_lines;
 use base64::Engine;
 use codex_core::config::Config;
+use codex_core::config_types::DiffRenderer;
 use codex_core::config_types::McpServerTransportConfig;
 use codex_core::config_types::ReasoningSummaryFormat;
 use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
 use codex_core::plan_tool::UpdatePlanArgs;
+use codex_core::protocol::AnnotationTarget;
+use codex_core::protocol::BackgroundJobEvent;
+use codex_core::protocol::BackgroundJobStatus;
+use codex_core::protocol::ContextUsageEvent;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::SandboxCapability;
+use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::num_format::format_with_separators;
 use image::DynamicImage;
 use image::ImageReader;
 use mcp_types::EmbeddedResourceResource;
@@ -44,6 +52,7 @@ use std::collections::HashMap;
 use std::io::Cursor;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::time::Instant;
 use tracing::error;
@@ -266,16 +275,40 @@ pub(crate) struct PatchHistoryCell {
     event_type: PatchEventType,
     changes: HashMap<PathBuf, FileChange>,
     cwd: PathBuf,
+    diff_renderer: Option<DiffRenderer>,
+    /// Output of the external diff renderer for the last width it was
+    /// requested at, so the program is not re-run on every frame.
+    rendered: Mutex<Option<(u16, Vec<Line<'static>>)>>,
 }
 
 impl HistoryCell for PatchHistoryCell {
     fn display_lines(&self, width: u16) -> Vec<Line<'static>> {
-        create_diff_summary(
+        let Some(diff_renderer) = self.diff_renderer.as_ref() else {
+            return create_diff_summary(
+                &self.changes,
+                self.event_type.clone(),
+                &self.cwd,
+                width as usize,
+                None,
+            );
+        };
+
+        #[expect(clippy::unwrap_used)]
+        let mut rendered = self.rendered.lock().unwrap();
+        if let Some((rendered_width, lines)) = rendered.as_ref()
+            && *rendered_width == width
+        {
+            return lines.clone();
+        }
+        let lines = create_diff_summary(
             &self.changes,
             self.event_type.clone(),
             &self.cwd,
             width as usize,
-        )
+            Some(diff_renderer),
+        );
+        *rendered = Some((width, lines.clone()));
+        lines
     }
 }
 
@@ -378,6 +411,7 @@ pub(crate) fn new_session_info(
         history_entry_count: _,
         initial_messages: _,
         rollout_path: _,
+        sandbox_capability,
     } = event;
     if is_first_event {
         // Header box rendered as history (so it appears at the very top)
@@ -421,12 +455,27 @@ pub(crate) fn new_session_info(
             ]),
         ];
 
-        CompositeHistoryCell {
-            parts: vec![
-                Box::new(header),
-                Box::new(PlainHistoryCell { lines: help_lines }),
-            ],
+        let mut parts: Vec<Box<dyn HistoryCell>> = vec![
+            Box::new(header),
+            Box::new(PlainHistoryCell { lines: help_lines }),
+        ];
+        if let Some(SandboxCapability::Unavailable { reason }) = sandbox_capability
+            && config.sandbox_policy != SandboxPolicy::DangerFullAccess
+        {
+            parts.push(Box::new(PlainHistoryCell {
+                lines: vec![
+                    vec![
+                        "⚠ ".red(),
+                        format!(
+                            "Commands cannot be sandboxed here ({reason}), so untrusted commands need your approval."
+                        )
+                        .red(),
+                    ]
+                    .into(),
+                ],
+            }));
         }
+        CompositeHistoryCell { parts }
     } else if config.model == model {
         CompositeHistoryCell { parts: vec![] }
     } else {
@@ -884,7 +933,8 @@ pub(crate) fn new_mcp_tools_output(
                 let cmd_display = format!("{command}{args_suffix}");
                 lines.push(vec!["    • Command: ".into(), cmd_display.into()].into());
             }
-            McpServerTransportConfig::StreamableHttp { url, .. } => {
+            McpServerTransportConfig::StreamableHttp { url, .. }
+            | McpServerTransportConfig::Sse { url, .. } => {
                 lines.push(vec!["    • URL: ".into(), url.clone().into()].into());
             }
         }
@@ -900,6 +950,139 @@ pub(crate) fn new_mcp_tools_output(
     PlainHistoryCell { lines }
 }
 
+fn background_job_status_span(job: &BackgroundJobEvent) -> Span<'static> {
+    let duration = job
+        .duration_ms
+        .map(|ms| {
+            format!(
+                " in {}",
+                super::status_indicator_widget::fmt_elapsed_compact(ms / 1000)
+            )
+        })
+        .unwrap_or_default();
+    match (job.status, job.exit_code) {
+        (BackgroundJobStatus::Running, _) => "running".cyan(),
+        (BackgroundJobStatus::Completed, _) => format!("succeeded{duration}").green(),
+        (BackgroundJobStatus::Failed, Some(exit_code)) => {
+            format!("exited {exit_code}{duration}").red()
+        }
+        (BackgroundJobStatus::Failed, None) => format!("failed{duration}").red(),
+        (BackgroundJobStatus::TimedOut, _) => format!("timed out{duration}").red(),
+        (BackgroundJobStatus::Killed, _) => format!("killed{duration}").dim(),
+    }
+}
+
+pub(crate) fn new_background_job_finished(job: &BackgroundJobEvent) -> PlainHistoryCell {
+    let lines: Vec<Line<'static>> = vec![
+        vec![
+            "• ".into(),
+            format!("Background job {} ", job.job_id).into(),
+            background_job_status_span(job),
+            " ".into(),
+            strip_bash_lc_and_escape(&job.command).dim(),
+        ]
+        .into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
+/// Render the `/jobs` listing of the background jobs started in this session.
+pub(crate) fn new_jobs_output(jobs: &[BackgroundJobEvent]) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = vec![
+        "/jobs".magenta().into(),
+        "".into(),
+        vec!["⏱  ".into(), "Background jobs".bold()].into(),
+        "".into(),
+    ];
+    if jobs.is_empty() {
+        lines.push("  • No background jobs in this session.".italic().into());
+    }
+    for job in jobs {
+        lines.push(
+            vec![
+                "  • ".into(),
+                job.job_id.clone().into(),
+                " ".into(),
+                background_job_status_span(job),
+            ]
+            .into(),
+        );
+        lines.push(
+            vec![
+                "    $ ".dim(),
+                strip_bash_lc_and_escape(&job.command).into(),
+            ]
+            .into(),
+        );
+    }
+    lines.push("".into());
+    PlainHistoryCell { lines }
+}
+
+/// Render the `/memory` view of the notes kept about the current project.
+pub(crate) fn new_memory_output(
+    path: &Path,
+    notes: Option<&str>,
+    enabled: bool,
+) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = vec![
+        "/memory".magenta().into(),
+        "".into(),
+        vec!["🧠  ".into(), "Project memory".bold()].into(),
+        vec!["  ".into(), path.display().to_string().dim()].into(),
+        "".into(),
+    ];
+    match notes.map(str::trim).filter(|notes| !notes.is_empty()) {
+        Some(notes) => {
+            lines.extend(notes.lines().map(|line| Line::from(format!("  {line}"))));
+        }
+        None => lines.push("  • Nothing remembered yet.".italic().into()),
+    }
+    lines.push("".into());
+    if !enabled {
+        lines.push(
+            "  Memory is off; sessions use these notes with `[memory] enabled = true`."
+                .dim()
+                .into(),
+        );
+    }
+    lines.push("  Run /memory edit to change the notes.".dim().into());
+    lines.push("".into());
+    PlainHistoryCell { lines }
+}
+
+/// Render the `/context` breakdown of the estimated prompt size.
+pub(crate) fn new_context_usage_output(usage: ContextUsageEvent) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = vec![
+        "/context".magenta().into(),
+        "".into(),
+        vec!["📐  ".into(), "Context window".bold()].into(),
+        "".into(),
+    ];
+    let total = format_with_separators(usage.total_tokens);
+    let summary = match usage.model_context_window {
+        Some(window) if window > 0 => format!(
+            "{total} of {} tokens used ({}%)",
+            format_with_separators(window),
+            usage.total_tokens * 100 / window
+        ),
+        _ => format!("{total} tokens used"),
+    };
+    lines.push(vec!["  • ".into(), summary.into()].into());
+    for part in usage.parts {
+        lines.push(
+            vec![
+                "    • ".into(),
+                format!("{}: ", part.name).into(),
+                format_with_separators(part.tokens).dim(),
+            ]
+            .into(),
+        );
+    }
+    lines.push("".into());
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_info_event(message: String, hint: Option<String>) -> PlainHistoryCell {
     let mut line = vec!["• ".into(), message.into()];
     if let Some(hint) = hint {
@@ -910,6 +1093,18 @@ pub(crate) fn new_info_event(message: String, hint: Option<String>) -> PlainHist
     PlainHistoryCell { lines }
 }
 
+pub(crate) fn new_annotation(target: AnnotationTarget, comment: String) -> PlainHistoryCell {
+    let lines: Vec<Line<'static>> = vec![
+        vec![
+            "✎ ".dim(),
+            format!("Annotated {target}: ").magenta(),
+            comment.into(),
+        ]
+        .into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_error_event(message: String) -> PlainHistoryCell {
     // Use a hair space (U+200A) to create a subtle, near-invisible separation
     // before the text. VS16 is intentionally omitted to keep spacing tighter
@@ -996,11 +1191,14 @@ pub(crate) fn new_patch_event(
     event_type: PatchEventType,
     changes: HashMap<PathBuf, FileChange>,
     cwd: &Path,
+    diff_renderer: Option<DiffRenderer>,
 ) -> PatchHistoryCell {
     PatchHistoryCell {
         event_type,
         changes,
         cwd: cwd.to_path_buf(),
+        diff_renderer,
+        rendered: Mutex::new(None),
     }
 }
 
@@ -1159,6 +1357,7 @@ mod tests {
     use codex_core::config::Config;
     use codex_core::config::ConfigOverrides;
     use codex_core::config::ConfigToml;
+    use codex_core::protocol::ContextUsagePart;
     use codex_protocol::parse_command::ParsedCommand;
     use dirs::home_dir;
     use pretty_assertions::assert_eq;
@@ -1193,6 +1392,73 @@ mod tests {
         render_lines(&cell.transcript_lines())
     }
 
+    #[test]
+    fn jobs_output_lists_status_and_command() {
+        let jobs = vec![
+            BackgroundJobEvent {
+                job_id: "job-1".to_string(),
+                command: vec!["cargo".to_string(), "test".to_string()],
+                status: BackgroundJobStatus::Failed,
+                exit_code: Some(101),
+                duration_ms: Some(125_000),
+            },
+            BackgroundJobEvent {
+                job_id: "job-2".to_string(),
+                command: vec!["bash".into(), "-lc".into(), "make -j8".into()],
+                status: BackgroundJobStatus::Running,
+                exit_code: None,
+                duration_ms: None,
+            },
+        ];
+
+        let cell = new_jobs_output(&jobs);
+        assert_eq!(
+            render_lines(&cell.display_lines(80)),
+            vec![
+                "/jobs",
+                "",
+                "⏱  Background jobs",
+                "",
+                "  • job-1 exited 101 in 2m 05s",
+                "    $ cargo test",
+                "  • job-2 running",
+                "    $ make -j8",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn context_usage_lists_the_parts_of_the_prompt() {
+        let cell = new_context_usage_output(ContextUsageEvent {
+            total_tokens: 200,
+            model_context_window: Some(800),
+            parts: vec![
+                ContextUsagePart {
+                    name: "System instructions".to_string(),
+                    tokens: 120,
+                },
+                ContextUsagePart {
+                    name: "Messages".to_string(),
+                    tokens: 80,
+                },
+            ],
+        });
+        assert_eq!(
+            render_lines(&cell.display_lines(80)),
+            vec![
+                "/context",
+                "",
+                "📐  Context window",
+                "",
+                "  • 200 of 800 tokens used (25%)",
+                "    • System instructions: 120",
+                "    • Messages: 80",
+                "",
+            ]
+        );
+    }
+
     #[test]
     fn active_mcp_tool_call_snapshot() {
         let invocation = McpInvocation {
@@ -1447,6 +1713,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         // Mark call complete so markers are ✓
         cell.complete_call(
@@ -1478,6 +1745,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         // Call 1: Search only
         cell.complete_call(
@@ -1560,6 +1828,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         cell.complete_call(
             "c1",
@@ -1588,6 +1857,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         // Mark call complete so it renders as "Ran"
         cell.complete_call(
@@ -1618,6 +1888,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         cell.complete_call(
             &call_id,
@@ -1646,6 +1917,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         cell.complete_call(
             &call_id,
@@ -1673,6 +1945,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         cell.complete_call(
             &call_id,
@@ -1701,6 +1974,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         cell.complete_call(
             &call_id,
@@ -1729,6 +2003,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
         let stderr: String = (1..=10)
             .map(|n| n.to_string())
@@ -1775,6 +2050,7 @@ mod tests {
             output: None,
             start_time: Some(Instant::now()),
             duration: None,
+            live_output: Vec::new(),
         });
 
         let stderr = "error: first line on stderr\nerror: second line on stderr".to_string();
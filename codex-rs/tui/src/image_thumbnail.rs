@@ -0,0 +1,136 @@
+//! Previews of the images attached to a prompt, shown in the transcript.
+//!
+//! On terminals with 24-bit color each image is drawn with half blocks, two
+//! pixels per cell; elsewhere only a line naming the image is shown.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use image::DynamicImage;
+use image::Rgba;
+use image::imageops::FilterType;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+
+use crate::history_cell::PlainHistoryCell;
+
+/// Largest thumbnail, in cells.
+const MAX_COLS: u32 = 32;
+const MAX_ROWS: u32 = 8;
+
+/// A history cell listing `paths`, each with a thumbnail when the terminal
+/// can show one.
+pub(crate) fn new_image_attachments(paths: &[PathBuf]) -> PlainHistoryCell {
+    let true_color = supports_color::on_cached(supports_color::Stream::Stdout)
+        .is_some_and(|level| level.has_16m);
+    let mut lines = Vec::new();
+    for path in paths {
+        lines.extend(attachment_lines(path, true_color));
+    }
+    PlainHistoryCell::new(lines)
+}
+
+fn attachment_lines(path: &Path, true_color: bool) -> Vec<Line<'static>> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let image = match image::open(path) {
+        Ok(image) => image,
+        Err(err) => {
+            tracing::warn!("failed to open attached image {}: {err}", path.display());
+            return vec![vec!["  ▣ ".dim(), name.into(), " (unreadable)".dim()].into()];
+        }
+    };
+    let mut lines: Vec<Line<'static>> = vec![
+        vec![
+            "  ▣ ".dim(),
+            name.into(),
+            format!(" {}x{}", image.width(), image.height()).dim(),
+        ]
+        .into(),
+    ];
+    if true_color {
+        lines.extend(thumbnail(&image).into_iter().map(|line| {
+            let mut spans = vec![Span::from("    ")];
+            spans.extend(line.spans);
+            Line::from(spans)
+        }));
+    }
+    lines
+}
+
+/// Draws `image` scaled to fit [`MAX_COLS`] by [`MAX_ROWS`] cells, the upper
+/// pixel of each cell in the foreground and the lower in the background.
+fn thumbnail(image: &DynamicImage) -> Vec<Line<'static>> {
+    let small = image
+        .resize(MAX_COLS, MAX_ROWS * 2, FilterType::Triangle)
+        .to_rgba8();
+    let (width, height) = small.dimensions();
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let mut style = Style::default().fg(rgb(*small.get_pixel(x, y)));
+                    if y + 1 < height {
+                        style = style.bg(rgb(*small.get_pixel(x, y + 1)));
+                    }
+                    Span::styled("▀", style)
+                })
+                .collect::<Vec<_>>()
+                .into()
+        })
+        .collect()
+}
+
+// The thumbnail reproduces the image's own colors, which only makes sense
+// in RGB.
+#[allow(clippy::disallowed_methods)]
+fn rgb(pixel: Rgba<u8>) -> Color {
+    let [r, g, b, _] = pixel.0;
+    Color::Rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use pretty_assertions::assert_eq;
+
+    fn text(line: &Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn attachments_are_named_with_their_size() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("shot.png");
+        RgbaImage::from_pixel(40, 20, Rgba([255, 0, 0, 255]))
+            .save(&path)
+            .expect("write png");
+
+        let plain = attachment_lines(&path, false);
+        assert_eq!(
+            plain.iter().map(text).collect::<Vec<_>>(),
+            vec!["  ▣ shot.png 40x20".to_string()]
+        );
+
+        // 40x20 scales to 32x16 pixels: 8 rows of 32 half blocks.
+        let drawn = attachment_lines(&path, true);
+        assert_eq!(drawn.len(), 9);
+        assert_eq!(text(&drawn[1]), format!("    {}", "▀".repeat(32)));
+
+        let missing = attachment_lines(&dir.path().join("missing.png"), true);
+        assert_eq!(
+            missing.iter().map(text).collect::<Vec<_>>(),
+            vec!["  ▣ missing.png (unreadable)".to_string()]
+        );
+    }
+}
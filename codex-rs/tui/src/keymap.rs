@@ -0,0 +1,366 @@
+//! Configurable key bindings for the core actions of the TUI (`[tui.keys]`).
+//!
+//! The keymap is loaded once at startup and is process-wide, so widgets can
+//! consult it without threading it through every constructor. Keys that are
+//! not actions (text editing, popups, Ctrl-C) keep their fixed bindings.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use codex_core::config_types::KeyPreset;
+use codex_core::config_types::TuiKeys;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum KeyAction {
+    Submit,
+    Newline,
+    Steer,
+    Interrupt,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollTop,
+    ScrollBottom,
+    Transcript,
+    Approve,
+    ApproveForSession,
+    Deny,
+    NewTab,
+    NextTab,
+    PrevTab,
+    CloseTab,
+    CopyMessage,
+    CopyCode,
+    CopyTranscript,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parses keys like `enter`, `ctrl-j`, `alt-<` or `G`.
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (prefix, key) = match spec.strip_suffix("--") {
+            Some(prefix) => (prefix, "-"),
+            None => spec.rsplit_once('-').unwrap_or(("", spec)),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('-').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{modifier}` in `{spec}`")),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                _ => return Err(format!("unknown key `{key}` in `{spec}`")),
+            },
+        };
+        // Terminals report the case of a character rather than Shift, so
+        // `shift-g` is `G`.
+        if let KeyCode::Char(c) = code
+            && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            modifiers.remove(KeyModifiers::SHIFT);
+            return Ok(Self {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers,
+            });
+        }
+        Ok(Self { code, modifiers })
+    }
+
+    fn matches(&self, event: &KeyEvent) -> bool {
+        let mut modifiers = event.modifiers;
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        self.code == event.code && self.modifiers == modifiers
+    }
+
+    /// Short name of the key for hints, e.g. `Y` or `ctrl-g`.
+    pub(crate) fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) if self.modifiers.is_empty() => c.to_uppercase().to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::PageUp => "pageup".to_string(),
+            KeyCode::PageDown => "pagedown".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            other => format!("{other:?}").to_ascii_lowercase(),
+        };
+        let mut label = String::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+        label.push_str(&key);
+        label
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Keymap {
+    bindings: HashMap<KeyAction, Vec<KeyBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&TuiKeys::default()).unwrap_or_else(|_| Self {
+            bindings: HashMap::new(),
+        })
+    }
+}
+
+impl Keymap {
+    pub(crate) fn from_config(config: &TuiKeys) -> Result<Self, String> {
+        let overrides = [
+            (KeyAction::Submit, &config.submit),
+            (KeyAction::Newline, &config.newline),
+            (KeyAction::Steer, &config.steer),
+            (KeyAction::Interrupt, &config.interrupt),
+            (KeyAction::ScrollUp, &config.scroll_up),
+            (KeyAction::ScrollDown, &config.scroll_down),
+            (KeyAction::PageUp, &config.page_up),
+            (KeyAction::PageDown, &config.page_down),
+            (KeyAction::ScrollTop, &config.scroll_top),
+            (KeyAction::ScrollBottom, &config.scroll_bottom),
+            (KeyAction::Transcript, &config.transcript),
+            (KeyAction::Approve, &config.approve),
+            (KeyAction::ApproveForSession, &config.approve_for_session),
+            (KeyAction::Deny, &config.deny),
+            (KeyAction::NewTab, &config.new_tab),
+            (KeyAction::NextTab, &config.next_tab),
+            (KeyAction::PrevTab, &config.prev_tab),
+            (KeyAction::CloseTab, &config.close_tab),
+            (KeyAction::CopyMessage, &config.copy_message),
+            (KeyAction::CopyCode, &config.copy_code),
+            (KeyAction::CopyTranscript, &config.copy_transcript),
+        ];
+        let mut bindings = HashMap::new();
+        for (action, keys) in overrides {
+            let specs: Vec<&str> = match keys {
+                Some(keys) => keys.iter().map(String::as_str).collect(),
+                None => preset_keys(config.preset, action),
+            };
+            let keys = specs
+                .into_iter()
+                .map(KeyBinding::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.insert(action, keys);
+        }
+        Ok(Self { bindings })
+    }
+
+    /// Whether `event` is bound to `action`.
+    pub(crate) fn matches(&self, action: KeyAction, event: &KeyEvent) -> bool {
+        self.bindings
+            .get(&action)
+            .is_some_and(|keys| keys.iter().any(|key| key.matches(event)))
+    }
+
+    /// The first key bound to `action`, if any.
+    pub(crate) fn primary(&self, action: KeyAction) -> Option<KeyBinding> {
+        self.bindings
+            .get(&action)
+            .and_then(|keys| keys.first().copied())
+    }
+}
+
+fn preset_keys(preset: KeyPreset, action: KeyAction) -> Vec<&'static str> {
+    let defaults: &[&str] = match action {
+        KeyAction::Submit => &["enter"],
+        KeyAction::Newline => &["shift-enter", "alt-enter", "ctrl-j"],
+        KeyAction::Steer => &["ctrl-s"],
+        KeyAction::Interrupt => &["esc"],
+        KeyAction::ScrollUp => &["up"],
+        KeyAction::ScrollDown => &["down"],
+        KeyAction::PageUp => &["pageup"],
+        KeyAction::PageDown => &["pagedown", "space"],
+        KeyAction::ScrollTop => &["home"],
+        KeyAction::ScrollBottom => &["end"],
+        KeyAction::Transcript => &["ctrl-t"],
+        KeyAction::Approve => &["y"],
+        KeyAction::ApproveForSession => &["a"],
+        KeyAction::Deny => &["n"],
+        // Ctrl-T already opens the transcript.
+        KeyAction::NewTab => &["alt-t"],
+        KeyAction::NextTab => &["alt-n"],
+        KeyAction::PrevTab => &["alt-p"],
+        KeyAction::CloseTab => &["alt-w"],
+        KeyAction::CopyMessage => &["alt-c"],
+        KeyAction::CopyCode => &["alt-x"],
+        KeyAction::CopyTranscript => &["alt-a"],
+    };
+    let extra: &[&str] = match (preset, action) {
+        (KeyPreset::Default, _) => &[],
+        (KeyPreset::Emacs, KeyAction::Interrupt) => &["ctrl-g"],
+        (KeyPreset::Emacs, KeyAction::ScrollUp) => &["ctrl-p"],
+        (KeyPreset::Emacs, KeyAction::ScrollDown) => &["ctrl-n"],
+        (KeyPreset::Emacs, KeyAction::PageUp) => &["alt-v"],
+        (KeyPreset::Emacs, KeyAction::PageDown) => &["ctrl-v"],
+        (KeyPreset::Emacs, KeyAction::ScrollTop) => &["alt-<"],
+        (KeyPreset::Emacs, KeyAction::ScrollBottom) => &["alt->"],
+        (KeyPreset::Vim, KeyAction::ScrollUp) => &["k"],
+        (KeyPreset::Vim, KeyAction::ScrollDown) => &["j"],
+        (KeyPreset::Vim, KeyAction::PageUp) => &["ctrl-b", "ctrl-u"],
+        (KeyPreset::Vim, KeyAction::PageDown) => &["ctrl-f", "ctrl-d"],
+        (KeyPreset::Vim, KeyAction::ScrollTop) => &["g"],
+        (KeyPreset::Vim, KeyAction::ScrollBottom) => &["G"],
+        (KeyPreset::Emacs | KeyPreset::Vim, _) => &[],
+    };
+    defaults.iter().chain(extra).copied().collect()
+}
+
+/// The keymap in use: the one installed with [`init`], or the default one.
+pub(crate) fn current() -> &'static Keymap {
+    KEYMAP.get_or_init(Keymap::default)
+}
+
+/// Installs the keymap for the rest of the process. Only the first call has
+/// an effect.
+pub(crate) fn init(keymap: Keymap) {
+    let _ = KEYMAP.set(keymap);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn keys_are_parsed() {
+        assert_eq!(
+            KeyBinding::parse("ctrl-alt-enter"),
+            Ok(KeyBinding {
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::CONTROL | KeyModifiers::ALT,
+            })
+        );
+        assert_eq!(
+            KeyBinding::parse("shift-g"),
+            Ok(KeyBinding {
+                code: KeyCode::Char('G'),
+                modifiers: KeyModifiers::NONE,
+            })
+        );
+        assert_eq!(
+            KeyBinding::parse("ctrl--"),
+            Ok(KeyBinding {
+                code: KeyCode::Char('-'),
+                modifiers: KeyModifiers::CONTROL,
+            })
+        );
+        assert_eq!(
+            KeyBinding::parse("hyper-x"),
+            Err("unknown modifier `hyper` in `hyper-x`".to_string())
+        );
+        assert_eq!(
+            KeyBinding::parse("ctrl-return"),
+            Err("unknown key `return` in `ctrl-return`".to_string())
+        );
+    }
+
+    #[test]
+    fn presets_add_to_the_default_bindings() {
+        let vim = Keymap::from_config(&TuiKeys {
+            preset: KeyPreset::Vim,
+            ..Default::default()
+        })
+        .expect("valid preset");
+
+        assert!(vim.matches(
+            KeyAction::ScrollDown,
+            &key(KeyCode::Down, KeyModifiers::NONE)
+        ));
+        assert!(vim.matches(
+            KeyAction::ScrollDown,
+            &key(KeyCode::Char('j'), KeyModifiers::NONE)
+        ));
+        assert!(vim.matches(
+            KeyAction::ScrollBottom,
+            &key(KeyCode::Char('G'), KeyModifiers::SHIFT)
+        ));
+        assert!(!vim.matches(
+            KeyAction::ScrollBottom,
+            &key(KeyCode::Char('g'), KeyModifiers::NONE)
+        ));
+        assert!(!Keymap::default().matches(
+            KeyAction::ScrollDown,
+            &key(KeyCode::Char('j'), KeyModifiers::NONE)
+        ));
+    }
+
+    #[test]
+    fn configured_keys_replace_the_preset() {
+        let keymap = Keymap::from_config(&TuiKeys {
+            preset: KeyPreset::Emacs,
+            submit: Some(vec!["ctrl-enter".to_string()]),
+            approve: Some(vec!["ctrl-y".to_string()]),
+            ..Default::default()
+        })
+        .expect("valid keys");
+
+        assert!(keymap.matches(
+            KeyAction::Submit,
+            &key(KeyCode::Enter, KeyModifiers::CONTROL)
+        ));
+        assert!(!keymap.matches(KeyAction::Submit, &key(KeyCode::Enter, KeyModifiers::NONE)));
+        assert!(keymap.matches(
+            KeyAction::Interrupt,
+            &key(KeyCode::Char('g'), KeyModifiers::CONTROL)
+        ));
+        assert_eq!(
+            keymap.primary(KeyAction::Approve).map(|key| key.label()),
+            Some("ctrl-y".to_string())
+        );
+        assert_eq!(
+            keymap.primary(KeyAction::Deny).map(|key| key.label()),
+            Some("N".to_string())
+        );
+    }
+}
@@ -17,6 +17,7 @@ use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::find_conversation_path_by_id_str;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::session_lock_status;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::mcp_protocol::AuthMode;
@@ -31,31 +32,43 @@ mod app;
 mod app_backtrack;
 mod app_event;
 mod app_event_sender;
+mod app_tabs;
 mod ascii_animation;
 mod bottom_pane;
 mod chatwidget;
 mod citation_regex;
 mod cli;
+mod clipboard_copy;
 mod clipboard_paste;
 mod color;
 pub mod custom_terminal;
 mod diff_render;
+mod diff_viewer;
 mod exec_cell;
 mod exec_command;
+mod external_diff;
+mod external_editor;
+mod file_mentions;
 mod file_search;
 mod frames;
 mod get_git_diff;
 mod history_cell;
+mod image_thumbnail;
 pub mod insert_history;
 mod key_hint;
+mod keymap;
 pub mod live_wrap;
 mod markdown;
 mod markdown_render;
 mod markdown_stream;
+mod mouse;
+mod notifications;
 pub mod onboarding;
 mod pager_overlay;
+mod patch_selection;
 mod render;
 mod resume_picker;
+mod session_locked;
 mod session_log;
 mod shimmer;
 mod slash_command;
@@ -65,6 +78,7 @@ mod streaming;
 mod style;
 mod terminal_palette;
 mod text_formatting;
+mod theme;
 mod tui;
 mod ui_consts;
 mod version;
@@ -239,7 +253,13 @@ pub async fn run_main(
             .map_err(|e| std::io::Error::other(format!("OSS setup failed: {e}")))?;
     }
 
-    let _ = tracing_subscriber::registry().with(file_layer).try_init();
+    let session_log_layer = codex_core::session_log::layer(&config.codex_home).ok();
+    let (otel_layer, _otel_guard) = codex_core::otel::layer("codex-tui").unzip();
+    let _ = tracing_subscriber::registry()
+        .with(file_layer)
+        .with(session_log_layer)
+        .with(otel_layer)
+        .try_init();
 
     run_ratatui_app(cli, config, active_profile, should_show_trust_screen)
         .await
@@ -264,6 +284,7 @@ async fn run_ratatui_app(
         tracing::error!("panic: {info}");
         prev_hook(info);
     }));
+    tui::set_mouse_capture(config.tui_mouse);
     let mut terminal = tui::init()?;
     terminal.clear()?;
 
@@ -334,7 +355,7 @@ async fn run_ratatui_app(
     // Initialize high-fidelity session event logging if enabled.
     session_log::maybe_init(&config);
 
-    let auth_manager = AuthManager::shared(config.codex_home.clone());
+    let auth_manager = AuthManager::shared_from_config(&config);
     let login_status = get_login_status(&config);
     let should_show_onboarding =
         should_show_onboarding(login_status, &config, should_show_trust_screen);
@@ -375,21 +396,44 @@ async fn run_ratatui_app(
             Err(_) => resume_picker::ResumeSelection::StartFresh,
         }
     } else if cli.resume_picker {
-        match resume_picker::run_resume_picker(&mut tui, &config.codex_home).await? {
-            resume_picker::ResumeSelection::Exit => {
-                restore();
-                session_log::log_session_end();
-                return Ok(AppExitInfo {
-                    token_usage: codex_core::protocol::TokenUsage::default(),
-                    conversation_id: None,
-                });
-            }
-            other => other,
-        }
+        resume_picker::run_resume_picker(
+            &mut tui,
+            &config.codex_home,
+            &config.cwd,
+            resume_picker::PickerOrigin::Startup,
+        )
+        .await?
     } else {
         resume_picker::ResumeSelection::StartFresh
     };
 
+    // Another process may be recording the session; offer to observe or fork
+    // it rather than interleaving writes.
+    let resume_selection = match resume_selection {
+        resume_picker::ResumeSelection::Resume(path) => {
+            match session_lock_status(&config.codex_home, &path) {
+                Ok(Some(locked)) => {
+                    session_locked::run_session_locked_view(&mut tui, &config.codex_home, locked)
+                        .await?
+                }
+                Ok(None) => resume_picker::ResumeSelection::Resume(path),
+                Err(e) => {
+                    error!("Failed to check whether the session is in use: {e}");
+                    resume_picker::ResumeSelection::Resume(path)
+                }
+            }
+        }
+        other => other,
+    };
+    if matches!(resume_selection, resume_picker::ResumeSelection::Exit) {
+        restore();
+        session_log::log_session_end();
+        return Ok(AppExitInfo {
+            token_usage: codex_core::protocol::TokenUsage::default(),
+            conversation_id: None,
+        });
+    }
+
     let Cli { prompt, images, .. } = cli;
 
     let app_result = App::run(
@@ -429,11 +473,14 @@ pub enum LoginStatus {
 }
 
 fn get_login_status(config: &Config) -> LoginStatus {
-    if config.model_provider.requires_openai_auth {
+    if config.external_auth_command.is_some() {
+        // Tokens come from the configured command; there is nothing to log in to.
+        LoginStatus::AuthMode(AuthMode::ApiKey)
+    } else if config.model_provider.requires_openai_auth {
         // Reading the OpenAI API key is an async operation because it may need
         // to refresh the token. Block on it.
         let codex_home = config.codex_home.clone();
-        match CodexAuth::from_codex_home(&codex_home) {
+        match CodexAuth::from_codex_home(&codex_home, config.credential_store) {
             Ok(Some(auth)) => LoginStatus::AuthMode(auth.mode),
             Ok(None) => LoginStatus::NotAuthenticated,
             Err(err) => {
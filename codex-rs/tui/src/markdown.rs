@@ -1,5 +1,8 @@
 use codex_core::config::Config;
 use codex_core::config_types::UriBasedFileOpener;
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use pulldown_cmark::TagEnd;
 use ratatui::text::Line;
 use std::path::Path;
 
@@ -26,6 +29,25 @@ fn append_markdown_with_opener_and_cwd(
     crate::render::line_utils::push_owned_lines(&rendered.lines, lines);
 }
 
+/// The contents of the last fenced or indented code block in `markdown_source`.
+pub(crate) fn last_code_block(markdown_source: &str) -> Option<String> {
+    let mut last = None;
+    let mut current: Option<String> = None;
+    for event in pulldown_cmark::Parser::new(markdown_source) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => current = Some(String::new()),
+            Event::Text(text) => {
+                if let Some(code) = current.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => last = current.take(),
+            _ => {}
+        }
+    }
+    last
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,4 +213,11 @@ mod tests {
             "did not expect a split into ['1.', 'Tight item']; got: {lines:?}"
         );
     }
+
+    #[test]
+    fn last_code_block_is_extracted() {
+        let src = "First:\n```rust\nfn a() {}\n```\nThen:\n\n```\nmake test\n```\nDone.\n";
+        assert_eq!(last_code_block(src), Some("make test\n".to_string()));
+        assert_eq!(last_code_block("no code here"), None);
+    }
 }
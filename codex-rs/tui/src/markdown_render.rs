@@ -1,4 +1,7 @@
 use crate::citation_regex::CITATION_REGEX;
+use crate::render::highlight::Language;
+use crate::render::highlight::highlight_code_to_lines;
+use crate::render::highlight::language_for_fence;
 use pulldown_cmark::CodeBlockKind;
 use pulldown_cmark::CowStr;
 use pulldown_cmark::Event;
@@ -71,6 +74,9 @@ where
     scheme: Option<String>,
     cwd: Option<std::path::PathBuf>,
     in_code_block: bool,
+    /// The open code block's language and text so far, when it can be
+    /// highlighted. Highlighting waits for the end of the block.
+    highlighted_code: Option<(&'static Language, String)>,
 }
 
 impl<'a, I> Writer<'a, I>
@@ -91,6 +97,7 @@ where
             scheme,
             cwd,
             in_code_block: false,
+            highlighted_code: None,
         }
     }
 
@@ -233,6 +240,10 @@ where
     }
 
     fn text(&mut self, text: CowStr<'a>) {
+        if let Some((_, code)) = &mut self.highlighted_code {
+            code.push_str(&text);
+            return;
+        }
         if self.pending_marker_line {
             self.push_line(Line::default());
         }
@@ -274,6 +285,32 @@ where
         self.needs_newline = false;
     }
 
+    /// Pushes the highlighted lines of a code block, the way `text` pushes
+    /// plain ones.
+    fn code_lines(&mut self, lines: Vec<Line<'static>>) {
+        if self.pending_marker_line {
+            self.push_line(Line::default());
+        }
+        self.pending_marker_line = false;
+        for (i, line) in lines.into_iter().enumerate() {
+            if self.needs_newline {
+                self.push_line(Line::default());
+                self.needs_newline = false;
+            }
+            if i > 0 {
+                self.push_line(Line::default());
+            }
+            for span in line
+                .spans
+                .into_iter()
+                .filter(|span| !span.content.is_empty())
+            {
+                self.push_span(span);
+            }
+        }
+        self.needs_newline = false;
+    }
+
     fn code(&mut self, code: CowStr<'a>) {
         if self.pending_marker_line {
             self.push_line(Line::default());
@@ -350,11 +387,15 @@ where
         self.needs_newline = false;
     }
 
-    fn start_codeblock(&mut self, _lang: Option<String>, indent: Option<Span<'static>>) {
+    fn start_codeblock(&mut self, lang: Option<String>, indent: Option<Span<'static>>) {
         if !self.text.lines.is_empty() {
             self.push_blank_line();
         }
         self.in_code_block = true;
+        self.highlighted_code = lang
+            .as_deref()
+            .and_then(language_for_fence)
+            .map(|language| (language, String::new()));
         self.indent_stack.push(IndentContext::new(
             vec![indent.unwrap_or_default()],
             None,
@@ -369,6 +410,12 @@ where
     }
 
     fn end_codeblock(&mut self) {
+        if let Some((language, code)) = self.highlighted_code.take()
+            && !code.is_empty()
+        {
+            let lines = highlight_code_to_lines(code.trim_end_matches('\n'), language);
+            self.code_lines(lines);
+        }
         // self.push_line("```".into());
         self.needs_newline = true;
         self.in_code_block = false;
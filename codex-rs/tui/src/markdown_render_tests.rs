@@ -652,9 +652,21 @@ fn link() {
 }
 
 #[test]
-fn code_block_unhighlighted() {
+fn code_block_highlighted() {
     let text = render_markdown_text("```rust\nfn main() {}\n```\n");
-    let expected = Text::from_iter([Line::from_iter(["", "fn main() {}"])]);
+    let keyword = crate::theme::current().syntax_keyword;
+    let expected = Text::from_iter([Line::from_iter([
+        Span::from(""),
+        Span::styled("fn", keyword),
+        Span::from(" main() {}"),
+    ])]);
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn code_block_in_unknown_language_unhighlighted() {
+    let text = render_markdown_text("```brainfuck\n+[-->-[>>+>-----<<]<--<---]>-.\n```\n");
+    let expected = Text::from_iter([Line::from_iter(["", "+[-->-[>>+>-----<<]<--<---]>-."])]);
     assert_eq!(text, expected);
 }
 
@@ -0,0 +1,173 @@
+//! Hit testing for mouse clicks, done on the last frame drawn: which file
+//! reference or list item is under the pointer.
+//!
+//! Only used when mouse capture is on (`tui.mouse = true`).
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use ratatui::buffer::Buffer;
+use unicode_width::UnicodeWidthStr;
+
+use crate::citation_regex::CITATION_REGEX;
+
+/// A file named in the transcript, e.g. `src/main.rs:42` or a citation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileReference {
+    pub(crate) path: PathBuf,
+    pub(crate) line: Option<u32>,
+}
+
+/// Characters that end a file reference; `:` stays so `path:line` is kept.
+const REFERENCE_DELIMITERS: &[char] =
+    &['"', '\'', '`', '(', ')', '[', ']', '{', '}', '<', '>', ','];
+
+/// The file reference under column `x` of row `y`, if it names a file that
+/// exists. Relative paths are resolved against `cwd`.
+pub(crate) fn file_reference_at(buf: &Buffer, x: u16, y: u16, cwd: &Path) -> Option<FileReference> {
+    let (text, offset) = row_text(buf, x, y)?;
+
+    for caps in CITATION_REGEX.captures_iter(&text) {
+        if let Some(whole) = caps.get(0)
+            && whole.range().contains(&offset)
+        {
+            let path = resolve(caps.get(1)?.as_str(), cwd)?;
+            let line = caps.get(2).and_then(|line| line.as_str().parse().ok());
+            return Some(FileReference { path, line });
+        }
+    }
+
+    let is_delimiter = |c: char| c.is_whitespace() || REFERENCE_DELIMITERS.contains(&c);
+    let start = text[..offset].rfind(is_delimiter).map_or(0, |idx| {
+        idx + text[idx..].chars().next().map_or(1, char::len_utf8)
+    });
+    let end = text[offset..]
+        .find(is_delimiter)
+        .map_or(text.len(), |idx| offset + idx);
+    let token = text.get(start..end)?.trim_end_matches(['.', ':', ';']);
+    if token.is_empty() {
+        return None;
+    }
+
+    // `path:line` or `path:line:column`.
+    let mut parts = token.splitn(3, ':');
+    let path = parts.next()?;
+    let line = parts.next().and_then(|line| line.parse().ok());
+    if let Some(path) = resolve(path, cwd) {
+        return Some(FileReference { path, line });
+    }
+    // Paths with a `:` of their own, e.g. on Windows.
+    resolve(token, cwd).map(|path| FileReference { path, line: None })
+}
+
+/// The number of the list item whose row is `y`, for lists drawn with the
+/// `▌` gutter of selection popups, e.g. `▌ > 2. Approve for this session`.
+pub(crate) fn list_item_at(buf: &Buffer, y: u16) -> Option<usize> {
+    let (text, _) = row_text(buf, buf.area.x, y)?;
+    let rest = text.trim_start().strip_prefix('▌')?;
+    let rest = rest
+        .trim_start()
+        .strip_prefix('>')
+        .unwrap_or(rest)
+        .trim_start();
+    let (number, rest) = rest.split_once(". ")?;
+    if rest.is_empty() {
+        return None;
+    }
+    number.parse().ok().filter(|n| *n > 0)
+}
+
+/// The text of row `y`, and the byte offset of the cell at column `x`.
+/// Cells hidden behind a wide character are skipped, so text such as
+/// `【F:…】` reads as it was written.
+fn row_text(buf: &Buffer, x: u16, y: u16) -> Option<(String, usize)> {
+    let area = buf.area;
+    if y < area.top() || y >= area.bottom() || x < area.left() || x >= area.right() {
+        return None;
+    }
+    let mut text = String::new();
+    let mut offset = 0;
+    let mut col = area.left();
+    while col < area.right() {
+        let symbol = buf.cell((col, y)).map_or(" ", |cell| cell.symbol());
+        let width = (symbol.width() as u16).max(1);
+        if (col..col + width).contains(&x) {
+            offset = text.len();
+        }
+        text.push_str(symbol);
+        col += width;
+    }
+    Some((text, offset))
+}
+
+fn resolve(path: &str, cwd: &Path) -> Option<PathBuf> {
+    if path.is_empty() {
+        return None;
+    }
+    let path = Path::new(path);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    };
+    path.is_file().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use ratatui::layout::Rect;
+    use tempfile::TempDir;
+
+    fn buffer(lines: &[&str]) -> Buffer {
+        let mut buf = Buffer::empty(Rect::new(0, 10, 60, lines.len() as u16));
+        for (idx, line) in lines.iter().enumerate() {
+            buf.set_string(0, 10 + idx as u16, line, ratatui::style::Style::default());
+        }
+        buf
+    }
+
+    #[test]
+    fn finds_paths_with_line_numbers_and_citations() {
+        let cwd = TempDir::new().expect("tempdir");
+        std::fs::create_dir(cwd.path().join("src")).expect("mkdir");
+        std::fs::write(cwd.path().join("src/main.rs"), "fn main() {}\n").expect("write");
+        let buf = buffer(&[
+            "See `src/main.rs:12:3`. Or src/missing.rs",
+            "Cited in 【F:src/main.rs†L7-L9】 above",
+        ]);
+
+        assert_eq!(
+            file_reference_at(&buf, 8, 10, cwd.path()),
+            Some(FileReference {
+                path: cwd.path().join("src/main.rs"),
+                line: Some(12),
+            })
+        );
+        assert_eq!(file_reference_at(&buf, 30, 10, cwd.path()), None);
+        assert_eq!(file_reference_at(&buf, 0, 10, cwd.path()), None);
+        assert_eq!(
+            file_reference_at(&buf, 12, 11, cwd.path()),
+            Some(FileReference {
+                path: cwd.path().join("src/main.rs"),
+                line: Some(7),
+            })
+        );
+    }
+
+    #[test]
+    fn finds_numbered_items_of_selection_lists() {
+        let buf = buffer(&[
+            "▌ Allow command?",
+            "▌ > 1. Approve and run now",
+            "▌   2. Always approve this session",
+            "1. a markdown list item",
+        ]);
+        assert_eq!(list_item_at(&buf, 10), None);
+        assert_eq!(list_item_at(&buf, 11), Some(1));
+        assert_eq!(list_item_at(&buf, 12), Some(2));
+        assert_eq!(list_item_at(&buf, 13), None);
+        assert_eq!(list_item_at(&buf, 14), None);
+    }
+}
@@ -0,0 +1,161 @@
+//! Desktop notifications, posted while the terminal is unfocused.
+//!
+//! Terminals that understand OSC 9 show the notification themselves.
+//! Elsewhere it goes through the platform's notifier: `terminal-notifier` or
+//! `osascript` on macOS, `notify-send` on Linux and the BSDs, and a toast
+//! raised from PowerShell on Windows.
+
+use std::io::stdout;
+use std::process::Command;
+use std::process::Stdio;
+
+use codex_core::config_types::NotificationMethod;
+use crossterm::execute;
+
+use crate::tui::PostNotification;
+
+const TITLE: &str = "Codex";
+
+/// The AppUserModelID toasts are raised under on Windows; unregistered ids
+/// are silently dropped, so borrow PowerShell's.
+const WINDOWS_APP_ID: &str =
+    r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// Posts `message` using `method`.
+pub(crate) fn post(method: NotificationMethod, message: &str) {
+    let native = match method {
+        NotificationMethod::Osc9 => false,
+        NotificationMethod::Native => true,
+        NotificationMethod::Auto => !terminal_shows_osc9(),
+    };
+    // Without a notifier to run, OSC 9 is the best there is.
+    if !native || !post_native(message) {
+        let _ = execute!(stdout(), PostNotification(message.to_string()));
+    }
+}
+
+/// Whether the terminal is one known to turn OSC 9 into a notification.
+fn terminal_shows_osc9() -> bool {
+    let term_program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+    let term = std::env::var("TERM").unwrap_or_default();
+    matches!(term_program.as_str(), "iTerm.app" | "WezTerm" | "ghostty")
+        || term.contains("kitty")
+        || term.contains("ghostty")
+}
+
+/// Runs the platform's notifier without waiting for it. Returns false when
+/// there is none.
+fn post_native(message: &str) -> bool {
+    let Some(argv) = native_command(message, |program| which::which(program).is_ok()) else {
+        return false;
+    };
+    let spawned = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(_) => true,
+        Err(err) => {
+            tracing::warn!("failed to run notifier {}: {err}", argv[0]);
+            false
+        }
+    }
+}
+
+/// The notifier command line for this platform, using the programs for which
+/// `installed` returns true.
+fn native_command(message: &str, installed: impl Fn(&str) -> bool) -> Option<Vec<String>> {
+    let argv = if cfg!(target_os = "macos") {
+        if installed("terminal-notifier") {
+            vec![
+                "terminal-notifier".to_string(),
+                "-title".to_string(),
+                TITLE.to_string(),
+                "-message".to_string(),
+                message.to_string(),
+            ]
+        } else {
+            vec![
+                "osascript".to_string(),
+                "-e".to_string(),
+                format!(
+                    "display notification {} with title {}",
+                    applescript_string(message),
+                    applescript_string(TITLE)
+                ),
+            ]
+        }
+    } else if cfg!(windows) {
+        vec![
+            "powershell".to_string(),
+            "-NoProfile".to_string(),
+            "-NonInteractive".to_string(),
+            "-Command".to_string(),
+            windows_toast_script(message),
+        ]
+    } else {
+        vec![
+            "notify-send".to_string(),
+            "--".to_string(),
+            TITLE.to_string(),
+            message.to_string(),
+        ]
+    };
+    installed(&argv[0]).then_some(argv)
+}
+
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn powershell_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn windows_toast_script(message: &str) -> String {
+    [
+        "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null".to_string(),
+        "$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)".to_string(),
+        "$text = $template.GetElementsByTagName('text')".to_string(),
+        format!(
+            "$text.Item(0).AppendChild($template.CreateTextNode({})) > $null",
+            powershell_string(TITLE)
+        ),
+        format!(
+            "$text.Item(1).AppendChild($template.CreateTextNode({})) > $null",
+            powershell_string(message)
+        ),
+        format!(
+            "[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier({}).Show([Windows.UI.Notifications.ToastNotification]::new($template))",
+            powershell_string(WINDOWS_APP_ID)
+        ),
+    ]
+    .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn messages_are_quoted_for_the_notifier_scripts() {
+        assert_eq!(
+            applescript_string(r#"run "make" in C:\src"#),
+            r#""run \"make\" in C:\\src""#
+        );
+        assert_eq!(powershell_string("it's done"), "'it''s done'");
+    }
+
+    #[test]
+    fn a_missing_notifier_yields_no_command() {
+        assert_eq!(native_command("done", |_| false), None);
+
+        let argv = native_command("done", |_| true).expect("notifier");
+        if cfg!(target_os = "linux") {
+            assert_eq!(argv, vec!["notify-send", "--", "Codex", "done"]);
+        }
+    }
+}
@@ -2,13 +2,21 @@ use std::io::Result;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::diff_viewer::DiffFile;
+use crate::diff_viewer::DiffLayout;
 use crate::history_cell::HistoryCell;
+use crate::keymap;
+use crate::keymap::KeyAction;
 use crate::render::line_utils::push_owned_lines;
+use crate::status::StatusDashboard;
 use crate::tui;
 use crate::tui::TuiEvent;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use crossterm::event::MouseEvent;
+use crossterm::event::MouseEventKind;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::style::Color;
@@ -26,6 +34,8 @@ use ratatui::widgets::WidgetRef;
 pub(crate) enum Overlay {
     Transcript(TranscriptOverlay),
     Static(StaticOverlay),
+    Diff(DiffOverlay),
+    Status(Box<StatusOverlay>),
 }
 
 impl Overlay {
@@ -37,10 +47,20 @@ impl Overlay {
         Self::Static(StaticOverlay::with_title(lines, title))
     }
 
+    pub(crate) fn new_diff(files: Vec<DiffFile>) -> Self {
+        Self::Diff(DiffOverlay::new(files))
+    }
+
+    pub(crate) fn new_status(dashboard: StatusDashboard) -> Self {
+        Self::Status(Box::new(StatusOverlay::new(dashboard)))
+    }
+
     pub(crate) fn handle_event(&mut self, tui: &mut tui::Tui, event: TuiEvent) -> Result<()> {
         match self {
             Overlay::Transcript(o) => o.handle_event(tui, event),
             Overlay::Static(o) => o.handle_event(tui, event),
+            Overlay::Diff(o) => o.handle_event(tui, event),
+            Overlay::Status(o) => o.handle_event(tui, event),
         }
     }
 
@@ -48,6 +68,8 @@ impl Overlay {
         match self {
             Overlay::Transcript(o) => o.is_done(),
             Overlay::Static(o) => o.is_done(),
+            Overlay::Diff(o) => o.is_done(),
+            Overlay::Status(o) => o.is_done(),
         }
     }
 }
@@ -76,6 +98,9 @@ fn render_key_hints(area: Rect, buf: &mut Buffer, pairs: &[(&str, &str)]) {
     Paragraph::new(vec![Line::from(spans).dim()]).render_ref(area, buf);
 }
 
+/// Lines scrolled per mouse wheel tick.
+const MOUSE_SCROLL_LINES: usize = 3;
+
 /// Generic widget for rendering a pager view.
 struct PagerView {
     texts: Vec<Text<'static>>,
@@ -85,6 +110,8 @@ struct PagerView {
     last_content_height: Option<usize>,
     /// If set, on next render ensure this chunk is visible.
     pending_scroll_chunk: Option<usize>,
+    /// If set, on next render scroll so this chunk starts at the top.
+    pending_top_chunk: Option<usize>,
 }
 
 impl PagerView {
@@ -96,6 +123,7 @@ impl PagerView {
             wrap_cache: None,
             last_content_height: None,
             pending_scroll_chunk: None,
+            pending_top_chunk: None,
         }
     }
 
@@ -113,6 +141,11 @@ impl PagerView {
         {
             self.ensure_range_visible(range, content_area.height as usize, cache.wrapped.len());
         }
+        if let (Some(idx), Some(cache)) = (self.pending_top_chunk.take(), self.wrap_cache.as_ref())
+            && let Some(range) = cache.chunk_ranges.get(idx)
+        {
+            self.scroll_offset = range.start;
+        }
         // Compute page bounds without holding an immutable borrow on cache while mutating self
         let wrapped_len = self
             .wrap_cache
@@ -190,54 +223,41 @@ impl PagerView {
     }
 
     fn handle_key_event(&mut self, tui: &mut tui::Tui, key_event: KeyEvent) -> Result<()> {
-        match key_event {
-            KeyEvent {
-                code: KeyCode::Up,
-                kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                ..
-            } => {
-                self.scroll_offset = self.scroll_offset.saturating_sub(1);
-            }
-            KeyEvent {
-                code: KeyCode::Down,
-                kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                ..
-            } => {
-                self.scroll_offset = self.scroll_offset.saturating_add(1);
-            }
-            KeyEvent {
-                code: KeyCode::PageUp,
-                kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                ..
-            } => {
-                let area = self.scroll_area(tui.terminal.viewport_area);
-                self.scroll_offset = self.scroll_offset.saturating_sub(area.height as usize);
-            }
-            KeyEvent {
-                code: KeyCode::PageDown | KeyCode::Char(' '),
-                kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                ..
-            } => {
-                let area = self.scroll_area(tui.terminal.viewport_area);
-                self.scroll_offset = self.scroll_offset.saturating_add(area.height as usize);
-            }
-            KeyEvent {
-                code: KeyCode::Home,
-                kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                ..
-            } => {
-                self.scroll_offset = 0;
-            }
-            KeyEvent {
-                code: KeyCode::End,
-                kind: KeyEventKind::Press | KeyEventKind::Repeat,
-                ..
-            } => {
-                self.scroll_offset = usize::MAX;
+        if !matches!(key_event.kind, KeyEventKind::Press | KeyEventKind::Repeat) {
+            return Ok(());
+        }
+        let keymap = keymap::current();
+        if keymap.matches(KeyAction::ScrollUp, &key_event) {
+            self.scroll_offset = self.scroll_offset.saturating_sub(1);
+        } else if keymap.matches(KeyAction::ScrollDown, &key_event) {
+            self.scroll_offset = self.scroll_offset.saturating_add(1);
+        } else if keymap.matches(KeyAction::PageUp, &key_event) {
+            let area = self.scroll_area(tui.terminal.viewport_area);
+            self.scroll_offset = self.scroll_offset.saturating_sub(area.height as usize);
+        } else if keymap.matches(KeyAction::PageDown, &key_event) {
+            let area = self.scroll_area(tui.terminal.viewport_area);
+            self.scroll_offset = self.scroll_offset.saturating_add(area.height as usize);
+        } else if keymap.matches(KeyAction::ScrollTop, &key_event) {
+            self.scroll_offset = 0;
+        } else if keymap.matches(KeyAction::ScrollBottom, &key_event) {
+            self.scroll_offset = usize::MAX;
+        } else {
+            return Ok(());
+        }
+        tui.frame_requester()
+            .schedule_frame_in(Duration::from_millis(16));
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, tui: &mut tui::Tui, mouse_event: MouseEvent) -> Result<()> {
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(MOUSE_SCROLL_LINES);
             }
-            _ => {
-                return Ok(());
+            MouseEventKind::ScrollDown => {
+                self.scroll_offset = self.scroll_offset.saturating_add(MOUSE_SCROLL_LINES);
             }
+            _ => return Ok(()),
         }
         tui.frame_requester()
             .schedule_frame_in(Duration::from_millis(16));
@@ -325,6 +345,12 @@ impl PagerView {
         self.pending_scroll_chunk = Some(chunk_index);
     }
 
+    /// Request that the given text chunk index start at the top of the view on
+    /// next render.
+    fn scroll_chunk_to_top(&mut self, chunk_index: usize) {
+        self.pending_top_chunk = Some(chunk_index);
+    }
+
     fn ensure_range_visible(
         &mut self,
         range: std::ops::Range<usize>,
@@ -453,17 +479,18 @@ impl TranscriptOverlay {
                     ..
                 }
                 | KeyEvent {
-                    code: KeyCode::Char('t'),
+                    code: KeyCode::Char('c'),
                     modifiers: crossterm::event::KeyModifiers::CONTROL,
                     kind: KeyEventKind::Press,
                     ..
+                } => {
+                    self.is_done = true;
+                    Ok(())
                 }
-                | KeyEvent {
-                    code: KeyCode::Char('c'),
-                    modifiers: crossterm::event::KeyModifiers::CONTROL,
+                KeyEvent {
                     kind: KeyEventKind::Press,
                     ..
-                } => {
+                } if keymap::current().matches(KeyAction::Transcript, &key_event) => {
                     self.is_done = true;
                     Ok(())
                 }
@@ -475,6 +502,7 @@ impl TranscriptOverlay {
                 })?;
                 Ok(())
             }
+            TuiEvent::Mouse(mouse_event) => self.view.handle_mouse_event(tui, mouse_event),
             _ => Ok(()),
         }
     }
@@ -539,9 +567,234 @@ impl StaticOverlay {
                 })?;
                 Ok(())
             }
+            TuiEvent::Mouse(mouse_event) => self.view.handle_mouse_event(tui, mouse_event),
+            _ => Ok(()),
+        }
+    }
+    pub(crate) fn is_done(&self) -> bool {
+        self.is_done
+    }
+}
+
+/// Full-screen diff viewer: one chunk per file, laid out unified or side by
+/// side, with unchanged context folded by default.
+pub(crate) struct DiffOverlay {
+    view: PagerView,
+    files: Vec<DiffFile>,
+    layout: DiffLayout,
+    fold_context: bool,
+    current_file: usize,
+    /// Width the files were last laid out for; `None` forces a relayout.
+    rendered_width: Option<u16>,
+    is_done: bool,
+}
+
+impl DiffOverlay {
+    pub(crate) fn new(files: Vec<DiffFile>) -> Self {
+        Self {
+            view: PagerView::new(Vec::new(), "D I F F".to_string(), 0),
+            files,
+            layout: DiffLayout::Unified,
+            fold_context: true,
+            current_file: 0,
+            rendered_width: None,
+            is_done: false,
+        }
+    }
+
+    fn relayout(&mut self, width: u16) {
+        if self.rendered_width == Some(width) {
+            return;
+        }
+        self.rendered_width = Some(width);
+        self.view.texts = if self.files.is_empty() {
+            vec![Text::from(vec![Line::from(
+                "No changes detected.".italic(),
+            )])]
+        } else {
+            self.files
+                .iter()
+                .enumerate()
+                .map(|(idx, file)| {
+                    let mut lines = Vec::new();
+                    if idx > 0 {
+                        lines.push(Line::from(""));
+                    }
+                    lines.extend(file.render(self.layout, width, self.fold_context));
+                    Text::from(lines)
+                })
+                .collect()
+        };
+        self.view.wrap_cache = None;
+    }
+
+    fn select_file(&mut self, idx: usize) {
+        self.current_file = idx;
+        self.view.scroll_chunk_to_top(idx);
+    }
+
+    fn render_hints(&self, area: Rect, buf: &mut Buffer) {
+        let line1 = Rect::new(area.x, area.y, area.width, 1);
+        let line2 = Rect::new(area.x, area.y.saturating_add(1), area.width, 1);
+        render_key_hints(line1, buf, PAGER_KEY_HINTS);
+        let layout = match self.layout {
+            DiffLayout::Unified => "side by side",
+            DiffLayout::SideBySide => "unified",
+        };
+        let fold = if self.fold_context {
+            "show context"
+        } else {
+            "fold context"
+        };
+        let position = format!("file {}/{}", self.current_file + 1, self.files.len());
+        let mut pairs = vec![("q", "quit"), ("s", layout), ("z", fold)];
+        if self.files.len() > 1 {
+            pairs.push(("[/]", position.as_str()));
+        }
+        render_key_hints(line2, buf, &pairs);
+    }
+
+    pub(crate) fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let top_h = area.height.saturating_sub(3);
+        let top = Rect::new(area.x, area.y, area.width, top_h);
+        let bottom = Rect::new(area.x, area.y + top_h, area.width, 3);
+        self.relayout(area.width);
+        self.view.render(top, buf);
+        self.render_hints(bottom, buf);
+    }
+}
+
+impl DiffOverlay {
+    pub(crate) fn handle_event(&mut self, tui: &mut tui::Tui, event: TuiEvent) -> Result<()> {
+        match event {
+            TuiEvent::Key(key_event) => {
+                if key_event.kind != KeyEventKind::Press {
+                    return self.view.handle_key_event(tui, key_event);
+                }
+                let plain = !key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                match key_event.code {
+                    KeyCode::Char('q') if plain => self.is_done = true,
+                    KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+                        self.is_done = true;
+                    }
+                    KeyCode::Char('s') if plain => {
+                        self.layout = match self.layout {
+                            DiffLayout::Unified => DiffLayout::SideBySide,
+                            DiffLayout::SideBySide => DiffLayout::Unified,
+                        };
+                        self.rendered_width = None;
+                        self.view.scroll_chunk_to_top(self.current_file);
+                    }
+                    KeyCode::Char('z') if plain => {
+                        self.fold_context = !self.fold_context;
+                        self.rendered_width = None;
+                        self.view.scroll_chunk_to_top(self.current_file);
+                    }
+                    KeyCode::Char(']' | 'n') if plain && !self.files.is_empty() => {
+                        self.select_file((self.current_file + 1).min(self.files.len() - 1));
+                    }
+                    KeyCode::Char('[' | 'p') if plain => {
+                        self.select_file(self.current_file.saturating_sub(1));
+                    }
+                    _ => return self.view.handle_key_event(tui, key_event),
+                }
+                tui.frame_requester().schedule_frame();
+                Ok(())
+            }
+            TuiEvent::Draw => {
+                tui.draw(u16::MAX, |frame| {
+                    self.render(frame.area(), frame.buffer);
+                })?;
+                Ok(())
+            }
+            TuiEvent::Mouse(mouse_event) => self.view.handle_mouse_event(tui, mouse_event),
+            _ => Ok(()),
+        }
+    }
+    pub(crate) fn is_done(&self) -> bool {
+        self.is_done
+    }
+}
+
+/// The live `/status` dashboard; App replaces its contents as core events
+/// arrive.
+pub(crate) struct StatusOverlay {
+    view: PagerView,
+    dashboard: StatusDashboard,
+    /// Width the dashboard was last laid out for; `None` forces a relayout.
+    rendered_width: Option<u16>,
+    is_done: bool,
+}
+
+impl StatusOverlay {
+    pub(crate) fn new(dashboard: StatusDashboard) -> Self {
+        Self {
+            view: PagerView::new(Vec::new(), "S T A T U S".to_string(), 0),
+            dashboard,
+            rendered_width: None,
+            is_done: false,
+        }
+    }
+
+    /// Shows `dashboard` from the next frame on, keeping the scroll position.
+    pub(crate) fn update(&mut self, dashboard: StatusDashboard) {
+        self.dashboard = dashboard;
+        self.rendered_width = None;
+    }
+
+    fn relayout(&mut self, width: u16) {
+        if self.rendered_width == Some(width) {
+            return;
+        }
+        self.rendered_width = Some(width);
+        self.view.texts = vec![Text::from(self.dashboard.display_lines(width))];
+        self.view.wrap_cache = None;
+    }
+
+    pub(crate) fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let top_h = area.height.saturating_sub(3);
+        let top = Rect::new(area.x, area.y, area.width, top_h);
+        let bottom = Rect::new(area.x, area.y + top_h, area.width, 3);
+        self.relayout(area.width);
+        self.view.render(top, buf);
+        let line1 = Rect::new(bottom.x, bottom.y, bottom.width, 1);
+        let line2 = Rect::new(bottom.x, bottom.y.saturating_add(1), bottom.width, 1);
+        render_key_hints(line1, buf, PAGER_KEY_HINTS);
+        render_key_hints(line2, buf, &[("q", "quit")]);
+    }
+
+    pub(crate) fn handle_event(&mut self, tui: &mut tui::Tui, event: TuiEvent) -> Result<()> {
+        match event {
+            TuiEvent::Key(key_event) => match key_event {
+                KeyEvent {
+                    code: KeyCode::Char('q') | KeyCode::Esc,
+                    kind: KeyEventKind::Press,
+                    ..
+                }
+                | KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    kind: KeyEventKind::Press,
+                    ..
+                } => {
+                    self.is_done = true;
+                    Ok(())
+                }
+                other => self.view.handle_key_event(tui, other),
+            },
+            TuiEvent::Draw => {
+                tui.draw(u16::MAX, |frame| {
+                    self.render(frame.area(), frame.buffer);
+                })?;
+                Ok(())
+            }
+            TuiEvent::Mouse(mouse_event) => self.view.handle_mouse_event(tui, mouse_event),
             _ => Ok(()),
         }
     }
+
     pub(crate) fn is_done(&self) -> bool {
         self.is_done
     }
@@ -661,6 +914,7 @@ mod tests {
             PatchEventType::ApprovalRequest,
             approval_changes,
             &cwd,
+            None,
         ));
         cells.push(approval_cell);
 
@@ -677,6 +931,7 @@ mod tests {
             },
             apply_changes,
             &cwd,
+            None,
         ));
         cells.push(apply_begin_cell);
 
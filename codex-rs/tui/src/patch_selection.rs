@@ -0,0 +1,350 @@
+//! Choosing which parts of a proposed patch to apply.
+//!
+//! A patch awaiting approval is split into files and, for edited files, into
+//! the hunks of their diff. Any of them can be left out; what remains is
+//! written back in `apply_patch` format, together with a note for the model
+//! describing what was left out.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::FileChange;
+
+use crate::diff_render::display_path_for;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PatchSelection {
+    files: Vec<FileSelection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FileSelection {
+    path: PathBuf,
+    label: String,
+    kind: FileKind,
+    /// Added and deleted files, and moves without edits, have one hunk
+    /// standing for the whole file.
+    hunks: Vec<HunkSelection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FileKind {
+    Add { content: String },
+    Delete,
+    Update { move_path: Option<PathBuf> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HunkSelection {
+    header: String,
+    /// Diff lines with their ` `, `-` or `+` prefix.
+    lines: Vec<String>,
+    selected: bool,
+}
+
+/// A row of the selection list: a file, or one of its hunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SelectionRow {
+    File(usize),
+    Hunk(usize, usize),
+}
+
+impl PatchSelection {
+    /// Everything in `changes` selected, files sorted by path.
+    pub(crate) fn from_changes(changes: &HashMap<PathBuf, FileChange>, cwd: &Path) -> Self {
+        let mut paths: Vec<&PathBuf> = changes.keys().collect();
+        paths.sort();
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let label = match path.strip_prefix(cwd) {
+                    Ok(relative) => relative.display().to_string(),
+                    Err(_) => display_path_for(path, cwd),
+                };
+                let whole_file = |header: &str| {
+                    vec![HunkSelection {
+                        header: header.to_string(),
+                        lines: Vec::new(),
+                        selected: true,
+                    }]
+                };
+                let (kind, hunks) = match &changes[path] {
+                    FileChange::Add { content } => (
+                        FileKind::Add {
+                            content: content.clone(),
+                        },
+                        whole_file("new file"),
+                    ),
+                    FileChange::Delete { .. } => (FileKind::Delete, whole_file("deleted")),
+                    FileChange::Update {
+                        unified_diff,
+                        move_path,
+                    } => {
+                        let hunks = split_hunks(unified_diff);
+                        let hunks = if hunks.is_empty() {
+                            whole_file("moved")
+                        } else {
+                            hunks
+                        };
+                        (
+                            FileKind::Update {
+                                move_path: move_path.clone(),
+                            },
+                            hunks,
+                        )
+                    }
+                };
+                FileSelection {
+                    path: path.clone(),
+                    label,
+                    kind,
+                    hunks,
+                }
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Files, each followed by its hunks when it has more than one.
+    pub(crate) fn rows(&self) -> Vec<SelectionRow> {
+        let mut rows = Vec::new();
+        for (file_idx, file) in self.files.iter().enumerate() {
+            rows.push(SelectionRow::File(file_idx));
+            if file.hunks.len() > 1 {
+                rows.extend((0..file.hunks.len()).map(|idx| SelectionRow::Hunk(file_idx, idx)));
+            }
+        }
+        rows
+    }
+
+    /// The text of `row` and whether it is selected: `Some(true)` or
+    /// `Some(false)`, or `None` for a file only partly selected.
+    pub(crate) fn describe(&self, row: SelectionRow) -> (String, Option<bool>) {
+        match row {
+            SelectionRow::File(file_idx) => {
+                let file = &self.files[file_idx];
+                let selected = file.hunks.iter().filter(|hunk| hunk.selected).count();
+                let state = match selected {
+                    0 => Some(false),
+                    n if n == file.hunks.len() => Some(true),
+                    _ => None,
+                };
+                let text = match file.hunks.as_slice() {
+                    [hunk] if hunk.lines.is_empty() => format!("{} ({})", file.label, hunk.header),
+                    _ => file.label.clone(),
+                };
+                (text, state)
+            }
+            SelectionRow::Hunk(file_idx, hunk_idx) => {
+                let hunk = &self.files[file_idx].hunks[hunk_idx];
+                let first_change = hunk
+                    .lines
+                    .iter()
+                    .find(|line| !line.starts_with(' '))
+                    .map(|line| line.trim())
+                    .unwrap_or_default();
+                (
+                    format!("{} {first_change}", hunk.header),
+                    Some(hunk.selected),
+                )
+            }
+        }
+    }
+
+    /// Selects or leaves out `row`; toggling a file toggles all its hunks.
+    pub(crate) fn toggle(&mut self, row: SelectionRow) {
+        match row {
+            SelectionRow::File(file_idx) => {
+                let Some(file) = self.files.get_mut(file_idx) else {
+                    return;
+                };
+                let select = !file.hunks.iter().all(|hunk| hunk.selected);
+                for hunk in &mut file.hunks {
+                    hunk.selected = select;
+                }
+            }
+            SelectionRow::Hunk(file_idx, hunk_idx) => {
+                if let Some(hunk) = self
+                    .files
+                    .get_mut(file_idx)
+                    .and_then(|file| file.hunks.get_mut(hunk_idx))
+                {
+                    hunk.selected = !hunk.selected;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn all_selected(&self) -> bool {
+        self.files
+            .iter()
+            .all(|file| file.hunks.iter().all(|hunk| hunk.selected))
+    }
+
+    /// The selected changes in `apply_patch` format, or `None` when nothing
+    /// is selected.
+    pub(crate) fn to_patch(&self) -> Option<String> {
+        let mut patch = String::from("*** Begin Patch\n");
+        let mut any = false;
+        for file in &self.files {
+            if !file.hunks.iter().any(|hunk| hunk.selected) {
+                continue;
+            }
+            any = true;
+            let path = file.path.display();
+            match &file.kind {
+                FileKind::Add { content } => {
+                    patch.push_str(&format!("*** Add File: {path}\n"));
+                    for line in content.lines() {
+                        patch.push_str(&format!("+{line}\n"));
+                    }
+                }
+                FileKind::Delete => patch.push_str(&format!("*** Delete File: {path}\n")),
+                FileKind::Update { move_path } => {
+                    patch.push_str(&format!("*** Update File: {path}\n"));
+                    if let Some(move_path) = move_path {
+                        patch.push_str(&format!("*** Move to: {}\n", move_path.display()));
+                    }
+                    for hunk in file.hunks.iter().filter(|hunk| hunk.selected) {
+                        if hunk.lines.is_empty() {
+                            continue;
+                        }
+                        patch.push_str("@@\n");
+                        for line in &hunk.lines {
+                            patch.push_str(line);
+                            patch.push('\n');
+                        }
+                    }
+                }
+            }
+        }
+        patch.push_str("*** End Patch\n");
+        any.then_some(patch)
+    }
+
+    /// What was left out, for the model, or `None` when everything is
+    /// selected.
+    pub(crate) fn note(&self) -> Option<String> {
+        let mut left_out = Vec::new();
+        for file in &self.files {
+            let rejected: Vec<&HunkSelection> =
+                file.hunks.iter().filter(|hunk| !hunk.selected).collect();
+            if rejected.is_empty() {
+                continue;
+            }
+            if rejected.len() == file.hunks.len() {
+                left_out.push(format!("- all changes to {}", file.label));
+            } else {
+                for hunk in rejected {
+                    left_out.push(format!("- {} {}", file.label, hunk.header));
+                }
+            }
+        }
+        if left_out.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "These changes were rejected and not applied:\n{}",
+            left_out.join("\n")
+        ))
+    }
+}
+
+/// The hunks of a unified diff, each with its `@@` header.
+fn split_hunks(unified_diff: &str) -> Vec<HunkSelection> {
+    let mut hunks: Vec<HunkSelection> = Vec::new();
+    for line in unified_diff.lines() {
+        if line.starts_with("@@") {
+            hunks.push(HunkSelection {
+                header: line.to_string(),
+                lines: Vec::new(),
+                selected: true,
+            });
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        match line.chars().next() {
+            Some(' ' | '-' | '+') => hunk.lines.push(line.to_string()),
+            // Some tools strip the space of empty context lines.
+            None => hunk.lines.push(" ".to_string()),
+            // "\ No newline at end of file"
+            _ => {}
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn selection() -> PatchSelection {
+        let changes = HashMap::from([
+            (
+                PathBuf::from("/repo/src/lib.rs"),
+                FileChange::Update {
+                    unified_diff: "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n fn a() {}\n-fn b() {}\n+fn b() -> u8 { 1 }\n@@ -10,1 +10,2 @@\n fn z() {}\n+fn y() {}\n".to_string(),
+                    move_path: None,
+                },
+            ),
+            (
+                PathBuf::from("/repo/notes.txt"),
+                FileChange::Add {
+                    content: "hello\n".to_string(),
+                },
+            ),
+        ]);
+        PatchSelection::from_changes(&changes, Path::new("/repo"))
+    }
+
+    #[test]
+    fn files_and_hunks_are_listed() {
+        let selection = selection();
+        let rows: Vec<(String, Option<bool>)> = selection
+            .rows()
+            .into_iter()
+            .map(|row| selection.describe(row))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("notes.txt (new file)".to_string(), Some(true)),
+                ("src/lib.rs".to_string(), Some(true)),
+                ("@@ -1,2 +1,2 @@ -fn b() {}".to_string(), Some(true)),
+                ("@@ -10,1 +10,2 @@ +fn y() {}".to_string(), Some(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn left_out_hunks_are_dropped_from_the_patch() {
+        let mut selection = selection();
+        selection.toggle(SelectionRow::File(0));
+        selection.toggle(SelectionRow::Hunk(1, 0));
+        assert!(!selection.all_selected());
+        assert_eq!(selection.describe(SelectionRow::File(1)).1, None);
+
+        assert_eq!(
+            selection.to_patch(),
+            Some(
+                "*** Begin Patch\n*** Update File: /repo/src/lib.rs\n@@\n fn z() {}\n+fn y() {}\n*** End Patch\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            selection.note(),
+            Some(
+                "These changes were rejected and not applied:\n- all changes to notes.txt\n- src/lib.rs @@ -1,2 +1,2 @@"
+                    .to_string()
+            )
+        );
+
+        selection.toggle(SelectionRow::File(1));
+        selection.toggle(SelectionRow::File(1));
+        assert_eq!(selection.to_patch(), None);
+    }
+}
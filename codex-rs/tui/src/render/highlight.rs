@@ -1,50 +1,57 @@
+use crate::theme;
 use codex_core::bash::try_parse_bash;
-use ratatui::style::Stylize;
+use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
+use std::ops::Range;
+use std::path::Path;
 
 /// Convert the full bash script into per-line styled content by first
-/// computing operator-dimmed spans across the entire script, then splitting
-/// by newlines and dimming heredoc body lines. Performs a single parse and
+/// computing operator spans across the entire script, then splitting by
+/// newlines and styling heredoc body lines. Both styles come from the theme. Performs a single parse and
 /// reuses it for both highlighting and heredoc detection.
 pub(crate) fn highlight_bash_to_lines(script: &str) -> Vec<Line<'static>> {
     // Parse once; use the tree for both highlighting and heredoc body detection.
     let spans: Vec<Span<'static>> = if let Some(tree) = try_parse_bash(script) {
         // Single walk: collect operator ranges and heredoc rows.
+        let theme = theme::current();
         let root = tree.root_node();
         let mut cursor = root.walk();
         let mut stack = vec![root];
-        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let mut ranges: Vec<(usize, usize, Style)> = Vec::new();
         while let Some(node) = stack.pop() {
             if !node.is_named() && !node.is_extra() {
                 let kind = node.kind();
                 let is_quote = matches!(kind, "\"" | "'" | "`");
                 let is_whitespace = kind.trim().is_empty();
                 if !is_quote && !is_whitespace {
-                    ranges.push((node.start_byte(), node.end_byte()));
+                    ranges.push((node.start_byte(), node.end_byte(), theme.syntax_operator));
                 }
             } else if node.kind() == "heredoc_body" {
-                ranges.push((node.start_byte(), node.end_byte()));
+                ranges.push((node.start_byte(), node.end_byte(), theme.syntax_heredoc));
             }
             for child in node.children(&mut cursor) {
                 stack.push(child);
             }
         }
         if ranges.is_empty() {
-            ranges.push((script.len(), script.len()));
+            ranges.push((script.len(), script.len(), Style::default()));
         }
-        ranges.sort_by_key(|(st, _)| *st);
+        ranges.sort_by_key(|(st, _, _)| *st);
         let mut spans: Vec<Span<'static>> = Vec::new();
         let mut i = 0usize;
-        for (start, end) in ranges.into_iter() {
-            let dim_start = start.max(i);
-            let dim_end = end;
-            if dim_start < dim_end {
-                if dim_start > i {
-                    spans.push(script[i..dim_start].to_string().into());
+        for (start, end, style) in ranges.into_iter() {
+            let styled_start = start.max(i);
+            let styled_end = end;
+            if styled_start < styled_end {
+                if styled_start > i {
+                    spans.push(script[i..styled_start].to_string().into());
                 }
-                spans.push(script[dim_start..dim_end].to_string().dim());
-                i = dim_end;
+                spans.push(Span::styled(
+                    script[styled_start..styled_end].to_string(),
+                    style,
+                ));
+                i = styled_end;
             }
         }
         if i < script.len() {
@@ -54,7 +61,11 @@ pub(crate) fn highlight_bash_to_lines(script: &str) -> Vec<Line<'static>> {
     } else {
         vec![script.to_string().into()]
     };
-    // Split spans into lines preserving style boundaries and highlights across newlines.
+    split_into_lines(spans)
+}
+
+/// Splits `spans` at newlines, keeping each piece's style.
+fn split_into_lines(spans: Vec<Span<'static>>) -> Vec<Line<'static>> {
     let mut lines: Vec<Line<'static>> = vec![Line::from("")];
     for sp in spans {
         let style = sp.style;
@@ -78,6 +89,504 @@ pub(crate) fn highlight_bash_to_lines(script: &str) -> Vec<Line<'static>> {
     lines
 }
 
+/// Lexical rules for highlighting one language: enough to pick out keywords,
+/// strings, comments and numbers, not to parse.
+pub(crate) struct Language {
+    /// Names used after a code fence.
+    names: &'static [&'static str],
+    extensions: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Quotes of strings that end at the line's end.
+    quotes: &'static [char],
+    /// Delimiters of strings that may span lines.
+    multiline_strings: &'static [&'static str],
+    /// `'` starts a character literal only when it closes right after one
+    /// character; otherwise it is a lifetime or label.
+    char_literals: bool,
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "nullptr",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "true",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        names: &["rust", "rs"],
+        extensions: &["rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &[],
+        multiline_strings: &["\""],
+        char_literals: true,
+    },
+    Language {
+        names: &["python", "py", "python3"],
+        extensions: &["py", "pyi"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+            "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return",
+            "True", "try", "while", "with", "yield",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        multiline_strings: &["\"\"\"", "'''"],
+        char_literals: false,
+    },
+    Language {
+        names: &[
+            "javascript",
+            "js",
+            "jsx",
+            "typescript",
+            "ts",
+            "tsx",
+            "mjs",
+            "cjs",
+        ],
+        extensions: &["js", "jsx", "ts", "tsx", "mjs", "cjs"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "return",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        multiline_strings: &["`"],
+        char_literals: false,
+    },
+    Language {
+        names: &["go", "golang"],
+        extensions: &["go"],
+        keywords: &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "fallthrough",
+            "false",
+            "for",
+            "func",
+            "go",
+            "goto",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        multiline_strings: &["`"],
+        char_literals: false,
+    },
+    Language {
+        names: &["c", "h", "cpp", "c++", "cc", "cxx", "hpp"],
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hpp", "hh"],
+        keywords: C_KEYWORDS,
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        multiline_strings: &[],
+        char_literals: false,
+    },
+    Language {
+        names: &["java"],
+        extensions: &["java"],
+        keywords: &[
+            "abstract",
+            "boolean",
+            "break",
+            "case",
+            "catch",
+            "char",
+            "class",
+            "continue",
+            "default",
+            "do",
+            "double",
+            "else",
+            "enum",
+            "extends",
+            "false",
+            "final",
+            "finally",
+            "float",
+            "for",
+            "if",
+            "implements",
+            "import",
+            "instanceof",
+            "int",
+            "interface",
+            "long",
+            "new",
+            "null",
+            "package",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "throws",
+            "true",
+            "try",
+            "void",
+            "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\''],
+        multiline_strings: &[],
+        char_literals: false,
+    },
+    Language {
+        names: &["bash", "sh", "shell", "zsh"],
+        extensions: &["sh", "bash", "zsh"],
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "then", "until", "while",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        multiline_strings: &[],
+        char_literals: false,
+    },
+    Language {
+        names: &["toml"],
+        extensions: &["toml"],
+        keywords: &["true", "false"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        multiline_strings: &["\"\"\"", "'''"],
+        char_literals: false,
+    },
+    Language {
+        names: &["yaml", "yml"],
+        extensions: &["yaml", "yml"],
+        keywords: &["true", "false", "null"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        multiline_strings: &[],
+        char_literals: false,
+    },
+    Language {
+        names: &["json", "jsonc"],
+        extensions: &["json"],
+        keywords: &["true", "false", "null"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        multiline_strings: &[],
+        char_literals: false,
+    },
+];
+
+/// The language named by a code fence's info string, such as `rust` or
+/// `python title="x"`.
+pub(crate) fn language_for_fence(info: &str) -> Option<&'static Language> {
+    let name = info
+        .split([',', ' ', '{'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|language| language.names.contains(&name.as_str()))
+}
+
+/// The language of the file at `path`, from its extension.
+pub(crate) fn language_for_path(path: &Path) -> Option<&'static Language> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&extension.as_str()))
+}
+
+/// Highlights `code` as `language`, one line per line of code. Colors come
+/// from the theme.
+pub(crate) fn highlight_code_to_lines(code: &str, language: &Language) -> Vec<Line<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut i = 0usize;
+    for (range, style) in tokens(code, language) {
+        if range.start > i {
+            spans.push(code[i..range.start].to_string().into());
+        }
+        spans.push(Span::styled(code[range.clone()].to_string(), style));
+        i = range.end;
+    }
+    if i < code.len() {
+        spans.push(code[i..].to_string().into());
+    }
+    split_into_lines(spans)
+}
+
+/// Restyles the tokens of one line of `language`, already split into styled
+/// `spans`, on top of the spans' own styles.
+pub(crate) fn highlight_spans(
+    spans: Vec<Span<'static>>,
+    language: &Language,
+) -> Vec<Span<'static>> {
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    let tokens = tokens(&text, language);
+    if tokens.is_empty() {
+        return spans;
+    }
+    let mut out: Vec<Span<'static>> = Vec::new();
+    let mut offset = 0usize;
+    for span in spans {
+        let end = offset + span.content.len();
+        let mut pos = offset;
+        for (range, style) in tokens
+            .iter()
+            .filter(|(range, _)| range.start < end && range.end > offset)
+        {
+            let start = range.start.max(pos);
+            let stop = range.end.min(end);
+            if start > pos {
+                out.push(Span::styled(text[pos..start].to_string(), span.style));
+            }
+            out.push(Span::styled(
+                text[start..stop].to_string(),
+                span.style.patch(*style),
+            ));
+            pos = stop;
+        }
+        if pos < end {
+            out.push(Span::styled(text[pos..end].to_string(), span.style));
+        }
+        offset = end;
+    }
+    out
+}
+
+/// The byte ranges of `code`'s keywords, strings, comments and numbers, in
+/// order, with their styles.
+fn tokens(code: &str, language: &Language) -> Vec<(Range<usize>, Style)> {
+    let theme = theme::current();
+    let mut out = Vec::new();
+    let mut i = 0usize;
+    while let Some(c) = code[i..].chars().next() {
+        let rest = &code[i..];
+        let (len, style) = if language
+            .line_comments
+            .iter()
+            .any(|marker| rest.starts_with(marker))
+        {
+            (
+                rest.find('\n').unwrap_or(rest.len()),
+                Some(theme.syntax_comment),
+            )
+        } else if let Some((open, close)) = language
+            .block_comment
+            .filter(|(open, _)| rest.starts_with(open))
+        {
+            let len = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |end| open.len() + end + close.len());
+            (len, Some(theme.syntax_comment))
+        } else if let Some(delimiter) = language
+            .multiline_strings
+            .iter()
+            .find(|delimiter| rest.starts_with(**delimiter))
+        {
+            (string_len(rest, delimiter, true), Some(theme.syntax_string))
+        } else if language.quotes.contains(&c) {
+            (
+                string_len(rest, &rest[..c.len_utf8()], false),
+                Some(theme.syntax_string),
+            )
+        } else if c == '\''
+            && language.char_literals
+            && let Some(len) = char_literal_len(rest)
+        {
+            (len, Some(theme.syntax_string))
+        } else if c.is_ascii_digit() {
+            (number_len(rest), Some(theme.syntax_number))
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let keyword = language.keywords.contains(&&rest[..len]);
+            (len, keyword.then_some(theme.syntax_keyword))
+        } else {
+            (c.len_utf8(), None)
+        };
+        if let Some(style) = style {
+            out.push((i..i + len, style));
+        }
+        i += len;
+    }
+    out
+}
+
+/// The length of the string opening `rest`, through its closing
+/// `delimiter`. Unless `multiline`, an unclosed string ends with its line.
+fn string_len(rest: &str, delimiter: &str, multiline: bool) -> usize {
+    let mut chars = rest.char_indices().skip(delimiter.chars().count());
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == '\n' && !multiline {
+            return i;
+        } else if rest[i..].starts_with(delimiter) {
+            return i + delimiter.len();
+        }
+    }
+    rest.len()
+}
+
+/// The length of the character literal opening `rest`, if it is one.
+fn char_literal_len(rest: &str) -> Option<usize> {
+    let body = &rest[1..];
+    if body.starts_with('\\') {
+        // Escapes such as '\n' or '\u{1F600}'.
+        return body
+            .char_indices()
+            .skip(2)
+            .take(10)
+            .find(|(_, c)| *c == '\'')
+            .map(|(i, _)| i + 2);
+    }
+    let c = body.chars().next()?;
+    body[c.len_utf8()..]
+        .starts_with('\'')
+        .then_some(c.len_utf8() + 2)
+}
+
+/// The length of the number opening `rest`, including suffixes such as
+/// `u32` and a fraction, but not a method call such as `.max(..)`.
+fn number_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut len = 0;
+    while len < bytes.len() {
+        let b = bytes[len];
+        let fraction = b == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit);
+        if b.is_ascii_alphanumeric() || b == b'_' || fraction {
+            len += 1;
+        } else {
+            break;
+        }
+    }
+    len
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,4 +651,75 @@ mod tests {
         assert!(!dimmed.contains(&"\"".to_string()));
         assert!(!dimmed.contains(&"'".to_string()));
     }
+
+    fn styled_tokens(code: &str, language: &Language) -> Vec<(String, Style)> {
+        tokens(code, language)
+            .into_iter()
+            .map(|(range, style)| (code[range].to_string(), style))
+            .collect()
+    }
+
+    #[test]
+    fn picks_out_rust_tokens() {
+        let theme = theme::current();
+        let rust = language_for_fence("rust,ignore").expect("rust");
+        let code =
+            "fn f<'a>(s: &'a str) -> char { let c = '\\n'; 1.max(0x1f); \"a\\\"b\" } // done";
+
+        assert_eq!(
+            styled_tokens(code, rust),
+            vec![
+                ("fn".to_string(), theme.syntax_keyword),
+                ("let".to_string(), theme.syntax_keyword),
+                ("'\\n'".to_string(), theme.syntax_string),
+                ("1".to_string(), theme.syntax_number),
+                ("0x1f".to_string(), theme.syntax_number),
+                ("\"a\\\"b\"".to_string(), theme.syntax_string),
+                ("// done".to_string(), theme.syntax_comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_span_lines_only_where_the_language_allows() {
+        let theme = theme::current();
+        let python = language_for_path(Path::new("tool.py")).expect("python");
+        let lines = highlight_code_to_lines("x = \"\"\"a\nb\"\"\"\ny = 'c\nz", python);
+
+        let text: Vec<String> = lines
+            .iter()
+            .map(|l| l.spans.iter().map(|sp| sp.content.clone()).collect())
+            .collect();
+        assert_eq!(text, vec!["x = \"\"\"a", "b\"\"\"", "y = 'c", "z"]);
+        let strings: Vec<String> = lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .filter(|sp| sp.style == theme.syntax_string)
+            .map(|sp| sp.content.clone().into_owned())
+            .collect();
+        assert_eq!(strings, vec!["\"\"\"a", "b\"\"\"", "'c"]);
+    }
+
+    #[test]
+    fn highlighting_keeps_the_base_style_of_plain_text() {
+        let theme = theme::current();
+        let go = language_for_fence("go").expect("go");
+        let base = Style::default().add_modifier(Modifier::REVERSED);
+        let spans = highlight_spans(
+            vec![Span::from("return "), Span::styled("nil // x", base)],
+            go,
+        );
+
+        assert_eq!(
+            spans,
+            vec![
+                Span::styled("return", theme.syntax_keyword),
+                Span::from(" "),
+                Span::styled("nil", base.patch(theme.syntax_keyword)),
+                Span::styled(" ", base),
+                Span::styled("// x", base.patch(theme.syntax_comment)),
+            ]
+        );
+        assert!(language_for_fence("text").is_none());
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
@@ -9,10 +10,12 @@ use codex_core::ConversationItem;
 use codex_core::ConversationsPage;
 use codex_core::Cursor;
 use codex_core::RolloutRecorder;
+use codex_core::session_lock_status;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
@@ -23,25 +26,49 @@ use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+use crate::session_locked::ObservedMessage;
+use crate::session_locked::parse_message;
+use crate::session_locked::render_transcript;
 use crate::text_formatting::truncate_text;
 use crate::tui::FrameRequester;
 use crate::tui::Tui;
 use crate::tui::TuiEvent;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::AnnotationAddedEvent;
+use codex_protocol::protocol::AnnotationTarget;
+use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
 use codex_protocol::protocol::USER_MESSAGE_BEGIN;
 
 const PAGE_SIZE: usize = 25;
 const LOAD_NEAR_THRESHOLD: usize = 5;
+/// Messages of the selected session kept for the preview pane.
+const PREVIEW_MESSAGES: usize = 20;
+/// Narrower terminals show the list without the preview pane.
+const MIN_PREVIEW_WIDTH: u16 = 100;
 
 #[derive(Debug, Clone)]
 pub enum ResumeSelection {
     StartFresh,
     Resume(PathBuf),
+    /// Start a new session with the history of the rollout, leaving the
+    /// rollout itself untouched.
+    Fork(PathBuf),
     Exit,
 }
 
+/// Where the picker was opened from, which decides what Esc does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerOrigin {
+    /// `codex resume`: Esc starts a new session.
+    Startup,
+    /// `/resume`: Esc returns to the current session.
+    Session,
+}
+
 #[derive(Clone)]
 struct PageLoadRequest {
     codex_home: PathBuf,
@@ -51,6 +78,7 @@ struct PageLoadRequest {
 }
 
 type PageLoader = Arc<dyn Fn(PageLoadRequest) + Send + Sync>;
+type PreviewLoader = Arc<dyn Fn(PathBuf) + Send + Sync>;
 
 enum BackgroundEvent {
     PageLoaded {
@@ -58,15 +86,35 @@ enum BackgroundEvent {
         search_token: Option<usize>,
         page: std::io::Result<ConversationsPage>,
     },
+    PreviewLoaded {
+        path: PathBuf,
+        messages: Vec<ObservedMessage>,
+    },
 }
 
 /// Interactive session picker that lists recorded rollout files with simple
-/// search and pagination. Shows the first user input as the preview, relative
-/// time (e.g., "5 seconds ago"), and the absolute path.
-pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<ResumeSelection> {
+/// search and pagination. Shows the first user input and relative time (e.g.,
+/// "5 seconds ago") of each session, and the last messages of the selected
+/// one. Sessions can also be forked, exported to Markdown in `export_dir`, or
+/// deleted from here.
+pub async fn run_resume_picker(
+    tui: &mut Tui,
+    codex_home: &Path,
+    export_dir: &Path,
+    origin: PickerOrigin,
+) -> Result<ResumeSelection> {
     let alt = AltScreenGuard::enter(tui);
     let (bg_tx, bg_rx) = mpsc::unbounded_channel();
 
+    let preview_tx = bg_tx.clone();
+    let preview_loader: PreviewLoader = Arc::new(move |path: PathBuf| {
+        let tx = preview_tx.clone();
+        tokio::spawn(async move {
+            let messages = load_preview(&path).await.unwrap_or_default();
+            let _ = tx.send(BackgroundEvent::PreviewLoaded { path, messages });
+        });
+    });
+
     let loader_tx = bg_tx.clone();
     let page_loader: PageLoader = Arc::new(move |request: PageLoadRequest| {
         let tx = loader_tx.clone();
@@ -90,6 +138,9 @@ pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<Resum
         alt.tui.frame_requester(),
         page_loader,
     );
+    state.preview_loader = Some(preview_loader);
+    state.export_dir = export_dir.to_path_buf();
+    state.origin = origin;
     state.load_initial_page().await?;
     state.request_frame();
 
@@ -110,10 +161,11 @@ pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<Resum
                     }
                     TuiEvent::Draw => {
                         if let Ok(size) = alt.tui.terminal.size() {
-                            let list_height = size.height.saturating_sub(3) as usize;
+                            let list_height = size.height.saturating_sub(4) as usize;
                             state.update_view_rows(list_height);
                             state.ensure_minimum_rows_for_view(list_height);
                         }
+                        state.request_preview();
                         draw_picker(alt.tui, &state)?;
                     }
                     _ => {}
@@ -131,12 +183,12 @@ pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<Resum
 }
 
 /// RAII guard that ensures we leave the alt-screen on scope exit.
-struct AltScreenGuard<'a> {
-    tui: &'a mut Tui,
+pub(crate) struct AltScreenGuard<'a> {
+    pub(crate) tui: &'a mut Tui,
 }
 
 impl<'a> AltScreenGuard<'a> {
-    fn enter(tui: &'a mut Tui) -> Self {
+    pub(crate) fn enter(tui: &'a mut Tui) -> Self {
         let _ = tui.enter_alt_screen();
         Self { tui }
     }
@@ -163,6 +215,15 @@ struct PickerState {
     next_search_token: usize,
     page_loader: PageLoader,
     view_rows: Option<usize>,
+    preview_loader: Option<PreviewLoader>,
+    /// Last messages of each session previewed so far; `None` while loading.
+    previews: HashMap<PathBuf, Option<Vec<ObservedMessage>>>,
+    export_dir: PathBuf,
+    origin: PickerOrigin,
+    /// Session that a second Ctrl+D deletes.
+    pending_delete: Option<PathBuf>,
+    /// Outcome of the last action, shown above the key hints.
+    status: Option<Line<'static>>,
 }
 
 struct PaginationState {
@@ -243,6 +304,12 @@ impl PickerState {
             next_search_token: 0,
             page_loader,
             view_rows: None,
+            preview_loader: None,
+            previews: HashMap::new(),
+            export_dir: PathBuf::from("."),
+            origin: PickerOrigin::Startup,
+            pending_delete: None,
+            status: None,
         }
     }
 
@@ -251,15 +318,23 @@ impl PickerState {
     }
 
     async fn handle_key(&mut self, key: KeyEvent) -> Result<Option<ResumeSelection>> {
+        let pending_delete = self.pending_delete.take();
+        if self.status.take().is_some() {
+            self.request_frame();
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
         match key.code {
             KeyCode::Esc => return Ok(Some(ResumeSelection::StartFresh)),
-            KeyCode::Char('c')
-                if key
-                    .modifiers
-                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-            {
+            KeyCode::Char('c') if ctrl => {
                 return Ok(Some(ResumeSelection::Exit));
             }
+            KeyCode::Char('f') if ctrl => {
+                if let Some(row) = self.filtered_rows.get(self.selected) {
+                    return Ok(Some(ResumeSelection::Fork(row.path.clone())));
+                }
+            }
+            KeyCode::Char('e') if ctrl => self.export_selected().await,
+            KeyCode::Char('d') if ctrl => self.delete_selected(pending_delete).await,
             KeyCode::Enter => {
                 if let Some(row) = self.filtered_rows.get(self.selected) {
                     return Ok(Some(ResumeSelection::Resume(row.path.clone())));
@@ -305,11 +380,7 @@ impl PickerState {
             }
             KeyCode::Char(c) => {
                 // basic text input for search
-                if !key
-                    .modifiers
-                    .contains(crossterm::event::KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(crossterm::event::KeyModifiers::ALT)
-                {
+                if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) {
                     let mut new_query = self.query.clone();
                     new_query.push(c);
                     self.set_query(new_query);
@@ -352,10 +423,80 @@ impl PickerState {
                 let completed_token = pending.search_token.or(search_token);
                 self.continue_search_if_token_matches(completed_token);
             }
+            BackgroundEvent::PreviewLoaded { path, messages } => {
+                if let Some(preview) = self.previews.get_mut(&path) {
+                    *preview = Some(messages);
+                    self.request_frame();
+                }
+            }
         }
         Ok(())
     }
 
+    /// Starts loading the preview of the selected session unless it was
+    /// loaded already.
+    fn request_preview(&mut self) {
+        let Some(loader) = self.preview_loader.as_ref() else {
+            return;
+        };
+        let Some(row) = self.filtered_rows.get(self.selected) else {
+            return;
+        };
+        if self.previews.contains_key(&row.path) {
+            return;
+        }
+        self.previews.insert(row.path.clone(), None);
+        loader(row.path.clone());
+    }
+
+    async fn export_selected(&mut self) {
+        let Some(row) = self.filtered_rows.get(self.selected) else {
+            return;
+        };
+        self.status = Some(match export_session(&row.path, &self.export_dir).await {
+            Ok(path) => format!("Exported to {}", path.display()).green().into(),
+            Err(e) => format!("Failed to export session: {e}").red().into(),
+        });
+        self.request_frame();
+    }
+
+    /// Deletes the selected session when `confirmed` is its path, i.e. on the
+    /// second Ctrl+D in a row; otherwise asks for that confirmation.
+    async fn delete_selected(&mut self, confirmed: Option<PathBuf>) {
+        let Some(path) = self
+            .filtered_rows
+            .get(self.selected)
+            .map(|r| r.path.clone())
+        else {
+            return;
+        };
+        self.request_frame();
+        if confirmed.as_ref() != Some(&path) {
+            self.status = Some(Line::from(vec![
+                "Press ".into(),
+                "Ctrl+D".bold(),
+                " again to delete this session".into(),
+            ]));
+            self.pending_delete = Some(path);
+            return;
+        }
+        let status: Line<'static> = match session_lock_status(&self.codex_home, &path) {
+            Ok(Some(locked)) => format!("Cannot delete: {locked}").red().into(),
+            Err(e) => format!("Failed to delete session: {e}").red().into(),
+            Ok(None) => match tokio::fs::remove_file(&path).await {
+                Ok(()) => {
+                    // Stays in `seen_paths` so a later page cannot bring it back.
+                    self.all_rows.retain(|row| row.path != path);
+                    self.previews.remove(&path);
+                    self.apply_filter();
+                    "Session deleted".into()
+                }
+                Err(e) => format!("Failed to delete session: {e}").red().into(),
+            },
+        };
+        self.status = Some(status);
+    }
+
     fn reset_pagination(&mut self) {
         self.pagination.next_cursor = None;
         self.pagination.num_scanned_files = 0;
@@ -622,15 +763,77 @@ fn preview_from_head(head: &[serde_json::Value]) -> Option<String> {
         })
 }
 
+/// The user and agent messages of the rollout at `path`, most recent last.
+async fn load_preview(path: &Path) -> std::io::Result<Vec<ObservedMessage>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let messages: Vec<ObservedMessage> = text.lines().filter_map(parse_message).collect();
+    let skip = messages.len().saturating_sub(PREVIEW_MESSAGES);
+    Ok(messages.into_iter().skip(skip).collect())
+}
+
+/// Writes the conversation recorded at `path` as Markdown to `target`, or to
+/// a file named after the rollout when `target` is a directory, returning the
+/// file's path.
+pub(crate) async fn export_session(path: &Path, target: &Path) -> std::io::Result<PathBuf> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let out = if tokio::fs::metadata(target).await.is_ok_and(|m| m.is_dir()) {
+        let name = path
+            .file_stem()
+            .ok_or_else(|| std::io::Error::other(format!("invalid rollout path {path:?}")))?;
+        target.join(format!("{}.md", name.to_string_lossy()))
+    } else {
+        target.to_path_buf()
+    };
+    tokio::fs::write(&out, session_markdown(&text)).await?;
+    Ok(out)
+}
+
+pub(crate) fn session_markdown(rollout: &str) -> String {
+    let annotations: Vec<AnnotationAddedEvent> =
+        rollout.lines().filter_map(parse_annotation).collect();
+    let mut markdown = String::from("# Codex session\n");
+    let (mut user_index, mut agent_index) = (0, 0);
+    for message in rollout.lines().filter_map(parse_message) {
+        let (speaker, text, target) = match &message {
+            ObservedMessage::User(text) => {
+                let index = user_index;
+                user_index += 1;
+                ("User", text, AnnotationTarget::UserMessage { index })
+            }
+            ObservedMessage::Agent(text) => {
+                let index = agent_index;
+                agent_index += 1;
+                ("Codex", text, AnnotationTarget::AgentMessage { index })
+            }
+        };
+        markdown.push_str(&format!("\n## {speaker}\n\n{}\n", text.trim_end()));
+        // Annotations are recorded when they are made, so they are placed
+        // after the message they refer to rather than where they occur.
+        for annotation in annotations.iter().filter(|a| a.target == target) {
+            markdown.push_str(&format!("\n> **Note:** {}\n", annotation.comment.trim()));
+        }
+    }
+    markdown
+}
+
+fn parse_annotation(line: &str) -> Option<AnnotationAddedEvent> {
+    let line: RolloutLine = serde_json::from_str(line).ok()?;
+    match line.item {
+        RolloutItem::EventMsg(EventMsg::AnnotationAdded(event)) => Some(event),
+        _ => None,
+    }
+}
+
 fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
     // Render full-screen overlay
     let height = tui.terminal.size()?.height;
     tui.draw(height, |frame| {
         let area = frame.area();
-        let [header, search, list, hint] = Layout::vertical([
+        let [header, search, body, status, hint] = Layout::vertical([
             Constraint::Length(1),
             Constraint::Length(1),
-            Constraint::Min(area.height.saturating_sub(3)),
+            Constraint::Min(area.height.saturating_sub(4)),
+            Constraint::Length(1),
             Constraint::Length(1),
         ])
         .areas(area);
@@ -649,28 +852,68 @@ fn draw_picker(tui: &mut Tui, state: &PickerState) -> std::io::Result<()> {
         };
         frame.render_widget_ref(Line::from(q), search);
 
-        // List
-        render_list(frame, list, state);
+        // List, with the preview of the selected session beside it
+        if body.width >= MIN_PREVIEW_WIDTH {
+            let [list, _, preview] = Layout::horizontal([
+                Constraint::Percentage(50),
+                Constraint::Length(2),
+                Constraint::Min(0),
+            ])
+            .areas(body);
+            render_list(frame, list, state);
+            render_preview(frame, preview, state);
+        } else {
+            render_list(frame, body, state);
+        }
+
+        if let Some(line) = &state.status {
+            frame.render_widget_ref(line.clone(), status);
+        }
 
         // Hint line
+        let esc_hint = match state.origin {
+            PickerOrigin::Startup => " to start new ",
+            PickerOrigin::Session => " to go back ",
+        };
         let hint_line: Line = vec![
             "Enter".bold(),
             " to resume ".into(),
             "• ".dim(),
+            "Ctrl+F".bold(),
+            " fork ".into(),
+            "• ".dim(),
+            "Ctrl+E".bold(),
+            " export ".into(),
+            "• ".dim(),
+            "Ctrl+D".bold(),
+            " delete ".into(),
+            "• ".dim(),
             "Esc".bold(),
-            " to start new ".into(),
+            esc_hint.into(),
             "• ".dim(),
             "Ctrl+C".into(),
-            " to quit ".into(),
-            "• ".dim(),
-            "↑/↓".into(),
-            " to browse".dim(),
+            " to quit".into(),
         ]
         .into();
         frame.render_widget_ref(hint_line, hint);
     })
 }
 
+fn render_preview(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &PickerState) {
+    let Some(row) = state.filtered_rows.get(state.selected) else {
+        return;
+    };
+    let placeholder = match state.previews.get(&row.path) {
+        Some(Some(messages)) if !messages.is_empty() => {
+            render_transcript(frame, area, messages);
+            return;
+        }
+        Some(Some(_)) => "(no messages)",
+        _ => "Loading…",
+    };
+    frame.render_widget_ref(Line::from(placeholder.italic().dim()), area);
+}
+
 fn render_list(frame: &mut crate::custom_terminal::Frame, area: Rect, state: &PickerState) {
     if area.height == 0 {
         return;
@@ -1147,4 +1390,79 @@ mod tests {
         assert!(!state.search_state.is_active());
         assert!(state.pagination.reached_scan_cap);
     }
+
+    #[test]
+    fn delete_needs_a_second_ctrl_d() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let rollout = codex_home.path().join("rollout-a.jsonl");
+        std::fs::write(&rollout, "{}\n").expect("write rollout");
+        let loader: PageLoader = Arc::new(|_| {});
+        let mut state = PickerState::new(
+            codex_home.path().to_path_buf(),
+            FrameRequester::test_dummy(),
+            loader,
+        );
+        state.ingest_page(page(
+            vec![make_item(
+                rollout.to_str().expect("utf-8 path"),
+                "2025-01-01T00:00:00Z",
+                "old work",
+            )],
+            None,
+            1,
+            false,
+        ));
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+
+        block_on_future(async {
+            state.handle_key(ctrl_d).await.unwrap();
+            // Any other key cancels the deletion.
+            state
+                .handle_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE))
+                .await
+                .unwrap();
+            state.handle_key(ctrl_d).await.unwrap();
+        });
+        assert!(rollout.exists());
+        assert_eq!(state.filtered_rows.len(), 1);
+
+        block_on_future(async {
+            state.handle_key(ctrl_d).await.unwrap();
+        });
+        assert!(!rollout.exists());
+        assert!(state.filtered_rows.is_empty());
+    }
+
+    #[test]
+    fn exported_sessions_list_user_and_agent_messages() {
+        let rollout = [
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"fix the build","kind":"plain"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"token_count","info":null}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_message","message":"Done.\n"}}"#,
+        ]
+        .join("\n");
+
+        assert_eq!(
+            session_markdown(&rollout),
+            "# Codex session\n\n## User\n\nfix the build\n\n## Codex\n\nDone.\n"
+        );
+    }
+
+    #[test]
+    fn exported_sessions_show_annotations_after_their_message() {
+        let rollout = [
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"fix the build","kind":"plain"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_message","message":"Done."}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"now the tests","kind":"plain"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"annotation_added","target":{"type":"agent_message","index":0},"comment":"it was not done"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"annotation_added","target":{"type":"user_message","index":0},"comment":"too vague"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"annotation_added","target":{"type":"user_message","index":5},"comment":"dangling"}}"#,
+        ]
+        .join("\n");
+
+        assert_eq!(
+            session_markdown(&rollout),
+            "# Codex session\n\n## User\n\nfix the build\n\n> **Note:** too vague\n\n## Codex\n\nDone.\n\n> **Note:** it was not done\n\n## User\n\nnow the tests\n"
+        );
+    }
 }
@@ -0,0 +1,265 @@
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use codex_core::SessionLocked;
+use codex_core::session_lock_status;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use color_eyre::eyre::Result;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize as _;
+use ratatui::text::Line;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
+use tokio_stream::StreamExt;
+
+use crate::resume_picker::AltScreenGuard;
+use crate::resume_picker::ResumeSelection;
+use crate::tui::Tui;
+use crate::tui::TuiEvent;
+
+/// How often the rollout is checked for new messages and for the lock being
+/// released.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shown instead of resuming a session that another process has open: follows
+/// the session's messages read-only and lets the user fork it instead.
+pub async fn run_session_locked_view(
+    tui: &mut Tui,
+    codex_home: &Path,
+    locked: SessionLocked,
+) -> Result<ResumeSelection> {
+    let alt = AltScreenGuard::enter(tui);
+    let mut state = ObserverState::new(locked);
+    state.poll_rollout().await;
+    alt.tui.frame_requester().schedule_frame();
+
+    let mut tui_events = alt.tui.event_stream().fuse();
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            Some(event) = tui_events.next() => match event {
+                TuiEvent::Key(key) => {
+                    if matches!(key.kind, KeyEventKind::Release) {
+                        continue;
+                    }
+                    if let Some(selection) = state.handle_key(key) {
+                        return Ok(selection);
+                    }
+                }
+                TuiEvent::Draw => draw(alt.tui, &state)?,
+                TuiEvent::Paste(_) | TuiEvent::Mouse(_) => {}
+            },
+            _ = poll.tick() => {
+                let mut changed = state.poll_rollout().await;
+                if !state.released
+                    && let Ok(None) = session_lock_status(codex_home, &state.locked.rollout_path)
+                {
+                    state.released = true;
+                    changed = true;
+                }
+                if changed {
+                    alt.tui.frame_requester().schedule_frame();
+                }
+            }
+            else => return Ok(ResumeSelection::Exit),
+        }
+    }
+}
+
+struct ObserverState {
+    locked: SessionLocked,
+    /// Set once the other process has closed the session.
+    released: bool,
+    messages: Vec<ObservedMessage>,
+    /// Bytes of the rollout consumed so far, up to the last complete line.
+    offset: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ObservedMessage {
+    User(String),
+    Agent(String),
+}
+
+impl ObserverState {
+    fn new(locked: SessionLocked) -> Self {
+        Self {
+            locked,
+            released: false,
+            messages: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    fn rollout_path(&self) -> &PathBuf {
+        &self.locked.rollout_path
+    }
+
+    fn handle_key(&self, key: KeyEvent) -> Option<ResumeSelection> {
+        match key.code {
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                Some(ResumeSelection::Fork(self.rollout_path().clone()))
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') if self.released => {
+                Some(ResumeSelection::Resume(self.rollout_path().clone()))
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(ResumeSelection::Exit)
+            }
+            KeyCode::Esc | KeyCode::Char('q') => Some(ResumeSelection::Exit),
+            _ => None,
+        }
+    }
+
+    /// Reads the lines appended to the rollout since the last poll. Returns
+    /// whether new messages were found.
+    async fn poll_rollout(&mut self) -> bool {
+        let Ok(appended) = read_appended(self.rollout_path(), self.offset).await else {
+            return false;
+        };
+        // Only consume complete lines; the holder may be mid-write.
+        let Some(end) = appended.iter().rposition(|b| *b == b'\n') else {
+            return false;
+        };
+        self.offset += end as u64 + 1;
+        let before = self.messages.len();
+        self.messages.extend(
+            String::from_utf8_lossy(&appended[..end])
+                .lines()
+                .filter_map(parse_message),
+        );
+        self.messages.len() > before
+    }
+}
+
+async fn read_appended(path: &Path, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = tokio::fs::File::open(path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut appended = Vec::new();
+    file.read_to_end(&mut appended).await?;
+    Ok(appended)
+}
+
+/// The user or agent message recorded on a rollout line, if any.
+pub(crate) fn parse_message(line: &str) -> Option<ObservedMessage> {
+    let line: RolloutLine = serde_json::from_str(line).ok()?;
+    match line.item {
+        RolloutItem::EventMsg(EventMsg::UserMessage(event))
+            if matches!(event.kind, None | Some(InputMessageKind::Plain)) =>
+        {
+            Some(ObservedMessage::User(event.message))
+        }
+        RolloutItem::EventMsg(EventMsg::AgentMessage(event)) => {
+            Some(ObservedMessage::Agent(event.message))
+        }
+        _ => None,
+    }
+}
+
+fn draw(tui: &mut Tui, state: &ObserverState) -> std::io::Result<()> {
+    let height = tui.terminal.size()?.height;
+    tui.draw(height, |frame| {
+        let area = frame.area();
+        let [header, holder, transcript, hint] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(area.height.saturating_sub(3)),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        frame.render_widget_ref(
+            Line::from(vec!["Observing a session (read-only)".bold().cyan()]),
+            header,
+        );
+        let holder_line: Line = if state.released {
+            "The other process has closed this session.".green().into()
+        } else {
+            state.locked.to_string().dim().into()
+        };
+        frame.render_widget_ref(holder_line, holder);
+
+        render_transcript(frame, transcript, &state.messages);
+
+        let mut hint_spans = vec!["F".bold(), " to fork into a new session ".into()];
+        if state.released {
+            hint_spans.extend(["• ".dim(), "R".bold(), " to resume ".into()]);
+        }
+        hint_spans.extend(["• ".dim(), "Esc".bold(), " to quit".into()]);
+        frame.render_widget_ref(Line::from(hint_spans), hint);
+    })
+}
+
+/// Renders `messages` wrapped to `area`, showing the most recent ones when
+/// they do not all fit.
+pub(crate) fn render_transcript(
+    frame: &mut crate::custom_terminal::Frame,
+    area: Rect,
+    messages: &[ObservedMessage],
+) {
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    for message in messages {
+        let (prefix, text) = match message {
+            ObservedMessage::User(text) => ("› ".cyan().bold(), text),
+            ObservedMessage::Agent(text) => ("• ".dim(), text),
+        };
+        for (idx, wrapped) in textwrap::wrap(text, width).into_iter().enumerate() {
+            let lead = if idx == 0 {
+                prefix.clone()
+            } else {
+                "  ".into()
+            };
+            lines.push(Line::from(vec![lead, wrapped.into_owned().into()]));
+        }
+        lines.push(Line::from(""));
+    }
+
+    // Follow the end of the session, like `tail -f`.
+    let start = lines.len().saturating_sub(area.height as usize);
+    for (row, line) in lines.into_iter().skip(start).enumerate() {
+        let rect = Rect::new(area.x, area.y + row as u16, area.width, 1);
+        frame.render_widget_ref(line, rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_user_and_agent_messages_only() {
+        let lines = [
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"fix the build","kind":"plain"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"user_message","message":"<environment_context/>","kind":"environment_context"}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"agent_message","message":"Done."}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"token_count","info":null}}"#,
+            "not json",
+        ];
+
+        let messages: Vec<ObservedMessage> = lines
+            .iter()
+            .filter_map(|line| parse_message(line))
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                ObservedMessage::User("fix the build".to_string()),
+                ObservedMessage::Agent("Done.".to_string()),
+            ]
+        );
+    }
+}
@@ -181,6 +181,22 @@ pub(crate) fn log_outbound_op(op: &Op) {
     if !LOGGER.is_enabled() {
         return;
     }
+    // Never write secrets the user typed to the log.
+    if let Op::ProvideSecret { id, name, value } = op {
+        let value = json!({
+            "ts": now_ts(),
+            "dir": "from_tui",
+            "kind": "op",
+            "payload": {
+                "type": "provide_secret",
+                "id": id,
+                "name": name,
+                "provided": value.is_some(),
+            },
+        });
+        LOGGER.write_json_line(value);
+        return;
+    }
     write_record("from_tui", "op", op);
 }
 
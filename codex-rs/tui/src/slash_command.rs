@@ -13,16 +13,30 @@ pub enum SlashCommand {
     // DO NOT ALPHA-SORT! Enum order is presentation order in the popup, so
     // more frequently used commands should be listed first.
     Model,
+    High,
+    Turn,
     Approvals,
+    Theme,
     Review,
     New,
+    Resume,
     Init,
+    Memory,
     Compact,
     Undo,
+    Checkpoint,
     Diff,
+    Pr,
+    Copy,
+    Export,
+    Annotate,
     Mention,
+    Image,
     Status,
+    Context,
     Mcp,
+    Jobs,
+    Debug,
     Logout,
     Quit,
     #[cfg(debug_assertions)]
@@ -34,17 +48,45 @@ impl SlashCommand {
     pub fn description(self) -> &'static str {
         match self {
             SlashCommand::New => "start a new chat during a conversation",
+            SlashCommand::Resume => "resume, fork, export or delete a previous session",
             SlashCommand::Init => "create an AGENTS.md file with instructions for Codex",
+            SlashCommand::Memory => {
+                "show what Codex remembers about this project (edit: change it)"
+            }
             SlashCommand::Compact => "summarize conversation to prevent hitting the context limit",
-            SlashCommand::Review => "review my current changes and find issues",
-            SlashCommand::Undo => "restore the workspace to the last Codex snapshot",
+            SlashCommand::Review => {
+                "review my current changes and find issues (or a range, commit or PR URL)"
+            }
+            SlashCommand::Undo => "roll back the file changes of the last turn",
+            SlashCommand::Checkpoint => {
+                "save the conversation and file changes under a name (restore <name>: go back)"
+            }
             SlashCommand::Quit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
+            SlashCommand::Pr => "push this session's changes and open a pull request",
+            SlashCommand::Copy => {
+                "copy the last message, its last code block (code) or the transcript (all)"
+            }
+            SlashCommand::Export => "write the transcript to a Markdown file",
+            SlashCommand::Annotate => "add a comment to an earlier message in this session",
             SlashCommand::Mention => "mention a file",
-            SlashCommand::Status => "show current session configuration and token usage",
-            SlashCommand::Model => "choose what model and reasoning effort to use",
+            SlashCommand::Image => "attach an image from a path, or from the clipboard",
+            SlashCommand::Status => "show live session settings, usage and MCP server health",
+            SlashCommand::Context => "show how much of the context window the conversation uses",
+            SlashCommand::Model => {
+                "choose what model and reasoning effort to use (or /model <model> [provider])"
+            }
+            SlashCommand::High => "send a message with high reasoning effort, for this turn only",
+            SlashCommand::Turn => {
+                "send a message with effort=, verbosity= or temperature= for this turn only"
+            }
             SlashCommand::Approvals => "choose what Codex can do without approval",
+            SlashCommand::Theme => "choose the colors of diffs, commands and the status line",
             SlashCommand::Mcp => "list configured MCP tools",
+            SlashCommand::Jobs => "list background jobs started by Codex",
+            SlashCommand::Debug => {
+                "toggle the wire log of raw model requests (level <level>: session log level)"
+            }
             SlashCommand::Logout => "log out of Codex",
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => "test approval request",
@@ -61,17 +103,31 @@ impl SlashCommand {
     pub fn available_during_task(self) -> bool {
         match self {
             SlashCommand::New
+            | SlashCommand::Resume
             | SlashCommand::Init
             | SlashCommand::Compact
             | SlashCommand::Undo
+            | SlashCommand::Checkpoint
             | SlashCommand::Model
+            | SlashCommand::High
+            | SlashCommand::Turn
             | SlashCommand::Approvals
             | SlashCommand::Review
+            | SlashCommand::Pr
             | SlashCommand::Logout => false,
             SlashCommand::Diff
+            | SlashCommand::Copy
+            | SlashCommand::Export
+            | SlashCommand::Annotate
             | SlashCommand::Mention
+            | SlashCommand::Image
             | SlashCommand::Status
+            | SlashCommand::Context
+            | SlashCommand::Memory
             | SlashCommand::Mcp
+            | SlashCommand::Jobs
+            | SlashCommand::Theme
+            | SlashCommand::Debug
             | SlashCommand::Quit => true,
 
             #[cfg(debug_assertions)]
@@ -82,20 +138,5 @@ impl SlashCommand {
 
 /// Return all built-in commands in a Vec paired with their command string.
 pub fn built_in_slash_commands() -> Vec<(&'static str, SlashCommand)> {
-    let show_beta_features = beta_features_enabled();
-
-    SlashCommand::iter()
-        .filter(|cmd| {
-            if *cmd == SlashCommand::Undo {
-                show_beta_features
-            } else {
-                true
-            }
-        })
-        .map(|c| (c.command(), c))
-        .collect()
-}
-
-fn beta_features_enabled() -> bool {
-    std::env::var_os("BETA_FEATURE").is_some()
+    SlashCommand::iter().map(|c| (c.command(), c)).collect()
 }
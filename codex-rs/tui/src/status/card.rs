@@ -37,7 +37,7 @@ pub(crate) struct StatusTokenUsageData {
 }
 
 #[derive(Debug)]
-struct StatusHistoryCell {
+pub(super) struct StatusHistoryCell {
     model_name: String,
     model_details: Vec<String>,
     directory: PathBuf,
@@ -63,7 +63,7 @@ pub(crate) fn new_status_output(
 }
 
 impl StatusHistoryCell {
-    fn new(
+    pub(super) fn new(
         config: &Config,
         usage: &TokenUsage,
         session_id: &Option<ConversationId>,
@@ -80,6 +80,7 @@ impl StatusHistoryCell {
             SandboxPolicy::DangerFullAccess => "danger-full-access".to_string(),
             SandboxPolicy::ReadOnly => "read-only".to_string(),
             SandboxPolicy::WorkspaceWrite { .. } => "workspace-write".to_string(),
+            SandboxPolicy::Container { image, .. } => format!("container ({image})"),
         };
         let agents_summary = compose_agents_summary(config);
         let account = compose_account_display(config);
@@ -0,0 +1,240 @@
+//! The live `/status` dashboard: the status card, followed by the provider,
+//! context window usage, cost and the health of each MCP server.
+//!
+//! It is rebuilt from the chat widget's state whenever core reports new
+//! token usage, rate limits or MCP server status, so it stays current while
+//! a task runs.
+
+use std::collections::BTreeMap;
+
+use codex_core::config::Config;
+use codex_core::protocol::McpServerStatus;
+use codex_core::protocol::TokenUsage;
+use codex_core::protocol::TokenUsageInfo;
+use codex_protocol::mcp_protocol::ConversationId;
+use ratatui::prelude::*;
+use ratatui::style::Stylize;
+
+use super::card::StatusHistoryCell;
+use super::format::FieldFormatter;
+use super::helpers::format_tokens_compact;
+use super::rate_limits::RateLimitSnapshotDisplay;
+use super::rate_limits::render_status_limit_progress_bar;
+use crate::history_cell::HistoryCell;
+
+#[derive(Debug)]
+pub(crate) struct StatusDashboard {
+    card: StatusHistoryCell,
+    provider: String,
+    context: Option<ContextWindowUsage>,
+    cost_usd: Option<f64>,
+    /// Configured MCP servers, sorted by name.
+    mcp_servers: Vec<(String, McpServerStatus)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ContextWindowUsage {
+    used: u64,
+    window: u64,
+    percent_left: u8,
+}
+
+impl StatusDashboard {
+    /// `unhealthy_mcp_servers` holds the last reported status of servers
+    /// that are degraded or failed; all other configured servers are healthy.
+    pub(crate) fn new(
+        config: &Config,
+        token_info: Option<&TokenUsageInfo>,
+        session_id: &Option<ConversationId>,
+        rate_limits: Option<&RateLimitSnapshotDisplay>,
+        unhealthy_mcp_servers: &BTreeMap<String, McpServerStatus>,
+    ) -> Self {
+        let default_usage = TokenUsage::default();
+        let usage = token_info.map_or(&default_usage, |info| &info.total_token_usage);
+        let card = StatusHistoryCell::new(config, usage, session_id, rate_limits);
+
+        let provider = if config.model_provider.name == config.model_provider_id {
+            config.model_provider_id.clone()
+        } else {
+            format!(
+                "{} ({})",
+                config.model_provider.name, config.model_provider_id
+            )
+        };
+        let context = token_info.and_then(|info| {
+            let window = info.model_context_window.or(config.model_context_window)?;
+            Some(ContextWindowUsage {
+                used: info.last_token_usage.tokens_in_context_window(),
+                window,
+                percent_left: info
+                    .last_token_usage
+                    .percent_of_context_window_remaining(window),
+            })
+        });
+        let mut mcp_servers: Vec<(String, McpServerStatus)> = config
+            .mcp_servers
+            .keys()
+            .map(|name| {
+                let status = unhealthy_mcp_servers
+                    .get(name)
+                    .copied()
+                    .unwrap_or(McpServerStatus::Healthy);
+                (name.clone(), status)
+            })
+            .collect();
+        mcp_servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Self {
+            card,
+            provider,
+            context,
+            cost_usd: token_info.and_then(|info| info.estimated_cost_usd),
+            mcp_servers,
+        }
+    }
+
+    fn context_spans(&self) -> Vec<Span<'static>> {
+        let Some(context) = self.context else {
+            return vec![Span::from("send a message to load usage data").dim()];
+        };
+        let percent_used = 100 - u16::from(context.percent_left.min(100));
+        vec![
+            Span::from(render_status_limit_progress_bar(f64::from(percent_used))),
+            Span::from(format!(" {}% left", context.percent_left)),
+            Span::from(format!(
+                " ({} / {})",
+                format_tokens_compact(context.used),
+                format_tokens_compact(context.window)
+            ))
+            .dim(),
+        ]
+    }
+
+    fn mcp_server_lines(&self, formatter: &FieldFormatter) -> Vec<Line<'static>> {
+        if self.mcp_servers.is_empty() {
+            return vec![formatter.line("MCP servers", vec![Span::from("<none>").dim()])];
+        }
+        self.mcp_servers
+            .iter()
+            .enumerate()
+            .map(|(idx, (name, status))| {
+                let health = match status {
+                    McpServerStatus::Healthy => Span::from("healthy").green(),
+                    McpServerStatus::Degraded => Span::from("degraded").magenta(),
+                    McpServerStatus::Failed => Span::from("failed").red(),
+                };
+                let spans = vec![Span::from(format!("{name} ")), health];
+                if idx == 0 {
+                    formatter.line("MCP servers", spans)
+                } else {
+                    formatter.continuation(spans)
+                }
+            })
+            .collect()
+    }
+}
+
+impl HistoryCell for StatusDashboard {
+    fn display_lines(&self, width: u16) -> Vec<Line<'static>> {
+        let mut lines = self.card.display_lines(width);
+        if lines.is_empty() {
+            return lines;
+        }
+        lines.push(Line::from(""));
+
+        let mut labels = vec!["Provider", "Context window", "MCP servers"];
+        if self.cost_usd.is_some() {
+            labels.push("Cost");
+        }
+        let formatter = FieldFormatter::from_labels(labels);
+        lines.push(formatter.line("Provider", vec![Span::from(self.provider.clone())]));
+        lines.push(formatter.line("Context window", self.context_spans()));
+        if let Some(cost) = self.cost_usd {
+            lines.push(formatter.line("Cost", vec![Span::from(format!("~${cost:.2}"))]));
+        }
+        lines.extend(self.mcp_server_lines(&formatter));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+    use codex_core::config_types::McpServerConfig;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn test_config(temp_home: &TempDir) -> Config {
+        Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            temp_home.path().to_path_buf(),
+        )
+        .expect("load config")
+    }
+
+    fn render(dashboard: &StatusDashboard) -> Vec<String> {
+        dashboard
+            .display_lines(80)
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dashboard_reports_context_window_and_mcp_health() {
+        let temp_home = TempDir::new().expect("temp home");
+        let mut config = test_config(&temp_home);
+        let server: McpServerConfig =
+            serde_json::from_value(serde_json::json!({ "command": "docs-server" }))
+                .expect("mcp server config");
+        config
+            .mcp_servers
+            .insert("docs".to_string(), server.clone());
+        config.mcp_servers.insert("search".to_string(), server);
+
+        let info = TokenUsageInfo {
+            total_token_usage: TokenUsage {
+                input_tokens: 20_000,
+                output_tokens: 2_000,
+                total_tokens: 22_000,
+                ..TokenUsage::default()
+            },
+            last_token_usage: TokenUsage {
+                input_tokens: 10_000,
+                output_tokens: 1_000,
+                total_tokens: 11_000,
+                ..TokenUsage::default()
+            },
+            model_context_window: Some(100_000),
+            estimated_cost_usd: Some(0.126),
+        };
+        let unhealthy = BTreeMap::from([("search".to_string(), McpServerStatus::Failed)]);
+        let dashboard = StatusDashboard::new(&config, Some(&info), &None, None, &unhealthy);
+        let lines = render(&dashboard);
+
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.contains("Context window:") && line.contains("(11K / 100K)")),
+            "{lines:#?}"
+        );
+        assert!(
+            lines.iter().any(|line| line.contains("~$0.13")),
+            "{lines:#?}"
+        );
+        let mcp: Vec<&str> = lines
+            .iter()
+            .filter(|line| line.contains("docs ") || line.contains("search "))
+            .map(|line| line.trim())
+            .collect();
+        assert_eq!(mcp, vec!["MCP servers:      docs healthy", "search failed"]);
+    }
+}
@@ -73,6 +73,8 @@ fn status_snapshot_includes_reasoning_details() {
         network_access: false,
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
+        network_allowlist: Vec::new(),
+        deny: Vec::new(),
     };
 
     config.cwd = PathBuf::from("/workspace/tests");
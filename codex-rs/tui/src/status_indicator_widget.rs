@@ -15,6 +15,8 @@ use ratatui::widgets::WidgetRef;
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::key_hint;
+use crate::keymap;
+use crate::keymap::KeyAction;
 use crate::shimmer::shimmer_spans;
 use crate::tui::FrameRequester;
 use crate::ui_consts::LIVE_PREFIX_COLS;
@@ -190,7 +192,11 @@ impl WidgetRef for StatusIndicatorWidget {
         }
         if !self.queued_messages.is_empty() {
             let shortcut = key_hint::alt("↑");
-            lines.push(Line::from(vec!["   ".into(), shortcut, " edit".into()]).dim());
+            let mut hint = vec!["   ".into(), shortcut, " edit".into()];
+            if let Some(steer) = keymap::current().primary(KeyAction::Steer) {
+                hint.push(format!(" · {} send now", steer.label()).into());
+            }
+            lines.push(Line::from(hint).dim());
         }
 
         let paragraph = Paragraph::new(lines);
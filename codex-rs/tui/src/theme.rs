@@ -0,0 +1,346 @@
+//! Colors for diffs, syntax highlighting and the status line.
+//!
+//! The active theme is process-wide so rendering code can look it up without
+//! threading it through every widget; `/theme` swaps it at runtime. Lines
+//! already written to the terminal scrollback keep the colors they were
+//! rendered with.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use codex_core::config_types::ThemePalette;
+use codex_core::config_types::TuiTheme;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+
+/// Names of the built-in themes, in the order `/theme` lists them.
+pub(crate) const BUILTIN_THEMES: [&str; 4] = ["dark", "light", "solarized", "high-contrast"];
+
+const DEFAULT_THEME: &str = "dark";
+
+static CURRENT: LazyLock<RwLock<Arc<Theme>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(Theme::dark())));
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Theme {
+    pub(crate) name: String,
+    pub(crate) diff_add: Style,
+    pub(crate) diff_delete: Style,
+    pub(crate) diff_gutter: Style,
+    pub(crate) syntax_operator: Style,
+    pub(crate) syntax_heredoc: Style,
+    pub(crate) syntax_keyword: Style,
+    pub(crate) syntax_string: Style,
+    pub(crate) syntax_comment: Style,
+    pub(crate) syntax_number: Style,
+    pub(crate) status_accent: Style,
+    pub(crate) status_warning: Style,
+}
+
+impl Theme {
+    fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            diff_add: Style::default().fg(Color::Green),
+            diff_delete: Style::default().fg(Color::Red),
+            diff_gutter: Style::default().add_modifier(Modifier::DIM),
+            syntax_operator: Style::default().add_modifier(Modifier::DIM),
+            syntax_heredoc: Style::default().add_modifier(Modifier::DIM),
+            syntax_keyword: Style::default().fg(Color::Magenta),
+            syntax_string: Style::default().fg(Color::Green),
+            syntax_comment: Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC),
+            syntax_number: Style::default().fg(Color::Cyan),
+            status_accent: Style::default().fg(Color::Cyan),
+            status_warning: Style::default().fg(Color::Yellow),
+        }
+    }
+
+    /// Dimmed text is hard to read on light backgrounds, so secondary text
+    /// uses a gray instead.
+    fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            diff_add: Style::default().fg(Color::Green),
+            diff_delete: Style::default().fg(Color::Red),
+            diff_gutter: Style::default().fg(Color::DarkGray),
+            syntax_operator: Style::default().fg(Color::DarkGray),
+            syntax_heredoc: Style::default().fg(Color::DarkGray),
+            syntax_keyword: Style::default().fg(Color::Magenta),
+            syntax_string: Style::default().fg(Color::Green),
+            syntax_comment: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+            syntax_number: Style::default().fg(Color::Blue),
+            status_accent: Style::default().fg(Color::Blue),
+            status_warning: Style::default().fg(Color::Magenta),
+        }
+    }
+
+    #[allow(clippy::disallowed_methods)]
+    fn solarized() -> Self {
+        Self {
+            name: "solarized".to_string(),
+            diff_add: Style::default().fg(Color::Rgb(0x85, 0x99, 0x00)),
+            diff_delete: Style::default().fg(Color::Rgb(0xdc, 0x32, 0x2f)),
+            diff_gutter: Style::default().fg(Color::Rgb(0x58, 0x6e, 0x75)),
+            syntax_operator: Style::default().fg(Color::Rgb(0x93, 0xa1, 0xa1)),
+            syntax_heredoc: Style::default().fg(Color::Rgb(0x2a, 0xa1, 0x98)),
+            syntax_keyword: Style::default().fg(Color::Rgb(0x85, 0x99, 0x00)),
+            syntax_string: Style::default().fg(Color::Rgb(0x2a, 0xa1, 0x98)),
+            syntax_comment: Style::default()
+                .fg(Color::Rgb(0x58, 0x6e, 0x75))
+                .add_modifier(Modifier::ITALIC),
+            syntax_number: Style::default().fg(Color::Rgb(0xd3, 0x36, 0x82)),
+            status_accent: Style::default().fg(Color::Rgb(0x26, 0x8b, 0xd2)),
+            status_warning: Style::default().fg(Color::Rgb(0xb5, 0x89, 0x00)),
+        }
+    }
+
+    /// No dimming, and bold colors for everything that carries meaning.
+    fn high_contrast() -> Self {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        Self {
+            name: "high-contrast".to_string(),
+            diff_add: bold.fg(Color::LightGreen),
+            diff_delete: bold.fg(Color::LightRed),
+            diff_gutter: Style::default(),
+            syntax_operator: bold,
+            syntax_heredoc: Style::default(),
+            syntax_keyword: bold.fg(Color::LightMagenta),
+            syntax_string: Style::default().fg(Color::LightGreen),
+            syntax_comment: Style::default().add_modifier(Modifier::ITALIC),
+            syntax_number: Style::default().fg(Color::LightCyan),
+            status_accent: bold.fg(Color::LightCyan),
+            status_warning: bold.fg(Color::LightYellow),
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "solarized" => Some(Self::solarized()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// The theme `name` refers to: a palette from `config`, or a built-in
+    /// theme.
+    pub(crate) fn resolve(config: &TuiTheme, name: &str) -> Result<Self, String> {
+        let Some(palette) = config.palettes.get(name) else {
+            return Self::builtin(name).ok_or_else(|| format!("unknown theme `{name}`"));
+        };
+        let base = palette
+            .base
+            .as_deref()
+            .unwrap_or(if Self::builtin(name).is_some() {
+                name
+            } else {
+                DEFAULT_THEME
+            });
+        let mut theme = Self::builtin(base)
+            .ok_or_else(|| format!("theme `{name}`: unknown base theme `{base}`"))?;
+        theme.name = name.to_string();
+        theme
+            .apply(palette)
+            .map_err(|e| format!("theme `{name}`: {e}"))?;
+        Ok(theme)
+    }
+
+    /// The theme to start with: `name` from `config`, or `dark`.
+    pub(crate) fn from_config(config: &TuiTheme) -> Result<Self, String> {
+        Self::resolve(config, config.name.as_deref().unwrap_or(DEFAULT_THEME))
+    }
+
+    fn apply(&mut self, palette: &ThemePalette) -> Result<(), String> {
+        let overrides = [
+            (&mut self.diff_add, &palette.diff_add, "diff_add"),
+            (&mut self.diff_delete, &palette.diff_delete, "diff_delete"),
+            (&mut self.diff_gutter, &palette.diff_gutter, "diff_gutter"),
+            (
+                &mut self.syntax_operator,
+                &palette.syntax_operator,
+                "syntax_operator",
+            ),
+            (
+                &mut self.syntax_heredoc,
+                &palette.syntax_heredoc,
+                "syntax_heredoc",
+            ),
+            (
+                &mut self.syntax_keyword,
+                &palette.syntax_keyword,
+                "syntax_keyword",
+            ),
+            (
+                &mut self.syntax_string,
+                &palette.syntax_string,
+                "syntax_string",
+            ),
+            (
+                &mut self.syntax_comment,
+                &palette.syntax_comment,
+                "syntax_comment",
+            ),
+            (
+                &mut self.syntax_number,
+                &palette.syntax_number,
+                "syntax_number",
+            ),
+            (
+                &mut self.status_accent,
+                &palette.status_accent,
+                "status_accent",
+            ),
+            (
+                &mut self.status_warning,
+                &palette.status_warning,
+                "status_warning",
+            ),
+        ];
+        for (style, color, key) in overrides {
+            if let Some(color) = color {
+                let color = Color::from_str(color)
+                    .map_err(|_| format!("invalid color `{color}` for `{key}`"))?;
+                // A configured color replaces the dimming of the base theme.
+                *style = style.remove_modifier(Modifier::DIM).fg(color);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names `/theme` offers: the built-in themes, then the user's palettes.
+pub(crate) fn theme_names(config: &TuiTheme) -> Vec<String> {
+    let mut palettes: Vec<String> = config
+        .palettes
+        .keys()
+        .filter(|name| !BUILTIN_THEMES.contains(&name.as_str()))
+        .cloned()
+        .collect();
+    palettes.sort();
+    BUILTIN_THEMES
+        .iter()
+        .map(|name| (*name).to_string())
+        .chain(palettes)
+        .collect()
+}
+
+/// The active theme.
+pub(crate) fn current() -> Arc<Theme> {
+    match CURRENT.read() {
+        Ok(theme) => theme.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Makes `theme` the active theme.
+pub(crate) fn set_current(theme: Theme) {
+    let theme = Arc::new(theme);
+    match CURRENT.write() {
+        Ok(mut current) => *current = theme,
+        Err(poisoned) => *poisoned.into_inner() = theme,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use ratatui::style::Stylize;
+    use std::collections::HashMap;
+
+    fn config_with(name: &str, palette: ThemePalette) -> TuiTheme {
+        TuiTheme {
+            name: Some(name.to_string()),
+            palettes: HashMap::from([(name.to_string(), palette)]),
+        }
+    }
+
+    #[test]
+    fn palettes_override_their_base_theme() {
+        let config = config_with(
+            "mine",
+            ThemePalette {
+                base: Some("light".to_string()),
+                diff_add: Some("blue".to_string()),
+                syntax_operator: Some("#ff8800".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let theme = Theme::from_config(&config).expect("valid palette");
+
+        #[allow(clippy::disallowed_methods)]
+        let orange = Color::Rgb(0xff, 0x88, 0x00);
+        assert_eq!(
+            theme,
+            Theme {
+                name: "mine".to_string(),
+                // Configured colors are never dimmed.
+                diff_add: Style::default().not_dim().fg(Color::Blue),
+                syntax_operator: Style::default().not_dim().fg(orange),
+                ..Theme::light()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_palettes_are_reported() {
+        let bad_color = config_with(
+            "mine",
+            ThemePalette {
+                diff_delete: Some("not-a-color".to_string()),
+                ..Default::default()
+            },
+        );
+        let bad_base = config_with(
+            "mine",
+            ThemePalette {
+                base: Some("neon".to_string()),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            Theme::from_config(&bad_color),
+            Err("theme `mine`: invalid color `not-a-color` for `diff_delete`".to_string())
+        );
+        assert_eq!(
+            Theme::from_config(&bad_base),
+            Err("theme `mine`: unknown base theme `neon`".to_string())
+        );
+        assert_eq!(
+            Theme::resolve(&TuiTheme::default(), "neon"),
+            Err("unknown theme `neon`".to_string())
+        );
+    }
+
+    #[test]
+    fn builtin_themes_come_before_palettes() {
+        let config = TuiTheme {
+            name: None,
+            palettes: HashMap::from([
+                ("zebra".to_string(), ThemePalette::default()),
+                ("dark".to_string(), ThemePalette::default()),
+                ("autumn".to_string(), ThemePalette::default()),
+            ]),
+        };
+
+        assert_eq!(
+            theme_names(&config),
+            vec![
+                "dark",
+                "light",
+                "solarized",
+                "high-contrast",
+                "autumn",
+                "zebra"
+            ]
+        );
+    }
+}
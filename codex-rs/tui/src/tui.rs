@@ -13,17 +13,21 @@ use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
+use codex_core::config_types::NotificationMethod;
 use crossterm::Command;
 use crossterm::SynchronizedUpdate;
 #[cfg(unix)]
 use crossterm::cursor::MoveTo;
 use crossterm::event::DisableBracketedPaste;
 use crossterm::event::DisableFocusChange;
+use crossterm::event::DisableMouseCapture;
 use crossterm::event::EnableBracketedPaste;
 use crossterm::event::EnableFocusChange;
+use crossterm::event::EnableMouseCapture;
 use crossterm::event::Event;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyboardEnhancementFlags;
+use crossterm::event::MouseEvent;
 use crossterm::event::PopKeyboardEnhancementFlags;
 use crossterm::event::PushKeyboardEnhancementFlags;
 use crossterm::terminal::EnterAlternateScreen;
@@ -45,6 +49,15 @@ use tokio_stream::Stream;
 /// A type alias for the terminal type used in this application
 pub type Terminal = CustomTerminal<CrosstermBackend<Stdout>>;
 
+/// Whether `set_modes` captures the mouse; see [`set_mouse_capture`].
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Capture the mouse whenever the terminal modes are set, from now on.
+/// Off by default so the terminal's own text selection keeps working.
+pub fn set_mouse_capture(enabled: bool) {
+    MOUSE_CAPTURE.store(enabled, Ordering::Relaxed);
+}
+
 pub fn set_modes() -> Result<()> {
     execute!(stdout(), EnableBracketedPaste)?;
 
@@ -65,6 +78,9 @@ pub fn set_modes() -> Result<()> {
     );
 
     let _ = execute!(stdout(), EnableFocusChange);
+    if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+        let _ = execute!(stdout(), EnableMouseCapture);
+    }
     Ok(())
 }
 
@@ -117,6 +133,9 @@ pub fn restore() -> Result<()> {
     let _ = execute!(stdout(), PopKeyboardEnhancementFlags);
     execute!(stdout(), DisableBracketedPaste)?;
     let _ = execute!(stdout(), DisableFocusChange);
+    if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+        let _ = execute!(stdout(), DisableMouseCapture);
+    }
     disable_raw_mode()?;
     let _ = execute!(stdout(), crossterm::cursor::Show);
     Ok(())
@@ -148,6 +167,8 @@ fn set_panic_hook() {
 pub enum TuiEvent {
     Key(KeyEvent),
     Paste(String),
+    /// Only sent when mouse capture is on.
+    Mouse(MouseEvent),
     Draw,
 }
 
@@ -219,9 +240,9 @@ impl FrameRequester {
 impl Tui {
     /// Emit a desktop notification now if the terminal is unfocused.
     /// Returns true if a notification was posted.
-    pub fn notify(&mut self, message: impl AsRef<str>) -> bool {
+    pub fn notify(&mut self, message: impl AsRef<str>, method: NotificationMethod) -> bool {
         if !self.terminal_focused.load(Ordering::Relaxed) {
-            let _ = execute!(stdout(), PostNotification(message.as_ref().to_string()));
+            crate::notifications::post(method, message.as_ref());
             true
         } else {
             false
@@ -360,13 +381,15 @@ impl Tui {
                             Event::Paste(pasted) => {
                                 yield TuiEvent::Paste(pasted);
                             }
+                            Event::Mouse(mouse_event) => {
+                                yield TuiEvent::Mouse(mouse_event);
+                            }
                             Event::FocusGained => {
                                 terminal_focused.store(true, Ordering::Relaxed);
                             }
                             Event::FocusLost => {
                                 terminal_focused.store(false, Ordering::Relaxed);
                             }
-                            _ => {}
                         }
                     }
                     result = draw_rx.recv() => {
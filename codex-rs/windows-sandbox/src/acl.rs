@@ -0,0 +1,158 @@
+use std::ffi::c_void;
+use std::io;
+use std::path::Path;
+use std::ptr;
+
+use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+use windows_sys::Win32::Foundation::LocalFree;
+use windows_sys::Win32::Security::ACCESS_ALLOWED_ACE;
+use windows_sys::Win32::Security::ACL;
+use windows_sys::Win32::Security::Authorization::DENY_ACCESS;
+use windows_sys::Win32::Security::Authorization::EXPLICIT_ACCESS_W;
+use windows_sys::Win32::Security::Authorization::GRANT_ACCESS;
+use windows_sys::Win32::Security::Authorization::GetNamedSecurityInfoW;
+use windows_sys::Win32::Security::Authorization::NO_MULTIPLE_TRUSTEE;
+use windows_sys::Win32::Security::Authorization::SE_FILE_OBJECT;
+use windows_sys::Win32::Security::Authorization::SetEntriesInAclW;
+use windows_sys::Win32::Security::Authorization::SetNamedSecurityInfoW;
+use windows_sys::Win32::Security::Authorization::TRUSTEE_IS_SID;
+use windows_sys::Win32::Security::Authorization::TRUSTEE_IS_UNKNOWN;
+use windows_sys::Win32::Security::Authorization::TRUSTEE_W;
+use windows_sys::Win32::Security::DACL_SECURITY_INFORMATION;
+use windows_sys::Win32::Security::EqualSid;
+use windows_sys::Win32::Security::GetAce;
+use windows_sys::Win32::Security::SUB_CONTAINERS_AND_OBJECTS_INHERIT;
+use windows_sys::Win32::Storage::FileSystem::DELETE;
+use windows_sys::Win32::Storage::FileSystem::FILE_APPEND_DATA;
+use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_EXECUTE;
+use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_READ;
+use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_WRITE;
+use windows_sys::Win32::Storage::FileSystem::FILE_WRITE_ATTRIBUTES;
+use windows_sys::Win32::Storage::FileSystem::FILE_WRITE_DATA;
+use windows_sys::Win32::Storage::FileSystem::FILE_WRITE_EA;
+
+use crate::to_wide;
+use crate::token::Sid;
+
+/// Granted on writable roots. `FILE_DELETE_CHILD` is left out on purpose so
+/// that a denied subpath cannot be removed through its parent.
+pub(crate) const WRITABLE_ROOT_ACCESS: u32 =
+    FILE_GENERIC_READ | FILE_GENERIC_WRITE | FILE_GENERIC_EXECUTE | DELETE;
+
+/// Denied on read-only subpaths such as `.git`.
+pub(crate) const READ_ONLY_SUBPATH_DENIED: u32 =
+    FILE_WRITE_DATA | FILE_APPEND_DATA | FILE_WRITE_EA | FILE_WRITE_ATTRIBUTES | DELETE;
+
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+const ACCESS_DENIED_ACE_TYPE: u8 = 1;
+
+#[derive(Clone, Copy)]
+pub(crate) enum AceMode {
+    Allow,
+    Deny,
+}
+
+/// Makes sure the DACL of `path` allows or denies `access` to `sid`, for the
+/// path itself and everything below it. The ACE is left in place once the
+/// command exits so later commands do not pay for propagating it again.
+pub(crate) fn ensure_ace(path: &Path, sid: &Sid, access: u32, mode: AceMode) -> io::Result<()> {
+    let name = to_wide(path);
+    let mut dacl: *mut ACL = ptr::null_mut();
+    let mut descriptor: *mut c_void = ptr::null_mut();
+    check(unsafe {
+        GetNamedSecurityInfoW(
+            name.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut dacl,
+            ptr::null_mut(),
+            &mut descriptor,
+        )
+    })?;
+    // `dacl` points into `descriptor`.
+    let _descriptor = LocalAlloc(descriptor);
+
+    if unsafe { has_ace(dacl, sid, access, mode) } {
+        return Ok(());
+    }
+
+    let entry = EXPLICIT_ACCESS_W {
+        grfAccessPermissions: access,
+        grfAccessMode: match mode {
+            AceMode::Allow => GRANT_ACCESS,
+            AceMode::Deny => DENY_ACCESS,
+        },
+        grfInheritance: SUB_CONTAINERS_AND_OBJECTS_INHERIT,
+        Trustee: TRUSTEE_W {
+            pMultipleTrustee: ptr::null_mut(),
+            MultipleTrusteeOperation: NO_MULTIPLE_TRUSTEE,
+            TrusteeForm: TRUSTEE_IS_SID,
+            TrusteeType: TRUSTEE_IS_UNKNOWN,
+            ptstrName: sid.as_ptr().cast(),
+        },
+    };
+    let mut new_dacl: *mut ACL = ptr::null_mut();
+    check(unsafe { SetEntriesInAclW(1, &entry, dacl, &mut new_dacl) })?;
+    let _new_dacl = LocalAlloc(new_dacl.cast());
+
+    check(unsafe {
+        SetNamedSecurityInfoW(
+            name.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            new_dacl,
+            ptr::null(),
+        )
+    })
+}
+
+/// Whether `dacl` already contains an ACE of the requested kind for `sid`
+/// that covers `access`, either explicit or inherited.
+unsafe fn has_ace(dacl: *const ACL, sid: &Sid, access: u32, mode: AceMode) -> bool {
+    if dacl.is_null() {
+        return false;
+    }
+    let ace_type = match mode {
+        AceMode::Allow => ACCESS_ALLOWED_ACE_TYPE,
+        AceMode::Deny => ACCESS_DENIED_ACE_TYPE,
+    };
+    let count = unsafe { (*dacl).AceCount };
+    (0..u32::from(count)).any(|index| {
+        let mut ace: *mut c_void = ptr::null_mut();
+        if unsafe { GetAce(dacl, index, &mut ace) } == 0 {
+            return false;
+        }
+        // Allowed and denied ACEs share the same layout.
+        let ace = ace.cast::<ACCESS_ALLOWED_ACE>();
+        unsafe {
+            (*ace).Header.AceType == ace_type
+                && (*ace).Mask & access == access
+                && EqualSid(ptr::addr_of_mut!((*ace).SidStart).cast(), sid.as_ptr()) != 0
+        }
+    })
+}
+
+fn check(code: u32) -> io::Result<()> {
+    if code == ERROR_SUCCESS {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(code as i32))
+    }
+}
+
+/// Frees memory returned by security APIs with `LocalFree` when dropped.
+struct LocalAlloc(*mut c_void);
+
+impl Drop for LocalAlloc {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe {
+                LocalFree(self.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Sandbox for shell tool commands on Windows.
+//!
+//! Commands run with a write-restricted token: every write access check has
+//! to pass a second time for a small set of restricting SIDs, and the only
+//! non-universal one among them is a Codex sandbox SID that is granted write
+//! access on the writable roots of the sandbox policy and nowhere else.
+//! Reads are not restricted, and neither is network access.
+//!
+//! Windows has no equivalent of the arg0 trick used for `codex-linux-sandbox`,
+//! so the launcher is selected by passing [`CODEX_WINDOWS_SANDBOX_ARG1`] as the
+//! first argument to a Codex executable.
+
+#[cfg(target_os = "windows")]
+mod acl;
+#[cfg(target_os = "windows")]
+mod process;
+#[cfg(target_os = "windows")]
+mod token;
+#[cfg(target_os = "windows")]
+mod windows_run_main;
+
+/// First argument that turns a Codex executable into the sandbox launcher.
+pub const CODEX_WINDOWS_SANDBOX_ARG1: &str = "--codex-run-as-windows-sandbox";
+
+#[cfg(target_os = "windows")]
+pub fn run_main() -> ! {
+    windows_run_main::run_main();
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn run_main() -> ! {
+    panic!("the Windows sandbox is only supported on Windows");
+}
+
+/// Checks whether this process can create the restricted tokens the sandbox
+/// relies on. The result is computed once and cached.
+#[cfg(target_os = "windows")]
+pub fn detect() -> Result<(), String> {
+    static DETECTED: std::sync::OnceLock<Result<(), String>> = std::sync::OnceLock::new();
+    DETECTED.get_or_init(token::probe).clone()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn detect() -> Result<(), String> {
+    Err("the Windows sandbox is only supported on Windows".to_string())
+}
+
+/// NUL-terminated UTF-16 copy of `s` for Win32 APIs.
+#[cfg(target_os = "windows")]
+fn to_wide(s: impl AsRef<std::ffi::OsStr>) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    s.as_ref().encode_wide().chain(std::iter::once(0)).collect()
+}
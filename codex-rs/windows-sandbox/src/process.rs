@@ -0,0 +1,185 @@
+use std::io;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::io::FromRawHandle;
+use std::os::windows::io::OwnedHandle;
+use std::ptr;
+
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::Foundation::HANDLE_FLAG_INHERIT;
+use windows_sys::Win32::Foundation::SetHandleInformation;
+use windows_sys::Win32::System::Console::GetStdHandle;
+use windows_sys::Win32::System::Console::STD_ERROR_HANDLE;
+use windows_sys::Win32::System::Console::STD_HANDLE;
+use windows_sys::Win32::System::Console::STD_INPUT_HANDLE;
+use windows_sys::Win32::System::Console::STD_OUTPUT_HANDLE;
+use windows_sys::Win32::System::JobObjects::AssignProcessToJobObject;
+use windows_sys::Win32::System::JobObjects::CreateJobObjectW;
+use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+use windows_sys::Win32::System::JobObjects::JOBOBJECT_EXTENDED_LIMIT_INFORMATION;
+use windows_sys::Win32::System::JobObjects::JobObjectExtendedLimitInformation;
+use windows_sys::Win32::System::JobObjects::SetInformationJobObject;
+use windows_sys::Win32::System::Threading::CREATE_SUSPENDED;
+use windows_sys::Win32::System::Threading::CreateProcessAsUserW;
+use windows_sys::Win32::System::Threading::GetExitCodeProcess;
+use windows_sys::Win32::System::Threading::INFINITE;
+use windows_sys::Win32::System::Threading::PROCESS_INFORMATION;
+use windows_sys::Win32::System::Threading::ResumeThread;
+use windows_sys::Win32::System::Threading::STARTF_USESTDHANDLES;
+use windows_sys::Win32::System::Threading::STARTUPINFOW;
+use windows_sys::Win32::System::Threading::TerminateProcess;
+use windows_sys::Win32::System::Threading::WaitForSingleObject;
+
+use crate::to_wide;
+
+/// Runs `command` with `token`, forwarding this process's stdio, and returns
+/// its exit code. The command is placed in a job that is killed when this
+/// launcher exits, so killing the launcher on timeout kills the command too.
+pub(crate) fn spawn_and_wait(token: &OwnedHandle, command: &[String]) -> io::Result<i32> {
+    let job = create_kill_on_close_job()?;
+    let mut command_line = to_wide(build_command_line(command));
+
+    let mut startup_info: STARTUPINFOW = unsafe { std::mem::zeroed() };
+    startup_info.cb = size_of::<STARTUPINFOW>() as u32;
+    startup_info.dwFlags = STARTF_USESTDHANDLES;
+    startup_info.hStdInput = inheritable_std_handle(STD_INPUT_HANDLE);
+    startup_info.hStdOutput = inheritable_std_handle(STD_OUTPUT_HANDLE);
+    startup_info.hStdError = inheritable_std_handle(STD_ERROR_HANDLE);
+
+    let mut process_info: PROCESS_INFORMATION = unsafe { std::mem::zeroed() };
+    let created = unsafe {
+        CreateProcessAsUserW(
+            token.as_raw_handle(),
+            ptr::null(),
+            command_line.as_mut_ptr(),
+            ptr::null(),
+            ptr::null(),
+            1,
+            CREATE_SUSPENDED,
+            ptr::null(),
+            ptr::null(),
+            &startup_info,
+            &mut process_info,
+        )
+    };
+    if created == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let process = unsafe { OwnedHandle::from_raw_handle(process_info.hProcess) };
+    let thread = unsafe { OwnedHandle::from_raw_handle(process_info.hThread) };
+
+    if unsafe { AssignProcessToJobObject(job.as_raw_handle(), process.as_raw_handle()) } == 0 {
+        let err = io::Error::last_os_error();
+        unsafe {
+            TerminateProcess(process.as_raw_handle(), 1);
+        }
+        return Err(err);
+    }
+    unsafe {
+        ResumeThread(thread.as_raw_handle());
+        WaitForSingleObject(process.as_raw_handle(), INFINITE);
+    }
+
+    let mut exit_code = 0u32;
+    if unsafe { GetExitCodeProcess(process.as_raw_handle(), &mut exit_code) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(exit_code as i32)
+}
+
+fn create_kill_on_close_job() -> io::Result<OwnedHandle> {
+    let job = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+    if job.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    let job = unsafe { OwnedHandle::from_raw_handle(job) };
+
+    let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+    limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+    let configured = unsafe {
+        SetInformationJobObject(
+            job.as_raw_handle(),
+            JobObjectExtendedLimitInformation,
+            ptr::addr_of!(limits).cast(),
+            size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )
+    };
+    if configured == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(job)
+}
+
+/// Standard handle of this process, marked inheritable so the command gets
+/// the same pipes.
+fn inheritable_std_handle(which: STD_HANDLE) -> HANDLE {
+    let handle = unsafe { GetStdHandle(which) };
+    if !handle.is_null() {
+        unsafe {
+            SetHandleInformation(handle, HANDLE_FLAG_INHERIT, HANDLE_FLAG_INHERIT);
+        }
+    }
+    handle
+}
+
+/// Joins `command` into a command line that `CommandLineToArgvW` and the
+/// MSVC runtime split back into the same arguments.
+fn build_command_line(command: &[String]) -> String {
+    let mut line = String::new();
+    for (i, arg) in command.iter().enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        quote_arg(arg, &mut line);
+    }
+    line
+}
+
+fn quote_arg(arg: &str, out: &mut String) {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\u{b}', '"']) {
+        out.push_str(arg);
+        return;
+    }
+    out.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Escape the preceding backslashes and the quote itself.
+                out.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                out.extend(std::iter::repeat_n('\\', backslashes));
+                out.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Backslashes before the closing quote must be doubled.
+    out.extend(std::iter::repeat_n('\\', backslashes * 2));
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn command_line_quotes_only_when_needed() {
+        let command = [
+            "cmd.exe".to_string(),
+            "/c".to_string(),
+            "echo hello world".to_string(),
+            String::new(),
+            r#"say "hi""#.to_string(),
+            r"C:\path with space\".to_string(),
+        ];
+        assert_eq!(
+            build_command_line(&command),
+            r#"cmd.exe /c "echo hello world" "" "say \"hi\"" "C:\path with space\\""#
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::ffi::c_void;
+use std::io;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::io::FromRawHandle;
+use std::os::windows::io::OwnedHandle;
+use std::ptr;
+
+use codex_protocol::protocol::WritableRoot;
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::Foundation::LocalFree;
+use windows_sys::Win32::Security::Authorization::ConvertStringSidToSidW;
+use windows_sys::Win32::Security::CreateRestrictedToken;
+use windows_sys::Win32::Security::DISABLE_MAX_PRIVILEGE;
+use windows_sys::Win32::Security::GetTokenInformation;
+use windows_sys::Win32::Security::SID_AND_ATTRIBUTES;
+use windows_sys::Win32::Security::TOKEN_ASSIGN_PRIMARY;
+use windows_sys::Win32::Security::TOKEN_DUPLICATE;
+use windows_sys::Win32::Security::TOKEN_GROUPS;
+use windows_sys::Win32::Security::TOKEN_QUERY;
+use windows_sys::Win32::Security::TokenGroups;
+use windows_sys::Win32::Security::WRITE_RESTRICTED;
+use windows_sys::Win32::System::Threading::GetCurrentProcess;
+use windows_sys::Win32::System::Threading::OpenProcessToken;
+
+use crate::to_wide;
+
+/// Everyone. Restricting on it keeps world-writable objects such as `NUL`
+/// and named pipes usable.
+const EVERYONE_SID: &str = "S-1-1-0";
+
+/// `SE_GROUP_LOGON_ID`; marks the logon session SID in the token groups.
+const SE_GROUP_LOGON_ID: u32 = 0xC000_0000;
+
+/// A SID allocated by `ConvertStringSidToSidW`.
+pub(crate) struct Sid(*mut c_void);
+
+impl Sid {
+    fn from_string(sid: &str) -> io::Result<Self> {
+        let wide = to_wide(sid);
+        let mut psid = ptr::null_mut();
+        if unsafe { ConvertStringSidToSidW(wide.as_ptr(), &mut psid) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(psid))
+    }
+
+    /// The sandbox SID for a set of writable roots. Deriving it from the
+    /// roots means write access granted for one workspace is not usable by
+    /// commands sandboxed to another. The SID uses the resource manager
+    /// authority (`S-1-9`) and does not belong to any account.
+    pub(crate) fn for_writable_roots(writable_roots: &[WritableRoot]) -> io::Result<Self> {
+        let mut roots: Vec<String> = writable_roots
+            .iter()
+            .map(|root| root.root.to_string_lossy().to_lowercase())
+            .collect();
+        roots.sort();
+        let key = roots.join("\n");
+        let high = fnv1a(key.as_bytes(), 0xcbf2_9ce4_8422_2325);
+        let low = fnv1a(key.as_bytes(), 0x6c62_272e_07bb_0142);
+        Self::from_string(&format!(
+            "S-1-9-{}-{}-{}-{}",
+            high >> 32,
+            high & 0xffff_ffff,
+            low >> 32,
+            low & 0xffff_ffff
+        ))
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+impl Drop for Sid {
+    fn drop(&mut self) {
+        unsafe {
+            LocalFree(self.0);
+        }
+    }
+}
+
+fn fnv1a(bytes: &[u8], seed: u64) -> u64 {
+    bytes.iter().fold(seed, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Creates a primary token for the sandboxed command: privileges are
+/// dropped and writes must additionally be granted to `sandbox_sid`,
+/// Everyone, or the logon session.
+pub(crate) fn create_restricted_token(sandbox_sid: &Sid) -> io::Result<OwnedHandle> {
+    let process_token = open_process_token()?;
+    let everyone = Sid::from_string(EVERYONE_SID)?;
+    let groups = token_groups(&process_token)?;
+
+    let mut restricting_sids = vec![
+        SID_AND_ATTRIBUTES {
+            Sid: sandbox_sid.as_ptr(),
+            Attributes: 0,
+        },
+        SID_AND_ATTRIBUTES {
+            Sid: everyone.as_ptr(),
+            Attributes: 0,
+        },
+    ];
+    // Console and other per-session objects are granted to the logon SID.
+    if let Some(logon_sid) = logon_sid(&groups) {
+        restricting_sids.push(SID_AND_ATTRIBUTES {
+            Sid: logon_sid,
+            Attributes: 0,
+        });
+    }
+
+    let mut token: HANDLE = ptr::null_mut();
+    let created = unsafe {
+        CreateRestrictedToken(
+            process_token.as_raw_handle(),
+            DISABLE_MAX_PRIVILEGE | WRITE_RESTRICTED,
+            0,
+            ptr::null(),
+            0,
+            ptr::null(),
+            restricting_sids.len() as u32,
+            restricting_sids.as_ptr(),
+            &mut token,
+        )
+    };
+    if created == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedHandle::from_raw_handle(token) })
+}
+
+/// Creates and discards a restricted token to find out whether the sandbox
+/// works in this process.
+pub(crate) fn probe() -> Result<(), String> {
+    Sid::from_string(EVERYONE_SID)
+        .and_then(|sid| create_restricted_token(&sid))
+        .map(drop)
+        .map_err(|e| format!("could not create a restricted token: {e}"))
+}
+
+fn open_process_token() -> io::Result<OwnedHandle> {
+    let mut token: HANDLE = ptr::null_mut();
+    let opened = unsafe {
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_DUPLICATE | TOKEN_QUERY | TOKEN_ASSIGN_PRIMARY,
+            &mut token,
+        )
+    };
+    if opened == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedHandle::from_raw_handle(token) })
+}
+
+/// Raw `TOKEN_GROUPS` of `token`, in a `u64` buffer to satisfy its alignment.
+fn token_groups(token: &OwnedHandle) -> io::Result<Vec<u64>> {
+    let mut len = 0u32;
+    // The first call only reports the required size.
+    unsafe {
+        GetTokenInformation(
+            token.as_raw_handle(),
+            TokenGroups,
+            ptr::null_mut(),
+            0,
+            &mut len,
+        );
+    }
+    let mut buffer = vec![0u64; (len as usize).div_ceil(size_of::<u64>())];
+    let queried = unsafe {
+        GetTokenInformation(
+            token.as_raw_handle(),
+            TokenGroups,
+            buffer.as_mut_ptr().cast(),
+            len,
+            &mut len,
+        )
+    };
+    if queried == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(buffer)
+}
+
+/// Logon session SID from a buffer filled by [`token_groups`]. The returned
+/// pointer borrows from `groups`.
+fn logon_sid(groups: &[u64]) -> Option<*mut c_void> {
+    let groups = groups.as_ptr().cast::<TOKEN_GROUPS>();
+    let entries = unsafe {
+        std::slice::from_raw_parts(
+            ptr::addr_of!((*groups).Groups).cast::<SID_AND_ATTRIBUTES>(),
+            (*groups).GroupCount as usize,
+        )
+    };
+    entries
+        .iter()
+        .find(|group| group.Attributes & SE_GROUP_LOGON_ID == SE_GROUP_LOGON_ID)
+        .map(|group| group.Sid)
+}
@@ -0,0 +1,76 @@
+use clap::Parser;
+use codex_protocol::protocol::SandboxPolicy;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::acl::AceMode;
+use crate::acl::READ_ONLY_SUBPATH_DENIED;
+use crate::acl::WRITABLE_ROOT_ACCESS;
+use crate::acl::ensure_ace;
+use crate::process::spawn_and_wait;
+use crate::token::Sid;
+use crate::token::create_restricted_token;
+
+#[derive(Debug, Parser)]
+pub struct WindowsSandboxCommand {
+    /// It is possible that the cwd used in the context of the sandbox policy
+    /// is different from the cwd of the process to spawn.
+    pub sandbox_policy_cwd: PathBuf,
+
+    pub sandbox_policy: SandboxPolicy,
+
+    /// Full command args to run in the sandbox.
+    #[arg(trailing_var_arg = true)]
+    pub command: Vec<String>,
+}
+
+pub fn run_main() -> ! {
+    // `argv[1]` is `CODEX_WINDOWS_SANDBOX_ARG1`; let clap treat it as the
+    // program name.
+    let WindowsSandboxCommand {
+        sandbox_policy_cwd,
+        sandbox_policy,
+        command,
+    } = WindowsSandboxCommand::parse_from(std::env::args_os().skip(1));
+
+    if command.is_empty() {
+        panic!("No command specified to execute.");
+    }
+
+    match run_sandboxed(&sandbox_policy, &sandbox_policy_cwd, &command) {
+        Ok(exit_code) => std::process::exit(exit_code),
+        Err(e) => panic!("error running windows sandbox: {e}"),
+    }
+}
+
+fn run_sandboxed(
+    sandbox_policy: &SandboxPolicy,
+    sandbox_policy_cwd: &Path,
+    command: &[String],
+) -> io::Result<i32> {
+    let writable_roots = sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd);
+    let sandbox_sid = Sid::for_writable_roots(&writable_roots)?;
+    for writable_root in &writable_roots {
+        if !writable_root.root.exists() {
+            continue;
+        }
+        ensure_ace(
+            &writable_root.root,
+            &sandbox_sid,
+            WRITABLE_ROOT_ACCESS,
+            AceMode::Allow,
+        )?;
+        for subpath in writable_root.existing_read_only_paths() {
+            ensure_ace(
+                &subpath,
+                &sandbox_sid,
+                READ_ONLY_SUBPATH_DENIED,
+                AceMode::Deny,
+            )?;
+        }
+    }
+
+    let token = create_restricted_token(&sandbox_sid)?;
+    spawn_and_wait(&token, command)
+}